@@ -0,0 +1,46 @@
+// Write Brotli and gzip siblings (`file.js.br`, `file.js.gz`) next to the
+// compressible files in dist/ so paracord-server can embed and serve them
+// without compressing on every request.
+import { readdir, readFile, stat, writeFile } from 'node:fs/promises';
+import { extname, join } from 'node:path';
+import { fileURLToPath } from 'node:url';
+import { brotliCompressSync, constants, gzipSync } from 'node:zlib';
+
+const DIST_DIR = fileURLToPath(new URL('../dist/', import.meta.url));
+const COMPRESSIBLE = new Set(['.js', '.mjs', '.css', '.html', '.json', '.svg', '.txt', '.wasm', '.webmanifest']);
+const MIN_SIZE = 1024;
+
+async function* walk(dir) {
+  for (const entry of await readdir(dir, { withFileTypes: true })) {
+    const path = join(dir, entry.name);
+    if (entry.isDirectory()) yield* walk(path);
+    else yield path;
+  }
+}
+
+let written = 0;
+let savedBytes = 0;
+for await (const path of walk(DIST_DIR)) {
+  if (!COMPRESSIBLE.has(extname(path))) continue;
+  if ((await stat(path)).size < MIN_SIZE) continue;
+  const data = await readFile(path);
+  const br = brotliCompressSync(data, {
+    params: {
+      [constants.BROTLI_PARAM_QUALITY]: constants.BROTLI_MAX_QUALITY,
+      [constants.BROTLI_PARAM_SIZE_HINT]: data.length,
+    },
+  });
+  const gz = gzipSync(data, { level: 9 });
+  // Skip variants that don't pay for themselves.
+  if (br.length < data.length * 0.9) {
+    await writeFile(`${path}.br`, br);
+    written += 1;
+    savedBytes += data.length - br.length;
+  }
+  if (gz.length < data.length * 0.9) {
+    await writeFile(`${path}.gz`, gz);
+    written += 1;
+  }
+}
+
+console.log(`precompress: wrote ${written} variants, brotli saves ${(savedBytes / 1024 / 1024).toFixed(1)} MiB`);
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::commands;
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+const ACCOUNT_CREDENTIAL_KEY_PREFIX: &str = "paracord:account:";
+const MAX_SAVED_ACCOUNTS: usize = 16;
+
+/// Serializes reads and writes of the accounts file across commands.
+static ACCOUNTS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Public account metadata. Credentials never leave the backend in this shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAccount {
+    pub id: String,
+    pub server_url: String,
+    pub user_id: String,
+    pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub avatar_hash: Option<String>,
+    #[serde(default)]
+    pub last_used_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountInput {
+    pub server_url: String,
+    pub user_id: String,
+    pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub avatar_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountList {
+    pub active_account_id: Option<String>,
+    pub accounts: Vec<SavedAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredAccount {
+    #[serde(flatten)]
+    account: SavedAccount,
+    /// Encrypted refresh token, only used when the OS keyring is unavailable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback_credential: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AccountsFile {
+    #[serde(default)]
+    active_account_id: Option<String>,
+    #[serde(default)]
+    accounts: Vec<StoredAccount>,
+}
+
+impl AccountsFile {
+    fn find_mut(&mut self, account_id: &str) -> Option<&mut StoredAccount> {
+        self.accounts
+            .iter_mut()
+            .find(|stored| stored.account.id == account_id)
+    }
+
+    fn to_list(&self) -> AccountList {
+        let mut accounts: Vec<SavedAccount> = self
+            .accounts
+            .iter()
+            .map(|stored| stored.account.clone())
+            .collect();
+        accounts.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+        AccountList {
+            active_account_id: self.active_account_id.clone(),
+            accounts,
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn normalize_server_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+fn accounts_file_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    use tauri::Manager;
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to resolve app data dir: {e}"))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create app data dir: {e}"))?;
+    Ok(dir.join(ACCOUNTS_FILE))
+}
+
+fn read_accounts(app: &tauri::AppHandle) -> Result<AccountsFile, String> {
+    let path = accounts_file_path(app)?;
+    if !path.is_file() {
+        return Ok(AccountsFile::default());
+    }
+    let raw =
+        std::fs::read_to_string(&path).map_err(|e| format!("failed to read accounts: {e}"))?;
+    // A corrupt file should not lock the user out; start over instead.
+    Ok(serde_json::from_str(&raw).unwrap_or_default())
+}
+
+fn write_accounts(app: &tauri::AppHandle, file: &AccountsFile) -> Result<(), String> {
+    let path = accounts_file_path(app)?;
+    let raw = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("failed to encode accounts: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, raw).map_err(|e| format!("failed to write accounts: {e}"))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("failed to replace accounts: {e}"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+fn credential_key(account_id: &str) -> String {
+    format!("{ACCOUNT_CREDENTIAL_KEY_PREFIX}{account_id}")
+}
+
+/// Store a refresh token in the OS keyring, falling back to an encrypted copy
+/// in the accounts file when the keyring is unavailable.
+fn store_credential(
+    app: &tauri::AppHandle,
+    stored: &mut StoredAccount,
+    refresh_token: &str,
+) -> Result<(), String> {
+    let key = credential_key(&stored.account.id);
+    match commands::secure_store_set(key, refresh_token.to_string()) {
+        Ok(()) => {
+            stored.fallback_credential = None;
+            Ok(())
+        }
+        Err(_) => {
+            stored.fallback_credential = Some(commands::secure_store_fallback_encrypt(
+                app.clone(),
+                refresh_token.to_string(),
+            )?);
+            Ok(())
+        }
+    }
+}
+
+fn load_credential(
+    app: &tauri::AppHandle,
+    stored: &StoredAccount,
+) -> Result<Option<String>, String> {
+    if let Ok(Some(token)) = commands::secure_store_get(credential_key(&stored.account.id)) {
+        return Ok(Some(token));
+    }
+    match &stored.fallback_credential {
+        Some(payload) => {
+            commands::secure_store_fallback_decrypt(app.clone(), payload.clone()).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+fn lock_accounts() -> std::sync::MutexGuard<'static, ()> {
+    ACCOUNTS_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[tauri::command]
+pub fn accounts_list(app: tauri::AppHandle) -> Result<AccountList, String> {
+    let _guard = lock_accounts();
+    Ok(read_accounts(&app)?.to_list())
+}
+
+/// Add or update the account for `server_url` + `user_id` and store its
+/// refresh token. The same user on the same server is always one entry.
+#[tauri::command]
+pub fn accounts_save(
+    app: tauri::AppHandle,
+    account: AccountInput,
+    refresh_token: String,
+    make_active: bool,
+) -> Result<SavedAccount, String> {
+    if account.user_id.trim().is_empty() || account.server_url.trim().is_empty() {
+        return Err("account requires a server URL and user id".into());
+    }
+    let _guard = lock_accounts();
+    let mut file = read_accounts(&app)?;
+    let server_key = normalize_server_url(&account.server_url);
+    let existing = file.accounts.iter().position(|stored| {
+        stored.account.user_id == account.user_id
+            && normalize_server_url(&stored.account.server_url) == server_key
+    });
+    let index = match existing {
+        Some(index) => index,
+        None => {
+            if file.accounts.len() >= MAX_SAVED_ACCOUNTS {
+                return Err(format!(
+                    "at most {MAX_SAVED_ACCOUNTS} accounts can be saved"
+                ));
+            }
+            file.accounts.push(StoredAccount {
+                account: SavedAccount {
+                    id: uuid::Uuid::new_v4().simple().to_string(),
+                    server_url: String::new(),
+                    user_id: String::new(),
+                    username: String::new(),
+                    display_name: None,
+                    avatar_hash: None,
+                    last_used_at: 0,
+                },
+                fallback_credential: None,
+            });
+            file.accounts.len() - 1
+        }
+    };
+
+    let stored = &mut file.accounts[index];
+    stored.account.server_url = account.server_url.trim().trim_end_matches('/').to_string();
+    stored.account.user_id = account.user_id;
+    stored.account.username = account.username;
+    stored.account.display_name = account.display_name;
+    stored.account.avatar_hash = account.avatar_hash;
+    if !refresh_token.is_empty() {
+        store_credential(&app, stored, &refresh_token)?;
+    }
+    if make_active {
+        stored.account.last_used_at = now_ms();
+    }
+    let saved = stored.account.clone();
+    if make_active {
+        file.active_account_id = Some(saved.id.clone());
+    }
+    write_accounts(&app, &file)?;
+    Ok(saved)
+}
+
+/// Replace the stored refresh token after the server rotates it.
+#[tauri::command]
+pub fn accounts_set_credential(
+    app: tauri::AppHandle,
+    account_id: String,
+    refresh_token: String,
+) -> Result<(), String> {
+    let _guard = lock_accounts();
+    let mut file = read_accounts(&app)?;
+    let stored = file.find_mut(&account_id).ok_or("unknown account")?;
+    store_credential(&app, stored, &refresh_token)?;
+    write_accounts(&app, &file)
+}
+
+#[tauri::command]
+pub fn accounts_get_credential(
+    app: tauri::AppHandle,
+    account_id: String,
+) -> Result<Option<String>, String> {
+    let _guard = lock_accounts();
+    let file = read_accounts(&app)?;
+    let stored = file
+        .accounts
+        .iter()
+        .find(|stored| stored.account.id == account_id)
+        .ok_or("unknown account")?;
+    load_credential(&app, stored)
+}
+
+/// Mark an account as active. The caller swaps the webview session itself;
+/// nothing here requires a restart.
+#[tauri::command]
+pub fn accounts_switch(app: tauri::AppHandle, account_id: String) -> Result<SavedAccount, String> {
+    let _guard = lock_accounts();
+    let mut file = read_accounts(&app)?;
+    let stored = file.find_mut(&account_id).ok_or("unknown account")?;
+    stored.account.last_used_at = now_ms();
+    let saved = stored.account.clone();
+    file.active_account_id = Some(saved.id.clone());
+    write_accounts(&app, &file)?;
+    Ok(saved)
+}
+
+/// Forget an account and its stored credential.
+#[tauri::command]
+pub fn accounts_remove(app: tauri::AppHandle, account_id: String) -> Result<AccountList, String> {
+    let _guard = lock_accounts();
+    let mut file = read_accounts(&app)?;
+    file.accounts
+        .retain(|stored| stored.account.id != account_id);
+    if file.active_account_id.as_deref() == Some(account_id.as_str()) {
+        file.active_account_id = None;
+    }
+    // The keyring may be unavailable; the fallback copy went with the entry.
+    let _ = commands::secure_store_delete(credential_key(&account_id));
+    write_accounts(&app, &file)?;
+    Ok(file.to_list())
+}
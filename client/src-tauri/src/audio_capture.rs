@@ -18,16 +18,136 @@ struct CaptureHandle {
 static CAPTURE: Mutex<Option<CaptureHandle>> = Mutex::new(None);
 static SYSTEM_AUDIO_CAPTURE_ENABLED: AtomicBool = AtomicBool::new(false);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapturePermission {
+    Granted,
+    Denied,
+    NotRequired,
+}
+
+/// What system audio capture can do on this platform, so the UI only offers
+/// options that will work.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemAudioCaptureCapabilities {
+    pub supported: bool,
+    /// `process_loopback` (Windows), `pulse_monitor` (Linux) or
+    /// `screencapturekit` (macOS).
+    pub backend: Option<&'static str>,
+    /// Whether our own voice playback is kept out of the captured mix.
+    pub excludes_own_audio: bool,
+    /// Whether capture can be limited to a single application.
+    pub application_audio: bool,
+    pub permission: CapturePermission,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureApplication {
+    /// Bundle identifier on macOS.
+    pub id: String,
+    pub name: String,
+}
+
+#[tauri::command]
+pub fn system_audio_capture_capabilities() -> SystemAudioCaptureCapabilities {
+    #[cfg(target_os = "windows")]
+    {
+        SystemAudioCaptureCapabilities {
+            supported: true,
+            backend: Some("process_loopback"),
+            excludes_own_audio: true,
+            application_audio: false,
+            permission: CapturePermission::NotRequired,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        SystemAudioCaptureCapabilities {
+            supported: true,
+            backend: Some("pulse_monitor"),
+            excludes_own_audio: false,
+            application_audio: false,
+            permission: CapturePermission::NotRequired,
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let supported = macos_screencapturekit::supports_audio_capture();
+        SystemAudioCaptureCapabilities {
+            supported,
+            backend: supported.then_some("screencapturekit"),
+            excludes_own_audio: supported,
+            application_audio: supported,
+            permission: macos_screencapturekit::permission(),
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        SystemAudioCaptureCapabilities {
+            supported: false,
+            backend: None,
+            excludes_own_audio: false,
+            application_audio: false,
+            permission: CapturePermission::NotRequired,
+        }
+    }
+}
+
+/// Show the OS permission prompt if capture needs one. On macOS this is the
+/// Screen Recording prompt; the user may have to restart the app after
+/// granting it.
+#[tauri::command]
+pub fn request_system_audio_capture_permission() -> CapturePermission {
+    #[cfg(target_os = "macos")]
+    {
+        macos_screencapturekit::request_permission()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        CapturePermission::NotRequired
+    }
+}
+
+/// Applications whose audio can be captured on their own.
+#[tauri::command]
+pub async fn list_system_audio_applications() -> Result<Vec<CaptureApplication>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        tokio::task::spawn_blocking(macos_screencapturekit::list_applications)
+            .await
+            .map_err(|e| format!("application list task failed: {e}"))?
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
 #[tauri::command]
 pub fn set_system_audio_capture_enabled(enabled: bool) {
     SYSTEM_AUDIO_CAPTURE_ENABLED.store(enabled, Ordering::SeqCst);
 }
 
 #[tauri::command]
-pub fn start_system_audio_capture(on_audio: Channel<AudioChunk>) -> Result<(), String> {
+pub fn start_system_audio_capture(
+    on_audio: Channel<AudioChunk>,
+    application: Option<String>,
+) -> Result<(), String> {
     if !SYSTEM_AUDIO_CAPTURE_ENABLED.load(Ordering::SeqCst) {
         return Err("System audio capture disabled".into());
     }
+    let capabilities = system_audio_capture_capabilities();
+    if application.is_some() && !capabilities.application_audio {
+        return Err("Per-application audio capture is not supported on this platform".into());
+    }
+    if capabilities.permission == CapturePermission::Denied {
+        return Err("Screen Recording permission is required to capture system audio".into());
+    }
 
     let mut guard = CAPTURE.lock().map_err(|e| e.to_string())?;
     if guard.is_some() {
@@ -38,7 +158,7 @@ pub fn start_system_audio_capture(on_audio: Channel<AudioChunk>) -> Result<(), S
     let stop = stop_flag.clone();
 
     let thread = thread::spawn(move || {
-        if let Err(e) = capture_loop(&on_audio, &stop) {
+        if let Err(e) = capture_loop(&on_audio, &stop, application.as_deref()) {
             eprintln!("[audio_capture] Capture loop error: {e}");
         }
     });
@@ -197,6 +317,7 @@ mod win_process_loopback {
 fn capture_loop(
     channel: &Channel<AudioChunk>,
     stop_flag: &Arc<AtomicBool>,
+    _application: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Initialize COM on this thread — required for all WASAPI / IAudioClient calls.
     // Both the Process Loopback path and the legacy WASAPI path need COM.
@@ -488,6 +609,7 @@ fn capture_loop_legacy(
 fn capture_loop(
     channel: &Channel<AudioChunk>,
     stop_flag: &Arc<AtomicBool>,
+    _application: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use libpulse_binding::sample::{Format, Spec};
     use libpulse_binding::stream::Direction;
@@ -554,18 +676,198 @@ fn capture_loop(
 }
 
 // ---------------------------------------------------------------------------
-// macOS: stub (not yet implemented)
+// macOS: ScreenCaptureKit (macOS 13+)
+// SCStream can deliver audio alongside (or instead of) video. We capture the
+// main display's audio mix, optionally narrowed to one application, with our
+// own process excluded so voice chat does not echo into the stream. Requires
+// the Screen Recording permission.
 // ---------------------------------------------------------------------------
+#[cfg(target_os = "macos")]
+mod macos_screencapturekit {
+    use super::{AudioChunk, CaptureApplication, CapturePermission};
+    use screencapturekit::output::CMSampleBuffer;
+    use screencapturekit::shareable_content::SCShareableContent;
+    use screencapturekit::stream::output_trait::SCStreamOutputTrait;
+    use screencapturekit::stream::output_type::SCStreamOutputType;
+    use tauri::ipc::Channel;
+
+    pub const SAMPLE_RATE: u32 = 48000;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    /// SCStream audio capture arrived in macOS 13.
+    pub fn supports_audio_capture() -> bool {
+        std::process::Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()
+            .and_then(|out| {
+                String::from_utf8_lossy(&out.stdout)
+                    .trim()
+                    .split('.')
+                    .next()
+                    .and_then(|major| major.parse::<u32>().ok())
+            })
+            .is_some_and(|major| major >= 13)
+    }
+
+    pub fn permission() -> CapturePermission {
+        if unsafe { CGPreflightScreenCaptureAccess() } {
+            CapturePermission::Granted
+        } else {
+            CapturePermission::Denied
+        }
+    }
+
+    pub fn request_permission() -> CapturePermission {
+        if unsafe { CGRequestScreenCaptureAccess() } {
+            CapturePermission::Granted
+        } else {
+            CapturePermission::Denied
+        }
+    }
+
+    pub fn list_applications() -> Result<Vec<CaptureApplication>, String> {
+        let content = SCShareableContent::get()
+            .map_err(|e| format!("failed to query shareable content: {e:?}"))?;
+        let own_pid = std::process::id() as i32;
+        let mut apps: Vec<CaptureApplication> = content
+            .applications()
+            .into_iter()
+            .filter(|app| app.process_id() != own_pid)
+            .filter_map(|app| {
+                let id = app.bundle_identifier();
+                let name = app.application_name();
+                (!id.is_empty() && !name.is_empty()).then_some(CaptureApplication { id, name })
+            })
+            .collect();
+        apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        apps.dedup_by(|a, b| a.id == b.id);
+        Ok(apps)
+    }
+
+    /// Forwards SCStream audio buffers to the webview as interleaved stereo.
+    pub struct AudioForwarder {
+        pub channel: Channel<AudioChunk>,
+    }
+
+    impl SCStreamOutputTrait for AudioForwarder {
+        fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
+            if !matches!(of_type, SCStreamOutputType::Audio) {
+                return;
+            }
+            let Ok(buffers) = sample.get_audio_buffer_list() else {
+                return;
+            };
+            // ScreenCaptureKit delivers planar f32: one buffer per channel.
+            let planes: Vec<Vec<f32>> = buffers
+                .iter()
+                .map(|buffer| {
+                    buffer
+                        .data()
+                        .chunks_exact(4)
+                        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect()
+                })
+                .collect();
+            let Some(left) = planes.first() else {
+                return;
+            };
+            let right = planes.get(1).unwrap_or(left);
+            let mut samples = Vec::with_capacity(left.len() * 2);
+            for (l, r) in left.iter().zip(right.iter()) {
+                samples.push(*l);
+                samples.push(*r);
+            }
+            if !samples.is_empty() {
+                let _ = self.channel.send(AudioChunk {
+                    samples,
+                    sample_rate: SAMPLE_RATE,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn sck<E: std::fmt::Debug>(context: &'static str) -> impl Fn(E) -> String {
+    move |e| format!("{context}: {e:?}")
+}
+
 #[cfg(target_os = "macos")]
 fn capture_loop(
-    _channel: &Channel<AudioChunk>,
-    _stop_flag: &Arc<AtomicBool>,
+    channel: &Channel<AudioChunk>,
+    stop_flag: &Arc<AtomicBool>,
+    application: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    Err(
-        "Native system audio capture is not yet supported on macOS. \
-         Audio from screen shares will still work via browser APIs."
-            .into(),
-    )
+    use screencapturekit::shareable_content::SCShareableContent;
+    use screencapturekit::stream::configuration::SCStreamConfiguration;
+    use screencapturekit::stream::content_filter::SCContentFilter;
+    use screencapturekit::stream::output_type::SCStreamOutputType;
+    use screencapturekit::stream::SCStream;
+
+    let content = SCShareableContent::get().map_err(sck("failed to query shareable content"))?;
+    let display = content
+        .displays()
+        .into_iter()
+        .next()
+        .ok_or("No display available for audio capture")?;
+    let filter = match application {
+        Some(bundle_id) => {
+            let app = content
+                .applications()
+                .into_iter()
+                .find(|app| app.bundle_identifier() == bundle_id)
+                .ok_or_else(|| format!("Application {bundle_id} is not running"))?;
+            SCContentFilter::new().with_display_including_application_excluding_windows(
+                &display,
+                &[&app],
+                &[],
+            )
+        }
+        None => SCContentFilter::new().with_display_excluding_windows(&display, &[]),
+    };
+
+    // SCStream always produces video; keep it tiny since only audio is used.
+    let config = SCStreamConfiguration::new()
+        .set_captures_audio(true)
+        .and_then(|c| c.set_sample_rate(macos_screencapturekit::SAMPLE_RATE))
+        .and_then(|c| c.set_channel_count(2))
+        .and_then(|c| c.set_excludes_current_process_audio(true))
+        .and_then(|c| c.set_width(2))
+        .and_then(|c| c.set_height(2))
+        .map_err(sck("failed to configure capture stream"))?;
+
+    let mut stream = SCStream::new(&filter, &config);
+    stream.add_output_handler(
+        macos_screencapturekit::AudioForwarder {
+            channel: channel.clone(),
+        },
+        SCStreamOutputType::Audio,
+    );
+    stream
+        .start_capture()
+        .map_err(sck("failed to start ScreenCaptureKit stream"))?;
+
+    eprintln!(
+        "[audio_capture] Started ScreenCaptureKit: {}Hz, 2 ch, f32{}",
+        macos_screencapturekit::SAMPLE_RATE,
+        application
+            .map(|id| format!(", app={id}"))
+            .unwrap_or_default()
+    );
+
+    while !stop_flag.load(Ordering::Relaxed) {
+        thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    let _ = stream.stop_capture();
+    eprintln!("[audio_capture] Stopped");
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -575,6 +877,7 @@ fn capture_loop(
 fn capture_loop(
     _channel: &Channel<AudioChunk>,
     _stop_flag: &Arc<AtomicBool>,
+    _application: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     Err("System audio capture is not supported on this platform.".into())
 }
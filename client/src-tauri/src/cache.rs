@@ -0,0 +1,441 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::commands;
+
+const CACHE_DB_FILE: &str = "offline-cache.db";
+const CACHE_KEY_NAME: &str = "paracord:offline-cache-key";
+const CACHE_FALLBACK_KEY_FILE: &str = "offline-cache.key";
+const NONCE_LEN: usize = 12;
+/// Recent history kept per channel; older pages always come from the server.
+const MAX_MESSAGES_PER_CHANNEL: i64 = 200;
+const MAX_MEDIA_ITEM_BYTES: usize = 512 * 1024;
+const MAX_MEDIA_TOTAL_BYTES: i64 = 64 * 1024 * 1024;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value BLOB NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+CREATE TABLE IF NOT EXISTS messages (
+    scope TEXT NOT NULL,
+    channel_id TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    payload BLOB NOT NULL,
+    PRIMARY KEY (scope, channel_id, id)
+);
+CREATE TABLE IF NOT EXISTS media (
+    scope TEXT NOT NULL,
+    url TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    size INTEGER NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (scope, url)
+);
+CREATE INDEX IF NOT EXISTS idx_media_fetched_at ON media (fetched_at);
+";
+
+/// Encrypted on-disk cache of recent messages, guild structure, and media
+/// thumbnails. Rows are sealed with AES-256-GCM under a key kept in the OS
+/// keyring; the database file alone exposes only IDs, URLs, and timestamps.
+#[derive(Default)]
+pub struct CacheState {
+    inner: Mutex<Option<OpenCache>>,
+}
+
+struct OpenCache {
+    conn: Connection,
+    cipher: Aes256Gcm,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedMedia {
+    pub content_type: String,
+    /// Base64 encoded bytes.
+    pub data: String,
+}
+
+impl OpenCache {
+    fn open(app: &tauri::AppHandle) -> Result<Self, String> {
+        let key = load_or_create_cache_key(app)?;
+        let cipher =
+            Aes256Gcm::new_from_slice(&key).map_err(|_| "invalid cache key".to_string())?;
+        let dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("failed to resolve app data dir: {e}"))?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create app data dir: {e}"))?;
+        let conn = Connection::open(dir.join(CACHE_DB_FILE))
+            .map_err(|e| format!("failed to open offline cache: {e}"))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("failed to initialize offline cache: {e}"))?;
+        Ok(Self { conn, cipher })
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut nonce_bytes = [0_u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+            .map_err(|_| "cache encryption failed".to_string())?;
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce_bytes);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn open_sealed(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() <= NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+
+    fn open_json(&self, sealed: &[u8]) -> Option<Value> {
+        serde_json::from_slice(&self.open_sealed(sealed)?).ok()
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Load the cache key from the OS keyring, creating it on first use. When the
+/// keyring is unavailable the key is stored wrapped by the secure store
+/// fallback key instead.
+fn load_or_create_cache_key(app: &tauri::AppHandle) -> Result<[u8; 32], String> {
+    fn decode(encoded: &str) -> Option<[u8; 32]> {
+        let raw = BASE64_STANDARD.decode(encoded.trim()).ok()?;
+        raw.try_into().ok()
+    }
+
+    if let Ok(Some(encoded)) = commands::secure_store_get(CACHE_KEY_NAME.to_string()) {
+        if let Some(key) = decode(&encoded) {
+            return Ok(key);
+        }
+    }
+
+    let fallback_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to resolve app data dir: {e}"))?
+        .join("security")
+        .join(CACHE_FALLBACK_KEY_FILE);
+    if let Ok(wrapped) = std::fs::read_to_string(&fallback_path) {
+        if let Some(key) = commands::secure_store_fallback_decrypt(app.clone(), wrapped)
+            .ok()
+            .and_then(|encoded| decode(&encoded))
+        {
+            return Ok(key);
+        }
+    }
+
+    let mut key = [0_u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut key);
+    let encoded = BASE64_STANDARD.encode(key);
+    if commands::secure_store_set(CACHE_KEY_NAME.to_string(), encoded.clone()).is_err() {
+        let wrapped = commands::secure_store_fallback_encrypt(app.clone(), encoded)?;
+        if let Some(dir) = fallback_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create security directory: {e}"))?;
+        }
+        std::fs::write(&fallback_path, wrapped)
+            .map_err(|e| format!("failed to write cache key: {e}"))?;
+    }
+    Ok(key)
+}
+
+fn with_cache<T>(
+    app: &tauri::AppHandle,
+    state: &CacheState,
+    f: impl FnOnce(&OpenCache) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut guard = state.inner.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        *guard = Some(OpenCache::open(app)?);
+    }
+    match guard.as_ref() {
+        Some(cache) => f(cache),
+        None => Err("offline cache unavailable".into()),
+    }
+}
+
+fn message_id(message: &Value) -> Option<i64> {
+    message.get("id")?.as_str()?.parse().ok()
+}
+
+#[tauri::command]
+pub async fn cache_get(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    key: String,
+) -> Result<Option<Value>, String> {
+    with_cache(&app, &state, |cache| {
+        let sealed: Option<Vec<u8>> = cache
+            .conn
+            .query_row(
+                "SELECT value FROM entries WHERE scope = ?1 AND key = ?2",
+                params![scope, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("cache read failed: {e}"))?;
+        Ok(sealed.and_then(|sealed| cache.open_json(&sealed)))
+    })
+}
+
+#[tauri::command]
+pub async fn cache_put(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    key: String,
+    value: Value,
+) -> Result<(), String> {
+    with_cache(&app, &state, |cache| {
+        let raw = serde_json::to_vec(&value).map_err(|e| format!("cache encode failed: {e}"))?;
+        let sealed = cache.seal(&raw)?;
+        cache
+            .conn
+            .execute(
+                "INSERT INTO entries (scope, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (scope, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                params![scope, key, sealed, now_ms()],
+            )
+            .map_err(|e| format!("cache write failed: {e}"))?;
+        Ok(())
+    })
+}
+
+/// Cached messages for a channel, newest first (the server's page order).
+#[tauri::command]
+pub async fn cache_get_messages(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    channel_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<Value>, String> {
+    let limit = limit
+        .unwrap_or(MAX_MESSAGES_PER_CHANNEL)
+        .clamp(1, MAX_MESSAGES_PER_CHANNEL);
+    with_cache(&app, &state, |cache| {
+        let mut stmt = cache
+            .conn
+            .prepare(
+                "SELECT payload FROM messages WHERE scope = ?1 AND channel_id = ?2
+                 ORDER BY id DESC LIMIT ?3",
+            )
+            .map_err(|e| format!("cache read failed: {e}"))?;
+        let rows = stmt
+            .query_map(params![scope, channel_id, limit], |row| {
+                row.get::<_, Vec<u8>>(0)
+            })
+            .map_err(|e| format!("cache read failed: {e}"))?;
+        let messages = rows
+            .filter_map(Result::ok)
+            .filter_map(|sealed| cache.open_json(&sealed))
+            .collect();
+        Ok(messages)
+    })
+}
+
+/// Upsert messages for a channel and trim it to the most recent window.
+/// With `replace` set the channel's cached history is discarded first, for
+/// when the server reports a gap the cache cannot bridge.
+#[tauri::command]
+pub async fn cache_put_messages(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    channel_id: String,
+    messages: Vec<Value>,
+    replace: Option<bool>,
+) -> Result<(), String> {
+    with_cache(&app, &state, |cache| {
+        let tx = cache
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("cache write failed: {e}"))?;
+        if replace.unwrap_or(false) {
+            tx.execute(
+                "DELETE FROM messages WHERE scope = ?1 AND channel_id = ?2",
+                params![scope, channel_id],
+            )
+            .map_err(|e| format!("cache write failed: {e}"))?;
+        }
+        for message in &messages {
+            let Some(id) = message_id(message) else {
+                continue;
+            };
+            let raw =
+                serde_json::to_vec(message).map_err(|e| format!("cache encode failed: {e}"))?;
+            tx.execute(
+                "INSERT INTO messages (scope, channel_id, id, payload) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (scope, channel_id, id) DO UPDATE SET payload = excluded.payload",
+                params![scope, channel_id, id, cache.seal(&raw)?],
+            )
+            .map_err(|e| format!("cache write failed: {e}"))?;
+        }
+        tx.execute(
+            "DELETE FROM messages WHERE scope = ?1 AND channel_id = ?2 AND id NOT IN (
+                 SELECT id FROM messages WHERE scope = ?1 AND channel_id = ?2
+                 ORDER BY id DESC LIMIT ?3
+             )",
+            params![scope, channel_id, MAX_MESSAGES_PER_CHANNEL],
+        )
+        .map_err(|e| format!("cache write failed: {e}"))?;
+        tx.commit().map_err(|e| format!("cache write failed: {e}"))
+    })
+}
+
+#[tauri::command]
+pub async fn cache_delete_messages(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    channel_id: String,
+    message_ids: Vec<String>,
+) -> Result<(), String> {
+    with_cache(&app, &state, |cache| {
+        for id in message_ids.iter().filter_map(|id| id.parse::<i64>().ok()) {
+            cache
+                .conn
+                .execute(
+                    "DELETE FROM messages WHERE scope = ?1 AND channel_id = ?2 AND id = ?3",
+                    params![scope, channel_id, id],
+                )
+                .map_err(|e| format!("cache write failed: {e}"))?;
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn cache_get_media(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    url: String,
+) -> Result<Option<CachedMedia>, String> {
+    with_cache(&app, &state, |cache| {
+        let row: Option<(String, Vec<u8>)> = cache
+            .conn
+            .query_row(
+                "SELECT content_type, data FROM media WHERE scope = ?1 AND url = ?2",
+                params![scope, url],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("cache read failed: {e}"))?;
+        Ok(row.and_then(|(content_type, sealed)| {
+            let data = cache.open_sealed(&sealed)?;
+            Some(CachedMedia {
+                content_type,
+                data: BASE64_STANDARD.encode(data),
+            })
+        }))
+    })
+}
+
+/// Store a thumbnail. Oversized items are ignored and the oldest entries are
+/// evicted once the cache exceeds its size budget.
+#[tauri::command]
+pub async fn cache_put_media(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: String,
+    url: String,
+    content_type: String,
+    data: String,
+) -> Result<(), String> {
+    let bytes = BASE64_STANDARD
+        .decode(data.as_bytes())
+        .map_err(|_| "media payload is not valid base64".to_string())?;
+    if bytes.len() > MAX_MEDIA_ITEM_BYTES {
+        return Ok(());
+    }
+    with_cache(&app, &state, |cache| {
+        let sealed = cache.seal(&bytes)?;
+        cache
+            .conn
+            .execute(
+                "INSERT INTO media (scope, url, content_type, data, size, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (scope, url) DO UPDATE SET content_type = excluded.content_type,
+                     data = excluded.data, size = excluded.size, fetched_at = excluded.fetched_at",
+                params![
+                    scope,
+                    url,
+                    content_type,
+                    sealed,
+                    bytes.len() as i64,
+                    now_ms()
+                ],
+            )
+            .map_err(|e| format!("cache write failed: {e}"))?;
+        let total: i64 = cache
+            .conn
+            .query_row("SELECT COALESCE(SUM(size), 0) FROM media", [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| format!("cache read failed: {e}"))?;
+        if total > MAX_MEDIA_TOTAL_BYTES {
+            cache
+                .conn
+                .execute(
+                    "DELETE FROM media WHERE rowid IN (
+                         SELECT rowid FROM media ORDER BY fetched_at ASC
+                         LIMIT MAX(1, (SELECT COUNT(*) FROM media) / 4)
+                     )",
+                    [],
+                )
+                .map_err(|e| format!("cache write failed: {e}"))?;
+        }
+        Ok(())
+    })
+}
+
+/// Drop everything cached for one scope, or the whole cache when `scope` is
+/// omitted (e.g. on sign-out).
+#[tauri::command]
+pub async fn cache_clear(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CacheState>,
+    scope: Option<String>,
+) -> Result<(), String> {
+    with_cache(&app, &state, |cache| {
+        for table in ["entries", "messages", "media"] {
+            let result = match &scope {
+                Some(scope) => cache.conn.execute(
+                    &format!("DELETE FROM {table} WHERE scope = ?1"),
+                    params![scope],
+                ),
+                None => cache.conn.execute(&format!("DELETE FROM {table}"), []),
+            };
+            result.map_err(|e| format!("cache clear failed: {e}"))?;
+        }
+        Ok(())
+    })
+}
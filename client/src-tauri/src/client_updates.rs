@@ -0,0 +1,153 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+const MANIFEST_PATH: &str = "api/v1/client/update-manifest";
+
+/// Update found through a Paracord server's client update manifest, kept
+/// between the check, download, and install steps.
+#[derive(Default)]
+pub struct ClientUpdateState {
+    inner: Mutex<Option<PendingUpdate>>,
+}
+
+struct PendingUpdate {
+    update: Update,
+    bytes: Option<Vec<u8>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ServerUpdateInfo {
+    version: String,
+    current_version: String,
+    notes: Option<String>,
+    pub_date: Option<String>,
+    download_url: String,
+    minimum_version: Option<String>,
+}
+
+/// Updater target used when the frontend doesn't pick an installer flavor,
+/// matching the Tauri updater's own `{os}-{arch}` naming.
+fn default_target() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    format!("{os}-{}", std::env::consts::ARCH)
+}
+
+fn manifest_url(server_url: &str, channel: &str, target: &str) -> Result<Url, String> {
+    let base = format!("{}/", server_url.trim().trim_end_matches('/'));
+    let mut url = Url::parse(&base)
+        .and_then(|base| base.join(MANIFEST_PATH))
+        .map_err(|e| format!("invalid server URL: {e}"))?;
+    url.query_pairs_mut()
+        .append_pair("channel", channel)
+        .append_pair("platform", target);
+    Ok(url)
+}
+
+fn raw_string(update: &Update, key: &str) -> Option<String> {
+    update
+        .raw_json
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+/// Ask `server_url` for the latest build on `channel`. Errors when the
+/// server doesn't publish a manifest, so callers can fall back to GitHub.
+#[tauri::command]
+pub async fn client_update_check(
+    app: AppHandle,
+    state: State<'_, ClientUpdateState>,
+    server_url: String,
+    channel: String,
+    target: Option<String>,
+) -> Result<Option<ServerUpdateInfo>, String> {
+    let target = target.unwrap_or_else(default_target);
+    let endpoint = manifest_url(&server_url, &channel, &target)?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| format!("failed to configure updater: {e}"))?
+        .target(target)
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("failed to configure updater: {e}"))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("update check failed: {e}"))?;
+
+    let mut pending = state
+        .inner
+        .lock()
+        .map_err(|e| format!("update state lock poisoned: {e}"))?;
+    let Some(update) = update else {
+        *pending = None;
+        return Ok(None);
+    };
+    let info = ServerUpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        pub_date: raw_string(&update, "pub_date"),
+        download_url: update.download_url.to_string(),
+        minimum_version: raw_string(&update, "minimum_version"),
+    };
+    *pending = Some(PendingUpdate {
+        update,
+        bytes: None,
+    });
+    Ok(Some(info))
+}
+
+#[tauri::command]
+pub async fn client_update_download(state: State<'_, ClientUpdateState>) -> Result<(), String> {
+    let update = state
+        .inner
+        .lock()
+        .map_err(|e| format!("update state lock poisoned: {e}"))?
+        .as_ref()
+        .map(|pending| pending.update.clone())
+        .ok_or_else(|| "no update is pending".to_string())?;
+    let bytes = update
+        .download(|_, _| {}, || {})
+        .await
+        .map_err(|e| format!("update download failed: {e}"))?;
+
+    let mut pending = state
+        .inner
+        .lock()
+        .map_err(|e| format!("update state lock poisoned: {e}"))?;
+    match pending.as_mut() {
+        Some(pending) if pending.update.version == update.version => {
+            pending.bytes = Some(bytes);
+            Ok(())
+        }
+        _ => Err("update changed while downloading".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn client_update_install(state: State<'_, ClientUpdateState>) -> Result<(), String> {
+    let pending = state
+        .inner
+        .lock()
+        .map_err(|e| format!("update state lock poisoned: {e}"))?;
+    let pending = pending
+        .as_ref()
+        .ok_or_else(|| "no update is pending".to_string())?;
+    let bytes = pending
+        .bytes
+        .as_deref()
+        .ok_or_else(|| "update has not been downloaded".to_string())?;
+    pending
+        .update
+        .install(bytes)
+        .map_err(|e| format!("update install failed: {e}"))
+}
@@ -177,7 +177,7 @@ fn load_or_create_secure_store_fallback_key(app: &tauri::AppHandle) -> Result<[u
     Ok(key)
 }
 
-fn diagnostics_log_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+pub(crate) fn diagnostics_log_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
     let mut dir = if cfg!(windows) {
         if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
             std::path::PathBuf::from(local_app_data)
@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::commands;
+use crate::native_media::MediaState;
+
+const CRASH_CONSENT_FILE: &str = "crash-reporting.json";
+const MAX_RECENT_ERRORS: usize = 100;
+/// Only the tail of the client log goes into a bundle.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const MAX_BUNDLED_CRASHES: usize = 5;
+
+static RECENT_ERRORS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static CRASH_REPORTING_ENABLED: AtomicBool = AtomicBool::new(false);
+static CRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CrashConsent {
+    #[serde(default)]
+    enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReportingStatus {
+    pub enabled: bool,
+    /// Crash reports captured so far, newest first.
+    pub reports: Vec<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Remember an error for the next diagnostics bundle.
+pub fn record_error(message: impl Into<String>) {
+    let line = format!("{} {}", now_secs(), message.into());
+    if let Ok(mut errors) = RECENT_ERRORS.lock() {
+        if errors.len() == MAX_RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(line);
+    }
+}
+
+#[tauri::command]
+pub fn record_client_error(message: String) {
+    record_error(format!("[webview] {message}"));
+}
+
+fn consent_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to resolve app data dir: {e}"))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create app data dir: {e}"))?;
+    Ok(dir.join(CRASH_CONSENT_FILE))
+}
+
+fn crash_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let log_path = commands::diagnostics_log_path(app)?;
+    let dir = log_path
+        .parent()
+        .map(|logs| logs.join("crashes"))
+        .ok_or("failed to resolve crash report dir")?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create crash report dir: {e}"))?;
+    Ok(dir)
+}
+
+/// Crash report files (`.txt` and `.dmp`), newest first.
+fn list_crash_reports(dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    reports.reverse();
+    reports
+}
+
+fn panic_message(info: &std::panic::PanicHookInfo<'_>) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+fn write_crash_report(info: &std::panic::PanicHookInfo<'_>) {
+    let Some(dir) = CRASH_DIR.get() else {
+        return;
+    };
+    let stamp = now_secs();
+    let thread = std::thread::current();
+    let report = format!(
+        "Paracord {} crash report\nos: {} {}\nthread: {}\nlocation: {}\nmessage: {}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        thread.name().unwrap_or("<unnamed>"),
+        info.location()
+            .map(|loc| format!("{}:{}:{}", loc.file(), loc.line(), loc.column()))
+            .unwrap_or_else(|| "<unknown>".into()),
+        panic_message(info),
+        std::backtrace::Backtrace::force_capture(),
+    );
+    let _ = std::fs::write(dir.join(format!("crash-{stamp}.txt")), report);
+    #[cfg(windows)]
+    minidump::write(&dir.join(format!("crash-{stamp}.dmp")), None);
+}
+
+/// Install the panic hook (and on Windows an unhandled exception filter).
+/// Reports are only written once the user has opted in.
+pub fn install_crash_handler(app: &tauri::AppHandle) {
+    let enabled = consent_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str::<CrashConsent>(&raw).ok())
+        .unwrap_or_default()
+        .enabled;
+    CRASH_REPORTING_ENABLED.store(enabled, Ordering::SeqCst);
+    match crash_dir(app) {
+        Ok(dir) => {
+            let _ = CRASH_DIR.set(dir);
+        }
+        Err(err) => eprintln!("crash reporting unavailable: {err}"),
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if CRASH_REPORTING_ENABLED.load(Ordering::SeqCst) {
+            write_crash_report(info);
+        }
+        previous(info);
+    }));
+    #[cfg(windows)]
+    minidump::install_exception_filter();
+}
+
+#[tauri::command]
+pub fn crash_reporting_status(app: tauri::AppHandle) -> Result<CrashReportingStatus, String> {
+    let dir = crash_dir(&app)?;
+    Ok(CrashReportingStatus {
+        enabled: CRASH_REPORTING_ENABLED.load(Ordering::SeqCst),
+        reports: list_crash_reports(&dir)
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect(),
+    })
+}
+
+#[tauri::command]
+pub fn crash_reporting_set_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let raw = serde_json::to_string(&CrashConsent { enabled })
+        .map_err(|e| format!("failed to encode crash consent: {e}"))?;
+    std::fs::write(consent_path(&app)?, raw)
+        .map_err(|e| format!("failed to save crash consent: {e}"))?;
+    CRASH_REPORTING_ENABLED.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(target_os = "windows")]
+fn os_version() -> Option<String> {
+    command_output("cmd", &["/C", "ver"])
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    command_output("sw_vers", &["-productVersion"]).map(|v| format!("macOS {v}"))
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let pretty = std::fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|raw| {
+            raw.lines()
+                .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+                .map(|name| name.trim_matches('"').to_string())
+        });
+    let kernel = command_output("uname", &["-r"]);
+    match (pretty, kernel) {
+        (Some(pretty), Some(kernel)) => Some(format!("{pretty} (kernel {kernel})")),
+        (pretty, kernel) => pretty.or(kernel),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn os_version() -> Option<String> {
+    None
+}
+
+fn gpu_info() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    let raw = command_output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | ForEach-Object { \"$($_.Name) (driver $($_.DriverVersion))\" }",
+        ],
+    );
+    #[cfg(target_os = "macos")]
+    let raw = command_output("system_profiler", &["SPDisplaysDataType"]).map(|text| {
+        text.lines()
+            .filter_map(|line| line.trim().strip_prefix("Chipset Model:"))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    #[cfg(target_os = "linux")]
+    let raw = command_output("lspci", &[]).map(|text| {
+        text.lines()
+            .filter(|line| {
+                line.contains("VGA compatible controller")
+                    || line.contains("3D controller")
+                    || line.contains("Display controller")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    let raw: Option<String> = None;
+    raw.map(|text| {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn read_log_tail(path: &Path) -> Vec<u8> {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len > MAX_LOG_BYTES {
+        let _ = file.seek(SeekFrom::Start(len - MAX_LOG_BYTES));
+    }
+    let mut tail = Vec::new();
+    let _ = file.read_to_end(&mut tail);
+    tail
+}
+
+fn bundle_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = match app.path().download_dir() {
+        Ok(dir) => dir,
+        Err(_) => commands::diagnostics_log_path(app)?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or("failed to resolve diagnostics dir")?,
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create bundle dir: {e}"))?;
+    Ok(dir)
+}
+
+/// Assemble a zip with the client log, native media stats, system info,
+/// recent errors, and the latest crash reports. `client_info` carries what
+/// only the webview knows (user agent, WebGL renderer, UI errors). Returns
+/// the path of the written bundle.
+#[tauri::command]
+pub async fn diagnostics_create_bundle(
+    app: tauri::AppHandle,
+    media: tauri::State<'_, MediaState>,
+    client_info: Option<Value>,
+) -> Result<String, String> {
+    let media_stats = match media.session.lock().await.as_ref() {
+        Some(session) => json!(session.stats().await),
+        None => Value::Null,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let system = json!({
+            "app_version": env!("CARGO_PKG_VERSION"),
+            "tauri_version": tauri::VERSION,
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "os_version": os_version(),
+            "gpus": gpu_info(),
+            "crash_reporting_enabled": CRASH_REPORTING_ENABLED.load(Ordering::SeqCst),
+            "created_at": now_secs(),
+        });
+        let recent_errors = RECENT_ERRORS
+            .lock()
+            .map(|errors| errors.iter().cloned().collect::<Vec<_>>().join("\n"))
+            .unwrap_or_default();
+        let log_tail = commands::diagnostics_log_path(&app)
+            .map(|path| read_log_tail(&path))
+            .unwrap_or_default();
+        let crashes = crash_dir(&app)
+            .map(|dir| list_crash_reports(&dir))
+            .unwrap_or_default();
+
+        let path = bundle_dir(&app)?.join(format!("paracord-diagnostics-{}.zip", now_secs()));
+        let file = std::fs::File::create(&path)
+            .map_err(|e| format!("failed to create diagnostics bundle: {e}"))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        let pretty = |value: &Value| serde_json::to_vec_pretty(value).unwrap_or_default();
+
+        let mut entries: Vec<(String, Vec<u8>)> = vec![
+            ("system.json".into(), pretty(&system)),
+            ("media-session.json".into(), pretty(&media_stats)),
+            (
+                "client.json".into(),
+                pretty(&client_info.unwrap_or(Value::Null)),
+            ),
+            ("recent-errors.txt".into(), recent_errors.into_bytes()),
+            ("client.log".into(), log_tail),
+        ];
+        // A report is a .txt/.dmp pair; take the newest few reports whole.
+        let mut stamps: Vec<String> = Vec::new();
+        for crash in &crashes {
+            let Some(stem) = crash.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if !stamps.contains(&stem) {
+                if stamps.len() == MAX_BUNDLED_CRASHES {
+                    continue;
+                }
+                stamps.push(stem);
+            }
+            if let (Some(name), Ok(bytes)) = (crash.file_name(), std::fs::read(crash)) {
+                entries.push((format!("crashes/{}", name.to_string_lossy()), bytes));
+            }
+        }
+
+        for (name, bytes) in entries {
+            zip.start_file(name, options)
+                .map_err(|e| format!("failed to write diagnostics bundle: {e}"))?;
+            zip.write_all(&bytes)
+                .map_err(|e| format!("failed to write diagnostics bundle: {e}"))?;
+        }
+        zip.finish()
+            .map_err(|e| format!("failed to write diagnostics bundle: {e}"))?;
+        Ok(path.display().to_string())
+    })
+    .await
+    .map_err(|e| format!("diagnostics bundle task failed: {e}"))?
+}
+
+#[cfg(windows)]
+mod minidump {
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Diagnostics::Debug::{
+        MiniDumpWithThreadInfo, MiniDumpWriteDump, SetUnhandledExceptionFilter, EXCEPTION_POINTERS,
+        MINIDUMP_EXCEPTION_INFORMATION,
+    };
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId,
+    };
+
+    /// Write a minidump of this process, attributing it to `exception` when
+    /// called from the unhandled exception filter.
+    pub fn write(path: &Path, exception: Option<*const EXCEPTION_POINTERS>) {
+        let Ok(file) = std::fs::File::create(path) else {
+            return;
+        };
+        let exception_info = exception.map(|pointers| MINIDUMP_EXCEPTION_INFORMATION {
+            ThreadId: unsafe { GetCurrentThreadId() },
+            ExceptionPointers: pointers as *mut EXCEPTION_POINTERS,
+            ClientPointers: false.into(),
+        });
+        unsafe {
+            let _ = MiniDumpWriteDump(
+                GetCurrentProcess(),
+                GetCurrentProcessId(),
+                HANDLE(file.as_raw_handle()),
+                MiniDumpWithThreadInfo,
+                exception_info
+                    .as_ref()
+                    .map(|info| info as *const MINIDUMP_EXCEPTION_INFORMATION),
+                None,
+                None,
+            );
+        }
+    }
+
+    unsafe extern "system" fn on_unhandled_exception(pointers: *const EXCEPTION_POINTERS) -> i32 {
+        if super::CRASH_REPORTING_ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
+            if let Some(dir) = super::CRASH_DIR.get() {
+                let stamp = super::now_secs();
+                let _ = std::fs::write(
+                    dir.join(format!("crash-{stamp}.txt")),
+                    format!(
+                        "Paracord {} crash report\nos: windows {}\nunhandled native exception; see crash-{stamp}.dmp\n",
+                        env!("CARGO_PKG_VERSION"),
+                        std::env::consts::ARCH,
+                    ),
+                );
+                write(&dir.join(format!("crash-{stamp}.dmp")), Some(pointers));
+            }
+        }
+        // EXCEPTION_CONTINUE_SEARCH: let Windows Error Reporting run as usual.
+        0
+    }
+
+    pub fn install_exception_filter() {
+        unsafe {
+            SetUnhandledExceptionFilter(Some(on_unhandled_exception));
+        }
+    }
+}
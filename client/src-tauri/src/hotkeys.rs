@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::native_media::MediaState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ToggleMute,
+    ToggleDeafen,
+    PushToTalk,
+}
+
+impl HotkeyAction {
+    fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleMute => "Toggle Mute",
+            HotkeyAction::ToggleDeafen => "Toggle Deafen",
+            HotkeyAction::PushToTalk => "Push to Talk",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    /// Key combo as stored in user keybinds, e.g. `Ctrl+Shift+M`.
+    pub accelerator: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub action: HotkeyAction,
+    pub accelerator: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Payload of the `voice_hotkey` event. When `native` is true the native
+/// media session has already applied the new mute/deaf state and the webview
+/// only needs to mirror it; otherwise the webview performs the action itself.
+#[derive(Debug, Clone, Serialize)]
+struct VoiceHotkeyEvent {
+    action: HotkeyAction,
+    pressed: bool,
+    native: bool,
+    muted: Option<bool>,
+    deafened: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct HotkeyState {
+    bindings: Mutex<HashMap<Shortcut, HotkeyAction>>,
+    /// Set while push-to-talk has unmuted the native session.
+    ptt_engaged: AtomicBool,
+}
+
+/// Turn a keybind string from user settings into a shortcut accelerator.
+fn parse_accelerator(raw: &str) -> Result<Shortcut, String> {
+    let parts: Vec<String> = raw
+        .split('+')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => "Control".to_string(),
+            "meta" | "win" | "cmd" | "command" | "super" => "Super".to_string(),
+            "option" => "Alt".to_string(),
+            "esc" => "Escape".to_string(),
+            "spacebar" => "Space".to_string(),
+            _ => part.to_string(),
+        })
+        .collect();
+    if parts.is_empty() {
+        return Err("empty key combo".into());
+    }
+    Shortcut::from_str(&parts.join("+")).map_err(|e| format!("invalid key combo '{raw}': {e}"))
+}
+
+/// Combos the OS reserves; registering them would either fail silently or
+/// steal a system action from the user.
+fn reserved_shortcuts() -> Vec<Shortcut> {
+    #[cfg(target_os = "windows")]
+    let combos: &[&str] = &[
+        "Alt+F4",
+        "Alt+Tab",
+        "Control+Alt+Delete",
+        "Super+L",
+        "Super+D",
+    ];
+    #[cfg(target_os = "macos")]
+    let combos: &[&str] = &["Super+Q", "Super+Tab", "Super+Space", "Super+H", "Super+W"];
+    #[cfg(target_os = "linux")]
+    let combos: &[&str] = &["Alt+F4", "Alt+Tab", "Control+Alt+Delete", "Super+L"];
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    let combos: &[&str] = &[];
+    combos
+        .iter()
+        .filter_map(|combo| Shortcut::from_str(combo).ok())
+        .collect()
+}
+
+/// Global shortcuts need an X server; pure Wayland sessions cannot grab keys.
+fn global_shortcuts_unsupported() -> Option<&'static str> {
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() && std::env::var_os("DISPLAY").is_none() {
+            return Some("global hotkeys are not supported on Wayland without XWayland");
+        }
+    }
+    None
+}
+
+/// Replace all registered voice hotkeys. Every binding gets a status so the
+/// settings UI can flag conflicts next to the offending combo.
+#[tauri::command]
+pub fn hotkeys_register(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, HotkeyState>,
+    bindings: Vec<HotkeyBinding>,
+) -> Result<Vec<HotkeyStatus>, String> {
+    let shortcuts = app.global_shortcut();
+    let mut registered = state.bindings.lock().map_err(|e| e.to_string())?;
+    for shortcut in registered.keys() {
+        let _ = shortcuts.unregister(*shortcut);
+    }
+    registered.clear();
+    state.ptt_engaged.store(false, Ordering::SeqCst);
+
+    let unsupported = global_shortcuts_unsupported();
+    let reserved = reserved_shortcuts();
+    let mut statuses = Vec::with_capacity(bindings.len());
+    for binding in bindings {
+        let mut status = HotkeyStatus {
+            action: binding.action,
+            accelerator: binding.accelerator.clone(),
+            registered: false,
+            error: None,
+        };
+        let result = (|| {
+            if let Some(reason) = unsupported {
+                return Err(reason.to_string());
+            }
+            let shortcut = parse_accelerator(&binding.accelerator)?;
+            if reserved.contains(&shortcut) {
+                return Err("reserved by the operating system".to_string());
+            }
+            if let Some(other) = registered.get(&shortcut) {
+                return Err(format!("already bound to {}", other.label()));
+            }
+            shortcuts
+                .register(shortcut)
+                .map_err(|e| format!("in use by another application: {e}"))?;
+            Ok(shortcut)
+        })();
+        match result {
+            Ok(shortcut) => {
+                registered.insert(shortcut, binding.action);
+                status.registered = true;
+            }
+            Err(error) => status.error = Some(error),
+        }
+        statuses.push(status);
+    }
+    Ok(statuses)
+}
+
+#[tauri::command]
+pub fn hotkeys_clear(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, HotkeyState>,
+) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    let mut registered = state.bindings.lock().map_err(|e| e.to_string())?;
+    for shortcut in registered.keys() {
+        let _ = shortcuts.unregister(*shortcut);
+    }
+    registered.clear();
+    state.ptt_engaged.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Global shortcut plugin handler.
+pub fn handle_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    let state = app.state::<HotkeyState>();
+    let action = match state.bindings.lock() {
+        Ok(bindings) => bindings.get(shortcut).copied(),
+        Err(_) => None,
+    };
+    let Some(action) = action else {
+        return;
+    };
+    let pressed = event.state() == ShortcutState::Pressed;
+    // Only push-to-talk cares about key release.
+    if !pressed && action != HotkeyAction::PushToTalk {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let payload = apply_to_native_session(&app, action, pressed).await;
+        let _ = app.emit("voice_hotkey", payload);
+    });
+}
+
+async fn apply_to_native_session(
+    app: &tauri::AppHandle,
+    action: HotkeyAction,
+    pressed: bool,
+) -> VoiceHotkeyEvent {
+    let hotkeys = app.state::<HotkeyState>();
+    let media = app.state::<MediaState>();
+    let guard = media.session.lock().await;
+    let Some(session) = guard.as_ref() else {
+        return VoiceHotkeyEvent {
+            action,
+            pressed,
+            native: false,
+            muted: None,
+            deafened: None,
+        };
+    };
+
+    let muted = session.muted.load(Ordering::SeqCst);
+    let deafened = session.deafened.load(Ordering::SeqCst);
+    // Same transitions as the webview voice store's toggleMute/toggleDeaf.
+    let (next_muted, next_deafened) = match action {
+        HotkeyAction::ToggleMute => {
+            let next_muted = !muted;
+            (next_muted, if next_muted { deafened } else { false })
+        }
+        HotkeyAction::ToggleDeafen => {
+            let next_deafened = !deafened;
+            (next_deafened || muted, next_deafened)
+        }
+        HotkeyAction::PushToTalk if pressed => {
+            if muted && !deafened {
+                hotkeys.ptt_engaged.store(true, Ordering::SeqCst);
+                (false, deafened)
+            } else {
+                (muted, deafened)
+            }
+        }
+        HotkeyAction::PushToTalk => {
+            if hotkeys.ptt_engaged.swap(false, Ordering::SeqCst) {
+                (true, deafened)
+            } else {
+                (muted, deafened)
+            }
+        }
+    };
+    session.muted.store(next_muted, Ordering::SeqCst);
+    session.deafened.store(next_deafened, Ordering::SeqCst);
+
+    VoiceHotkeyEvent {
+        action,
+        pressed,
+        native: true,
+        muted: Some(next_muted),
+        deafened: Some(next_deafened),
+    }
+}
@@ -1,6 +1,12 @@
+mod accounts;
 mod audio_capture;
+mod cache;
+mod client_updates;
 mod commands;
+mod diagnostics;
+mod hotkeys;
 mod native_media;
+mod overlay;
 
 #[cfg(windows)]
 fn configure_webview2_overrides(app: &tauri::App) {
@@ -62,9 +68,19 @@ fn configure_webview2_overrides(app: &tauri::App) {
 pub fn run() {
     let builder = tauri::Builder::default()
         .manage(native_media::MediaState::new())
+        .manage(hotkeys::HotkeyState::default())
+        .manage(cache::CacheState::default())
+        .manage(overlay::OverlayState::default())
+        .manage(client_updates::ClientUpdateState::default())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::handle_shortcut)
+                .build(),
+        )
+        .on_window_event(overlay::close_with_main_window)
         .setup(|app| {
             let startup_line = format!(
                 "{} [desktop] startup version={} pid={}",
@@ -75,6 +91,7 @@ pub fn run() {
             if let Err(err) = commands::append_client_log(app.handle().clone(), startup_line) {
                 eprintln!("failed to write startup diagnostics log line: {err}");
             }
+            diagnostics::install_crash_handler(app.handle());
             #[cfg(windows)]
             configure_webview2_overrides(app);
             Ok(())
@@ -84,6 +101,9 @@ pub fn run() {
         commands::greet,
         commands::get_app_version,
         commands::get_update_target,
+        client_updates::client_update_check,
+        client_updates::client_update_download,
+        client_updates::client_update_install,
         commands::append_client_log,
         commands::get_client_log_path,
         commands::secure_store_set,
@@ -93,6 +113,35 @@ pub fn run() {
         commands::secure_store_fallback_decrypt,
         commands::set_activity_sharing_enabled,
         commands::get_foreground_application,
+        diagnostics::diagnostics_create_bundle,
+        diagnostics::crash_reporting_status,
+        diagnostics::crash_reporting_set_enabled,
+        diagnostics::record_client_error,
+        accounts::accounts_list,
+        accounts::accounts_save,
+        accounts::accounts_set_credential,
+        accounts::accounts_get_credential,
+        accounts::accounts_switch,
+        accounts::accounts_remove,
+        cache::cache_get,
+        cache::cache_put,
+        cache::cache_get_messages,
+        cache::cache_put_messages,
+        cache::cache_delete_messages,
+        cache::cache_get_media,
+        cache::cache_put_media,
+        cache::cache_clear,
+        hotkeys::hotkeys_register,
+        hotkeys::hotkeys_clear,
+        overlay::overlay_list_monitors,
+        overlay::overlay_show,
+        overlay::overlay_hide,
+        overlay::overlay_set_click_through,
+        overlay::overlay_set_roster,
+        overlay::overlay_get_state,
+        audio_capture::system_audio_capture_capabilities,
+        audio_capture::request_system_audio_capture_permission,
+        audio_capture::list_system_audio_applications,
         audio_capture::set_system_audio_capture_enabled,
         audio_capture::start_system_audio_capture,
         audio_capture::stop_system_audio_capture,
@@ -108,6 +157,8 @@ pub fn run() {
         native_media::commands::voice_enable_video,
         native_media::commands::voice_start_screen_share,
         native_media::commands::voice_stop_screen_share,
+        native_media::commands::screen_capture_capabilities,
+        native_media::commands::voice_start_native_screen_share,
         native_media::commands::voice_push_video_frame,
         native_media::commands::voice_push_screen_frame,
         native_media::commands::voice_set_screen_audio_enabled,
@@ -2,12 +2,19 @@ use std::sync::atomic::Ordering;
 use std::time::Instant;
 
 use bytes::{BufMut, BytesMut};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
 use tokio::time::{interval, Duration};
 
+use paracord_codec::audio::capture::{default_input_device_name, list_input_devices, AudioCapture};
 use paracord_codec::audio::opus::FRAME_SIZE;
+use paracord_codec::audio::playback::{
+    default_output_device_name, list_output_devices, AudioPlayback,
+};
 use paracord_transport::protocol::{MediaHeader, TrackType, HEADER_SIZE};
 
 use super::session::NativeMediaSession;
+use super::MediaState;
 
 const VOICE_BITRATE_BPS: i32 = 96_000;
 const STREAM_BITRATE_BPS: i32 = 192_000;
@@ -20,9 +27,11 @@ pub fn spawn_audio_send_task(session: &mut NativeMediaSession) {
     let local_ssrc = session.local_ssrc;
 
     // Take ownership of the PCM receiver — it moves into the task
-    let Some(mut pcm_rx) = session.pcm_rx.take() else {
+    let Some(pcm_rx) = session.pcm_rx.take() else {
         return;
     };
+    let mut pcm_rx = Some(pcm_rx);
+    let mut pcm_swap_rx = session.pcm_swap_rx.take();
     let mut screen_audio_rx = session.screen_audio_rx.take();
 
     let conn_inner = session.connection.inner().clone();
@@ -61,8 +70,29 @@ pub fn spawn_audio_send_task(session: &mut NativeMediaSession) {
                         None => screen_audio_rx = None,
                     }
                 }
-                frame = pcm_rx.recv() => {
-                    let Some(pcm) = frame else { break };
+                maybe_swap = async {
+                    match pcm_swap_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending::<Option<tokio::sync::mpsc::Receiver<Vec<f32>>>>().await,
+                    }
+                } => {
+                    match maybe_swap {
+                        Some(rx) => pcm_rx = Some(rx),
+                        None => pcm_swap_rx = None,
+                    }
+                }
+                frame = async {
+                    match pcm_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending::<Option<Vec<f32>>>().await,
+                    }
+                } => {
+                    // The capture stream went away (device unplugged); keep the
+                    // task alive until the device monitor swaps in a new one.
+                    let Some(pcm) = frame else {
+                        pcm_rx = None;
+                        continue;
+                    };
 
                     let include_screen_audio = screen_audio_enabled.load(Ordering::SeqCst);
                     let has_screen_audio = include_screen_audio && latest_screen_frame.is_some();
@@ -290,6 +320,163 @@ pub fn spawn_playout_task(session: &mut NativeMediaSession) {
     session.playout_task = Some(handle);
 }
 
+/// How often the device monitor re-enumerates audio devices.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Snapshot of the audio devices the OS currently exposes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AudioDeviceList {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+impl AudioDeviceList {
+    pub fn enumerate() -> Self {
+        let inputs = list_input_devices()
+            .map(|devices| devices.into_iter().map(|d| d.name).collect())
+            .unwrap_or_default();
+        let outputs = list_output_devices()
+            .map(|devices| devices.into_iter().map(|d| d.name).collect())
+            .unwrap_or_default();
+        Self { inputs, outputs }
+    }
+}
+
+/// Emitted when the session falls back to the default device because the
+/// one in use disappeared.
+#[derive(Debug, Clone, Serialize)]
+struct DeviceFallback {
+    kind: &'static str,
+    previous: Option<String>,
+    current: Option<String>,
+}
+
+/// Spawn the device monitor: polls for hot-plug changes and, when the input or
+/// output device in use disappears, fails over to the system default instead
+/// of leaving the session silent.
+pub fn spawn_device_monitor_task(session: &mut NativeMediaSession, app: tauri::AppHandle) {
+    let shutdown = session.shutdown.clone();
+
+    let handle = tokio::spawn(async move {
+        let mut tick = interval(DEVICE_POLL_INTERVAL);
+        // The first tick fires immediately, before the session is stored.
+        tick.tick().await;
+        let mut known: Option<AudioDeviceList> = None;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = tick.tick() => {
+                    let Ok(devices) = tokio::task::spawn_blocking(AudioDeviceList::enumerate).await else {
+                        continue;
+                    };
+                    if known.as_ref().is_some_and(|prev| *prev != devices) {
+                        let _ = app.emit("media_devices_changed", &devices);
+                    }
+
+                    let state = app.state::<MediaState>();
+                    let mut guard = state.session.lock().await;
+                    let Some(session) = guard.as_mut() else { continue };
+                    if let Some(fallback) = recover_input_device(session, &devices) {
+                        let _ = app.emit("media_device_fallback", fallback);
+                    }
+                    if let Some(fallback) = recover_output_device(session, &devices).await {
+                        let _ = app.emit("media_device_fallback", fallback);
+                    }
+                    drop(guard);
+
+                    known = Some(devices);
+                }
+            }
+        }
+    });
+
+    session.device_monitor_task = Some(handle);
+}
+
+fn recover_input_device(
+    session: &mut NativeMediaSession,
+    devices: &AudioDeviceList,
+) -> Option<DeviceFallback> {
+    let running = session
+        .audio_capture
+        .as_ref()
+        .is_some_and(|capture| capture.is_running());
+    let present = session
+        .input_device
+        .as_ref()
+        .map_or(true, |name| devices.inputs.contains(name));
+    if running && present {
+        return None;
+    }
+
+    // Retried on the next tick if no input device is available yet.
+    let (capture, rx) = match AudioCapture::start() {
+        Ok(started) => started,
+        Err(e) => {
+            tracing::debug!("input device fallback failed: {e}");
+            return None;
+        }
+    };
+    if let Some(old) = session.audio_capture.replace(capture) {
+        old.stop();
+    }
+    if session.pcm_swap_tx.try_send(rx).is_err() {
+        tracing::warn!("audio send task did not accept the new capture stream");
+    }
+    let previous = session.input_device.take();
+    session.input_device = default_input_device_name();
+    tracing::info!(?previous, current = ?session.input_device, "input device lost, using default");
+    Some(DeviceFallback {
+        kind: "input",
+        previous,
+        current: session.input_device.clone(),
+    })
+}
+
+async fn recover_output_device(
+    session: &mut NativeMediaSession,
+    devices: &AudioDeviceList,
+) -> Option<DeviceFallback> {
+    let present = session
+        .output_device
+        .as_ref()
+        .map_or(true, |name| devices.outputs.contains(name));
+    if session.audio_playback.is_running() && present {
+        return None;
+    }
+
+    let playback = match AudioPlayback::start() {
+        Ok(playback) => playback,
+        Err(e) => {
+            tracing::debug!("output device fallback failed: {e}");
+            return None;
+        }
+    };
+    replace_playback(session, playback).await;
+    let previous = session.output_device.take();
+    session.output_device = default_output_device_name();
+    tracing::info!(?previous, current = ?session.output_device, "output device lost, using default");
+    Some(DeviceFallback {
+        kind: "output",
+        previous,
+        current: session.output_device.clone(),
+    })
+}
+
+/// Swap the playback engine, re-registering every remote participant so
+/// audio continues on the new device.
+pub async fn replace_playback(session: &mut NativeMediaSession, playback: AudioPlayback) {
+    {
+        let mut remote = session.remote_audio.lock().await;
+        for (ssrc, state) in remote.iter_mut() {
+            state.playback_tx = playback.add_source(*ssrc);
+        }
+    }
+    let old = std::mem::replace(&mut session.audio_playback, playback);
+    old.stop();
+}
+
 /// Compute audio level from PCM samples.
 /// Returns 0 (loudest) to 127 (silence) in dBov-like scale.
 fn compute_audio_level(pcm: &[f32]) -> u8 {
@@ -38,6 +38,7 @@ pub async fn start_voice_session(
     audio_pipeline::spawn_audio_send_task(&mut session);
     audio_pipeline::spawn_datagram_recv_task(&mut session, app.clone());
     audio_pipeline::spawn_playout_task(&mut session);
+    audio_pipeline::spawn_device_monitor_task(&mut session, app.clone());
 
     // Spawn event tasks
     events::spawn_speaking_detector(&mut session, app.clone());
@@ -95,24 +96,30 @@ pub async fn voice_switch_input_device(
     device_id: String,
     state: State<'_, MediaState>,
 ) -> Result<(), String> {
-    use paracord_codec::audio::capture::AudioCapture;
+    use paracord_codec::audio::capture::{list_input_devices, AudioCapture};
 
-    let mut guard = state.session.lock().await;
-    let session = guard.as_mut().ok_or("no active session")?;
-
-    // Stop existing capture
-    if let Some(old) = session.audio_capture.take() {
-        old.stop();
-    }
-
-    // Start capture on new device
     let index: usize = device_id
         .parse()
         .map_err(|_| "invalid device index".to_string())?;
     let (capture, rx) =
         AudioCapture::start_device(index).map_err(|e| format!("capture device: {e}"))?;
-    session.audio_capture = Some(capture);
-    session.pcm_rx = Some(rx);
+    let name = list_input_devices()
+        .ok()
+        .and_then(|devices| devices.into_iter().find(|d| d.index == index))
+        .map(|d| d.name);
+
+    let mut guard = state.session.lock().await;
+    let session = guard.as_mut().ok_or("no active session")?;
+
+    // Hand the new stream to the running send task, then stop the old capture
+    session
+        .pcm_swap_tx
+        .try_send(rx)
+        .map_err(|e| format!("switch capture stream: {e}"))?;
+    if let Some(old) = session.audio_capture.replace(capture) {
+        old.stop();
+    }
+    session.input_device = name;
 
     Ok(())
 }
@@ -122,12 +129,24 @@ pub async fn voice_switch_output_device(
     device_id: String,
     state: State<'_, MediaState>,
 ) -> Result<(), String> {
-    // AudioPlayback doesn't support runtime device switching yet.
-    // We'd need to make start_from_device public (Task 12) and
-    // rebuild the playback with all existing sources.
-    let _ = device_id;
-    let _ = state;
-    Err("output device switching not yet implemented".into())
+    use paracord_codec::audio::playback::{list_output_devices, AudioPlayback};
+
+    let index: usize = device_id
+        .parse()
+        .map_err(|_| "invalid device index".to_string())?;
+    let playback =
+        AudioPlayback::start_device(index).map_err(|e| format!("playback device: {e}"))?;
+    let name = list_output_devices()
+        .ok()
+        .and_then(|devices| devices.into_iter().find(|d| d.index == index))
+        .map(|d| d.name);
+
+    let mut guard = state.session.lock().await;
+    let session = guard.as_mut().ok_or("no active session")?;
+    super::audio_pipeline::replace_playback(session, playback).await;
+    session.output_device = name;
+
+    Ok(())
 }
 
 // ── Video commands ──────────────────────────────────────────────────────────
@@ -155,6 +174,64 @@ pub async fn voice_stop_screen_share(state: State<'_, MediaState>) -> Result<(),
     Ok(())
 }
 
+/// Report whether screen share frames should come from the native capture
+/// backend (the webview has no `getDisplayMedia` on Linux).
+#[tauri::command]
+pub async fn screen_capture_capabilities() -> super::screen_capture::ScreenCaptureCapabilities {
+    super::screen_capture::capabilities().await
+}
+
+/// Start screen sharing from the native capture backend. The system share
+/// dialog picks the window or screen; frames go straight to the screen
+/// encoder without a round trip through the webview. Emits
+/// `screen_capture_ended` if the stream ends without `voice_stop_screen_share`.
+#[tauri::command]
+pub async fn voice_start_native_screen_share(
+    options: super::screen_capture::ScreenCaptureOptions,
+    state: State<'_, MediaState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    use super::screen_capture::CapturedFrame;
+    use tauri::{Emitter, Manager};
+
+    if state.session.lock().await.is_none() {
+        return Err("no active session".into());
+    }
+    // The share dialog can stay open for a while; don't hold the session lock.
+    let (frame_tx, mut frame_rx) = tokio::sync::mpsc::channel::<CapturedFrame>(2);
+    let capture = super::screen_capture::start(options, frame_tx).await?;
+
+    let mut guard = state.session.lock().await;
+    let session = guard.as_mut().ok_or("no active session")?;
+    super::video_pipeline::stop_screen_share(session);
+    super::video_pipeline::start_screen_share(session)?;
+
+    let task = tokio::spawn(async move {
+        while let Some(frame) = frame_rx.recv().await {
+            let state = app.state::<MediaState>();
+            let mut guard = state.session.lock().await;
+            let Some(session) = guard.as_mut() else {
+                return;
+            };
+            if let Err(e) = super::video_pipeline::encode_and_send_video_frame(
+                session,
+                frame.width,
+                frame.height,
+                &frame.rgba,
+                true,
+            ) {
+                tracing::debug!("native screen frame dropped: {e}");
+            }
+        }
+        // The capture backend went away on its own (stream closed from the
+        // system UI or by the compositor).
+        let _ = app.emit("screen_capture_ended", ());
+    });
+    session.screen_capture = Some(capture);
+    session.screen_send_task = Some(task);
+    Ok(())
+}
+
 /// Parse a binary frame payload: `[width:u32 LE][height:u32 LE][RGBA bytes…]`
 fn parse_frame_payload<'a>(
     request: &'a tauri::ipc::Request<'a>,
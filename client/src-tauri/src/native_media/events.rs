@@ -89,5 +89,6 @@ pub fn emit_participant_leave(app: &tauri::AppHandle, user_id: &str) {
 #[allow(dead_code)]
 pub fn emit_session_error(app: &tauri::AppHandle, error: &str) {
     use tauri::Emitter;
+    crate::diagnostics::record_error(format!("[media] {error}"));
     let _ = app.emit("media_session_error", error);
 }
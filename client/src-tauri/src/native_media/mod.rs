@@ -2,6 +2,7 @@ pub mod audio_pipeline;
 pub mod commands;
 pub mod events;
 pub mod file_transfer;
+pub mod screen_capture;
 pub mod session;
 pub mod video_pipeline;
 
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// What the user is asked to pick in the system share dialog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    #[default]
+    Monitor,
+    Window,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorCapture {
+    /// Draw the pointer into the captured frames.
+    #[default]
+    Embedded,
+    Hidden,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenCaptureOptions {
+    #[serde(default)]
+    pub source: CaptureSource,
+    #[serde(default)]
+    pub cursor: CursorCapture,
+    #[serde(default = "default_max_frame_rate")]
+    pub max_frame_rate: u32,
+}
+
+fn default_max_frame_rate() -> u32 {
+    30
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenCaptureCapabilities {
+    /// True when screen share frames come from a native backend instead of
+    /// the webview's `getDisplayMedia`.
+    pub supported: bool,
+    pub backend: Option<&'static str>,
+    pub sources: Vec<CaptureSource>,
+    pub cursor_modes: Vec<CursorCapture>,
+}
+
+impl ScreenCaptureCapabilities {
+    fn unsupported() -> Self {
+        Self {
+            supported: false,
+            backend: None,
+            sources: Vec::new(),
+            cursor_modes: Vec::new(),
+        }
+    }
+}
+
+/// A captured frame, already converted to tightly packed RGBA.
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// A running native capture. Dropping it stops the stream; the frame channel
+/// closes once the backend has shut down.
+pub struct ScreenCapture {
+    #[cfg(target_os = "linux")]
+    _inner: pipewire_capture::PortalCapture,
+}
+
+pub async fn capabilities() -> ScreenCaptureCapabilities {
+    #[cfg(target_os = "linux")]
+    {
+        pipewire_capture::capabilities().await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        ScreenCaptureCapabilities::unsupported()
+    }
+}
+
+/// Ask the user for a source and start delivering frames to `frames`.
+pub async fn start(
+    options: ScreenCaptureOptions,
+    frames: mpsc::Sender<CapturedFrame>,
+) -> Result<ScreenCapture, String> {
+    #[cfg(target_os = "linux")]
+    {
+        Ok(ScreenCapture {
+            _inner: pipewire_capture::start(options, frames).await?,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (options, frames);
+        Err("native screen capture is not supported on this platform".into())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Linux: xdg-desktop-portal ScreenCast + PipeWire
+// Works on Wayland compositors and on X11 desktops that ship the portal.
+// The portal shows the compositor's own picker (window vs full screen) and
+// hands back a PipeWire remote scoped to the chosen stream.
+// ---------------------------------------------------------------------------
+#[cfg(target_os = "linux")]
+mod pipewire_capture {
+    use super::{
+        CaptureSource, CapturedFrame, CursorCapture, ScreenCaptureCapabilities,
+        ScreenCaptureOptions,
+    };
+    use ashpd::desktop::screencast::{CursorMode, Screencast, SourceType};
+    use ashpd::desktop::PersistMode;
+    use pipewire as pw;
+    use pw::spa;
+    use spa::param::format::{FormatProperties, MediaSubtype, MediaType};
+    use spa::param::format_utils;
+    use spa::param::video::{VideoFormat, VideoInfoRaw};
+    use spa::pod::Pod;
+    use std::os::fd::OwnedFd;
+    use std::time::{Duration, Instant};
+    use tokio::sync::{mpsc, oneshot};
+
+    pub struct PortalCapture {
+        stop_stream: Option<pw::channel::Sender<()>>,
+        close_session: Option<oneshot::Sender<()>>,
+    }
+
+    impl Drop for PortalCapture {
+        fn drop(&mut self) {
+            if let Some(stop) = self.stop_stream.take() {
+                let _ = stop.send(());
+            }
+            if let Some(close) = self.close_session.take() {
+                let _ = close.send(());
+            }
+        }
+    }
+
+    pub async fn capabilities() -> ScreenCaptureCapabilities {
+        let Ok(proxy) = Screencast::new().await else {
+            return ScreenCaptureCapabilities::unsupported();
+        };
+        let source_types = proxy.available_source_types().await.unwrap_or_default();
+        let cursor_modes = proxy.available_cursor_modes().await.unwrap_or_default();
+
+        let mut sources = Vec::new();
+        if source_types.contains(SourceType::Monitor) {
+            sources.push(CaptureSource::Monitor);
+        }
+        if source_types.contains(SourceType::Window) {
+            sources.push(CaptureSource::Window);
+        }
+        let mut cursors = vec![CursorCapture::Hidden];
+        if cursor_modes.contains(CursorMode::Embedded) {
+            cursors.insert(0, CursorCapture::Embedded);
+        }
+        ScreenCaptureCapabilities {
+            supported: !sources.is_empty(),
+            backend: Some("pipewire"),
+            sources,
+            cursor_modes: cursors,
+        }
+    }
+
+    pub async fn start(
+        options: ScreenCaptureOptions,
+        frames: mpsc::Sender<CapturedFrame>,
+    ) -> Result<PortalCapture, String> {
+        let proxy: Screencast<'static> = Screencast::new()
+            .await
+            .map_err(|e| format!("screen capture portal unavailable: {e}"))?;
+        let cursor_mode = match options.cursor {
+            CursorCapture::Embedded
+                if proxy
+                    .available_cursor_modes()
+                    .await
+                    .map(|modes| modes.contains(CursorMode::Embedded))
+                    .unwrap_or(false) =>
+            {
+                CursorMode::Embedded
+            }
+            _ => CursorMode::Hidden,
+        };
+        let source_type = match options.source {
+            CaptureSource::Monitor => SourceType::Monitor,
+            CaptureSource::Window => SourceType::Window,
+        };
+
+        let session = proxy
+            .create_session()
+            .await
+            .map_err(|e| format!("failed to create screen capture session: {e}"))?;
+        proxy
+            .select_sources(
+                &session,
+                cursor_mode,
+                source_type.into(),
+                false,
+                None,
+                PersistMode::DoNot,
+            )
+            .await
+            .map_err(|e| format!("failed to select screen capture source: {e}"))?;
+        let response = proxy
+            .start(&session, None)
+            .await
+            .and_then(|request| request.response())
+            .map_err(|e| match e {
+                ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled) => {
+                    "screen capture cancelled".to_string()
+                }
+                e => format!("failed to start screen capture: {e}"),
+            })?;
+        let node_id = response
+            .streams()
+            .first()
+            .map(|stream| stream.pipe_wire_node_id())
+            .ok_or("screen capture portal returned no stream")?;
+        let fd = proxy
+            .open_pipe_wire_remote(&session)
+            .await
+            .map_err(|e| format!("failed to open PipeWire remote: {e}"))?;
+
+        let (stop_tx, stop_rx) = pw::channel::channel::<()>();
+        let max_frame_rate = options.max_frame_rate.clamp(1, 60);
+        std::thread::Builder::new()
+            .name("pipewire-screen-capture".into())
+            .spawn(move || {
+                if let Err(e) = run_stream(fd, node_id, max_frame_rate, frames, stop_rx) {
+                    tracing::warn!("PipeWire screen capture stopped: {e}");
+                }
+            })
+            .map_err(|e| format!("failed to spawn screen capture thread: {e}"))?;
+
+        // The portal session must stay open for as long as we capture.
+        let (close_tx, close_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = close_rx.await;
+            let _ = session.close().await;
+            drop(proxy);
+        });
+
+        Ok(PortalCapture {
+            stop_stream: Some(stop_tx),
+            close_session: Some(close_tx),
+        })
+    }
+
+    struct StreamData {
+        format: VideoInfoRaw,
+        frames: mpsc::Sender<CapturedFrame>,
+        min_interval: Duration,
+        last_frame: Option<Instant>,
+    }
+
+    /// Run the PipeWire loop for one stream until stopped or the stream ends.
+    fn run_stream(
+        fd: OwnedFd,
+        node_id: u32,
+        max_frame_rate: u32,
+        frames: mpsc::Sender<CapturedFrame>,
+        stop: pw::channel::Receiver<()>,
+    ) -> Result<(), pw::Error> {
+        pw::init();
+        let mainloop = pw::main_loop::MainLoop::new(None)?;
+        let context = pw::context::Context::new(&mainloop)?;
+        let core = context.connect_fd(fd, None)?;
+
+        let _stop = stop.attach(mainloop.loop_(), {
+            let mainloop = mainloop.clone();
+            move |_| mainloop.quit()
+        });
+
+        let stream = pw::stream::Stream::new(
+            &core,
+            "paracord-screen-capture",
+            pw::properties::properties! {
+                *pw::keys::MEDIA_TYPE => "Video",
+                *pw::keys::MEDIA_CATEGORY => "Capture",
+                *pw::keys::MEDIA_ROLE => "Screen",
+            },
+        )?;
+
+        let data = StreamData {
+            format: VideoInfoRaw::default(),
+            frames,
+            min_interval: Duration::from_secs(1) / max_frame_rate,
+            last_frame: None,
+        };
+        let _listener = stream
+            .add_local_listener_with_user_data(data)
+            .state_changed({
+                let mainloop = mainloop.clone();
+                move |_, _, _, state| {
+                    if matches!(
+                        state,
+                        pw::stream::StreamState::Error(_) | pw::stream::StreamState::Unconnected
+                    ) {
+                        mainloop.quit();
+                    }
+                }
+            })
+            .param_changed(|_, data, id, param| {
+                let Some(param) = param else {
+                    return;
+                };
+                if id != spa::param::ParamType::Format.as_raw() {
+                    return;
+                }
+                let Ok((media_type, media_subtype)) = format_utils::parse_format(param) else {
+                    return;
+                };
+                if media_type != MediaType::Video || media_subtype != MediaSubtype::Raw {
+                    return;
+                }
+                if let Err(e) = data.format.parse(param) {
+                    tracing::warn!("unsupported PipeWire video format: {e}");
+                }
+            })
+            .process({
+                let mainloop = mainloop.clone();
+                move |stream, data| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else {
+                        return;
+                    };
+                    let now = Instant::now();
+                    if data
+                        .last_frame
+                        .is_some_and(|last| now.duration_since(last) < data.min_interval)
+                    {
+                        return;
+                    }
+                    let size = data.format.size();
+                    let format = data.format.format();
+                    let datas = buffer.datas_mut();
+                    let Some(plane) = datas.first_mut() else {
+                        return;
+                    };
+                    let chunk = plane.chunk();
+                    let offset = chunk.offset() as usize;
+                    let stride = chunk.stride().max(0) as usize;
+                    let Some(bytes) = plane.data() else {
+                        return;
+                    };
+                    let Some(rgba) = packed_to_rgba(
+                        bytes.get(offset..).unwrap_or_default(),
+                        size.width,
+                        size.height,
+                        stride,
+                        format,
+                    ) else {
+                        return;
+                    };
+                    data.last_frame = Some(now);
+                    match data.frames.try_send(CapturedFrame {
+                        width: size.width,
+                        height: size.height,
+                        rgba,
+                    }) {
+                        // The encoder is behind; drop this frame.
+                        Ok(()) | Err(mpsc::error::TrySendError::Full(_)) => {}
+                        Err(mpsc::error::TrySendError::Closed(_)) => mainloop.quit(),
+                    }
+                }
+            })
+            .register()?;
+
+        let format = pw::spa::pod::object!(
+            pw::spa::utils::SpaTypes::ObjectParamFormat,
+            pw::spa::param::ParamType::EnumFormat,
+            pw::spa::pod::property!(FormatProperties::MediaType, Id, MediaType::Video),
+            pw::spa::pod::property!(FormatProperties::MediaSubtype, Id, MediaSubtype::Raw),
+            pw::spa::pod::property!(
+                FormatProperties::VideoFormat,
+                Choice,
+                Enum,
+                Id,
+                VideoFormat::BGRx,
+                VideoFormat::BGRx,
+                VideoFormat::BGRA,
+                VideoFormat::RGBx,
+                VideoFormat::RGBA
+            ),
+            pw::spa::pod::property!(
+                FormatProperties::VideoSize,
+                Choice,
+                Range,
+                Rectangle,
+                pw::spa::utils::Rectangle {
+                    width: 1920,
+                    height: 1080
+                },
+                pw::spa::utils::Rectangle {
+                    width: 1,
+                    height: 1
+                },
+                pw::spa::utils::Rectangle {
+                    width: 8192,
+                    height: 8192
+                }
+            ),
+            pw::spa::pod::property!(
+                FormatProperties::VideoFramerate,
+                Choice,
+                Range,
+                Fraction,
+                pw::spa::utils::Fraction {
+                    num: max_frame_rate,
+                    denom: 1
+                },
+                pw::spa::utils::Fraction { num: 0, denom: 1 },
+                pw::spa::utils::Fraction { num: 240, denom: 1 }
+            ),
+        );
+        let values: Vec<u8> = pw::spa::pod::serialize::PodSerializer::serialize(
+            std::io::Cursor::new(Vec::new()),
+            &pw::spa::pod::Value::Object(format),
+        )
+        .map_err(|_| pw::Error::CreationFailed)?
+        .0
+        .into_inner();
+        let mut params = [Pod::from_bytes(&values).ok_or(pw::Error::CreationFailed)?];
+
+        stream.connect(
+            spa::utils::Direction::Input,
+            Some(node_id),
+            pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+            &mut params,
+        )?;
+
+        mainloop.run();
+        Ok(())
+    }
+
+    /// Repack a 32-bit packed frame (with row padding) into tight RGBA.
+    fn packed_to_rgba(
+        src: &[u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        format: VideoFormat,
+    ) -> Option<Vec<u8>> {
+        let (swap_rb, opaque) = match format {
+            VideoFormat::RGBA => (false, false),
+            VideoFormat::RGBx => (false, true),
+            VideoFormat::BGRA => (true, false),
+            VideoFormat::BGRx => (true, true),
+            _ => return None,
+        };
+        let row_bytes = width as usize * 4;
+        let stride = if stride == 0 { row_bytes } else { stride };
+        if width == 0 || height == 0 || stride < row_bytes {
+            return None;
+        }
+        let mut rgba = Vec::with_capacity(row_bytes * height as usize);
+        for row in 0..height as usize {
+            let line = src.get(row * stride..row * stride + row_bytes)?;
+            for px in line.chunks_exact(4) {
+                let (r, b) = if swap_rb {
+                    (px[2], px[0])
+                } else {
+                    (px[0], px[2])
+                };
+                rgba.extend_from_slice(&[r, px[1], b, if opaque { 255 } else { px[3] }]);
+            }
+        }
+        Some(rgba)
+    }
+}
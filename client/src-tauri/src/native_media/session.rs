@@ -1,16 +1,17 @@
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use serde::Serialize;
 use tokio::sync::{mpsc, Notify};
 use tokio::task::JoinHandle;
 
-use paracord_codec::audio::capture::AudioCapture;
+use paracord_codec::audio::capture::{default_input_device_name, AudioCapture};
 use paracord_codec::audio::jitter::JitterBuffer;
 use paracord_codec::audio::noise::NoiseSuppressor;
 use paracord_codec::audio::opus::{OpusDecoder, OpusEncoder};
-use paracord_codec::audio::playback::AudioPlayback;
+use paracord_codec::audio::playback::{default_output_device_name, AudioPlayback};
 use paracord_codec::crypto::{FrameDecryptor, FrameEncryptor};
 use paracord_transport::connection::MediaConnection;
 use paracord_transport::endpoint::MediaEndpoint;
@@ -24,6 +25,36 @@ pub struct RemoteAudioState {
     pub audio_level: u8,
 }
 
+/// Jitter and loss figures for one remote participant.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteAudioStats {
+    pub ssrc: u32,
+    pub audio_level: u8,
+    pub buffer_depth: usize,
+    pub jitter_ms: f64,
+    pub packets_received: u64,
+    pub packets_lost: u64,
+    pub loss_rate: f64,
+    pub target_latency_ms: u32,
+}
+
+/// Point-in-time snapshot of a session for diagnostics.
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaSessionStats {
+    pub session_id: String,
+    pub remote_address: String,
+    pub rtt_ms: u128,
+    pub max_datagram_size: Option<usize>,
+    pub local_ssrc: u32,
+    pub muted: bool,
+    pub deafened: bool,
+    pub capture_running: bool,
+    pub playback_running: bool,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    pub remote_audio: Vec<RemoteAudioStats>,
+}
+
 /// Active native media session connected to the relay via QUIC.
 #[allow(dead_code)]
 pub struct NativeMediaSession {
@@ -34,12 +65,20 @@ pub struct NativeMediaSession {
     // Audio capture
     pub audio_capture: Option<AudioCapture>,
     pub pcm_rx: Option<mpsc::Receiver<Vec<f32>>>,
+    /// Hands a replacement capture receiver to the running send task when the
+    /// input device changes.
+    pub pcm_swap_tx: mpsc::Sender<mpsc::Receiver<Vec<f32>>>,
+    pub pcm_swap_rx: Option<mpsc::Receiver<mpsc::Receiver<Vec<f32>>>>,
+    /// Name of the input device in use, for hot-plug detection.
+    pub input_device: Option<String>,
     pub screen_audio_rx: Option<mpsc::Receiver<Vec<f32>>>,
     pub screen_audio_tx: mpsc::Sender<Vec<f32>>,
     pub screen_audio_enabled: Arc<AtomicBool>,
 
     // Audio playback
     pub audio_playback: AudioPlayback,
+    /// Name of the output device in use, for hot-plug detection.
+    pub output_device: Option<String>,
 
     // Opus codec
     pub opus_encoder: OpusEncoder,
@@ -71,6 +110,7 @@ pub struct NativeMediaSession {
     pub playout_task: Option<JoinHandle<()>>,
     pub speaking_task: Option<JoinHandle<()>>,
     pub control_recv_task: Option<JoinHandle<()>>,
+    pub device_monitor_task: Option<JoinHandle<()>>,
 
     // Video encoders (optional, behind feature gate)
     #[cfg(feature = "vpx")]
@@ -85,6 +125,8 @@ pub struct NativeMediaSession {
 
     pub video_send_task: Option<JoinHandle<()>>,
     pub screen_send_task: Option<JoinHandle<()>>,
+    /// Native screen capture feeding the screen encoder (Linux/PipeWire).
+    pub screen_capture: Option<super::screen_capture::ScreenCapture>,
 
     pub video_ssrc: u32,
     pub screen_ssrc: u32,
@@ -162,10 +204,13 @@ impl NativeMediaSession {
         let opus_encoder = OpusEncoder::new().map_err(|e| format!("opus encoder: {e}"))?;
         let noise_suppressor = NoiseSuppressor::new();
         let audio_playback = AudioPlayback::start().map_err(|e| format!("audio playback: {e}"))?;
+        let output_device = default_output_device_name();
 
         // Start audio capture
         let (audio_capture, pcm_rx) =
             AudioCapture::start().map_err(|e| format!("audio capture: {e}"))?;
+        let input_device = default_input_device_name();
+        let (pcm_swap_tx, pcm_swap_rx) = mpsc::channel::<mpsc::Receiver<Vec<f32>>>(4);
         let (screen_audio_tx, screen_audio_rx) = mpsc::channel::<Vec<f32>>(64);
 
         // E2EE key setup
@@ -185,10 +230,14 @@ impl NativeMediaSession {
             connection,
             audio_capture: Some(audio_capture),
             pcm_rx: Some(pcm_rx),
+            pcm_swap_tx,
+            pcm_swap_rx: Some(pcm_swap_rx),
+            input_device,
             screen_audio_rx: Some(screen_audio_rx),
             screen_audio_tx,
             screen_audio_enabled: Arc::new(AtomicBool::new(false)),
             audio_playback,
+            output_device,
             opus_encoder,
             noise_suppressor,
             frame_encryptor,
@@ -206,6 +255,7 @@ impl NativeMediaSession {
             playout_task: None,
             speaking_task: None,
             control_recv_task: None,
+            device_monitor_task: None,
             #[cfg(feature = "vpx")]
             video_encoder: None,
             #[cfg(feature = "vpx")]
@@ -216,6 +266,7 @@ impl NativeMediaSession {
             i420_convert_buf: Vec::new(),
             video_send_task: None,
             screen_send_task: None,
+            screen_capture: None,
             video_ssrc,
             screen_ssrc,
             video_seq: 0,
@@ -223,6 +274,45 @@ impl NativeMediaSession {
         })
     }
 
+    pub async fn stats(&self) -> MediaSessionStats {
+        let remote_audio = self
+            .remote_audio
+            .lock()
+            .await
+            .iter()
+            .map(|(&ssrc, state)| {
+                let jitter = state.jitter_buffer.stats();
+                RemoteAudioStats {
+                    ssrc,
+                    audio_level: state.audio_level,
+                    buffer_depth: jitter.buffer_depth,
+                    jitter_ms: jitter.jitter_ms,
+                    packets_received: jitter.packets_received,
+                    packets_lost: jitter.packets_lost,
+                    loss_rate: jitter.loss_rate,
+                    target_latency_ms: jitter.target_latency_ms,
+                }
+            })
+            .collect();
+        MediaSessionStats {
+            session_id: self.session_id.clone(),
+            remote_address: self.connection.remote_address().to_string(),
+            rtt_ms: self.connection.rtt().as_millis(),
+            max_datagram_size: self.connection.max_datagram_size(),
+            local_ssrc: self.local_ssrc,
+            muted: self.muted.load(Ordering::SeqCst),
+            deafened: self.deafened.load(Ordering::SeqCst),
+            capture_running: self
+                .audio_capture
+                .as_ref()
+                .is_some_and(|capture| capture.is_running()),
+            playback_running: self.audio_playback.is_running(),
+            input_device: self.input_device.clone(),
+            output_device: self.output_device.clone(),
+            remote_audio,
+        }
+    }
+
     /// Shut down the session, abort all tasks, and close the QUIC connection.
     pub async fn disconnect(&mut self) {
         // Signal all tasks to stop
@@ -244,12 +334,16 @@ impl NativeMediaSession {
         if let Some(h) = self.control_recv_task.take() {
             h.abort();
         }
+        if let Some(h) = self.device_monitor_task.take() {
+            h.abort();
+        }
         if let Some(h) = self.video_send_task.take() {
             h.abort();
         }
         if let Some(h) = self.screen_send_task.take() {
             h.abort();
         }
+        self.screen_capture = None;
 
         // Stop audio capture
         if let Some(capture) = self.audio_capture.take() {
@@ -60,8 +60,13 @@ pub fn start_screen_share(session: &mut NativeMediaSession) -> Result<(), String
     }
 }
 
-/// Stop screen share encoder.
+/// Stop screen share encoder and any native capture feeding it.
 pub fn stop_screen_share(session: &mut NativeMediaSession) {
+    if let Some(h) = session.screen_send_task.take() {
+        h.abort();
+    }
+    session.screen_capture = None;
+
     #[cfg(feature = "vpx")]
     {
         session.screen_encoder = None;
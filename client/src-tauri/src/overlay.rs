@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder};
+
+pub const OVERLAY_LABEL: &str = "overlay";
+const OVERLAY_WIDTH: f64 = 240.0;
+const OVERLAY_HEIGHT: f64 = 360.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayCorner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayConfig {
+    /// Monitor name from `overlay_list_monitors`; the primary monitor when
+    /// unset or no longer connected.
+    #[serde(default)]
+    pub monitor: Option<String>,
+    #[serde(default)]
+    pub corner: OverlayCorner,
+    /// Gap to the monitor edges, in logical pixels.
+    #[serde(default = "default_margin")]
+    pub margin: u32,
+    /// Let mouse input pass through to the game underneath.
+    #[serde(default = "default_click_through")]
+    pub click_through: bool,
+}
+
+fn default_margin() -> u32 {
+    16
+}
+
+fn default_click_through() -> bool {
+    true
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self {
+            monitor: None,
+            corner: OverlayCorner::default(),
+            margin: default_margin(),
+            click_through: default_click_through(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayParticipant {
+    pub user_id: String,
+    pub username: String,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub deafened: bool,
+}
+
+/// What the overlay window renders besides live speaking state, which it
+/// reads straight from `media_speaking_change`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OverlaySnapshot {
+    pub visible: bool,
+    pub config: OverlayConfig,
+    pub channel_name: Option<String>,
+    pub participants: Vec<OverlayParticipant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayMonitor {
+    pub name: String,
+    pub primary: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+#[derive(Default)]
+pub struct OverlayState {
+    snapshot: Mutex<OverlaySnapshot>,
+}
+
+impl OverlayState {
+    fn snapshot(&self) -> Result<OverlaySnapshot, String> {
+        self.snapshot
+            .lock()
+            .map(|snapshot| snapshot.clone())
+            .map_err(|e| format!("overlay state poisoned: {e}"))
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut OverlaySnapshot)) -> Result<OverlaySnapshot, String> {
+        let mut snapshot = self
+            .snapshot
+            .lock()
+            .map_err(|e| format!("overlay state poisoned: {e}"))?;
+        apply(&mut snapshot);
+        Ok(snapshot.clone())
+    }
+}
+
+fn emit_snapshot(app: &tauri::AppHandle, snapshot: &OverlaySnapshot) {
+    let _ = app.emit_to(OVERLAY_LABEL, "overlay_state", snapshot);
+}
+
+fn find_monitor(app: &tauri::AppHandle, name: Option<&str>) -> Result<tauri::Monitor, String> {
+    if let Some(name) = name {
+        let monitors = app
+            .available_monitors()
+            .map_err(|e| format!("failed to list monitors: {e}"))?;
+        if let Some(monitor) = monitors
+            .into_iter()
+            .find(|monitor| monitor.name().map(String::as_str) == Some(name))
+        {
+            return Ok(monitor);
+        }
+    }
+    app.primary_monitor()
+        .map_err(|e| format!("failed to resolve primary monitor: {e}"))?
+        .ok_or_else(|| "no monitor available for the overlay".to_string())
+}
+
+/// Move the overlay into the configured corner of its monitor.
+fn place_overlay(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    config: &OverlayConfig,
+) -> Result<(), String> {
+    let monitor = find_monitor(app, config.monitor.as_deref())?;
+    let scale = monitor.scale_factor();
+    let origin = monitor.position();
+    let size = monitor.size();
+    let margin = (config.margin as f64 * scale).round() as i32;
+    let width = (OVERLAY_WIDTH * scale).round() as i32;
+    let height = (OVERLAY_HEIGHT * scale).round() as i32;
+
+    let left = origin.x + margin;
+    let right = origin.x + size.width as i32 - width - margin;
+    let top = origin.y + margin;
+    let bottom = origin.y + size.height as i32 - height - margin;
+    let (x, y) = match config.corner {
+        OverlayCorner::TopLeft => (left, top),
+        OverlayCorner::TopRight => (right, top),
+        OverlayCorner::BottomLeft => (left, bottom),
+        OverlayCorner::BottomRight => (right, bottom),
+    };
+    window
+        .set_size(tauri::PhysicalSize::new(width as u32, height as u32))
+        .map_err(|e| format!("failed to size overlay: {e}"))?;
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| format!("failed to position overlay: {e}"))
+}
+
+fn overlay_window(app: &tauri::AppHandle) -> Result<tauri::WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        return Ok(window);
+    }
+    let builder = WebviewWindowBuilder::new(app, OVERLAY_LABEL, WebviewUrl::App("overlay".into()))
+        .title("Paracord Overlay")
+        .inner_size(OVERLAY_WIDTH, OVERLAY_HEIGHT)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .shadow(false)
+        .visible(false);
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    builder
+        .build()
+        .map_err(|e| format!("failed to create overlay window: {e}"))
+}
+
+#[tauri::command]
+pub fn overlay_list_monitors(app: tauri::AppHandle) -> Result<Vec<OverlayMonitor>, String> {
+    let primary = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| format!("failed to list monitors: {e}"))?;
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let name = monitor
+                .name()
+                .cloned()
+                .unwrap_or_else(|| format!("Display {}", index + 1));
+            OverlayMonitor {
+                primary: primary.as_deref() == Some(name.as_str()),
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+                scale_factor: monitor.scale_factor(),
+                name,
+            }
+        })
+        .collect())
+}
+
+/// Show the overlay (creating it on first use) with the given placement.
+/// Async so window creation does not block the main thread on Windows.
+#[tauri::command]
+pub async fn overlay_show(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OverlayState>,
+    config: OverlayConfig,
+) -> Result<(), String> {
+    let window = overlay_window(&app)?;
+    place_overlay(&app, &window, &config)?;
+    window
+        .set_ignore_cursor_events(config.click_through)
+        .map_err(|e| format!("failed to set overlay click-through: {e}"))?;
+    window
+        .show()
+        .map_err(|e| format!("failed to show overlay: {e}"))?;
+    let snapshot = state.update(|snapshot| {
+        snapshot.visible = true;
+        snapshot.config = config;
+    })?;
+    emit_snapshot(&app, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn overlay_hide(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OverlayState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        window
+            .close()
+            .map_err(|e| format!("failed to close overlay: {e}"))?;
+    }
+    state.update(|snapshot| snapshot.visible = false)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn overlay_set_click_through(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OverlayState>,
+    enabled: bool,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        window
+            .set_ignore_cursor_events(enabled)
+            .map_err(|e| format!("failed to set overlay click-through: {e}"))?;
+    }
+    let snapshot = state.update(|snapshot| snapshot.config.click_through = enabled)?;
+    emit_snapshot(&app, &snapshot);
+    Ok(())
+}
+
+/// Replace the voice roster shown in the overlay. Called by the main window
+/// whenever the connected channel or its participants change.
+#[tauri::command]
+pub fn overlay_set_roster(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OverlayState>,
+    channel_name: Option<String>,
+    participants: Vec<OverlayParticipant>,
+) -> Result<(), String> {
+    let snapshot = state.update(|snapshot| {
+        snapshot.channel_name = channel_name;
+        snapshot.participants = participants;
+    })?;
+    emit_snapshot(&app, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn overlay_get_state(state: tauri::State<'_, OverlayState>) -> Result<OverlaySnapshot, String> {
+    state.snapshot()
+}
+
+/// The overlay must not outlive the main window.
+pub fn close_with_main_window(window: &tauri::Window, event: &tauri::WindowEvent) {
+    if window.label() != "main" || !matches!(event, tauri::WindowEvent::Destroyed) {
+        return;
+    }
+    if let Some(overlay) = window.app_handle().get_webview_window(OVERLAY_LABEL) {
+        let _ = overlay.close();
+    }
+}
@@ -78,6 +78,7 @@ function ProtectedRoute({ children }: { children: React.ReactNode }) {
   const servers = useServerListStore((s) => s.servers);
   const token = useAuthStore((s) => s.token);
   const sessionBootstrapComplete = useAuthStore((s) => s.sessionBootstrapComplete);
+  const offlineUser = useAuthStore((s) => (s.offline ? s.user : null));
   const settings = useAuthStore((s) => s.settings);
   const hasFetchedSettings = useAuthStore((s) => s.hasFetchedSettings);
   const fetchSettings = useAuthStore((s) => s.fetchSettings);
@@ -122,8 +123,9 @@ function ProtectedRoute({ children }: { children: React.ReactNode }) {
     return <Navigate to="/connect" />;
   }
 
-  // Password mode: valid token can enter directly.
-  if (token && serverStatus === 'ready') {
+  // Password mode: valid token can enter directly; offline desktop sessions
+  // read from the local cache until the token refresh succeeds.
+  if ((token || offlineUser) && serverStatus === 'ready') {
     return <>{children}</>;
   }
 
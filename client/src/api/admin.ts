@@ -1,4 +1,5 @@
 import { apiClient } from './client';
+import type { InstanceBranding } from '../types';
 
 export interface SecurityEvent {
   id: string;
@@ -29,6 +30,14 @@ export const adminApi = {
   updateSettings: (data: Record<string, string>) =>
     apiClient.patch<Record<string, string>>('/admin/settings', data),
 
+  uploadBrandingLogo: (file: File) => {
+    const formData = new FormData();
+    formData.append('image', file);
+    return apiClient.put<InstanceBranding>('/admin/branding/logo', formData);
+  },
+
+  deleteBrandingLogo: () => apiClient.delete('/admin/branding/logo'),
+
   getUsers: (params?: { offset?: number; limit?: number }) =>
     apiClient.get<{
       users: Array<{
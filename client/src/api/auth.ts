@@ -1,5 +1,5 @@
 import { apiClient } from './client';
-import type { LoginRequest, LoginResponse, RegisterRequest, ReadState, User, UserSettings } from '../types';
+import type { LoginRequest, LoginResponse, RegisterRequest, ReadState, User, UserSettings, UserSettingsUpdate } from '../types';
 
 export interface AuthSession {
   id: string;
@@ -10,6 +10,8 @@ export interface AuthSession {
   issued_at: string;
   last_seen_at: string;
   expires_at: string;
+  device_approved?: boolean;
+  device_approval_requested_at?: string | null;
 }
 
 export interface AuthOptions {
@@ -29,12 +31,19 @@ export const authApi = {
   logout: () => apiClient.post('/auth/logout'),
   listSessions: () => apiClient.get<AuthSession[]>('/auth/sessions'),
   revokeSession: (sessionId: string) => apiClient.delete(`/auth/sessions/${sessionId}`),
+  approveSessionDevice: (sessionId: string) => apiClient.post(`/auth/sessions/${sessionId}/approve`),
+  denySessionDevice: (sessionId: string) => apiClient.post(`/auth/sessions/${sessionId}/deny`),
+  recoverDevice: (recoveryCode: string) =>
+    apiClient.post<{ recovery_code: string }>('/auth/device-recovery', { recovery_code: recoveryCode }),
+  regenerateDeviceRecoveryCode: () =>
+    apiClient.post<{ recovery_code: string }>('/auth/device-recovery-code'),
   attachPublicKey: (publicKey: string) =>
     apiClient.post<LoginResponse>('/auth/attach-public-key', { public_key: publicKey }),
   getMe: () => apiClient.get<User>('/users/@me'),
   updateMe: (data: Partial<User>) => apiClient.patch<User>('/users/@me', data),
   getSettings: () => apiClient.get<UserSettings>('/users/@me/settings'),
-  updateSettings: (data: Partial<UserSettings>) => apiClient.patch<UserSettings>('/users/@me/settings', data),
+  updateSettings: (data: UserSettingsUpdate) =>
+    apiClient.patch<UserSettings & { device_recovery_code?: string }>('/users/@me/settings', data),
   getReadStates: () => apiClient.get<ReadState[]>('/users/@me/read-states'),
   changePassword: (currentPassword: string, newPassword: string) =>
     apiClient.put('/users/@me/password', {
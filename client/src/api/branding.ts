@@ -0,0 +1,38 @@
+import { apiClient } from './client';
+import type { GuildTheme, GuildThemeAsset, InstanceBranding } from '../types';
+
+const MAX_THEME_ASSET_BYTES = 1024 * 1024;
+const VALID_ASSET_NAME = /^[A-Za-z0-9_-]{1,32}$/;
+
+interface UpdateGuildThemeRequest {
+  accent_color?: string | null;
+  custom_css?: string | null;
+}
+
+export const brandingApi = {
+  get: () => apiClient.get<InstanceBranding>('/branding'),
+};
+
+export const guildThemeApi = {
+  get: (guildId: string) => apiClient.get<GuildTheme>(`/guilds/${guildId}/theme`),
+
+  update: (guildId: string, data: UpdateGuildThemeRequest) =>
+    apiClient.put<GuildTheme>(`/guilds/${guildId}/theme`, data),
+
+  uploadAsset: (guildId: string, name: string, file: File) => {
+    const trimmed = name.trim();
+    if (!VALID_ASSET_NAME.test(trimmed)) {
+      throw new Error('Asset name must be 1-32 characters using letters, numbers, dash, or underscore.');
+    }
+    if (file.size <= 0 || file.size > MAX_THEME_ASSET_BYTES) {
+      throw new Error('Theme assets must be between 1 byte and 1 MB.');
+    }
+    const formData = new FormData();
+    formData.append('name', trimmed);
+    formData.append('image', file);
+    return apiClient.post<GuildThemeAsset>(`/guilds/${guildId}/theme/assets`, formData);
+  },
+
+  deleteAsset: (guildId: string, assetId: string) =>
+    apiClient.delete(`/guilds/${guildId}/theme/assets/${assetId}`),
+};
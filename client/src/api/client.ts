@@ -1,5 +1,7 @@
 import axios, { type AxiosError, type AxiosInstance } from 'axios';
 import { resolveApiBaseUrl } from '../lib/apiBaseUrl';
+import { clientVersionHeaders, noteClientOutdated } from '../lib/clientUpdates';
+import { deviceIdHeaders } from '../lib/deviceId';
 import { clearLegacyPersistedAuth, getAccessToken, getRefreshToken, setAccessToken, setRefreshToken } from '../lib/authToken';
 import { useAuthStore } from '../stores/authStore';
 import { useServerListStore } from '../stores/serverListStore';
@@ -8,6 +10,8 @@ import { toast } from '../stores/toastStore';
 /** Standardized API error response shape from the server. */
 export interface ApiErrorResponse {
   code: string;
+  /** Numeric catalogue code (see docs/api-contracts.md). */
+  error_code?: number;
   message: string;
   details?: unknown;
   /** Legacy field kept for backwards compatibility. */
@@ -60,7 +64,7 @@ export function toastApiError(err: unknown): void {
 // New code should use createApiClient() or the connection manager.
 export const apiClient = axios.create({
   baseURL: resolveApiBaseUrl(),
-  headers: { 'Content-Type': 'application/json' },
+  headers: { 'Content-Type': 'application/json', 'X-Paracord-Permissions-Version': '2' },
   withCredentials: true,
   timeout: 15_000, // 15s default timeout to avoid indefinite hangs.
 });
@@ -81,6 +85,7 @@ function markActiveServerApiReachable(reachable: boolean): void {
 apiClient.interceptors.request.use((config) => {
   // Resolve at request time so "Add Server" updates apply without full reload.
   config.baseURL = resolveApiBaseUrl();
+  Object.assign(config.headers, clientVersionHeaders(), deviceIdHeaders());
   const token = getAccessToken();
   if (token && token !== 'null' && token !== 'undefined') {
     config.headers.Authorization = `Bearer ${token}`;
@@ -99,6 +104,7 @@ apiClient.interceptors.response.use(
     if (err.response) {
       // HTTP response means transport was reachable (even for 4xx/5xx).
       markActiveServerApiReachable(true);
+      noteClientOutdated(err.response.status, err.response.data);
     }
 
     if (
@@ -150,13 +156,14 @@ export function createApiClient(
 ): AxiosInstance {
   const client = axios.create({
     baseURL: baseUrl,
-    headers: { 'Content-Type': 'application/json' },
+    headers: { 'Content-Type': 'application/json', 'X-Paracord-Permissions-Version': '2' },
     withCredentials: true,
     timeout: 15_000,
   });
 
   // Auth interceptor
   client.interceptors.request.use((config) => {
+    Object.assign(config.headers, clientVersionHeaders(), deviceIdHeaders());
     const token = getToken();
     if (token && token !== 'null' && token !== 'undefined') {
       config.headers.Authorization = `Bearer ${token}`;
@@ -174,6 +181,7 @@ export function createApiClient(
       const original = err.config as { _retry?: boolean; url?: string; headers?: Record<string, string> };
       if (err.response) {
         onApiReachabilityChanged?.(true);
+        noteClientOutdated(err.response.status, err.response.data);
       }
       const token = getToken();
       if (
@@ -1,8 +1,33 @@
 import { apiClient } from './client';
-import type { Channel } from '../types';
+import type { Channel, GroupDmPrekeyBundle, GroupDmSenderKey } from '../types';
 
 export const dmApi = {
   list: () => apiClient.get<Channel[]>('/users/@me/dms'),
   create: (recipientId: string) =>
     apiClient.post<Channel>('/users/@me/dms', { recipient_id: recipientId }),
+  createGroup: (recipientIds: string[], name?: string) =>
+    apiClient.post<Channel>('/users/@me/dms', { recipient_ids: recipientIds, name }),
+  listRequests: () => apiClient.get<Channel[]>('/users/@me/dms/requests'),
+  acceptRequest: (channelId: string) =>
+    apiClient.post<void>(`/users/@me/dms/requests/${channelId}/accept`),
+  declineRequest: (channelId: string) =>
+    apiClient.post<void>(`/users/@me/dms/requests/${channelId}/decline`),
+  updateGroup: (channelId: string, data: { name?: string; icon?: string }) =>
+    apiClient.patch<Channel>(`/users/@me/dms/${channelId}`, data),
+  leaveGroup: (channelId: string) => apiClient.delete<void>(`/users/@me/dms/${channelId}`),
+  addRecipient: (channelId: string, userId: string) =>
+    apiClient.put<void>(`/users/@me/dms/${channelId}/recipients/${userId}`),
+  removeRecipient: (channelId: string, userId: string) =>
+    apiClient.delete<void>(`/users/@me/dms/${channelId}/recipients/${userId}`),
+  transferOwner: (channelId: string, ownerId: string) =>
+    apiClient.post<Channel>(`/users/@me/dms/${channelId}/owner`, { owner_id: ownerId }),
+  getGroupKeys: (channelId: string) =>
+    apiClient.get<GroupDmPrekeyBundle[]>(`/users/@me/dms/${channelId}/keys`),
+  getSenderKeys: (channelId: string) =>
+    apiClient.get<GroupDmSenderKey[]>(`/users/@me/dms/${channelId}/sender-keys`),
+  uploadSenderKeys: (
+    channelId: string,
+    epoch: number,
+    keys: { recipient_id: string; ciphertext: string; header?: string }[],
+  ) => apiClient.post<void>(`/users/@me/dms/${channelId}/sender-keys`, { epoch, keys }),
 };
@@ -4,6 +4,7 @@ import type {
   Guild,
   Channel,
   Member,
+  Presence,
   Role,
   Invite,
   Ban,
@@ -20,6 +21,11 @@ export const guildApi = {
   create: (data: CreateGuildRequest) => apiClient.post<Guild>('/guilds', data),
   get: (id: string) => apiClient.get<Guild>(`/guilds/${id}`),
   update: (id: string, data: Partial<Guild>) => apiClient.patch<Guild>(`/guilds/${id}`, data),
+  uploadIcon: (id: string, file: File) => {
+    const formData = new FormData();
+    formData.append('icon', file);
+    return apiClient.put<Guild>(`/guilds/${id}/icon`, formData);
+  },
   delete: (id: string) => apiClient.delete(`/guilds/${id}`),
   transferOwnership: (id: string, newOwnerId: string) =>
     apiClient.post(`/guilds/${id}/owner`, { new_owner_id: newOwnerId }),
@@ -30,6 +36,8 @@ export const guildApi = {
     apiClient.post<Channel>(`/guilds/${id}/channels`, data),
 
   getMembers: (id: string) => apiClient.get<Member[]>(`/guilds/${id}/members`),
+  getPresences: (id: string, params?: { after?: string; limit?: number }) =>
+    apiClient.get<Presence[]>(`/guilds/${id}/presences`, { params }),
   updateMember: (guildId: string, userId: string, data: UpdateMemberRequest) =>
     apiClient.patch<Member>(`/guilds/${guildId}/members/${userId}`, data),
   kickMember: (guildId: string, userId: string) =>
@@ -1,6 +1,15 @@
 import { invoke } from '@tauri-apps/api/core';
 import { check, type Update } from '@tauri-apps/plugin-updater';
 import { useCallback, useEffect, useMemo, useRef, useState } from 'react';
+import { getStoredServerUrl } from '../lib/apiBaseUrl';
+import {
+  CLIENT_VERSION,
+  checkServerUpdate,
+  downloadServerUpdate,
+  installServerUpdate,
+  useClientUpdateGateStore,
+  type ServerUpdateInfo,
+} from '../lib/clientUpdates';
 import { isTauri } from '../lib/tauriEnv';
 
 const GITHUB_OWNER = (import.meta.env.VITE_GITHUB_OWNER as string | undefined)?.trim() || 'Scoduglas1999';
@@ -17,9 +26,12 @@ interface UpdateTargetInfo {
 }
 
 interface AvailableUpdate {
+  /** `server` updates come from the connected server's update manifest. */
+  source: 'github' | 'server';
   version: string;
   releaseTag: string;
-  htmlUrl: string;
+  htmlUrl: string | null;
+  notes: string | null;
   publishedAt: string | null;
   assetName: string;
   target: string | null;
@@ -109,15 +121,31 @@ function extractUpdateInfo(update: Update, target: string | null): AvailableUpda
   const assetName = assetUrl ? fileNameFromUrl(assetUrl) : `Paracord ${version} update`;
 
   return {
+    source: 'github',
     version,
     releaseTag,
     htmlUrl,
+    notes: null,
     publishedAt,
     assetName,
     target,
   };
 }
 
+function serverUpdateToInfo(update: ServerUpdateInfo, target: string | null): AvailableUpdate {
+  const version = normalizeVersion(update.version);
+  return {
+    source: 'server',
+    version,
+    releaseTag: `v${version}`,
+    htmlUrl: null,
+    notes: update.notes?.trim() || null,
+    publishedAt: update.pub_date,
+    assetName: fileNameFromUrl(update.download_url),
+    target,
+  };
+}
+
 function getErrorMessage(error: unknown): string {
   if (error instanceof Error && error.message) return error.message;
   if (typeof error === 'string') return error;
@@ -138,6 +166,7 @@ export function UpdateNotification() {
   const [errorText, setErrorText] = useState<string | null>(null);
   const [visible, setVisible] = useState(false);
   const [updateInfo, setUpdateInfo] = useState<AvailableUpdate | null>(null);
+  const requiredVersion = useClientUpdateGateStore((s) => s.requiredVersion);
 
   useEffect(() => {
     statusRef.current = status;
@@ -183,6 +212,35 @@ export function UpdateNotification() {
     try {
       const targetInfo = await invoke<UpdateTargetInfo>('get_update_target');
       const target = buildUpdaterTarget(targetInfo);
+
+      // A server that publishes a client update manifest is authoritative;
+      // servers without one fall back to the GitHub release feed.
+      const serverUrl = getStoredServerUrl();
+      let serverUpdate: ServerUpdateInfo | null | undefined;
+      if (serverUrl) {
+        try {
+          serverUpdate = await checkServerUpdate(serverUrl, target);
+        } catch {
+          serverUpdate = undefined;
+        }
+      }
+      if (serverUpdate !== undefined) {
+        await closeActiveUpdate();
+        const info = serverUpdate ? serverUpdateToInfo(serverUpdate, target) : null;
+        const dismissed = window.localStorage.getItem(DISMISSED_RELEASE_STORAGE_KEY);
+        const required = useClientUpdateGateStore.getState().requiredVersion;
+        if (!info || (dismissed === info.releaseTag && !required)) {
+          setStatus('idle');
+          setVisible(false);
+          setUpdateInfo(null);
+          return;
+        }
+        setUpdateInfo(info);
+        setStatus('available');
+        setVisible(true);
+        return;
+      }
+
       const update = await check(target ? { target, timeout: 15_000 } : { timeout: 15_000 });
 
       if (!update) {
@@ -232,6 +290,12 @@ export function UpdateNotification() {
     };
   }, [closeActiveUpdate]);
 
+  // A server just rejected this build; look for the update it asks for.
+  useEffect(() => {
+    if (!requiredVersion) return;
+    void checkForUpdates();
+  }, [checkForUpdates, requiredVersion]);
+
   const onDismiss = useCallback(() => {
     if (status === 'downloaded') {
       setVisible(false);
@@ -250,52 +314,101 @@ export function UpdateNotification() {
 
   const onDownload = useCallback(async () => {
     const update = activeUpdateRef.current;
-    if (!update || status === 'downloading') return;
+    const fromServer = updateInfo?.source === 'server';
+    if ((!update && !fromServer) || status === 'downloading') return;
 
     setStatus('downloading');
     setErrorText(null);
 
     try {
-      await update.download();
+      if (fromServer) {
+        await downloadServerUpdate();
+      } else if (update) {
+        await update.download();
+      }
       setStatus('downloaded');
       setVisible(true);
     } catch (error) {
       setStatus('available');
       setErrorText(getErrorMessage(error));
     }
-  }, [status]);
+  }, [status, updateInfo]);
 
   const onRestartAndInstall = useCallback(async () => {
     const update = activeUpdateRef.current;
-    if (!update) return;
+    const fromServer = updateInfo?.source === 'server';
+    if (!update && !fromServer) return;
 
     setErrorText(null);
     try {
-      await update.install();
+      if (fromServer) {
+        await installServerUpdate();
+      } else if (update) {
+        await update.install();
+      }
     } catch (error) {
       setErrorText(getErrorMessage(error));
     }
-  }, []);
+  }, [updateInfo]);
+
+  if (!runningInTauri) return null;
 
-  if (!runningInTauri || !visible || !updateInfo) return null;
+  if (requiredVersion && (!visible || !updateInfo)) {
+    return (
+      <div className="fixed bottom-4 right-4 z-[140] w-[min(24rem,calc(100vw-1.5rem))] rounded-xl border border-border-subtle bg-[color:var(--bg-floating)] p-3 shadow-xl backdrop-blur">
+        <div className="text-sm font-semibold text-text-primary">Update required</div>
+        <div className="mt-1 text-xs text-text-secondary">
+          This server requires Paracord {requiredVersion} or newer. You are running {CLIENT_VERSION}.
+        </div>
+        {errorText && <div className="mt-2 text-xs text-accent-danger">{errorText}</div>}
+        <div className="mt-3 flex flex-wrap gap-2">
+          <button
+            className="btn-primary !min-h-9 !px-3 !text-sm"
+            onClick={() => void checkForUpdates()}
+            type="button"
+            disabled={status === 'checking'}
+          >
+            {status === 'checking' ? 'Checking...' : 'Check for updates'}
+          </button>
+          <a
+            className="btn-ghost !min-h-9 !px-3 !text-sm"
+            href={`https://github.com/${GITHUB_OWNER}/${GITHUB_REPO}/releases/latest`}
+            target="_blank"
+            rel="noreferrer"
+          >
+            Download manually
+          </a>
+        </div>
+      </div>
+    );
+  }
+
+  if (!visible || !updateInfo) return null;
 
   return (
     <div className="fixed bottom-4 right-4 z-[140] w-[min(24rem,calc(100vw-1.5rem))] rounded-xl border border-border-subtle bg-[color:var(--bg-floating)] p-3 shadow-xl backdrop-blur">
       <div className="flex items-start justify-between gap-3">
         <div className="min-w-0">
-          <div className="text-sm font-semibold text-text-primary">New release available</div>
+          <div className="text-sm font-semibold text-text-primary">
+            {requiredVersion ? 'Update required' : 'New release available'}
+          </div>
           <div className="mt-1 text-xs text-text-secondary">
             Paracord {updateInfo.version}
             {updateInfo.publishedAt ? ` - ${new Date(updateInfo.publishedAt).toLocaleDateString()}` : ''}
           </div>
-          <a
-            className="mt-1 inline-block text-xs text-text-link hover:underline"
-            href={updateInfo.htmlUrl}
-            target="_blank"
-            rel="noreferrer"
-          >
-            View release notes
-          </a>
+          {updateInfo.notes && (
+            <div className="mt-1 line-clamp-3 whitespace-pre-line text-xs text-text-muted">{updateInfo.notes}</div>
+          )}
+          {updateInfo.htmlUrl && (
+            <a
+              className="mt-1 inline-block text-xs text-text-link hover:underline"
+              href={updateInfo.htmlUrl}
+              target="_blank"
+              rel="noreferrer"
+            >
+              View release notes
+            </a>
+          )}
         </div>
         <button
           className="icon-btn !h-7 !w-7 shrink-0 text-text-muted hover:text-text-primary"
@@ -80,7 +80,11 @@ export function BotStoreSection({ guildId, canManage }: BotStoreSectionProps) {
     useEffect(() => {
         // Initialize config state from database when modal opens
         if (configuringId && botSettings[configuringId]) {
-            setConfigState(botSettings[configuringId] || {});
+            const initial = { ...(botSettings[configuringId] || {}) };
+            if (configuringId === 'auto_mod' && botSettings.url_filter?.level) {
+                initial.url_filter_level = botSettings.url_filter.level;
+            }
+            setConfigState(initial);
         } else {
             setConfigState({});
         }
@@ -129,7 +133,15 @@ export function BotStoreSection({ guildId, canManage }: BotStoreSectionProps) {
 
     const saveConfig = async () => {
         if (!canManage || !configuringId) return;
-        const newSettings = { ...botSettings, [configuringId]: { ...configState, enabled: true } };
+        const { url_filter_level, ...botConfig } = configState;
+        const newSettings: Record<string, any> = { ...botSettings, [configuringId]: { ...botConfig, enabled: true } };
+        if (configuringId === 'auto_mod') {
+            if (url_filter_level) {
+                newSettings.url_filter = { level: url_filter_level };
+            } else {
+                delete newSettings.url_filter;
+            }
+        }
         await updateGuild(guildId, { bot_settings: newSettings });
         setConfiguringId(null);
     };
@@ -205,6 +217,20 @@ export function BotStoreSection({ guildId, canManage }: BotStoreSectionProps) {
                                     />
                                     <p className="settings-description mt-1 text-xs">Comma separated list of words. Any message containing these will be automatically deleted.</p>
                                 </div>
+                                <div>
+                                    <label className="settings-label">Known Scam Links</label>
+                                    <select
+                                        className="select-field w-full"
+                                        value={configState.url_filter_level || ''}
+                                        onChange={(e) => setConfigState({ ...configState, url_filter_level: e.target.value })}
+                                    >
+                                        <option value="">Server default</option>
+                                        <option value="flag">Flag for moderators</option>
+                                        <option value="block">Block the message</option>
+                                        <option value="off">Off</option>
+                                    </select>
+                                    <p className="settings-description mt-1 text-xs">Links to domains on the server's phishing denylist.</p>
+                                </div>
                             </>
                         )}
                     </div>
@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useState } from 'react';
 import type { ChangeEvent, ReactNode } from 'react';
-import { X, Upload, GripVertical, Shield, Users, Hash, Link, Gavel, ScrollText, RefreshCw, Trash2, Smile, Calendar, Bot, ArrowLeft, HardDrive, LayoutTemplate } from 'lucide-react';
+import { X, Upload, GripVertical, Shield, Users, Hash, Link, Gavel, ScrollText, RefreshCw, Trash2, Smile, Calendar, Bot, ArrowLeft, HardDrive, LayoutTemplate, Palette } from 'lucide-react';
 import { useLocation, useNavigate } from 'react-router-dom';
 import { guildApi } from '../../api/guilds';
 import { inviteApi } from '../../api/invites';
@@ -21,6 +21,7 @@ import { buildGuildEmojiImageUrl } from '../../lib/customEmoji';
 import { EventList } from './EventList';
 import { ChannelManager } from './ChannelManager';
 import { FileStorageSection } from './FileStorageSection';
+import { GuildThemeSection } from './GuildThemeSection';
 import { ServerHubSettings } from './ServerHubSettings';
 import { BotStoreSection } from './BotStoreSection';
 
@@ -30,7 +31,7 @@ interface GuildSettingsProps {
   onClose: () => void;
 }
 
-type SettingsSection = 'overview' | 'server-hub' | 'bot-store' | 'roles' | 'members' | 'channels' | 'invites' | 'emojis' | 'webhooks' | 'bots' | 'events' | 'bans' | 'audit-log' | 'file-storage';
+type SettingsSection = 'overview' | 'server-hub' | 'bot-store' | 'roles' | 'members' | 'channels' | 'invites' | 'emojis' | 'webhooks' | 'bots' | 'events' | 'bans' | 'audit-log' | 'file-storage' | 'theme';
 
 const NAV_ITEMS: { id: SettingsSection; label: string; icon: ReactNode }[] = [
   { id: 'overview', label: 'Overview', icon: <Hash size={16} /> },
@@ -45,6 +46,7 @@ const NAV_ITEMS: { id: SettingsSection; label: string; icon: ReactNode }[] = [
   { id: 'bots', label: 'Bots', icon: <Bot size={16} /> },
   { id: 'events', label: 'Events', icon: <Calendar size={16} /> },
   { id: 'file-storage', label: 'File Storage', icon: <HardDrive size={16} /> },
+  { id: 'theme', label: 'Theme', icon: <Palette size={16} /> },
   { id: 'bans', label: 'Bans', icon: <Gavel size={16} /> },
   { id: 'audit-log', label: 'Audit Log', icon: <ScrollText size={16} /> },
 ];
@@ -102,6 +104,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
   const [editingMemberRoleUserId, setEditingMemberRoleUserId] = useState<string | null>(null);
   const [draftMemberRoleIds, setDraftMemberRoleIds] = useState<string[]>([]);
   const [iconDataUrl, setIconDataUrl] = useState<string | null>(null);
+  const [iconFile, setIconFile] = useState<File | null>(null);
   const [banReasonInput, setBanReasonInput] = useState('');
   const [banConfirmUserId, setBanConfirmUserId] = useState<string | null>(null);
   const [ownershipTargetUserId, setOwnershipTargetUserId] = useState('');
@@ -167,7 +170,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
       } else if (incomingIcon.startsWith('data:')) {
         setIconDataUrl(isSafeImageDataUrl(incomingIcon) ? incomingIcon : null);
       } else {
-        setIconDataUrl(`/api/v1/guilds/${guildId}/icon`);
+        setIconDataUrl(`/api/v1/guilds/${guildId}/icon?v=${incomingIcon}`);
       }
       setRoles(rolesRes.data);
       setMembers(membersRes.data);
@@ -222,7 +225,8 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
       requested === 'events' ||
       requested === 'bans' ||
       requested === 'audit-log' ||
-      requested === 'file-storage'
+      requested === 'file-storage' ||
+      requested === 'theme'
     ) {
       setActiveSection(requested);
     }
@@ -290,11 +294,12 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
 
   const saveOverview = async () => {
     await runAction(async () => {
-      await guildApi.update(guildId, {
-        name,
-        description,
-        icon: iconDataUrl?.startsWith('data:') ? iconDataUrl : undefined,
-      });
+      await guildApi.update(guildId, { name, description });
+      if (iconFile) {
+        // The server resizes uploads into size variants served from /icon.
+        await guildApi.uploadIcon(guildId, iconFile);
+        setIconFile(null);
+      }
       await refreshAll();
     }, 'Failed to save server overview');
   };
@@ -307,6 +312,7 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
       return;
     }
     setError(null);
+    setIconFile(file);
     const reader = new FileReader();
     reader.onload = () => {
       if (typeof reader.result === 'string') {
@@ -1690,6 +1696,13 @@ export function GuildSettings({ guildId, guildName, onClose }: GuildSettingsProp
             />
           )}
 
+          {activeSection === 'theme' && (
+            <GuildThemeSection
+              guildId={guildId}
+              canManage={canManageRoleSettings}
+            />
+          )}
+
           {activeSection === 'bans' && (
             <div className="settings-surface-card min-h-[calc(100dvh-13.5rem)] !p-8 max-sm:!p-6 card-stack">
               <h2 className="settings-section-title !mb-0">Bans</h2>
@@ -0,0 +1,260 @@
+import { useEffect, useState, useCallback } from 'react';
+import type { ChangeEvent } from 'react';
+import { Copy, ImagePlus, Trash2 } from 'lucide-react';
+import { guildThemeApi } from '../../api/branding';
+import { resolveResourceUrl } from '../../lib/apiBaseUrl';
+import { isAllowedImageMimeType } from '../../lib/security';
+import { confirm } from '../../stores/confirmStore';
+import type { GuildThemeAsset } from '../../types';
+
+interface GuildThemeSectionProps {
+  guildId: string;
+  canManage: boolean;
+}
+
+const HEX_COLOR_RE = /^#[0-9a-f]{6}$/i;
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  return `${(bytes / 1024).toFixed(1)} KB`;
+}
+
+export function GuildThemeSection({ guildId, canManage }: GuildThemeSectionProps) {
+  const [accentColor, setAccentColor] = useState('');
+  const [customCss, setCustomCss] = useState('');
+  const [assets, setAssets] = useState<GuildThemeAsset[]>([]);
+  const [assetName, setAssetName] = useState('');
+  const [loading, setLoading] = useState(true);
+  const [saving, setSaving] = useState(false);
+  const [uploading, setUploading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const getApiErrorMessage = (err: unknown, fallback: string) => {
+    const responseData = (err as { response?: { data?: { message?: string; error?: string } } }).response?.data;
+    return responseData?.message || responseData?.error || (err instanceof Error ? err.message : fallback);
+  };
+
+  const loadTheme = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      const { data } = await guildThemeApi.get(guildId);
+      setAccentColor(data.accent_color ?? '');
+      setCustomCss(data.custom_css ?? '');
+      setAssets(data.assets);
+    } catch (err: unknown) {
+      setError(getApiErrorMessage(err, 'Failed to load theme'));
+    } finally {
+      setLoading(false);
+    }
+  }, [guildId]);
+
+  useEffect(() => {
+    void loadTheme();
+  }, [loadTheme]);
+
+  const saveTheme = async () => {
+    if (!canManage) return;
+    const accent = accentColor.trim();
+    if (accent && !HEX_COLOR_RE.test(accent)) {
+      setError('Accent color must be a hex color like #4f7cff.');
+      return;
+    }
+    setSaving(true);
+    setError(null);
+    try {
+      const { data } = await guildThemeApi.update(guildId, {
+        accent_color: accent,
+        custom_css: customCss,
+      });
+      setAccentColor(data.accent_color ?? '');
+      setCustomCss(data.custom_css ?? '');
+      setAssets(data.assets);
+    } catch (err: unknown) {
+      setError(getApiErrorMessage(err, 'Failed to save theme'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const uploadAsset = async (event: ChangeEvent<HTMLInputElement>) => {
+    const file = event.target.files?.[0];
+    event.target.value = '';
+    if (!file) return;
+    if (!isAllowedImageMimeType(file.type)) {
+      setError('Theme assets must be PNG, JPEG, GIF, or WebP images.');
+      return;
+    }
+    setUploading(true);
+    setError(null);
+    try {
+      const { data } = await guildThemeApi.uploadAsset(guildId, assetName, file);
+      setAssets((prev) => [...prev, data]);
+      setAssetName('');
+    } catch (err: unknown) {
+      setError(getApiErrorMessage(err, 'Failed to upload asset'));
+    } finally {
+      setUploading(false);
+    }
+  };
+
+  const deleteAsset = async (asset: GuildThemeAsset) => {
+    if (!(await confirm({
+      title: `Delete asset "${asset.name}"?`,
+      description: 'Rules in the custom CSS that reference it will stop loading.',
+      confirmLabel: 'Delete',
+      variant: 'danger',
+    }))) return;
+    setError(null);
+    try {
+      await guildThemeApi.deleteAsset(guildId, asset.id);
+      setAssets((prev) => prev.filter((a) => a.id !== asset.id));
+    } catch (err: unknown) {
+      setError(getApiErrorMessage(err, 'Failed to delete asset'));
+    }
+  };
+
+  const copyAssetUrl = (asset: GuildThemeAsset) => {
+    void navigator.clipboard?.writeText(`url("${asset.url}")`);
+  };
+
+  return (
+    <div className="settings-surface-card min-h-[calc(100dvh-13.5rem)] !p-8 max-sm:!p-6 card-stack-relaxed">
+      <h2 className="settings-section-title !mb-0">Theme</h2>
+
+      {error && (
+        <div className="rounded-xl border border-accent-danger/35 bg-accent-danger/10 px-4 py-2.5 text-sm font-medium text-accent-danger">{error}</div>
+      )}
+
+      {loading ? (
+        <div className="text-sm text-text-muted">Loading theme...</div>
+      ) : (
+        <>
+          <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/65 p-4 sm:p-5">
+            <div className="mb-4 text-xs font-semibold uppercase tracking-wide text-text-secondary">
+              Appearance
+            </div>
+            <label className="block">
+              <span className="text-xs font-semibold uppercase tracking-wide text-text-secondary">Accent Color</span>
+              <div className="mt-2 flex items-center gap-3">
+                <input
+                  type="color"
+                  value={HEX_COLOR_RE.test(accentColor) ? accentColor : '#eb4d4b'}
+                  onChange={(e) => setAccentColor(e.target.value)}
+                  disabled={!canManage}
+                  className="h-10 w-14 cursor-pointer rounded-lg border border-border-subtle bg-transparent"
+                />
+                <input
+                  type="text"
+                  value={accentColor}
+                  onChange={(e) => setAccentColor(e.target.value)}
+                  disabled={!canManage}
+                  className="input-field"
+                  placeholder="Use each member's own accent"
+                />
+              </div>
+            </label>
+            <label className="mt-4 block">
+              <span className="text-xs font-semibold uppercase tracking-wide text-text-secondary">Custom CSS</span>
+              <textarea
+                value={customCss}
+                onChange={(e) => setCustomCss(e.target.value)}
+                disabled={!canManage}
+                rows={10}
+                spellCheck={false}
+                className="input-field mt-2 resize-y font-mono text-xs"
+                placeholder=".channel-sidebar { background-image: url(...); }"
+              />
+              <p className="mt-1 text-xs text-text-muted">
+                Applied for members while they view this server. <code>url()</code> may only reference this server's theme assets.
+              </p>
+            </label>
+            {canManage && (
+              <div className="settings-action-row mt-4">
+                <button
+                  className="btn-primary"
+                  onClick={() => void saveTheme()}
+                  disabled={saving}
+                >
+                  {saving ? 'Saving...' : 'Save Theme'}
+                </button>
+              </div>
+            )}
+          </div>
+
+          {!canManage && (
+            <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/60 px-4 py-3 text-sm text-text-secondary">
+              Only members with Manage Server can change the server theme.
+            </div>
+          )}
+
+          <div>
+            <div className="mb-3 text-xs font-semibold uppercase tracking-wide text-text-secondary">
+              Theme Assets
+            </div>
+            {canManage && (
+              <div className="mb-3 flex flex-wrap items-center gap-3">
+                <input
+                  type="text"
+                  value={assetName}
+                  onChange={(e) => setAssetName(e.target.value)}
+                  className="input-field max-w-xs"
+                  placeholder="asset-name"
+                  maxLength={32}
+                />
+                <label className={`btn-primary inline-flex cursor-pointer items-center gap-1.5 ${uploading || !assetName.trim() ? 'pointer-events-none opacity-60' : ''}`}>
+                  <ImagePlus size={14} />
+                  {uploading ? 'Uploading...' : 'Upload Image'}
+                  <input
+                    type="file"
+                    accept="image/png,image/jpeg,image/gif,image/webp"
+                    className="hidden"
+                    onChange={(e) => void uploadAsset(e)}
+                  />
+                </label>
+              </div>
+            )}
+
+            {assets.length === 0 ? (
+              <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/60 px-4 py-8 text-center text-sm text-text-muted">
+                No theme assets uploaded yet.
+              </div>
+            ) : (
+              <div className="card-stack">
+                {assets.map((asset) => (
+                  <div key={asset.id} className="card-surface flex flex-wrap items-center gap-3 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-3.5 py-3">
+                    <img
+                      src={resolveResourceUrl(asset.url)}
+                      alt={asset.name}
+                      className="h-10 w-10 rounded-lg object-cover"
+                    />
+                    <div className="min-w-0 flex-1">
+                      <div className="truncate text-sm font-semibold text-text-primary">{asset.name}</div>
+                      <div className="truncate text-xs text-text-muted">{asset.url} · {formatBytes(asset.size)}</div>
+                    </div>
+                    <button
+                      className="icon-btn"
+                      onClick={() => copyAssetUrl(asset)}
+                      title="Copy CSS url()"
+                    >
+                      <Copy size={14} />
+                    </button>
+                    {canManage && (
+                      <button
+                        className="icon-btn text-accent-danger"
+                        onClick={() => void deleteAsset(asset)}
+                        title="Delete asset"
+                      >
+                        <Trash2 size={14} />
+                      </button>
+                    )}
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
+        </>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,81 @@
+import { useNavigate } from 'react-router-dom';
+import { UserPlus } from 'lucide-react';
+import { useSavedAccountStore } from '../../stores/savedAccountStore';
+import { supportsSavedAccounts } from '../../lib/desktopAccounts';
+import { getAvatarInitials } from '../../lib/avatars';
+import { toast } from '../../stores/toastStore';
+import { Tooltip } from '../ui/Tooltip';
+import { cn } from '../../lib/utils';
+
+function hostLabel(serverUrl: string): string {
+  try {
+    return new URL(serverUrl).host;
+  } catch {
+    return serverUrl;
+  }
+}
+
+/** Dock buttons for the desktop app's other saved accounts. */
+export function AccountSwitcher() {
+  const navigate = useNavigate();
+  const accounts = useSavedAccountStore((s) => s.accounts);
+  const activeAccountId = useSavedAccountStore((s) => s.activeAccountId);
+  const badges = useSavedAccountStore((s) => s.badges);
+  const switching = useSavedAccountStore((s) => s.switching);
+
+  if (!supportsSavedAccounts()) return null;
+
+  const others = accounts.filter((account) => account.id !== activeAccountId);
+
+  const switchTo = async (accountId: string) => {
+    try {
+      await useSavedAccountStore.getState().switchTo(accountId);
+      navigate('/app');
+    } catch (err) {
+      toast.error(err instanceof Error ? err.message : 'Failed to switch account');
+      navigate('/login');
+    }
+  };
+
+  const addAccount = () => {
+    useSavedAccountStore.getState().startAddAccount();
+    navigate('/connect');
+  };
+
+  return (
+    <>
+      {others.map((account) => {
+        const name = account.display_name || account.username;
+        const badge = badges[account.id] ?? 0;
+        return (
+          <Tooltip key={account.id} side="right" content={`Switch to ${name} (${hostLabel(account.server_url)})`}>
+            <button
+              onClick={() => void switchTo(account.id)}
+              disabled={switching}
+              className={cn(
+                'group relative flex h-9 w-9 shrink-0 items-center justify-center rounded-xl bg-white/10 text-xs font-bold text-white/75 transition-all duration-200 hover:-translate-y-0.5 hover:bg-white/20 hover:text-white',
+                switching && 'pointer-events-none opacity-60'
+              )}
+            >
+              {getAvatarInitials(name)}
+              {badge > 0 && (
+                <span className="absolute -bottom-1 -right-1 flex h-4 min-w-4 items-center justify-center rounded-full bg-accent-danger px-1 text-[10px] font-bold leading-none text-white">
+                  {badge > 99 ? '99+' : badge}
+                </span>
+              )}
+            </button>
+          </Tooltip>
+        );
+      })}
+      <Tooltip side="right" content="Add Account">
+        <button
+          onClick={addAccount}
+          disabled={switching}
+          className="flex h-9 w-9 shrink-0 items-center justify-center rounded-xl border border-dashed border-white/25 text-white/60 transition-all duration-200 hover:border-white/60 hover:bg-white/12 hover:text-white"
+        >
+          <UserPlus size={15} />
+        </button>
+      </Tooltip>
+    </>
+  );
+}
@@ -45,6 +45,16 @@ interface ChannelSidebarProps {
   collapsed?: boolean;
 }
 
+function dmDisplayName(dm: Channel): string {
+  if (dm.recipients) {
+    if (dm.name) return dm.name;
+    const selfId = useAuthStore.getState().user?.id;
+    const names = dm.recipients.filter((r) => r.id !== selfId).map((r) => r.username);
+    return names.join(', ') || 'Group DM';
+  }
+  return dm.recipient?.username || 'Direct Message';
+}
+
 export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
   const channels = useChannelStore((s) => s.channels);
   const dmChannels = useChannelStore((s) => s.channelsByGuild[''] ?? EMPTY_CHANNELS);
@@ -62,6 +72,7 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
   const [showInviteModal, setShowInviteModal] = useState(false);
   const [dmSearch, setDmSearch] = useState('');
   const [showDmPicker, setShowDmPicker] = useState(false);
+  const [dmRequests, setDmRequests] = useState<Channel[]>([]);
   const relationships = useRelationshipStore((s) => s.relationships);
   const fetchRelationships = useRelationshipStore((s) => s.fetchRelationships);
   const { connected, channelId: activeVoiceChannelId, joinChannel, selfMute, selfDeaf, toggleMute, toggleDeaf } = useVoice();
@@ -87,14 +98,46 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
 
   useEffect(() => {
     if (currentGuild) return;
-    dmApi
-      .list()
-      .then(({ data }) => setDmChannels(data))
-      .catch(() => {
-        // ignore
-      });
+    const load = () => {
+      dmApi
+        .list()
+        .then(({ data }) => setDmChannels(data))
+        .catch(() => {
+          // ignore
+        });
+      dmApi
+        .listRequests()
+        .then(({ data }) => setDmRequests(data))
+        .catch(() => {
+          // ignore
+        });
+    };
+    load();
+    window.addEventListener('paracord:dm-requests-changed', load);
+    window.addEventListener('paracord:group-dms-changed', load);
+    return () => {
+      window.removeEventListener('paracord:dm-requests-changed', load);
+      window.removeEventListener('paracord:group-dms-changed', load);
+    };
   }, [currentGuild, setDmChannels]);
 
+  const respondToDmRequest = async (channelId: string, accept: boolean) => {
+    try {
+      if (accept) {
+        await dmApi.acceptRequest(channelId);
+      } else {
+        await dmApi.declineRequest(channelId);
+      }
+    } catch {
+      return;
+    }
+    setDmRequests((prev) => prev.filter((dm) => dm.id !== channelId));
+    if (accept) {
+      const { data } = await dmApi.list();
+      setDmChannels(data);
+    }
+  };
+
   useEffect(() => {
     if (showDmPicker) {
       void fetchRelationships();
@@ -199,7 +242,7 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
           {compactDms.map((dm) => {
             const isSelected = selectedChannelId === dm.id;
             return (
-              <Tooltip key={dm.id} content={dm.recipient?.username || 'Direct Message'} side="right">
+              <Tooltip key={dm.id} content={dmDisplayName(dm)} side="right">
                 <button
                   onClick={() => {
                     selectChannel(dm.id);
@@ -212,7 +255,7 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
                       : 'border-transparent bg-bg-mod-subtle text-text-secondary hover:border-border-subtle hover:text-text-primary'
                   )}
                 >
-                  {(dm.recipient?.username || 'D').charAt(0).toUpperCase()}
+                  {dmDisplayName(dm).charAt(0).toUpperCase()}
                   <PresenceStatusDot userId={dm.recipient?.id} className="absolute -bottom-0.5 -right-0.5 h-2.5 w-2.5 border border-bg-secondary" />
                 </button>
               </Tooltip>
@@ -225,7 +268,7 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
 
   if (!currentGuild) {
     const filteredDms = dmChannels.filter((dm) =>
-      (dm.recipient?.username || 'Direct Message').toLowerCase().includes(dmSearch.toLowerCase())
+      dmDisplayName(dm).toLowerCase().includes(dmSearch.toLowerCase())
     );
 
     return (
@@ -273,6 +316,49 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
             Friends
           </button>
 
+          {dmRequests.length > 0 && (
+            <>
+              <div className="mb-3 mt-5 px-2.5">
+                <span className="text-xs font-semibold uppercase tracking-wide text-text-muted">
+                  Message Requests ({dmRequests.length})
+                </span>
+              </div>
+              <div className="space-y-1.5">
+                {dmRequests.map((dm) => (
+                  <div
+                    key={dm.id}
+                    className="architect-nav-item flex w-full items-center gap-3 rounded-xl px-3 py-2.5 text-text-secondary"
+                  >
+                    <button
+                      className="flex min-w-0 flex-1 items-center gap-3"
+                      onClick={() => {
+                        selectChannel(dm.id);
+                        navigate(`/app/dms/${dm.id}`);
+                      }}
+                    >
+                      <div className="flex h-9 w-9 items-center justify-center rounded-xl bg-bg-mod-strong text-sm font-semibold text-text-primary">
+                        {dmDisplayName(dm).charAt(0).toUpperCase()}
+                      </div>
+                      <span className="truncate font-semibold text-[15px]">{dmDisplayName(dm)}</span>
+                    </button>
+                    <button
+                      className="rounded-lg px-2 py-1 text-xs font-semibold text-text-primary hover:bg-bg-mod-strong"
+                      onClick={() => void respondToDmRequest(dm.id, true)}
+                    >
+                      Accept
+                    </button>
+                    <button
+                      className="rounded-lg px-2 py-1 text-xs font-semibold text-text-muted hover:bg-bg-mod-strong hover:text-text-primary"
+                      onClick={() => void respondToDmRequest(dm.id, false)}
+                    >
+                      Decline
+                    </button>
+                  </div>
+                ))}
+              </div>
+            </>
+          )}
+
           <div className="group mb-3 mt-5 flex items-center justify-between px-2.5">
             <span className="text-xs font-semibold uppercase tracking-wide text-text-muted transition-colors group-hover:text-text-secondary">
               Direct Messages
@@ -312,12 +398,12 @@ export function ChannelSidebar({ collapsed = false }: ChannelSidebarProps) {
                 >
                   <div className="relative">
                     <div className="flex h-9 w-9 items-center justify-center rounded-xl bg-bg-mod-strong text-sm font-semibold text-text-primary">
-                      {(dm.recipient?.username || 'D').charAt(0).toUpperCase()}
+                      {dmDisplayName(dm).charAt(0).toUpperCase()}
                     </div>
                     <PresenceStatusDot userId={dm.recipient?.id} className="absolute -bottom-0.5 -right-0.5 h-3 w-3 border-[2px] border-bg-secondary" />
                   </div>
                   <div className="flex min-w-0 flex-1 flex-col items-start">
-                    <span className="truncate font-semibold text-[15px]">{dm.recipient?.username || 'Direct Message'}</span>
+                    <span className="truncate font-semibold text-[15px]">{dmDisplayName(dm)}</span>
                     <PresenceStatusText userId={dm.recipient?.id} className="truncate text-xs text-text-muted opacity-0 group-hover:opacity-100 transition-opacity" />
                   </div>
                 </button>
@@ -9,6 +9,7 @@ import { useUIStore } from '../../stores/uiStore';
 import { useServerListStore } from '../../stores/serverListStore';
 import { channelApi } from '../../api/channels';
 import { CreateGuildModal } from '../guild/CreateGuildModal';
+import { AccountSwitcher } from './AccountSwitcher';
 import { InviteModal } from '../guild/InviteModal';
 import { usePermissions } from '../../hooks/usePermissions';
 import { useUnreadCounts } from '../../hooks/useUnreadCounts';
@@ -96,7 +97,7 @@ export function Sidebar() {
     const iconSrc = guild.icon_hash
       ? guild.icon_hash.startsWith('data:')
         ? (isSafeImageDataUrl(guild.icon_hash) ? guild.icon_hash : null)
-        : `/api/v1/guilds/${guild.id}/icon`
+        : `/api/v1/guilds/${guild.id}/icon?v=${guild.icon_hash}`
       : null;
     return (
       <div key={guild.id} className="relative flex shrink-0 items-center justify-center">
@@ -247,6 +248,8 @@ export function Sidebar() {
                     </button>
                   </Tooltip>
 
+                  <AccountSwitcher />
+
                   <Tooltip side="right" content="User Settings">
                     <button
                       onClick={() => useUIStore.getState().setUserSettingsOpen(true)}
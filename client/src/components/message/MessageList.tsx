@@ -28,6 +28,7 @@ import { MessageEmbedCard, extractUrls } from './MessageEmbed';
 import { GitHubEventEmbed, isGitHubWebhookMessage } from './GitHubEventEmbed';
 import { PollMessageCard } from './PollMessageCard';
 import { toast } from '../../stores/toastStore';
+import { CachedImage } from '../ui/CachedImage';
 
 const EMPTY_TYPING: string[] = [];
 const EMPTY_CHANNELS: Channel[] = [];
@@ -1193,8 +1194,9 @@ export function MessageList({ channelId, onReply }: MessageListProps) {
                         className="inline-block max-w-fit cursor-pointer border-0 bg-transparent p-0 text-left"
                         onClick={() => void openImageLightbox()}
                       >
-                        <img
+                        <CachedImage
                           src={src}
+                          cacheKey={att.url}
                           alt={att.filename}
                           className="max-w-[min(100%,400px)] rounded-lg border border-border-subtle"
                           style={{ maxHeight: '300px', objectFit: 'contain' }}
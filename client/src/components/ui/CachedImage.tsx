@@ -0,0 +1,29 @@
+import { useEffect, useState, type ImgHTMLAttributes, type SyntheticEvent } from 'react';
+import { offlineCache, supportsOfflineCache } from '../../lib/offlineCache';
+
+interface CachedImageProps extends ImgHTMLAttributes<HTMLImageElement> {
+  /** Offline cache key, usually the attachment's API path. */
+  cacheKey: string;
+}
+
+/** An `<img>` that falls back to the desktop offline cache when the network copy fails. */
+export function CachedImage({ cacheKey, src, onError, ...rest }: CachedImageProps) {
+  const [cachedSrc, setCachedSrc] = useState<string | null>(null);
+
+  useEffect(() => {
+    setCachedSrc(null);
+  }, [src]);
+
+  const handleError = (event: SyntheticEvent<HTMLImageElement>) => {
+    if (cachedSrc || !supportsOfflineCache()) {
+      onError?.(event);
+      return;
+    }
+    void offlineCache.loadMedia(cacheKey).then((cached) => {
+      if (cached) setCachedSrc(cached);
+      else onError?.(event);
+    });
+  };
+
+  return <img {...rest} src={cachedSrc ?? src} onError={handleError} />;
+}
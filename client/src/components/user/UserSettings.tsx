@@ -5,15 +5,29 @@ import { useAuthStore } from '../../stores/authStore';
 import { useAccountStore } from '../../stores/accountStore';
 import { useUIStore } from '../../stores/uiStore';
 import { useVoiceStore } from '../../stores/voiceStore';
+import { useGuildStore } from '../../stores/guildStore';
 import { useMediaDevices } from '../../hooks/useMediaDevices';
 import { APP_NAME } from '../../lib/constants';
 import { hasAccount as hasLocalCryptoAccount } from '../../lib/account';
-import { isAdmin } from '../../types';
+import { isAdmin, type PresenceVisibility } from '../../types';
 import { adminApi } from '../../api/admin';
 import { apiClient, extractApiError } from '../../api/client';
 import { authApi, type AuthSession } from '../../api/auth';
 import { cn } from '../../lib/utils';
+import { useGlobalHotkeyStore } from '../../lib/globalHotkeys';
 import { confirm } from '../../stores/confirmStore';
+import { isTauri } from '../../lib/tauriEnv';
+import {
+  createDiagnosticsBundle,
+  getCrashReportingStatus,
+  setCrashReportingEnabled,
+} from '../../lib/desktopDiagnostics';
+import {
+  listOverlayMonitors,
+  useOverlaySettingsStore,
+  type OverlayCorner,
+  type OverlayMonitor,
+} from '../../lib/desktopOverlay';
 import {
   isEnabled as isNotificationsEnabled,
   setEnabled as setNotificationsEnabled,
@@ -79,10 +93,15 @@ export function UserSettings({ onClose }: UserSettingsProps) {
   const [notifications, setNotifications] = useState<Record<string, unknown>>({});
   const [knownActivityApps, setKnownActivityApps] = useState<string[]>([]);
   const [keybinds, setKeybinds] = useState<Record<string, unknown>>({});
+  const [presenceVisibility, setPresenceVisibility] = useState<PresenceVisibility>('everyone');
+  const [presenceHiddenGuildIds, setPresenceHiddenGuildIds] = useState<string[]>([]);
+  const guilds = useGuildStore((s) => s.guilds);
   const [capturingKeybind, setCapturingKeybind] = useState<string | null>(null);
+  const hotkeyStatuses = useGlobalHotkeyStore((s) => s.statuses);
   const [saving, setSaving] = useState(false);
   const [statusText, setStatusText] = useState<string | null>(null);
   const cryptoAuthEnabled = settings?.crypto_auth_enabled === true;
+  const deviceApprovalRequired = settings?.device_approval_required === true;
   const {
     audioInputDevices,
     audioOutputDevices,
@@ -107,6 +126,8 @@ export function UserSettings({ onClose }: UserSettingsProps) {
   const [sessions, setSessions] = useState<AuthSession[]>([]);
   const [sessionsLoading, setSessionsLoading] = useState(false);
   const [sessionBusyId, setSessionBusyId] = useState<string | null>(null);
+  const [deviceRecoveryCode, setDeviceRecoveryCode] = useState<string | null>(null);
+  const [recoveryCodeInput, setRecoveryCodeInput] = useState('');
   const [emailCurrentPassword, setEmailCurrentPassword] = useState('');
   const [passwordCurrentPassword, setPasswordCurrentPassword] = useState('');
   const [accountNewPassword, setAccountNewPassword] = useState('');
@@ -170,6 +191,8 @@ export function UserSettings({ onClose }: UserSettingsProps) {
         ),
       });
       setKeybinds((settings.keybinds as Record<string, unknown>) || {});
+      setPresenceVisibility(settings.presence_visibility ?? 'everyone');
+      setPresenceHiddenGuildIds(settings.presence_hidden_guild_ids ?? []);
       if (typeof notif?.['audioInputDeviceId'] === 'string') {
         selectAudioInput(notif['audioInputDeviceId'] as string);
       }
@@ -301,6 +324,75 @@ export function UserSettings({ onClose }: UserSettingsProps) {
     void loadSessions();
   }, [activeSection, loadSessions]);
 
+  const handleDeviceApprovalToggle = async (enabled: boolean) => {
+    setSaving(true);
+    try {
+      const recoveryCode = await updateSettings({ device_approval_required: enabled });
+      setDeviceRecoveryCode(recoveryCode);
+      setStatusText(enabled ? 'New devices now need approval.' : 'Device approval disabled.');
+      void loadSessions();
+    } catch (err) {
+      setStatusText(`Failed to update device approval: ${extractApiError(err)}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const resolveDeviceApproval = async (sessionId: string, approve: boolean) => {
+    if (sessionBusyId) return;
+    setSessionBusyId(sessionId);
+    try {
+      if (approve) {
+        await authApi.approveSessionDevice(sessionId);
+        setSessions((prev) =>
+          prev.map((session) => (session.id === sessionId ? { ...session, device_approved: true } : session))
+        );
+        setStatusText('Device approved.');
+      } else {
+        await authApi.denySessionDevice(sessionId);
+        setSessions((prev) => prev.filter((session) => session.id !== sessionId));
+        setStatusText('Device denied.');
+      }
+    } catch (err) {
+      setStatusText(`Failed to update device: ${extractApiError(err)}`);
+    } finally {
+      setSessionBusyId(null);
+    }
+  };
+
+  const regenerateRecoveryCode = async () => {
+    setSaving(true);
+    try {
+      const { data } = await authApi.regenerateDeviceRecoveryCode();
+      setDeviceRecoveryCode(data.recovery_code);
+      setStatusText('New recovery code generated. The previous one no longer works.');
+    } catch (err) {
+      setStatusText(`Failed to generate recovery code: ${extractApiError(err)}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const recoverCurrentDevice = async () => {
+    const code = recoveryCodeInput.trim();
+    if (!code) return;
+    setSaving(true);
+    try {
+      const { data } = await authApi.recoverDevice(code);
+      setRecoveryCodeInput('');
+      setDeviceRecoveryCode(data.recovery_code);
+      setStatusText('This device is approved. Save your new recovery code.');
+      void loadSessions();
+    } catch (err) {
+      setStatusText(`Recovery failed: ${extractApiError(err)}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const currentDeviceAwaitingApproval =
+    deviceApprovalRequired && sessions.some((session) => session.current && session.device_approved === false);
+
   const revokeSession = async (sessionId: string) => {
     if (sessionBusyId) return;
     if (!(await confirm({ title: 'Sign out this session?', description: 'This will end the session immediately.', confirmLabel: 'Sign out', variant: 'danger' }))) return;
@@ -403,6 +495,8 @@ export function UserSettings({ onClose }: UserSettingsProps) {
           audioOutputDeviceId: selectedAudioOutput,
         },
         keybinds: mergedKeybinds,
+        presence_visibility: presenceVisibility,
+        presence_hidden_guild_ids: presenceHiddenGuildIds,
       });
       setThemeUI(theme);
       setAccentPresetUI(accentPreset);
@@ -443,6 +537,12 @@ export function UserSettings({ onClose }: UserSettingsProps) {
     });
   };
 
+  const togglePresenceHiddenGuild = (guildId: string) => {
+    setPresenceHiddenGuildIds((prev) =>
+      prev.includes(guildId) ? prev.filter((id) => id !== guildId) : [...prev, guildId]
+    );
+  };
+
   const saveActivitySettings = async () => {
     saveKnownActivityAppsToStorage(visibleKnownActivityApps);
     await saveSettings();
@@ -840,6 +940,57 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                               {sessionsLoading ? 'Refreshing...' : 'Refresh'}
                             </button>
                           </div>
+                          <label className="mb-3 flex items-center justify-between gap-3 rounded-lg border border-border-subtle bg-bg-tertiary/70 px-3 py-2.5">
+                            <span className="text-sm text-text-secondary">
+                              Require approval from a signed-in device for new devices
+                            </span>
+                            <input
+                              type="checkbox"
+                              checked={deviceApprovalRequired}
+                              disabled={saving}
+                              onChange={(e) => void handleDeviceApprovalToggle(e.target.checked)}
+                            />
+                          </label>
+                          {deviceRecoveryCode && (
+                            <div className="mb-3 rounded-lg border border-border-subtle bg-bg-tertiary/70 px-3 py-2.5 text-sm text-text-secondary">
+                              <div>
+                                Recovery code: <code className="font-mono text-text-primary">{deviceRecoveryCode}</code>
+                              </div>
+                              <div className="mt-1 text-xs text-text-muted">
+                                Store it somewhere safe. It approves a new device when no signed-in device is available,
+                                and is shown only once.
+                              </div>
+                            </div>
+                          )}
+                          {deviceApprovalRequired && !currentDeviceAwaitingApproval && (
+                            <button
+                              className="mb-3 rounded-lg px-3 py-1.5 text-xs font-semibold text-text-secondary transition-colors hover:bg-bg-mod-strong hover:text-text-primary"
+                              onClick={() => void regenerateRecoveryCode()}
+                              disabled={saving}
+                            >
+                              Generate new recovery code
+                            </button>
+                          )}
+                          {currentDeviceAwaitingApproval && (
+                            <div className="mb-3 flex flex-wrap items-center gap-2 rounded-lg border border-border-subtle bg-bg-tertiary/70 px-3 py-2.5">
+                              <span className="text-sm text-text-secondary">
+                                This device is waiting for approval. No other device available?
+                              </span>
+                              <input
+                                className="input-field min-w-0 flex-1 font-mono text-sm"
+                                placeholder="Recovery code"
+                                value={recoveryCodeInput}
+                                onChange={(e) => setRecoveryCodeInput(e.target.value)}
+                              />
+                              <button
+                                className="btn-primary px-2.5 py-1.5 text-xs"
+                                onClick={() => void recoverCurrentDevice()}
+                                disabled={saving || recoveryCodeInput.trim().length === 0}
+                              >
+                                Approve with code
+                              </button>
+                            </div>
+                          )}
                           <div className="space-y-2.5">
                             {sessions.map((session) => (
                               <div
@@ -861,6 +1012,24 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                                     {session.ip_address || 'No IP'} - Last seen {new Date(session.last_seen_at).toLocaleString()}
                                   </div>
                                 </div>
+                                {deviceApprovalRequired && session.device_approved === false && (
+                                  <div className="flex items-center gap-2">
+                                    <button
+                                      className="btn-primary px-2.5 py-1.5 text-xs"
+                                      onClick={() => void resolveDeviceApproval(session.id, true)}
+                                      disabled={sessionBusyId === session.id}
+                                    >
+                                      Approve
+                                    </button>
+                                    <button
+                                      className="rounded-lg border border-accent-danger/35 bg-accent-danger/10 px-2.5 py-1.5 text-xs font-semibold text-accent-danger transition-colors hover:bg-accent-danger/15 disabled:opacity-60"
+                                      onClick={() => void resolveDeviceApproval(session.id, false)}
+                                      disabled={sessionBusyId === session.id}
+                                    >
+                                      Deny
+                                    </button>
+                                  </div>
+                                )}
                                 <button
                                   className="rounded-lg border border-accent-danger/35 bg-accent-danger/10 px-2.5 py-1.5 text-xs font-semibold text-accent-danger transition-colors hover:bg-accent-danger/15 disabled:opacity-60"
                                   onClick={() => void revokeSession(session.id)}
@@ -1112,6 +1281,7 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                     onToggle={() => setNotifications((prev) => ({ ...prev, autoGainControl: !Boolean(prev['autoGainControl'] ?? false) }))}
                   />
                 </div>
+                {isTauri() && <VoiceOverlaySettingsCard />}
               </div>
               <div className="settings-action-row">
                 <button className="btn-primary" onClick={() => {
@@ -1187,6 +1357,47 @@ export function UserSettings({ onClose }: UserSettingsProps) {
             <div className="settings-surface-card w-full min-h-[calc(100dvh-13.5rem)]">
               <h2 className="settings-section-title mb-8">Activity Privacy</h2>
               <div className="card-stack">
+                <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
+                  <div className="text-sm font-medium text-text-primary">Who can see my status</div>
+                  <div className="text-xs text-text-muted">
+                    Anyone you hide from will see you as offline, along with your activity.
+                  </div>
+                  <select
+                    className="select-field mt-3"
+                    value={presenceVisibility}
+                    onChange={(e) => setPresenceVisibility(e.target.value as PresenceVisibility)}
+                  >
+                    <option value="everyone">Everyone in my servers</option>
+                    <option value="friends">Friends only</option>
+                    <option value="nobody">Nobody</option>
+                  </select>
+                </div>
+
+                {presenceVisibility === 'everyone' && guilds.length > 0 && (
+                  <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
+                    <div className="mb-3 text-xs font-semibold uppercase tracking-wide text-text-secondary">
+                      Appear Offline In
+                    </div>
+                    <div className="mb-3 text-xs text-text-muted">
+                      Members of these servers won&apos;t see your status unless you are friends.
+                    </div>
+                    <div className="space-y-2">
+                      {guilds.map((guild) => (
+                        <div
+                          key={guild.id}
+                          className="flex items-center justify-between rounded-lg border border-border-subtle bg-bg-tertiary/70 px-3 py-2.5"
+                        >
+                          <div className="text-sm font-medium text-text-primary">{guild.name}</div>
+                          <ToggleSwitch
+                            on={presenceHiddenGuildIds.includes(guild.id)}
+                            onToggle={() => togglePresenceHiddenGuild(guild.id)}
+                          />
+                        </div>
+                      ))}
+                    </div>
+                  </div>
+                )}
+
                 <div className="card-surface flex flex-wrap items-center justify-between gap-3 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
                   <div>
                     <div className="text-sm font-medium text-text-primary">Display current activity</div>
@@ -1245,39 +1456,50 @@ export function UserSettings({ onClose }: UserSettingsProps) {
               <h2 className="settings-section-title mb-8">Keybinds</h2>
               <div className="card-stack">
                 {[
-                  { key: 'toggleMute' as const, action: 'Toggle Mute' },
-                  { key: 'toggleDeafen' as const, action: 'Toggle Deafen' },
-                  { key: 'pushToTalk' as const, action: 'Push to Talk' },
-                ].map(kb => (
-                  <div
-                    key={kb.key}
-                    className="card-surface flex flex-col items-stretch gap-2 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6 sm:flex-row sm:items-center sm:justify-between"
-                  >
-                    <span className="text-sm font-medium text-text-primary">{kb.action}</span>
-                    <input
-                      className="h-10 w-full rounded-lg border border-border-subtle bg-bg-tertiary px-3 py-2 text-sm font-mono text-text-muted outline-none focus:border-accent-primary sm:w-48"
-                      value={capturingKeybind === kb.key ? 'Press keys...' : String(mergedKeybinds[kb.key] ?? '')}
-                      onFocus={() => setCapturingKeybind(kb.key)}
-                      onBlur={() => setCapturingKeybind(null)}
-                      onKeyDown={(e) => {
-                        e.preventDefault();
-                        const keys: string[] = [];
-                        if (e.ctrlKey) keys.push('Ctrl');
-                        if (e.shiftKey) keys.push('Shift');
-                        if (e.altKey) keys.push('Alt');
-                        if (e.metaKey) keys.push('Meta');
-                        const base = e.key.length === 1 ? e.key.toUpperCase() : e.key;
-                        if (!['Control', 'Shift', 'Alt', 'Meta'].includes(base)) {
-                          keys.push(base);
-                        }
-                        if (keys.length > 0) {
-                          setKeybinds((prev) => ({ ...prev, [kb.key]: keys.join('+') }));
-                          setCapturingKeybind(null);
-                        }
-                      }}
-                    />
-                  </div>
-                ))}
+                  { key: 'toggleMute' as const, action: 'Toggle Mute', hotkey: 'toggle_mute' },
+                  { key: 'toggleDeafen' as const, action: 'Toggle Deafen', hotkey: 'toggle_deafen' },
+                  { key: 'pushToTalk' as const, action: 'Push to Talk', hotkey: 'push_to_talk' },
+                ].map(kb => {
+                  const hotkeyStatus = hotkeyStatuses.find((status) => status.action === kb.hotkey);
+                  return (
+                    <div
+                      key={kb.key}
+                      className="card-surface flex flex-col items-stretch gap-2 rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6 sm:flex-row sm:items-center sm:justify-between"
+                    >
+                      <div className="min-w-0">
+                        <span className="text-sm font-medium text-text-primary">{kb.action}</span>
+                        {hotkeyStatus?.registered && (
+                          <p className="mt-1 text-xs text-text-muted">Works while {APP_NAME} is in the background.</p>
+                        )}
+                        {hotkeyStatus?.error && (
+                          <p className="mt-1 text-xs text-accent-danger">Global hotkey unavailable: {hotkeyStatus.error}</p>
+                        )}
+                      </div>
+                      <input
+                        className="h-10 w-full rounded-lg border border-border-subtle bg-bg-tertiary px-3 py-2 text-sm font-mono text-text-muted outline-none focus:border-accent-primary sm:w-48"
+                        value={capturingKeybind === kb.key ? 'Press keys...' : String(mergedKeybinds[kb.key] ?? '')}
+                        onFocus={() => setCapturingKeybind(kb.key)}
+                        onBlur={() => setCapturingKeybind(null)}
+                        onKeyDown={(e) => {
+                          e.preventDefault();
+                          const keys: string[] = [];
+                          if (e.ctrlKey) keys.push('Ctrl');
+                          if (e.shiftKey) keys.push('Shift');
+                          if (e.altKey) keys.push('Alt');
+                          if (e.metaKey) keys.push('Meta');
+                          const base = e.key.length === 1 ? e.key.toUpperCase() : e.key;
+                          if (!['Control', 'Shift', 'Alt', 'Meta'].includes(base)) {
+                            keys.push(base);
+                          }
+                          if (keys.length > 0) {
+                            setKeybinds((prev) => ({ ...prev, [kb.key]: keys.join('+') }));
+                            setCapturingKeybind(null);
+                          }
+                        }}
+                      />
+                    </div>
+                  );
+                })}
               </div>
               <div className="settings-action-row">
                 <button className="btn-primary" onClick={() => void saveSettings()} disabled={saving}>
@@ -1487,6 +1709,7 @@ export function UserSettings({ onClose }: UserSettingsProps) {
                 <div className="text-sm leading-6 text-text-muted">
                   A decentralized, self-hostable Discord alternative built with Rust, Tauri, and React.
                 </div>
+                {isTauri() && <DesktopDiagnosticsCard />}
               </div>
             </div>
           )}
@@ -1496,6 +1719,145 @@ export function UserSettings({ onClose }: UserSettingsProps) {
   );
 }
 
+const OVERLAY_CORNERS: { value: OverlayCorner; label: string }[] = [
+  { value: 'top_left', label: 'Top left' },
+  { value: 'top_right', label: 'Top right' },
+  { value: 'bottom_left', label: 'Bottom left' },
+  { value: 'bottom_right', label: 'Bottom right' },
+];
+
+function VoiceOverlaySettingsCard() {
+  const enabled = useOverlaySettingsStore((s) => s.enabled);
+  const config = useOverlaySettingsStore((s) => s.config);
+  const setEnabled = useOverlaySettingsStore((s) => s.setEnabled);
+  const updateConfig = useOverlaySettingsStore((s) => s.updateConfig);
+  const [monitors, setMonitors] = useState<OverlayMonitor[]>([]);
+
+  useEffect(() => {
+    void listOverlayMonitors()
+      .then(setMonitors)
+      .catch(() => setMonitors([]));
+  }, []);
+
+  return (
+    <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-5">
+      <div className="flex flex-wrap items-center justify-between gap-3">
+        <div>
+          <div className="text-sm font-medium text-text-primary">In-Game Overlay</div>
+          <div className="text-xs text-text-muted">Show who is speaking in an always-on-top window while in voice</div>
+        </div>
+        <ToggleSwitch on={enabled} onToggle={() => setEnabled(!enabled)} />
+      </div>
+      {enabled && (
+        <div className="mt-4 grid gap-3 sm:grid-cols-2">
+          <label className="block">
+            <span className="text-xs font-semibold uppercase tracking-wide text-text-secondary">Monitor</span>
+            <select
+              className="select-field mt-2"
+              value={config.monitor ?? ''}
+              onChange={(e) => updateConfig({ monitor: e.target.value || null })}
+            >
+              <option value="">Primary monitor</option>
+              {monitors.map((monitor) => (
+                <option key={monitor.name} value={monitor.name}>
+                  {monitor.name} ({monitor.width}x{monitor.height}){monitor.primary ? ' - primary' : ''}
+                </option>
+              ))}
+            </select>
+          </label>
+          <label className="block">
+            <span className="text-xs font-semibold uppercase tracking-wide text-text-secondary">Position</span>
+            <select
+              className="select-field mt-2"
+              value={config.corner}
+              onChange={(e) => updateConfig({ corner: e.target.value as OverlayCorner })}
+            >
+              {OVERLAY_CORNERS.map((corner) => (
+                <option key={corner.value} value={corner.value}>
+                  {corner.label}
+                </option>
+              ))}
+            </select>
+          </label>
+          <div className="flex items-center justify-between gap-3 sm:col-span-2">
+            <div>
+              <div className="text-sm font-medium text-text-primary">Click-through</div>
+              <div className="text-xs text-text-muted">Let clicks pass through the overlay to the game underneath</div>
+            </div>
+            <ToggleSwitch
+              on={config.click_through}
+              onToggle={() => updateConfig({ click_through: !config.click_through })}
+            />
+          </div>
+        </div>
+      )}
+    </div>
+  );
+}
+
+function DesktopDiagnosticsCard() {
+  const [crashReporting, setCrashReporting] = useState(false);
+  const [crashReportCount, setCrashReportCount] = useState(0);
+  const [bundling, setBundling] = useState(false);
+  const [status, setStatus] = useState<string | null>(null);
+
+  useEffect(() => {
+    void getCrashReportingStatus().then((current) => {
+      if (!current) return;
+      setCrashReporting(current.enabled);
+      setCrashReportCount(current.reports.filter((name) => name.endsWith('.txt')).length);
+    });
+  }, []);
+
+  const toggleCrashReporting = async () => {
+    const next = !crashReporting;
+    try {
+      await setCrashReportingEnabled(next);
+      setCrashReporting(next);
+    } catch (err) {
+      setStatus(`Failed to update crash reporting: ${String(err)}`);
+    }
+  };
+
+  const handleCreateBundle = async () => {
+    setBundling(true);
+    setStatus(null);
+    try {
+      const path = await createDiagnosticsBundle();
+      setStatus(`Diagnostics bundle saved to ${path}`);
+    } catch (err) {
+      setStatus(`Failed to create diagnostics bundle: ${String(err)}`);
+    } finally {
+      setBundling(false);
+    }
+  };
+
+  return (
+    <div className="card-surface rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
+      <div className="text-sm font-semibold text-text-primary">Diagnostics</div>
+      <div className="mt-1 text-xs text-text-muted">
+        Collect the client log, voice session stats, system and GPU info, and recent errors into a zip you can attach to a bug report.
+      </div>
+      <div className="mt-4 flex flex-wrap items-center justify-between gap-3">
+        <div>
+          <div className="text-sm font-medium text-text-primary">Save crash reports</div>
+          <div className="text-xs text-text-muted">
+            Write a report (and a minidump on Windows) when the app crashes. Reports stay on this device
+            {crashReportCount > 0 ? ` (${crashReportCount} saved)` : ''}.
+          </div>
+        </div>
+        <ToggleSwitch on={crashReporting} onToggle={() => void toggleCrashReporting()} />
+      </div>
+      <div className="settings-action-row mt-4">
+        <button className="btn-primary" onClick={() => void handleCreateBundle()} disabled={bundling}>
+          {bundling ? 'Collecting...' : 'Create Diagnostics Bundle'}
+        </button>
+      </div>
+      {status && <div className="mt-3 break-all text-xs text-text-muted">{status}</div>}
+    </div>
+  );
+}
+
 function ToggleSwitch({ on, onToggle, disabled = false }: { on: boolean; onToggle: () => void; disabled?: boolean }) {
   return (
     <button
@@ -5,6 +5,15 @@ import { useStream } from '../../hooks/useStream';
 import { useVoiceStore } from '../../stores/voiceStore';
 import { cn } from '../../lib/utils';
 import { Tooltip } from '../ui/Tooltip';
+import { getNativeScreenCaptureCapabilities } from '../../lib/media/tauriMediaEngine';
+import type { NativeScreenCaptureCapabilities, ScreenCaptureCursor, ScreenCaptureSource } from '../../lib/media/mediaEngine';
+import {
+    getSystemAudioCaptureCapabilities,
+    listSystemAudioApplications,
+    requestSystemAudioCapturePermission,
+    type CaptureApplication,
+    type SystemAudioCaptureCapabilities,
+} from '../../lib/systemAudioCapture';
 
 function getStreamErrorMessage(error: unknown): string {
     console.error('[stream] getStreamErrorMessage raw error:', error, 'type:', typeof error);
@@ -23,9 +32,12 @@ function getStreamErrorMessage(error: unknown): string {
     if (name === 'NotFoundError') {
         return 'No shareable display source was found.';
     }
-    if (name === 'AbortError') {
+    if (name === 'AbortError' || message.includes('screen capture cancelled')) {
         return 'Screen share prompt was closed before selecting a source.';
     }
+    if (message.includes('screen capture portal unavailable')) {
+        return 'Screen sharing needs xdg-desktop-portal with PipeWire. Install the portal for your desktop and try again.';
+    }
     if (message.includes('voice connection is not ready')) {
         return 'Voice connection is not ready yet. Wait a moment and try again.';
     }
@@ -61,6 +73,12 @@ export function VoiceControlBar({
     const [captureQuality, setCaptureQuality] = useState('1080p60');
     const [showStreamMenu, setShowStreamMenu] = useState(false);
     const [showError, setShowError] = useState(false);
+    const [nativeCapture, setNativeCapture] = useState<NativeScreenCaptureCapabilities | null>(null);
+    const [captureSource, setCaptureSource] = useState<ScreenCaptureSource>('monitor');
+    const [captureCursor, setCaptureCursor] = useState<ScreenCaptureCursor>('embedded');
+    const [audioCapture, setAudioCapture] = useState<SystemAudioCaptureCapabilities | null>(null);
+    const [audioApplications, setAudioApplications] = useState<CaptureApplication[]>([]);
+    const [audioApplication, setAudioApplication] = useState('');
 
     const streamMenuRef = useRef<HTMLDivElement>(null);
 
@@ -76,13 +94,41 @@ export function VoiceControlBar({
         return () => document.removeEventListener('mousedown', handleClickOutside);
     }, []);
 
+    useEffect(() => {
+        void getNativeScreenCaptureCapabilities().then((caps) => setNativeCapture(caps?.supported ? caps : null));
+    }, []);
+
+    useEffect(() => {
+        if (!showStreamMenu) return;
+        void getSystemAudioCaptureCapabilities().then((caps) => {
+            setAudioCapture(caps);
+            if (caps?.application_audio && caps.permission !== 'denied') {
+                void listSystemAudioApplications().then(setAudioApplications);
+            }
+        });
+    }, [showStreamMenu]);
+
+    const handleRequestAudioPermission = async () => {
+        const permission = await requestSystemAudioCapturePermission().catch(() => 'denied' as const);
+        setAudioCapture((caps) => (caps ? { ...caps, permission } : caps));
+        if (permission === 'granted') {
+            void listSystemAudioApplications().then(setAudioApplications);
+        }
+    };
+
     const handleStartStream = async () => {
         setShowStreamMenu(false);
         setStreamError(null);
         setShowError(false);
         setStreamStarting(true);
         try {
-            await startStream(captureQuality);
+            await startStream(
+                captureQuality,
+                {
+                    ...(nativeCapture ? { source: captureSource, cursor: captureCursor } : {}),
+                    audioApplication: audioApplication || undefined,
+                }
+            );
         } catch (error) {
             setStreamError(getStreamErrorMessage(error));
             setShowError(true);
@@ -200,6 +246,82 @@ export function VoiceControlBar({
                                 </button>
                             ))}
                         </div>
+                        {nativeCapture && nativeCapture.sources.length > 0 && (
+                            <>
+                                <div className="mb-2 mt-3 text-xs font-bold uppercase tracking-wider text-text-muted px-2">
+                                    Capture
+                                </div>
+                                <div className="flex gap-1">
+                                    {nativeCapture.sources.map((source) => (
+                                        <button
+                                            key={source}
+                                            onClick={() => setCaptureSource(source)}
+                                            className={cn(
+                                                "flex-1 rounded-xl px-3 py-2 text-sm font-medium transition-colors",
+                                                captureSource === source
+                                                    ? "bg-accent-primary text-white"
+                                                    : "text-text-secondary hover:bg-bg-mod-subtle hover:text-text-primary"
+                                            )}
+                                        >
+                                            {source === 'window' ? 'Window' : 'Full Screen'}
+                                        </button>
+                                    ))}
+                                </div>
+                                {nativeCapture.cursor_modes.includes('embedded') && (
+                                    <label className="mt-2 flex cursor-pointer items-center justify-between rounded-xl px-3 py-2 text-sm font-medium text-text-secondary hover:bg-bg-mod-subtle">
+                                        Show cursor
+                                        <input
+                                            type="checkbox"
+                                            checked={captureCursor === 'embedded'}
+                                            onChange={(e) => setCaptureCursor(e.target.checked ? 'embedded' : 'hidden')}
+                                        />
+                                    </label>
+                                )}
+                            </>
+                        )}
+                        {audioCapture && (
+                            <>
+                                <div className="mb-2 mt-3 text-xs font-bold uppercase tracking-wider text-text-muted px-2">
+                                    Stream Audio
+                                </div>
+                                {!audioCapture.supported ? (
+                                    <div className="px-3 py-1 text-xs text-text-muted">
+                                        PC audio can't be captured on this system; streams will be video-only.
+                                    </div>
+                                ) : audioCapture.permission === 'denied' ? (
+                                    <div className="flex flex-col gap-2 px-3 py-1">
+                                        <div className="text-xs text-text-muted">
+                                            Sharing PC audio needs the Screen Recording permission.
+                                        </div>
+                                        <button
+                                            onClick={() => void handleRequestAudioPermission()}
+                                            className="rounded-xl bg-bg-mod-strong px-3 py-2 text-sm font-medium text-text-primary transition-colors hover:bg-bg-mod-subtle"
+                                        >
+                                            Allow Screen Recording
+                                        </button>
+                                    </div>
+                                ) : audioCapture.application_audio ? (
+                                    <select
+                                        className="select-field"
+                                        value={audioApplication}
+                                        onChange={(e) => setAudioApplication(e.target.value)}
+                                    >
+                                        <option value="">All system audio</option>
+                                        {audioApplications.map((app) => (
+                                            <option key={app.id} value={app.id}>
+                                                {app.name}
+                                            </option>
+                                        ))}
+                                    </select>
+                                ) : (
+                                    <div className="px-3 py-1 text-xs text-text-muted">
+                                        {audioCapture.excludes_own_audio
+                                            ? 'All PC audio except Paracord is shared.'
+                                            : 'All PC audio is shared, including voice chat playback.'}
+                                    </div>
+                                )}
+                            </>
+                        )}
                     </div>
                 )}
 
@@ -11,9 +11,30 @@ import { usePollStore } from '../stores/pollStore';
 import { useAuthStore } from '../stores/authStore';
 import { hasUnlockedPrivateKey } from '../lib/accountSession';
 import { ensurePrekeysUploaded } from '../lib/signalPrekeys';
+import { syncGuilds } from '../lib/offlineSync';
 import { GatewayEvents } from './events';
+import { guildApi } from '../api/guilds';
 import { sendNotification, isEnabled as notificationsEnabled } from '../lib/notifications';
 
+const PRESENCE_PAGE_SIZE = 1000;
+
+/** Page in presences that READY left out of a large guild. */
+async function loadGuildPresences(serverId: string, guildId: string): Promise<void> {
+  let after: string | undefined;
+  try {
+    for (;;) {
+      const { data } = await guildApi.getPresences(guildId, { after, limit: PRESENCE_PAGE_SIZE });
+      for (const presence of data) {
+        usePresenceStore.getState().updatePresence(presence, serverId);
+      }
+      if (data.length < PRESENCE_PAGE_SIZE) break;
+      after = data[data.length - 1].user_id;
+    }
+  } catch (err) {
+    console.warn('Failed to load guild presences:', err);
+  }
+}
+
 /* eslint-disable @typescript-eslint/no-explicit-any */
 export function dispatchGatewayEvent(serverId: string, event: string, data: any): void {
   switch (event) {
@@ -66,6 +87,9 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
             usePresenceStore.getState().updatePresence(p, serverId);
           }
         }
+        if (g.presences_has_more) {
+          void loadGuildPresences(serverId, g.id);
+        }
       });
 
       const selectedGuildId = useGuildStore.getState().selectedGuildId;
@@ -94,6 +118,22 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
         }, serverId);
       }
 
+      // Catch the offline cache up with anything missed while disconnected,
+      // and fill open channels in place.
+      void syncGuilds(readyGuildIds, {
+        onMessages: (channelId, messages, truncated) => {
+          const messageState = useMessageStore.getState();
+          if (!messageState.messages[channelId]) return;
+          if (truncated) {
+            void messageState.fetchMessages(channelId);
+            return;
+          }
+          for (const message of messages) {
+            messageState.addMessage(channelId, message);
+          }
+        },
+      });
+
       // Ensure Signal prekeys are uploaded for E2EE DMs
       if (hasUnlockedPrivateKey()) {
         void ensurePrekeysUploaded().catch((err) => {
@@ -107,7 +147,11 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
       useMessageStore.getState().addMessage(data.channel_id, data);
       useChannelStore.getState().updateLastMessageId(data.channel_id, data.id);
       // Desktop notification for messages not from self and not in focused channel
-      if (notificationsEnabled()) {
+      if (data.message_request) {
+        window.dispatchEvent(new CustomEvent('paracord:dm-requests-changed'));
+      }
+      // Message requests stay silent until accepted
+      if (notificationsEnabled() && !data.author_blocked && !data.message_request) {
         const currentUserId = useAuthStore.getState().user?.id;
         const authorId = data.author?.id ?? data.user_id;
         const focusedChannelId = useChannelStore.getState().selectedChannelId;
@@ -239,6 +283,11 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
       );
       break;
     }
+    case GatewayEvents.MESSAGE_REACTION_BULK:
+      useMessageStore.getState().handleReactionBulk(
+        data.channel_id, data.message_id, data.reactions || []
+      );
+      break;
     case GatewayEvents.POLL_VOTE_ADD:
     case GatewayEvents.POLL_VOTE_REMOVE:
       if (data.poll) {
@@ -262,11 +311,35 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
       useAuthStore.getState().fetchUser();
       break;
 
+    case GatewayEvents.USER_SETTINGS_UPDATE:
+      useAuthStore.getState().applyRemoteSettings(data);
+      break;
+
     case GatewayEvents.RELATIONSHIP_ADD:
     case GatewayEvents.RELATIONSHIP_REMOVE:
       void useRelationshipStore.getState().fetchRelationships();
       break;
 
+    case GatewayEvents.DM_REQUEST_UPDATE:
+      window.dispatchEvent(new CustomEvent('paracord:dm-requests-changed', {
+        detail: { channel_id: data.channel_id, accepted: data.accepted },
+      }));
+      break;
+
+    case GatewayEvents.GROUP_DM_CREATE:
+    case GatewayEvents.GROUP_DM_UPDATE:
+    case GatewayEvents.GROUP_DM_DELETE:
+    case GatewayEvents.GROUP_DM_RECIPIENT_ADD:
+    case GatewayEvents.GROUP_DM_RECIPIENT_REMOVE:
+      window.dispatchEvent(new CustomEvent('paracord:group-dms-changed', {
+        detail: { channel_id: data.channel_id ?? data.id },
+      }));
+      break;
+
+    case GatewayEvents.GROUP_DM_SENDER_KEY:
+      window.dispatchEvent(new CustomEvent('paracord:group-dm-sender-key', { detail: data }));
+      break;
+
     case GatewayEvents.GUILD_SCHEDULED_EVENT_CREATE:
     case GatewayEvents.GUILD_SCHEDULED_EVENT_UPDATE:
     case GatewayEvents.GUILD_SCHEDULED_EVENT_DELETE:
@@ -277,12 +350,44 @@ export function dispatchGatewayEvent(serverId: string, event: string, data: any)
       }));
       break;
 
+    case GatewayEvents.GUILD_SCHEDULED_EVENT_REMINDER:
+      if (notificationsEnabled()) {
+        const minutes = Math.max(0, Number(data.starts_in_minutes) || 0);
+        void sendNotification(
+          data.event?.name ?? 'Event reminder',
+          minutes > 0 ? `Starts in ${minutes} minute${minutes === 1 ? '' : 's'}` : 'Starting now',
+        );
+      }
+      break;
+
+    case GatewayEvents.GUILD_SCHEDULED_EVENT_JOIN_PROMPT:
+      window.dispatchEvent(new CustomEvent('paracord:scheduled-event-join-prompt', {
+        detail: {
+          guild_id: data.guild_id,
+          channel_id: data.channel_id,
+          event: data.event,
+        },
+      }));
+      if (notificationsEnabled()) {
+        void sendNotification(
+          data.event?.name ?? 'Event started',
+          'The event has started. Join the voice channel to take part.',
+        );
+      }
+      break;
+
     case GatewayEvents.GUILD_EMOJIS_UPDATE:
       window.dispatchEvent(new CustomEvent('paracord:emojis-changed', {
         detail: { guild_id: data.guild_id },
       }));
       break;
 
+    case GatewayEvents.GUILD_THEME_UPDATE:
+      window.dispatchEvent(new CustomEvent('paracord:guild-theme-changed', {
+        detail: { guild_id: data.guild_id, theme: data },
+      }));
+      break;
+
     case GatewayEvents.SERVER_RESTART:
       useUIStore.getState().setServerRestarting(true);
       break;
@@ -26,6 +26,7 @@ export const GatewayEvents = {
   MESSAGE_DELETE_BULK: 'MESSAGE_DELETE_BULK',
   MESSAGE_REACTION_ADD: 'MESSAGE_REACTION_ADD',
   MESSAGE_REACTION_REMOVE: 'MESSAGE_REACTION_REMOVE',
+  MESSAGE_REACTION_BULK: 'MESSAGE_REACTION_BULK',
   POLL_VOTE_ADD: 'POLL_VOTE_ADD',
   POLL_VOTE_REMOVE: 'POLL_VOTE_REMOVE',
 
@@ -58,20 +59,35 @@ export const GatewayEvents = {
 
   // User events
   USER_UPDATE: 'USER_UPDATE',
+  USER_SETTINGS_UPDATE: 'USER_SETTINGS_UPDATE',
 
   // Relationship events
   RELATIONSHIP_ADD: 'RELATIONSHIP_ADD',
   RELATIONSHIP_REMOVE: 'RELATIONSHIP_REMOVE',
 
+  // DM request events
+  DM_REQUEST_UPDATE: 'DM_REQUEST_UPDATE',
+
+  // Group DM events
+  GROUP_DM_CREATE: 'GROUP_DM_CREATE',
+  GROUP_DM_UPDATE: 'GROUP_DM_UPDATE',
+  GROUP_DM_DELETE: 'GROUP_DM_DELETE',
+  GROUP_DM_RECIPIENT_ADD: 'GROUP_DM_RECIPIENT_ADD',
+  GROUP_DM_RECIPIENT_REMOVE: 'GROUP_DM_RECIPIENT_REMOVE',
+  GROUP_DM_SENDER_KEY: 'GROUP_DM_SENDER_KEY',
+
   // Scheduled event events
   GUILD_SCHEDULED_EVENT_CREATE: 'GUILD_SCHEDULED_EVENT_CREATE',
   GUILD_SCHEDULED_EVENT_UPDATE: 'GUILD_SCHEDULED_EVENT_UPDATE',
   GUILD_SCHEDULED_EVENT_DELETE: 'GUILD_SCHEDULED_EVENT_DELETE',
   GUILD_SCHEDULED_EVENT_USER_ADD: 'GUILD_SCHEDULED_EVENT_USER_ADD',
   GUILD_SCHEDULED_EVENT_USER_REMOVE: 'GUILD_SCHEDULED_EVENT_USER_REMOVE',
+  GUILD_SCHEDULED_EVENT_REMINDER: 'GUILD_SCHEDULED_EVENT_REMINDER',
+  GUILD_SCHEDULED_EVENT_JOIN_PROMPT: 'GUILD_SCHEDULED_EVENT_JOIN_PROMPT',
 
   // Emoji events
   GUILD_EMOJIS_UPDATE: 'GUILD_EMOJIS_UPDATE',
+  GUILD_THEME_UPDATE: 'GUILD_THEME_UPDATE',
 
   // Server events
   SERVER_RESTART: 'SERVER_RESTART',
@@ -0,0 +1,79 @@
+import { useEffect, useState } from 'react';
+import { guildThemeApi } from '../api/branding';
+import { resolveApiBaseUrl } from '../lib/apiBaseUrl';
+import { sanitizeGuildCss } from '../lib/security';
+import type { GuildTheme } from '../types';
+
+const STYLE_ELEMENT_ID = 'paracord-guild-css';
+const HEX_COLOR_RE = /^#[0-9a-f]{6}$/i;
+
+function buildGuildStylesheet(theme: GuildTheme): string {
+  const parts: string[] = [];
+  const accent = theme.accent_color?.trim() ?? '';
+  if (HEX_COLOR_RE.test(accent)) {
+    // The user's accent is applied as inline vars on <html>, so the guild
+    // accent needs !important to take precedence while the guild is open.
+    parts.push(
+      `:root { --color-accent-primary: ${accent} !important; --accent-primary: ${accent} !important; ` +
+        `--accent: ${accent} !important; --sidebar-active-indicator: ${accent} !important; }`,
+    );
+  }
+  const css = sanitizeGuildCss(theme.custom_css ?? '', theme.guild_id, resolveApiBaseUrl());
+  if (css) parts.push(css);
+  return parts.join('\n');
+}
+
+/**
+ * Applies a guild's accent color and custom CSS while the guild is open, and
+ * keeps it in sync with GUILD_THEME_UPDATE events.
+ */
+export function useGuildTheme(guildId: string | undefined) {
+  const [theme, setTheme] = useState<GuildTheme | null>(null);
+
+  useEffect(() => {
+    setTheme(null);
+    if (!guildId) return;
+    let disposed = false;
+
+    guildThemeApi
+      .get(guildId)
+      .then(({ data }) => {
+        if (!disposed) setTheme(data);
+      })
+      .catch(() => {
+        // themes are cosmetic; fall back to the user's own theme
+      });
+
+    const onThemeChanged = (event: Event) => {
+      const detail = (event as CustomEvent<{ guild_id?: string; theme?: GuildTheme }>).detail;
+      if (detail?.guild_id !== guildId || !detail.theme) return;
+      setTheme(detail.theme);
+    };
+    window.addEventListener('paracord:guild-theme-changed', onThemeChanged);
+
+    return () => {
+      disposed = true;
+      window.removeEventListener('paracord:guild-theme-changed', onThemeChanged);
+    };
+  }, [guildId]);
+
+  useEffect(() => {
+    const css = theme ? buildGuildStylesheet(theme) : '';
+    let styleEl = document.getElementById(STYLE_ELEMENT_ID) as HTMLStyleElement | null;
+    if (css) {
+      if (!styleEl) {
+        styleEl = document.createElement('style');
+        styleEl.id = STYLE_ELEMENT_ID;
+        document.head.appendChild(styleEl);
+      }
+      styleEl.textContent = css;
+    } else if (styleEl) {
+      styleEl.remove();
+    }
+    return () => {
+      document.getElementById(STYLE_ELEMENT_ID)?.remove();
+    };
+  }, [theme]);
+
+  return theme;
+}
@@ -1,13 +1,17 @@
 import { useCallback } from 'react';
 import { useVoiceStore } from '../stores/voiceStore';
+import type { ScreenShareConfig } from '../lib/media/mediaEngine';
 
 export function useStream() {
   const selfStream = useVoiceStore((s) => s.selfStream);
   const connected = useVoiceStore((s) => s.connected);
 
-  const startStream = useCallback(async (qualityPreset?: string) => {
+  const startStream = useCallback(async (
+    qualityPreset?: string,
+    captureOptions?: Pick<ScreenShareConfig, 'source' | 'cursor' | 'audioApplication'>
+  ) => {
     if (!connected) return;
-    await useVoiceStore.getState().startStream(qualityPreset);
+    await useVoiceStore.getState().startStream(qualityPreset, captureOptions);
   }, [connected]);
 
   const stopStream = useCallback(() => {
@@ -2,6 +2,14 @@ import { useEffect, useMemo, useRef } from 'react';
 import { gateway } from '../gateway/manager';
 import { useAuthStore } from '../stores/authStore';
 import { useVoiceStore } from '../stores/voiceStore';
+import {
+  clearGlobalHotkeys,
+  onVoiceHotkey,
+  registerGlobalHotkeys,
+  type HotkeyAction,
+  type HotkeyBinding,
+} from '../lib/globalHotkeys';
+import { isTauri } from '../lib/tauriEnv';
 
 type ParsedBinding = {
   ctrl: boolean;
@@ -100,23 +108,84 @@ async function toggleDeafAndPublish() {
 export function useVoiceKeybinds() {
   const rawKeybinds = useAuthStore((s) => s.settings?.keybinds as Record<string, unknown> | undefined);
   const pushToTalkEngaged = useRef(false);
+  /** Actions handled by desktop global hotkeys; window key events skip them. */
+  const globalActions = useRef(new Set<HotkeyAction>());
 
-  const bindings = useMemo(() => {
+  const combos = useMemo(() => {
     const keybinds = rawKeybinds || {};
     return {
-      toggleMute: parseBinding(String(keybinds.toggleMute || 'Ctrl+Shift+M')),
-      toggleDeafen: parseBinding(String(keybinds.toggleDeafen || 'Ctrl+Shift+D')),
-      pushToTalk: parseBinding(String(keybinds.pushToTalk || 'Not set')),
+      toggleMute: String(keybinds.toggleMute || 'Ctrl+Shift+M'),
+      toggleDeafen: String(keybinds.toggleDeafen || 'Ctrl+Shift+D'),
+      pushToTalk: String(keybinds.pushToTalk || 'Not set'),
     };
   }, [rawKeybinds]);
 
+  const bindings = useMemo(
+    () => ({
+      toggleMute: parseBinding(combos.toggleMute),
+      toggleDeafen: parseBinding(combos.toggleDeafen),
+      pushToTalk: parseBinding(combos.pushToTalk),
+    }),
+    [combos]
+  );
+
+  useEffect(() => {
+    if (!isTauri()) return;
+    let cancelled = false;
+    const requested: HotkeyBinding[] = [
+      { action: 'toggle_mute' as const, accelerator: combos.toggleMute },
+      { action: 'toggle_deafen' as const, accelerator: combos.toggleDeafen },
+      { action: 'push_to_talk' as const, accelerator: combos.pushToTalk },
+    ].filter((binding) => parseBinding(binding.accelerator) !== null);
+    void registerGlobalHotkeys(requested)
+      .then((statuses) => {
+        if (cancelled) return;
+        globalActions.current = new Set(
+          statuses.filter((status) => status.registered).map((status) => status.action)
+        );
+      })
+      .catch(() => {
+        // Fall back to in-window keybinds only.
+        globalActions.current = new Set();
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [combos]);
+
+  useEffect(
+    () => () => {
+      globalActions.current = new Set();
+      void clearGlobalHotkeys().catch(() => {});
+    },
+    []
+  );
+
   useEffect(() => {
+    const pressPushToTalk = () => {
+      if (pushToTalkEngaged.current) return;
+      if (useVoiceStore.getState().selfMute) {
+        pushToTalkEngaged.current = true;
+        void toggleMuteAndPublish();
+      }
+    };
+
+    const releasePushToTalk = () => {
+      if (!pushToTalkEngaged.current) return;
+      pushToTalkEngaged.current = false;
+      const voiceState = useVoiceStore.getState();
+      if (voiceState.connected && voiceState.channelId && !voiceState.selfMute) {
+        void toggleMuteAndPublish();
+      }
+    };
+
     const handleKeyDown = (event: KeyboardEvent) => {
       if (isTypingTarget(event.target)) return;
       const voiceState = useVoiceStore.getState();
       if (!voiceState.connected || !voiceState.channelId) return;
 
       if (matchesBinding(event, bindings.toggleMute)) {
+        if (globalActions.current.has('toggle_mute')) return;
         if (event.repeat) return;
         event.preventDefault();
         void toggleMuteAndPublish();
@@ -124,6 +193,7 @@ export function useVoiceKeybinds() {
       }
 
       if (matchesBinding(event, bindings.toggleDeafen)) {
+        if (globalActions.current.has('toggle_deafen')) return;
         if (event.repeat) return;
         event.preventDefault();
         void toggleDeafAndPublish();
@@ -131,49 +201,58 @@ export function useVoiceKeybinds() {
       }
 
       if (matchesBinding(event, bindings.pushToTalk)) {
+        if (globalActions.current.has('push_to_talk')) return;
         event.preventDefault();
-        if (event.repeat || pushToTalkEngaged.current) return;
-        if (voiceState.selfMute) {
-          pushToTalkEngaged.current = true;
-          void toggleMuteAndPublish();
-        }
+        if (event.repeat) return;
+        pressPushToTalk();
       }
     };
 
     const handleKeyUp = (event: KeyboardEvent) => {
       if (!matchesBinding(event, bindings.pushToTalk)) return;
+      if (globalActions.current.has('push_to_talk')) return;
       event.preventDefault();
-      if (!pushToTalkEngaged.current) return;
-      const voiceState = useVoiceStore.getState();
-      if (!voiceState.connected || !voiceState.channelId) {
-        pushToTalkEngaged.current = false;
-        return;
-      }
-      if (!voiceState.selfMute) {
-        void toggleMuteAndPublish();
-      }
-      pushToTalkEngaged.current = false;
+      releasePushToTalk();
     };
 
     const handleWindowBlur = () => {
-      if (!pushToTalkEngaged.current) return;
+      // Global push-to-talk keeps working while the window is unfocused.
+      if (globalActions.current.has('push_to_talk')) return;
+      releasePushToTalk();
+    };
+
+    const stopGlobalHotkeys = onVoiceHotkey((hotkey) => {
       const voiceState = useVoiceStore.getState();
-      if (voiceState.connected && voiceState.channelId && !voiceState.selfMute) {
+      if (!voiceState.connected || !voiceState.channelId) return;
+      if (hotkey.native) {
+        // The native media session already applied the change; mirror it.
+        useVoiceStore.setState({
+          selfMute: hotkey.muted ?? voiceState.selfMute,
+          selfDeaf: hotkey.deafened ?? voiceState.selfDeaf,
+        });
+        publishVoiceState();
+        return;
+      }
+      if (hotkey.action === 'toggle_mute') {
         void toggleMuteAndPublish();
+      } else if (hotkey.action === 'toggle_deafen') {
+        void toggleDeafAndPublish();
+      } else if (hotkey.pressed) {
+        pressPushToTalk();
+      } else {
+        releasePushToTalk();
       }
-      pushToTalkEngaged.current = false;
-    };
+    });
 
     window.addEventListener('keydown', handleKeyDown);
     window.addEventListener('keyup', handleKeyUp);
     window.addEventListener('blur', handleWindowBlur);
 
     return () => {
+      stopGlobalHotkeys();
       window.removeEventListener('keydown', handleKeyDown);
       window.removeEventListener('keyup', handleKeyUp);
       window.removeEventListener('blur', handleWindowBlur);
     };
   }, [bindings]);
 }
-
-
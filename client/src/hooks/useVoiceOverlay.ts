@@ -0,0 +1,51 @@
+import { useEffect } from 'react';
+import { getUserAvatarUrl } from '../lib/avatars';
+import {
+  hideOverlay,
+  setOverlayRoster,
+  showOverlay,
+  supportsVoiceOverlay,
+  useOverlaySettingsStore,
+  type OverlayParticipant,
+} from '../lib/desktopOverlay';
+import { useChannelStore } from '../stores/channelStore';
+import { useVoiceStore } from '../stores/voiceStore';
+
+/**
+ * Drive the desktop voice overlay: show it while connected to voice with the
+ * overlay enabled, and keep its roster in sync with the channel.
+ */
+export function useVoiceOverlay() {
+  const enabled = useOverlaySettingsStore((s) => s.enabled);
+  const config = useOverlaySettingsStore((s) => s.config);
+  const connected = useVoiceStore((s) => s.connected);
+  const channelId = useVoiceStore((s) => s.channelId);
+  const guildId = useVoiceStore((s) => s.guildId);
+  const participants = useVoiceStore((s) => s.participants);
+  const channelName = useChannelStore((s) => {
+    if (!channelId) return null;
+    const channels = (guildId ? s.channelsByGuild[guildId] : undefined) ?? s.channels;
+    return channels.find((channel) => channel.id === channelId)?.name ?? null;
+  });
+  const active = supportsVoiceOverlay() && enabled && connected;
+
+  useEffect(() => {
+    if (!supportsVoiceOverlay()) return;
+    const update = active ? showOverlay(config) : hideOverlay();
+    update.catch((err) => console.warn('[overlay] failed to update overlay window', err));
+  }, [active, config]);
+
+  useEffect(() => {
+    if (!active) return;
+    const roster: OverlayParticipant[] = Array.from(participants.values()).map((state) => ({
+      user_id: state.user_id,
+      username: state.username ?? state.user_id,
+      avatar_url: getUserAvatarUrl(state.user_id, state.avatar_hash),
+      muted: state.mute || state.self_mute,
+      deafened: state.deaf || state.self_deaf,
+    }));
+    setOverlayRoster(channelName, roster).catch((err) =>
+      console.warn('[overlay] failed to update overlay roster', err)
+    );
+  }, [active, channelName, participants]);
+}
@@ -3,16 +3,22 @@ import { useGateway } from '../hooks/useGateway';
 import { useTheme } from '../hooks/useTheme';
 import { useVoiceKeybinds } from '../hooks/useVoiceKeybinds';
 import { useActivityPresence } from '../hooks/useActivityPresence';
+import { useVoiceOverlay } from '../hooks/useVoiceOverlay';
 import { useAuthStore } from '../stores/authStore';
 import { useGuildStore } from '../stores/guildStore';
 import { useVoiceStore } from '../stores/voiceStore';
 import { useServerListStore } from '../stores/serverListStore';
+import { useSavedAccountStore } from '../stores/savedAccountStore';
 import { RestartBanner } from '../components/RestartBanner';
 import { ConnectionStatusBar } from '../components/ConnectionStatusBar';
 import { UpdateNotification } from '../components/UpdateNotification';
 import { ToastContainer } from '../components/ui/Toast';
 import { ImageLightbox } from '../components/ui/ImageLightbox';
 import { logVoiceDiagnostic } from './desktopDiagnostics';
+import { offlineCache, setOfflineCacheUser } from './offlineCache';
+
+/** How often an offline session retries reaching the server. */
+const OFFLINE_RETRY_MS = 15_000;
 
 function AppInitializer({ children }: { children: ReactNode }) {
   // Initialize gateway connection when authenticated
@@ -28,6 +34,8 @@ function AppInitializer({ children }: { children: ReactNode }) {
   useVoiceKeybinds();
   // Detect foreground desktop app and publish "Playing ..." presence.
   useActivityPresence();
+  // Show the desktop voice overlay while in a voice channel, if enabled.
+  useVoiceOverlay();
   const token = useAuthStore((s) => s.token);
   const initializeSession = useAuthStore((s) => s.initializeSession);
   const hydrateServerTokens = useServerListStore((s) => s.hydrateTokens);
@@ -35,6 +43,10 @@ function AppInitializer({ children }: { children: ReactNode }) {
   const fetchSettings = useAuthStore((s) => s.fetchSettings);
   const settings = useAuthStore((s) => s.settings);
   const fetchGuilds = useGuildStore((s) => s.fetchGuilds);
+  const user = useAuthStore((s) => s.user);
+  const offline = useAuthStore((s) => s.offline);
+  const loadSavedAccounts = useSavedAccountStore((s) => s.load);
+  const rememberActiveAccount = useSavedAccountStore((s) => s.rememberActive);
   const voiceConnected = useVoiceStore((s) => s.connected);
   const applyAudioInputDevice = useVoiceStore((s) => s.applyAudioInputDevice);
   const applyAudioOutputDevice = useVoiceStore((s) => s.applyAudioOutputDevice);
@@ -47,6 +59,19 @@ function AppInitializer({ children }: { children: ReactNode }) {
     void initializeSession();
   }, [initializeSession]);
 
+  useEffect(() => {
+    void loadSavedAccounts().catch(() => {
+      // Saved accounts are a desktop convenience; the session works without them.
+    });
+  }, [loadSavedAccounts]);
+
+  useEffect(() => {
+    if (!token || !user) return;
+    void rememberActiveAccount(user).catch(() => {
+      // Retried on the next profile or token change.
+    });
+  }, [token, user, rememberActiveAccount]);
+
   useEffect(() => {
     if (token) {
       void fetchUser();
@@ -55,6 +80,25 @@ function AppInitializer({ children }: { children: ReactNode }) {
     }
   }, [token, fetchUser, fetchSettings, fetchGuilds]);
 
+  useEffect(() => {
+    if (!token || !user) return;
+    setOfflineCacheUser(user.id);
+    void offlineCache.saveUser(user);
+  }, [token, user]);
+
+  useEffect(() => {
+    if (!offline) return;
+    // Render cached servers now and reconnect as soon as the server answers.
+    void fetchGuilds();
+    const retry = () => void initializeSession();
+    const timer = window.setInterval(retry, OFFLINE_RETRY_MS);
+    window.addEventListener('online', retry);
+    return () => {
+      window.clearInterval(timer);
+      window.removeEventListener('online', retry);
+    };
+  }, [offline, fetchGuilds, initializeSession]);
+
   useEffect(() => {
     if (!voiceConnected || !settings) return;
     const notif = settings.notifications as Record<string, unknown> | undefined;
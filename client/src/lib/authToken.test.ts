@@ -3,6 +3,7 @@ import {
   clearLegacyPersistedAuth,
   getAccessToken,
   getRefreshToken,
+  onRefreshTokenChange,
   setAccessToken,
   setRefreshToken,
 } from './authToken';
@@ -39,4 +40,16 @@ describe('authToken', () => {
     expect(localStorage.getItem('auth-storage')).toBeNull();
     expect(getRefreshToken()).toBe('refresh-token');
   });
+
+  it('notifies refresh token listeners until unsubscribed', () => {
+    const seen: (string | null)[] = [];
+    const unsubscribe = onRefreshTokenChange((token) => seen.push(token));
+
+    setRefreshToken('rotated');
+    setRefreshToken(null);
+    unsubscribe();
+    setRefreshToken('ignored');
+
+    expect(seen).toEqual(['rotated', null]);
+  });
 });
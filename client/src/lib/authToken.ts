@@ -2,6 +2,17 @@ let accessToken: string | null = null;
 
 const REFRESH_TOKEN_KEY = 'paracord:refresh-token';
 
+type RefreshTokenListener = (token: string | null) => void;
+const refreshTokenListeners = new Set<RefreshTokenListener>();
+
+/** Observe refresh token rotation, e.g. to mirror it into the desktop account store. */
+export function onRefreshTokenChange(listener: RefreshTokenListener): () => void {
+  refreshTokenListeners.add(listener);
+  return () => {
+    refreshTokenListeners.delete(listener);
+  };
+}
+
 export function getAccessToken(): string | null {
   return accessToken;
 }
@@ -29,6 +40,7 @@ export function setRefreshToken(token: string | null): void {
   } catch {
     // Ignore storage failures.
   }
+  for (const listener of refreshTokenListeners) listener(token);
 }
 
 export function clearLegacyPersistedAuth(): void {
@@ -0,0 +1,27 @@
+import { describe, expect, it } from 'vitest';
+import { isBadgeWorthyMessage } from './backgroundAccounts';
+
+const account = { user_id: '42', username: 'alice' };
+
+describe('isBadgeWorthyMessage', () => {
+  it('badges DMs and @everyone but not own messages', () => {
+    expect(isBadgeWorthyMessage(account, { author: { id: '7' }, content: 'hi' })).toBe(true);
+    expect(
+      isBadgeWorthyMessage(account, { guild_id: '1', author: { id: '7' }, mention_everyone: true })
+    ).toBe(true);
+    expect(isBadgeWorthyMessage(account, { author: { id: '42' }, content: 'hi' })).toBe(false);
+  });
+
+  it('matches whole @username mentions in guild channels', () => {
+    const msg = (content: string) => ({ guild_id: '1', author: { id: '7' }, content });
+    expect(isBadgeWorthyMessage(account, msg('hey @Alice, look'))).toBe(true);
+    expect(isBadgeWorthyMessage(account, msg('@alice'))).toBe(true);
+    expect(isBadgeWorthyMessage(account, msg('hey @alicette'))).toBe(false);
+    expect(isBadgeWorthyMessage(account, msg('mail alice@example.com'))).toBe(false);
+  });
+
+  it('stays quiet for blocked authors and message requests', () => {
+    expect(isBadgeWorthyMessage(account, { author: { id: '7' }, author_blocked: true })).toBe(false);
+    expect(isBadgeWorthyMessage(account, { author: { id: '7' }, message_request: true })).toBe(false);
+  });
+});
@@ -0,0 +1,194 @@
+import type { GatewayPayload } from '../types';
+import { getAccountCredential, setAccountCredential, type SavedAccount } from './desktopAccounts';
+import { logVoiceDiagnostic } from './desktopDiagnostics';
+
+interface BackgroundConnection {
+  account: SavedAccount;
+  eventSource: EventSource | null;
+  /** In-flight connect, which may still be rotating the stored credential. */
+  opening: Promise<void> | null;
+  reconnectTimer: ReturnType<typeof setTimeout> | null;
+  reconnectAttempts: number;
+  closed: boolean;
+}
+
+type ActivityListener = (accountId: string, count: number) => void;
+
+const MAX_RECONNECT_DELAY_MS = 5 * 60_000;
+
+function escapeRegExp(value: string): string {
+  return value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+}
+
+/**
+ * Whether a message on a non-active account deserves a badge: DMs, @everyone
+ * and direct @mentions, never the account's own messages.
+ */
+export function isBadgeWorthyMessage(
+  account: Pick<SavedAccount, 'user_id' | 'username'>,
+  data: {
+    guild_id?: string | null;
+    author?: { id?: string } | null;
+    content?: string | null;
+    mention_everyone?: boolean;
+    author_blocked?: boolean;
+    message_request?: boolean;
+  },
+): boolean {
+  if (data.author?.id === account.user_id) return false;
+  if (data.author_blocked || data.message_request) return false;
+  if (!data.guild_id) return true;
+  if (data.mention_everyone) return true;
+  if (!data.content || !account.username) return false;
+  return new RegExp(`(^|\\W)@${escapeRegExp(account.username)}(?!\\w)`, 'i').test(data.content);
+}
+
+/**
+ * Lightweight realtime streams for saved accounts that are not signed in to
+ * the UI. They only count badge-worthy messages; the active account keeps
+ * using the full connection manager.
+ */
+class BackgroundAccountConnections {
+  private connections = new Map<string, BackgroundConnection>();
+  private listener: ActivityListener | null = null;
+
+  setActivityListener(listener: ActivityListener | null): void {
+    this.listener = listener;
+  }
+
+  /** Keep exactly one stream per non-active account. */
+  sync(accounts: SavedAccount[], activeAccountId: string | null): void {
+    const wanted = new Map(
+      accounts.filter((account) => account.id !== activeAccountId).map((account) => [account.id, account]),
+    );
+    for (const id of Array.from(this.connections.keys())) {
+      if (!wanted.has(id)) void this.stop(id);
+    }
+    for (const account of wanted.values()) {
+      const existing = this.connections.get(account.id);
+      if (existing) {
+        existing.account = account;
+        continue;
+      }
+      const conn: BackgroundConnection = {
+        account,
+        eventSource: null,
+        opening: null,
+        reconnectTimer: null,
+        reconnectAttempts: 0,
+        closed: false,
+      };
+      this.connections.set(account.id, conn);
+      this.startOpen(conn);
+    }
+  }
+
+  /**
+   * Close an account's stream. Resolves once any in-flight token refresh has
+   * stored its rotated credential, so callers can safely read it afterwards.
+   */
+  stop(accountId: string): Promise<void> {
+    const conn = this.connections.get(accountId);
+    if (!conn) return Promise.resolve();
+    conn.closed = true;
+    if (conn.reconnectTimer) clearTimeout(conn.reconnectTimer);
+    conn.eventSource?.close();
+    conn.eventSource = null;
+    this.connections.delete(accountId);
+    return conn.opening ?? Promise.resolve();
+  }
+
+  stopAll(): void {
+    for (const id of Array.from(this.connections.keys())) void this.stop(id);
+  }
+
+  private startOpen(conn: BackgroundConnection): void {
+    const opening = this.open(conn).finally(() => {
+      if (conn.opening === opening) conn.opening = null;
+    });
+    conn.opening = opening;
+  }
+
+  private async open(conn: BackgroundConnection): Promise<void> {
+    const { account } = conn;
+    const base = account.server_url.replace(/\/+$/, '');
+    try {
+      const refreshToken = await getAccountCredential(account.id);
+      if (!refreshToken || conn.closed) return;
+
+      // Cookies belong to the active account, so authenticate by body only.
+      const refreshResp = await fetch(`${base}/api/v1/auth/refresh`, {
+        method: 'POST',
+        credentials: 'omit',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ refresh_token: refreshToken }),
+      });
+      if (refreshResp.status === 401) {
+        // Session revoked elsewhere; the user has to sign in to this account again.
+        logVoiceDiagnostic('[accounts] background session expired', { account: account.id });
+        return;
+      }
+      if (!refreshResp.ok) throw new Error(`refresh failed with ${refreshResp.status}`);
+      const refreshed = (await refreshResp.json()) as { token: string; refresh_token?: string };
+      if (refreshed.refresh_token) {
+        await setAccountCredential(account.id, refreshed.refresh_token);
+      }
+      if (conn.closed) return;
+
+      const sessionResp = await fetch(`${base}/api/v2/rt/session`, {
+        method: 'POST',
+        credentials: 'omit',
+        headers: { Authorization: `Bearer ${refreshed.token}` },
+      });
+      if (!sessionResp.ok) throw new Error(`session failed with ${sessionResp.status}`);
+      const session = (await sessionResp.json()) as { session_id?: string; cursor?: number };
+      if (conn.closed) return;
+
+      const params = new URLSearchParams();
+      params.set('token', refreshed.token);
+      if (session.session_id) params.set('session_id', session.session_id);
+      if (typeof session.cursor === 'number') params.set('cursor', String(session.cursor));
+      const es = new EventSource(`${base}/api/v2/rt/events?${params.toString()}`);
+      conn.eventSource = es;
+
+      const handle = (raw: string) => {
+        if (conn.closed || conn.eventSource !== es) return;
+        try {
+          const payload = JSON.parse(raw) as GatewayPayload;
+          if (payload.op !== 0 || payload.t !== 'MESSAGE_CREATE') return;
+          if (isBadgeWorthyMessage(conn.account, payload.d as Parameters<typeof isBadgeWorthyMessage>[1])) {
+            this.listener?.(conn.account.id, 1);
+          }
+        } catch {
+          // ignore malformed payloads
+        }
+      };
+      es.onopen = () => {
+        conn.reconnectAttempts = 0;
+      };
+      es.onmessage = (evt) => handle(evt.data);
+      es.addEventListener('gateway', (evt) => handle((evt as MessageEvent<string>).data));
+      es.onerror = () => {
+        if (conn.eventSource !== es) return;
+        es.close();
+        conn.eventSource = null;
+        this.scheduleReconnect(conn);
+      };
+    } catch (err) {
+      logVoiceDiagnostic('[accounts] background connect failed', { account: account.id, error: String(err) });
+      this.scheduleReconnect(conn);
+    }
+  }
+
+  private scheduleReconnect(conn: BackgroundConnection): void {
+    if (conn.closed || conn.reconnectTimer) return;
+    const delay = Math.min(5000 * Math.pow(2, conn.reconnectAttempts), MAX_RECONNECT_DELAY_MS);
+    conn.reconnectAttempts++;
+    conn.reconnectTimer = setTimeout(() => {
+      conn.reconnectTimer = null;
+      if (!conn.closed) this.startOpen(conn);
+    }, delay);
+  }
+}
+
+export const backgroundAccounts = new BackgroundAccountConnections();
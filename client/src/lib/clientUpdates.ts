@@ -0,0 +1,71 @@
+import { invoke } from '@tauri-apps/api/core';
+import { create } from 'zustand';
+import { isTauri } from './tauriEnv';
+
+export const CLIENT_VERSION = __APP_VERSION__;
+export const CLIENT_VERSION_HEADER = 'X-Paracord-Client-Version';
+export const UPDATE_CHANNEL = 'stable';
+
+/** Latest build published in a server's client update manifest. */
+export interface ServerUpdateInfo {
+  version: string;
+  current_version: string;
+  notes: string | null;
+  pub_date: string | null;
+  download_url: string;
+  minimum_version: string | null;
+}
+
+interface ClientUpdateGateState {
+  /** Minimum version demanded by a server that rejected this client. */
+  requiredVersion: string | null;
+  setRequiredVersion: (version: string | null) => void;
+}
+
+export const useClientUpdateGateStore = create<ClientUpdateGateState>()((set) => ({
+  requiredVersion: null,
+  setRequiredVersion: (requiredVersion) => set({ requiredVersion }),
+}));
+
+/** Only desktop builds identify themselves; the web client ships with the server. */
+export function clientVersionHeaders(): Record<string, string> {
+  return isTauri() ? { [CLIENT_VERSION_HEADER]: CLIENT_VERSION } : {};
+}
+
+/**
+ * Record a `CLIENT_OUTDATED` rejection so the update prompt can explain why
+ * requests are failing. Returns whether `data` was such a rejection.
+ */
+export function noteClientOutdated(status: number | undefined, data: unknown): boolean {
+  if (status !== 426 || typeof data !== 'object' || data === null) return false;
+  const body = data as { code?: unknown; details?: { minimum_version?: unknown } | null };
+  if (body.code !== 'CLIENT_OUTDATED') return false;
+  const minimum = body.details?.minimum_version;
+  if (typeof minimum === 'string') {
+    useClientUpdateGateStore.getState().setRequiredVersion(minimum);
+  }
+  return true;
+}
+
+/**
+ * Check `serverUrl`'s update manifest with the desktop updater. Rejects when
+ * the server doesn't publish one.
+ */
+export async function checkServerUpdate(
+  serverUrl: string,
+  target: string | null
+): Promise<ServerUpdateInfo | null> {
+  return invoke<ServerUpdateInfo | null>('client_update_check', {
+    serverUrl,
+    channel: UPDATE_CHANNEL,
+    target,
+  });
+}
+
+export async function downloadServerUpdate(): Promise<void> {
+  await invoke('client_update_download');
+}
+
+export async function installServerUpdate(): Promise<void> {
+  await invoke('client_update_install');
+}
@@ -15,8 +15,11 @@ import type { Activity, GatewayPayload } from '../types';
 import { GatewayEvents } from '../gateway/events';
 import { dispatchGatewayEvent } from '../gateway/dispatch';
 import { logVoiceDiagnostic } from './desktopDiagnostics';
+import { isTauri } from './tauriEnv';
 
 export const LOCAL_SERVER_ID = '__local__';
+/** Minimum gap between SSE cursor acknowledgements. */
+const REALTIME_ACK_INTERVAL_MS = 15_000;
 
 export interface ServerConnection {
   serverId: string;
@@ -30,6 +33,7 @@ export interface ServerConnection {
   sequence: number | null;
   sessionId: string | null;
   realtimeCursor: number | null;
+  lastRealtimeAck: number;
   reconnectAttempts: number;
   reconnectTimer: ReturnType<typeof setTimeout> | null;
   allowReconnect: boolean;
@@ -238,6 +242,7 @@ class ConnectionManager {
       sequence: null,
       sessionId: null,
       realtimeCursor: null,
+      lastRealtimeAck: 0,
       reconnectAttempts: 0,
       reconnectTimer: null,
       allowReconnect: true,
@@ -308,6 +313,7 @@ class ConnectionManager {
       sequence: null,
       sessionId: null,
       realtimeCursor: null,
+      lastRealtimeAck: 0,
       reconnectAttempts: 0,
       reconnectTimer: null,
       allowReconnect: true,
@@ -530,6 +536,7 @@ class ConnectionManager {
               conn.realtimeCursor = payload.event_id;
             }
             this.handlePayload(conn, payload);
+            this.ackRealtimeCursor(conn);
           } catch {
             // ignore malformed payloads
           }
@@ -731,7 +738,7 @@ class ConnectionManager {
     } else {
       this.send(conn, {
         op: 2,
-        d: { token },
+        d: { token, properties: { client: isTauri() ? 'desktop' : 'web' } },
       });
     }
   }
@@ -780,7 +787,22 @@ class ConnectionManager {
     }
   }
 
-  private async postRealtimeCommand(
+  /** Let the server drop buffered SSE events the client already has. */
+  private ackRealtimeCursor(conn: ServerConnection): void {
+    const cursor = conn.realtimeCursor;
+    if (cursor == null || cursor <= 0 || !conn.sessionId) return;
+    const now = Date.now();
+    if (now - conn.lastRealtimeAck < REALTIME_ACK_INTERVAL_MS) return;
+    conn.lastRealtimeAck = now;
+    const url = `${conn.serverUrl.replace(/\/+$/, '')}/api/v2/rt/ack`;
+    void conn.apiClient
+      .post(url, { session_id: conn.sessionId, cursor }, { timeout: 10_000 })
+      .catch(() => {
+        // Best effort; an unacknowledged event just stays buffered longer.
+      });
+  }
+
+    private async postRealtimeCommand(
     conn: ServerConnection,
     commandType: string,
     payload: Record<string, unknown>,
@@ -0,0 +1,59 @@
+import { invoke } from '@tauri-apps/api/core';
+import { isTauri } from './tauriEnv';
+
+/** Server + account pair remembered by the desktop app. */
+export interface SavedAccount {
+  id: string;
+  server_url: string;
+  user_id: string;
+  username: string;
+  display_name: string | null;
+  avatar_hash: string | null;
+  last_used_at: number;
+}
+
+export interface SavedAccountList {
+  active_account_id: string | null;
+  accounts: SavedAccount[];
+}
+
+export interface SavedAccountInput {
+  server_url: string;
+  user_id: string;
+  username: string;
+  display_name?: string | null;
+  avatar_hash?: string | null;
+}
+
+/** Multi-account storage is only available in the desktop app. */
+export function supportsSavedAccounts(): boolean {
+  return isTauri();
+}
+
+export function listSavedAccounts(): Promise<SavedAccountList> {
+  return invoke<SavedAccountList>('accounts_list');
+}
+
+export function saveAccount(
+  account: SavedAccountInput,
+  refreshToken: string,
+  makeActive: boolean,
+): Promise<SavedAccount> {
+  return invoke<SavedAccount>('accounts_save', { account, refreshToken, makeActive });
+}
+
+export function setAccountCredential(accountId: string, refreshToken: string): Promise<void> {
+  return invoke('accounts_set_credential', { accountId, refreshToken });
+}
+
+export function getAccountCredential(accountId: string): Promise<string | null> {
+  return invoke<string | null>('accounts_get_credential', { accountId });
+}
+
+export function markAccountActive(accountId: string): Promise<SavedAccount> {
+  return invoke<SavedAccount>('accounts_switch', { accountId });
+}
+
+export function removeSavedAccount(accountId: string): Promise<SavedAccountList> {
+  return invoke<SavedAccountList>('accounts_remove', { accountId });
+}
@@ -88,3 +88,79 @@ export function logVoiceDiagnostic(message: string, extra?: unknown) {
 }
 
 logVoiceDiagnostic('[voice] desktop diagnostics initialized');
+
+export interface CrashReportingStatus {
+  enabled: boolean;
+  /** Crash report files captured so far, newest first. */
+  reports: string[];
+}
+
+const MAX_RECENT_CLIENT_ERRORS = 50;
+const recentClientErrors: string[] = [];
+
+function webglRenderer(): string | null {
+  try {
+    const canvas = document.createElement('canvas');
+    const gl = canvas.getContext('webgl2') ?? canvas.getContext('webgl');
+    if (!gl) return null;
+    const info = gl.getExtension('WEBGL_debug_renderer_info');
+    return String(gl.getParameter(info ? info.UNMASKED_RENDERER_WEBGL : gl.RENDERER));
+  } catch {
+    return null;
+  }
+}
+
+/** Keep a UI error for the next diagnostics bundle. */
+export function reportClientError(message: string) {
+  recentClientErrors.push(`${new Date().toISOString()} ${message}`);
+  if (recentClientErrors.length > MAX_RECENT_CLIENT_ERRORS) {
+    recentClientErrors.splice(0, recentClientErrors.length - MAX_RECENT_CLIENT_ERRORS);
+  }
+  void getInvoke().then((invoke) => invoke?.('record_client_error', { message }).catch(() => {}));
+}
+
+/** Forward uncaught errors and rejections to the native recent-errors buffer. */
+export function installClientErrorReporting() {
+  window.addEventListener('error', (event) => {
+    const where = event.filename ? ` (${event.filename}:${event.lineno}:${event.colno})` : '';
+    reportClientError(`${event.message}${where}`);
+  });
+  window.addEventListener('unhandledrejection', (event) => {
+    const reason = event.reason instanceof Error ? event.reason.stack ?? event.reason.message : safeSerialize(event.reason);
+    reportClientError(`unhandled rejection: ${reason}`);
+  });
+}
+
+/** Zip up logs, media stats, and system info; resolves to the bundle path. */
+export async function createDiagnosticsBundle(): Promise<string> {
+  const invoke = await getInvoke();
+  if (!invoke) throw new Error('Diagnostics bundles are only available in the desktop app');
+  await flushClientDiagnostics();
+  const path = await invoke('diagnostics_create_bundle', {
+    clientInfo: {
+      userAgent: navigator.userAgent,
+      language: navigator.language,
+      webglRenderer: webglRenderer(),
+      screen: `${window.screen.width}x${window.screen.height}@${window.devicePixelRatio}`,
+      location: window.location.pathname,
+      recentErrors: [...recentClientErrors],
+    },
+  });
+  return String(path);
+}
+
+export async function getCrashReportingStatus(): Promise<CrashReportingStatus | null> {
+  const invoke = await getInvoke();
+  if (!invoke) return null;
+  try {
+    return (await invoke('crash_reporting_status')) as CrashReportingStatus;
+  } catch {
+    return null;
+  }
+}
+
+export async function setCrashReportingEnabled(enabled: boolean): Promise<void> {
+  const invoke = await getInvoke();
+  if (!invoke) return;
+  await invoke('crash_reporting_set_enabled', { enabled });
+}
@@ -0,0 +1,120 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { create } from 'zustand';
+import { persist } from 'zustand/middleware';
+import { isTauri } from './tauriEnv';
+
+export type OverlayCorner = 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+
+export interface OverlayConfig {
+  /** Monitor name from `listOverlayMonitors`; null means the primary monitor. */
+  monitor: string | null;
+  corner: OverlayCorner;
+  margin: number;
+  /** Let mouse input pass through to the game underneath. */
+  click_through: boolean;
+}
+
+export interface OverlayParticipant {
+  user_id: string;
+  username: string;
+  avatar_url: string | null;
+  muted: boolean;
+  deafened: boolean;
+}
+
+export interface OverlaySnapshot {
+  visible: boolean;
+  config: OverlayConfig;
+  channel_name: string | null;
+  participants: OverlayParticipant[];
+}
+
+export interface OverlayMonitor {
+  name: string;
+  primary: boolean;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  scale_factor: number;
+}
+
+interface OverlaySettingsState {
+  enabled: boolean;
+  config: OverlayConfig;
+  setEnabled: (enabled: boolean) => void;
+  updateConfig: (patch: Partial<OverlayConfig>) => void;
+}
+
+export const useOverlaySettingsStore = create<OverlaySettingsState>()(
+  persist(
+    (set) => ({
+      enabled: false,
+      config: { monitor: null, corner: 'top_left', margin: 16, click_through: true },
+      setEnabled: (enabled) => set({ enabled }),
+      updateConfig: (patch) => set((state) => ({ config: { ...state.config, ...patch } })),
+    }),
+    { name: 'voice-overlay-settings' }
+  )
+);
+
+export function supportsVoiceOverlay(): boolean {
+  return isTauri();
+}
+
+export async function listOverlayMonitors(): Promise<OverlayMonitor[]> {
+  if (!isTauri()) return [];
+  return invoke<OverlayMonitor[]>('overlay_list_monitors');
+}
+
+export async function showOverlay(config: OverlayConfig): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('overlay_show', { config });
+}
+
+export async function hideOverlay(): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('overlay_hide');
+}
+
+export async function setOverlayClickThrough(enabled: boolean): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('overlay_set_click_through', { enabled });
+}
+
+export async function setOverlayRoster(
+  channelName: string | null,
+  participants: OverlayParticipant[]
+): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('overlay_set_roster', { channelName, participants });
+}
+
+export async function getOverlayState(): Promise<OverlaySnapshot | null> {
+  if (!isTauri()) return null;
+  return invoke<OverlaySnapshot>('overlay_get_state');
+}
+
+function subscribe<T>(event: string, handler: (payload: T) => void): () => void {
+  if (!isTauri()) return () => {};
+  let disposed = false;
+  let unlisten: (() => void) | null = null;
+  void listen<T>(event, (e) => handler(e.payload)).then((fn) => {
+    if (disposed) fn();
+    else unlisten = fn;
+  });
+  return () => {
+    disposed = true;
+    unlisten?.();
+  };
+}
+
+export function onOverlayState(handler: (snapshot: OverlaySnapshot) => void): () => void {
+  return subscribe('overlay_state', handler);
+}
+
+/** Speaking levels from the native session's speaker detector, keyed by speaker id. */
+export function onOverlaySpeaking(handler: (speakers: Record<string, number>) => void): () => void {
+  return subscribe('media_speaking_change', handler);
+}
@@ -0,0 +1,33 @@
+const DEVICE_ID_STORAGE_KEY = 'paracord:device-id';
+export const DEVICE_ID_HEADER = 'X-Device-Id';
+
+let cachedDeviceId: string | null = null;
+
+/**
+ * Stable identifier for this browser profile or desktop install. Servers use
+ * it to recognize a device the user already approved.
+ */
+export function getDeviceId(): string {
+  if (cachedDeviceId) return cachedDeviceId;
+  try {
+    const stored = window.localStorage.getItem(DEVICE_ID_STORAGE_KEY);
+    if (stored) {
+      cachedDeviceId = stored;
+      return stored;
+    }
+  } catch {
+    // Storage can be unavailable in private windows; fall through.
+  }
+  const generated = crypto.randomUUID();
+  try {
+    window.localStorage.setItem(DEVICE_ID_STORAGE_KEY, generated);
+  } catch {
+    // Kept for this page load only.
+  }
+  cachedDeviceId = generated;
+  return generated;
+}
+
+export function deviceIdHeaders(): Record<string, string> {
+  return { [DEVICE_ID_HEADER]: getDeviceId() };
+}
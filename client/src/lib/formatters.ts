@@ -132,6 +132,13 @@ const PERMISSION_NAMES: Record<string, string> = {
   MANAGE_ROLES: 'Manage Roles',
   MANAGE_WEBHOOKS: 'Manage Webhooks',
   MANAGE_EMOJIS: 'Manage Emojis',
+  RECORD_VOICE: 'Record & Restream Voice',
+  POST_OVERRIDE: 'Post in Read-Only Channels',
+  MANAGE_THREADS: 'Manage Threads',
+  MODERATE_MEMBERS: 'Timeout Members',
+  USE_SOUNDBOARD: 'Use Soundboard',
+  MANAGE_INVITES: 'Manage Invites',
+  VIEW_REPORTS: 'View Reports',
 };
 
 /**
@@ -0,0 +1,62 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { create } from 'zustand';
+import { isTauri } from './tauriEnv';
+
+export type HotkeyAction = 'toggle_mute' | 'toggle_deafen' | 'push_to_talk';
+
+export interface HotkeyBinding {
+  action: HotkeyAction;
+  accelerator: string;
+}
+
+/** Registration result per binding; `error` explains conflicts. */
+export interface HotkeyStatus {
+  action: HotkeyAction;
+  accelerator: string;
+  registered: boolean;
+  error: string | null;
+}
+
+/**
+ * Fired when a global hotkey is pressed or released. With `native` set the
+ * desktop media session already applied `muted`/`deafened`.
+ */
+export interface VoiceHotkeyEvent {
+  action: HotkeyAction;
+  pressed: boolean;
+  native: boolean;
+  muted: boolean | null;
+  deafened: boolean | null;
+}
+
+export const useGlobalHotkeyStore = create<{ statuses: HotkeyStatus[] }>()(() => ({
+  statuses: [],
+}));
+
+export async function registerGlobalHotkeys(bindings: HotkeyBinding[]): Promise<HotkeyStatus[]> {
+  if (!isTauri()) return [];
+  const statuses = await invoke<HotkeyStatus[]>('hotkeys_register', { bindings });
+  useGlobalHotkeyStore.setState({ statuses });
+  return statuses;
+}
+
+export async function clearGlobalHotkeys(): Promise<void> {
+  if (!isTauri()) return;
+  useGlobalHotkeyStore.setState({ statuses: [] });
+  await invoke('hotkeys_clear');
+}
+
+export function onVoiceHotkey(handler: (event: VoiceHotkeyEvent) => void): () => void {
+  if (!isTauri()) return () => {};
+  let disposed = false;
+  let unlisten: (() => void) | null = null;
+  void listen<VoiceHotkeyEvent>('voice_hotkey', (event) => handler(event.payload)).then((fn) => {
+    if (disposed) fn();
+    else unlisten = fn;
+  });
+  return () => {
+    disposed = true;
+    unlisten?.();
+  };
+}
@@ -1,8 +1,32 @@
+export type ScreenCaptureSource = 'monitor' | 'window';
+export type ScreenCaptureCursor = 'embedded' | 'hidden';
+
 export interface ScreenShareConfig {
   audio: boolean;
   maxFrameRate?: number;
   maxWidth?: number;
   maxHeight?: number;
+  /** Native capture only: offer windows or whole screens in the share dialog. */
+  source?: ScreenCaptureSource;
+  /** Native capture only: whether the pointer is drawn into the stream. */
+  cursor?: ScreenCaptureCursor;
+  /** Stream audio from this application only (macOS bundle id). */
+  audioApplication?: string;
+}
+
+/** Result of the desktop `screen_capture_capabilities` command. */
+export interface NativeScreenCaptureCapabilities {
+  supported: boolean;
+  backend: string | null;
+  sources: ScreenCaptureSource[];
+  cursor_modes: ScreenCaptureCursor[];
+}
+
+/** The session lost a device and switched to the system default. */
+export interface DeviceFallback {
+  kind: 'input' | 'output';
+  previous: string | null;
+  current: string | null;
 }
 
 export interface MediaEngine {
@@ -23,6 +47,8 @@ export interface MediaEngine {
   onSpeakingChange(cb: (speakers: Map<string, number>) => void): void;
   onParticipantJoin(cb: (userId: string) => void): void;
   onParticipantLeave(cb: (userId: string) => void): void;
+  /** Native sessions only: fired after failing over from an unplugged device. */
+  onDeviceFallback?(cb: (change: DeviceFallback) => void): void;
   subscribeVideo(userId: string, canvas: HTMLCanvasElement): void;
 }
 
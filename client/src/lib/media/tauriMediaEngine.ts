@@ -1,4 +1,9 @@
-import type { MediaEngine, ScreenShareConfig } from './mediaEngine';
+import type {
+  DeviceFallback,
+  MediaEngine,
+  NativeScreenCaptureCapabilities,
+  ScreenShareConfig,
+} from './mediaEngine';
 
 // Tauri API imports - these resolve at runtime in the Tauri environment
 let invoke: (cmd: string, args?: Record<string, unknown> | ArrayBuffer | Uint8Array) => Promise<unknown>;
@@ -18,6 +23,22 @@ const tauriReady = (async () => {
 
 type UnlistenFn = () => void;
 
+let nativeCaptureCapabilities: Promise<NativeScreenCaptureCapabilities | null> | null = null;
+
+/**
+ * Whether screen share frames come from the native capture backend (PipeWire
+ * on Linux, where WebKitGTK has no getDisplayMedia). Queried once per launch.
+ */
+export function getNativeScreenCaptureCapabilities(): Promise<NativeScreenCaptureCapabilities | null> {
+  if (!nativeCaptureCapabilities) {
+    nativeCaptureCapabilities = tauriReady
+      .then(() => (invoke ? invoke('screen_capture_capabilities') : null))
+      .then((caps) => (caps as NativeScreenCaptureCapabilities | null) ?? null)
+      .catch(() => null);
+  }
+  return nativeCaptureCapabilities;
+}
+
 function normalizeNativeRelayEndpoint(endpoint: string): string {
   if (!endpoint) return '';
   const trimmed = endpoint.trim();
@@ -55,6 +76,7 @@ export class TauriMediaEngine implements MediaEngine {
   private screenShareEndedCb: (() => void) | null = null;
   private screenAudioAccumulator: number[] = [];
   private screenAudioActive = false;
+  private nativeScreenShareUnlisten: UnlistenFn | null = null;
 
   // Video frame extraction
   private videoStream: MediaStream | null = null;
@@ -122,6 +144,12 @@ export class TauriMediaEngine implements MediaEngine {
       throw new Error('Tauri IPC not available — cannot start screen share');
     }
 
+    const nativeCapture = await getNativeScreenCaptureCapabilities();
+    if (nativeCapture?.supported) {
+      await this.startNativeScreenShare(config);
+      return;
+    }
+
     if (!navigator.mediaDevices?.getDisplayMedia) {
       throw new Error('getDisplayMedia API not available in this WebView. Screen sharing requires WebView2 v93+.');
     }
@@ -158,7 +186,7 @@ export class TauriMediaEngine implements MediaEngine {
 
     await invoke('voice_start_screen_share');
     if (config.audio) {
-      const audioForwardingReady = await this.startScreenAudioForwarding();
+      const audioForwardingReady = await this.startScreenAudioForwarding(config.audioApplication);
       if (!audioForwardingReady) {
         console.warn('[TauriMediaEngine] Native system audio capture unavailable; streaming video-only.');
       }
@@ -172,6 +200,38 @@ export class TauriMediaEngine implements MediaEngine {
     this.startVideoFrameExtraction(this.screenStream, true);
   }
 
+  /**
+   * Screen share where Rust captures and encodes frames itself. The system
+   * share dialog picks the source, so there is no local preview track.
+   */
+  private async startNativeScreenShare(config: ScreenShareConfig): Promise<void> {
+    this.cleanupScreenShare();
+    this.screenAudioActive = false;
+
+    await invoke('voice_start_native_screen_share', {
+      options: {
+        source: config.source ?? 'monitor',
+        cursor: config.cursor ?? 'embedded',
+        max_frame_rate: config.maxFrameRate ?? 30,
+      },
+    });
+    this.nativeScreenShareUnlisten = await listen('screen_capture_ended', () => {
+      this.cleanupScreenShare();
+      this.screenAudioActive = false;
+      this.screenShareEndedCb?.();
+    });
+
+    if (config.audio) {
+      const audioForwardingReady = await this.startScreenAudioForwarding(config.audioApplication);
+      if (!audioForwardingReady) {
+        console.warn('[TauriMediaEngine] Native system audio capture unavailable; streaming video-only.');
+      }
+      this.screenAudioActive = audioForwardingReady;
+    } else {
+      await invoke('voice_set_screen_audio_enabled', { enabled: false }).catch(() => { });
+    }
+  }
+
   stopScreenShare(): void {
     if (this.screenFrameLoop !== null) {
       cancelAnimationFrame(this.screenFrameLoop);
@@ -195,6 +255,10 @@ export class TauriMediaEngine implements MediaEngine {
   }
 
   private cleanupScreenShare(): void {
+    if (this.nativeScreenShareUnlisten) {
+      this.nativeScreenShareUnlisten();
+      this.nativeScreenShareUnlisten = null;
+    }
     if (this.screenFrameLoop !== null) {
       cancelAnimationFrame(this.screenFrameLoop);
       this.screenFrameLoop = null;
@@ -213,7 +277,7 @@ export class TauriMediaEngine implements MediaEngine {
     this.stopScreenAudioForwarding();
   }
 
-  private async startScreenAudioForwarding(): Promise<boolean> {
+  private async startScreenAudioForwarding(application?: string): Promise<boolean> {
     this.stopScreenAudioForwarding();
 
     try {
@@ -245,7 +309,7 @@ export class TauriMediaEngine implements MediaEngine {
       };
 
       try {
-        await invoke('start_system_audio_capture', { onAudio: channel });
+        await invoke('start_system_audio_capture', { onAudio: channel, application });
       } catch (startErr) {
         // Retry once for "already running" races from overlapping stop/start
         const errMsg = startErr instanceof Error ? startErr.message : String(startErr);
@@ -253,7 +317,7 @@ export class TauriMediaEngine implements MediaEngine {
           throw startErr;
         }
         await invoke('stop_system_audio_capture').catch(() => {});
-        await invoke('start_system_audio_capture', { onAudio: channel });
+        await invoke('start_system_audio_capture', { onAudio: channel, application });
       }
 
       await invoke('voice_set_screen_audio_enabled', { enabled: true }).catch(() => {});
@@ -407,6 +471,15 @@ export class TauriMediaEngine implements MediaEngine {
     });
   }
 
+  onDeviceFallback(cb: (change: DeviceFallback) => void): void {
+    tauriReady.then(async () => {
+      const unlisten = await listen('media_device_fallback', (event) => {
+        cb(event.payload as DeviceFallback);
+      });
+      this.unlisteners.push(unlisten);
+    });
+  }
+
   subscribeVideo(userId: string, canvas: HTMLCanvasElement): void {
     invoke('media_subscribe_video', {
       userId,
@@ -0,0 +1,28 @@
+import { AxiosError } from 'axios';
+import { describe, expect, it } from 'vitest';
+import { cacheScopeFor, isCacheableThumbnail, isNetworkError } from './offlineCache';
+
+describe('offlineCache', () => {
+  it('scopes the cache per server and account', () => {
+    expect(cacheScopeFor('https://Chat.Example.com/', '42')).toBe('https://chat.example.com#42');
+    expect(cacheScopeFor('https://chat.example.com', '42')).not.toBe(
+      cacheScopeFor('https://chat.example.com', '43'),
+    );
+  });
+
+  it('treats only unanswered requests as network errors', () => {
+    expect(isNetworkError(new AxiosError('Network Error', 'ERR_NETWORK'))).toBe(true);
+    const unauthorized = new AxiosError('Unauthorized');
+    unauthorized.response = { status: 401 } as AxiosError['response'];
+    expect(isNetworkError(unauthorized)).toBe(false);
+    expect(isNetworkError(new Error('boom'))).toBe(false);
+  });
+
+  it('keeps small local image thumbnails only', () => {
+    const image = { id: '1', filename: 'a.png', url: '/a', content_type: 'image/png', size: 2048 };
+    expect(isCacheableThumbnail(image)).toBe(true);
+    expect(isCacheableThumbnail({ ...image, size: 4 * 1024 * 1024 })).toBe(false);
+    expect(isCacheableThumbnail({ ...image, content_type: 'video/mp4' })).toBe(false);
+    expect(isCacheableThumbnail({ ...image, origin_server: 'remote.example' })).toBe(false);
+  });
+});
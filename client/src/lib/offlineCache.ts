@@ -0,0 +1,135 @@
+import axios from 'axios';
+import { invoke } from '@tauri-apps/api/core';
+import type { Attachment, Channel, Guild, Message, User } from '../types';
+import { resolveApiBaseUrl } from './apiBaseUrl';
+import { isTauri } from './tauriEnv';
+
+const SCOPE_STORAGE_KEY = 'paracord:offline-cache-scope';
+/** Thumbnails above this size are not worth keeping offline. */
+const MAX_CACHED_MEDIA_BYTES = 512 * 1024;
+
+interface CachedMedia {
+  content_type: string;
+  data: string;
+}
+
+/** The encrypted offline cache lives in the desktop app's SQLite database. */
+export function supportsOfflineCache(): boolean {
+  return isTauri();
+}
+
+/** Cache partition for one account on one server. */
+export function cacheScopeFor(serverUrl: string, userId: string): string {
+  return `${serverUrl.trim().replace(/\/+$/, '').toLowerCase()}#${userId}`;
+}
+
+/** True for failures where the server was never reached (offline, DNS, timeout). */
+export function isNetworkError(err: unknown): boolean {
+  return axios.isAxiosError(err) && !err.response;
+}
+
+function readStoredScope(): string | null {
+  try {
+    const stored = window.localStorage.getItem(SCOPE_STORAGE_KEY);
+    // Only reuse the last scope while pointed at the same server.
+    const prefix = cacheScopeFor(resolveApiBaseUrl(), '');
+    return stored && stored.startsWith(prefix) ? stored : null;
+  } catch {
+    return null;
+  }
+}
+
+let scope: string | null = typeof window === 'undefined' ? null : readStoredScope();
+
+/** Point the cache at the signed-in account, or detach it with `null`. */
+export function setOfflineCacheUser(userId: string | null): void {
+  scope = userId ? cacheScopeFor(resolveApiBaseUrl(), userId) : null;
+  try {
+    if (scope) window.localStorage.setItem(SCOPE_STORAGE_KEY, scope);
+    else window.localStorage.removeItem(SCOPE_STORAGE_KEY);
+  } catch {
+    // The scope is re-derived on the next sign-in.
+  }
+}
+
+/** Invoke a cache command; the cache is best effort, so failures read as a miss. */
+async function call<T>(command: string, args: Record<string, unknown> = {}): Promise<T | null> {
+  if (!supportsOfflineCache() || !scope) return null;
+  try {
+    return await invoke<T>(command, { scope, ...args });
+  } catch (err) {
+    console.warn(`[offline-cache] ${command} failed`, err);
+    return null;
+  }
+}
+
+function blobToBase64(blob: Blob): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const reader = new FileReader();
+    reader.onload = () => resolve(String(reader.result).replace(/^data:[^,]*,/, ''));
+    reader.onerror = () => reject(reader.error);
+    reader.readAsDataURL(blob);
+  });
+}
+
+export function isCacheableThumbnail(attachment: Attachment): boolean {
+  return (
+    (attachment.content_type ?? '').toLowerCase().startsWith('image/') &&
+    attachment.size > 0 &&
+    attachment.size <= MAX_CACHED_MEDIA_BYTES &&
+    !attachment.origin_server
+  );
+}
+
+export const offlineCache = {
+  loadUser: () => call<User>('cache_get', { key: 'user' }),
+  saveUser: (user: User) => call('cache_put', { key: 'user', value: user }),
+
+  loadGuilds: () => call<Guild[]>('cache_get', { key: 'guilds' }),
+  saveGuilds: (guilds: Guild[]) => call('cache_put', { key: 'guilds', value: guilds }),
+
+  loadChannels: (guildId: string) => call<Channel[]>('cache_get', { key: `channels:${guildId}` }),
+  saveChannels: (guildId: string, channels: Channel[]) =>
+    call('cache_put', { key: `channels:${guildId}`, value: channels }),
+
+  loadSyncCursor: (guildId: string) => call<string>('cache_get', { key: `sync:${guildId}` }),
+  saveSyncCursor: (guildId: string, cursor: string) =>
+    call('cache_put', { key: `sync:${guildId}`, value: cursor }),
+
+  /** Raw (still encrypted for E2EE DMs) messages, newest first. */
+  async loadMessages(channelId: string): Promise<Message[]> {
+    return (await call<Message[]>('cache_get_messages', { channelId })) ?? [];
+  },
+  /** With `replace` the cached history becomes exactly `messages`. */
+  saveMessages: (channelId: string, messages: Message[], replace = false) =>
+    call('cache_put_messages', { channelId, messages, replace }),
+  saveMessage: (channelId: string, message: Message) =>
+    call('cache_put_messages', { channelId, messages: [message], replace: false }),
+  deleteMessages: (channelId: string, messageIds: string[]) =>
+    call('cache_delete_messages', { channelId, messageIds }),
+
+  /** A cached thumbnail as a data URL, keyed by the attachment's API path. */
+  async loadMedia(url: string): Promise<string | null> {
+    const media = await call<CachedMedia>('cache_get_media', { url });
+    return media ? `data:${media.content_type};base64,${media.data}` : null;
+  },
+  async saveMedia(url: string, fetchUrl: string): Promise<void> {
+    if (!supportsOfflineCache() || !scope) return;
+    try {
+      const response = await fetch(fetchUrl);
+      if (!response.ok) return;
+      const blob = await response.blob();
+      if (blob.size > MAX_CACHED_MEDIA_BYTES) return;
+      await call('cache_put_media', {
+        url,
+        contentType: blob.type || 'application/octet-stream',
+        data: await blobToBase64(blob),
+      });
+    } catch {
+      // Thumbnails are fetched again the next time the message is cached.
+    }
+  },
+
+  /** Forget everything cached for the current account. */
+  clear: () => call('cache_clear'),
+};
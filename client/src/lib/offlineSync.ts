@@ -0,0 +1,76 @@
+import type { Channel, Guild, Message } from '../types';
+import { apiClient } from '../api/client';
+import { getAccessToken } from './authToken';
+import { resolveResourceUrl } from './apiBaseUrl';
+import { isCacheableThumbnail, offlineCache, supportsOfflineCache } from './offlineCache';
+
+/** Response of `GET /guilds/{id}/sync`. */
+export interface GuildSyncResponse {
+  guild: Guild;
+  channels: Channel[];
+  /** New messages per channel, oldest first. */
+  messages: Record<string, Message[]>;
+  /** Channels whose delta was cut short; their cached history has a gap. */
+  truncated: string[];
+  cursor: string | null;
+}
+
+export interface GuildSyncHandlers {
+  /** Called with the messages the client missed, oldest first. */
+  onMessages?: (channelId: string, messages: Message[], truncated: boolean) => void;
+}
+
+/** Keep thumbnails for cached image attachments so they render offline. */
+export function cacheMessageThumbnails(messages: Message[]): void {
+  if (!supportsOfflineCache()) return;
+  for (const message of messages) {
+    for (const attachment of message.attachments ?? []) {
+      if (!isCacheableThumbnail(attachment)) continue;
+      void offlineCache.saveMedia(attachment.url, resolveResourceUrl(attachment.url, getAccessToken()));
+    }
+  }
+}
+
+/** Pull everything a guild gained since the last sync into the offline cache. */
+export async function syncGuild(guildId: string, handlers: GuildSyncHandlers = {}): Promise<void> {
+  const after = await offlineCache.loadSyncCursor(guildId);
+  const { data } = await apiClient.get<GuildSyncResponse>(`/guilds/${guildId}/sync`, {
+    params: after ? { after } : undefined,
+  });
+  const truncated = new Set(data.truncated);
+  await offlineCache.saveChannels(guildId, data.channels);
+  for (const [channelId, messages] of Object.entries(data.messages)) {
+    // The cache stores server page order (newest first).
+    await offlineCache.saveMessages(channelId, [...messages].reverse(), truncated.has(channelId));
+    cacheMessageThumbnails(messages);
+    handlers.onMessages?.(channelId, messages, truncated.has(channelId));
+  }
+  if (data.cursor) {
+    await offlineCache.saveSyncCursor(guildId, data.cursor);
+  }
+}
+
+let syncInFlight: Promise<void> | null = null;
+
+/**
+ * Sync the offline cache for each guild, one at a time. Runs after every
+ * gateway READY so a reconnect fills in whatever was missed while offline.
+ */
+export function syncGuilds(guildIds: string[], handlers: GuildSyncHandlers = {}): Promise<void> {
+  if (!supportsOfflineCache()) return Promise.resolve();
+  if (syncInFlight) return syncInFlight;
+  syncInFlight = (async () => {
+    try {
+      for (const guildId of guildIds) {
+        try {
+          await syncGuild(guildId, handlers);
+        } catch (err) {
+          console.warn(`[offline-cache] sync failed for guild ${guildId}`, err);
+        }
+      }
+    } finally {
+      syncInFlight = null;
+    }
+  })();
+  return syncInFlight;
+}
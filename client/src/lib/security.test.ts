@@ -1,5 +1,10 @@
 import { describe, it, expect } from 'vitest';
-import { isSafeImageDataUrl, isAllowedImageMimeType, sanitizeCustomCss } from './security';
+import {
+  isSafeImageDataUrl,
+  isAllowedImageMimeType,
+  sanitizeCustomCss,
+  sanitizeGuildCss,
+} from './security';
 
 describe('isSafeImageDataUrl', () => {
   it('accepts valid png data URL', () => {
@@ -131,3 +136,33 @@ describe('sanitizeCustomCss', () => {
     expect(result).not.toContain('-moz-binding');
   });
 });
+
+describe('sanitizeGuildCss', () => {
+  const assetPath = '/api/v1/guilds/42/theme/assets/7';
+
+  it('rewrites urls for the guild\'s own assets against the api base', () => {
+    const css = `.banner { background-image: url("${assetPath}"); color: red; }`;
+    const result = sanitizeGuildCss(css, '42', 'https://chat.example.com/api/v1');
+    expect(result).toContain(`url("https://chat.example.com${assetPath}")`);
+    expect(result).toContain('color: red');
+  });
+
+  it('keeps relative asset urls when the api base is relative', () => {
+    const css = `.banner { background: url(${assetPath}) no-repeat; }`;
+    expect(sanitizeGuildCss(css, '42', '/api/v1')).toContain(`url("${assetPath}")`);
+  });
+
+  it('drops urls pointing anywhere else', () => {
+    const css = [
+      '.a { background-image: url(https://evil.com/track.gif); }',
+      '.b { background-image: url(/api/v1/guilds/43/theme/assets/7); }',
+      `.c { background-image: url(${assetPath}?x=1); }`,
+    ].join('\n');
+    expect(sanitizeGuildCss(css, '42', '/api/v1')).toBe('');
+  });
+
+  it('still blocks dangerous values next to an allowed url', () => {
+    const css = `.a { background: url(${assetPath}), expression(alert(1)); }`;
+    expect(sanitizeGuildCss(css, '42', '/api/v1')).toBe('');
+  });
+});
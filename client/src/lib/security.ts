@@ -1,5 +1,6 @@
 const SAFE_IMAGE_DATA_URL_RE = /^data:image\/(?:png|jpe?g|gif|webp);base64,[a-z0-9+/=\s]+$/i;
 const MAX_CUSTOM_CSS_LENGTH = 10 * 1024;
+const MAX_GUILD_CSS_LENGTH = 20 * 1024;
 
 const ALLOWED_CSS_PROPERTIES = new Set([
   'background',
@@ -49,7 +50,22 @@ const BLOCKED_VALUE_PATTERNS = [
   /-moz-binding/i,
 ];
 
-function sanitizeDeclarations(block: string): string {
+// Guild themes may reference their own uploaded assets and nothing else.
+const GUILD_CSS_EXTRA_PROPERTIES = new Set([
+  'background-image',
+  'background-position',
+  'background-repeat',
+  'background-size',
+]);
+const CSS_URL_RE = /url\(\s*(['"]?)([^'")]*)\1\s*\)/gi;
+
+type ValueRewriter = (value: string) => string | null;
+
+function sanitizeDeclarations(
+  block: string,
+  extraProperties?: Set<string>,
+  rewriteValue?: ValueRewriter,
+): string {
   const safe: string[] = [];
   const declarations = block.split(';');
   for (const declaration of declarations) {
@@ -58,9 +74,13 @@ function sanitizeDeclarations(block: string): string {
     const prop = declaration.slice(0, idx).trim().toLowerCase();
     const value = declaration.slice(idx + 1).trim();
     if (!prop || !value) continue;
-    if (!ALLOWED_CSS_PROPERTIES.has(prop)) continue;
-    if (BLOCKED_VALUE_PATTERNS.some((pattern) => pattern.test(value))) continue;
-    safe.push(`${prop}: ${value}`);
+    if (!ALLOWED_CSS_PROPERTIES.has(prop) && !extraProperties?.has(prop)) continue;
+    const rewritten = rewriteValue ? rewriteValue(value) : value;
+    if (rewritten === null) continue;
+    // Rewritten values carry only vetted url() tokens, so check what remains around them.
+    const checked = rewriteValue ? rewritten.replace(CSS_URL_RE, '') : rewritten;
+    if (BLOCKED_VALUE_PATTERNS.some((pattern) => pattern.test(checked))) continue;
+    safe.push(`${prop}: ${rewritten}`);
   }
   return safe.join('; ');
 }
@@ -80,13 +100,12 @@ export function isSafeImageDataUrl(value: string): boolean {
   return SAFE_IMAGE_DATA_URL_RE.test(value.trim());
 }
 
-export function sanitizeCustomCss(value: string): string {
-  const source = value.trim();
-  if (!source) return '';
-  if (source.length > MAX_CUSTOM_CSS_LENGTH) {
-    return '';
-  }
-
+function sanitizeStylesheet(
+  source: string,
+  maxLength: number,
+  extraProperties?: Set<string>,
+  rewriteValue?: ValueRewriter,
+): string {
   // Strip all at-rules to block @import/@font-face and similar fetch-based exfiltration vectors.
   const withoutAtRules = source.replace(/@[^{;]+(?:;|\{[^}]*\})/g, '');
   const sanitizedRules: string[] = [];
@@ -95,10 +114,47 @@ export function sanitizeCustomCss(value: string): string {
   while ((match = ruleRegex.exec(withoutAtRules)) !== null) {
     const selector = match[1].trim();
     if (!selector) continue;
-    const declarations = sanitizeDeclarations(match[2]);
+    const declarations = sanitizeDeclarations(match[2], extraProperties, rewriteValue);
     if (!declarations) continue;
     sanitizedRules.push(`${selector} { ${declarations}; }`);
   }
 
-  return sanitizedRules.join('\n').slice(0, MAX_CUSTOM_CSS_LENGTH);
+  return sanitizedRules.join('\n').slice(0, maxLength);
+}
+
+export function sanitizeCustomCss(value: string): string {
+  const source = value.trim();
+  if (!source) return '';
+  if (source.length > MAX_CUSTOM_CSS_LENGTH) {
+    return '';
+  }
+  return sanitizeStylesheet(source, MAX_CUSTOM_CSS_LENGTH);
+}
+
+/**
+ * Sanitize a guild theme stylesheet. `url()` is only kept when it points at
+ * one of the guild's own theme assets, and is rewritten against `apiBase` so
+ * it resolves when the client talks to a remote server.
+ */
+export function sanitizeGuildCss(value: string, guildId: string, apiBase: string): string {
+  const source = value.trim();
+  if (!source) return '';
+  if (source.length > MAX_GUILD_CSS_LENGTH) {
+    return '';
+  }
+  const assetPrefix = `/api/v1/guilds/${guildId}/theme/assets/`;
+  const origin = apiBase.replace(/\/+$/, '').replace(/\/api\/v1$/, '');
+  const rewriteUrls: ValueRewriter = (declValue) => {
+    let rejected = false;
+    const rewritten = declValue.replace(CSS_URL_RE, (_match, _quote, target: string) => {
+      const path = target.trim();
+      if (!path.startsWith(assetPrefix) || !/^[0-9]+$/.test(path.slice(assetPrefix.length))) {
+        rejected = true;
+        return '';
+      }
+      return `url("${origin}${path}")`;
+    });
+    return rejected ? null : rewritten;
+  };
+  return sanitizeStylesheet(source, MAX_GUILD_CSS_LENGTH, GUILD_CSS_EXTRA_PROPERTIES, rewriteUrls);
 }
@@ -4,6 +4,51 @@ import { logVoiceDiagnostic } from './desktopDiagnostics';
 
 let activeBridge: SystemAudioBridge | null = null;
 
+export type CapturePermission = 'granted' | 'denied' | 'not_required';
+
+/** Result of the desktop `system_audio_capture_capabilities` command. */
+export interface SystemAudioCaptureCapabilities {
+  supported: boolean;
+  backend: 'process_loopback' | 'pulse_monitor' | 'screencapturekit' | null;
+  /** Whether our own voice playback is kept out of the captured mix. */
+  excludes_own_audio: boolean;
+  /** Whether capture can be limited to a single application. */
+  application_audio: boolean;
+  permission: CapturePermission;
+}
+
+export interface CaptureApplication {
+  id: string;
+  name: string;
+}
+
+export async function getSystemAudioCaptureCapabilities(): Promise<SystemAudioCaptureCapabilities | null> {
+  if (!isTauri()) return null;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke<SystemAudioCaptureCapabilities>('system_audio_capture_capabilities');
+  } catch {
+    return null;
+  }
+}
+
+/** Show the OS capture permission prompt (Screen Recording on macOS). */
+export async function requestSystemAudioCapturePermission(): Promise<CapturePermission> {
+  if (!isTauri()) return 'not_required';
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<CapturePermission>('request_system_audio_capture_permission');
+}
+
+export async function listSystemAudioApplications(): Promise<CaptureApplication[]> {
+  if (!isTauri()) return [];
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke<CaptureApplication[]>('list_system_audio_applications');
+  } catch {
+    return [];
+  }
+}
+
 function errorMessage(err: unknown): string {
   if (err instanceof Error) return err.message;
   return String(err);
@@ -13,7 +58,8 @@ function isCaptureAlreadyRunningError(err: unknown): boolean {
   return errorMessage(err).toLowerCase().includes('already running');
 }
 
-export async function startNativeSystemAudio(): Promise<MediaStreamTrack | null> {
+/** `application` limits capture to one app where supported (macOS bundle id). */
+export async function startNativeSystemAudio(application?: string): Promise<MediaStreamTrack | null> {
   if (!isTauri()) return null;
 
   let bridge: SystemAudioBridge | null = null;
@@ -44,14 +90,14 @@ export async function startNativeSystemAudio(): Promise<MediaStreamTrack | null>
     };
 
     try {
-      await invoke('start_system_audio_capture', { onAudio: channel });
+      await invoke('start_system_audio_capture', { onAudio: channel, application });
     } catch (startErr) {
       // One retry path for "already running" races from overlapping stop/start.
       if (!isCaptureAlreadyRunningError(startErr)) {
         throw startErr;
       }
       await invoke('stop_system_audio_capture').catch(() => {});
-      await invoke('start_system_audio_capture', { onAudio: channel });
+      await invoke('start_system_audio_capture', { onAudio: channel, application });
     }
 
     activeBridge = bridge;
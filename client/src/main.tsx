@@ -5,8 +5,13 @@ import App from './App';
 import './styles/globals.css';
 import { AppProviders } from './lib/AppProviders';
 import { ErrorBoundary } from './components/ErrorBoundary';
-import { getDesktopDiagnosticsLogPath, logVoiceDiagnostic } from './lib/desktopDiagnostics';
+import {
+  getDesktopDiagnosticsLogPath,
+  installClientErrorReporting,
+  logVoiceDiagnostic,
+} from './lib/desktopDiagnostics';
 import { isTauri } from './lib/tauriEnv';
+import { VoiceOverlay } from './pages/VoiceOverlay';
 
 // In Tauri, assets are embedded in the exe. The PWA service worker caches stale
 // assets in WebView2 storage that override the exe's embedded files, preventing
@@ -26,6 +31,7 @@ if (isTauri()) {
   });
   document.addEventListener('dragover', (e) => e.preventDefault());
   document.addEventListener('drop', (e) => e.preventDefault());
+  installClientErrorReporting();
 }
 
 logVoiceDiagnostic('[desktop] frontend main.tsx boot');
@@ -35,14 +41,22 @@ void getDesktopDiagnosticsLogPath().then((path) => {
   }
 });
 
+// The desktop voice overlay window loads /overlay and must not start its own
+// session, gateway connection, or voice stack.
+const isOverlayWindow = isTauri() && window.location.pathname === '/overlay';
+
 ReactDOM.createRoot(document.getElementById('root')!).render(
   <React.StrictMode>
     <ErrorBoundary>
-      <BrowserRouter>
-        <AppProviders>
-          <App />
-        </AppProviders>
-      </BrowserRouter>
+      {isOverlayWindow ? (
+        <VoiceOverlay />
+      ) : (
+        <BrowserRouter>
+          <AppProviders>
+            <App />
+          </AppProviders>
+        </BrowserRouter>
+      )}
     </ErrorBoundary>
   </React.StrictMode>
 );
@@ -2,6 +2,8 @@ import { useState, useEffect } from 'react';
 import { useNavigate } from 'react-router-dom';
 import { ArrowLeft, Users, Server, Settings, BarChart3, Shield, ShieldOff, Trash2, Pencil, HardDrive, Download, Plus, Loader2, RotateCcw } from 'lucide-react';
 import { adminApi } from '../api/admin';
+import { brandingApi } from '../api/branding';
+import { resolveResourceUrl } from '../lib/apiBaseUrl';
 import { extractApiError } from '../api/client';
 import { toast } from '../stores/toastStore';
 import { useAuthStore } from '../stores/authStore';
@@ -597,6 +599,8 @@ function SettingsPanel() {
   const [settings, setSettings] = useState<Record<string, string>>({});
   const [saving, setSaving] = useState(false);
   const [saved, setSaved] = useState(false);
+  const [logoUrl, setLogoUrl] = useState<string | null>(null);
+  const [logoBusy, setLogoBusy] = useState(false);
 
   useEffect(() => {
     adminApi
@@ -605,8 +609,39 @@ function SettingsPanel() {
       .catch((err) => {
         toast.error(`Failed to load settings: ${extractApiError(err)}`);
       });
+    brandingApi
+      .get()
+      .then(({ data }) => setLogoUrl(data.logo_url))
+      .catch(() => {
+        // The logo preview is optional.
+      });
   }, []);
 
+  const handleLogoUpload = async (file: File | undefined) => {
+    if (!file) return;
+    setLogoBusy(true);
+    try {
+      const { data } = await adminApi.uploadBrandingLogo(file);
+      setLogoUrl(data.logo_url);
+    } catch (err) {
+      toast.error(`Failed to upload logo: ${extractApiError(err)}`);
+    } finally {
+      setLogoBusy(false);
+    }
+  };
+
+  const handleLogoRemove = async () => {
+    setLogoBusy(true);
+    try {
+      await adminApi.deleteBrandingLogo();
+      setLogoUrl(null);
+    } catch (err) {
+      toast.error(`Failed to remove logo: ${extractApiError(err)}`);
+    } finally {
+      setLogoBusy(false);
+    }
+  };
+
   const handleSave = async () => {
     setSaving(true);
     try {
@@ -657,6 +692,86 @@ function SettingsPanel() {
           />
         </div>
 
+        {/* ── Branding ──────────────────────────────────────── */}
+        <div className="border-t border-border-subtle pt-6">
+          <h3 className="mb-4 text-sm font-semibold uppercase tracking-wide text-text-secondary">
+            Branding
+          </h3>
+        </div>
+
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Logo
+          </label>
+          <div className="flex items-center gap-4">
+            {logoUrl ? (
+              <img
+                src={resolveResourceUrl(logoUrl)}
+                alt="Server logo"
+                className="h-14 w-14 rounded-xl border border-border-subtle object-contain"
+              />
+            ) : (
+              <div className="flex h-14 w-14 items-center justify-center rounded-xl border border-dashed border-border-subtle text-xs text-text-muted">
+                None
+              </div>
+            )}
+            <label className={`btn-primary cursor-pointer ${logoBusy ? 'pointer-events-none opacity-60' : ''}`}>
+              {logoBusy ? 'Uploading...' : 'Upload'}
+              <input
+                type="file"
+                accept="image/png,image/jpeg,image/gif,image/webp"
+                className="hidden"
+                onChange={(e) => {
+                  const file = e.target.files?.[0];
+                  e.target.value = '';
+                  void handleLogoUpload(file);
+                }}
+              />
+            </label>
+            {logoUrl && (
+              <button
+                onClick={() => void handleLogoRemove()}
+                disabled={logoBusy}
+                className="text-sm font-medium text-accent-danger hover:underline"
+              >
+                Remove
+              </button>
+            )}
+          </div>
+          <p className="mt-1 text-xs text-text-muted">
+            Shown on the login screen. PNG, JPEG, GIF or WebP.
+          </p>
+        </div>
+
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Accent Color
+          </label>
+          <input
+            type="text"
+            value={settings.branding_accent_color || ''}
+            onChange={(e) => update('branding_accent_color', e.target.value)}
+            placeholder="#eb4d4b"
+            className="input-field"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            Hex color used on the login screen. Leave empty for the default.
+          </p>
+        </div>
+
+        <div>
+          <label className="mb-3 block text-sm font-medium text-text-secondary">
+            Login Text
+          </label>
+          <textarea
+            value={settings.branding_login_text || ''}
+            onChange={(e) => update('branding_login_text', e.target.value)}
+            rows={3}
+            maxLength={1000}
+            className="input-field resize-none"
+          />
+        </div>
+
         {/* Registration Toggle */}
         <div className="card-surface flex items-center justify-between rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
           <div>
@@ -681,6 +796,30 @@ function SettingsPanel() {
           </button>
         </div>
 
+        {/* DM friendship requirement */}
+        <div className="card-surface flex items-center justify-between rounded-xl border border-border-subtle bg-bg-mod-subtle/70 px-6 py-6">
+          <div>
+            <p className="font-medium text-text-primary">Require Friendship for DMs</p>
+            <p className="text-sm text-text-muted">Block DMs between non-friends instead of sending them to message requests</p>
+          </div>
+          <button
+            onClick={() =>
+              update('dm_require_friendship', settings.dm_require_friendship === 'true' ? 'false' : 'true')
+            }
+            className={`relative h-7 w-12 rounded-full transition-colors ${
+              settings.dm_require_friendship === 'true'
+                ? 'bg-accent-success'
+                : 'bg-bg-mod-strong'
+            }`}
+          >
+            <div
+              className={`absolute top-0.5 h-6 w-6 rounded-full bg-white shadow transition-transform ${
+                settings.dm_require_friendship === 'true' ? 'translate-x-5' : 'translate-x-0.5'
+              }`}
+            />
+          </button>
+        </div>
+
         {/* Max guilds per user */}
         <div>
           <label className="mb-3 block text-sm font-medium text-text-secondary">
@@ -21,6 +21,7 @@ import { useVoice } from '../hooks/useVoice';
 import { useStream } from '../hooks/useStream';
 import { useWebcamTiles } from '../hooks/useWebcamTiles';
 import { useScreenShareSubscriptions } from '../hooks/useScreenShareSubscriptions';
+import { useGuildTheme } from '../hooks/useGuildTheme';
 import { useVoiceStore } from '../stores/voiceStore';
 import { useAuthStore } from '../stores/authStore';
 import { SearchPanel } from '../components/message/SearchPanel';
@@ -45,6 +46,7 @@ export function GuildPage() {
   );
   const selectChannel = useChannelStore((s) => s.selectChannel);
   const channel = channels.find(c => c.id === channelId);
+  useGuildTheme(guildId);
   const {
     connected: voiceConnected,
     joining: voiceJoining,
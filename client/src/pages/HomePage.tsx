@@ -322,7 +322,7 @@ export function HomePage() {
                   const iconSrc = guild.icon_hash
                     ? guild.icon_hash.startsWith('data:')
                       ? (isSafeImageDataUrl(guild.icon_hash) ? guild.icon_hash : null)
-                      : `/api/v1/guilds/${guild.id}/icon`
+                      : `/api/v1/guilds/${guild.id}/icon?v=${guild.icon_hash}`
                     : null;
                   return (
                     <button
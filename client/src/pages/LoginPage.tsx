@@ -1,4 +1,4 @@
-import { useEffect, useState } from 'react';
+import { useEffect, useState, type CSSProperties } from 'react';
 import { Link, useNavigate } from 'react-router-dom';
 import { useAuthStore } from '../stores/authStore';
 import { useAccountStore } from '../stores/accountStore';
@@ -8,9 +8,12 @@ import {
   getCurrentOriginServerUrl,
   setStoredServerUrl,
   clearStoredServerUrl,
+  resolveResourceUrl,
 } from '../lib/apiBaseUrl';
 import { hasAccount } from '../lib/account';
 import { authApi } from '../api/auth';
+import { brandingApi } from '../api/branding';
+import type { InstanceBranding } from '../types';
 
 type LoginIdentifierMode = {
   allowUsernameInput: boolean;
@@ -33,6 +36,16 @@ export function resolveLoginIdentifierMode(
   };
 }
 
+function brandingAccentStyle(branding: InstanceBranding | null): CSSProperties | undefined {
+  const accent = branding?.accent_color;
+  if (!accent || !/^#[0-9a-f]{6}$/i.test(accent)) return undefined;
+  return {
+    '--color-accent-primary': accent,
+    '--accent-primary': accent,
+    '--accent': accent,
+  } as CSSProperties;
+}
+
 export function LoginPage() {
   const [identifier, setIdentifier] = useState('');
   const [password, setPassword] = useState('');
@@ -42,6 +55,7 @@ export function LoginPage() {
   const [requireEmail, setRequireEmail] = useState(false);
   const [failedAttempts, setFailedAttempts] = useState(0);
   const [cooldownUntil, setCooldownUntil] = useState(0);
+  const [branding, setBranding] = useState<InstanceBranding | null>(null);
   const navigate = useNavigate();
   const login = useAuthStore((s) => s.login);
   const serverUrl = getStoredServerUrl() || getCurrentOriginServerUrl();
@@ -58,6 +72,14 @@ export function LoginPage() {
       .catch(() => {
         // Keep conservative defaults when options are unavailable.
       });
+    brandingApi
+      .get()
+      .then(({ data }) => {
+        if (!cancelled) setBranding(data);
+      })
+      .catch(() => {
+        // Fall back to the stock login screen.
+      });
     return () => {
       cancelled = true;
     };
@@ -133,11 +155,23 @@ export function LoginPage() {
   const identifierMode = resolveLoginIdentifierMode(allowUsernameLogin, requireEmail);
 
   return (
-    <div className="auth-shell">
+    <div className="auth-shell" style={brandingAccentStyle(branding)}>
       <form onSubmit={handleSubmit} className="auth-card mx-auto w-full max-w-md space-y-8 p-10">
         <div className="text-center">
-          <h1 className="text-3xl font-bold leading-tight text-text-primary">Welcome back</h1>
+          {branding?.logo_url && (
+            <img
+              src={resolveResourceUrl(branding.logo_url)}
+              alt={branding.server_name}
+              className="mx-auto mb-5 h-16 w-16 rounded-2xl object-contain"
+            />
+          )}
+          <h1 className="text-3xl font-bold leading-tight text-text-primary">
+            {branding?.server_name ? `Welcome to ${branding.server_name}` : 'Welcome back'}
+          </h1>
           <p className="mt-3 text-sm text-text-muted">Sign in to continue to your servers.</p>
+          {branding?.login_text && (
+            <p className="mt-3 whitespace-pre-line text-sm text-text-secondary">{branding.login_text}</p>
+          )}
         </div>
 
         {error && (
@@ -0,0 +1,80 @@
+import { useEffect, useState } from 'react';
+import { MicOff, HeadphoneOff } from 'lucide-react';
+import { getAvatarInitials, getDefaultAvatarColor } from '../lib/avatars';
+import {
+  getOverlayState,
+  onOverlaySpeaking,
+  onOverlayState,
+  type OverlaySnapshot,
+} from '../lib/desktopOverlay';
+
+/**
+ * Always-on-top voice overlay rendered in its own transparent desktop window.
+ * Speaking state comes straight from the native session's speaker detector;
+ * the main window only pushes the roster.
+ */
+export function VoiceOverlay() {
+  const [snapshot, setSnapshot] = useState<OverlaySnapshot | null>(null);
+  const [speaking, setSpeaking] = useState<Set<string>>(new Set());
+
+  useEffect(() => {
+    document.documentElement.style.background = 'transparent';
+    document.body.style.background = 'transparent';
+    void getOverlayState().then((state) => {
+      if (state) setSnapshot(state);
+    });
+    const stopState = onOverlayState(setSnapshot);
+    const stopSpeaking = onOverlaySpeaking((speakers) => setSpeaking(new Set(Object.keys(speakers))));
+    return () => {
+      stopState();
+      stopSpeaking();
+    };
+  }, []);
+
+  const participants = snapshot?.participants ?? [];
+  const alignRight = snapshot?.config.corner.endsWith('right') ?? false;
+  const alignBottom = snapshot?.config.corner.startsWith('bottom') ?? false;
+
+  return (
+    <div
+      className={`flex h-screen w-screen select-none flex-col gap-1 p-2 ${alignRight ? 'items-end' : 'items-start'} ${alignBottom ? 'justify-end' : 'justify-start'}`}
+    >
+      {snapshot?.channel_name && (
+        <div className="rounded-md bg-black/60 px-2 py-0.5 text-[11px] font-semibold text-white/80">
+          {snapshot.channel_name}
+        </div>
+      )}
+      {participants.map((participant) => {
+        const isSpeaking = speaking.has(participant.user_id) && !participant.muted;
+        return (
+          <div
+            key={participant.user_id}
+            className="flex max-w-full items-center gap-2 rounded-full bg-black/60 py-0.5 pl-0.5 pr-3"
+          >
+            <div
+              className="flex h-7 w-7 flex-shrink-0 items-center justify-center overflow-hidden rounded-full text-xs font-semibold text-white"
+              style={{
+                backgroundColor: getDefaultAvatarColor(participant.user_id),
+                boxShadow: isSpeaking ? '0 0 0 2px var(--accent-success)' : 'none',
+              }}
+            >
+              {participant.avatar_url ? (
+                <img src={participant.avatar_url} alt="" className="h-full w-full object-cover" />
+              ) : (
+                getAvatarInitials(participant.username)
+              )}
+            </div>
+            <span className={`truncate text-sm ${isSpeaking ? 'font-semibold text-white' : 'text-white/75'}`}>
+              {participant.username}
+            </span>
+            {participant.deafened ? (
+              <HeadphoneOff size={14} className="flex-shrink-0 text-red-400" />
+            ) : (
+              participant.muted && <MicOff size={14} className="flex-shrink-0 text-red-400" />
+            )}
+          </div>
+        );
+      })}
+    </div>
+  );
+}
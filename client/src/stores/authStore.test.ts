@@ -256,6 +256,58 @@ describe('authStore', () => {
       expect(state.settings).toEqual(updatedSettings);
       expect(state.hasFetchedSettings).toBe(true);
     });
+
+    it('sends only changed fields with the appearance version and keybind deltas', async () => {
+      useAuthStore.setState({
+        settings: {
+          ...fakeSettings,
+          keybinds: { mute: 'Ctrl+M', deafen: 'Ctrl+D' },
+          versions: { appearance: 3, notifications: 1, keybinds: 2 },
+        },
+      });
+      mockAuthApi.updateSettings.mockResolvedValue({ data: fakeSettings });
+
+      await useAuthStore.getState().updateSettings({
+        theme: 'light',
+        locale: 'en',
+        keybinds: { mute: 'Ctrl+Shift+M' },
+      });
+      expect(mockAuthApi.updateSettings).toHaveBeenCalledWith({
+        theme: 'light',
+        versions: { appearance: 3 },
+        keybinds_patch: { mute: 'Ctrl+Shift+M', deafen: null },
+      });
+    });
+
+    it('refetches settings on a version conflict', async () => {
+      useAuthStore.setState({ settings: fakeSettings });
+      mockAuthApi.updateSettings.mockRejectedValue({ response: { status: 409 } });
+      mockAuthApi.getSettings.mockResolvedValue({ data: { ...fakeSettings, theme: 'amoled' } });
+
+      await expect(useAuthStore.getState().updateSettings({ theme: 'light' })).rejects.toBeDefined();
+      expect(useAuthStore.getState().settings?.theme).toBe('amoled');
+    });
+  });
+
+  describe('applyRemoteSettings', () => {
+    it('ignores documents older than the local copy', () => {
+      const local = { ...fakeSettings, versions: { appearance: 2, notifications: 0, keybinds: 0 } };
+      useAuthStore.setState({ settings: local });
+
+      useAuthStore.getState().applyRemoteSettings({
+        ...fakeSettings,
+        theme: 'light',
+        versions: { appearance: 1, notifications: 0, keybinds: 0 },
+      });
+      expect(useAuthStore.getState().settings?.theme).toBe('dark');
+
+      useAuthStore.getState().applyRemoteSettings({
+        ...fakeSettings,
+        theme: 'light',
+        versions: { appearance: 3, notifications: 0, keybinds: 0 },
+      });
+      expect(useAuthStore.getState().settings?.theme).toBe('light');
+    });
   });
 
   describe('initializeSession', () => {
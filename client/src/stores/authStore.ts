@@ -1,8 +1,9 @@
 import { create } from 'zustand';
-import type { User, UserSettings } from '../types';
+import type { User, UserSettings, UserSettingsUpdate } from '../types';
 import { authApi } from '../api/auth';
 import { extractApiError } from '../api/client';
 import { clearLegacyPersistedAuth, getRefreshToken, setAccessToken, setRefreshToken } from '../lib/authToken';
+import { isNetworkError, offlineCache, setOfflineCacheUser } from '../lib/offlineCache';
 import { toast } from './toastStore';
 
 interface AuthState {
@@ -11,6 +12,8 @@ interface AuthState {
   settings: UserSettings | null;
   hasFetchedSettings: boolean;
   sessionBootstrapComplete: boolean;
+  /** Server unreachable at startup; showing the cached profile and history. */
+  offline: boolean;
   isLoading: boolean;
   error: string | null;
 
@@ -22,7 +25,13 @@ interface AuthState {
   fetchUser: () => Promise<void>;
   updateUser: (data: Partial<User>) => Promise<void>;
   fetchSettings: () => Promise<void>;
-  updateSettings: (data: Partial<UserSettings>) => Promise<void>;
+  /**
+   * Resolves with the device recovery code when this update turned device
+   * approval on; the code is shown once and never kept in the store.
+   */
+  updateSettings: (data: Partial<UserSettings>) => Promise<string | null>;
+  /** Apply a settings document pushed by another session, if it is not older than ours. */
+  applyRemoteSettings: (settings: UserSettings) => void;
   clearError: () => void;
 }
 
@@ -35,15 +44,74 @@ function clearAuthState(set: (partial: Partial<AuthState>) => void): void {
     user: null,
     settings: null,
     hasFetchedSettings: false,
+    offline: false,
   });
 }
 
-export const useAuthStore = create<AuthState>()((set) => ({
+const APPEARANCE_KEYS = ['theme', 'locale', 'message_display_compact', 'custom_css'] as const;
+
+/** Top-level changes from `prev` to `next`; removed keys map to `null`. */
+function diffSettingsObject(
+  prev: Record<string, unknown> | undefined,
+  next: Record<string, unknown>,
+): Record<string, unknown> {
+  const patch: Record<string, unknown> = {};
+  const base = prev ?? {};
+  for (const [key, value] of Object.entries(next)) {
+    if (JSON.stringify(base[key]) !== JSON.stringify(value)) patch[key] = value;
+  }
+  for (const key of Object.keys(base)) {
+    if (!(key in next)) patch[key] = null;
+  }
+  return patch;
+}
+
+/**
+ * Turn a full settings form into a minimal update: only changed appearance
+ * fields (guarded by their version), and notification/keybind deltas that
+ * merge on the server instead of overwriting edits from other devices.
+ */
+export function buildSettingsUpdate(
+  current: UserSettings | null,
+  data: Partial<UserSettings>,
+): UserSettingsUpdate {
+  if (!current) return data;
+  const { notifications, keybinds, ...rest } = data;
+  const update: UserSettingsUpdate = { ...rest };
+  delete update.versions;
+  for (const key of APPEARANCE_KEYS) {
+    if (key in update && update[key] === current[key]) delete update[key];
+  }
+  if (APPEARANCE_KEYS.some((key) => key in update) && current.versions) {
+    update.versions = { appearance: current.versions.appearance };
+  }
+  if (notifications) {
+    const patch = diffSettingsObject(current.notifications, notifications);
+    if (Object.keys(patch).length > 0) update.notifications_patch = patch;
+  }
+  if (keybinds) {
+    const patch = diffSettingsObject(current.keybinds, keybinds);
+    if (Object.keys(patch).length > 0) update.keybinds_patch = patch;
+  }
+  return update;
+}
+
+function isNotOlder(local: UserSettings, incoming: UserSettings): boolean {
+  if (!local.versions || !incoming.versions) return true;
+  return (
+    incoming.versions.appearance >= local.versions.appearance
+    && incoming.versions.notifications >= local.versions.notifications
+    && incoming.versions.keybinds >= local.versions.keybinds
+  );
+}
+
+export const useAuthStore = create<AuthState>()((set, get) => ({
   token: null,
   user: null,
   settings: null,
   hasFetchedSettings: false,
   sessionBootstrapComplete: false,
+  offline: false,
   isLoading: false,
   error: null,
 
@@ -96,10 +164,18 @@ export const useAuthStore = create<AuthState>()((set) => ({
       const { data } = await authApi.refresh(refreshToken || undefined);
       setAccessToken(data.token);
       if (data.refresh_token) setRefreshToken(data.refresh_token);
-      set({ token: data.token, sessionBootstrapComplete: true });
-    } catch {
+      set({ token: data.token, sessionBootstrapComplete: true, offline: false });
+    } catch (err) {
       setAccessToken(null);
-      set({ token: null, sessionBootstrapComplete: true });
+      // Keep a desktop session with a saved credential usable while offline;
+      // the refresh is retried once connectivity returns.
+      const cachedUser =
+        isNetworkError(err) && getRefreshToken() ? await offlineCache.loadUser() : null;
+      if (cachedUser) {
+        set({ token: null, user: cachedUser, sessionBootstrapComplete: true, offline: true });
+        return;
+      }
+      set({ token: null, sessionBootstrapComplete: true, offline: false });
     }
   },
 
@@ -114,6 +190,8 @@ export const useAuthStore = create<AuthState>()((set) => ({
     } catch {
       // Best effort: local session should always clear.
     }
+    await offlineCache.clear();
+    setOfflineCacheUser(null);
     clearAuthState(set);
   },
 
@@ -141,8 +219,32 @@ export const useAuthStore = create<AuthState>()((set) => ({
   },
 
   updateSettings: async (settingsData) => {
-    const { data } = await authApi.updateSettings(settingsData);
-    set({ settings: data, hasFetchedSettings: true });
+    try {
+      const { data } = await authApi.updateSettings(buildSettingsUpdate(get().settings, settingsData));
+      const { device_recovery_code: recoveryCode, ...settings } = data;
+      set({ settings, hasFetchedSettings: true });
+      return recoveryCode ?? null;
+    } catch (err) {
+      // Another device won the race; pick up its changes before the user retries.
+      if ((err as { response?: { status?: number } }).response?.status === 409) {
+        await get().fetchSettings();
+      }
+      throw err;
+    }
+  },
+
+  applyRemoteSettings: (incoming) => {
+    const local = get().settings;
+    if (local && !isNotOlder(local, incoming)) return;
+    set({
+      settings: {
+        ...incoming,
+        // Status fields are session-local and not part of the stored document.
+        status: local?.status ?? incoming.status,
+        custom_status: local?.custom_status ?? incoming.custom_status,
+      },
+      hasFetchedSettings: true,
+    });
   },
 
   clearError: () => set({ error: null }),
@@ -5,6 +5,7 @@ import { guildApi } from '../api/guilds';
 import { channelApi } from '../api/channels';
 import { extractApiError } from '../api/client';
 import { toast } from './toastStore';
+import { isNetworkError, offlineCache } from '../lib/offlineCache';
 
 function normalizeChannel(channel: Channel): Channel {
   return {
@@ -76,6 +77,19 @@ export const useChannelStore = create<ChannelState>()((set, get) => ({
     const controller = new AbortController();
     _channelFetchControllers.set(guildId, controller);
 
+    let hydratedFromCache = false;
+    if (!get().channelsByGuild[guildId]) {
+      const cached = await offlineCache.loadChannels(guildId);
+      if (cached?.length && !get().channelsByGuild[guildId]) {
+        const sorted = cached.map(normalizeChannel).sort((a, b) => a.position - b.position);
+        set((state) => ({
+          channelsByGuild: { ...state.channelsByGuild, [guildId]: sorted },
+          channels: state.selectedGuildId === guildId ? sorted : state.channels,
+        }));
+        hydratedFromCache = true;
+      }
+    }
+
     let lastErr: unknown;
     for (let attempt = 0; attempt <= MAX_FETCH_RETRIES; attempt++) {
       if (controller.signal.aborted) {
@@ -97,6 +111,7 @@ export const useChannelStore = create<ChannelState>()((set, get) => ({
           signal: controller.signal,
         });
         const sorted = data.map(normalizeChannel).sort((a, b) => a.position - b.position);
+        void offlineCache.saveChannels(guildId, sorted);
         set((state) => {
           const channelsByGuild = { ...state.channelsByGuild, [guildId]: sorted };
           const channels = state.selectedGuildId === guildId ? sorted : state.channels;
@@ -116,7 +131,9 @@ export const useChannelStore = create<ChannelState>()((set, get) => ({
       }
     }
     set({ isLoading: false });
-    toast.error(`Failed to load channels: ${extractApiError(lastErr)}`);
+    if (!(hydratedFromCache && isNetworkError(lastErr))) {
+      toast.error(`Failed to load channels: ${extractApiError(lastErr)}`);
+    }
     _fetchInFlight.delete(guildId);
     _channelFetchControllers.delete(guildId);
   },
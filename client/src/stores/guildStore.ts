@@ -4,6 +4,7 @@ import { guildApi } from '../api/guilds';
 import { extractApiError } from '../api/client';
 import { toast } from './toastStore';
 import { resolveApiBaseUrl } from '../lib/apiBaseUrl';
+import { isNetworkError, offlineCache } from '../lib/offlineCache';
 
 interface GuildState {
   guilds: Guild[];
@@ -22,21 +23,32 @@ interface GuildState {
   updateGuildData: (id: string, data: Partial<Guild>) => void;
 }
 
-export const useGuildStore = create<GuildState>()((set, _get) => ({
+export const useGuildStore = create<GuildState>()((set, get) => ({
   guilds: [],
   selectedGuildId: null,
   isLoading: false,
 
   fetchGuilds: async () => {
     set({ isLoading: true });
+    let hydratedFromCache = false;
+    if (get().guilds.length === 0) {
+      const cached = await offlineCache.loadGuilds();
+      if (cached?.length && get().guilds.length === 0) {
+        set({ guilds: cached });
+        hydratedFromCache = true;
+      }
+    }
     try {
       const serverUrl = resolveApiBaseUrl();
       const { data } = await guildApi.getAll();
       const stamped = data.map((g) => ({ ...g, server_url: g.server_url || serverUrl }));
       set({ guilds: stamped, isLoading: false });
+      void offlineCache.saveGuilds(stamped);
     } catch (err) {
       set({ isLoading: false });
-      toast.error(`Failed to load servers: ${extractApiError(err)}`);
+      if (!(hydratedFromCache && isNetworkError(err))) {
+        toast.error(`Failed to load servers: ${extractApiError(err)}`);
+      }
     }
   },
 
@@ -24,8 +24,22 @@ const mockChannelApi = vi.hoisted(() => ({
   removeReaction: vi.fn(),
 }));
 
+const mockOfflineCache = vi.hoisted(() => ({
+  loadMessages: vi.fn(async () => [] as unknown[]),
+  saveMessages: vi.fn(),
+  saveMessage: vi.fn(),
+  deleteMessages: vi.fn(),
+}));
+
 vi.mock('./toastStore', () => ({ toast: mockToast }));
 
+vi.mock('../lib/offlineCache', () => ({
+  offlineCache: mockOfflineCache,
+  isNetworkError: (err: unknown) => err instanceof Error && err.message === 'Network Error',
+}));
+
+vi.mock('../lib/offlineSync', () => ({ cacheMessageThumbnails: vi.fn() }));
+
 vi.mock('./pollStore', () => ({
   usePollStore: {
     getState: () => ({
@@ -174,6 +188,27 @@ describe('messageStore', () => {
       expect(useMessageStore.getState().loading['ch1']).toBe(false);
     });
 
+    it('renders cached history and stays quiet when offline', async () => {
+      mockOfflineCache.loadMessages.mockResolvedValueOnce([
+        makeMessage({ id: 'm2', content: 'Cached newer' }),
+        makeMessage({ id: 'm1', content: 'Cached older' }),
+      ]);
+      mockApiClient.get.mockRejectedValue(new Error('Network Error'));
+
+      await useMessageStore.getState().fetchMessages('ch1');
+      const messages = useMessageStore.getState().messages['ch1'];
+      expect(messages.map((m) => m.id)).toEqual(['m1', 'm2']);
+      expect(mockToast.error).not.toHaveBeenCalled();
+    });
+
+    it('replaces the cached page after a successful fetch', async () => {
+      const msgs = [makeMessage({ id: 'm2' }), makeMessage({ id: 'm1' })];
+      mockApiClient.get.mockResolvedValue({ data: msgs });
+
+      await useMessageStore.getState().fetchMessages('ch1');
+      expect(mockOfflineCache.saveMessages).toHaveBeenCalledWith('ch1', msgs, true);
+    });
+
     it('prepends messages when params.before is specified', async () => {
       useMessageStore.setState({
         messages: { ch1: [makeMessage({ id: 'm3', content: 'Current' })] },
@@ -12,6 +12,8 @@ import { apiClient, extractApiError } from '../api/client';
 import { DEFAULT_MESSAGE_FETCH_LIMIT } from '../lib/constants';
 import { decryptDmMessage, encryptDmMessageV2 } from '../lib/dmE2ee';
 import { hasUnlockedPrivateKey, withUnlockedPrivateKey } from '../lib/accountSession';
+import { isNetworkError, offlineCache } from '../lib/offlineCache';
+import { cacheMessageThumbnails } from '../lib/offlineSync';
 import { useChannelStore } from './channelStore';
 import { toast } from './toastStore';
 import { usePollStore } from './pollStore';
@@ -190,6 +192,7 @@ interface MessageState {
   // Reaction gateway event handlers
   handleReactionAdd: (channelId: string, messageId: string, emoji: string, userId: string, currentUserId: string) => void;
   handleReactionRemove: (channelId: string, messageId: string, emoji: string, userId: string, currentUserId: string) => void;
+  handleReactionBulk: (channelId: string, messageId: string, reactions: Array<{ emoji: string; count: number }>) => void;
 
   // Pin state update
   updatePinState: (channelId: string, messageId: string, pinned: boolean) => void;
@@ -224,6 +227,20 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
     const controller = new AbortController();
     _messageFetchControllers.set(channelId, controller);
 
+    // Render cached history immediately; the network page replaces it.
+    let hydratedFromCache = false;
+    if (!params?.before && !get().messages[channelId]) {
+      const cached = await offlineCache.loadMessages(channelId);
+      if (cached.length > 0 && !get().messages[channelId]) {
+        const decrypted = await decryptMessagesForChannel(channelId, cached);
+        set((state) => ({
+          messages: { ...state.messages, [channelId]: [...decrypted].reverse() },
+          hasMore: { ...state.hasMore, [channelId]: true },
+        }));
+        hydratedFromCache = true;
+      }
+    }
+
     const MAX_RETRIES = 2;
     const RETRY_DELAY = 300;
     const REQUEST_TIMEOUT = 5_000;
@@ -247,6 +264,9 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
           const decrypted = await decryptMessagesForChannel(channelId, data);
           if (!params?.before) {
             usePollStore.getState().clearPollsForChannel(channelId);
+            // Cache the raw page so E2EE content stays encrypted at rest.
+            void offlineCache.saveMessages(channelId, data, true);
+            cacheMessageThumbnails(data);
           }
           for (const message of decrypted) {
             if (message.poll) {
@@ -273,7 +293,9 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
           lastErr = err;
         }
       }
-      toast.error(`Failed to load messages: ${extractApiError(lastErr)}`);
+      if (!(hydratedFromCache && isNetworkError(lastErr))) {
+        toast.error(`Failed to load messages: ${extractApiError(lastErr)}`);
+      }
     } finally {
       set((state) => ({ loading: { ...state.loading, [channelId]: false } }));
       _messageFetchControllers.delete(channelId);
@@ -317,6 +339,7 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
 
   deleteMessage: async (channelId, messageId) => {
     await channelApi.deleteMessage(channelId, messageId);
+    void offlineCache.deleteMessages(channelId, [messageId]);
     set((state) => {
       const existing = state.messages[channelId] || [];
       return {
@@ -526,6 +549,27 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
       };
     }),
 
+  // Coalesced burst: the server sends current counts, our own `me` flags stay as they are.
+  handleReactionBulk: (channelId, messageId, counts) =>
+    set((state) => {
+      const existing = state.messages[channelId] || [];
+      return {
+        messages: {
+          ...state.messages,
+          [channelId]: existing.map((m) => {
+            if (m.id !== messageId) return m;
+            const previous = (m.reactions || []) as Array<{ emoji: string; count: number; me: boolean }>;
+            const reactions = counts.map((r) => ({
+              emoji: r.emoji,
+              count: r.count,
+              me: previous.find((p) => p.emoji === r.emoji)?.me ?? false,
+            }));
+            return { ...m, reactions };
+          }),
+        },
+      };
+    }),
+
   // Pin state update
   updatePinState: (channelId, messageId, pinned) =>
     set((state) => {
@@ -542,6 +586,8 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
 
   // Gateway event handlers
   addMessage: (channelId, message) => {
+    void offlineCache.saveMessage(channelId, message);
+    cacheMessageThumbnails([message]);
     const isE2ee = Boolean(message.e2ee);
     const baseMessage = {
       ...message,
@@ -581,6 +627,7 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
   },
 
   updateMessage: (channelId, message) => {
+    void offlineCache.saveMessage(channelId, message);
     const isE2ee = Boolean(message.e2ee);
     const baseMessage = {
       ...message,
@@ -620,7 +667,8 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
     }
   },
 
-  removeMessage: (channelId, messageId) =>
+  removeMessage: (channelId, messageId) => {
+    void offlineCache.deleteMessages(channelId, [messageId]);
     set((state) => {
       const existing = state.messages[channelId] || [];
       return {
@@ -629,9 +677,11 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
           [channelId]: existing.filter((m) => m.id !== messageId),
         },
       };
-    }),
+    });
+  },
 
-  removeMessages: (channelId, messageIds) =>
+  removeMessages: (channelId, messageIds) => {
+    void offlineCache.deleteMessages(channelId, messageIds);
     set((state) => {
       const existing = state.messages[channelId] || [];
       const idSet = new Set(messageIds);
@@ -641,5 +691,6 @@ export const useMessageStore = create<MessageState>()((set, get) => ({
           [channelId]: existing.filter((m) => !idSet.has(m.id)),
         },
       };
-    }),
+    });
+  },
 }));
@@ -0,0 +1,142 @@
+import { create } from 'zustand';
+import type { User } from '../types';
+import { gateway, LOCAL_SERVER_ID } from '../gateway/manager';
+import { getCurrentOriginServerUrl, getStoredServerUrl, setStoredServerUrl } from '../lib/apiBaseUrl';
+import { getRefreshToken, onRefreshTokenChange, setAccessToken, setRefreshToken } from '../lib/authToken';
+import { backgroundAccounts } from '../lib/backgroundAccounts';
+import {
+  getAccountCredential,
+  listSavedAccounts,
+  markAccountActive,
+  removeSavedAccount,
+  saveAccount,
+  setAccountCredential,
+  supportsSavedAccounts,
+  type SavedAccount,
+} from '../lib/desktopAccounts';
+import { useAuthStore } from './authStore';
+
+interface SavedAccountState {
+  accounts: SavedAccount[];
+  activeAccountId: string | null;
+  /** Unseen DMs and mentions per non-active account. */
+  badges: Record<string, number>;
+  switching: boolean;
+
+  load: () => Promise<void>;
+  /** Remember the signed-in user as the active saved account. */
+  rememberActive: (user: User) => Promise<void>;
+  /** Swap the UI session to another saved account without restarting. */
+  switchTo: (accountId: string) => Promise<void>;
+  /** Put the current session in the background so another account can sign in. */
+  startAddAccount: () => void;
+  remove: (accountId: string) => Promise<void>;
+  addBadge: (accountId: string, count: number) => void;
+}
+
+export const useSavedAccountStore = create<SavedAccountState>()((set, get) => ({
+  accounts: [],
+  activeAccountId: null,
+  badges: {},
+  switching: false,
+
+  load: async () => {
+    if (!supportsSavedAccounts()) return;
+    const list = await listSavedAccounts();
+    set({ accounts: list.accounts, activeAccountId: list.active_account_id });
+    backgroundAccounts.sync(list.accounts, list.active_account_id);
+  },
+
+  rememberActive: async (user) => {
+    if (!supportsSavedAccounts()) return;
+    const serverUrl = getStoredServerUrl() ?? getCurrentOriginServerUrl();
+    if (!serverUrl) return;
+    const saved = await saveAccount(
+      {
+        server_url: serverUrl,
+        user_id: user.id,
+        username: user.username,
+        display_name: user.display_name ?? null,
+        avatar_hash: user.avatar_hash ?? null,
+      },
+      getRefreshToken() ?? '',
+      true,
+    );
+    set({ activeAccountId: saved.id });
+    await get().load();
+  },
+
+  switchTo: async (accountId) => {
+    const { activeAccountId, accounts, switching } = get();
+    if (switching || accountId === activeAccountId) return;
+    const target = accounts.find((account) => account.id === accountId);
+    if (!target) return;
+    set({ switching: true });
+    try {
+      // Let a background refresh finish rotating the credential before reading it.
+      await backgroundAccounts.stop(accountId);
+      const refreshToken = await getAccountCredential(accountId);
+      if (!refreshToken) throw new Error(`Sign in to ${target.username} again to use this account.`);
+      await markAccountActive(accountId);
+
+      gateway.disconnectServer(LOCAL_SERVER_ID);
+      set({ activeAccountId: accountId });
+      setStoredServerUrl(target.server_url);
+      setAccessToken(null);
+      setRefreshToken(refreshToken);
+      useAuthStore.setState({ token: null, user: null, settings: null, hasFetchedSettings: false });
+      await useAuthStore.getState().initializeSession();
+      if (!useAuthStore.getState().token) {
+        throw new Error(`Sign in to ${target.username} again to use this account.`);
+      }
+      set((state) => {
+        const badges = { ...state.badges };
+        delete badges[accountId];
+        return { badges };
+      });
+    } finally {
+      set({ switching: false });
+      await get().load();
+    }
+  },
+
+  startAddAccount: () => {
+    gateway.disconnectServer(LOCAL_SERVER_ID);
+    // Local only: the saved account keeps its credential and moves to the background.
+    set({ activeAccountId: null });
+    setAccessToken(null);
+    setRefreshToken(null);
+    useAuthStore.setState({ token: null, user: null, settings: null, hasFetchedSettings: false });
+    backgroundAccounts.sync(get().accounts, null);
+  },
+
+  remove: async (accountId) => {
+    if (!supportsSavedAccounts()) return;
+    await backgroundAccounts.stop(accountId);
+    const list = await removeSavedAccount(accountId);
+    set((state) => {
+      const badges = { ...state.badges };
+      delete badges[accountId];
+      return { accounts: list.accounts, activeAccountId: list.active_account_id, badges };
+    });
+    backgroundAccounts.sync(list.accounts, list.active_account_id);
+  },
+
+  addBadge: (accountId, count) =>
+    set((state) => ({
+      badges: { ...state.badges, [accountId]: (state.badges[accountId] ?? 0) + count },
+    })),
+}));
+
+backgroundAccounts.setActivityListener((accountId, count) => {
+  useSavedAccountStore.getState().addBadge(accountId, count);
+});
+
+// Keep the active account's stored credential in step with token rotation.
+onRefreshTokenChange((token) => {
+  const { activeAccountId } = useSavedAccountStore.getState();
+  if (!token || !activeAccountId || !supportsSavedAccounts()) return;
+  void setAccountCredential(activeAccountId, token).catch(() => {
+    // The next successful refresh stores it again.
+  });
+});
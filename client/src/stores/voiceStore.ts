@@ -24,14 +24,21 @@ import {
 } from 'livekit-client';
 import { useAuthStore } from './authStore';
 import { useServerListStore } from './serverListStore';
+import { toast } from './toastStore';
 import { playVoiceJoinSound, playVoiceLeaveSound } from '../lib/voiceSounds';
 import { startNativeSystemAudio, stopNativeSystemAudio } from '../lib/systemAudioCapture';
 import { isTauri } from '../lib/tauriEnv';
 import { getStoredServerUrl, resolveApiBaseUrl } from '../lib/apiBaseUrl';
 import { NoiseGateProcessor } from '../lib/noiseGate';
 import { logVoiceDiagnostic } from '../lib/desktopDiagnostics';
-import type { MediaEngine } from '../lib/media/mediaEngine';
+import type { DeviceFallback, MediaEngine, ScreenShareConfig } from '../lib/media/mediaEngine';
 import { createMediaEngine } from '../lib/media/mediaEngine';
+
+function notifyDeviceFallback({ kind, current }: DeviceFallback): void {
+  const device = kind === 'input' ? 'Microphone' : 'Audio output';
+  toast.warning(`${device} disconnected — switched to ${current ?? 'the system default'}`);
+}
+
 /** Direct stderr logging that bypasses the async diagnostics buffer. */
 function voiceTimingLog(msg: string): void {
   try {
@@ -2303,7 +2310,14 @@ interface VoiceStoreState {
   leaveChannel: () => Promise<void>;
   toggleMute: () => Promise<void>;
   toggleDeaf: () => Promise<void>;
-  startStream: (qualityPreset?: string) => Promise<void>;
+  /**
+   * `captureOptions` picks the source and cursor mode for native (PipeWire)
+   * capture and, where supported, a single application to take audio from.
+   */
+  startStream: (
+    qualityPreset?: string,
+    captureOptions?: Pick<ScreenShareConfig, 'source' | 'cursor' | 'audioApplication'>
+  ) => Promise<void>;
   stopStream: () => void;
   toggleVideo: () => void;
   applyAudioInputDevice: (deviceId: string | null) => Promise<void>;
@@ -2557,6 +2571,7 @@ export const useVoiceStore = create<VoiceStoreState>()((set, get) => ({
           engine.onSpeakingChange((speakers) => {
             set({ speakingUsers: new Set(speakers.keys()) });
           });
+          engine.onDeviceFallback?.(notifyDeviceFallback);
 
           // Try each candidate endpoint in order (LAN first, then public).
           let connected = false;
@@ -2612,6 +2627,7 @@ export const useVoiceStore = create<VoiceStoreState>()((set, get) => ({
               engine.onSpeakingChange((speakers) => {
                 set({ speakingUsers: new Set(speakers.keys()) });
               });
+              engine.onDeviceFallback?.(notifyDeviceFallback);
             }
           }
           if (!connected) {
@@ -3323,7 +3339,7 @@ export const useVoiceStore = create<VoiceStoreState>()((set, get) => ({
     }
   },
 
-  startStream: async (qualityPreset = '1080p60') => {
+  startStream: async (qualityPreset = '1080p60', captureOptions) => {
     const { channelId, room, mediaEngine } = get();
 
     // Native media path: use MediaEngine screen share instead of LiveKit
@@ -3349,6 +3365,9 @@ export const useVoiceStore = create<VoiceStoreState>()((set, get) => ({
           maxFrameRate: capture.frameRate,
           maxWidth: capture.width,
           maxHeight: capture.height,
+          source: captureOptions?.source,
+          cursor: captureOptions?.cursor,
+          audioApplication: captureOptions?.audioApplication,
         });
         const nativeStreamAudioActive = mediaEngine.isScreenShareAudioActive();
         const nativeStreamAudioWarning = nativeStreamAudioActive
@@ -3538,7 +3557,7 @@ export const useVoiceStore = create<VoiceStoreState>()((set, get) => ({
 
       const publishNativeSystemAudio = async (): Promise<boolean> => {
         for (let attempt = 1; attempt <= 2; attempt += 1) {
-          const nativeAudioTrack = await startNativeSystemAudio();
+          const nativeAudioTrack = await startNativeSystemAudio(captureOptions?.audioApplication);
           if (!nativeAudioTrack) {
             if (attempt < 2) {
               await delay(250);
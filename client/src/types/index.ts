@@ -25,10 +25,29 @@ export interface UserSettings {
   status: 'online' | 'idle' | 'dnd' | 'invisible';
   custom_status?: string;
   crypto_auth_enabled: boolean;
+  device_approval_required?: boolean;
   notifications?: Record<string, unknown>;
   keybinds?: Record<string, unknown>;
+  presence_visibility?: PresenceVisibility;
+  presence_hidden_guild_ids?: string[];
+  versions?: UserSettingsVersions;
 }
 
+export interface UserSettingsVersions {
+  appearance: number;
+  notifications: number;
+  keybinds: number;
+}
+
+/** PATCH body for settings: full values, per-scope delta patches, and the versions they were based on. */
+export interface UserSettingsUpdate extends Partial<Omit<UserSettings, 'versions'>> {
+  notifications_patch?: Record<string, unknown>;
+  keybinds_patch?: Record<string, unknown>;
+  versions?: Partial<UserSettingsVersions>;
+}
+
+export type PresenceVisibility = 'everyone' | 'friends' | 'nobody';
+
 export interface HubSettings {
   description?: string;
   banner_hash?: string;
@@ -98,14 +117,34 @@ export interface Channel {
   message_count?: number | null;
   applied_tags?: string[] | null;
   default_sort_order?: number | null;
+  /** 0 = open, 1 = read-only (POST_OVERRIDE), 2 = webhooks and bots only. */
+  posting_mode?: number;
   created_at: string;
-  recipient?: {
-    id: string;
-    username: string;
-    discriminator: string | number;
-    avatar_hash?: string | null;
-    public_key?: string | null;
-  };
+  is_message_request?: boolean;
+  recipient?: DmRecipient;
+  /** Group DMs only. */
+  icon_hash?: string | null;
+  recipients?: DmRecipient[];
+}
+
+export interface DmRecipient {
+  id: string;
+  username: string;
+  discriminator: string | number;
+  avatar_hash?: string | null;
+  public_key?: string | null;
+}
+
+export interface GroupDmPrekeyBundle {
+  user_id: string;
+  bundle: Record<string, unknown> | null;
+}
+
+export interface GroupDmSenderKey {
+  sender_id: string;
+  epoch: number;
+  ciphertext: string;
+  header?: string | null;
 }
 
 export enum MessageType {
@@ -153,6 +192,10 @@ export interface Message {
   poll?: Poll;
   referenced_message?: Message;
   embeds?: MessageEmbed[];
+  /** Set on gateway dispatches when the author is blocked by the current user. */
+  author_blocked?: boolean;
+  /** Set on gateway dispatches for DMs still waiting in the message request inbox. */
+  message_request?: boolean;
 }
 
 export interface MessageAuthor {
@@ -286,6 +329,33 @@ export interface GuildEmoji {
   created_at: string;
 }
 
+export interface InstanceBranding {
+  server_name: string;
+  server_description: string;
+  accent_color: string | null;
+  login_text: string | null;
+  logo_url: string | null;
+}
+
+export interface GuildThemeAsset {
+  id: string;
+  guild_id: string;
+  name: string;
+  content_type: string;
+  size: number;
+  uploader_id: string | null;
+  url: string;
+  created_at: string;
+}
+
+export interface GuildTheme {
+  guild_id: string;
+  accent_color: string | null;
+  custom_css: string | null;
+  assets: GuildThemeAsset[];
+  updated_at: string | null;
+}
+
 export interface VoiceState {
   user_id: string;
   channel_id?: string;
@@ -307,6 +377,7 @@ export interface Presence {
   guild_id?: string;
   status: 'online' | 'idle' | 'dnd' | 'offline';
   activities: Activity[];
+  client_status?: Partial<Record<'desktop' | 'web' | 'mobile', 'online' | 'idle' | 'dnd'>>;
 }
 
 export interface Activity {
@@ -385,6 +456,13 @@ export const Permissions = {
   MANAGE_ROLES: 1n << 28n,
   MANAGE_WEBHOOKS: 1n << 29n,
   MANAGE_EMOJIS: 1n << 30n,
+  RECORD_VOICE: 1n << 31n,
+  POST_OVERRIDE: 1n << 32n,
+  MANAGE_THREADS: 1n << 33n,
+  MODERATE_MEMBERS: 1n << 34n,
+  USE_SOUNDBOARD: 1n << 35n,
+  MANAGE_INVITES: 1n << 36n,
+  VIEW_REPORTS: 1n << 37n,
 } as const;
 
 export function hasPermission(permissions: bigint, flag: bigint): boolean {
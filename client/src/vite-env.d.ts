@@ -1 +1,4 @@
 /// <reference types="vite/client" />
+
+/** Client version from package.json, sent to servers that gate old desktop builds. */
+declare const __APP_VERSION__: string;
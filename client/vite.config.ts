@@ -44,6 +44,9 @@ export default defineConfig(({ mode }) => {
         },
       }),
     ],
+    define: {
+      __APP_VERSION__: JSON.stringify(process.env.npm_package_version ?? "0.0.0"),
+    },
     clearScreen: false,
     server: {
       port: 1420,
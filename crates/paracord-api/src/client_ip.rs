@@ -0,0 +1,284 @@
+//! Client address resolution for requests that may arrive through reverse
+//! proxies.
+//!
+//! Forwarding headers are only honored when `PARACORD_TRUST_PROXY` is enabled
+//! and the connecting peer falls inside `PARACORD_TRUSTED_PROXY_IPS`, a comma
+//! separated list of addresses or CIDR ranges (`10.0.0.5, 172.16.0.0/12,
+//! fd00::/8`). The standard `Forwarded` header (RFC 7239) takes precedence
+//! over `X-Forwarded-For`. The chain is walked from the nearest hop outwards,
+//! skipping trusted proxies, so a client cannot pick its own address by
+//! prepending entries.
+
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+tokio::task_local! {
+    static REQUEST_CLIENT_IP: Option<IpAddr>;
+}
+
+/// Client address resolved for the request being handled, if any.
+pub fn request_client_ip() -> Option<IpAddr> {
+    REQUEST_CLIENT_IP.try_with(|ip| *ip).ok().flatten()
+}
+
+/// Run `fut` with `ip` as the request's client address.
+pub async fn with_request_client_ip<F: std::future::Future>(
+    ip: Option<IpAddr>,
+    fut: F,
+) -> F::Output {
+    REQUEST_CLIENT_IP.scope(ip, fut).await
+}
+
+/// A single address or CIDR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        let (addr, prefix_len) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.trim().parse::<u8>().ok()?)),
+            None => (raw, None),
+        };
+        let network = addr.trim().parse::<IpAddr>().ok()?.to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return None;
+        }
+        Some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes = usize::from(prefix_len / 8);
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+    let rest_bits = prefix_len % 8;
+    if rest_bits == 0 {
+        return true;
+    }
+    let mask = 0xffu8 << (8 - rest_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+/// Proxies whose forwarding headers are believed.
+#[derive(Debug, Default)]
+pub struct TrustedProxies {
+    ranges: Vec<IpRange>,
+}
+
+impl TrustedProxies {
+    /// Parse a comma separated list of addresses and CIDR ranges, skipping
+    /// entries that don't parse.
+    pub fn parse(raw: &str) -> Self {
+        let ranges = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(IpRange::parse)
+            .collect();
+        Self { ranges }
+    }
+
+    /// Trusted proxies from `PARACORD_TRUSTED_PROXY_IPS`, or none unless
+    /// `PARACORD_TRUST_PROXY` is enabled.
+    pub fn from_env() -> Self {
+        let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
+            .ok()
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        if !trust_proxy {
+            return Self::default();
+        }
+        std::env::var("PARACORD_TRUSTED_PROXY_IPS")
+            .map(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+}
+
+/// Whether forwarding headers from `peer` should be believed.
+pub fn peer_is_trusted_proxy(peer: Option<IpAddr>) -> bool {
+    peer.is_some_and(|peer| TrustedProxies::from_env().contains(peer))
+}
+
+/// Resolve the client address of a request received from `peer`.
+pub fn resolve(headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+    resolve_with(&TrustedProxies::from_env(), headers, peer)
+}
+
+fn resolve_with(
+    trusted: &TrustedProxies,
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+) -> Option<IpAddr> {
+    let peer = peer.map(|ip| ip.to_canonical())?;
+    if !trusted.contains(peer) {
+        return Some(peer);
+    }
+    let hops = forwarded_for(headers).or_else(|| x_forwarded_for(headers));
+    let Some(hops) = hops else {
+        return Some(peer);
+    };
+
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        // An obfuscated or unknown hop hides everything beyond it.
+        let Some(hop) = hop else {
+            break;
+        };
+        client = hop;
+        if !trusted.contains(hop) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+/// `for=` addresses from `Forwarded`, nearest hop last. `None` entries are
+/// hops that didn't disclose a usable address.
+fn forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let hops: Vec<Option<IpAddr>> = headers
+        .get_all("forwarded")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                name.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value.trim().trim_matches('"')))
+            })
+        })
+        .collect();
+    (!hops.is_empty()).then_some(hops)
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let hops: Vec<Option<IpAddr>> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(parse_node)
+        .collect();
+    (!hops.is_empty()).then_some(hops)
+}
+
+/// Parse an RFC 7239 node (`192.0.2.43`, `192.0.2.43:47011`,
+/// `[2001:db8::1]:4711`). `unknown` and obfuscated identifiers yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip.to_canonical());
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip().to_canonical());
+    }
+    node.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().expect("valid ip")
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn trusted_proxies_match_addresses_and_cidr_ranges() {
+        let trusted =
+            TrustedProxies::parse("10.0.0.5, 172.16.0.0/12, fd00::/8, bogus, 10.0.0.0/33");
+        assert!(trusted.contains(ip("10.0.0.5")));
+        assert!(!trusted.contains(ip("10.0.0.6")));
+        assert!(trusted.contains(ip("172.31.255.1")));
+        assert!(!trusted.contains(ip("172.32.0.1")));
+        assert!(trusted.contains(ip("fd12:3456::1")));
+        assert!(trusted.contains(ip("::ffff:172.20.0.1")));
+        assert_eq!(trusted.ranges.len(), 3);
+    }
+
+    #[test]
+    fn forwarded_header_takes_precedence_and_skips_trusted_hops() {
+        let trusted = TrustedProxies::parse("10.0.0.0/8");
+        let headers = headers(&[
+            (
+                "forwarded",
+                "for=198.51.100.7, for=\"[2001:db8:cafe::17]:4711\";proto=https, for=10.1.2.3",
+            ),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+        assert_eq!(
+            resolve_with(&trusted, &headers, Some(ip("10.0.0.5"))),
+            Some(ip("2001:db8:cafe::17"))
+        );
+    }
+
+    #[test]
+    fn spoofed_leading_entries_are_ignored() {
+        let trusted = TrustedProxies::parse("10.0.0.5");
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.7")]);
+        assert_eq!(
+            resolve_with(&trusted, &headers, Some(ip("10.0.0.5"))),
+            Some(ip("198.51.100.7"))
+        );
+    }
+
+    #[test]
+    fn forwarding_headers_from_untrusted_peers_are_ignored() {
+        let trusted = TrustedProxies::parse("10.0.0.5");
+        let headers = headers(&[("forwarded", "for=198.51.100.7")]);
+        assert_eq!(
+            resolve_with(&trusted, &headers, Some(ip("192.0.2.1"))),
+            Some(ip("192.0.2.1"))
+        );
+        assert_eq!(resolve_with(&trusted, &headers, None), None);
+    }
+
+    #[test]
+    fn obfuscated_hops_stop_the_walk() {
+        let trusted = TrustedProxies::parse("10.0.0.5");
+        let headers = headers(&[("forwarded", "for=198.51.100.7, for=_hidden")]);
+        assert_eq!(
+            resolve_with(&trusted, &headers, Some(ip("10.0.0.5"))),
+            Some(ip("10.0.0.5"))
+        );
+    }
+}
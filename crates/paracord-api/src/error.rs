@@ -3,6 +3,8 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use paracord_models::error_code::ErrorCode;
+use paracord_util::i18n;
 use serde_json::{json, Value};
 use thiserror::Error;
 
@@ -22,22 +24,65 @@ pub enum ApiError {
     RateLimited,
     #[error("service unavailable: {0}")]
     ServiceUnavailable(String),
+    /// The calling desktop client is older than the server's minimum version.
+    #[error("client outdated: update to version {minimum_version} or later")]
+    ClientOutdated { minimum_version: String },
     #[error("internal server error")]
     Internal(#[from] anyhow::Error),
+    /// A per-guild limit from the runtime settings was reached.
+    #[error("{}", .code.message())]
+    LimitReached { code: ErrorCode, limit: u32 },
+    /// A catalogued error more specific than the variants above, such as
+    /// `UnknownChannel` or `MissingPermissions`.
+    #[error("{}", .0.message())]
+    Code(ErrorCode),
 }
 
 impl ApiError {
-    /// Machine-readable error code string.
-    fn error_code(&self) -> &'static str {
+    /// Catalogue entry reported to clients as `code` / `error_code`.
+    pub fn error_code(&self) -> ErrorCode {
         match self {
-            ApiError::NotFound => "NOT_FOUND",
-            ApiError::Unauthorized => "UNAUTHORIZED",
-            ApiError::Forbidden => "FORBIDDEN",
-            ApiError::BadRequest(_) => "BAD_REQUEST",
-            ApiError::Conflict(_) => "CONFLICT",
-            ApiError::RateLimited => "RATE_LIMITED",
-            ApiError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
-            ApiError::Internal(_) => "INTERNAL_ERROR",
+            ApiError::NotFound => ErrorCode::NotFound,
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+            ApiError::Forbidden => ErrorCode::Forbidden,
+            ApiError::BadRequest(_) => ErrorCode::BadRequest,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::RateLimited => ErrorCode::RateLimited,
+            ApiError::ServiceUnavailable(_) => ErrorCode::ServiceUnavailable,
+            ApiError::ClientOutdated { .. } => ErrorCode::ClientOutdated,
+            ApiError::Internal(_) => ErrorCode::InternalError,
+            ApiError::LimitReached { code, .. } | ApiError::Code(code) => *code,
+        }
+    }
+
+    /// User-facing message in `locale`. English matches the `Display` text.
+    fn localized_message(&self, locale: &str) -> String {
+        let with_reason = |template: &str, reason: &str| {
+            i18n::translate_args(
+                locale,
+                template,
+                &[("reason", i18n::translate(locale, reason).as_ref())],
+            )
+        };
+        match self {
+            ApiError::NotFound => i18n::translate(locale, "not found").into_owned(),
+            ApiError::Unauthorized => i18n::translate(locale, "unauthorized").into_owned(),
+            ApiError::Forbidden => i18n::translate(locale, "forbidden").into_owned(),
+            ApiError::BadRequest(reason) => with_reason("bad request: {reason}", reason),
+            ApiError::Conflict(reason) => with_reason("conflict: {reason}", reason),
+            ApiError::RateLimited => i18n::translate(locale, "rate limited").into_owned(),
+            ApiError::ServiceUnavailable(reason) => {
+                with_reason("service unavailable: {reason}", reason)
+            }
+            ApiError::ClientOutdated { minimum_version } => i18n::translate_args(
+                locale,
+                "client outdated: update to version {version} or later",
+                &[("version", minimum_version)],
+            ),
+            ApiError::Internal(_) => i18n::translate(locale, "internal server error").into_owned(),
+            ApiError::LimitReached { code, .. } | ApiError::Code(code) => {
+                i18n::translate(locale, code.message()).into_owned()
+            }
         }
     }
 
@@ -50,7 +95,24 @@ impl ApiError {
             ApiError::Conflict(_) => StatusCode::CONFLICT,
             ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
             ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::ClientOutdated { .. } => StatusCode::UPGRADE_REQUIRED,
             ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::LimitReached { code, .. } | ApiError::Code(code) => {
+                StatusCode::from_u16(code.http_status())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    /// Structured data clients can act on without parsing the message.
+    fn details(&self) -> Value {
+        match self {
+            ApiError::ClientOutdated { minimum_version } => json!({
+                "minimum_version": minimum_version,
+                "update_manifest_url": "/api/v1/client/update-manifest",
+            }),
+            ApiError::LimitReached { limit, .. } => json!({ "limit": limit }),
+            _ => Value::Null,
         }
     }
 }
@@ -60,20 +122,19 @@ impl IntoResponse for ApiError {
         let status = self.status_code();
         let code = self.error_code();
 
-        let message = match &self {
-            ApiError::Internal(err) => {
-                tracing::error!("API internal error: {err:#}");
-                "internal server error".to_string()
-            }
-            other => other.to_string(),
-        };
+        if let ApiError::Internal(err) = &self {
+            tracing::error!("API internal error: {err:#}");
+        }
+        let message = self.localized_message(crate::middleware::request_locale());
 
         let body = json!({
-            "code": code,
+            // Clients branch on the stable name; the number is for bots.
+            "code": code.as_str(),
+            "error_code": code.code(),
             "message": message,
             // Keep legacy "error" field for backwards compatibility
             "error": message,
-            "details": Value::Null,
+            "details": self.details(),
         });
 
         (status, Json(body)).into_response()
@@ -85,15 +146,24 @@ impl From<paracord_core::error::CoreError> for ApiError {
         match e {
             paracord_core::error::CoreError::NotFound => ApiError::NotFound,
             paracord_core::error::CoreError::Forbidden => ApiError::Forbidden,
-            paracord_core::error::CoreError::MissingPermission => ApiError::Forbidden,
+            paracord_core::error::CoreError::MissingPermission => {
+                ApiError::Code(ErrorCode::MissingPermissions)
+            }
             paracord_core::error::CoreError::BadRequest(msg) => ApiError::BadRequest(msg),
             paracord_core::error::CoreError::Conflict(msg) => ApiError::Conflict(msg),
+            paracord_core::error::CoreError::LimitReached { limit, max } => {
+                ApiError::LimitReached {
+                    code: limit.error_code(),
+                    limit: max,
+                }
+            }
             paracord_core::error::CoreError::Database(_) => {
                 ApiError::Internal(anyhow::anyhow!("database error"))
             }
             paracord_core::error::CoreError::Internal(msg) => {
                 ApiError::Internal(anyhow::anyhow!(msg))
             }
+            paracord_core::error::CoreError::Snowflake(err) => err.into(),
         }
     }
 }
@@ -103,6 +173,88 @@ impl From<paracord_db::DbError> for ApiError {
         match e {
             paracord_db::DbError::NotFound => ApiError::NotFound,
             paracord_db::DbError::Sqlx(_) => ApiError::Internal(anyhow::anyhow!("database error")),
+            paracord_db::DbError::Snowflake(err) => err.into(),
+        }
+    }
+}
+
+/// IDs can't be issued while the clock is behind the last one handed out;
+/// that passes once the clock catches up, so it's a 503 rather than a 500.
+impl From<paracord_util::snowflake::SnowflakeError> for ApiError {
+    fn from(e: paracord_util::snowflake::SnowflakeError) -> Self {
+        tracing::error!(error = %e, "snowflake generation failed");
+        ApiError::ServiceUnavailable("ID generation is temporarily unavailable".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_messages_match_display() {
+        let errors = [
+            ApiError::NotFound,
+            ApiError::Forbidden,
+            ApiError::BadRequest("File too large".into()),
+            ApiError::Conflict("Asset name already in use".into()),
+            ApiError::ServiceUnavailable("federation is disabled".into()),
+            ApiError::ClientOutdated {
+                minimum_version: "1.4.0".into(),
+            },
+            ApiError::Code(ErrorCode::UnknownChannel),
+            ApiError::LimitReached {
+                code: ErrorCode::MaxGuildRoles,
+                limit: 250,
+            },
+        ];
+        for err in errors {
+            assert_eq!(err.localized_message("en"), err.to_string());
+        }
+    }
+
+    #[test]
+    fn messages_are_translated_with_their_reason() {
+        assert_eq!(
+            ApiError::BadRequest("File too large".into()).localized_message("de"),
+            "Ungültige Anfrage: Die Datei ist zu groß"
+        );
+        assert_eq!(
+            ApiError::BadRequest("Image must be under 512 KB".into()).localized_message("es"),
+            "solicitud no válida: La imagen debe pesar menos de 512 KB"
+        );
+        assert_eq!(
+            ApiError::Internal(anyhow::anyhow!("boom")).localized_message("fr"),
+            "erreur interne du serveur"
+        );
+    }
+
+    #[test]
+    fn error_codes_are_unique_and_match_status() {
+        let mut codes = std::collections::HashSet::new();
+        let mut names = std::collections::HashSet::new();
+        for &code in ErrorCode::ALL {
+            assert!(codes.insert(code.code()), "duplicate {}", code.code());
+            assert!(names.insert(code.as_str()), "duplicate {}", code.as_str());
+            assert!(StatusCode::from_u16(code.http_status()).is_ok());
         }
+        assert_eq!(
+            ApiError::from(paracord_core::error::CoreError::MissingPermission)
+                .error_code()
+                .code(),
+            50013
+        );
+        assert_eq!(ApiError::Forbidden.error_code().as_str(), "FORBIDDEN");
+    }
+
+    #[test]
+    fn clock_skew_is_a_temporary_outage() {
+        let skew = paracord_util::snowflake::SnowflakeError::ClockMovedBackwards(5_000);
+        let err = ApiError::from(paracord_core::error::CoreError::from(skew));
+        assert_eq!(err.error_code(), ErrorCode::ServiceUnavailable);
+        assert_eq!(
+            err.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
     }
 }
@@ -0,0 +1,212 @@
+//! OpenGraph tags for invite links.
+//!
+//! Other chat apps unfurl a shared `/invite/{code}` link from the page's
+//! meta tags, but the web UI's `index.html` has none. When the server also
+//! serves the web UI, [`middleware`] adds tags describing the invite's
+//! guild to that page.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use paracord_core::AppState;
+
+/// Larger pages are passed through untouched.
+const MAX_PAGE_BYTES: usize = 1024 * 1024;
+const MAX_DESCRIPTION_CHARS: usize = 200;
+
+struct InvitePreview {
+    guild_name: String,
+    description: Option<String>,
+    member_count: i64,
+    url: String,
+}
+
+fn invite_code(path: &str) -> Option<&str> {
+    let code = path.strip_prefix("/invite/")?.trim_end_matches('/');
+    let valid = !code.is_empty()
+        && code.len() <= 64
+        && code
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    valid.then_some(code)
+}
+
+async fn load_preview(state: &AppState, code: &str) -> Option<InvitePreview> {
+    let invite = paracord_db::invites::get_invite(&state.db, code)
+        .await
+        .ok()??;
+    let channel = paracord_db::channels::get_channel(&state.db, invite.channel_id)
+        .await
+        .ok()??;
+    let guild = paracord_db::guilds::get_guild(&state.db, channel.guild_id()?)
+        .await
+        .ok()??;
+    let member_count = paracord_db::members::get_member_count(&state.db, guild.id)
+        .await
+        .unwrap_or(0);
+    Some(InvitePreview {
+        guild_name: guild.name,
+        description: guild.description.filter(|d| !d.trim().is_empty()),
+        member_count,
+        url: crate::routes::widgets::invite_url(state, &invite.code),
+    })
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn meta_tags(preview: &InvitePreview, site_name: &str, accent_color: &str) -> String {
+    let title = format!("Join {} on {}", preview.guild_name, site_name);
+    let members = if preview.member_count == 1 {
+        "1 member".to_string()
+    } else {
+        format!("{} members", preview.member_count)
+    };
+    let description = match &preview.description {
+        Some(description) => {
+            let description: String = description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+            format!("{description} · {members}")
+        }
+        None => format!(
+            "You've been invited to join {}. {members}.",
+            preview.guild_name
+        ),
+    };
+
+    let mut tags = vec![
+        ("property", "og:type", "website".to_string()),
+        ("property", "og:site_name", site_name.to_string()),
+        ("property", "og:title", title.clone()),
+        ("property", "og:description", description.clone()),
+        ("name", "twitter:card", "summary".to_string()),
+        ("name", "twitter:title", title),
+        ("name", "twitter:description", description),
+    ];
+    if preview.url.starts_with("http") {
+        tags.push(("property", "og:url", preview.url.clone()));
+    }
+    if !accent_color.is_empty() {
+        tags.push(("name", "theme-color", accent_color.to_string()));
+    }
+    tags.into_iter()
+        .map(|(attribute, key, value)| {
+            format!(
+                "<meta {attribute}=\"{key}\" content=\"{}\">\n",
+                escape_attribute(&value)
+            )
+        })
+        .collect()
+}
+
+/// Insert `tags` before `</head>`, or `None` when the page has no head.
+fn inject_tags(page: &str, tags: &str) -> Option<String> {
+    let index = page.to_ascii_lowercase().find("</head>")?;
+    let mut out = String::with_capacity(page.len() + tags.len());
+    out.push_str(&page[..index]);
+    out.push_str(tags);
+    out.push_str(&page[index..]);
+    Some(out)
+}
+
+/// Add OpenGraph tags to the web UI page served for `/invite/{code}`.
+pub async fn middleware(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let Some(code) = invite_code(req.uri().path()).map(str::to_string) else {
+        return next.run(req).await;
+    };
+    let Some(preview) = load_preview(&state, &code).await else {
+        return next.run(req).await;
+    };
+
+    // The page is rewritten, so ask for it uncompressed and in full.
+    let headers = req.headers_mut();
+    headers.remove(header::ACCEPT_ENCODING);
+    headers.remove(header::IF_NONE_MATCH);
+    headers.remove(header::IF_MODIFIED_SINCE);
+    let response = next.run(req).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if response.status() != StatusCode::OK || !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_PAGE_BYTES).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let page = String::from_utf8_lossy(&bytes);
+    let (site_name, accent_color) = {
+        let runtime = state.runtime.read().await;
+        (
+            runtime.server_name.clone(),
+            runtime.branding_accent_color.clone(),
+        )
+    };
+    let Some(page) = inject_tags(&page, &meta_tags(&preview, &site_name, &accent_color)) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::ETAG);
+    parts.headers.remove(header::LAST_MODIFIED);
+    parts
+        .headers
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Response::from_parts(parts, Body::from(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invite_paths_yield_codes() {
+        assert_eq!(invite_code("/invite/abc123"), Some("abc123"));
+        assert_eq!(invite_code("/invite/abc123/"), Some("abc123"));
+        assert_eq!(invite_code("/invite/"), None);
+        assert_eq!(invite_code("/invite/a/b"), None);
+        assert_eq!(invite_code("/invites/abc"), None);
+    }
+
+    #[test]
+    fn tags_are_escaped_and_placed_in_the_head() {
+        let preview = InvitePreview {
+            guild_name: "Rust \"Fans\" <3".to_string(),
+            description: None,
+            member_count: 2,
+            url: "https://chat.example.com/invite/abc".to_string(),
+        };
+        let tags = meta_tags(&preview, "Paracord", "#5865f2");
+        assert!(tags.contains(
+            "<meta property=\"og:title\" content=\"Join Rust &quot;Fans&quot; &lt;3 on Paracord\">"
+        ));
+        assert!(tags.contains("content=\"https://chat.example.com/invite/abc\""));
+        assert!(tags.contains("2 members"));
+        assert!(tags.contains("<meta name=\"theme-color\" content=\"#5865f2\">"));
+
+        let page = inject_tags("<html><HEAD><title>x</title></HEAD></html>", &tags).unwrap();
+        assert!(page.find("og:title").unwrap() < page.find("</HEAD>").unwrap());
+        assert!(inject_tags("<p>no head</p>", &tags).is_none());
+    }
+}
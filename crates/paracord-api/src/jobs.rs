@@ -0,0 +1,40 @@
+//! Background job kinds run by the API's handlers.
+
+use paracord_core::jobs::JobRegistry;
+
+use crate::routes::{admin, channel_exports, imports, recordings};
+use crate::{link_previews, mention_fanout};
+
+/// Render and store a channel export. Payload: `{"export_id"}`.
+pub const JOB_CHANNEL_EXPORT: &str = "channel_export";
+/// Import a finished voice recording. Payload: `{"recording_id", "info"}`.
+pub const JOB_VOICE_RECORDING_FINALIZE: &str = "voice_recording_finalize";
+/// Run an admin repair or integrity task. Payload: `{"task"}`.
+pub const JOB_ADMIN_MAINTENANCE: &str = "admin_maintenance";
+/// Import a Discord or Matrix export into a guild. Payload:
+/// [`imports::ImportJobPayload`].
+pub const JOB_GUILD_IMPORT: &str = "guild_import";
+/// Fetch link previews for a message. Payload: `{"message_id"}`.
+pub const JOB_LINK_UNFURL: &str = "link_unfurl";
+/// Count a message's role and `@everyone` mentions and queue their pushes.
+/// Payload: `{"message_id"}`.
+pub const JOB_MENTION_FANOUT: &str = "mention_fanout";
+/// Recurring: purge guilds, channels and roles past their undo window.
+pub const JOB_TOMBSTONE_PURGE: &str = "tombstone_purge";
+
+/// Registry with a handler for every job kind the API enqueues.
+pub fn job_registry() -> JobRegistry {
+    let mut registry = JobRegistry::new();
+    registry.register(JOB_CHANNEL_EXPORT, channel_exports::run_export_job);
+    registry.register(JOB_VOICE_RECORDING_FINALIZE, recordings::run_finalize_job);
+    registry.register(JOB_ADMIN_MAINTENANCE, admin::run_maintenance_job);
+    registry.register(JOB_GUILD_IMPORT, imports::run_import_job);
+    registry.register(JOB_LINK_UNFURL, link_previews::run_unfurl_job);
+    registry.register(JOB_MENTION_FANOUT, mention_fanout::run_fanout_job);
+    registry.register_recurring(
+        JOB_TOMBSTONE_PURGE,
+        chrono::Duration::minutes(5),
+        paracord_core::tombstone::run_purge_job,
+    );
+    registry
+}
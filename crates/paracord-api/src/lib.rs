@@ -1,5 +1,5 @@
 use axum::{
-    extract::{ConnectInfo, DefaultBodyLimit, Request},
+    extract::{ConnectInfo, DefaultBodyLimit, Request, State},
     http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
     middleware::{from_fn, Next},
     response::IntoResponse,
@@ -11,29 +11,187 @@ use dashmap::DashMap;
 use paracord_core::{observability, AppState};
 use serde_json::json;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::Notify;
 
+pub mod client_ip;
 pub mod error;
+pub mod invite_previews;
+pub mod jobs;
+pub mod link_previews;
+pub mod mention_fanout;
 pub mod middleware;
+pub mod openapi;
+pub mod rate_limit;
+pub mod rate_limit_bypass;
 pub mod routes;
+pub mod supporter_webhooks;
 
 const DEFAULT_REQUEST_BODY_LIMIT_BYTES: usize = 2 * 1024 * 1024;
 const ATTACHMENT_REQUEST_BODY_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+const AVATAR_REQUEST_BODY_LIMIT_BYTES: usize = 10 * 1024 * 1024;
+const WEBHOOK_REQUEST_BODY_LIMIT_BYTES: usize = 512 * 1024;
 
+/// Every route on a single listener.
 pub fn build_router() -> Router<AppState> {
-    let cors = build_cors_layer();
+    with_layers(
+        api_routes()
+            .merge(health_routes())
+            .merge(management_routes(true)),
+    )
+}
+
+/// Public listener routes when `/metrics` and `/api/v1/admin/*` are served
+/// separately by [`build_internal_router`].
+pub fn build_public_router() -> Router<AppState> {
+    // Explicit 404s keep the web UI's SPA fallback from answering these.
+    let moved = || async { error::ApiError::NotFound };
+    with_layers(
+        api_routes()
+            .merge(health_routes())
+            .route("/metrics", any(moved))
+            .route("/api/v1/metrics", any(moved))
+            .route("/api/v1/admin/{*path}", any(moved)),
+    )
+}
+
+/// Health, metrics, and admin routes for the internal listener. Metrics skip
+/// the token check there since access is controlled by the bind address.
+pub fn build_internal_router() -> Router<AppState> {
+    with_layers(health_routes().merge(management_routes(false)))
+}
+
+/// Swagger UI for the API description, served when
+/// `developer.swagger_ui` is enabled.
+pub fn build_swagger_ui_router() -> Router<AppState> {
+    with_layers(openapi::swagger_ui().into())
+}
+
+fn health_routes() -> Router<AppState> {
     Router::new()
-        // Health
         .route("/health", get(health))
         .route("/api/v1/health", get(health))
-        .route("/metrics", get(metrics))
-        .route("/api/v1/metrics", get(metrics))
+}
+
+fn management_routes(require_metrics_token: bool) -> Router<AppState> {
+    let metrics_handler = move |State(state): State<AppState>, headers: HeaderMap| {
+        metrics(state, headers, require_metrics_token)
+    };
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/api/v1/metrics", get(metrics_handler))
+        // Admin
+        .route("/api/v1/admin/stats", get(routes::admin::get_stats))
+        .route(
+            "/api/v1/admin/observability",
+            get(routes::admin::get_observability),
+        )
+        .route(
+            "/api/v1/admin/security-events",
+            get(routes::admin::list_security_events),
+        )
+        .route(
+            "/api/v1/admin/settings",
+            get(routes::admin::get_settings).patch(routes::admin::update_settings),
+        )
+        .route(
+            "/api/v1/admin/branding/logo",
+            put(routes::branding::upload_branding_logo)
+                .delete(routes::branding::delete_branding_logo),
+        )
+        .route("/api/v1/admin/users", get(routes::admin::list_users))
+        .route(
+            "/api/v1/admin/users/{user_id}",
+            patch(routes::admin::update_user).delete(routes::admin::delete_user),
+        )
+        .route("/api/v1/admin/guilds", get(routes::admin::list_guilds))
+        .route(
+            "/api/v1/admin/guilds/{guild_id}",
+            patch(routes::admin::update_guild).delete(routes::admin::delete_guild),
+        )
+        .route("/api/v1/admin/deleted", get(routes::admin::list_deleted))
+        .route(
+            "/api/v1/admin/deleted/{kind}/{id}/restore",
+            post(routes::admin::restore_deleted),
+        )
+        .route(
+            "/api/v1/admin/restart-update",
+            post(routes::admin::restart_update),
+        )
+        .route(
+            "/api/v1/admin/url-denylist",
+            get(routes::url_denylist::list_entries).post(routes::url_denylist::add_entry),
+        )
+        .route(
+            "/api/v1/admin/url-denylist/{domain}",
+            delete(routes::url_denylist::remove_entry),
+        )
+        .route(
+            "/api/v1/admin/image-hashes",
+            get(routes::image_hashes::list_entries).post(routes::image_hashes::add_entries),
+        )
+        .route(
+            "/api/v1/admin/image-hashes/{hash}",
+            delete(routes::image_hashes::remove_entry),
+        )
+        .route("/api/v1/admin/jobs", get(routes::admin::list_jobs))
+        .route(
+            "/api/v1/admin/jobs/{job_id}",
+            get(routes::admin::get_job).delete(routes::admin::cancel_job),
+        )
+        .route(
+            "/api/v1/admin/maintenance",
+            post(routes::admin::start_maintenance_task),
+        )
+        .route("/api/v1/admin/imports", post(routes::imports::start_import))
+        .route(
+            "/api/v1/admin/jobs/{job_id}/retry",
+            post(routes::admin::retry_job),
+        )
+        .route(
+            "/api/v1/admin/supporters",
+            get(routes::supporters::list_supporters),
+        )
+        .route(
+            "/api/v1/admin/supporters/{user_id}",
+            put(routes::supporters::grant_supporter).delete(routes::supporters::revoke_supporter),
+        )
+        .route(
+            "/api/v1/admin/supporter-roles/{role_id}",
+            put(routes::supporters::add_supporter_role)
+                .delete(routes::supporters::remove_supporter_role),
+        )
+        .route("/api/v1/admin/events", get(routes::event_log::list_events))
+        .route(
+            "/api/v1/admin/events/stream",
+            get(routes::event_log::stream_events),
+        )
+        // Admin backups
+        .route("/api/v1/admin/backup", post(routes::admin::create_backup))
+        .route("/api/v1/admin/backups", get(routes::admin::list_backups))
+        .route("/api/v1/admin/restore", post(routes::admin::restore_backup))
+        .route(
+            "/api/v1/admin/database",
+            get(routes::admin::get_database_status),
+        )
+        .route(
+            "/api/v1/admin/database/compact",
+            post(routes::admin::compact_database),
+        )
+        .route(
+            "/api/v1/admin/backups/{name}",
+            get(routes::admin::download_backup).delete(routes::admin::delete_backup),
+        )
+}
+
+fn api_routes() -> Router<AppState> {
+    Router::new()
         // Realtime v2 (SSE + HTTP command bus)
         .route("/api/v2/rt/session", post(routes::realtime::create_session))
         .route("/api/v2/rt/events", get(routes::realtime::stream_events))
         .route("/api/v2/rt/commands", post(routes::realtime::post_command))
+        .route("/api/v2/rt/ack", post(routes::realtime::post_ack))
         // Federation discovery and transport
         .route(
             "/.well-known/paracord/server",
@@ -84,6 +242,10 @@ pub fn build_router() -> Router<AppState> {
             get(routes::federation::file_download),
         )
         // Federation server management (admin)
+        .route(
+            "/_paracord/federation/v1/users/search",
+            get(routes::federation::search_users),
+        )
         .route(
             "/_paracord/federation/v1/servers",
             get(routes::federation::list_servers).post(routes::federation::add_server),
@@ -92,10 +254,21 @@ pub fn build_router() -> Router<AppState> {
             "/_paracord/federation/v1/servers/{server_name}",
             get(routes::federation::get_server).delete(routes::federation::delete_server),
         )
+        .route("/api/v1/openapi.json", get(openapi::openapi_json))
         // Auth
         .route("/api/v1/auth/register", post(routes::auth::register))
         .route("/api/v1/auth/login", post(routes::auth::login))
         .route("/api/v1/auth/options", get(routes::auth::auth_options))
+        // Desktop client updates
+        .route(
+            "/api/v1/client/update-manifest",
+            get(routes::client_updates::get_update_manifest),
+        )
+        .route("/api/v1/branding", get(routes::branding::get_branding))
+        .route(
+            "/api/v1/branding/logo/{file_name}",
+            get(routes::branding::get_branding_logo),
+        )
         .route("/api/v1/auth/refresh", post(routes::auth::refresh))
         .route("/api/v1/auth/logout", post(routes::auth::logout))
         .route("/api/v1/auth/challenge", post(routes::auth::challenge))
@@ -109,6 +282,22 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/auth/sessions/{session_id}",
             delete(routes::auth::revoke_session),
         )
+        .route(
+            "/api/v1/auth/sessions/{session_id}/approve",
+            post(routes::auth::approve_session_device),
+        )
+        .route(
+            "/api/v1/auth/sessions/{session_id}/deny",
+            post(routes::auth::deny_session_device),
+        )
+        .route(
+            "/api/v1/auth/device-recovery",
+            post(routes::auth::recover_device),
+        )
+        .route(
+            "/api/v1/auth/device-recovery-code",
+            post(routes::auth::regenerate_device_recovery_code),
+        )
         // Users
         .route(
             "/api/v1/users/@me",
@@ -116,6 +305,10 @@ pub fn build_router() -> Router<AppState> {
                 .patch(routes::users::update_me)
                 .delete(routes::users::delete_me),
         )
+        .route(
+            "/api/v1/users/@me/perks",
+            get(routes::supporters::get_my_perks),
+        )
         .route(
             "/api/v1/users/@me/settings",
             get(routes::users::get_settings).patch(routes::users::update_settings),
@@ -125,6 +318,23 @@ pub fn build_router() -> Router<AppState> {
             put(routes::users::change_password),
         )
         .route("/api/v1/users/@me/email", put(routes::users::change_email))
+        .route(
+            "/api/v1/users/@me/age-gate",
+            get(routes::users::get_age_gate).post(routes::users::acknowledge_age_gate),
+        )
+        .route("/api/v1/users/@me/notes", get(routes::users::list_notes))
+        .route(
+            "/api/v1/users/@me/notes/{user_id}",
+            get(routes::users::get_note).put(routes::users::update_note),
+        )
+        .route(
+            "/api/v1/users/@me/reminders",
+            get(routes::reminders::list_reminders).post(routes::reminders::create_reminder),
+        )
+        .route(
+            "/api/v1/users/@me/reminders/{reminder_id}",
+            delete(routes::reminders::delete_reminder),
+        )
         .route(
             "/api/v1/users/@me/data-export",
             get(routes::users::export_my_data),
@@ -137,15 +347,73 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/users/@me/import",
             post(routes::users::import_identity),
         )
+        .route(
+            "/api/v1/users/@me/avatar",
+            put(routes::users::upload_avatar)
+                .layer(DefaultBodyLimit::max(AVATAR_REQUEST_BODY_LIMIT_BYTES)),
+        )
+        .route("/api/v1/users/search", get(routes::users::search_users))
         .route(
             "/api/v1/users/{user_id}/profile",
             get(routes::users::get_user_profile),
         )
+        .route(
+            "/api/v1/users/{user_id}/avatar",
+            get(routes::users::get_user_avatar),
+        )
         .route("/api/v1/users/@me/guilds", get(routes::guilds::list_guilds))
+        .route(
+            "/api/v1/users/@me/guilds/{guild_id}/notification-settings",
+            get(routes::notification_settings::get_notification_settings)
+                .patch(routes::notification_settings::update_notification_settings),
+        )
+        .route(
+            "/api/v1/users/@me/push-subscriptions",
+            get(routes::push_subscriptions::list_push_subscriptions)
+                .post(routes::push_subscriptions::create_push_subscription)
+                .delete(routes::push_subscriptions::delete_push_subscription),
+        )
         .route(
             "/api/v1/users/@me/dms",
             get(routes::dms::list_dms).post(routes::dms::create_dm),
         )
+        .route(
+            "/api/v1/users/@me/dms/requests",
+            get(routes::dms::list_dm_requests),
+        )
+        .route(
+            "/api/v1/users/@me/dms/requests/{channel_id}/accept",
+            post(routes::dms::accept_dm_request),
+        )
+        .route(
+            "/api/v1/users/@me/dms/requests/{channel_id}/decline",
+            post(routes::dms::decline_dm_request),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}",
+            patch(routes::dms::update_group_dm).delete(routes::dms::leave_group_dm),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}/recipients/{user_id}",
+            put(routes::dms::add_group_dm_recipient).delete(routes::dms::remove_group_dm_recipient),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}/owner",
+            post(routes::dms::transfer_group_dm_owner),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}/keys",
+            get(routes::dms::get_group_dm_keys),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}/sender-keys",
+            get(routes::dms::list_group_dm_sender_keys)
+                .post(routes::dms::upload_group_dm_sender_keys),
+        )
+        .route(
+            "/api/v1/users/@me/dms/{channel_id}/receipts",
+            get(routes::dms::list_dm_receipts),
+        )
         .route(
             "/api/v1/users/@me/read-states",
             get(routes::users::get_read_states),
@@ -158,6 +426,21 @@ pub fn build_router() -> Router<AppState> {
                 .patch(routes::guilds::update_guild)
                 .delete(routes::guilds::delete_guild),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/icon",
+            get(routes::guilds::get_guild_icon).merge(
+                put(routes::guilds::upload_guild_icon)
+                    .layer(DefaultBodyLimit::max(AVATAR_REQUEST_BODY_LIMIT_BYTES)),
+            ),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/sync",
+            get(routes::guilds::sync_guild),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/messages/search",
+            get(routes::guilds::search_guild_messages),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/owner",
             post(routes::guilds::transfer_ownership),
@@ -172,14 +455,30 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/members",
             get(routes::members::list_members),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/presences",
+            get(routes::members::list_presences),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/members/{user_id}",
             patch(routes::members::update_member).delete(routes::members::kick_member),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/members/{user_id}/notes",
+            get(routes::members::list_member_notes).post(routes::members::create_member_note),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/members/{user_id}/notes/{note_id}",
+            delete(routes::members::delete_member_note),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/members/@me",
             delete(routes::members::leave_guild),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/members/@me/roles/{role_id}",
+            put(routes::members::add_self_role).delete(routes::members::remove_self_role),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/bans",
             get(routes::bans::list_bans),
@@ -204,6 +503,14 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/emojis",
             get(routes::emojis::list_guild_emojis).post(routes::emojis::create_emoji),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/emojis/analytics",
+            get(routes::emojis::get_emoji_analytics),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/emojis/unused",
+            get(routes::emojis::get_unused_emojis),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/emojis/{emoji_id}",
             patch(routes::emojis::update_emoji).delete(routes::emojis::delete_emoji),
@@ -212,6 +519,29 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/emojis/{emoji_id}/image",
             get(routes::emojis::get_emoji_image),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/tags",
+            get(routes::tags::list_tags).post(routes::tags::create_tag),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/tags/{tag_name}",
+            get(routes::tags::get_tag)
+                .patch(routes::tags::update_tag)
+                .delete(routes::tags::delete_tag),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/theme",
+            get(routes::branding::get_guild_theme).put(routes::branding::update_guild_theme),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/theme/assets",
+            post(routes::branding::upload_guild_theme_asset),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/theme/assets/{asset_id}",
+            get(routes::branding::get_guild_theme_asset)
+                .delete(routes::branding::delete_guild_theme_asset),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/webhooks",
             get(routes::webhooks::list_guild_webhooks).post(routes::webhooks::create_webhook),
@@ -220,6 +550,14 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/events",
             get(routes::events::list_events).post(routes::events::create_event),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/events/calendar-token",
+            get(routes::events::get_calendar_token),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/events.ics",
+            get(routes::events::calendar_feed),
+        )
         // Guild Commands
         .route(
             "/api/v1/guilds/{guild_id}/commands",
@@ -248,6 +586,43 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/guilds/{guild_id}/storage",
             get(routes::guilds::get_storage).patch(routes::guilds::update_storage),
         )
+        .route(
+            "/api/v1/guilds/{guild_id}/thread-policy",
+            get(routes::guilds::get_thread_policy).put(routes::guilds::update_thread_policy),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/mod-log",
+            get(routes::mod_log::get_mod_log)
+                .put(routes::mod_log::update_mod_log)
+                .delete(routes::mod_log::delete_mod_log),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/widget",
+            get(routes::widgets::get_widget_settings)
+                .patch(routes::widgets::update_widget_settings),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/widget.json",
+            get(routes::widgets::get_widget),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/verification-hook",
+            get(routes::verification::get_verification_hook)
+                .put(routes::verification::update_verification_hook)
+                .delete(routes::verification::delete_verification_hook),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/verification-hook/callback",
+            post(routes::verification::hook_callback),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/join-requests",
+            get(routes::verification::list_join_requests),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/join-requests/{user_id}",
+            put(routes::verification::decide_join_request),
+        )
         .route(
             "/api/v1/guilds/{guild_id}/files",
             get(routes::guilds::list_files).delete(routes::guilds::delete_files),
@@ -263,6 +638,22 @@ pub fn build_router() -> Router<AppState> {
                 .patch(routes::channels::update_channel)
                 .delete(routes::channels::delete_channel),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/archive",
+            put(routes::channels::archive_channel).delete(routes::channels::unarchive_channel),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/exports",
+            get(routes::channel_exports::list_exports).post(routes::channel_exports::create_export),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/exports/{export_id}",
+            get(routes::channel_exports::get_export),
+        )
+        .route(
+            "/api/v1/channel-exports/{export_id}/{token}",
+            get(routes::channel_exports::download_export),
+        )
         .route(
             "/api/v1/channels/{channel_id}/messages",
             get(routes::channels::get_messages).post(routes::channels::send_message),
@@ -309,7 +700,8 @@ pub fn build_router() -> Router<AppState> {
         )
         .route(
             "/api/v1/channels/{channel_id}/overwrites",
-            get(routes::channels::list_channel_overwrites),
+            get(routes::channels::list_channel_overwrites)
+                .put(routes::channels::replace_channel_overwrites),
         )
         .route(
             "/api/v1/channels/{channel_id}/overwrites/{target_id}",
@@ -370,9 +762,20 @@ pub fn build_router() -> Router<AppState> {
                 .patch(routes::webhooks::update_webhook)
                 .delete(routes::webhooks::delete_webhook),
         )
+        .route(
+            "/api/v1/supporters/webhooks/kofi",
+            post(routes::supporters::kofi_webhook)
+                .layer(DefaultBodyLimit::max(WEBHOOK_REQUEST_BODY_LIMIT_BYTES)),
+        )
+        .route(
+            "/api/v1/supporters/webhooks/patreon",
+            post(routes::supporters::patreon_webhook)
+                .layer(DefaultBodyLimit::max(WEBHOOK_REQUEST_BODY_LIMIT_BYTES)),
+        )
         .route(
             "/api/v1/webhooks/{webhook_id}/{token}",
-            post(routes::webhooks::execute_webhook),
+            post(routes::webhooks::execute_webhook)
+                .layer(DefaultBodyLimit::max(WEBHOOK_REQUEST_BODY_LIMIT_BYTES)),
         )
         .route(
             "/api/v1/discovery/guilds",
@@ -436,9 +839,16 @@ pub fn build_router() -> Router<AppState> {
         )
         .route(
             "/api/v1/interactions/{app_id}/{token}/messages/@original",
-            patch(routes::interactions::edit_original_response)
+            get(routes::interactions::get_original_response)
+                .patch(routes::interactions::edit_original_response)
                 .delete(routes::interactions::delete_original_response),
         )
+        .route(
+            "/api/v1/interactions/{app_id}/{token}/messages/{message_id}",
+            get(routes::interactions::get_followup_message)
+                .patch(routes::interactions::edit_followup_message)
+                .delete(routes::interactions::delete_followup_message),
+        )
         .route(
             "/api/v1/interactions/{app_id}/{token}/followup",
             post(routes::interactions::create_followup_message),
@@ -464,6 +874,35 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/voice/{channel_id}/stream",
             post(routes::voice::start_stream),
         )
+        .route(
+            "/api/v1/voice/{channel_id}/recordings",
+            get(routes::recordings::list_recordings).post(routes::recordings::start_recording),
+        )
+        .route(
+            "/api/v1/voice/{channel_id}/recordings/{recording_id}/stop",
+            post(routes::recordings::stop_recording),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/stream-keys",
+            get(routes::whip::list_stream_keys).post(routes::whip::create_stream_key),
+        )
+        .route(
+            "/api/v1/channels/{channel_id}/stream-keys/{key_id}",
+            delete(routes::whip::delete_stream_key),
+        )
+        .route(
+            "/api/v1/whip/{channel_id}",
+            post(routes::whip::whip_publish),
+        )
+        .route(
+            "/api/v1/whip/{channel_id}/{session_id}",
+            delete(routes::whip::whip_stop),
+        )
+        .route("/api/v1/whep/{channel_id}", post(routes::whip::whep_play))
+        .route(
+            "/api/v1/whep/{channel_id}/{session_id}",
+            delete(routes::whip::whep_stop),
+        )
         .route(
             "/api/v1/voice/{channel_id}/stream/stop",
             post(routes::voice::stop_stream),
@@ -476,6 +915,18 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/voice/livekit/webhook",
             post(routes::voice::livekit_webhook),
         )
+        .route(
+            "/api/v1/channels/{channel_id}/voice-stack",
+            put(routes::voice::update_channel_voice_stack),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/voice-stacks",
+            get(routes::voice::get_guild_voice_stacks),
+        )
+        .route(
+            "/api/v1/guilds/{guild_id}/voice-stacks/migrate",
+            post(routes::voice::migrate_guild_voice_stacks),
+        )
         .route(
             "/api/v2/voice/{channel_id}/join",
             post(routes::voice_v2::join_voice_v2),
@@ -492,6 +943,10 @@ pub fn build_router() -> Router<AppState> {
             "/api/v2/voice/recover",
             post(routes::voice_v2::recover_voice_v2),
         )
+        .route(
+            "/api/v2/voice/{channel_id}/stats",
+            get(routes::voice_v2::get_voice_stats_v2),
+        )
         // Files
         .route(
             "/api/v1/channels/{channel_id}/attachments",
@@ -502,6 +957,14 @@ pub fn build_router() -> Router<AppState> {
             "/api/v1/attachments/{id}",
             get(routes::files::download_file).delete(routes::files::delete_file),
         )
+        .route(
+            "/api/v1/attachments/{id}/transcoded",
+            get(routes::files::download_transcoded_file),
+        )
+        .route(
+            "/api/v1/attachments/{id}/thumbnail",
+            get(routes::files::download_thumbnail),
+        )
         // QUIC file transfer pre-authorization
         .route(
             "/api/v2/channels/{channel_id}/upload-token",
@@ -522,47 +985,20 @@ pub fn build_router() -> Router<AppState> {
             put(routes::relationships::accept_friend)
                 .delete(routes::relationships::remove_relationship),
         )
-        // Admin
-        .route("/api/v1/admin/stats", get(routes::admin::get_stats))
-        .route(
-            "/api/v1/admin/security-events",
-            get(routes::admin::list_security_events),
-        )
-        .route(
-            "/api/v1/admin/settings",
-            get(routes::admin::get_settings).patch(routes::admin::update_settings),
-        )
-        .route("/api/v1/admin/users", get(routes::admin::list_users))
-        .route(
-            "/api/v1/admin/users/{user_id}",
-            patch(routes::admin::update_user).delete(routes::admin::delete_user),
-        )
-        .route("/api/v1/admin/guilds", get(routes::admin::list_guilds))
-        .route(
-            "/api/v1/admin/guilds/{guild_id}",
-            patch(routes::admin::update_guild).delete(routes::admin::delete_guild),
-        )
-        .route(
-            "/api/v1/admin/restart-update",
-            post(routes::admin::restart_update),
-        )
-        // Admin backups
-        .route("/api/v1/admin/backup", post(routes::admin::create_backup))
-        .route("/api/v1/admin/backups", get(routes::admin::list_backups))
-        .route("/api/v1/admin/restore", post(routes::admin::restore_backup))
-        .route(
-            "/api/v1/admin/backups/{name}",
-            get(routes::admin::download_backup).delete(routes::admin::delete_backup),
-        )
         // LiveKit reverse proxy (voice signaling + Twirp API on the same port)
         .route(
             "/livekit/{*path}",
             any(routes::livekit_proxy::livekit_proxy),
         )
-        // Middleware layers
+}
+
+fn with_layers(router: Router<AppState>) -> Router<AppState> {
+    let cors = build_cors_layer();
+    router
         .layer(DefaultBodyLimit::max(DEFAULT_REQUEST_BODY_LIMIT_BYTES))
         .layer(from_fn(metrics_middleware))
         .layer(from_fn(rate_limit_middleware))
+        .layer(from_fn(locale_middleware))
         .layer(from_fn(security_headers_middleware))
         .layer(cors)
         .layer(
@@ -680,7 +1116,10 @@ fn build_cors_layer() -> tower_http::cors::CorsLayer {
             header::AUTHORIZATION,
             header::CONTENT_TYPE,
             header::ACCEPT,
+            header::ACCEPT_LANGUAGE,
             header::ORIGIN,
+            HeaderName::from_static(routes::client_updates::CLIENT_VERSION_HEADER),
+            HeaderName::from_static("x-device-id"),
         ])
         .max_age(Duration::from_secs(600));
 
@@ -702,6 +1141,12 @@ fn build_cors_layer() -> tower_http::cors::CorsLayer {
     cors
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = serde_json::Value)),
+)]
 async fn health() -> impl IntoResponse {
     (
         StatusCode::OK,
@@ -709,30 +1154,42 @@ async fn health() -> impl IntoResponse {
     )
 }
 
-async fn metrics(headers: HeaderMap) -> impl IntoResponse {
+fn metrics_token() -> Option<String> {
+    std::env::var("PARACORD_METRICS_TOKEN")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn bearer_matches(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|raw| raw.strip_prefix("Bearer "))
+        .map(str::trim)
+        == Some(expected)
+}
+
+/// Whether the request carries `PARACORD_METRICS_TOKEN`, which also unlocks
+/// the admin observability snapshot for scrapers without an admin account.
+pub(crate) fn presents_metrics_token(headers: &HeaderMap) -> bool {
+    metrics_token().is_some_and(|expected| bearer_matches(headers, &expected))
+}
+
+async fn metrics(state: AppState, headers: HeaderMap, require_token: bool) -> impl IntoResponse {
     let public_metrics = std::env::var("PARACORD_ENABLE_PUBLIC_METRICS")
         .ok()
         .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
         .unwrap_or(false);
-    if !public_metrics {
-        let expected = std::env::var("PARACORD_METRICS_TOKEN")
-            .ok()
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty());
-        let Some(expected) = expected else {
+    if require_token && !public_metrics {
+        let Some(expected) = metrics_token() else {
             return (
                 StatusCode::FORBIDDEN,
                 [("content-type", "text/plain; charset=utf-8")],
                 "metrics disabled".to_string(),
             );
         };
-
-        let presented = headers
-            .get(header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|raw| raw.strip_prefix("Bearer "))
-            .map(str::trim);
-        if presented != Some(expected.as_str()) {
+        if !bearer_matches(&headers, &expected) {
             return (
                 StatusCode::UNAUTHORIZED,
                 [("content-type", "text/plain; charset=utf-8")],
@@ -743,6 +1200,7 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
 
     let requests = REQUEST_COUNT.load(Ordering::Relaxed);
     let limited = RATE_LIMITED_COUNT.load(Ordering::Relaxed);
+    let bypassed = RATE_LIMIT_BYPASSED_COUNT.load(Ordering::Relaxed);
 
     let s2xx = STATUS_2XX.load(Ordering::Relaxed);
     let s4xx = STATUS_4XX.load(Ordering::Relaxed);
@@ -756,6 +1214,21 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
     let dur_count = DURATION_COUNT.load(Ordering::Relaxed);
     let dur_sum_s = dur_sum_us as f64 / 1_000_000.0;
 
+    let fed_cache_hits = FED_FILE_CACHE_HITS.load(Ordering::Relaxed);
+    let fed_cache_misses = FED_FILE_CACHE_MISSES.load(Ordering::Relaxed);
+    let fed_cache_deduped = FED_FILE_CACHE_DEDUPED.load(Ordering::Relaxed);
+    let fed_prefetched = FED_FILE_PREFETCHED.load(Ordering::Relaxed);
+    let fed_prefetch_failed = FED_FILE_PREFETCH_FAILED.load(Ordering::Relaxed);
+
+    let pool = paracord_core::observability::db_pool_metrics_snapshot(&state.db);
+    let pool_in_use = (pool.size as usize).saturating_sub(pool.idle);
+    let pool_max = pool.max_connections;
+    let pool_idle = pool.idle;
+    let pool_wait_last_s = pool.wait_last.as_secs_f64();
+    let pool_wait_sum_s = pool.wait_sum.as_secs_f64();
+    let pool_wait_count = pool.wait_count;
+    let pool_exhausted = pool.exhausted_total;
+
     let mut body = format!(
         "# HELP paracord_up Whether the server is up.\n\
          # TYPE paracord_up gauge\n\
@@ -766,6 +1239,9 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
          # HELP paracord_http_rate_limited_total Requests rejected by rate limiter.\n\
          # TYPE paracord_http_rate_limited_total counter\n\
          paracord_http_rate_limited_total {limited}\n\
+         # HELP paracord_http_rate_limit_bypassed_total Requests let past the rate limiter by an allowlist or path exemption.\n\
+         # TYPE paracord_http_rate_limit_bypassed_total counter\n\
+         paracord_http_rate_limit_bypassed_total {bypassed}\n\
          # HELP paracord_http_responses_total HTTP responses by status class.\n\
          # TYPE paracord_http_responses_total counter\n\
          paracord_http_responses_total{{status_class=\"2xx\"}} {s2xx}\n\
@@ -790,6 +1266,34 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
          # HELP paracord_ws_events_total Total WebSocket events dispatched.\n\
          # TYPE paracord_ws_events_total counter\n\
          paracord_ws_events_total {ws_events}\n\
+         # HELP paracord_federation_file_cache_requests_total Federated file downloads by cache result.\n\
+         # TYPE paracord_federation_file_cache_requests_total counter\n\
+         paracord_federation_file_cache_requests_total{{result=\"hit\"}} {fed_cache_hits}\n\
+         paracord_federation_file_cache_requests_total{{result=\"miss\"}} {fed_cache_misses}\n\
+         # HELP paracord_federation_file_cache_deduplicated_total Cached federated files that reused an existing blob.\n\
+         # TYPE paracord_federation_file_cache_deduplicated_total counter\n\
+         paracord_federation_file_cache_deduplicated_total {fed_cache_deduped}\n\
+         # HELP paracord_federation_file_prefetch_total Federated attachments prefetched on ingest by outcome.\n\
+         # TYPE paracord_federation_file_prefetch_total counter\n\
+         paracord_federation_file_prefetch_total{{result=\"cached\"}} {fed_prefetched}\n\
+         paracord_federation_file_prefetch_total{{result=\"failed\"}} {fed_prefetch_failed}\n\
+         # HELP paracord_db_pool_connections Database pool connections by state.\n\
+         # TYPE paracord_db_pool_connections gauge\n\
+         paracord_db_pool_connections{{state=\"idle\"}} {pool_idle}\n\
+         paracord_db_pool_connections{{state=\"in_use\"}} {pool_in_use}\n\
+         # HELP paracord_db_pool_max_connections Connections the database pool may open.\n\
+         # TYPE paracord_db_pool_max_connections gauge\n\
+         paracord_db_pool_max_connections {pool_max}\n\
+         # HELP paracord_db_pool_acquire_wait_seconds Connection checkout wait measured by the pool probe.\n\
+         # TYPE paracord_db_pool_acquire_wait_seconds summary\n\
+         paracord_db_pool_acquire_wait_seconds_sum {pool_wait_sum_s}\n\
+         paracord_db_pool_acquire_wait_seconds_count {pool_wait_count}\n\
+         # HELP paracord_db_pool_acquire_wait_last_seconds Wait of the most recent pool probe.\n\
+         # TYPE paracord_db_pool_acquire_wait_last_seconds gauge\n\
+         paracord_db_pool_acquire_wait_last_seconds {pool_wait_last_s}\n\
+         # HELP paracord_db_pool_exhausted_total Pool probes that waited 250ms or more for a connection.\n\
+         # TYPE paracord_db_pool_exhausted_total counter\n\
+         paracord_db_pool_exhausted_total {pool_exhausted}\n\
          # HELP paracord_ws_events_by_type_total Total WebSocket events dispatched by event type.\n\
          # TYPE paracord_ws_events_by_type_total counter\n",
         DURATION_LE_5.load(Ordering::Relaxed),
@@ -809,6 +1313,41 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
             count
         ));
     }
+    body.push_str(
+        "# HELP paracord_ws_payload_bytes Size of READY and GUILD_CREATE dispatches.\n\
+         # TYPE paracord_ws_payload_bytes histogram\n",
+    );
+    for sizes in &ws_snapshot.payload_sizes {
+        let event_type = sizes.event_type;
+        for (le, count) in paracord_core::observability::WS_PAYLOAD_SIZE_BUCKETS
+            .iter()
+            .zip(&sizes.buckets)
+        {
+            body.push_str(&format!(
+                "paracord_ws_payload_bytes_bucket{{event_type=\"{event_type}\",le=\"{le}\"}} {count}\n"
+            ));
+        }
+        body.push_str(&format!(
+            "paracord_ws_payload_bytes_bucket{{event_type=\"{event_type}\",le=\"+Inf\"}} {count}\n\
+             paracord_ws_payload_bytes_sum{{event_type=\"{event_type}\"}} {sum}\n\
+             paracord_ws_payload_bytes_count{{event_type=\"{event_type}\"}} {count}\n",
+            count = sizes.count,
+            sum = sizes.sum_bytes,
+        ));
+    }
+    body.push_str(
+        "# HELP paracord_ws_payload_truncated_total READY and GUILD_CREATE dispatches whose member or presence lists were cut to fit the size budget.\n\
+         # TYPE paracord_ws_payload_truncated_total counter\n",
+    );
+    for sizes in &ws_snapshot.payload_sizes {
+        body.push_str(&format!(
+            "paracord_ws_payload_truncated_total{{event_type=\"{}\"}} {}\n",
+            sizes.event_type, sizes.truncated
+        ));
+    }
+    if let Some(media) = &state.native_media {
+        push_media_metrics(&mut body, media);
+    }
 
     (
         StatusCode::OK,
@@ -817,55 +1356,176 @@ async fn metrics(headers: HeaderMap) -> impl IntoResponse {
     )
 }
 
-struct RateBucket {
-    count: u32,
-    window_start: i64,
+/// Native media relay and transport series. Rates (packets or bytes per
+/// second) come from `rate()` over the `_total` counters.
+fn push_media_metrics(body: &mut String, media: &paracord_core::NativeMediaState) {
+    let relay = media.relay_forwarder.metrics();
+    let transport = paracord_transport::metrics::snapshot();
+    body.push_str(&format!(
+        "# HELP paracord_media_rooms_active Relay rooms with at least one connected participant.\n\
+         # TYPE paracord_media_rooms_active gauge\n\
+         paracord_media_rooms_active {}\n\
+         # HELP paracord_media_participants Participants connected to the relay.\n\
+         # TYPE paracord_media_participants gauge\n\
+         paracord_media_participants {}\n\
+         # HELP paracord_media_forwarded_packets_total Media packets forwarded to recipients.\n\
+         # TYPE paracord_media_forwarded_packets_total counter\n\
+         paracord_media_forwarded_packets_total {}\n\
+         # HELP paracord_media_forwarded_bytes_total Media bytes forwarded to recipients.\n\
+         # TYPE paracord_media_forwarded_bytes_total counter\n\
+         paracord_media_forwarded_bytes_total {}\n\
+         # HELP paracord_media_quic_connections_total Media connections that completed the QUIC handshake.\n\
+         # TYPE paracord_media_quic_connections_total counter\n\
+         paracord_media_quic_connections_total {}\n\
+         # HELP paracord_media_webtransport_sessions_active Open WebTransport sessions, across all connections.\n\
+         # TYPE paracord_media_webtransport_sessions_active gauge\n\
+         paracord_media_webtransport_sessions_active {}\n\
+         # HELP paracord_media_webtransport_sessions_total WebTransport sessions opened since startup.\n\
+         # TYPE paracord_media_webtransport_sessions_total counter\n\
+         paracord_media_webtransport_sessions_total {}\n\
+         # HELP paracord_media_handshake_failures_total Media connection attempts that failed, by stage.\n\
+         # TYPE paracord_media_handshake_failures_total counter\n",
+        relay.active_rooms,
+        relay.participants,
+        relay.forwarded_packets,
+        relay.forwarded_bytes,
+        transport.quic_connections_total,
+        transport.webtransport_sessions_active,
+        transport.webtransport_sessions_total,
+    ));
+    for (stage, count) in transport.handshake_failures {
+        body.push_str(&format!(
+            "paracord_media_handshake_failures_total{{stage=\"{}\"}} {count}\n",
+            stage.as_str()
+        ));
+    }
+    body.push_str(
+        "# HELP paracord_media_room_participants Participants connected to each relay room.\n\
+         # TYPE paracord_media_room_participants gauge\n",
+    );
+    for room in &relay.rooms {
+        body.push_str(&format!(
+            "paracord_media_room_participants{{room=\"{}\"}} {}\n",
+            prometheus_escape_label_value(&room.room_id),
+            room.participants
+        ));
+    }
+    body.push_str(
+        "# HELP paracord_media_room_bandwidth_kbps Relay bandwidth per room over the last second.\n\
+         # TYPE paracord_media_room_bandwidth_kbps gauge\n",
+    );
+    for room in &relay.rooms {
+        let room_id = prometheus_escape_label_value(&room.room_id);
+        body.push_str(&format!(
+            "paracord_media_room_bandwidth_kbps{{room=\"{room_id}\",direction=\"in\"}} {}\n\
+             paracord_media_room_bandwidth_kbps{{room=\"{room_id}\",direction=\"out\"}} {}\n",
+            room.inbound_kbps, room.outbound_kbps
+        ));
+    }
 }
 
-pub struct HttpRateLimiter {
-    buckets: DashMap<String, Mutex<RateBucket>>,
+/// Webhook execution limits apply even when the per-IP limiter is off.
+static WEBHOOK_RATE_LIMITER: OnceLock<rate_limit::Buckets> = OnceLock::new();
+
+fn webhook_rate_limiter() -> &'static rate_limit::Buckets {
+    WEBHOOK_RATE_LIMITER.get_or_init(rate_limit::Buckets::new)
 }
 
-impl HttpRateLimiter {
-    fn new() -> Self {
-        Self {
-            buckets: DashMap::new(),
-        }
+/// Count a webhook execution against `key`; `false` once `max_count` is
+/// exceeded within the window.
+pub(crate) fn check_webhook_rate_limit(key: &str, window_seconds: i64, max_count: u32) -> bool {
+    let allowed = webhook_rate_limiter().check_window(key, window_seconds, max_count);
+    if !allowed {
+        RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
     }
+    allowed
+}
 
-    fn check_rate_limit(&self, key: &str, window_seconds: i64, max_count: u32) -> bool {
-        let now = chrono::Utc::now().timestamp();
-        let bucket = self.buckets.entry(key.to_string()).or_insert_with(|| {
-            Mutex::new(RateBucket {
-                count: 0,
-                window_start: now,
-            })
-        });
-        let mut guard = match bucket.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => poisoned.into_inner(),
-        };
-        if now.saturating_sub(guard.window_start) >= window_seconds {
-            guard.window_start = now;
-            guard.count = 0;
-        }
-        guard.count = guard.count.saturating_add(1);
-        guard.count <= max_count
-    }
+/// Record a rejected webhook execution; `true` once rejections within the
+/// window exceed `max_strikes`.
+pub(crate) fn webhook_abuse_threshold_reached(
+    key: &str,
+    window_seconds: i64,
+    max_strikes: u32,
+) -> bool {
+    !webhook_rate_limiter().check_window(key, window_seconds, max_strikes)
+}
+struct RateLimitOffender {
+    rejected: u64,
+    last_rejected_at: i64,
+}
+
+/// Rejected requests per client IP, pruned with the limiter buckets.
+static RATE_LIMIT_OFFENDERS: OnceLock<DashMap<String, RateLimitOffender>> = OnceLock::new();
 
-    fn cleanup_stale(&self, max_age_seconds: i64) {
-        let now = chrono::Utc::now().timestamp();
-        self.buckets.retain(|_, bucket| {
-            let guard = bucket.get_mut().unwrap();
-            now.saturating_sub(guard.window_start) <= max_age_seconds
+fn rate_limit_offenders() -> &'static DashMap<String, RateLimitOffender> {
+    RATE_LIMIT_OFFENDERS.get_or_init(DashMap::new)
+}
+
+fn record_rate_limited(client_key: &str) {
+    RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
+    let now = chrono::Utc::now().timestamp();
+    let mut offender = rate_limit_offenders()
+        .entry(client_key.to_string())
+        .or_insert(RateLimitOffender {
+            rejected: 0,
+            last_rejected_at: now,
         });
+    offender.rejected = offender.rejected.saturating_add(1);
+    offender.last_rejected_at = now;
+}
+
+pub(crate) struct RateLimitedClient {
+    pub ip: String,
+    pub rejected: u64,
+    pub last_rejected_at: i64,
+}
+
+pub(crate) struct RateLimiterSnapshot {
+    pub enabled: bool,
+    pub requests: u64,
+    pub rate_limited: u64,
+    pub bypassed: u64,
+    pub http_buckets: Vec<(String, usize)>,
+    pub webhook_buckets: Vec<(String, usize)>,
+    /// Clients with the most rejections, most first.
+    pub top_offenders: Vec<RateLimitedClient>,
+}
+
+pub(crate) fn rate_limiter_snapshot(offender_limit: usize) -> RateLimiterSnapshot {
+    let mut top_offenders: Vec<RateLimitedClient> = rate_limit_offenders()
+        .iter()
+        .map(|entry| RateLimitedClient {
+            ip: entry.key().clone(),
+            rejected: entry.rejected,
+            last_rejected_at: entry.last_rejected_at,
+        })
+        .collect();
+    top_offenders.sort_by(|a, b| {
+        b.rejected
+            .cmp(&a.rejected)
+            .then(b.last_rejected_at.cmp(&a.last_rejected_at))
+    });
+    top_offenders.truncate(offender_limit);
+
+    let http = rate_limit::limiter();
+    RateLimiterSnapshot {
+        enabled: http.is_some(),
+        requests: REQUEST_COUNT.load(Ordering::Relaxed),
+        rate_limited: RATE_LIMITED_COUNT.load(Ordering::Relaxed),
+        bypassed: RATE_LIMIT_BYPASSED_COUNT.load(Ordering::Relaxed),
+        http_buckets: http
+            .map(|limiter| limiter.buckets.bucket_counts())
+            .unwrap_or_default(),
+        webhook_buckets: webhook_rate_limiter().bucket_counts(),
+        top_offenders,
     }
 }
 
-static HTTP_RATE_LIMITER: OnceLock<HttpRateLimiter> = OnceLock::new();
 static HTTP_TRACE_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
 static RATE_LIMITED_COUNT: AtomicU64 = AtomicU64::new(0);
+static RATE_LIMIT_BYPASSED_COUNT: AtomicU64 = AtomicU64::new(0);
 
 // ── Observability: request duration histogram buckets ──────────────────────
 // We track durations in discrete buckets (in milliseconds) using atomics.
@@ -886,6 +1546,13 @@ static STATUS_2XX: AtomicU64 = AtomicU64::new(0);
 static STATUS_4XX: AtomicU64 = AtomicU64::new(0);
 static STATUS_5XX: AtomicU64 = AtomicU64::new(0);
 
+// ── Observability: federated file cache counters ───────────────────────────
+pub(crate) static FED_FILE_CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+pub(crate) static FED_FILE_CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+pub(crate) static FED_FILE_CACHE_DEDUPED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static FED_FILE_PREFETCHED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static FED_FILE_PREFETCH_FAILED: AtomicU64 = AtomicU64::new(0);
+
 fn prometheus_escape_label_value(value: &str) -> String {
     let mut escaped = String::with_capacity(value.len());
     for ch in value.chars() {
@@ -944,8 +1611,9 @@ fn record_status_code(status: u16) {
     }
 }
 
+/// Install the HTTP rate limiter with the default limits.
 pub fn install_http_rate_limiter() {
-    let _ = HTTP_RATE_LIMITER.set(HttpRateLimiter::new());
+    rate_limit::install(rate_limit::RateLimits::default());
 }
 
 pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
@@ -955,9 +1623,12 @@ pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
             tokio::select! {
                 _ = shutdown.notified() => break,
                 _ = ticker.tick() => {
-                    if let Some(limiter) = HTTP_RATE_LIMITER.get() {
-                        limiter.cleanup_stale(600);
+                    if let Some(limiter) = rate_limit::limiter() {
+                        limiter.buckets.cleanup_stale(Duration::from_secs(600));
                     }
+                    webhook_rate_limiter().cleanup_stale(Duration::from_secs(600));
+                    let cutoff = chrono::Utc::now().timestamp() - 600;
+                    rate_limit_offenders().retain(|_, offender| offender.last_rejected_at >= cutoff);
                 }
             }
         }
@@ -965,10 +1636,6 @@ pub fn spawn_http_rate_limiter_cleanup(shutdown: Arc<Notify>) {
 }
 
 async fn rate_limit_middleware(req: Request, next: Next) -> Response {
-    const GLOBAL_LIMIT_PER_SECOND: u32 = 120;
-    const AUTH_LIMIT_PER_MINUTE: u32 = 60;
-    const BOT_LIMIT_PER_MINUTE: u32 = 300;
-
     if req.method() == Method::OPTIONS {
         return next.run(req).await;
     }
@@ -982,81 +1649,86 @@ async fn rate_limit_middleware(req: Request, next: Next) -> Response {
     }
 
     REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
-    let is_auth_path = path.starts_with("/api/v1/auth/");
-    let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false);
     let peer_ip = req
         .extensions()
         .get::<ConnectInfo<std::net::SocketAddr>>()
-        .map(|info| info.0.ip().to_string());
-    let trusted_proxy_ips = if trust_proxy {
-        std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-            .ok()
-            .map(|raw| {
-                raw.split(',')
-                    .map(str::trim)
-                    .filter(|v| !v.is_empty())
-                    .map(str::to_string)
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
-    let can_trust_forwarded = trust_proxy
-        && peer_ip
-            .as_deref()
-            .is_some_and(|ip| trusted_proxy_ips.iter().any(|trusted| trusted == ip));
+        .map(|info| info.0.ip());
+    let client_ip = crate::client_ip::resolve(req.headers(), peer_ip);
+    let key = client_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
 
-    let key = if can_trust_forwarded {
-        req.headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.split(',').next())
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .map(str::to_string)
-            .or_else(|| peer_ip.clone())
-            .unwrap_or_else(|| "unknown".to_string())
-    } else {
-        peer_ip.unwrap_or_else(|| "unknown".to_string())
+    let limiter = rate_limit::limiter().filter(|_| {
+        let exempt = crate::rate_limit_bypass::is_exempt(&path, client_ip, req.headers());
+        if exempt {
+            RATE_LIMIT_BYPASSED_COUNT.fetch_add(1, Ordering::Relaxed);
+        }
+        !exempt
+    });
+    let Some(limiter) = limiter else {
+        return crate::client_ip::with_request_client_ip(client_ip, next.run(req)).await;
     };
 
-    if let Some(limiter) = HTTP_RATE_LIMITER.get() {
-        let global_key = format!("http:global:{key}");
-        if !limiter.check_rate_limit(&global_key, 1, GLOBAL_LIMIT_PER_SECOND) {
-            RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-            return crate::error::ApiError::RateLimited.into_response();
-        }
+    let rejected = |decision: rate_limit::Decision| {
+        record_rate_limited(&key);
+        let mut response = crate::error::ApiError::RateLimited.into_response();
+        decision.apply_headers(response.headers_mut());
+        response
+    };
+    let global = limiter.check(rate_limit::RouteClass::Global, &key).await;
+    if !global.allowed {
+        return rejected(global);
+    }
 
-        if let Some(bot_token) = req
-            .headers()
-            .get(header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|raw| raw.strip_prefix("Bot "))
-            .map(str::trim)
-            .filter(|token| !token.is_empty())
-        {
-            let token_hash = paracord_db::bot_applications::hash_token(bot_token);
-            let bot_key = format!("http:bot:{}", &token_hash[..24]);
-            if !limiter.check_rate_limit(&bot_key, 60, BOT_LIMIT_PER_MINUTE) {
-                RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-                return crate::error::ApiError::RateLimited.into_response();
-            }
+    let class = rate_limit::RouteClass::classify(req.method(), &path);
+    if !rate_limit::defer_to_user(class, req.headers(), req.uri()) {
+        let decision = limiter.check(class, &key).await;
+        if !decision.allowed {
+            return rejected(decision);
         }
+        let mut response = crate::client_ip::with_request_client_ip(client_ip, next.run(req)).await;
+        decision.apply_headers(response.headers_mut());
+        return response;
+    }
 
-        if is_auth_path {
-            let auth_key = format!("http:auth:{key}");
-            if !limiter.check_rate_limit(&auth_key, 60, AUTH_LIMIT_PER_MINUTE) {
-                RATE_LIMITED_COUNT.fetch_add(1, Ordering::Relaxed);
-                return crate::error::ApiError::RateLimited.into_response();
+    // Requests with credentials are charged to the user by the auth
+    // extractors; ones that never authenticate fall back to the address.
+    let (mut response, decision) = rate_limit::with_user_check(
+        class,
+        crate::client_ip::with_request_client_ip(client_ip, next.run(req)),
+    )
+    .await;
+    let decision = match decision {
+        Some(decision) => {
+            if !decision.allowed {
+                record_rate_limited(&key);
             }
+            decision
         }
-    }
+        None => limiter.check(class, &key).await,
+    };
+    decision.apply_headers(response.headers_mut());
+    response
+}
 
-    next.run(req).await
+/// Negotiate the response locale from `Accept-Language`. Authenticated
+/// requests switch to the user's saved locale once the auth extractor runs.
+async fn locale_middleware(req: Request, next: Next) -> Response {
+    let locale = paracord_util::i18n::negotiate(
+        None,
+        req.headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok()),
+    );
+    let (mut response, resolved) = crate::middleware::with_request_locale(locale, async move {
+        let response = next.run(req).await;
+        (response, crate::middleware::request_locale())
+    })
+    .await;
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(resolved));
+    headers.append(header::VARY, HeaderValue::from_static("Accept-Language"));
+    response
 }
 
 async fn security_headers_middleware(req: Request, next: Next) -> Response {
@@ -1091,11 +1763,14 @@ async fn security_headers_middleware(req: Request, next: Next) -> Response {
         HeaderName::from_static("cross-origin-resource-policy"),
         HeaderValue::from_static("same-origin"),
     );
-    if path == "/health"
-        || path == "/metrics"
-        || path.starts_with("/api/")
-        || path.starts_with("/_paracord/")
-        || path.starts_with("/.well-known/")
+    // Swagger UI is a page of its own, so it gets the page policy below.
+    let is_api_page = path.starts_with("/api/v1/docs");
+    if !is_api_page
+        && (path == "/health"
+            || path == "/metrics"
+            || path.starts_with("/api/")
+            || path.starts_with("/_paracord/")
+            || path.starts_with("/.well-known/"))
     {
         headers.insert(
             header::CONTENT_SECURITY_POLICY,
@@ -1122,6 +1797,11 @@ async fn security_headers_middleware(req: Request, next: Next) -> Response {
 /// Middleware that records request duration and response status for the /metrics endpoint.
 async fn metrics_middleware(req: Request, next: Next) -> Response {
     let start = Instant::now();
+    let _in_flight = paracord_core::observability::track_request(format!(
+        "{} {}",
+        req.method(),
+        req.uri().path()
+    ));
     let response = next.run(req).await;
     let elapsed_ms = start.elapsed().as_millis() as u64;
     record_request_duration(elapsed_ms);
@@ -0,0 +1,107 @@
+//! Mention fan-out for role and `@everyone`/`@here` mentions.
+//!
+//! Resolving who such a mention reaches, checking each member's settings and
+//! queueing their pushes grows with the guild, so a message that needs it
+//! queues a [`JOB_MENTION_FANOUT`](crate::jobs::JOB_MENTION_FANOUT) job
+//! instead and the send request returns right away. Messages that only name
+//! users are still counted inline.
+
+use paracord_core::jobs::JobOptions;
+use paracord_core::AppState;
+use paracord_db::channels::ChannelRow;
+use paracord_db::jobs::JobRow;
+use paracord_db::messages::MessageRow;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct FanoutJobPayload {
+    message_id: String,
+}
+
+/// Count a new guild message's mentions and queue pushes for them, in a
+/// background job when the mentions fan out. Returns the members notified
+/// inline, for the caller to push to; fanned-out messages return none.
+pub(crate) async fn record_for_message(
+    state: &AppState,
+    channel: &ChannelRow,
+    msg: &MessageRow,
+    content: &str,
+) -> Vec<i64> {
+    if paracord_core::notifications::parse_mentions(content).fans_out() {
+        if let Err(err) = paracord_core::jobs::enqueue(
+            &state.db,
+            crate::jobs::JOB_MENTION_FANOUT,
+            &json!({ "message_id": msg.id.to_string() }),
+            JobOptions {
+                priority: 1,
+                ..JobOptions::default()
+            },
+        )
+        .await
+        {
+            tracing::warn!("Failed to queue mentions for message {}: {}", msg.id, err);
+        }
+        return Vec::new();
+    }
+    match paracord_core::notifications::record_mentions(
+        state,
+        channel,
+        msg.id,
+        msg.author_id,
+        content,
+    )
+    .await
+    {
+        Ok(notified) => notified,
+        Err(err) => {
+            tracing::warn!("Failed to record mentions for message {}: {}", msg.id, err);
+            Vec::new()
+        }
+    }
+}
+
+/// Background job: count a message's mentions and queue pushes to the
+/// members it notified.
+pub(crate) async fn run_fanout_job(state: AppState, job: JobRow) -> Result<(), String> {
+    let payload: FanoutJobPayload = paracord_core::jobs::payload(&job)?;
+    let message_id = payload
+        .message_id
+        .parse::<i64>()
+        .map_err(|_| "Invalid message_id".to_string())?;
+    let Some(msg) = paracord_db::messages::get_message(&state.db, message_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+    let Some(channel) = paracord_db::channels::get_channel(&state.db, msg.channel_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+    let content = msg.content.clone().unwrap_or_default();
+    let recipients =
+        paracord_core::notifications::mention_recipients(&state, &channel, msg.author_id, &content)
+            .await
+            .map_err(|e| e.to_string())?;
+    // Count the mentions and queue their pushes together: a failed attempt
+    // leaves neither behind, and the retry counts and pushes everyone.
+    let mut tx = paracord_db::begin(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let counted = paracord_db::read_states::increment_mention_counts(
+        &mut tx,
+        channel.id,
+        msg.id,
+        &recipients,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    paracord_core::push::queue_message_push_in(&mut tx, &state, &channel, &msg, &counted)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
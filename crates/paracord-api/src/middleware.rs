@@ -1,11 +1,64 @@
 use axum::{
     extract::FromRequestParts,
-    http::{header, request::Parts, Uri},
+    http::{header, request::Parts, HeaderMap, Uri},
 };
 use chrono::Utc;
+use dashmap::DashMap;
 use paracord_core::AppState;
+use paracord_models::permissions::{Permissions, PERMISSIONS_VERSION, PERMISSIONS_VERSION_HEADER};
+use paracord_util::i18n;
+use std::cell::Cell;
+use std::sync::OnceLock;
 
 use crate::error::ApiError;
+use crate::routes::client_updates::ensure_supported_client;
+
+tokio::task_local! {
+    static REQUEST_LOCALE: Cell<&'static str>;
+}
+
+/// Saved locale per user, filled whenever their settings are read or
+/// written, so authenticated requests can honor it without a DB round trip.
+static USER_LOCALES: OnceLock<DashMap<i64, &'static str>> = OnceLock::new();
+
+fn user_locales() -> &'static DashMap<i64, &'static str> {
+    USER_LOCALES.get_or_init(DashMap::new)
+}
+
+/// Locale negotiated for the request being handled, or the default outside
+/// of one.
+pub fn request_locale() -> &'static str {
+    REQUEST_LOCALE
+        .try_with(Cell::get)
+        .unwrap_or(i18n::DEFAULT_LOCALE)
+}
+
+/// Run `fut` with `locale` as the request locale.
+pub async fn with_request_locale<F: std::future::Future>(
+    locale: &'static str,
+    fut: F,
+) -> F::Output {
+    REQUEST_LOCALE.scope(Cell::new(locale), fut).await
+}
+
+/// Record a user's saved locale setting.
+pub fn remember_user_locale(user_id: i64, locale: &str) {
+    match i18n::supported_locale(locale) {
+        Some(locale) => {
+            user_locales().insert(user_id, locale);
+        }
+        None => {
+            user_locales().remove(&user_id);
+        }
+    }
+}
+
+/// Switch the current request to the user's saved locale, if known.
+fn apply_user_locale(user_id: i64) {
+    if let Some(locale) = user_locales().get(&user_id).map(|entry| *entry) {
+        let _ = REQUEST_LOCALE.try_with(|current| current.set(locale));
+    }
+}
 
 pub struct AuthUser {
     pub user_id: i64,
@@ -35,8 +88,8 @@ fn extract_auth_scheme(parts: &Parts) -> Option<AuthScheme<'_>> {
     None
 }
 
-fn get_cookie_value(parts: &Parts, cookie_name: &str) -> Option<String> {
-    let raw = parts.headers.get(header::COOKIE)?.to_str().ok()?;
+fn get_cookie_value(headers: &HeaderMap, cookie_name: &str) -> Option<String> {
+    let raw = headers.get(header::COOKIE)?.to_str().ok()?;
     for part in raw.split(';') {
         let trimmed = part.trim();
         let Some((name, value)) = trimmed.split_once('=') else {
@@ -59,13 +112,20 @@ fn get_query_token(uri: &Uri) -> Option<String> {
     })
 }
 
+/// Whether a request carries credentials the auth extractors would try.
+pub(crate) fn has_credentials(headers: &HeaderMap, uri: &Uri) -> bool {
+    headers.contains_key(header::AUTHORIZATION)
+        || get_cookie_value(headers, ACCESS_COOKIE_NAME).is_some()
+        || get_query_token(uri).is_some()
+}
+
 async fn validate_auth(
     parts: &Parts,
     state: &AppState,
 ) -> Result<paracord_core::auth::Claims, ApiError> {
     let token = match extract_auth_scheme(parts) {
         Some(AuthScheme::Bearer(t)) => t.to_string(),
-        _ => get_cookie_value(parts, ACCESS_COOKIE_NAME)
+        _ => get_cookie_value(&parts.headers, ACCESS_COOKIE_NAME)
             .or_else(|| get_query_token(&parts.uri))
             .ok_or(ApiError::Unauthorized)?,
     };
@@ -118,8 +178,12 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        ensure_supported_client(parts, state)?;
+
         // Try Bearer JWT first, then Bot token.
         if let Ok(claims) = validate_auth(parts, state).await {
+            apply_user_locale(claims.sub);
+            crate::rate_limit::check_user(claims.sub).await?;
             return Ok(AuthUser {
                 user_id: claims.sub,
                 session_id: claims.sid,
@@ -128,6 +192,7 @@ impl FromRequestParts<AppState> for AuthUser {
         }
 
         if let Ok(bot_user_id) = validate_bot_auth(parts, state).await {
+            crate::rate_limit::check_user(bot_user_id).await?;
             return Ok(AuthUser {
                 user_id: bot_user_id,
                 session_id: None,
@@ -151,7 +216,10 @@ impl FromRequestParts<AppState> for AdminUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        ensure_supported_client(parts, state)?;
         let claims = validate_auth(parts, state).await?;
+        apply_user_locale(claims.sub);
+        crate::rate_limit::check_user(claims.sub).await?;
 
         let user = paracord_db::users::get_user_by_id(&state.db, claims.sub)
             .await
@@ -167,3 +235,34 @@ impl FromRequestParts<AppState> for AdminUser {
         })
     }
 }
+
+/// Which permission bit set the client speaks, from the
+/// `X-Paracord-Permissions-Version` header. Clients that don't send it are
+/// assumed to predate `MANAGE_THREADS` and friends.
+#[derive(Debug, Clone, Copy)]
+pub struct PermissionsVersion(pub u32);
+
+impl PermissionsVersion {
+    /// Translate a bitfield from the client into the current set.
+    pub fn normalize(self, bits: i64) -> i64 {
+        if self.0 >= PERMISSIONS_VERSION {
+            bits
+        } else {
+            Permissions::upgrade_legacy(bits)
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for PermissionsVersion {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let version = parts
+            .headers
+            .get(PERMISSIONS_VERSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .unwrap_or(1);
+        Ok(PermissionsVersion(version))
+    }
+}
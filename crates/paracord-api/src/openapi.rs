@@ -0,0 +1,539 @@
+//! OpenAPI description of the HTTP API.
+//!
+//! Handlers carry `#[utoipa::path]` annotations; [`ApiDoc`] collects them
+//! into one document served at `/api/v1/openapi.json`. Most handlers build
+//! their responses with `json!`, so their bodies are documented as free-form
+//! objects while request bodies use the real payload types.
+
+use std::sync::OnceLock;
+
+use axum::{http::header, response::IntoResponse};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::openapi::response::{ResponseBuilder, ResponsesBuilder};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, RefOr};
+use utoipa::{Modify, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::routes;
+
+/// Body of every error response.
+#[derive(Serialize, ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorBody {
+    /// Stable error name, e.g. `missing_permissions`.
+    code: String,
+    /// Numeric form of `code`.
+    error_code: u32,
+    /// Human-readable message in the request locale.
+    message: String,
+    /// Same as `message`; kept for older clients.
+    error: String,
+    /// Structured data for errors that carry any, otherwise `null`.
+    details: Value,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Paracord API"),
+    paths(
+        crate::health,
+        routes::admin::get_stats,
+        routes::admin::get_observability,
+        routes::admin::list_security_events,
+        routes::admin::get_settings,
+        routes::admin::update_settings,
+        routes::branding::upload_branding_logo,
+        routes::branding::delete_branding_logo,
+        routes::admin::list_users,
+        routes::admin::update_user,
+        routes::admin::delete_user,
+        routes::admin::list_guilds,
+        routes::admin::update_guild,
+        routes::admin::delete_guild,
+        routes::admin::list_deleted,
+        routes::admin::restore_deleted,
+        routes::admin::restart_update,
+        routes::url_denylist::list_entries,
+        routes::url_denylist::add_entry,
+        routes::url_denylist::remove_entry,
+        routes::image_hashes::list_entries,
+        routes::image_hashes::add_entries,
+        routes::image_hashes::remove_entry,
+        routes::admin::list_jobs,
+        routes::admin::get_job,
+        routes::admin::cancel_job,
+        routes::admin::start_maintenance_task,
+        routes::admin::retry_job,
+        routes::imports::start_import,
+        routes::supporters::list_supporters,
+        routes::supporters::grant_supporter,
+        routes::supporters::revoke_supporter,
+        routes::supporters::add_supporter_role,
+        routes::supporters::remove_supporter_role,
+        routes::event_log::list_events,
+        routes::event_log::stream_events,
+        routes::admin::create_backup,
+        routes::admin::list_backups,
+        routes::admin::restore_backup,
+        routes::admin::get_database_status,
+        routes::admin::compact_database,
+        routes::admin::download_backup,
+        routes::admin::delete_backup,
+        routes::realtime::create_session,
+        routes::realtime::stream_events,
+        routes::realtime::post_command,
+        routes::realtime::post_ack,
+        routes::federation::well_known,
+        routes::federation::get_keys,
+        routes::federation::ingest_event,
+        routes::federation::get_event,
+        routes::federation::list_events,
+        routes::federation::invite,
+        routes::federation::join,
+        routes::federation::leave,
+        routes::federation::media_token,
+        routes::federation::media_relay,
+        routes::federation::file_token,
+        routes::federation::file_download,
+        routes::federation::search_users,
+        routes::federation::list_servers,
+        routes::federation::add_server,
+        routes::federation::get_server,
+        routes::federation::delete_server,
+        routes::auth::register,
+        routes::auth::login,
+        routes::auth::auth_options,
+        routes::client_updates::get_update_manifest,
+        routes::branding::get_branding,
+        routes::branding::get_branding_logo,
+        routes::auth::refresh,
+        routes::auth::logout,
+        routes::auth::challenge,
+        routes::auth::verify,
+        routes::auth::attach_public_key,
+        routes::auth::list_sessions,
+        routes::auth::revoke_session,
+        routes::auth::approve_session_device,
+        routes::auth::deny_session_device,
+        routes::auth::recover_device,
+        routes::auth::regenerate_device_recovery_code,
+        routes::users::get_me,
+        routes::users::update_me,
+        routes::users::delete_me,
+        routes::supporters::get_my_perks,
+        routes::users::get_settings,
+        routes::users::update_settings,
+        routes::users::change_password,
+        routes::users::change_email,
+        routes::users::get_age_gate,
+        routes::users::acknowledge_age_gate,
+        routes::users::list_notes,
+        routes::users::get_note,
+        routes::users::update_note,
+        routes::reminders::list_reminders,
+        routes::reminders::create_reminder,
+        routes::reminders::delete_reminder,
+        routes::notification_settings::get_notification_settings,
+        routes::notification_settings::update_notification_settings,
+        routes::push_subscriptions::list_push_subscriptions,
+        routes::push_subscriptions::create_push_subscription,
+        routes::push_subscriptions::delete_push_subscription,
+        routes::users::export_my_data,
+        routes::users::export_identity,
+        routes::users::import_identity,
+        routes::users::upload_avatar,
+        routes::users::search_users,
+        routes::users::get_user_profile,
+        routes::users::get_user_avatar,
+        routes::guilds::list_guilds,
+        routes::dms::list_dms,
+        routes::dms::create_dm,
+        routes::dms::list_dm_requests,
+        routes::dms::accept_dm_request,
+        routes::dms::decline_dm_request,
+        routes::dms::update_group_dm,
+        routes::dms::leave_group_dm,
+        routes::dms::add_group_dm_recipient,
+        routes::dms::remove_group_dm_recipient,
+        routes::dms::transfer_group_dm_owner,
+        routes::dms::get_group_dm_keys,
+        routes::dms::list_group_dm_sender_keys,
+        routes::dms::upload_group_dm_sender_keys,
+        routes::dms::list_dm_receipts,
+        routes::users::get_read_states,
+        routes::guilds::create_guild,
+        routes::guilds::get_guild,
+        routes::guilds::update_guild,
+        routes::guilds::upload_guild_icon,
+        routes::guilds::get_guild_icon,
+        routes::guilds::delete_guild,
+        routes::guilds::sync_guild,
+        routes::guilds::search_guild_messages,
+        routes::guilds::transfer_ownership,
+        routes::guilds::get_channels,
+        routes::channels::create_channel,
+        routes::guilds::update_channel_positions,
+        routes::members::list_members,
+        routes::members::list_presences,
+        routes::members::update_member,
+        routes::members::kick_member,
+        routes::members::list_member_notes,
+        routes::members::create_member_note,
+        routes::members::delete_member_note,
+        routes::members::leave_guild,
+        routes::members::add_self_role,
+        routes::members::remove_self_role,
+        routes::bans::list_bans,
+        routes::bans::ban_member,
+        routes::bans::unban_member,
+        routes::roles::list_roles,
+        routes::roles::create_role,
+        routes::roles::update_role,
+        routes::roles::delete_role,
+        routes::invites::list_guild_invites,
+        routes::emojis::list_guild_emojis,
+        routes::emojis::create_emoji,
+        routes::emojis::get_emoji_analytics,
+        routes::emojis::get_unused_emojis,
+        routes::emojis::update_emoji,
+        routes::emojis::delete_emoji,
+        routes::emojis::get_emoji_image,
+        routes::tags::list_tags,
+        routes::tags::create_tag,
+        routes::tags::get_tag,
+        routes::tags::update_tag,
+        routes::tags::delete_tag,
+        routes::branding::get_guild_theme,
+        routes::branding::update_guild_theme,
+        routes::branding::upload_guild_theme_asset,
+        routes::branding::get_guild_theme_asset,
+        routes::branding::delete_guild_theme_asset,
+        routes::webhooks::list_guild_webhooks,
+        routes::webhooks::create_webhook,
+        routes::events::list_events,
+        routes::events::create_event,
+        routes::events::get_calendar_token,
+        routes::events::calendar_feed,
+        routes::commands::list_guild_available_commands_handler,
+        routes::events::add_rsvp,
+        routes::events::remove_rsvp,
+        routes::bots::list_guild_bots,
+        routes::events::get_event,
+        routes::events::update_event,
+        routes::events::delete_event,
+        routes::bots::remove_guild_bot,
+        routes::guilds::get_storage,
+        routes::guilds::update_storage,
+        routes::guilds::get_thread_policy,
+        routes::guilds::update_thread_policy,
+        routes::mod_log::get_mod_log,
+        routes::mod_log::update_mod_log,
+        routes::mod_log::delete_mod_log,
+        routes::widgets::get_widget_settings,
+        routes::widgets::update_widget_settings,
+        routes::widgets::get_widget,
+        routes::verification::get_verification_hook,
+        routes::verification::update_verification_hook,
+        routes::verification::delete_verification_hook,
+        routes::verification::hook_callback,
+        routes::verification::list_join_requests,
+        routes::verification::decide_join_request,
+        routes::guilds::list_files,
+        routes::guilds::delete_files,
+        routes::audit_logs::get_audit_logs,
+        routes::channels::get_channel,
+        routes::channels::update_channel,
+        routes::channels::delete_channel,
+        routes::channels::archive_channel,
+        routes::channels::unarchive_channel,
+        routes::channel_exports::list_exports,
+        routes::channel_exports::create_export,
+        routes::channel_exports::get_export,
+        routes::channel_exports::download_export,
+        routes::channels::get_messages,
+        routes::channels::send_message,
+        routes::channels::search_messages,
+        routes::channels::bulk_delete_messages,
+        routes::channels::edit_message,
+        routes::channels::delete_message,
+        routes::channels::create_poll,
+        routes::channels::get_poll,
+        routes::channels::add_poll_vote,
+        routes::channels::remove_poll_vote,
+        routes::channels::get_pins,
+        routes::channels::pin_message,
+        routes::channels::unpin_message,
+        routes::channels::typing,
+        routes::channels::update_read_state,
+        routes::channels::list_channel_overwrites,
+        routes::channels::replace_channel_overwrites,
+        routes::channels::upsert_channel_overwrite,
+        routes::channels::delete_channel_overwrite,
+        routes::channels::add_reaction,
+        routes::channels::remove_reaction,
+        routes::webhooks::list_channel_webhooks,
+        routes::channels::create_thread,
+        routes::channels::get_threads,
+        routes::channels::get_archived_threads,
+        routes::channels::update_thread,
+        routes::channels::delete_thread,
+        routes::channels::get_forum_posts,
+        routes::channels::create_forum_post,
+        routes::channels::list_forum_tags,
+        routes::channels::create_forum_tag,
+        routes::channels::delete_forum_tag,
+        routes::channels::update_forum_sort_order,
+        routes::invites::create_invite,
+        routes::invites::get_invite,
+        routes::invites::accept_invite,
+        routes::invites::delete_invite,
+        routes::webhooks::get_webhook,
+        routes::webhooks::update_webhook,
+        routes::webhooks::delete_webhook,
+        routes::supporters::kofi_webhook,
+        routes::supporters::patreon_webhook,
+        routes::webhooks::execute_webhook,
+        routes::discovery::list_discoverable_guilds,
+        routes::bots::list_bot_applications,
+        routes::bots::create_bot_application,
+        routes::bots::get_bot_application,
+        routes::bots::update_bot_application,
+        routes::bots::delete_bot_application,
+        routes::bots::get_public_bot_application,
+        routes::bots::regenerate_bot_token,
+        routes::bots::list_bot_application_installs,
+        routes::commands::list_global_commands,
+        routes::commands::create_global_command,
+        routes::commands::bulk_overwrite_global_commands,
+        routes::commands::get_global_command,
+        routes::commands::update_global_command,
+        routes::commands::delete_global_command,
+        routes::commands::list_guild_commands,
+        routes::commands::create_guild_command,
+        routes::commands::bulk_overwrite_guild_commands,
+        routes::commands::get_guild_command,
+        routes::commands::update_guild_command,
+        routes::commands::delete_guild_command,
+        routes::interactions::invoke_interaction,
+        routes::interactions::interaction_callback,
+        routes::interactions::get_original_response,
+        routes::interactions::edit_original_response,
+        routes::interactions::delete_original_response,
+        routes::interactions::create_followup_message,
+        routes::interactions::get_followup_message,
+        routes::interactions::edit_followup_message,
+        routes::interactions::delete_followup_message,
+        routes::bots::oauth2_authorize,
+        routes::keys::upload_keys,
+        routes::keys::get_key_count,
+        routes::keys::get_keys,
+        routes::voice::join_voice,
+        routes::voice::start_stream,
+        routes::recordings::list_recordings,
+        routes::recordings::start_recording,
+        routes::recordings::stop_recording,
+        routes::whip::list_stream_keys,
+        routes::whip::create_stream_key,
+        routes::whip::delete_stream_key,
+        routes::whip::whip_publish,
+        routes::whip::whip_stop,
+        routes::whip::whep_play,
+        routes::whip::whep_stop,
+        routes::voice::stop_stream,
+        routes::voice::leave_voice,
+        routes::voice::livekit_webhook,
+        routes::voice::update_channel_voice_stack,
+        routes::voice::get_guild_voice_stacks,
+        routes::voice::migrate_guild_voice_stacks,
+        routes::voice_v2::join_voice_v2,
+        routes::voice_v2::leave_voice_v2,
+        routes::voice_v2::update_voice_state_v2,
+        routes::voice_v2::recover_voice_v2,
+        routes::voice_v2::get_voice_stats_v2,
+        routes::files::upload_file,
+        routes::files::download_file,
+        routes::files::delete_file,
+        routes::files::download_transcoded_file,
+        routes::files::download_thumbnail,
+        routes::files::upload_token,
+        routes::files::download_federated_file,
+        routes::relationships::list_relationships,
+        routes::relationships::add_friend,
+        routes::relationships::accept_friend,
+        routes::relationships::remove_relationship,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&SecurityAddon, &ErrorResponses),
+)]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        // Bots send `Authorization: Bot <token>`.
+        components.add_security_scheme(
+            "bot",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+        );
+    }
+}
+
+/// Adds the shared error responses to every operation so handlers only
+/// document their success case.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let error = |description: &str| {
+            ResponseBuilder::new()
+                .description(description)
+                .content(
+                    "application/json",
+                    ContentBuilder::new()
+                        .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+                            "ErrorBody",
+                        ))))
+                        .build(),
+                )
+                .build()
+        };
+        let defaults = ResponsesBuilder::new()
+            .response("4XX", error("Client error"))
+            .response("5XX", error("Server error"))
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                for (status, response) in &defaults.responses {
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.clone())
+                        .or_insert_with(|| response.clone());
+                }
+            }
+        }
+    }
+}
+
+/// `GET /api/v1/openapi.json`. The document is built once and reused.
+pub async fn openapi_json() -> impl IntoResponse {
+    static DOCUMENT: OnceLock<String> = OnceLock::new();
+    let body = DOCUMENT.get_or_init(|| {
+        ApiDoc::openapi()
+            .to_json()
+            .expect("OpenAPI document serializes")
+    });
+    ([(header::CONTENT_TYPE, "application/json")], body.as_str())
+}
+
+/// Swagger UI at `/api/v1/docs`, reading the document served by
+/// [`openapi_json`].
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/api/v1/docs").config(utoipa_swagger_ui::Config::from("/api/v1/openapi.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(path, method)` for every `.route(...)` registered in lib.rs.
+    fn registered_routes() -> Vec<(String, &'static str)> {
+        let source = include_str!("lib.rs");
+        let mut routes = Vec::new();
+        for chunk in source.split(".route(").skip(1) {
+            let path = chunk.split('"').nth(1).unwrap().to_string();
+            // The route call ends at its closing paren.
+            let mut depth = 1;
+            let end = chunk
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|(i, _)| i)
+                .unwrap();
+            let handlers = &chunk[..end];
+            for method in ["get", "post", "put", "patch", "delete"] {
+                let called = handlers.match_indices(&format!("{method}(")).any(|(i, _)| {
+                    !handlers[..i]
+                        .chars()
+                        .last()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                });
+                if called {
+                    routes.push((path.clone(), method));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let doc = ApiDoc::openapi();
+        let undocumented = [
+            "/health",
+            "/metrics",
+            "/api/v1/metrics",
+            "/api/v1/openapi.json",
+        ];
+        let routes = registered_routes();
+        assert!(routes.len() > 250);
+        for (path, method) in routes {
+            if undocumented.contains(&path.as_str()) {
+                continue;
+            }
+            let item = doc
+                .paths
+                .paths
+                .get(&path)
+                .unwrap_or_else(|| panic!("{path} is not documented"));
+            let operation = match method {
+                "get" => &item.get,
+                "post" => &item.post,
+                "put" => &item.put,
+                "patch" => &item.patch,
+                _ => &item.delete,
+            };
+            assert!(operation.is_some(), "{method} {path} is not documented");
+        }
+    }
+
+    #[test]
+    fn document_declares_auth_and_errors() {
+        let json: Value = serde_json::from_str(&ApiDoc::openapi().to_json().unwrap()).unwrap();
+        assert!(json["openapi"].as_str().unwrap().starts_with("3.1"));
+        let schemes = &json["components"]["securitySchemes"];
+        assert_eq!(schemes["bearer"]["scheme"], "bearer");
+        assert_eq!(schemes["bot"]["in"], "header");
+        let login = &json["paths"]["/api/v1/auth/login"]["post"];
+        assert!(login["requestBody"].is_object());
+        assert!(login["responses"]["4XX"].is_object());
+    }
+}
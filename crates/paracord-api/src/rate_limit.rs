@@ -0,0 +1,549 @@
+//! HTTP rate limits.
+//!
+//! Requests draw from token buckets that hold up to `burst` requests and
+//! refill at `per_minute`. Every request counts against its client address
+//! in the `global` class, then against the bucket of its route class. Route
+//! classes other than `auth` are keyed by user once the request
+//! authenticates, so clients sharing an address don't share a budget.
+//!
+//! Responses carry `X-RateLimit-Limit`, `X-RateLimit-Remaining`,
+//! `X-RateLimit-Reset` (Unix time at which the bucket is full again) and
+//! `X-RateLimit-Bucket`; rejected requests also get `Retry-After`.
+//!
+//! Clustered servers share their buckets through Redis (see
+//! [`share_buckets`]) and fall back to local ones while it is unreachable.
+
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method, Uri};
+use dashmap::DashMap;
+use paracord_core::cluster::Cluster;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+pub const LIMIT_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+pub const REMAINING_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+pub const RESET_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+pub const BUCKET_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-bucket");
+
+/// Route classes with their own buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// Every request, per client address.
+    Global,
+    /// `/api/v1/auth/*`, per client address.
+    Auth,
+    /// Sending messages.
+    Messages,
+    /// Attachments, avatars, emoji and other uploads.
+    Uploads,
+    /// Everything else.
+    Api,
+}
+
+impl RouteClass {
+    pub const ALL: [RouteClass; 5] = [
+        RouteClass::Global,
+        RouteClass::Auth,
+        RouteClass::Messages,
+        RouteClass::Uploads,
+        RouteClass::Api,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RouteClass::Global => "global",
+            RouteClass::Auth => "auth",
+            RouteClass::Messages => "messages",
+            RouteClass::Uploads => "uploads",
+            RouteClass::Api => "api",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.name() == name.trim())
+    }
+
+    pub fn default_limit(self) -> RouteLimit {
+        let (burst, per_minute) = match self {
+            RouteClass::Global => (120, 7200),
+            RouteClass::Auth => (60, 60),
+            RouteClass::Messages => (30, 120),
+            RouteClass::Uploads => (10, 60),
+            RouteClass::Api => (200, 3000),
+        };
+        RouteLimit { burst, per_minute }
+    }
+
+    /// Whether the class is keyed by user once the request authenticates.
+    fn per_user(self) -> bool {
+        !matches!(self, RouteClass::Global | RouteClass::Auth)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The class a request falls into, besides `global`.
+    pub fn classify(method: &Method, path: &str) -> Self {
+        if path.starts_with("/api/v1/auth/") {
+            return RouteClass::Auth;
+        }
+        if *method == Method::GET || *method == Method::HEAD {
+            return RouteClass::Api;
+        }
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["", "api", _, "channels", _, "messages"] if *method == Method::POST => {
+                RouteClass::Messages
+            }
+            ["", "api", _, "channels", _, "attachments" | "upload-token"]
+            | ["", "api", _, "users", "@me", "avatar"]
+            | ["", "api", _, "guilds", _, "emojis"]
+            | ["", "api", _, "guilds", _, "theme", "assets"]
+            | ["", "api", _, "admin", "branding", "logo"] => RouteClass::Uploads,
+            _ => RouteClass::Api,
+        }
+    }
+}
+
+/// Bucket size and refill rate for a route class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RouteLimit {
+    /// Parse `burst/per_minute`, e.g. `30/120`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (burst, per_minute) = raw.trim().split_once('/')?;
+        let limit = RouteLimit {
+            burst: burst.trim().parse().ok()?,
+            per_minute: per_minute.trim().parse().ok()?,
+        };
+        limit.is_valid().then_some(limit)
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.burst > 0 && self.per_minute > 0
+    }
+
+    fn refill_per_second(self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+impl std::fmt::Display for RouteLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.burst, self.per_minute)
+    }
+}
+
+/// Parse a comma separated `class=burst/per_minute` list, as used by the
+/// `rate_limits` server setting and `PARACORD_RATE_LIMITS`.
+pub fn parse_limits(spec: &str) -> Result<Vec<(RouteClass, RouteLimit)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, limit) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("'{entry}' is not class=burst/per_minute"))?;
+            let class = RouteClass::from_name(name)
+                .ok_or_else(|| format!("unknown rate limit class '{}'", name.trim()))?;
+            let limit = RouteLimit::parse(limit).ok_or_else(|| {
+                format!(
+                    "'{}' needs a positive burst/per_minute, e.g. 30/120",
+                    name.trim()
+                )
+            })?;
+            Ok((class, limit))
+        })
+        .collect()
+}
+
+/// Limits for every route class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits([RouteLimit; RouteClass::ALL.len()]);
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self(RouteClass::ALL.map(RouteClass::default_limit))
+    }
+}
+
+impl RateLimits {
+    pub fn get(&self, class: RouteClass) -> RouteLimit {
+        self.0[class.index()]
+    }
+
+    pub fn set(&mut self, class: RouteClass, limit: RouteLimit) {
+        self.0[class.index()] = limit;
+    }
+
+    fn with_overrides(mut self, overrides: &[(RouteClass, RouteLimit)]) -> Self {
+        for (class, limit) in overrides {
+            self.set(*class, *limit);
+        }
+        self
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of drawing from a bucket.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Decision {
+    pub class: RouteClass,
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the bucket is full again.
+    pub reset_after: Duration,
+    /// Time until the next request would be allowed, once rejected.
+    pub retry_after: Option<Duration>,
+}
+
+impl Decision {
+    fn from_draw(class: RouteClass, limit: RouteLimit, allowed: bool, tokens: f64) -> Self {
+        let capacity = f64::from(limit.burst);
+        let rate = limit.refill_per_second();
+        Self {
+            class,
+            allowed,
+            limit: limit.burst,
+            remaining: tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64(((capacity - tokens) / rate).max(0.0)),
+            retry_after: (!allowed).then(|| Duration::from_secs_f64((1.0 - tokens) / rate)),
+        }
+    }
+
+    pub(crate) fn apply_headers(&self, headers: &mut HeaderMap) {
+        let reset_at =
+            chrono::Utc::now().timestamp() + self.reset_after.as_secs_f64().ceil() as i64;
+        headers.insert(LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(REMAINING_HEADER, HeaderValue::from(self.remaining));
+        headers.insert(RESET_HEADER, HeaderValue::from(reset_at));
+        headers.insert(BUCKET_HEADER, HeaderValue::from_static(self.class.name()));
+        if let Some(retry_after) = self.retry_after {
+            let seconds = (retry_after.as_secs_f64().ceil() as u64).max(1);
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+    }
+}
+
+/// Token buckets by key.
+pub(crate) struct Buckets {
+    buckets: DashMap<String, Mutex<TokenBucket>>,
+}
+
+impl Buckets {
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Refill the bucket for `key` and take a token if one is available.
+    /// Returns whether it was taken and the tokens left.
+    fn draw(&self, key: &str, capacity: f64, rate: f64, now: Instant) -> (bool, f64) {
+        let bucket = self.buckets.entry(key.to_string()).or_insert_with(|| {
+            Mutex::new(TokenBucket {
+                tokens: capacity,
+                updated: now,
+            })
+        });
+        let mut guard = match bucket.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let elapsed = now.saturating_duration_since(guard.updated).as_secs_f64();
+        guard.tokens = (guard.tokens + elapsed * rate).min(capacity);
+        guard.updated = now;
+        let allowed = guard.tokens >= 1.0;
+        if allowed {
+            guard.tokens -= 1.0;
+        }
+        (allowed, guard.tokens)
+    }
+
+    fn take(&self, key: &str, class: RouteClass, limit: RouteLimit, now: Instant) -> Decision {
+        let (allowed, tokens) =
+            self.draw(key, f64::from(limit.burst), limit.refill_per_second(), now);
+        Decision::from_draw(class, limit, allowed, tokens)
+    }
+
+    /// Allow `max_count` requests per `window_seconds` for `key`, refilled
+    /// evenly over the window.
+    pub(crate) fn check_window(&self, key: &str, window_seconds: i64, max_count: u32) -> bool {
+        let capacity = f64::from(max_count.max(1));
+        let rate = capacity / window_seconds.max(1) as f64;
+        self.draw(key, capacity, rate, Instant::now()).0
+    }
+
+    /// Live buckets grouped by key scope (`http:global`, `webhook:minute`, ...).
+    pub(crate) fn bucket_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for entry in self.buckets.iter() {
+            let key = entry.key();
+            let scope = key
+                .match_indices(':')
+                .nth(1)
+                .map_or(key.as_str(), |(index, _)| &key[..index]);
+            *counts.entry(scope.to_string()).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort();
+        counts
+    }
+
+    /// Drop buckets untouched for `max_age`; by then they have refilled.
+    pub(crate) fn cleanup_stale(&self, max_age: Duration) {
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| {
+            let guard = bucket
+                .get_mut()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            now.saturating_duration_since(guard.updated) <= max_age
+        });
+    }
+}
+
+/// The HTTP limiter: configured limits, runtime overrides and buckets.
+pub struct HttpRateLimiter {
+    configured: RateLimits,
+    limits: RwLock<RateLimits>,
+    pub(crate) buckets: Buckets,
+    /// Cluster holding the shared buckets, when there is one.
+    shared: OnceLock<Arc<Cluster>>,
+}
+
+impl HttpRateLimiter {
+    fn new(configured: RateLimits) -> Self {
+        Self {
+            configured,
+            limits: RwLock::new(configured),
+            buckets: Buckets::new(),
+            shared: OnceLock::new(),
+        }
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        *self
+            .limits
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Replace the runtime overrides layered over the configured limits.
+    pub fn set_overrides(&self, overrides: &[(RouteClass, RouteLimit)]) {
+        let limits = self.configured.with_overrides(overrides);
+        *self
+            .limits
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = limits;
+    }
+
+    /// Draw from the `class` bucket for a client address or `user-{id}`.
+    pub(crate) async fn check(&self, class: RouteClass, subject: &str) -> Decision {
+        let key = format!("http:{}:{subject}", class.name());
+        let limit = self.limits().get(class);
+        if let Some(cluster) = self.shared.get() {
+            let drawn = cluster
+                .take_token(&key, f64::from(limit.burst), limit.refill_per_second())
+                .await;
+            match drawn {
+                Ok((allowed, tokens)) => return Decision::from_draw(class, limit, allowed, tokens),
+                Err(err) => warn_shared_unavailable(&err),
+            }
+        }
+        self.buckets.take(&key, class, limit, Instant::now())
+    }
+}
+
+/// Log a failed shared draw, at most once a minute.
+fn warn_shared_unavailable(err: &dyn std::fmt::Display) {
+    static LAST_WARNED: AtomicI64 = AtomicI64::new(0);
+    let now = chrono::Utc::now().timestamp();
+    let last = LAST_WARNED.load(Ordering::Relaxed);
+    if now - last >= 60
+        && LAST_WARNED
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    {
+        tracing::warn!("Shared rate limit buckets unavailable, using local ones: {err}");
+    }
+}
+
+static HTTP_RATE_LIMITER: OnceLock<HttpRateLimiter> = OnceLock::new();
+
+/// Install the HTTP limiter with `limits`. Only the first call takes effect.
+pub fn install(limits: RateLimits) {
+    let _ = HTTP_RATE_LIMITER.set(HttpRateLimiter::new(limits));
+}
+
+pub fn limiter() -> Option<&'static HttpRateLimiter> {
+    HTTP_RATE_LIMITER.get()
+}
+
+/// Draw HTTP rate limits from `cluster`'s shared buckets.
+pub fn share_buckets(cluster: Arc<Cluster>) {
+    if let Some(limiter) = limiter() {
+        let _ = limiter.shared.set(cluster);
+    }
+}
+
+/// Apply the `rate_limits` server setting. An empty value restores the
+/// configured limits.
+pub fn apply_setting(spec: &str) -> Result<(), String> {
+    let overrides = parse_limits(spec)?;
+    if let Some(limiter) = limiter() {
+        limiter.set_overrides(&overrides);
+    }
+    Ok(())
+}
+
+/// Per-request state shared between the middleware and the auth extractors.
+struct PendingCheck {
+    class: Cell<Option<RouteClass>>,
+    decision: Cell<Option<Decision>>,
+}
+
+tokio::task_local! {
+    static PENDING_CHECK: PendingCheck;
+}
+
+/// Run `fut` with the `class` check deferred until the request
+/// authenticates. Returns the decision made by [`check_user`], if any.
+pub(crate) async fn with_user_check<F: std::future::Future>(
+    class: RouteClass,
+    fut: F,
+) -> (F::Output, Option<Decision>) {
+    let pending = PendingCheck {
+        class: Cell::new(Some(class)),
+        decision: Cell::new(None),
+    };
+    PENDING_CHECK
+        .scope(pending, async move {
+            let output = fut.await;
+            (output, PENDING_CHECK.with(|pending| pending.decision.get()))
+        })
+        .await
+}
+
+/// Charge the deferred route class check to `user_id`. Called by the auth
+/// extractors; only the first call per request counts.
+pub(crate) async fn check_user(user_id: i64) -> Result<(), crate::error::ApiError> {
+    let Some(limiter) = limiter() else {
+        return Ok(());
+    };
+    let Ok(Some(class)) = PENDING_CHECK.try_with(|pending| pending.class.take()) else {
+        return Ok(());
+    };
+    let decision = limiter.check(class, &format!("user-{user_id}")).await;
+    let _ = PENDING_CHECK.try_with(|pending| pending.decision.set(Some(decision)));
+    if decision.allowed {
+        Ok(())
+    } else {
+        Err(crate::error::ApiError::RateLimited)
+    }
+}
+
+/// Whether the class is worth deferring for this request: it is keyed by
+/// user and the request carries credentials.
+pub(crate) fn defer_to_user(class: RouteClass, headers: &HeaderMap, uri: &Uri) -> bool {
+    class.per_user() && crate::middleware::has_credentials(headers, uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_allow_a_burst_then_refill_at_the_sustained_rate() {
+        let buckets = Buckets::new();
+        let limit = RouteLimit {
+            burst: 3,
+            per_minute: 60,
+        };
+        let start = Instant::now();
+        for remaining in [2, 1, 0] {
+            let decision = buckets.take("k", RouteClass::Api, limit, start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+        let rejected = buckets.take("k", RouteClass::Api, limit, start);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.retry_after, Some(Duration::from_secs(1)));
+        assert_eq!(rejected.reset_after, Duration::from_secs(3));
+
+        let later = buckets.take("k", RouteClass::Api, limit, start + Duration::from_secs(1));
+        assert!(later.allowed);
+        assert_eq!(later.remaining, 0);
+        let full = buckets.take("k", RouteClass::Api, limit, start + Duration::from_secs(60));
+        assert_eq!(full.remaining, 2);
+    }
+
+    #[test]
+    fn routes_are_classified_by_method_and_path() {
+        let classify = RouteClass::classify;
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/auth/login"),
+            RouteClass::Auth
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/channels/1/messages"),
+            RouteClass::Messages
+        );
+        assert_eq!(
+            classify(&Method::GET, "/api/v1/channels/1/messages"),
+            RouteClass::Api
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/v1/channels/1/attachments"),
+            RouteClass::Uploads
+        );
+        assert_eq!(
+            classify(&Method::PUT, "/api/v1/users/@me/avatar"),
+            RouteClass::Uploads
+        );
+        assert_eq!(
+            classify(&Method::PATCH, "/api/v1/users/@me"),
+            RouteClass::Api
+        );
+    }
+
+    #[test]
+    fn limit_specs_are_parsed() {
+        assert_eq!(
+            parse_limits(" messages = 5/30, uploads=2/10 ,").unwrap(),
+            vec![
+                (
+                    RouteClass::Messages,
+                    RouteLimit {
+                        burst: 5,
+                        per_minute: 30
+                    }
+                ),
+                (
+                    RouteClass::Uploads,
+                    RouteLimit {
+                        burst: 2,
+                        per_minute: 10
+                    }
+                ),
+            ]
+        );
+        assert!(parse_limits("").unwrap().is_empty());
+        assert!(parse_limits("messages=0/30").is_err());
+        assert!(parse_limits("typing=5/30").is_err());
+        assert!(parse_limits("messages").is_err());
+    }
+}
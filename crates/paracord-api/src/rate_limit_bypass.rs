@@ -0,0 +1,166 @@
+//! Exemptions from the HTTP rate limiter.
+//!
+//! Monitoring probes, load balancer health checks and internal services can
+//! otherwise use up a small instance's per-address budget. A request skips
+//! the limiter when its client address is allowlisted, when it presents an
+//! allowlisted token in the [`BYPASS_HEADER`] header, or when its path is
+//! exempt. Path entries match exactly, or as a prefix when they end in `*`.
+
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use crate::client_ip::IpRange;
+
+/// Header carrying a rate limit bypass token.
+pub const BYPASS_HEADER: &str = "x-paracord-ratelimit-bypass";
+
+static BYPASS: OnceLock<RateLimitBypass> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathRule {
+    Exact(String),
+    Prefix(String),
+}
+
+impl PathRule {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if !raw.starts_with('/') {
+            return None;
+        }
+        Some(match raw.strip_suffix('*') {
+            Some(prefix) => PathRule::Prefix(prefix.to_string()),
+            None => PathRule::Exact(raw.to_string()),
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            PathRule::Exact(exact) => path == exact,
+            PathRule::Prefix(prefix) => path.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// Addresses, tokens and paths that are not rate limited.
+#[derive(Debug, Default)]
+pub struct RateLimitBypass {
+    ips: Vec<IpRange>,
+    token_digests: Vec<[u8; 32]>,
+    paths: Vec<PathRule>,
+}
+
+impl RateLimitBypass {
+    /// Build from addresses or CIDR ranges, tokens and path rules. Entries
+    /// that don't parse are logged and skipped.
+    pub fn new(ips: &[String], tokens: &[String], paths: &[String]) -> Self {
+        let ips = ips
+            .iter()
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let range = IpRange::parse(entry);
+                if range.is_none() {
+                    tracing::warn!("Ignoring invalid rate limit allowlist address '{}'", entry);
+                }
+                range
+            })
+            .collect();
+        let token_digests = tokens
+            .iter()
+            .map(|token| token.trim())
+            .filter(|token| !token.is_empty())
+            .map(token_digest)
+            .collect();
+        let paths = paths
+            .iter()
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let rule = PathRule::parse(entry);
+                if rule.is_none() {
+                    tracing::warn!("Ignoring invalid rate limit exempt path '{}'", entry);
+                }
+                rule
+            })
+            .collect();
+        Self {
+            ips,
+            token_digests,
+            paths,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ips.is_empty() && self.token_digests.is_empty() && self.paths.is_empty()
+    }
+
+    fn matches(&self, path: &str, client_ip: Option<IpAddr>, headers: &HeaderMap) -> bool {
+        if self.paths.iter().any(|rule| rule.matches(path)) {
+            return true;
+        }
+        if client_ip.is_some_and(|ip| self.ips.iter().any(|range| range.contains(ip))) {
+            return true;
+        }
+        if self.token_digests.is_empty() {
+            return false;
+        }
+        headers
+            .get(BYPASS_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .is_some_and(|token| self.token_digests.contains(&token_digest(token)))
+    }
+}
+
+/// Install the exemptions used by the HTTP rate limiter. Only the first
+/// call takes effect.
+pub fn install(bypass: RateLimitBypass) {
+    let _ = BYPASS.set(bypass);
+}
+
+/// Whether a request should skip the HTTP rate limiter.
+pub(crate) fn is_exempt(path: &str, client_ip: Option<IpAddr>, headers: &HeaderMap) -> bool {
+    BYPASS
+        .get()
+        .is_some_and(|bypass| bypass.matches(path, client_ip, headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn allowlisted_addresses_tokens_and_paths_are_exempt() {
+        let bypass = RateLimitBypass::new(
+            &strings(&["10.0.0.0/8", "not-an-ip"]),
+            &strings(&["probe-secret"]),
+            &strings(&["/health", "/metrics*", "relative"]),
+        );
+        let none = HeaderMap::new();
+        let outside: IpAddr = "192.0.2.1".parse().unwrap();
+
+        assert!(bypass.matches("/health", Some(outside), &none));
+        assert!(!bypass.matches("/healthz", Some(outside), &none));
+        assert!(bypass.matches("/metrics/extra", Some(outside), &none));
+        assert!(bypass.matches("/api/v1/users/@me", "10.1.2.3".parse().ok(), &none));
+        assert!(!bypass.matches("/api/v1/users/@me", Some(outside), &none));
+        assert!(!bypass.matches("/api/v1/users/@me", None, &none));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(BYPASS_HEADER, HeaderValue::from_static("probe-secret"));
+        assert!(bypass.matches("/api/v1/users/@me", Some(outside), &headers));
+        headers.insert(BYPASS_HEADER, HeaderValue::from_static("guess"));
+        assert!(!bypass.matches("/api/v1/users/@me", Some(outside), &headers));
+    }
+}
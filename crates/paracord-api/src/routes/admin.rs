@@ -4,18 +4,30 @@ use axum::{
     http::{HeaderMap, StatusCode},
     Json,
 };
+use paracord_core::supporters;
+use paracord_core::tombstone::MAX_UNDO_WINDOW_HOURS;
 use paracord_core::AppState;
+use paracord_db::tombstones::TombstoneKind;
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use tokio_util::io::ReaderStream;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AdminUser;
-use crate::routes::security;
+use crate::routes::{branding, channels, roles, security};
 
 // ── Restart & Update ─────────────────────────────────────────────────
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/restart-update",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn restart_update(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -38,6 +50,13 @@ pub async fn restart_update(
 
 // ── Stats ───────────────────────────────────────────────────────────────
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/stats",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn get_stats(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -51,13 +70,110 @@ pub async fn get_stats(
     })))
 }
 
-#[derive(Deserialize)]
+// ── Observability ───────────────────────────────────────────────────────
+
+/// Entries kept in each ranked list of the observability snapshot.
+const OBSERVABILITY_TOP_N: usize = 25;
+
+/// Live internals for incident diagnosis: rate limiter buckets and the
+/// clients hitting them, WebSocket sessions per guild, event bus queue
+/// depths, and process memory. Admins or holders of the metrics token.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/observability",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn get_observability(
+    State(state): State<AppState>,
+    admin: Result<AdminUser, ApiError>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    if let Err(err) = admin {
+        if !crate::presents_metrics_token(&headers) {
+            return Err(err);
+        }
+    }
+
+    let limiter = crate::rate_limiter_snapshot(OBSERVABILITY_TOP_N);
+    let bucket_map = |buckets: Vec<(String, usize)>| -> serde_json::Map<String, Value> {
+        buckets
+            .into_iter()
+            .map(|(scope, count)| (scope, json!(count)))
+            .collect()
+    };
+    let ws = paracord_core::observability::ws_metrics_snapshot();
+    let bus = state.event_bus.snapshot();
+    let memory = paracord_core::observability::process_memory_snapshot().map(|memory| {
+        json!({
+            "resident_bytes": memory.resident_bytes,
+            "peak_resident_bytes": memory.peak_resident_bytes,
+            "virtual_bytes": memory.virtual_bytes,
+        })
+    });
+
+    Ok(Json(json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "rate_limiter": {
+            "enabled": limiter.enabled,
+            "requests_total": limiter.requests,
+            "rate_limited_total": limiter.rate_limited,
+            "bypassed_total": limiter.bypassed,
+            "buckets": {
+                "http": bucket_map(limiter.http_buckets),
+                "webhook": bucket_map(limiter.webhook_buckets),
+            },
+            "top_offenders": limiter.top_offenders.iter().map(|client| json!({
+                "ip": client.ip,
+                "rejected": client.rejected,
+                "last_rejected_at": chrono::DateTime::from_timestamp(client.last_rejected_at, 0)
+                    .map(|at| at.to_rfc3339()),
+            })).collect::<Vec<_>>(),
+        },
+        "websocket": {
+            "active_connections": ws.active_connections,
+            "events_total": ws.total_events,
+            "payloads": ws.payload_sizes.iter().map(|sizes| json!({
+                "event_type": sizes.event_type,
+                "count": sizes.count,
+                "sum_bytes": sizes.sum_bytes,
+                "truncated": sizes.truncated,
+            })).collect::<Vec<_>>(),
+            "sessions": bus.sessions,
+            "users": bus.users,
+            "guild_count": bus.guild_sessions.len(),
+            "guilds": bus.guild_sessions.iter().take(OBSERVABILITY_TOP_N).map(|(guild_id, sessions)| json!({
+                "guild_id": guild_id.to_string(),
+                "sessions": sessions,
+            })).collect::<Vec<_>>(),
+        },
+        "event_bus": {
+            "session_capacity": bus.session_capacity,
+            "system_queue_depth": bus.system_queue_depth,
+            "system_receivers": bus.system_receivers,
+            "session_queue_total": bus.session_queue_total,
+            "session_queue_max": bus.session_queue_max,
+        },
+        "memory": memory,
+    })))
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct SecurityEventsQuery {
     pub before: Option<i64>,
     pub limit: Option<i64>,
     pub action: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/security-events",
+    tag = "admin",
+    params(SecurityEventsQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn list_security_events(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -96,6 +212,14 @@ pub async fn list_security_events(
 
 // ── Settings ────────────────────────────────────────────────────────────
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/settings",
+    tag = "admin",
+    operation_id = "admin_get_settings",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn get_settings(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -127,6 +251,11 @@ pub async fn get_settings(
             .ok()
             .flatten()
             .unwrap_or_else(|| state.config.federation_file_cache_ttl_hours.to_string());
+    let rate_limits = paracord_db::server_settings::get_setting(&state.db, "rate_limits")
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
 
     Ok(Json(json!({
         "registration_enabled": settings.registration_enabled.to_string(),
@@ -134,10 +263,26 @@ pub async fn get_settings(
         "server_description": settings.server_description,
         "max_guilds_per_user": settings.max_guilds_per_user.to_string(),
         "max_members_per_guild": settings.max_members_per_guild.to_string(),
+        "dm_require_friendship": settings.dm_require_friendship.to_string(),
+        "branding_accent_color": settings.branding_accent_color,
+        "branding_login_text": settings.branding_login_text,
+        "verification_hooks_allow_private_networks": settings.verification_hooks_allow_private_networks.to_string(),
+        "deletion_undo_window_hours": settings.deletion_undo_window_hours.to_string(),
+        "max_emojis_per_guild": settings.max_emojis_per_guild.to_string(),
+        "max_webhooks_per_channel": settings.max_webhooks_per_channel.to_string(),
+        "max_webhooks_per_guild": settings.max_webhooks_per_guild.to_string(),
+        "max_bots_per_guild": settings.max_bots_per_guild.to_string(),
+        "max_roles_per_guild": settings.max_roles_per_guild.to_string(),
+        "max_channels_per_guild": settings.max_channels_per_guild.to_string(),
+        "max_stream_quality": settings.max_stream_quality,
+        "supporter_max_upload_size": settings.supporter_max_upload_size.to_string(),
+        "supporter_extra_emoji_slots": settings.supporter_extra_emoji_slots.to_string(),
+        "supporter_max_stream_quality": settings.supporter_max_stream_quality,
         "max_guild_storage_quota": max_guild_storage_quota,
         "federation_file_cache_enabled": federation_file_cache_enabled,
         "federation_file_cache_max_size": federation_file_cache_max_size,
         "federation_file_cache_ttl_hours": federation_file_cache_ttl_hours,
+        "rate_limits": rate_limits,
     })))
 }
 
@@ -147,20 +292,42 @@ const ALLOWED_SETTINGS: &[&str] = &[
     "server_description",
     "max_guilds_per_user",
     "max_members_per_guild",
+    "dm_require_friendship",
+    "branding_accent_color",
+    "branding_login_text",
     "max_guild_storage_quota",
     "federation_file_cache_enabled",
     "federation_file_cache_max_size",
     "federation_file_cache_ttl_hours",
+    "verification_hooks_allow_private_networks",
+    "deletion_undo_window_hours",
+    "max_emojis_per_guild",
+    "max_webhooks_per_channel",
+    "max_webhooks_per_guild",
+    "max_bots_per_guild",
+    "max_roles_per_guild",
+    "max_channels_per_guild",
+    "max_stream_quality",
+    "supporter_max_upload_size",
+    "supporter_extra_emoji_slots",
+    "supporter_max_stream_quality",
+    "rate_limits",
 ];
 
 const MAX_STRING_SETTING_LEN: usize = 256;
+const MAX_LOGIN_TEXT_LEN: usize = 1000;
+const MAX_EMOJI_SLOTS_SETTING: u32 = 10_000;
+const MAX_GUILD_LIMIT_SETTING: u32 = 100_000;
 
 fn validate_setting(key: &str, value: &str) -> Result<(), String> {
     match key {
-        "registration_enabled" => {
-            if value != "true" && value != "false" {
-                return Err(format!("{key}: must be \"true\" or \"false\""));
-            }
+        "registration_enabled"
+        | "dm_require_friendship"
+        | "federation_file_cache_enabled"
+        | "verification_hooks_allow_private_networks"
+            if value != "true" && value != "false" =>
+        {
+            return Err(format!("{key}: must be \"true\" or \"false\""));
         }
         "server_name" => {
             let trimmed = value.trim();
@@ -170,12 +337,18 @@ fn validate_setting(key: &str, value: &str) -> Result<(), String> {
                 ));
             }
         }
-        "server_description" => {
-            if value.len() > MAX_STRING_SETTING_LEN {
-                return Err(format!(
-                    "{key}: must be at most {MAX_STRING_SETTING_LEN} characters"
-                ));
-            }
+        "server_description" if value.len() > MAX_STRING_SETTING_LEN => {
+            return Err(format!(
+                "{key}: must be at most {MAX_STRING_SETTING_LEN} characters"
+            ));
+        }
+        "branding_accent_color" if !value.is_empty() && !branding::is_hex_color(value) => {
+            return Err(format!("{key}: must be a #rrggbb color or empty"));
+        }
+        "branding_login_text" if value.chars().count() > MAX_LOGIN_TEXT_LEN => {
+            return Err(format!(
+                "{key}: must be at most {MAX_LOGIN_TEXT_LEN} characters"
+            ));
         }
         "max_guilds_per_user" | "max_members_per_guild" => {
             let n: u32 = value
@@ -185,17 +358,55 @@ fn validate_setting(key: &str, value: &str) -> Result<(), String> {
                 return Err(format!("{key}: must be between 1 and 100000"));
             }
         }
-        "max_guild_storage_quota" | "federation_file_cache_max_size" => {
-            let _n: u64 = value
+        "deletion_undo_window_hours" => {
+            let n: u32 = value
                 .parse()
                 .map_err(|_| format!("{key}: must be a positive integer"))?;
+            if n == 0 || n > MAX_UNDO_WINDOW_HOURS {
+                return Err(format!(
+                    "{key}: must be between 1 and {MAX_UNDO_WINDOW_HOURS}"
+                ));
+            }
+        }
+        "max_emojis_per_guild" | "supporter_extra_emoji_slots" => {
+            let n: u32 = value
+                .parse()
+                .map_err(|_| format!("{key}: must be a non-negative integer"))?;
+            if n > MAX_EMOJI_SLOTS_SETTING {
+                return Err(format!(
+                    "{key}: must be between 0 and {MAX_EMOJI_SLOTS_SETTING}"
+                ));
+            }
         }
-        "federation_file_cache_enabled" => {
-            if value != "true" && value != "false" {
-                return Err(format!("{key}: must be \"true\" or \"false\""));
+        "max_webhooks_per_channel"
+        | "max_webhooks_per_guild"
+        | "max_bots_per_guild"
+        | "max_roles_per_guild"
+        | "max_channels_per_guild" => {
+            let n: u32 = value
+                .parse()
+                .map_err(|_| format!("{key}: must be a non-negative integer"))?;
+            if n > MAX_GUILD_LIMIT_SETTING {
+                return Err(format!(
+                    "{key}: must be between 0 and {MAX_GUILD_LIMIT_SETTING}"
+                ));
             }
         }
-        "federation_file_cache_ttl_hours" => {
+        "max_stream_quality" | "supporter_max_stream_quality"
+            if supporters::stream_quality_rank(value).is_none() =>
+        {
+            return Err(format!(
+                "{key}: must be one of {}",
+                supporters::STREAM_QUALITY_ORDER.join(", ")
+            ));
+        }
+        "rate_limits" => {
+            crate::rate_limit::parse_limits(value).map_err(|err| format!("{key}: {err}"))?;
+        }
+        "max_guild_storage_quota"
+        | "supporter_max_upload_size"
+        | "federation_file_cache_max_size"
+        | "federation_file_cache_ttl_hours" => {
             let _n: u64 = value
                 .parse()
                 .map_err(|_| format!("{key}: must be a positive integer"))?;
@@ -205,6 +416,15 @@ fn validate_setting(key: &str, value: &str) -> Result<(), String> {
     Ok(())
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/settings",
+    tag = "admin",
+    operation_id = "admin_update_settings",
+    request_body = HashMap<String, String>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn update_settings(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -225,7 +445,10 @@ pub async fn update_settings(
         .into_iter()
         .map(|(key, value)| {
             let value = match key.as_str() {
-                "server_name" | "server_description" => value.trim().to_string(),
+                "server_name" | "server_description" | "branding_login_text" => {
+                    value.trim().to_string()
+                }
+                "branding_accent_color" => value.to_ascii_lowercase(),
                 _ => value,
             };
             (key, value)
@@ -239,6 +462,10 @@ pub async fn update_settings(
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     }
 
+    if let Some(spec) = sanitized.get("rate_limits") {
+        crate::rate_limit::apply_setting(spec).map_err(ApiError::BadRequest)?;
+    }
+
     // Update in-memory runtime settings
     let mut settings = state.runtime.write().await;
     for (key, value) in &sanitized {
@@ -262,6 +489,69 @@ pub async fn update_settings(
                     settings.max_members_per_guild = v;
                 }
             }
+            "dm_require_friendship" => {
+                settings.dm_require_friendship = value == "true";
+            }
+            "verification_hooks_allow_private_networks" => {
+                settings.verification_hooks_allow_private_networks = value == "true";
+            }
+            "deletion_undo_window_hours" => {
+                if let Ok(v) = value.parse() {
+                    settings.deletion_undo_window_hours = v;
+                }
+            }
+            "max_emojis_per_guild" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_emojis_per_guild = v;
+                }
+            }
+            "max_webhooks_per_channel" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_webhooks_per_channel = v;
+                }
+            }
+            "max_webhooks_per_guild" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_webhooks_per_guild = v;
+                }
+            }
+            "max_bots_per_guild" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_bots_per_guild = v;
+                }
+            }
+            "max_roles_per_guild" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_roles_per_guild = v;
+                }
+            }
+            "max_channels_per_guild" => {
+                if let Ok(v) = value.parse() {
+                    settings.max_channels_per_guild = v;
+                }
+            }
+            "supporter_max_upload_size" => {
+                if let Ok(v) = value.parse() {
+                    settings.supporter_max_upload_size = v;
+                }
+            }
+            "supporter_extra_emoji_slots" => {
+                if let Ok(v) = value.parse() {
+                    settings.supporter_extra_emoji_slots = v;
+                }
+            }
+            "max_stream_quality" => {
+                settings.max_stream_quality = value.clone();
+            }
+            "supporter_max_stream_quality" => {
+                settings.supporter_max_stream_quality = value.clone();
+            }
+            "branding_accent_color" => {
+                settings.branding_accent_color = value.clone();
+            }
+            "branding_login_text" => {
+                settings.branding_login_text = value.clone();
+            }
             _ => {}
         }
     }
@@ -284,17 +574,40 @@ pub async fn update_settings(
         "server_description": settings.server_description,
         "max_guilds_per_user": settings.max_guilds_per_user.to_string(),
         "max_members_per_guild": settings.max_members_per_guild.to_string(),
+        "dm_require_friendship": settings.dm_require_friendship.to_string(),
+        "branding_accent_color": settings.branding_accent_color,
+        "branding_login_text": settings.branding_login_text,
+        "verification_hooks_allow_private_networks": settings.verification_hooks_allow_private_networks.to_string(),
+        "deletion_undo_window_hours": settings.deletion_undo_window_hours.to_string(),
+        "max_emojis_per_guild": settings.max_emojis_per_guild.to_string(),
+        "max_webhooks_per_channel": settings.max_webhooks_per_channel.to_string(),
+        "max_webhooks_per_guild": settings.max_webhooks_per_guild.to_string(),
+        "max_bots_per_guild": settings.max_bots_per_guild.to_string(),
+        "max_roles_per_guild": settings.max_roles_per_guild.to_string(),
+        "max_channels_per_guild": settings.max_channels_per_guild.to_string(),
+        "max_stream_quality": settings.max_stream_quality,
+        "supporter_max_upload_size": settings.supporter_max_upload_size.to_string(),
+        "supporter_extra_emoji_slots": settings.supporter_extra_emoji_slots.to_string(),
+        "supporter_max_stream_quality": settings.supporter_max_stream_quality,
     })))
 }
 
 // ── Users ───────────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct PaginationParams {
     pub offset: Option<i64>,
     pub limit: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/users",
+    tag = "admin",
+    params(PaginationParams),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn list_users(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -335,11 +648,20 @@ pub async fn list_users(
     })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub flags: Option<i32>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/users/{user_id}",
+    tag = "admin",
+    params(("user_id" = i64, Path)),
+    request_body = UpdateUserRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn update_user(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -385,6 +707,14 @@ pub async fn update_user(
     Err(ApiError::BadRequest("No updates provided".into()))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/users/{user_id}",
+    tag = "admin",
+    params(("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
 pub async fn delete_user(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -411,13 +741,21 @@ pub async fn delete_user(
 
 // ── Guilds ──────────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateGuildRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub icon: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/guilds",
+    tag = "admin",
+    operation_id = "admin_list_guilds",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn list_guilds(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -443,6 +781,16 @@ pub async fn list_guilds(
     Ok(Json(json!({ "guilds": guild_list })))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/guilds/{guild_id}",
+    tag = "admin",
+    operation_id = "admin_update_guild",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateGuildRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn update_guild(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -486,20 +834,33 @@ pub async fn update_guild(
     Ok(Json(guild_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/guilds/{guild_id}",
+    tag = "admin",
+    operation_id = "admin_delete_guild",
+    params(("guild_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
 pub async fn delete_guild(
     State(state): State<AppState>,
     admin: AdminUser,
     headers: HeaderMap,
     Path(guild_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    paracord_core::admin::admin_delete_guild(&state.db, guild_id).await?;
+    let deleted_at = paracord_core::admin::admin_delete_guild(&state.db, guild_id).await?;
 
     state.member_index.remove_guild(guild_id);
-    state.event_bus.dispatch(
-        "GUILD_DELETE",
+    let payload = paracord_core::tombstone::with_tombstone(
+        &state,
         json!({"id": guild_id.to_string()}),
-        Some(guild_id),
-    );
+        deleted_at,
+    )
+    .await;
+    state
+        .event_bus
+        .dispatch("GUILD_DELETE", payload, Some(guild_id));
 
     security::log_security_event(
         &state,
@@ -515,13 +876,161 @@ pub async fn delete_guild(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ── Deleted guilds, channels and roles ──────────────────────────────────
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/deleted",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_deleted(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, ApiError> {
+    let tombstones = paracord_db::tombstones::list_tombstones(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let window_hours = state.runtime.read().await.deletion_undo_window_hours;
+    let result: Vec<Value> = tombstones
+        .iter()
+        .map(|tombstone| {
+            json!({
+                "type": tombstone.kind.as_str(),
+                "id": tombstone.id.to_string(),
+                "guild_id": tombstone.guild_id.to_string(),
+                "name": tombstone.name,
+                "deleted_at": tombstone.deleted_at.to_rfc3339(),
+                "restorable_until": paracord_core::tombstone::restorable_until(
+                    tombstone.deleted_at,
+                    window_hours,
+                )
+                .to_rfc3339(),
+            })
+        })
+        .collect();
+    Ok(Json(json!(result)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/deleted/{kind}/{id}/restore",
+    tag = "admin",
+    params(("kind" = String, Path), ("id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn restore_deleted(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path((kind, id)): Path<(String, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    let kind = TombstoneKind::parse(&kind).ok_or(ApiError::NotFound)?;
+    let (tombstone, thread_ids) = paracord_core::tombstone::restore(&state.db, kind, id).await?;
+    let guild_id = tombstone.guild_id;
+
+    let restored = match kind {
+        TombstoneKind::Guild => {
+            let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+            let member_ids = paracord_db::members::get_guild_member_user_ids(&state.db, guild_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            for user_id in &member_ids {
+                state.member_index.add_member(guild_id, *user_id);
+            }
+            let guild_json = json!({
+                "id": guild.id.to_string(),
+                "name": guild.name,
+                "description": guild.description,
+                "icon_hash": guild.icon_hash,
+                "owner_id": guild.owner_id.to_string(),
+                "member_count": member_ids.len(),
+                "created_at": guild.created_at.to_rfc3339(),
+            });
+            state
+                .event_bus
+                .dispatch("GUILD_CREATE", guild_json.clone(), Some(guild_id));
+            guild_json
+        }
+        TombstoneKind::Channel => {
+            let channel = paracord_db::channels::get_channel(&state.db, id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+            let channel_json = channels::channel_to_json(&channel);
+            state
+                .event_bus
+                .dispatch("CHANNEL_CREATE", channel_json.clone(), Some(guild_id));
+            for thread_id in thread_ids {
+                let thread = paracord_db::channels::get_channel(&state.db, thread_id)
+                    .await
+                    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+                if let Some(thread) = thread {
+                    state.event_bus.dispatch(
+                        "THREAD_CREATE",
+                        channels::channel_to_json(&thread),
+                        Some(guild_id),
+                    );
+                }
+            }
+            channel_json
+        }
+        TombstoneKind::Role => {
+            let role = paracord_db::roles::get_role(&state.db, id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
+            paracord_core::permissions::invalidate_all(&state.permission_cache).await;
+            let role_json = roles::role_to_json(&role);
+            state.event_bus.dispatch(
+                "GUILD_ROLE_CREATE",
+                json!({"guild_id": guild_id.to_string(), "role": &role_json}),
+                Some(guild_id),
+            );
+            role_json
+        }
+    };
+
+    security::log_security_event(
+        &state,
+        "admin.tombstone.restore",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "type": kind.as_str(),
+            "id": id.to_string(),
+            "guild_id": guild_id.to_string(),
+        })),
+    )
+    .await;
+
+    Ok(Json(
+        json!({ "type": kind.as_str(), kind.as_str(): restored }),
+    ))
+}
+
 // ── Backups ─────────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateBackupRequest {
     pub include_media: Option<bool>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backup",
+    tag = "admin",
+    request_body = Option<CreateBackupRequest>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn create_backup(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -553,6 +1062,13 @@ pub async fn create_backup(
     Ok(Json(json!({ "filename": filename })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn list_backups(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -573,11 +1089,19 @@ pub async fn list_backups(
     Ok(Json(json!({ "backups": list })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct RestoreBackupRequest {
     pub name: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/restore",
+    tag = "admin",
+    request_body = RestoreBackupRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn restore_backup(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -615,6 +1139,14 @@ pub async fn restore_backup(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups/{name}",
+    tag = "admin",
+    params(("name" = String, Path)),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = [])),
+)]
 pub async fn download_backup(
     State(state): State<AppState>,
     _admin: AdminUser,
@@ -649,6 +1181,14 @@ pub async fn download_backup(
         .unwrap())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/backups/{name}",
+    tag = "admin",
+    params(("name" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
 pub async fn delete_backup(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -685,6 +1225,368 @@ pub async fn delete_backup(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ── Database maintenance ────────────────────────────────────────────────
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/database",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn get_database_status(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, ApiError> {
+    let engine = paracord_db::active_database_engine();
+    let sqlite = if engine == paracord_db::DatabaseEngine::Sqlite {
+        let stats = paracord_db::maintenance::sqlite_stats(&state.db)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let (size_bytes, wal_bytes) =
+            paracord_core::maintenance::sqlite_file_sizes(&state.config.database_url);
+        json!({
+            "page_size": stats.page_size,
+            "page_count": stats.page_count,
+            "freelist_count": stats.freelist_count,
+            "auto_vacuum": match stats.auto_vacuum {
+                1 => "full",
+                paracord_db::maintenance::AUTO_VACUUM_INCREMENTAL => "incremental",
+                _ => "none",
+            },
+            "size_bytes": size_bytes,
+            "wal_bytes": wal_bytes,
+        })
+    } else {
+        Value::Null
+    };
+    let last_maintenance = paracord_core::maintenance::last_maintenance_run().map(|run| {
+        json!({
+            "ran_at": run.ran_at.to_rfc3339(),
+            "checkpoint_busy": run.checkpoint_busy,
+            "wal_bytes_before": run.wal_bytes_before,
+            "wal_bytes_after": run.wal_bytes_after,
+            "freelist_pages_before": run.freelist_pages_before,
+            "freelist_pages_after": run.freelist_pages_after,
+        })
+    });
+    Ok(Json(json!({
+        "engine": engine.as_str(),
+        "sqlite": sqlite,
+        "last_maintenance": last_maintenance,
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/database/compact",
+    tag = "admin",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn compact_database(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    let copy = paracord_core::maintenance::compact_into(
+        &state.db,
+        &state.config.database_url,
+        &state.config.backup_dir,
+    )
+    .await?;
+
+    security::log_security_event(
+        &state,
+        "admin.database.compact",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "filename": &copy.filename, "size_bytes": copy.size_bytes })),
+    )
+    .await;
+
+    Ok(Json(json!({
+        "filename": copy.filename,
+        "size_bytes": copy.size_bytes,
+        "source_size_bytes": copy.source_size_bytes,
+    })))
+}
+
+// ── Background jobs ─────────────────────────────────────────────────────
+
+#[derive(Deserialize, IntoParams)]
+pub struct JobsQuery {
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+pub(crate) fn job_to_json(job: &paracord_db::jobs::JobRow) -> Value {
+    json!({
+        "id": job.id.to_string(),
+        "kind": job.kind,
+        "payload": serde_json::from_str::<Value>(&job.payload).unwrap_or(Value::Null),
+        "priority": job.priority,
+        "status": job.status,
+        "attempts": job.attempts,
+        "max_attempts": job.max_attempts,
+        "run_at": job.run_at.to_rfc3339(),
+        "locked_by": job.locked_by,
+        "locked_at": job.locked_at.map(|at| at.to_rfc3339()),
+        "last_error": job.last_error,
+        "progress": job
+            .progress
+            .as_deref()
+            .and_then(|progress| serde_json::from_str::<Value>(progress).ok()),
+        "created_at": job.created_at.to_rfc3339(),
+        "updated_at": job.updated_at.to_rfc3339(),
+        "completed_at": job.completed_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/jobs",
+    tag = "admin",
+    params(JobsQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(params): Query<JobsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+    let jobs = paracord_db::jobs::list_jobs(
+        &state.db,
+        params.status.as_deref(),
+        params.kind.as_deref(),
+        params.before,
+        limit,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let counts = paracord_db::jobs::count_jobs_by_status(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    Ok(Json(json!({
+        "counts": counts.into_iter().collect::<HashMap<String, i64>>(),
+        "jobs": jobs.iter().map(job_to_json).collect::<Vec<Value>>(),
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/jobs/{job_id}",
+    tag = "admin",
+    params(("job_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(job_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let job = paracord_db::jobs::get_job(&state.db, job_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(job_to_json(&job)))
+}
+
+/// Queue a failed job again with a fresh set of attempts.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/jobs/{job_id}/retry",
+    tag = "admin",
+    params(("job_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn retry_job(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(job_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let job = paracord_db::jobs::reset_failed_job(&state.db, job_id, chrono::Utc::now())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| ApiError::Conflict("Only failed jobs can be retried".into()))?;
+
+    security::log_security_event(
+        &state,
+        "admin.job.retry",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "job_id": job.id.to_string(), "kind": job.kind })),
+    )
+    .await;
+
+    Ok(Json(job_to_json(&job)))
+}
+
+/// Delete a pending, failed, or completed job. Running jobs can't be
+/// cancelled.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/jobs/{job_id}",
+    tag = "admin",
+    params(("job_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(job_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let job = paracord_db::jobs::get_job(&state.db, job_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let deleted = paracord_db::jobs::cancel_job(&state.db, job.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !deleted {
+        return Err(ApiError::Conflict("Job is running".into()));
+    }
+
+    security::log_security_event(
+        &state,
+        "admin.job.cancel",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "job_id": job.id.to_string(), "kind": job.kind })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Maintenance tasks ───────────────────────────────────────────────────
+
+#[derive(Deserialize, ToSchema)]
+pub struct MaintenanceTaskRequest {
+    pub task: String,
+}
+
+fn maintenance_task_from_job(
+    job: &paracord_db::jobs::JobRow,
+) -> Option<paracord_core::integrity::MaintenanceTask> {
+    paracord_core::jobs::payload::<MaintenanceTaskRequest>(job)
+        .ok()
+        .and_then(|payload| paracord_core::integrity::MaintenanceTask::parse(&payload.task))
+}
+
+/// Queue a repair or integrity task. Its progress and final report are on
+/// the returned job.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/maintenance",
+    tag = "admin",
+    request_body = MaintenanceTaskRequest,
+    responses((status = 202, description = "Accepted", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn start_maintenance_task(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Json(body): Json<MaintenanceTaskRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let task = paracord_core::integrity::MaintenanceTask::parse(&body.task).ok_or_else(|| {
+        let known: Vec<&str> = paracord_core::integrity::MaintenanceTask::ALL
+            .iter()
+            .map(|task| task.as_str())
+            .collect();
+        ApiError::BadRequest(format!(
+            "Unknown task; expected one of {}",
+            known.join(", ")
+        ))
+    })?;
+
+    for status in [
+        paracord_db::jobs::JOB_STATUS_PENDING,
+        paracord_db::jobs::JOB_STATUS_RUNNING,
+    ] {
+        let queued = paracord_db::jobs::list_jobs(
+            &state.db,
+            Some(status),
+            Some(crate::jobs::JOB_ADMIN_MAINTENANCE),
+            None,
+            500,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if queued
+            .iter()
+            .any(|job| maintenance_task_from_job(job) == Some(task))
+        {
+            return Err(ApiError::Conflict(format!(
+                "{} is already queued",
+                task.as_str()
+            )));
+        }
+    }
+
+    let job = paracord_core::jobs::enqueue(
+        &state.db,
+        crate::jobs::JOB_ADMIN_MAINTENANCE,
+        &json!({ "task": task.as_str() }),
+        paracord_core::jobs::JobOptions {
+            max_attempts: 1,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    security::log_security_event(
+        &state,
+        "admin.maintenance.start",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "job_id": job.id.to_string(), "task": task.as_str() })),
+    )
+    .await;
+
+    Ok((StatusCode::ACCEPTED, Json(job_to_json(&job))))
+}
+
+pub(crate) async fn run_maintenance_job(
+    state: AppState,
+    job: paracord_db::jobs::JobRow,
+) -> Result<(), String> {
+    let payload: MaintenanceTaskRequest = paracord_core::jobs::payload(&job)?;
+    let task = paracord_core::integrity::MaintenanceTask::parse(&payload.task)
+        .ok_or_else(|| format!("Unknown maintenance task '{}'", payload.task))?;
+    let report = paracord_core::integrity::run_task(&state.db, task, job.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    paracord_db::jobs::set_job_progress(&state.db, job.id, &report.to_string())
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!(
+        "Maintenance task {} finished: {}",
+        task.as_str(),
+        report["result"]
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::validate_setting;
@@ -5,15 +5,23 @@ pub const ACTION_GUILD_UPDATE: i16 = 1;
 pub const ACTION_CHANNEL_CREATE: i16 = 10;
 pub const ACTION_CHANNEL_UPDATE: i16 = 11;
 pub const ACTION_CHANNEL_DELETE: i16 = 12;
+pub const ACTION_CHANNEL_OVERWRITES_UPDATE: i16 = 13;
 pub const ACTION_MEMBER_UPDATE: i16 = 20;
 pub const ACTION_MEMBER_KICK: i16 = 21;
 pub const ACTION_MEMBER_BAN_ADD: i16 = 22;
 pub const ACTION_MEMBER_BAN_REMOVE: i16 = 23;
+pub const ACTION_MEMBER_NOTE_CREATE: i16 = 24;
+pub const ACTION_MEMBER_NOTE_DELETE: i16 = 25;
+pub const ACTION_MEMBER_ROLE_SELF_ADD: i16 = 26;
+pub const ACTION_MEMBER_ROLE_SELF_REMOVE: i16 = 27;
 pub const ACTION_ROLE_CREATE: i16 = 30;
 pub const ACTION_ROLE_UPDATE: i16 = 31;
 pub const ACTION_ROLE_DELETE: i16 = 32;
 pub const ACTION_INVITE_CREATE: i16 = 40;
 pub const ACTION_INVITE_DELETE: i16 = 41;
+pub const ACTION_TAG_CREATE: i16 = 50;
+pub const ACTION_TAG_UPDATE: i16 = 51;
+pub const ACTION_TAG_DELETE: i16 = 52;
 
 pub async fn log_action(
     state: &AppState,
@@ -24,7 +32,13 @@ pub async fn log_action(
     reason: Option<&str>,
     changes: Option<Value>,
 ) {
-    let log_id = paracord_util::snowflake::generate(1);
+    let log_id = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => id,
+        Err(err) => {
+            tracing::warn!("failed to write audit entry: {}", err);
+            return;
+        }
+    };
     let change_ref = changes.as_ref();
     if let Err(err) = paracord_db::audit_log::create_entry(
         &state.db,
@@ -40,4 +54,13 @@ pub async fn log_action(
     {
         tracing::warn!("failed to write audit entry: {}", err);
     }
+    crate::routes::mod_log::record_audit_entry(
+        state,
+        guild_id,
+        actor_id,
+        action_type,
+        target_id,
+        reason,
+    )
+    .await;
 }
@@ -3,14 +3,16 @@ use axum::{
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::IntoParams;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct AuditLogQuery {
     pub user_id: Option<i64>,
     pub action_type: Option<i16>,
@@ -18,6 +20,14 @@ pub struct AuditLogQuery {
     pub limit: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/audit-logs",
+    tag = "audit_logs",
+    params(("guild_id" = i64, Path), AuditLogQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_audit_logs(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -27,7 +37,7 @@ pub async fn get_audit_logs(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
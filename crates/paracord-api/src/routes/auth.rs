@@ -7,6 +7,7 @@ use axum::{
 };
 use chrono::{Duration, Utc};
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -15,6 +16,7 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::error::ApiError;
@@ -69,46 +71,16 @@ fn constant_time_equal(a: &str, b: &str) -> bool {
     diff == 0
 }
 
-fn trust_proxy_headers() -> bool {
-    std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false)
-}
-
 fn proxy_peer_is_trusted(peer_ip: Option<&str>) -> bool {
-    if !trust_proxy_headers() {
-        return false;
-    }
-    let Some(peer_ip) = peer_ip else {
-        return false;
-    };
-    let trusted = std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-        .ok()
-        .map(|raw| {
-            raw.split(',')
-                .map(str::trim)
-                .filter(|v| !v.is_empty())
-                .map(str::to_string)
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-    !trusted.is_empty() && trusted.iter().any(|ip| ip == peer_ip)
+    crate::client_ip::peer_is_trusted_proxy(peer_ip.and_then(|ip| ip.parse().ok()))
 }
 
 fn resolve_client_ip(headers: &HeaderMap, peer_ip: Option<&str>) -> String {
-    if proxy_peer_is_trusted(peer_ip) {
-        if let Some(ip) = headers
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|raw| raw.split(',').next())
-            .map(str::trim)
-            .filter(|v| !v.is_empty())
-        {
-            return ip.to_string();
-        }
+    let peer = peer_ip.and_then(|ip| ip.parse().ok());
+    match crate::client_ip::resolve(headers, peer) {
+        Some(ip) => ip.to_string(),
+        None => peer_ip.unwrap_or("unknown").to_string(),
     }
-    peer_ip.unwrap_or("unknown").to_string()
 }
 
 fn auth_guard_keys(
@@ -743,7 +715,7 @@ async fn auto_join_public_spaces(state: &AppState, user_id: i64) -> Result<(), A
     Ok(())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct RegisterRequest {
     #[serde(default)]
     pub email: String,
@@ -752,7 +724,7 @@ pub struct RegisterRequest {
     pub display_name: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct LoginRequest {
     #[serde(default, alias = "identifier", alias = "username", alias = "login")]
     pub email: String,
@@ -780,14 +752,22 @@ pub struct AuthSessionView {
     pub issued_at: String,
     pub last_seen_at: String,
     pub expires_at: String,
+    pub device_approved: bool,
+    pub device_approval_requested_at: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct AuthOptionsResponse {
     pub allow_username_login: bool,
     pub require_email: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/options",
+    tag = "auth",
+    responses((status = 200, description = "OK", body = AuthOptionsResponse)),
+)]
 pub async fn auth_options(State(state): State<AppState>) -> Json<AuthOptionsResponse> {
     let allow_username_login = username_login_effective(
         state.config.allow_username_login,
@@ -799,6 +779,13 @@ pub async fn auth_options(State(state): State<AppState>) -> Json<AuthOptionsResp
     })
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses((status = 200, description = "OK")),
+)]
 pub async fn register(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -908,7 +895,7 @@ pub async fn register(
     let password_hash = paracord_core::auth::hash_password(&body.password)
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::try_next_id().await?;
     let resolved_email = if normalized_email.is_empty() {
         synthesized_local_email(id)
     } else {
@@ -979,6 +966,13 @@ pub async fn register(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses((status = 200, description = "OK")),
+)]
 pub async fn login(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -1120,20 +1114,28 @@ pub async fn login(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/refresh",
+    tag = "auth",
+    request_body = Option<Value>,
+    responses((status = 200, description = "OK")),
+)]
 pub async fn refresh(
     State(state): State<AppState>,
     headers: HeaderMap,
     body: Option<Json<serde_json::Value>>,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Accept refresh token from cookie OR request body (for cross-origin clients).
-    let refresh_token = get_cookie_value(&headers, REFRESH_COOKIE_NAME)
-        .or_else(|| {
-            body.as_ref()
-                .and_then(|b| b.get("refresh_token"))
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.is_empty())
-                .map(str::to_string)
-        })
+    // Accept refresh token from request body (cross-origin and multi-account
+    // clients) OR cookie. An explicit body token wins so a desktop client can
+    // switch accounts on a server whose cookie belongs to another account.
+    let refresh_token = body
+        .as_ref()
+        .and_then(|b| b.get("refresh_token"))
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .or_else(|| get_cookie_value(&headers, REFRESH_COOKIE_NAME))
         .ok_or(ApiError::Unauthorized)?;
     let (token, access_cookie, refresh_cookie, session_id, new_raw_refresh) =
         rotate_auth_session(&state, &refresh_token).await?;
@@ -1156,6 +1158,13 @@ pub async fn refresh(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "auth",
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn logout(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1216,6 +1225,13 @@ pub async fn logout(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/sessions",
+    tag = "auth",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_sessions(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1231,18 +1247,33 @@ pub async fn list_sessions(
         .map(|session| AuthSessionView {
             id: session.id.clone(),
             current: session.id == current,
-            device_id: session.device_id.clone(),
+            device_id: session
+                .device_id
+                .as_deref()
+                .map(paracord_db::sessions::device_fingerprint),
             user_agent: session.user_agent.clone(),
             ip_address: session.ip_address.clone(),
             issued_at: session.issued_at.to_rfc3339(),
             last_seen_at: session.last_seen_at.to_rfc3339(),
             expires_at: session.expires_at.to_rfc3339(),
+            device_approved: session.device_approved_at.is_some(),
+            device_approval_requested_at: session
+                .device_approval_requested_at
+                .map(|at| at.to_rfc3339()),
         })
         .collect();
 
     Ok(Json(json!(mapped)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/sessions/{session_id}",
+    tag = "auth",
+    params(("session_id" = String, Path)),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn revoke_session(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1290,13 +1321,266 @@ pub async fn revoke_session(
     }
 }
 
+/// Only a session whose own device is trusted may approve or deny others.
+pub(crate) async fn require_approved_device(
+    state: &AppState,
+    auth: &AuthUser,
+) -> Result<(), ApiError> {
+    let session_id = auth.session_id.as_deref().ok_or(ApiError::Forbidden)?;
+    let session = paracord_db::sessions::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Unauthorized)?;
+    if session.device_approved_at.is_none() {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+/// Trust the device behind `session_id` for later sign-ins as well.
+async fn remember_session_device(
+    state: &AppState,
+    session_id: &str,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let device_id = paracord_db::sessions::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .and_then(|session| session.device_id);
+    if let Some(device_id) = device_id {
+        paracord_db::sessions::remember_approved_device(&state.db, user_id, &device_id, Utc::now())
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    Ok(())
+}
+
+/// Issue a new device recovery code for `user_id`, replacing the old one.
+/// Returns the code, which is only ever shown this once.
+pub(crate) async fn issue_device_recovery_code(
+    state: &AppState,
+    user_id: i64,
+) -> Result<String, ApiError> {
+    let code = random_token_hex(16);
+    paracord_db::sessions::set_device_recovery_code(&state.db, user_id, &code, Utc::now())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(code)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/sessions/{session_id}/approve",
+    tag = "auth",
+    params(("session_id" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn approve_session_device(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_approved_device(&state, &auth).await?;
+    let approved = paracord_db::sessions::approve_session_device(
+        &state.db,
+        &session_id,
+        auth.user_id,
+        Utc::now(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !approved {
+        return Err(ApiError::NotFound);
+    }
+    remember_session_device(&state, &session_id, auth.user_id).await?;
+
+    security::log_security_event(
+        &state,
+        "auth.session.device_approve",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        Some(&session_id),
+        Some(&headers),
+        Some(json!({ "approved_by_session_id": auth.session_id })),
+    )
+    .await;
+    state.event_bus.dispatch_to_users(
+        "DEVICE_APPROVAL_UPDATE",
+        json!({ "session_id": session_id, "approved": true }),
+        vec![auth.user_id],
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/sessions/{session_id}/deny",
+    tag = "auth",
+    params(("session_id" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn deny_session_device(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_approved_device(&state, &auth).await?;
+    let session = paracord_db::sessions::get_session_by_id(&state.db, &session_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|session| session.user_id == auth.user_id && session.device_approved_at.is_none())
+        .ok_or(ApiError::Code(ErrorCode::UnknownSession))?;
+    let revoked = paracord_db::sessions::revoke_session(
+        &state.db,
+        &session.id,
+        auth.user_id,
+        "device_denied",
+        Utc::now(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !revoked {
+        return Err(ApiError::NotFound);
+    }
+
+    security::log_security_event(
+        &state,
+        "auth.session.device_deny",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        Some(&session_id),
+        Some(&headers),
+        Some(json!({ "denied_by_session_id": auth.session_id })),
+    )
+    .await;
+    state.event_bus.dispatch_to_users(
+        "DEVICE_APPROVAL_UPDATE",
+        json!({ "session_id": session_id, "approved": false }),
+        vec![auth.user_id],
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeviceRecoveryRequest {
+    pub recovery_code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeviceRecoveryCodeResponse {
+    /// Replacement code; the previous one no longer works.
+    pub recovery_code: String,
+}
+
+/// Approve the caller's own device with the account's recovery code, for
+/// when no trusted session is around to approve it. The code is used up and
+/// a new one returned.
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/device-recovery",
+    tag = "auth",
+    request_body = DeviceRecoveryRequest,
+    responses((status = 200, description = "OK", body = DeviceRecoveryCodeResponse)),
+    security(("bearer" = [])),
+)]
+pub async fn recover_device(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<DeviceRecoveryRequest>,
+) -> Result<Json<DeviceRecoveryCodeResponse>, ApiError> {
+    let session_id = auth.session_id.clone().ok_or(ApiError::Forbidden)?;
+    let valid = paracord_db::sessions::consume_device_recovery_code(
+        &state.db,
+        auth.user_id,
+        &body.recovery_code,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !valid {
+        security::log_security_event(
+            &state,
+            "auth.session.device_recovery_failed",
+            Some(auth.user_id),
+            Some(auth.user_id),
+            Some(&session_id),
+            Some(&headers),
+            None,
+        )
+        .await;
+        return Err(ApiError::Forbidden);
+    }
+    paracord_db::sessions::approve_session_device(&state.db, &session_id, auth.user_id, Utc::now())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    remember_session_device(&state, &session_id, auth.user_id).await?;
+    let recovery_code = issue_device_recovery_code(&state, auth.user_id).await?;
+
+    security::log_security_event(
+        &state,
+        "auth.session.device_recover",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        Some(&session_id),
+        Some(&headers),
+        None,
+    )
+    .await;
+    state.event_bus.dispatch_to_users(
+        "DEVICE_APPROVAL_UPDATE",
+        json!({ "session_id": session_id, "approved": true }),
+        vec![auth.user_id],
+    );
+    Ok(Json(DeviceRecoveryCodeResponse { recovery_code }))
+}
+
+/// Replace the account's device recovery code from a trusted session.
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/device-recovery-code",
+    tag = "auth",
+    responses((status = 200, description = "OK", body = DeviceRecoveryCodeResponse)),
+    security(("bearer" = [])),
+)]
+pub async fn regenerate_device_recovery_code(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+) -> Result<Json<DeviceRecoveryCodeResponse>, ApiError> {
+    require_approved_device(&state, &auth).await?;
+    let recovery_code = issue_device_recovery_code(&state, auth.user_id).await?;
+    security::log_security_event(
+        &state,
+        "auth.session.device_recovery_code_reset",
+        Some(auth.user_id),
+        Some(auth.user_id),
+        auth.session_id.as_deref(),
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(Json(DeviceRecoveryCodeResponse { recovery_code }))
+}
+
 // --- Public key attachment (migration for existing password-based accounts) ---
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct AttachPublicKeyRequest {
     pub public_key: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/attach-public-key",
+    tag = "auth",
+    request_body = AttachPublicKeyRequest,
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn attach_public_key(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1376,13 +1660,19 @@ pub async fn attach_public_key(
 
 // --- Ed25519 challenge-response authentication ---
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ChallengeResponse {
     pub nonce: String,
     pub timestamp: i64,
     pub server_origin: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/challenge",
+    tag = "auth",
+    responses((status = 200, description = "OK", body = ChallengeResponse)),
+)]
 pub async fn challenge(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -1416,7 +1706,7 @@ pub async fn challenge(
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct VerifyRequest {
     pub public_key: String,
     pub nonce: String,
@@ -1426,6 +1716,13 @@ pub struct VerifyRequest {
     pub display_name: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/verify",
+    tag = "auth",
+    request_body = VerifyRequest,
+    responses((status = 200, description = "OK")),
+)]
 pub async fn verify(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -1564,7 +1861,7 @@ pub async fn verify(
             }
 
             // Auto-register: create new user from public key.
-            let id = paracord_util::snowflake::generate(1);
+            let id = paracord_util::snowflake::try_next_id().await?;
             let new_user = paracord_db::users::create_user_from_pubkey_as_first_admin(
                 &state.db,
                 id,
@@ -1649,6 +1946,22 @@ mod tests {
         assert!(keys.contains(&"acct:user@example.com".to_string()));
     }
 
+    #[test]
+    fn auth_guard_keys_use_forwarded_client_from_trusted_proxy_range() {
+        let _guard = env_lock().lock().expect("env lock");
+        std::env::set_var("PARACORD_TRUST_PROXY", "true");
+        std::env::set_var("PARACORD_TRUSTED_PROXY_IPS", "10.0.0.0/8");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "forwarded",
+            HeaderValue::from_static("for=203.0.113.4;proto=https"),
+        );
+        let keys = auth_guard_keys(&headers, Some("10.20.30.40"), None);
+        assert!(keys.contains(&"ip:203.0.113.4".to_string()));
+        std::env::remove_var("PARACORD_TRUST_PROXY");
+        std::env::remove_var("PARACORD_TRUSTED_PROXY_IPS");
+    }
+
     #[test]
     fn refresh_cookie_roundtrip_parsing_works() {
         let cookie = build_refresh_cookie("token-value", 7, true);
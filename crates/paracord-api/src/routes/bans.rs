@@ -4,8 +4,10 @@ use axum::{
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -22,6 +24,14 @@ fn contains_dangerous_markup(value: &str) -> bool {
         || lower.contains("<iframe")
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/bans",
+    tag = "bans",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_bans(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -31,7 +41,7 @@ pub async fn list_bans(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
@@ -66,11 +76,20 @@ pub async fn list_bans(
     Ok(Json(json!(result)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BanRequest {
     pub reason: Option<String>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/bans/{user_id}",
+    tag = "bans",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    request_body = Option<BanRequest>,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn ban_member(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -106,7 +125,7 @@ pub async fn ban_member(
         Some(guild_id),
     );
 
-    state.member_index.remove_member(guild_id, user_id);
+    paracord_core::member_list::remove_member(&state, guild_id, user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_REMOVE",
         json!({
@@ -143,6 +162,14 @@ pub async fn ban_member(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/bans/{user_id}",
+    tag = "bans",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn unban_member(
     State(state): State<AppState>,
     auth: AuthUser,
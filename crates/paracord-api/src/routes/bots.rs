@@ -3,15 +3,18 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use paracord_core::limits::{self, GuildLimit};
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use rand::RngCore;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use url::Url;
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
-use crate::middleware::AuthUser;
+use crate::middleware::{AuthUser, PermissionsVersion};
 
 const MAX_BOT_NAME_LEN: usize = 80;
 const MAX_BOT_DESCRIPTION_LEN: usize = 400;
@@ -114,7 +117,7 @@ async fn ensure_manage_guild(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
 
@@ -127,7 +130,7 @@ async fn ensure_manage_guild(
     Ok(())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateBotApplicationRequest {
     pub name: String,
     pub description: Option<String>,
@@ -135,9 +138,18 @@ pub struct CreateBotApplicationRequest {
     pub permissions: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/bots/applications",
+    tag = "bots",
+    request_body = CreateBotApplicationRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_bot_application(
     State(state): State<AppState>,
     auth: AuthUser,
+    permissions_version: PermissionsVersion,
     Json(body): Json<CreateBotApplicationRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
     let name = body.name.trim();
@@ -172,10 +184,11 @@ pub async fn create_bot_application(
         .as_deref()
         .map(|v| parse_permission_bits(v, "permissions"))
         .transpose()?
+        .map(|bits| permissions_version.normalize(bits))
         .unwrap_or(0);
 
-    let app_id = paracord_util::snowflake::generate(1);
-    let bot_user_id = paracord_util::snowflake::generate(1);
+    let app_id = paracord_util::snowflake::try_next_id().await?;
+    let bot_user_id = paracord_util::snowflake::try_next_id().await?;
     let bot_username = format!("bot-{}", app_id);
     let bot_email = format!("bot-{}@bots.paracord.local", bot_user_id);
     let discriminator = ((bot_user_id % 9000) + 1000) as i16;
@@ -223,6 +236,13 @@ pub async fn create_bot_application(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/bots/applications",
+    tag = "bots",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_bot_applications(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -236,6 +256,14 @@ pub async fn list_bot_applications(
         .collect::<Vec<Value>>())))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/bots/applications/{bot_app_id}",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_bot_application(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -253,6 +281,14 @@ pub async fn get_bot_application(
     Ok(Json(bot_app_to_json(&app, None)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/bots/applications/{bot_app_id}/public",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_public_bot_application(
     State(state): State<AppState>,
     _auth: AuthUser,
@@ -286,13 +322,22 @@ pub async fn get_public_bot_application(
     })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateBotApplicationRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub redirect_uri: Option<String>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/bots/applications/{bot_app_id}",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    request_body = UpdateBotApplicationRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_bot_application(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -349,6 +394,14 @@ pub async fn update_bot_application(
     Ok(Json(bot_app_to_json(&updated, None)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/bots/applications/{bot_app_id}",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_bot_application(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -368,9 +421,7 @@ pub async fn delete_bot_application(
     for install in installs {
         let _ =
             paracord_db::members::remove_member(&state.db, app.bot_user_id, install.guild_id).await;
-        state
-            .member_index
-            .remove_member(install.guild_id, app.bot_user_id);
+        paracord_core::member_list::remove_member(&state, install.guild_id, app.bot_user_id);
         state.event_bus.dispatch(
             "GUILD_MEMBER_REMOVE",
             json!({
@@ -392,6 +443,14 @@ pub async fn delete_bot_application(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/bots/applications/{bot_app_id}/token",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn regenerate_bot_token(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -414,6 +473,14 @@ pub async fn regenerate_bot_token(
     Ok(Json(bot_app_to_json(&updated, Some(&token))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/bots/applications/{bot_app_id}/installs",
+    tag = "bots",
+    params(("bot_app_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_bot_application_installs(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -442,6 +509,14 @@ pub async fn list_bot_application_installs(
         .collect::<Vec<Value>>())))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/bots",
+    tag = "bots",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_bots(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -475,6 +550,14 @@ pub async fn list_guild_bots(
     Ok(Json(json!(rows)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/bots/{bot_app_id}",
+    tag = "bots",
+    params(("guild_id" = i64, Path), ("bot_app_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn remove_guild_bot(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -492,7 +575,7 @@ pub async fn remove_guild_bot(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     let _ = paracord_db::members::remove_member(&state.db, app.bot_user_id, guild_id).await;
 
-    state.member_index.remove_member(guild_id, app.bot_user_id);
+    paracord_core::member_list::remove_member(&state, guild_id, app.bot_user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_REMOVE",
         json!({
@@ -506,7 +589,7 @@ pub async fn remove_guild_bot(
     Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct OAuth2AuthorizeRequest {
     pub application_id: String,
     pub guild_id: String,
@@ -515,9 +598,18 @@ pub struct OAuth2AuthorizeRequest {
     pub state: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/oauth2/authorize",
+    tag = "bots",
+    request_body = OAuth2AuthorizeRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn oauth2_authorize(
     State(state): State<AppState>,
     auth: AuthUser,
+    permissions_version: PermissionsVersion,
     Json(body): Json<OAuth2AuthorizeRequest>,
 ) -> Result<Json<Value>, ApiError> {
     let app_id = body
@@ -532,7 +624,8 @@ pub async fn oauth2_authorize(
         .permissions
         .as_deref()
         .map(|v| parse_permission_bits(v, "permissions"))
-        .transpose()?;
+        .transpose()?
+        .map(|bits| permissions_version.normalize(bits));
     let redirect_uri = body
         .redirect_uri
         .as_deref()
@@ -564,6 +657,18 @@ pub async fn oauth2_authorize(
         }
     }
 
+    // Re-authorizing an installed bot only updates its permissions.
+    let installed = paracord_db::bot_applications::is_bot_in_guild(&state.db, app_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !installed {
+        let max_bots = GuildLimit::Bots.configured(&*state.runtime.read().await);
+        let bot_count = paracord_db::bot_applications::count_guild_bots(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        limits::ensure_below(GuildLimit::Bots, max_bots, bot_count)?;
+    }
+
     let effective_permissions = requested_permissions.unwrap_or(app.permissions);
     let _ = paracord_db::bot_applications::add_bot_to_guild(
         &state.db,
@@ -575,7 +680,7 @@ pub async fn oauth2_authorize(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     let _ = paracord_db::members::add_member(&state.db, app.bot_user_id, guild_id).await;
-    state.member_index.add_member(guild_id, app.bot_user_id);
+    paracord_core::member_list::add_member(&state, guild_id, app.bot_user_id);
 
     let user_row = paracord_db::users::get_user_by_id(&state.db, app.bot_user_id)
         .await
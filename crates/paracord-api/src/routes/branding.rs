@@ -0,0 +1,615 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use paracord_core::AppState;
+use paracord_media::images::AssetImageType;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::{AdminUser, AuthUser};
+use crate::routes::security;
+
+const MAX_LOGO_SIZE: usize = 1024 * 1024; // 1 MB
+const MAX_THEME_ASSET_SIZE: usize = 1024 * 1024; // 1 MB
+const MAX_THEME_ASSETS_PER_GUILD: i64 = 25;
+const MAX_THEME_ASSET_NAME_LEN: usize = 32;
+const MAX_GUILD_CSS_LEN: usize = 20 * 1024;
+
+/// `#rrggbb`, the only color form accepted for branding and guild themes.
+pub(crate) fn is_hex_color(value: &str) -> bool {
+    value.len() == 7
+        && value.starts_with('#')
+        && value[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn logo_storage_key(file_name: &str) -> String {
+    format!("branding/{}", file_name)
+}
+
+fn theme_asset_storage_key(guild_id: i64, asset_id: i64, ext: &str) -> String {
+    format!("guild-themes/{}/{}.{}", guild_id, asset_id, ext)
+}
+
+fn theme_asset_path(guild_id: i64, asset_id: i64) -> String {
+    format!("/api/v1/guilds/{}/theme/assets/{}", guild_id, asset_id)
+}
+
+fn content_type_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "png" => "image/png",
+        "jpg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Read the first file field from a multipart body, plus an optional `name`.
+async fn read_image_upload(
+    multipart: &mut Multipart,
+    max_size: usize,
+) -> Result<(Option<String>, Vec<u8>, AssetImageType), ApiError> {
+    let mut name: Option<String> = None;
+    let mut data: Option<Vec<u8>> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        match field.name() {
+            Some("name") => {
+                name = Some(
+                    field
+                        .text()
+                        .await
+                        .map_err(|e| ApiError::BadRequest(e.to_string()))?,
+                );
+            }
+            Some("image") | Some("file") => {
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                data = Some(bytes.to_vec());
+            }
+            _ => {}
+        }
+    }
+
+    let data = data.ok_or_else(|| ApiError::BadRequest("Missing image".into()))?;
+    if data.is_empty() {
+        return Err(ApiError::BadRequest("Empty image".into()));
+    }
+    if data.len() > max_size {
+        return Err(ApiError::BadRequest(format!(
+            "Image must be under {} KB",
+            max_size / 1024
+        )));
+    }
+    let image_type = AssetImageType::sniff(&data).ok_or_else(|| {
+        ApiError::BadRequest("Only PNG, JPEG, GIF, and WebP images are supported".into())
+    })?;
+    Ok((name, data, image_type))
+}
+
+fn image_response(data: Vec<u8>, content_type: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static("public, max-age=31536000, immutable"),
+            ),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+        ],
+        data,
+    )
+        .into_response()
+}
+
+// ── Instance branding ───────────────────────────────────────────────────
+
+/// Public branding for login pages and the client shell. No auth required.
+#[utoipa::path(
+    get,
+    path = "/api/v1/branding",
+    tag = "branding",
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn get_branding(State(state): State<AppState>) -> Json<Value> {
+    let settings = state.runtime.read().await;
+    Json(json!({
+        "server_name": settings.server_name,
+        "server_description": settings.server_description,
+        "accent_color": Some(settings.branding_accent_color.as_str()).filter(|c| !c.is_empty()),
+        "login_text": Some(settings.branding_login_text.as_str()).filter(|t| !t.is_empty()),
+        // The file name changes with every upload, so the URL is cache-safe.
+        "logo_url": settings
+            .branding_logo
+            .as_ref()
+            .map(|logo| format!("/api/v1/branding/logo/{}", logo)),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/branding/logo/{file_name}",
+    tag = "branding",
+    params(("file_name" = String, Path)),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn get_branding_logo(
+    State(state): State<AppState>,
+    Path(file_name): Path<String>,
+) -> Result<Response, ApiError> {
+    let current = state.runtime.read().await.branding_logo.clone();
+    if current.as_deref() != Some(file_name.as_str()) {
+        return Err(ApiError::NotFound);
+    }
+    let ext = file_name.rsplit('.').next().unwrap_or_default();
+    let data = state
+        .storage_backend
+        .retrieve(&logo_storage_key(&file_name))
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    Ok(image_response(data, content_type_for_extension(ext)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/branding/logo",
+    tag = "branding",
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn upload_branding_logo(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let (_, data, image_type) = read_image_upload(&mut multipart, MAX_LOGO_SIZE).await?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&data);
+    let file_name = format!(
+        "{}.{}",
+        &format!("{:x}", hasher.finalize())[..32],
+        image_type.extension()
+    );
+    state
+        .storage_backend
+        .store(&logo_storage_key(&file_name), &data)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    paracord_db::server_settings::set_setting(&state.db, "branding_logo", &file_name)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let previous = state
+        .runtime
+        .write()
+        .await
+        .branding_logo
+        .replace(file_name.clone());
+    if let Some(previous) = previous.filter(|previous| *previous != file_name) {
+        let _ = state
+            .storage_backend
+            .delete(&logo_storage_key(&previous))
+            .await;
+    }
+
+    security::log_security_event(
+        &state,
+        "admin.branding.logo.update",
+        Some(admin.user_id),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    Ok(Json(get_branding(State(state)).await.0))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/branding/logo",
+    tag = "branding",
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn delete_branding_logo(
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> Result<StatusCode, ApiError> {
+    paracord_db::server_settings::set_setting(&state.db, "branding_logo", "")
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let previous = state.runtime.write().await.branding_logo.take();
+    if let Some(previous) = previous {
+        let _ = state
+            .storage_backend
+            .delete(&logo_storage_key(&previous))
+            .await;
+    }
+
+    security::log_security_event(
+        &state,
+        "admin.branding.logo.delete",
+        Some(admin.user_id),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Guild themes ────────────────────────────────────────────────────────
+
+async fn ensure_manage_guild(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+
+    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms =
+        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    Ok(())
+}
+
+/// Guild CSS follows the user custom CSS rules, except that `url()` may
+/// point at this guild's own theme assets (and nothing else).
+fn sanitize_guild_css(value: &str, guild_id: i64) -> Result<Option<String>, ApiError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed.len() > MAX_GUILD_CSS_LEN {
+        return Err(ApiError::BadRequest("custom_css exceeds 20KB".into()));
+    }
+    let lower = trimmed.to_ascii_lowercase();
+    if lower.contains("@import")
+        || lower.contains("@font-face")
+        || lower.contains("expression(")
+        || lower.contains("javascript:")
+        || lower.contains('\\')
+    {
+        return Err(ApiError::BadRequest(
+            "custom_css contains disallowed directives".into(),
+        ));
+    }
+
+    let allowed_prefix = format!("/api/v1/guilds/{}/theme/assets/", guild_id);
+    let mut rest = lower.as_str();
+    while let Some(start) = rest.find("url(") {
+        let after = &rest[start + 4..];
+        let end = after
+            .find(')')
+            .ok_or_else(|| ApiError::BadRequest("custom_css has an unterminated url()".into()))?;
+        let target = after[..end]
+            .trim()
+            .trim_matches(|ch| ch == '"' || ch == '\'');
+        let asset_id = target.strip_prefix(&allowed_prefix).unwrap_or_default();
+        if asset_id.is_empty() || !asset_id.chars().all(|ch| ch.is_ascii_digit()) {
+            return Err(ApiError::BadRequest(
+                "custom_css may only reference this guild's theme assets".into(),
+            ));
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(Some(trimmed.to_string()))
+}
+
+fn theme_asset_to_json(asset: &paracord_db::guild_themes::GuildThemeAssetRow) -> Value {
+    json!({
+        "id": asset.id.to_string(),
+        "guild_id": asset.guild_id.to_string(),
+        "name": asset.name,
+        "content_type": asset.content_type,
+        "size": asset.size,
+        "uploader_id": asset.uploader_id.map(|id| id.to_string()),
+        "url": theme_asset_path(asset.guild_id, asset.id),
+        "created_at": asset.created_at.to_rfc3339(),
+    })
+}
+
+fn theme_to_json(
+    guild_id: i64,
+    theme: Option<&paracord_db::guild_themes::GuildThemeRow>,
+    assets: &[paracord_db::guild_themes::GuildThemeAssetRow],
+) -> Value {
+    json!({
+        "guild_id": guild_id.to_string(),
+        "accent_color": theme.and_then(|t| t.accent_color.clone()),
+        "custom_css": theme.and_then(|t| t.custom_css.clone()),
+        "assets": assets.iter().map(theme_asset_to_json).collect::<Vec<Value>>(),
+        "updated_at": theme.map(|t| t.updated_at.to_rfc3339()),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/theme",
+    tag = "branding",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_guild_theme(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let theme = paracord_db::guild_themes::get_theme(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let assets = paracord_db::guild_themes::list_assets(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(theme_to_json(guild_id, theme.as_ref(), &assets)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateGuildThemeRequest {
+    /// `#rrggbb`; empty string clears it.
+    pub accent_color: Option<String>,
+    /// Empty string clears it.
+    pub custom_css: Option<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/theme",
+    tag = "branding",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateGuildThemeRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_guild_theme(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateGuildThemeRequest>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let existing = paracord_db::guild_themes::get_theme(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let accent_color = match body.accent_color.as_deref().map(str::trim) {
+        Some("") => None,
+        Some(color) if is_hex_color(color) => Some(color.to_ascii_lowercase()),
+        Some(_) => {
+            return Err(ApiError::BadRequest(
+                "accent_color must be a #rrggbb color".into(),
+            ))
+        }
+        None => existing.as_ref().and_then(|t| t.accent_color.clone()),
+    };
+    let custom_css = match body.custom_css.as_deref() {
+        Some(css) => sanitize_guild_css(css, guild_id)?,
+        None => existing.as_ref().and_then(|t| t.custom_css.clone()),
+    };
+
+    let theme = paracord_db::guild_themes::upsert_theme(
+        &state.db,
+        guild_id,
+        accent_color.as_deref(),
+        custom_css.as_deref(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let assets = paracord_db::guild_themes::list_assets(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let theme_json = theme_to_json(guild_id, Some(&theme), &assets);
+
+    state
+        .event_bus
+        .dispatch("GUILD_THEME_UPDATE", theme_json.clone(), Some(guild_id));
+
+    Ok(Json(theme_json))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/theme/assets",
+    tag = "branding",
+    params(("guild_id" = i64, Path)),
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn upload_guild_theme_asset(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    ensure_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let (name, data, image_type) = read_image_upload(&mut multipart, MAX_THEME_ASSET_SIZE).await?;
+    let name = name
+        .map(|name| name.trim().to_string())
+        .ok_or_else(|| ApiError::BadRequest("Missing asset name".into()))?;
+    if name.is_empty()
+        || name.len() > MAX_THEME_ASSET_NAME_LEN
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+    {
+        return Err(ApiError::BadRequest(
+            "Asset name must be 1-32 letters, digits, '_' or '-'".into(),
+        ));
+    }
+
+    let count = paracord_db::guild_themes::count_assets(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if count >= MAX_THEME_ASSETS_PER_GUILD {
+        return Err(ApiError::BadRequest(format!(
+            "Guilds can have at most {} theme assets",
+            MAX_THEME_ASSETS_PER_GUILD
+        )));
+    }
+    let taken = paracord_db::guild_themes::list_assets(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .iter()
+        .any(|asset| asset.name == name);
+    if taken {
+        return Err(ApiError::Conflict(format!(
+            "A theme asset named \"{}\" already exists",
+            name
+        )));
+    }
+
+    let asset_id = paracord_util::snowflake::try_next_id().await?;
+    state
+        .storage_backend
+        .store(
+            &theme_asset_storage_key(guild_id, asset_id, image_type.extension()),
+            &data,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let asset = paracord_db::guild_themes::create_asset(
+        &state.db,
+        asset_id,
+        guild_id,
+        &name,
+        image_type.content_type(),
+        data.len() as i64,
+        auth.user_id,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    Ok((StatusCode::CREATED, Json(theme_asset_to_json(&asset))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/theme/assets/{asset_id}",
+    tag = "branding",
+    params(("guild_id" = i64, Path), ("asset_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_guild_theme_asset(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, asset_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    ensure_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let asset = paracord_db::guild_themes::get_asset(&state.db, asset_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|asset| asset.guild_id == guild_id)
+        .ok_or(ApiError::NotFound)?;
+
+    paracord_db::guild_themes::delete_asset(&state.db, asset_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let ext = AssetImageType::from_content_type(&asset.content_type)
+        .map(AssetImageType::extension)
+        .unwrap_or("bin");
+    let _ = state
+        .storage_backend
+        .delete(&theme_asset_storage_key(guild_id, asset_id, ext))
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/theme/assets/{asset_id}",
+    tag = "branding",
+    params(("guild_id" = i64, Path), ("asset_id" = i64, Path)),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn get_guild_theme_asset(
+    State(state): State<AppState>,
+    Path((guild_id, asset_id)): Path<(i64, i64)>,
+) -> Result<Response, ApiError> {
+    let asset = paracord_db::guild_themes::get_asset(&state.db, asset_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|asset| asset.guild_id == guild_id)
+        .ok_or(ApiError::NotFound)?;
+    let image_type =
+        AssetImageType::from_content_type(&asset.content_type).ok_or(ApiError::NotFound)?;
+    let data = state
+        .storage_backend
+        .retrieve(&theme_asset_storage_key(
+            guild_id,
+            asset_id,
+            image_type.extension(),
+        ))
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    Ok(image_response(data, image_type.content_type()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_require_six_digits() {
+        assert!(is_hex_color("#5865f2"));
+        assert!(is_hex_color("#ABCDEF"));
+        assert!(!is_hex_color("5865f2"));
+        assert!(!is_hex_color("#fff"));
+        assert!(!is_hex_color("#gggggg"));
+    }
+
+    #[test]
+    fn guild_css_only_references_own_assets() {
+        let ok = "body { background: url('/api/v1/guilds/7/theme/assets/123'); }";
+        assert!(sanitize_guild_css(ok, 7).unwrap().is_some());
+        assert!(sanitize_guild_css("  ", 7).unwrap().is_none());
+
+        for bad in [
+            "body { background: url(https://evil.example/x.png); }",
+            "body { background: url(/api/v1/guilds/8/theme/assets/123); }",
+            "body { background: url(/api/v1/guilds/7/theme/assets/../x); }",
+            "@import 'x.css';",
+            "a { b: \\75rl(x) }",
+            "body { background: url(/api/v1/guilds/7/theme/assets/1",
+        ] {
+            assert!(sanitize_guild_css(bad, 7).is_err(), "accepted: {bad}");
+        }
+    }
+}
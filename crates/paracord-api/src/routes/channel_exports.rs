@@ -0,0 +1,366 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Json,
+};
+use chrono::{Duration, Utc};
+use paracord_core::AppState;
+use paracord_db::channel_exports::{
+    ChannelExportRow, EXPORT_FORMAT_HTML, EXPORT_FORMAT_JSON, EXPORT_STATUS_COMPLETE,
+    EXPORT_STATUS_PENDING,
+};
+use paracord_db::jobs::JobRow;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
+use paracord_transport::transfer_limits::TransferDirection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::channels::{ensure_age_gate_acknowledged, ensure_channel_permissions};
+
+/// How long a finished export can be downloaded.
+const EXPORT_TTL_HOURS: i64 = 24;
+const EXPORT_LIST_LIMIT: i64 = 20;
+const EXPORT_AAD_PREFIX: &str = "channel-export:";
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateExportRequest {
+    /// `html` (default) or `json`.
+    pub format: Option<String>,
+}
+
+fn export_aad(export_id: i64) -> String {
+    format!("{EXPORT_AAD_PREFIX}{export_id}")
+}
+
+fn export_storage_key(export_id: i64, format: &str) -> String {
+    format!("exports/{export_id}.{format}")
+}
+
+fn download_url(export_id: i64, token: &str) -> String {
+    format!("/api/v1/channel-exports/{export_id}/{token}")
+}
+
+fn export_to_json(export: &ChannelExportRow) -> Value {
+    json!({
+        "id": export.id.to_string(),
+        "guild_id": export.guild_id.to_string(),
+        "channel_id": export.channel_id.to_string(),
+        "requested_by": export.requested_by.map(|id| id.to_string()),
+        "format": export.format,
+        "status": export.status,
+        "message_count": export.message_count,
+        "error": export.error,
+        "created_at": export.created_at.to_rfc3339(),
+        "completed_at": export.completed_at.map(|at| at.to_rfc3339()),
+        "expires_at": export.expires_at.to_rfc3339(),
+    })
+}
+
+fn generate_download_token() -> String {
+    use rand::RngCore;
+    let mut bytes = [0_u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+/// Load a guild channel and check the caller may export it.
+async fn export_channel_context(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<paracord_db::channels::ChannelRow, ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    if channel.guild_id().is_none() {
+        return Err(ApiError::BadRequest(
+            "Only guild channels can be exported".into(),
+        ));
+    }
+    ensure_channel_permissions(
+        state,
+        &channel,
+        user_id,
+        &[
+            Permissions::VIEW_CHANNEL,
+            Permissions::READ_MESSAGE_HISTORY,
+            Permissions::MANAGE_MESSAGES,
+        ],
+    )
+    .await?;
+    ensure_age_gate_acknowledged(state, &channel, user_id).await?;
+    Ok(channel)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/exports",
+    tag = "channel_exports",
+    params(("channel_id" = i64, Path)),
+    request_body = CreateExportRequest,
+    responses((status = 202, description = "Accepted", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn create_export(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<CreateExportRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let format = body.format.as_deref().unwrap_or(EXPORT_FORMAT_HTML);
+    if format != EXPORT_FORMAT_HTML && format != EXPORT_FORMAT_JSON {
+        return Err(ApiError::BadRequest(
+            "format must be 'html' or 'json'".into(),
+        ));
+    }
+    let channel = export_channel_context(&state, channel_id, auth.user_id).await?;
+    let guild_id = channel.guild_id().unwrap_or_default();
+
+    let recent = paracord_db::channel_exports::list_channel_exports(&state.db, channel_id, 1)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if recent
+        .iter()
+        .any(|export| export.status == EXPORT_STATUS_PENDING)
+    {
+        return Err(ApiError::Conflict(
+            "An export of this channel is already running".into(),
+        ));
+    }
+
+    let export_id = paracord_util::snowflake::try_next_id().await?;
+    let token = generate_download_token();
+    let export = paracord_db::channel_exports::create_export(
+        &state.db,
+        export_id,
+        guild_id,
+        channel_id,
+        auth.user_id,
+        format,
+        &token,
+        Utc::now() + Duration::hours(EXPORT_TTL_HOURS),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    paracord_core::jobs::enqueue(
+        &state.db,
+        crate::jobs::JOB_CHANNEL_EXPORT,
+        &json!({ "export_id": export.id.to_string() }),
+        paracord_core::jobs::JobOptions::default(),
+    )
+    .await?;
+
+    let mut payload = export_to_json(&export);
+    // The token is only handed out here; the database keeps its hash.
+    payload["download_url"] = json!(download_url(export.id, &token));
+    Ok((StatusCode::ACCEPTED, Json(payload)))
+}
+
+#[derive(Deserialize)]
+struct ExportJobPayload {
+    export_id: String,
+}
+
+/// Background job: render a pending export and store it. The export is
+/// marked failed once the job runs out of attempts.
+pub(crate) async fn run_export_job(state: AppState, job: JobRow) -> Result<(), String> {
+    let payload: ExportJobPayload = paracord_core::jobs::payload(&job)?;
+    let export_id = payload
+        .export_id
+        .parse::<i64>()
+        .map_err(|_| "Invalid export_id".to_string())?;
+    let Some(export) = paracord_db::channel_exports::get_export(&state.db, export_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|export| export.status == EXPORT_STATUS_PENDING)
+    else {
+        return Ok(());
+    };
+    let Some(channel) = paracord_db::channels::get_channel(&state.db, export.channel_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        paracord_db::channel_exports::fail_export(&state.db, export.id, "Channel was deleted")
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+
+    match render_and_store(&state, &channel, &export).await {
+        Ok((storage_key, message_count)) => {
+            paracord_db::channel_exports::complete_export(
+                &state.db,
+                export.id,
+                &storage_key,
+                message_count,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        Err(error) => {
+            if job.is_last_attempt() {
+                tracing::warn!("Channel export {} failed: {}", export.id, error);
+                if let Err(e) =
+                    paracord_db::channel_exports::fail_export(&state.db, export.id, &error).await
+                {
+                    tracing::warn!("Failed to finish channel export {}: {}", export.id, e);
+                }
+            }
+            Err(error)
+        }
+    }
+}
+
+async fn render_and_store(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    export: &ChannelExportRow,
+) -> Result<(String, i64), String> {
+    let transcript = paracord_core::channel_export::collect_transcript(&state.db, channel)
+        .await
+        .map_err(|e| e.to_string())?;
+    let data = if export.format == EXPORT_FORMAT_JSON {
+        paracord_core::channel_export::render_json(&transcript)
+    } else {
+        paracord_core::channel_export::render_html(&transcript)
+    };
+    let stored_payload = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
+        let aad = export_aad(export.id);
+        cryptor
+            .encrypt_with_aad(&data, aad.as_bytes())
+            .map_err(|e| e.to_string())?
+    } else {
+        data
+    };
+    let storage_key = export_storage_key(export.id, &export.format);
+    state
+        .storage_backend
+        .store(&storage_key, &stored_payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((storage_key, transcript.messages.len() as i64))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/exports",
+    tag = "channel_exports",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_exports(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    export_channel_context(&state, channel_id, auth.user_id).await?;
+    let exports = paracord_db::channel_exports::list_channel_exports(
+        &state.db,
+        channel_id,
+        EXPORT_LIST_LIMIT,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(Value::Array(
+        exports.iter().map(export_to_json).collect(),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/exports/{export_id}",
+    tag = "channel_exports",
+    params(("channel_id" = i64, Path), ("export_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_export(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, export_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    export_channel_context(&state, channel_id, auth.user_id).await?;
+    let export = paracord_db::channel_exports::get_export(&state.db, export_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|export| export.channel_id == channel_id)
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(export_to_json(&export)))
+}
+
+/// Download a finished export - no auth required, uses the token from the
+/// download link.
+#[utoipa::path(
+    get,
+    path = "/api/v1/channel-exports/{export_id}/{token}",
+    tag = "channel_exports",
+    params(("export_id" = i64, Path), ("token" = String, Path)),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path((export_id, token)): Path<(i64, String)>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let export = paracord_db::channel_exports::get_export_by_token(&state.db, export_id, &token)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|export| !export.is_expired(Utc::now()))
+        .ok_or(ApiError::NotFound)?;
+    if export.status != EXPORT_STATUS_COMPLETE {
+        return Err(ApiError::Conflict("Export is not ready".into()));
+    }
+    let storage_key = export.storage_key.as_deref().ok_or(ApiError::NotFound)?;
+    // Links are shareable; the transfer counts against whoever asked for it.
+    let permit = export
+        .requested_by
+        .map(|user_id| super::files::begin_transfer(&state, user_id, TransferDirection::Download))
+        .transpose()?;
+    let stored_data = state
+        .storage_backend
+        .retrieve(storage_key)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let data = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
+        let aad = export_aad(export.id);
+        cryptor
+            .decrypt_with_aad(&stored_data, aad.as_bytes())
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    } else {
+        stored_data
+    };
+
+    let content_type = if export.format == EXPORT_FORMAT_JSON {
+        "application/json"
+    } else {
+        "text/html; charset=utf-8"
+    };
+    let filename = format!(
+        "channel-{}-{}.{}",
+        export.channel_id,
+        export.created_at.format("%Y%m%d-%H%M%S"),
+        export.format
+    );
+    // Always a download: the HTML must not run in the API origin.
+    let disposition = super::files::build_content_disposition(&filename, false);
+    Ok(super::files::media_response(
+        &headers,
+        content_type,
+        &disposition,
+        data,
+        permit,
+    ))
+}
@@ -1,19 +1,23 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
-use paracord_core::{AppState, MESSAGE_FLAG_DM_E2EE};
+use paracord_core::limits::GuildLimit;
+use paracord_core::{AppState, MESSAGE_FLAG_DM_E2EE, MESSAGE_FLAG_VOICE_MESSAGE};
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
-use crate::middleware::AuthUser;
-use crate::routes::audit;
+use crate::middleware::{AuthUser, PermissionsVersion};
+use crate::routes::{audit, dms, url_denylist};
 
 const MAX_CHANNEL_TOPIC_LEN: usize = 1_024;
 const MAX_BULK_DELETE_REQUEST_IDS: usize = 500;
+const MAX_CHANNEL_OVERWRITES: usize = 100;
 const MAX_POLL_QUESTION_LEN: usize = 300;
 const MAX_POLL_OPTION_LEN: usize = 100;
 const MAX_POLL_OPTIONS: usize = 10;
@@ -29,35 +33,67 @@ fn contains_dangerous_markup(value: &str) -> bool {
         || lower.contains("<iframe")
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateChannelRequest {
     pub name: String,
     #[serde(default)]
     pub channel_type: i16,
     pub parent_id: Option<i64>,
     pub required_role_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub nsfw: bool,
+    pub posting_mode: Option<i16>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateChannelRequest {
     pub name: Option<String>,
     pub topic: Option<String>,
     pub required_role_ids: Option<Vec<String>>,
+    pub nsfw: Option<bool>,
+    pub posting_mode: Option<i16>,
 }
 
-#[derive(Deserialize)]
+/// Message history cursor. At most one of `before`, `after` and `around`
+/// may be given; without any the latest messages are returned.
+#[derive(Deserialize, IntoParams)]
 pub struct MessageQuery {
+    /// Messages older than this id.
     pub before: Option<i64>,
+    /// Messages newer than this id.
+    pub after: Option<i64>,
+    /// Messages on both sides of this id, including it.
+    pub around: Option<i64>,
+    /// Page size, 1-100. Defaults to 50.
     pub limit: Option<i64>,
 }
 
-#[derive(Deserialize)]
+pub(crate) const SEARCH_DEFAULT_LIMIT: i64 = 20;
+pub(crate) const SEARCH_MAX_LIMIT: i64 = 100;
+
+/// Search filters. `q` may also carry them inline as `from:<user_id>`,
+/// `in:<channel_id>`, `has:attachment|none`, `before:` and `after:`; the
+/// explicit parameters win when both are given.
+#[derive(Deserialize, IntoParams)]
 pub struct MessageSearchQuery {
+    #[serde(default)]
     pub q: String,
+    /// Only messages by this user.
+    pub author_id: Option<i64>,
+    /// `attachment` or `none`.
+    pub has: Option<String>,
+    /// Message ID or `YYYY-MM-DD`; only older messages.
+    pub before: Option<String>,
+    /// Message ID or `YYYY-MM-DD`; only newer messages.
+    pub after: Option<String>,
+    /// `relevance` (default), `newest` or `oldest`.
+    pub sort: Option<String>,
+    /// Page size, 1-100. Defaults to 20.
     pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct DmE2eePayloadRequest {
     pub version: u8,
     pub nonce: String,
@@ -65,7 +101,7 @@ pub struct DmE2eePayloadRequest {
     pub header: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendMessageRequest {
     pub content: String,
     pub referenced_message_id: Option<String>,
@@ -73,15 +109,17 @@ pub struct SendMessageRequest {
     pub attachment_ids: Vec<String>,
     pub e2ee: Option<DmE2eePayloadRequest>,
     pub nonce: Option<String>,
+    #[serde(default)]
+    pub is_voice_message: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreatePollOptionRequest {
     pub text: String,
     pub emoji: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreatePollRequest {
     pub question: String,
     pub options: Vec<CreatePollOptionRequest>,
@@ -89,29 +127,42 @@ pub struct CreatePollRequest {
     pub expires_in_minutes: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct EditMessageRequest {
     pub content: String,
     pub e2ee: Option<DmE2eePayloadRequest>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BulkDeleteMessagesRequest {
     pub message_ids: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateReadStateRequest {
     pub last_message_id: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpsertChannelOverwriteRequest {
     pub target_type: i16,
     pub allow_perms: i64,
     pub deny_perms: i64,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct ReplaceChannelOverwritesRequest {
+    pub overwrites: Vec<ChannelOverwriteEntry>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChannelOverwriteEntry {
+    pub target_id: String,
+    pub target_type: i16,
+    pub allow_perms: i64,
+    pub deny_perms: i64,
+}
+
 pub fn channel_to_json(c: &paracord_db::channels::ChannelRow) -> Value {
     let required_role_ids: Vec<String> =
         paracord_db::channels::parse_required_role_ids(&c.required_role_ids)
@@ -147,6 +198,8 @@ pub fn channel_to_json(c: &paracord_db::channels::ChannelRow) -> Value {
         "message_count": c.message_count,
         "applied_tags": applied_tags,
         "default_sort_order": c.default_sort_order,
+        "posting_mode": c.posting_mode,
+        "archived_at": c.archived_at.map(|at| at.to_rfc3339()),
         "created_at": c.created_at.to_rfc3339(),
     })
 }
@@ -182,7 +235,7 @@ async fn normalize_required_role_ids(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let actor_roles = paracord_db::roles::get_member_roles(&state.db, actor_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -219,7 +272,7 @@ async fn normalize_required_role_ids(
     ))
 }
 
-async fn ensure_channel_permissions(
+pub(crate) async fn ensure_channel_permissions(
     state: &AppState,
     channel: &paracord_db::channels::ChannelRow,
     user_id: i64,
@@ -230,7 +283,7 @@ async fn ensure_channel_permissions(
         let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
         let perms = paracord_core::permissions::compute_channel_permissions(
             &state.db,
             guild_id,
@@ -251,6 +304,30 @@ async fn ensure_channel_permissions(
     Ok(())
 }
 
+/// NSFW channels, and threads under them, only serve content to users who
+/// have acknowledged the age gate.
+pub(crate) async fn ensure_age_gate_acknowledged(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let nsfw = channel.nsfw
+        || (channel.channel_type == 6
+            && paracord_db::channels::is_channel_nsfw(&state.db, channel.id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?);
+    if !nsfw {
+        return Ok(());
+    }
+    let acknowledged = paracord_db::users::get_age_gate_acknowledged_at(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if acknowledged.is_none() {
+        return Err(ApiError::Code(ErrorCode::AgeGateRequired));
+    }
+    Ok(())
+}
+
 async fn author_to_json(state: &AppState, author_id: i64) -> Value {
     if let Some(author) = paracord_db::users::get_user_by_id(&state.db, author_id)
         .await
@@ -308,10 +385,63 @@ fn poll_to_json(poll: &paracord_db::polls::PollWithOptions) -> Value {
     })
 }
 
-async fn message_to_json(
+fn reactions_to_json(rows: &[&paracord_db::reactions::MessageReactionCountRow]) -> Vec<Value> {
+    rows.iter()
+        .map(|reaction| {
+            json!({
+                "emoji": reaction.emoji_name,
+                "emoji_id": reaction.emoji_id.map(|id| id.to_string()),
+                "count": reaction.count,
+                "me": reaction.me,
+            })
+        })
+        .collect()
+}
+
+pub(crate) async fn message_to_json(
+    state: &AppState,
+    msg: &paracord_db::messages::MessageRow,
+    viewer_id: i64,
+) -> Value {
+    messages_to_json(state, std::slice::from_ref(msg), viewer_id)
+        .await
+        .pop()
+        .unwrap_or(Value::Null)
+}
+
+/// Render a page of messages. Reactions for the whole page come from one
+/// query instead of one per message.
+pub(crate) async fn messages_to_json(
+    state: &AppState,
+    messages: &[paracord_db::messages::MessageRow],
+    viewer_id: i64,
+) -> Vec<Value> {
+    let message_ids: Vec<i64> = messages.iter().map(|msg| msg.id).collect();
+    let reactions =
+        paracord_db::reactions::get_reactions_for_messages(&state.db, &message_ids, viewer_id)
+            .await
+            .unwrap_or_default();
+    let mut by_message: std::collections::HashMap<i64, Vec<_>> = std::collections::HashMap::new();
+    for reaction in &reactions {
+        by_message
+            .entry(reaction.message_id)
+            .or_default()
+            .push(reaction);
+    }
+    let mut result = Vec::with_capacity(messages.len());
+    for msg in messages {
+        let reaction_json =
+            reactions_to_json(by_message.get(&msg.id).map(Vec::as_slice).unwrap_or(&[]));
+        result.push(render_message(state, msg, viewer_id, reaction_json).await);
+    }
+    result
+}
+
+async fn render_message(
     state: &AppState,
     msg: &paracord_db::messages::MessageRow,
     viewer_id: i64,
+    reaction_json: Vec<Value>,
 ) -> Value {
     let is_dm_e2ee = (msg.flags & MESSAGE_FLAG_DM_E2EE) != 0;
     let e2ee_payload = if is_dm_e2ee {
@@ -354,36 +484,32 @@ async fn message_to_json(
                 "url": a.url,
                 "width": a.width,
                 "height": a.height,
+                "duration_secs": a.duration_ms.map(|ms| f64::from(ms) / 1000.0),
+                "waveform": a.waveform,
+                "transcode_status": a.transcode_status,
+                "transcoded_url": (a.transcode_status.as_deref()
+                    == Some(paracord_media::transcode::TRANSCODE_DONE))
+                .then(|| format!("/api/v1/attachments/{}/transcoded", a.id)),
+                "blurhash": a.blurhash,
+                "thumbnail_url": a
+                    .thumbnail_format
+                    .is_some()
+                    .then(|| format!("/api/v1/attachments/{}/thumbnail", a.id)),
             })
         })
         .collect();
 
-    let reactions = paracord_db::reactions::get_message_reactions(&state.db, msg.id)
-        .await
-        .unwrap_or_default();
-    let mut reaction_json = Vec::with_capacity(reactions.len());
-    for reaction in reactions {
-        let me = paracord_db::reactions::get_reaction_users(
-            &state.db,
-            msg.id,
-            &reaction.emoji_name,
-            1000,
-        )
-        .await
-        .map(|users| users.contains(&viewer_id))
-        .unwrap_or(false);
-        reaction_json.push(json!({
-            "emoji": reaction.emoji_name,
-            "count": reaction.count,
-            "me": me,
-        }));
-    }
-
     let poll_json = paracord_db::polls::get_message_poll(&state.db, msg.id, viewer_id)
         .await
         .ok()
         .flatten()
         .map(|poll| poll_to_json(&poll));
+    let embeds: Vec<Value> = paracord_db::message_embeds::get_message_embeds(&state.db, msg.id)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|embed| serde_json::from_str(embed).ok())
+        .collect();
 
     json!({
         "id": msg.id.to_string(),
@@ -400,18 +526,35 @@ async fn message_to_json(
         "edited_at": msg.edited_at.map(|t| t.to_rfc3339()),
         "reference_id": msg.reference_id.map(|id| id.to_string()),
         "attachments": attachment_json,
+        "embeds": embeds,
+        "is_voice_message": (msg.flags & MESSAGE_FLAG_VOICE_MESSAGE) != 0,
         "reactions": reaction_json,
         "poll": poll_json,
     })
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/channels",
+    tag = "channels",
+    params(("guild_id" = i64, Path)),
+    request_body = CreateChannelRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_channel(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
     Json(body): Json<CreateChannelRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
-    let channel_id = paracord_util::snowflake::generate(1);
+    if body
+        .posting_mode
+        .is_some_and(|mode| !paracord_core::channel::is_valid_posting_mode(mode))
+    {
+        return Err(ApiError::BadRequest("Invalid posting_mode".into()));
+    }
+    let channel_id = paracord_util::snowflake::try_next_id().await?;
     let required_role_ids = match body.required_role_ids.as_deref() {
         Some(raw_role_ids) => {
             Some(normalize_required_role_ids(&state, guild_id, auth.user_id, raw_role_ids).await?)
@@ -419,7 +562,8 @@ pub async fn create_channel(
         None => None,
     };
 
-    let channel = paracord_core::channel::create_channel(
+    let max_channels = GuildLimit::Channels.configured(&*state.runtime.read().await);
+    let mut channel = paracord_core::channel::create_channel(
         &state.db,
         guild_id,
         auth.user_id,
@@ -428,8 +572,24 @@ pub async fn create_channel(
         body.channel_type,
         body.parent_id,
         required_role_ids.as_deref(),
+        max_channels,
     )
     .await?;
+    if body.nsfw {
+        paracord_db::channels::set_channel_nsfw(&state.db, channel.id, true)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        channel.nsfw = true;
+    }
+    if let Some(mode) = body
+        .posting_mode
+        .filter(|mode| *mode != paracord_core::channel::POSTING_MODE_OPEN)
+    {
+        paracord_db::channels::set_channel_posting_mode(&state.db, channel.id, mode)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        channel.posting_mode = mode;
+    }
 
     let channel_json = channel_to_json(&channel);
 
@@ -450,6 +610,14 @@ pub async fn create_channel(
     Ok((StatusCode::CREATED, Json(channel_json)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_channel(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -458,7 +626,7 @@ pub async fn get_channel(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     ensure_channel_permissions(&state, &channel, auth.user_id, &[Permissions::VIEW_CHANNEL])
         .await?;
@@ -466,6 +634,15 @@ pub async fn get_channel(
     Ok(Json(channel_to_json(&channel)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/channels/{channel_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = UpdateChannelRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_channel(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -485,7 +662,7 @@ pub async fn update_channel(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .and_then(|c| c.guild_id())
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     let required_role_ids = match body.required_role_ids.as_deref() {
         Some(raw_role_ids) => {
             Some(normalize_required_role_ids(&state, guild_id, auth.user_id, raw_role_ids).await?)
@@ -500,6 +677,8 @@ pub async fn update_channel(
         body.name.as_deref(),
         body.topic.as_deref(),
         required_role_ids.as_deref(),
+        body.nsfw,
+        body.posting_mode,
     )
     .await?;
 
@@ -516,7 +695,74 @@ pub async fn update_channel(
             audit::ACTION_CHANNEL_UPDATE,
             Some(updated.id),
             None,
-            Some(json!({ "name": updated.name, "topic": updated.topic })),
+            Some(json!({
+                "name": updated.name,
+                "topic": updated.topic,
+                "nsfw": updated.nsfw,
+                "posting_mode": updated.posting_mode,
+            })),
+        )
+        .await;
+    }
+
+    Ok(Json(channel_json))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/archive",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn archive_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    set_channel_archived(&state, auth.user_id, channel_id, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/archive",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn unarchive_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    set_channel_archived(&state, auth.user_id, channel_id, false).await
+}
+
+async fn set_channel_archived(
+    state: &AppState,
+    user_id: i64,
+    channel_id: i64,
+    archived: bool,
+) -> Result<Json<Value>, ApiError> {
+    let updated =
+        paracord_core::channel::set_channel_archived(&state.db, channel_id, user_id, archived)
+            .await?;
+
+    let channel_json = channel_to_json(&updated);
+    state
+        .event_bus
+        .dispatch("CHANNEL_UPDATE", channel_json.clone(), updated.guild_id());
+    if let Some(guild_id) = updated.guild_id() {
+        audit::log_action(
+            state,
+            guild_id,
+            user_id,
+            audit::ACTION_CHANNEL_UPDATE,
+            Some(updated.id),
+            None,
+            Some(json!({ "name": updated.name, "archived": archived })),
         )
         .await;
     }
@@ -524,19 +770,47 @@ pub async fn update_channel(
     Ok(Json(channel_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_channel(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(channel_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    let channel =
+    let (channel, deleted_at, thread_ids) =
         paracord_core::channel::delete_channel(&state.db, channel_id, auth.user_id).await?;
 
-    state.event_bus.dispatch(
-        "CHANNEL_DELETE",
-        json!({"id": channel_id.to_string(), "guild_id": channel.guild_id().map(|id| id.to_string())}),
-        channel.guild_id(),
-    );
+    let guild_id = channel.guild_id().map(|id| id.to_string());
+    for thread_id in thread_ids {
+        let payload = paracord_core::tombstone::with_tombstone(
+            &state,
+            json!({
+                "id": thread_id.to_string(),
+                "guild_id": guild_id,
+                "parent_id": channel_id.to_string(),
+            }),
+            deleted_at,
+        )
+        .await;
+        state
+            .event_bus
+            .dispatch("THREAD_DELETE", payload, channel.guild_id());
+    }
+    let payload = paracord_core::tombstone::with_tombstone(
+        &state,
+        json!({"id": channel_id.to_string(), "guild_id": guild_id}),
+        deleted_at,
+    )
+    .await;
+    state
+        .event_bus
+        .dispatch("CHANNEL_DELETE", payload, channel.guild_id());
     if let Some(guild_id) = channel.guild_id() {
         audit::log_action(
             &state,
@@ -553,6 +827,14 @@ pub async fn delete_channel(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/messages",
+    tag = "channels",
+    params(("channel_id" = i64, Path), MessageQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_messages(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -562,7 +844,7 @@ pub async fn get_messages(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -570,58 +852,164 @@ pub async fn get_messages(
         &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY],
     )
     .await?;
+    ensure_age_gate_acknowledged(&state, &channel, auth.user_id).await?;
 
-    let limit = params.limit.unwrap_or(50).min(100);
-    let messages = paracord_db::messages::get_channel_messages(
-        &state.db,
-        channel_id,
-        params.before,
-        None,
-        limit,
-    )
-    .await
+    let cursors = [params.before, params.after, params.around]
+        .iter()
+        .filter(|cursor| cursor.is_some())
+        .count();
+    if cursors > 1 {
+        return Err(ApiError::BadRequest(
+            "Only one of before, after and around may be given".into(),
+        ));
+    }
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let mut messages = match params.around {
+        Some(around) => {
+            paracord_db::messages::get_messages_around(&state.db, channel_id, around, limit).await
+        }
+        None => {
+            paracord_db::messages::get_channel_messages(
+                &state.db,
+                channel_id,
+                params.before,
+                params.after,
+                limit,
+            )
+            .await
+        }
+    }
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-
-    let mut result = Vec::new();
-    for msg in &messages {
-        result.push(message_to_json(&state, msg, auth.user_id).await);
+    // Pages are always newest first, whichever way the cursor points.
+    if params.after.is_some() {
+        messages.reverse();
     }
 
+    let result = messages_to_json(&state, &messages, auth.user_id).await;
+
     Ok(Json(json!(result)))
 }
 
+/// Merge the inline operators of `q` with the explicit search parameters.
+pub(crate) fn parse_search_params(
+    params: &MessageSearchQuery,
+) -> Result<
+    (
+        paracord_core::search::SearchQuery,
+        paracord_db::search::SearchOrder,
+    ),
+    ApiError,
+> {
+    let mut query = paracord_core::search::parse_query(&params.q).map_err(ApiError::BadRequest)?;
+    if let Some(author_id) = params.author_id {
+        query.author_ids = vec![author_id];
+    }
+    if let Some(has) = params.has.as_deref() {
+        query.has_attachment =
+            Some(paracord_core::search::parse_has(has).map_err(ApiError::BadRequest)?);
+    }
+    if let Some(before) = params.before.as_deref() {
+        query.before = Some(
+            paracord_core::search::parse_bound("before", before).map_err(ApiError::BadRequest)?,
+        );
+    }
+    if let Some(after) = params.after.as_deref() {
+        query.after =
+            Some(paracord_core::search::parse_bound("after", after).map_err(ApiError::BadRequest)?);
+    }
+    let order = match params.sort.as_deref() {
+        None | Some("relevance") => paracord_db::search::SearchOrder::Relevance,
+        Some("newest") => paracord_db::search::SearchOrder::Newest,
+        Some("oldest") => paracord_db::search::SearchOrder::Oldest,
+        Some(_) => {
+            return Err(ApiError::BadRequest(
+                "sort must be relevance, newest or oldest".into(),
+            ))
+        }
+    };
+    if paracord_db::search::search_terms(&query.text).is_empty() && !query.has_filters() {
+        return Err(ApiError::BadRequest("Query must not be empty".into()));
+    }
+    Ok((query, order))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/messages/search",
+    tag = "channels",
+    params(("channel_id" = i64, Path), MessageSearchQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn search_messages(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(channel_id): Path<i64>,
     Query(params): Query<MessageSearchQuery>,
 ) -> Result<Json<Value>, ApiError> {
-    if params.q.trim().is_empty() {
-        return Err(ApiError::BadRequest("Query must not be empty".into()));
-    }
+    let (mut query, order) = parse_search_params(&params)?;
+
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
-    ensure_channel_permissions(
-        &state,
-        &channel,
-        auth.user_id,
-        &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY],
-    )
-    .await?;
-
-    let limit = params.limit.unwrap_or(20).min(100);
-    let messages = paracord_db::messages::search_messages(&state.db, channel_id, &params.q, limit)
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    // `in:` may name other channels of the same guild; each needs the same
+    // access as the one in the path.
+    let mut channels = vec![channel];
+    for id in std::mem::take(&mut query.channel_ids) {
+        if channels.iter().any(|c| c.id == id) {
+            continue;
+        }
+        let other = paracord_db::channels::get_channel(&state.db, id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .filter(|other| {
+                other.guild_id().is_some() && other.guild_id() == channels[0].guild_id()
+            })
+            .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+        channels.push(other);
+    }
+    for channel in &channels {
+        ensure_channel_permissions(
+            &state,
+            channel,
+            auth.user_id,
+            &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY],
+        )
+        .await?;
+        ensure_age_gate_acknowledged(&state, channel, auth.user_id).await?;
+    }
+
+    let search = paracord_db::search::MessageSearch {
+        text: query.text,
+        channel_ids: channels.iter().map(|c| c.id).collect(),
+        author_ids: query.author_ids,
+        has_attachment: query.has_attachment,
+        before: query.before,
+        after: query.after,
+        order,
+        limit: params
+            .limit
+            .unwrap_or(SEARCH_DEFAULT_LIMIT)
+            .clamp(1, SEARCH_MAX_LIMIT),
+        offset: params.offset.unwrap_or(0).max(0),
+    };
+    let messages = paracord_db::search::search_messages(&state.db, &search)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let mut result = Vec::with_capacity(messages.len());
-    for msg in &messages {
-        result.push(message_to_json(&state, msg, auth.user_id).await);
-    }
+    let result = messages_to_json(&state, &messages, auth.user_id).await;
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/messages/bulk-delete",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = BulkDeleteMessagesRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn bulk_delete_messages(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -641,7 +1029,7 @@ pub async fn bulk_delete_messages(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -680,9 +1068,19 @@ pub async fn bulk_delete_messages(
     Ok(Json(json!({ "deleted": deleted })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/messages",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = SendMessageRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn send_message(
     State(state): State<AppState>,
     auth: AuthUser,
+    headers: HeaderMap,
     Path(channel_id): Path<i64>,
     Json(body): Json<SendMessageRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
@@ -722,7 +1120,7 @@ pub async fn send_message(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -730,6 +1128,47 @@ pub async fn send_message(
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
+    if channel.guild_id().is_none() {
+        dms::ensure_dm_send_allowed(&state, &channel, auth.user_id).await?;
+    }
+    // Posting in an archived thread brings it back when the guild allows it.
+    if let Some(reopened) =
+        paracord_core::thread::reopen_for_message(&state.db, &channel, auth.user_id).await?
+    {
+        state.event_bus.dispatch(
+            "THREAD_UPDATE",
+            channel_to_json(&reopened),
+            reopened.guild_id(),
+        );
+    }
+    // `/tag <name>` posts the guild's snippet in place of the command.
+    let content = match channel.guild_id() {
+        Some(guild_id) if body.e2ee.is_none() && body.attachment_ids.is_empty() => {
+            match paracord_core::tag::parse_tag_invocation(&body.content) {
+                Some(name) => {
+                    paracord_core::tag::use_tag(&state.db, guild_id, name)
+                        .await?
+                        .content
+                }
+                None => body.content.clone(),
+            }
+        }
+        _ => body.content.clone(),
+    };
+    let flagged_url = match channel.guild_id() {
+        Some(guild_id) if body.e2ee.is_none() => {
+            url_denylist::screen_message(
+                &state,
+                &headers,
+                guild_id,
+                channel_id,
+                auth.user_id,
+                &content,
+            )
+            .await?
+        }
+        _ => None,
+    };
 
     let referenced_message_id = match body.referenced_message_id.as_deref() {
         Some(id) => Some(
@@ -768,7 +1207,20 @@ pub async fn send_message(
         attachments.push(attachment);
     }
 
-    let msg_id = paracord_util::snowflake::generate(1);
+    if body.is_voice_message {
+        if !body.content.trim().is_empty() || attachments.len() != 1 {
+            return Err(ApiError::BadRequest(
+                "Voice messages must contain exactly one audio attachment and no text".into(),
+            ));
+        }
+        if attachments[0].duration_ms.is_none() {
+            return Err(ApiError::BadRequest(
+                "Voice message attachment is missing duration metadata".into(),
+            ));
+        }
+    }
+
+    let msg_id = paracord_util::snowflake::try_next_id().await?;
 
     let dm_e2ee = body
         .e2ee
@@ -784,13 +1236,14 @@ pub async fn send_message(
         msg_id,
         channel_id,
         auth.user_id,
-        &body.content,
+        &content,
         paracord_core::message::CreateMessageOptions {
             message_type: 0,
             reference_id: referenced_message_id,
             allow_empty_content: !body.attachment_ids.is_empty(),
             dm_e2ee,
             nonce,
+            voice_message: body.is_voice_message,
         },
     )
     .await?;
@@ -838,25 +1291,38 @@ pub async fn send_message(
     let msg_json = message_to_json(&state, &msg, auth.user_id).await;
 
     if created_new {
+        let mut push_recipients = Vec::new();
         if guild_id.is_none() {
             // DM channel: deliver only to participants, not all connected users
-            let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
-                .await
-                .unwrap_or_default();
-            state
-                .event_bus
-                .dispatch_to_users("MESSAGE_CREATE", msg_json.clone(), recipient_ids);
+            push_recipients =
+                dms::dispatch_dm_message_create(&state, channel_id, auth.user_id, &msg_json).await;
         } else {
             state
                 .event_bus
                 .dispatch("MESSAGE_CREATE", msg_json.clone(), guild_id);
         }
+        if let Some(flagged_url) = flagged_url {
+            flagged_url
+                .report(&state, &headers, channel_id, auth.user_id, msg.id)
+                .await;
+        }
+        if let Some(gid) = guild_id {
+            paracord_core::emoji::record_message_usage(&state, gid, &content).await;
+            push_recipients =
+                crate::mention_fanout::record_for_message(&state, &channel, &msg, &content).await;
+        }
+        if let Err(err) =
+            paracord_core::push::queue_message_push(&state, &channel, &msg, &push_recipients).await
+        {
+            tracing::warn!("Failed to queue push for message {}: {}", msg.id, err);
+        }
+        crate::link_previews::queue_for_message(&state, &msg).await;
 
         // Federation: forward message to peer servers (non-blocking)
         if let Some(gid) = guild_id {
             if paracord_federation::is_enabled() {
                 let fed_state = state.clone();
-                let fed_content = json!(body.content);
+                let fed_content = json!(content);
                 let fed_msg_id = msg.id;
                 let fed_author = auth.user_id;
                 let fed_ts = msg.created_at.timestamp_millis();
@@ -886,6 +1352,15 @@ pub async fn send_message(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/polls",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = CreatePollRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_poll(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -943,7 +1418,7 @@ pub async fn create_poll(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -951,8 +1426,11 @@ pub async fn create_poll(
         &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
     )
     .await?;
+    if channel.guild_id().is_none() {
+        dms::ensure_dm_send_allowed(&state, &channel, auth.user_id).await?;
+    }
 
-    let message_id = paracord_util::snowflake::generate(1);
+    let message_id = paracord_util::snowflake::try_next_id().await?;
     let msg = paracord_core::message::create_message_with_type(
         &state.db,
         message_id,
@@ -964,7 +1442,7 @@ pub async fn create_poll(
     )
     .await?;
 
-    let poll_id = paracord_util::snowflake::generate(1);
+    let poll_id = paracord_util::snowflake::try_next_id().await?;
     paracord_db::polls::create_poll(
         &state.db,
         poll_id,
@@ -982,12 +1460,7 @@ pub async fn create_poll(
     let msg_json = message_to_json(&state, &msg, auth.user_id).await;
 
     if guild_id.is_none() {
-        let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
-            .await
-            .unwrap_or_default();
-        state
-            .event_bus
-            .dispatch_to_users("MESSAGE_CREATE", msg_json.clone(), recipient_ids);
+        dms::dispatch_dm_message_create(&state, channel_id, auth.user_id, &msg_json).await;
     } else {
         state
             .event_bus
@@ -997,6 +1470,14 @@ pub async fn create_poll(
     Ok((StatusCode::CREATED, Json(msg_json)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/polls/{poll_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("poll_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_poll(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1005,7 +1486,7 @@ pub async fn get_poll(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(&state, &channel, auth.user_id, &[Permissions::VIEW_CHANNEL])
         .await?;
 
@@ -1020,6 +1501,14 @@ pub async fn get_poll(
     Ok(Json(poll_to_json(&poll)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/polls/{poll_id}/votes/{option_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("poll_id" = i64, Path), ("option_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn add_poll_vote(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1028,7 +1517,7 @@ pub async fn add_poll_vote(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1089,6 +1578,14 @@ pub async fn add_poll_vote(
     Ok(Json(poll_to_json(&updated)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/polls/{poll_id}/votes/{option_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("poll_id" = i64, Path), ("option_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn remove_poll_vote(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1097,7 +1594,7 @@ pub async fn remove_poll_vote(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1151,9 +1648,19 @@ pub async fn remove_poll_vote(
     Ok(Json(poll_to_json(&updated)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/channels/{channel_id}/messages/{message_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path)),
+    request_body = EditMessageRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn edit_message(
     State(state): State<AppState>,
     auth: AuthUser,
+    headers: HeaderMap,
     Path((channel_id, message_id)): Path<(i64, i64)>,
     Json(body): Json<EditMessageRequest>,
 ) -> Result<Json<Value>, ApiError> {
@@ -1167,6 +1674,26 @@ pub async fn edit_message(
             "Message contains unsafe markup".into(),
         ));
     }
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .ok()
+        .flatten();
+    let guild_id = channel.and_then(|c| c.guild_id());
+    let flagged_url = match guild_id {
+        Some(guild_id) if body.e2ee.is_none() => {
+            url_denylist::screen_message(
+                &state,
+                &headers,
+                guild_id,
+                channel_id,
+                auth.user_id,
+                &body.content,
+            )
+            .await?
+        }
+        _ => None,
+    };
+
     let dm_e2ee = body
         .e2ee
         .map(|payload| paracord_core::message::DmE2eePayload {
@@ -1184,12 +1711,12 @@ pub async fn edit_message(
         dm_e2ee,
     )
     .await?;
-
-    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
-        .await
-        .ok()
-        .flatten();
-    let guild_id = channel.and_then(|c| c.guild_id());
+    if let Some(flagged_url) = flagged_url {
+        flagged_url
+            .report(&state, &headers, channel_id, auth.user_id, updated.id)
+            .await;
+    }
+    crate::link_previews::queue_for_message(&state, &updated).await;
 
     let msg_json = message_to_json(&state, &updated, auth.user_id).await;
 
@@ -1236,6 +1763,14 @@ pub async fn edit_message(
     Ok(Json(msg_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/messages/{message_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_message(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1293,6 +1828,14 @@ pub async fn delete_message(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/pins",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_pins(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1301,7 +1844,7 @@ pub async fn get_pins(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1309,19 +1852,25 @@ pub async fn get_pins(
         &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY],
     )
     .await?;
+    ensure_age_gate_acknowledged(&state, &channel, auth.user_id).await?;
 
     let messages = paracord_db::messages::get_pinned_messages(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    let mut pinned = Vec::with_capacity(messages.len());
-    for msg in &messages {
-        pinned.push(message_to_json(&state, msg, auth.user_id).await);
-    }
+    let pinned = messages_to_json(&state, &messages, auth.user_id).await;
 
     Ok(Json(json!(pinned)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/pins/{message_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn pin_message(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1330,7 +1879,7 @@ pub async fn pin_message(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1365,6 +1914,14 @@ pub async fn pin_message(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/pins/{message_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn unpin_message(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1373,7 +1930,7 @@ pub async fn unpin_message(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1408,6 +1965,14 @@ pub async fn unpin_message(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/typing",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn typing(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1417,7 +1982,7 @@ pub async fn typing(
         .await
         .ok()
         .flatten()
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1448,6 +2013,15 @@ pub async fn typing(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/read",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = UpdateReadStateRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_read_state(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1457,7 +2031,7 @@ pub async fn update_read_state(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1479,6 +2053,16 @@ pub async fn update_read_state(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if channel.guild_id().is_none() && last_message_id > 0 {
+        paracord_core::receipts::record_receipt(
+            &state,
+            channel_id,
+            auth.user_id,
+            last_message_id,
+            paracord_core::receipts::ReceiptKind::Read,
+        )
+        .await?;
+    }
     Ok(Json(json!({
         "channel_id": read_state.channel_id.to_string(),
         "last_message_id": read_state.last_message_id.to_string(),
@@ -1486,6 +2070,14 @@ pub async fn update_read_state(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/overwrites",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_channel_overwrites(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1494,7 +2086,7 @@ pub async fn list_channel_overwrites(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1505,31 +2097,176 @@ pub async fn list_channel_overwrites(
     let overwrites = paracord_db::channel_overwrites::get_channel_overwrites(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let result: Vec<Value> = overwrites
-        .iter()
-        .map(|o| {
-            json!({
-                "channel_id": o.channel_id.to_string(),
-                "target_id": o.target_id.to_string(),
-                "target_type": o.target_type,
-                "allow_perms": o.allow_perms,
-                "deny_perms": o.deny_perms,
-            })
-        })
-        .collect();
+    let result: Vec<Value> = overwrites.iter().map(overwrite_to_json).collect();
     Ok(Json(json!(result)))
 }
 
+fn overwrite_to_json(o: &paracord_db::channel_overwrites::ChannelOverwriteRow) -> Value {
+    json!({
+        "channel_id": o.channel_id.to_string(),
+        "target_id": o.target_id.to_string(),
+        "target_type": o.target_type,
+        "allow_perms": o.allow_perms,
+        "deny_perms": o.deny_perms,
+    })
+}
+
+/// Replace the whole overwrite set of a channel at once. The set is
+/// validated up front and applied in one transaction, so a permissions
+/// editor never leaves the channel half-updated.
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/overwrites",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = ReplaceChannelOverwritesRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn replace_channel_overwrites(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    permissions_version: PermissionsVersion,
+    Json(mut body): Json<ReplaceChannelOverwritesRequest>,
+) -> Result<Json<Value>, ApiError> {
+    for entry in &mut body.overwrites {
+        entry.allow_perms = permissions_version.normalize(entry.allow_perms);
+        entry.deny_perms = permissions_version.normalize(entry.deny_perms);
+    }
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    let guild_id = channel
+        .guild_id()
+        .ok_or_else(|| ApiError::BadRequest("DM channels have no permission overwrites".into()))?;
+    ensure_channel_permissions(
+        &state,
+        &channel,
+        auth.user_id,
+        &[Permissions::VIEW_CHANNEL, Permissions::MANAGE_CHANNELS],
+    )
+    .await?;
+    if body.overwrites.len() > MAX_CHANNEL_OVERWRITES {
+        return Err(ApiError::BadRequest(format!(
+            "A channel can have at most {} permission overwrites",
+            MAX_CHANNEL_OVERWRITES
+        )));
+    }
+
+    let guild_role_ids: std::collections::HashSet<i64> =
+        paracord_db::roles::get_guild_roles(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .iter()
+            .map(|r| r.id)
+            .collect();
+    let mut seen_targets = std::collections::HashSet::new();
+    let mut overwrites = Vec::with_capacity(body.overwrites.len());
+    for entry in &body.overwrites {
+        let target_id = entry
+            .target_id
+            .parse::<i64>()
+            .map_err(|_| ApiError::BadRequest("Invalid overwrite target id".into()))?;
+        if !seen_targets.insert(target_id) {
+            return Err(ApiError::BadRequest(format!(
+                "Duplicate overwrite for target {}",
+                target_id
+            )));
+        }
+        if entry.allow_perms & entry.deny_perms != 0 {
+            return Err(ApiError::BadRequest(
+                "A permission cannot be both allowed and denied".into(),
+            ));
+        }
+        match entry.target_type {
+            paracord_core::permissions::OVERWRITE_TARGET_ROLE => {
+                if target_id != guild_id && !guild_role_ids.contains(&target_id) {
+                    return Err(ApiError::BadRequest(format!(
+                        "Role {} does not belong to this guild",
+                        target_id
+                    )));
+                }
+            }
+            paracord_core::permissions::OVERWRITE_TARGET_MEMBER => {
+                let member = paracord_db::members::get_member(&state.db, target_id, guild_id)
+                    .await
+                    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+                if member.is_none() {
+                    return Err(ApiError::BadRequest(format!(
+                        "User {} is not a member of this guild",
+                        target_id
+                    )));
+                }
+            }
+            _ => return Err(ApiError::BadRequest("Invalid overwrite target type".into())),
+        }
+        overwrites.push(paracord_db::channel_overwrites::ChannelOverwriteRow {
+            channel_id,
+            target_id,
+            target_type: entry.target_type,
+            allow_perms: entry.allow_perms,
+            deny_perms: entry.deny_perms,
+        });
+    }
+
+    let diff = paracord_db::channel_overwrites::replace_channel_overwrites(
+        &state.db,
+        channel_id,
+        &overwrites,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !diff.is_empty() {
+        paracord_core::permissions::invalidate_channel(&state.permission_cache, channel_id).await;
+        audit::log_action(
+            &state,
+            guild_id,
+            auth.user_id,
+            audit::ACTION_CHANNEL_OVERWRITES_UPDATE,
+            Some(channel_id),
+            None,
+            Some(json!({
+                "added": diff.added.iter().map(overwrite_to_json).collect::<Vec<_>>(),
+                "updated": diff.updated.iter().map(overwrite_to_json).collect::<Vec<_>>(),
+                "removed": diff.removed.iter().map(overwrite_to_json).collect::<Vec<_>>(),
+            })),
+        )
+        .await;
+        state.event_bus.dispatch(
+            "CHANNEL_UPDATE",
+            json!({ "id": channel_id.to_string() }),
+            Some(guild_id),
+        );
+    }
+
+    let result: Vec<Value> = overwrites.iter().map(overwrite_to_json).collect();
+    Ok(Json(json!(result)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/overwrites/{target_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("target_id" = i64, Path)),
+    request_body = UpsertChannelOverwriteRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn upsert_channel_overwrite(
     State(state): State<AppState>,
     auth: AuthUser,
     Path((channel_id, target_id)): Path<(i64, i64)>,
-    Json(body): Json<UpsertChannelOverwriteRequest>,
+    permissions_version: PermissionsVersion,
+    Json(mut body): Json<UpsertChannelOverwriteRequest>,
 ) -> Result<StatusCode, ApiError> {
+    body.allow_perms = permissions_version.normalize(body.allow_perms);
+    body.deny_perms = permissions_version.normalize(body.deny_perms);
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1562,6 +2299,14 @@ pub async fn upsert_channel_overwrite(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/overwrites/{target_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("target_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_channel_overwrite(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1570,7 +2315,7 @@ pub async fn delete_channel_overwrite(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1591,6 +2336,14 @@ pub async fn delete_channel_overwrite(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/messages/{message_id}/reactions/{emoji}/@me",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path), ("emoji" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn add_reaction(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1599,7 +2352,7 @@ pub async fn add_reaction(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1608,12 +2361,29 @@ pub async fn add_reaction(
     )
     .await?;
 
-    paracord_db::reactions::add_reaction(&state.db, message_id, auth.user_id, &emoji, None)
+    let message = paracord_db::messages::get_message(&state.db, message_id)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|message| message.channel_id == channel_id)
+        .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?;
+    if message.author_id != auth.user_id
+        && paracord_db::relationships::has_blocked(&state.db, message.author_id, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::Forbidden);
+    }
+
+    let added =
+        paracord_db::reactions::add_reaction(&state.db, message_id, auth.user_id, &emoji, None)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let emoji_for_federation = emoji.clone();
     let guild_id = channel.guild_id();
+    if let (true, Some(gid)) = (added, guild_id) {
+        paracord_core::emoji::record_reaction_usage(&state, gid, &emoji).await;
+    }
     let reaction_payload = json!({
         "user_id": auth.user_id.to_string(),
         "channel_id": channel_id.to_string(),
@@ -1621,17 +2391,22 @@ pub async fn add_reaction(
         "emoji": emoji,
     });
 
-    if guild_id.is_none() {
+    if let Some(gid) = guild_id {
+        paracord_core::reaction_burst::dispatch_reaction_change(
+            &state,
+            "MESSAGE_REACTION_ADD",
+            reaction_payload,
+            gid,
+            channel_id,
+            message_id,
+        );
+    } else {
         let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
             .await
             .unwrap_or_default();
         state
             .event_bus
             .dispatch_to_users("MESSAGE_REACTION_ADD", reaction_payload, recipient_ids);
-    } else {
-        state
-            .event_bus
-            .dispatch("MESSAGE_REACTION_ADD", reaction_payload, guild_id);
     }
 
     if let Some(gid) = guild_id {
@@ -1664,6 +2439,14 @@ pub async fn add_reaction(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/messages/{message_id}/reactions/{emoji}/@me",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("message_id" = i64, Path), ("emoji" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn remove_reaction(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1672,7 +2455,7 @@ pub async fn remove_reaction(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     ensure_channel_permissions(
         &state,
         &channel,
@@ -1694,7 +2477,16 @@ pub async fn remove_reaction(
         "emoji": emoji,
     });
 
-    if guild_id.is_none() {
+    if let Some(gid) = guild_id {
+        paracord_core::reaction_burst::dispatch_reaction_change(
+            &state,
+            "MESSAGE_REACTION_REMOVE",
+            reaction_payload,
+            gid,
+            channel_id,
+            message_id,
+        );
+    } else {
         let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id)
             .await
             .unwrap_or_default();
@@ -1703,10 +2495,6 @@ pub async fn remove_reaction(
             reaction_payload,
             recipient_ids,
         );
-    } else {
-        state
-            .event_bus
-            .dispatch("MESSAGE_REACTION_REMOVE", reaction_payload, guild_id);
     }
 
     if let Some(gid) = guild_id {
@@ -1741,45 +2529,55 @@ pub async fn remove_reaction(
 
 // ============ Thread endpoints ============
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateThreadRequest {
     pub name: String,
     pub message_id: Option<String>,
     pub auto_archive_duration: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateThreadRequest {
     pub name: Option<String>,
     pub archived: Option<bool>,
     pub locked: Option<bool>,
+    pub auto_archive_duration: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct ForumPostQuery {
     pub sort_order: Option<i32>,
     pub include_archived: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateForumPostRequest {
     pub name: String,
     pub content: Option<String>,
     pub applied_tag_ids: Option<Vec<String>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateForumTagRequest {
     pub name: String,
     pub emoji: Option<String>,
     pub moderated: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateForumSortOrderRequest {
     pub sort_order: i32,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/threads",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = CreateThreadRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_thread(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1800,7 +2598,7 @@ pub async fn create_thread(
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     // Threads can only be created in text or announcement channels
     if parent_channel.channel_type != 0 && parent_channel.channel_type != 5 {
@@ -1821,7 +2619,13 @@ pub async fn create_thread(
         .guild_id()
         .ok_or(ApiError::BadRequest("Cannot create threads in DMs".into()))?;
 
-    let auto_archive_duration = body.auto_archive_duration.unwrap_or(1440);
+    let auto_archive_duration = paracord_core::thread::resolve_auto_archive_duration(
+        &state.db,
+        guild_id,
+        body.auto_archive_duration,
+    )
+    .await?;
+    paracord_core::thread::ensure_thread_capacity(&state.db, guild_id).await?;
     let starter_message_id = match body.message_id.as_deref() {
         Some(raw_message_id) => {
             let parsed_message_id = raw_message_id
@@ -1841,7 +2645,7 @@ pub async fn create_thread(
         None => None,
     };
 
-    let thread_id = paracord_util::snowflake::generate(1);
+    let thread_id = paracord_util::snowflake::try_next_id().await?;
     let thread = paracord_db::channels::create_thread(
         &state.db,
         thread_id,
@@ -1864,6 +2668,14 @@ pub async fn create_thread(
     Ok((StatusCode::CREATED, Json(thread_json)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/threads",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_threads(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1872,7 +2684,7 @@ pub async fn get_threads(
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     ensure_channel_permissions(
         &state,
@@ -1890,6 +2702,14 @@ pub async fn get_threads(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/threads/archived",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_archived_threads(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1898,7 +2718,7 @@ pub async fn get_archived_threads(
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     ensure_channel_permissions(
         &state,
@@ -1916,6 +2736,15 @@ pub async fn get_archived_threads(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/channels/{channel_id}/threads/{thread_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("thread_id" = i64, Path)),
+    request_body = UpdateThreadRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_thread(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -1938,7 +2767,7 @@ pub async fn update_thread(
     let thread = paracord_db::channels::get_channel(&state.db, thread_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if thread.channel_type != 6 {
         return Err(ApiError::BadRequest("Channel is not a thread".into()));
@@ -1950,7 +2779,7 @@ pub async fn update_thread(
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     let is_thread_owner = thread.owner_id == Some(auth.user_id);
 
@@ -1959,20 +2788,33 @@ pub async fn update_thread(
             &state,
             &parent_channel,
             auth.user_id,
-            &[Permissions::MANAGE_CHANNELS],
+            &[Permissions::MANAGE_THREADS],
         )
         .await?;
     }
 
-    if body.name.is_some() && !is_thread_owner {
+    if (body.name.is_some() || body.auto_archive_duration.is_some()) && !is_thread_owner {
         ensure_channel_permissions(
             &state,
             &parent_channel,
             auth.user_id,
-            &[Permissions::MANAGE_CHANNELS],
+            &[Permissions::MANAGE_THREADS],
         )
         .await?;
     }
+    if body
+        .auto_archive_duration
+        .is_some_and(|minutes| !paracord_core::thread::is_valid_auto_archive_duration(minutes))
+    {
+        return Err(ApiError::BadRequest(
+            "auto_archive_duration must be one of 60, 1440, 4320 or 10080".into(),
+        ));
+    }
+    if body.archived == Some(false) && thread.is_archived_thread() {
+        if let Some(guild_id) = thread.guild_id() {
+            paracord_core::thread::ensure_thread_capacity(&state.db, guild_id).await?;
+        }
+    }
 
     let updated = paracord_db::channels::update_thread(
         &state.db,
@@ -1980,6 +2822,7 @@ pub async fn update_thread(
         body.name.as_deref(),
         body.archived,
         body.locked,
+        body.auto_archive_duration,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -1994,6 +2837,32 @@ pub async fn update_thread(
     Ok(Json(thread_json))
 }
 
+/// Archive threads that went quiet or push a guild over its active thread
+/// limit, emitting `THREAD_UPDATE` for each. Run periodically by the server.
+pub async fn run_thread_auto_archive_once(state: &AppState) {
+    let archived =
+        match paracord_core::thread::archive_stale_threads(&state.db, chrono::Utc::now()).await {
+            Ok(archived) => archived,
+            Err(err) => {
+                tracing::warn!("Failed to auto-archive threads: {}", err);
+                return;
+            }
+        };
+    for thread in archived {
+        state
+            .event_bus
+            .dispatch("THREAD_UPDATE", channel_to_json(&thread), thread.guild_id());
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/threads/{thread_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("thread_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_thread(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2002,7 +2871,7 @@ pub async fn delete_thread(
     let thread = paracord_db::channels::get_channel(&state.db, thread_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if thread.channel_type != 6 {
         return Err(ApiError::BadRequest("Channel is not a thread".into()));
@@ -2014,13 +2883,13 @@ pub async fn delete_thread(
     let parent_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     ensure_channel_permissions(
         &state,
         &parent_channel,
         auth.user_id,
-        &[Permissions::MANAGE_CHANNELS],
+        &[Permissions::MANAGE_THREADS],
     )
     .await?;
 
@@ -2043,6 +2912,14 @@ pub async fn delete_thread(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/forum/posts",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ForumPostQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_forum_posts(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2052,7 +2929,7 @@ pub async fn get_forum_posts(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
@@ -2065,6 +2942,7 @@ pub async fn get_forum_posts(
         &[Permissions::VIEW_CHANNEL],
     )
     .await?;
+    ensure_age_gate_acknowledged(&state, &forum_channel, auth.user_id).await?;
 
     let sort_order = query
         .sort_order
@@ -2086,6 +2964,15 @@ pub async fn get_forum_posts(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/forum/posts",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = CreateForumPostRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_forum_post(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2107,7 +2994,7 @@ pub async fn create_forum_post(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
     }
@@ -2123,6 +3010,29 @@ pub async fn create_forum_post(
     let guild_id = forum_channel.guild_id().ok_or(ApiError::BadRequest(
         "Cannot create forum posts in DMs".into(),
     ))?;
+    paracord_core::channel::ensure_not_archived(&forum_channel)?;
+    paracord_core::thread::ensure_thread_capacity(&state.db, guild_id).await?;
+    if forum_channel.posting_mode != paracord_core::channel::POSTING_MODE_OPEN {
+        let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+        let perms = paracord_core::permissions::compute_channel_permissions(
+            &state.db,
+            guild_id,
+            channel_id,
+            guild.owner_id,
+            auth.user_id,
+        )
+        .await?;
+        paracord_core::channel::ensure_posting_allowed(
+            &state.db,
+            &forum_channel,
+            perms,
+            auth.user_id,
+        )
+        .await?;
+    }
 
     let applied_tags = match body.applied_tag_ids {
         Some(tags) => {
@@ -2138,7 +3048,7 @@ pub async fn create_forum_post(
         None => None,
     };
 
-    let post_id = paracord_util::snowflake::generate(1);
+    let post_id = paracord_util::snowflake::try_next_id().await?;
     let post = paracord_db::channels::create_forum_post(
         &state.db,
         post_id,
@@ -2157,7 +3067,7 @@ pub async fn create_forum_post(
         .map(str::trim)
         .filter(|value| !value.is_empty())
     {
-        let message_id = paracord_util::snowflake::generate(1);
+        let message_id = paracord_util::snowflake::try_next_id().await?;
         let _ = paracord_db::messages::create_message(
             &state.db,
             message_id,
@@ -2179,6 +3089,15 @@ pub async fn create_forum_post(
     Ok((StatusCode::CREATED, Json(post_json)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/forum/tags",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = CreateForumTagRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_forum_tag(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2200,7 +3119,7 @@ pub async fn create_forum_tag(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
     }
@@ -2215,7 +3134,7 @@ pub async fn create_forum_tag(
 
     let tag = paracord_db::channels::create_forum_tag(
         &state.db,
-        paracord_util::snowflake::generate(1),
+        paracord_util::snowflake::try_next_id().await?,
         channel_id,
         name,
         body.emoji.as_deref(),
@@ -2227,6 +3146,14 @@ pub async fn create_forum_tag(
     Ok((StatusCode::CREATED, Json(forum_tag_to_json(&tag))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/forum/tags",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_forum_tags(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2235,7 +3162,7 @@ pub async fn list_forum_tags(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
     }
@@ -2256,6 +3183,14 @@ pub async fn list_forum_tags(
         .collect::<Vec<Value>>())))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/forum/tags/{tag_id}",
+    tag = "channels",
+    params(("channel_id" = i64, Path), ("tag_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_forum_tag(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2264,7 +3199,7 @@ pub async fn delete_forum_tag(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
     }
@@ -2285,6 +3220,15 @@ pub async fn delete_forum_tag(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/channels/{channel_id}/forum/sort",
+    tag = "channels",
+    params(("channel_id" = i64, Path)),
+    request_body = UpdateForumSortOrderRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_forum_sort_order(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -2298,7 +3242,7 @@ pub async fn update_forum_sort_order(
     let forum_channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if forum_channel.channel_type != 7 {
         return Err(ApiError::BadRequest("Channel is not a forum".into()));
     }
@@ -2393,6 +3337,9 @@ async fn federation_forward_message(
         if let Some(kind) = channel_meta.as_ref().map(|channel| channel.channel_type) {
             message_content["channel_type"] = Value::Number(serde_json::Number::from(kind));
         }
+        if let Some(nsfw) = channel_meta.as_ref().map(|channel| channel.nsfw) {
+            message_content["channel_nsfw"] = Value::Bool(nsfw);
+        }
         if let Some(name) = guild_meta.as_ref().map(|guild| guild.name.as_str()) {
             message_content["guild_name"] = Value::String(name.to_string());
         }
@@ -2440,6 +3387,7 @@ async fn federation_forward_message(
                 .as_ref()
                 .and_then(|channel| channel.name.as_deref()),
             channel_meta.as_ref().map(|channel| channel.channel_type),
+            channel_meta.as_ref().map(|channel| channel.nsfw),
             guild_meta.as_ref().map(|guild| guild.name.as_str()),
             timestamp_ms,
         ) {
@@ -2459,6 +3407,12 @@ async fn federation_forward_message(
                         })
                         .collect();
                     env.content["attachments"] = serde_json::json!(meta);
+                    if let Err(e) = service.sign_envelope(&mut env) {
+                        tracing::warn!(
+                            "federation: failed to re-sign envelope for message {message_id}: {e}"
+                        );
+                        return;
+                    }
                 }
                 env
             }
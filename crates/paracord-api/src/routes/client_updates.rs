@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Query, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use paracord_core::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use utoipa::IntoParams;
+
+use crate::error::ApiError;
+
+/// Header desktop clients send with their own version so the server can
+/// turn away builds older than `min_client_version`.
+pub const CLIENT_VERSION_HEADER: &str = "x-paracord-client-version";
+
+const DEFAULT_CHANNEL: &str = "stable";
+
+/// `major.minor.patch` with an optional leading `v`. A pre-release suffix
+/// (`1.4.0-beta.2`) sorts before the release it leads up to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ClientVersion {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    release: bool,
+}
+
+impl ClientVersion {
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let raw = raw.strip_prefix(['v', 'V']).unwrap_or(raw);
+        let raw = raw.split('+').next().unwrap_or(raw);
+        let (core, pre) = match raw.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (raw, None),
+        };
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |v| v.parse().ok())?;
+        let patch = parts.next().map_or(Some(0), |v| v.parse().ok())?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+            release: pre.is_none_or(str::is_empty),
+        })
+    }
+}
+
+/// One published build in the operator's manifest file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct ClientRelease {
+    #[serde(default = "default_channel")]
+    channel: String,
+    /// Updater target, e.g. `windows-x86_64` or `linux-x86_64-appimage`.
+    platform: String,
+    version: String,
+    url: String,
+    signature: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    pub_date: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClientUpdateManifestFile {
+    #[serde(default)]
+    releases: Vec<ClientRelease>,
+}
+
+fn default_channel() -> String {
+    DEFAULT_CHANNEL.to_string()
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct UpdateManifestQuery {
+    pub channel: Option<String>,
+    pub platform: Option<String>,
+}
+
+/// Reject requests from desktop clients older than the configured minimum.
+/// Requests without a parseable version header (browsers, bots) pass.
+pub(crate) fn ensure_supported_client(parts: &Parts, state: &AppState) -> Result<(), ApiError> {
+    let Some(minimum_raw) = state.config.min_client_version.as_deref() else {
+        return Ok(());
+    };
+    let Some(minimum) = ClientVersion::parse(minimum_raw) else {
+        return Ok(());
+    };
+    let client = parts
+        .headers
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(ClientVersion::parse);
+    match client {
+        Some(client) if client < minimum => Err(ApiError::ClientOutdated {
+            minimum_version: minimum_raw.trim().to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+async fn load_manifest(path: &str) -> Result<ClientUpdateManifestFile, ApiError> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to read client update manifest {path}: {e}"))?;
+    serde_json::from_str(&raw)
+        .map_err(|e| anyhow::anyhow!("invalid client update manifest {path}: {e}").into())
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (ClientVersion::parse(a), ClientVersion::parse(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn release_json(release: &ClientRelease) -> Value {
+    json!({
+        "version": release.version,
+        "url": release.url,
+        "signature": release.signature,
+        "notes": release.notes,
+        "pub_date": release.pub_date,
+    })
+}
+
+/// Latest desktop build per platform for a release channel.
+///
+/// With `platform`, the body follows the Tauri updater's dynamic format so
+/// the desktop updater can use this URL as its endpoint; `204` means the
+/// channel has nothing published for that platform.
+#[utoipa::path(
+    get,
+    path = "/api/v1/client/update-manifest",
+    tag = "client_updates",
+    params(UpdateManifestQuery),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn get_update_manifest(
+    State(state): State<AppState>,
+    Query(query): Query<UpdateManifestQuery>,
+) -> Result<Response, ApiError> {
+    let path = state
+        .config
+        .client_update_manifest_path
+        .as_deref()
+        .ok_or(ApiError::NotFound)?;
+    let manifest = load_manifest(path).await?;
+    let channel = query
+        .channel
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_CHANNEL);
+    let minimum_version = state.config.min_client_version.as_deref().map(str::trim);
+
+    let mut latest: Vec<&ClientRelease> = Vec::new();
+    for release in manifest.releases.iter().filter(|r| r.channel == channel) {
+        match latest.iter_mut().find(|r| r.platform == release.platform) {
+            Some(current) => {
+                if compare_versions(&release.version, &current.version) == Ordering::Greater {
+                    *current = release;
+                }
+            }
+            None => latest.push(release),
+        }
+    }
+
+    if let Some(platform) = query.platform.as_deref().map(str::trim) {
+        let Some(release) = latest.iter().find(|r| r.platform == platform) else {
+            return Ok(StatusCode::NO_CONTENT.into_response());
+        };
+        let mut body = release_json(release);
+        body["channel"] = json!(channel);
+        body["platform"] = json!(platform);
+        body["minimum_version"] = json!(minimum_version);
+        return Ok(Json(body).into_response());
+    }
+
+    let platforms: Map<String, Value> = latest
+        .iter()
+        .map(|r| (r.platform.clone(), release_json(r)))
+        .collect();
+    Ok(Json(json!({
+        "channel": channel,
+        "minimum_version": minimum_version,
+        "platforms": platforms,
+    }))
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically_with_prereleases_first() {
+        let parse = |v| ClientVersion::parse(v).unwrap();
+        assert!(parse("1.10.0") > parse("1.9.3"));
+        assert!(parse("v2.0.0") > parse("1.99.99"));
+        assert!(parse("1.4.0-beta.1") < parse("1.4.0"));
+        assert_eq!(parse("1.4"), parse("1.4.0"));
+        assert_eq!(parse("1.4.0+build.7"), parse("1.4.0"));
+        assert!(ClientVersion::parse("latest").is_none());
+        assert!(ClientVersion::parse("1.2.3.4").is_none());
+    }
+}
@@ -6,6 +6,7 @@ use axum::{
 use paracord_core::AppState;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -126,7 +127,7 @@ fn command_row_to_json(row: &paracord_db::application_commands::ApplicationComma
 
 // ── Request bodies ──────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateCommandRequest {
     pub name: String,
     pub description: String,
@@ -139,7 +140,7 @@ pub struct CreateCommandRequest {
     pub nsfw: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateCommandRequest {
     pub name: Option<String>,
     pub description: Option<String>,
@@ -149,7 +150,7 @@ pub struct UpdateCommandRequest {
     pub nsfw: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BulkOverwriteCommandRequest {
     pub name: String,
     pub description: String,
@@ -164,6 +165,14 @@ pub struct BulkOverwriteCommandRequest {
 
 // ── Global command endpoints ────────────────────────────────────────────────
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/applications/{app_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_global_commands(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -181,6 +190,15 @@ pub async fn list_global_commands(
         .collect::<Vec<Value>>())))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/applications/{app_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path)),
+    request_body = CreateCommandRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_global_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -221,7 +239,7 @@ pub async fn create_global_command(
         })
         .transpose()?;
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::try_next_id().await?;
     let row = paracord_db::application_commands::create_command(
         &state.db,
         id,
@@ -241,6 +259,14 @@ pub async fn create_global_command(
     Ok((StatusCode::CREATED, Json(command_row_to_json(&row))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/applications/{app_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("cmd_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_global_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -261,6 +287,15 @@ pub async fn get_global_command(
     Ok(Json(command_row_to_json(&row)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/applications/{app_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("cmd_id" = i64, Path)),
+    request_body = UpdateCommandRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_global_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -329,6 +364,14 @@ pub async fn update_global_command(
     Ok(Json(command_row_to_json(&row)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/applications/{app_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("cmd_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_global_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -352,6 +395,15 @@ pub async fn delete_global_command(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/applications/{app_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path)),
+    request_body = Vec<BulkOverwriteCommandRequest>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn bulk_overwrite_global_commands(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -386,7 +438,7 @@ pub async fn bulk_overwrite_global_commands(
             })
             .transpose()?;
 
-        let id = paracord_util::snowflake::generate(1);
+        let id = paracord_util::snowflake::try_next_id().await?;
         prepared.push((
             id,
             name,
@@ -434,6 +486,14 @@ pub async fn bulk_overwrite_global_commands(
 ///
 /// List all commands available in a guild (global + guild-scoped from installed bots).
 /// Available to any guild member.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/commands",
+    tag = "commands",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_available_commands_handler(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -455,6 +515,14 @@ pub async fn list_guild_available_commands_handler(
 
 // ── Guild command endpoints ─────────────────────────────────────────────────
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_commands(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -472,6 +540,15 @@ pub async fn list_guild_commands(
         .collect::<Vec<Value>>())))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path)),
+    request_body = CreateCommandRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_guild_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -524,7 +601,7 @@ pub async fn create_guild_command(
         })
         .transpose()?;
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::try_next_id().await?;
     let row = paracord_db::application_commands::create_command(
         &state.db,
         id,
@@ -544,6 +621,14 @@ pub async fn create_guild_command(
     Ok((StatusCode::CREATED, Json(command_row_to_json(&row))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path), ("cmd_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_guild_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -563,6 +648,15 @@ pub async fn get_guild_command(
     Ok(Json(command_row_to_json(&row)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path), ("cmd_id" = i64, Path)),
+    request_body = UpdateCommandRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_guild_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -641,6 +735,14 @@ pub async fn update_guild_command(
     Ok(Json(command_row_to_json(&row)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands/{cmd_id}",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path), ("cmd_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_guild_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -674,6 +776,15 @@ pub async fn delete_guild_command(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/applications/{app_id}/guilds/{guild_id}/commands",
+    tag = "commands",
+    params(("app_id" = i64, Path), ("guild_id" = i64, Path)),
+    request_body = Vec<BulkOverwriteCommandRequest>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn bulk_overwrite_guild_commands(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -717,7 +828,7 @@ pub async fn bulk_overwrite_guild_commands(
             })
             .transpose()?;
 
-        let id = paracord_util::snowflake::generate(1);
+        let id = paracord_util::snowflake::try_next_id().await?;
         prepared.push((
             id,
             name,
@@ -5,17 +5,28 @@ use axum::{
 use paracord_core::AppState;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashSet;
+use utoipa::IntoParams;
 
 use crate::error::ApiError;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct DiscoveryQuery {
     pub search: Option<String>,
     pub tag: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    /// Guilds with NSFW channels are left out unless this is `true`.
+    pub include_nsfw: Option<bool>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/discovery/guilds",
+    tag = "discovery",
+    params(DiscoveryQuery),
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn list_discoverable_guilds(
     State(state): State<AppState>,
     Query(params): Query<DiscoveryQuery>,
@@ -28,9 +39,17 @@ pub async fn list_discoverable_guilds(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
+    let nsfw_guild_ids: HashSet<i64> = paracord_db::channels::list_nsfw_space_ids(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .into_iter()
+        .collect();
+    let include_nsfw = params.include_nsfw.unwrap_or(false);
+
     let mut discoverable: Vec<_> = all_guilds
         .into_iter()
         .filter(|g| g.visibility.eq_ignore_ascii_case("public"))
+        .filter(|g| include_nsfw || !nsfw_guild_ids.contains(&g.id))
         .collect();
 
     // Filter by search query
@@ -92,6 +111,7 @@ pub async fn list_discoverable_guilds(
             "member_count": member_count,
             "online_count": online_count,
             "tags": tags,
+            "nsfw": nsfw_guild_ids.contains(&guild.id),
             "created_at": guild.created_at.to_rfc3339(),
         }));
     }
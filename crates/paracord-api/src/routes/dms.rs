@@ -1,16 +1,86 @@
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
 use paracord_core::AppState;
+use paracord_db::dms::{
+    DmRecipientRow, GroupDmRow, DM_REQUEST_ACCEPTED, DM_REQUEST_DECLINED, DM_REQUEST_PENDING,
+};
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
+use crate::routes::keys;
 
-#[derive(Debug, Deserialize)]
+/// Group DMs hold at most this many participants, including the owner.
+const MAX_GROUP_DM_RECIPIENTS: usize = 10;
+const MAX_GROUP_DM_NAME_LEN: usize = 100;
+const MAX_GROUP_DM_ICON_LEN: usize = 256 * 1024;
+const MAX_SENDER_KEY_LEN: usize = 4096;
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDmRequest {
+    pub recipient_id: Option<String>,
+    /// Creates a group DM with these users when set.
+    pub recipient_ids: Option<Vec<String>>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateGroupDmRequest {
+    pub name: Option<String>,
+    /// Image data URL; an empty string removes the icon.
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TransferGroupDmOwnerRequest {
+    pub owner_id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SenderKeyUpload {
     pub recipient_id: String,
+    pub ciphertext: String,
+    pub header: Option<String>,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UploadSenderKeysRequest {
+    pub epoch: i64,
+    pub keys: Vec<SenderKeyUpload>,
+}
+
+fn dm_channel_to_json(c: &paracord_db::dms::DmChannelWithRecipientRow) -> Value {
+    json!({
+        "id": c.id.to_string(),
+        "type": c.channel_type,
+        "channel_type": c.channel_type,
+        "guild_id": null,
+        "name": null,
+        "last_message_id": c.last_message_id.map(|id| id.to_string()),
+        "is_message_request": c.request_state == DM_REQUEST_PENDING,
+        "recipient": {
+            "id": c.recipient_id.to_string(),
+            "username": c.recipient_username,
+            "discriminator": c.recipient_discriminator,
+            "avatar_hash": c.recipient_avatar_hash,
+            "public_key": c.recipient_public_key,
+        }
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/dms",
+    tag = "dms",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_dms(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -19,39 +89,134 @@ pub async fn list_dms(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
+    let mut result: Vec<Value> = channels
+        .iter()
+        .filter(|c| c.request_state == DM_REQUEST_ACCEPTED)
+        .map(dm_channel_to_json)
+        .collect();
+
+    let groups = paracord_db::dms::list_user_group_dms(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    for group in &groups {
+        let recipients = paracord_db::dms::list_dm_recipients(&state.db, group.id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        result.push(group_dm_to_json(group, &recipients));
+    }
+
+    Ok(Json(json!(result)))
+}
+
+/// Pending message requests: DMs opened by non-friends that have at least one
+/// message and haven't been accepted or declined yet.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/dms/requests",
+    tag = "dms",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_dm_requests(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let channels = paracord_db::dms::list_user_dm_channels(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
     let result: Vec<Value> = channels
         .iter()
-        .map(|c| {
-            json!({
-                "id": c.id.to_string(),
-                "type": c.channel_type,
-                "channel_type": c.channel_type,
-                "guild_id": null,
-                "name": null,
-                "last_message_id": c.last_message_id.map(|id| id.to_string()),
-                "recipient": {
-                    "id": c.recipient_id.to_string(),
-                    "username": c.recipient_username,
-                    "discriminator": c.recipient_discriminator,
-                    "avatar_hash": c.recipient_avatar_hash,
-                    "public_key": c.recipient_public_key,
-                }
-            })
-        })
+        .filter(|c| c.request_state == DM_REQUEST_PENDING && c.last_message_id.is_some())
+        .map(dm_channel_to_json)
         .collect();
 
     Ok(Json(json!(result)))
 }
 
+async fn update_dm_request(
+    state: &AppState,
+    user_id: i64,
+    channel_id: i64,
+    request_state: i16,
+) -> Result<StatusCode, ApiError> {
+    let current = paracord_db::dms::get_request_state(&state.db, channel_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    if current == DM_REQUEST_ACCEPTED {
+        return Err(ApiError::BadRequest(
+            "This conversation is not a message request".into(),
+        ));
+    }
+    paracord_db::dms::set_request_state(&state.db, channel_id, user_id, request_state)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    state.event_bus.dispatch_to_users(
+        "DM_REQUEST_UPDATE",
+        json!({
+            "channel_id": channel_id.to_string(),
+            "accepted": request_state == DM_REQUEST_ACCEPTED,
+        }),
+        vec![user_id],
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/dms/requests/{channel_id}/accept",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn accept_dm_request(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    update_dm_request(&state, auth.user_id, channel_id, DM_REQUEST_ACCEPTED).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/dms/requests/{channel_id}/decline",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn decline_dm_request(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    update_dm_request(&state, auth.user_id, channel_id, DM_REQUEST_DECLINED).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/dms",
+    tag = "dms",
+    request_body = CreateDmRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_dm(
     State(state): State<AppState>,
     auth: AuthUser,
     Json(body): Json<CreateDmRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if let Some(recipient_ids) = body.recipient_ids.as_deref() {
+        return create_group_dm(&state, auth.user_id, recipient_ids, body.name.as_deref()).await;
+    }
     let recipient_id: i64 = body
         .recipient_id
-        .parse()
-        .map_err(|_| ApiError::BadRequest("Invalid recipient_id".into()))?;
+        .as_deref()
+        .and_then(|raw| raw.parse().ok())
+        .ok_or_else(|| ApiError::BadRequest("Invalid recipient_id".into()))?;
 
     if recipient_id == auth.user_id {
         return Err(ApiError::BadRequest(
@@ -77,14 +242,14 @@ pub async fn create_dm(
     let share_guild = paracord_db::members::share_any_guild(&state.db, auth.user_id, recipient_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    if !are_friends && !share_guild {
+    if !are_friends && (!share_guild || state.runtime.read().await.dm_require_friendship) {
         return Err(ApiError::Forbidden);
     }
 
     let recipient = paracord_db::users::get_user_by_id(&state.db, recipient_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     let channel = if let Some(existing) =
         paracord_db::dms::find_dm_channel_between(&state.db, auth.user_id, recipient_id)
@@ -93,11 +258,28 @@ pub async fn create_dm(
     {
         existing
     } else {
-        let channel_id = paracord_util::snowflake::generate(1);
-        paracord_db::dms::create_dm_channel(&state.db, channel_id, auth.user_id, recipient_id)
+        let channel_id = paracord_util::snowflake::try_next_id().await?;
+        let channel =
+            paracord_db::dms::create_dm_channel(&state.db, channel_id, auth.user_id, recipient_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        // DMs from non-friends land in the recipient's request inbox.
+        if !are_friends {
+            paracord_db::dms::set_request_state(
+                &state.db,
+                channel_id,
+                recipient_id,
+                DM_REQUEST_PENDING,
+            )
             .await
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        channel
     };
+    let request_state = paracord_db::dms::get_request_state(&state.db, channel.id, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .unwrap_or(DM_REQUEST_ACCEPTED);
 
     Ok((
         StatusCode::CREATED,
@@ -108,6 +290,7 @@ pub async fn create_dm(
             "guild_id": null,
             "name": null,
             "last_message_id": channel.last_message_id.map(|id| id.to_string()),
+            "is_message_request": request_state == DM_REQUEST_PENDING,
             "recipient": {
                 "id": recipient.id.to_string(),
                 "username": recipient.username,
@@ -118,3 +301,628 @@ pub async fn create_dm(
         })),
     ))
 }
+
+/// Reject 1:1 DM sends between non-friends when the server requires
+/// friendship. Group DMs are friends-only at the point members are added.
+pub(crate) async fn ensure_dm_send_allowed(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    if channel.channel_type != 1 || !state.runtime.read().await.dm_require_friendship {
+        return Ok(());
+    }
+    let recipient_ids = paracord_db::dms::get_dm_recipient_ids(&state.db, channel.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    for recipient_id in recipient_ids {
+        if recipient_id == user_id {
+            continue;
+        }
+        let friends = paracord_db::relationships::are_friends(&state.db, user_id, recipient_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if !friends {
+            return Err(ApiError::Forbidden);
+        }
+    }
+    Ok(())
+}
+
+/// Deliver a new DM message. Recipients with a pending request get it flagged
+/// as `message_request` so clients skip notifications; declined recipients
+/// don't get it at all. Sending into a request accepts it. Returns the
+/// other recipients who accepted the DM, the ones a push may go to.
+pub(crate) async fn dispatch_dm_message_create(
+    state: &AppState,
+    channel_id: i64,
+    author_id: i64,
+    msg_json: &Value,
+) -> Vec<i64> {
+    let recipients = paracord_db::dms::get_dm_recipient_request_states(&state.db, channel_id)
+        .await
+        .unwrap_or_default();
+
+    let mut accepted = Vec::new();
+    let mut pending = Vec::new();
+    for (user_id, request_state) in recipients {
+        if user_id == author_id {
+            if request_state != DM_REQUEST_ACCEPTED
+                && paracord_db::dms::set_request_state(
+                    &state.db,
+                    channel_id,
+                    author_id,
+                    DM_REQUEST_ACCEPTED,
+                )
+                .await
+                .unwrap_or(false)
+            {
+                state.event_bus.dispatch_to_users(
+                    "DM_REQUEST_UPDATE",
+                    json!({ "channel_id": channel_id.to_string(), "accepted": true }),
+                    vec![author_id],
+                );
+            }
+            accepted.push(user_id);
+            continue;
+        }
+        match request_state {
+            DM_REQUEST_PENDING => pending.push(user_id),
+            DM_REQUEST_DECLINED => {}
+            _ => accepted.push(user_id),
+        }
+    }
+
+    let notify: Vec<i64> = accepted
+        .iter()
+        .copied()
+        .filter(|user_id| *user_id != author_id)
+        .collect();
+    state
+        .event_bus
+        .dispatch_to_users("MESSAGE_CREATE", msg_json.clone(), accepted);
+    if !pending.is_empty() {
+        let mut flagged = msg_json.clone();
+        flagged["message_request"] = json!(true);
+        state
+            .event_bus
+            .dispatch_to_users("MESSAGE_CREATE", flagged, pending);
+    }
+    notify
+}
+
+/// Move any pending or declined DM between two new friends into their regular
+/// DM lists.
+pub(crate) async fn accept_requests_between(state: &AppState, user_a: i64, user_b: i64) {
+    let Ok(Some(channel)) =
+        paracord_db::dms::find_dm_channel_between(&state.db, user_a, user_b).await
+    else {
+        return;
+    };
+    for user_id in [user_a, user_b] {
+        if paracord_db::dms::set_request_state(&state.db, channel.id, user_id, DM_REQUEST_ACCEPTED)
+            .await
+            .unwrap_or(false)
+        {
+            state.event_bus.dispatch_to_users(
+                "DM_REQUEST_UPDATE",
+                json!({ "channel_id": channel.id.to_string(), "accepted": true }),
+                vec![user_id],
+            );
+        }
+    }
+}
+
+fn recipient_to_json(r: &DmRecipientRow) -> Value {
+    json!({
+        "id": r.user_id.to_string(),
+        "username": r.username,
+        "discriminator": r.discriminator,
+        "avatar_hash": r.avatar_hash,
+        "public_key": r.public_key,
+    })
+}
+
+fn group_dm_to_json(group: &GroupDmRow, recipients: &[DmRecipientRow]) -> Value {
+    json!({
+        "id": group.id.to_string(),
+        "type": 3,
+        "channel_type": 3,
+        "guild_id": null,
+        "name": group.name,
+        "icon_hash": group.icon_hash,
+        "owner_id": group.owner_id.map(|id| id.to_string()),
+        "last_message_id": group.last_message_id.map(|id| id.to_string()),
+        "recipients": recipients.iter().map(recipient_to_json).collect::<Vec<Value>>(),
+    })
+}
+
+fn validate_group_dm_name(name: &str) -> Result<(), ApiError> {
+    if name.trim().chars().count() > MAX_GROUP_DM_NAME_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "Group DM name must be at most {} characters",
+            MAX_GROUP_DM_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Group DMs can only include friends of the user adding them, and never
+/// anyone on either side of a block.
+async fn ensure_can_add_to_group(
+    state: &AppState,
+    user_id: i64,
+    target_id: i64,
+) -> Result<(), ApiError> {
+    paracord_db::users::get_user_by_id(&state.db, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+    let blocked =
+        paracord_db::relationships::is_blocked_either_direction(&state.db, user_id, target_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let friends = paracord_db::relationships::are_friends(&state.db, user_id, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if blocked || !friends {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+async fn create_group_dm(
+    state: &AppState,
+    user_id: i64,
+    raw_recipient_ids: &[String],
+    name: Option<&str>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let mut recipient_ids = Vec::with_capacity(raw_recipient_ids.len());
+    for raw in raw_recipient_ids {
+        let id: i64 = raw
+            .parse()
+            .map_err(|_| ApiError::BadRequest("Invalid recipient id".into()))?;
+        if id != user_id && !recipient_ids.contains(&id) {
+            recipient_ids.push(id);
+        }
+    }
+    if recipient_ids.is_empty() {
+        return Err(ApiError::BadRequest(
+            "A group DM needs at least one other recipient".into(),
+        ));
+    }
+    if recipient_ids.len() + 1 > MAX_GROUP_DM_RECIPIENTS {
+        return Err(ApiError::BadRequest(format!(
+            "Group DMs are limited to {} participants",
+            MAX_GROUP_DM_RECIPIENTS
+        )));
+    }
+    if let Some(name) = name {
+        validate_group_dm_name(name)?;
+    }
+    for &recipient_id in &recipient_ids {
+        ensure_can_add_to_group(state, user_id, recipient_id).await?;
+    }
+
+    let channel_id = paracord_util::snowflake::try_next_id().await?;
+    let name = name.map(str::trim).filter(|name| !name.is_empty());
+    let group = paracord_db::dms::create_group_dm_channel(
+        &state.db,
+        channel_id,
+        user_id,
+        name,
+        &recipient_ids,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let recipients = paracord_db::dms::list_dm_recipients(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let group_json = group_dm_to_json(&group, &recipients);
+
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_CREATE",
+        group_json.clone(),
+        recipients.iter().map(|r| r.user_id).collect(),
+    );
+
+    Ok((StatusCode::CREATED, Json(group_json)))
+}
+
+/// Load a group DM the user participates in, with its current recipients.
+async fn load_group_dm(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<(GroupDmRow, Vec<DmRecipientRow>), ApiError> {
+    let group = paracord_db::dms::get_group_dm(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    let recipients = paracord_db::dms::list_dm_recipients(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !recipients.iter().any(|r| r.user_id == user_id) {
+        return Err(ApiError::Forbidden);
+    }
+    Ok((group, recipients))
+}
+
+fn recipient_ids(recipients: &[DmRecipientRow]) -> Vec<i64> {
+    recipients.iter().map(|r| r.user_id).collect()
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/users/@me/dms/{channel_id}",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    request_body = UpdateGroupDmRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_group_dm(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<UpdateGroupDmRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let (_, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    if let Some(name) = body.name.as_deref() {
+        validate_group_dm_name(name)?;
+    }
+    if let Some(icon) = body.icon.as_deref() {
+        if !icon.is_empty()
+            && (!icon.starts_with("data:image/") || icon.len() > MAX_GROUP_DM_ICON_LEN)
+        {
+            return Err(ApiError::BadRequest(
+                "icon must be an image data URL under 256KB".into(),
+            ));
+        }
+    }
+
+    let group = paracord_db::dms::update_group_dm(
+        &state.db,
+        channel_id,
+        body.name.as_deref().map(str::trim),
+        body.icon.as_deref(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let group_json = group_dm_to_json(&group, &recipients);
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_UPDATE",
+        group_json.clone(),
+        recipient_ids(&recipients),
+    );
+    Ok(Json(group_json))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/dms/{channel_id}/recipients/{user_id}",
+    tag = "dms",
+    params(("channel_id" = i64, Path), ("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn add_group_dm_recipient(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, user_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    let (group, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    if recipients.iter().any(|r| r.user_id == user_id) {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    if recipients.len() >= MAX_GROUP_DM_RECIPIENTS {
+        return Err(ApiError::BadRequest(format!(
+            "Group DMs are limited to {} participants",
+            MAX_GROUP_DM_RECIPIENTS
+        )));
+    }
+    ensure_can_add_to_group(&state, auth.user_id, user_id).await?;
+
+    paracord_db::dms::add_dm_recipient(&state.db, channel_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let updated = paracord_db::dms::list_dm_recipients(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    if let Some(added) = updated.iter().find(|r| r.user_id == user_id) {
+        state.event_bus.dispatch_to_users(
+            "GROUP_DM_RECIPIENT_ADD",
+            json!({
+                "channel_id": channel_id.to_string(),
+                "user": recipient_to_json(added),
+            }),
+            recipient_ids(&recipients),
+        );
+    }
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_CREATE",
+        group_dm_to_json(&group, &updated),
+        vec![user_id],
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove `user_id` from a group, handing ownership to the longest-standing
+/// remaining member when the owner leaves and deleting the group once empty.
+async fn remove_from_group(
+    state: &AppState,
+    group: &GroupDmRow,
+    recipients: &[DmRecipientRow],
+    user_id: i64,
+) -> Result<(), ApiError> {
+    paracord_db::dms::remove_dm_recipient(&state.db, group.id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_DELETE",
+        json!({ "id": group.id.to_string() }),
+        vec![user_id],
+    );
+
+    let remaining: Vec<&DmRecipientRow> =
+        recipients.iter().filter(|r| r.user_id != user_id).collect();
+    if remaining.is_empty() {
+        paracord_db::channels::delete_channel(&state.db, group.id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        return Ok(());
+    }
+    let remaining_ids: Vec<i64> = remaining.iter().map(|r| r.user_id).collect();
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_RECIPIENT_REMOVE",
+        json!({
+            "channel_id": group.id.to_string(),
+            "user_id": user_id.to_string(),
+        }),
+        remaining_ids.clone(),
+    );
+
+    if group.owner_id == Some(user_id) {
+        let updated = paracord_db::dms::set_group_dm_owner(&state.db, group.id, remaining_ids[0])
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let remaining_rows: Vec<DmRecipientRow> = remaining.into_iter().cloned().collect();
+        state.event_bus.dispatch_to_users(
+            "GROUP_DM_UPDATE",
+            group_dm_to_json(&updated, &remaining_rows),
+            remaining_ids,
+        );
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me/dms/{channel_id}/recipients/{user_id}",
+    tag = "dms",
+    params(("channel_id" = i64, Path), ("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn remove_group_dm_recipient(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, user_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    let (group, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    if user_id != auth.user_id && group.owner_id != Some(auth.user_id) {
+        return Err(ApiError::Forbidden);
+    }
+    if !recipients.iter().any(|r| r.user_id == user_id) {
+        return Err(ApiError::NotFound);
+    }
+    remove_from_group(&state, &group, &recipients, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me/dms/{channel_id}",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn leave_group_dm(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let (group, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    remove_from_group(&state, &group, &recipients, auth.user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/dms/{channel_id}/owner",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    request_body = TransferGroupDmOwnerRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn transfer_group_dm_owner(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<TransferGroupDmOwnerRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let (group, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    if group.owner_id != Some(auth.user_id) {
+        return Err(ApiError::Forbidden);
+    }
+    let owner_id: i64 = body
+        .owner_id
+        .parse()
+        .map_err(|_| ApiError::BadRequest("Invalid owner_id".into()))?;
+    if !recipients.iter().any(|r| r.user_id == owner_id) {
+        return Err(ApiError::BadRequest(
+            "New owner must be a member of the group".into(),
+        ));
+    }
+
+    let updated = paracord_db::dms::set_group_dm_owner(&state.db, channel_id, owner_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let group_json = group_dm_to_json(&updated, &recipients);
+    state.event_bus.dispatch_to_users(
+        "GROUP_DM_UPDATE",
+        group_json.clone(),
+        recipient_ids(&recipients),
+    );
+    Ok(Json(group_json))
+}
+
+/// Prekey bundles for every other participant, so the caller can open
+/// pairwise sessions and distribute its group sender key.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/dms/{channel_id}/keys",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_group_dm_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let (_, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    let mut bundles = Vec::with_capacity(recipients.len());
+    for recipient in recipients.iter().filter(|r| r.user_id != auth.user_id) {
+        let bundle = keys::load_prekey_bundle(&state, recipient.user_id).await?;
+        bundles.push(json!({
+            "user_id": recipient.user_id.to_string(),
+            "bundle": bundle,
+        }));
+    }
+    Ok(Json(json!(bundles)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/dms/{channel_id}/sender-keys",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    request_body = UploadSenderKeysRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn upload_group_dm_sender_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<UploadSenderKeysRequest>,
+) -> Result<StatusCode, ApiError> {
+    let (_, recipients) = load_group_dm(&state, channel_id, auth.user_id).await?;
+    if body.keys.len() > MAX_GROUP_DM_RECIPIENTS {
+        return Err(ApiError::BadRequest("Too many sender keys".into()));
+    }
+
+    let mut parsed = Vec::with_capacity(body.keys.len());
+    for key in &body.keys {
+        let recipient_id: i64 = key
+            .recipient_id
+            .parse()
+            .map_err(|_| ApiError::BadRequest("Invalid recipient_id".into()))?;
+        if recipient_id == auth.user_id || !recipients.iter().any(|r| r.user_id == recipient_id) {
+            return Err(ApiError::BadRequest(
+                "Sender keys can only be sent to other group members".into(),
+            ));
+        }
+        if key.ciphertext.is_empty()
+            || key.ciphertext.len() > MAX_SENDER_KEY_LEN
+            || key
+                .header
+                .as_ref()
+                .is_some_and(|header| header.len() > MAX_SENDER_KEY_LEN)
+        {
+            return Err(ApiError::BadRequest("Invalid sender key payload".into()));
+        }
+        parsed.push((recipient_id, key));
+    }
+
+    for (recipient_id, key) in parsed {
+        paracord_db::dms::upsert_sender_key(
+            &state.db,
+            channel_id,
+            auth.user_id,
+            recipient_id,
+            body.epoch,
+            &key.ciphertext,
+            key.header.as_deref(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        state.event_bus.dispatch_to_users(
+            "GROUP_DM_SENDER_KEY",
+            json!({
+                "channel_id": channel_id.to_string(),
+                "sender_id": auth.user_id.to_string(),
+                "epoch": body.epoch,
+                "ciphertext": key.ciphertext,
+                "header": key.header,
+            }),
+            vec![recipient_id],
+        );
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/dms/{channel_id}/sender-keys",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_group_dm_sender_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    load_group_dm(&state, channel_id, auth.user_id).await?;
+    let keys = paracord_db::dms::list_sender_keys_for(&state.db, channel_id, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(keys
+        .iter()
+        .map(|k| json!({
+            "sender_id": k.sender_id.to_string(),
+            "epoch": k.epoch,
+            "ciphertext": k.ciphertext,
+            "header": k.header,
+        }))
+        .collect::<Vec<Value>>())))
+}
+
+/// Delivered/read receipts of the other recipients, for DMs and group DMs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/dms/{channel_id}/receipts",
+    tag = "dms",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_dm_receipts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let receipts =
+        paracord_core::receipts::visible_receipts(&state, channel_id, auth.user_id).await?;
+    Ok(Json(json!(receipts
+        .iter()
+        .map(paracord_core::receipts::receipt_to_json)
+        .collect::<Vec<Value>>())))
+}
@@ -1,20 +1,28 @@
 use axum::{
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::StatusCode,
     Json,
 };
+use paracord_core::limits::{self, GuildLimit};
 use paracord_core::AppState;
+use paracord_db::emoji_usage::EmojiUsageRow;
+use paracord_db::emojis::EmojiRow;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
-const MAX_EMOJI_NAME_LEN: usize = 32;
-const MAX_EMOJI_IMAGE_SIZE: usize = 256 * 1024; // 256 KB
+pub(crate) const MAX_EMOJI_NAME_LEN: usize = 32;
+pub(crate) const MAX_EMOJI_IMAGE_SIZE: usize = 256 * 1024; // 256 KB
+const DEFAULT_EMOJI_USAGE_DAYS: i64 = 30;
+const MAX_EMOJI_USAGE_DAYS: i64 = 365;
 
-fn emoji_to_json(e: &paracord_db::emojis::EmojiRow) -> Value {
+pub(crate) fn emoji_to_json(e: &EmojiRow) -> Value {
     json!({
         "id": e.id.to_string(),
         "guild_id": e.guild_id.to_string(),
@@ -34,7 +42,7 @@ async fn ensure_emoji_permission(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -59,6 +67,14 @@ async fn ensure_emoji_permission(
     Ok(())
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/emojis",
+    tag = "emojis",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_emojis(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -74,6 +90,125 @@ pub async fn list_guild_emojis(
     Ok(Json(json!(result)))
 }
 
+#[derive(Deserialize, IntoParams)]
+pub struct EmojiUsageQuery {
+    pub days: Option<i64>,
+}
+
+impl EmojiUsageQuery {
+    fn days(&self) -> Result<i64, ApiError> {
+        let days = self.days.unwrap_or(DEFAULT_EMOJI_USAGE_DAYS);
+        if !(1..=MAX_EMOJI_USAGE_DAYS).contains(&days) {
+            return Err(ApiError::BadRequest(format!(
+                "days must be between 1 and {MAX_EMOJI_USAGE_DAYS}"
+            )));
+        }
+        Ok(days)
+    }
+}
+
+/// The guild's emojis with their usage over the last `days` days.
+async fn load_emoji_usage(
+    state: &AppState,
+    guild_id: i64,
+    days: i64,
+) -> Result<Vec<(EmojiRow, EmojiUsageRow)>, ApiError> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days - 1)).date_naive();
+    let usage = paracord_db::emoji_usage::get_guild_emoji_usage(&state.db, guild_id, since)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let mut emojis: HashMap<i64, EmojiRow> =
+        paracord_db::emojis::get_guild_emojis(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .into_iter()
+            .map(|emoji| (emoji.id, emoji))
+            .collect();
+    Ok(usage
+        .into_iter()
+        .filter_map(|row| emojis.remove(&row.emoji_id).map(|emoji| (emoji, row)))
+        .collect())
+}
+
+fn emoji_usage_to_json(emoji: &EmojiRow, usage: &EmojiUsageRow) -> Value {
+    json!({
+        "emoji": emoji_to_json(emoji),
+        "message_count": usage.message_count,
+        "reaction_count": usage.reaction_count,
+        "total": usage.message_count + usage.reaction_count,
+        "last_used_at": usage.last_used_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/emojis/analytics",
+    tag = "emojis",
+    params(("guild_id" = i64, Path), EmojiUsageQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_emoji_analytics(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<EmojiUsageQuery>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_emoji_permission(&state, guild_id, auth.user_id).await?;
+    let days = params.days()?;
+
+    let usage = load_emoji_usage(&state, guild_id, days).await?;
+    let emojis: Vec<Value> = usage
+        .iter()
+        .map(|(emoji, usage)| emoji_usage_to_json(emoji, usage))
+        .collect();
+    Ok(Json(json!({ "days": days, "emojis": emojis })))
+}
+
+/// Emojis nobody used in the last `days` days that are at least that old,
+/// least recently used first, as candidates for removal.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/emojis/unused",
+    tag = "emojis",
+    params(("guild_id" = i64, Path), EmojiUsageQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_unused_emojis(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<EmojiUsageQuery>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_emoji_permission(&state, guild_id, auth.user_id).await?;
+    let days = params.days()?;
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(days);
+    let mut unused: Vec<(EmojiRow, EmojiUsageRow)> = load_emoji_usage(&state, guild_id, days)
+        .await?
+        .into_iter()
+        .filter(|(emoji, usage)| {
+            usage.message_count + usage.reaction_count == 0 && emoji.created_at <= cutoff
+        })
+        .collect();
+    unused.sort_by_key(|(emoji, usage)| (usage.last_used_at, emoji.created_at, emoji.id));
+    let emojis: Vec<Value> = unused
+        .iter()
+        .map(|(emoji, usage)| emoji_usage_to_json(emoji, usage))
+        .collect();
+    Ok(Json(json!({ "days": days, "emojis": emojis })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/emojis",
+    tag = "emojis",
+    params(("guild_id" = i64, Path)),
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_emoji(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -135,6 +270,14 @@ pub async fn create_emoji(
     let content_type =
         content_type.ok_or_else(|| ApiError::BadRequest("Missing emoji content type".into()))?;
 
+    let perks = paracord_core::supporters::perks_for(&state, auth.user_id).await;
+    if perks.max_emojis_per_guild.is_some() {
+        let count = paracord_db::emojis::count_guild_emojis(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        limits::ensure_below(GuildLimit::Emojis, perks.max_emojis_per_guild, count)?;
+    }
+
     let (animated, ext) = match content_type.as_str() {
         "image/png" => (false, "png"),
         "image/gif" => (true, "gif"),
@@ -157,7 +300,7 @@ pub async fn create_emoji(
     }
 
     // Store emoji image to disk
-    let emoji_id = paracord_util::snowflake::generate(1);
+    let emoji_id = paracord_util::snowflake::try_next_id().await?;
     let storage_dir = std::path::Path::new(&state.config.storage_path).join("emojis");
     tokio::fs::create_dir_all(&storage_dir)
         .await
@@ -193,11 +336,20 @@ pub async fn create_emoji(
     Ok((StatusCode::CREATED, Json(emoji_json)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateEmojiRequest {
     pub name: String,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/emojis/{emoji_id}",
+    tag = "emojis",
+    params(("guild_id" = i64, Path), ("emoji_id" = i64, Path)),
+    request_body = UpdateEmojiRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_emoji(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -216,7 +368,7 @@ pub async fn update_emoji(
     let existing = paracord_db::emojis::get_emoji(&state.db, emoji_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownEmoji))?;
 
     if existing.guild_id != guild_id {
         return Err(ApiError::NotFound);
@@ -240,6 +392,14 @@ pub async fn update_emoji(
     Ok(Json(emoji_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/emojis/{emoji_id}",
+    tag = "emojis",
+    params(("guild_id" = i64, Path), ("emoji_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_emoji(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -251,7 +411,7 @@ pub async fn delete_emoji(
     let existing = paracord_db::emojis::get_emoji(&state.db, emoji_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownEmoji))?;
 
     if existing.guild_id != guild_id {
         return Err(ApiError::NotFound);
@@ -280,6 +440,13 @@ pub async fn delete_emoji(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/emojis/{emoji_id}/image",
+    tag = "emojis",
+    params(("guild_id" = i64, Path), ("emoji_id" = i64, Path)),
+    responses((status = 200, description = "OK")),
+)]
 pub async fn get_emoji_image(
     State(state): State<AppState>,
     Path((guild_id, emoji_id)): Path<(i64, i64)>,
@@ -287,7 +454,7 @@ pub async fn get_emoji_image(
     let emoji = paracord_db::emojis::get_emoji(&state.db, emoji_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownEmoji))?;
 
     if emoji.guild_id != guild_id {
         return Err(ApiError::NotFound);
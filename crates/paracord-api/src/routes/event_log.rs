@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_util::stream;
+use paracord_core::event_log::{EventFilter, EventLog, RecordedEvent};
+use paracord_core::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use utoipa::IntoParams;
+
+use crate::error::ApiError;
+use crate::middleware::AdminUser;
+use crate::routes::security;
+
+#[derive(Deserialize, IntoParams)]
+pub struct EventLogQuery {
+    /// Comma-separated event types, e.g. `MESSAGE_CREATE,GUILD_UPDATE`.
+    pub types: Option<String>,
+    pub guild_id: Option<String>,
+    /// Only events with a higher sequence number.
+    pub after: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl EventLogQuery {
+    fn filter(&self) -> Result<EventFilter, ApiError> {
+        let event_types = self.types.as_deref().map(|raw| {
+            raw.split(',')
+                .map(|t| t.trim().to_ascii_uppercase())
+                .filter(|t| !t.is_empty())
+                .collect()
+        });
+        let guild_id = self
+            .guild_id
+            .as_deref()
+            .map(|raw| {
+                raw.parse::<i64>()
+                    .map_err(|_| ApiError::BadRequest("Invalid guild_id".into()))
+            })
+            .transpose()?;
+        Ok(EventFilter {
+            event_types,
+            guild_id,
+        })
+    }
+}
+
+fn event_log(state: &AppState) -> Result<Arc<EventLog>, ApiError> {
+    state.event_log.clone().ok_or(ApiError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/events",
+    tag = "event_log",
+    operation_id = "event_log_list_events",
+    params(EventLogQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_events(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<EventLogQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let log = event_log(&state)?;
+    let filter = query.filter()?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let events = log.replay(&filter, query.after.unwrap_or(0), limit).await;
+    Ok(Json(json!({
+        "events": events.iter().map(RecordedEvent::to_json).collect::<Vec<_>>(),
+        "latest_seq": log.latest_seq().await,
+        "capacity": log.capacity(),
+    })))
+}
+
+struct FirehoseState {
+    backlog: VecDeque<RecordedEvent>,
+    receiver: broadcast::Receiver<Arc<RecordedEvent>>,
+    filter: EventFilter,
+    last_seq: u64,
+}
+
+fn sse_event(event: &RecordedEvent) -> Event {
+    Event::default()
+        .event("event")
+        .id(event.seq.to_string())
+        .data(event.to_json().to_string())
+}
+
+/// Stream gateway events as they happen. `after` (or `Last-Event-ID` on
+/// reconnect) first replays buffered events newer than that sequence number.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/events/stream",
+    tag = "event_log",
+    operation_id = "event_log_stream_events",
+    params(EventLogQuery),
+    responses((status = 200, description = "Event stream", content_type = "text/event-stream")),
+    security(("bearer" = [])),
+)]
+pub async fn stream_events(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Query(query): Query<EventLogQuery>,
+) -> Result<Sse<impl futures_util::Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let log = event_log(&state)?;
+    let filter = query.filter()?;
+    let after = query.after.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    });
+
+    security::log_security_event(
+        &state,
+        "admin.event_firehose.open",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "types": query.types,
+            "guild_id": query.guild_id,
+            "after": after,
+        })),
+    )
+    .await;
+
+    // Subscribe before reading the backlog so nothing falls in between.
+    let receiver = log.subscribe();
+    let (backlog, last_seq) = match after {
+        Some(after) => {
+            let backlog = log.replay(&filter, after, log.capacity()).await;
+            let last_seq = backlog.last().map(|event| event.seq).unwrap_or(after);
+            (backlog, last_seq)
+        }
+        None => (Vec::new(), log.latest_seq().await),
+    };
+    let firehose = FirehoseState {
+        backlog: backlog.into(),
+        receiver,
+        filter,
+        last_seq,
+    };
+
+    let event_stream = stream::unfold(firehose, |mut st| async move {
+        if let Some(event) = st.backlog.pop_front() {
+            return Some((Ok(sse_event(&event)), st));
+        }
+        loop {
+            match st.receiver.recv().await {
+                Ok(event) => {
+                    if event.seq <= st.last_seq || !st.filter.matches(&event) {
+                        continue;
+                    }
+                    st.last_seq = event.seq;
+                    return Some((Ok(sse_event(&event)), st));
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    let lagged = Event::default()
+                        .event("lagged")
+                        .data(json!({ "skipped": skipped }).to_string());
+                    return Some((Ok(lagged), st));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(event_stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("keep-alive"),
+    ))
+}
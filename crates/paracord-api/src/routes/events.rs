@@ -1,12 +1,19 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{SecondsFormat, Utc};
+use paracord_core::calendar::RecurrenceRule;
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -14,6 +21,19 @@ use crate::middleware::AuthUser;
 const MAX_EVENT_NAME_LEN: usize = 100;
 const MAX_EVENT_DESCRIPTION_LEN: usize = 1000;
 const MAX_EVENT_LOCATION_LEN: usize = 200;
+/// Reminders can be scheduled up to a week ahead.
+const MAX_REMINDER_MINUTES: i32 = 10_080;
+
+const EVENT_STATUS_SCHEDULED: i32 = 1;
+const EVENT_STATUS_ACTIVE: i32 = 2;
+const EVENT_STATUS_COMPLETED: i32 = 3;
+const EVENT_ENTITY_VOICE: i32 = 1;
+/// A host joining this long before an occurrence starts it early.
+const EVENT_AUTO_START_EARLY_MINUTES: i64 = 15;
+/// ...or this long after, if it never got started on time.
+const EVENT_AUTO_START_LATE_HOURS: i64 = 6;
+/// How long a voice event's channel may sit empty before the event ends.
+const EVENT_VOICE_EMPTY_GRACE: Duration = Duration::from_secs(300);
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -42,13 +62,17 @@ fn event_to_json(
         "entity_type": e.entity_type,
         "location": e.location,
         "image_url": e.image_url,
+        "recurrence_rule": e.recurrence_rule,
+        "reminder_minutes": e.reminder_minutes,
+        "next_occurrence": paracord_core::calendar::next_occurrence(e, Utc::now())
+            .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
         "user_count": rsvp_count,
         "user_rsvp": user_rsvp,
         "created_at": e.created_at.to_rfc3339(),
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateEventRequest {
     pub name: String,
     pub description: Option<String>,
@@ -59,13 +83,15 @@ pub struct CreateEventRequest {
     pub channel_id: Option<String>,
     pub location: Option<String>,
     pub image_url: Option<String>,
+    pub recurrence_rule: Option<String>,
+    pub reminder_minutes: Option<i32>,
 }
 
 fn default_entity_type() -> i32 {
     1
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateEventRequest {
     pub name: Option<String>,
     pub description: Option<String>,
@@ -75,6 +101,61 @@ pub struct UpdateEventRequest {
     pub channel_id: Option<String>,
     pub location: Option<String>,
     pub image_url: Option<String>,
+    /// Empty string removes the recurrence.
+    pub recurrence_rule: Option<String>,
+    /// Zero removes the reminder.
+    pub reminder_minutes: Option<i32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CalendarFeedQuery {
+    pub token: String,
+}
+
+/// Validate an RRULE against the event start and return its normalized form.
+fn normalize_recurrence(rule: &str, scheduled_start: &str) -> Result<String, ApiError> {
+    let rule = RecurrenceRule::parse(rule)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid recurrence_rule: {}", e)))?;
+    if chrono::DateTime::parse_from_rfc3339(scheduled_start).is_err() {
+        return Err(ApiError::BadRequest(
+            "Recurring events need an RFC 3339 scheduled_start".into(),
+        ));
+    }
+    Ok(rule.to_string())
+}
+
+fn validate_reminder_minutes(minutes: i32, allow_zero: bool) -> Result<(), ApiError> {
+    let min = if allow_zero { 0 } else { 1 };
+    if !(min..=MAX_REMINDER_MINUTES).contains(&minutes) {
+        return Err(ApiError::BadRequest(format!(
+            "reminder_minutes must be {}-{}",
+            min, MAX_REMINDER_MINUTES
+        )));
+    }
+    Ok(())
+}
+
+/// Voice events must point at a voice channel in the same guild.
+async fn validate_event_channel(
+    state: &AppState,
+    guild_id: i64,
+    entity_type: i32,
+    channel_id: Option<i64>,
+) -> Result<(), ApiError> {
+    let Some(channel_id) = channel_id else {
+        return Ok(());
+    };
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|channel| channel.guild_id() == Some(guild_id))
+        .ok_or_else(|| ApiError::BadRequest("Unknown channel_id".into()))?;
+    if entity_type == EVENT_ENTITY_VOICE && channel.channel_type != 2 {
+        return Err(ApiError::BadRequest(
+            "Voice events must be linked to a voice channel".into(),
+        ));
+    }
+    Ok(())
 }
 
 async fn ensure_manage_events(
@@ -86,7 +167,7 @@ async fn ensure_manage_events(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -97,6 +178,15 @@ async fn ensure_manage_events(
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/events",
+    tag = "events",
+    params(("guild_id" = i64, Path)),
+    request_body = CreateEventRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_event(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -133,6 +223,15 @@ pub async fn create_event(
     if body.entity_type != 1 && body.entity_type != 2 {
         return Err(ApiError::BadRequest("Invalid entity type".into()));
     }
+    let recurrence_rule = match body.recurrence_rule.as_deref() {
+        Some(rule) if !rule.trim().is_empty() => {
+            Some(normalize_recurrence(rule, &body.scheduled_start)?)
+        }
+        _ => None,
+    };
+    if let Some(minutes) = body.reminder_minutes {
+        validate_reminder_minutes(minutes, false)?;
+    }
 
     let channel_id = match body.channel_id.as_deref() {
         Some(raw) => Some(
@@ -141,8 +240,9 @@ pub async fn create_event(
         ),
         None => None,
     };
+    validate_event_channel(&state, guild_id, body.entity_type, channel_id).await?;
 
-    let event_id = paracord_util::snowflake::generate(1);
+    let event_id = paracord_util::snowflake::try_next_id().await?;
     let event = paracord_db::scheduled_events::create_event(
         &state.db,
         event_id,
@@ -156,6 +256,8 @@ pub async fn create_event(
         channel_id,
         body.location.as_deref(),
         body.image_url.as_deref(),
+        recurrence_rule.as_deref(),
+        body.reminder_minutes,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -170,6 +272,15 @@ pub async fn create_event(
     Ok((StatusCode::CREATED, Json(event_json)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/events",
+    tag = "events",
+    operation_id = "events_list_events",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_events(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -195,6 +306,15 @@ pub async fn list_events(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/events/{event_id}",
+    tag = "events",
+    operation_id = "events_get_event",
+    params(("guild_id" = i64, Path), ("event_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_event(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -220,6 +340,15 @@ pub async fn get_event(
     Ok(Json(event_to_json(&event, count, user_rsvp)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/events/{event_id}",
+    tag = "events",
+    params(("guild_id" = i64, Path), ("event_id" = i64, Path)),
+    request_body = UpdateEventRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_event(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -258,6 +387,27 @@ pub async fn update_event(
             return Err(ApiError::BadRequest("Invalid status".into()));
         }
     }
+    let scheduled_start = body
+        .scheduled_start
+        .as_deref()
+        .unwrap_or(&existing.scheduled_start);
+    let recurrence_rule = match body.recurrence_rule.as_deref() {
+        Some(rule) if rule.trim().is_empty() => Some(String::new()),
+        Some(rule) => Some(normalize_recurrence(rule, scheduled_start)?),
+        None => {
+            // Moving a recurring event still needs a start the rule can expand from.
+            if let (Some(rule), Some(_)) = (
+                existing.recurrence_rule.as_deref(),
+                body.scheduled_start.as_deref(),
+            ) {
+                normalize_recurrence(rule, scheduled_start)?;
+            }
+            None
+        }
+    };
+    if let Some(minutes) = body.reminder_minutes {
+        validate_reminder_minutes(minutes, true)?;
+    }
 
     let channel_id = match body.channel_id.as_deref() {
         Some(raw) => Some(
@@ -266,6 +416,7 @@ pub async fn update_event(
         ),
         None => None,
     };
+    validate_event_channel(&state, guild_id, existing.entity_type, channel_id).await?;
 
     let updated = paracord_db::scheduled_events::update_event(
         &state.db,
@@ -278,6 +429,8 @@ pub async fn update_event(
         channel_id,
         body.location.as_deref(),
         body.image_url.as_deref(),
+        recurrence_rule.as_deref(),
+        body.reminder_minutes,
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -299,6 +452,14 @@ pub async fn update_event(
     Ok(Json(event_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/events/{event_id}",
+    tag = "events",
+    params(("guild_id" = i64, Path), ("event_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_event(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -327,6 +488,14 @@ pub async fn delete_event(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/events/{event_id}/rsvp",
+    tag = "events",
+    params(("guild_id" = i64, Path), ("event_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn add_rsvp(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -359,6 +528,14 @@ pub async fn add_rsvp(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/events/{event_id}/rsvp",
+    tag = "events",
+    params(("guild_id" = i64, Path), ("event_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn remove_rsvp(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -390,3 +567,307 @@ pub async fn remove_rsvp(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+fn calendar_host(state: &AppState) -> String {
+    state
+        .config
+        .public_url
+        .as_deref()
+        .and_then(|url| url.split("://").nth(1))
+        .and_then(|rest| rest.split('/').next())
+        .filter(|host| !host.is_empty())
+        .unwrap_or("paracord")
+        .to_string()
+}
+
+/// Issue a signed ICS feed URL the caller can subscribe to from a calendar app.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/events/calendar-token",
+    tag = "events",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_calendar_token(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+
+    let token = paracord_core::calendar::create_calendar_token(
+        auth.user_id,
+        guild_id,
+        &state.config.jwt_secret,
+    )
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let path = format!("/api/v1/guilds/{}/events.ics?token={}", guild_id, token);
+    let url = match state.config.public_url.as_deref() {
+        Some(base) => format!("{}{}", base.trim_end_matches('/'), path),
+        None => path,
+    };
+
+    Ok(Json(json!({ "token": token, "url": url })))
+}
+
+/// iCalendar feed of a guild's events. Authenticated by the feed token in the
+/// query string since calendar apps can't send headers.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/events.ics",
+    tag = "events",
+    params(("guild_id" = i64, Path), CalendarFeedQuery),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn calendar_feed(
+    State(state): State<AppState>,
+    Path(guild_id): Path<i64>,
+    Query(query): Query<CalendarFeedQuery>,
+) -> Result<Response, ApiError> {
+    let (user_id, token_guild_id) =
+        paracord_core::calendar::validate_calendar_token(&query.token, &state.config.jwt_secret)
+            .map_err(|_| ApiError::Unauthorized)?;
+    if token_guild_id != guild_id {
+        return Err(ApiError::Unauthorized);
+    }
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let events = paracord_db::scheduled_events::get_guild_events(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let body =
+        paracord_core::calendar::render_calendar(&guild.name, &calendar_host(&state), &events);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Send `GUILD_SCHEDULED_EVENT_REMINDER` to RSVP'd members for every event
+/// occurrence that has entered its reminder window. Each occurrence is only
+/// reminded once, even across restarts.
+pub async fn run_event_reminders_once(state: &AppState) {
+    let events = match paracord_db::scheduled_events::get_events_with_reminders(&state.db).await {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::warn!("Failed to load event reminders: {}", err);
+            return;
+        }
+    };
+
+    let now = Utc::now();
+    for event in events {
+        let Some(minutes) = event.reminder_minutes else {
+            continue;
+        };
+        let horizon = now + chrono::Duration::minutes(i64::from(minutes));
+        for occurrence in paracord_core::calendar::event_occurrences_between(&event, now, horizon) {
+            let occurrence_start = occurrence.to_rfc3339_opts(SecondsFormat::Secs, true);
+            match paracord_db::scheduled_events::mark_reminder_sent(
+                &state.db,
+                event.id,
+                &occurrence_start,
+            )
+            .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(err) => {
+                    tracing::warn!("Failed to record reminder for event {}: {}", event.id, err);
+                    continue;
+                }
+            }
+
+            let user_ids: Vec<i64> =
+                paracord_db::scheduled_events::get_event_rsvps(&state.db, event.id)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|rsvp| rsvp.user_id)
+                    .collect();
+            if user_ids.is_empty() {
+                continue;
+            }
+            let event_json = event_to_json(&event, user_ids.len() as i64, true);
+            state.event_bus.dispatch_to_users(
+                "GUILD_SCHEDULED_EVENT_REMINDER",
+                json!({
+                    "guild_scheduled_event_id": event.id.to_string(),
+                    "guild_id": event.guild_id.to_string(),
+                    "occurrence_start": occurrence_start,
+                    "starts_in_minutes": (occurrence - now).num_minutes(),
+                    "event": event_json,
+                }),
+                user_ids,
+            );
+        }
+    }
+
+    let cutoff = (now - chrono::Duration::days(7)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let _ = paracord_db::scheduled_events::prune_sent_reminders(&state.db, &cutoff).await;
+}
+
+/// Start any event in `channel_id` hosted by `user_id` whose occurrence is
+/// close, then prompt its other RSVP'd members to join the channel. Spawned
+/// from voice joins.
+pub(crate) async fn start_events_for_voice_join(
+    state: AppState,
+    guild_id: i64,
+    channel_id: i64,
+    user_id: i64,
+) {
+    let events = match paracord_db::scheduled_events::get_scheduled_voice_events(
+        &state.db, channel_id,
+    )
+    .await
+    {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::warn!("Failed to load events for channel {}: {}", channel_id, err);
+            return;
+        }
+    };
+
+    let now = Utc::now();
+    for event in events {
+        if event.creator_id != user_id || event.guild_id != guild_id {
+            continue;
+        }
+        let due = paracord_core::calendar::event_occurrences_between(
+            &event,
+            now - chrono::Duration::hours(EVENT_AUTO_START_LATE_HOURS),
+            now + chrono::Duration::minutes(EVENT_AUTO_START_EARLY_MINUTES),
+        );
+        if due.is_empty() {
+            continue;
+        }
+        let Ok(Some(started)) = paracord_db::scheduled_events::transition_event_status(
+            &state.db,
+            event.id,
+            EVENT_STATUS_SCHEDULED,
+            EVENT_STATUS_ACTIVE,
+        )
+        .await
+        else {
+            continue;
+        };
+
+        let count = paracord_db::scheduled_events::get_rsvp_count(&state.db, event.id)
+            .await
+            .unwrap_or(0);
+        state.event_bus.dispatch(
+            "GUILD_SCHEDULED_EVENT_UPDATE",
+            event_to_json(&started, count, false),
+            Some(guild_id),
+        );
+
+        let in_channel: HashSet<i64> =
+            paracord_db::voice_states::get_channel_voice_states(&state.db, channel_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|voice_state| voice_state.user_id)
+                .collect();
+        let user_ids: Vec<i64> =
+            paracord_db::scheduled_events::get_event_rsvps(&state.db, event.id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|rsvp| rsvp.user_id)
+                .filter(|id| !in_channel.contains(id))
+                .collect();
+        if !user_ids.is_empty() {
+            state.event_bus.dispatch_to_users(
+                "GUILD_SCHEDULED_EVENT_JOIN_PROMPT",
+                json!({
+                    "guild_scheduled_event_id": event.id.to_string(),
+                    "guild_id": guild_id.to_string(),
+                    "channel_id": channel_id.to_string(),
+                    "event": event_to_json(&started, count, true),
+                }),
+                user_ids,
+            );
+        }
+        tracing::info!(
+            "Scheduled event {} started by host joining channel {}",
+            event.id,
+            channel_id
+        );
+    }
+}
+
+/// End active voice events whose channel has been empty for the grace period.
+/// Recurring events go back to scheduled for their next occurrence. The
+/// caller keeps `empty_since` between runs.
+pub async fn end_idle_voice_events_once(state: &AppState, empty_since: &mut HashMap<i64, Instant>) {
+    let events = match paracord_db::scheduled_events::get_active_voice_events(&state.db).await {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::warn!("Failed to load active voice events: {}", err);
+            return;
+        }
+    };
+    let active: HashSet<i64> = events.iter().map(|event| event.id).collect();
+    empty_since.retain(|id, _| active.contains(id));
+
+    for event in events {
+        let Some(channel_id) = event.channel_id else {
+            continue;
+        };
+        let occupied = match paracord_db::voice_states::get_channel_voice_states(
+            &state.db, channel_id,
+        )
+        .await
+        {
+            Ok(voice_states) => !voice_states.is_empty(),
+            Err(_) => continue,
+        };
+        if occupied {
+            empty_since.remove(&event.id);
+            continue;
+        }
+        let since = *empty_since.entry(event.id).or_insert_with(Instant::now);
+        if since.elapsed() < EVENT_VOICE_EMPTY_GRACE {
+            continue;
+        }
+        empty_since.remove(&event.id);
+
+        let next_status = if event.recurrence_rule.is_some() {
+            EVENT_STATUS_SCHEDULED
+        } else {
+            EVENT_STATUS_COMPLETED
+        };
+        if let Ok(Some(ended)) = paracord_db::scheduled_events::transition_event_status(
+            &state.db,
+            event.id,
+            EVENT_STATUS_ACTIVE,
+            next_status,
+        )
+        .await
+        {
+            let count = paracord_db::scheduled_events::get_rsvp_count(&state.db, event.id)
+                .await
+                .unwrap_or(0);
+            state.event_bus.dispatch(
+                "GUILD_SCHEDULED_EVENT_UPDATE",
+                event_to_json(&ended, count, false),
+                Some(ended.guild_id),
+            );
+            tracing::info!(
+                "Scheduled event {} ended after channel {} emptied",
+                event.id,
+                channel_id
+            );
+        }
+    }
+}
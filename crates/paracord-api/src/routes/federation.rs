@@ -7,12 +7,14 @@ use axum::{
 use ed25519_dalek::SigningKey;
 use paracord_core::AppState;
 use paracord_federation::{
-    client::FederationClient, protocol::FederatedIdentity, FederationConfig,
-    FederationEventEnvelope, FederationServerKey, FederationService,
+    bandwidth, client::FederationClient, protocol::FederatedIdentity, transport::TransportHeaders,
+    FederationConfig, FederationEventEnvelope, FederationServerKey, FederationService,
 };
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AdminUser;
@@ -61,7 +63,7 @@ pub fn build_federation_service() -> FederationService {
 }
 
 /// Get the FederationService from AppState, falling back to env-var construction.
-fn federation_service_from_state(state: &AppState) -> FederationService {
+pub(crate) fn federation_service_from_state(state: &AppState) -> FederationService {
     state
         .federation_service
         .clone()
@@ -100,6 +102,15 @@ fn validate_federation_content(content: &Value) -> Result<(), ApiError> {
     Ok(())
 }
 
+/// Reject malformed peer envelopes before their signature is checked or
+/// anything is stored.
+fn validate_inbound_envelope(envelope: &FederationEventEnvelope) -> Result<(), ApiError> {
+    envelope
+        .validate()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    validate_federation_content(&envelope.content)
+}
+
 fn validate_json_structure(value: &Value) -> Result<usize, &'static str> {
     match value {
         Value::Array(arr) => {
@@ -351,47 +362,15 @@ fn ensure_federation_guild_allowed(guild_id: i64) -> Result<(), ApiError> {
     Err(ApiError::Forbidden)
 }
 
-#[derive(Debug)]
-struct FederationTransportHeaders {
-    origin: String,
-    key_id: String,
-    timestamp_ms: i64,
-    signature_hex: String,
-}
-
-fn parse_transport_headers(headers: &HeaderMap) -> Result<FederationTransportHeaders, ApiError> {
-    let origin = headers
-        .get("x-paracord-origin")
-        .and_then(|v| v.to_str().ok())
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .ok_or(ApiError::Unauthorized)?
-        .to_string();
-    let key_id = headers
-        .get("x-paracord-key-id")
-        .and_then(|v| v.to_str().ok())
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .ok_or(ApiError::Unauthorized)?
-        .to_string();
-    let timestamp_ms = headers
-        .get("x-paracord-timestamp")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.trim().parse::<i64>().ok())
-        .ok_or(ApiError::Unauthorized)?;
-    let signature_hex = headers
-        .get("x-paracord-signature")
-        .and_then(|v| v.to_str().ok())
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .ok_or(ApiError::Unauthorized)?
-        .to_string();
-    Ok(FederationTransportHeaders {
-        origin,
-        key_id,
-        timestamp_ms,
-        signature_hex,
-    })
+fn parse_transport_headers(headers: &HeaderMap) -> Result<TransportHeaders, ApiError> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    paracord_federation::transport::parse_transport_headers(
+        header("x-paracord-origin"),
+        header("x-paracord-key-id"),
+        header("x-paracord-timestamp"),
+        header("x-paracord-signature"),
+    )
+    .map_err(|_| ApiError::Unauthorized)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -401,10 +380,10 @@ async fn verify_transport_request(
     headers: &HeaderMap,
     method: &str,
     path: &str,
-    body_bytes: &[u8],
+    body_encodings: &[Vec<u8>],
     expected_origin: Option<&str>,
     enforce_replay_protection: bool,
-) -> Result<FederationTransportHeaders, ApiError> {
+) -> Result<TransportHeaders, ApiError> {
     let transport = parse_transport_headers(headers)?;
     if let Some(expected) = expected_origin {
         if transport.origin != expected {
@@ -413,8 +392,11 @@ async fn verify_transport_request(
     }
 
     let now_ms = chrono::Utc::now().timestamp_millis();
-    if (now_ms - transport.timestamp_ms).abs() > paracord_federation::transport::DEFAULT_MAX_SKEW_MS
-    {
+    if !paracord_federation::transport::timestamp_within_skew(
+        now_ms,
+        transport.timestamp_ms,
+        paracord_federation::transport::DEFAULT_MAX_SKEW_MS,
+    ) {
         return Err(ApiError::Unauthorized);
     }
 
@@ -435,19 +417,24 @@ async fn verify_transport_request(
         .find(|k| k.key_id == transport.key_id && k.valid_until >= now_ms)
         .ok_or(ApiError::Forbidden)?;
 
-    let canonical = paracord_federation::transport::canonical_transport_bytes_with_body(
-        method,
-        path,
-        transport.timestamp_ms,
-        body_bytes,
-    );
-    service
-        .verify_payload(
-            &canonical,
-            &transport.signature_hex,
-            &trusted_key.public_key,
-        )
-        .map_err(|_| ApiError::Forbidden)?;
+    let signed = body_encodings.iter().any(|body_bytes| {
+        let canonical = paracord_federation::transport::canonical_transport_bytes_with_body(
+            method,
+            path,
+            transport.timestamp_ms,
+            body_bytes,
+        );
+        service
+            .verify_payload(
+                &canonical,
+                &transport.signature_hex,
+                &trusted_key.public_key,
+            )
+            .is_ok()
+    });
+    if !signed {
+        return Err(ApiError::Forbidden);
+    }
 
     if enforce_replay_protection {
         let replay_material = format!(
@@ -480,7 +467,34 @@ async fn verify_transport_request(
     Ok(transport)
 }
 
-fn sanitize_remote_username(localpart: &str, fallback: &str) -> String {
+/// Reject `origin` once it has used up its bandwidth quota for the current
+/// window. Accounting errors let the request through.
+async fn ensure_peer_bandwidth_available(state: &AppState, origin: &str) -> Result<(), ApiError> {
+    let Some(quota) = state
+        .config
+        .federation_max_bytes_per_peer_per_hour
+        .filter(|quota| *quota > 0)
+    else {
+        return Ok(());
+    };
+    let used = match bandwidth::usage(&state.db, origin).await {
+        Ok(used) => used,
+        Err(e) => {
+            tracing::warn!("federation: bandwidth lookup for {} failed: {}", origin, e);
+            return Ok(());
+        }
+    };
+    if bandwidth::quota_exceeded(used, Some(quota)) {
+        return Err(ApiError::Code(ErrorCode::FederationQuotaExceeded));
+    }
+    Ok(())
+}
+
+fn json_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+pub(crate) fn sanitize_remote_username(localpart: &str, fallback: &str) -> String {
     let mut out: String = localpart
         .chars()
         .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '_' || *ch == '-')
@@ -533,7 +547,7 @@ async fn ensure_remote_user_mapping(
         &digest[..6]
     );
     let email = format!("fed+{}@remote.invalid", &digest[..24]);
-    let user_id = paracord_util::snowflake::generate(1);
+    let user_id = paracord_util::snowflake::try_next_id().await?;
 
     let created =
         paracord_db::users::create_user(&state.db, user_id, &username, 0, &email, "!federated!")
@@ -558,21 +572,6 @@ async fn ensure_remote_user_mapping(
     Ok(user_id)
 }
 
-fn canonical_event_payload_bytes(envelope: &FederationEventEnvelope) -> Vec<u8> {
-    serde_json::to_vec(&json!({
-        "event_id": envelope.event_id,
-        "room_id": envelope.room_id,
-        "event_type": envelope.event_type,
-        "sender": envelope.sender,
-        "origin_server": envelope.origin_server,
-        "origin_ts": envelope.origin_ts,
-        "content": envelope.content,
-        "depth": envelope.depth,
-        "state_key": envelope.state_key,
-    }))
-    .unwrap_or_default()
-}
-
 fn extract_signature_for_origin(
     signatures: &Value,
     origin_server: &str,
@@ -626,7 +625,7 @@ async fn verify_envelope_origin_signature(
         .find(|k| k.key_id == payload_key_id && k.valid_until >= now_ms)
         .ok_or(ApiError::Forbidden)?;
 
-    let payload_bytes = canonical_event_payload_bytes(payload);
+    let payload_bytes = paracord_federation::canonical_envelope_bytes(payload);
     service
         .verify_payload(&payload_bytes, &signature_hex, &trusted_key.public_key)
         .map_err(|_| ApiError::Forbidden)?;
@@ -681,6 +680,9 @@ async fn ingest_verified_payload(
             "m.member.leave" => {
                 dispatch_federated_member_leave(state, &payload).await;
             }
+            paracord_core::receipts::FEDERATION_RECEIPT_EVENT => {
+                dispatch_federated_receipt(state, &payload).await;
+            }
             _ => {
                 state.event_bus.dispatch(
                     &format!("FEDERATION_{}", payload.event_type.to_uppercase()),
@@ -695,6 +697,11 @@ async fn ingest_verified_payload(
             }
         }
 
+        // Receipts are addressed to a single peer and are not relayed.
+        if payload.event_type == paracord_core::receipts::FEDERATION_RECEIPT_EVENT {
+            return Ok(inserted);
+        }
+
         // Relay newly accepted events to other trusted peers so non-full-mesh
         // topologies can still converge. Skip the immediate sender hop.
         let relay_state = state.clone();
@@ -717,6 +724,12 @@ async fn ingest_verified_payload(
 
 // ── Discovery & Key Exchange ────────────────────────────────────────────────
 
+#[utoipa::path(
+    get,
+    path = "/.well-known/paracord/server",
+    tag = "federation",
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn well_known() -> Result<Json<Value>, ApiError> {
     let service = federation_service();
     Ok(Json(json!({
@@ -728,6 +741,13 @@ pub async fn well_known() -> Result<Json<Value>, ApiError> {
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/keys",
+    tag = "federation",
+    operation_id = "federation_get_keys",
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn get_keys(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
     let service = federation_service_from_state(&state);
     if !service.is_enabled() {
@@ -763,6 +783,13 @@ pub async fn get_keys(State(state): State<AppState>) -> Result<Json<Value>, ApiE
 
 // ── Event Ingestion ─────────────────────────────────────────────────────────
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/event",
+    tag = "federation",
+    request_body = Object,
+    responses((status = 202, description = "Accepted", body = Value)),
+)]
 pub async fn ingest_event(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -773,7 +800,7 @@ pub async fn ingest_event(
         return Err(ApiError::Forbidden);
     }
 
-    let transport_body = serde_json::to_vec(&payload).unwrap_or_default();
+    let transport_body = paracord_federation::transport::json_body_encodings(&payload);
     let transport = verify_transport_request(
         &state,
         &service,
@@ -785,6 +812,15 @@ pub async fn ingest_event(
         true,
     )
     .await?;
+    ensure_peer_bandwidth_available(&state, &transport.origin).await?;
+    bandwidth::record(
+        &state.db,
+        &transport.origin,
+        bandwidth::CATEGORY_EVENTS,
+        transport_body.first().map_or(0, Vec::len),
+        0,
+    )
+    .await;
 
     // Per-peer rate limiting on event ingestion
     if let Some(limit) = state.config.federation_max_events_per_peer_per_minute {
@@ -806,8 +842,8 @@ pub async fn ingest_event(
         }
     }
 
-    // Validate content size and depth
-    validate_federation_content(&payload.content)?;
+    // Validate envelope fields and content size and depth
+    validate_inbound_envelope(&payload)?;
 
     verify_envelope_origin_signature(&state, &service, &payload).await?;
     let inserted =
@@ -866,7 +902,7 @@ async fn dispatch_federated_message(state: &AppState, payload: &FederationEventE
 
     // Resolve (or materialize) the target channel locally so federated events
     // don't require pre-cloned guild/channel IDs.
-    let channel = if let Some(mapped_channel_id) =
+    let mut channel = if let Some(mapped_channel_id) =
         resolve_local_channel_id(state, &mapping_namespace, remote_ch_id).await
     {
         match paracord_db::channels::get_channel(&state.db, mapped_channel_id).await {
@@ -972,10 +1008,21 @@ async fn dispatch_federated_message(state: &AppState, payload: &FederationEventE
         };
         materialized
     };
+    apply_federated_channel_nsfw(state, payload, &mut channel).await;
     let local_channel_id = channel.id;
 
     // Generate a local message ID for storage
-    let local_msg_id = paracord_util::snowflake::generate(1);
+    let local_msg_id = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!(
+                "federation: could not store m.message event {}: {}",
+                payload.event_id,
+                e
+            );
+            return;
+        }
+    };
 
     let author_id = match FederatedIdentity::parse(&payload.sender) {
         Some(identity) => match ensure_remote_user_mapping(state, &identity).await {
@@ -1067,6 +1114,17 @@ async fn dispatch_federated_message(state: &AppState, payload: &FederationEventE
                 .event_bus
                 .dispatch("MESSAGE_CREATE", msg_json, channel.guild_id());
 
+            let attachment_ids = crate::routes::files::prefetch_attachment_ids(&payload.content);
+            if let Some(guild_id) = channel.guild_id().filter(|_| !attachment_ids.is_empty()) {
+                tokio::spawn(crate::routes::files::prefetch_federated_attachments(
+                    state.clone(),
+                    payload.origin_server.clone(),
+                    payload.room_id.clone(),
+                    guild_id,
+                    attachment_ids,
+                ));
+            }
+
             let remote_mid = payload
                 .content
                 .get("message_id")
@@ -1119,6 +1177,42 @@ fn content_str<'a>(content: &'a Value, key: &str) -> Option<&'a str> {
         .filter(|v| !v.is_empty())
 }
 
+fn content_bool(content: &Value, key: &str) -> Option<bool> {
+    content.get(key).and_then(Value::as_bool)
+}
+
+/// Mirror the origin's NSFW flag onto the local copy of a federated channel.
+/// Peers that don't send one leave the local flag alone.
+async fn apply_federated_channel_nsfw(
+    state: &AppState,
+    payload: &FederationEventEnvelope,
+    channel: &mut paracord_db::channels::ChannelRow,
+) {
+    let Some(nsfw) = content_bool(&payload.content, "channel_nsfw")
+        .or_else(|| content_bool(&payload.content, "nsfw"))
+    else {
+        return;
+    };
+    if channel.nsfw == nsfw {
+        return;
+    }
+    if let Err(err) = paracord_db::channels::set_channel_nsfw(&state.db, channel.id, nsfw).await {
+        tracing::warn!(
+            "federation: failed to apply nsfw flag from event {} to channel {}: {}",
+            payload.event_id,
+            channel.id,
+            err
+        );
+        return;
+    }
+    channel.nsfw = nsfw;
+    state.event_bus.dispatch(
+        "CHANNEL_UPDATE",
+        crate::routes::channels::channel_to_json(channel),
+        channel.guild_id(),
+    );
+}
+
 fn content_i64(content: &Value, key: &str) -> Option<i64> {
     content.get(key).and_then(|v| match v {
         Value::Number(num) => num.as_i64(),
@@ -1216,7 +1310,7 @@ async fn ensure_federated_space_exists(
     {
         mapped
     } else {
-        paracord_util::snowflake::generate(1)
+        paracord_util::snowflake::try_next_id().await.ok()?
     };
 
     if matches!(
@@ -1305,7 +1399,7 @@ async fn ensure_federated_channel_exists(
     {
         mapped
     } else {
-        paracord_util::snowflake::generate(1)
+        paracord_util::snowflake::try_next_id().await.ok()?
     };
 
     if let Ok(Some(existing)) =
@@ -1526,6 +1620,45 @@ async fn dispatch_federated_message_delete(state: &AppState, payload: &Federatio
     );
 }
 
+/// Apply a peer's receipt for a message that originated on this server.
+async fn dispatch_federated_receipt(state: &AppState, payload: &FederationEventEnvelope) {
+    let Some(kind) = content_str(&payload.content, "receipt_type")
+        .and_then(paracord_core::receipts::ReceiptKind::parse)
+    else {
+        return;
+    };
+    let Some(message_id) =
+        content_str(&payload.content, "message_id").and_then(|raw| raw.parse::<i64>().ok())
+    else {
+        return;
+    };
+    let Some(identity) = FederatedIdentity::parse(&payload.sender) else {
+        return;
+    };
+    if ensure_identity_matches_origin_or_alias(state, &identity, &payload.origin_server)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    // Only users we already know about can have received one of our DMs.
+    let Ok(Some(mapping)) =
+        paracord_db::federation::get_remote_user_mapping(&state.db, &identity.to_canonical()).await
+    else {
+        return;
+    };
+    if let Err(err) = paracord_core::receipts::apply_federated_receipt(
+        state,
+        mapping.local_user_id,
+        message_id,
+        kind,
+    )
+    .await
+    {
+        tracing::debug!("Failed to apply federated receipt: {}", err);
+    }
+}
+
 async fn dispatch_federated_reaction_add(state: &AppState, payload: &FederationEventEnvelope) {
     let Some(local_message_id) = resolve_local_message_id_from_payload(state, payload).await else {
         return;
@@ -1652,7 +1785,7 @@ async fn dispatch_federated_member_join(state: &AppState, payload: &FederationEv
         guild_id,
     )
     .await;
-    state.member_index.add_member(guild_id, local_user_id);
+    paracord_core::member_list::add_member(state, guild_id, local_user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_ADD",
         json!({
@@ -1717,9 +1850,7 @@ async fn dispatch_federated_member_leave(state: &AppState, payload: &FederationE
         &identity.to_canonical(),
     )
     .await;
-    state
-        .member_index
-        .remove_member(guild_id, mapping.local_user_id);
+    paracord_core::member_list::remove_member(state, guild_id, mapping.local_user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_REMOVE",
         json!({
@@ -1761,6 +1892,14 @@ async fn ensure_federated_system_user(state: &AppState) -> bool {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/event/{event_id}",
+    tag = "federation",
+    operation_id = "federation_get_event",
+    params(("event_id" = String, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn get_event(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1771,24 +1910,44 @@ pub async fn get_event(
     if !service.is_enabled() {
         return Err(ApiError::Forbidden);
     }
-    authorize_federation_read_request(&state, &service, &headers, uri.path()).await?;
+    let origin = authorize_federation_read_request(&state, &service, &headers, uri.path()).await?;
+    if let Some(origin) = origin.as_deref() {
+        ensure_peer_bandwidth_available(&state, origin).await?;
+    }
     let event = service
         .fetch_event(&state.db, &event_id)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    match event {
-        Some(envelope) => Ok(Json(json!(envelope))),
-        None => Err(ApiError::NotFound),
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let response = json!(event);
+    if let Some(origin) = origin.as_deref() {
+        bandwidth::record(
+            &state.db,
+            origin,
+            bandwidth::CATEGORY_EVENTS,
+            0,
+            json_len(&response),
+        )
+        .await;
     }
+    Ok(Json(response))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct ListEventsQuery {
     pub room_id: String,
     pub since_depth: Option<i64>,
     pub limit: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/events",
+    tag = "federation",
+    operation_id = "federation_list_events",
+    params(ListEventsQuery),
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn list_events(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1803,7 +1962,11 @@ pub async fn list_events(
         .path_and_query()
         .map(|pq| pq.as_str().to_string())
         .unwrap_or_else(|| uri.path().to_string());
-    authorize_federation_read_request(&state, &service, &headers, &path_and_query).await?;
+    let origin =
+        authorize_federation_read_request(&state, &service, &headers, &path_and_query).await?;
+    if let Some(origin) = origin.as_deref() {
+        ensure_peer_bandwidth_available(&state, origin).await?;
+    }
 
     let since_depth = query.since_depth.unwrap_or(0).max(0);
     let limit = query.limit.unwrap_or(100).clamp(1, 1000);
@@ -1811,7 +1974,91 @@ pub async fn list_events(
         .list_room_events(&state.db, &query.room_id, since_depth, limit)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    Ok(Json(json!({ "events": events })))
+    let response = json!({ "events": events });
+    if let Some(origin) = origin.as_deref() {
+        bandwidth::record(
+            &state.db,
+            origin,
+            bandwidth::CATEGORY_EVENTS,
+            0,
+            json_len(&response),
+        )
+        .await;
+    }
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UserSearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+/// Directory search for trusted peers. Only lists local users who opted in
+/// to the directory.
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/users/search",
+    tag = "federation",
+    operation_id = "federation_search_users",
+    params(UserSearchQuery),
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let service = federation_service_from_state(&state);
+    if !service.is_enabled() {
+        return Err(ApiError::Forbidden);
+    }
+    let path_and_query = uri
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| uri.path().to_string());
+    let origin =
+        authorize_federation_read_request(&state, &service, &headers, &path_and_query).await?;
+
+    let window_start = chrono::Utc::now().timestamp() / 60;
+    let bucket_key = format!(
+        "federation_user_search:{}",
+        origin.as_deref().unwrap_or("shared-token")
+    );
+    let searches = paracord_db::rate_limits::increment_window_counter(
+        &state.db,
+        &bucket_key,
+        window_start,
+        60,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if searches > crate::routes::users::PEER_DIRECTORY_SEARCHES_PER_MINUTE {
+        return Err(ApiError::RateLimited);
+    }
+
+    let q = crate::routes::users::parse_directory_query(&query.q)?;
+    let limit = crate::routes::users::directory_limit(query.limit);
+    let users = paracord_db::users::search_directory_users(
+        &state.db,
+        &q,
+        paracord_core::USER_FLAG_BOT,
+        limit,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let users: Vec<Value> = users
+        .iter()
+        .map(|user| {
+            json!({
+                "user_id": format!("@{}:{}", user.username, service.domain()),
+                "username": user.username,
+                "display_name": user.display_name,
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "users": users })))
 }
 
 pub async fn run_federation_catchup_once(
@@ -1932,9 +2179,10 @@ pub async fn run_federation_catchup_once(
 
                 let mut newest_depth = since_depth;
                 for event in events {
-                    if verify_envelope_origin_signature(state, &service, &event)
-                        .await
-                        .is_err()
+                    if validate_inbound_envelope(&event).is_err()
+                        || verify_envelope_origin_signature(state, &service, &event)
+                            .await
+                            .is_err()
                     {
                         tracing::warn!(
                             "federation: catch-up rejected invalid event {} for peer {}",
@@ -1986,23 +2234,33 @@ async fn authorize_federation_read_request(
     service: &FederationService,
     headers: &HeaderMap,
     path: &str,
-) -> Result<(), ApiError> {
+) -> Result<Option<String>, ApiError> {
     if let Some(expected) = optional_federation_read_token() {
         let presented = headers
             .get("x-paracord-federation-token")
             .and_then(|v| v.to_str().ok())
             .map(str::trim);
         if presented == Some(expected.as_str()) {
-            return Ok(());
+            // Shared-token reads aren't tied to a peer, so they aren't metered.
+            return Ok(None);
         }
     }
 
-    verify_transport_request(state, service, headers, "GET", path, &[], None, false)
-        .await
-        .map(|_| ())
+    verify_transport_request(
+        state,
+        service,
+        headers,
+        "GET",
+        path,
+        &[Vec::new()],
+        None,
+        false,
+    )
+    .await
+    .map(|transport| Some(transport.origin))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationInviteRequest {
     pub origin_server: String,
     pub room_id: String,
@@ -2010,28 +2268,28 @@ pub struct FederationInviteRequest {
     pub max_age_seconds: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationJoinRequest {
     pub origin_server: String,
     pub room_id: String,
     pub user_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationLeaveRequest {
     pub origin_server: String,
     pub room_id: String,
     pub user_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationMediaTokenRequest {
     pub origin_server: String,
     pub channel_id: String,
     pub user_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationMediaRelayRequest {
     pub origin_server: String,
     pub channel_id: String,
@@ -2040,6 +2298,13 @@ pub struct FederationMediaRelayRequest {
     pub title: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/invite",
+    tag = "federation",
+    request_body = FederationInviteRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn invite(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2050,14 +2315,14 @@ pub async fn invite(
         return Err(ApiError::Forbidden);
     }
 
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/invite",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
@@ -2069,7 +2334,7 @@ pub async fn invite(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -2091,6 +2356,13 @@ pub async fn invite(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/join",
+    tag = "federation",
+    request_body = FederationJoinRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn join(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2100,14 +2372,14 @@ pub async fn join(
     if !service.is_enabled() {
         return Err(ApiError::Forbidden);
     }
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/join",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
@@ -2122,7 +2394,7 @@ pub async fn join(
     let _guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let canonical_room_id = canonical_local_room_id(&service, guild_id);
 
     let local_user_id = ensure_remote_user_mapping(&state, &identity).await?;
@@ -2140,7 +2412,7 @@ pub async fn join(
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    state.member_index.add_member(guild_id, local_user_id);
+    paracord_core::member_list::add_member(&state, guild_id, local_user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_ADD",
         json!({
@@ -2171,6 +2443,13 @@ pub async fn join(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/leave",
+    tag = "federation",
+    request_body = FederationLeaveRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn leave(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2180,14 +2459,14 @@ pub async fn leave(
     if !service.is_enabled() {
         return Err(ApiError::Forbidden);
     }
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/leave",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
@@ -2211,9 +2490,7 @@ pub async fn leave(
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
         removed = true;
-        state
-            .member_index
-            .remove_member(guild_id, mapping.local_user_id);
+        paracord_core::member_list::remove_member(&state, guild_id, mapping.local_user_id);
         state.event_bus.dispatch(
             "GUILD_MEMBER_REMOVE",
             json!({
@@ -2237,6 +2514,13 @@ pub async fn leave(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/media/token",
+    tag = "federation",
+    request_body = FederationMediaTokenRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn media_token(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2246,18 +2530,19 @@ pub async fn media_token(
     if !service.is_enabled() {
         return Err(ApiError::Forbidden);
     }
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/media/token",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
     .await?;
+    ensure_peer_bandwidth_available(&state, &body.origin_server).await?;
 
     let identity = FederatedIdentity::parse(&body.user_id)
         .ok_or(ApiError::BadRequest("Invalid user_id".to_string()))?;
@@ -2271,7 +2556,7 @@ pub async fn media_token(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".to_string()));
     }
@@ -2297,7 +2582,7 @@ pub async fn media_token(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -2311,7 +2596,7 @@ pub async fn media_token(
     let user = paracord_db::users::get_user_by_id(&state.db, local_user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     let session_id = uuid::Uuid::new_v4().to_string();
     let join_resp = state
@@ -2328,15 +2613,31 @@ pub async fn media_token(
         .await
         .map_err(ApiError::Internal)?;
 
-    Ok(Json(json!({
+    let response = json!({
         "token": join_resp.token,
         "url": state.config.livekit_public_url,
         "room_name": join_resp.room_name,
         "session_id": session_id,
         "local_user_id": local_user_id.to_string(),
-    })))
+    });
+    bandwidth::record(
+        &state.db,
+        &body.origin_server,
+        bandwidth::CATEGORY_MEDIA_RELAY,
+        body_encodings.first().map_or(0, Vec::len),
+        json_len(&response),
+    )
+    .await;
+    Ok(Json(response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/media/relay",
+    tag = "federation",
+    request_body = FederationMediaRelayRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn media_relay(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2346,18 +2647,19 @@ pub async fn media_relay(
     if !service.is_enabled() {
         return Err(ApiError::Forbidden);
     }
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/media/relay",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
     .await?;
+    ensure_peer_bandwidth_available(&state, &body.origin_server).await?;
 
     let identity = FederatedIdentity::parse(&body.user_id)
         .ok_or(ApiError::BadRequest("Invalid user_id".to_string()))?;
@@ -2370,7 +2672,7 @@ pub async fn media_relay(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".to_string()));
     }
@@ -2396,7 +2698,7 @@ pub async fn media_relay(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -2409,12 +2711,12 @@ pub async fn media_relay(
     paracord_core::permissions::require_permission(perms, Permissions::CONNECT)?;
     paracord_core::permissions::require_permission(perms, Permissions::STREAM)?;
 
-    match body.action.as_str() {
+    let response = match body.action.as_str() {
         "start_stream" => {
             let user = paracord_db::users::get_user_by_id(&state.db, local_user_id)
                 .await
                 .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                .ok_or(ApiError::NotFound)?;
+                .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
             let stream = state
                 .voice
                 .start_stream(
@@ -2426,30 +2728,41 @@ pub async fn media_relay(
                 )
                 .await
                 .map_err(ApiError::Internal)?;
-            Ok(Json(json!({
+            json!({
                 "ok": true,
                 "action": "start_stream",
                 "token": stream.token,
                 "room_name": stream.room_name,
                 "url": state.config.livekit_public_url,
-            })))
+            })
         }
         "stop_stream" => {
             state.voice.stop_stream(channel_id, local_user_id).await;
-            Ok(Json(json!({
+            json!({
                 "ok": true,
                 "action": "stop_stream",
-            })))
+            })
         }
-        _ => Err(ApiError::BadRequest(
-            "Unsupported media relay action".to_string(),
-        )),
-    }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Unsupported media relay action".to_string(),
+            ))
+        }
+    };
+    bandwidth::record(
+        &state.db,
+        &body.origin_server,
+        bandwidth::CATEGORY_MEDIA_RELAY,
+        body_encodings.first().map_or(0, Vec::len),
+        json_len(&response),
+    )
+    .await;
+    Ok(Json(response))
 }
 
 // ── Federated Server Management (admin-only) ────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddServerRequest {
     pub server_name: String,
     pub domain: String,
@@ -2462,6 +2775,13 @@ pub struct AddServerRequest {
     pub discover: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/servers",
+    tag = "federation",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn list_servers(
     _admin: AdminUser,
     State(state): State<AppState>,
@@ -2474,9 +2794,71 @@ pub async fn list_servers(
     let servers = paracord_db::federation::list_federated_servers(&state.db)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    Ok(Json(json!({ "servers": servers })))
+    let usage = current_peer_bandwidth(&state).await?;
+    let quota = state.config.federation_max_bytes_per_peer_per_hour;
+    let servers: Vec<Value> = servers
+        .into_iter()
+        .map(|server| {
+            let bandwidth = peer_bandwidth_json(&usage, &server.server_name, quota);
+            let mut value = json!(server);
+            value["bandwidth"] = bandwidth;
+            value
+        })
+        .collect();
+    Ok(Json(json!({
+        "servers": servers,
+        "bandwidth_window_minutes": bandwidth::WINDOW_MINUTES,
+        "bandwidth_quota_bytes": quota.filter(|q| *q > 0),
+    })))
 }
 
+async fn current_peer_bandwidth(
+    state: &AppState,
+) -> Result<Vec<paracord_db::federation::PeerBandwidthRow>, ApiError> {
+    let since = bandwidth::window_start(chrono::Utc::now().timestamp_millis());
+    paracord_db::federation::list_peer_bandwidth_since(&state.db, since)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))
+}
+
+/// Current-window byte counts for one peer, as shown on the admin dashboard.
+fn peer_bandwidth_json(
+    usage: &[paracord_db::federation::PeerBandwidthRow],
+    server_name: &str,
+    quota: Option<u64>,
+) -> Value {
+    let mut bytes_in = 0i64;
+    let mut bytes_out = 0i64;
+    let mut categories = serde_json::Map::new();
+    for row in usage
+        .iter()
+        .filter(|row| row.server_name.eq_ignore_ascii_case(server_name))
+    {
+        bytes_in = bytes_in.saturating_add(row.bytes_in);
+        bytes_out = bytes_out.saturating_add(row.bytes_out);
+        categories.insert(
+            row.category.clone(),
+            json!({ "bytes_in": row.bytes_in, "bytes_out": row.bytes_out }),
+        );
+    }
+    let total = bytes_in.saturating_add(bytes_out);
+    json!({
+        "bytes_in": bytes_in,
+        "bytes_out": bytes_out,
+        "total_bytes": total,
+        "categories": categories,
+        "quota_exceeded": bandwidth::quota_exceeded(total.max(0) as u64, quota),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/servers",
+    tag = "federation",
+    request_body = AddServerRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn add_server(
     _admin: AdminUser,
     State(state): State<AppState>,
@@ -2520,7 +2902,7 @@ pub async fn add_server(
         }
     }
 
-    let id = paracord_util::snowflake::generate(1);
+    let id = paracord_util::snowflake::try_next_id().await?;
     paracord_db::federation::upsert_federated_server(
         &state.db,
         id,
@@ -2545,6 +2927,14 @@ pub async fn add_server(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/servers/{server_name}",
+    tag = "federation",
+    params(("server_name" = String, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
 pub async fn get_server(
     _admin: AdminUser,
     State(state): State<AppState>,
@@ -2558,12 +2948,25 @@ pub async fn get_server(
     let server = paracord_db::federation::get_federated_server(&state.db, &server_name)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    match server {
-        Some(s) => Ok(Json(json!(s))),
-        None => Err(ApiError::NotFound),
-    }
+    let server = server.ok_or(ApiError::NotFound)?;
+    let usage = current_peer_bandwidth(&state).await?;
+    let mut value = json!(server);
+    value["bandwidth"] = peer_bandwidth_json(
+        &usage,
+        &server.server_name,
+        state.config.federation_max_bytes_per_peer_per_hour,
+    );
+    Ok(Json(value))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/_paracord/federation/v1/servers/{server_name}",
+    tag = "federation",
+    params(("server_name" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
 pub async fn delete_server(
     _admin: AdminUser,
     State(state): State<AppState>,
@@ -2615,7 +3018,7 @@ fn validate_federation_file_token(
     jwt_secret: &str,
     token: &str,
     expected_attachment_id: i64,
-) -> Result<(), ApiError> {
+) -> Result<String, ApiError> {
     let dot_pos = token.rfind('.').ok_or_else(|| ApiError::Unauthorized)?;
     let payload = &token[..dot_pos];
     let mac = &token[dot_pos + 1..];
@@ -2625,12 +3028,13 @@ fn validate_federation_file_token(
         return Err(ApiError::Unauthorized);
     }
 
-    let parts: Vec<&str> = payload.splitn(3, ':').collect();
-    if parts.len() != 3 {
-        return Err(ApiError::Unauthorized);
-    }
-    let attachment_id: i64 = parts[0].parse().map_err(|_| ApiError::Unauthorized)?;
-    let exp: i64 = parts[2].parse().map_err(|_| ApiError::Unauthorized)?;
+    // The requester server name may itself contain ':' (a port).
+    let (attachment_part, rest) = payload.split_once(':').ok_or(ApiError::Unauthorized)?;
+    let (requester_server, exp_part) = rest.rsplit_once(':').ok_or(ApiError::Unauthorized)?;
+    let attachment_id: i64 = attachment_part
+        .parse()
+        .map_err(|_| ApiError::Unauthorized)?;
+    let exp: i64 = exp_part.parse().map_err(|_| ApiError::Unauthorized)?;
 
     if attachment_id != expected_attachment_id {
         return Err(ApiError::Unauthorized);
@@ -2639,10 +3043,10 @@ fn validate_federation_file_token(
         return Err(ApiError::Unauthorized);
     }
 
-    Ok(())
+    Ok(requester_server.to_string())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FederationFileTokenRequest {
     pub origin_server: String,
     pub attachment_id: String,
@@ -2650,6 +3054,13 @@ pub struct FederationFileTokenRequest {
     pub user_id: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/_paracord/federation/v1/file/token",
+    tag = "federation",
+    request_body = FederationFileTokenRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn file_token(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -2660,18 +3071,19 @@ pub async fn file_token(
         return Err(ApiError::Forbidden);
     }
 
-    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+    let body_encodings = paracord_federation::transport::json_body_encodings(&body);
     let transport = verify_transport_request(
         &state,
         &service,
         &headers,
         "POST",
         "/_paracord/federation/v1/file/token",
-        &body_bytes,
+        &body_encodings,
         Some(body.origin_server.as_str()),
         true,
     )
     .await?;
+    ensure_peer_bandwidth_available(&state, &transport.origin).await?;
 
     let identity = FederatedIdentity::parse(&body.user_id)
         .ok_or(ApiError::BadRequest("Invalid user_id".to_string()))?;
@@ -2694,7 +3106,7 @@ pub async fn file_token(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
         "File token requires guild channel".to_string(),
     ))?;
@@ -2719,7 +3131,7 @@ pub async fn file_token(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -2745,17 +3157,26 @@ pub async fn file_token(
     })))
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, IntoParams)]
 pub struct FileDownloadQuery {
     pub token: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/_paracord/federation/v1/file/{attachment_id}",
+    tag = "federation",
+    params(("attachment_id" = i64, Path), FileDownloadQuery),
+    responses((status = 200, description = "OK")),
+)]
 pub async fn file_download(
     State(state): State<AppState>,
     Path(attachment_id): Path<i64>,
     Query(query): Query<FileDownloadQuery>,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    validate_federation_file_token(&state.config.jwt_secret, &query.token, attachment_id)?;
+    let requester_server =
+        validate_federation_file_token(&state.config.jwt_secret, &query.token, attachment_id)?;
+    ensure_peer_bandwidth_available(&state, &requester_server).await?;
 
     let attachment = paracord_db::attachments::get_attachment(&state.db, attachment_id)
         .await
@@ -2791,6 +3212,14 @@ pub async fn file_download(
         .filter(|ch| *ch != '"' && *ch != '\\' && *ch != '\r' && *ch != '\n')
         .collect();
     let disposition = format!("attachment; filename=\"{}\"", safe_filename);
+    bandwidth::record(
+        &state.db,
+        &requester_server,
+        bandwidth::CATEGORY_FILE,
+        0,
+        data.len(),
+    )
+    .await;
 
     Ok((
         [
@@ -2890,4 +3319,29 @@ mod tests {
         let ok = Value::Array((0..5_000).map(|idx| Value::Number(idx.into())).collect());
         assert!(validate_federation_content(&ok).is_ok());
     }
+
+    #[test]
+    fn peer_bandwidth_sums_categories_for_one_peer() {
+        let row = |server: &str, category: &str, bytes_in: i64, bytes_out: i64| {
+            paracord_db::federation::PeerBandwidthRow {
+                server_name: server.to_string(),
+                category: category.to_string(),
+                bytes_in,
+                bytes_out,
+            }
+        };
+        let usage = vec![
+            row("remote.example", "events", 400, 100),
+            row("remote.example", "file", 0, 1_500),
+            row("other.example", "events", 9_999, 9_999),
+        ];
+        let value = peer_bandwidth_json(&usage, "Remote.Example", Some(2_000));
+        assert_eq!(value["total_bytes"], 2_000);
+        assert_eq!(value["categories"]["file"]["bytes_out"], 1_500);
+        assert_eq!(value["quota_exceeded"], true);
+
+        let value = peer_bandwidth_json(&usage, "quiet.example", Some(2_000));
+        assert_eq!(value["total_bytes"], 0);
+        assert_eq!(value["quota_exceeded"], false);
+    }
 }
@@ -1,16 +1,21 @@
 use axum::{
-    extract::{Multipart, Path, State},
+    body::{Body, Bytes},
+    extract::{Multipart, Path, Query, State},
     http::{header, HeaderMap, HeaderValue, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{Duration, Utc};
 use jsonwebtoken::{Algorithm, EncodingKey, Header as JwtHeader};
 use paracord_core::AppState;
+use paracord_media::transcode::TranscodeFormat;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
+use paracord_transport::transfer_limits::{TransferDirection, TransferPermit};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -23,11 +28,32 @@ const MALWARE_SCAN_FAIL_CLOSED_ENV: &str = "PARACORD_MALWARE_SCAN_FAIL_CLOSED";
 const MALWARE_SCAN_INFECTED_EXIT_CODES_ENV: &str = "PARACORD_MALWARE_SCAN_INFECTED_EXIT_CODES";
 const MALWARE_QUARANTINE_PATH_ENV: &str = "PARACORD_MALWARE_QUARANTINE_PATH";
 const ATTACHMENT_AAD_PREFIX: &str = "attachment:";
-
-fn attachment_aad(attachment_id: i64) -> String {
+const MAX_VOICE_MESSAGE_DURATION_MS: i32 = 10 * 60 * 1000;
+/// 256 waveform samples, base64-encoded.
+const MAX_VOICE_WAVEFORM_LEN: usize = 344;
+/// Download bodies are sent in chunks of this size so bandwidth limits can
+/// pace them.
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) fn attachment_aad(attachment_id: i64) -> String {
     format!("{ATTACHMENT_AAD_PREFIX}{attachment_id}")
 }
 
+/// MP4 keeps the original suffix so renditions stored before WebM was added
+/// still decrypt.
+pub fn transcoded_attachment_aad(attachment_id: i64, format: TranscodeFormat) -> String {
+    match format {
+        TranscodeFormat::Mp4 => format!("{ATTACHMENT_AAD_PREFIX}{attachment_id}:transcoded"),
+        TranscodeFormat::WebM => {
+            format!("{ATTACHMENT_AAD_PREFIX}{attachment_id}:transcoded:webm")
+        }
+    }
+}
+
+fn thumbnail_attachment_aad(attachment_id: i64, size: u32) -> String {
+    format!("{ATTACHMENT_AAD_PREFIX}{attachment_id}:thumb_{size}")
+}
+
 fn sanitize_filename_for_disposition(filename: &str) -> String {
     filename
         .chars()
@@ -91,6 +117,8 @@ fn is_inline_safe_content_type(content_type: &str) -> bool {
             | "image/avif"
             | "audio/mpeg"
             | "audio/ogg"
+            | "audio/opus"
+            | "audio/webm"
             | "audio/wav"
             | "video/mp4"
             | "video/webm"
@@ -125,7 +153,63 @@ fn resolve_stored_content_type(filename: &str, claimed: Option<&str>, data: &[u8
     normalized
 }
 
-fn build_content_disposition(filename: &str, allow_inline: bool) -> String {
+fn is_voice_message_content_type(content_type: &str) -> bool {
+    matches!(content_type, "audio/ogg" | "audio/opus" | "audio/webm")
+}
+
+/// Validate the optional voice-message fields sent alongside an upload.
+///
+/// Returns `None` for ordinary attachments; voice messages must be Opus/OGG
+/// audio with a positive duration and an optional base64 waveform summary.
+fn parse_voice_metadata(
+    duration_secs: Option<&str>,
+    waveform: Option<&str>,
+    content_type: &str,
+) -> Result<Option<(i32, Option<String>)>, ApiError> {
+    let Some(duration_raw) = duration_secs else {
+        if waveform.is_some() {
+            return Err(ApiError::BadRequest(
+                "waveform requires duration_secs".into(),
+            ));
+        }
+        return Ok(None);
+    };
+    if !is_voice_message_content_type(content_type) {
+        return Err(ApiError::BadRequest(
+            "Voice messages must be Opus/OGG audio".into(),
+        ));
+    }
+    let duration_secs = duration_raw
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| ApiError::BadRequest("Invalid duration_secs".into()))?;
+    let duration_ms = (duration_secs * 1000.0).round();
+    if !duration_ms.is_finite()
+        || duration_ms <= 0.0
+        || duration_ms > f64::from(MAX_VOICE_MESSAGE_DURATION_MS)
+    {
+        return Err(ApiError::BadRequest(
+            "Voice message duration must be between 0 and 600 seconds".into(),
+        ));
+    }
+
+    let waveform = waveform
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            let valid_base64_char =
+                |c: char| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=';
+            if value.len() > MAX_VOICE_WAVEFORM_LEN || !value.chars().all(valid_base64_char) {
+                return Err(ApiError::BadRequest("Invalid waveform".into()));
+            }
+            Ok(value.to_string())
+        })
+        .transpose()?;
+
+    Ok(Some((duration_ms as i32, waveform)))
+}
+
+pub(crate) fn build_content_disposition(filename: &str, allow_inline: bool) -> String {
     let safe_name = sanitize_filename_for_disposition(filename);
     if allow_inline {
         format!("inline; filename=\"{}\"", safe_name)
@@ -359,6 +443,26 @@ fn mime_matches_pattern(content_type: &str, pattern: &str) -> bool {
     content_type == pattern
 }
 
+/// The instance upload limit, raised for supporters.
+async fn max_upload_size_for(state: &AppState, user_id: i64) -> u64 {
+    paracord_core::supporters::perks_for(state, user_id)
+        .await
+        .max_upload_size
+}
+
+/// Claim one of the user's transfer slots. Fails with 429 when they
+/// already have the configured number of transfers running.
+pub(crate) fn begin_transfer(
+    state: &AppState,
+    user_id: i64,
+    direction: TransferDirection,
+) -> Result<TransferPermit, ApiError> {
+    state
+        .transfer_limiter
+        .try_begin(user_id, direction)
+        .ok_or(ApiError::RateLimited)
+}
+
 async fn check_guild_upload_policy(
     state: &AppState,
     channel_id: i64,
@@ -465,12 +569,136 @@ async fn cleanup_expired_pending_attachments(state: &AppState) {
             .unwrap_or("bin");
         let storage_key = format!("attachments/{}.{}", attachment.id, ext);
         let _ = state.storage_backend.delete(&storage_key).await;
+        if attachment.transcode_status.is_some() {
+            for format in TranscodeFormat::ALL {
+                let _ = state
+                    .storage_backend
+                    .delete(&paracord_media::transcode::transcoded_storage_key(
+                        attachment.id,
+                        format,
+                    ))
+                    .await;
+            }
+        }
+        delete_thumbnails(state, &attachment).await;
+    }
+}
+
+/// Queue a web-compatible rendition for video formats browsers can't play.
+async fn queue_transcode_if_needed(
+    state: &AppState,
+    attachment_id: i64,
+    content_type: &str,
+) -> Result<Option<&'static str>, ApiError> {
+    if !state.config.video_transcoding_enabled
+        || !paracord_media::transcode::needs_transcode(content_type)
+    {
+        return Ok(None);
+    }
+    paracord_db::attachments::set_transcode_status(
+        &state.db,
+        attachment_id,
+        paracord_media::transcode::TRANSCODE_PENDING,
+        None,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Some(paracord_media::transcode::TRANSCODE_PENDING))
+}
+
+/// Generate thumbnails, dimensions and a BlurHash for an image upload in the
+/// background. The attachment's `thumbnail_format` is set once they're stored.
+fn spawn_thumbnail_generation(
+    state: &AppState,
+    attachment_id: i64,
+    content_type: &str,
+    data: Vec<u8>,
+) {
+    if !paracord_media::thumbnails::is_thumbnailable(content_type) {
+        return;
     }
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = store_thumbnails(&state, attachment_id, data).await {
+            tracing::debug!("No thumbnails for attachment {}: {}", attachment_id, err);
+        }
+    });
 }
 
+async fn store_thumbnails(
+    state: &AppState,
+    attachment_id: i64,
+    data: Vec<u8>,
+) -> anyhow::Result<()> {
+    let set =
+        tokio::task::spawn_blocking(move || paracord_media::thumbnails::generate_thumbnails(&data))
+            .await??;
+    for thumbnail in &set.thumbnails {
+        let payload = match state.config.file_cryptor.as_ref() {
+            Some(cryptor) => {
+                let aad = thumbnail_attachment_aad(attachment_id, thumbnail.size);
+                cryptor
+                    .encrypt_with_aad(&thumbnail.data, aad.as_bytes())
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+            }
+            None => thumbnail.data.clone(),
+        };
+        state
+            .storage_backend
+            .store(
+                &paracord_media::thumbnails::thumbnail_storage_key(
+                    attachment_id,
+                    thumbnail.size,
+                    set.format,
+                ),
+                &payload,
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+    paracord_db::attachments::set_image_metadata(
+        &state.db,
+        attachment_id,
+        i32::try_from(set.width)?,
+        i32::try_from(set.height)?,
+        set.blurhash.as_deref(),
+        set.format.as_str(),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn delete_thumbnails(state: &AppState, attachment: &paracord_db::attachments::AttachmentRow) {
+    use paracord_media::thumbnails::{thumbnail_storage_key, ThumbnailFormat, THUMBNAIL_SIZES};
+
+    let Some(format) = attachment
+        .thumbnail_format
+        .as_deref()
+        .and_then(ThumbnailFormat::parse)
+    else {
+        return;
+    };
+    for size in THUMBNAIL_SIZES {
+        let _ = state
+            .storage_backend
+            .delete(&thumbnail_storage_key(attachment.id, size, format))
+            .await;
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/attachments",
+    tag = "files",
+    params(("channel_id" = i64, Path)),
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn upload_file(
     State(state): State<AppState>,
     auth: AuthUser,
+    headers: HeaderMap,
     Path(channel_id): Path<i64>,
     mut multipart: Multipart,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
@@ -480,13 +708,13 @@ pub async fn upload_file(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if let Some(guild_id) = channel.guild_id() {
         paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
         let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
         let perms = paracord_core::permissions::compute_channel_permissions(
             &state.db,
             guild_id,
@@ -504,7 +732,7 @@ pub async fn upload_file(
         return Err(ApiError::Forbidden);
     }
 
-    let field = multipart
+    let mut field = multipart
         .next_field()
         .await
         .map_err(|e| ApiError::BadRequest(e.to_string()))?
@@ -512,10 +740,41 @@ pub async fn upload_file(
 
     let filename = field.file_name().unwrap_or("upload").to_string();
     let claimed_content_type = field.content_type().map(|s| s.to_string());
-    let data = field
-        .bytes()
+    let permit = begin_transfer(&state, auth.user_id, TransferDirection::Upload)?;
+    let mut data = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
         .await
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        permit.throttle(chunk.len()).await;
+        data.extend_from_slice(&chunk);
+    }
+    // multer refuses to yield the next part while this one is alive.
+    drop(field);
+
+    // Optional voice-message metadata follows the file part.
+    let mut duration_field: Option<String> = None;
+    let mut waveform_field: Option<String> = None;
+    while let Some(extra) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        let name = extra.name().unwrap_or_default().to_string();
+        if name != "duration_secs" && name != "waveform" {
+            continue;
+        }
+        let value = extra
+            .text()
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        if name == "duration_secs" {
+            duration_field = Some(value);
+        } else {
+            waveform_field = Some(value);
+        }
+    }
 
     let size =
         u64::try_from(data.len()).map_err(|_| ApiError::BadRequest("File too large".into()))?;
@@ -524,7 +783,7 @@ pub async fn upload_file(
         return Err(ApiError::BadRequest("Empty file".into()));
     }
 
-    if size > state.config.max_upload_size {
+    if size > max_upload_size_for(&state, auth.user_id).await {
         return Err(ApiError::BadRequest("File too large".into()));
     }
     let db_size = i32::try_from(size).map_err(|_| ApiError::BadRequest("File too large".into()))?;
@@ -537,9 +796,24 @@ pub async fn upload_file(
     // Check guild-level upload policy (file size, quota, type restrictions)
     let resolved_ct = normalized_content_type(&filename, claimed_content_type.as_deref());
     check_guild_upload_policy(&state, channel_id, size, &resolved_ct).await?;
+    let voice_metadata = parse_voice_metadata(
+        duration_field.as_deref(),
+        waveform_field.as_deref(),
+        &resolved_ct,
+    )?;
+
+    crate::routes::image_hashes::screen_upload(
+        &state,
+        Some(&headers),
+        &data,
+        &content_hash,
+        channel_id,
+        auth.user_id,
+    )
+    .await?;
 
     // Store file via storage backend
-    let attachment_id = paracord_util::snowflake::generate(1);
+    let attachment_id = paracord_util::snowflake::try_next_id().await?;
     scan_upload_with_malware_hook(&data, &filename, &state.config.storage_path, attachment_id)
         .await?;
 
@@ -586,6 +860,23 @@ pub async fn upload_file(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let transcode_status = queue_transcode_if_needed(&state, attachment.id, &content_type).await?;
+    spawn_thumbnail_generation(&state, attachment.id, &content_type, data);
+
+    let (duration_ms, waveform) = match voice_metadata {
+        Some((duration_ms, waveform)) => {
+            paracord_db::attachments::set_voice_metadata(
+                &state.db,
+                attachment.id,
+                duration_ms,
+                waveform.as_deref(),
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            (Some(duration_ms), waveform)
+        }
+        None => (None, None),
+    };
 
     Ok((
         StatusCode::CREATED,
@@ -595,52 +886,88 @@ pub async fn upload_file(
             "size": attachment.size,
             "content_type": attachment.content_type,
             "url": attachment.url,
+            "duration_secs": duration_ms.map(|ms| f64::from(ms) / 1000.0),
+            "waveform": waveform,
+            "transcode_status": transcode_status,
         })),
     ))
 }
 
-pub async fn download_file(
-    State(state): State<AppState>,
-    auth: AuthUser,
-    Path(id): Path<i64>,
-) -> Result<impl IntoResponse, ApiError> {
-    let attachment = paracord_db::attachments::get_attachment(&state.db, id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
-
-    let message_id = attachment.message_id.ok_or(ApiError::NotFound)?;
-    let message = paracord_db::messages::get_message(&state.db, message_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
-    let channel = paracord_db::channels::get_channel(&state.db, message.channel_id)
+/// Check that `user_id` can read the message an attachment is linked to.
+async fn ensure_attachment_read_access(
+    state: &AppState,
+    attachment: &paracord_db::attachments::AttachmentRow,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let channel_id = match attachment.message_id {
+        Some(message_id) => {
+            paracord_db::messages::get_message(&state.db, message_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?
+                .channel_id
+        }
+        // Voice recordings are stored as unlinked attachments of the channel
+        // they were recorded in.
+        None => {
+            paracord_db::voice_recordings::get_recording_by_attachment(&state.db, attachment.id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::NotFound)?
+                .channel_id
+        }
+    };
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if let Some(guild_id) = channel.guild_id() {
-        paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+        paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
         let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
         let perms = paracord_core::permissions::compute_channel_permissions(
             &state.db,
             guild_id,
             channel.id,
             guild.owner_id,
-            auth.user_id,
+            user_id,
         )
         .await?;
         paracord_core::permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
         paracord_core::permissions::require_permission(perms, Permissions::READ_MESSAGE_HISTORY)?;
-    } else if !paracord_db::dms::is_dm_recipient(&state.db, channel.id, auth.user_id)
+        crate::routes::channels::ensure_age_gate_acknowledged(state, &channel, user_id).await?;
+    } else if !paracord_db::dms::is_dm_recipient(&state.db, channel.id, user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
     {
         return Err(ApiError::Forbidden);
     }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/attachments/{id}",
+    tag = "files",
+    params(("id" = i64, Path)),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn download_file(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let attachment = paracord_db::attachments::get_attachment(&state.db, id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    ensure_attachment_read_access(&state, &attachment, auth.user_id).await?;
+    let permit = begin_transfer(&state, auth.user_id, TransferDirection::Download)?;
 
     let ext = std::path::Path::new(&attachment.filename)
         .extension()
@@ -700,27 +1027,293 @@ pub async fn download_file(
         is_inline_safe_content_type(&content_type) && !has_active_extension(&attachment.filename);
     let disposition = build_content_disposition(&attachment.filename, allow_inline);
 
-    Ok((
-        [
+    Ok(media_response(
+        &headers,
+        &content_type,
+        &disposition,
+        data,
+        Some(permit),
+    ))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TranscodedQuery {
+    /// `mp4` or `webm`; without it the `Accept` header picks, defaulting to MP4.
+    pub format: Option<String>,
+}
+
+/// Serve a rendition produced by the transcoding worker.
+#[utoipa::path(
+    get,
+    path = "/api/v1/attachments/{id}/transcoded",
+    tag = "files",
+    params(("id" = i64, Path), TranscodedQuery),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn download_transcoded_file(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i64>,
+    Query(query): Query<TranscodedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let format = match query.format.as_deref() {
+        Some(requested) => TranscodeFormat::parse(requested)
+            .ok_or_else(|| ApiError::BadRequest("format must be mp4 or webm".into()))?,
+        None => TranscodeFormat::negotiate(
+            headers
+                .get(header::ACCEPT)
+                .and_then(|value| value.to_str().ok()),
+        ),
+    };
+    let attachment = paracord_db::attachments::get_attachment(&state.db, id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    ensure_attachment_read_access(&state, &attachment, auth.user_id).await?;
+    if attachment.transcode_status.as_deref() != Some(paracord_media::transcode::TRANSCODE_DONE) {
+        return Err(ApiError::NotFound);
+    }
+    let permit = begin_transfer(&state, auth.user_id, TransferDirection::Download)?;
+
+    let stored_data = state
+        .storage_backend
+        .retrieve(&paracord_media::transcode::transcoded_storage_key(
+            attachment.id,
+            format,
+        ))
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let data = match state.config.file_cryptor.as_ref() {
+        Some(cryptor)
+            if paracord_util::at_rest::FileCryptor::payload_is_encrypted(&stored_data) =>
+        {
+            let aad = transcoded_attachment_aad(attachment.id, format);
+            cryptor
+                .decrypt_with_aad(&stored_data, aad.as_bytes())
+                .map_err(|err| ApiError::Internal(anyhow::anyhow!(err.to_string())))?
+        }
+        _ => stored_data,
+    };
+
+    let stem = std::path::Path::new(&attachment.filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+    let disposition = build_content_disposition(&format!("{}.{}", stem, format.extension()), true);
+    Ok(media_response(
+        &headers,
+        format.content_type(),
+        &disposition,
+        data,
+        Some(permit),
+    ))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ThumbnailQuery {
+    /// Requested bounding box; snapped up to the nearest generated size.
+    pub size: Option<u32>,
+}
+
+/// Serve a downscaled preview of an image attachment.
+#[utoipa::path(
+    get,
+    path = "/api/v1/attachments/{id}/thumbnail",
+    tag = "files",
+    params(("id" = i64, Path), ThumbnailQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not an image, or thumbnails are not ready yet"),
+    ),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn download_thumbnail(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<i64>,
+    Query(params): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    use paracord_media::thumbnails::{
+        normalize_thumbnail_size, thumbnail_storage_key, ThumbnailFormat,
+    };
+
+    let attachment = paracord_db::attachments::get_attachment(&state.db, id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    ensure_attachment_read_access(&state, &attachment, auth.user_id).await?;
+    let format = attachment
+        .thumbnail_format
+        .as_deref()
+        .and_then(ThumbnailFormat::parse)
+        .ok_or(ApiError::NotFound)?;
+    let size = normalize_thumbnail_size(params.size);
+    let permit = begin_transfer(&state, auth.user_id, TransferDirection::Download)?;
+
+    let stored_data = state
+        .storage_backend
+        .retrieve(&thumbnail_storage_key(attachment.id, size, format))
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let data = match state.config.file_cryptor.as_ref() {
+        Some(cryptor)
+            if paracord_util::at_rest::FileCryptor::payload_is_encrypted(&stored_data) =>
+        {
+            let aad = thumbnail_attachment_aad(attachment.id, size);
+            cryptor
+                .decrypt_with_aad(&stored_data, aad.as_bytes())
+                .map_err(|err| ApiError::Internal(anyhow::anyhow!(err.to_string())))?
+        }
+        _ => stored_data,
+    };
+
+    let stem = std::path::Path::new(&attachment.filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let disposition =
+        build_content_disposition(&format!("{}_{}.{}", stem, size, format.extension()), true);
+    Ok(media_response(
+        &headers,
+        format.content_type(),
+        &disposition,
+        data,
+        Some(permit),
+    ))
+}
+
+/// Build a download response, honouring a single `Range` request so audio
+/// and video players can seek. With a permit, the body is paced by the
+/// user's download rate and holds their transfer slot until it is sent.
+pub(crate) fn media_response(
+    headers: &HeaderMap,
+    content_type: &str,
+    disposition: &str,
+    data: Vec<u8>,
+    permit: Option<TransferPermit>,
+) -> Response {
+    let base_headers = [
+        (
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(content_type)
+                .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_str(disposition).unwrap_or(HeaderValue::from_static("attachment")),
+        ),
+        (
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        ),
+        (header::ACCEPT_RANGES, HeaderValue::from_static("bytes")),
+    ];
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    match range.map(|value| parse_byte_range(value, data.len())) {
+        Some(Some((start, end))) => {
+            let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+            let body = Bytes::from(data).slice(start..=end);
             (
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(&content_type)
-                    .unwrap_or(HeaderValue::from_static("application/octet-stream")),
-            ),
+                StatusCode::PARTIAL_CONTENT,
+                base_headers,
+                [
+                    (
+                        header::CONTENT_RANGE,
+                        HeaderValue::from_str(&content_range)
+                            .unwrap_or(HeaderValue::from_static("bytes */0")),
+                    ),
+                    (header::CONTENT_LENGTH, HeaderValue::from(body.len())),
+                ],
+                paced_body(body, permit),
+            )
+                .into_response()
+        }
+        Some(None) => {
+            let content_range = format!("bytes */{}", data.len());
             (
-                header::CONTENT_DISPOSITION,
-                HeaderValue::from_str(&disposition)
-                    .unwrap_or(HeaderValue::from_static("attachment")),
-            ),
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(
+                    header::CONTENT_RANGE,
+                    HeaderValue::from_str(&content_range)
+                        .unwrap_or(HeaderValue::from_static("bytes */0")),
+                )],
+            )
+                .into_response()
+        }
+        None => {
+            let body = Bytes::from(data);
             (
-                header::X_CONTENT_TYPE_OPTIONS,
-                HeaderValue::from_static("nosniff"),
-            ),
-        ],
-        data,
-    ))
+                base_headers,
+                [(header::CONTENT_LENGTH, HeaderValue::from(body.len()))],
+                paced_body(body, permit),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Stream `data` in chunks, waiting on the permit's bandwidth budget before
+/// each one. The permit is released when the body finishes or the client
+/// goes away.
+fn paced_body(data: Bytes, permit: Option<TransferPermit>) -> Body {
+    let Some(permit) = permit else {
+        return Body::from(data);
+    };
+    let chunks = futures_util::stream::unfold((data, permit), |(mut rest, permit)| async move {
+        if rest.is_empty() {
+            return None;
+        }
+        let chunk = rest.split_to(rest.len().min(DOWNLOAD_CHUNK_SIZE));
+        permit.throttle(chunk.len()).await;
+        Some((Ok::<_, std::convert::Infallible>(chunk), (rest, permit)))
+    });
+    Body::from_stream(chunks)
+}
+
+/// Parse a single-range `Range: bytes=` header into inclusive offsets.
+///
+/// Returns `None` when the range is malformed, multi-part, or unsatisfiable.
+fn parse_byte_range(value: &str, len: usize) -> Option<(usize, usize)> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start_raw, end_raw) = spec.split_once('-')?;
+    let (start_raw, end_raw) = (start_raw.trim(), end_raw.trim());
+    if start_raw.is_empty() {
+        // Suffix range: last N bytes.
+        let suffix = end_raw.parse::<usize>().ok().filter(|n| *n > 0)?;
+        return Some((len.saturating_sub(suffix), len - 1));
+    }
+    let start = start_raw.parse::<usize>().ok()?;
+    if start >= len {
+        return None;
+    }
+    let end = if end_raw.is_empty() {
+        len - 1
+    } else {
+        end_raw.parse::<usize>().ok()?.min(len - 1)
+    };
+    if end < start {
+        return None;
+    }
+    Some((start, end))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/attachments/{id}",
+    tag = "files",
+    params(("id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_file(
     State(state): State<AppState>,
     _auth: AuthUser,
@@ -735,7 +1328,7 @@ pub async fn delete_file(
         let message = paracord_db::messages::get_message(&state.db, message_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?;
         if message.author_id != _auth.user_id {
             return Err(ApiError::Forbidden);
         }
@@ -753,6 +1346,18 @@ pub async fn delete_file(
         .unwrap_or("bin");
     let storage_key = format!("attachments/{}.{}", attachment.id, ext);
     let _ = state.storage_backend.delete(&storage_key).await;
+    if attachment.transcode_status.is_some() {
+        for format in TranscodeFormat::ALL {
+            let _ = state
+                .storage_backend
+                .delete(&paracord_media::transcode::transcoded_storage_key(
+                    attachment.id,
+                    format,
+                ))
+                .await;
+        }
+    }
+    delete_thumbnails(&state, &attachment).await;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -768,13 +1373,13 @@ pub async fn validate_upload_permissions(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if let Some(guild_id) = channel.guild_id() {
         paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
         let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
         let perms = paracord_core::permissions::compute_channel_permissions(
             &state.db,
             guild_id,
@@ -794,7 +1399,7 @@ pub async fn validate_upload_permissions(
     Ok(())
 }
 
-/// Process an uploaded file: malware scan, encrypt, store, and create DB record.
+/// Process an uploaded file: hash matching, malware scan, encrypt, store, and create DB record.
 ///
 /// Returns the attachment JSON value on success.
 pub async fn process_uploaded_file(
@@ -810,7 +1415,7 @@ pub async fn process_uploaded_file(
     if size == 0 {
         return Err(ApiError::BadRequest("Empty file".into()));
     }
-    if size > state.config.max_upload_size {
+    if size > max_upload_size_for(state, user_id).await {
         return Err(ApiError::BadRequest("File too large".into()));
     }
     let db_size = i32::try_from(size).map_err(|_| ApiError::BadRequest("File too large".into()))?;
@@ -823,8 +1428,17 @@ pub async fn process_uploaded_file(
     // Check guild-level upload policy
     let resolved_ct = normalized_content_type(filename, claimed_content_type);
     check_guild_upload_policy(state, channel_id, size, &resolved_ct).await?;
+    crate::routes::image_hashes::screen_upload(
+        state,
+        None,
+        data,
+        &content_hash,
+        channel_id,
+        user_id,
+    )
+    .await?;
 
-    let attachment_id = paracord_util::snowflake::generate(1);
+    let attachment_id = paracord_util::snowflake::try_next_id().await?;
     scan_upload_with_malware_hook(data, filename, &state.config.storage_path, attachment_id)
         .await?;
 
@@ -870,6 +1484,8 @@ pub async fn process_uploaded_file(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let transcode_status = queue_transcode_if_needed(state, attachment.id, &content_type).await?;
+    spawn_thumbnail_generation(state, attachment.id, &content_type, data.to_vec());
 
     Ok(json!({
         "id": attachment.id.to_string(),
@@ -877,12 +1493,13 @@ pub async fn process_uploaded_file(
         "size": attachment.size,
         "content_type": attachment.content_type,
         "url": attachment.url,
+        "transcode_status": transcode_status,
     }))
 }
 
 // ── Upload token endpoint (QUIC pre-authorization) ──────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UploadTokenRequest {
     pub filename: String,
     pub size: u64,
@@ -905,6 +1522,15 @@ struct FileTransferClaims {
     iat: usize,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/channels/{channel_id}/upload-token",
+    tag = "files",
+    params(("channel_id" = i64, Path)),
+    request_body = UploadTokenRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn upload_token(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -919,7 +1545,7 @@ pub async fn upload_token(
     if req.size == 0 {
         return Err(ApiError::BadRequest("Empty file".into()));
     }
-    if req.size > state.config.max_upload_size {
+    if req.size > max_upload_size_for(&state, auth.user_id).await {
         return Err(ApiError::BadRequest("File too large".into()));
     }
 
@@ -928,7 +1554,7 @@ pub async fn upload_token(
     check_guild_upload_policy(&state, channel_id, req.size, &resolved_ct).await?;
 
     // 3. Generate transfer ID
-    let transfer_id = paracord_util::snowflake::generate(1).to_string();
+    let transfer_id = paracord_util::snowflake::try_next_id().await?.to_string();
 
     // 4. Mint upload JWT (15 min expiry)
     let now = Utc::now();
@@ -983,91 +1609,211 @@ pub async fn upload_token(
 
 // ── Federated file proxy ────────────────────────────────────────────────────
 
-pub async fn download_federated_file(
-    State(state): State<AppState>,
-    auth: AuthUser,
-    Path((origin_server, attachment_id)): Path<(String, String)>,
-) -> Result<impl IntoResponse, ApiError> {
-    // Check that the user is a member of at least one guild federated with origin_server
-    let space_mappings =
-        paracord_db::federation::list_space_mappings_by_origin(&state.db, &origin_server)
-            .await
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let mut has_access = false;
-    for mapping in &space_mappings {
-        let member =
-            paracord_db::members::get_member(&state.db, auth.user_id, mapping.local_guild_id)
-                .await
-                .ok()
-                .flatten();
-        if member.is_some() {
-            has_access = true;
+/// Cached federated files are stored once per SHA-256 of their bytes.
+const FEDERATED_FILE_CACHE_PREFIX: &str = "fed-cache/sha256";
+/// Attachments prefetched from a single inbound message, at most.
+const MAX_PREFETCH_ATTACHMENTS: usize = 10;
+/// Guild members considered when picking who to request prefetches as.
+const PREFETCH_REQUESTER_CANDIDATES: usize = 32;
+const FEDERATED_FILE_CACHE_SWEEP_BATCH: i64 = 256;
+
+struct FederatedFile {
+    data: Vec<u8>,
+    content_type: String,
+    filename: String,
+}
+
+impl IntoResponse for FederatedFile {
+    fn into_response(self) -> Response {
+        let disposition = format!(
+            "attachment; filename=\"{}\"",
+            sanitize_filename_for_disposition(&self.filename)
+        );
+        (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_str(&self.content_type)
+                        .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    HeaderValue::from_str(&disposition)
+                        .unwrap_or(HeaderValue::from_static("attachment")),
+                ),
+                (
+                    header::X_CONTENT_TYPE_OPTIONS,
+                    HeaderValue::from_static("nosniff"),
+                ),
+            ],
+            self.data,
+        )
+            .into_response()
+    }
+}
+
+fn federated_cache_storage_key(content_hash: &str) -> String {
+    format!("{FEDERATED_FILE_CACHE_PREFIX}/{content_hash}")
+}
+
+fn cache_timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Attachment ids listed in a federated `m.message` content, deduplicated
+/// and capped at [`MAX_PREFETCH_ATTACHMENTS`].
+pub(crate) fn prefetch_attachment_ids(content: &Value) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    let Some(attachments) = content.get("attachments").and_then(Value::as_array) else {
+        return ids;
+    };
+    for attachment in attachments {
+        let Some(id) = attachment.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        if !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
+        }
+        if ids.len() == MAX_PREFETCH_ATTACHMENTS {
             break;
         }
     }
-    if !has_access {
-        return Err(ApiError::Forbidden);
+    ids
+}
+
+/// Drop a cache row, and its blob once no other row shares it.
+async fn evict_federated_cache_entry(
+    state: &AppState,
+    entry: &paracord_db::federation_file_cache::FedFileCacheRow,
+) {
+    if paracord_db::federation_file_cache::delete_cache_entry(&state.db, entry.id)
+        .await
+        .is_err()
+    {
+        return;
     }
+    let still_referenced = paracord_db::federation_file_cache::count_entries_for_storage_key(
+        &state.db,
+        &entry.storage_key,
+    )
+    .await
+    .map_or(true, |count| count > 0);
+    if !still_referenced {
+        let _ = state.storage_backend.delete(&entry.storage_key).await;
+    }
+}
 
-    // Check federation file cache for a cached copy
-    if let Ok(Some(cached)) = paracord_db::federation_file_cache::get_cached_file(
+/// The cached copy of a federated attachment, if there is a live one.
+async fn cached_federated_file(
+    state: &AppState,
+    origin_server: &str,
+    attachment_id: &str,
+) -> Option<FederatedFile> {
+    let cached = paracord_db::federation_file_cache::get_cached_file(
         &state.db,
-        &origin_server,
-        &attachment_id,
+        origin_server,
+        attachment_id,
     )
     .await
-    {
-        let now_str = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-        let is_expired = cached
-            .expires_at
-            .as_deref()
-            .is_some_and(|exp| exp < now_str.as_str());
-        if !is_expired {
-            let _ =
-                paracord_db::federation_file_cache::update_cache_access_time(&state.db, cached.id)
-                    .await;
+    .ok()
+    .flatten()?;
+    let now_str = cache_timestamp(Utc::now());
+    let is_expired = cached
+        .expires_at
+        .as_deref()
+        .is_some_and(|exp| exp < now_str.as_str());
+    if is_expired {
+        evict_federated_cache_entry(state, &cached).await;
+        return None;
+    }
+    let Ok(data) = state.storage_backend.retrieve(&cached.storage_key).await else {
+        // The blob went missing underneath us; forget the row so it's refetched.
+        evict_federated_cache_entry(state, &cached).await;
+        return None;
+    };
+    let _ =
+        paracord_db::federation_file_cache::update_cache_access_time(&state.db, cached.id).await;
+
+    Some(FederatedFile {
+        data,
+        content_type: cached
+            .content_type
+            .unwrap_or_else(|| "application/octet-stream".to_string()),
+        filename: cached.filename,
+    })
+}
 
-            let data = state
+/// Store `file` in the federation file cache. Identical bytes from any
+/// origin share one blob; the hash is computed here, never taken from the
+/// remote's attachment metadata.
+async fn cache_federated_file(
+    state: &AppState,
+    origin_server: &str,
+    attachment_id: &str,
+    file: &FederatedFile,
+) {
+    let hash = format!("{:x}", Sha256::digest(&file.data));
+    let deduped = paracord_db::federation_file_cache::get_cached_file_by_hash(&state.db, &hash)
+        .await
+        .ok()
+        .flatten()
+        .filter(|existing| existing.size == file.data.len() as i64);
+    let storage_key = match deduped {
+        Some(existing) => {
+            crate::FED_FILE_CACHE_DEDUPED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            existing.storage_key
+        }
+        None => {
+            let cache_size = paracord_db::federation_file_cache::get_total_cache_size(&state.db)
+                .await
+                .unwrap_or(0);
+            if cache_size + file.data.len() as i64
+                > state.config.federation_file_cache_max_size as i64
+            {
+                return;
+            }
+            let storage_key = federated_cache_storage_key(&hash);
+            if state
                 .storage_backend
-                .retrieve(&cached.storage_key)
+                .store(&storage_key, &file.data)
                 .await
-                .map_err(|_| ApiError::NotFound)?;
-
-            let content_type = cached
-                .content_type
-                .clone()
-                .unwrap_or_else(|| "application/octet-stream".to_string());
-            let safe_filename: String = cached
-                .filename
-                .chars()
-                .filter(|ch| *ch != '"' && *ch != '\\' && *ch != '\r' && *ch != '\n')
-                .collect();
-            let disposition = format!("attachment; filename=\"{}\"", safe_filename);
-
-            return Ok((
-                [
-                    (
-                        header::CONTENT_TYPE,
-                        HeaderValue::from_str(&content_type)
-                            .unwrap_or(HeaderValue::from_static("application/octet-stream")),
-                    ),
-                    (
-                        header::CONTENT_DISPOSITION,
-                        HeaderValue::from_str(&disposition)
-                            .unwrap_or(HeaderValue::from_static("attachment")),
-                    ),
-                    (
-                        header::X_CONTENT_TYPE_OPTIONS,
-                        HeaderValue::from_static("nosniff"),
-                    ),
-                ],
-                data,
-            ));
+                .is_err()
+            {
+                return;
+            }
+            storage_key
         }
-    }
+    };
+
+    let expires = Utc::now() + Duration::hours(state.config.federation_file_cache_ttl_hours as i64);
+    let expires_str = cache_timestamp(expires);
+    let _ = paracord_db::federation_file_cache::insert_cached_file(
+        &state.db,
+        origin_server,
+        attachment_id,
+        &hash,
+        &file.filename,
+        Some(&file.content_type),
+        file.data.len() as i64,
+        &storage_key,
+        Some(&expires_str),
+    )
+    .await;
+}
 
-    // Not cached -- look up origin server's federation endpoint
-    let server = paracord_db::federation::get_federated_server(&state.db, &origin_server)
+/// Fetch an attachment from `origin_server` on behalf of `requester_user_id`
+/// and cache it when the file cache is enabled.
+async fn fetch_federated_file(
+    state: &AppState,
+    origin_server: &str,
+    attachment_id: &str,
+    requester_user_id: i64,
+    room_id: String,
+) -> Result<FederatedFile, ApiError> {
+    let server = paracord_db::federation::get_federated_server(&state.db, origin_server)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .ok_or(ApiError::NotFound)?;
@@ -1076,14 +1822,10 @@ pub async fn download_federated_file(
     let client = crate::routes::federation::build_signed_federation_client(&service)
         .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("federation client unavailable")))?;
 
-    let room_id = space_mappings
-        .first()
-        .map(|m| format!("!{}:{}", m.remote_space_id, m.origin_server))
-        .unwrap_or_default();
-    let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+    let user = paracord_db::users::get_user_by_id(&state.db, requester_user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
     let user_id = format!("@{}:{}", user.username, service.domain());
 
     let token_resp = client
@@ -1091,7 +1833,7 @@ pub async fn download_federated_file(
             &server.federation_endpoint,
             &paracord_federation::client::FederationFileTokenRequest {
                 origin_server: service.server_name().to_string(),
-                attachment_id: attachment_id.clone(),
+                attachment_id: attachment_id.to_string(),
                 room_id,
                 user_id,
             },
@@ -1113,82 +1855,215 @@ pub async fn download_federated_file(
         )
     };
 
-    let (file_data, resp_content_type, resp_filename) = client
+    let (data, resp_content_type, resp_filename) = client
         .download_federated_file(&full_download_url)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!("failed to download file: {}", e)))?;
 
-    let content_type = resp_content_type.unwrap_or_else(|| "application/octet-stream".to_string());
-    let filename = resp_filename.unwrap_or_else(|| format!("federated_{}", attachment_id));
-
-    // Optionally cache the file
+    let file = FederatedFile {
+        data,
+        content_type: resp_content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+        filename: resp_filename.unwrap_or_else(|| format!("federated_{}", attachment_id)),
+    };
     if state.config.federation_file_cache_enabled {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(&file_data);
-        let hash = format!("{:x}", hasher.finalize());
+        cache_federated_file(state, origin_server, attachment_id, &file).await;
+    }
+    Ok(file)
+}
 
-        let cache_key = format!("fed-cache/{}/{}", origin_server, attachment_id);
-        let cache_size = paracord_db::federation_file_cache::get_total_cache_size(&state.db)
+#[utoipa::path(
+    get,
+    path = "/api/v1/federated-files/{origin_server}/{attachment_id}",
+    tag = "files",
+    params(("origin_server" = String, Path), ("attachment_id" = String, Path)),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn download_federated_file(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((origin_server, attachment_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Check that the user is a member of at least one guild federated with origin_server
+    let space_mappings =
+        paracord_db::federation::list_space_mappings_by_origin(&state.db, &origin_server)
             .await
-            .unwrap_or(0);
-        if cache_size + file_data.len() as i64 <= state.config.federation_file_cache_max_size as i64
-            && state
-                .storage_backend
-                .store(&cache_key, &file_data)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let mut has_access = false;
+    for mapping in &space_mappings {
+        let member =
+            paracord_db::members::get_member(&state.db, auth.user_id, mapping.local_guild_id)
                 .await
-                .is_ok()
+                .ok()
+                .flatten();
+        if member.is_some() {
+            has_access = true;
+            break;
+        }
+    }
+    if !has_access {
+        return Err(ApiError::Forbidden);
+    }
+
+    if let Some(cached) = cached_federated_file(&state, &origin_server, &attachment_id).await {
+        crate::FED_FILE_CACHE_HITS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        return Ok(cached);
+    }
+    crate::FED_FILE_CACHE_MISSES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+    let room_id = space_mappings
+        .first()
+        .map(|m| format!("!{}:{}", m.remote_space_id, m.origin_server))
+        .unwrap_or_default();
+    fetch_federated_file(
+        &state,
+        &origin_server,
+        &attachment_id,
+        auth.user_id,
+        room_id,
+    )
+    .await
+}
+
+/// A local member of `guild_id` to request prefetches as; the origin only
+/// hands out file tokens to users in the room.
+async fn prefetch_requester(state: &AppState, guild_id: i64) -> Option<i64> {
+    let member_ids = paracord_db::members::get_guild_member_user_ids(&state.db, guild_id)
+        .await
+        .ok()?;
+    for user_id in member_ids
+        .into_iter()
+        .filter(|id| *id != 0)
+        .take(PREFETCH_REQUESTER_CANDIDATES)
+    {
+        let is_remote =
+            paracord_db::federation::get_remote_user_mapping_by_local(&state.db, user_id)
+                .await
+                .map_or(true, |mapping| mapping.is_some());
+        if !is_remote {
+            return Some(user_id);
+        }
+    }
+    None
+}
+
+/// Warm the federation file cache with attachments from a freshly ingested
+/// message so the first click doesn't wait on the origin.
+pub(crate) async fn prefetch_federated_attachments(
+    state: AppState,
+    origin_server: String,
+    room_id: String,
+    local_guild_id: i64,
+    attachment_ids: Vec<String>,
+) {
+    if !state.config.federation_file_cache_enabled || attachment_ids.is_empty() {
+        return;
+    }
+    let mut missing = Vec::with_capacity(attachment_ids.len());
+    for attachment_id in attachment_ids {
+        let cached = paracord_db::federation_file_cache::get_cached_file(
+            &state.db,
+            &origin_server,
+            &attachment_id,
+        )
+        .await
+        .map_or(true, |row| row.is_some());
+        if !cached {
+            missing.push(attachment_id);
+        }
+    }
+    if missing.is_empty() {
+        return;
+    }
+    let Some(requester) = prefetch_requester(&state, local_guild_id).await else {
+        tracing::debug!(
+            "federation: no local member of guild {} to prefetch attachments as",
+            local_guild_id
+        );
+        return;
+    };
+
+    for attachment_id in missing {
+        match fetch_federated_file(
+            &state,
+            &origin_server,
+            &attachment_id,
+            requester,
+            room_id.clone(),
+        )
+        .await
         {
-            let expires = chrono::Utc::now()
-                + chrono::Duration::hours(state.config.federation_file_cache_ttl_hours as i64);
-            let expires_str = expires.format("%Y-%m-%d %H:%M:%S").to_string();
-            let _ = paracord_db::federation_file_cache::insert_cached_file(
-                &state.db,
-                &origin_server,
-                &attachment_id,
-                &hash,
-                &filename,
-                Some(&content_type),
-                file_data.len() as i64,
-                &cache_key,
-                Some(&expires_str),
-            )
-            .await;
+            Ok(_) => {
+                crate::FED_FILE_PREFETCHED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            Err(err) => {
+                crate::FED_FILE_PREFETCH_FAILED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                tracing::debug!(
+                    "federation: prefetch of {}/{} failed: {}",
+                    origin_server,
+                    attachment_id,
+                    err
+                );
+            }
         }
     }
+}
 
-    let safe_filename: String = filename
-        .chars()
-        .filter(|ch| *ch != '"' && *ch != '\\' && *ch != '\r' && *ch != '\n')
-        .collect();
-    let disposition = format!("attachment; filename=\"{}\"", safe_filename);
+/// Evict expired federation file cache entries, then least recently used
+/// ones until the cache fits `federation_file_cache_max_size`.
+pub async fn sweep_federated_file_cache_once(state: &AppState) {
+    let now_str = cache_timestamp(Utc::now());
+    if let Ok(expired) = paracord_db::federation_file_cache::get_expired_cache_entries(
+        &state.db,
+        &now_str,
+        FEDERATED_FILE_CACHE_SWEEP_BATCH,
+    )
+    .await
+    {
+        for entry in &expired {
+            evict_federated_cache_entry(state, entry).await;
+        }
+    }
 
-    Ok((
-        [
-            (
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(&content_type)
-                    .unwrap_or(HeaderValue::from_static("application/octet-stream")),
-            ),
-            (
-                header::CONTENT_DISPOSITION,
-                HeaderValue::from_str(&disposition)
-                    .unwrap_or(HeaderValue::from_static("attachment")),
-            ),
-            (
-                header::X_CONTENT_TYPE_OPTIONS,
-                HeaderValue::from_static("nosniff"),
-            ),
-        ],
-        file_data,
-    ))
+    let max_size = state.config.federation_file_cache_max_size as i64;
+    loop {
+        let Ok(total) = paracord_db::federation_file_cache::get_total_cache_size(&state.db).await
+        else {
+            return;
+        };
+        if total <= max_size {
+            return;
+        }
+        let Ok(lru) = paracord_db::federation_file_cache::get_lru_cache_entries(
+            &state.db,
+            FEDERATED_FILE_CACHE_SWEEP_BATCH,
+        )
+        .await
+        else {
+            return;
+        };
+        if lru.is_empty() {
+            return;
+        }
+        let mut freed = 0;
+        for entry in &lru {
+            evict_federated_cache_entry(state, entry).await;
+            freed += entry.size;
+            if total - freed <= max_size {
+                break;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        build_content_disposition, is_inline_safe_content_type, resolve_stored_content_type,
+        build_content_disposition, federated_cache_storage_key, is_inline_safe_content_type,
+        parse_byte_range, parse_voice_metadata, prefetch_attachment_ids,
+        resolve_stored_content_type, MAX_PREFETCH_ATTACHMENTS,
     };
+    use serde_json::json;
 
     #[test]
     fn forces_octet_stream_for_active_content() {
@@ -1210,4 +2085,61 @@ mod tests {
         let disposition = build_content_disposition("bad\"name\r\n.js", false);
         assert_eq!(disposition, "attachment; filename=\"badname.js\"");
     }
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(parse_byte_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_byte_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=500-5000", 1000), Some((500, 999)));
+        assert_eq!(parse_byte_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_byte_range("bytes=0-1,5-9", 1000), None);
+        assert_eq!(parse_byte_range("items=0-1", 1000), None);
+    }
+
+    #[test]
+    fn voice_metadata_requires_audio_and_sane_duration() {
+        assert!(parse_voice_metadata(None, None, "image/png")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            parse_voice_metadata(Some("3.25"), Some("AAEC/w=="), "audio/ogg").unwrap(),
+            Some((3250, Some("AAEC/w==".to_string())))
+        );
+        assert!(parse_voice_metadata(Some("3"), None, "image/png").is_err());
+        assert!(parse_voice_metadata(Some("0"), None, "audio/ogg").is_err());
+        assert!(parse_voice_metadata(Some("9999"), None, "audio/ogg").is_err());
+        assert!(parse_voice_metadata(Some("2"), Some("<bad>"), "audio/ogg").is_err());
+        assert!(parse_voice_metadata(None, Some("AAEC"), "audio/ogg").is_err());
+    }
+
+    #[test]
+    fn prefetch_ids_are_numeric_unique_and_capped() {
+        let content = json!({
+            "attachments": [
+                { "id": "42" },
+                { "id": "42" },
+                { "id": "../etc" },
+                { "id": 7 },
+                {},
+                { "id": "43" },
+            ]
+        });
+        assert_eq!(prefetch_attachment_ids(&content), vec!["42", "43"]);
+        assert!(prefetch_attachment_ids(&json!({ "body": "hi" })).is_empty());
+
+        let many: Vec<_> = (0..50).map(|i| json!({ "id": i.to_string() })).collect();
+        assert_eq!(
+            prefetch_attachment_ids(&json!({ "attachments": many })).len(),
+            MAX_PREFETCH_ATTACHMENTS
+        );
+    }
+
+    #[test]
+    fn cache_keys_are_content_addressed() {
+        assert_eq!(
+            federated_cache_storage_key("abc123"),
+            "fed-cache/sha256/abc123"
+        );
+    }
 }
@@ -1,16 +1,20 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
 use paracord_core::AppState;
+use paracord_media::images::{icon_source_key, icon_variant_key};
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
-use crate::routes::audit;
+use crate::routes::{audit, image_variants};
 
 const MAX_GUILD_DESCRIPTION_LEN: usize = 1_024;
 
@@ -23,13 +27,13 @@ fn contains_dangerous_markup(value: &str) -> bool {
         || lower.contains("<iframe")
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateGuildRequest {
     pub name: String,
     pub icon: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateGuildRequest {
     pub name: Option<String>,
     pub description: Option<String>,
@@ -38,11 +42,19 @@ pub struct UpdateGuildRequest {
     pub bot_settings: Option<Value>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TransferOwnershipRequest {
     pub new_owner_id: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds",
+    tag = "guilds",
+    request_body = CreateGuildRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_guild(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -54,7 +66,7 @@ pub async fn create_guild(
         ));
     }
 
-    let guild_id = paracord_util::snowflake::generate(1);
+    let guild_id = paracord_util::snowflake::try_next_id().await?;
 
     let guild = paracord_core::guild::create_guild_full(
         &state.db,
@@ -85,6 +97,14 @@ pub async fn create_guild(
     Ok((StatusCode::CREATED, Json(guild_json)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/guilds",
+    tag = "guilds",
+    operation_id = "guilds_list_guilds",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guilds(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -112,6 +132,14 @@ pub async fn list_guilds(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_guild(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -122,7 +150,7 @@ pub async fn get_guild(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let member_count = paracord_db::members::get_member_count(&state.db, guild_id)
         .await
@@ -141,6 +169,138 @@ pub async fn get_guild(
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/icon",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn upload_guild_icon(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let image_data =
+        image_variants::read_image_upload(&mut multipart, &["icon", "image", "file"], "Icon")
+            .await?;
+    let icon_hash = image_variants::store_image_variants(
+        &state,
+        image_data,
+        |hash| icon_source_key(guild_id, hash),
+        |hash, size, format| icon_variant_key(guild_id, hash, size, format),
+    )
+    .await?;
+
+    let previous_hash = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .and_then(|guild| guild.icon_hash);
+
+    let updated = paracord_core::guild::update_guild(
+        &state.db,
+        guild_id,
+        auth.user_id,
+        None,
+        None,
+        Some(&icon_hash),
+        None,
+        None,
+    )
+    .await?;
+
+    if let Some(previous_hash) =
+        previous_hash.filter(|hash| *hash != icon_hash && image_variants::is_generated_hash(hash))
+    {
+        image_variants::delete_image_variants(
+            &state,
+            &previous_hash,
+            |hash| icon_source_key(guild_id, hash),
+            |hash, size, format| icon_variant_key(guild_id, hash, size, format),
+        )
+        .await;
+    }
+
+    let guild_json = json!({
+        "id": updated.id.to_string(),
+        "name": updated.name,
+        "description": updated.description,
+        "icon_hash": updated.icon_hash,
+        "owner_id": updated.owner_id.to_string(),
+        "created_at": updated.created_at.to_rfc3339(),
+        "hub_settings": updated.hub_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+        "bot_settings": updated.bot_settings.as_deref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+    });
+
+    state
+        .event_bus
+        .dispatch("GUILD_UPDATE", guild_json.clone(), Some(guild_id));
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_GUILD_UPDATE,
+        Some(guild_id),
+        None,
+        Some(json!({ "icon_hash": updated.icon_hash })),
+    )
+    .await;
+
+    Ok(Json(guild_json))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct GuildIconQuery {
+    pub size: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/icon",
+    tag = "guilds",
+    params(("guild_id" = i64, Path), GuildIconQuery),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn get_guild_icon(
+    State(state): State<AppState>,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<GuildIconQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let hash = guild.icon_hash.ok_or(ApiError::NotFound)?;
+    if !image_variants::is_generated_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    image_variants::serve_image_variant(
+        &state,
+        &hash,
+        params.size,
+        &headers,
+        |hash| icon_source_key(guild_id, hash),
+        |hash, size, format| icon_variant_key(guild_id, hash, size, format),
+    )
+    .await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}",
+    tag = "guilds",
+    operation_id = "guilds_update_guild",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateGuildRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_guild(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -211,19 +371,32 @@ pub async fn update_guild(
     Ok(Json(guild_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}",
+    tag = "guilds",
+    operation_id = "guilds_delete_guild",
+    params(("guild_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_guild(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    paracord_core::guild::delete_guild(&state.db, guild_id, auth.user_id).await?;
+    let deleted_at = paracord_core::guild::delete_guild(&state.db, guild_id, auth.user_id).await?;
 
     state.member_index.remove_guild(guild_id);
-    state.event_bus.dispatch(
-        "GUILD_DELETE",
+    let payload = paracord_core::tombstone::with_tombstone(
+        &state,
         json!({"id": guild_id.to_string()}),
-        Some(guild_id),
-    );
+        deleted_at,
+    )
+    .await;
+    state
+        .event_bus
+        .dispatch("GUILD_DELETE", payload, Some(guild_id));
     audit::log_action(
         &state,
         guild_id,
@@ -238,6 +411,15 @@ pub async fn delete_guild(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/owner",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = TransferOwnershipRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn transfer_ownership(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -251,7 +433,7 @@ pub async fn transfer_ownership(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     if guild.owner_id != auth.user_id {
         return Err(ApiError::Forbidden);
     }
@@ -287,13 +469,22 @@ pub async fn transfer_ownership(
     Ok(Json(payload))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ChannelPositionEntry {
     pub id: String,
     pub position: i32,
     pub parent_id: Option<String>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/channels",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = Vec<ChannelPositionEntry>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_channel_positions(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -314,7 +505,7 @@ pub async fn update_channel_positions(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -360,23 +551,42 @@ pub async fn update_channel_positions(
     Ok(Json(json!({ "updated": changed.len() })))
 }
 
+#[derive(Deserialize, IntoParams)]
+pub struct GetChannelsQuery {
+    /// List archived channels instead of the active ones.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/channels",
+    tag = "guilds",
+    params(("guild_id" = i64, Path), GetChannelsQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_channels(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
+    Query(params): Query<GetChannelsQuery>,
 ) -> Result<Json<Value>, ApiError> {
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     let mut result: Vec<Value> = Vec::with_capacity(channels.len());
-    for c in channels {
+    for c in channels
+        .into_iter()
+        .filter(|c| c.archived_at.is_some() == params.archived)
+    {
         let perms = paracord_core::permissions::compute_channel_permissions(
             &state.db,
             guild_id,
@@ -406,15 +616,278 @@ pub async fn get_channels(
             "rate_limit_per_user": c.rate_limit_per_user,
             "last_message_id": c.last_message_id.map(|id| id.to_string()),
             "required_role_ids": required_role_ids,
+            "posting_mode": c.posting_mode,
+            "archived_at": c.archived_at.map(|at| at.to_rfc3339()),
         }));
     }
 
     Ok(Json(json!(result)))
 }
 
+const SYNC_DEFAULT_MESSAGES_PER_CHANNEL: i64 = 50;
+const SYNC_MAX_MESSAGES_PER_CHANNEL: i64 = 100;
+
+#[derive(Deserialize, IntoParams)]
+pub struct GuildSyncQuery {
+    /// Highest message ID the client already has; omit for a cold start.
+    pub after: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Delta sync for clients with a local cache: the guild, its visible channels,
+/// and per-channel messages newer than `after` (oldest first). Channels listed
+/// in `truncated` had more new messages than `limit`; the client should drop
+/// its cached history for them and page normally.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/sync",
+    tag = "guilds",
+    params(("guild_id" = i64, Path), GuildSyncQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn sync_guild(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<GuildSyncQuery>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let member_count = paracord_db::members::get_member_count(&state.db, guild_id)
+        .await
+        .unwrap_or(0);
+    let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let age_gate_acknowledged =
+        paracord_db::users::get_age_gate_acknowledged_at(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some();
+    let nsfw_channel_ids: std::collections::HashSet<i64> =
+        channels.iter().filter(|c| c.nsfw).map(|c| c.id).collect();
+
+    let limit = params
+        .limit
+        .unwrap_or(SYNC_DEFAULT_MESSAGES_PER_CHANNEL)
+        .clamp(1, SYNC_MAX_MESSAGES_PER_CHANNEL);
+    let mut cursor = params.after.unwrap_or(0);
+    let mut channel_json = Vec::with_capacity(channels.len());
+    let mut messages = serde_json::Map::new();
+    let mut truncated = Vec::new();
+    for channel in channels.iter().filter(|c| c.archived_at.is_none()) {
+        let perms = paracord_core::permissions::compute_channel_permissions(
+            &state.db,
+            guild_id,
+            channel.id,
+            guild.owner_id,
+            auth.user_id,
+        )
+        .await?;
+        if !perms.contains(Permissions::VIEW_CHANNEL) {
+            continue;
+        }
+        channel_json.push(crate::routes::channels::channel_to_json(channel));
+
+        let has_new = channel
+            .last_message_id
+            .is_some_and(|last| params.after.is_none_or(|after| last > after));
+        if !has_new || !perms.contains(Permissions::READ_MESSAGE_HISTORY) {
+            continue;
+        }
+        // Threads inherit NSFW from their parent, as in `is_channel_nsfw`.
+        let nsfw = nsfw_channel_ids.contains(&channel.id)
+            || (channel.channel_type == 6
+                && channel
+                    .parent_id
+                    .is_some_and(|parent| nsfw_channel_ids.contains(&parent)));
+        if nsfw && !age_gate_acknowledged {
+            continue;
+        }
+        // Fetch one extra row to tell whether the delta was cut short.
+        let mut rows = match params.after {
+            Some(after) => paracord_db::messages::get_channel_messages(
+                &state.db,
+                channel.id,
+                None,
+                Some(after),
+                limit + 1,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?,
+            None => {
+                let mut latest = paracord_db::messages::get_channel_messages(
+                    &state.db, channel.id, None, None, limit,
+                )
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+                latest.reverse();
+                latest
+            }
+        };
+        if rows.len() as i64 > limit {
+            // Keep the newest page so the client's view ends at the present.
+            rows.drain(..rows.len() - limit as usize);
+            truncated.push(channel.id.to_string());
+        }
+        cursor = rows.iter().map(|row| row.id).fold(cursor, i64::max);
+        let channel_messages =
+            crate::routes::channels::messages_to_json(&state, &rows, auth.user_id).await;
+        if !channel_messages.is_empty() {
+            messages.insert(channel.id.to_string(), Value::Array(channel_messages));
+        }
+    }
+
+    Ok(Json(json!({
+        "guild": {
+            "id": guild.id.to_string(),
+            "name": guild.name,
+            "description": guild.description,
+            "icon_hash": guild.icon_hash,
+            "owner_id": guild.owner_id.to_string(),
+            "member_count": member_count,
+            "created_at": guild.created_at.to_rfc3339(),
+        },
+        "channels": channel_json,
+        "messages": messages,
+        "truncated": truncated,
+        "cursor": (cursor > 0).then(|| cursor.to_string()),
+    })))
+}
+
+/// Search every channel of the guild the caller may read (`VIEW_CHANNEL` and
+/// `READ_MESSAGE_HISTORY` after overwrites; NSFW channels only once the age
+/// gate is acknowledged). Takes the same filters as channel search. Results
+/// keep their rank order and are grouped by channel, each group placed at
+/// its best-ranked hit.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/messages/search",
+    tag = "guilds",
+    params(("guild_id" = i64, Path), crate::routes::channels::MessageSearchQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn search_guild_messages(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<crate::routes::channels::MessageSearchQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let (query, order) = crate::routes::channels::parse_search_params(&params)?;
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms = paracord_core::permissions::compute_all_channel_permissions(
+        &state.db,
+        guild_id,
+        &channels,
+        guild.owner_id,
+        auth.user_id,
+    )
+    .await?;
+    let age_gate_acknowledged =
+        paracord_db::users::get_age_gate_acknowledged_at(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some();
+    let nsfw_channel_ids: std::collections::HashSet<i64> =
+        channels.iter().filter(|c| c.nsfw).map(|c| c.id).collect();
+    let readable: std::collections::HashMap<i64, &paracord_db::channels::ChannelRow> = channels
+        .iter()
+        .filter(|channel| {
+            perms.get(&channel.id).is_some_and(|perms| {
+                perms.contains(Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY)
+            })
+        })
+        .filter(|channel| {
+            // Threads inherit NSFW from their parent, as in `is_channel_nsfw`.
+            let nsfw = nsfw_channel_ids.contains(&channel.id)
+                || (channel.channel_type == 6
+                    && channel
+                        .parent_id
+                        .is_some_and(|parent| nsfw_channel_ids.contains(&parent)));
+            age_gate_acknowledged || !nsfw
+        })
+        .map(|channel| (channel.id, channel))
+        .collect();
+
+    // `in:` narrows the search; channels the caller can't read look the same
+    // as ones that don't exist.
+    let channel_ids: Vec<i64> = if query.channel_ids.is_empty() {
+        readable.keys().copied().collect()
+    } else {
+        if query
+            .channel_ids
+            .iter()
+            .any(|id| !readable.contains_key(id))
+        {
+            return Err(ApiError::Code(ErrorCode::UnknownChannel));
+        }
+        query.channel_ids.clone()
+    };
+    let limit = params
+        .limit
+        .unwrap_or(crate::routes::channels::SEARCH_DEFAULT_LIMIT)
+        .clamp(1, crate::routes::channels::SEARCH_MAX_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+    let search = paracord_db::search::MessageSearch {
+        text: query.text,
+        channel_ids,
+        author_ids: query.author_ids,
+        has_attachment: query.has_attachment,
+        before: query.before,
+        after: query.after,
+        order,
+        // One extra row tells whether there is another page.
+        limit: limit + 1,
+        offset,
+    };
+    let mut rows = paracord_db::search::search_messages(&state.db, &search)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let mut groups: Vec<(i64, Vec<paracord_db::messages::MessageRow>)> = Vec::new();
+    for row in rows {
+        match groups.iter_mut().find(|(id, _)| *id == row.channel_id) {
+            Some((_, group)) => group.push(row),
+            None => groups.push((row.channel_id, vec![row])),
+        }
+    }
+    let mut results = Vec::with_capacity(groups.len());
+    for (channel_id, group) in groups {
+        let messages =
+            crate::routes::channels::messages_to_json(&state, &group, auth.user_id).await;
+        results.push(json!({
+            "channel": readable
+                .get(&channel_id)
+                .map(|channel| crate::routes::channels::channel_to_json(channel)),
+            "messages": messages,
+        }));
+    }
+
+    Ok(Json(json!({
+        "results": results,
+        "offset": offset,
+        "has_more": has_more,
+    })))
+}
+
 // ── Guild Storage ────────────────────────────────────────────────────────
 
-async fn require_manage_guild(
+pub(crate) async fn require_manage_guild(
     state: &AppState,
     guild_id: i64,
     user_id: i64,
@@ -423,7 +896,7 @@ async fn require_manage_guild(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -433,6 +906,14 @@ async fn require_manage_guild(
     Ok(())
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/storage",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_storage(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -472,7 +953,7 @@ pub async fn get_storage(
     })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateStorageRequest {
     pub max_file_size: Option<i64>,
     pub storage_quota: Option<i64>,
@@ -481,6 +962,15 @@ pub struct UpdateStorageRequest {
     pub blocked_types: Option<Vec<String>>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/storage",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateStorageRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_storage(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -551,12 +1041,113 @@ pub async fn update_storage(
     })))
 }
 
-#[derive(Deserialize)]
+fn thread_policy_to_json(
+    policy: &paracord_db::guild_thread_policies::GuildThreadPolicyRow,
+) -> Value {
+    json!({
+        "guild_id": policy.guild_id.to_string(),
+        "max_active_threads": policy.max_active_threads,
+        "default_auto_archive_duration": policy.default_auto_archive_duration,
+        "unarchive_on_message": policy.unarchive_on_message,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/thread-policy",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_thread_policy(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let policy =
+        paracord_db::guild_thread_policies::get_effective_guild_thread_policy(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let active_threads = paracord_db::channels::count_active_guild_threads(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let mut payload = thread_policy_to_json(&policy);
+    payload["active_threads"] = json!(active_threads);
+    Ok(Json(payload))
+}
+
+/// Replaces the whole policy; omitted limits are cleared.
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateThreadPolicyRequest {
+    pub max_active_threads: Option<i32>,
+    pub default_auto_archive_duration: Option<i32>,
+    pub unarchive_on_message: Option<bool>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/thread-policy",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateThreadPolicyRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_thread_policy(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateThreadPolicyRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    if body.max_active_threads.is_some_and(|limit| {
+        !(1..=paracord_core::thread::MAX_ACTIVE_THREADS_LIMIT).contains(&limit)
+    }) {
+        return Err(ApiError::BadRequest(format!(
+            "max_active_threads must be between 1 and {}",
+            paracord_core::thread::MAX_ACTIVE_THREADS_LIMIT
+        )));
+    }
+    if body.default_auto_archive_duration.is_some_and(|minutes| {
+        !paracord_core::thread::is_valid_auto_archive_duration(i64::from(minutes))
+    }) {
+        return Err(ApiError::BadRequest(
+            "default_auto_archive_duration must be one of 60, 1440, 4320 or 10080".into(),
+        ));
+    }
+
+    let policy = paracord_db::guild_thread_policies::upsert_guild_thread_policy(
+        &state.db,
+        guild_id,
+        body.max_active_threads,
+        body.default_auto_archive_duration,
+        body.unarchive_on_message.unwrap_or(true),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    Ok(Json(thread_policy_to_json(&policy)))
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct ListFilesParams {
     pub before: Option<i64>,
     pub limit: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/files",
+    tag = "guilds",
+    params(("guild_id" = i64, Path), ListFilesParams),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_files(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -596,11 +1187,20 @@ pub async fn list_files(
     Ok(Json(json!(files)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct DeleteFilesRequest {
     pub attachment_ids: Vec<String>,
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/files",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = DeleteFilesRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_files(
     State(state): State<AppState>,
     auth: AuthUser,
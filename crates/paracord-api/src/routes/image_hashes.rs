@@ -0,0 +1,315 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use paracord_core::hash_matching::{self, HashMatchingSettings};
+use paracord_core::AppState;
+use paracord_media::images::AssetImageType;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AdminUser;
+use crate::routes::security;
+
+const MAX_REASON_LEN: usize = 200;
+/// Hashes accepted in one request; larger lists are imported in batches.
+const MAX_HASHES_PER_REQUEST: usize = 10_000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddImageHashesRequest {
+    /// Perceptual hashes as 16 hex digits.
+    pub hashes: Vec<String>,
+    pub reason: Option<String>,
+}
+
+fn entry_to_json(entry: &paracord_db::image_hashes::ImageHashRow) -> Value {
+    json!({
+        "hash": entry.hash,
+        "reason": entry.reason,
+        "created_by": entry.created_by.map(|id| id.to_string()),
+        "created_at": entry.created_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/image-hashes",
+    tag = "image_hashes",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_entries(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, ApiError> {
+    let entries = paracord_db::image_hashes::list_entries(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let runtime = state.runtime.read().await;
+    let settings = &runtime.image_hash_matching;
+    Ok(Json(json!({
+        "entries": entries.iter().map(entry_to_json).collect::<Vec<_>>(),
+        "enabled": settings.enabled,
+        "max_distance": settings.max_distance,
+        "service_configured": settings.service_url.is_some(),
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/image-hashes",
+    tag = "image_hashes",
+    request_body = AddImageHashesRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn add_entries(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Json(body): Json<AddImageHashesRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if body.hashes.is_empty() || body.hashes.len() > MAX_HASHES_PER_REQUEST {
+        return Err(ApiError::BadRequest(format!(
+            "Provide between 1 and {MAX_HASHES_PER_REQUEST} hashes"
+        )));
+    }
+    let mut hashes = Vec::with_capacity(body.hashes.len());
+    for raw in &body.hashes {
+        let hash = hash_matching::parse_hash(raw)
+            .ok_or_else(|| ApiError::BadRequest(format!("Invalid image hash: {raw:?}")))?;
+        hashes.push(hash_matching::format_hash(hash));
+    }
+    hashes.sort();
+    hashes.dedup();
+    let reason = body
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+    if reason.is_some_and(|reason| reason.chars().count() > MAX_REASON_LEN) {
+        return Err(ApiError::BadRequest(
+            "Reason must be at most 200 characters".into(),
+        ));
+    }
+
+    paracord_db::image_hashes::upsert_entries(&state.db, &hashes, reason, admin.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let total = hash_matching::reload_image_hashes(&state).await?;
+
+    security::log_security_event(
+        &state,
+        "admin.image_hashes.add",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "count": hashes.len(), "reason": reason })),
+    )
+    .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "added": hashes.len(), "total": total })),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/image-hashes/{hash}",
+    tag = "image_hashes",
+    params(("hash" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn remove_entry(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(hash): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let hash = hash_matching::parse_hash(&hash)
+        .map(hash_matching::format_hash)
+        .ok_or(ApiError::NotFound)?;
+    let removed = paracord_db::image_hashes::delete_entry(&state.db, &hash)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+    hash_matching::reload_image_hashes(&state).await?;
+
+    security::log_security_event(
+        &state,
+        "admin.image_hashes.remove",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "hash": &hash })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ServiceVerdict {
+    #[serde(rename = "match")]
+    matched: bool,
+    reason: Option<String>,
+}
+
+/// What flagged an upload.
+enum UploadMatch {
+    List { hash: u64, distance: u32 },
+    Service { reason: Option<String> },
+}
+
+/// Screen an image upload when hash matching is enabled. Matches are
+/// recorded as security events and the upload is rejected; the file itself
+/// is never stored.
+pub(crate) async fn screen_upload(
+    state: &AppState,
+    headers: Option<&HeaderMap>,
+    data: &[u8],
+    content_hash: &str,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    let (settings, list) = {
+        let runtime = state.runtime.read().await;
+        (
+            runtime.image_hash_matching.clone(),
+            runtime.image_hash_list.clone(),
+        )
+    };
+    if !settings.enabled {
+        return Ok(());
+    }
+    let Some(image_type) = AssetImageType::sniff(data) else {
+        return Ok(());
+    };
+
+    let owned = data.to_vec();
+    let phash =
+        tokio::task::spawn_blocking(move || paracord_media::images::perceptual_hash(&owned).ok())
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let hit = match phash.and_then(|phash| list.closest(phash, settings.max_distance)) {
+        Some(hit) => Some(UploadMatch::List {
+            hash: hit.hash,
+            distance: hit.distance,
+        }),
+        None => {
+            query_service(
+                &settings,
+                data,
+                image_type.content_type(),
+                content_hash,
+                phash,
+            )
+            .await?
+        }
+    };
+    let Some(hit) = hit else {
+        return Ok(());
+    };
+
+    let mut details = match &hit {
+        UploadMatch::List { hash, distance } => json!({
+            "source": "list",
+            "matched_hash": hash_matching::format_hash(*hash),
+            "distance": distance,
+        }),
+        UploadMatch::Service { reason } => json!({
+            "source": "service",
+            "reason": reason,
+        }),
+    };
+    details["channel_id"] = json!(channel_id.to_string());
+    details["sha256"] = json!(content_hash);
+    details["phash"] = json!(phash.map(hash_matching::format_hash));
+    tracing::warn!(
+        "Image upload by user {} in channel {} matched the hash blocklist",
+        user_id,
+        channel_id
+    );
+    security::log_security_event(
+        state,
+        "upload.hash_matched",
+        Some(user_id),
+        None,
+        None,
+        headers,
+        Some(details),
+    )
+    .await;
+
+    Err(ApiError::BadRequest(
+        "File upload blocked by content matching policy".into(),
+    ))
+}
+
+/// Ask the external service about an image. `Ok(None)` means no match,
+/// including when no service is configured.
+async fn query_service(
+    settings: &HashMatchingSettings,
+    data: &[u8],
+    content_type: &str,
+    content_hash: &str,
+    phash: Option<u64>,
+) -> Result<Option<UploadMatch>, ApiError> {
+    let Some(url) = settings.service_url.as_deref() else {
+        return Ok(None);
+    };
+    let verdict = async {
+        let client = reqwest::Client::builder()
+            .timeout(settings.service_timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header("X-Paracord-Sha256", content_hash)
+            .body(data.to_vec());
+        if let Some(phash) = phash {
+            request = request.header("X-Paracord-Phash", hash_matching::format_hash(phash));
+        }
+        if let Some(token) = settings.service_token.as_deref() {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("status {}", response.status()));
+        }
+        response
+            .json::<ServiceVerdict>()
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .await;
+
+    match verdict {
+        Ok(verdict) if verdict.matched => Ok(Some(UploadMatch::Service {
+            reason: verdict.reason,
+        })),
+        Ok(_) => Ok(None),
+        Err(err) if settings.fail_closed => {
+            tracing::warn!("Image matching service failed: {}; upload rejected", err);
+            Err(ApiError::ServiceUnavailable(
+                "Content matching service unavailable".into(),
+            ))
+        }
+        Err(err) => {
+            tracing::warn!("Image matching service failed: {} (fail-open)", err);
+            Ok(None)
+        }
+    }
+}
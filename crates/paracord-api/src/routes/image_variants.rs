@@ -0,0 +1,182 @@
+//! Storing and serving resized avatar and icon variants.
+//!
+//! User avatars and guild icons share one pipeline and differ only in where
+//! their files live, so each caller passes its own storage key functions.
+
+use axum::{
+    extract::Multipart,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use paracord_core::AppState;
+use paracord_media::images::{normalize_avatar_size, VariantFormat, AVATAR_SIZES};
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+const MAX_IMAGE_UPLOAD_SIZE: usize = 8 * 1024 * 1024; // 8 MB
+
+/// Read the first image field from a multipart upload. `label` names the
+/// image in error messages ("Avatar", "Icon").
+pub(crate) async fn read_image_upload(
+    multipart: &mut Multipart,
+    fields: &[&str],
+    label: &str,
+) -> Result<Vec<u8>, ApiError> {
+    let mut image_data: Option<Vec<u8>> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        if field.name().is_some_and(|name| fields.contains(&name)) {
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            image_data = Some(data.to_vec());
+            break;
+        }
+    }
+
+    let lowercase = label.to_lowercase();
+    let image_data =
+        image_data.ok_or_else(|| ApiError::BadRequest(format!("Missing {lowercase} image")))?;
+    if image_data.is_empty() {
+        return Err(ApiError::BadRequest(format!("Empty {lowercase} image")));
+    }
+    if image_data.len() > MAX_IMAGE_UPLOAD_SIZE {
+        return Err(ApiError::BadRequest(format!(
+            "{label} image must be under 8 MB"
+        )));
+    }
+    Ok(image_data)
+}
+
+/// Resize an upload to every variant, store them, and return the image hash.
+pub(crate) async fn store_image_variants(
+    state: &AppState,
+    image_data: Vec<u8>,
+    source_key: impl Fn(&str) -> String,
+    variant_key: impl Fn(&str, u32, VariantFormat) -> String,
+) -> Result<String, ApiError> {
+    let mut hasher = Sha256::new();
+    hasher.update(&image_data);
+    let hash = format!("{:x}", hasher.finalize())[..32].to_string();
+
+    // Decoding and resizing is CPU-bound; keep it off the async workers.
+    let (source, variants) = tokio::task::spawn_blocking(move || {
+        paracord_media::images::generate_avatar_variants(&image_data)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    state
+        .storage_backend
+        .store(&source_key(&hash), &source)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    for variant in &variants {
+        state
+            .storage_backend
+            .store(
+                &variant_key(&hash, variant.size, variant.format),
+                &variant.data,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    Ok(hash)
+}
+
+pub(crate) async fn delete_image_variants(
+    state: &AppState,
+    hash: &str,
+    source_key: impl Fn(&str) -> String,
+    variant_key: impl Fn(&str, u32, VariantFormat) -> String,
+) {
+    let _ = state.storage_backend.delete(&source_key(hash)).await;
+    for size in AVATAR_SIZES {
+        for format in [VariantFormat::Png, VariantFormat::WebP] {
+            let _ = state
+                .storage_backend
+                .delete(&variant_key(hash, size, format))
+                .await;
+        }
+    }
+}
+
+/// Only server-generated hashes have variants on disk; legacy clients stored
+/// arbitrary strings (e.g. data URLs) in the hash column.
+pub(crate) fn is_generated_hash(hash: &str) -> bool {
+    hash.len() == 32 && hash.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+/// Serve the variant closest to `size` in the format the `Accept` header
+/// allows, regenerating it from the stored source if it has gone missing.
+pub(crate) async fn serve_image_variant(
+    state: &AppState,
+    hash: &str,
+    size: Option<u32>,
+    headers: &HeaderMap,
+    source_key: impl Fn(&str) -> String,
+    variant_key: impl Fn(&str, u32, VariantFormat) -> String,
+) -> Result<Response, ApiError> {
+    let size = normalize_avatar_size(size);
+    let format = VariantFormat::negotiate(
+        headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let etag = format!("\"{}-{}.{}\"", hash, size, format.extension());
+
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if if_none_match.is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag)) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::VARY, "Accept".to_string())],
+        )
+            .into_response());
+    }
+
+    let key = variant_key(hash, size, format);
+    let data = match state.storage_backend.retrieve(&key).await {
+        Ok(data) => data,
+        Err(_) => {
+            // Variant missing (older upload, or storage pruned): regenerate it
+            // from the normalized source and cache it for next time.
+            let source = state
+                .storage_backend
+                .retrieve(&source_key(hash))
+                .await
+                .map_err(|_| ApiError::NotFound)?;
+            let rendered = tokio::task::spawn_blocking(move || {
+                paracord_media::images::render_variant(&source, size, format)
+            })
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            if let Err(err) = state.storage_backend.store(&key, &rendered).await {
+                tracing::warn!("Failed caching image variant {}: {}", key, err);
+            }
+            rendered
+        }
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+            (header::ETAG, etag),
+            (header::VARY, "Accept".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
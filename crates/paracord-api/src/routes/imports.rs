@@ -0,0 +1,794 @@
+//! Guild imports from Discord and Matrix exports.
+//!
+//! Admins drop an export into `<storage_path>/imports` and queue it against
+//! an existing guild. The job recreates channels, emojis and message history
+//! there, with each original author mapped to a placeholder account that
+//! can't log in. Everything created is recorded in `import_mappings`, so a
+//! failed or repeated import picks up where it left off instead of
+//! duplicating history.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use paracord_core::error::CoreError;
+use paracord_core::import::{ImportArchive, ImportFormat, ImportMessage, ImportPlan};
+use paracord_core::AppState;
+use paracord_db::imports::{KIND_CHANNEL, KIND_EMOJI, KIND_MESSAGE, KIND_USER};
+use paracord_db::jobs::JobRow;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AdminUser;
+use crate::routes::{admin, channels, emojis, federation, files, security};
+
+/// Directory under `storage_path` that admin imports are read from.
+pub const IMPORTS_DIR: &str = "imports";
+/// Progress is saved after this many messages.
+const PROGRESS_EVERY_MESSAGES: usize = 500;
+/// Warnings kept in the job report; the rest are only counted.
+const MAX_REPORTED_WARNINGS: usize = 100;
+const MAX_CHANNEL_NAME_LEN: usize = 100;
+const MAX_CHANNEL_TOPIC_LEN: usize = 1_024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportJobPayload {
+    pub guild_id: String,
+    /// Absolute path of the archive on the server.
+    pub path: String,
+    /// Detected from the archive when unset.
+    #[serde(default)]
+    pub format: Option<ImportFormat>,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Admin who queued the import; owns imported emojis. The guild owner
+    /// is used for imports queued from the command line.
+    #[serde(default)]
+    pub requested_by: Option<String>,
+}
+
+/// Queue an import job. Callers are responsible for checking `path`.
+pub async fn enqueue_import(
+    db: &paracord_db::DbPool,
+    payload: &ImportJobPayload,
+) -> Result<JobRow, CoreError> {
+    let guild_id = payload
+        .guild_id
+        .parse::<i64>()
+        .map_err(|_| CoreError::BadRequest("Invalid guild_id".into()))?;
+    paracord_db::guilds::get_guild(db, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    let payload = serde_json::to_value(payload).map_err(|e| CoreError::Internal(e.to_string()))?;
+    paracord_core::jobs::enqueue(
+        db,
+        crate::jobs::JOB_GUILD_IMPORT,
+        &payload,
+        paracord_core::jobs::JobOptions {
+            max_attempts: 3,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct StartImportRequest {
+    pub guild_id: String,
+    /// Archive path relative to the server's imports directory.
+    pub path: String,
+    /// `discord` or `matrix`; detected when omitted.
+    pub format: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Resolve `requested` inside the imports directory, refusing anything that
+/// leaves it (including through symlinks).
+fn resolve_import_path(storage_path: &str, requested: &str) -> Result<PathBuf, ApiError> {
+    let root = Path::new(storage_path)
+        .join(IMPORTS_DIR)
+        .canonicalize()
+        .map_err(|_| {
+            ApiError::BadRequest(format!(
+                "No {IMPORTS_DIR} directory in storage; copy the archive there first"
+            ))
+        })?;
+    let resolved = root
+        .join(requested.trim())
+        .canonicalize()
+        .map_err(|_| ApiError::BadRequest("Import archive not found".into()))?;
+    if !resolved.starts_with(&root) || resolved == root {
+        return Err(ApiError::BadRequest(format!(
+            "path must name an archive inside the {IMPORTS_DIR} directory"
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Queue an import of a Discord or Matrix export into a guild. Its progress
+/// and, for dry runs, the import plan are on the returned job.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/imports",
+    tag = "imports",
+    request_body = StartImportRequest,
+    responses((status = 202, description = "Accepted", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn start_import(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Json(body): Json<StartImportRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let format = match body.format.as_deref() {
+        Some(raw) => Some(ImportFormat::parse(raw).ok_or_else(|| {
+            ApiError::BadRequest("Unknown format; expected discord or matrix".into())
+        })?),
+        None => None,
+    };
+    let path = resolve_import_path(&state.config.storage_path, &body.path)?;
+    let payload = ImportJobPayload {
+        guild_id: body.guild_id,
+        path: path.to_string_lossy().into_owned(),
+        format,
+        dry_run: body.dry_run,
+        requested_by: Some(admin.user_id.to_string()),
+    };
+    let job = enqueue_import(&state.db, &payload).await?;
+
+    security::log_security_event(
+        &state,
+        "admin.import.start",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({
+            "job_id": job.id.to_string(),
+            "guild_id": payload.guild_id,
+            "path": body.path,
+            "dry_run": payload.dry_run,
+        })),
+    )
+    .await;
+
+    Ok((StatusCode::ACCEPTED, Json(admin::job_to_json(&job))))
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ImportCounts {
+    users: usize,
+    channels: usize,
+    emojis: usize,
+    messages: usize,
+    attachments: usize,
+}
+
+/// Progress stored on the job while it runs and as its final report.
+#[derive(Debug, Serialize)]
+struct ImportReport {
+    stage: &'static str,
+    dry_run: bool,
+    plan: Value,
+    created: ImportCounts,
+    /// Already imported by an earlier attempt, or not importable (emojis
+    /// with a clashing name or bad image, attachments kept as links).
+    skipped: ImportCounts,
+    warnings: Vec<String>,
+    warning_count: usize,
+}
+
+impl ImportReport {
+    fn warn(&mut self, warning: String) {
+        self.warning_count += 1;
+        if self.warnings.len() < MAX_REPORTED_WARNINGS {
+            self.warnings.push(warning);
+        }
+    }
+
+    async fn save(&self, state: &AppState, job_id: i64) -> Result<(), String> {
+        let progress = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        paracord_db::jobs::set_job_progress(&state.db, job_id, &progress)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) async fn run_import_job(state: AppState, job: JobRow) -> Result<(), String> {
+    let payload: ImportJobPayload = paracord_core::jobs::payload(&job)?;
+    let guild_id = payload
+        .guild_id
+        .parse::<i64>()
+        .map_err(|_| "Invalid guild_id".to_string())?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Guild {guild_id} no longer exists"))?;
+    let creator_id = payload
+        .requested_by
+        .as_deref()
+        .and_then(|id| id.parse::<i64>().ok())
+        .unwrap_or(guild.owner_id);
+
+    let path = PathBuf::from(&payload.path);
+    let requested_format = payload.format;
+    let (archive, plan) = tokio::task::spawn_blocking(move || {
+        let archive = ImportArchive::open(&path)?;
+        let format = match requested_format {
+            Some(format) => format,
+            None => paracord_core::import::detect_format(&archive).ok_or_else(|| {
+                CoreError::BadRequest("archive is not a Discord or Matrix export".into())
+            })?,
+        };
+        let plan = paracord_core::import::parse(&archive, format)?;
+        Ok::<_, CoreError>((archive, plan))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    let archive = Arc::new(archive);
+
+    let mut report = ImportReport {
+        stage: "users",
+        dry_run: payload.dry_run,
+        plan: plan.summary(),
+        created: ImportCounts::default(),
+        skipped: ImportCounts::default(),
+        warnings: Vec::new(),
+        warning_count: 0,
+    };
+    for warning in &plan.warnings {
+        report.warn(warning.clone());
+    }
+    if payload.dry_run {
+        report.stage = "complete";
+        return report.save(&state, job.id).await;
+    }
+    report.save(&state, job.id).await?;
+
+    let mut authors = HashMap::new();
+    for author in plan.authors.values() {
+        let (user_id, created) = ensure_author(&state, guild_id, author).await?;
+        if created {
+            report.created.users += 1;
+        } else {
+            report.skipped.users += 1;
+        }
+        authors.insert(author.source_id.clone(), user_id);
+    }
+
+    report.stage = "emojis";
+    report.save(&state, job.id).await?;
+    import_emojis(&state, &archive, &plan, guild_id, creator_id, &mut report).await?;
+
+    report.stage = "messages";
+    report.save(&state, job.id).await?;
+    for channel in &plan.channels {
+        let channel_id = ensure_channel(&state, guild_id, channel, &mut report).await?;
+        let ids = message_ids(plan.format, &channel.messages);
+        for (index, message) in channel.messages.iter().enumerate() {
+            let Some(&author_id) = authors.get(&message.author_id) else {
+                continue;
+            };
+            let message_id = ids.as_ref().map(|ids| ids[index]);
+            import_one_message(
+                &state,
+                &archive,
+                guild_id,
+                channel_id,
+                author_id,
+                message,
+                message_id,
+                &mut report,
+            )
+            .await?;
+            if (index + 1) % PROGRESS_EVERY_MESSAGES == 0 {
+                report.save(&state, job.id).await?;
+            }
+        }
+        report.save(&state, job.id).await?;
+    }
+
+    report.stage = "complete";
+    report.save(&state, job.id).await?;
+    tracing::info!(
+        "Import into guild {} finished: {} channels, {} messages, {} users",
+        guild_id,
+        report.created.channels,
+        report.created.messages,
+        report.created.users
+    );
+    Ok(())
+}
+
+/// Local placeholder account for an imported author, created on first use.
+async fn ensure_author(
+    state: &AppState,
+    guild_id: i64,
+    author: &paracord_core::import::ImportAuthor,
+) -> Result<(i64, bool), String> {
+    if let Some(user_id) =
+        paracord_db::imports::get_import_mapping(&state.db, guild_id, KIND_USER, &author.source_id)
+            .await
+            .map_err(|e| e.to_string())?
+    {
+        return Ok((user_id, false));
+    }
+
+    let digest = paracord_federation::transport::sha256_hex(
+        format!("{guild_id}:{}", author.source_id).as_bytes(),
+    );
+    let email = format!("import+{}@import.invalid", &digest[..24]);
+    // An earlier attempt may have created the account but not the mapping.
+    let user_id = match paracord_db::users::get_user_by_email(&state.db, &email)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Some(existing) => existing.id,
+        None => {
+            let username = format!(
+                "{}_{}",
+                federation::sanitize_remote_username(&author.name, "imported"),
+                &digest[..6]
+            );
+            let user = paracord_db::users::create_user(
+                &state.db,
+                paracord_util::snowflake::try_next_id()
+                    .await
+                    .map_err(|e| e.to_string())?,
+                &username,
+                0,
+                &email,
+                "!imported!",
+            )
+            .await
+            .map_err(|e| format!("failed to create placeholder for {}: {e}", author.name))?;
+            if author.bot {
+                paracord_db::users::update_user_flags(
+                    &state.db,
+                    user.id,
+                    user.flags | paracord_core::USER_FLAG_BOT,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            user.id
+        }
+    };
+    paracord_db::imports::set_import_mapping(
+        &state.db,
+        guild_id,
+        KIND_USER,
+        &author.source_id,
+        user_id,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((user_id, true))
+}
+
+async fn import_emojis(
+    state: &AppState,
+    archive: &Arc<ImportArchive>,
+    plan: &ImportPlan,
+    guild_id: i64,
+    creator_id: i64,
+    report: &mut ImportReport,
+) -> Result<(), String> {
+    let mut taken: Vec<String> = paracord_db::emojis::get_guild_emojis(&state.db, guild_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|emoji| emoji.name)
+        .collect();
+    for emoji in &plan.emojis {
+        if paracord_db::imports::get_import_mapping(
+            &state.db,
+            guild_id,
+            KIND_EMOJI,
+            &emoji.source_id,
+        )
+        .await
+        .map_err(|e| e.to_string())?
+        .is_some()
+        {
+            report.skipped.emojis += 1;
+            continue;
+        }
+        let name: String = emoji
+            .name
+            .chars()
+            .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '_')
+            .take(emojis::MAX_EMOJI_NAME_LEN)
+            .collect();
+        if name.is_empty() || taken.contains(&name) {
+            report.skipped.emojis += 1;
+            report.warn(format!(
+                "emoji :{}: skipped: name is empty or already used",
+                emoji.name
+            ));
+            continue;
+        }
+        let data = match read_archive_file(
+            archive,
+            &emoji.path,
+            emojis::MAX_EMOJI_IMAGE_SIZE as u64,
+        )
+        .await
+        {
+            Ok(data) => data,
+            Err(err) => {
+                report.skipped.emojis += 1;
+                report.warn(format!("emoji :{name}: skipped: {err}"));
+                continue;
+            }
+        };
+        let (animated, ext) = if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            (true, "gif")
+        } else if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            (false, "png")
+        } else {
+            report.skipped.emojis += 1;
+            report.warn(format!("emoji :{name}: skipped: not a PNG or GIF image"));
+            continue;
+        };
+
+        let emoji_id = paracord_util::snowflake::try_next_id()
+            .await
+            .map_err(|e| e.to_string())?;
+        let storage_dir = Path::new(&state.config.storage_path).join("emojis");
+        tokio::fs::create_dir_all(&storage_dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(storage_dir.join(format!("{emoji_id}.{ext}")), &data)
+            .await
+            .map_err(|e| e.to_string())?;
+        let created = paracord_db::emojis::create_emoji(
+            &state.db, emoji_id, guild_id, &name, creator_id, animated,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        paracord_db::imports::set_import_mapping(
+            &state.db,
+            guild_id,
+            KIND_EMOJI,
+            &emoji.source_id,
+            emoji_id,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        state.event_bus.dispatch(
+            "GUILD_EMOJIS_UPDATE",
+            json!({
+                "guild_id": guild_id.to_string(),
+                "emoji": emojis::emoji_to_json(&created),
+            }),
+            Some(guild_id),
+        );
+        taken.push(name);
+        report.created.emojis += 1;
+    }
+    Ok(())
+}
+
+/// The text channel an imported channel's history goes into.
+async fn ensure_channel(
+    state: &AppState,
+    guild_id: i64,
+    channel: &paracord_core::import::ImportChannel,
+    report: &mut ImportReport,
+) -> Result<i64, String> {
+    if let Some(channel_id) = paracord_db::imports::get_import_mapping(
+        &state.db,
+        guild_id,
+        KIND_CHANNEL,
+        &channel.source_id,
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    {
+        report.skipped.channels += 1;
+        return Ok(channel_id);
+    }
+
+    let name: String = channel
+        .name
+        .trim()
+        .chars()
+        .take(MAX_CHANNEL_NAME_LEN)
+        .collect();
+    let name = if name.is_empty() {
+        "imported".to_string()
+    } else {
+        name
+    };
+    let position = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .len() as i32;
+    let channel_id = paracord_util::snowflake::try_next_id()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut created = paracord_db::channels::create_channel(
+        &state.db, channel_id, guild_id, &name, 0, position, None, None,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Some(topic) = channel.topic.as_deref() {
+        let topic: String = topic.chars().take(MAX_CHANNEL_TOPIC_LEN).collect();
+        created = paracord_db::channels::update_channel(
+            &state.db,
+            channel_id,
+            None,
+            Some(&topic),
+            None,
+            None,
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    paracord_db::imports::set_import_mapping(
+        &state.db,
+        guild_id,
+        KIND_CHANNEL,
+        &channel.source_id,
+        channel_id,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    state.event_bus.dispatch(
+        "CHANNEL_CREATE",
+        channels::channel_to_json(&created),
+        Some(guild_id),
+    );
+    report.created.channels += 1;
+    Ok(channel_id)
+}
+
+/// IDs that keep a channel's imported messages in their original time
+/// order: rebased Discord snowflakes, or IDs derived from the timestamp.
+/// `None` when any message predates this node's epoch; those channels get
+/// fresh IDs in chronological order instead.
+fn message_ids(format: ImportFormat, messages: &[ImportMessage]) -> Option<Vec<i64>> {
+    messages
+        .iter()
+        .map(|message| {
+            let rebased = match format {
+                ImportFormat::Discord => message.source_id.parse::<i64>().ok().and_then(|id| {
+                    paracord_util::snowflake::rebase(id, paracord_util::snowflake::DISCORD_EPOCH)
+                }),
+                ImportFormat::Matrix => None,
+            };
+            rebased.or_else(|| id_from_timestamp(message.created_at, &message.source_id))
+        })
+        .collect()
+}
+
+fn id_from_timestamp(at: DateTime<Utc>, source_id: &str) -> Option<i64> {
+    let epoch = i64::try_from(paracord_util::snowflake::epoch()).ok()?;
+    if at.timestamp_millis() < epoch {
+        return None;
+    }
+    // The low bits come from the source id so retries produce the same ID.
+    let digest = paracord_federation::transport::sha256_hex(source_id.as_bytes());
+    let low = i64::from_str_radix(&digest[..6], 16).ok()? & 0x3F_FFFF;
+    Some(paracord_util::snowflake::min_id_at(at) | low)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn import_one_message(
+    state: &AppState,
+    archive: &Arc<ImportArchive>,
+    guild_id: i64,
+    channel_id: i64,
+    author_id: i64,
+    message: &ImportMessage,
+    message_id: Option<i64>,
+    report: &mut ImportReport,
+) -> Result<(), String> {
+    if paracord_db::imports::get_import_mapping(
+        &state.db,
+        guild_id,
+        KIND_MESSAGE,
+        &message.source_id,
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .is_some()
+    {
+        report.skipped.messages += 1;
+        return Ok(());
+    }
+
+    let mut content = message.content.clone();
+    let mut attachment_ids = Vec::new();
+    for attachment in &message.attachments {
+        let uploaded = match attachment.path.as_deref() {
+            Some(path) => {
+                match read_archive_file(archive, path, state.config.max_upload_size).await {
+                    Ok(data) => files::process_uploaded_file(
+                        state,
+                        &data,
+                        &attachment.filename,
+                        None,
+                        channel_id,
+                        author_id,
+                    )
+                    .await
+                    .map_err(|e| e.to_string()),
+                    Err(err) => Err(err),
+                }
+            }
+            None => Err("not included in the export".to_string()),
+        };
+        match uploaded.map(|json| json["id"].as_str().and_then(|id| id.parse::<i64>().ok())) {
+            Ok(Some(id)) => attachment_ids.push(id),
+            result => {
+                if let Err(err) = result.as_ref() {
+                    if attachment.path.is_some() {
+                        report.warn(format!("attachment {}: {err}", attachment.filename));
+                    }
+                }
+                let link = attachment
+                    .url
+                    .clone()
+                    .unwrap_or_else(|| format!("[attachment: {}]", attachment.filename));
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                content.push_str(&link);
+                report.skipped.attachments += 1;
+            }
+        }
+    }
+    if content.chars().count() > paracord_core::import::MAX_IMPORTED_CONTENT_CHARS {
+        content = content
+            .chars()
+            .take(paracord_core::import::MAX_IMPORTED_CONTENT_CHARS)
+            .collect();
+    }
+
+    let created_at = message.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
+    let edited_at = message
+        .edited_at
+        .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string());
+    // Message, attachments and mapping commit together: a retry after a
+    // failure part way through finds nothing and starts over, instead of
+    // colliding with its own half-imported message.
+    let mut tx = paracord_db::begin(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut local_id = match message_id {
+        Some(id) => id,
+        None => paracord_util::snowflake::try_next_id()
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+    let mut inserted = false;
+    for _ in 0..2 {
+        inserted = paracord_db::messages::import_message(
+            &mut tx,
+            local_id,
+            channel_id,
+            author_id,
+            &content,
+            &created_at,
+            edited_at.as_deref(),
+            message.pinned,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        if inserted {
+            break;
+        }
+        // A derived ID collided with an existing message.
+        local_id = paracord_util::snowflake::try_next_id()
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if !inserted {
+        return Err(format!("could not insert message {}", message.source_id));
+    }
+
+    let now = Utc::now();
+    for &attachment_id in &attachment_ids {
+        paracord_db::attachments::attach_to_message(
+            &mut *tx,
+            attachment_id,
+            local_id,
+            author_id,
+            channel_id,
+            now,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    paracord_db::imports::set_import_mapping(
+        &mut *tx,
+        guild_id,
+        KIND_MESSAGE,
+        &message.source_id,
+        local_id,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    report.created.attachments += attachment_ids.len();
+    report.created.messages += 1;
+    Ok(())
+}
+
+async fn read_archive_file(
+    archive: &Arc<ImportArchive>,
+    path: &str,
+    max_bytes: u64,
+) -> Result<Vec<u8>, String> {
+    let archive = archive.clone();
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || archive.read(&path, max_bytes))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_paths_stay_inside_the_imports_directory() {
+        let storage = tempfile::tempdir().unwrap();
+        let storage_path = storage.path().to_string_lossy().into_owned();
+        assert!(resolve_import_path(&storage_path, "export.zip").is_err());
+
+        std::fs::create_dir_all(storage.path().join(IMPORTS_DIR)).unwrap();
+        std::fs::write(storage.path().join(IMPORTS_DIR).join("export.zip"), b"").unwrap();
+        std::fs::write(storage.path().join("secret.db"), b"").unwrap();
+        assert!(resolve_import_path(&storage_path, "export.zip").is_ok());
+        assert!(resolve_import_path(&storage_path, "../secret.db").is_err());
+        assert!(resolve_import_path(&storage_path, "").is_err());
+        assert!(resolve_import_path(&storage_path, "missing.zip").is_err());
+    }
+
+    #[test]
+    fn derived_message_ids_follow_timestamps() {
+        let at = |secs| DateTime::from_timestamp(secs, 0).unwrap();
+        let message = |id: &str, secs| ImportMessage {
+            source_id: id.to_string(),
+            author_id: "a".to_string(),
+            content: String::new(),
+            created_at: at(secs),
+            edited_at: None,
+            pinned: false,
+            attachments: Vec::new(),
+        };
+        let recent = 1_750_000_000;
+        let ids = message_ids(
+            ImportFormat::Matrix,
+            &[message("$b", recent), message("$a", recent + 1)],
+        )
+        .unwrap();
+        assert!(ids[0] < ids[1]);
+        assert_eq!(
+            paracord_util::snowflake::created_at(ids[0]).timestamp(),
+            recent
+        );
+        assert_eq!(
+            message_ids(ImportFormat::Matrix, &[message("$b", recent)]).unwrap(),
+            ids[..1]
+        );
+        // History older than the node's epoch can't be given matching IDs.
+        assert!(message_ids(ImportFormat::Matrix, &[message("$old", 1_500_000_000)]).is_none());
+    }
+}
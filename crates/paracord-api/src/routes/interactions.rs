@@ -4,18 +4,23 @@ use axum::{
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
 use paracord_util::validation::contains_dangerous_markup;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
 // ── Request bodies ──────────────────────────────────────────────────────────
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct InvokeInteractionRequest {
     pub command_name: Option<String>,
+    /// Picks the command when several applications use the same name.
+    pub command_id: Option<String>,
     pub guild_id: String,
     pub channel_id: String,
     #[serde(default)]
@@ -35,21 +40,21 @@ fn default_interaction_type() -> i16 {
     2
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct InteractionCallbackRequest {
     #[serde(rename = "type")]
     pub callback_type: u8,
     pub data: Option<Value>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct EditOriginalRequest {
     pub content: Option<String>,
     pub embeds: Option<Vec<Value>>,
     pub components: Option<Vec<Value>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct FollowupMessageRequest {
     pub content: Option<String>,
     pub embeds: Option<Vec<Value>>,
@@ -143,6 +148,14 @@ async fn validate_webhook_token(
 /// Client invokes a slash command. The server looks up the command,
 /// creates an Interaction + token, dispatches INTERACTION_CREATE to the bot,
 /// and returns the interaction to the client.
+#[utoipa::path(
+    post,
+    path = "/api/v1/interactions",
+    tag = "interactions",
+    request_body = InvokeInteractionRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn invoke_interaction(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -160,19 +173,56 @@ pub async fn invoke_interaction(
     // Verify the user is a member of this guild
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
 
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|channel| channel.guild_id() == Some(guild_id))
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    crate::routes::channels::ensure_age_gate_acknowledged(&state, &channel, auth.user_id).await?;
+
     match body.interaction_type {
         // ApplicationCommand (2)
         2 => {
-            let command_name = body.command_name.as_deref().ok_or_else(|| {
-                ApiError::BadRequest("command_name required for slash commands".into())
-            })?;
+            let command_id = body
+                .command_id
+                .as_deref()
+                .map(|id| {
+                    id.parse::<i64>()
+                        .map_err(|_| ApiError::BadRequest("Invalid command_id".into()))
+                })
+                .transpose()?;
+            if body.command_name.is_none() && command_id.is_none() {
+                return Err(ApiError::BadRequest(
+                    "command_name or command_id required for slash commands".into(),
+                ));
+            }
 
             // Resolve the command
-            let cmd =
-                paracord_core::interactions::resolve_slash_command(&state, command_name, guild_id)
-                    .await
-                    .map_err(ApiError::from)?
-                    .ok_or_else(|| ApiError::NotFound)?;
+            let cmd = paracord_core::interactions::resolve_slash_command(
+                &state,
+                body.command_name.as_deref(),
+                command_id,
+                guild_id,
+            )
+            .await
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::NotFound)?;
+
+            paracord_core::interactions::authorize_command_invocation(
+                &state,
+                &cmd,
+                guild_id,
+                &channel,
+                auth.user_id,
+            )
+            .await?;
+            let declared: Vec<Value> = cmd
+                .options
+                .as_deref()
+                .and_then(|raw| serde_json::from_str(raw).ok())
+                .unwrap_or_default();
+            paracord_core::interactions::validate_command_options(&declared, &body.options)
+                .map_err(ApiError::BadRequest)?;
 
             // Look up the bot application to get the bot_user_id
             let bot_app =
@@ -220,7 +270,23 @@ pub async fn invoke_interaction(
             let msg = paracord_db::messages::get_message(&state.db, message_id)
                 .await
                 .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                .ok_or(ApiError::NotFound)?;
+                .filter(|msg| msg.channel_id == channel_id)
+                .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?;
+            let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+            let perms = paracord_core::permissions::compute_channel_permissions(
+                &state.db,
+                guild_id,
+                channel_id,
+                guild.owner_id,
+                auth.user_id,
+            )
+            .await?;
+            if !perms.contains(Permissions::VIEW_CHANNEL) {
+                return Err(ApiError::Code(ErrorCode::MissingPermissions));
+            }
 
             // Find the bot application by bot_user_id (the message author)
             let bot_app = paracord_db::bot_applications::get_bot_application_by_user_id(
@@ -266,6 +332,14 @@ pub async fn invoke_interaction(
 /// POST /api/v1/interactions/{interaction_id}/{token}/callback
 ///
 /// Bot responds to an interaction.
+#[utoipa::path(
+    post,
+    path = "/api/v1/interactions/{interaction_id}/{token}/callback",
+    tag = "interactions",
+    params(("interaction_id" = i64, Path), ("token" = String, Path)),
+    request_body = InteractionCallbackRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn interaction_callback(
     State(state): State<AppState>,
     Path((interaction_id, token)): Path<(i64, String)>,
@@ -297,9 +371,42 @@ pub async fn interaction_callback(
     Ok(Json(result.unwrap_or(json!({"type": body.callback_type}))))
 }
 
+/// GET /api/v1/interactions/{app_id}/{token}/messages/@original
+///
+/// Fetch the original interaction response message.
+#[utoipa::path(
+    get,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/@original",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn get_original_response(
+    State(state): State<AppState>,
+    Path((app_id, token)): Path<(i64, String)>,
+) -> Result<Json<Value>, ApiError> {
+    let token_row = validate_webhook_token(&state, app_id, &token).await?;
+    let msg_id = token_row
+        .response_message_id
+        .ok_or_else(|| ApiError::NotFound)?;
+    let msg = paracord_db::messages::get_message(&state.db, msg_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?;
+    Ok(Json(interaction_message_json(&msg)))
+}
+
 /// PATCH /api/v1/interactions/{app_id}/{token}/messages/@original
 ///
 /// Edit original interaction response message.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/@original",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path)),
+    request_body = EditOriginalRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn edit_original_response(
     State(state): State<AppState>,
     Path((app_id, token)): Path<(i64, String)>,
@@ -307,7 +414,131 @@ pub async fn edit_original_response(
 ) -> Result<Json<Value>, ApiError> {
     let token_row = validate_webhook_token(&state, app_id, &token).await?;
 
-    // M14: Verify bot is still installed in the guild before allowing edit
+    // H12: Use the stored response_message_id to find the original message.
+    // This ensures we only edit the message created by this specific interaction,
+    // preventing bots from editing arbitrary messages via token reuse.
+    let msg_id = token_row
+        .response_message_id
+        .ok_or_else(|| ApiError::NotFound)?;
+
+    edit_interaction_message(&state, app_id, &token_row, msg_id, &body).await
+}
+
+/// DELETE /api/v1/interactions/{app_id}/{token}/messages/@original
+///
+/// Delete original interaction response message.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/@original",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path)),
+    responses((status = 204, description = "No Content")),
+)]
+pub async fn delete_original_response(
+    State(state): State<AppState>,
+    Path((app_id, token)): Path<(i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    let token_row = validate_webhook_token(&state, app_id, &token).await?;
+
+    // Use the stored response_message_id to find the original message
+    let msg_id = token_row
+        .response_message_id
+        .ok_or_else(|| ApiError::NotFound)?;
+
+    delete_interaction_message(&state, app_id, &token_row, msg_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/interactions/{app_id}/{token}/messages/{message_id}
+///
+/// Fetch a followup message sent for this interaction.
+#[utoipa::path(
+    get,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/{message_id}",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path), ("message_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn get_followup_message(
+    State(state): State<AppState>,
+    Path((app_id, token, message_id)): Path<(i64, String, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    let token_row = validate_webhook_token(&state, app_id, &token).await?;
+    let msg = followup_message(&state, app_id, &token_row, message_id).await?;
+    Ok(Json(interaction_message_json(&msg)))
+}
+
+/// PATCH /api/v1/interactions/{app_id}/{token}/messages/{message_id}
+///
+/// Edit a followup message sent for this interaction.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/{message_id}",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path), ("message_id" = i64, Path)),
+    request_body = EditOriginalRequest,
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn edit_followup_message(
+    State(state): State<AppState>,
+    Path((app_id, token, message_id)): Path<(i64, String, i64)>,
+    Json(body): Json<EditOriginalRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let token_row = validate_webhook_token(&state, app_id, &token).await?;
+    let msg = followup_message(&state, app_id, &token_row, message_id).await?;
+    edit_interaction_message(&state, app_id, &token_row, msg.id, &body).await
+}
+
+/// DELETE /api/v1/interactions/{app_id}/{token}/messages/{message_id}
+///
+/// Delete a followup message sent for this interaction.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/interactions/{app_id}/{token}/messages/{message_id}",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path), ("message_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+)]
+pub async fn delete_followup_message(
+    State(state): State<AppState>,
+    Path((app_id, token, message_id)): Path<(i64, String, i64)>,
+) -> Result<StatusCode, ApiError> {
+    let token_row = validate_webhook_token(&state, app_id, &token).await?;
+    let msg = followup_message(&state, app_id, &token_row, message_id).await?;
+    delete_interaction_message(&state, app_id, &token_row, msg.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A followup is the bot's own interaction-response message in the
+/// interaction's channel, sent after the interaction was created.
+async fn followup_message(
+    state: &AppState,
+    app_id: i64,
+    token_row: &paracord_db::interaction_tokens::InteractionTokenRow,
+    message_id: i64,
+) -> Result<paracord_db::messages::MessageRow, ApiError> {
+    let bot_app = paracord_db::bot_applications::get_bot_application(&state.db, app_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    paracord_db::messages::get_message(&state.db, message_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|msg| {
+            msg.author_id == bot_app.bot_user_id
+                && msg.channel_id == token_row.channel_id
+                && msg.message_type == 20
+                && msg.id > token_row.interaction_id
+        })
+        .ok_or(ApiError::Code(ErrorCode::UnknownMessage))
+}
+
+/// M14: the bot must still be installed in the guild to touch its messages.
+async fn ensure_still_installed(
+    state: &AppState,
+    app_id: i64,
+    token_row: &paracord_db::interaction_tokens::InteractionTokenRow,
+) -> Result<(), ApiError> {
     if let Some(guild_id) = token_row.guild_id {
         let is_installed =
             paracord_db::bot_applications::is_bot_in_guild(&state.db, app_id, guild_id)
@@ -317,6 +548,17 @@ pub async fn edit_original_response(
             return Err(ApiError::Forbidden);
         }
     }
+    Ok(())
+}
+
+async fn edit_interaction_message(
+    state: &AppState,
+    app_id: i64,
+    token_row: &paracord_db::interaction_tokens::InteractionTokenRow,
+    msg_id: i64,
+    body: &EditOriginalRequest,
+) -> Result<Json<Value>, ApiError> {
+    ensure_still_installed(state, app_id, token_row).await?;
 
     let content = body.content.as_deref().unwrap_or("");
 
@@ -327,27 +569,10 @@ pub async fn edit_original_response(
         ));
     }
 
-    // H12: Use the stored response_message_id to find the original message.
-    // This ensures we only edit the message created by this specific interaction,
-    // preventing bots from editing arbitrary messages via token reuse.
-    let msg_id = token_row
-        .response_message_id
-        .ok_or_else(|| ApiError::NotFound)?;
-
     let updated = paracord_db::messages::update_message(&state.db, msg_id, content)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-
-    let msg_json = json!({
-        "id": updated.id.to_string(),
-        "channel_id": updated.channel_id.to_string(),
-        "author_id": updated.author_id.to_string(),
-        "content": updated.content,
-        "message_type": updated.message_type,
-        "flags": updated.flags,
-        "edited_at": updated.edited_at.map(|t| t.to_rfc3339()),
-        "created_at": updated.created_at.to_rfc3339(),
-    });
+    let msg_json = interaction_message_json(&updated);
 
     // Dispatch MESSAGE_UPDATE
     state
@@ -357,30 +582,13 @@ pub async fn edit_original_response(
     Ok(Json(msg_json))
 }
 
-/// DELETE /api/v1/interactions/{app_id}/{token}/messages/@original
-///
-/// Delete original interaction response message.
-pub async fn delete_original_response(
-    State(state): State<AppState>,
-    Path((app_id, token)): Path<(i64, String)>,
-) -> Result<StatusCode, ApiError> {
-    let token_row = validate_webhook_token(&state, app_id, &token).await?;
-
-    // M14: Verify bot is still installed in the guild before allowing delete
-    if let Some(guild_id) = token_row.guild_id {
-        let is_installed =
-            paracord_db::bot_applications::is_bot_in_guild(&state.db, app_id, guild_id)
-                .await
-                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-        if !is_installed {
-            return Err(ApiError::Forbidden);
-        }
-    }
-
-    // Use the stored response_message_id to find the original message
-    let msg_id = token_row
-        .response_message_id
-        .ok_or_else(|| ApiError::NotFound)?;
+async fn delete_interaction_message(
+    state: &AppState,
+    app_id: i64,
+    token_row: &paracord_db::interaction_tokens::InteractionTokenRow,
+    msg_id: i64,
+) -> Result<(), ApiError> {
+    ensure_still_installed(state, app_id, token_row).await?;
 
     paracord_db::messages::delete_message(&state.db, msg_id)
         .await
@@ -396,13 +604,33 @@ pub async fn delete_original_response(
         }),
         token_row.guild_id,
     );
+    Ok(())
+}
 
-    Ok(StatusCode::NO_CONTENT)
+fn interaction_message_json(msg: &paracord_db::messages::MessageRow) -> Value {
+    json!({
+        "id": msg.id.to_string(),
+        "channel_id": msg.channel_id.to_string(),
+        "author_id": msg.author_id.to_string(),
+        "content": msg.content,
+        "message_type": msg.message_type,
+        "flags": msg.flags,
+        "edited_at": msg.edited_at.map(|t| t.to_rfc3339()),
+        "created_at": msg.created_at.to_rfc3339(),
+    })
 }
 
 /// POST /api/v1/interactions/{app_id}/{token}/followup
 ///
 /// Send a followup message for an interaction.
+#[utoipa::path(
+    post,
+    path = "/api/v1/interactions/{app_id}/{token}/followup",
+    tag = "interactions",
+    params(("app_id" = i64, Path), ("token" = String, Path)),
+    request_body = FollowupMessageRequest,
+    responses((status = 201, description = "Created", body = Value)),
+)]
 pub async fn create_followup_message(
     State(state): State<AppState>,
     Path((app_id, token)): Path<(i64, String)>,
@@ -432,7 +660,7 @@ pub async fn create_followup_message(
         .transpose()
         .map_err(|e| ApiError::Internal(anyhow::anyhow!("serialize components: {}", e)))?;
     let flags = body.flags.unwrap_or(0) as i32;
-    let message_id = paracord_util::snowflake::generate(1);
+    let message_id = paracord_util::snowflake::try_next_id().await?;
 
     let msg = paracord_db::messages::create_message_with_meta(
         &state.db,
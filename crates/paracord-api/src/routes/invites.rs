@@ -5,15 +5,18 @@ use axum::{
 };
 use paracord_core::AppState;
 use paracord_federation::client::{FederationInviteRequest, FederationJoinRequest};
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 use crate::routes::audit;
+use crate::routes::verification::{self, JoinScreening};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateInviteRequest {
     #[serde(default = "default_max_uses")]
     pub max_uses: i32,
@@ -130,6 +133,15 @@ async fn federation_send_join_rpc_for_mirrored_guild(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/invites",
+    tag = "invites",
+    params(("channel_id" = i64, Path)),
+    request_body = CreateInviteRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_invite(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -139,7 +151,7 @@ pub async fn create_invite(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     let space_id = channel
         .guild_id()
@@ -149,7 +161,7 @@ pub async fn create_invite(
     let guild = paracord_db::guilds::get_guild(&state.db, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         space_id,
@@ -202,6 +214,13 @@ pub async fn create_invite(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/invites/{code}",
+    tag = "invites",
+    params(("code" = String, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+)]
 pub async fn get_invite(
     State(state): State<AppState>,
     Path(code): Path<String>,
@@ -209,7 +228,7 @@ pub async fn get_invite(
     let invite = paracord_db::invites::get_invite(&state.db, &code)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownInvite))?;
 
     // Look up the space via the invite's channel
     let channel = paracord_db::channels::get_channel(&state.db, invite.channel_id)
@@ -247,21 +266,29 @@ pub async fn get_invite(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/invites/{code}",
+    tag = "invites",
+    params(("code" = String, Path)),
+    responses((status = 202, description = "Accepted", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn accept_invite(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(code): Path<String>,
-) -> Result<Json<Value>, ApiError> {
+) -> Result<(StatusCode, Json<Value>), ApiError> {
     let preview = paracord_db::invites::get_invite(&state.db, &code)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownInvite))?;
 
     // Resolve the space from the invite's channel
     let channel = paracord_db::channels::get_channel(&state.db, preview.channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     let space_id = channel.guild_id().ok_or(ApiError::BadRequest(
         "Invite target must be a guild/space channel".into(),
     ))?;
@@ -271,6 +298,25 @@ pub async fn accept_invite(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
         .is_some();
 
+    if !already_member {
+        // Guilds with a verification hook may hold or refuse the join.
+        match verification::screen_join(&state, space_id, auth.user_id, &code).await? {
+            JoinScreening::Allowed => {}
+            JoinScreening::Pending(reason) => {
+                return Ok((
+                    StatusCode::ACCEPTED,
+                    Json(json!({
+                        "guild": null,
+                        "verification": {"status": "pending", "reason": reason},
+                    })),
+                ));
+            }
+            JoinScreening::Denied(_) => {
+                return Err(ApiError::Code(ErrorCode::MemberVerificationDenied));
+            }
+        }
+    }
+
     let invite_state = if already_member {
         Some(preview.clone())
     } else {
@@ -309,7 +355,7 @@ pub async fn accept_invite(
     let guild = paracord_db::guilds::get_guild(&state.db, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let channels = paracord_db::channels::get_guild_channels(&state.db, space_id)
         .await
@@ -337,7 +383,7 @@ pub async fn accept_invite(
 
     // Only dispatch GUILD_MEMBER_ADD for genuinely new members
     if !already_member {
-        state.member_index.add_member(guild.id, auth.user_id);
+        paracord_core::member_list::add_member(&state, guild.id, auth.user_id);
         state.event_bus.dispatch(
             "GUILD_MEMBER_ADD",
             json!({"guild_id": guild.id.to_string(), "user_id": auth.user_id.to_string()}),
@@ -369,9 +415,17 @@ pub async fn accept_invite(
         }
     }
 
-    Ok(Json(json!({ "guild": guild_json })))
+    Ok((StatusCode::OK, Json(json!({ "guild": guild_json }))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/invites",
+    tag = "invites",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_invites(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -380,7 +434,7 @@ pub async fn list_guild_invites(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -389,7 +443,7 @@ pub async fn list_guild_invites(
         guild.owner_id,
         auth.user_id,
     );
-    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_INVITES)?;
 
     let invites = paracord_db::invites::get_guild_invites(&state.db, guild_id)
         .await
@@ -414,6 +468,14 @@ pub async fn list_guild_invites(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/invites/{code}",
+    tag = "invites",
+    params(("code" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_invite(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -422,19 +484,19 @@ pub async fn delete_invite(
     let invite = paracord_db::invites::get_invite(&state.db, &code)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownInvite))?;
     // Resolve space from channel
     let channel = paracord_db::channels::get_channel(&state.db, invite.channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     let space_id = channel.guild_id().ok_or(ApiError::BadRequest(
         "Invite target must be a guild/space channel".into(),
     ))?;
     let guild = paracord_db::guilds::get_guild(&state.db, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, space_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -443,7 +505,7 @@ pub async fn delete_invite(
         guild.owner_id,
         auth.user_id,
     );
-    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_INVITES)?;
     paracord_db::invites::delete_invite(&state.db, &code)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -3,8 +3,10 @@ use axum::{
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -24,26 +26,34 @@ fn is_valid_base64(s: &str, expected_len: usize) -> bool {
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SignedPrekeyUpload {
     pub id: i64,
     pub public_key: String,
     pub signature: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct OneTimePrekeyUpload {
     pub id: i64,
     pub public_key: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UploadKeysRequest {
     pub signed_prekey: Option<SignedPrekeyUpload>,
     pub one_time_prekeys: Option<Vec<OneTimePrekeyUpload>>,
 }
 
 /// PUT /api/v1/users/@me/keys -- Upload prekey bundle
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/keys",
+    tag = "keys",
+    request_body = UploadKeysRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn upload_keys(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -109,23 +119,28 @@ pub async fn upload_keys(
     })))
 }
 
-/// GET /api/v1/users/{user_id}/keys -- Fetch peer's prekey bundle
-pub async fn get_keys(
-    State(state): State<AppState>,
-    _auth: AuthUser,
-    Path(user_id): Path<i64>,
-) -> Result<Json<Value>, ApiError> {
+/// Build a user's prekey bundle, consuming one of their one-time prekeys.
+/// Returns `None` when the user hasn't published an identity key and signed
+/// prekey yet.
+pub(crate) async fn load_prekey_bundle(
+    state: &AppState,
+    user_id: i64,
+) -> Result<Option<Value>, ApiError> {
     let user = paracord_db::users::get_user_by_id(&state.db, user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
-    let identity_key = user.public_key.ok_or_else(|| ApiError::NotFound)?;
+    let Some(identity_key) = user.public_key else {
+        return Ok(None);
+    };
 
-    let spk = paracord_db::prekeys::get_signed_prekey(&state.db, user_id)
+    let Some(spk) = paracord_db::prekeys::get_signed_prekey(&state.db, user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+    else {
+        return Ok(None);
+    };
 
     let opk = paracord_db::prekeys::consume_one_time_prekey(&state.db, user_id)
         .await
@@ -138,7 +153,7 @@ pub async fn get_keys(
         })
     });
 
-    Ok(Json(json!({
+    Ok(Some(json!({
         "identity_key": identity_key,
         "signed_prekey": {
             "id": spk.id,
@@ -149,7 +164,35 @@ pub async fn get_keys(
     })))
 }
 
+/// GET /api/v1/users/{user_id}/keys -- Fetch peer's prekey bundle
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/keys",
+    tag = "keys",
+    operation_id = "keys_get_keys",
+    params(("user_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_keys(
+    State(state): State<AppState>,
+    _auth: AuthUser,
+    Path(user_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let bundle = load_prekey_bundle(&state, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(bundle))
+}
+
 /// GET /api/v1/users/@me/keys/count -- Check OPK count
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/keys/count",
+    tag = "keys",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_key_count(
     State(state): State<AppState>,
     auth: AuthUser,
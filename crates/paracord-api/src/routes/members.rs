@@ -1,26 +1,48 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use paracord_core::AppState;
 use paracord_federation::client::FederationLeaveRequest;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 use crate::routes::audit;
 
+/// Member list cursor. Members are ordered by user id; pass the last
+/// `user_id` of a page as `after` to fetch the next one.
+#[derive(Deserialize, IntoParams)]
+pub struct MemberListQuery {
+    pub after: Option<i64>,
+    /// Page size, 1-1000. Defaults to 1000.
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/members",
+    tag = "members",
+    params(("guild_id" = i64, Path), MemberListQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_members(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
+    Query(params): Query<MemberListQuery>,
 ) -> Result<Json<Value>, ApiError> {
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
 
-    let members = paracord_db::members::get_guild_members(&state.db, guild_id, 1000, None)
+    let limit = params.limit.unwrap_or(1000).clamp(1, 1000);
+    let members = paracord_db::members::get_guild_members(&state.db, guild_id, limit, params.after)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
@@ -54,13 +76,82 @@ pub async fn list_members(
     Ok(Json(json!(result)))
 }
 
-#[derive(Deserialize)]
+/// Presences of a guild's online members, for lists READY or GUILD_CREATE
+/// cut short with `presences_has_more`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/presences",
+    tag = "members",
+    params(("guild_id" = i64, Path), MemberListQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_presences(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Query(params): Query<MemberListQuery>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+
+    let limit = params.limit.unwrap_or(1000).clamp(1, 1000) as usize;
+    let mut member_ids = paracord_db::members::get_guild_member_user_ids(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    member_ids.sort_unstable();
+    let friend_ids: HashSet<i64> =
+        paracord_db::relationships::get_friend_user_ids(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .into_iter()
+            .collect();
+
+    let online_users = state.online_users.read().await;
+    let user_presences = state.user_presences.read().await;
+    let presences: Vec<Value> = member_ids
+        .into_iter()
+        .filter(|uid| params.after.is_none_or(|after| *uid > after))
+        .filter(|uid| online_users.contains(uid))
+        .filter(|uid| {
+            *uid == auth.user_id
+                || state
+                    .presence_manager
+                    .cached_privacy(*uid)
+                    .unwrap_or_default()
+                    .visible_to(friend_ids.contains(uid), &[guild_id])
+        })
+        .take(limit)
+        .map(|uid| {
+            user_presences.get(&uid).cloned().unwrap_or_else(|| {
+                json!({
+                    "user_id": uid.to_string(),
+                    "status": "online",
+                    "custom_status": Value::Null,
+                    "activities": [],
+                })
+            })
+        })
+        .collect();
+
+    Ok(Json(json!(presences)))
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateMemberRequest {
     pub nick: Option<String>,
     pub roles: Option<Vec<String>>,
     pub communication_disabled_until: Option<String>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/members/{user_id}",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    request_body = UpdateMemberRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_member(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -70,7 +161,7 @@ pub async fn update_member(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let actor_roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -186,7 +277,7 @@ pub async fn update_member(
 
     let mut timed_out_until = updated.communication_disabled_until;
     if let Some(raw_until) = body.communication_disabled_until {
-        paracord_core::permissions::require_permission(actor_perms, Permissions::MUTE_MEMBERS)?;
+        paracord_core::permissions::require_permission(actor_perms, Permissions::MODERATE_MEMBERS)?;
         if user_id == guild.owner_id {
             return Err(ApiError::Forbidden);
         }
@@ -258,6 +349,14 @@ pub async fn update_member(
     Ok(Json(member_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/members/{user_id}",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn kick_member(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -265,7 +364,7 @@ pub async fn kick_member(
 ) -> Result<StatusCode, ApiError> {
     paracord_core::admin::kick_member(&state.db, guild_id, auth.user_id, user_id).await?;
 
-    state.member_index.remove_member(guild_id, user_id);
+    paracord_core::member_list::remove_member(&state, guild_id, user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_REMOVE",
         json!({
@@ -296,6 +395,318 @@ pub async fn kick_member(
     Ok(StatusCode::NO_CONTENT)
 }
 
+const MAX_MEMBER_NOTE_LEN: usize = 2000;
+
+fn member_note_to_json(note: &paracord_db::member_notes::MemberNoteRow) -> Value {
+    json!({
+        "id": note.id.to_string(),
+        "guild_id": note.guild_id.to_string(),
+        "user_id": note.user_id.to_string(),
+        "author_id": note.author_id.map(|id| id.to_string()),
+        "content": note.content,
+        "created_at": note.created_at.to_rfc3339(),
+    })
+}
+
+/// Moderation notes are visible to anyone who can kick or ban members.
+async fn ensure_member_note_access(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms =
+        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    if !perms.intersects(Permissions::KICK_MEMBERS | Permissions::BAN_MEMBERS) {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/members/{user_id}/notes",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_member_notes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, user_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_member_note_access(&state, guild_id, auth.user_id).await?;
+
+    // Notes outlive membership so context survives a leave/rejoin or ban.
+    let notes = paracord_db::member_notes::list_member_notes(&state.db, guild_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(notes
+        .iter()
+        .map(member_note_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateMemberNoteRequest {
+    pub content: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/members/{user_id}/notes",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    request_body = CreateMemberNoteRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn create_member_note(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, user_id)): Path<(i64, i64)>,
+    Json(body): Json<CreateMemberNoteRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    ensure_member_note_access(&state, guild_id, auth.user_id).await?;
+
+    let content = body.content.trim();
+    if content.is_empty() || content.chars().count() > MAX_MEMBER_NOTE_LEN {
+        return Err(ApiError::BadRequest(
+            "Note must be 1-2000 characters".into(),
+        ));
+    }
+    let is_member = paracord_db::members::get_member(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .is_some();
+    let is_banned = paracord_db::bans::get_ban(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .is_some();
+    if !is_member && !is_banned {
+        return Err(ApiError::NotFound);
+    }
+
+    let note = paracord_db::member_notes::create_note(
+        &state.db,
+        paracord_util::snowflake::try_next_id().await?,
+        guild_id,
+        user_id,
+        auth.user_id,
+        content,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_MEMBER_NOTE_CREATE,
+        Some(user_id),
+        None,
+        Some(json!({ "note_id": note.id.to_string() })),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(member_note_to_json(&note))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/members/{user_id}/notes/{note_id}",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path), ("note_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_member_note(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, user_id, note_id)): Path<(i64, i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    ensure_member_note_access(&state, guild_id, auth.user_id).await?;
+
+    let note = paracord_db::member_notes::get_note(&state.db, note_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|note| note.guild_id == guild_id && note.user_id == user_id)
+        .ok_or(ApiError::NotFound)?;
+
+    paracord_db::member_notes::delete_note(&state.db, note.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    // Keep the removed text in the audit log so the history stays complete.
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_MEMBER_NOTE_DELETE,
+        Some(user_id),
+        None,
+        Some(json!({
+            "note_id": note.id.to_string(),
+            "author_id": note.author_id.map(|id| id.to_string()),
+            "content": note.content,
+        })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Self-assignable role changes a member may make per minute.
+const SELF_ROLE_CHANGES_PER_MINUTE: i64 = 10;
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/members/@me/roles/{role_id}",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("role_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn add_self_role(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, role_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    update_self_role(&state, guild_id, auth.user_id, role_id, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/members/@me/roles/{role_id}",
+    tag = "members",
+    params(("guild_id" = i64, Path), ("role_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn remove_self_role(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, role_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    update_self_role(&state, guild_id, auth.user_id, role_id, false).await
+}
+
+/// Add or remove one of the guild's self-assignable roles for `user_id`.
+/// Adding a role drops the member's other roles in the same group.
+async fn update_self_role(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+    role_id: i64,
+    add: bool,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let role = paracord_db::roles::get_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|role| role.guild_id() == guild_id)
+        .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
+    if !role.self_assignable {
+        return Err(ApiError::Forbidden);
+    }
+
+    let window_start = chrono::Utc::now().timestamp() / 60;
+    let bucket_key = format!("self_role:{guild_id}:{user_id}");
+    let changes = paracord_db::rate_limits::increment_window_counter(
+        &state.db,
+        &bucket_key,
+        window_start,
+        60,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if changes > SELF_ROLE_CHANGES_PER_MINUTE {
+        return Err(ApiError::RateLimited);
+    }
+
+    let current = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let mut removed = Vec::new();
+    if add {
+        if let Some(group) = role.self_assign_group.as_deref() {
+            for other in current.iter().filter(|other| {
+                other.id != role_id
+                    && other.self_assignable
+                    && other.self_assign_group.as_deref() == Some(group)
+            }) {
+                paracord_db::roles::remove_member_role(&state.db, user_id, guild_id, other.id)
+                    .await
+                    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+                removed.push(other.id);
+            }
+        }
+        paracord_db::roles::add_member_role(&state.db, user_id, guild_id, role_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    } else {
+        paracord_db::roles::remove_member_role(&state.db, user_id, guild_id, role_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+    paracord_core::permissions::invalidate_user(&state.permission_cache, user_id).await;
+
+    let role_ids: Vec<String> = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .iter()
+        .map(|role| role.id.to_string())
+        .collect();
+    state.event_bus.dispatch(
+        "GUILD_MEMBER_UPDATE",
+        json!({
+            "guild_id": guild_id.to_string(),
+            "user_id": user_id.to_string(),
+            "roles": role_ids.clone(),
+        }),
+        Some(guild_id),
+    );
+    audit::log_action(
+        state,
+        guild_id,
+        user_id,
+        if add {
+            audit::ACTION_MEMBER_ROLE_SELF_ADD
+        } else {
+            audit::ACTION_MEMBER_ROLE_SELF_REMOVE
+        },
+        Some(role_id),
+        None,
+        Some(json!({
+            "role_id": role_id.to_string(),
+            "replaced_role_ids": removed.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+        })),
+    )
+    .await;
+
+    Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "user_id": user_id.to_string(),
+        "roles": role_ids,
+    })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/members/@me",
+    tag = "members",
+    params(("guild_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn leave_guild(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -305,7 +716,7 @@ pub async fn leave_guild(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     if guild.owner_id == auth.user_id {
         return Err(ApiError::BadRequest(
@@ -317,7 +728,7 @@ pub async fn leave_guild(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
-    state.member_index.remove_member(guild_id, auth.user_id);
+    paracord_core::member_list::remove_member(&state, guild_id, auth.user_id);
     state.event_bus.dispatch(
         "GUILD_MEMBER_REMOVE",
         json!({
@@ -4,25 +4,43 @@ pub mod audit_logs;
 pub mod auth;
 pub mod bans;
 pub mod bots;
+pub mod branding;
+pub mod channel_exports;
 pub mod channels;
+pub mod client_updates;
 pub mod commands;
 pub mod discovery;
 pub mod dms;
 pub mod emojis;
+pub mod event_log;
 pub mod events;
 pub mod federation;
 pub mod files;
 pub mod guilds;
+pub mod image_hashes;
+pub(crate) mod image_variants;
+pub mod imports;
 pub mod interactions;
 pub mod invites;
 pub mod keys;
 pub mod livekit_proxy;
 pub mod members;
+pub mod mod_log;
+pub mod notification_settings;
+pub mod push_subscriptions;
 pub mod realtime;
+pub mod recordings;
 pub mod relationships;
+pub mod reminders;
 pub mod roles;
 pub mod security;
+pub mod supporters;
+pub mod tags;
+pub mod url_denylist;
 pub mod users;
+pub mod verification;
 pub mod voice;
 pub mod voice_v2;
 pub mod webhooks;
+pub mod whip;
+pub mod widgets;
@@ -0,0 +1,430 @@
+//! Guild mod log: audit log entries and automod actions mirrored into a
+//! channel as system messages, so moderators see them as they happen.
+//!
+//! Actions queue a line in `mod_log_queue`; [`run_mod_log_flush_once`],
+//! run every few seconds by the server, posts each guild's queued lines as
+//! one message (or a few, when they don't fit in one).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use paracord_core::AppState;
+use paracord_models::message::MessageType;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::audit;
+use crate::routes::channels::message_to_json;
+use crate::routes::guilds::require_manage_guild;
+
+/// System user the mod log posts as.
+pub const MOD_LOG_USER_ID: i64 = -3;
+/// Lines read per flush; anything past this waits for the next one.
+const FLUSH_BATCH_SIZE: i64 = 500;
+const MAX_MESSAGE_CHARS: usize = 2000;
+const MAX_REASON_CHARS: usize = 200;
+
+fn settings_to_json(
+    guild_id: i64,
+    settings: Option<&paracord_db::mod_log::ModLogSettingsRow>,
+) -> Value {
+    json!({
+        "guild_id": guild_id.to_string(),
+        "channel_id": settings.map(|s| s.channel_id.to_string()),
+        "include_audit_log": settings.is_none_or(|s| s.include_audit_log),
+        "include_automod": settings.is_none_or(|s| s.include_automod),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/mod-log",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_mod_log(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let settings = paracord_db::mod_log::get_settings(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(settings_to_json(guild_id, settings.as_ref())))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateModLogRequest {
+    pub channel_id: String,
+    pub include_audit_log: Option<bool>,
+    pub include_automod: Option<bool>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/mod-log",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateModLogRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_mod_log(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateModLogRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let channel_id: i64 = body
+        .channel_id
+        .parse()
+        .map_err(|_| ApiError::BadRequest("Invalid channel_id".into()))?;
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|channel| channel.guild_id() == Some(guild_id))
+        .ok_or_else(|| ApiError::BadRequest("Channel is not in this guild".into()))?;
+    if channel.channel_type != 0 {
+        return Err(ApiError::BadRequest(
+            "The mod log must be a text channel".into(),
+        ));
+    }
+
+    let settings = paracord_db::mod_log::upsert_settings(
+        &state.db,
+        guild_id,
+        channel_id,
+        body.include_audit_log.unwrap_or(true),
+        body.include_automod.unwrap_or(true),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(settings_to_json(guild_id, Some(&settings))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/mod-log",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_mod_log(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    paracord_db::mod_log::delete_settings(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Queue an audit log entry for the guild's mod log, if it has one.
+pub(crate) async fn record_audit_entry(
+    state: &AppState,
+    guild_id: i64,
+    actor_id: i64,
+    action_type: i16,
+    target_id: Option<i64>,
+    reason: Option<&str>,
+) {
+    let line = audit_line(actor_id, action_type, target_id, reason);
+    queue_line(state, guild_id, &line, |settings| {
+        settings.include_audit_log
+    })
+    .await;
+}
+
+/// Queue an automod action for the guild's mod log, if it has one.
+/// `action` reads as a verb phrase, e.g. "removed a message".
+pub async fn record_automod_action(
+    state: &AppState,
+    guild_id: i64,
+    channel_id: i64,
+    user_id: i64,
+    action: &str,
+    detail: Option<&str>,
+) {
+    let mut line = format!("**AutoMod** {action} by <@{user_id}> in <#{channel_id}>");
+    if let Some(detail) = detail {
+        line.push_str(&format!(" ({})", plain_text(detail)));
+    }
+    queue_line(state, guild_id, &line, |settings| settings.include_automod).await;
+}
+
+async fn queue_line(
+    state: &AppState,
+    guild_id: i64,
+    line: &str,
+    wanted: impl Fn(&paracord_db::mod_log::ModLogSettingsRow) -> bool,
+) {
+    let settings = match paracord_db::mod_log::get_settings(&state.db, guild_id).await {
+        Ok(Some(settings)) if wanted(&settings) => settings,
+        Ok(_) => return,
+        Err(err) => {
+            tracing::warn!("Failed to load mod log settings for {}: {}", guild_id, err);
+            return;
+        }
+    };
+    let queued = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => paracord_db::mod_log::enqueue_line(&state.db, id, settings.guild_id, line).await,
+        Err(err) => Err(err.into()),
+    };
+    if let Err(err) = queued {
+        tracing::warn!("Failed to queue mod log line for {}: {}", guild_id, err);
+    }
+}
+
+fn audit_line(
+    actor_id: i64,
+    action_type: i16,
+    target_id: Option<i64>,
+    reason: Option<&str>,
+) -> String {
+    let (label, target_kind) = match action_type {
+        audit::ACTION_GUILD_UPDATE => ("Server updated", TargetKind::None),
+        audit::ACTION_CHANNEL_CREATE => ("Channel created", TargetKind::Channel),
+        audit::ACTION_CHANNEL_UPDATE => ("Channel updated", TargetKind::Channel),
+        audit::ACTION_CHANNEL_DELETE => ("Channel deleted", TargetKind::Id),
+        audit::ACTION_CHANNEL_OVERWRITES_UPDATE => {
+            ("Channel permissions updated", TargetKind::Channel)
+        }
+        audit::ACTION_MEMBER_UPDATE => ("Member updated", TargetKind::User),
+        audit::ACTION_MEMBER_KICK => ("Member kicked", TargetKind::User),
+        audit::ACTION_MEMBER_BAN_ADD => ("Member banned", TargetKind::User),
+        audit::ACTION_MEMBER_BAN_REMOVE => ("Member unbanned", TargetKind::User),
+        audit::ACTION_MEMBER_NOTE_CREATE => ("Member note added", TargetKind::User),
+        audit::ACTION_MEMBER_NOTE_DELETE => ("Member note removed", TargetKind::User),
+        audit::ACTION_MEMBER_ROLE_SELF_ADD => ("Self-assigned role added", TargetKind::Role),
+        audit::ACTION_MEMBER_ROLE_SELF_REMOVE => ("Self-assigned role removed", TargetKind::Role),
+        audit::ACTION_ROLE_CREATE => ("Role created", TargetKind::Role),
+        audit::ACTION_ROLE_UPDATE => ("Role updated", TargetKind::Role),
+        audit::ACTION_ROLE_DELETE => ("Role deleted", TargetKind::Id),
+        audit::ACTION_INVITE_CREATE => ("Invite created", TargetKind::None),
+        audit::ACTION_INVITE_DELETE => ("Invite deleted", TargetKind::None),
+        audit::ACTION_TAG_CREATE => ("Tag created", TargetKind::Id),
+        audit::ACTION_TAG_UPDATE => ("Tag updated", TargetKind::Id),
+        audit::ACTION_TAG_DELETE => ("Tag deleted", TargetKind::Id),
+        _ => ("Audit log entry", TargetKind::Id),
+    };
+    let mut line = format!("**{label}**");
+    match (target_kind, target_id) {
+        (TargetKind::User, Some(id)) => line.push_str(&format!(": <@{id}>")),
+        (TargetKind::Channel, Some(id)) => line.push_str(&format!(": <#{id}>")),
+        (TargetKind::Role, Some(id)) => line.push_str(&format!(": <@&{id}>")),
+        (TargetKind::Id, Some(id)) => line.push_str(&format!(": `{id}`")),
+        _ => {}
+    }
+    line.push_str(&format!(" by <@{actor_id}>"));
+    if let Some(reason) = reason.map(str::trim).filter(|reason| !reason.is_empty()) {
+        line.push_str(&format!(" — {}", plain_text(reason)));
+    }
+    line
+}
+
+#[derive(Clone, Copy)]
+enum TargetKind {
+    None,
+    User,
+    Channel,
+    Role,
+    Id,
+}
+
+/// User-supplied text on one line, without mentions or code spans that
+/// would break out of the line's formatting.
+fn plain_text(text: &str) -> String {
+    let flat: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(['`', '<', '>'], "")
+        .replace('@', "@\u{200b}");
+    match flat.char_indices().nth(MAX_REASON_CHARS) {
+        Some((end, _)) => format!("{}…", &flat[..end]),
+        None => flat,
+    }
+}
+
+/// Post every guild's queued mod log lines. Run periodically by the server.
+pub async fn run_mod_log_flush_once(state: &AppState) {
+    let lines = match paracord_db::mod_log::get_queued_lines(&state.db, FLUSH_BATCH_SIZE).await {
+        Ok(lines) if !lines.is_empty() => lines,
+        Ok(_) => return,
+        Err(err) => {
+            tracing::warn!("Failed to load queued mod log lines: {}", err);
+            return;
+        }
+    };
+    if !ensure_mod_log_user(state).await {
+        return;
+    }
+
+    let mut by_guild: Vec<(i64, Vec<paracord_db::mod_log::ModLogQueueRow>)> = Vec::new();
+    for line in lines {
+        match by_guild
+            .iter_mut()
+            .find(|(guild_id, _)| *guild_id == line.guild_id)
+        {
+            Some((_, guild_lines)) => guild_lines.push(line),
+            None => by_guild.push((line.guild_id, vec![line])),
+        }
+    }
+
+    for (guild_id, guild_lines) in by_guild {
+        let ids: Vec<i64> = guild_lines.iter().map(|line| line.id).collect();
+        match paracord_db::mod_log::get_settings(&state.db, guild_id).await {
+            Ok(Some(settings)) => {
+                let contents: Vec<&str> = guild_lines
+                    .iter()
+                    .map(|line| line.content.as_str())
+                    .collect();
+                for content in pack_lines(&contents, MAX_MESSAGE_CHARS) {
+                    post_mod_log_message(state, guild_id, settings.channel_id, &content).await;
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!("Failed to load mod log settings for {}: {}", guild_id, err);
+                continue;
+            }
+        }
+        if let Err(err) = paracord_db::mod_log::delete_queued_lines(&state.db, &ids).await {
+            tracing::warn!("Failed to clear mod log lines for {}: {}", guild_id, err);
+        }
+    }
+}
+
+/// Join lines into as few messages of at most `max_chars` as possible.
+fn pack_lines(lines: &[&str], max_chars: usize) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        let line: String = line.chars().take(max_chars).collect();
+        if !current.is_empty() && current.chars().count() + 1 + line.chars().count() > max_chars {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        messages.push(current);
+    }
+    messages
+}
+
+async fn post_mod_log_message(state: &AppState, guild_id: i64, channel_id: i64, content: &str) {
+    let msg_id = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => id,
+        Err(err) => {
+            tracing::warn!("Failed to post mod log for {}: {}", guild_id, err);
+            return;
+        }
+    };
+    let msg = match paracord_db::messages::create_message_with_meta(
+        &state.db,
+        msg_id,
+        channel_id,
+        MOD_LOG_USER_ID,
+        content,
+        MessageType::SystemMessage as i16,
+        None,
+        0,
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(msg) => msg,
+        Err(err) => {
+            tracing::warn!("Failed to post mod log for {}: {}", guild_id, err);
+            return;
+        }
+    };
+    let msg_json = message_to_json(state, &msg, MOD_LOG_USER_ID).await;
+    state
+        .event_bus
+        .dispatch("MESSAGE_CREATE", msg_json, Some(guild_id));
+}
+
+async fn ensure_mod_log_user(state: &AppState) -> bool {
+    match paracord_db::users::get_user_by_id(&state.db, MOD_LOG_USER_ID).await {
+        Ok(Some(_)) => return true,
+        Ok(None) => {}
+        Err(err) => {
+            tracing::warn!("Failed to look up the mod log user: {}", err);
+            return false;
+        }
+    }
+    match paracord_db::users::create_user(
+        &state.db,
+        MOD_LOG_USER_ID,
+        "Mod Log",
+        0,
+        "modlog@paracord.internal",
+        "",
+    )
+    .await
+    {
+        Ok(_) => true,
+        // Another instance may have created it first.
+        Err(_) => matches!(
+            paracord_db::users::get_user_by_id(&state.db, MOD_LOG_USER_ID).await,
+            Ok(Some(_))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_lines_mention_targets_by_kind() {
+        assert_eq!(
+            audit_line(
+                1,
+                audit::ACTION_MEMBER_BAN_ADD,
+                Some(2),
+                Some("spam\nlinks")
+            ),
+            "**Member banned**: <@2> by <@1> — spam links"
+        );
+        assert_eq!(
+            audit_line(1, audit::ACTION_ROLE_UPDATE, Some(3), None),
+            "**Role updated**: <@&3> by <@1>"
+        );
+        assert_eq!(
+            audit_line(1, audit::ACTION_GUILD_UPDATE, Some(9), Some(" ")),
+            "**Server updated** by <@1>"
+        );
+        assert_eq!(
+            plain_text("ping `@everyone` <@5>"),
+            "ping @\u{200b}everyone @\u{200b}5"
+        );
+    }
+
+    #[test]
+    fn lines_are_packed_into_messages() {
+        let packed = pack_lines(&["aaaa", "bbbb", "cccc"], 9);
+        assert_eq!(packed, vec!["aaaa\nbbbb", "cccc"]);
+        let long = "x".repeat(20);
+        assert_eq!(pack_lines(&[&long], 9), vec!["x".repeat(9)]);
+        assert!(pack_lines(&[], 9).is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use paracord_core::notifications::NotificationLevel;
+use paracord_core::AppState;
+use paracord_db::notification_settings::{
+    ChannelNotificationOverrideRow, GuildNotificationSettingsRow,
+};
+use paracord_models::error_code::ErrorCode;
+use paracord_models::gateway::EVENT_USER_GUILD_SETTINGS_UPDATE;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+
+const MAX_CHANNEL_OVERRIDES_PER_REQUEST: usize = 100;
+
+fn parse_level(raw: &str) -> Result<NotificationLevel, ApiError> {
+    NotificationLevel::parse(raw)
+        .ok_or_else(|| ApiError::BadRequest("level must be all, mentions or none".into()))
+}
+
+/// `None` for absent or `""`, which clears the expiry.
+fn parse_muted_until(raw: Option<&str>) -> Result<Option<DateTime<Utc>>, ApiError> {
+    match raw.map(str::trim).filter(|raw| !raw.is_empty()) {
+        None => Ok(None),
+        Some(raw) => DateTime::parse_from_rfc3339(raw)
+            .map(|dt| Some(dt.with_timezone(&Utc)))
+            .map_err(|_| ApiError::BadRequest("Invalid muted_until".into())),
+    }
+}
+
+fn settings_to_json(
+    guild: &GuildNotificationSettingsRow,
+    overrides: &[ChannelNotificationOverrideRow],
+) -> Value {
+    json!({
+        "guild_id": guild.guild_id.to_string(),
+        "level": NotificationLevel::from_db(guild.level).as_str(),
+        "muted": guild.muted,
+        "muted_until": guild.muted_until.map(|at| at.to_rfc3339()),
+        "suppress_everyone": guild.suppress_everyone,
+        "channel_overrides": overrides.iter().map(|o| json!({
+            "channel_id": o.channel_id.to_string(),
+            "level": o.level.map(|level| NotificationLevel::from_db(level).as_str()),
+            "muted": o.muted,
+            "muted_until": o.muted_until.map(|at| at.to_rfc3339()),
+        })).collect::<Vec<Value>>(),
+    })
+}
+
+async fn load_settings(
+    state: &AppState,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<
+    (
+        GuildNotificationSettingsRow,
+        Vec<ChannelNotificationOverrideRow>,
+    ),
+    ApiError,
+> {
+    let guild =
+        paracord_db::notification_settings::get_guild_settings(&state.db, user_id, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .unwrap_or_else(|| GuildNotificationSettingsRow::defaults(user_id, guild_id));
+    let overrides =
+        paracord_db::notification_settings::get_channel_overrides(&state.db, user_id, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok((guild, overrides))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/guilds/{guild_id}/notification-settings",
+    tag = "users",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_notification_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let (guild, overrides) = load_settings(&state, auth.user_id, guild_id).await?;
+    Ok(Json(settings_to_json(&guild, &overrides)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChannelOverrideRequest {
+    pub channel_id: String,
+    /// `all`, `mentions`, `none`, or `null` to use the guild level.
+    pub level: Option<String>,
+    #[serde(default)]
+    pub muted: bool,
+    /// RFC 3339 time the mute ends; omit to mute until unmuted.
+    pub muted_until: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateNotificationSettingsRequest {
+    pub level: Option<String>,
+    pub muted: Option<bool>,
+    /// RFC 3339 time the mute ends; `""` clears it.
+    pub muted_until: Option<String>,
+    pub suppress_everyone: Option<bool>,
+    /// Replaces the override of each listed channel. An entry with no level
+    /// that isn't muted removes the override.
+    pub channel_overrides: Option<Vec<ChannelOverrideRequest>>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/users/@me/guilds/{guild_id}/notification-settings",
+    tag = "users",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateNotificationSettingsRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_notification_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateNotificationSettingsRequest>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let overrides = body.channel_overrides.unwrap_or_default();
+    if overrides.len() > MAX_CHANNEL_OVERRIDES_PER_REQUEST {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_CHANNEL_OVERRIDES_PER_REQUEST} channel overrides per request"
+        )));
+    }
+
+    let (mut guild, _) = load_settings(&state, auth.user_id, guild_id).await?;
+    if let Some(level) = body.level.as_deref() {
+        guild.level = parse_level(level)?.to_db();
+    }
+    if let Some(muted) = body.muted {
+        guild.muted = muted;
+        if !muted {
+            guild.muted_until = None;
+        }
+    }
+    if body.muted_until.is_some() {
+        guild.muted_until = parse_muted_until(body.muted_until.as_deref())?;
+    }
+    if let Some(suppress_everyone) = body.suppress_everyone {
+        guild.suppress_everyone = suppress_everyone;
+    }
+
+    let mut parsed_overrides = Vec::with_capacity(overrides.len());
+    for entry in &overrides {
+        let channel_id = entry
+            .channel_id
+            .parse::<i64>()
+            .map_err(|_| ApiError::BadRequest("Invalid channel_id".into()))?;
+        let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .filter(|channel| channel.guild_id() == Some(guild_id))
+            .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+        parsed_overrides.push(ChannelNotificationOverrideRow {
+            user_id: auth.user_id,
+            channel_id: channel.id,
+            guild_id,
+            level: entry
+                .level
+                .as_deref()
+                .map(parse_level)
+                .transpose()?
+                .map(NotificationLevel::to_db),
+            muted: entry.muted,
+            muted_until: if entry.muted {
+                parse_muted_until(entry.muted_until.as_deref())?
+            } else {
+                None
+            },
+        });
+    }
+
+    paracord_db::notification_settings::upsert_guild_settings(&state.db, &guild)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    for row in &parsed_overrides {
+        let result = if row.level.is_none() && !row.muted {
+            paracord_db::notification_settings::delete_channel_override(
+                &state.db,
+                auth.user_id,
+                row.channel_id,
+            )
+            .await
+        } else {
+            paracord_db::notification_settings::upsert_channel_override(&state.db, row)
+                .await
+                .map(|_| ())
+        };
+        result.map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    let (guild, overrides) = load_settings(&state, auth.user_id, guild_id).await?;
+    let payload = settings_to_json(&guild, &overrides);
+    state.event_bus.dispatch_to_users(
+        EVENT_USER_GUILD_SETTINGS_UPDATE,
+        payload.clone(),
+        vec![auth.user_id],
+    );
+    Ok(Json(payload))
+}
@@ -0,0 +1,222 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use base64::engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use paracord_core::push::MAX_SUBSCRIPTIONS_PER_USER;
+use paracord_core::AppState;
+use paracord_db::push_subscriptions::PushSubscriptionRow;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+
+const MAX_ENDPOINT_LEN: usize = 2048;
+const MAX_USER_AGENT_LEN: usize = 256;
+/// Uncompressed P-256 point.
+const P256DH_LEN: usize = 65;
+const AUTH_SECRET_LEN: usize = 16;
+
+fn subscription_to_json(row: &PushSubscriptionRow) -> Value {
+    json!({
+        "id": row.id.to_string(),
+        "endpoint": row.endpoint,
+        "user_agent": row.user_agent,
+        "created_at": row.created_at.to_rfc3339(),
+        "last_used_at": row.last_used_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Push services are reached by name; IP literals and plain HTTP are refused
+/// so a subscription can't point the server at an internal address.
+fn validate_endpoint(raw: &str) -> Result<String, ApiError> {
+    let invalid = || ApiError::BadRequest("endpoint must be an https:// URL".into());
+    let raw = raw.trim();
+    if raw.is_empty() || raw.len() > MAX_ENDPOINT_LEN {
+        return Err(invalid());
+    }
+    let url = url::Url::parse(raw).map_err(|_| invalid())?;
+    let named_host = matches!(url.host(), Some(url::Host::Domain(_)));
+    if url.scheme() != "https"
+        || !named_host
+        || !url.username().is_empty()
+        || url.password().is_some()
+    {
+        return Err(invalid());
+    }
+    Ok(url.to_string())
+}
+
+/// Decode a base64url key (padded or not) and check its length.
+fn decode_key(raw: &str, len: usize, field: &str) -> Result<String, ApiError> {
+    let raw = raw.trim();
+    let bytes = URL_SAFE_NO_PAD
+        .decode(raw.trim_end_matches('='))
+        .or_else(|_| URL_SAFE.decode(raw))
+        .map_err(|_| ApiError::BadRequest(format!("keys.{field} must be base64url")))?;
+    if bytes.len() != len || (len == P256DH_LEN && bytes[0] != 0x04) {
+        return Err(ApiError::BadRequest(format!("Invalid keys.{field}")));
+    }
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/push-subscriptions",
+    tag = "users",
+    responses((status = 200, description = "VAPID public key and the caller's subscriptions", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_push_subscriptions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let rows = paracord_db::push_subscriptions::list_user_subscriptions(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!({
+        "vapid_public_key": state.config.web_push_public_key,
+        "subscriptions": rows.iter().map(subscription_to_json).collect::<Vec<_>>(),
+    })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PushSubscriptionKeys {
+    /// Client public key, base64url.
+    pub p256dh: String,
+    /// Client auth secret, base64url.
+    pub auth: String,
+}
+
+/// The browser's `PushSubscription.toJSON()`.
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePushSubscriptionRequest {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/push-subscriptions",
+    tag = "users",
+    request_body = CreatePushSubscriptionRequest,
+    responses(
+        (status = 201, description = "Subscription registered", body = Value),
+        (status = 503, description = "Web Push is not configured"),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn create_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Json(body): Json<CreatePushSubscriptionRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if state.config.web_push_public_key.is_none() {
+        return Err(ApiError::ServiceUnavailable(
+            "Web Push is not configured".into(),
+        ));
+    }
+    let endpoint = validate_endpoint(&body.endpoint)?;
+    let p256dh = decode_key(&body.keys.p256dh, P256DH_LEN, "p256dh")?;
+    let auth_secret = decode_key(&body.keys.auth, AUTH_SECRET_LEN, "auth")?;
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect::<String>());
+
+    let existing =
+        paracord_db::push_subscriptions::list_user_subscriptions(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if existing.len() >= MAX_SUBSCRIPTIONS_PER_USER
+        && !existing.iter().any(|row| row.endpoint == endpoint)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_SUBSCRIPTIONS_PER_USER} push subscriptions per user"
+        )));
+    }
+
+    let row = paracord_db::push_subscriptions::upsert_subscription(
+        &state.db,
+        paracord_util::snowflake::try_next_id().await?,
+        auth.user_id,
+        &endpoint,
+        &p256dh,
+        &auth_secret,
+        user_agent.as_deref(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok((StatusCode::CREATED, Json(subscription_to_json(&row))))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeletePushSubscriptionRequest {
+    pub endpoint: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me/push-subscriptions",
+    tag = "users",
+    request_body = DeletePushSubscriptionRequest,
+    responses(
+        (status = 204, description = "Subscription removed"),
+        (status = 404, description = "No such subscription"),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn delete_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<DeletePushSubscriptionRequest>,
+) -> Result<StatusCode, ApiError> {
+    let endpoint = url::Url::parse(body.endpoint.trim())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| body.endpoint.trim().to_string());
+    let deleted = paracord_db::push_subscriptions::delete_user_subscription(
+        &state.db,
+        auth.user_id,
+        &endpoint,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_must_be_named_https_hosts() {
+        assert!(validate_endpoint("https://fcm.googleapis.com/fcm/send/abc").is_ok());
+        assert!(validate_endpoint("http://push.example.com/x").is_err());
+        assert!(validate_endpoint("https://127.0.0.1/x").is_err());
+        assert!(validate_endpoint("https://[::1]/x").is_err());
+        assert!(validate_endpoint("https://user:pw@push.example.com/x").is_err());
+    }
+
+    #[test]
+    fn keys_are_normalized_and_length_checked() {
+        let mut point = vec![0x04];
+        point.extend([7_u8; 64]);
+        let padded = URL_SAFE.encode(&point);
+        assert_eq!(
+            decode_key(&padded, P256DH_LEN, "p256dh").unwrap(),
+            URL_SAFE_NO_PAD.encode(&point)
+        );
+        assert!(decode_key(&URL_SAFE_NO_PAD.encode([4_u8; 16]), P256DH_LEN, "p256dh").is_err());
+        assert!(decode_key(&URL_SAFE_NO_PAD.encode([1_u8; 16]), AUTH_SECRET_LEN, "auth").is_ok());
+    }
+}
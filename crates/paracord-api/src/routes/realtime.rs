@@ -1,28 +1,39 @@
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     response::sse::{Event, KeepAlive, Sse},
     Json,
 };
 use chrono::Utc;
 use futures_util::stream;
+use paracord_core::gateway_session::{self, GatewaySession};
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct RealtimeEventsQuery {
     pub session_id: Option<String>,
     pub cursor: Option<u64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct RealtimeAckRequest {
+    pub session_id: String,
+    pub cursor: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct RealtimeCommandRequest {
     pub command_id: String,
     #[serde(rename = "type")]
@@ -127,10 +138,9 @@ async fn build_ready_payload(state: &AppState, user_id: i64, session_id: &str) -
 
 struct RealtimeStreamState {
     app_state: AppState,
-    session_id: String,
-    user_id: i64,
-    sequence: u64,
-    ready_payload: Option<String>,
+    session: GatewaySession,
+    /// Frames sent before live events: READY, or RESUMED plus the replay.
+    pending: VecDeque<(u64, String)>,
     receiver: tokio::sync::broadcast::Receiver<paracord_core::events::ServerEvent>,
 }
 
@@ -138,10 +148,17 @@ impl Drop for RealtimeStreamState {
     fn drop(&mut self) {
         self.app_state
             .event_bus
-            .unregister_session(&self.session_id);
+            .unregister_session(&self.session.session_id);
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/rt/session",
+    tag = "realtime",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_session(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -165,130 +182,137 @@ pub async fn create_session(
     })))
 }
 
+/// The resume cursor: the `cursor` query parameter, or the `Last-Event-ID`
+/// header an `EventSource` sends when it reconnects on its own.
+fn resume_cursor(query: &RealtimeEventsQuery, headers: &HeaderMap) -> u64 {
+    query
+        .cursor
+        .or_else(|| {
+            headers
+                .get("last-event-id")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/rt/events",
+    tag = "realtime",
+    operation_id = "realtime_stream_events",
+    params(RealtimeEventsQuery),
+    responses((status = 200, description = "Event stream", content_type = "text/event-stream")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn stream_events(
     State(state): State<AppState>,
     auth: AuthUser,
+    headers: HeaderMap,
     Query(query): Query<RealtimeEventsQuery>,
 ) -> Result<Sse<impl futures_util::Stream<Item = Result<Event, Infallible>>>, ApiError> {
-    let session_id = query
-        .session_id
-        .filter(|sid| !sid.trim().is_empty())
-        .unwrap_or_else(|| Uuid::new_v4().to_string());
-    let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, auth.user_id)
-        .await
-        .unwrap_or_default()
-        .iter()
-        .map(|g| g.id)
-        .collect();
-    let receiver = state
-        .event_bus
-        .register_session(session_id.clone(), auth.user_id, &guild_ids);
-    let start_sequence = query.cursor.unwrap_or(0);
-    let ready_payload = build_ready_payload(&state, auth.user_id, &session_id)
-        .await
-        .to_string();
+    let cursor = resume_cursor(&query, &headers);
+    let requested_session_id = query.session_id.filter(|sid| !sid.trim().is_empty());
+    let mut session = GatewaySession::for_user(&state, auth.user_id).await;
+    session.auth_session_id = auth.session_id.clone();
+    if let Some(session_id) = &requested_session_id {
+        session.session_id = session_id.clone();
+    }
+    session.load_filters(&state).await;
+
+    let replay = requested_session_id
+        .as_ref()
+        .filter(|_| cursor > 0)
+        .and_then(|session_id| gateway_session::replay_since(session_id, auth.user_id, cursor));
+    let mut pending = VecDeque::new();
+    if let Some((latest, events)) = replay {
+        session.sequence = latest;
+        let resumed = json!({
+            "event_id": cursor,
+            "op": 0,
+            "t": "RESUMED",
+            "s": latest,
+            "d": { "session_id": &session.session_id },
+        });
+        pending.push_back((cursor, resumed.to_string()));
+        pending.extend(
+            events
+                .iter()
+                .map(|dispatch| (dispatch.sequence, dispatch.sse_frame())),
+        );
+    } else {
+        // Nothing to resume from; start over so stale events can't be
+        // replayed into the new sequence.
+        gateway_session::invalidate_replay(&session.session_id);
+        session.sequence = 1;
+        let ready = build_ready_payload(&state, auth.user_id, &session.session_id).await;
+        pending.push_back((1, ready.to_string()));
+    }
+
+    let receiver = state.event_bus.register_session(
+        session.session_id.clone(),
+        auth.user_id,
+        &session.guild_ids,
+    );
     let stream_state = RealtimeStreamState {
         app_state: state,
-        session_id,
-        user_id: auth.user_id,
-        sequence: start_sequence,
-        ready_payload: Some(ready_payload),
+        session,
+        pending,
         receiver,
     };
 
     let event_stream = stream::unfold(stream_state, |mut st| async move {
-        if let Some(payload) = st.ready_payload.take() {
-            let event = Event::default().event("gateway").id("1").data(payload);
+        if let Some((event_id, payload)) = st.pending.pop_front() {
+            let event = Event::default()
+                .event("gateway")
+                .id(event_id.to_string())
+                .data(payload);
             return Some((Ok(event), st));
         }
 
-        match st.receiver.recv().await {
-            Ok(event) => {
-                if event.event_type == "GUILD_MEMBER_ADD" {
-                    if let Some(uid) = event.payload.get("user_id").and_then(|v| v.as_str()) {
-                        if uid == st.user_id.to_string() {
-                            if let Some(gid) = event
-                                .payload
-                                .get("guild_id")
-                                .and_then(|v| v.as_str())
-                                .and_then(|s| s.parse::<i64>().ok())
-                            {
-                                st.app_state
-                                    .event_bus
-                                    .add_session_guild(&st.session_id, gid);
-                            }
-                        }
-                    }
-                } else if event.event_type == "GUILD_MEMBER_REMOVE"
-                    || event.event_type == "GUILD_BAN_ADD"
-                {
-                    if let Some(uid) = event.payload.get("user_id").and_then(|v| v.as_str()) {
-                        if uid == st.user_id.to_string() {
-                            if let Some(gid) = event
-                                .payload
-                                .get("guild_id")
-                                .and_then(|v| v.as_str())
-                                .and_then(|s| s.parse::<i64>().ok())
-                            {
-                                st.app_state
-                                    .event_bus
-                                    .remove_session_guild(&st.session_id, gid);
-                            }
-                        }
-                    }
-                } else if event.event_type == "GUILD_DELETE" {
-                    if let Some(gid) = event
-                        .payload
-                        .get("id")
-                        .or_else(|| event.payload.get("guild_id"))
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| s.parse::<i64>().ok())
-                    {
-                        st.app_state
-                            .event_bus
-                            .remove_session_guild(&st.session_id, gid);
-                    }
+        loop {
+            match st.receiver.recv().await {
+                Ok(event) => {
+                    let Some(dispatch) =
+                        gateway_session::prepare_dispatch(&st.app_state, &mut st.session, &event)
+                            .await
+                    else {
+                        continue;
+                    };
+                    paracord_core::receipts::note_event_delivered(
+                        &st.app_state,
+                        st.session.user_id,
+                        &event.event_type,
+                        event.guild_id,
+                        &event.payload,
+                    );
+                    let sse_event = Event::default()
+                        .event("gateway")
+                        .id(dispatch.sequence.to_string())
+                        .data(dispatch.sse_frame());
+                    return Some((Ok(sse_event), st));
                 }
-                st.sequence = st.sequence.saturating_add(1);
-                let event_data = if let Some(serialized) = event.serialized_payload {
-                    format!(
-                        r#"{{"event_id":{},"op":0,"t":"{}","s":{},"d":{}}}"#,
-                        st.sequence, event.event_type, st.sequence, serialized
-                    )
-                } else {
-                    json!({
-                        "event_id": st.sequence,
-                        "op": 0,
-                        "t": event.event_type,
-                        "s": st.sequence,
-                        "d": *event.payload,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    // The skipped events were never buffered, so the client
+                    // has to start over rather than resume.
+                    gateway_session::invalidate_replay(&st.session.session_id);
+                    let reconnect = json!({
+                        "event_id": st.session.sequence,
+                        "op": 7,
+                        "d": {
+                            "reason": "lagged",
+                            "skipped": skipped,
+                        }
                     })
-                    .to_string()
-                };
-                let sse_event = Event::default()
-                    .event("gateway")
-                    .id(st.sequence.to_string())
-                    .data(event_data);
-                Some((Ok(sse_event), st))
-            }
-            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                st.sequence = st.sequence.saturating_add(1);
-                let reconnect = json!({
-                    "event_id": st.sequence,
-                    "op": 7,
-                    "d": {
-                        "reason": "lagged",
-                        "skipped": skipped,
-                    }
-                })
-                .to_string();
-                let sse_event = Event::default()
-                    .event("gateway")
-                    .id(st.sequence.to_string())
-                    .data(reconnect);
-                Some((Ok(sse_event), st))
+                    .to_string();
+                    let sse_event = Event::default()
+                        .event("gateway")
+                        .id(st.session.sequence.to_string())
+                        .data(reconnect);
+                    return Some((Ok(sse_event), st));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
             }
-            Err(tokio::sync::broadcast::error::RecvError::Closed) => None,
         }
     });
 
@@ -299,6 +323,35 @@ pub async fn stream_events(
     ))
 }
 
+/// Acknowledge everything up to `cursor` so the server stops buffering it
+/// for replay. The WebSocket gateway does the same with heartbeat sequences.
+#[utoipa::path(
+    post,
+    path = "/api/v2/rt/ack",
+    tag = "realtime",
+    request_body = RealtimeAckRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn post_ack(
+    auth: AuthUser,
+    Json(req): Json<RealtimeAckRequest>,
+) -> Result<Json<Value>, ApiError> {
+    if req.session_id.trim().is_empty() {
+        return Err(ApiError::BadRequest("session_id is required".into()));
+    }
+    gateway_session::acknowledge(&req.session_id, auth.user_id, req.cursor);
+    Ok(Json(json!({ "ok": true, "cursor": req.cursor })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/rt/commands",
+    tag = "realtime",
+    request_body = RealtimeCommandRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn post_command(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -337,28 +390,21 @@ pub async fn post_command(
                 .await
                 .insert(auth.user_id, presence_payload.clone());
 
-            let mut recipients: std::collections::HashSet<i64> = std::collections::HashSet::new();
-            recipients.insert(auth.user_id);
-            if let Ok(guilds) = paracord_db::guilds::get_user_guilds(&state.db, auth.user_id).await
-            {
-                for guild in guilds {
-                    if let Ok(member_ids) =
-                        paracord_db::members::get_guild_member_user_ids(&state.db, guild.id).await
-                    {
-                        recipients.extend(member_ids);
-                    }
-                }
-            }
-            if let Ok(friend_ids) =
-                paracord_db::relationships::get_friend_user_ids(&state.db, auth.user_id).await
-            {
-                recipients.extend(friend_ids);
-            }
-            state.event_bus.dispatch_to_users(
-                "PRESENCE_UPDATE",
-                presence_payload,
-                recipients.into_iter().collect(),
-            );
+            let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, auth.user_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|guild| guild.id)
+                .collect();
+            let recipients = paracord_core::presence_manager::presence_recipients(
+                &state,
+                auth.user_id,
+                &guild_ids,
+            )
+            .await;
+            state
+                .event_bus
+                .dispatch_to_users("PRESENCE_UPDATE", presence_payload, recipients);
         }
         "voice_state_update" => {
             let payload: VoiceStateCommandPayload = serde_json::from_value(req.payload.clone())
@@ -374,7 +420,7 @@ pub async fn post_command(
                 let channel = paracord_db::channels::get_channel(&state.db, channel_id)
                     .await
                     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                    .ok_or(ApiError::NotFound)?;
+                    .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
                 if channel.channel_type != 2 {
                     return Err(ApiError::BadRequest("Not a voice channel".into()));
                 }
@@ -389,7 +435,7 @@ pub async fn post_command(
                 let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
                     .await
                     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                    .ok_or(ApiError::NotFound)?;
+                    .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
                 let perms = paracord_core::permissions::compute_channel_permissions(
                     &state.db,
                     guild_id,
@@ -401,7 +447,7 @@ pub async fn post_command(
                 if !perms.contains(Permissions::VIEW_CHANNEL)
                     || !perms.contains(Permissions::CONNECT)
                 {
-                    return Err(ApiError::Forbidden);
+                    return Err(ApiError::Code(ErrorCode::MissingPermissions));
                 }
 
                 let session_id = auth
@@ -424,6 +470,13 @@ pub async fn post_command(
                     .voice
                     .update_self_deaf(channel_id, auth.user_id, self_deaf)
                     .await;
+                // Deafen implies mute; the native relay stops forwarding the
+                // user's audio and clears their speaking indicator.
+                if let Some(media) = &state.native_media {
+                    media
+                        .relay_forwarder
+                        .set_muted(auth.user_id, self_mute || self_deaf);
+                }
 
                 let current_self_stream = state
                     .voice
@@ -506,7 +559,7 @@ pub async fn post_command(
             let channel = paracord_db::channels::get_channel(&state.db, channel_id)
                 .await
                 .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-                .ok_or(ApiError::NotFound)?;
+                .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
             let guild_id = channel.guild_id();
 
             let allowed = if let Some(gid) = guild_id {
@@ -568,6 +621,13 @@ pub async fn post_command(
                     .dispatch("TYPING_START", typing_payload, guild_id);
             }
         }
+        "read_state_ack" => {
+            let payload: paracord_core::read_state::ReadAckBatch =
+                serde_json::from_value(req.payload.clone()).map_err(|e| {
+                    ApiError::BadRequest(format!("invalid read_state_ack payload: {e}"))
+                })?;
+            paracord_core::read_state::apply_acks(&state, auth.user_id, &payload.acks).await?;
+        }
         _ => {
             return Err(ApiError::BadRequest("Unsupported command type".into()));
         }
@@ -0,0 +1,504 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use paracord_core::AppState;
+use paracord_db::jobs::JobRow;
+use paracord_db::voice_recordings::{
+    VoiceRecordingRow, RECORDING_KIND_FILE, RECORDING_KIND_RTMP, RECORDING_STATUS_COMPLETE,
+    RECORDING_STATUS_FAILED,
+};
+use paracord_media::{EgressInfo, EgressOutput};
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+
+const MAX_RTMP_TARGETS: usize = 3;
+const MAX_RTMP_URL_LEN: usize = 2048;
+const RECORDING_LIST_LIMIT: i64 = 50;
+
+#[derive(Deserialize, ToSchema)]
+pub struct StartRecordingRequest {
+    /// `file` (recorded to the storage backend) or `rtmp` (restream).
+    pub kind: String,
+    /// RTMP ingest URLs including the stream key, for `rtmp` recordings.
+    #[serde(default)]
+    pub rtmp_urls: Vec<String>,
+    /// Skip video tracks and record/stream audio only.
+    #[serde(default)]
+    pub audio_only: bool,
+}
+
+fn recording_to_json(recording: &VoiceRecordingRow) -> Value {
+    json!({
+        "id": recording.id.to_string(),
+        "guild_id": recording.guild_id.to_string(),
+        "channel_id": recording.channel_id.to_string(),
+        "kind": recording.kind,
+        "status": recording.status,
+        "started_by": recording.started_by.map(|id| id.to_string()),
+        "attachment_id": recording.attachment_id.map(|id| id.to_string()),
+        "error": recording.error,
+        "created_at": recording.created_at,
+        "ended_at": recording.ended_at,
+    })
+}
+
+/// Load a guild voice channel and check the caller's permissions in it.
+async fn voice_channel_context(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+    required: Permissions,
+) -> Result<i64, ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    if channel.channel_type != 2 {
+        return Err(ApiError::BadRequest("Not a voice channel".into()));
+    }
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Voice is only supported in guild channels".into(),
+    ))?;
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let perms = paracord_core::permissions::compute_channel_permissions(
+        &state.db,
+        guild_id,
+        channel_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    paracord_core::permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
+    paracord_core::permissions::require_permission(perms, required)?;
+    Ok(guild_id)
+}
+
+fn validate_rtmp_urls(urls: &[String]) -> Result<Vec<String>, ApiError> {
+    if urls.is_empty() || urls.len() > MAX_RTMP_TARGETS {
+        return Err(ApiError::BadRequest(format!(
+            "rtmp_urls must contain between 1 and {} URLs",
+            MAX_RTMP_TARGETS
+        )));
+    }
+    urls.iter()
+        .map(|url| {
+            let url = url.trim();
+            if url.len() > MAX_RTMP_URL_LEN
+                || !(url.starts_with("rtmp://") || url.starts_with("rtmps://"))
+            {
+                return Err(ApiError::BadRequest(
+                    "rtmp_urls must be rtmp:// or rtmps:// URLs".into(),
+                ));
+            }
+            Ok(url.to_string())
+        })
+        .collect()
+}
+
+fn recording_extension(audio_only: bool) -> &'static str {
+    if audio_only {
+        "ogg"
+    } else {
+        "mp4"
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/{channel_id}/recordings",
+    tag = "recordings",
+    params(("channel_id" = i64, Path)),
+    request_body = StartRecordingRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn start_recording(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<StartRecordingRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if !state.config.livekit_available {
+        return Err(ApiError::ServiceUnavailable(
+            "Recording requires LiveKit, which is not available on this server".into(),
+        ));
+    }
+    let guild_id =
+        voice_channel_context(&state, channel_id, auth.user_id, Permissions::RECORD_VOICE).await?;
+
+    let recording_id = paracord_util::snowflake::try_next_id().await?;
+    let output = match body.kind.as_str() {
+        RECORDING_KIND_FILE => {
+            let dir = state.voice.recording_dir().ok_or_else(|| {
+                ApiError::ServiceUnavailable("File recording is not configured".into())
+            })?;
+            let filename = format!("{}.{}", recording_id, recording_extension(body.audio_only));
+            EgressOutput::File {
+                filepath: dir.join(filename).to_string_lossy().into_owned(),
+            }
+        }
+        RECORDING_KIND_RTMP => EgressOutput::Rtmp {
+            urls: validate_rtmp_urls(&body.rtmp_urls)?,
+        },
+        _ => {
+            return Err(ApiError::BadRequest("kind must be 'file' or 'rtmp'".into()));
+        }
+    };
+
+    let active =
+        paracord_db::voice_recordings::get_active_channel_recordings(&state.db, channel_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if active.iter().any(|recording| recording.kind == body.kind) {
+        return Err(ApiError::Conflict(
+            "A recording of this kind is already running in this channel".into(),
+        ));
+    }
+
+    let room_name = state.voice.livekit_room(channel_id).await.ok_or_else(|| {
+        ApiError::BadRequest("No one is connected to this channel over LiveKit".into())
+    })?;
+    let egress = state
+        .voice
+        .start_egress(&room_name, &output, body.audio_only)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to start egress for channel {}: {}", channel_id, e);
+            ApiError::ServiceUnavailable("Failed to start recording".into())
+        })?;
+
+    let recording = paracord_db::voice_recordings::create_recording(
+        &state.db,
+        recording_id,
+        guild_id,
+        channel_id,
+        &egress.egress_id,
+        &body.kind,
+        auth.user_id,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let payload = recording_to_json(&recording);
+    state.event_bus.dispatch(
+        paracord_models::gateway::EVENT_VOICE_RECORDING_START,
+        payload.clone(),
+        Some(guild_id),
+    );
+
+    Ok((StatusCode::CREATED, Json(payload)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/{channel_id}/recordings/{recording_id}/stop",
+    tag = "recordings",
+    params(("channel_id" = i64, Path), ("recording_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn stop_recording(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, recording_id)): Path<(i64, i64)>,
+) -> Result<Json<Value>, ApiError> {
+    voice_channel_context(&state, channel_id, auth.user_id, Permissions::RECORD_VOICE).await?;
+    let recording = paracord_db::voice_recordings::get_recording(&state.db, recording_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|recording| recording.channel_id == channel_id)
+        .ok_or(ApiError::NotFound)?;
+    if recording.is_finished() {
+        return Err(ApiError::BadRequest("Recording has already ended".into()));
+    }
+
+    // The recording is finalized when LiveKit reports `egress_ended`.
+    state
+        .voice
+        .stop_egress(&recording.egress_id)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to stop egress {}: {}", recording.egress_id, e);
+            ApiError::ServiceUnavailable("Failed to stop recording".into())
+        })?;
+
+    Ok(Json(recording_to_json(&recording)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/voice/{channel_id}/recordings",
+    tag = "recordings",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_recordings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    voice_channel_context(&state, channel_id, auth.user_id, Permissions::VIEW_CHANNEL).await?;
+    let recordings = paracord_db::voice_recordings::list_channel_recordings(
+        &state.db,
+        channel_id,
+        RECORDING_LIST_LIMIT,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(Value::Array(
+        recordings.iter().map(recording_to_json).collect(),
+    )))
+}
+
+/// Apply an `egress_started` / `egress_updated` / `egress_ended` webhook to
+/// the matching recording.
+pub(crate) async fn handle_egress_event(
+    state: &AppState,
+    event: &str,
+    info: EgressInfo,
+) -> Result<(), ApiError> {
+    let Some(recording) =
+        paracord_db::voice_recordings::get_recording_by_egress(&state.db, &info.egress_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    else {
+        return Ok(());
+    };
+    if recording.is_finished() {
+        return Ok(());
+    }
+
+    if event == "egress_ended" || info.is_complete() || info.is_failed() {
+        // Importing a file recording can take a while; acknowledge the
+        // webhook right away so LiveKit does not retry it.
+        paracord_core::jobs::enqueue(
+            &state.db,
+            crate::jobs::JOB_VOICE_RECORDING_FINALIZE,
+            &json!({ "recording_id": recording.id.to_string(), "info": info }),
+            paracord_core::jobs::JobOptions {
+                priority: 10,
+                ..Default::default()
+            },
+        )
+        .await?;
+        return Ok(());
+    }
+
+    if info.status.as_deref() == Some("EGRESS_ACTIVE") {
+        let updated = paracord_db::voice_recordings::mark_recording_active(&state.db, recording.id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if updated {
+            dispatch_recording_event(
+                state,
+                paracord_models::gateway::EVENT_VOICE_RECORDING_UPDATE,
+                recording.id,
+            )
+            .await;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct FinalizeJobPayload {
+    recording_id: String,
+    info: EgressInfo,
+}
+
+/// Background job: import the output of an ended egress and mark the
+/// recording finished. Failed imports are retried until the job's last
+/// attempt, which marks the recording failed.
+pub(crate) async fn run_finalize_job(state: AppState, job: JobRow) -> Result<(), String> {
+    let payload: FinalizeJobPayload = paracord_core::jobs::payload(&job)?;
+    let recording_id = payload
+        .recording_id
+        .parse::<i64>()
+        .map_err(|_| "Invalid recording_id".to_string())?;
+    let Some(recording) = paracord_db::voice_recordings::get_recording(&state.db, recording_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|recording| !recording.is_finished())
+    else {
+        return Ok(());
+    };
+    finalize_recording(&state, recording, payload.info, job.is_last_attempt()).await
+}
+
+async fn finalize_recording(
+    state: &AppState,
+    recording: VoiceRecordingRow,
+    info: EgressInfo,
+    last_attempt: bool,
+) -> Result<(), String> {
+    let result = if !info.is_complete() {
+        Err(info
+            .error
+            .clone()
+            .filter(|error| !error.is_empty())
+            .unwrap_or_else(|| "Recording ended without output".to_string()))
+    } else if recording.kind == RECORDING_KIND_FILE {
+        match import_recording_file(state, &recording).await {
+            Ok(attachment_id) => Ok(Some(attachment_id)),
+            Err(error) if !last_attempt => return Err(error),
+            Err(error) => Err(error),
+        }
+    } else {
+        Ok(None)
+    };
+
+    let finished = match &result {
+        Ok(attachment_id) => {
+            paracord_db::voice_recordings::finish_recording(
+                &state.db,
+                recording.id,
+                RECORDING_STATUS_COMPLETE,
+                *attachment_id,
+                None,
+            )
+            .await
+        }
+        Err(error) => {
+            tracing::warn!("Voice recording {} failed: {}", recording.id, error);
+            paracord_db::voice_recordings::finish_recording(
+                &state.db,
+                recording.id,
+                RECORDING_STATUS_FAILED,
+                None,
+                Some(error),
+            )
+            .await
+        }
+    };
+    match finished {
+        Ok(true) => {
+            dispatch_recording_event(
+                state,
+                paracord_models::gateway::EVENT_VOICE_RECORDING_STOP,
+                recording.id,
+            )
+            .await;
+        }
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Failed to finish voice recording {}: {}", recording.id, e),
+    }
+    Ok(())
+}
+
+/// Move a finished recording from the egress output directory into the
+/// storage backend and register it as an attachment of the voice channel.
+async fn import_recording_file(
+    state: &AppState,
+    recording: &VoiceRecordingRow,
+) -> Result<i64, String> {
+    let dir = state
+        .voice
+        .recording_dir()
+        .ok_or_else(|| "File recording is not configured".to_string())?;
+    // Look for the file under the name we asked for rather than trusting the
+    // path reported back by the egress service.
+    let (path, ext) = ["mp4", "ogg"]
+        .into_iter()
+        .map(|ext| (dir.join(format!("{}.{}", recording.id, ext)), ext))
+        .find(|(path, _)| path.exists())
+        .ok_or_else(|| "Recording file not found".to_string())?;
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    if data.is_empty() {
+        return Err("Recording file is empty".to_string());
+    }
+    let size = i32::try_from(data.len()).map_err(|_| "Recording is too large".to_string())?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&data);
+    let content_hash = format!("{:x}", hasher.finalize());
+
+    let attachment_id = paracord_util::snowflake::try_next_id()
+        .await
+        .map_err(|e| e.to_string())?;
+    let stored_payload = if let Some(cryptor) = state.config.file_cryptor.as_ref() {
+        let aad = super::files::attachment_aad(attachment_id);
+        cryptor
+            .encrypt_with_aad(&data, aad.as_bytes())
+            .map_err(|e| e.to_string())?
+    } else {
+        data
+    };
+    let storage_key = format!("attachments/{}.{}", attachment_id, ext);
+    state
+        .storage_backend
+        .store(&storage_key, &stored_payload)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let filename = format!(
+        "recording-{}-{}.{}",
+        recording.channel_id,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+        ext
+    );
+    let content_type = if ext == "ogg" {
+        "audio/ogg"
+    } else {
+        "video/mp4"
+    };
+    let url = format!("/api/v1/attachments/{}", attachment_id);
+    let created = paracord_db::attachments::create_attachment(
+        &state.db,
+        attachment_id,
+        None,
+        &filename,
+        Some(content_type),
+        size,
+        &url,
+        None,
+        None,
+        recording.started_by,
+        Some(recording.channel_id),
+        None,
+        Some(&content_hash),
+    )
+    .await;
+    if let Err(e) = created {
+        let _ = state.storage_backend.delete(&storage_key).await;
+        return Err(e.to_string());
+    }
+
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        tracing::warn!(
+            "Failed to remove imported recording {}: {}",
+            path.display(),
+            e
+        );
+    }
+    Ok(attachment_id)
+}
+
+async fn dispatch_recording_event(state: &AppState, event: &str, recording_id: i64) {
+    match paracord_db::voice_recordings::get_recording(&state.db, recording_id).await {
+        Ok(Some(recording)) => {
+            state.event_bus.dispatch(
+                event,
+                recording_to_json(&recording),
+                Some(recording.guild_id),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to load voice recording {}: {}", recording_id, e),
+    }
+}
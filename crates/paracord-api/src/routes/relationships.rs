@@ -6,11 +6,13 @@ use axum::{
 use paracord_core::AppState;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
+use crate::routes::dms;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateRelationshipRequest {
     pub user_id: Option<String>,
     pub username: Option<String>,
@@ -18,6 +20,13 @@ pub struct CreateRelationshipRequest {
     pub rel_type: Option<i16>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/relationships",
+    tag = "relationships",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_relationships(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -49,6 +58,14 @@ pub async fn list_relationships(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/relationships",
+    tag = "relationships",
+    request_body = CreateRelationshipRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn add_friend(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -82,10 +99,60 @@ pub async fn add_friend(
     // Check if this is a block request
     let rel_type = body.rel_type.unwrap_or(1);
     if rel_type == 2 {
-        // Block: store directly
+        // Block: store directly, replacing any friendship or pending request.
         paracord_db::relationships::create_relationship(&state.db, auth.user_id, target_id, 2)
             .await
             .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        let theirs =
+            paracord_db::relationships::get_relationship(&state.db, target_id, auth.user_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        // Their own block on us stays in place.
+        if theirs.is_some_and(|rel| rel.rel_type != 2) {
+            paracord_db::relationships::delete_relationship(&state.db, target_id, auth.user_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            state.event_bus.dispatch_to_users(
+                "RELATIONSHIP_REMOVE",
+                json!({ "user_id": auth.user_id.to_string() }),
+                vec![target_id],
+            );
+        }
+        if let Some(tu) = paracord_db::users::get_user_by_id(&state.db, target_id)
+            .await
+            .ok()
+            .flatten()
+        {
+            state.event_bus.dispatch_to_users(
+                "RELATIONSHIP_ADD",
+                json!({
+                    "type": 2,
+                    "user": {
+                        "id": tu.id.to_string(),
+                        "username": tu.username,
+                        "discriminator": tu.discriminator,
+                        "avatar_hash": tu.avatar_hash,
+                    }
+                }),
+                vec![auth.user_id],
+            );
+        }
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    if paracord_db::relationships::has_blocked(&state.db, auth.user_id, target_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::BadRequest(
+            "Unblock this user before sending a friend request".into(),
+        ));
+    }
+    if paracord_db::relationships::has_blocked(&state.db, target_id, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        // Auto-decline without telling the sender they are blocked.
         return Ok(StatusCode::NO_CONTENT);
     }
 
@@ -103,6 +170,7 @@ pub async fn add_friend(
             paracord_db::relationships::create_relationship(&state.db, auth.user_id, target_id, 1)
                 .await
                 .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            dms::accept_requests_between(&state, auth.user_id, target_id).await;
 
             // Notify both users
             let target_user = paracord_db::users::get_user_by_id(&state.db, target_id)
@@ -179,6 +247,14 @@ pub async fn add_friend(
 }
 
 /// Accept an incoming friend request.
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/relationships/{user_id}",
+    tag = "relationships",
+    params(("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn accept_friend(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -197,6 +273,14 @@ pub async fn accept_friend(
             ));
         }
     }
+    if paracord_db::relationships::is_blocked_either_direction(&state.db, auth.user_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::BadRequest(
+            "No pending friend request from this user".into(),
+        ));
+    }
 
     // Accept: update their row to friend, create our row as friend
     paracord_db::relationships::update_relationship(&state.db, user_id, auth.user_id, 1)
@@ -205,6 +289,7 @@ pub async fn accept_friend(
     paracord_db::relationships::create_relationship(&state.db, auth.user_id, user_id, 1)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    dms::accept_requests_between(&state, auth.user_id, user_id).await;
 
     // Notify both users
     let target_user = paracord_db::users::get_user_by_id(&state.db, user_id)
@@ -250,25 +335,38 @@ pub async fn accept_friend(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me/relationships/{user_id}",
+    tag = "relationships",
+    params(("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn remove_relationship(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(target_id): Path<i64>,
 ) -> Result<StatusCode, ApiError> {
-    // Delete both directions so the relationship is fully cleaned up
+    // Delete both directions so the relationship is fully cleaned up, except
+    // that a block can only be lifted by the user who placed it.
     paracord_db::relationships::delete_relationship(&state.db, auth.user_id, target_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    paracord_db::relationships::delete_relationship(&state.db, target_id, auth.user_id)
+    let theirs = paracord_db::relationships::get_relationship(&state.db, target_id, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if theirs.is_some_and(|rel| rel.rel_type != 2) {
+        paracord_db::relationships::delete_relationship(&state.db, target_id, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        state.event_bus.dispatch_to_users(
+            "RELATIONSHIP_REMOVE",
+            json!({ "user_id": auth.user_id.to_string() }),
+            vec![target_id],
+        );
+    }
 
-    // Notify both users
-    state.event_bus.dispatch_to_users(
-        "RELATIONSHIP_REMOVE",
-        json!({ "user_id": auth.user_id.to_string() }),
-        vec![target_id],
-    );
     state.event_bus.dispatch_to_users(
         "RELATIONSHIP_REMOVE",
         json!({ "user_id": target_id.to_string() }),
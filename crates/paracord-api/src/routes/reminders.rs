@@ -0,0 +1,304 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::gateway::EVENT_REMINDER_DUE;
+use paracord_models::permissions::Permissions;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::channels::{ensure_channel_permissions, message_to_json};
+use crate::routes::dms;
+
+const MAX_REMINDER_CONTENT_LEN: usize = 1000;
+const MAX_PENDING_REMINDERS: i64 = 25;
+const MAX_REMINDER_DELAY_DAYS: i64 = 365;
+const REMINDER_BATCH_SIZE: i64 = 100;
+/// Delivered reminders are kept this long before the worker prunes them.
+const DELIVERED_REMINDER_RETENTION_DAYS: i64 = 7;
+
+fn reminder_to_json(reminder: &paracord_db::reminders::ReminderRow) -> Value {
+    json!({
+        "id": reminder.id.to_string(),
+        "user_id": reminder.user_id.to_string(),
+        "channel_id": reminder.channel_id.map(|id| id.to_string()),
+        "message_id": reminder.message_id.map(|id| id.to_string()),
+        "content": reminder.content,
+        "due_at": reminder.due_at.to_rfc3339(),
+        "delivered_at": reminder.delivered_at.map(|at| at.to_rfc3339()),
+        "created_at": reminder.created_at.to_rfc3339(),
+    })
+}
+
+fn parse_snowflake(value: Option<&str>, field: &str) -> Result<Option<i64>, ApiError> {
+    value
+        .map(|raw| {
+            raw.parse::<i64>()
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {field}")))
+        })
+        .transpose()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateReminderRequest {
+    pub content: String,
+    /// RFC 3339 time to fire at. Exactly one of `due_at` and `in_seconds`.
+    pub due_at: Option<String>,
+    pub in_seconds: Option<i64>,
+    /// Channel to ping when the reminder fires.
+    pub channel_id: Option<String>,
+    /// Message in `channel_id` the reminder refers to.
+    pub message_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/reminders",
+    tag = "reminders",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_reminders(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let reminders = paracord_db::reminders::list_pending_user_reminders(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(reminders
+        .iter()
+        .map(reminder_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/reminders",
+    tag = "reminders",
+    request_body = CreateReminderRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn create_reminder(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<CreateReminderRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let content = body.content.trim();
+    if content.is_empty() || content.chars().count() > MAX_REMINDER_CONTENT_LEN {
+        return Err(ApiError::BadRequest(
+            "Reminder must be 1-1000 characters".into(),
+        ));
+    }
+
+    let now = Utc::now();
+    let due_at = match (body.due_at.as_deref(), body.in_seconds) {
+        (Some(raw), None) => DateTime::parse_from_rfc3339(raw)
+            .map_err(|_| ApiError::BadRequest("Invalid due_at".into()))?
+            .with_timezone(&Utc),
+        (None, Some(seconds)) if seconds > 0 => {
+            now + Duration::seconds(seconds.min(MAX_REMINDER_DELAY_DAYS * 86_400))
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Provide either due_at or a positive in_seconds".into(),
+            ))
+        }
+    };
+    if due_at <= now || due_at > now + Duration::days(MAX_REMINDER_DELAY_DAYS) {
+        return Err(ApiError::BadRequest(
+            "Reminders must be due within the next 365 days".into(),
+        ));
+    }
+
+    let channel_id = parse_snowflake(body.channel_id.as_deref(), "channel_id")?;
+    let message_id = parse_snowflake(body.message_id.as_deref(), "message_id")?;
+    if message_id.is_some() && channel_id.is_none() {
+        return Err(ApiError::BadRequest(
+            "message_id requires channel_id".into(),
+        ));
+    }
+    if let Some(channel_id) = channel_id {
+        let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+        ensure_channel_permissions(
+            &state,
+            &channel,
+            auth.user_id,
+            &[Permissions::VIEW_CHANNEL, Permissions::SEND_MESSAGES],
+        )
+        .await?;
+        if let Some(message_id) = message_id {
+            paracord_db::messages::get_message(&state.db, message_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .filter(|message| message.channel_id == channel_id)
+                .ok_or(ApiError::Code(ErrorCode::UnknownMessage))?;
+        }
+    }
+
+    let pending = paracord_db::reminders::count_pending_user_reminders(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if pending >= MAX_PENDING_REMINDERS {
+        return Err(ApiError::BadRequest(
+            "Maximum number of pending reminders reached".into(),
+        ));
+    }
+
+    let reminder = paracord_db::reminders::create_reminder(
+        &state.db,
+        paracord_util::snowflake::try_next_id().await?,
+        auth.user_id,
+        channel_id,
+        message_id,
+        content,
+        due_at,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    Ok((StatusCode::CREATED, Json(reminder_to_json(&reminder))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me/reminders/{reminder_id}",
+    tag = "reminders",
+    params(("reminder_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_reminder(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(reminder_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let reminder = paracord_db::reminders::get_reminder(&state.db, reminder_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|reminder| reminder.user_id == auth.user_id && reminder.delivered_at.is_none())
+        .ok_or(ApiError::NotFound)?;
+    paracord_db::reminders::delete_reminder(&state.db, reminder.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fire every reminder that has come due. The user always gets
+/// `REMINDER_DUE`; reminders with a channel also ping the user there, as
+/// long as they can still post in it. Run periodically by the server.
+pub async fn run_due_reminders_once(state: &AppState) {
+    let now = Utc::now();
+    let due = match paracord_db::reminders::get_due_reminders(&state.db, now, REMINDER_BATCH_SIZE)
+        .await
+    {
+        Ok(due) => due,
+        Err(err) => {
+            tracing::warn!("Failed to load due reminders: {}", err);
+            return;
+        }
+    };
+
+    for reminder in due {
+        match paracord_db::reminders::mark_reminder_delivered(&state.db, reminder.id).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                tracing::warn!("Failed to claim reminder {}: {}", reminder.id, err);
+                continue;
+            }
+        }
+
+        let message_json = match reminder.channel_id {
+            Some(channel_id) => post_reminder_ping(state, &reminder, channel_id).await,
+            None => None,
+        };
+        state.event_bus.dispatch_to_users(
+            EVENT_REMINDER_DUE,
+            json!({
+                "reminder": reminder_to_json(&reminder),
+                "message": message_json,
+            }),
+            vec![reminder.user_id],
+        );
+    }
+
+    let cutoff = now - Duration::days(DELIVERED_REMINDER_RETENTION_DAYS);
+    let _ = paracord_db::reminders::prune_delivered_reminders(&state.db, cutoff).await;
+}
+
+async fn post_reminder_ping(
+    state: &AppState,
+    reminder: &paracord_db::reminders::ReminderRow,
+    channel_id: i64,
+) -> Option<Value> {
+    let reference_id = match reminder.message_id {
+        Some(message_id) => paracord_db::messages::get_message(&state.db, message_id)
+            .await
+            .ok()
+            .flatten()
+            .filter(|message| message.channel_id == channel_id)
+            .map(|message| message.id),
+        None => None,
+    };
+    let content = format!("<@{}> Reminder: {}", reminder.user_id, reminder.content);
+    let msg_id = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => id,
+        Err(err) => {
+            tracing::debug!("Reminder {} could not be posted: {}", reminder.id, err);
+            return None;
+        }
+    };
+    let msg = match paracord_core::message::create_message_with_options(
+        &state.db,
+        msg_id,
+        channel_id,
+        reminder.user_id,
+        &content,
+        paracord_core::message::CreateMessageOptions {
+            reference_id,
+            ..Default::default()
+        },
+    )
+    .await
+    {
+        Ok(msg) => msg,
+        Err(err) => {
+            tracing::debug!(
+                "Reminder {} could not be posted in channel {}: {}",
+                reminder.id,
+                channel_id,
+                err
+            );
+            return None;
+        }
+    };
+
+    let msg_json = message_to_json(state, &msg, reminder.user_id).await;
+    match paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|channel| channel.guild_id())
+    {
+        Some(guild_id) => {
+            state
+                .event_bus
+                .dispatch("MESSAGE_CREATE", msg_json.clone(), Some(guild_id));
+        }
+        None => {
+            dms::dispatch_dm_message_create(state, channel_id, reminder.user_id, &msg_json).await;
+        }
+    }
+    Some(msg_json)
+}
@@ -3,12 +3,15 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use paracord_core::limits::{self, GuildLimit};
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
-use crate::middleware::AuthUser;
+use crate::middleware::{AuthUser, PermissionsVersion};
 use crate::routes::audit;
 
 fn validate_role_permission_assignment(
@@ -32,7 +35,33 @@ fn validate_role_permission_assignment(
     Ok(())
 }
 
-fn role_to_json(r: &paracord_db::roles::RoleRow) -> Value {
+const MAX_SELF_ASSIGN_GROUP_LEN: usize = 32;
+
+/// Parse a requested `self_assign_group`: `None` keeps the current group,
+/// `Some(None)` clears it.
+fn parse_self_assign_group(group: Option<&str>) -> Result<Option<Option<String>>, ApiError> {
+    match group.map(str::trim) {
+        None => Ok(None),
+        Some("") => Ok(Some(None)),
+        Some(name) if name.chars().count() > MAX_SELF_ASSIGN_GROUP_LEN => {
+            Err(ApiError::BadRequest(format!(
+                "self_assign_group must be at most {MAX_SELF_ASSIGN_GROUP_LEN} characters"
+            )))
+        }
+        Some(name) => Ok(Some(Some(name.to_string()))),
+    }
+}
+
+fn ensure_self_assignable_permissions(permissions: i64) -> Result<(), ApiError> {
+    if permissions & paracord_core::permissions::privileged_permissions().bits() != 0 {
+        return Err(ApiError::BadRequest(
+            "Self-assignable roles cannot grant moderation or management permissions".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn role_to_json(r: &paracord_db::roles::RoleRow) -> Value {
     json!({
         "id": r.id.to_string(),
         "guild_id": r.guild_id().to_string(),
@@ -43,10 +72,20 @@ fn role_to_json(r: &paracord_db::roles::RoleRow) -> Value {
         "permissions": r.permissions,
         "managed": r.managed,
         "mentionable": r.mentionable,
+        "self_assignable": r.self_assignable,
+        "self_assign_group": r.self_assign_group,
         "created_at": r.created_at.to_rfc3339(),
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/roles",
+    tag = "roles",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_roles(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -62,7 +101,7 @@ pub async fn list_roles(
     Ok(Json(json!(result)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateRoleRequest {
     pub name: String,
     #[serde(default)]
@@ -73,18 +112,32 @@ pub struct CreateRoleRequest {
     pub hoist: bool,
     #[serde(default)]
     pub mentionable: bool,
+    #[serde(default)]
+    pub self_assignable: bool,
+    pub self_assign_group: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/roles",
+    tag = "roles",
+    params(("guild_id" = i64, Path)),
+    request_body = CreateRoleRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_role(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<i64>,
-    Json(body): Json<CreateRoleRequest>,
+    permissions_version: PermissionsVersion,
+    Json(mut body): Json<CreateRoleRequest>,
 ) -> Result<(StatusCode, Json<Value>), ApiError> {
+    body.permissions = permissions_version.normalize(body.permissions);
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let user_roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
@@ -98,12 +151,21 @@ pub async fn create_role(
         return Err(ApiError::Forbidden);
     }
     validate_role_permission_assignment(guild.owner_id, auth.user_id, perms, body.permissions)?;
+    let self_assign_group = parse_self_assign_group(body.self_assign_group.as_deref())?.flatten();
+    if body.self_assignable {
+        ensure_self_assignable_permissions(body.permissions)?;
+    }
+    let max_roles = GuildLimit::Roles.configured(&*state.runtime.read().await);
+    let role_count = paracord_db::roles::count_guild_roles(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    limits::ensure_below(GuildLimit::Roles, max_roles, role_count)?;
 
-    let role_id = paracord_util::snowflake::generate(1);
+    let role_id = paracord_util::snowflake::try_next_id().await?;
     paracord_db::roles::create_role(&state.db, role_id, guild_id, &body.name, body.permissions)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
-    let role = paracord_db::roles::update_role(
+    let mut role = paracord_db::roles::update_role(
         &state.db,
         role_id,
         None,
@@ -114,6 +176,16 @@ pub async fn create_role(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if body.self_assignable || self_assign_group.is_some() {
+        role = paracord_db::roles::set_role_self_assign(
+            &state.db,
+            role_id,
+            body.self_assignable,
+            self_assign_group.as_deref(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
 
     let role_json = role_to_json(&role);
 
@@ -136,25 +208,41 @@ pub async fn create_role(
     Ok((StatusCode::CREATED, Json(role_json)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateRoleRequest {
     pub name: Option<String>,
     pub permissions: Option<i64>,
     pub color: Option<i32>,
     pub hoist: Option<bool>,
     pub mentionable: Option<bool>,
+    pub self_assignable: Option<bool>,
+    /// `""` clears the group.
+    pub self_assign_group: Option<String>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/roles/{role_id}",
+    tag = "roles",
+    params(("guild_id" = i64, Path), ("role_id" = i64, Path)),
+    request_body = UpdateRoleRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_role(
     State(state): State<AppState>,
     auth: AuthUser,
     Path((guild_id, role_id)): Path<(i64, i64)>,
-    Json(body): Json<UpdateRoleRequest>,
+    permissions_version: PermissionsVersion,
+    Json(mut body): Json<UpdateRoleRequest>,
 ) -> Result<Json<Value>, ApiError> {
+    body.permissions = body
+        .permissions
+        .map(|bits| permissions_version.normalize(bits));
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     let user_roles = paracord_db::roles::get_member_roles(&state.db, auth.user_id, guild_id)
         .await
@@ -179,9 +267,9 @@ pub async fn update_role(
     let target_role = paracord_db::roles::get_role(&state.db, role_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
     if target_role.guild_id() != guild_id {
-        return Err(ApiError::NotFound);
+        return Err(ApiError::Code(ErrorCode::UnknownRole));
     }
     if auth.user_id != guild.owner_id {
         let actor_top_role_pos = user_roles.iter().map(|r| r.position).max().unwrap_or(0);
@@ -190,7 +278,18 @@ pub async fn update_role(
         }
     }
 
-    let updated = paracord_db::roles::update_role(
+    let self_assign_group = parse_self_assign_group(body.self_assign_group.as_deref())?;
+    let self_assignable = body.self_assignable.unwrap_or(target_role.self_assignable);
+    if self_assignable {
+        if role_id == guild_id || target_role.managed {
+            return Err(ApiError::BadRequest(
+                "This role cannot be self-assignable".into(),
+            ));
+        }
+        ensure_self_assignable_permissions(body.permissions.unwrap_or(target_role.permissions))?;
+    }
+
+    let mut updated = paracord_db::roles::update_role(
         &state.db,
         role_id,
         body.name.as_deref(),
@@ -201,6 +300,17 @@ pub async fn update_role(
     )
     .await
     .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if body.self_assignable.is_some() || self_assign_group.is_some() {
+        let group = self_assign_group.unwrap_or_else(|| target_role.self_assign_group.clone());
+        updated = paracord_db::roles::set_role_self_assign(
+            &state.db,
+            role_id,
+            self_assignable,
+            group.as_deref(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
 
     // Invalidate permission cache when role permissions change
     paracord_core::permissions::invalidate_all(&state.permission_cache).await;
@@ -222,6 +332,8 @@ pub async fn update_role(
         Some(json!({
             "name": updated.name,
             "permissions": updated.permissions,
+            "self_assignable": updated.self_assignable,
+            "self_assign_group": updated.self_assign_group,
         })),
     )
     .await;
@@ -229,6 +341,14 @@ pub async fn update_role(
     Ok(Json(role_json))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/roles/{role_id}",
+    tag = "roles",
+    params(("guild_id" = i64, Path), ("role_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_role(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -237,7 +357,7 @@ pub async fn delete_role(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     if role_id == guild_id {
         return Err(ApiError::BadRequest(
@@ -260,9 +380,9 @@ pub async fn delete_role(
     let target_role = paracord_db::roles::get_role(&state.db, role_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
     if target_role.guild_id() != guild_id {
-        return Err(ApiError::NotFound);
+        return Err(ApiError::Code(ErrorCode::UnknownRole));
     }
     if auth.user_id != guild.owner_id {
         let actor_top_role_pos = user_roles.iter().map(|r| r.position).max().unwrap_or(0);
@@ -271,21 +391,26 @@ pub async fn delete_role(
         }
     }
 
-    paracord_db::roles::delete_role(&state.db, role_id)
+    let deleted_at = paracord_db::tombstones::soft_delete_role(&state.db, guild_id, role_id)
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
 
     // Invalidate permission cache when a role is deleted
     paracord_core::permissions::invalidate_all(&state.permission_cache).await;
 
-    state.event_bus.dispatch(
-        "GUILD_ROLE_DELETE",
+    let payload = paracord_core::tombstone::with_tombstone(
+        &state,
         json!({
             "guild_id": guild_id.to_string(),
             "role_id": role_id.to_string(),
         }),
-        Some(guild_id),
-    );
+        deleted_at,
+    )
+    .await;
+    state
+        .event_bus
+        .dispatch("GUILD_ROLE_DELETE", payload, Some(guild_id));
     audit::log_action(
         &state,
         guild_id,
@@ -25,19 +25,7 @@ fn request_metadata(
         .map(str::trim)
         .filter(|v| !v.is_empty())
         .map(str::to_string);
-    let trust_proxy = std::env::var("PARACORD_TRUST_PROXY")
-        .ok()
-        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
-        .unwrap_or(false);
-    let trusted_proxy_configured = std::env::var("PARACORD_TRUSTED_PROXY_IPS")
-        .ok()
-        .map(|raw| !raw.trim().is_empty())
-        .unwrap_or(false);
-    let ip_address = if trust_proxy && trusted_proxy_configured {
-        header_opt(headers, "x-forwarded-for")
-    } else {
-        None
-    };
+    let ip_address = crate::client_ip::request_client_ip().map(|ip| ip.to_string());
     (device_id, user_agent, ip_address)
 }
 
@@ -51,7 +39,13 @@ pub async fn log_security_event(
     details: Option<Value>,
 ) {
     let (device_id, user_agent, ip_address) = request_metadata(headers);
-    let id = paracord_util::snowflake::generate(1);
+    let id = match paracord_util::snowflake::try_next_id().await {
+        Ok(id) => id,
+        Err(err) => {
+            tracing::warn!("failed to write security event '{}': {}", action, err);
+            return;
+        }
+    };
     let details_ref = details.as_ref();
 
     if let Err(err) = paracord_db::security_events::create_event(
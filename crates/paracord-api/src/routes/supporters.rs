@@ -0,0 +1,367 @@
+//! Supporter grants: admin management, donation webhooks and the caller's
+//! effective perks.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Form, Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use paracord_core::AppState;
+use paracord_db::supporters::SupporterRow;
+use paracord_models::error_code::ErrorCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::{AdminUser, AuthUser};
+use crate::routes::security;
+use crate::supporter_webhooks::{
+    self, SupporterUpdate, PATREON_EVENT_HEADER, PATREON_SIGNATURE_HEADER,
+};
+
+const MAX_NOTE_LEN: usize = 256;
+
+fn supporter_to_json(row: &SupporterRow) -> Value {
+    json!({
+        "user_id": row.user_id.to_string(),
+        "source": row.source,
+        "note": row.note,
+        "expires_at": row.expires_at.map(|at| at.to_rfc3339()),
+        "created_at": row.created_at.to_rfc3339(),
+        "updated_at": row.updated_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/perks",
+    tag = "supporters",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_my_perks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let perks = paracord_core::supporters::perks_for(&state, auth.user_id).await;
+    Ok(Json(json!({
+        "supporter": perks.supporter,
+        "max_upload_size": perks.max_upload_size,
+        "max_emojis_per_guild": perks.max_emojis_per_guild,
+        "max_stream_quality": perks.max_stream_quality,
+    })))
+}
+
+// ── Admin ───────────────────────────────────────────────────────────────
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/supporters",
+    tag = "supporters",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_supporters(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, ApiError> {
+    let supporters = paracord_db::supporters::list_supporters(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let roles = paracord_db::supporters::list_supporter_roles(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!({
+        "supporters": supporters.iter().map(supporter_to_json).collect::<Vec<_>>(),
+        "roles": roles
+            .iter()
+            .map(|(role_id, guild_id)| json!({
+                "role_id": role_id.to_string(),
+                "guild_id": guild_id.to_string(),
+            }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+pub struct GrantSupporterRequest {
+    pub note: Option<String>,
+    /// Omit for a grant that never expires.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/supporters/{user_id}",
+    tag = "supporters",
+    params(("user_id" = i64, Path)),
+    request_body = Option<GrantSupporterRequest>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn grant_supporter(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(user_id): Path<i64>,
+    body: Option<Json<GrantSupporterRequest>>,
+) -> Result<Json<Value>, ApiError> {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let note = body
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+    if note.is_some_and(|note| note.chars().count() > MAX_NOTE_LEN) {
+        return Err(ApiError::BadRequest(format!(
+            "note must be at most {MAX_NOTE_LEN} characters"
+        )));
+    }
+    if body.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(ApiError::BadRequest(
+            "expires_at must be in the future".into(),
+        ));
+    }
+    paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+
+    let row = paracord_db::supporters::upsert_supporter(
+        &state.db,
+        user_id,
+        "manual",
+        note,
+        body.expires_at,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    security::log_security_event(
+        &state,
+        "admin.supporter.grant",
+        Some(admin.user_id),
+        Some(user_id),
+        None,
+        Some(&headers),
+        Some(json!({ "expires_at": row.expires_at.map(|at| at.to_rfc3339()) })),
+    )
+    .await;
+
+    Ok(Json(supporter_to_json(&row)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/supporters/{user_id}",
+    tag = "supporters",
+    params(("user_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn revoke_supporter(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(user_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if !paracord_db::supporters::delete_supporter(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::NotFound);
+    }
+    security::log_security_event(
+        &state,
+        "admin.supporter.revoke",
+        Some(admin.user_id),
+        Some(user_id),
+        None,
+        Some(&headers),
+        None,
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/supporter-roles/{role_id}",
+    tag = "supporters",
+    params(("role_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn add_supporter_role(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(role_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    paracord_db::roles::get_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownRole))?;
+    paracord_db::supporters::add_supporter_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    security::log_security_event(
+        &state,
+        "admin.supporter_role.add",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "role_id": role_id.to_string() })),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/supporter-roles/{role_id}",
+    tag = "supporters",
+    params(("role_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn remove_supporter_role(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(role_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    if !paracord_db::supporters::remove_supporter_role(&state.db, role_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        return Err(ApiError::NotFound);
+    }
+    security::log_security_event(
+        &state,
+        "admin.supporter_role.remove",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "role_id": role_id.to_string() })),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── Webhooks ────────────────────────────────────────────────────────────
+
+/// Apply a platform's update. Returns whether the payer has an account here.
+async fn apply_update(
+    state: &AppState,
+    source: &str,
+    update: SupporterUpdate,
+    grant_days: u32,
+) -> Result<bool, ApiError> {
+    let (email, grant) = match update {
+        SupporterUpdate::Grant { email } => (email, true),
+        SupporterUpdate::Revoke { email } => (email, false),
+        SupporterUpdate::Ignore => return Ok(false),
+    };
+    let Some(user) = paracord_db::users::get_user_by_email(&state.db, &email)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    else {
+        return Ok(false);
+    };
+    let existing = paracord_db::supporters::get_supporter(&state.db, user.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    if !grant {
+        // Only take back what this platform granted.
+        if existing.is_some_and(|row| row.source == source) {
+            paracord_db::supporters::delete_supporter(&state.db, user.id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        return Ok(true);
+    }
+
+    let now = Utc::now();
+    let expires_at = match &existing {
+        // A grant that never expires is left alone.
+        Some(row) if row.expires_at.is_none() => return Ok(true),
+        Some(row) => row.expires_at.filter(|at| *at > now).unwrap_or(now),
+        None => now,
+    } + Duration::days(i64::from(grant_days));
+    let note = existing.as_ref().and_then(|row| row.note.as_deref());
+    paracord_db::supporters::upsert_supporter(&state.db, user.id, source, note, Some(expires_at))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    tracing::info!(
+        "Supporter status of user {} extended to {} by {}",
+        user.id,
+        expires_at,
+        source
+    );
+    Ok(true)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct KofiForm {
+    pub data: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/supporters/webhooks/kofi",
+    tag = "supporters",
+    request_body(content = KofiForm, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn kofi_webhook(
+    State(state): State<AppState>,
+    Form(form): Form<KofiForm>,
+) -> Result<Json<Value>, ApiError> {
+    let webhooks = supporter_webhooks::installed()
+        .filter(|webhooks| webhooks.kofi_enabled())
+        .ok_or(ApiError::NotFound)?;
+    let data: Value = serde_json::from_str(&form.data)
+        .map_err(|e| ApiError::BadRequest(format!("invalid data: {e}")))?;
+    let update = webhooks.verify_kofi(&data).ok_or(ApiError::Unauthorized)?;
+    let matched = apply_update(&state, "kofi", update, webhooks.grant_days()).await?;
+    Ok(Json(json!({ "ok": true, "matched": matched })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/supporters/webhooks/patreon",
+    tag = "supporters",
+    request_body = Value,
+    responses((status = 200, description = "OK", body = Value)),
+)]
+pub async fn patreon_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, ApiError> {
+    let webhooks = supporter_webhooks::installed()
+        .filter(|webhooks| webhooks.patreon_enabled())
+        .ok_or(ApiError::NotFound)?;
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+    let update = webhooks
+        .verify_patreon(
+            header(PATREON_SIGNATURE_HEADER),
+            header(PATREON_EVENT_HEADER),
+            &body,
+        )
+        .ok_or(ApiError::Unauthorized)?;
+    let matched = apply_update(&state, "patreon", update, webhooks.grant_days()).await?;
+    Ok(Json(json!({ "ok": true, "matched": matched })))
+}
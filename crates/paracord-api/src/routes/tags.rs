@@ -0,0 +1,307 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::gateway::EVENT_GUILD_TAGS_UPDATE;
+use paracord_models::permissions::Permissions;
+use paracord_util::validation::contains_dangerous_markup;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::audit;
+
+fn tag_to_json(tag: &paracord_db::guild_tags::GuildTagRow) -> Value {
+    json!({
+        "id": tag.id.to_string(),
+        "guild_id": tag.guild_id.to_string(),
+        "name": tag.name,
+        "content": tag.content,
+        "created_by": tag.created_by.map(|id| id.to_string()),
+        "uses": tag.uses,
+        "created_at": tag.created_at.to_rfc3339(),
+        "updated_at": tag.updated_at.to_rfc3339(),
+    })
+}
+
+/// Tags are managed by anyone who can manage messages.
+async fn ensure_tag_permission(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms =
+        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_MESSAGES)?;
+    Ok(())
+}
+
+fn validate_tag_content(content: &str) -> Result<(), ApiError> {
+    paracord_util::validation::validate_message_content(content)
+        .map_err(|_| ApiError::BadRequest("Tag content must be 1-2000 characters".into()))?;
+    if contains_dangerous_markup(content) {
+        return Err(ApiError::BadRequest("Tag contains unsafe markup".into()));
+    }
+    Ok(())
+}
+
+async fn get_guild_tag(
+    state: &AppState,
+    guild_id: i64,
+    tag_name: &str,
+) -> Result<paracord_db::guild_tags::GuildTagRow, ApiError> {
+    let name = paracord_core::tag::normalize_tag_name(tag_name).map_err(|_| ApiError::NotFound)?;
+    paracord_db::guild_tags::get_tag_by_name(&state.db, guild_id, &name)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)
+}
+
+fn dispatch_tags_update(state: &AppState, guild_id: i64, payload: Value) {
+    state
+        .event_bus
+        .dispatch(EVENT_GUILD_TAGS_UPDATE, payload, Some(guild_id));
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/tags",
+    tag = "tags",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_tags(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let tags = paracord_db::guild_tags::list_guild_tags(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(tags
+        .iter()
+        .map(tag_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/tags/{tag_name}",
+    tag = "tags",
+    params(("guild_id" = i64, Path), ("tag_name" = String, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_tag(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, tag_name)): Path<(i64, String)>,
+) -> Result<Json<Value>, ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let tag = get_guild_tag(&state, guild_id, &tag_name).await?;
+    Ok(Json(tag_to_json(&tag)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub content: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/tags",
+    tag = "tags",
+    params(("guild_id" = i64, Path)),
+    request_body = CreateTagRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn create_tag(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<CreateTagRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    ensure_tag_permission(&state, guild_id, auth.user_id).await?;
+    let name = paracord_core::tag::normalize_tag_name(&body.name)?;
+    validate_tag_content(&body.content)?;
+
+    let count = paracord_db::guild_tags::count_guild_tags(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if count >= paracord_core::tag::MAX_TAGS_PER_GUILD {
+        return Err(ApiError::BadRequest(
+            "Maximum number of tags reached".into(),
+        ));
+    }
+    if paracord_db::guild_tags::get_tag_by_name(&state.db, guild_id, &name)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .is_some()
+    {
+        return Err(ApiError::Conflict("Tag name already in use".into()));
+    }
+
+    let tag = paracord_db::guild_tags::create_tag(
+        &state.db,
+        paracord_util::snowflake::try_next_id().await?,
+        guild_id,
+        &name,
+        &body.content,
+        auth.user_id,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let tag_json = tag_to_json(&tag);
+    dispatch_tags_update(
+        &state,
+        guild_id,
+        json!({ "guild_id": guild_id.to_string(), "tag": tag_json }),
+    );
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_TAG_CREATE,
+        Some(tag.id),
+        None,
+        Some(json!({ "name": tag.name })),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(tag_json)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+    pub content: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/tags/{tag_name}",
+    tag = "tags",
+    params(("guild_id" = i64, Path), ("tag_name" = String, Path)),
+    request_body = UpdateTagRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_tag(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, tag_name)): Path<(i64, String)>,
+    Json(body): Json<UpdateTagRequest>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_tag_permission(&state, guild_id, auth.user_id).await?;
+    let existing = get_guild_tag(&state, guild_id, &tag_name).await?;
+
+    let new_name = match body.name.as_deref() {
+        Some(name) => Some(paracord_core::tag::normalize_tag_name(name)?),
+        None => None,
+    };
+    if let Some(content) = body.content.as_deref() {
+        validate_tag_content(content)?;
+    }
+    if let Some(name) = new_name.as_deref().filter(|name| *name != existing.name) {
+        if paracord_db::guild_tags::get_tag_by_name(&state.db, guild_id, name)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some()
+        {
+            return Err(ApiError::Conflict("Tag name already in use".into()));
+        }
+    }
+
+    let updated = paracord_db::guild_tags::update_tag(
+        &state.db,
+        existing.id,
+        new_name.as_deref(),
+        body.content.as_deref(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .ok_or(ApiError::NotFound)?;
+
+    let tag_json = tag_to_json(&updated);
+    dispatch_tags_update(
+        &state,
+        guild_id,
+        json!({ "guild_id": guild_id.to_string(), "tag": tag_json }),
+    );
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_TAG_UPDATE,
+        Some(updated.id),
+        None,
+        Some(json!({
+            "name": updated.name,
+            "previous_name": existing.name,
+            "content_changed": body.content.is_some(),
+        })),
+    )
+    .await;
+
+    Ok(Json(tag_json))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/tags/{tag_name}",
+    tag = "tags",
+    params(("guild_id" = i64, Path), ("tag_name" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_tag(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, tag_name)): Path<(i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    ensure_tag_permission(&state, guild_id, auth.user_id).await?;
+    let tag = get_guild_tag(&state, guild_id, &tag_name).await?;
+
+    paracord_db::guild_tags::delete_tag(&state.db, tag.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    dispatch_tags_update(
+        &state,
+        guild_id,
+        json!({
+            "guild_id": guild_id.to_string(),
+            "deleted_tag_id": tag.id.to_string(),
+        }),
+    );
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_TAG_DELETE,
+        Some(tag.id),
+        None,
+        Some(json!({ "name": tag.name, "content": tag.content })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
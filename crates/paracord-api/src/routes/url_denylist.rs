@@ -0,0 +1,291 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use paracord_core::url_reputation::{self, DenylistMatch, UrlFilterLevel};
+use paracord_core::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AdminUser;
+use crate::routes::{mod_log, security};
+
+const MAX_REASON_LEN: usize = 200;
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddUrlDenylistEntryRequest {
+    pub domain: String,
+    pub reason: Option<String>,
+}
+
+fn entry_to_json(entry: &paracord_db::url_denylist::UrlDenylistRow) -> Value {
+    json!({
+        "domain": entry.domain,
+        "reason": entry.reason,
+        "created_by": entry.created_by.map(|id| id.to_string()),
+        "created_at": entry.created_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/url-denylist",
+    tag = "url_denylist",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn list_entries(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, ApiError> {
+    let entries = paracord_db::url_denylist::list_entries(&state.db)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let runtime = state.runtime.read().await;
+    Ok(Json(json!({
+        "entries": entries.iter().map(entry_to_json).collect::<Vec<_>>(),
+        "feed_domain_count": runtime.url_denylist.feed_len(),
+        "default_level": runtime.url_filter_default_level.as_str(),
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/url-denylist",
+    tag = "url_denylist",
+    request_body = AddUrlDenylistEntryRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = [])),
+)]
+pub async fn add_entry(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Json(body): Json<AddUrlDenylistEntryRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let domain = url_reputation::normalize_domain(&body.domain)
+        .ok_or_else(|| ApiError::BadRequest("Invalid domain".into()))?;
+    let reason = body
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+    if reason.is_some_and(|reason| reason.chars().count() > MAX_REASON_LEN) {
+        return Err(ApiError::BadRequest(
+            "Reason must be at most 200 characters".into(),
+        ));
+    }
+
+    let entry = paracord_db::url_denylist::upsert_entry(&state.db, &domain, reason, admin.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    url_reputation::reload_manual_domains(&state).await?;
+
+    security::log_security_event(
+        &state,
+        "admin.url_denylist.add",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "domain": &domain, "reason": reason })),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(entry_to_json(&entry))))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/url-denylist/{domain}",
+    tag = "url_denylist",
+    params(("domain" = String, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = [])),
+)]
+pub async fn remove_entry(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    headers: HeaderMap,
+    Path(domain): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let domain = url_reputation::normalize_domain(&domain).ok_or(ApiError::NotFound)?;
+    let removed = paracord_db::url_denylist::delete_entry(&state.db, &domain)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+    url_reputation::reload_manual_domains(&state).await?;
+
+    security::log_security_event(
+        &state,
+        "admin.url_denylist.remove",
+        Some(admin.user_id),
+        None,
+        None,
+        Some(&headers),
+        Some(json!({ "domain": &domain })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A denylisted link that was let through under the guild's `flag` level,
+/// reported once the message exists.
+pub(crate) struct FlaggedUrl {
+    guild_id: i64,
+    owner_id: i64,
+    hit: DenylistMatch,
+}
+
+/// Screen plaintext guild message content against the URL denylist.
+/// Returns an error when the guild blocks denylisted links.
+pub(crate) async fn screen_message(
+    state: &AppState,
+    headers: &HeaderMap,
+    guild_id: i64,
+    channel_id: i64,
+    author_id: i64,
+    content: &str,
+) -> Result<Option<FlaggedUrl>, ApiError> {
+    let (denylist, default_level) = {
+        let runtime = state.runtime.read().await;
+        (
+            runtime.url_denylist.clone(),
+            runtime.url_filter_default_level,
+        )
+    };
+    if denylist.is_empty() {
+        return Ok(None);
+    }
+    let Some(hit) = denylist.check(content) else {
+        return Ok(None);
+    };
+
+    let Some(guild) = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    else {
+        return Ok(None);
+    };
+    match url_reputation::guild_level(guild.bot_settings.as_deref(), default_level) {
+        UrlFilterLevel::Off => Ok(None),
+        UrlFilterLevel::Flag => Ok(Some(FlaggedUrl {
+            guild_id,
+            owner_id: guild.owner_id,
+            hit,
+        })),
+        UrlFilterLevel::Block => {
+            report_match(
+                state,
+                headers,
+                guild_id,
+                guild.owner_id,
+                channel_id,
+                author_id,
+                None,
+                &hit,
+                UrlFilterLevel::Block,
+            )
+            .await;
+            Err(ApiError::BadRequest(
+                "Message contains a link to a blocked domain".into(),
+            ))
+        }
+    }
+}
+
+impl FlaggedUrl {
+    pub(crate) async fn report(
+        self,
+        state: &AppState,
+        headers: &HeaderMap,
+        channel_id: i64,
+        author_id: i64,
+        message_id: i64,
+    ) {
+        report_match(
+            state,
+            headers,
+            self.guild_id,
+            self.owner_id,
+            channel_id,
+            author_id,
+            Some(message_id),
+            &self.hit,
+            UrlFilterLevel::Flag,
+        )
+        .await;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn report_match(
+    state: &AppState,
+    headers: &HeaderMap,
+    guild_id: i64,
+    owner_id: i64,
+    channel_id: i64,
+    author_id: i64,
+    message_id: Option<i64>,
+    hit: &DenylistMatch,
+    action: UrlFilterLevel,
+) {
+    security::log_security_event(
+        state,
+        "message.url_denylisted",
+        Some(author_id),
+        None,
+        None,
+        Some(headers),
+        Some(json!({
+            "guild_id": guild_id.to_string(),
+            "channel_id": channel_id.to_string(),
+            "message_id": message_id.map(|id| id.to_string()),
+            "host": &hit.host,
+            "domain": &hit.domain,
+            "source": hit.source.as_str(),
+            "action": action.as_str(),
+        })),
+    )
+    .await;
+
+    let automod_action = match action {
+        UrlFilterLevel::Block => "blocked a denylisted link",
+        _ => "flagged a denylisted link",
+    };
+    mod_log::record_automod_action(
+        state,
+        guild_id,
+        channel_id,
+        author_id,
+        automod_action,
+        Some(&hit.host),
+    )
+    .await;
+
+    // Moderation reports go to the owner and VIEW_REPORTS holders rather
+    // than every member.
+    let recipients = paracord_core::permissions::report_recipients(&state.db, guild_id, owner_id)
+        .await
+        .unwrap_or_else(|_| vec![owner_id]);
+    state.event_bus.dispatch_to_users(
+        "AUTO_MODERATION_ACTION_EXECUTION",
+        json!({
+            "guild_id": guild_id.to_string(),
+            "channel_id": channel_id.to_string(),
+            "user_id": author_id.to_string(),
+            "message_id": message_id.map(|id| id.to_string()),
+            "rule_trigger_type": "url_reputation",
+            "action": action.as_str(),
+            "matched_content": &hit.host,
+            "matched_keyword": &hit.domain,
+        }),
+        recipients,
+    );
+}
@@ -1,20 +1,36 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Multipart, Path, Query, State},
     http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
+use paracord_core::presence_manager::{self, PresencePrivacy, PresenceVisibility};
 use paracord_core::AppState;
+use paracord_db::users::{SettingsScope, UserSettingsRow};
+use paracord_media::images::{avatar_source_key, avatar_variant_key};
+use paracord_models::error_code::ErrorCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
-use crate::middleware::AuthUser;
-use crate::routes::security;
+use crate::middleware::{remember_user_locale, AuthUser};
+use crate::routes::{image_variants, security};
 
 const MAX_DISPLAY_NAME_LEN: usize = 64;
 const MAX_BIO_LEN: usize = 512;
 const MAX_CUSTOM_STATUS_LEN: usize = 128;
 const MAX_CUSTOM_CSS_LEN: usize = 10 * 1024;
+const MAX_USER_NOTE_LEN: usize = 256;
+const MAX_PRESENCE_HIDDEN_GUILDS: usize = 200;
+const MIN_DIRECTORY_QUERY_LEN: usize = 2;
+const MAX_DIRECTORY_QUERY_LEN: usize = 64;
+const DEFAULT_DIRECTORY_LIMIT: i64 = 25;
+const MAX_DIRECTORY_LIMIT: i64 = 50;
+const DIRECTORY_SEARCHES_PER_MINUTE: i64 = 30;
+pub(crate) const PEER_DIRECTORY_SEARCHES_PER_MINUTE: i64 = 120;
+/// Peers that don't answer in time are left out of federated results.
+const PEER_DIRECTORY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 fn contains_dangerous_markup(value: &str) -> bool {
     let lower = value.to_ascii_lowercase();
@@ -47,6 +63,13 @@ fn sanitize_custom_css(value: &str) -> Result<Option<String>, ApiError> {
     Ok(Some(trimmed.to_string()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_me(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -54,7 +77,7 @@ pub async fn get_me(
     let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     Ok(Json(json!({
         "id": user.id.to_string(),
@@ -72,13 +95,21 @@ pub async fn get_me(
     })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateMeRequest {
     pub display_name: Option<String>,
     pub bio: Option<String>,
     pub avatar_hash: Option<String>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/users/@me",
+    tag = "users",
+    request_body = UpdateMeRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_me(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -130,6 +161,141 @@ pub async fn update_me(
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/avatar",
+    tag = "users",
+    request_body(content_type = "multipart/form-data"),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    let image_data =
+        image_variants::read_image_upload(&mut multipart, &["avatar", "image", "file"], "Avatar")
+            .await?;
+    let avatar_hash = image_variants::store_image_variants(
+        &state,
+        image_data,
+        |hash| avatar_source_key(auth.user_id, hash),
+        |hash, size, format| avatar_variant_key(auth.user_id, hash, size, format),
+    )
+    .await?;
+
+    let previous_hash = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .and_then(|user| user.avatar_hash);
+
+    let updated = paracord_core::user::update_profile(
+        &state.db,
+        auth.user_id,
+        None,
+        None,
+        Some(&avatar_hash),
+    )
+    .await?;
+
+    if let Some(previous_hash) = previous_hash.filter(|hash| *hash != avatar_hash) {
+        image_variants::delete_image_variants(
+            &state,
+            &previous_hash,
+            |hash| avatar_source_key(auth.user_id, hash),
+            |hash, size, format| avatar_variant_key(auth.user_id, hash, size, format),
+        )
+        .await;
+    }
+
+    Ok(Json(json!({
+        "id": updated.id.to_string(),
+        "username": updated.username,
+        "discriminator": updated.discriminator,
+        "email": updated.email,
+        "display_name": updated.display_name,
+        "avatar_hash": updated.avatar_hash,
+        "banner_hash": updated.banner_hash,
+        "bio": updated.bio,
+        "flags": updated.flags,
+        "bot": paracord_core::is_bot(updated.flags),
+        "system": false,
+        "created_at": updated.created_at.to_rfc3339(),
+    })))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct AvatarQuery {
+    pub size: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/avatar",
+    tag = "users",
+    params(("user_id" = i64, Path), AvatarQuery),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn get_user_avatar(
+    State(state): State<AppState>,
+    Path(user_id): Path<i64>,
+    Query(params): Query<AvatarQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+    let hash = user.avatar_hash.ok_or(ApiError::NotFound)?;
+    if !image_variants::is_generated_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+
+    image_variants::serve_image_variant(
+        &state,
+        &hash,
+        params.size,
+        &headers,
+        |hash| avatar_source_key(user_id, hash),
+        |hash, size, format| avatar_variant_key(user_id, hash, size, format),
+    )
+    .await
+}
+
+fn settings_to_json(s: &UserSettingsRow) -> Value {
+    json!({
+        "user_id": s.user_id.to_string(),
+        "theme": s.theme,
+        "locale": s.locale,
+        "message_display_compact": s.message_display == "compact",
+        "custom_css": s.custom_css,
+        "status": "online",
+        "custom_status": null,
+        "crypto_auth_enabled": s.crypto_auth_enabled,
+        "device_approval_required": s.device_approval_required,
+        "dm_receipts_enabled": s.dm_receipts_enabled,
+        "directory_discoverable": s.directory_discoverable,
+        "notifications": s.notifications,
+        "keybinds": s.keybinds,
+        "presence_visibility": s.presence_visibility,
+        "presence_hidden_guild_ids": s.presence_hidden_guild_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>(),
+        "versions": {
+            "appearance": s.appearance_version,
+            "notifications": s.notifications_version,
+            "keybinds": s.keybinds_version,
+        },
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/settings",
+    tag = "users",
+    operation_id = "users_get_settings",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_settings(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -139,18 +305,8 @@ pub async fn get_settings(
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     if let Some(s) = settings {
-        Ok(Json(json!({
-            "user_id": s.user_id.to_string(),
-            "theme": s.theme,
-            "locale": s.locale,
-            "message_display_compact": s.message_display == "compact",
-            "custom_css": s.custom_css,
-            "status": "online",
-            "custom_status": null,
-            "crypto_auth_enabled": s.crypto_auth_enabled,
-            "notifications": s.notifications,
-            "keybinds": s.keybinds,
-        })))
+        remember_user_locale(auth.user_id, &s.locale);
+        Ok(Json(settings_to_json(&s)))
     } else {
         Ok(Json(json!({
             "user_id": auth.user_id.to_string(),
@@ -161,13 +317,28 @@ pub async fn get_settings(
             "status": "online",
             "custom_status": null,
             "crypto_auth_enabled": false,
+            "device_approval_required": false,
+            "dm_receipts_enabled": true,
+            "directory_discoverable": false,
             "notifications": {},
             "keybinds": {},
+            "presence_visibility": "everyone",
+            "presence_hidden_guild_ids": [],
+            "versions": { "appearance": 0, "notifications": 0, "keybinds": 0 },
         })))
     }
 }
 
-#[derive(Deserialize)]
+/// Scope versions the client last saw. A full write to a scope whose version
+/// has moved on is rejected with 409 so the client can refetch and retry.
+#[derive(Deserialize, Default, ToSchema)]
+pub struct SettingsVersions {
+    pub appearance: Option<i64>,
+    pub notifications: Option<i64>,
+    pub keybinds: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateSettingsRequest {
     pub theme: Option<String>,
     pub locale: Option<String>,
@@ -176,45 +347,118 @@ pub struct UpdateSettingsRequest {
     pub status: Option<String>,
     pub custom_status: Option<String>,
     pub crypto_auth_enabled: Option<bool>,
+    /// Require approval from a trusted session before a new device can
+    /// connect to the gateway.
+    pub device_approval_required: Option<bool>,
+    /// Share delivered/read receipts in DMs and see other recipients' receipts.
+    pub dm_receipts_enabled: Option<bool>,
+    /// List the user in the user directory, including for trusted peers.
+    pub directory_discoverable: Option<bool>,
     pub notifications: Option<serde_json::Value>,
     pub keybinds: Option<serde_json::Value>,
+    /// Top-level keys merged into the stored notifications; `null` removes a key.
+    pub notifications_patch: Option<serde_json::Map<String, Value>>,
+    /// Top-level keys merged into the stored keybinds; `null` removes a key.
+    pub keybinds_patch: Option<serde_json::Map<String, Value>>,
+    #[serde(default)]
+    pub versions: SettingsVersions,
+    pub presence_visibility: Option<String>,
+    pub presence_hidden_guild_ids: Option<Vec<String>>,
 }
 
+/// Delta patches are retried against the latest version this many times
+/// before giving up on a heavily contended scope.
+const SETTINGS_PATCH_ATTEMPTS: usize = 5;
+
+fn settings_conflict(scope: SettingsScope) -> ApiError {
+    ApiError::Conflict(format!(
+        "{} settings were changed on another device",
+        scope.as_str()
+    ))
+}
+
+/// Shallow merge: each key in `patch` replaces the stored value, and `null`
+/// removes it.
+fn merge_settings_patch(base: &Value, patch: &serde_json::Map<String, Value>) -> Value {
+    let mut merged = base.as_object().cloned().unwrap_or_default();
+    for (key, value) in patch {
+        if value.is_null() {
+            merged.remove(key);
+        } else {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(merged)
+}
+
+async fn apply_settings_patch(
+    state: &AppState,
+    user_id: i64,
+    scope: SettingsScope,
+    patch: &serde_json::Map<String, Value>,
+) -> Result<UserSettingsRow, ApiError> {
+    for _ in 0..SETTINGS_PATCH_ATTEMPTS {
+        let current = paracord_db::users::get_user_settings(&state.db, user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::NotFound)?;
+        let base = match scope {
+            SettingsScope::Notifications => &current.notifications,
+            _ => &current.keybinds,
+        };
+        let merged = merge_settings_patch(base, patch);
+        let updated = paracord_db::users::update_json_settings(
+            &state.db,
+            user_id,
+            scope,
+            Some(current.version(scope)),
+            &merged,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if let Some(updated) = updated {
+            return Ok(updated);
+        }
+    }
+    Err(settings_conflict(scope))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/users/@me/settings",
+    tag = "users",
+    operation_id = "users_update_settings",
+    request_body = UpdateSettingsRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_settings(
     State(state): State<AppState>,
     auth: AuthUser,
     headers: HeaderMap,
     Json(body): Json<UpdateSettingsRequest>,
 ) -> Result<Json<Value>, ApiError> {
-    let existing = paracord_db::users::get_user_settings(&state.db, auth.user_id)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if body.notifications.is_some() && body.notifications_patch.is_some() {
+        return Err(ApiError::BadRequest(
+            "Send either notifications or notifications_patch, not both".into(),
+        ));
+    }
+    if body.keybinds.is_some() && body.keybinds_patch.is_some() {
+        return Err(ApiError::BadRequest(
+            "Send either keybinds or keybinds_patch, not both".into(),
+        ));
+    }
 
-    let theme = body
-        .theme
-        .as_deref()
-        .or_else(|| existing.as_ref().map(|s| s.theme.as_str()))
-        .unwrap_or("dark");
-    let locale = body
-        .locale
-        .as_deref()
-        .or_else(|| existing.as_ref().map(|s| s.locale.as_str()))
-        .unwrap_or("en-US");
-    let message_display = if let Some(is_compact) = body.message_display_compact {
-        if is_compact {
-            "compact"
-        } else {
-            "cozy"
-        }
-    } else if let Some(existing_settings) = existing.as_ref() {
-        if existing_settings.message_display == "compact" {
-            "compact"
-        } else {
-            "cozy"
+    if body.device_approval_required == Some(false) {
+        // Otherwise a password alone could switch the protection off again.
+        let required = paracord_db::users::get_user_settings(&state.db, auth.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .is_some_and(|settings| settings.device_approval_required);
+        if required {
+            crate::routes::auth::require_approved_device(&state, &auth).await?;
         }
-    } else {
-        "cozy"
-    };
+    }
 
     if let Some(status) = body.custom_status.as_deref() {
         if status.trim().len() > MAX_CUSTOM_STATUS_LEN {
@@ -227,25 +471,192 @@ pub async fn update_settings(
         }
     }
 
-    let custom_css = if let Some(css) = body.custom_css.as_deref() {
-        sanitize_custom_css(css)?
-    } else {
-        existing.as_ref().and_then(|s| s.custom_css.clone())
-    };
+    let custom_css = body
+        .custom_css
+        .as_deref()
+        .map(sanitize_custom_css)
+        .transpose()?;
 
-    let settings = paracord_db::users::upsert_user_settings(
-        &state.db,
-        auth.user_id,
-        theme,
-        locale,
-        message_display,
-        custom_css.as_deref(),
-        body.crypto_auth_enabled,
-        body.notifications.as_ref(),
-        body.keybinds.as_ref(),
-    )
-    .await
-    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let presence_visibility = body
+        .presence_visibility
+        .as_deref()
+        .map(|raw| {
+            PresenceVisibility::parse(raw).ok_or_else(|| {
+                ApiError::BadRequest(
+                    "presence_visibility must be everyone, friends, or nobody".into(),
+                )
+            })
+        })
+        .transpose()?;
+    let presence_hidden_guild_ids = body
+        .presence_hidden_guild_ids
+        .as_ref()
+        .map(|ids| {
+            if ids.len() > MAX_PRESENCE_HIDDEN_GUILDS {
+                return Err(ApiError::BadRequest(
+                    "Too many guilds in presence_hidden_guild_ids".into(),
+                ));
+            }
+            let mut parsed = ids
+                .iter()
+                .map(|id| {
+                    id.parse::<i64>().map_err(|_| {
+                        ApiError::BadRequest("Invalid guild id in presence_hidden_guild_ids".into())
+                    })
+                })
+                .collect::<Result<Vec<i64>, ApiError>>()?;
+            parsed.sort_unstable();
+            parsed.dedup();
+            Ok(parsed)
+        })
+        .transpose()?;
+
+    paracord_db::users::ensure_user_settings(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let mut settings = paracord_db::users::get_user_settings(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    let mut changed_scopes: Vec<SettingsScope> = Vec::new();
+
+    if body.theme.is_some()
+        || body.locale.is_some()
+        || body.message_display_compact.is_some()
+        || custom_css.is_some()
+    {
+        let theme = body.theme.clone().unwrap_or_else(|| settings.theme.clone());
+        let locale = body
+            .locale
+            .clone()
+            .unwrap_or_else(|| settings.locale.clone());
+        let compact = body
+            .message_display_compact
+            .unwrap_or(settings.message_display == "compact");
+        let message_display = if compact { "compact" } else { "cozy" };
+        let custom_css = custom_css.unwrap_or_else(|| settings.custom_css.clone());
+        settings = paracord_db::users::update_appearance_settings(
+            &state.db,
+            auth.user_id,
+            body.versions.appearance,
+            &theme,
+            &locale,
+            message_display,
+            custom_css.as_deref(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or_else(|| settings_conflict(SettingsScope::Appearance))?;
+        changed_scopes.push(SettingsScope::Appearance);
+    }
+
+    let json_scopes = [
+        (
+            SettingsScope::Notifications,
+            body.notifications.as_ref(),
+            body.notifications_patch.as_ref(),
+            body.versions.notifications,
+        ),
+        (
+            SettingsScope::Keybinds,
+            body.keybinds.as_ref(),
+            body.keybinds_patch.as_ref(),
+            body.versions.keybinds,
+        ),
+    ];
+    for (scope, value, patch, expected_version) in json_scopes {
+        if let Some(value) = value {
+            settings = paracord_db::users::update_json_settings(
+                &state.db,
+                auth.user_id,
+                scope,
+                expected_version,
+                value,
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or_else(|| settings_conflict(scope))?;
+            changed_scopes.push(scope);
+        } else if let Some(patch) = patch {
+            settings = apply_settings_patch(&state, auth.user_id, scope, patch).await?;
+            changed_scopes.push(scope);
+        }
+    }
+
+    if let Some(enabled) = body.crypto_auth_enabled {
+        settings = paracord_db::users::update_crypto_auth_enabled(&state.db, auth.user_id, enabled)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    let mut device_recovery_code = None;
+    if let Some(enabled) = body.device_approval_required {
+        if enabled && !settings.device_approval_required {
+            // Devices already signed in stay trusted.
+            let now = chrono::Utc::now();
+            paracord_db::sessions::approve_all_user_session_devices(&state.db, auth.user_id, now)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            let sessions = paracord_db::sessions::list_user_sessions(&state.db, auth.user_id, now)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            for device_id in sessions.iter().filter_map(|s| s.device_id.as_deref()) {
+                paracord_db::sessions::remember_approved_device(
+                    &state.db,
+                    auth.user_id,
+                    device_id,
+                    now,
+                )
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            }
+            device_recovery_code =
+                Some(crate::routes::auth::issue_device_recovery_code(&state, auth.user_id).await?);
+        }
+        settings =
+            paracord_db::users::update_device_approval_required(&state.db, auth.user_id, enabled)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    if let Some(enabled) = body.dm_receipts_enabled {
+        settings = paracord_db::users::update_dm_receipts_enabled(&state.db, auth.user_id, enabled)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    if let Some(discoverable) = body.directory_discoverable {
+        settings = paracord_db::users::update_directory_discoverable(
+            &state.db,
+            auth.user_id,
+            discoverable,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    if presence_visibility.is_some() || presence_hidden_guild_ids.is_some() {
+        let visibility = presence_visibility
+            .map(PresenceVisibility::as_str)
+            .unwrap_or(settings.presence_visibility.as_str())
+            .to_string();
+        let hidden_guild_ids =
+            presence_hidden_guild_ids.unwrap_or_else(|| settings.presence_hidden_guild_ids.clone());
+        settings = paracord_db::users::update_presence_privacy(
+            &state.db,
+            auth.user_id,
+            &visibility,
+            &hidden_guild_ids,
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        presence_manager::apply_privacy(
+            &state,
+            auth.user_id,
+            PresencePrivacy::from_settings(&settings),
+        )
+        .await;
+    }
 
     if let Some(enabled) = body.crypto_auth_enabled {
         security::log_security_event(
@@ -259,21 +670,56 @@ pub async fn update_settings(
         )
         .await;
     }
+    if let Some(enabled) = body.device_approval_required {
+        security::log_security_event(
+            &state,
+            "user.settings.device_approval.update",
+            Some(auth.user_id),
+            Some(auth.user_id),
+            auth.session_id.as_deref(),
+            Some(&headers),
+            Some(json!({ "device_approval_required": enabled })),
+        )
+        .await;
+    }
 
-    Ok(Json(json!({
-        "user_id": settings.user_id.to_string(),
-        "theme": settings.theme,
-        "locale": settings.locale,
-        "message_display_compact": settings.message_display == "compact",
-        "custom_css": settings.custom_css,
-        "status": body.status.unwrap_or_else(|| "online".to_string()),
-        "custom_status": body.custom_status,
-        "crypto_auth_enabled": settings.crypto_auth_enabled,
-        "notifications": settings.notifications,
-        "keybinds": settings.keybinds,
-    })))
+    remember_user_locale(auth.user_id, &settings.locale);
+    let mut settings_json = settings_to_json(&settings);
+    if !changed_scopes.is_empty()
+        || body.crypto_auth_enabled.is_some()
+        || body.device_approval_required.is_some()
+        || body.dm_receipts_enabled.is_some()
+        || body.directory_discoverable.is_some()
+        || presence_visibility.is_some()
+        || body.presence_hidden_guild_ids.is_some()
+    {
+        // Other open clients converge on the stored document.
+        let mut event = settings_json.clone();
+        event["scopes"] = json!(changed_scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<&str>>());
+        state
+            .event_bus
+            .dispatch_to_users("USER_SETTINGS_UPDATE", event, vec![auth.user_id]);
+    }
+
+    settings_json["status"] = json!(body.status.unwrap_or_else(|| "online".to_string()));
+    settings_json["custom_status"] = json!(body.custom_status);
+    if let Some(code) = device_recovery_code {
+        // Only in the response, never in the broadcast settings.
+        settings_json["device_recovery_code"] = json!(code);
+    }
+    Ok(Json(settings_json))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/read-states",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_read_states(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -294,6 +740,13 @@ pub async fn get_read_states(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/data-export",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn export_my_data(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -301,7 +754,7 @@ pub async fn export_my_data(
     let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
     let settings = paracord_db::users::get_user_settings(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
@@ -324,6 +777,12 @@ pub async fn export_my_data(
     let messages = paracord_db::messages::list_messages_by_author(&state.db, auth.user_id, 50_000)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let notes = paracord_db::user_notes::list_notes(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let moderator_notes = paracord_db::member_notes::list_notes_by_author(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
     Ok(Json(json!({
         "exported_at": chrono::Utc::now().to_rfc3339(),
@@ -346,8 +805,16 @@ pub async fn export_my_data(
             "message_display": s.message_display,
             "custom_css": s.custom_css,
             "crypto_auth_enabled": s.crypto_auth_enabled,
+            "device_approval_required": s.device_approval_required,
             "notifications": s.notifications,
             "keybinds": s.keybinds,
+            "presence_visibility": s.presence_visibility,
+            "presence_hidden_guild_ids": s.presence_hidden_guild_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>(),
+            "versions": {
+                "appearance": s.appearance_version,
+                "notifications": s.notifications_version,
+                "keybinds": s.keybinds_version,
+            },
             "updated_at": s.updated_at.to_rfc3339(),
         })),
         "guilds": guilds.into_iter().map(|g| json!({
@@ -397,9 +864,177 @@ pub async fn export_my_data(
             "created_at": msg.created_at.to_rfc3339(),
             "edited_at": msg.edited_at.map(|dt| dt.to_rfc3339()),
         })).collect::<Vec<Value>>(),
+        "notes": notes.into_iter().map(|note| json!({
+            "user_id": note.target_id.to_string(),
+            "note": note.note,
+            "updated_at": note.updated_at.to_rfc3339(),
+        })).collect::<Vec<Value>>(),
+        "moderator_notes": moderator_notes.into_iter().map(|note| json!({
+            "id": note.id.to_string(),
+            "guild_id": note.guild_id.to_string(),
+            "user_id": note.user_id.to_string(),
+            "content": note.content,
+            "created_at": note.created_at.to_rfc3339(),
+        })).collect::<Vec<Value>>(),
     })))
 }
 
+pub(crate) fn parse_directory_query(raw: &str) -> Result<String, ApiError> {
+    let query = raw.trim();
+    let len = query.chars().count();
+    if !(MIN_DIRECTORY_QUERY_LEN..=MAX_DIRECTORY_QUERY_LEN).contains(&len) {
+        return Err(ApiError::BadRequest(format!(
+            "Search query must be {MIN_DIRECTORY_QUERY_LEN}-{MAX_DIRECTORY_QUERY_LEN} characters"
+        )));
+    }
+    Ok(query.to_string())
+}
+
+pub(crate) fn directory_limit(limit: Option<i64>) -> i64 {
+    limit
+        .unwrap_or(DEFAULT_DIRECTORY_LIMIT)
+        .clamp(1, MAX_DIRECTORY_LIMIT)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct UserSearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+    /// Also search the directories of trusted federated peers.
+    #[serde(default)]
+    pub federated: bool,
+}
+
+/// Search the user directory. Only users who opted in through
+/// `directory_discoverable` are listed.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/search",
+    tag = "users",
+    operation_id = "users_search_users",
+    params(UserSearchQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<UserSearchQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let query = parse_directory_query(&params.q)?;
+    let limit = directory_limit(params.limit);
+
+    let window_start = chrono::Utc::now().timestamp() / 60;
+    let bucket_key = format!("user_search:{}", auth.user_id);
+    let searches = paracord_db::rate_limits::increment_window_counter(
+        &state.db,
+        &bucket_key,
+        window_start,
+        60,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if searches > DIRECTORY_SEARCHES_PER_MINUTE {
+        return Err(ApiError::RateLimited);
+    }
+
+    let users = paracord_db::users::search_directory_users(
+        &state.db,
+        &query,
+        paracord_core::USER_FLAG_BOT,
+        limit,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let users: Vec<Value> = users
+        .iter()
+        .map(|user| {
+            json!({
+                "id": user.id.to_string(),
+                "username": user.username,
+                "discriminator": user.discriminator,
+                "display_name": user.display_name,
+                "avatar_hash": user.avatar_hash,
+            })
+        })
+        .collect();
+
+    let federated_users = if params.federated {
+        search_peer_directories(&state, &query, limit).await
+    } else {
+        Vec::new()
+    };
+    Ok(Json(
+        json!({ "users": users, "federated_users": federated_users }),
+    ))
+}
+
+/// Query every trusted peer's directory at once. Peers that fail or time
+/// out are skipped.
+async fn search_peer_directories(state: &AppState, query: &str, limit: i64) -> Vec<Value> {
+    let service = crate::routes::federation::federation_service_from_state(state);
+    if !service.is_enabled() {
+        return Vec::new();
+    }
+    let Some(client) = crate::routes::federation::build_signed_federation_client(&service) else {
+        return Vec::new();
+    };
+    let peers = match paracord_db::federation::list_trusted_federated_servers(&state.db).await {
+        Ok(peers) => peers,
+        Err(err) => {
+            tracing::warn!("Failed to list federation peers for user search: {}", err);
+            return Vec::new();
+        }
+    };
+
+    let searches = peers.iter().map(|peer| {
+        let client = client.clone();
+        async move {
+            let result = tokio::time::timeout(
+                PEER_DIRECTORY_TIMEOUT,
+                client.search_users(&peer.federation_endpoint, query, limit),
+            )
+            .await;
+            match result {
+                Ok(Ok(response)) => response
+                    .users
+                    .into_iter()
+                    .take(limit as usize)
+                    .map(|user| {
+                        json!({
+                            "user_id": user.user_id,
+                            "username": user.username,
+                            "display_name": user.display_name,
+                            "server": peer.server_name,
+                        })
+                    })
+                    .collect(),
+                Ok(Err(err)) => {
+                    tracing::debug!("User search on {} failed: {}", peer.server_name, err);
+                    Vec::new()
+                }
+                Err(_) => {
+                    tracing::debug!("User search on {} timed out", peer.server_name);
+                    Vec::new()
+                }
+            }
+        }
+    });
+    futures_util::future::join_all(searches)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/profile",
+    tag = "users",
+    params(("user_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_user_profile(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -408,7 +1043,7 @@ pub async fn get_user_profile(
     let user = paracord_db::users::get_user_by_id(&state.db, user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     let mutual_guilds = paracord_db::users::get_mutual_guilds(&state.db, auth.user_id, user_id)
         .await
@@ -418,6 +1053,25 @@ pub async fn get_user_profile(
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
 
+    let note = paracord_db::user_notes::get_note(&state.db, auth.user_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .map(|row| row.note);
+
+    let presence = if presence_manager::can_view_presence(&state, auth.user_id, user_id).await
+        && state.online_users.read().await.contains(&user_id)
+    {
+        state
+            .user_presences
+            .read()
+            .await
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_else(|| presence_manager::offline_presence(user_id))
+    } else {
+        presence_manager::offline_presence(user_id)
+    };
+
     // Get roles from the first mutual guild (if any) for context
     let roles: Vec<Value> = if let Some(first_guild) = mutual_guilds.first() {
         let role_rows = paracord_db::roles::get_member_roles(&state.db, user_id, first_guild.id)
@@ -469,10 +1123,125 @@ pub async fn get_user_profile(
             "discriminator": f.discriminator,
             "avatar_hash": f.avatar_hash,
         })).collect::<Vec<Value>>(),
+        "note": note,
+        "presence": presence,
         "created_at": user.created_at.to_rfc3339(),
     })))
 }
 
+fn user_note_to_json(note: &paracord_db::user_notes::UserNoteRow) -> Value {
+    json!({
+        "user_id": note.target_id.to_string(),
+        "note": note.note,
+        "updated_at": note.updated_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/notes",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_notes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let notes = paracord_db::user_notes::list_notes(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!(notes
+        .iter()
+        .map(user_note_to_json)
+        .collect::<Vec<Value>>())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/notes/{user_id}",
+    tag = "users",
+    params(("user_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_note(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let note = paracord_db::user_notes::get_note(&state.db, auth.user_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(user_note_to_json(&note)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateNoteRequest {
+    pub note: Option<String>,
+}
+
+/// Set the caller's private note on a user. An empty or missing note deletes it.
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/notes/{user_id}",
+    tag = "users",
+    params(("user_id" = i64, Path)),
+    request_body = UpdateNoteRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_note(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<i64>,
+    Json(body): Json<UpdateNoteRequest>,
+) -> Result<StatusCode, ApiError> {
+    if user_id == auth.user_id {
+        return Err(ApiError::BadRequest("Cannot add a note to yourself".into()));
+    }
+    paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+
+    let note = body.note.as_deref().map(str::trim).unwrap_or_default();
+    if note.chars().count() > MAX_USER_NOTE_LEN {
+        return Err(ApiError::BadRequest(
+            "Note must be at most 256 characters".into(),
+        ));
+    }
+    if note.is_empty() {
+        paracord_db::user_notes::delete_note(&state.db, auth.user_id, user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    } else {
+        paracord_db::user_notes::upsert_note(&state.db, auth.user_id, user_id, note)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    // Keep the caller's other sessions in sync.
+    state.event_bus.dispatch_to_users(
+        "USER_NOTE_UPDATE",
+        json!({
+            "id": user_id.to_string(),
+            "note": note,
+        }),
+        vec![auth.user_id],
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/@me",
+    tag = "users",
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_me(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -503,12 +1272,20 @@ pub async fn delete_me(
     Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ChangePasswordRequest {
     pub current_password: String,
     pub new_password: String,
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/password",
+    tag = "users",
+    request_body = ChangePasswordRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn change_password(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -568,12 +1345,20 @@ pub async fn change_password(
     Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ChangeEmailRequest {
     pub current_password: String,
     pub new_email: String,
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/@me/email",
+    tag = "users",
+    request_body = ChangeEmailRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn change_email(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -639,6 +1424,54 @@ pub async fn change_email(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ── Age gate ───────────────────────────────────────────────────────────────
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/@me/age-gate",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_age_gate(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let acknowledged_at = paracord_db::users::get_age_gate_acknowledged_at(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(json!({
+        "acknowledged": acknowledged_at.is_some(),
+        "acknowledged_at": acknowledged_at,
+    })))
+}
+
+/// Confirm the user may view NSFW channels. Their open gateway sessions
+/// start receiving NSFW channel messages straight away.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/age-gate",
+    tag = "users",
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn acknowledge_age_gate(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Value>, ApiError> {
+    let acknowledged_at = paracord_db::users::acknowledge_age_gate(&state.db, auth.user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let payload = json!({
+        "acknowledged": true,
+        "acknowledged_at": acknowledged_at,
+    });
+    state
+        .event_bus
+        .dispatch_to_users("AGE_GATE_ACKNOWLEDGED", payload.clone(), vec![auth.user_id]);
+    Ok(Json(payload))
+}
+
 // ── Identity Portability ───────────────────────────────────────────────────
 
 fn parse_signing_key() -> Option<ed25519_dalek::SigningKey> {
@@ -650,11 +1483,19 @@ fn get_server_name() -> String {
     std::env::var("PARACORD_SERVER_NAME").unwrap_or_else(|_| "localhost".to_string())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct ExportIdentityQuery {
     pub include_messages: Option<bool>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/export",
+    tag = "users",
+    params(ExportIdentityQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn export_identity(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -682,6 +1523,14 @@ pub async fn export_identity(
     Ok(Json(json_value))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/@me/import",
+    tag = "users",
+    request_body = Object,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn import_identity(
     State(state): State<AppState>,
     auth: AuthUser,
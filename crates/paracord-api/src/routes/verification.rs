@@ -0,0 +1,668 @@
+//! Membership screening through an external verification hook.
+//!
+//! A guild can register a URL that the server calls whenever someone
+//! accepts one of its invites. The request carries the joining user's
+//! public metadata and is signed with HMAC-SHA256 over
+//! `"{timestamp}.{body}"` using the hook's secret. The hook answers
+//! `allow`, `deny` or `pending`; pending joins are held until the
+//! integration posts a signed decision to the callback endpoint or a
+//! moderator decides them.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use paracord_core::AppState;
+use paracord_db::guild_verification::{
+    JoinRequestRow, VerificationHookRow, JOIN_REQUEST_DENIED, JOIN_REQUEST_PENDING,
+};
+use paracord_models::error_code::ErrorCode;
+use paracord_models::gateway::{EVENT_GUILD_JOIN_REQUEST_UPDATE, EVENT_GUILD_MEMBER_ADD};
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::net::SocketAddr;
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::guilds::require_manage_guild;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_HEADER: &str = "x-paracord-signature";
+pub const TIMESTAMP_HEADER: &str = "x-paracord-timestamp";
+const DEFAULT_HOOK_TIMEOUT_MS: i32 = 5000;
+const MIN_HOOK_TIMEOUT_MS: i32 = 1000;
+const MAX_HOOK_TIMEOUT_MS: i32 = 10_000;
+const MAX_HOOK_URL_LEN: usize = 2048;
+const MIN_SECRET_LEN: usize = 16;
+const MAX_SECRET_LEN: usize = 256;
+const MAX_REASON_LEN: usize = 256;
+/// Signed callbacks older or newer than this are rejected as replays.
+const CALLBACK_TOLERANCE_SECONDS: i64 = 300;
+
+/// Outcome of screening a join.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinScreening {
+    Allowed,
+    Pending(Option<String>),
+    Denied(Option<String>),
+}
+
+fn parse_decision(decision: &str, reason: Option<String>) -> Option<JoinScreening> {
+    let reason = reason
+        .map(|reason| {
+            reason
+                .trim()
+                .chars()
+                .take(MAX_REASON_LEN)
+                .collect::<String>()
+        })
+        .filter(|reason| !reason.is_empty());
+    match decision {
+        "allow" => Some(JoinScreening::Allowed),
+        "pending" => Some(JoinScreening::Pending(reason)),
+        "deny" => Some(JoinScreening::Denied(reason)),
+        _ => None,
+    }
+}
+
+/// `sha256=<hex>` signature of a hook request or callback.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "sha256={}",
+        paracord_federation::hex_encode(&mac.finalize().into_bytes())
+    )
+}
+
+fn verify_signature(secret: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), ApiError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let timestamp: i64 = header(TIMESTAMP_HEADER)
+        .and_then(|raw| raw.trim().parse().ok())
+        .ok_or(ApiError::Unauthorized)?;
+    if (chrono::Utc::now().timestamp() - timestamp).abs() > CALLBACK_TOLERANCE_SECONDS {
+        return Err(ApiError::Unauthorized);
+    }
+    let signature = header(SIGNATURE_HEADER)
+        .and_then(|raw| raw.trim().strip_prefix("sha256="))
+        .and_then(decode_hex)
+        .ok_or(ApiError::Unauthorized)?;
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&signature)
+        .map_err(|_| ApiError::Unauthorized)
+}
+
+fn decode_hex(raw: &str) -> Option<Vec<u8>> {
+    if !raw.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(raw.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0_u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    paracord_federation::hex_encode(&bytes)
+}
+
+fn validate_hook_url(raw: &str, allow_private: bool) -> Result<url::Url, ApiError> {
+    let invalid = || {
+        ApiError::BadRequest(if allow_private {
+            "url must be an http:// or https:// URL".into()
+        } else {
+            "url must be an https:// URL".into()
+        })
+    };
+    let raw = raw.trim();
+    if raw.is_empty() || raw.len() > MAX_HOOK_URL_LEN {
+        return Err(invalid());
+    }
+    let url = url::Url::parse(raw).map_err(|_| invalid())?;
+    let scheme_ok = url.scheme() == "https" || (allow_private && url.scheme() == "http");
+    if !scheme_ok
+        || url.host_str().is_none()
+        || !url.username().is_empty()
+        || url.password().is_some()
+    {
+        return Err(invalid());
+    }
+    Ok(url)
+}
+
+/// Resolve the hook's host once and pin the request to that address, so a
+/// DNS answer can't switch to an internal address after the check.
+async fn resolve_hook_target(
+    url: &url::Url,
+    allow_private: bool,
+) -> Result<(String, SocketAddr), String> {
+    let host = url.host_str().ok_or("hook URL has no host")?.to_string();
+    let port = url.port_or_known_default().ok_or("hook URL has no port")?;
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+        .await
+        .map_err(|e| format!("failed to resolve {host}: {e}"))?
+        .collect();
+    if !allow_private
+        && addrs
+            .iter()
+            .any(|addr| !paracord_util::net::is_public_ip(addr.ip()))
+    {
+        return Err(format!("{host} resolves to a private address"));
+    }
+    let addr = addrs
+        .first()
+        .copied()
+        .ok_or_else(|| format!("{host} did not resolve"))?;
+    Ok((host, addr))
+}
+
+#[derive(Deserialize)]
+struct HookDecision {
+    decision: String,
+    reason: Option<String>,
+}
+
+async fn call_hook(
+    state: &AppState,
+    hook: &VerificationHookRow,
+    payload: &Value,
+) -> Result<JoinScreening, String> {
+    let allow_private = state
+        .runtime
+        .read()
+        .await
+        .verification_hooks_allow_private_networks;
+    let url = validate_hook_url(&hook.url, allow_private).map_err(|e| e.to_string())?;
+    let (host, addr) = resolve_hook_target(&url, allow_private).await?;
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_millis(
+            hook.timeout_ms.max(0) as u64
+        ))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve(&host, addr)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let timestamp = chrono::Utc::now().timestamp();
+    let response = client
+        .post(url)
+        .header("content-type", "application/json")
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            sign_payload(&hook.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("hook returned {}", response.status()));
+    }
+    let decision: HookDecision = response
+        .json()
+        .await
+        .map_err(|e| format!("invalid hook response: {e}"))?;
+    parse_decision(&decision.decision, decision.reason)
+        .ok_or_else(|| format!("unknown decision '{}'", decision.decision))
+}
+
+/// Ask the guild's verification hook whether `user_id` may join, and record
+/// held or refused joins. Guilds without an enabled hook allow everyone.
+pub(crate) async fn screen_join(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+    invite_code: &str,
+) -> Result<JoinScreening, ApiError> {
+    let hook = paracord_db::guild_verification::get_verification_hook(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let Some(hook) = hook.filter(|hook| hook.enabled) else {
+        return Ok(JoinScreening::Allowed);
+    };
+    let user = paracord_db::users::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+
+    let payload = json!({
+        "type": "guild_join",
+        "guild_id": guild_id.to_string(),
+        "invite_code": invite_code,
+        "user": {
+            "id": user.id.to_string(),
+            "username": user.username,
+            "discriminator": user.discriminator,
+            "display_name": user.display_name,
+            "created_at": user.created_at.to_rfc3339(),
+            "bot": paracord_core::is_bot(user.flags),
+        },
+    });
+    let outcome = match call_hook(state, &hook, &payload).await {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            tracing::warn!("Verification hook for guild {} failed: {}", guild_id, err);
+            if hook.fail_open {
+                JoinScreening::Allowed
+            } else {
+                JoinScreening::Pending(None)
+            }
+        }
+    };
+
+    let recorded = match &outcome {
+        JoinScreening::Allowed => {
+            paracord_db::guild_verification::delete_join_request(&state.db, guild_id, user_id)
+                .await
+                .map(|_| ())
+        }
+        JoinScreening::Pending(reason) | JoinScreening::Denied(reason) => {
+            let status = if matches!(outcome, JoinScreening::Pending(_)) {
+                JOIN_REQUEST_PENDING
+            } else {
+                JOIN_REQUEST_DENIED
+            };
+            paracord_db::guild_verification::upsert_join_request(
+                &state.db,
+                guild_id,
+                user_id,
+                status,
+                reason.as_deref(),
+                Some(invite_code),
+            )
+            .await
+            .map(|_| ())
+        }
+    };
+    recorded.map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(outcome)
+}
+
+/// Add an approved user to the guild with the default role.
+async fn admit_member(state: &AppState, guild_id: i64, user_id: i64) -> Result<(), ApiError> {
+    let already_member = paracord_db::members::get_member(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .is_some();
+    if already_member {
+        return Ok(());
+    }
+    paracord_db::members::add_member(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if let Err(e) =
+        paracord_db::roles::add_member_role(&state.db, user_id, guild_id, guild_id).await
+    {
+        tracing::warn!("Failed to assign Member role: {e}");
+    }
+
+    paracord_core::member_list::add_member(state, guild_id, user_id);
+    state.event_bus.dispatch(
+        EVENT_GUILD_MEMBER_ADD,
+        json!({"guild_id": guild_id.to_string(), "user_id": user_id.to_string()}),
+        Some(guild_id),
+    );
+    if paracord_federation::is_enabled() {
+        let fed_state = state.clone();
+        tokio::spawn(async move {
+            crate::routes::members::federation_forward_member_event(
+                &fed_state,
+                "m.member.join",
+                guild_id,
+                user_id,
+            )
+            .await;
+        });
+    }
+    Ok(())
+}
+
+/// Settle a pending join, admitting or refusing the user.
+async fn decide_join(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+    allow: bool,
+    reason: Option<String>,
+) -> Result<(), ApiError> {
+    let request = paracord_db::guild_verification::get_join_request(&state.db, guild_id, user_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|request| request.status == JOIN_REQUEST_PENDING)
+        .ok_or(ApiError::NotFound)?;
+
+    if allow {
+        admit_member(state, guild_id, user_id).await?;
+        paracord_db::guild_verification::delete_join_request(&state.db, guild_id, user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    } else {
+        paracord_db::guild_verification::upsert_join_request(
+            &state.db,
+            guild_id,
+            user_id,
+            JOIN_REQUEST_DENIED,
+            reason.as_deref(),
+            request.invite_code.as_deref(),
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    }
+
+    state.event_bus.dispatch_to_users(
+        EVENT_GUILD_JOIN_REQUEST_UPDATE,
+        json!({
+            "guild_id": guild_id.to_string(),
+            "user_id": user_id.to_string(),
+            "status": if allow { "approved" } else { JOIN_REQUEST_DENIED },
+            "reason": reason,
+        }),
+        vec![user_id],
+    );
+    Ok(())
+}
+
+fn hook_to_json(hook: &VerificationHookRow) -> Value {
+    json!({
+        "guild_id": hook.guild_id.to_string(),
+        "url": hook.url,
+        "timeout_ms": hook.timeout_ms,
+        "fail_open": hook.fail_open,
+        "enabled": hook.enabled,
+        "updated_at": hook.updated_at,
+    })
+}
+
+fn join_request_to_json(request: &JoinRequestRow) -> Value {
+    json!({
+        "guild_id": request.guild_id.to_string(),
+        "user_id": request.user_id.to_string(),
+        "status": request.status,
+        "reason": request.reason,
+        "invite_code": request.invite_code,
+        "created_at": request.created_at.to_rfc3339(),
+        "updated_at": request.updated_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/verification-hook",
+    tag = "verification",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_verification_hook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let hook = paracord_db::guild_verification::get_verification_hook(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(hook_to_json(&hook)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateVerificationHookRequest {
+    pub url: String,
+    /// Keeps the current secret (or generates one) when omitted.
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub rotate_secret: bool,
+    pub timeout_ms: Option<i32>,
+    #[serde(default)]
+    pub fail_open: bool,
+    pub enabled: Option<bool>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/verification-hook",
+    tag = "verification",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateVerificationHookRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_verification_hook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateVerificationHookRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+
+    let allow_private = state
+        .runtime
+        .read()
+        .await
+        .verification_hooks_allow_private_networks;
+    let url = validate_hook_url(&body.url, allow_private)?;
+    let timeout_ms = body.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS);
+    if !(MIN_HOOK_TIMEOUT_MS..=MAX_HOOK_TIMEOUT_MS).contains(&timeout_ms) {
+        return Err(ApiError::BadRequest(format!(
+            "timeout_ms must be between {MIN_HOOK_TIMEOUT_MS} and {MAX_HOOK_TIMEOUT_MS}"
+        )));
+    }
+    if let Some(secret) = body.secret.as_deref() {
+        if !(MIN_SECRET_LEN..=MAX_SECRET_LEN).contains(&secret.len()) {
+            return Err(ApiError::BadRequest(format!(
+                "secret must be {MIN_SECRET_LEN}-{MAX_SECRET_LEN} characters"
+            )));
+        }
+    }
+
+    let existing = paracord_db::guild_verification::get_verification_hook(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let (secret, generated) = match (body.secret, existing) {
+        (Some(secret), _) => (secret, false),
+        (None, Some(existing)) if !body.rotate_secret => (existing.secret, false),
+        (None, _) => (generate_secret(), true),
+    };
+
+    let hook = paracord_db::guild_verification::upsert_verification_hook(
+        &state.db,
+        guild_id,
+        url.as_str(),
+        &secret,
+        timeout_ms,
+        body.fail_open,
+        body.enabled.unwrap_or(true),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let mut hook_json = hook_to_json(&hook);
+    if generated {
+        // Only shown once; the integration needs it to check signatures.
+        hook_json["secret"] = json!(secret);
+    }
+    Ok(Json(hook_json))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guilds/{guild_id}/verification-hook",
+    tag = "verification",
+    params(("guild_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_verification_hook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let deleted = paracord_db::guild_verification::delete_verification_hook(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/join-requests",
+    tag = "verification",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_join_requests(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let requests = paracord_db::guild_verification::list_pending_join_requests(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let result: Vec<Value> = requests.iter().map(join_request_to_json).collect();
+    Ok(Json(json!(result)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct JoinDecisionRequest {
+    pub decision: String,
+    pub reason: Option<String>,
+}
+
+fn parse_final_decision(body: JoinDecisionRequest) -> Result<(bool, Option<String>), ApiError> {
+    match parse_decision(&body.decision, body.reason) {
+        Some(JoinScreening::Allowed) => Ok((true, None)),
+        Some(JoinScreening::Denied(reason)) => Ok((false, reason)),
+        _ => Err(ApiError::BadRequest(
+            "decision must be \"allow\" or \"deny\"".into(),
+        )),
+    }
+}
+
+/// A moderator settles a pending join.
+#[utoipa::path(
+    put,
+    path = "/api/v1/guilds/{guild_id}/join-requests/{user_id}",
+    tag = "verification",
+    params(("guild_id" = i64, Path), ("user_id" = i64, Path)),
+    request_body = JoinDecisionRequest,
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn decide_join_request(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, user_id)): Path<(i64, i64)>,
+    Json(body): Json<JoinDecisionRequest>,
+) -> Result<StatusCode, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let (allow, reason) = parse_final_decision(body)?;
+    decide_join(&state, guild_id, user_id, allow, reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct HookCallback {
+    user_id: String,
+    decision: String,
+    reason: Option<String>,
+}
+
+/// The integration settles a pending join. Not authenticated as a user;
+/// the body must be signed with the hook's secret.
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/verification-hook/callback",
+    tag = "verification",
+    params(("guild_id" = i64, Path)),
+    request_body = Value,
+    responses((status = 204, description = "No Content")),
+)]
+pub async fn hook_callback(
+    State(state): State<AppState>,
+    Path(guild_id): Path<i64>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let hook = paracord_db::guild_verification::get_verification_hook(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Unauthorized)?;
+    verify_signature(&hook.secret, &headers, &body)?;
+
+    let callback: HookCallback = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::BadRequest(format!("invalid callback body: {e}")))?;
+    let user_id: i64 = callback
+        .user_id
+        .parse()
+        .map_err(|_| ApiError::BadRequest("invalid user_id".into()))?;
+    let (allow, reason) = parse_final_decision(JoinDecisionRequest {
+        decision: callback.decision,
+        reason: callback.reason,
+    })?;
+    decide_join(&state, guild_id, user_id, allow, reason).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_verify_and_reject_tampering() {
+        let now = chrono::Utc::now().timestamp();
+        let body = br#"{"user_id":"1","decision":"allow"}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, now.to_string().parse().unwrap());
+        headers.insert(
+            SIGNATURE_HEADER,
+            sign_payload("secret", now, body).parse().unwrap(),
+        );
+        assert!(verify_signature("secret", &headers, body).is_ok());
+        assert!(verify_signature("other", &headers, body).is_err());
+        assert!(verify_signature("secret", &headers, b"{}").is_err());
+
+        let stale = now - CALLBACK_TOLERANCE_SECONDS - 1;
+        headers.insert(TIMESTAMP_HEADER, stale.to_string().parse().unwrap());
+        headers.insert(
+            SIGNATURE_HEADER,
+            sign_payload("secret", stale, body).parse().unwrap(),
+        );
+        assert!(verify_signature("secret", &headers, body).is_err());
+    }
+
+    #[test]
+    fn hook_urls_require_https_unless_private_networks_are_allowed() {
+        assert!(validate_hook_url("https://verify.example/hook", false).is_ok());
+        assert!(validate_hook_url("http://verify.example/hook", false).is_err());
+        assert!(validate_hook_url("http://127.0.0.1:8080/hook", true).is_ok());
+        assert!(validate_hook_url("https://user:pw@verify.example", false).is_err());
+        assert!(validate_hook_url("ftp://verify.example", true).is_err());
+    }
+}
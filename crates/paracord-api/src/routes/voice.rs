@@ -7,10 +7,12 @@ use axum::{
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use paracord_core::AppState;
 use paracord_federation::client::{FederationMediaRelayRequest, FederationMediaTokenRequest};
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
@@ -183,17 +185,17 @@ fn verify_livekit_webhook_auth(
     Ok(())
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Default, IntoParams)]
 pub struct VoiceJoinQuery {
     pub fallback: Option<String>,
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Default, IntoParams)]
 pub struct VoiceLeaveQuery {
     pub session_id: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct StartStreamRequest {
     pub title: Option<String>,
     pub quality_preset: Option<String>,
@@ -204,6 +206,8 @@ pub struct LiveKitWebhookPayload {
     pub event: String,
     pub room: Option<LiveKitRoom>,
     pub participant: Option<LiveKitParticipant>,
+    #[serde(rename = "egressInfo", alias = "egress_info", default)]
+    pub egress_info: Option<paracord_media::EgressInfo>,
 }
 
 #[derive(Deserialize)]
@@ -216,6 +220,14 @@ pub struct LiveKitParticipant {
     pub identity: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/voice/{channel_id}/join",
+    tag = "voice",
+    params(("channel_id" = i64, Path), VoiceJoinQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn join_voice(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -235,7 +247,7 @@ pub async fn join_voice(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".into()));
@@ -248,7 +260,7 @@ pub async fn join_voice(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -263,7 +275,7 @@ pub async fn join_voice(
     let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     // If the user was tracked in any other voice room, remove that stale
     // in-memory membership before joining the new channel.
@@ -406,8 +418,23 @@ pub async fn join_voice(
     // ── Native media path ──────────────────────────────────────────────
     // When native media is enabled, use it by default unless the client
     // explicitly requests LiveKit as a fallback (after a native failure).
+    // A per-channel voice stack (set during a LiveKit -> native migration)
+    // overrides the server default.
+    let voice_stack = paracord_db::voice_stacks::get_channel_stack(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
     let requesting_livekit_fallback = query.fallback.as_deref() == Some("livekit");
-    if state.config.native_media_enabled && !requesting_livekit_fallback {
+    if requesting_livekit_fallback
+        && voice_stack.as_deref() == Some(paracord_db::voice_stacks::VOICE_STACK_NATIVE)
+    {
+        return Err(ApiError::ServiceUnavailable(
+            "This voice channel has been migrated to native media".into(),
+        ));
+    }
+    let use_native = state.config.native_media_enabled
+        && !requesting_livekit_fallback
+        && voice_stack.as_deref() != Some(paracord_db::voice_stacks::VOICE_STACK_LIVEKIT);
+    if use_native {
         let session_id = uuid::Uuid::new_v4().to_string();
         let _ = paracord_db::voice_states::upsert_voice_state(
             &state.db,
@@ -417,6 +444,12 @@ pub async fn join_voice(
             &session_id,
         )
         .await;
+        tokio::spawn(crate::routes::events::start_events_for_voice_join(
+            state.clone(),
+            guild_id,
+            channel_id,
+            auth.user_id,
+        ));
 
         state.event_bus.dispatch(
             "VOICE_STATE_UPDATE",
@@ -477,7 +510,10 @@ pub async fn join_voice(
         .unwrap_or_default();
 
         // Include the cert hash so browsers can trust self-signed certs
-        let cert_hash = state.native_media.as_ref().map(|nm| nm.cert_hash.clone());
+        let cert_hash = state
+            .native_media
+            .as_ref()
+            .and_then(|nm| nm.cert_hash.clone());
 
         tracing::info!(
             "Native media voice join issued for user={} channel={}",
@@ -494,6 +530,7 @@ pub async fn join_voice(
             "room_name": room_name,
             "session_id": session_id,
             "livekit_available": state.config.livekit_available,
+            "voice_stack": voice_stack,
         })));
     }
 
@@ -527,6 +564,12 @@ pub async fn join_voice(
         &session_id,
     )
     .await;
+    tokio::spawn(crate::routes::events::start_events_for_voice_join(
+        state.clone(),
+        guild_id,
+        channel_id,
+        auth.user_id,
+    ));
 
     state.event_bus.dispatch(
         "VOICE_STATE_UPDATE",
@@ -565,9 +608,250 @@ pub async fn join_voice(
         "url_candidates": url_candidates,
         "room_name": join_resp.room_name,
         "session_id": session_id,
+        "voice_stack": voice_stack,
+    })))
+}
+
+/// Sign a native media token for a server-side participant (the WebRTC
+/// gateway) that has no voice state, scoping it to a single relay room.
+pub(crate) fn mint_native_media_token(state: &AppState, subject: i64, media_room: &str) -> String {
+    let issued_at = chrono::Utc::now().timestamp();
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(Algorithm::HS256),
+        &json!({
+            "sub": subject,
+            "iat": issued_at,
+            "exp": issued_at + 86400,
+            "room": media_room,
+        }),
+        &jsonwebtoken::EncodingKey::from_secret(state.config.jwt_secret.as_bytes()),
+    )
+    .unwrap_or_default()
+}
+
+// ── Voice stack migration ────────────────────────────────────────────────
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateVoiceStackRequest {
+    /// `livekit`, `native`, or null to follow the server default.
+    pub stack: Option<String>,
+}
+
+async fn require_manage_guild_voice(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<(), ApiError> {
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let roles = paracord_db::roles::get_member_roles(&state.db, user_id, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let perms =
+        paracord_core::permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    paracord_core::permissions::require_permission(perms, Permissions::MANAGE_GUILD)?;
+    Ok(())
+}
+
+fn default_voice_stack(state: &AppState) -> &'static str {
+    if state.config.native_media_enabled {
+        paracord_db::voice_stacks::VOICE_STACK_NATIVE
+    } else {
+        paracord_db::voice_stacks::VOICE_STACK_LIVEKIT
+    }
+}
+
+/// The stack a channel's voice sessions actually run on.
+pub(crate) async fn effective_voice_stack(
+    state: &AppState,
+    channel_id: i64,
+) -> Result<String, ApiError> {
+    let stack = paracord_db::voice_stacks::get_channel_stack(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(stack.unwrap_or_else(|| default_voice_stack(state).to_string()))
+}
+
+fn validate_voice_stack(state: &AppState, stack: &str) -> Result<(), ApiError> {
+    use paracord_db::voice_stacks::{VOICE_STACK_LIVEKIT, VOICE_STACK_NATIVE};
+    let available = match stack {
+        VOICE_STACK_LIVEKIT => state.config.livekit_available,
+        VOICE_STACK_NATIVE => state.config.native_media_enabled,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "stack must be one of livekit, native".into(),
+            ))
+        }
+    };
+    if !available {
+        return Err(ApiError::BadRequest(format!(
+            "Voice stack '{}' is not available on this server",
+            stack
+        )));
+    }
+    Ok(())
+}
+
+/// Apply a stack change to one channel.
+async fn apply_voice_stack(
+    state: &AppState,
+    guild_id: i64,
+    channel_id: i64,
+    stack: Option<&str>,
+) -> Result<(), ApiError> {
+    match stack {
+        Some(stack) => {
+            paracord_db::voice_stacks::set_channel_stack(&state.db, channel_id, guild_id, stack)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        None => {
+            paracord_db::voice_stacks::clear_channel_stack(&state.db, channel_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+    }
+    Ok(())
+}
+
+/// Per-channel voice stack report for a guild, used to drive a gradual
+/// LiveKit -> native migration.
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/voice-stacks",
+    tag = "voice",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_guild_voice_stacks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild_voice(&state, guild_id, auth.user_id).await?;
+    let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let overrides: std::collections::HashMap<i64, String> =
+        paracord_db::voice_stacks::list_guild_stacks(&state.db, guild_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .into_iter()
+            .map(|row| (row.channel_id, row.stack))
+            .collect();
+    let default_stack = default_voice_stack(&state);
+
+    let channels: Vec<Value> = channels
+        .iter()
+        .filter(|channel| channel.channel_type == 2)
+        .map(|channel| {
+            let stack = overrides.get(&channel.id);
+            json!({
+                "channel_id": channel.id.to_string(),
+                "name": channel.name,
+                "stack": stack,
+                "effective_stack": stack.map(String::as_str).unwrap_or(default_stack),
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "default_stack": default_stack,
+        "livekit_available": state.config.livekit_available,
+        "native_media_enabled": state.config.native_media_enabled,
+        "channels": channels,
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/channels/{channel_id}/voice-stack",
+    tag = "voice",
+    params(("channel_id" = i64, Path)),
+    request_body = UpdateVoiceStackRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_channel_voice_stack(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    Json(body): Json<UpdateVoiceStackRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    if channel.channel_type != 2 {
+        return Err(ApiError::BadRequest("Not a voice channel".into()));
+    }
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Voice is only supported in guild channels".into(),
+    ))?;
+    require_manage_guild_voice(&state, guild_id, auth.user_id).await?;
+    let stack = body.stack.as_deref().map(str::trim);
+    if let Some(stack) = stack {
+        validate_voice_stack(&state, stack)?;
+    }
+    apply_voice_stack(&state, guild_id, channel_id, stack).await?;
+
+    Ok(Json(json!({
+        "channel_id": channel_id.to_string(),
+        "stack": stack,
+        "effective_stack": stack.unwrap_or(default_voice_stack(&state)),
     })))
 }
 
+/// Move every voice channel in a guild to the same stack at once.
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/voice-stacks/migrate",
+    tag = "voice",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateVoiceStackRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn migrate_guild_voice_stacks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateVoiceStackRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild_voice(&state, guild_id, auth.user_id).await?;
+    let stack = body.stack.as_deref().map(str::trim);
+    if let Some(stack) = stack {
+        validate_voice_stack(&state, stack)?;
+    }
+    let channels = paracord_db::channels::get_guild_channels(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let mut migrated = Vec::new();
+    for channel in channels.iter().filter(|channel| channel.channel_type == 2) {
+        apply_voice_stack(&state, guild_id, channel.id, stack).await?;
+        migrated.push(channel.id.to_string());
+    }
+
+    Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "stack": stack,
+        "channel_ids": migrated,
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/{channel_id}/stream",
+    tag = "voice",
+    params(("channel_id" = i64, Path), VoiceJoinQuery),
+    request_body = Option<StartStreamRequest>,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn start_stream(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -588,7 +872,7 @@ pub async fn start_stream(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".into()));
@@ -601,7 +885,7 @@ pub async fn start_stream(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
     let perms = paracord_core::permissions::compute_channel_permissions(
         &state.db,
         guild_id,
@@ -626,7 +910,7 @@ pub async fn start_stream(
     let user = paracord_db::users::get_user_by_id(&state.db, auth.user_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
 
     let requested_quality = body
         .as_ref()
@@ -635,6 +919,14 @@ pub async fn start_stream(
     if paracord_media::ScreenCaptureConfig::from_preset(&requested_quality).is_none() {
         return Err(ApiError::BadRequest("Invalid quality_preset".into()));
     }
+    let max_quality = paracord_core::supporters::perks_for(&state, auth.user_id)
+        .await
+        .max_stream_quality;
+    if !paracord_core::supporters::stream_quality_allowed(&max_quality, &requested_quality) {
+        return Err(ApiError::BadRequest(format!(
+            "quality_preset above {max_quality} is not available on this server"
+        )));
+    }
     let stream_title = body.as_ref().and_then(|b| b.title.as_deref());
 
     let federation_service = crate::routes::federation::build_federation_service();
@@ -860,6 +1152,14 @@ pub async fn start_stream(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/{channel_id}/stream/stop",
+    tag = "voice",
+    params(("channel_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn stop_stream(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -868,7 +1168,7 @@ pub async fn stop_stream(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".into()));
@@ -969,6 +1269,14 @@ pub async fn stop_stream(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/{channel_id}/leave",
+    tag = "voice",
+    params(("channel_id" = i64, Path), VoiceLeaveQuery),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn leave_voice(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -978,7 +1286,7 @@ pub async fn leave_voice(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if channel.channel_type != 2 {
         return Err(ApiError::BadRequest("Not a voice channel".into()));
     }
@@ -1042,6 +1350,13 @@ pub async fn leave_voice(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/voice/livekit/webhook",
+    tag = "voice",
+    request_body = Value,
+    responses((status = 204, description = "No Content")),
+)]
 pub async fn livekit_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1056,6 +1371,12 @@ pub async fn livekit_webhook(
     let payload: LiveKitWebhookPayload =
         serde_json::from_slice(&body).map_err(|e| ApiError::BadRequest(e.to_string()))?;
 
+    if payload.event.starts_with("egress_") {
+        if let Some(info) = payload.egress_info {
+            super::recordings::handle_egress_event(&state, &payload.event, info).await?;
+        }
+        return Ok(StatusCode::NO_CONTENT);
+    }
     if payload.event != "participant_left" {
         return Ok(StatusCode::NO_CONTENT);
     }
@@ -4,14 +4,17 @@ use axum::{
     Json,
 };
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use super::voice::{VoiceJoinQuery, VoiceLeaveQuery};
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct VoiceStateUpdateRequest {
     pub guild_id: Option<String>,
     pub channel_id: Option<String>,
@@ -19,11 +22,19 @@ pub struct VoiceStateUpdateRequest {
     pub self_deaf: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct VoiceRecoverRequest {
     pub channel_id: i64,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/voice/{channel_id}/join",
+    tag = "voice_v2",
+    params(("channel_id" = i64, Path), VoiceJoinQuery),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn join_voice_v2(
     state: State<AppState>,
     auth: AuthUser,
@@ -34,6 +45,14 @@ pub async fn join_voice_v2(
     super::voice::join_voice(state, auth, headers, Path(channel_id), query).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/voice/{channel_id}/leave",
+    tag = "voice_v2",
+    params(("channel_id" = i64, Path), VoiceLeaveQuery),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn leave_voice_v2(
     state: State<AppState>,
     auth: AuthUser,
@@ -43,6 +62,15 @@ pub async fn leave_voice_v2(
     super::voice::leave_voice(state, auth, Path(channel_id), query).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/voice/recover",
+    tag = "voice_v2",
+    params(VoiceJoinQuery),
+    request_body = VoiceRecoverRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn recover_voice_v2(
     state: State<AppState>,
     auth: AuthUser,
@@ -53,6 +81,14 @@ pub async fn recover_voice_v2(
     super::voice::join_voice(state, auth, headers, Path(req.channel_id), query).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/voice/state",
+    tag = "voice_v2",
+    request_body = VoiceStateUpdateRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_voice_state_v2(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -70,3 +106,63 @@ pub async fn update_voice_state_v2(
     };
     super::realtime::post_command(State(state), auth, Json(body)).await
 }
+
+/// Relay forwarding stats (loss, jitter, bitrate) for everyone connected to a
+/// voice channel. Restricted to members who can mute others.
+#[utoipa::path(
+    get,
+    path = "/api/v2/voice/{channel_id}/stats",
+    tag = "voice_v2",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_voice_stats_v2(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    if channel.channel_type != 2 {
+        return Err(ApiError::BadRequest("Not a voice channel".into()));
+    }
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Voice is only supported in guild channels".into(),
+    ))?;
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, auth.user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let perms = paracord_core::permissions::compute_channel_permissions(
+        &state.db,
+        guild_id,
+        channel_id,
+        guild.owner_id,
+        auth.user_id,
+    )
+    .await?;
+    paracord_core::permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
+    paracord_core::permissions::require_permission(perms, Permissions::MUTE_MEMBERS)?;
+
+    let native = state
+        .native_media
+        .as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Native media server is not enabled".into()))?;
+    let room_id = format!("{}:{}", guild_id, channel_id);
+    let participants: Vec<Value> = native
+        .relay_forwarder
+        .room_stats(&room_id)
+        .iter()
+        .map(|stats| stats.to_json())
+        .collect();
+
+    Ok(Json(json!({
+        "guild_id": guild_id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "participants": participants,
+    })))
+}
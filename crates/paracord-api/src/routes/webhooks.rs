@@ -4,13 +4,28 @@ use axum::{
     http::{HeaderMap, StatusCode},
     Json,
 };
+use paracord_core::limits::{self, GuildLimit};
 use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
 use paracord_models::permissions::Permissions;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::ToSchema;
 
 use crate::error::ApiError;
 use crate::middleware::AuthUser;
+use crate::routes::security;
+
+/// Burst allowance per webhook: 5 executions every 2 seconds.
+const WEBHOOK_BURST_WINDOW_SECONDS: i64 = 2;
+const WEBHOOK_BURST_LIMIT: u32 = 5;
+/// Sustained allowance per webhook and across all webhooks of a guild.
+const WEBHOOK_LIMIT_PER_MINUTE: u32 = 30;
+const GUILD_WEBHOOK_LIMIT_PER_MINUTE: u32 = 120;
+/// Rate-limited executions tolerated within the window before the webhook
+/// is suspended.
+const WEBHOOK_ABUSE_WINDOW_SECONDS: i64 = 600;
+const WEBHOOK_ABUSE_MAX_STRIKES: u32 = 50;
 
 fn webhook_to_json(w: &paracord_db::webhooks::WebhookRow, token: Option<&str>) -> Value {
     let mut v = json!({
@@ -20,6 +35,7 @@ fn webhook_to_json(w: &paracord_db::webhooks::WebhookRow, token: Option<&str>) -
         "name": w.name,
         "creator_id": w.creator_id.map(|id| id.to_string()),
         "created_at": w.created_at.to_rfc3339(),
+        "suspended_at": w.suspended_at.map(|t| t.to_rfc3339()),
     });
     if let Some(token) = token {
         v["token"] = json!(token);
@@ -35,7 +51,7 @@ async fn require_manage_webhooks(
     let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
 
     paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
 
@@ -48,12 +64,21 @@ async fn require_manage_webhooks(
     Ok(())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateWebhookRequest {
     pub name: String,
     pub channel_id: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/guilds/{guild_id}/webhooks",
+    tag = "webhooks",
+    params(("guild_id" = i64, Path)),
+    request_body = CreateWebhookRequest,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn create_webhook(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -91,14 +116,30 @@ pub async fn create_webhook(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
     if channel.guild_id() != Some(guild_id) {
         return Err(ApiError::BadRequest(
             "Channel does not belong to this guild".into(),
         ));
     }
 
-    let id = paracord_util::snowflake::generate(1);
+    let (max_per_channel, max_per_guild) = {
+        let settings = state.runtime.read().await;
+        (
+            GuildLimit::ChannelWebhooks.configured(&settings),
+            GuildLimit::GuildWebhooks.configured(&settings),
+        )
+    };
+    let channel_count = paracord_db::webhooks::count_channel_webhooks(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    limits::ensure_below(GuildLimit::ChannelWebhooks, max_per_channel, channel_count)?;
+    let guild_count = paracord_db::webhooks::count_guild_webhooks(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    limits::ensure_below(GuildLimit::GuildWebhooks, max_per_guild, guild_count)?;
+
+    let id = paracord_util::snowflake::try_next_id().await?;
     let token = generate_webhook_token();
 
     let webhook = paracord_db::webhooks::create_webhook(
@@ -119,6 +160,14 @@ pub async fn create_webhook(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/webhooks",
+    tag = "webhooks",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_guild_webhooks(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -134,6 +183,14 @@ pub async fn list_guild_webhooks(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/webhooks",
+    tag = "webhooks",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn list_channel_webhooks(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -142,7 +199,7 @@ pub async fn list_channel_webhooks(
     let channel = paracord_db::channels::get_channel(&state.db, channel_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
 
     if let Some(guild_id) = channel.guild_id() {
         require_manage_webhooks(&state, guild_id, auth.user_id).await?;
@@ -156,6 +213,14 @@ pub async fn list_channel_webhooks(
     Ok(Json(json!(result)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn get_webhook(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -164,18 +229,29 @@ pub async fn get_webhook(
     let webhook = paracord_db::webhooks::get_webhook(&state.db, webhook_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownWebhook))?;
 
     require_manage_webhooks(&state, webhook.space_id, auth.user_id).await?;
 
     Ok(Json(webhook_to_json(&webhook, None)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateWebhookRequest {
     pub name: Option<String>,
+    /// `false` lifts an abuse suspension; `true` suspends the webhook.
+    pub suspended: Option<bool>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/v1/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = i64, Path)),
+    request_body = UpdateWebhookRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn update_webhook(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -185,7 +261,7 @@ pub async fn update_webhook(
     let webhook = paracord_db::webhooks::get_webhook(&state.db, webhook_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownWebhook))?;
 
     require_manage_webhooks(&state, webhook.space_id, auth.user_id).await?;
 
@@ -198,6 +274,20 @@ pub async fn update_webhook(
         }
     }
 
+    match body.suspended {
+        Some(true) => {
+            paracord_db::webhooks::suspend_webhook(&state.db, webhook_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        Some(false) => {
+            paracord_db::webhooks::clear_webhook_suspension(&state.db, webhook_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        None => {}
+    }
+
     let updated =
         paracord_db::webhooks::update_webhook(&state.db, webhook_id, body.name.as_deref())
             .await
@@ -206,6 +296,14 @@ pub async fn update_webhook(
     Ok(Json(webhook_to_json(&updated, None)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
 pub async fn delete_webhook(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -214,7 +312,7 @@ pub async fn delete_webhook(
     let webhook = paracord_db::webhooks::get_webhook(&state.db, webhook_id)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownWebhook))?;
 
     require_manage_webhooks(&state, webhook.space_id, auth.user_id).await?;
 
@@ -374,6 +472,14 @@ fn format_github_event(event_type: &str, payload: &Value) -> String {
 }
 
 /// Execute a webhook - no auth required, uses token in path.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{webhook_id}/{token}",
+    tag = "webhooks",
+    params(("webhook_id" = i64, Path), ("token" = String, Path)),
+    request_body = Value,
+    responses((status = 201, description = "Created", body = Value)),
+)]
 pub async fn execute_webhook(
     State(state): State<AppState>,
     Path((webhook_id, token)): Path<(i64, String)>,
@@ -383,7 +489,17 @@ pub async fn execute_webhook(
     let webhook = paracord_db::webhooks::get_webhook_by_id_and_token(&state.db, webhook_id, &token)
         .await
         .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::Code(ErrorCode::UnknownWebhook))?;
+    if webhook.suspended_at.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    enforce_execute_rate_limits(&state, &webhook).await?;
+    if let Some(channel) = paracord_db::channels::get_channel(&state.db, webhook.channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    {
+        paracord_core::channel::ensure_not_archived(&channel)?;
+    }
 
     // Check for GitHub webhook
     let (content, display_name) = if let Some(github_event) = headers.get("X-GitHub-Event") {
@@ -410,7 +526,7 @@ pub async fn execute_webhook(
     };
 
     // Create the message using the webhook creator as the author
-    let msg_id = paracord_util::snowflake::generate(1);
+    let msg_id = paracord_util::snowflake::try_next_id().await?;
     let author_id = webhook.creator_id.unwrap_or(0);
 
     let msg = paracord_db::messages::create_message(
@@ -462,6 +578,80 @@ pub async fn execute_webhook(
     Ok((StatusCode::CREATED, Json(msg_json)))
 }
 
+async fn enforce_execute_rate_limits(
+    state: &AppState,
+    webhook: &paracord_db::webhooks::WebhookRow,
+) -> Result<(), ApiError> {
+    let allowed = crate::check_webhook_rate_limit(
+        &format!("webhook:burst:{}", webhook.id),
+        WEBHOOK_BURST_WINDOW_SECONDS,
+        WEBHOOK_BURST_LIMIT,
+    ) && crate::check_webhook_rate_limit(
+        &format!("webhook:minute:{}", webhook.id),
+        60,
+        WEBHOOK_LIMIT_PER_MINUTE,
+    ) && crate::check_webhook_rate_limit(
+        &format!("webhook:guild:{}", webhook.space_id),
+        60,
+        GUILD_WEBHOOK_LIMIT_PER_MINUTE,
+    );
+    if allowed {
+        return Ok(());
+    }
+
+    if crate::webhook_abuse_threshold_reached(
+        &format!("webhook:strikes:{}", webhook.id),
+        WEBHOOK_ABUSE_WINDOW_SECONDS,
+        WEBHOOK_ABUSE_MAX_STRIKES,
+    ) {
+        suspend_for_abuse(state, webhook).await;
+    }
+    Err(ApiError::RateLimited)
+}
+
+/// Suspend a webhook that keeps hitting its limits and tell server admins
+/// through the security event log.
+async fn suspend_for_abuse(state: &AppState, webhook: &paracord_db::webhooks::WebhookRow) {
+    match paracord_db::webhooks::suspend_webhook(&state.db, webhook.id).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!("Failed to suspend webhook {}: {}", webhook.id, e);
+            return;
+        }
+    }
+    tracing::warn!(
+        webhook_id = webhook.id,
+        guild_id = webhook.space_id,
+        "Webhook suspended after sustained rate limit violations"
+    );
+    security::log_security_event(
+        state,
+        "webhook.suspended",
+        None,
+        webhook.creator_id,
+        None,
+        None,
+        Some(json!({
+            "webhook_id": webhook.id.to_string(),
+            "guild_id": webhook.space_id.to_string(),
+            "channel_id": webhook.channel_id.to_string(),
+            "reason": "rate_limit_abuse",
+            "window_seconds": WEBHOOK_ABUSE_WINDOW_SECONDS,
+            "rejected_executions": WEBHOOK_ABUSE_MAX_STRIKES,
+        })),
+    )
+    .await;
+    state.event_bus.dispatch(
+        "WEBHOOKS_UPDATE",
+        json!({
+            "guild_id": webhook.space_id.to_string(),
+            "channel_id": webhook.channel_id.to_string(),
+        }),
+        Some(webhook.space_id),
+    );
+}
+
 fn generate_webhook_token() -> String {
     use rand::RngCore;
     let mut bytes = [0_u8; 32];
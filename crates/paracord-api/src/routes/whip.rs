@@ -0,0 +1,577 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use paracord_core::AppState;
+use paracord_db::stream_keys::StreamKeyRow;
+use paracord_media::whip::{GatewayTarget, WhipDirection, WhipSession};
+use paracord_models::error_code::ErrorCode;
+use paracord_models::permissions::Permissions;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+
+const STREAM_KEY_PREFIX: &str = "psk_";
+const MAX_STREAM_KEYS_PER_USER: usize = 5;
+const MAX_STREAM_KEY_NAME_LEN: usize = 64;
+const MAX_WHEP_SESSIONS_PER_USER: usize = 3;
+
+#[derive(Deserialize, Default, ToSchema)]
+pub struct CreateStreamKeyRequest {
+    pub name: Option<String>,
+}
+
+fn generate_stream_key() -> String {
+    let mut bytes = [0_u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let mut out = String::with_capacity(STREAM_KEY_PREFIX.len() + bytes.len() * 2);
+    out.push_str(STREAM_KEY_PREFIX);
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+fn stream_key_to_json(key: &StreamKeyRow, live: bool) -> Value {
+    json!({
+        "id": key.id.to_string(),
+        "channel_id": key.channel_id.to_string(),
+        "user_id": key.user_id.to_string(),
+        "name": key.name,
+        "created_at": key.created_at,
+        "last_used_at": key.last_used_at,
+        "live": live,
+    })
+}
+
+/// Load a guild voice channel and return its guild id and `user_id`'s
+/// permissions in it.
+async fn voice_channel_permissions(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<(i64, Permissions), ApiError> {
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownChannel))?;
+    if channel.channel_type != 2 {
+        return Err(ApiError::BadRequest("Not a voice channel".into()));
+    }
+    let guild_id = channel.guild_id().ok_or(ApiError::BadRequest(
+        "Voice is only supported in guild channels".into(),
+    ))?;
+    paracord_core::permissions::ensure_guild_member(&state.db, guild_id, user_id).await?;
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let perms = paracord_core::permissions::compute_channel_permissions(
+        &state.db,
+        guild_id,
+        channel_id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    paracord_core::permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
+    Ok((guild_id, perms))
+}
+
+fn require_streaming(perms: Permissions) -> Result<(), ApiError> {
+    paracord_core::permissions::require_permission(perms, Permissions::CONNECT)?;
+    paracord_core::permissions::require_permission(perms, Permissions::STREAM)?;
+    Ok(())
+}
+
+// ── Stream keys ──────────────────────────────────────────────────────────
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/channels/{channel_id}/stream-keys",
+    tag = "whip",
+    params(("channel_id" = i64, Path)),
+    request_body = Option<CreateStreamKeyRequest>,
+    responses((status = 201, description = "Created", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn create_stream_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+    body: Option<Json<CreateStreamKeyRequest>>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let (guild_id, perms) = voice_channel_permissions(&state, channel_id, auth.user_id).await?;
+    require_streaming(perms)?;
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let name = body
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    if name.is_some_and(|name| name.chars().count() > MAX_STREAM_KEY_NAME_LEN) {
+        return Err(ApiError::BadRequest(format!(
+            "name must be at most {} characters",
+            MAX_STREAM_KEY_NAME_LEN
+        )));
+    }
+
+    let existing = paracord_db::stream_keys::list_channel_stream_keys(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    if existing
+        .iter()
+        .filter(|key| key.user_id == auth.user_id)
+        .count()
+        >= MAX_STREAM_KEYS_PER_USER
+    {
+        return Err(ApiError::BadRequest(
+            "Too many stream keys for this channel".into(),
+        ));
+    }
+
+    let raw_key = generate_stream_key();
+    let key = paracord_db::stream_keys::create_stream_key(
+        &state.db,
+        paracord_util::snowflake::try_next_id().await?,
+        channel_id,
+        guild_id,
+        auth.user_id,
+        &paracord_db::bot_applications::hash_token(&raw_key),
+        name,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    // The raw key is only ever returned here.
+    let mut value = stream_key_to_json(&key, false);
+    value["key"] = json!(raw_key);
+    value["whip_url"] = json!(format!("/api/v1/whip/{}", channel_id));
+    Ok((StatusCode::CREATED, Json(value)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/channels/{channel_id}/stream-keys",
+    tag = "whip",
+    params(("channel_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn list_stream_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(channel_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let (_, perms) = voice_channel_permissions(&state, channel_id, auth.user_id).await?;
+    let can_manage = perms.contains(Permissions::MANAGE_CHANNELS);
+    let keys = paracord_db::stream_keys::list_channel_stream_keys(&state.db, channel_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+
+    let mut result = Vec::new();
+    for key in keys
+        .iter()
+        .filter(|key| can_manage || key.user_id == auth.user_id)
+    {
+        let live = !state
+            .voice
+            .whip_sessions()
+            .owned_by(WhipDirection::Publish, key.id)
+            .await
+            .is_empty();
+        result.push(stream_key_to_json(key, live));
+    }
+    Ok(Json(Value::Array(result)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/channels/{channel_id}/stream-keys/{key_id}",
+    tag = "whip",
+    params(("channel_id" = i64, Path), ("key_id" = i64, Path)),
+    responses((status = 204, description = "No Content")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn delete_stream_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, key_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    let (_, perms) = voice_channel_permissions(&state, channel_id, auth.user_id).await?;
+    let key = paracord_db::stream_keys::get_stream_key(&state.db, key_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|key| key.channel_id == channel_id)
+        .ok_or(ApiError::NotFound)?;
+    if key.user_id != auth.user_id && !perms.contains(Permissions::MANAGE_CHANNELS) {
+        return Err(ApiError::Forbidden);
+    }
+
+    paracord_db::stream_keys::delete_stream_key(&state.db, key.id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    // Revoking a key also takes its stream off the air.
+    for session in state
+        .voice
+        .whip_sessions()
+        .owned_by(WhipDirection::Publish, key.id)
+        .await
+    {
+        end_session(&state, &session).await;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── WHIP / WHEP ──────────────────────────────────────────────────────────
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+fn require_sdp(headers: &HeaderMap, body: &str) -> Result<(), ApiError> {
+    let is_sdp = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/sdp"));
+    if !is_sdp || body.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "Expected an application/sdp offer".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Resolve the stream key in the `Authorization` header for `channel_id`.
+async fn authenticate_stream_key(
+    state: &AppState,
+    headers: &HeaderMap,
+    channel_id: i64,
+) -> Result<StreamKeyRow, ApiError> {
+    let raw_key = bearer_token(headers)
+        .filter(|key| key.starts_with(STREAM_KEY_PREFIX))
+        .ok_or(ApiError::Unauthorized)?;
+    paracord_db::stream_keys::get_stream_key_by_hash(
+        &state.db,
+        &paracord_db::bot_applications::hash_token(raw_key),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+    .filter(|key| key.channel_id == channel_id)
+    .ok_or(ApiError::Unauthorized)
+}
+
+fn sdp_created(kind: &str, channel_id: i64, session_id: &str, answer: String) -> Response {
+    (
+        StatusCode::CREATED,
+        [
+            (header::CONTENT_TYPE, "application/sdp".to_string()),
+            (
+                header::LOCATION,
+                format!("/api/v1/{}/{}/{}", kind, channel_id, session_id),
+            ),
+        ],
+        answer,
+    )
+        .into_response()
+}
+
+async fn end_session(state: &AppState, session: &WhipSession) {
+    if state
+        .voice
+        .whip_sessions()
+        .remove(&session.id)
+        .await
+        .is_none()
+    {
+        return;
+    }
+    if let Err(e) = paracord_media::whip::delete_resource(&session.resource_url, None).await {
+        tracing::warn!("Failed to end WHIP/WHEP session {}: {}", session.id, e);
+    }
+    if let Some(ingress_id) = session.ingress_id.as_deref() {
+        if let Err(e) = state.voice.livekit().delete_ingress(ingress_id).await {
+            tracing::warn!("Failed to delete LiveKit ingress {}: {}", ingress_id, e);
+        }
+    }
+}
+
+/// WHIP ingest: an external encoder publishes into the channel using a
+/// stream key as its bearer token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/whip/{channel_id}",
+    tag = "whip",
+    params(("channel_id" = i64, Path)),
+    request_body(content = String, content_type = "application/sdp"),
+    responses((status = 201, description = "SDP answer", body = String, content_type = "application/sdp")),
+)]
+pub async fn whip_publish(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(channel_id): Path<i64>,
+    body: String,
+) -> Result<Response, ApiError> {
+    require_sdp(&headers, &body)?;
+    let key = authenticate_stream_key(&state, &headers, channel_id).await?;
+    // The key publishes as its owner, so it stops working if they lose access.
+    let (guild_id, perms) = voice_channel_permissions(&state, channel_id, key.user_id).await?;
+    require_streaming(perms)?;
+    if !state
+        .voice
+        .whip_sessions()
+        .owned_by(WhipDirection::Publish, key.id)
+        .await
+        .is_empty()
+    {
+        return Err(ApiError::Conflict("This stream key is already live".into()));
+    }
+
+    let session_id = paracord_util::snowflake::try_next_id().await?.to_string();
+    let stack = super::voice::effective_voice_stack(&state, channel_id).await?;
+    let (exchange, ingress_id) = if stack == paracord_db::voice_stacks::VOICE_STACK_NATIVE {
+        let gateway = state.voice.webrtc_gateway().ok_or_else(|| {
+            ApiError::ServiceUnavailable(
+                "WHIP into native media channels requires a WebRTC gateway".into(),
+            )
+        })?;
+        let room = format!("{}:{}", guild_id, channel_id);
+        let target = GatewayTarget::Native {
+            endpoint: format!("127.0.0.1:{}", state.config.native_media_port),
+            // Negative ids never collide with real users in the relay.
+            token: super::voice::mint_native_media_token(&state, -key.id, &room),
+            room,
+        };
+        let exchange = gateway
+            .offer(WhipDirection::Publish, &target, &body)
+            .await
+            .map_err(|e| {
+                tracing::warn!(
+                    "WHIP gateway publish failed for channel {}: {}",
+                    channel_id,
+                    e
+                );
+                ApiError::ServiceUnavailable("Failed to start WHIP session".into())
+            })?;
+        (exchange, None)
+    } else {
+        if !state.config.livekit_available {
+            return Err(ApiError::ServiceUnavailable(
+                "WHIP ingest requires LiveKit, which is not available on this server".into(),
+            ));
+        }
+        let user = paracord_db::users::get_user_by_id(&state.db, key.user_id)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .ok_or(ApiError::Code(ErrorCode::UnknownUser))?;
+        let room_name = format!("guild_{}_channel_{}", guild_id, channel_id);
+        let display_name = user.display_name.as_deref().unwrap_or(&user.username);
+        let ingress = state
+            .voice
+            .livekit()
+            .create_whip_ingress(
+                &room_name,
+                &format!("whip-{}", key.id),
+                &format!("{} (OBS)", display_name),
+            )
+            .await
+            .map_err(|e| {
+                tracing::warn!("Failed to create ingress for channel {}: {}", channel_id, e);
+                ApiError::ServiceUnavailable("Failed to start WHIP session".into())
+            })?;
+        match paracord_media::whip::exchange_sdp(&ingress.whip_url(), None, &[], &body).await {
+            Ok(exchange) => (exchange, Some(ingress.ingress_id)),
+            Err(e) => {
+                tracing::warn!("LiveKit ingress rejected WHIP offer: {}", e);
+                let _ = state
+                    .voice
+                    .livekit()
+                    .delete_ingress(&ingress.ingress_id)
+                    .await;
+                return Err(ApiError::ServiceUnavailable(
+                    "Failed to start WHIP session".into(),
+                ));
+            }
+        }
+    };
+
+    state
+        .voice
+        .whip_sessions()
+        .insert(WhipSession {
+            id: session_id.clone(),
+            channel_id,
+            direction: WhipDirection::Publish,
+            owner_id: key.id,
+            resource_url: exchange.resource_url,
+            ingress_id,
+        })
+        .await;
+    if let Err(e) = paracord_db::stream_keys::touch_stream_key(&state.db, key.id).await {
+        tracing::warn!("Failed to update stream key {} usage: {}", key.id, e);
+    }
+
+    Ok(sdp_created(
+        "whip",
+        channel_id,
+        &session_id,
+        exchange.answer,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/whip/{channel_id}/{session_id}",
+    tag = "whip",
+    params(("channel_id" = i64, Path), ("session_id" = String, Path)),
+    responses((status = 200, description = "OK")),
+)]
+pub async fn whip_stop(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((channel_id, session_id)): Path<(i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    let key = authenticate_stream_key(&state, &headers, channel_id).await?;
+    let session = state
+        .voice
+        .whip_sessions()
+        .get(&session_id)
+        .await
+        .filter(|session| {
+            session.direction == WhipDirection::Publish
+                && session.channel_id == channel_id
+                && session.owner_id == key.id
+        })
+        .ok_or(ApiError::NotFound)?;
+    end_session(&state, &session).await;
+    Ok(StatusCode::OK)
+}
+
+/// WHEP playback: a lightweight viewer subscribes to the channel through the
+/// WebRTC gateway.
+#[utoipa::path(
+    post,
+    path = "/api/v1/whep/{channel_id}",
+    tag = "whip",
+    params(("channel_id" = i64, Path)),
+    request_body(content = String, content_type = "application/sdp"),
+    responses((status = 201, description = "SDP answer", body = String, content_type = "application/sdp")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn whep_play(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    headers: HeaderMap,
+    Path(channel_id): Path<i64>,
+    body: String,
+) -> Result<Response, ApiError> {
+    require_sdp(&headers, &body)?;
+    let (guild_id, perms) = voice_channel_permissions(&state, channel_id, auth.user_id).await?;
+    paracord_core::permissions::require_permission(perms, Permissions::CONNECT)?;
+    let gateway = state.voice.webrtc_gateway().ok_or_else(|| {
+        ApiError::ServiceUnavailable("WHEP playback requires a WebRTC gateway".into())
+    })?;
+    if state
+        .voice
+        .whip_sessions()
+        .owned_by(WhipDirection::Play, auth.user_id)
+        .await
+        .len()
+        >= MAX_WHEP_SESSIONS_PER_USER
+    {
+        return Err(ApiError::RateLimited);
+    }
+
+    let session_snowflake = paracord_util::snowflake::try_next_id().await?;
+    let session_id = session_snowflake.to_string();
+    let stack = super::voice::effective_voice_stack(&state, channel_id).await?;
+    let target = if stack == paracord_db::voice_stacks::VOICE_STACK_NATIVE {
+        let room = format!("{}:{}", guild_id, channel_id);
+        GatewayTarget::Native {
+            endpoint: format!("127.0.0.1:{}", state.config.native_media_port),
+            token: super::voice::mint_native_media_token(&state, -session_snowflake, &room),
+            room,
+        }
+    } else {
+        let room = format!("guild_{}_channel_{}", guild_id, channel_id);
+        let token = state
+            .voice
+            .livekit()
+            .generate_whep_viewer_token(&room, &format!("whep-{}", session_id))
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        GatewayTarget::LiveKit {
+            url: state.config.livekit_url.clone(),
+            room,
+            token,
+        }
+    };
+    let exchange = gateway
+        .offer(WhipDirection::Play, &target, &body)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                "WHEP gateway playback failed for channel {}: {}",
+                channel_id,
+                e
+            );
+            ApiError::ServiceUnavailable("Failed to start WHEP session".into())
+        })?;
+
+    state
+        .voice
+        .whip_sessions()
+        .insert(WhipSession {
+            id: session_id.clone(),
+            channel_id,
+            direction: WhipDirection::Play,
+            owner_id: auth.user_id,
+            resource_url: exchange.resource_url,
+            ingress_id: None,
+        })
+        .await;
+
+    Ok(sdp_created(
+        "whep",
+        channel_id,
+        &session_id,
+        exchange.answer,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/whep/{channel_id}/{session_id}",
+    tag = "whip",
+    params(("channel_id" = i64, Path), ("session_id" = String, Path)),
+    responses((status = 200, description = "OK")),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn whep_stop(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((channel_id, session_id)): Path<(i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    let session = state
+        .voice
+        .whip_sessions()
+        .get(&session_id)
+        .await
+        .filter(|session| {
+            session.direction == WhipDirection::Play
+                && session.channel_id == channel_id
+                && session.owner_id == auth.user_id
+        })
+        .ok_or(ApiError::NotFound)?;
+    end_session(&state, &session).await;
+    Ok(StatusCode::OK)
+}
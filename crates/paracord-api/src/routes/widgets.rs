@@ -0,0 +1,233 @@
+//! Guild widget: a public JSON summary of a guild (member and online counts
+//! and an instant invite) for embedding on websites. Off until a guild
+//! manager enables it.
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use paracord_core::AppState;
+use paracord_models::error_code::ErrorCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::middleware::AuthUser;
+use crate::routes::audit;
+use crate::routes::guilds::require_manage_guild;
+
+fn settings_to_json(
+    guild_id: i64,
+    settings: Option<&paracord_db::guild_widgets::WidgetSettingsRow>,
+) -> Value {
+    json!({
+        "guild_id": guild_id.to_string(),
+        "enabled": settings.is_some_and(|s| s.enabled),
+        "channel_id": settings.and_then(|s| s.channel_id).map(|id| id.to_string()),
+        "invite_code": settings.and_then(|s| s.invite_code.clone()),
+    })
+}
+
+/// Link to an invite page, absolute when the public URL is known.
+pub(crate) fn invite_url(state: &AppState, code: &str) -> String {
+    let base = state
+        .config
+        .public_url
+        .as_deref()
+        .map(|url| url.trim_end_matches('/'))
+        .unwrap_or_default();
+    format!("{base}/invite/{code}")
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/widget",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn get_widget_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let settings = paracord_db::guild_widgets::get_settings(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    Ok(Json(settings_to_json(guild_id, settings.as_ref())))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateWidgetRequest {
+    pub enabled: Option<bool>,
+    /// Text channel the widget's invite leads to; empty removes the invite.
+    pub channel_id: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/guilds/{guild_id}/widget",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    request_body = UpdateWidgetRequest,
+    responses((status = 200, description = "OK", body = Value)),
+    security(("bearer" = []), ("bot" = [])),
+)]
+pub async fn update_widget_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<i64>,
+    Json(body): Json<UpdateWidgetRequest>,
+) -> Result<Json<Value>, ApiError> {
+    require_manage_guild(&state, guild_id, auth.user_id).await?;
+    let existing = paracord_db::guild_widgets::get_settings(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let enabled = body
+        .enabled
+        .unwrap_or_else(|| existing.as_ref().is_some_and(|s| s.enabled));
+    let current_channel = existing.as_ref().and_then(|s| s.channel_id);
+    let current_invite = existing.as_ref().and_then(|s| s.invite_code.clone());
+
+    let channel_id = match body.channel_id.as_deref().map(str::trim) {
+        None => current_channel,
+        Some("") => None,
+        Some(raw) => {
+            let channel_id: i64 = raw
+                .parse()
+                .map_err(|_| ApiError::BadRequest("Invalid channel_id".into()))?;
+            let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+                .filter(|channel| channel.guild_id() == Some(guild_id))
+                .ok_or_else(|| ApiError::BadRequest("Channel is not in this guild".into()))?;
+            if channel.channel_type != 0 {
+                return Err(ApiError::BadRequest(
+                    "The widget invite must lead to a text channel".into(),
+                ));
+            }
+            Some(channel_id)
+        }
+    };
+
+    // Keep the invite while it still points at the chosen channel.
+    let mut invite_code = None;
+    if let (Some(channel_id), Some(code)) = (channel_id, current_invite.as_deref()) {
+        let invite = paracord_db::invites::get_invite(&state.db, code)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        if invite.is_some_and(|invite| invite.channel_id == channel_id) {
+            invite_code = Some(code.to_string());
+        }
+    }
+    if invite_code.is_none() {
+        if let Some(code) = current_invite.as_deref() {
+            paracord_db::invites::delete_invite(&state.db, code)
+                .await
+                .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+        }
+        if let Some(channel_id) = channel_id {
+            let code = paracord_core::guild::generate_invite_code(8);
+            let invite = paracord_db::invites::create_invite(
+                &state.db,
+                &code,
+                guild_id,
+                channel_id,
+                auth.user_id,
+                Some(0),
+                Some(0),
+            )
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+            invite_code = Some(invite.code);
+        }
+    }
+
+    let settings = paracord_db::guild_widgets::upsert_settings(
+        &state.db,
+        guild_id,
+        enabled,
+        channel_id,
+        invite_code.as_deref(),
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    audit::log_action(
+        &state,
+        guild_id,
+        auth.user_id,
+        audit::ACTION_GUILD_UPDATE,
+        None,
+        None,
+        Some(json!({
+            "widget_enabled": settings.enabled,
+            "widget_channel_id": settings.channel_id.map(|id| id.to_string()),
+        })),
+    )
+    .await;
+    Ok(Json(settings_to_json(guild_id, Some(&settings))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/guilds/{guild_id}/widget.json",
+    tag = "guilds",
+    params(("guild_id" = i64, Path)),
+    responses(
+        (status = 200, description = "OK", body = Value),
+        (status = 403, description = "The widget is disabled"),
+    ),
+)]
+pub async fn get_widget(
+    State(state): State<AppState>,
+    Path(guild_id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .ok_or(ApiError::Code(ErrorCode::UnknownGuild))?;
+    let settings = paracord_db::guild_widgets::get_settings(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+        .filter(|settings| settings.enabled)
+        .ok_or(ApiError::Code(ErrorCode::WidgetDisabled))?;
+
+    let member_count = paracord_db::members::get_member_count(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let member_ids = paracord_db::members::get_guild_member_user_ids(&state.db, guild_id)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?;
+    let presence_count = {
+        let online_users = state.online_users.read().await;
+        member_ids
+            .iter()
+            .filter(|id| online_users.contains(id))
+            .count()
+    };
+
+    // The invite may have been deleted by hand since the widget was set up.
+    let instant_invite = match settings.invite_code.as_deref() {
+        Some(code) => paracord_db::invites::get_invite(&state.db, code)
+            .await
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e.to_string())))?
+            .map(|invite| invite_url(&state, &invite.code)),
+        None => None,
+    };
+
+    Ok((
+        [(header::CACHE_CONTROL, "public, max-age=60")],
+        Json(json!({
+            "id": guild.id.to_string(),
+            "name": guild.name,
+            "instant_invite": instant_invite,
+            "member_count": member_count,
+            "presence_count": presence_count,
+        })),
+    ))
+}
@@ -0,0 +1,204 @@
+//! Donation platform webhooks that grant supporter status.
+//!
+//! Ko-fi posts a form with a `data` field holding JSON, authenticated by the
+//! account's verification token inside that JSON. Patreon posts JSON signed
+//! with HMAC-MD5 of the body in `X-Patreon-Signature`. Payers are matched to
+//! accounts by email; payments nobody here signed up with are acknowledged
+//! and ignored so the platforms don't retry them.
+
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+pub const PATREON_SIGNATURE_HEADER: &str = "x-patreon-signature";
+pub const PATREON_EVENT_HEADER: &str = "x-patreon-event";
+
+type HmacMd5 = Hmac<Md5>;
+
+static WEBHOOKS: OnceLock<SupporterWebhooks> = OnceLock::new();
+
+/// Credentials for the supported platforms and how long a payment counts.
+#[derive(Debug, Default)]
+pub struct SupporterWebhooks {
+    kofi_token_digest: Option<[u8; 32]>,
+    patreon_secret: Option<String>,
+    grant_days: u32,
+}
+
+/// What a webhook asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupporterUpdate {
+    /// Extend the payer's supporter status by the configured grant.
+    Grant { email: String },
+    /// Remove status the platform granted earlier.
+    Revoke { email: String },
+    /// Nothing to do, e.g. a shop order without an email.
+    Ignore,
+}
+
+fn digest(value: &str) -> [u8; 32] {
+    Sha256::digest(value.as_bytes()).into()
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+impl SupporterWebhooks {
+    pub fn new(
+        kofi_verification_token: Option<&str>,
+        patreon_webhook_secret: Option<&str>,
+        grant_days: u32,
+    ) -> Self {
+        Self {
+            kofi_token_digest: non_empty(kofi_verification_token).map(digest),
+            patreon_secret: non_empty(patreon_webhook_secret).map(str::to_string),
+            grant_days: grant_days.max(1),
+        }
+    }
+
+    pub fn kofi_enabled(&self) -> bool {
+        self.kofi_token_digest.is_some()
+    }
+
+    pub fn patreon_enabled(&self) -> bool {
+        self.patreon_secret.is_some()
+    }
+
+    pub fn grant_days(&self) -> u32 {
+        self.grant_days
+    }
+
+    /// Check a Ko-fi `data` document. `None` when the token doesn't match
+    /// or Ko-fi isn't configured.
+    pub fn verify_kofi(&self, data: &Value) -> Option<SupporterUpdate> {
+        let expected = self.kofi_token_digest?;
+        let token = data.get("verification_token").and_then(Value::as_str)?;
+        if digest(token) != expected {
+            return None;
+        }
+        Some(match non_empty(data.get("email").and_then(Value::as_str)) {
+            Some(email) => SupporterUpdate::Grant {
+                email: email.to_string(),
+            },
+            None => SupporterUpdate::Ignore,
+        })
+    }
+
+    /// Check a Patreon delivery. `None` when the signature doesn't match or
+    /// Patreon isn't configured.
+    pub fn verify_patreon(
+        &self,
+        signature: &str,
+        event: &str,
+        body: &[u8],
+    ) -> Option<SupporterUpdate> {
+        let secret = self.patreon_secret.as_deref()?;
+        let signature = decode_hex(signature.trim())?;
+        let mut mac =
+            HmacMd5::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac.verify_slice(&signature).ok()?;
+
+        let payload: Value = serde_json::from_slice(body).ok()?;
+        let attributes = payload.pointer("/data/attributes");
+        let email = non_empty(
+            attributes
+                .and_then(|attributes| attributes.get("email"))
+                .and_then(Value::as_str),
+        );
+        let Some(email) = email else {
+            return Some(SupporterUpdate::Ignore);
+        };
+        let active = attributes
+            .and_then(|attributes| attributes.get("patron_status"))
+            .and_then(Value::as_str)
+            == Some("active_patron");
+        let email = email.to_string();
+        Some(if event.ends_with(":delete") || !active {
+            SupporterUpdate::Revoke { email }
+        } else {
+            SupporterUpdate::Grant { email }
+        })
+    }
+}
+
+fn decode_hex(raw: &str) -> Option<Vec<u8>> {
+    if !raw.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(raw.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Install the webhook credentials. Only the first call takes effect.
+pub fn install(webhooks: SupporterWebhooks) {
+    let _ = WEBHOOKS.set(webhooks);
+}
+
+pub(crate) fn installed() -> Option<&'static SupporterWebhooks> {
+    WEBHOOKS.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn kofi_requires_the_verification_token() {
+        let webhooks = SupporterWebhooks::new(Some("kofi-token"), None, 31);
+        let data = json!({ "verification_token": "kofi-token", "email": "fan@example.com" });
+        assert_eq!(
+            webhooks.verify_kofi(&data),
+            Some(SupporterUpdate::Grant {
+                email: "fan@example.com".into()
+            })
+        );
+        let forged = json!({ "verification_token": "guess", "email": "fan@example.com" });
+        assert_eq!(webhooks.verify_kofi(&forged), None);
+        let anonymous = json!({ "verification_token": "kofi-token" });
+        assert_eq!(
+            webhooks.verify_kofi(&anonymous),
+            Some(SupporterUpdate::Ignore)
+        );
+        assert!(!webhooks.patreon_enabled());
+    }
+
+    #[test]
+    fn patreon_requires_a_valid_signature() {
+        let webhooks = SupporterWebhooks::new(None, Some("patreon-secret"), 31);
+        let body = json!({
+            "data": { "attributes": { "email": "fan@example.com", "patron_status": "active_patron" } }
+        })
+        .to_string();
+        let mut mac = HmacMd5::new_from_slice(b"patreon-secret").unwrap();
+        mac.update(body.as_bytes());
+        let signature = paracord_federation::hex_encode(&mac.finalize().into_bytes());
+
+        assert_eq!(
+            webhooks.verify_patreon(&signature, "members:pledge:create", body.as_bytes()),
+            Some(SupporterUpdate::Grant {
+                email: "fan@example.com".into()
+            })
+        );
+        assert_eq!(
+            webhooks.verify_patreon(&signature, "members:pledge:delete", body.as_bytes()),
+            Some(SupporterUpdate::Revoke {
+                email: "fan@example.com".into()
+            })
+        );
+        assert_eq!(
+            webhooks.verify_patreon(&signature, "members:update", b"{}"),
+            None
+        );
+        assert_eq!(
+            webhooks.verify_patreon("zz", "members:update", body.as_bytes()),
+            None
+        );
+    }
+}
@@ -72,6 +72,7 @@ impl TestContext {
                 database_url: "sqlite::memory:".to_string(),
                 federation_max_events_per_peer_per_minute: None,
                 federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
                 native_media_enabled: false,
                 native_media_port: 8443,
                 native_media_max_participants: 50,
@@ -80,6 +81,11 @@ impl TestContext {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path: None,
+                min_client_version: None,
+                web_push_public_key: None,
+                link_previews_enabled: false,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -97,6 +103,8 @@ impl TestContext {
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
             native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
             presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
         };
 
@@ -1468,6 +1476,191 @@ async fn create_followup_message_uses_bot_user_id() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn invoke_checks_channel_and_declared_options() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let bot = create_bot_application(&ctx, "OptionsBot").await?;
+    let guild_id = create_guild(&ctx, "OptionsGuild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "chat").await?;
+    let other_guild_id = create_guild(&ctx, "OtherGuild").await?;
+    let other_channel_id = create_text_channel(&ctx, &other_guild_id, "elsewhere").await?;
+    authorize_bot_in_guild(&ctx, &bot.app_id, &guild_id).await?;
+    let (status, payload) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/applications/{}/commands", bot.app_id),
+            Some(json!({
+                "name": "echo",
+                "description": "Echoes text",
+                "options": [{ "name": "text", "description": "What to say", "type": 3, "required": true }],
+            })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "create command failed: {payload}"
+    );
+
+    let invoke = |channel_id: &str, options: Value| {
+        json!({
+            "command_name": "echo",
+            "guild_id": guild_id,
+            "channel_id": channel_id,
+            "options": options,
+        })
+    };
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/interactions",
+            Some(invoke(
+                &other_channel_id,
+                json!([{ "name": "text", "value": "hi" }]),
+            )),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND, "channel outside the guild");
+
+    for options in [json!([]), json!([{ "name": "text", "value": 5 }])] {
+        let (status, payload) = ctx
+            .request_json(
+                Method::POST,
+                "/api/v1/interactions",
+                Some(invoke(&channel_id, options)),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{payload}");
+    }
+
+    let (status, interaction) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/interactions",
+            Some(invoke(
+                &channel_id,
+                json!([{ "name": "text", "value": "hi" }]),
+            )),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "valid invoke failed: {interaction}"
+    );
+    assert_eq!(interaction["data"]["options"][0]["value"], "hi");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn shared_command_names_need_a_command_id() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let first = create_bot_application(&ctx, "FirstBot").await?;
+    let second = create_bot_application(&ctx, "SecondBot").await?;
+    let guild_id = create_guild(&ctx, "SharedGuild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "chat").await?;
+    authorize_bot_in_guild(&ctx, &first.app_id, &guild_id).await?;
+    authorize_bot_in_guild(&ctx, &second.app_id, &guild_id).await?;
+    create_global_command(&ctx, &first.app_id, "status", "First status").await?;
+    let (_, second_cmd_id) =
+        create_global_command(&ctx, &second.app_id, "status", "Second status").await?;
+
+    let (status, payload) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/interactions",
+            Some(
+                json!({ "command_name": "status", "guild_id": guild_id, "channel_id": channel_id }),
+            ),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{payload}");
+
+    let (status, interaction) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/interactions",
+            Some(json!({ "command_id": second_cmd_id, "guild_id": guild_id, "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "{interaction}");
+    assert_eq!(interaction["application_id"], second.app_id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn followup_messages_can_be_fetched_edited_and_deleted() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let bot = create_bot_application(&ctx, "FollowupEditBot").await?;
+    let guild_id = create_guild(&ctx, "FollowupEditGuild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "chat").await?;
+    authorize_bot_in_guild(&ctx, &bot.app_id, &guild_id).await?;
+    create_global_command(&ctx, &bot.app_id, "slow", "Deferred work").await?;
+
+    let (interaction, token) = invoke_slash_command(&ctx, "slow", &guild_id, &channel_id).await?;
+    let interaction_id = interaction["id"].as_str().unwrap();
+    let (status, placeholder) = interaction_callback(&ctx, interaction_id, &token, 5, None).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let base = format!("/api/v1/interactions/{}/{}/messages", bot.app_id, token);
+    let (status, original) = ctx
+        .request_json_no_auth(Method::GET, &format!("{base}/@original"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "{original}");
+    assert_eq!(original["id"], placeholder["id"]);
+
+    let (status, followup) = ctx
+        .request_json_no_auth(
+            Method::POST,
+            &format!("/api/v1/interactions/{}/{}/followup", bot.app_id, token),
+            Some(json!({ "content": "working on it" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let followup_url = format!("{base}/{}", followup["id"].as_str().unwrap());
+
+    let (status, edited) = ctx
+        .request_json_no_auth(
+            Method::PATCH,
+            &followup_url,
+            Some(json!({ "content": "done" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "{edited}");
+    assert_eq!(edited["content"], "done");
+
+    // Messages that aren't this bot's interaction responses are off limits.
+    let (status, user_msg) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "not yours" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "{user_msg}");
+    let (status, _) = ctx
+        .request_json_no_auth(
+            Method::DELETE,
+            &format!("{base}/{}", user_msg["id"].as_str().unwrap()),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = ctx
+        .request_json_no_auth(Method::DELETE, &followup_url, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_no_auth(Method::GET, &followup_url, None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Group 6: Component Interactions
 // ═══════════════════════════════════════════════════════════════════════════
@@ -7,6 +7,8 @@ use axum::{
     http::{header, Method, Request, StatusCode},
     Router,
 };
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use chrono::{Duration, Utc};
 use paracord_core::{build_permission_cache, AppConfig, AppState, RuntimeSettings};
 use paracord_media::{
@@ -20,6 +22,8 @@ use uuid::Uuid;
 
 struct TestContext {
     app: Router,
+    state: AppState,
+    db: paracord_db::DbPool,
     token: String,
     _storage_dir: TempDir,
     _media_dir: TempDir,
@@ -69,6 +73,7 @@ impl TestContext {
                 database_url: "sqlite::memory:".to_string(),
                 federation_max_events_per_peer_per_minute: None,
                 federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
                 native_media_enabled: false,
                 native_media_port: 8443,
                 native_media_max_participants: 50,
@@ -77,6 +82,11 @@ impl TestContext {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path: None,
+                min_client_version: None,
+                web_push_public_key: None,
+                link_previews_enabled: false,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -94,15 +104,19 @@ impl TestContext {
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
             native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
             presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
         };
 
         paracord_api::install_http_rate_limiter();
-        let app = paracord_api::build_router().with_state(state);
+        let app = paracord_api::build_router().with_state(state.clone());
         let token = create_authenticated_user_token(&db, &jwt_secret).await?;
 
         Ok(Self {
             app,
+            state,
+            db,
             token,
             _storage_dir: storage_dir,
             _media_dir: media_dir,
@@ -115,11 +129,21 @@ impl TestContext {
         method: Method,
         path: &str,
         body: Option<Value>,
+    ) -> anyhow::Result<(StatusCode, Value)> {
+        self.request_json_as(&self.token, method, path, body).await
+    }
+
+    async fn request_json_as(
+        &self,
+        token: &str,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
     ) -> anyhow::Result<(StatusCode, Value)> {
         let mut builder = Request::builder()
             .method(method)
             .uri(path)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.token));
+            .header(header::AUTHORIZATION, format!("Bearer {token}"));
 
         let request = if let Some(payload) = body {
             builder = builder.header(header::CONTENT_TYPE, "application/json");
@@ -300,10 +324,12 @@ async fn channel_crud_routes_work() -> anyhow::Result<()> {
         .await?;
     assert_eq!(status, StatusCode::NO_CONTENT);
 
-    let (status, _) = ctx
+    let (status, body) = ctx
         .request_json(Method::GET, &format!("/api/v1/channels/{channel_id}"), None)
         .await?;
     assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "UNKNOWN_CHANNEL");
+    assert_eq!(body["error_code"], 10003);
 
     Ok(())
 }
@@ -379,86 +405,4905 @@ async fn message_crud_routes_work() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn search_ids(
+    ctx: &TestContext,
+    channel_id: &str,
+    query: &str,
+) -> anyhow::Result<(StatusCode, Vec<String>)> {
+    let (status, body) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages/search?{query}"),
+            None,
+        )
+        .await?;
+    let found = body
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|m| m["id"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok((status, found))
+}
+
 #[tokio::test]
-async fn thread_routes_work() -> anyhow::Result<()> {
+async fn message_search_applies_filters_across_channels() -> anyhow::Result<()> {
     let ctx = TestContext::new().await?;
-    let guild_id = create_guild(&ctx, "Thread Routes Guild").await?;
-    let channel_id = create_text_channel(&ctx, &guild_id, "thread-parent").await?;
+    let guild_id = create_guild(&ctx, "Search Guild").await?;
+    let first = create_text_channel(&ctx, &guild_id, "ops").await?;
+    let second = create_text_channel(&ctx, &guild_id, "release").await?;
 
-    let (status, created_thread) = ctx
+    let mut ids = Vec::new();
+    for (channel_id, content) in [
+        (&first, "deploy started"),
+        (&second, "deploy finished"),
+        (&first, "lunch plans"),
+    ] {
+        let (status, created) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/messages"),
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        ids.push(created["id"].as_str().context("message id")?.to_string());
+    }
+    assert_eq!(
+        search_ids(&ctx, &first, "q=deploy").await?,
+        (StatusCode::OK, vec![ids[0].clone()])
+    );
+    let (status, found) =
+        search_ids(&ctx, &first, &format!("q=deploy+in:{second}&sort=oldest")).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found, vec![ids[0].clone(), ids[1].clone()]);
+    // Filters alone are a valid search.
+    let (status, found) = search_ids(&ctx, &first, &format!("after={}&has=none", ids[0])).await?;
+    assert_eq!((status, found), (StatusCode::OK, vec![ids[2].clone()]));
+
+    // Edits are searchable straight away.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{first}/messages/{}", ids[2]),
+            Some(json!({ "content": "lunch after the deploy" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, found) = search_ids(&ctx, &first, "q=deploy&sort=newest").await?;
+    assert_eq!(found, vec![ids[2].clone(), ids[0].clone()]);
+
+    assert_eq!(
+        search_ids(&ctx, &first, "q=%20").await?.0,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        search_ids(&ctx, &first, "q=deploy+has:link").await?.0,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_icons_are_resized_and_served_as_webp_when_accepted() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Icon Guild").await?;
+    let member = create_authenticated_user_token(&ctx.db, "integration-test-secret").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
         .request_json(
             Method::POST,
-            &format!("/api/v1/channels/{channel_id}/threads"),
-            Some(json!({
-                "name": "first-thread",
-                "auto_archive_duration": 1440
-            })),
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let join = Request::builder()
+        .method(Method::POST)
+        .uri(format!(
+            "/api/v1/invites/{}",
+            invite["code"].as_str().context("code")?
+        ))
+        .header(header::AUTHORIZATION, format!("Bearer {member}"))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(join).await?;
+    assert!(response.status().is_success());
+
+    let image = image::RgbImage::from_fn(64, 48, |x, y| {
+        let v = ((x * 4) ^ (y * 2)) as u8;
+        image::Rgb([v, 255 - v, v / 2])
+    });
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+    let png = png.into_inner();
+
+    let upload = |token: String| {
+        let boundary = "paracord-test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"icon\"; filename=\"icon.png\"\r\nContent-Type: image/png\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(&png);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        let request = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/v1/guilds/{guild_id}/icon"))
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body));
+        let app = ctx.app.clone();
+        async move {
+            let response = app.oneshot(request?).await?;
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+            anyhow::Ok((
+                status,
+                serde_json::from_slice::<Value>(&bytes).unwrap_or(Value::Null),
+            ))
+        }
+    };
+    let (status, _) = upload(member).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, guild) = upload(ctx.token.clone()).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {guild}");
+    let icon_hash = guild["icon_hash"].as_str().context("icon hash")?;
+    assert_eq!(icon_hash.len(), 32);
+
+    let fetch = |query: &'static str, accept: &'static str| {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/guilds/{guild_id}/icon{query}"))
+            .header(header::ACCEPT, accept)
+            .body(Body::empty());
+        let app = ctx.app.clone();
+        async move {
+            let response = app.oneshot(request?).await?;
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+            anyhow::Ok((status, content_type, image::load_from_memory(&bytes)?))
+        }
+    };
+    let (status, content_type, icon) = fetch("?size=48", "image/webp,image/*").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "image/webp");
+    assert_eq!((icon.width(), icon.height()), (64, 64));
+    let (status, content_type, icon) = fetch("", "image/png").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "image/png");
+    assert_eq!((icon.width(), icon.height()), (256, 256));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transcoded_renditions_are_negotiated_between_mp4_and_webm() -> anyhow::Result<()> {
+    use paracord_media::transcode::{transcoded_storage_key, TranscodeFormat, TRANSCODE_DONE};
+
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Video Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "clips").await?;
+    let boundary = "paracord-test-boundary";
+    let upload = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/api/v1/channels/{channel_id}/attachments"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
         )
+        .body(Body::from(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"clip.txt\"\r\nContent-Type: text/plain\r\n\r\nnot really a video\r\n--{boundary}--\r\n"
+        )))?;
+    let response = ctx.app.clone().oneshot(upload).await?;
+    let status = response.status();
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {body}");
+    let attachment_id: i64 = body["id"].as_str().context("attachment id")?.parse()?;
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "clip", "attachment_ids": [attachment_id.to_string()] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    // Stand in for the ffmpeg worker: one marker payload per rendition.
+    for format in TranscodeFormat::ALL {
+        ctx.state
+            .storage_backend
+            .store(
+                &transcoded_storage_key(attachment_id, format),
+                format.extension().as_bytes(),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+    paracord_db::attachments::set_transcode_status(&ctx.db, attachment_id, TRANSCODE_DONE, Some(7))
         .await?;
+
+    let fetch = |query: &'static str, accept: Option<&'static str>| {
+        let mut request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/api/v1/attachments/{attachment_id}/transcoded{query}"
+            ))
+            .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token));
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let app = ctx.app.clone();
+        async move {
+            let response = app.oneshot(request.body(Body::empty())?).await?;
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+            anyhow::Ok((status, content_type, bytes.to_vec()))
+        }
+    };
+    let (status, content_type, body) = fetch("", None).await?;
+    assert_eq!(status, StatusCode::OK);
     assert_eq!(
-        status,
-        StatusCode::CREATED,
-        "unexpected thread payload: {created_thread}"
+        (content_type.as_str(), body.as_slice()),
+        ("video/mp4", &b"mp4"[..])
     );
-    let thread_id = created_thread["id"]
-        .as_str()
-        .context("thread id should be a string")?
-        .to_string();
-    assert_eq!(created_thread["parent_id"], channel_id);
-    assert!(created_thread["owner_id"].is_string());
+    let (_, content_type, body) = fetch("", Some("video/webm")).await?;
+    assert_eq!(
+        (content_type.as_str(), body.as_slice()),
+        ("video/webm", &b"webm"[..])
+    );
+    let (_, content_type, _) = fetch("", Some("video/webm, video/mp4")).await?;
+    assert_eq!(content_type, "video/mp4");
+    let (_, content_type, _) = fetch("?format=webm", Some("video/mp4")).await?;
+    assert_eq!(content_type, "video/webm");
+    let (status, _, _) = fetch("?format=avi", None).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
 
-    let (status, threads) = ctx
+    Ok(())
+}
+
+#[tokio::test]
+async fn voice_recordings_need_record_permission_and_surface_egress_failures() -> anyhow::Result<()>
+{
+    let mut ctx = TestContext::new().await?;
+    ctx.state.config.livekit_available = true;
+    ctx.app = paracord_api::build_router().with_state(ctx.state.clone());
+    let ctx = &ctx;
+    let guild_id = create_guild(ctx, "Recording Guild").await?;
+    let (status, voice) = ctx
         .request_json(
-            Method::GET,
-            &format!("/api/v1/channels/{channel_id}/threads"),
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            Some(json!({ "name": "stage", "channel_type": 2 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {voice}");
+    let channel_id = voice["id"].as_str().context("channel id")?.to_string();
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &format!(
+                "/api/v1/invites/{}",
+                invite["code"].as_str().context("code")?
+            ),
             None,
         )
         .await?;
     assert_eq!(status, StatusCode::OK);
-    let active_threads = threads
-        .as_array()
-        .context("threads response should be an array")?;
-    assert!(active_threads
-        .iter()
-        .any(|thread| thread.get("id").and_then(Value::as_str) == Some(thread_id.as_str())));
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let owner_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let mut events =
+        ctx.state
+            .event_bus
+            .register_session("recordings", owner_id, &[guild_id.parse()?]);
 
-    let (status, archived) = ctx
-        .request_json(
-            Method::PATCH,
-            &format!("/api/v1/channels/{channel_id}/threads/{thread_id}"),
-            Some(json!({ "archived": true })),
+    // Starting needs RECORD_VOICE; with it the request gets as far as
+    // looking for the LiveKit room.
+    let recordings_path = format!("/api/v1/voice/{channel_id}/recordings");
+    let restream = json!({ "kind": "rtmp", "rtmp_urls": ["rtmp://live.example.com/app/key"] });
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &recordings_path,
+            Some(restream.clone()),
         )
         .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = ctx
+        .request_json(Method::POST, &recordings_path, Some(restream))
+        .await?;
     assert_eq!(
         status,
-        StatusCode::OK,
-        "unexpected archived payload: {archived}"
+        StatusCode::BAD_REQUEST,
+        "unexpected payload: {body}"
     );
-    assert_eq!(archived["id"], thread_id);
+    assert!(body["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("No one is connected")));
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &recordings_path,
+            Some(json!({ "kind": "rtmp", "rtmp_urls": ["https://example.com/live"] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
 
-    let (status, archived_threads) = ctx
+    // Stopping needs RECORD_VOICE too; listing only needs to see the channel.
+    let recording = paracord_db::voice_recordings::create_recording(
+        &ctx.db,
+        paracord_util::snowflake::generate(1),
+        guild_id.parse()?,
+        channel_id.parse()?,
+        "EG_failing",
+        "rtmp",
+        owner_id,
+    )
+    .await?;
+    let stop_path = format!("{recordings_path}/{}/stop", recording.id);
+    let (status, _) = ctx
+        .request_json_as(&member, Method::POST, &stop_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, listed) = ctx
+        .request_json_as(&member, Method::GET, &recordings_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed[0]["status"], "starting");
+
+    // A failed egress ends the recording as failed, with LiveKit's error.
+    let webhook = json!({
+        "event": "egress_ended",
+        "egressInfo": {
+            "egressId": "EG_failing",
+            "status": "EGRESS_FAILED",
+            "error": "rtmp endpoint refused the stream",
+        },
+    });
+    let mut hasher = <sha2::Sha256 as sha2::Digest>::new();
+    sha2::Digest::update(&mut hasher, webhook.to_string().as_bytes());
+    let body_hash: String = sha2::Digest::finalize(hasher)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let webhook_token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+        &json!({
+            "iss": "lk-test-key",
+            "exp": (Utc::now() + Duration::minutes(5)).timestamp(),
+            "sha256": body_hash,
+        }),
+        &jsonwebtoken::EncodingKey::from_secret(b"lk-test-secret"),
+    )?;
+    let (status, _) = ctx
+        .request_json_as(
+            &webhook_token,
+            Method::POST,
+            "/api/v1/voice/livekit/webhook",
+            Some(webhook),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let ran = paracord_core::jobs::run_pending_jobs_once(
+        &ctx.state,
+        &paracord_api::jobs::job_registry(),
+        "test",
+        10,
+    )
+    .await;
+    assert_eq!(ran, 1);
+    let stops: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| event.event_type == "VOICE_RECORDING_STOP")
+        .collect();
+    assert_eq!(stops.len(), 1);
+    assert_eq!(stops[0].payload["status"], "failed");
+    let (_, listed) = ctx
+        .request_json(Method::GET, &recordings_path, None)
+        .await?;
+    assert_eq!(listed[0]["status"], "failed");
+    assert_eq!(listed[0]["error"], "rtmp endpoint refused the stream");
+    let (status, _) = ctx.request_json(Method::POST, &stop_path, None).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "already ended");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn voice_event_past_its_start_goes_active_once_when_the_host_joins() -> anyhow::Result<()> {
+    let mut ctx = TestContext::new().await?;
+    ctx.state.config.native_media_enabled = true;
+    ctx.app = paracord_api::build_router().with_state(ctx.state.clone());
+    let guild_id = create_guild(&ctx, "Event Guild").await?;
+    let (status, voice) = ctx
         .request_json(
-            Method::GET,
-            &format!("/api/v1/channels/{channel_id}/threads/archived"),
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            Some(json!({ "name": "town-hall", "channel_type": 2 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {voice}");
+    let channel_id = voice["id"].as_str().context("channel id")?.to_string();
+    let started_at = (Utc::now() - Duration::minutes(30)).to_rfc3339();
+    let (status, event) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/events"),
+            Some(json!({
+                "name": "Town hall",
+                "scheduled_start": started_at,
+                "entity_type": 1,
+                "channel_id": channel_id,
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {event}");
+    assert_eq!(event["status"], 1);
+    let event_path = format!(
+        "/api/v1/guilds/{guild_id}/events/{}",
+        event["id"].as_str().context("event id")?
+    );
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let owner_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let mut events = ctx
+        .state
+        .event_bus
+        .register_session("events", owner_id, &[guild_id.parse()?]);
+
+    // The start runs in a task spawned by the join, so join twice and wait
+    // for each to settle; only the first may flip the event.
+    for _ in 0..2 {
+        let (status, joined) = ctx
+            .request_json(
+                Method::GET,
+                &format!("/api/v1/voice/{channel_id}/join"),
+                None,
+            )
+            .await?;
+        assert_eq!(status, StatusCode::OK, "unexpected payload: {joined}");
+        let mut current = Value::Null;
+        for _ in 0..50 {
+            (_, current) = ctx.request_json(Method::GET, &event_path, None).await?;
+            if current["status"] == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(current["status"], 2, "event did not start: {current}");
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let updates: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| event.event_type == "GUILD_SCHEDULED_EVENT_UPDATE")
+        .collect();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].payload["id"], event["id"]);
+    assert_eq!(updates[0].payload["status"], 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn user_notes_are_private_and_member_notes_need_moderation_rights() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Notes Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &format!(
+                "/api/v1/invites/{}",
+                invite["code"].as_str().context("code")?
+            ),
             None,
         )
         .await?;
     assert_eq!(status, StatusCode::OK);
-    let archived_list = archived_threads
-        .as_array()
-        .context("archived threads response should be an array")?;
-    assert!(archived_list
-        .iter()
-        .any(|thread| thread.get("id").and_then(Value::as_str) == Some(thread_id.as_str())));
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let owner_id = me["id"].as_str().context("user id")?.to_string();
+    let (_, them) = ctx
+        .request_json_as(&member, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let member_id = them["id"].as_str().context("user id")?.to_string();
 
+    // Private notes: set, read back, replace, and clear.
+    let note_path = format!("/api/v1/users/@me/notes/{member_id}");
     let (status, _) = ctx
         .request_json(
-            Method::DELETE,
-            &format!("/api/v1/channels/{channel_id}/threads/{thread_id}"),
+            Method::PUT,
+            &note_path,
+            Some(json!({ "note": "met at conf" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, note) = ctx.request_json(Method::GET, &note_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(note["note"], "met at conf");
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &note_path,
+            Some(json!({ "note": "plays bass" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, notes) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/notes", None)
+        .await?;
+    assert_eq!(notes.as_array().map(Vec::len), Some(1));
+    assert_eq!(notes[0]["user_id"], member_id.as_str());
+    assert_eq!(notes[0]["note"], "plays bass");
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/users/@me/notes/{owner_id}"),
+            Some(json!({ "note": "me" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &note_path,
+            Some(json!({ "note": "x".repeat(257) })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only the author sees them, even the user the note is about.
+    let (_, theirs) = ctx
+        .request_json_as(&member, Method::GET, "/api/v1/users/@me/notes", None)
+        .await?;
+    assert_eq!(theirs.as_array().map(Vec::len), Some(0));
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::GET,
+            &format!("/api/v1/users/@me/notes/{owner_id}"),
             None,
         )
         .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = ctx
+        .request_json(Method::PUT, &note_path, Some(json!({ "note": "" })))
+        .await?;
     assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx.request_json(Method::GET, &note_path, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Moderation notes: staff only.
+    let mod_notes = format!("/api/v1/guilds/{guild_id}/members/{member_id}/notes");
+    let (status, created) = ctx
+        .request_json(
+            Method::POST,
+            &mod_notes,
+            Some(json!({ "content": "warned for spam" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {created}");
+    assert_eq!(created["author_id"], owner_id.as_str());
+    let note_id = created["id"].as_str().context("note id")?.to_string();
+    let (status, _) = ctx
+        .request_json(Method::POST, &mod_notes, Some(json!({ "content": "  " })))
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let outsider = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (_, outsider) = ctx
+        .request_json_as(&outsider, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!(
+                "/api/v1/guilds/{guild_id}/members/{}/notes",
+                outsider["id"].as_str().context("user id")?
+            ),
+            Some(json!({ "content": "never joined" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let own_notes = format!("/api/v1/guilds/{guild_id}/members/{owner_id}/notes");
+    let (status, _) = ctx
+        .request_json_as(&member, Method::GET, &mod_notes, None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &own_notes,
+            Some(json!({ "content": "payback" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::DELETE,
+            &format!("{mod_notes}/{note_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Kick rights are enough to read them.
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({ "name": "mods", "permissions": 1 << 1 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}/members/{member_id}"),
+            Some(json!({ "roles": [role["id"]] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, listed) = ctx
+        .request_json_as(&member, Method::GET, &mod_notes, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {listed}");
+    assert_eq!(listed[0]["content"], "warned for spam");
 
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &format!("{mod_notes}/{note_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, listed) = ctx.request_json(Method::GET, &mod_notes, None).await?;
+    assert_eq!(listed.as_array().map(Vec::len), Some(0));
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &format!("{mod_notes}/{note_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn blocked_users_cannot_befriend_react_to_or_reply_to_the_blocker() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Block Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let blocked = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &blocked,
+            Method::POST,
+            &format!(
+                "/api/v1/invites/{}",
+                invite["code"].as_str().context("code")?
+            ),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let owner_id = me["id"].as_str().context("user id")?.to_string();
+    let (_, them) = ctx
+        .request_json_as(&blocked, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let blocked_id = them["id"].as_str().context("user id")?.to_string();
+
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "hello all" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let message_id = message["id"].as_str().context("message id")?.to_string();
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/relationships",
+            Some(json!({ "user_id": blocked_id, "type": 2 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // The friend request looks accepted to the sender but is dropped.
+    let (status, _) = ctx
+        .request_json_as(
+            &blocked,
+            Method::POST,
+            "/api/v1/users/@me/relationships",
+            Some(json!({ "user_id": owner_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, relationships) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/relationships", None)
+        .await?;
+    let relationships = relationships.as_array().context("relationships")?;
+    assert_eq!(relationships.len(), 1);
+    assert_eq!(relationships[0]["type"], 2);
+    assert!(paracord_db::relationships::get_relationship(
+        &ctx.db,
+        blocked_id.parse()?,
+        owner_id.parse()?
+    )
+    .await?
+    .is_none());
+
+    let (status, _) = ctx
+        .request_json_as(
+            &blocked,
+            Method::PUT,
+            &format!("{messages_path}/{message_id}/reactions/%F0%9F%91%8D/@me"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = ctx
+        .request_json_as(
+            &blocked,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "replying", "referenced_message_id": message_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // Messages that aren't replies still go through.
+    let (status, _) = ctx
+        .request_json_as(
+            &blocked,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "not a reply" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The blocker can still react to their own message.
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("{messages_path}/{message_id}/reactions/%F0%9F%91%8D/@me"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dm_requests_gate_delivery_until_accepted_and_stop_it_once_declined() -> anyhow::Result<()>
+{
+    let mut ctx = TestContext::new().await?;
+    ctx.state.config.web_push_public_key = Some("test-vapid-key".to_string());
+    ctx.app = paracord_api::build_router().with_state(ctx.state.clone());
+    let ctx = &ctx;
+    let guild_id = create_guild(ctx, "Request Guild").await?;
+    let channel_id = create_text_channel(ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let code = invite["code"].as_str().context("code")?;
+    let mut p256dh = vec![0x04_u8];
+    p256dh.extend([9_u8; 64]);
+    let mut strangers = Vec::new();
+    for endpoint in ["accepts", "declines"] {
+        let token = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+        let (status, _) = ctx
+            .request_json_as(
+                &token,
+                Method::POST,
+                &format!("/api/v1/invites/{code}"),
+                None,
+            )
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = ctx
+            .request_json_as(
+                &token,
+                Method::POST,
+                "/api/v1/users/@me/push-subscriptions",
+                Some(json!({
+                    "endpoint": format!("https://push.example.com/send/{endpoint}"),
+                    "keys": {
+                        "p256dh": URL_SAFE_NO_PAD.encode(&p256dh),
+                        "auth": URL_SAFE_NO_PAD.encode([1_u8; 16]),
+                    },
+                })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, me) = ctx
+            .request_json_as(&token, Method::GET, "/api/v1/users/@me", None)
+            .await?;
+        let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+        strangers.push((token, user_id));
+    }
+    let claim_pushes = || async {
+        let now = Utc::now();
+        let due = paracord_db::push_subscriptions::claim_due_deliveries(
+            &ctx.db,
+            now + Duration::seconds(5),
+            now + Duration::minutes(2),
+            10,
+        )
+        .await?;
+        anyhow::Ok(due.len())
+    };
+
+    let mut dm_channels = Vec::new();
+    let mut sessions = Vec::new();
+    for (_, user_id) in &strangers {
+        let (status, dm) = ctx
+            .request_json(
+                Method::POST,
+                "/api/v1/users/@me/dms",
+                Some(json!({ "recipient_id": user_id.to_string() })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED, "unexpected payload: {dm}");
+        assert_eq!(dm["is_message_request"], false);
+        dm_channels.push(dm["id"].as_str().context("dm id")?.to_string());
+        sessions.push(
+            ctx.state
+                .event_bus
+                .register_session(format!("dm-{user_id}"), *user_id, &[]),
+        );
+    }
+    let send = |dm_id: &str, content: &str| {
+        let path = format!("/api/v1/channels/{dm_id}/messages");
+        // Plaintext DMs are refused, so send the content as a v1 payload.
+        let body = json!({
+            "content": "",
+            "e2ee": {
+                "version": 1,
+                "nonce": Uuid::new_v4().simple().to_string(),
+                "ciphertext": URL_SAFE_NO_PAD.encode(content),
+            },
+        });
+        async move {
+            let (status, sent) = ctx.request_json(Method::POST, &path, Some(body)).await?;
+            assert_eq!(status, StatusCode::CREATED, "unexpected payload: {sent}");
+            anyhow::Ok(())
+        }
+    };
+
+    // Pending: the message is listed as a request, dispatched flagged, and
+    // not pushed.
+    for dm_id in &dm_channels {
+        send(dm_id, "hello stranger").await?;
+    }
+    for ((token, _), (dm_id, events)) in strangers
+        .iter()
+        .zip(dm_channels.iter().zip(sessions.iter_mut()))
+    {
+        let (status, requests) = ctx
+            .request_json_as(token, Method::GET, "/api/v1/users/@me/dms/requests", None)
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(requests[0]["id"], dm_id.as_str());
+        assert_eq!(requests[0]["is_message_request"], true);
+        let (_, dms) = ctx
+            .request_json_as(token, Method::GET, "/api/v1/users/@me/dms", None)
+            .await?;
+        assert_eq!(dms.as_array().map(Vec::len), Some(0));
+        let event = events.try_recv()?;
+        assert_eq!(event.event_type, "MESSAGE_CREATE");
+        assert_eq!(event.payload["message_request"], true);
+    }
+    assert_eq!(claim_pushes().await?, 0);
+
+    // Accepted: the DM moves to the regular list and is delivered normally.
+    let (accepter, declines) = (&strangers[0].0, &strangers[1].0);
+    let accept_path = format!("/api/v1/users/@me/dms/requests/{}/accept", dm_channels[0]);
+    let (status, _) = ctx
+        .request_json_as(accepter, Method::POST, &accept_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let event = sessions[0].try_recv()?;
+    assert_eq!(event.event_type, "DM_REQUEST_UPDATE");
+    assert_eq!(event.payload["accepted"], true);
+    let (status, _) = ctx
+        .request_json_as(accepter, Method::POST, &accept_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, requests) = ctx
+        .request_json_as(
+            accepter,
+            Method::GET,
+            "/api/v1/users/@me/dms/requests",
+            None,
+        )
+        .await?;
+    assert_eq!(requests.as_array().map(Vec::len), Some(0));
+    let (_, dms) = ctx
+        .request_json_as(accepter, Method::GET, "/api/v1/users/@me/dms", None)
+        .await?;
+    assert_eq!(dms[0]["id"], dm_channels[0].as_str());
+    assert_eq!(dms[0]["is_message_request"], false);
+    send(&dm_channels[0], "glad you accepted").await?;
+    let event = sessions[0].try_recv()?;
+    assert_eq!(event.event_type, "MESSAGE_CREATE");
+    assert!(event.payload.get("message_request").is_none());
+    assert_eq!(claim_pushes().await?, 1);
+
+    // Declined: the DM is hidden and new messages reach nobody.
+    let (status, _) = ctx
+        .request_json_as(
+            declines,
+            Method::POST,
+            &format!("/api/v1/users/@me/dms/requests/{}/decline", dm_channels[1]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let event = sessions[1].try_recv()?;
+    assert_eq!(event.event_type, "DM_REQUEST_UPDATE");
+    assert_eq!(event.payload["accepted"], false);
+    for path in ["/api/v1/users/@me/dms/requests", "/api/v1/users/@me/dms"] {
+        let (_, listed) = ctx
+            .request_json_as(declines, Method::GET, path, None)
+            .await?;
+        assert_eq!(listed.as_array().map(Vec::len), Some(0), "{path}");
+    }
+    send(&dm_channels[1], "are you there?").await?;
+    assert!(sessions[1].try_recv().is_err());
+    assert_eq!(claim_pushes().await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn group_dms_cap_participants_and_keep_management_to_the_owner() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let owner_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let mut friends = Vec::new();
+    for _ in 0..10 {
+        let token = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+        let (_, user) = ctx
+            .request_json_as(&token, Method::GET, "/api/v1/users/@me", None)
+            .await?;
+        let user_id: i64 = user["id"].as_str().context("user id")?.parse()?;
+        paracord_db::relationships::create_relationship(&ctx.db, owner_id, user_id, 1).await?;
+        paracord_db::relationships::create_relationship(&ctx.db, user_id, owner_id, 1).await?;
+        friends.push((token, user_id));
+    }
+    let stranger = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (_, stranger) = ctx
+        .request_json_as(&stranger, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let stranger_id = stranger["id"].as_str().context("user id")?.to_string();
+    let mut first_events = ctx
+        .state
+        .event_bus
+        .register_session("group-first", friends[0].1, &[]);
+    let mut second_events = ctx
+        .state
+        .event_bus
+        .register_session("group-second", friends[1].1, &[]);
+
+    let create = |ids: Vec<String>| {
+        let ctx = &ctx;
+        async move {
+            ctx.request_json(
+                Method::POST,
+                "/api/v1/users/@me/dms",
+                Some(json!({ "recipient_ids": ids, "name": "crew" })),
+            )
+            .await
+        }
+    };
+    let (status, _) = create(vec![stranger_id.clone()]).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN, "only friends can be added");
+    let (status, _) = create(friends.iter().map(|(_, id)| id.to_string()).collect()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "eleven participants");
+    let (status, group) = create(vec![friends[0].1.to_string(), friends[1].1.to_string()]).await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {group}");
+    assert_eq!(group["type"], 3);
+    assert_eq!(group["name"], "crew");
+    assert_eq!(group["owner_id"], owner_id.to_string());
+    assert_eq!(group["recipients"].as_array().map(Vec::len), Some(3));
+    let group_id = group["id"].as_str().context("group id")?.to_string();
+    let event = first_events.try_recv()?;
+    assert_eq!(event.event_type, "GROUP_DM_CREATE");
+    assert_eq!(event.payload["id"], group_id.as_str());
+
+    // Members other than the owner can't remove others or hand over the
+    // group, and can only add their own friends.
+    let (first, first_id) = (&friends[0].0, friends[0].1);
+    let second_id = friends[1].1;
+    let recipient_path =
+        |user_id: i64| format!("/api/v1/users/@me/dms/{group_id}/recipients/{user_id}");
+    let (status, _) = ctx
+        .request_json_as(first, Method::DELETE, &recipient_path(second_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(
+            first,
+            Method::POST,
+            &format!("/api/v1/users/@me/dms/{group_id}/owner"),
+            Some(json!({ "owner_id": first_id.to_string() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(first, Method::PUT, &recipient_path(friends[2].1), None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The owner fills the group up to the cap.
+    for (_, friend_id) in &friends[2..9] {
+        let (status, _) = ctx
+            .request_json(Method::PUT, &recipient_path(*friend_id), None)
+            .await?;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+    let (status, full) = ctx
+        .request_json(Method::PUT, &recipient_path(friends[9].1), None)
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::BAD_REQUEST,
+        "unexpected payload: {full}"
+    );
+    while let Ok(event) = first_events.try_recv() {
+        assert_eq!(event.event_type, "GROUP_DM_RECIPIENT_ADD");
+    }
+
+    // The owner removes a member, who is told the group is gone for them.
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &recipient_path(second_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let removed = std::iter::from_fn(|| second_events.try_recv().ok())
+        .find(|event| event.event_type == "GROUP_DM_DELETE")
+        .context("GROUP_DM_DELETE for the removed member")?;
+    assert_eq!(removed.payload["id"], group_id.as_str());
+    let event = first_events.try_recv()?;
+    assert_eq!(event.event_type, "GROUP_DM_RECIPIENT_REMOVE");
+    assert_eq!(event.payload["user_id"], second_id.to_string());
+    let (status, _) = ctx
+        .request_json_as(
+            &friends[1].0,
+            Method::PATCH,
+            &format!("/api/v1/users/@me/dms/{group_id}"),
+            Some(json!({ "name": "still here?" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // After a transfer the previous owner loses the owner-only actions.
+    let (status, updated) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/users/@me/dms/{group_id}/owner"),
+            Some(json!({ "owner_id": first_id.to_string() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {updated}");
+    assert_eq!(updated["owner_id"], first_id.to_string());
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &recipient_path(friends[2].1), None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(first, Method::DELETE, &recipient_path(friends[2].1), None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recurring_jobs_are_queued_again_after_every_run() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut registry = paracord_core::jobs::JobRegistry::default();
+    let counter = runs.clone();
+    registry.register_recurring("sweep", Duration::minutes(5), move |_, _| {
+        let counter = counter.clone();
+        async move {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Err("first sweep failed".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    });
+
+    // Scheduling again (as every node's reaper does) keeps the single row.
+    paracord_core::jobs::schedule_recurring_jobs(&ctx.db, &registry).await;
+    paracord_core::jobs::schedule_recurring_jobs(&ctx.db, &registry).await;
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let jobs = paracord_db::jobs::list_jobs(&ctx.db, None, Some("sweep"), None, 10).await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].status, paracord_db::jobs::JOB_STATUS_PENDING);
+    assert_eq!(jobs[0].last_error.as_deref(), Some("first sweep failed"));
+    assert!(jobs[0].run_at > Utc::now() + Duration::minutes(4));
+
+    // Not due again until the interval passes.
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 0);
+    sqlx::query("UPDATE background_jobs SET run_at = '2020-01-01 00:00:00' WHERE kind = 'sweep'")
+        .execute(&ctx.db)
+        .await?;
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let job = paracord_db::jobs::get_job(&ctx.db, jobs[0].id)
+        .await?
+        .context("recurring job")?;
+    assert_eq!(job.status, paracord_db::jobs::JOB_STATUS_PENDING);
+    assert!(job.last_error.is_none());
+    assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_delete_restore_and_purge_cover_threads_but_not_category_children(
+) -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+    let guild_id = create_guild(&ctx, "Cascade Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "parent").await?;
+    let (status, thread) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/threads"),
+            Some(json!({ "name": "side-talk", "auto_archive_duration": 1440 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {thread}");
+    let thread_id = thread["id"].as_str().context("thread id")?.to_string();
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+    let (status, category) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "topics", "channel_type": 4 })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "unexpected payload: {category}"
+    );
+    let category_id = category["id"].as_str().context("category id")?.to_string();
+    let (status, filed) = ctx
+        .request_json(
+            Method::POST,
+            &channels_path,
+            Some(json!({ "name": "filed", "channel_type": 0, "parent_id": category_id.parse::<i64>()? })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {filed}");
+    let filed_id = filed["id"].as_str().context("channel id")?.to_string();
+    let mut events = ctx
+        .state
+        .event_bus
+        .register_session("cascade", user_id, &[guild_id.parse()?]);
+
+    // Deleting the channel takes its thread with it, one event each.
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/channels/{channel_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "THREAD_DELETE");
+    assert_eq!(event.payload["id"], thread_id.as_str());
+    assert_eq!(event.payload["parent_id"], channel_id.as_str());
+    assert!(event.payload["restorable_until"].is_string());
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "CHANNEL_DELETE");
+    assert_eq!(event.payload["id"], channel_id.as_str());
+    assert!(event.payload["deleted_at"].is_string());
+    assert!(events.try_recv().is_err());
+
+    // Deleting a category leaves the channels filed under it alone.
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/channels/{category_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "CHANNEL_DELETE");
+    assert_eq!(event.payload["id"], category_id.as_str());
+    assert!(events.try_recv().is_err());
+    let (status, _) = ctx
+        .request_json(Method::GET, &format!("/api/v1/channels/{filed_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, deleted) = ctx
+        .request_json(Method::GET, "/api/v1/admin/deleted", None)
+        .await?;
+    let deleted_ids: Vec<&str> = deleted
+        .as_array()
+        .context("tombstones")?
+        .iter()
+        .filter_map(|tombstone| tombstone["id"].as_str())
+        .collect();
+    assert_eq!(deleted_ids.len(), 2, "unexpected tombstones: {deleted}");
+    assert!(deleted_ids.contains(&channel_id.as_str()));
+    assert!(deleted_ids.contains(&category_id.as_str()));
+
+    // Restoring the channel brings the thread back, again one event each.
+    let (status, restored) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/admin/deleted/channel/{channel_id}/restore"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {restored}");
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "CHANNEL_CREATE");
+    assert_eq!(event.payload["id"], channel_id.as_str());
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "THREAD_CREATE");
+    assert_eq!(event.payload["id"], thread_id.as_str());
+    assert_eq!(event.payload["parent_id"], channel_id.as_str());
+    assert!(events.try_recv().is_err());
+
+    // The purge job removes the category for good and moves the channel
+    // filed under it out of it. Purged rows are already gone for clients,
+    // so nothing is dispatched.
+    let registry = paracord_api::jobs::job_registry();
+    paracord_core::jobs::schedule_recurring_jobs(&ctx.db, &registry).await;
+    sqlx::query("UPDATE channels SET deleted_at = '2020-01-01 00:00:00' WHERE id = $1")
+        .bind(category_id.parse::<i64>()?)
+        .execute(&ctx.db)
+        .await?;
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    assert!(events.try_recv().is_err());
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/admin/deleted/channel/{category_id}/restore"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, filed) = ctx
+        .request_json(Method::GET, &format!("/api/v1/channels/{filed_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(filed["parent_id"].is_null(), "unexpected payload: {filed}");
+    let jobs = paracord_db::jobs::list_jobs(
+        &ctx.db,
+        None,
+        Some(paracord_api::jobs::JOB_TOMBSTONE_PURGE),
+        None,
+        10,
+    )
+    .await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].status, paracord_db::jobs::JOB_STATUS_PENDING);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_search_skips_channels_the_caller_cannot_read() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Guild Search").await?;
+    let open = create_text_channel(&ctx, &guild_id, "open").await?;
+    let staff = create_text_channel(&ctx, &guild_id, "staff").await?;
+    for channel_id in [&open, &staff, &open] {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/messages"),
+                Some(json!({ "content": "roadmap draft" })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{staff}/overwrites"),
+            Some(json!({ "overwrites": [
+                { "target_id": guild_id, "target_type": 0, "allow_perms": 0, "deny_perms": 1024 },
+            ] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{open}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &format!(
+                "/api/v1/invites/{}",
+                invite["code"].as_str().context("code")?
+            ),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let search_path = format!("/api/v1/guilds/{guild_id}/messages/search?q=roadmap");
+    let (status, owner_view) = ctx.request_json(Method::GET, &search_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {owner_view}");
+    let groups = owner_view["results"].as_array().context("results")?;
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["messages"].as_array().map(Vec::len), Some(2));
+    assert_eq!(groups[0]["channel"]["id"], open.as_str());
+    assert_eq!(owner_view["has_more"], false);
+
+    let (status, member_view) = ctx
+        .request_json_as(&member, Method::GET, &search_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let groups = member_view["results"].as_array().context("results")?;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0]["channel"]["id"], open.as_str());
+
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::GET,
+            &format!("{search_path}+in:{staff}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, page) = ctx
+        .request_json(Method::GET, &format!("{search_path}&limit=1"), None)
+        .await?;
+    assert_eq!(page["has_more"], true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn thread_routes_work() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Thread Routes Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "thread-parent").await?;
+
+    let (status, created_thread) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/threads"),
+            Some(json!({
+                "name": "first-thread",
+                "auto_archive_duration": 1440
+            })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "unexpected thread payload: {created_thread}"
+    );
+    let thread_id = created_thread["id"]
+        .as_str()
+        .context("thread id should be a string")?
+        .to_string();
+    assert_eq!(created_thread["parent_id"], channel_id);
+    assert!(created_thread["owner_id"].is_string());
+
+    let (status, threads) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/threads"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let active_threads = threads
+        .as_array()
+        .context("threads response should be an array")?;
+    assert!(active_threads
+        .iter()
+        .any(|thread| thread.get("id").and_then(Value::as_str) == Some(thread_id.as_str())));
+
+    let (status, archived) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}/threads/{thread_id}"),
+            Some(json!({ "archived": true })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "unexpected archived payload: {archived}"
+    );
+    assert_eq!(archived["id"], thread_id);
+
+    let (status, archived_threads) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/threads/archived"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let archived_list = archived_threads
+        .as_array()
+        .context("archived threads response should be an array")?;
+    assert!(archived_list
+        .iter()
+        .any(|thread| thread.get("id").and_then(Value::as_str) == Some(thread_id.as_str())));
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/channels/{channel_id}/threads/{thread_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_sync_returns_messages_after_cursor() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Sync Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "sync-chat").await?;
+
+    let mut message_ids = Vec::new();
+    for content in ["first", "second", "third"] {
+        let (status, message) = ctx
+            .request_json(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/messages"),
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        message_ids.push(
+            message["id"]
+                .as_str()
+                .context("message id should be a string")?
+                .to_string(),
+        );
+    }
+
+    let (status, full) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/sync"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected sync payload: {full}");
+    assert_eq!(full["guild"]["id"], guild_id);
+    assert!(full["channels"]
+        .as_array()
+        .context("channels should be an array")?
+        .iter()
+        .any(|c| c["id"] == channel_id));
+    let synced: Vec<&str> = full["messages"][&channel_id]
+        .as_array()
+        .context("channel messages should be an array")?
+        .iter()
+        .filter_map(|m| m["id"].as_str())
+        .collect();
+    assert_eq!(synced, message_ids);
+    assert_eq!(full["cursor"], message_ids[2]);
+
+    let (status, delta) = ctx
+        .request_json(
+            Method::GET,
+            &format!(
+                "/api/v1/guilds/{guild_id}/sync?after={}&limit=1",
+                message_ids[0]
+            ),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected delta payload: {delta}");
+    let delta_ids: Vec<&str> = delta["messages"][&channel_id]
+        .as_array()
+        .context("delta messages should be an array")?
+        .iter()
+        .filter_map(|m| m["id"].as_str())
+        .collect();
+    assert_eq!(delta_ids, vec![message_ids[2].as_str()]);
+    assert_eq!(delta["truncated"], json!([channel_id]));
+
+    let (status, empty) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/sync?after={}", message_ids[2]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(empty["messages"], json!({}));
+    assert_eq!(empty["cursor"], message_ids[2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn webhook_execution_is_rate_limited_and_suspended_after_abuse() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Webhook Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "hooks").await?;
+
+    let (status, webhook) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/webhooks"),
+            Some(json!({ "name": "ci", "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {webhook}");
+    let webhook_id = webhook["id"].as_str().context("webhook id")?.to_string();
+    let token = webhook["token"].as_str().context("webhook token")?;
+    let execute_path = format!("/api/v1/webhooks/{webhook_id}/{token}");
+
+    for _ in 0..5 {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &execute_path,
+                Some(json!({ "content": "build passed" })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &execute_path,
+            Some(json!({ "content": "build passed" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &execute_path,
+            Some(json!({ "content": "x".repeat(600 * 1024) })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    let mut suspended = false;
+    // Windows may roll over on a slow runner, so successes are tolerated here.
+    for _ in 0..120 {
+        let (status, _) = ctx
+            .request_json(
+                Method::POST,
+                &execute_path,
+                Some(json!({ "content": "spam" })),
+            )
+            .await?;
+        if status == StatusCode::FORBIDDEN {
+            suspended = true;
+            break;
+        }
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+    assert!(
+        suspended,
+        "webhook should be suspended after sustained abuse"
+    );
+
+    let (status, fetched) = ctx
+        .request_json(Method::GET, &format!("/api/v1/webhooks/{webhook_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(fetched["suspended_at"].is_string());
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/webhooks/{webhook_id}"),
+            Some(json!({ "suspended": false })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(updated["suspended_at"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn denylisted_links_are_flagged_or_blocked_per_guild() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Link Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "links").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let scam = json!({ "content": "free nitro: https://claim.nitro-gift.example/login" });
+
+    let (status, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    let (status, entry) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/url-denylist",
+            Some(json!({ "domain": "https://Nitro-Gift.example/", "reason": "gift scam" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {entry}");
+    assert_eq!(entry["domain"], "nitro-gift.example");
+
+    // The server default level flags the message but lets it through.
+    let (status, _) = ctx
+        .request_json(Method::POST, &messages_path, Some(scam.clone()))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, events) = ctx
+        .request_json(
+            Method::GET,
+            "/api/v1/admin/security-events?action=message.url_denylisted",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(events.as_array().map(Vec::len), Some(1));
+    assert_eq!(events[0]["details"]["action"], "flag");
+    assert_eq!(events[0]["details"]["domain"], "nitro-gift.example");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}"),
+            Some(json!({ "bot_settings": { "url_filter": { "level": "block" } } })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json(Method::POST, &messages_path, Some(scam.clone()))
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "see https://nitro-gift.example.org.invalid.test" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            "/api/v1/admin/url-denylist/nitro-gift.example",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json(Method::POST, &messages_path, Some(scam))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn new_devices_wait_for_approval_from_a_trusted_session() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (status, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+
+    let (status, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({ "device_approval_required": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(settings["device_approval_required"], true);
+    let recovery_code = settings["device_recovery_code"]
+        .as_str()
+        .context("recovery code issued when enabling")?
+        .to_string();
+    let (status, stored) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/settings", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(stored.get("device_recovery_code").is_none());
+
+    let (status, sessions) = ctx
+        .request_json(Method::GET, "/api/v1/auth/sessions", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sessions[0]["current"], true);
+    assert_eq!(sessions[0]["device_approved"], true);
+
+    let mut pending = Vec::new();
+    for _ in 0..2 {
+        let session_id = format!("sess-{}", Uuid::new_v4().simple());
+        paracord_db::sessions::create_session(
+            &ctx.db,
+            &session_id,
+            user_id,
+            &format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            &format!("jti-{}", Uuid::new_v4().simple()),
+            None,
+            None,
+            Some("New Device"),
+            None,
+            Utc::now() + Duration::days(1),
+        )
+        .await?;
+        pending.push(session_id);
+    }
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/auth/sessions/{}/approve", pending[0]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let approved = paracord_db::sessions::get_session_by_id(&ctx.db, &pending[0])
+        .await?
+        .context("approved session exists")?;
+    assert!(approved.device_approved_at.is_some());
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/auth/sessions/{}/deny", pending[1]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let denied = paracord_db::sessions::get_session_by_id(&ctx.db, &pending[1])
+        .await?
+        .context("denied session exists")?;
+    assert_eq!(denied.revoked_reason.as_deref(), Some("device_denied"));
+
+    // Approving twice is a no-op the caller can see.
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/auth/sessions/{}/approve", pending[0]),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // With nobody to approve it, a new device gets in only with the recovery code.
+    let session_id = format!("sess-{}", Uuid::new_v4().simple());
+    let jti = format!("jti-{}", Uuid::new_v4().simple());
+    paracord_db::sessions::create_session(
+        &ctx.db,
+        &session_id,
+        user_id,
+        &format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        &jti,
+        None,
+        Some("laptop-device"),
+        Some("Laptop"),
+        None,
+        Utc::now() + Duration::days(1),
+    )
+    .await?;
+    let laptop = paracord_core::auth::create_session_token(
+        user_id,
+        None,
+        &ctx.state.config.jwt_secret,
+        3600,
+        &session_id,
+        &jti,
+    )?;
+
+    let (status, _) = ctx
+        .request_json_as(
+            &laptop,
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({ "device_approval_required": false })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = ctx
+        .request_json_as(
+            &laptop,
+            Method::POST,
+            "/api/v1/auth/device-recovery",
+            Some(json!({ "recovery_code": "not-the-code" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, recovered) = ctx
+        .request_json_as(
+            &laptop,
+            Method::POST,
+            "/api/v1/auth/device-recovery",
+            Some(json!({ "recovery_code": recovery_code })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(
+        recovered["recovery_code"].as_str(),
+        Some(recovery_code.as_str())
+    );
+    let laptop_session = paracord_db::sessions::get_session_by_id(&ctx.db, &session_id)
+        .await?
+        .context("recovered session exists")?;
+    assert!(laptop_session.device_approved_at.is_some());
+    assert!(paracord_db::sessions::is_device_approved(&ctx.db, user_id, "laptop-device").await?);
+
+    // The code is single-use.
+    let (status, _) = ctx
+        .request_json_as(
+            &laptop,
+            Method::POST,
+            "/api/v1/auth/device-recovery",
+            Some(json!({ "recovery_code": recovery_code })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, sessions) = ctx
+        .request_json(Method::GET, "/api/v1/auth/sessions", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let listed = sessions
+        .as_array()
+        .context("sessions array")?
+        .iter()
+        .find(|session| session["id"] == session_id.as_str())
+        .context("recovered session listed")?;
+    assert_ne!(listed["device_id"], "laptop-device");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn nsfw_channels_require_age_gate_acknowledgement() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "After Dark Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "after-dark").await?;
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/channels/{channel_id}"),
+            Some(json!({ "nsfw": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["nsfw"], true);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "behind the gate" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let (status, body) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "AGE_GATE_REQUIRED");
+
+    let (status, gate) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/age-gate", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(gate["acknowledged"], false);
+
+    let (status, gate) = ctx
+        .request_json(Method::POST, "/api/v1/users/@me/age-gate", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(gate["acknowledged"], true);
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(messages[0]["content"], "behind the gate");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_posting_modes_restrict_who_can_post() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Announcements Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "announcements").await?;
+    let channel_path = format!("/api/v1/channels/{channel_id}");
+    let messages_path = format!("{channel_path}/messages");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "posting_mode": 9 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The guild owner holds POST_OVERRIDE, so read-only does not stop them.
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "posting_mode": 1 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["posting_mode"], 1);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "release notes" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &channel_path,
+            Some(json!({ "posting_mode": 2 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["posting_mode"], 2);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "from a person" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, webhook) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/webhooks"),
+            Some(json!({ "name": "feed", "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {webhook}");
+    let webhook_id = webhook["id"].as_str().context("webhook id")?;
+    let token = webhook["token"].as_str().context("webhook token")?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/webhooks/{webhook_id}/{token}"),
+            Some(json!({ "content": "from a webhook" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn archived_channels_are_frozen_and_exportable() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Archive Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "old-news").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "<b>last words</b>" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, archived) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/archive"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(archived["archived_at"].is_string());
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "too late" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+    let (status, active) = ctx.request_json(Method::GET, &channels_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(active
+        .as_array()
+        .context("channel list")?
+        .iter()
+        .all(|channel| channel["id"] != channel_id.as_str()));
+    let (status, listed) = ctx
+        .request_json(Method::GET, &format!("{channels_path}?archived=true"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed[0]["id"], channel_id.as_str());
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(messages[0]["content"], "<b>last words</b>");
+
+    let (status, export) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/exports"),
+            Some(json!({ "format": "json" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {export}");
+    let export_id = export["id"].as_str().context("export id")?;
+    let download_url = export["download_url"].as_str().context("download url")?;
+
+    let ran = paracord_core::jobs::run_pending_jobs_once(
+        &ctx.state,
+        &paracord_api::jobs::job_registry(),
+        "test",
+        10,
+    )
+    .await;
+    assert_eq!(ran, 1);
+    let (status, fetched) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/exports/{export_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        fetched["status"], "complete",
+        "unexpected payload: {fetched}"
+    );
+    assert_eq!(fetched["message_count"], 1);
+
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/admin/jobs", None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+    let (status, jobs) = ctx
+        .request_json(Method::GET, "/api/v1/admin/jobs", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {jobs}");
+    assert_eq!(jobs["jobs"][0]["kind"], "channel_export");
+    assert_eq!(jobs["jobs"][0]["status"], "complete");
+    assert_eq!(jobs["counts"]["complete"], 1);
+
+    let (status, transcript) = ctx.request_json(Method::GET, download_url, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(transcript["channel_name"], "old-news");
+    assert_eq!(transcript["messages"][0]["content"], "<b>last words</b>");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channel-exports/{export_id}/not-the-token"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, restored) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/channels/{channel_id}/archive"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(restored["archived_at"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_tags_expand_into_messages() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Tag Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "help").await?;
+    let tags_path = format!("/api/v1/guilds/{guild_id}/tags");
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, tag) = ctx
+        .request_json(
+            Method::POST,
+            &tags_path,
+            Some(json!({ "name": "Rules", "content": "Be excellent to each other." })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {tag}");
+    assert_eq!(tag["name"], "rules");
+    assert_eq!(tag["uses"], 0);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &tags_path,
+            Some(json!({ "name": "rules", "content": "again" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "/tag RULES" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(message["content"], "Be excellent to each other.");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "/tag missing" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, updated) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("{tags_path}/rules"),
+            Some(json!({ "name": "code-of-conduct" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["name"], "code-of-conduct");
+    assert_eq!(updated["uses"], 1);
+
+    let (status, listed) = ctx.request_json(Method::GET, &tags_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().context("tag list")?.len(), 1);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("{tags_path}/code-of-conduct"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json(Method::GET, &format!("{tags_path}/code-of-conduct"), None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn due_reminders_ping_the_channel_once() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Reminder Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "todo").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "ship the release" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let message_id = message["id"].as_str().context("message id")?;
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/reminders",
+            Some(json!({ "content": "past", "due_at": "2001-01-01T00:00:00Z" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, reminder) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/reminders",
+            Some(json!({
+                "content": "check the release",
+                "in_seconds": 3600,
+                "channel_id": channel_id,
+                "message_id": message_id,
+            })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "unexpected payload: {reminder}"
+    );
+    let reminder_id = reminder["id"].as_str().context("reminder id")?;
+
+    let (status, cancelled) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/reminders",
+            Some(json!({ "content": "never mind", "in_seconds": 60 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!(
+                "/api/v1/users/@me/reminders/{}",
+                cancelled["id"].as_str().context("reminder id")?
+            ),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    sqlx::query("UPDATE reminders SET due_at = '2020-01-01 00:00:00' WHERE id = $1")
+        .bind(reminder_id.parse::<i64>()?)
+        .execute(&ctx.db)
+        .await?;
+    paracord_api::routes::reminders::run_due_reminders_once(&ctx.state).await;
+    paracord_api::routes::reminders::run_due_reminders_once(&ctx.state).await;
+
+    let (status, pending) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/reminders", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pending, json!([]));
+
+    let (status, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let pings: Vec<&Value> = messages
+        .as_array()
+        .context("message list")?
+        .iter()
+        .filter(|message| {
+            message["content"]
+                .as_str()
+                .is_some_and(|content| content.ends_with("Reminder: check the release"))
+        })
+        .collect();
+    assert_eq!(pings.len(), 1);
+    assert_eq!(pings[0]["reference_id"], message_id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dm_receipts_follow_delivery_reads_and_privacy() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let peer_id = paracord_util::snowflake::next_id();
+    paracord_db::users::create_user(&ctx.db, peer_id, "receipt_peer", 1, "peer@example.com", "x")
+        .await?;
+    let channel_id = paracord_util::snowflake::next_id();
+    paracord_db::dms::create_dm_channel(&ctx.db, channel_id, user_id, peer_id).await?;
+
+    // Plaintext DMs are rejected by the API, so store the message directly.
+    let message_id = paracord_util::snowflake::next_id();
+    paracord_db::messages::create_message(
+        &ctx.db,
+        message_id,
+        channel_id,
+        user_id,
+        "did you get this?",
+        0,
+        None,
+    )
+    .await?;
+
+    // The gateway records delivery when it sends the MESSAGE_CREATE.
+    use paracord_core::receipts::{record_receipt, ReceiptKind};
+    assert!(
+        record_receipt(
+            &ctx.state,
+            channel_id,
+            peer_id,
+            message_id,
+            ReceiptKind::Delivered
+        )
+        .await?
+    );
+    let receipts_path = format!("/api/v1/users/@me/dms/{channel_id}/receipts");
+    let (status, receipts) = ctx.request_json(Method::GET, &receipts_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {receipts}");
+    assert_eq!(receipts[0]["user_id"], peer_id.to_string());
+    assert_eq!(
+        receipts[0]["last_delivered_message_id"],
+        message_id.to_string()
+    );
+    assert_eq!(receipts[0]["last_read_message_id"], Value::Null);
+
+    assert!(
+        record_receipt(
+            &ctx.state,
+            channel_id,
+            peer_id,
+            message_id,
+            ReceiptKind::Read
+        )
+        .await?
+    );
+    let (_, receipts) = ctx.request_json(Method::GET, &receipts_path, None).await?;
+    assert_eq!(receipts[0]["last_read_message_id"], message_id.to_string());
+
+    // Acking the channel records our own read receipt, which we don't list.
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/read"),
+            Some(json!({ "last_message_id": message_id.to_string() })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let own = paracord_db::dm_receipts::get_receipt(&ctx.db, channel_id, user_id)
+        .await?
+        .context("own receipt")?;
+    assert_eq!(own.last_read_message_id, Some(message_id));
+    let (_, receipts) = ctx.request_json(Method::GET, &receipts_path, None).await?;
+    assert_eq!(receipts.as_array().map(Vec::len), Some(1));
+
+    paracord_db::users::ensure_user_settings(&ctx.db, peer_id).await?;
+    paracord_db::users::update_dm_receipts_enabled(&ctx.db, peer_id, false).await?;
+    let (_, receipts) = ctx.request_json(Method::GET, &receipts_path, None).await?;
+    assert_eq!(receipts, json!([]));
+    paracord_db::users::update_dm_receipts_enabled(&ctx.db, peer_id, true).await?;
+
+    let (status, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({ "dm_receipts_enabled": false })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(settings["dm_receipts_enabled"], false);
+    let (_, receipts) = ctx.request_json(Method::GET, &receipts_path, None).await?;
+    assert_eq!(receipts, json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn threads_auto_archive_and_respect_guild_limits() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Thread Policy Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "thread-policy").await?;
+    let threads_path = format!("/api/v1/channels/{channel_id}/threads");
+    let policy_path = format!("/api/v1/guilds/{guild_id}/thread-policy");
+
+    let (status, policy) = ctx
+        .request_json(
+            Method::PUT,
+            &policy_path,
+            Some(json!({ "max_active_threads": 1, "default_auto_archive_duration": 60 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {policy}");
+    assert_eq!(policy["unarchive_on_message"], true);
+
+    let (status, thread) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "quiet-thread" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {thread}");
+    assert_eq!(thread["thread_metadata"]["auto_archive_duration"], 60);
+    let thread_id = thread["id"].as_str().context("thread id")?.to_string();
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "one-too-many" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &threads_path,
+            Some(json!({ "name": "odd-duration", "auto_archive_duration": 90 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // An hour without activity archives the thread.
+    sqlx::query("UPDATE channels SET created_at = '2020-01-01 00:00:00' WHERE id = $1")
+        .bind(thread_id.parse::<i64>()?)
+        .execute(&ctx.db)
+        .await?;
+    paracord_api::routes::channels::run_thread_auto_archive_once(&ctx.state).await;
+    let (_, active) = ctx.request_json(Method::GET, &threads_path, None).await?;
+    assert_eq!(active, json!([]));
+    let (_, policy) = ctx.request_json(Method::GET, &policy_path, None).await?;
+    assert_eq!(policy["active_threads"], 0);
+
+    // Posting un-archives it, and the worker leaves it alone afterwards.
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{thread_id}/messages"),
+            Some(json!({ "content": "still here" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    paracord_api::routes::channels::run_thread_auto_archive_once(&ctx.state).await;
+    let (_, active) = ctx.request_json(Method::GET, &threads_path, None).await?;
+    assert_eq!(active[0]["id"], thread_id);
+    assert_eq!(active[0]["thread_metadata"]["archived"], false);
+
+    // The policy is replaced as a whole, so omitted limits are cleared.
+    let (status, policy) = ctx
+        .request_json(
+            Method::PUT,
+            &policy_path,
+            Some(json!({ "unarchive_on_message": false })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["max_active_threads"], Value::Null);
+    assert_eq!(policy["unarchive_on_message"], false);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn members_pick_self_assignable_roles() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Self Roles Guild").await?;
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+
+    let mut role_ids = Vec::new();
+    for (name, self_assignable) in [("red", true), ("blue", true), ("staff", false)] {
+        let (status, role) = ctx
+            .request_json(
+                Method::POST,
+                &roles_path,
+                Some(json!({
+                    "name": name,
+                    "self_assignable": self_assignable,
+                    "self_assign_group": "colors",
+                })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+        assert_eq!(role["self_assignable"], self_assignable);
+        role_ids.push(role["id"].as_str().context("role id")?.to_string());
+    }
+    let (red, blue, staff) = (&role_ids[0], &role_ids[1], &role_ids[2]);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &roles_path,
+            Some(json!({ "name": "mods", "permissions": 1 << 13, "self_assignable": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let self_role_path =
+        |role_id: &str| format!("/api/v1/guilds/{guild_id}/members/@me/roles/{role_id}");
+    let (status, _) = ctx
+        .request_json(Method::PUT, &self_role_path(staff), None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, member) = ctx
+        .request_json(Method::PUT, &self_role_path(red), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {member}");
+    assert!(member["roles"]
+        .as_array()
+        .context("roles")?
+        .contains(&json!(red)));
+
+    // Roles in a group are exclusive.
+    let (_, member) = ctx
+        .request_json(Method::PUT, &self_role_path(blue), None)
+        .await?;
+    let held = member["roles"].as_array().context("roles")?;
+    assert!(held.contains(&json!(blue)));
+    assert!(!held.contains(&json!(red)));
+
+    let (status, member) = ctx
+        .request_json(Method::DELETE, &self_role_path(blue), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!member["roles"]
+        .as_array()
+        .context("roles")?
+        .contains(&json!(blue)));
+
+    let (_, logs) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/audit-logs"),
+            None,
+        )
+        .await?;
+    let entries = logs["audit_log_entries"]
+        .as_array()
+        .context("audit entries")?;
+    assert!(entries.iter().any(|entry| entry["action_type"] == 26));
+    assert!(entries.iter().any(|entry| entry["action_type"] == 27));
+
+    let mut limited = false;
+    for _ in 0..25 {
+        let (status, _) = ctx
+            .request_json(Method::PUT, &self_role_path(red), None)
+            .await?;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            limited = true;
+            break;
+        }
+    }
+    assert!(limited, "self-assigning roles should be rate limited");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn emoji_analytics_count_uses_and_suggest_cleanup() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Emoji Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "emotes").await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let guild: i64 = guild_id.parse()?;
+
+    for (id, name) in [(9001, "wave"), (9002, "dusty"), (9003, "fresh")] {
+        paracord_db::emojis::create_emoji(&ctx.db, id, guild, name, user_id, false).await?;
+    }
+    sqlx::query("UPDATE emojis SET created_at = '2020-01-01 00:00:00' WHERE id IN (9001, 9002)")
+        .execute(&ctx.db)
+        .await?;
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "hi <:wave:9001> <:wave:9001> <:other:424242>" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {message}");
+    let message_id = message["id"].as_str().context("message id")?;
+    let reaction_path = format!(
+        "/api/v1/channels/{channel_id}/messages/{message_id}/reactions/%3C%3Awave%3A9001%3E/@me"
+    );
+    for _ in 0..2 {
+        let (status, _) = ctx.request_json(Method::PUT, &reaction_path, None).await?;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    let (status, analytics) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/emojis/analytics"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {analytics}");
+    assert_eq!(analytics["days"], 30);
+    let emojis = analytics["emojis"].as_array().context("emojis")?;
+    assert_eq!(emojis.len(), 3);
+    assert_eq!(emojis[0]["emoji"]["id"], "9001");
+    assert_eq!(emojis[0]["message_count"], 1);
+    assert_eq!(emojis[0]["reaction_count"], 1);
+    assert_eq!(emojis[0]["total"], 2);
+    assert!(emojis[0]["last_used_at"].is_string());
+
+    // Only old emojis without recent use are cleanup candidates.
+    let (status, unused) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/emojis/unused?days=7"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<&str> = unused["emojis"]
+        .as_array()
+        .context("emojis")?
+        .iter()
+        .filter_map(|entry| entry["emoji"]["id"].as_str())
+        .collect();
+    assert_eq!(ids, vec!["9002"]);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/emojis/unused?days=0"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn user_directory_lists_only_opted_in_users() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let username = me["username"].as_str().context("username")?.to_string();
+    let prefix: String = username.chars().take(3).collect();
+    paracord_db::users::create_user(
+        &ctx.db,
+        777_001,
+        &format!("{prefix}hidden"),
+        1,
+        "hidden@example.com",
+        "hash",
+    )
+    .await?;
+
+    let search_path = format!("/api/v1/users/search?q={prefix}");
+    let (status, found) = ctx.request_json(Method::GET, &search_path, None).await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {found}");
+    assert_eq!(found["users"], json!([]));
+
+    let (status, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/users/@me/settings",
+            Some(json!({ "directory_discoverable": true })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(settings["directory_discoverable"], true);
+
+    let (_, found) = ctx
+        .request_json(Method::GET, &format!("{search_path}&federated=true"), None)
+        .await?;
+    let users = found["users"].as_array().context("users")?;
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["username"], username.as_str());
+    assert_eq!(found["federated_users"], json!([]));
+
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/users/search?q=a", None)
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut limited = false;
+    for _ in 0..40 {
+        let (status, _) = ctx.request_json(Method::GET, &search_path, None).await?;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            limited = true;
+            break;
+        }
+    }
+    assert!(limited, "directory searches should be rate limited");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verification_hook_screens_invite_joins() -> anyhow::Result<()> {
+    use paracord_api::routes::verification::{sign_payload, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    let ctx = TestContext::new().await?;
+    ctx.state
+        .runtime
+        .write()
+        .await
+        .verification_hooks_allow_private_networks = true;
+    let guild_id = create_guild(&ctx, "Screened Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "lobby").await?;
+    let (status, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {invite}");
+    let invite_path = format!(
+        "/api/v1/invites/{}",
+        invite["code"].as_str().context("code")?
+    );
+
+    // A stand-in verification service answering with whatever decision is set.
+    let decision = Arc::new(std::sync::Mutex::new("pending"));
+    let secret = Arc::new(std::sync::Mutex::new(String::new()));
+    let hook_app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post({
+            let decision = decision.clone();
+            let secret = secret.clone();
+            move |headers: axum::http::HeaderMap, body: axum::body::Bytes| async move {
+                let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+                let expected = sign_payload(&secret.lock().unwrap(), timestamp, &body);
+                if headers[SIGNATURE_HEADER].to_str().unwrap() != expected {
+                    return (StatusCode::UNAUTHORIZED, axum::Json(json!({})));
+                }
+                let payload: Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(payload["type"], "guild_join");
+                let decision = *decision.lock().unwrap();
+                (
+                    StatusCode::OK,
+                    axum::Json(json!({ "decision": decision, "reason": "checked" })),
+                )
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let hook_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, hook_app).await;
+    });
+
+    let hook_path = format!("/api/v1/guilds/{guild_id}/verification-hook");
+    let (status, hook) = ctx
+        .request_json(
+            Method::PUT,
+            &hook_path,
+            Some(json!({ "url": format!("http://{hook_addr}/hook"), "timeout_ms": 2000 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {hook}");
+    let hook_secret = hook["secret"].as_str().context("generated secret")?;
+    *secret.lock().unwrap() = hook_secret.to_string();
+    let (_, hook) = ctx.request_json(Method::GET, &hook_path, None).await?;
+    assert!(hook.get("secret").is_none());
+
+    // Pending joins are held for a decision.
+    let joiner = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, joined) = ctx
+        .request_json_as(&joiner, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {joined}");
+    assert_eq!(joined["verification"]["status"], "pending");
+    let (_, requests) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/join-requests"),
+            None,
+        )
+        .await?;
+    let requests = requests.as_array().context("join requests")?;
+    assert_eq!(requests.len(), 1);
+    let joiner_id = requests[0]["user_id"].as_str().context("user id")?;
+    let (status, _) = ctx
+        .request_json_as(
+            &joiner,
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            None,
+        )
+        .await?;
+    assert_ne!(status, StatusCode::OK);
+
+    // A badly signed callback is refused; a signed one admits the user.
+    let callback_path = format!("/api/v1/guilds/{guild_id}/verification-hook/callback");
+    let callback_body = json!({ "user_id": joiner_id, "decision": "allow" }).to_string();
+    let timestamp = Utc::now().timestamp();
+    let send_callback = |signature: String| {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&callback_path)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(callback_body.clone()))
+            .unwrap();
+        ctx.app.clone().oneshot(request)
+    };
+    let response = send_callback(sign_payload("wrong-secret", timestamp, b"{}")).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = send_callback(sign_payload(
+        hook_secret,
+        timestamp,
+        callback_body.as_bytes(),
+    ))
+    .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(
+            &joiner,
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Denials are reported to the joiner; moderators can still approve them.
+    *decision.lock().unwrap() = "deny";
+    let second = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, denied) = ctx
+        .request_json_as(&second, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::FORBIDDEN,
+        "unexpected payload: {denied}"
+    );
+
+    *decision.lock().unwrap() = "allow";
+    let (status, joined) = ctx
+        .request_json_as(&second, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {joined}");
+    assert_eq!(joined["guild"]["id"], guild_id.as_str());
+
+    // Moderators settle held joins.
+    *decision.lock().unwrap() = "pending";
+    let third = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let (status, _) = ctx
+        .request_json_as(&third, Method::POST, &invite_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let (_, requests) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/join-requests"),
+            None,
+        )
+        .await?;
+    let third_id = requests[0]["user_id"].as_str().context("user id")?;
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/guilds/{guild_id}/join-requests/{third_id}"),
+            Some(json!({ "decision": "allow" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx
+        .request_json_as(
+            &third,
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleted_guilds_channels_and_roles_can_be_restored_until_purged() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+    let guild_id = create_guild(&ctx, "Undo Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "doomed").await?;
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+    let (status, role) = ctx
+        .request_json(Method::POST, &roles_path, Some(json!({ "name": "doomed" })))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {role}");
+    let role_id = role["id"].as_str().context("role id")?.to_string();
+
+    let channel_path = format!("/api/v1/channels/{channel_id}");
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &channel_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = ctx.request_json(Method::GET, &channel_path, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &format!("{roles_path}/{role_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, roles) = ctx.request_json(Method::GET, &roles_path, None).await?;
+    assert!(!roles
+        .as_array()
+        .context("roles")?
+        .iter()
+        .any(|role| role["id"] == role_id.as_str()));
+
+    let (status, deleted) = ctx
+        .request_json(Method::GET, "/api/v1/admin/deleted", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {deleted}");
+    let deleted = deleted.as_array().context("tombstones")?;
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted
+        .iter()
+        .all(|tombstone| tombstone["restorable_until"].is_string()));
+
+    // Channels of a deleted guild only come back with the guild.
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &format!("/api/v1/guilds/{guild_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let restore = |kind: &str, id: &str| format!("/api/v1/admin/deleted/{kind}/{id}/restore");
+    let (status, _) = ctx
+        .request_json(Method::POST, &restore("channel", &channel_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, restored) = ctx
+        .request_json(Method::POST, &restore("guild", &guild_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {restored}");
+    assert_eq!(restored["guild"]["id"], guild_id.as_str());
+    let (status, _) = ctx
+        .request_json(Method::POST, &restore("channel", &channel_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx.request_json(Method::GET, &channel_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = ctx
+        .request_json(Method::POST, &restore("role", &role_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, roles) = ctx.request_json(Method::GET, &roles_path, None).await?;
+    assert!(roles
+        .as_array()
+        .context("roles")?
+        .iter()
+        .any(|role| role["id"] == role_id.as_str()));
+
+    // Once the undo window has passed the purge removes the row for good.
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &channel_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let purged =
+        paracord_core::tombstone::purge_expired(&ctx.db, Utc::now() + Duration::hours(25), 24)
+            .await?;
+    assert_eq!(purged.len(), 1);
+    let (status, _) = ctx
+        .request_json(Method::POST, &restore("channel", &channel_id), None)
+        .await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn channel_overwrites_are_replaced_as_a_set() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Overwrites Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "private").await?;
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({ "name": "mods" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let role_id = role["id"].as_str().context("role id")?.to_string();
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id = me["id"].as_str().context("user id")?.to_string();
+    let overwrites_path = format!("/api/v1/channels/{channel_id}/overwrites");
+
+    let (status, overwrites) = ctx
+        .request_json(
+            Method::PUT,
+            &overwrites_path,
+            Some(json!({ "overwrites": [
+                { "target_id": guild_id, "target_type": 0, "allow_perms": 0, "deny_perms": 1024 },
+                { "target_id": role_id, "target_type": 0, "allow_perms": 1024, "deny_perms": 0 },
+                { "target_id": user_id, "target_type": 1, "allow_perms": 2048, "deny_perms": 0 },
+            ] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {overwrites}");
+    assert_eq!(overwrites.as_array().context("overwrites")?.len(), 3);
+
+    // An invalid entry rejects the whole set.
+    for bad in [
+        json!({ "target_id": "123", "target_type": 1, "allow_perms": 0, "deny_perms": 0 }),
+        json!({ "target_id": "123", "target_type": 0, "allow_perms": 0, "deny_perms": 0 }),
+        json!({ "target_id": role_id, "target_type": 0, "allow_perms": 8, "deny_perms": 8 }),
+    ] {
+        let (status, _) = ctx
+            .request_json(
+                Method::PUT,
+                &overwrites_path,
+                Some(json!({ "overwrites": [
+                    { "target_id": guild_id, "target_type": 0, "allow_perms": 0, "deny_perms": 0 },
+                    bad,
+                ] })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (_, current) = ctx
+        .request_json(Method::GET, &overwrites_path, None)
+        .await?;
+    assert_eq!(current.as_array().context("overwrites")?.len(), 3);
+
+    let (status, overwrites) = ctx
+        .request_json(
+            Method::PUT,
+            &overwrites_path,
+            Some(json!({ "overwrites": [
+                { "target_id": guild_id, "target_type": 0, "allow_perms": 0, "deny_perms": 1024 },
+                { "target_id": role_id, "target_type": 0, "allow_perms": 3072, "deny_perms": 0 },
+            ] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(overwrites.as_array().context("overwrites")?.len(), 2);
+
+    let (_, logs) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/audit-logs"),
+            None,
+        )
+        .await?;
+    let entries: Vec<&Value> = logs["audit_log_entries"]
+        .as_array()
+        .context("audit entries")?
+        .iter()
+        .filter(|entry| entry["action_type"] == 13)
+        .collect();
+    assert_eq!(entries.len(), 2, "one audit entry per applied set");
+    let latest = entries
+        .iter()
+        .find(|entry| entry["changes"]["removed"].as_array().map(Vec::len) == Some(1))
+        .context("entry for the second set")?;
+    assert_eq!(latest["changes"]["updated"][0]["target_id"], json!(role_id));
+    assert_eq!(latest["changes"]["added"], json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_history_includes_aggregated_reactions() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Reactions Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "chat").await?;
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+
+    let mut message_ids = Vec::new();
+    for content in ["first", "second"] {
+        let (status, created) = ctx
+            .request_json(
+                Method::POST,
+                &messages_path,
+                Some(json!({ "content": content })),
+            )
+            .await?;
+        assert_eq!(status, StatusCode::CREATED);
+        message_ids.push(created["id"].as_str().context("message id")?.to_string());
+    }
+    for emoji in ["%F0%9F%91%8D", "%F0%9F%8E%89"] {
+        let (status, _) = ctx
+            .request_json(
+                Method::PUT,
+                &format!("{messages_path}/{}/reactions/{emoji}/@me", message_ids[0]),
+                None,
+            )
+            .await?;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    let (status, history) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let history = history.as_array().context("messages")?;
+    let find = |id: &str| {
+        history
+            .iter()
+            .find(|m| m["id"] == json!(id))
+            .context("message in history")
+    };
+    let reactions = find(&message_ids[0])?["reactions"]
+        .as_array()
+        .context("reactions")?;
+    assert_eq!(reactions.len(), 2);
+    let thumbs = reactions
+        .iter()
+        .find(|r| r["emoji"] == "👍")
+        .context("thumbs up reaction")?;
+    assert_eq!(thumbs["count"], 1);
+    assert_eq!(thumbs["me"], true);
+    assert_eq!(find(&message_ids[1])?["reactions"], json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_database_status_reports_sqlite_stats() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/admin/database", None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    let (status, body) = ctx
+        .request_json(Method::GET, "/api/v1/admin/database", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {body}");
+    assert_eq!(body["engine"], "sqlite");
+    assert!(body["sqlite"]["page_count"].as_i64().unwrap_or(0) > 0);
+    assert_eq!(body["sqlite"]["auto_vacuum"], "none");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_observability_reports_sessions_per_guild() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+
+    let (status, _) = ctx
+        .request_json(Method::GET, "/api/v1/admin/observability", None)
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    let _first = ctx.state.event_bus.register_session("s1", user_id, &[77]);
+    let _second = ctx
+        .state
+        .event_bus
+        .register_session("s2", user_id, &[77, 88]);
+    ctx.state
+        .event_bus
+        .dispatch("TYPING_START", json!({}), Some(77));
+
+    let (status, body) = ctx
+        .request_json(Method::GET, "/api/v1/admin/observability", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {body}");
+    assert_eq!(body["websocket"]["sessions"], 2);
+    assert_eq!(body["websocket"]["guilds"][0]["guild_id"], "77");
+    assert_eq!(body["websocket"]["guilds"][0]["sessions"], 2);
+    assert_eq!(body["event_bus"]["session_queue_total"], 2);
+    assert!(body["rate_limiter"]["top_offenders"].is_array());
+    assert!(body["rate_limiter"]["buckets"]["http"].is_object());
+
+    Ok(())
+}
+
+fn sample_png(mirrored: bool) -> anyhow::Result<Vec<u8>> {
+    let image = image::RgbImage::from_fn(64, 48, |x, y| {
+        let x = if mirrored { 63 - x } else { x };
+        let v = ((x * 4) ^ (y * 2)) as u8;
+        image::Rgb([v, 255 - v, v / 2])
+    });
+    let mut out = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut out, image::ImageFormat::Png)?;
+    Ok(out.into_inner())
+}
+
+async fn upload_attachment(
+    ctx: &TestContext,
+    channel_id: &str,
+    data: &[u8],
+) -> anyhow::Result<(StatusCode, Value)> {
+    let boundary = "paracord-test-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"photo.png\"\r\nContent-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/api/v1/channels/{channel_id}/attachments"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    ))
+}
+
+#[tokio::test]
+async fn uploads_matching_blocklisted_image_hashes_are_rejected() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Hash Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "photos").await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+    ctx.state.runtime.write().await.image_hash_matching.enabled = true;
+
+    let blocked = sample_png(false)?;
+    let hash = paracord_media::images::perceptual_hash(&blocked)?;
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/image-hashes",
+            Some(json!({ "hashes": [format!("{hash:016x}"), "not-a-hash"] })),
+        )
+        .await?;
+    assert_eq!(
+        status,
+        StatusCode::BAD_REQUEST,
+        "unexpected payload: {body}"
+    );
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/image-hashes",
+            Some(json!({ "hashes": [format!("{hash:016X}")], "reason": "test list" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {body}");
+    assert_eq!(body["total"], 1);
+
+    let (status, body) = upload_attachment(&ctx, &channel_id, &blocked).await?;
+    assert_eq!(
+        status,
+        StatusCode::BAD_REQUEST,
+        "unexpected payload: {body}"
+    );
+    let events =
+        paracord_db::security_events::list_events(&ctx.db, Some("upload.hash_matched"), None, 10)
+            .await?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].actor_user_id, Some(user_id));
+
+    let (status, body) = upload_attachment(&ctx, &channel_id, &sample_png(true)?).await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {body}");
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/admin/image-hashes/{hash:016x}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = upload_attachment(&ctx, &channel_id, &blocked).await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_maintenance_task_removes_orphaned_overwrites() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Integrity Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let channel: i64 = channel_id.parse()?;
+    // A role overwrite left behind by a role that no longer exists.
+    paracord_db::channel_overwrites::upsert_channel_overwrite(&ctx.db, channel, 4242, 0, 0, 1024)
+        .await?;
+
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/maintenance",
+            Some(json!({ "task": "drop_everything" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, job) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/maintenance",
+            Some(json!({ "task": "validate_overwrites" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {job}");
+    let job_id = job["id"].as_str().context("job id")?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/maintenance",
+            Some(json!({ "task": "validate_overwrites" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let ran = paracord_core::jobs::run_pending_jobs_once(
+        &ctx.state,
+        &paracord_api::jobs::job_registry(),
+        "test",
+        10,
+    )
+    .await;
+    assert_eq!(ran, 1);
+    let (status, job) = ctx
+        .request_json(Method::GET, &format!("/api/v1/admin/jobs/{job_id}"), None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["status"], "complete", "unexpected payload: {job}");
+    assert_eq!(job["progress"]["stage"], "complete");
+    assert_eq!(job["progress"]["done"], 1);
+    assert_eq!(job["progress"]["result"]["removed"][0]["target_id"], "4242");
+    assert!(
+        paracord_db::channel_overwrites::get_channel_overwrites(&ctx.db, channel)
+            .await?
+            .iter()
+            .all(|overwrite| overwrite.target_id != 4242)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_import_recreates_discord_history_once() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Import Guild").await?;
+    let export_dir = std::path::Path::new(&ctx.state.config.storage_path)
+        .join("imports")
+        .join("old-server");
+    std::fs::create_dir_all(export_dir.join("general.json_Files"))?;
+    std::fs::write(
+        export_dir.join("general.json_Files").join("notes.txt"),
+        b"meeting notes",
+    )?;
+    std::fs::write(
+        export_dir.join("general.json"),
+        json!({
+            "guild": { "id": "1", "name": "Old server" },
+            "channel": { "id": "100", "name": "old-general", "topic": "Imported chat" },
+            "messages": [
+                {
+                    "id": "1378704634675200000",
+                    "type": "Default",
+                    "timestamp": "2025-06-01T12:00:00+00:00",
+                    "content": "first!",
+                    "author": { "id": "7", "name": "alice", "isBot": false },
+                    "attachments": [
+                        { "url": "general.json_Files/notes.txt", "fileName": "notes.txt" }
+                    ]
+                },
+                {
+                    "id": "1378705892966400000",
+                    "type": "Default",
+                    "timestamp": "2025-06-01T12:05:00+00:00",
+                    "content": "beep",
+                    "author": { "id": "8", "name": "helper", "isBot": true },
+                    "attachments": [
+                        { "url": "https://cdn.example/gone.png", "fileName": "gone.png" }
+                    ]
+                }
+            ]
+        })
+        .to_string(),
+    )?;
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/imports",
+            Some(json!({ "guild_id": guild_id, "path": "old-server" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/imports",
+            Some(json!({ "guild_id": guild_id, "path": "../old-server" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let run_import = |dry_run: bool| {
+        let ctx = &ctx;
+        let guild_id = guild_id.clone();
+        async move {
+            let (status, job) = ctx
+                .request_json(
+                    Method::POST,
+                    "/api/v1/admin/imports",
+                    Some(json!({ "guild_id": guild_id, "path": "old-server", "dry_run": dry_run })),
+                )
+                .await?;
+            assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {job}");
+            let job_id = job["id"].as_str().context("job id")?.to_string();
+            let ran = paracord_core::jobs::run_pending_jobs_once(
+                &ctx.state,
+                &paracord_api::jobs::job_registry(),
+                "test",
+                10,
+            )
+            .await;
+            assert_eq!(ran, 1);
+            let (_, job) = ctx
+                .request_json(Method::GET, &format!("/api/v1/admin/jobs/{job_id}"), None)
+                .await?;
+            assert_eq!(job["status"], "complete", "unexpected payload: {job}");
+            anyhow::Ok(job["progress"].clone())
+        }
+    };
+
+    let report = run_import(true).await?;
+    assert_eq!(report["plan"]["messages"], 2);
+    assert_eq!(report["plan"]["attachments"]["with_files"], 1);
+    assert_eq!(report["created"]["messages"], 0);
+    let channels_path = format!("/api/v1/guilds/{guild_id}/channels");
+    let (_, channels) = ctx.request_json(Method::GET, &channels_path, None).await?;
+    let channel_count = channels.as_array().context("channel list")?.len();
+
+    let report = run_import(false).await?;
+    assert_eq!(report["created"]["channels"], 1);
+    assert_eq!(report["created"]["users"], 2);
+    assert_eq!(report["created"]["messages"], 2);
+    assert_eq!(report["created"]["attachments"], 1);
+    assert_eq!(report["skipped"]["attachments"], 1);
+
+    let (_, channels) = ctx.request_json(Method::GET, &channels_path, None).await?;
+    let channels = channels.as_array().context("channel list")?;
+    assert_eq!(channels.len(), channel_count + 1);
+    let imported = channels
+        .iter()
+        .find(|channel| channel["name"] == "old-general")
+        .context("imported channel")?;
+    assert_eq!(imported["topic"], "Imported chat");
+    let channel_id = imported["id"].as_str().context("channel id")?;
+    let (status, messages) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {messages}");
+    assert_eq!(messages[0]["content"], "beep\nhttps://cdn.example/gone.png");
+    assert_eq!(messages[0]["author"]["bot"], true);
+    assert_eq!(messages[1]["content"], "first!");
+    assert!(messages[1]["author"]["username"]
+        .as_str()
+        .is_some_and(|name| name.starts_with("alice_")));
+    assert_eq!(messages[1]["timestamp"], "2025-06-01T12:00:00+00:00");
+    assert_eq!(messages[1]["attachments"][0]["filename"], "notes.txt");
+
+    let report = run_import(false).await?;
+    assert_eq!(report["created"]["messages"], 0);
+    assert_eq!(report["skipped"]["messages"], 2);
+    assert_eq!(report["skipped"]["channels"], 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_import_retry_after_failed_mapping_does_not_duplicate_messages() -> anyhow::Result<()>
+{
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Retry Import Guild").await?;
+    let export_dir = std::path::Path::new(&ctx.state.config.storage_path)
+        .join("imports")
+        .join("flaky-server");
+    std::fs::create_dir_all(&export_dir)?;
+    std::fs::write(
+        export_dir.join("general.json"),
+        json!({
+            "guild": { "id": "1", "name": "Flaky server" },
+            "channel": { "id": "200", "name": "flaky-general" },
+            "messages": [
+                {
+                    "id": "1378704634675200000",
+                    "type": "Default",
+                    "timestamp": "2025-06-01T12:00:00+00:00",
+                    "content": "first",
+                    "author": { "id": "7", "name": "alice", "isBot": false },
+                    "attachments": []
+                },
+                {
+                    "id": "1378705892966400000",
+                    "type": "Default",
+                    "timestamp": "2025-06-01T12:05:00+00:00",
+                    "content": "second",
+                    "author": { "id": "7", "name": "alice", "isBot": false },
+                    "attachments": []
+                }
+            ]
+        })
+        .to_string(),
+    )?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+
+    // Fail the mapping write for the second message, after its row and the
+    // first message have already gone in.
+    sqlx::query(
+        "CREATE TRIGGER fail_second_message_mapping BEFORE INSERT ON import_mappings
+         WHEN NEW.kind = 'message' AND NEW.source_id = '1378705892966400000'
+         BEGIN SELECT RAISE(ABORT, 'simulated mapping failure'); END",
+    )
+    .execute(&ctx.db)
+    .await?;
+
+    let (status, job) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/admin/imports",
+            Some(json!({ "guild_id": guild_id, "path": "flaky-server" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::ACCEPTED, "unexpected payload: {job}");
+    let job_id = job["id"].as_str().context("job id")?.to_string();
+    let registry = paracord_api::jobs::job_registry();
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let (_, job) = ctx
+        .request_json(Method::GET, &format!("/api/v1/admin/jobs/{job_id}"), None)
+        .await?;
+    assert_eq!(job["status"], "pending", "unexpected payload: {job}");
+
+    sqlx::query("DROP TRIGGER fail_second_message_mapping")
+        .execute(&ctx.db)
+        .await?;
+    sqlx::query("UPDATE background_jobs SET run_at = '2020-01-01 00:00:00' WHERE id = $1")
+        .bind(job_id.parse::<i64>()?)
+        .execute(&ctx.db)
+        .await?;
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let (_, job) = ctx
+        .request_json(Method::GET, &format!("/api/v1/admin/jobs/{job_id}"), None)
+        .await?;
+    assert_eq!(job["status"], "complete", "unexpected payload: {job}");
+    assert_eq!(job["progress"]["created"]["messages"], 1);
+    assert_eq!(job["progress"]["skipped"]["messages"], 1);
+
+    let (_, channels) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            None,
+        )
+        .await?;
+    let channel_id = channels
+        .as_array()
+        .context("channel list")?
+        .iter()
+        .find(|channel| channel["name"] == "flaky-general")
+        .and_then(|channel| channel["id"].as_str())
+        .context("imported channel")?
+        .to_string();
+    let (_, messages) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            None,
+        )
+        .await?;
+    let contents: Vec<&str> = messages
+        .as_array()
+        .context("message list")?
+        .iter()
+        .filter_map(|message| message["content"].as_str())
+        .collect();
+    assert_eq!(contents, ["second", "first"]);
+
+    Ok(())
+}
+
+/// Reads `gateway` frames off an SSE response body.
+struct SseFrames {
+    body: axum::body::BodyDataStream,
+    buffered: String,
+}
+
+impl SseFrames {
+    async fn open(ctx: &TestContext, path: &str) -> anyhow::Result<Self> {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+            .body(Body::empty())?;
+        let response = ctx.app.clone().oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(Self {
+            body: response.into_body().into_data_stream(),
+            buffered: String::new(),
+        })
+    }
+
+    async fn next(&mut self) -> anyhow::Result<Value> {
+        use futures_util::StreamExt;
+        loop {
+            if let Some(end) = self.buffered.find("\n\n") {
+                let frame: String = self.buffered.drain(..end + 2).collect();
+                if let Some(data) = frame.lines().find_map(|line| line.strip_prefix("data: ")) {
+                    return Ok(serde_json::from_str(data)?);
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), self.body.next())
+                .await
+                .context("timed out waiting for an SSE frame")?
+                .context("SSE stream ended")??;
+            self.buffered.push_str(&String::from_utf8_lossy(&chunk));
+        }
+    }
+}
+
+#[tokio::test]
+async fn sse_stream_resumes_from_cursor_and_honors_acks() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "SSE Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "sse-chat").await?;
+    let events_path = "/api/v2/rt/events?session_id=sse-resume-test";
+
+    let mut stream = SseFrames::open(&ctx, events_path).await?;
+    let ready = stream.next().await?;
+    assert_eq!(ready["t"], "READY");
+    assert_eq!(ready["event_id"], 1);
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "over sse" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let created = loop {
+        let frame = stream.next().await?;
+        if frame["t"] == "MESSAGE_CREATE" {
+            break frame;
+        }
+    };
+    assert_eq!(created["d"]["content"], "over sse");
+    let sequence = created["event_id"].as_u64().context("event_id")?;
+    assert_eq!(created["s"], sequence);
+    drop(stream);
+
+    // Reconnecting from the READY cursor replays what came after it.
+    let mut stream = SseFrames::open(&ctx, &format!("{events_path}&cursor=1")).await?;
+    let resumed = stream.next().await?;
+    assert_eq!(resumed["t"], "RESUMED");
+    assert_eq!(resumed["s"], sequence);
+    let mut replayed = Vec::new();
+    for _ in 1..sequence {
+        replayed.push(stream.next().await?);
+    }
+    assert_eq!(replayed.last().unwrap()["d"]["content"], "over sse");
+    drop(stream);
+
+    // Once acknowledged, the events are gone and a resume from before them
+    // starts a fresh session instead.
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v2/rt/ack",
+            Some(json!({ "session_id": "sse-resume-test", "cursor": sequence })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let mut stream = SseFrames::open(&ctx, &format!("{events_path}&cursor=1")).await?;
+    assert_eq!(stream.next().await?["t"], "READY");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_resource_limits_are_enforced_at_create() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    paracord_db::users::update_user_flags(&ctx.db, user_id, paracord_core::USER_FLAG_ADMIN).await?;
+    let guild_id = create_guild(&ctx, "Limited Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "hooks").await?;
+    let (_, channels) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            None,
+        )
+        .await?;
+    let channel_count = channels.as_array().context("channels")?.len();
+    let (_, roles) = ctx
+        .request_json(
+            Method::GET,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            None,
+        )
+        .await?;
+    let role_count = roles.as_array().context("roles")?.len();
+
+    let (status, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            "/api/v1/admin/settings",
+            Some(json!({
+                "max_webhooks_per_channel": "1",
+                "max_channels_per_guild": channel_count.to_string(),
+                "max_roles_per_guild": (role_count + 1).to_string(),
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(settings["max_webhooks_per_channel"], "1");
+
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/channels"),
+            Some(json!({ "name": "one-too-many", "channel_type": 0 })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "MAX_GUILD_CHANNELS");
+    assert_eq!(body["details"]["limit"], channel_count);
+
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+    let (status, _) = ctx
+        .request_json(Method::POST, &roles_path, Some(json!({ "name": "first" })))
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = ctx
+        .request_json(Method::POST, &roles_path, Some(json!({ "name": "second" })))
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "MAX_GUILD_ROLES");
+    assert_eq!(body["error_code"], 30005);
+
+    let webhooks_path = format!("/api/v1/guilds/{guild_id}/webhooks");
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &webhooks_path,
+            Some(json!({ "name": "ci", "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            &webhooks_path,
+            Some(json!({ "name": "deploys", "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "MAX_CHANNEL_WEBHOOKS");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batched_acks_store_read_states_and_notify_once() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Ack Guild").await?;
+    let first = create_text_channel(&ctx, &guild_id, "first").await?;
+    let second = create_text_channel(&ctx, &guild_id, "second").await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let mut events = ctx.state.event_bus.register_session("acks", user_id, &[]);
+
+    let (status, body) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v2/rt/commands",
+            Some(json!({
+                "command_id": "ack-1",
+                "type": "read_state_ack",
+                "payload": { "acks": [
+                    { "channel_id": first, "last_message_id": "100" },
+                    { "channel_id": second, "last_message_id": "200" },
+                    { "channel_id": "999999", "last_message_id": "1" },
+                ] },
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {body}");
+
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "READ_STATE_UPDATE");
+    let acked = event.payload["read_states"]
+        .as_array()
+        .context("read states")?;
+    assert_eq!(acked.len(), 2);
+    assert!(events.try_recv().is_err());
+
+    let (_, read_states) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me/read-states", None)
+        .await?;
+    let stored: Vec<(String, String)> = read_states
+        .as_array()
+        .context("read states")?
+        .iter()
+        .map(|row| {
+            (
+                row["channel_id"].as_str().unwrap_or_default().to_string(),
+                row["last_message_id"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        })
+        .collect();
+    assert!(stored.contains(&(first.clone(), "100".to_string())));
+    assert!(stored.contains(&(second.clone(), "200".to_string())));
+
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v2/rt/commands",
+            Some(json!({
+                "command_id": "ack-2",
+                "type": "read_state_ack",
+                "payload": { "acks": [{ "channel_id": "nope", "last_message_id": "1" }] },
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn legacy_permission_bitfields_are_upgraded_unless_client_opts_in() -> anyhow::Result<()> {
+    use paracord_models::permissions::{Permissions, PERMISSIONS_VERSION_HEADER};
+
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Perm Bits Guild").await?;
+    let roles_path = format!("/api/v1/guilds/{guild_id}/roles");
+    let legacy = (Permissions::MANAGE_CHANNELS | Permissions::MANAGE_GUILD).bits();
+
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &roles_path,
+            Some(json!({ "name": "old client", "permissions": legacy })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "{role}");
+    let upgraded = Permissions::from_bits_truncate(role["permissions"].as_i64().context("bits")?);
+    assert!(upgraded.contains(
+        Permissions::MANAGE_THREADS | Permissions::MANAGE_INVITES | Permissions::VIEW_REPORTS
+    ));
+    assert!(!upgraded.contains(Permissions::MODERATE_MEMBERS));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(&roles_path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(PERMISSIONS_VERSION_HEADER, "2")
+        .body(Body::from(
+            json!({ "name": "new client", "permissions": legacy }).to_string(),
+        ))?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let role: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+    assert_eq!(role["permissions"].as_i64(), Some(legacy));
+    Ok(())
+}
+
+#[tokio::test]
+async fn notification_settings_gate_mention_counts() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Notify Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let code = invite["code"].as_str().context("code")?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &format!("/api/v1/invites/{code}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, me) = ctx
+        .request_json_as(&member, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let member_id = me["id"].as_str().context("member id")?.to_string();
+
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let mention_count = || async {
+        let (_, states) = ctx
+            .request_json_as(&member, Method::GET, "/api/v1/users/@me/read-states", None)
+            .await?;
+        anyhow::Ok(
+            states
+                .as_array()
+                .context("read states")?
+                .iter()
+                .find(|s| s["channel_id"] == channel_id.as_str())
+                .and_then(|s| s["mention_count"].as_i64())
+                .unwrap_or(0),
+        )
+    };
+    let post = |content: String| {
+        let messages_path = messages_path.clone();
+        let ctx = &ctx;
+        async move {
+            let (status, body) = ctx
+                .request_json(
+                    Method::POST,
+                    &messages_path,
+                    Some(json!({ "content": content })),
+                )
+                .await?;
+            assert_eq!(status, StatusCode::CREATED, "{body}");
+            anyhow::Ok(())
+        }
+    };
+
+    let run_jobs = || async {
+        paracord_core::jobs::run_pending_jobs_once(
+            &ctx.state,
+            &paracord_api::jobs::job_registry(),
+            "test",
+            10,
+        )
+        .await
+    };
+
+    post(format!("hey <@{member_id}>")).await?;
+    post("@everyone standup".to_string()).await?;
+    // `@everyone` is counted by the fan-out job, not during the send.
+    assert_eq!(mention_count().await?, 1);
+    assert_eq!(run_jobs().await, 1);
+    assert_eq!(mention_count().await?, 2);
+
+    // A retried fan-out doesn't count anyone twice.
+    let (_, latest) = ctx
+        .request_json(Method::GET, &format!("{messages_path}?limit=1"), None)
+        .await?;
+    let everyone_id = latest[0]["id"].as_str().context("message id")?;
+    paracord_core::jobs::enqueue(
+        &ctx.db,
+        paracord_api::jobs::JOB_MENTION_FANOUT,
+        &json!({ "message_id": everyone_id }),
+        paracord_core::jobs::JobOptions::default(),
+    )
+    .await?;
+    assert_eq!(run_jobs().await, 1);
+    assert_eq!(mention_count().await?, 2);
+
+    let settings_path = format!("/api/v1/users/@me/guilds/{guild_id}/notification-settings");
+    let (status, settings) = ctx
+        .request_json_as(
+            &member,
+            Method::PATCH,
+            &settings_path,
+            Some(json!({ "suppress_everyone": true, "level": "mentions" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "{settings}");
+    assert_eq!(settings["level"], "mentions");
+    post("@everyone again".to_string()).await?;
+    post(format!("<@{member_id}> ping")).await?;
+    run_jobs().await;
+    assert_eq!(mention_count().await?, 3);
+
+    let (status, settings) = ctx
+        .request_json_as(
+            &member,
+            Method::PATCH,
+            &settings_path,
+            Some(json!({ "channel_overrides": [{ "channel_id": channel_id, "muted": true }] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(settings["channel_overrides"][0]["muted"], true);
+    post(format!("<@{member_id}> muted")).await?;
+    assert_eq!(mention_count().await?, 3);
+
+    let (_, settings) = ctx
+        .request_json_as(
+            &member,
+            Method::PATCH,
+            &settings_path,
+            Some(json!({ "channel_overrides": [{ "channel_id": channel_id }] })),
+        )
+        .await?;
+    assert_eq!(
+        settings["channel_overrides"].as_array().map(Vec::len),
+        Some(0)
+    );
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::PATCH,
+            &settings_path,
+            Some(json!({ "level": "loud" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn mentions_queue_web_push_for_offline_subscribers() -> anyhow::Result<()> {
+    let mut ctx = TestContext::new().await?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            "/api/v1/users/@me/push-subscriptions",
+            Some(json!({ "endpoint": "https://push.example.com/a", "keys": { "p256dh": "x", "auth": "y" } })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    ctx.state.config.web_push_public_key = Some("test-vapid-key".to_string());
+    ctx.app = paracord_api::build_router().with_state(ctx.state.clone());
+    let guild_id = create_guild(&ctx, "Push Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let code = invite["code"].as_str().context("code")?;
+    ctx.request_json_as(
+        &member,
+        Method::POST,
+        &format!("/api/v1/invites/{code}"),
+        None,
+    )
+    .await?;
+    let (_, me) = ctx
+        .request_json_as(&member, Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let member_id = me["id"].as_str().context("member id")?.to_string();
+
+    let mut p256dh = vec![0x04_u8];
+    p256dh.extend([9_u8; 64]);
+    let subscription = json!({
+        "endpoint": "https://push.example.com/send/abc",
+        "keys": {
+            "p256dh": URL_SAFE_NO_PAD.encode(&p256dh),
+            "auth": URL_SAFE_NO_PAD.encode([1_u8; 16]),
+        },
+    });
+    let (status, created) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            "/api/v1/users/@me/push-subscriptions",
+            Some(subscription.clone()),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "{created}");
+    let (_, listed) = ctx
+        .request_json_as(
+            &member,
+            Method::GET,
+            "/api/v1/users/@me/push-subscriptions",
+            None,
+        )
+        .await?;
+    assert_eq!(listed["vapid_public_key"], "test-vapid-key");
+    assert_eq!(listed["subscriptions"].as_array().map(Vec::len), Some(1));
+
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": format!("hey <@{member_id}>") })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "no mention" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let now = Utc::now();
+    let due = paracord_db::push_subscriptions::claim_due_deliveries(
+        &ctx.db,
+        now + Duration::seconds(5),
+        now + Duration::minutes(2),
+        10,
+    )
+    .await?;
+    assert_eq!(due.len(), 1);
+    let payload: Value = serde_json::from_str(&due[0].payload)?;
+    assert_eq!(payload["channel_id"], channel_id.as_str());
+    assert!(payload["body"]
+        .as_str()
+        .is_some_and(|body| body.contains("hey")));
+
+    // A fan-out whose push fails counts nobody, and its retry both counts
+    // and pushes.
+    let mention_count = || async {
+        let (_, states) = ctx
+            .request_json_as(&member, Method::GET, "/api/v1/users/@me/read-states", None)
+            .await?;
+        anyhow::Ok(
+            states
+                .as_array()
+                .context("read states")?
+                .iter()
+                .find(|s| s["channel_id"] == channel_id.as_str())
+                .and_then(|s| s["mention_count"].as_i64())
+                .unwrap_or(0),
+        )
+    };
+    let registry = paracord_api::jobs::job_registry();
+    sqlx::query(
+        "CREATE TRIGGER fail_push_delivery BEFORE INSERT ON push_deliveries
+         BEGIN SELECT RAISE(ABORT, 'simulated push failure'); END",
+    )
+    .execute(&ctx.db)
+    .await?;
+    let (status, _) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": "@everyone lunch" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await,
+        1
+    );
+    assert_eq!(mention_count().await?, 1);
+
+    sqlx::query("DROP TRIGGER fail_push_delivery")
+        .execute(&ctx.db)
+        .await?;
+    sqlx::query(
+        "UPDATE background_jobs SET run_at = '2020-01-01 00:00:00' WHERE status = 'pending'",
+    )
+    .execute(&ctx.db)
+    .await?;
+    assert_eq!(
+        paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await,
+        1
+    );
+    assert_eq!(mention_count().await?, 2);
+    let due = paracord_db::push_subscriptions::claim_due_deliveries(
+        &ctx.db,
+        now + Duration::seconds(5),
+        now + Duration::minutes(2),
+        10,
+    )
+    .await?;
+    assert_eq!(due.len(), 1);
+    let payload: Value = serde_json::from_str(&due[0].payload)?;
+    assert!(payload["body"]
+        .as_str()
+        .is_some_and(|body| body.contains("lunch")));
+
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::DELETE,
+            "/api/v1/users/@me/push-subscriptions",
+            Some(json!({ "endpoint": subscription["endpoint"] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(paracord_db::push_subscriptions::claim_due_deliveries(
+        &ctx.db,
+        now + Duration::minutes(5),
+        now + Duration::minutes(6),
+        10,
+    )
+    .await?
+    .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn image_uploads_get_thumbnails_and_blurhash() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Thumb Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "photos").await?;
+    let (status, uploaded) = upload_attachment(&ctx, &channel_id, &sample_png(false)?).await?;
+    assert_eq!(status, StatusCode::CREATED, "{uploaded}");
+    let attachment_id: i64 = uploaded["id"].as_str().context("id")?.parse()?;
+
+    // Thumbnails are generated off the request path.
+    let mut attachment = None;
+    for _ in 0..100 {
+        let row = paracord_db::attachments::get_attachment(&ctx.db, attachment_id)
+            .await?
+            .context("attachment")?;
+        if row.thumbnail_format.is_some() {
+            attachment = Some(row);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let attachment = attachment.context("thumbnails were not generated")?;
+    assert_eq!((attachment.width, attachment.height), (Some(64), Some(48)));
+    assert_eq!(attachment.thumbnail_format.as_deref(), Some("jpeg"));
+
+    let (status, message) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/messages"),
+            Some(json!({ "content": "", "attachment_ids": [attachment_id.to_string()] })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "{message}");
+    let listed = &message["attachments"][0];
+    assert_eq!(listed["width"], 64);
+    assert!(listed["blurhash"]
+        .as_str()
+        .is_some_and(|hash| !hash.is_empty()));
+    let thumbnail_url = listed["thumbnail_url"].as_str().context("thumbnail_url")?;
+    assert_eq!(
+        thumbnail_url,
+        format!("/api/v1/attachments/{attachment_id}/thumbnail")
+    );
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("{thumbnail_url}?size=100"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.as_bytes()),
+        Some(&b"image/jpeg"[..])
+    );
+    let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+    let thumbnail = image::load_from_memory(&bytes)?;
+    assert_eq!((thumbnail.width(), thumbnail.height()), (64, 48));
+
+    let (status, _) = ctx
+        .request_json(
+            Method::DELETE,
+            &format!("/api/v1/attachments/{attachment_id}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let key = paracord_media::thumbnails::thumbnail_storage_key(
+        attachment_id,
+        160,
+        paracord_media::thumbnails::ThumbnailFormat::Jpeg,
+    );
+    assert!(ctx.state.storage_backend.retrieve(&key).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn links_unfurl_into_message_embeds() -> anyhow::Result<()> {
+    let mut ctx = TestContext::new().await?;
+    ctx.state.config.link_previews_enabled = true;
+    ctx.app = paracord_api::build_router().with_state(ctx.state.clone());
+    let guild_id = create_guild(&ctx, "Preview Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "links").await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let mut events =
+        ctx.state
+            .event_bus
+            .register_session("previews", user_id, &[guild_id.parse()?]);
+
+    // Seed the cache so the job never has to reach the network.
+    let now = chrono::Utc::now();
+    let cached = json!({
+        "title": "An Article",
+        "description": "Worth reading",
+        "url": "https://example.com/article",
+        "color": null,
+        "timestamp": null,
+        "footer": null,
+        "image": null,
+        "thumbnail": null,
+        "video": null,
+        "provider": { "name": "Example", "url": null },
+        "author": null,
+        "fields": [],
+    });
+    paracord_db::link_previews::upsert_preview(
+        &ctx.db,
+        "https://example.com/article",
+        Some(&cached.to_string()),
+        now,
+    )
+    .await?;
+    paracord_db::link_previews::upsert_preview(&ctx.db, "https://example.org/empty", None, now)
+        .await?;
+
+    let messages_path = format!("/api/v1/channels/{channel_id}/messages");
+    let (status, sent) = ctx
+        .request_json(
+            Method::POST,
+            &messages_path,
+            Some(json!({
+                "content": "read https://example.com/article, https://example.org/empty and <https://example.net/quiet>"
+            })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED, "unexpected payload: {sent}");
+    assert_eq!(sent["embeds"], json!([]));
+    let message_id = sent["id"].as_str().context("message id")?;
+    while events.try_recv().is_ok() {}
+
+    let registry = paracord_api::jobs::job_registry();
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let event = events.try_recv()?;
+    assert_eq!(event.event_type, "MESSAGE_UPDATE");
+    assert_eq!(event.payload["id"], message_id);
+    assert_eq!(event.payload["embeds"][0]["title"], "An Article");
+
+    let (_, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    let embeds = messages[0]["embeds"].as_array().context("embeds")?;
+    assert_eq!(embeds.len(), 1);
+    assert_eq!(embeds[0]["provider"]["name"], "Example");
+
+    // Editing the links away clears the embeds.
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("{messages_path}/{message_id}"),
+            Some(json!({ "content": "never mind" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    while events.try_recv().is_ok() {}
+    let ran = paracord_core::jobs::run_pending_jobs_once(&ctx.state, &registry, "test", 10).await;
+    assert_eq!(ran, 1);
+    let event = events.try_recv()?;
+    assert_eq!(event.payload["embeds"], json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn mod_log_mirrors_audit_entries_in_batches() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Mod Log Guild").await?;
+    let log_channel = create_text_channel(&ctx, &guild_id, "mod-log").await?;
+    let other_guild = create_guild(&ctx, "Other Guild").await?;
+    let foreign_channel = create_text_channel(&ctx, &other_guild, "general").await?;
+    let mod_log_path = format!("/api/v1/guilds/{guild_id}/mod-log");
+
+    let (status, settings) = ctx.request_json(Method::GET, &mod_log_path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(settings["channel_id"].is_null());
+    let (status, _) = ctx
+        .request_json(
+            Method::PUT,
+            &mod_log_path,
+            Some(json!({ "channel_id": foreign_channel })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, settings) = ctx
+        .request_json(
+            Method::PUT,
+            &mod_log_path,
+            Some(json!({ "channel_id": log_channel, "include_automod": false })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(settings["channel_id"], log_channel.as_str());
+    assert_eq!(settings["include_automod"], false);
+
+    let (status, role) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/guilds/{guild_id}/roles"),
+            Some(json!({ "name": "helpers" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    let role_id = role["id"].as_str().context("role id")?;
+    let (status, _) = ctx
+        .request_json(
+            Method::PATCH,
+            &format!("/api/v1/guilds/{guild_id}"),
+            Some(json!({ "name": "Renamed Guild" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    paracord_api::routes::mod_log::run_mod_log_flush_once(&ctx.state).await;
+    let messages_path = format!("/api/v1/channels/{log_channel}/messages");
+    let (_, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    let messages = messages.as_array().context("messages")?;
+    assert_eq!(messages.len(), 1, "lines should be batched: {messages:?}");
+    assert_eq!(messages[0]["type"], 8);
+    assert_eq!(messages[0]["author"]["id"], "-3");
+    let content = messages[0]["content"].as_str().context("content")?;
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2, "unexpected content: {content}");
+    assert!(lines[0].starts_with(&format!("**Role created**: <@&{role_id}>")));
+    assert!(lines[1].starts_with("**Server updated**"));
+
+    // Nothing is left to post, and turning the log off stops new lines.
+    let (status, _) = ctx
+        .request_json(Method::DELETE, &mod_log_path, None)
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    ctx.request_json(
+        Method::PATCH,
+        &format!("/api/v1/guilds/{guild_id}"),
+        Some(json!({ "name": "Quiet Guild" })),
+    )
+    .await?;
+    paracord_api::routes::mod_log::run_mod_log_flush_once(&ctx.state).await;
+    let (_, messages) = ctx.request_json(Method::GET, &messages_path, None).await?;
+    assert_eq!(messages.as_array().map(Vec::len), Some(1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_sends_are_rate_limited_per_user_with_headers() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Rate Limit Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let send = || {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/v1/channels/{channel_id}/messages"))
+            .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "content": "hello" }).to_string()))
+    };
+    let header_value = |response: &axum::response::Response, name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let first = ctx.app.clone().oneshot(send()?).await?;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert_eq!(
+        header_value(&first, "x-ratelimit-bucket").as_deref(),
+        Some("messages")
+    );
+    assert_eq!(
+        header_value(&first, "x-ratelimit-limit").as_deref(),
+        Some("30")
+    );
+    assert_eq!(
+        header_value(&first, "x-ratelimit-remaining").as_deref(),
+        Some("29")
+    );
+    let reset: i64 = header_value(&first, "x-ratelimit-reset")
+        .context("reset header")?
+        .parse()?;
+    assert!(reset >= Utc::now().timestamp());
+
+    let mut rejected = None;
+    for _ in 0..60 {
+        let response = ctx.app.clone().oneshot(send()?).await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            rejected = Some(response);
+            break;
+        }
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let rejected = rejected.context("message sends should be rate limited")?;
+    assert_eq!(
+        header_value(&rejected, "x-ratelimit-remaining").as_deref(),
+        Some("0")
+    );
+    let retry_after: u64 = header_value(&rejected, header::RETRY_AFTER.as_str())
+        .context("retry-after header")?
+        .parse()?;
+    assert!(retry_after >= 1);
+
+    // Other route classes keep their own budget.
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/channels/{channel_id}/messages"))
+        .header(header::AUTHORIZATION, format!("Bearer {}", ctx.token))
+        .body(Body::empty())?;
+    let response = ctx.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        header_value(&response, "x-ratelimit-bucket").as_deref(),
+        Some("api")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_widget_is_public_only_once_enabled() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Widget Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "welcome").await?;
+    let widget_path = format!("/api/v1/guilds/{guild_id}/widget");
+    let fetch_widget = || async {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/guilds/{guild_id}/widget.json"))
+            .body(Body::empty())?;
+        let response = ctx.app.clone().oneshot(request).await?;
+        let status = response.status();
+        let cache_control = response
+            .headers()
+            .get(header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let payload: Value = serde_json::from_slice(&body)?;
+        anyhow::Ok((status, cache_control, payload))
+    };
+
+    let (status, _, payload) = fetch_widget().await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(payload["code"], "WIDGET_DISABLED");
+
+    let (status, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            &widget_path,
+            Some(json!({ "enabled": true, "channel_id": channel_id })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {settings}");
+    assert_eq!(settings["enabled"], true);
+    let invite_code = settings["invite_code"]
+        .as_str()
+        .context("invite code")?
+        .to_string();
+
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    ctx.state.online_users.write().await.insert(user_id);
+
+    let (status, cache_control, widget) = fetch_widget().await?;
+    assert_eq!(status, StatusCode::OK, "unexpected payload: {widget}");
+    assert_eq!(cache_control.as_deref(), Some("public, max-age=60"));
+    assert_eq!(widget["name"], "Widget Guild");
+    assert_eq!(widget["member_count"], 1);
+    assert_eq!(widget["presence_count"], 1);
+    assert_eq!(widget["instant_invite"], format!("/invite/{invite_code}"));
+
+    // Toggling the widget keeps the same invite.
+    let (_, settings) = ctx
+        .request_json(
+            Method::PATCH,
+            &widget_path,
+            Some(json!({ "enabled": false })),
+        )
+        .await?;
+    assert_eq!(settings["invite_code"], invite_code.as_str());
+    let (status, _, _) = fetch_widget().await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn detached_gateway_session_buffers_events_until_resumed() -> anyhow::Result<()> {
+    use paracord_core::gateway_session::{self, GatewaySession};
+
+    let ctx = TestContext::new().await?;
+    let (status, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+
+    let session = GatewaySession::for_user(&ctx.state, user_id).await;
+    let session_id = session.session_id.clone();
+    let events =
+        ctx.state
+            .event_bus
+            .register_session(session_id.clone(), user_id, &session.guild_ids);
+    gateway_session::detach(&ctx.state, session, events);
+    assert!(gateway_session::reattach(&session_id, user_id + 1)
+        .await
+        .is_none());
+
+    for n in 0..3 {
+        ctx.state.event_bus.dispatch_to_users(
+            "NOTIFICATION_CREATE",
+            json!({ "n": n }),
+            vec![user_id],
+        );
+    }
+    // Give the detached session a chance to take the events off the bus.
+    for _ in 0..50 {
+        if gateway_session::replay_since(&session_id, user_id, 0)
+            .is_some_and(|(latest, _)| latest == 3)
+        {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let (session, _events) = gateway_session::reattach(&session_id, user_id)
+        .await
+        .context("detached session")?;
+    assert_eq!(session.sequence, 3);
+    let (_, missed) =
+        gateway_session::replay_since(&session_id, user_id, 1).context("replay buffer")?;
+    assert_eq!(
+        missed.iter().map(|d| d.sequence).collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+    assert!(gateway_session::reattach(&session_id, user_id)
+        .await
+        .is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn guild_presences_page_online_members() -> anyhow::Result<()> {
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Presence Guild").await?;
+    let (status, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+
+    let path = format!("/api/v1/guilds/{guild_id}/presences");
+    let (status, presences) = ctx.request_json(Method::GET, &path, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(presences, json!([]));
+
+    ctx.state.online_users.write().await.insert(user_id);
+    let (_, presences) = ctx.request_json(Method::GET, &path, None).await?;
+    assert_eq!(presences[0]["user_id"], user_id.to_string());
+    assert_eq!(presences[0]["status"], "online");
+
+    let (_, presences) = ctx
+        .request_json(Method::GET, &format!("{path}?after={user_id}"), None)
+        .await?;
+    assert_eq!(presences, json!([]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn member_list_subscription_syncs_ranges_and_streams_joins() -> anyhow::Result<()> {
+    use paracord_core::gateway_session::{self, GatewaySession};
+
+    let ctx = TestContext::new().await?;
+    let guild_id = create_guild(&ctx, "Lazy Guild").await?;
+    let channel_id = create_text_channel(&ctx, &guild_id, "general").await?;
+    let (_, me) = ctx
+        .request_json(Method::GET, "/api/v1/users/@me", None)
+        .await?;
+    let user_id: i64 = me["id"].as_str().context("user id")?.parse()?;
+    let gid: i64 = guild_id.parse()?;
+    let cid: i64 = channel_id.parse()?;
+
+    let mut session = GatewaySession::for_user(&ctx.state, user_id).await;
+    let mut events = ctx.state.event_bus.register_session(
+        session.session_id.clone(),
+        user_id,
+        &session.guild_ids,
+    );
+
+    ctx.state.online_users.write().await.insert(user_id);
+    let sync =
+        gateway_session::subscribe_member_list(&ctx.state, &mut session, gid, cid, &[(0, 99)])
+            .await
+            .context("sync")?;
+    assert_eq!(sync.event_type, "GUILD_MEMBER_LIST_UPDATE");
+    assert_eq!(sync.payload["member_count"], 1);
+    assert_eq!(sync.payload["online_count"], 1);
+    let op = &sync.payload["ops"][0];
+    assert_eq!(op["op"], "SYNC");
+    assert_eq!(op["items"][0]["user"]["id"], user_id.to_string());
+    assert_eq!(op["items"][0]["presence"]["status"], "online");
+
+    let (_, invite) = ctx
+        .request_json(
+            Method::POST,
+            &format!("/api/v1/channels/{channel_id}/invites"),
+            Some(json!({})),
+        )
+        .await?;
+    let member = create_authenticated_user_token(&ctx.db, &ctx.state.config.jwt_secret).await?;
+    let code = invite["code"].as_str().context("code")?;
+    let (status, _) = ctx
+        .request_json_as(
+            &member,
+            Method::POST,
+            &format!("/api/v1/invites/{code}"),
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let update = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let event = events.recv().await?;
+            if let Some(dispatch) =
+                gateway_session::prepare_dispatch(&ctx.state, &mut session, &event).await
+            {
+                if dispatch.event_type == "GUILD_MEMBER_LIST_UPDATE" {
+                    return anyhow::Ok(dispatch);
+                }
+            }
+        }
+    })
+    .await??;
+    // The offline newcomer goes after the online owner.
+    assert_eq!(update.payload["member_count"], 2);
+    assert_eq!(update.payload["ops"][0]["op"], "INSERT");
+    assert_eq!(update.payload["ops"][0]["index"], 1);
+    assert!(update.payload["ops"][0]["item"]["presence"].is_null());
+
+    // Unsubscribed sessions don't get list updates.
+    assert!(
+        gateway_session::subscribe_member_list(&ctx.state, &mut session, gid, cid, &[])
+            .await
+            .is_none()
+    );
+    let event = paracord_core::events::ServerEvent {
+        event_type: "GUILD_MEMBER_LIST_UPDATE".to_string(),
+        payload: Arc::new(json!({ "guild_id": guild_id })),
+        guild_id: Some(gid),
+        target_user_ids: None,
+        serialized_payload: None,
+    };
+    assert!(
+        gateway_session::prepare_dispatch(&ctx.state, &mut session, &event)
+            .await
+            .is_none()
+    );
     Ok(())
 }
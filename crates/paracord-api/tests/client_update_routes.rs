@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use paracord_core::{build_permission_cache, AppConfig, AppState, RuntimeSettings};
+use paracord_media::{
+    LiveKitConfig, LocalStorage, Storage, StorageConfig, StorageManager, VoiceManager,
+};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::sync::{Notify, RwLock};
+use tower::ServiceExt;
+
+struct TestHarness {
+    app: Router,
+    _storage_dir: TempDir,
+    _media_dir: TempDir,
+    _backup_dir: TempDir,
+}
+
+impl TestHarness {
+    async fn new(
+        client_update_manifest_path: Option<String>,
+        min_client_version: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let db = paracord_db::create_pool("sqlite::memory:", 1).await?;
+        paracord_db::run_migrations(&db).await?;
+
+        let storage_dir = tempfile::tempdir()?;
+        let media_dir = tempfile::tempdir()?;
+        let backup_dir = tempfile::tempdir()?;
+        let livekit = Arc::new(LiveKitConfig {
+            api_key: "lk-test-key".to_string(),
+            api_secret: "lk-test-secret".to_string(),
+            url: "ws://localhost:7880".to_string(),
+            http_url: "http://localhost:7880".to_string(),
+        });
+
+        let state = AppState {
+            db,
+            event_bus: paracord_core::events::EventBus::default(),
+            config: AppConfig {
+                jwt_secret: "integration-test-secret".to_string(),
+                jwt_expiry_seconds: 3600,
+                registration_enabled: true,
+                allow_username_login: false,
+                require_email: true,
+                storage_path: storage_dir.path().to_string_lossy().into_owned(),
+                max_upload_size: 10 * 1024 * 1024,
+                livekit_api_key: livekit.api_key.clone(),
+                livekit_api_secret: livekit.api_secret.clone(),
+                livekit_url: livekit.url.clone(),
+                livekit_http_url: livekit.http_url.clone(),
+                livekit_public_url: livekit.url.clone(),
+                livekit_available: false,
+                public_url: None,
+                media_storage_path: media_dir.path().to_string_lossy().into_owned(),
+                media_max_file_size: 10 * 1024 * 1024,
+                media_p2p_threshold: 1024 * 1024,
+                file_cryptor: None,
+                backup_dir: backup_dir.path().to_string_lossy().into_owned(),
+                database_url: "sqlite::memory:".to_string(),
+                federation_max_events_per_peer_per_minute: None,
+                federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
+                native_media_enabled: false,
+                native_media_port: 8443,
+                native_media_max_participants: 50,
+                native_media_e2ee_required: false,
+                max_guild_storage_quota: 0,
+                federation_file_cache_enabled: false,
+                federation_file_cache_max_size: 0,
+                federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path,
+                min_client_version,
+                web_push_public_key: None,
+                link_previews_enabled: false,
+            },
+            runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
+            voice: Arc::new(VoiceManager::new(livekit)),
+            storage: Arc::new(StorageManager::new(StorageConfig {
+                base_path: media_dir.path().to_path_buf(),
+                max_file_size: 10 * 1024 * 1024,
+                p2p_threshold: 1024 * 1024,
+                allowed_extensions: None,
+            })),
+            storage_backend: Arc::new(Storage::Local(LocalStorage::new(storage_dir.path()))),
+            shutdown: Arc::new(Notify::new()),
+            online_users: Arc::new(RwLock::new(HashSet::new())),
+            user_presences: Arc::new(RwLock::new(HashMap::new())),
+            permission_cache: build_permission_cache(),
+            federation_service: None,
+            member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
+            native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
+        };
+
+        let app = paracord_api::build_router().with_state(state);
+        Ok(Self {
+            app,
+            _storage_dir: storage_dir,
+            _media_dir: media_dir,
+            _backup_dir: backup_dir,
+        })
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+        client_version: Option<&str>,
+    ) -> anyhow::Result<(StatusCode, Value)> {
+        let mut builder = Request::builder().uri(path);
+        if let Some(version) = client_version {
+            builder = builder.header("X-Paracord-Client-Version", version);
+        }
+        let response = self
+            .app
+            .clone()
+            .oneshot(builder.body(Body::empty())?)
+            .await?;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let payload = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&body)
+                .unwrap_or_else(|_| json!({ "raw": String::from_utf8_lossy(&body) }))
+        };
+        Ok((status, payload))
+    }
+}
+
+fn write_manifest(dir: &TempDir) -> anyhow::Result<String> {
+    let path = dir.path().join("client-updates.json");
+    let manifest = json!({
+        "releases": [
+            {
+                "platform": "windows-x86_64",
+                "version": "1.3.2",
+                "url": "https://downloads.example/Paracord_1.3.2_x64.msi",
+                "signature": "sig-132"
+            },
+            {
+                "platform": "windows-x86_64",
+                "version": "1.10.0",
+                "url": "https://downloads.example/Paracord_1.10.0_x64.msi",
+                "signature": "sig-1100",
+                "notes": "Voice overlay and faster startup",
+                "pub_date": "2026-10-01T00:00:00Z"
+            },
+            {
+                "channel": "beta",
+                "platform": "windows-x86_64",
+                "version": "1.11.0-beta.1",
+                "url": "https://downloads.example/Paracord_1.11.0-beta.1_x64.msi",
+                "signature": "sig-beta"
+            },
+            {
+                "platform": "linux-x86_64-appimage",
+                "version": "1.9.0",
+                "url": "https://downloads.example/Paracord_1.9.0_amd64.AppImage",
+                "signature": "sig-190"
+            }
+        ]
+    });
+    std::fs::write(&path, manifest.to_string())?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[tokio::test]
+async fn update_manifest_lists_latest_release_per_platform() -> anyhow::Result<()> {
+    let manifest_dir = tempfile::tempdir()?;
+    let manifest_path = write_manifest(&manifest_dir)?;
+    let harness = TestHarness::new(Some(manifest_path), Some("1.3.0".to_string())).await?;
+
+    let (status, body) = harness.get("/api/v1/client/update-manifest", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["channel"], "stable");
+    assert_eq!(body["minimum_version"], "1.3.0");
+    assert_eq!(body["platforms"]["windows-x86_64"]["version"], "1.10.0");
+    assert_eq!(
+        body["platforms"]["linux-x86_64-appimage"]["version"],
+        "1.9.0"
+    );
+
+    let (status, body) = harness
+        .get(
+            "/api/v1/client/update-manifest?platform=windows-x86_64",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["version"], "1.10.0");
+    assert_eq!(body["signature"], "sig-1100");
+    assert_eq!(body["notes"], "Voice overlay and faster startup");
+
+    let (status, body) = harness
+        .get(
+            "/api/v1/client/update-manifest?channel=beta&platform=windows-x86_64",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["version"], "1.11.0-beta.1");
+
+    let (status, _) = harness
+        .get(
+            "/api/v1/client/update-manifest?platform=darwin-aarch64",
+            None,
+        )
+        .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_manifest_is_not_found_when_unconfigured() -> anyhow::Result<()> {
+    let harness = TestHarness::new(None, None).await?;
+    let (status, _) = harness.get("/api/v1/client/update-manifest", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn outdated_clients_are_rejected_with_structured_error() -> anyhow::Result<()> {
+    let harness = TestHarness::new(None, Some("1.3.0".to_string())).await?;
+
+    let (status, body) = harness.get("/api/v1/users/@me", Some("1.2.9")).await?;
+    assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+    assert_eq!(body["code"], "CLIENT_OUTDATED");
+    assert_eq!(body["details"]["minimum_version"], "1.3.0");
+    assert_eq!(
+        body["details"]["update_manifest_url"],
+        "/api/v1/client/update-manifest"
+    );
+
+    // Supported and unversioned clients fall through to normal auth.
+    let (status, _) = harness.get("/api/v1/users/@me", Some("1.3.0")).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = harness.get("/api/v1/users/@me", None).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
@@ -62,6 +62,7 @@ impl TestHarness {
                 database_url: "sqlite::memory:".to_string(),
                 federation_max_events_per_peer_per_minute: None,
                 federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
                 native_media_enabled: false,
                 native_media_port: 8443,
                 native_media_max_participants: 50,
@@ -70,6 +71,11 @@ impl TestHarness {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path: None,
+                min_client_version: None,
+                web_push_public_key: None,
+                link_previews_enabled: false,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -87,6 +93,8 @@ impl TestHarness {
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
             native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
             presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
         };
 
@@ -113,3 +121,29 @@ async fn auth_refresh_without_migrations_still_reaches_handler() -> anyhow::Resu
 
     Ok(())
 }
+
+#[tokio::test]
+async fn auth_routes_report_their_bucket_per_address() -> anyhow::Result<()> {
+    let harness = TestHarness::new_without_migrations().await?;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/auth/refresh")
+        .header("authorization", "Bearer not-a-token")
+        .body(Body::empty())?;
+    let response = harness.app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let headers = response.headers();
+    assert_eq!(
+        headers.get("x-ratelimit-bucket").map(|v| v.as_bytes()),
+        Some(&b"auth"[..])
+    );
+    assert_eq!(
+        headers.get("x-ratelimit-limit").map(|v| v.as_bytes()),
+        Some(&b"60"[..])
+    );
+    assert!(headers.contains_key("x-ratelimit-remaining"));
+    assert!(headers.contains_key("x-ratelimit-reset"));
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, OnceLock};
 
 use axum::{
     body::{to_bytes, Body},
@@ -12,7 +12,7 @@ use paracord_media::{
 };
 use serde_json::{json, Value};
 use tempfile::TempDir;
-use tokio::sync::{Notify, RwLock};
+use tokio::sync::{Mutex, Notify, RwLock};
 use tower::ServiceExt;
 
 fn env_lock() -> &'static Mutex<()> {
@@ -71,6 +71,7 @@ impl TestHarness {
                 database_url: "sqlite::memory:".to_string(),
                 federation_max_events_per_peer_per_minute: None,
                 federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
                 native_media_enabled: false,
                 native_media_port: 8443,
                 native_media_max_participants: 50,
@@ -79,6 +80,11 @@ impl TestHarness {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path: None,
+                min_client_version: None,
+                web_push_public_key: None,
+                link_previews_enabled: false,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -96,6 +102,8 @@ impl TestHarness {
             federation_service: None,
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
             native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
             presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
         };
 
@@ -125,7 +133,7 @@ impl TestHarness {
 
 #[tokio::test]
 async fn federation_read_rejects_unsigned_requests_without_token() -> anyhow::Result<()> {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
     std::env::remove_var("PARACORD_FEDERATION_READ_TOKEN");
 
@@ -143,7 +151,7 @@ async fn federation_read_rejects_unsigned_requests_without_token() -> anyhow::Re
 
 #[tokio::test]
 async fn federation_read_accepts_configured_token() -> anyhow::Result<()> {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
     std::env::set_var("PARACORD_FEDERATION_READ_TOKEN", "token-123");
 
@@ -164,7 +172,7 @@ async fn federation_read_accepts_configured_token() -> anyhow::Result<()> {
 
 #[tokio::test]
 async fn federation_media_token_requires_existing_room_membership() -> anyhow::Result<()> {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
 
     let harness = TestHarness::new(true).await?;
@@ -258,7 +266,7 @@ async fn federation_media_token_requires_existing_room_membership() -> anyhow::R
 
 #[tokio::test]
 async fn federation_message_ingest_materializes_missing_space_and_channel() -> anyhow::Result<()> {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
 
     let harness = TestHarness::new(true).await?;
@@ -319,6 +327,7 @@ async fn federation_message_ingest_materializes_missing_space_and_channel() -> a
         depth: chrono::Utc::now().timestamp_millis(),
         state_key: None,
         signatures: json!({}),
+        signature_version: paracord_federation::SIGNATURE_VERSION_CANONICAL,
     };
     let payload_sig = paracord_federation::signing::sign(
         &signing_key,
@@ -389,7 +398,7 @@ async fn federation_message_ingest_materializes_missing_space_and_channel() -> a
 
 #[tokio::test]
 async fn federation_ingest_does_not_collide_with_existing_local_ids() -> anyhow::Result<()> {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
 
     let harness = TestHarness::new(true).await?;
@@ -471,6 +480,7 @@ async fn federation_ingest_does_not_collide_with_existing_local_ids() -> anyhow:
         depth: chrono::Utc::now().timestamp_millis(),
         state_key: None,
         signatures: json!({}),
+        signature_version: paracord_federation::SIGNATURE_VERSION_CANONICAL,
     };
     let payload_sig = paracord_federation::signing::sign(
         &signing_key,
@@ -546,7 +556,7 @@ async fn federation_ingest_does_not_collide_with_existing_local_ids() -> anyhow:
 #[tokio::test]
 async fn federation_room_namespace_mapping_is_used_even_when_sender_differs() -> anyhow::Result<()>
 {
-    let _guard = env_lock().lock().unwrap_or_else(|e| e.into_inner());
+    let _guard = env_lock().lock().await;
     std::env::set_var("PARACORD_FEDERATION_ENABLED", "true");
 
     let harness = TestHarness::new(true).await?;
@@ -654,6 +664,7 @@ async fn federation_room_namespace_mapping_is_used_even_when_sender_differs() ->
         depth: chrono::Utc::now().timestamp_millis(),
         state_key: None,
         signatures: json!({}),
+        signature_version: paracord_federation::SIGNATURE_VERSION_CANONICAL,
     };
     let payload_sig = paracord_federation::signing::sign(
         &signing_key,
@@ -71,6 +71,7 @@ impl VoiceTestContext {
                 database_url: "sqlite::memory:".to_string(),
                 federation_max_events_per_peer_per_minute: None,
                 federation_max_user_creates_per_peer_per_hour: None,
+                federation_max_bytes_per_peer_per_hour: None,
                 native_media_enabled,
                 native_media_port: 8443,
                 native_media_max_participants: 50,
@@ -79,6 +80,11 @@ impl VoiceTestContext {
                 federation_file_cache_enabled: false,
                 federation_file_cache_max_size: 0,
                 federation_file_cache_ttl_hours: 0,
+                video_transcoding_enabled: false,
+                client_update_manifest_path: None,
+                min_client_version: None,
+                web_push_public_key: None,
+                link_previews_enabled: false,
             },
             runtime: Arc::new(RwLock::new(RuntimeSettings::default())),
             voice: Arc::new(VoiceManager::new(livekit)),
@@ -97,6 +103,8 @@ impl VoiceTestContext {
             member_index: Arc::new(paracord_core::member_index::MemberIndex::empty()),
             presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
             native_media: None,
+            event_log: None,
+            transfer_limiter: Default::default(),
         };
 
         paracord_api::install_http_rate_limiter();
@@ -498,3 +506,29 @@ async fn v2_leave_ignores_stale_session_id() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn voice_stack_rejects_unknown_stacks() -> anyhow::Result<()> {
+    let ctx = VoiceTestContext::new(true, true).await?;
+    let (_guild_id, channel_id) = create_guild_and_voice_channel(&ctx).await?;
+
+    let (status, payload) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/voice-stack"),
+            Some(json!({ "stack": "bridged" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "bridged stack: {payload}");
+
+    let (status, payload) = ctx
+        .request_json(
+            Method::PUT,
+            &format!("/api/v1/channels/{channel_id}/voice-stack"),
+            Some(json!({ "stack": "livekit" })),
+        )
+        .await?;
+    assert_eq!(status, StatusCode::OK, "livekit stack: {payload}");
+
+    Ok(())
+}
@@ -54,6 +54,13 @@ pub fn list_input_devices() -> Result<Vec<AudioInputDevice>, CaptureError> {
     Ok(devices)
 }
 
+/// Name of the system default input device, if there is one.
+pub fn default_input_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_input_device()
+        .and_then(|device| device.name().ok())
+}
+
 /// Handle to a running audio capture session.
 /// Dropping this stops the capture.
 pub struct AudioCapture {
@@ -28,6 +28,35 @@ pub enum PlaybackError {
     Resampler(String),
 }
 
+/// Information about an available audio output device.
+#[derive(Debug, Clone)]
+pub struct AudioOutputDevice {
+    /// Human-readable device name.
+    pub name: String,
+    /// Index for selection (used internally).
+    pub index: usize,
+}
+
+/// Enumerate available audio output devices.
+pub fn list_output_devices() -> Result<Vec<AudioOutputDevice>, PlaybackError> {
+    let host = cpal::default_host();
+    let mut devices = Vec::new();
+
+    for (index, device) in host.output_devices()?.enumerate() {
+        let name = device.name().unwrap_or_else(|_| format!("Device {index}"));
+        devices.push(AudioOutputDevice { name, index });
+    }
+
+    Ok(devices)
+}
+
+/// Name of the system default output device, if there is one.
+pub fn default_output_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.name().ok())
+}
+
 /// Internal buffer for a single audio source.
 struct SourceBuffer {
     /// Ring buffer of PCM samples ready for playback (at device sample rate).
@@ -114,6 +143,17 @@ impl AudioPlayback {
         Self::start_from_device(device)
     }
 
+    /// Start playback on a specific device (by index from `list_output_devices`).
+    pub fn start_device(index: usize) -> Result<Self, PlaybackError> {
+        let host = cpal::default_host();
+        let device = host
+            .output_devices()?
+            .nth(index)
+            .ok_or(PlaybackError::NoOutputDevice)?;
+
+        Self::start_from_device(device)
+    }
+
     pub fn start_from_device(device: Device) -> Result<Self, PlaybackError> {
         let config = device.default_output_config()?;
         let device_sample_rate = config.sample_rate().0;
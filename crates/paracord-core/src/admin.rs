@@ -1,6 +1,7 @@
 use crate::error::CoreError;
 use crate::permissions;
 use crate::{is_admin, USER_FLAG_ADMIN};
+use chrono::{DateTime, Utc};
 use paracord_db::DbPool;
 use paracord_models::permissions::Permissions;
 use serde::Serialize;
@@ -138,13 +139,12 @@ pub async fn demote_from_admin(
     Ok(updated)
 }
 
-/// Force-delete a guild (server admin action, no permission checks).
-pub async fn admin_delete_guild(pool: &DbPool, guild_id: i64) -> Result<(), CoreError> {
-    paracord_db::guilds::get_guild(pool, guild_id)
+/// Force-delete a guild (server admin action, no permission checks). Like
+/// owner deletes it leaves a restorable tombstone; returns the deletion time.
+pub async fn admin_delete_guild(pool: &DbPool, guild_id: i64) -> Result<DateTime<Utc>, CoreError> {
+    paracord_db::tombstones::soft_delete_guild(pool, guild_id)
         .await?
-        .ok_or(CoreError::NotFound)?;
-    paracord_db::guilds::delete_guild(pool, guild_id).await?;
-    Ok(())
+        .ok_or(CoreError::NotFound)
 }
 
 /// Force-update a guild (server admin action, no permission checks).
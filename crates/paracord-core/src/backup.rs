@@ -267,7 +267,7 @@ pub fn backup_file_path(backup_dir: &str, name: &str) -> PathBuf {
 
 // ── Internal helpers ──────────────────────────────────────────────────────
 
-fn parse_sqlite_path(url: &str) -> Result<String, CoreError> {
+pub(crate) fn parse_sqlite_path(url: &str) -> Result<String, CoreError> {
     let path = url
         .strip_prefix("sqlite://")
         .or_else(|| url.strip_prefix("sqlite:"))
@@ -0,0 +1,601 @@
+//! Recurring scheduled events and the per-guild iCalendar feed.
+//!
+//! Only a subset of RFC 5545 RRULE is supported: `FREQ=DAILY|WEEKLY|MONTHLY`
+//! with optional `INTERVAL`, `COUNT` or `UNTIL`, and `BYDAY` (weekly only).
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use paracord_db::scheduled_events::ScheduledEventRow;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::auth::AuthError;
+
+const MAX_INTERVAL: u32 = 365;
+const MAX_COUNT: u32 = 1000;
+/// Upper bound on recurrence periods walked while expanding a rule.
+const MAX_PERIODS: u32 = 10_000;
+const CALENDAR_TOKEN_AUDIENCE: &str = "paracord-calendar";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RecurrenceError {
+    #[error("missing FREQ")]
+    MissingFrequency,
+    #[error("unsupported FREQ {0}")]
+    UnsupportedFrequency(String),
+    #[error("unsupported rule part {0}")]
+    UnsupportedPart(String),
+    #[error("invalid value for {0}")]
+    InvalidValue(&'static str),
+    #[error("COUNT and UNTIL are mutually exclusive")]
+    CountAndUntil,
+    #[error("BYDAY is only supported with FREQ=WEEKLY")]
+    ByDayRequiresWeekly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+    /// Weekdays for weekly rules, sorted Monday first. Empty means the
+    /// weekday of the first occurrence.
+    pub by_day: Vec<Weekday>,
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_until(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Some(dt.and_utc());
+    }
+    // A bare date includes the whole day.
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|dt| dt.and_utc())
+}
+
+impl RecurrenceRule {
+    /// Parse an RRULE value, with or without the `RRULE:` prefix.
+    pub fn parse(raw: &str) -> Result<Self, RecurrenceError> {
+        let raw = raw.trim().to_ascii_uppercase();
+        let raw = raw.strip_prefix("RRULE:").unwrap_or(&raw);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+
+        for part in raw.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| RecurrenceError::UnsupportedPart(part.to_string()))?;
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        other => {
+                            return Err(RecurrenceError::UnsupportedFrequency(other.to_string()))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|v| (1..=MAX_INTERVAL).contains(v))
+                        .ok_or(RecurrenceError::InvalidValue("INTERVAL"))?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|v| (1..=MAX_COUNT).contains(v))
+                            .ok_or(RecurrenceError::InvalidValue("COUNT"))?,
+                    )
+                }
+                "UNTIL" => {
+                    until = Some(parse_until(value).ok_or(RecurrenceError::InvalidValue("UNTIL"))?)
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let day =
+                            parse_weekday(day).ok_or(RecurrenceError::InvalidValue("BYDAY"))?;
+                        if !by_day.contains(&day) {
+                            by_day.push(day);
+                        }
+                    }
+                }
+                "WKST" if value == "MO" => {}
+                other => return Err(RecurrenceError::UnsupportedPart(other.to_string())),
+            }
+        }
+
+        let frequency = frequency.ok_or(RecurrenceError::MissingFrequency)?;
+        if count.is_some() && until.is_some() {
+            return Err(RecurrenceError::CountAndUntil);
+        }
+        if !by_day.is_empty() && frequency != Frequency::Weekly {
+            return Err(RecurrenceError::ByDayRequiresWeekly);
+        }
+        by_day.sort_by_key(|day| day.num_days_from_monday());
+
+        Ok(Self {
+            frequency,
+            interval,
+            count,
+            until,
+            by_day,
+        })
+    }
+
+    /// Occurrence start times for an event first starting at `dtstart`.
+    pub fn occurrences(&self, dtstart: DateTime<Utc>) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            dtstart,
+            period: 0,
+            emitted: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self.by_day.iter().map(|day| weekday_code(*day)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the occurrences of a [`RecurrenceRule`], in order.
+pub struct Occurrences<'a> {
+    rule: &'a RecurrenceRule,
+    dtstart: DateTime<Utc>,
+    period: u32,
+    emitted: u32,
+    pending: VecDeque<DateTime<Utc>>,
+}
+
+impl Occurrences<'_> {
+    fn fill_period(&mut self) {
+        let step = i64::from(self.period) * i64::from(self.rule.interval);
+        match self.rule.frequency {
+            Frequency::Daily => self.pending.push_back(self.dtstart + Duration::days(step)),
+            Frequency::Weekly if self.rule.by_day.is_empty() => {
+                self.pending.push_back(self.dtstart + Duration::weeks(step))
+            }
+            Frequency::Weekly => {
+                let week_start = self.dtstart
+                    - Duration::days(i64::from(self.dtstart.weekday().num_days_from_monday()))
+                    + Duration::weeks(step);
+                for day in &self.rule.by_day {
+                    let candidate =
+                        week_start + Duration::days(i64::from(day.num_days_from_monday()));
+                    if candidate >= self.dtstart {
+                        self.pending.push_back(candidate);
+                    }
+                }
+            }
+            Frequency::Monthly => {
+                let months = i64::from(self.dtstart.month0()) + step;
+                let year = self.dtstart.year() + (months / 12) as i32;
+                let month = (months % 12) as u32 + 1;
+                // Months without this day (e.g. the 31st) are skipped, as in RFC 5545.
+                if let Some(date) = NaiveDate::from_ymd_opt(year, month, self.dtstart.day()) {
+                    self.pending
+                        .push_back(date.and_time(self.dtstart.time()).and_utc());
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rule.count.is_some_and(|count| self.emitted >= count) {
+                return None;
+            }
+            if let Some(next) = self.pending.pop_front() {
+                if self.rule.until.is_some_and(|until| next > until) {
+                    return None;
+                }
+                self.emitted += 1;
+                return Some(next);
+            }
+            if self.period >= MAX_PERIODS {
+                return None;
+            }
+            self.fill_period();
+            self.period += 1;
+        }
+    }
+}
+
+fn event_start(event: &ScheduledEventRow) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&event.scheduled_start)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Occurrences of `event` starting in `[from, to]`.
+pub fn event_occurrences_between(
+    event: &ScheduledEventRow,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let Some(start) = event_start(event) else {
+        return Vec::new();
+    };
+    match event
+        .recurrence_rule
+        .as_deref()
+        .and_then(|raw| RecurrenceRule::parse(raw).ok())
+    {
+        Some(rule) => rule
+            .occurrences(start)
+            .skip_while(|occurrence| *occurrence < from)
+            .take_while(|occurrence| *occurrence <= to)
+            .collect(),
+        None if start >= from && start <= to => vec![start],
+        None => Vec::new(),
+    }
+}
+
+/// The first occurrence of `event` starting at or after `now`.
+pub fn next_occurrence(event: &ScheduledEventRow, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start = event_start(event)?;
+    match event
+        .recurrence_rule
+        .as_deref()
+        .and_then(|raw| RecurrenceRule::parse(raw).ok())
+    {
+        Some(rule) => rule
+            .occurrences(start)
+            .find(|occurrence| *occurrence >= now),
+        None => (start >= now).then_some(start),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CalendarClaims {
+    sub: i64,
+    gid: i64,
+    aud: String,
+    iat: usize,
+}
+
+/// Sign a feed token binding `user_id` to one guild's calendar.
+///
+/// Feed tokens don't expire (calendar apps can't refresh them) and carry their
+/// own audience, so they are never accepted as session tokens and vice versa.
+/// Membership is re-checked every time the feed is fetched.
+pub fn create_calendar_token(
+    user_id: i64,
+    guild_id: i64,
+    secret: &str,
+) -> Result<String, AuthError> {
+    let claims = CalendarClaims {
+        sub: user_id,
+        gid: guild_id,
+        aud: CALENDAR_TOKEN_AUDIENCE.to_string(),
+        iat: Utc::now().timestamp() as usize,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| AuthError::Internal(e.to_string()))
+}
+
+/// Validate a feed token, returning `(user_id, guild_id)`.
+pub fn validate_calendar_token(token: &str, secret: &str) -> Result<(i64, i64), AuthError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = false;
+    validation.set_required_spec_claims(&["aud"]);
+    validation.set_audience(&[CALENDAR_TOKEN_AUDIENCE]);
+    decode::<CalendarClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &validation,
+    )
+    .map(|data| (data.claims.sub, data.claims.gid))
+    .map_err(|_| AuthError::InvalidToken)
+}
+
+fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append a content line, folded at 75 octets as RFC 5545 requires.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn format_ics_time(dt: DateTime<Utc>) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Render a guild's events as an iCalendar document. Events whose start time
+/// isn't RFC 3339 are left out.
+pub fn render_calendar(calendar_name: &str, host: &str, events: &[ScheduledEventRow]) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//Paracord//Scheduled Events//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(
+        &mut out,
+        &format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    );
+
+    for event in events {
+        let Some(start) = event_start(event) else {
+            continue;
+        };
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}@{}", event.id, host));
+        push_line(
+            &mut out,
+            &format!("DTSTAMP:{}", format_ics_time(event.created_at)),
+        );
+        push_line(&mut out, &format!("DTSTART:{}", format_ics_time(start)));
+        if let Some(end) = event
+            .scheduled_end
+            .as_deref()
+            .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+        {
+            push_line(
+                &mut out,
+                &format!("DTEND:{}", format_ics_time(end.with_timezone(&Utc))),
+            );
+        }
+        if let Some(rule) = event
+            .recurrence_rule
+            .as_deref()
+            .and_then(|raw| RecurrenceRule::parse(raw).ok())
+        {
+            push_line(&mut out, &format!("RRULE:{}", rule));
+        }
+        push_line(&mut out, &format!("SUMMARY:{}", escape_text(&event.name)));
+        if let Some(description) = event.description.as_deref() {
+            push_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(description)),
+            );
+        }
+        if let Some(location) = event.location.as_deref() {
+            push_line(&mut out, &format!("LOCATION:{}", escape_text(location)));
+        }
+        let status = if event.status == 4 {
+            "CANCELLED"
+        } else {
+            "CONFIRMED"
+        };
+        push_line(&mut out, &format!("STATUS:{}", status));
+        if let Some(minutes) = event.reminder_minutes {
+            push_line(&mut out, "BEGIN:VALARM");
+            push_line(&mut out, "ACTION:DISPLAY");
+            push_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(&event.name)),
+            );
+            push_line(&mut out, &format!("TRIGGER:-PT{}M", minutes));
+            push_line(&mut out, "END:VALARM");
+        }
+        push_line(&mut out, "END:VEVENT");
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    fn event(start: &str, rule: Option<&str>) -> ScheduledEventRow {
+        ScheduledEventRow {
+            id: 1,
+            guild_id: 2,
+            channel_id: None,
+            creator_id: 3,
+            name: "Game night, weekly; bring snacks".to_string(),
+            description: None,
+            scheduled_start: start.to_string(),
+            scheduled_end: None,
+            status: 1,
+            entity_type: 1,
+            location: None,
+            image_url: None,
+            recurrence_rule: rule.map(str::to_string),
+            reminder_minutes: Some(15),
+            created_at: at(2026, 1, 1, 0),
+        }
+    }
+
+    #[test]
+    fn parses_and_normalizes_rules() {
+        let rule =
+            RecurrenceRule::parse("rrule:freq=weekly;byday=we,mo;interval=2;count=4").unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Wed]);
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=4"
+        );
+
+        assert_eq!(
+            RecurrenceRule::parse("INTERVAL=2"),
+            Err(RecurrenceError::MissingFrequency)
+        );
+        assert_eq!(
+            RecurrenceRule::parse("FREQ=YEARLY"),
+            Err(RecurrenceError::UnsupportedFrequency("YEARLY".to_string()))
+        );
+        assert_eq!(
+            RecurrenceRule::parse("FREQ=DAILY;COUNT=2;UNTIL=20260301"),
+            Err(RecurrenceError::CountAndUntil)
+        );
+        assert_eq!(
+            RecurrenceRule::parse("FREQ=DAILY;BYDAY=MO"),
+            Err(RecurrenceError::ByDayRequiresWeekly)
+        );
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYHOUR=3").is_err());
+    }
+
+    #[test]
+    fn expands_weekly_byday_with_interval() {
+        // 2026-03-04 is a Wednesday.
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=4").unwrap();
+        let occurrences: Vec<_> = rule.occurrences(at(2026, 3, 4, 18)).collect();
+        assert_eq!(
+            occurrences,
+            vec![
+                at(2026, 3, 4, 18),
+                at(2026, 3, 16, 18),
+                at(2026, 3, 18, 18),
+                at(2026, 3, 30, 18),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_skips_short_months_and_stops_at_until() {
+        let rule = RecurrenceRule::parse("FREQ=MONTHLY;UNTIL=20260601T000000Z").unwrap();
+        let occurrences: Vec<_> = rule.occurrences(at(2026, 1, 31, 9)).collect();
+        assert_eq!(
+            occurrences,
+            vec![at(2026, 1, 31, 9), at(2026, 3, 31, 9), at(2026, 5, 31, 9)]
+        );
+    }
+
+    #[test]
+    fn finds_occurrences_in_window() {
+        let daily = event("2026-03-01T20:00:00Z", Some("FREQ=DAILY"));
+        assert_eq!(
+            event_occurrences_between(&daily, at(2026, 3, 10, 19), at(2026, 3, 10, 21)),
+            vec![at(2026, 3, 10, 20)]
+        );
+        assert_eq!(
+            next_occurrence(&daily, at(2026, 3, 10, 21)),
+            Some(at(2026, 3, 11, 20))
+        );
+
+        let once = event("2026-03-01T20:00:00Z", None);
+        assert!(next_occurrence(&once, at(2026, 3, 2, 0)).is_none());
+    }
+
+    #[test]
+    fn calendar_tokens_are_not_session_tokens() {
+        let token = create_calendar_token(5, 7, "secret").unwrap();
+        assert_eq!(validate_calendar_token(&token, "secret").unwrap(), (5, 7));
+        assert!(validate_calendar_token(&token, "other").is_err());
+        assert!(crate::auth::validate_token(&token, "secret").is_err());
+
+        let session = crate::auth::create_token(5, "secret", 3600).unwrap();
+        assert!(validate_calendar_token(&session, "secret").is_err());
+    }
+
+    #[test]
+    fn renders_escaped_folded_ics() {
+        let mut recurring = event("2026-03-01T20:00:00Z", Some("freq=weekly;byday=su"));
+        recurring.description = Some("x".repeat(120));
+        let ics = render_calendar("Guild", "chat.example.com", &[recurring]);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:1@chat.example.com\r\n"));
+        assert!(ics.contains("DTSTART:20260301T200000Z\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=SU\r\n"));
+        assert!(ics.contains("SUMMARY:Game night\\, weekly\\; bring snacks\r\n"));
+        assert!(ics.contains("TRIGGER:-PT15M\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -1,9 +1,60 @@
 use crate::error::CoreError;
+use crate::limits::{self, GuildLimit};
 use crate::permissions;
+use chrono::{DateTime, Utc};
 use paracord_db::DbPool;
 use paracord_models::permissions::Permissions;
 
-/// Create a channel in a guild, requires MANAGE_CHANNELS.
+/// Anyone with SEND_MESSAGES may post.
+pub const POSTING_MODE_OPEN: i16 = 0;
+/// Only members holding POST_OVERRIDE may post (announcement-style channels).
+pub const POSTING_MODE_READ_ONLY: i16 = 1;
+/// Only webhooks and bot accounts may post.
+pub const POSTING_MODE_WEBHOOK_ONLY: i16 = 2;
+
+pub fn is_valid_posting_mode(mode: i16) -> bool {
+    matches!(
+        mode,
+        POSTING_MODE_OPEN | POSTING_MODE_READ_ONLY | POSTING_MODE_WEBHOOK_ONLY
+    )
+}
+
+/// Archived channels are frozen: nothing can be posted or edited in them.
+pub fn ensure_not_archived(channel: &paracord_db::channels::ChannelRow) -> Result<(), CoreError> {
+    if channel.archived_at.is_some() {
+        return Err(CoreError::BadRequest("Channel is archived".into()));
+    }
+    Ok(())
+}
+
+/// Check a member post against the channel's posting mode. `perms` are the
+/// author's channel permissions. Webhook executions never go through here.
+pub async fn ensure_posting_allowed(
+    pool: &DbPool,
+    channel: &paracord_db::channels::ChannelRow,
+    perms: Permissions,
+    author_id: i64,
+) -> Result<(), CoreError> {
+    ensure_not_archived(channel)?;
+    match channel.posting_mode {
+        POSTING_MODE_READ_ONLY => {
+            permissions::require_permission(perms, Permissions::POST_OVERRIDE)
+        }
+        POSTING_MODE_WEBHOOK_ONLY => {
+            let author = paracord_db::users::get_user_by_id(pool, author_id)
+                .await?
+                .ok_or(CoreError::NotFound)?;
+            if !crate::is_bot(author.flags) {
+                return Err(CoreError::MissingPermission);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Create a channel in a guild, requires MANAGE_CHANNELS. `max_channels`
+/// caps the guild's channel count.
 #[allow(clippy::too_many_arguments)]
 pub async fn create_channel(
     pool: &DbPool,
@@ -14,6 +65,7 @@ pub async fn create_channel(
     channel_type: i16,
     parent_id: Option<i64>,
     required_role_ids: Option<&str>,
+    max_channels: Option<u32>,
 ) -> Result<paracord_db::channels::ChannelRow, CoreError> {
     let guild = paracord_db::guilds::get_guild(pool, guild_id)
         .await?
@@ -23,6 +75,9 @@ pub async fn create_channel(
     let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
+    let count = paracord_db::channels::count_guild_channels(pool, guild_id).await?;
+    limits::ensure_below(GuildLimit::Channels, max_channels, count)?;
+
     // Compute next position
     let channels = paracord_db::channels::get_guild_channels(pool, guild_id).await?;
     let position = channels.len() as i32;
@@ -42,12 +97,13 @@ pub async fn create_channel(
     Ok(channel)
 }
 
-/// Delete a channel, requires MANAGE_CHANNELS.
+/// Delete a channel, requires MANAGE_CHANNELS. Returns the channel, the
+/// deletion time and the ids of the threads deleted with it.
 pub async fn delete_channel(
     pool: &DbPool,
     channel_id: i64,
     user_id: i64,
-) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+) -> Result<(paracord_db::channels::ChannelRow, DateTime<Utc>, Vec<i64>), CoreError> {
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -64,11 +120,14 @@ pub async fn delete_channel(
     let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
-    paracord_db::channels::delete_channel(pool, channel_id).await?;
-    Ok(channel)
+    let (deleted_at, thread_ids) = paracord_db::tombstones::soft_delete_channel(pool, channel_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    Ok((channel, deleted_at, thread_ids))
 }
 
 /// Update a channel, requires MANAGE_CHANNELS.
+#[allow(clippy::too_many_arguments)]
 pub async fn update_channel(
     pool: &DbPool,
     channel_id: i64,
@@ -76,7 +135,12 @@ pub async fn update_channel(
     name: Option<&str>,
     topic: Option<&str>,
     required_role_ids: Option<&str>,
+    nsfw: Option<bool>,
+    posting_mode: Option<i16>,
 ) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+    if posting_mode.is_some_and(|mode| !is_valid_posting_mode(mode)) {
+        return Err(CoreError::BadRequest("Invalid posting_mode".into()));
+    }
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -93,8 +157,48 @@ pub async fn update_channel(
     let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
     permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
 
-    let updated =
-        paracord_db::channels::update_channel(pool, channel_id, name, topic, required_role_ids)
-            .await?;
+    let updated = paracord_db::channels::update_channel(
+        pool,
+        channel_id,
+        name,
+        topic,
+        required_role_ids,
+        nsfw,
+        posting_mode,
+    )
+    .await?;
     Ok(updated)
 }
+
+/// Archive or unarchive a guild channel, requires MANAGE_CHANNELS.
+pub async fn set_channel_archived(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+    archived: bool,
+) -> Result<paracord_db::channels::ChannelRow, CoreError> {
+    let channel = paracord_db::channels::get_channel(pool, channel_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+
+    let guild_id = channel
+        .guild_id()
+        .ok_or(CoreError::BadRequest("Cannot archive a DM channel".into()))?;
+    if channel.channel_type == 6 {
+        return Err(CoreError::BadRequest(
+            "Threads are archived through their thread metadata".into(),
+        ));
+    }
+
+    let guild = paracord_db::guilds::get_guild(pool, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+
+    let roles = paracord_db::roles::get_member_roles(pool, user_id, guild_id).await?;
+    let perms = permissions::compute_permissions_from_roles(&roles, guild.owner_id, user_id);
+    permissions::require_permission(perms, Permissions::MANAGE_CHANNELS)?;
+
+    paracord_db::channels::set_channel_archived(pool, channel_id, archived)
+        .await?
+        .ok_or(CoreError::NotFound)
+}
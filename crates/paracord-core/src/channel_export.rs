@@ -0,0 +1,275 @@
+//! Channel transcripts for moderation records and public archives.
+//!
+//! A transcript is either a JSON document or a standalone HTML page with no
+//! scripts or external resources. Message content is escaped, never rendered
+//! as markup.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use paracord_db::channels::ChannelRow;
+use paracord_db::DbPool;
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+
+const PAGE_SIZE: i64 = 500;
+/// Transcripts stop after this many messages; `truncated` is set when they do.
+pub const MAX_EXPORT_MESSAGES: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub struct TranscriptMessage {
+    pub id: i64,
+    pub author_id: i64,
+    pub author_name: String,
+    pub content: String,
+    pub pinned: bool,
+    pub reference_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub guild_name: String,
+    pub channel_name: String,
+    pub topic: Option<String>,
+    pub messages: Vec<TranscriptMessage>,
+    pub truncated: bool,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Load a channel's messages, oldest first, with author names resolved.
+pub async fn collect_transcript(
+    pool: &DbPool,
+    channel: &ChannelRow,
+) -> Result<Transcript, CoreError> {
+    let guild_name = match channel.guild_id() {
+        Some(guild_id) => paracord_db::guilds::get_guild(pool, guild_id)
+            .await?
+            .map(|guild| guild.name)
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+
+    let mut authors: HashMap<i64, String> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut cursor = 0_i64;
+    let mut truncated = false;
+    loop {
+        let page = paracord_db::messages::get_channel_messages(
+            pool,
+            channel.id,
+            None,
+            Some(cursor),
+            PAGE_SIZE,
+        )
+        .await?;
+        let page_len = page.len();
+        for message in page {
+            cursor = message.id;
+            if messages.len() >= MAX_EXPORT_MESSAGES {
+                truncated = true;
+                break;
+            }
+            let author_name = match authors.get(&message.author_id) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = paracord_db::users::get_user_by_id(pool, message.author_id)
+                        .await?
+                        .map(|user| user.display_name.unwrap_or(user.username))
+                        .unwrap_or_else(|| "Deleted User".to_string());
+                    authors.insert(message.author_id, name.clone());
+                    name
+                }
+            };
+            messages.push(TranscriptMessage {
+                id: message.id,
+                author_id: message.author_id,
+                author_name,
+                content: message.content.unwrap_or_default(),
+                pinned: message.pinned,
+                reference_id: message.reference_id,
+                created_at: message.created_at,
+                edited_at: message.edited_at,
+            });
+        }
+        if truncated || (page_len as i64) < PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(Transcript {
+        guild_name,
+        channel_name: channel.name.clone().unwrap_or_default(),
+        topic: channel.topic.clone(),
+        messages,
+        truncated,
+        exported_at: Utc::now(),
+    })
+}
+
+pub fn render_json(transcript: &Transcript) -> Vec<u8> {
+    let messages: Vec<Value> = transcript
+        .messages
+        .iter()
+        .map(|message| {
+            json!({
+                "id": message.id.to_string(),
+                "author_id": message.author_id.to_string(),
+                "author_name": message.author_name,
+                "content": message.content,
+                "pinned": message.pinned,
+                "reference_id": message.reference_id.map(|id| id.to_string()),
+                "created_at": message.created_at.to_rfc3339(),
+                "edited_at": message.edited_at.map(|at| at.to_rfc3339()),
+            })
+        })
+        .collect();
+    let document = json!({
+        "guild_name": transcript.guild_name,
+        "channel_name": transcript.channel_name,
+        "topic": transcript.topic,
+        "exported_at": transcript.exported_at.to_rfc3339(),
+        "truncated": transcript.truncated,
+        "messages": messages,
+    });
+    serde_json::to_vec_pretty(&document).unwrap_or_default()
+}
+
+fn escape_html(out: &mut String, value: &str) {
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+pub fn render_html(transcript: &Transcript) -> Vec<u8> {
+    let mut title = String::new();
+    escape_html(&mut title, &transcript.guild_name);
+    title.push_str(" / #");
+    escape_html(&mut title, &transcript.channel_name);
+
+    let mut out = String::with_capacity(1024 + transcript.messages.len() * 256);
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(
+        "<meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'none'; style-src 'unsafe-inline'\">\n",
+    );
+    let _ = writeln!(out, "<title>{title}</title>");
+    out.push_str(
+        "<style>body{font-family:sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem}\
+         .message{padding:.5rem 0;border-bottom:1px solid #ddd}\
+         .pinned{background:#fff8dc}.meta{color:#666;font-size:.85rem}\
+         .content{white-space:pre-wrap;word-wrap:break-word}</style>\n",
+    );
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{title}</h1>");
+    if let Some(topic) = transcript.topic.as_deref().filter(|t| !t.is_empty()) {
+        out.push_str("<p class=\"topic\">");
+        escape_html(&mut out, topic);
+        out.push_str("</p>\n");
+    }
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\">Exported {} &middot; {} message(s){}</p>",
+        transcript.exported_at.to_rfc3339(),
+        transcript.messages.len(),
+        if transcript.truncated {
+            " (truncated)"
+        } else {
+            ""
+        }
+    );
+
+    for message in &transcript.messages {
+        let _ = write!(
+            out,
+            "<div class=\"message{}\" id=\"m{}\">\n<div class=\"meta\"><strong>",
+            if message.pinned { " pinned" } else { "" },
+            message.id
+        );
+        escape_html(&mut out, &message.author_name);
+        let _ = write!(out, "</strong> {}", message.created_at.to_rfc3339());
+        if message.edited_at.is_some() {
+            out.push_str(" (edited)");
+        }
+        if message.pinned {
+            out.push_str(" &middot; pinned");
+        }
+        if let Some(reference_id) = message.reference_id {
+            let _ = write!(out, " &middot; <a href=\"#m{reference_id}\">reply</a>");
+        }
+        out.push_str("</div>\n<div class=\"content\">");
+        escape_html(&mut out, &message.content);
+        out.push_str("</div>\n</div>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        let at = DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Transcript {
+            guild_name: "Guild <One>".to_string(),
+            channel_name: "general".to_string(),
+            topic: None,
+            messages: vec![
+                TranscriptMessage {
+                    id: 1,
+                    author_id: 10,
+                    author_name: "alice".to_string(),
+                    content: "<script>alert(1)</script> & \"quotes\"".to_string(),
+                    pinned: true,
+                    reference_id: None,
+                    created_at: at,
+                    edited_at: None,
+                },
+                TranscriptMessage {
+                    id: 2,
+                    author_id: 11,
+                    author_name: "bob".to_string(),
+                    content: "reply".to_string(),
+                    pinned: false,
+                    reference_id: Some(1),
+                    created_at: at,
+                    edited_at: Some(at),
+                },
+            ],
+            truncated: false,
+            exported_at: at,
+        }
+    }
+
+    #[test]
+    fn html_escapes_content_and_links_replies() {
+        let html = String::from_utf8(render_html(&transcript())).unwrap();
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt; &amp; &quot;quotes&quot;"));
+        assert!(html.contains("<title>Guild &lt;One&gt; / #general</title>"));
+        assert!(html.contains("<div class=\"message pinned\" id=\"m1\">"));
+        assert!(html.contains("<a href=\"#m1\">reply</a>"));
+        assert!(html.contains("(edited)"));
+    }
+
+    #[test]
+    fn json_keeps_ids_as_strings() {
+        let value: Value = serde_json::from_slice(&render_json(&transcript())).unwrap();
+        assert_eq!(value["channel_name"], "general");
+        assert_eq!(value["messages"][0]["id"], "1");
+        assert_eq!(value["messages"][0]["pinned"], true);
+        assert_eq!(value["messages"][1]["reference_id"], "1");
+    }
+}
@@ -0,0 +1,453 @@
+//! Optional Redis backend for running several servers behind one load
+//! balancer.
+//!
+//! By default the event bus, presence and rate limits live in this process.
+//! Servers joined through [`Cluster::start`] share them through Redis:
+//!
+//! - events published on the [`EventBus`] are relayed over pub/sub and
+//!   delivered to the matching sessions on every server;
+//! - each server keeps the set of users connected to it, with each
+//!   session's presence, in Redis and merges the other servers' sets into
+//!   `online_users` and the [`PresenceManager`](crate::presence_manager::PresenceManager),
+//!   so a user with sessions on several servers shows one merged presence;
+//! - [`Cluster::take_token`] backs the HTTP rate limiter's buckets.
+//!
+//! Servers with the same key prefix form one cluster.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Notify};
+
+use crate::events::ServerEvent;
+use crate::presence_manager::{self, merge_sessions, SessionPresences};
+use crate::AppState;
+
+/// How often each server publishes the users connected to it.
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+/// A server that hasn't synced for this long is taken to be gone.
+const NODE_TTL_SECONDS: i64 = 15;
+/// Events waiting to be relayed. While Redis is unreachable, events beyond
+/// this only reach local sessions.
+const RELAY_QUEUE: usize = 4096;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const EVENT_PRESENCE_UPDATE: &str = "PRESENCE_UPDATE";
+
+/// Refill a token bucket stored as a hash and take a token if one is
+/// available. Uses the Redis clock so servers with skewed clocks agree.
+const TAKE_TOKEN_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+local tokens = tonumber(bucket[1]) or capacity
+local updated = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated) * rate)
+local allowed = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil((capacity - tokens) / rate * 1000) + 1000)
+return {allowed, tostring(tokens)}
+"#;
+
+/// An event as relayed between servers.
+#[derive(Serialize, Deserialize)]
+struct Envelope<'a> {
+    /// Server that published the event; it skips its own messages.
+    node: Cow<'a, str>,
+    event_type: Cow<'a, str>,
+    guild_id: Option<i64>,
+    target_user_ids: Option<Cow<'a, [i64]>>,
+    /// The serialized payload, passed through untouched.
+    payload: Cow<'a, str>,
+}
+
+impl<'a> Envelope<'a> {
+    fn from_event(node: &'a str, event: &'a ServerEvent) -> Self {
+        let payload = match &event.serialized_payload {
+            Some(serialized) => Cow::Borrowed(serialized.as_str()),
+            None => Cow::Owned(serde_json::to_string(&*event.payload).unwrap_or_default()),
+        };
+        Self {
+            node: Cow::Borrowed(node),
+            event_type: Cow::Borrowed(&event.event_type),
+            guild_id: event.guild_id,
+            target_user_ids: event.target_user_ids.as_deref().map(Cow::Borrowed),
+            payload,
+        }
+    }
+
+    fn into_event(self) -> Option<ServerEvent> {
+        let payload = serde_json::from_str(&self.payload).ok()?;
+        Some(ServerEvent {
+            event_type: self.event_type.into_owned(),
+            payload: Arc::new(payload),
+            guild_id: self.guild_id,
+            target_user_ids: self.target_user_ids.map(Cow::into_owned),
+            serialized_payload: Some(Arc::new(self.payload.into_owned())),
+        })
+    }
+}
+
+/// This server's membership in a Redis-backed cluster.
+pub struct Cluster {
+    node_id: String,
+    prefix: String,
+    client: redis::Client,
+    conn: ConnectionManager,
+    token_script: redis::Script,
+    /// Users merged in from other servers at the last sync.
+    remote_users: Mutex<HashSet<i64>>,
+}
+
+impl Cluster {
+    /// Connect to Redis at `url` and join `state` to the cluster named by
+    /// `prefix`: relay its events, share its presence and spawn the sync
+    /// tasks. Fails if Redis can't be reached.
+    pub async fn start(
+        state: &AppState,
+        url: &str,
+        prefix: &str,
+        shutdown: Arc<Notify>,
+    ) -> redis::RedisResult<Arc<Self>> {
+        let client = redis::Client::open(url)?;
+        let conn = ConnectionManager::new(client.clone()).await?;
+        let cluster = Arc::new(Self {
+            node_id: uuid::Uuid::new_v4().simple().to_string(),
+            prefix: prefix.trim_end_matches(':').to_string(),
+            client,
+            conn,
+            token_script: redis::Script::new(TAKE_TOKEN_SCRIPT),
+            remote_users: Mutex::new(HashSet::new()),
+        });
+
+        let (relay, outgoing) = mpsc::channel(RELAY_QUEUE);
+        if !state.event_bus.set_relay(relay) {
+            tracing::warn!("Event bus already relays to a cluster; not relaying again");
+        } else {
+            tokio::spawn(cluster.clone().run_publisher(outgoing, shutdown.clone()));
+        }
+        tokio::spawn(
+            cluster
+                .clone()
+                .run_subscriber(state.clone(), shutdown.clone()),
+        );
+        tokio::spawn(cluster.clone().run_presence_sync(state.clone(), shutdown));
+        tracing::info!(
+            node_id = %cluster.node_id,
+            prefix = %cluster.prefix,
+            "Joined Redis cluster"
+        );
+        Ok(cluster)
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    fn key(&self, suffix: &str) -> String {
+        format!("{}:{suffix}", self.prefix)
+    }
+
+    fn online_key(&self, node_id: &str) -> String {
+        self.key(&format!("online:{node_id}"))
+    }
+
+    fn presence_key(&self, node_id: &str) -> String {
+        self.key(&format!("presence:{node_id}"))
+    }
+
+    /// Refill the shared bucket `key` (`capacity` tokens, `rate` per
+    /// second) and take a token if one is available. Returns whether it was
+    /// taken and the tokens left.
+    pub async fn take_token(
+        &self,
+        key: &str,
+        capacity: f64,
+        rate: f64,
+    ) -> redis::RedisResult<(bool, f64)> {
+        let mut conn = self.conn.clone();
+        let (allowed, tokens): (i64, String) = self
+            .token_script
+            .key(self.key(&format!("ratelimit:{key}")))
+            .arg(capacity)
+            .arg(rate)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok((allowed == 1, tokens.parse().unwrap_or(0.0)))
+    }
+
+    async fn run_publisher(
+        self: Arc<Self>,
+        mut outgoing: mpsc::Receiver<ServerEvent>,
+        shutdown: Arc<Notify>,
+    ) {
+        let channel = self.key("events");
+        let mut conn = self.conn.clone();
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.notified() => break,
+                event = outgoing.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+            let message = match serde_json::to_string(&Envelope::from_event(&self.node_id, &event))
+            {
+                Ok(message) => message,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to encode {} for the cluster: {}",
+                        event.event_type,
+                        err
+                    );
+                    continue;
+                }
+            };
+            if let Err(err) = conn.publish::<_, _, ()>(&channel, message).await {
+                tracing::warn!(
+                    "Failed to relay {} to the cluster: {}",
+                    event.event_type,
+                    err
+                );
+            }
+        }
+    }
+
+    async fn run_subscriber(self: Arc<Self>, state: AppState, shutdown: Arc<Notify>) {
+        let channel = self.key("events");
+        loop {
+            let mut pubsub = match self.client.get_async_pubsub().await {
+                Ok(pubsub) => pubsub,
+                Err(err) => {
+                    tracing::warn!("Cluster subscriber failed to connect: {}", err);
+                    tokio::select! {
+                        _ = shutdown.notified() => return,
+                        _ = tokio::time::sleep(RECONNECT_DELAY) => continue,
+                    }
+                }
+            };
+            if let Err(err) = pubsub.subscribe(&channel).await {
+                tracing::warn!("Cluster subscriber failed to subscribe: {}", err);
+                tokio::select! {
+                    _ = shutdown.notified() => return,
+                    _ = tokio::time::sleep(RECONNECT_DELAY) => continue,
+                }
+            }
+            let mut messages = pubsub.on_message();
+            loop {
+                let message = tokio::select! {
+                    _ = shutdown.notified() => return,
+                    message = messages.next() => message,
+                };
+                let Some(message) = message else {
+                    tracing::warn!("Cluster subscriber disconnected; reconnecting");
+                    break;
+                };
+                let Ok(envelope) = serde_json::from_slice::<Envelope>(message.get_payload_bytes())
+                else {
+                    continue;
+                };
+                if envelope.node == self.node_id {
+                    continue;
+                }
+                if let Some(event) = envelope.into_event() {
+                    self.apply_remote_presence(&state, &event).await;
+                    state.event_bus.deliver_remote(event);
+                }
+            }
+        }
+    }
+
+    /// Keep `user_presences` current for users connected elsewhere between
+    /// syncs.
+    async fn apply_remote_presence(&self, state: &AppState, event: &ServerEvent) {
+        if event.event_type != EVENT_PRESENCE_UPDATE {
+            return;
+        }
+        let Some(user_id) = event
+            .payload
+            .get("user_id")
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.parse::<i64>().ok())
+        else {
+            return;
+        };
+        if state.event_bus.has_local_sessions(user_id) {
+            return;
+        }
+        state
+            .user_presences
+            .write()
+            .await
+            .insert(user_id, (*event.payload).clone());
+    }
+
+    async fn run_presence_sync(self: Arc<Self>, state: AppState, shutdown: Arc<Notify>) {
+        let mut ticker = tokio::time::interval(SYNC_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = ticker.tick() => {
+                    if let Err(err) = self.sync_presence(&state).await {
+                        tracing::warn!("Cluster presence sync failed: {}", err);
+                    }
+                }
+            }
+        }
+        if let Err(err) = self.leave().await {
+            tracing::warn!("Failed to leave the cluster cleanly: {}", err);
+        }
+    }
+
+    /// Publish the users connected here, then merge in everyone else's.
+    async fn sync_presence(&self, state: &AppState) -> redis::RedisResult<()> {
+        let mut conn = self.conn.clone();
+        let local_users = state.event_bus.local_user_ids();
+        let local_presences: Vec<(i64, String)> = state
+            .presence_manager
+            .local_sessions()
+            .into_iter()
+            .filter_map(|(id, sessions)| {
+                serde_json::to_string(&sessions)
+                    .ok()
+                    .map(|sessions| (id, sessions))
+            })
+            .collect();
+
+        let now = chrono::Utc::now().timestamp();
+        let nodes_key = self.key("nodes");
+        let online_key = self.online_key(&self.node_id);
+        let presence_key = self.presence_key(&self.node_id);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .del(&online_key)
+            .ignore()
+            .del(&presence_key)
+            .ignore();
+        if !local_users.is_empty() {
+            pipe.sadd(&online_key, &local_users)
+                .ignore()
+                .expire(&online_key, NODE_TTL_SECONDS)
+                .ignore();
+        }
+        if !local_presences.is_empty() {
+            pipe.hset_multiple(&presence_key, &local_presences)
+                .ignore()
+                .expire(&presence_key, NODE_TTL_SECONDS)
+                .ignore();
+        }
+        pipe.zadd(&nodes_key, &self.node_id, now)
+            .ignore()
+            .zrembyscore(&nodes_key, "-inf", now - NODE_TTL_SECONDS)
+            .ignore();
+        pipe.query_async::<()>(&mut conn).await?;
+
+        let nodes: Vec<String> = conn.zrange(&nodes_key, 0, -1).await?;
+        let mut remote_users = HashSet::new();
+        let mut remote_sessions: HashMap<i64, SessionPresences> = HashMap::new();
+        for node in nodes.iter().filter(|node| **node != self.node_id) {
+            let users: Vec<i64> = conn.smembers(self.online_key(node)).await?;
+            let presences: HashMap<i64, String> = conn.hgetall(self.presence_key(node)).await?;
+            remote_users.extend(users);
+            for (user_id, sessions) in presences {
+                if let Ok(sessions) = serde_json::from_str::<SessionPresences>(&sessions) {
+                    merge_sessions(remote_sessions.entry(user_id).or_default(), &sessions);
+                }
+            }
+        }
+
+        let previous = std::mem::replace(
+            &mut *self
+                .remote_users
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            remote_users.clone(),
+        );
+        {
+            let mut online = state.online_users.write().await;
+            for user_id in previous.difference(&remote_users) {
+                if !state.event_bus.has_local_sessions(*user_id) {
+                    online.remove(user_id);
+                }
+            }
+            online.extend(remote_users.iter().copied());
+        }
+
+        // Users connected here as well publish the merged presence, since
+        // the other servers only saw their own sessions when they did.
+        for user_id in state.presence_manager.set_remote_sessions(remote_sessions) {
+            let Some(presence) = presence_manager::refresh_presence(state, user_id).await else {
+                continue;
+            };
+            if !state.event_bus.has_local_sessions(user_id) {
+                continue;
+            }
+            let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, user_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|guild| guild.id)
+                .collect();
+            let recipients =
+                presence_manager::presence_recipients(state, user_id, &guild_ids).await;
+            state
+                .event_bus
+                .dispatch_to_users(EVENT_PRESENCE_UPDATE, presence, recipients);
+        }
+        Ok(())
+    }
+
+    /// Drop this server's sets so others stop counting its users at once.
+    async fn leave(&self) -> redis::RedisResult<()> {
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .del(self.online_key(&self.node_id))
+            .ignore()
+            .del(self.presence_key(&self.node_id))
+            .ignore()
+            .zrem(self.key("nodes"), &self.node_id)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelopes_round_trip_events() {
+        let payload = serde_json::json!({ "user_id": "7", "status": "idle" });
+        let event = ServerEvent {
+            event_type: EVENT_PRESENCE_UPDATE.to_string(),
+            payload: Arc::new(payload.clone()),
+            guild_id: None,
+            target_user_ids: Some(vec![1, 2]),
+            serialized_payload: None,
+        };
+        let encoded = serde_json::to_string(&Envelope::from_event("node-a", &event)).unwrap();
+        let envelope: Envelope = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(envelope.node, "node-a");
+        let decoded = envelope.into_event().unwrap();
+        assert_eq!(decoded.event_type, EVENT_PRESENCE_UPDATE);
+        assert_eq!(decoded.target_user_ids, Some(vec![1, 2]));
+        assert_eq!(*decoded.payload, payload);
+        assert_eq!(
+            decoded.serialized_payload.as_deref().map(String::as_str),
+            Some(payload.to_string().as_str())
+        );
+    }
+}
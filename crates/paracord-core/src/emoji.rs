@@ -0,0 +1,108 @@
+//! Custom emoji usage analytics.
+//!
+//! Custom emojis appear in message content and reactions as `<:name:id>` or
+//! `<a:name:id>` tokens. Each use of one of a guild's own emojis inside that
+//! guild is added to a daily rollup; tokens naming other guilds' emojis are
+//! ignored.
+
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+use paracord_db::emoji_usage::EmojiUsageKind;
+
+use crate::AppState;
+
+const MAX_EMOJI_NAME_LEN: usize = 32;
+
+/// Parse a single custom emoji token, returning the emoji id.
+pub fn parse_custom_emoji(token: &str) -> Option<i64> {
+    let inner = token.strip_prefix('<')?.strip_suffix('>')?;
+    let inner = inner.strip_prefix('a').unwrap_or(inner);
+    let (name, id) = inner.strip_prefix(':')?.split_once(':')?;
+    let valid_name = !name.is_empty()
+        && name.len() <= MAX_EMOJI_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    if !valid_name || id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    id.parse().ok()
+}
+
+/// Distinct custom emoji ids used in message content.
+pub fn custom_emoji_ids(content: &str) -> BTreeSet<i64> {
+    let mut ids = BTreeSet::new();
+    let mut rest = content;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        match rest.find('>') {
+            Some(end) => {
+                if let Some(id) = parse_custom_emoji(&rest[..=end]) {
+                    ids.insert(id);
+                    rest = &rest[end + 1..];
+                } else {
+                    rest = &rest[1..];
+                }
+            }
+            None => break,
+        }
+    }
+    ids
+}
+
+/// Count the custom emojis in a guild message. Each emoji counts once per
+/// message however often it repeats.
+pub async fn record_message_usage(state: &AppState, guild_id: i64, content: &str) {
+    let now = Utc::now();
+    for emoji_id in custom_emoji_ids(content) {
+        if let Err(err) = paracord_db::emoji_usage::record_emoji_usage(
+            &state.db,
+            guild_id,
+            emoji_id,
+            EmojiUsageKind::Message,
+            now,
+        )
+        .await
+        {
+            tracing::debug!("Failed to record usage of emoji {}: {}", emoji_id, err);
+        }
+    }
+}
+
+/// Count a new reaction in a guild if it is a custom emoji.
+pub async fn record_reaction_usage(state: &AppState, guild_id: i64, emoji: &str) {
+    let Some(emoji_id) = parse_custom_emoji(emoji) else {
+        return;
+    };
+    if let Err(err) = paracord_db::emoji_usage::record_emoji_usage(
+        &state.db,
+        guild_id,
+        emoji_id,
+        EmojiUsageKind::Reaction,
+        Utc::now(),
+    )
+    .await
+    {
+        tracing::debug!("Failed to record usage of emoji {}: {}", emoji_id, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_custom_emoji_tokens() {
+        assert_eq!(parse_custom_emoji("<:wave:123>"), Some(123));
+        assert_eq!(parse_custom_emoji("<a:party_1:456>"), Some(456));
+        assert_eq!(parse_custom_emoji("<:bad name:1>"), None);
+        assert_eq!(parse_custom_emoji("<:wave:>"), None);
+        assert_eq!(parse_custom_emoji("👍"), None);
+        assert_eq!(parse_custom_emoji("<@123>"), None);
+    }
+
+    #[test]
+    fn collects_distinct_ids_from_content() {
+        let ids = custom_emoji_ids("hi <:wave:1> <<a:dance:2>> <:wave:1> <:x:3 and <@5>");
+        assert_eq!(ids.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::limits::GuildLimit;
+
 #[derive(Debug, Error)]
 pub enum CoreError {
     #[error("not found")]
@@ -12,8 +14,12 @@ pub enum CoreError {
     BadRequest(String),
     #[error("conflict: {0}")]
     Conflict(String),
+    #[error("{limit:?} limit of {max} reached")]
+    LimitReached { limit: GuildLimit, max: u32 },
     #[error("database error: {0}")]
     Database(#[from] paracord_db::DbError),
     #[error("internal error: {0}")]
     Internal(String),
+    #[error("id generation failed: {0}")]
+    Snowflake(#[from] paracord_util::snowflake::SnowflakeError),
 }
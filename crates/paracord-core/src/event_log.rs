@@ -0,0 +1,359 @@
+//! Developer event log: a bounded, on-disk ring of recent gateway events.
+//!
+//! When enabled, every event published on the bus is redacted, numbered and
+//! appended to a JSON-lines file. Only the newest `capacity` events are kept;
+//! the file is rewritten once it holds twice that many lines. Admins can
+//! replay the ring and follow new events through the firehose endpoints.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, Mutex, Notify};
+
+use crate::events::ServerEvent;
+
+const REDACTED: &str = "[redacted]";
+
+/// Payload keys whose values never leave the server through the firehose.
+const SENSITIVE_KEYS: &[&str] = &[
+    "email",
+    "phone",
+    "ip_address",
+    "authorization",
+    "private_key",
+    "totp_secret",
+];
+
+/// Key fragments treated as sensitive wherever they appear in a key name.
+const SENSITIVE_KEY_FRAGMENTS: &[&str] = &["token", "secret", "password"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub seq: u64,
+    pub event_type: String,
+    pub guild_id: Option<i64>,
+    pub target_user_ids: Option<Vec<i64>>,
+    pub recorded_at: DateTime<Utc>,
+    pub payload: Value,
+}
+
+impl RecordedEvent {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "seq": self.seq,
+            "t": self.event_type,
+            "guild_id": self.guild_id.map(|id| id.to_string()),
+            "target_user_ids": self
+                .target_user_ids
+                .as_ref()
+                .map(|ids| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>()),
+            "recorded_at": self.recorded_at.to_rfc3339(),
+            "d": self.payload,
+        })
+    }
+}
+
+/// Which events a firehose or replay request wants.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Event types to include; `None` includes all of them.
+    pub event_types: Option<HashSet<String>>,
+    pub guild_id: Option<i64>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &RecordedEvent) -> bool {
+        if let Some(types) = &self.event_types {
+            if !types.contains(&event.event_type) {
+                return false;
+            }
+        }
+        match self.guild_id {
+            Some(guild_id) => event.guild_id == Some(guild_id),
+            None => true,
+        }
+    }
+}
+
+struct EventLogInner {
+    events: VecDeque<RecordedEvent>,
+    next_seq: u64,
+    lines_on_disk: usize,
+    file: Option<File>,
+}
+
+pub struct EventLog {
+    path: PathBuf,
+    capacity: usize,
+    inner: Mutex<EventLogInner>,
+    live: broadcast::Sender<Arc<RecordedEvent>>,
+}
+
+impl EventLog {
+    /// Open the ring at `path`, keeping the newest events already on disk.
+    pub async fn open(path: impl Into<PathBuf>, capacity: usize) -> std::io::Result<Self> {
+        let path = path.into();
+        let capacity = capacity.max(1);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut events = VecDeque::with_capacity(capacity);
+        let mut lines_on_disk = 0;
+        match tokio::fs::read_to_string(&path).await {
+            Ok(body) => {
+                for line in body.lines().filter(|line| !line.trim().is_empty()) {
+                    lines_on_disk += 1;
+                    if let Ok(event) = serde_json::from_str::<RecordedEvent>(line) {
+                        events.push_back(event);
+                        if events.len() > capacity {
+                            events.pop_front();
+                        }
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        let next_seq = events.back().map(|event| event.seq + 1).unwrap_or(1);
+        let file = open_append(&path).await?;
+        let (live, _) = broadcast::channel(1024);
+
+        Ok(Self {
+            path,
+            capacity,
+            inner: Mutex::new(EventLogInner {
+                events,
+                next_seq,
+                lines_on_disk,
+                file: Some(file),
+            }),
+            live,
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Follow events as they are recorded.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<RecordedEvent>> {
+        self.live.subscribe()
+    }
+
+    /// Sequence number of the newest recorded event, 0 when the ring is empty.
+    pub async fn latest_seq(&self) -> u64 {
+        self.inner.lock().await.next_seq - 1
+    }
+
+    /// Buffered events after `after_seq` that match `filter`, oldest first.
+    pub async fn replay(
+        &self,
+        filter: &EventFilter,
+        after_seq: u64,
+        limit: usize,
+    ) -> Vec<RecordedEvent> {
+        let inner = self.inner.lock().await;
+        inner
+            .events
+            .iter()
+            .filter(|event| event.seq > after_seq && filter.matches(event))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn record(&self, event: &ServerEvent) {
+        let mut payload = (*event.payload).clone();
+        redact(&mut payload);
+
+        let mut inner = self.inner.lock().await;
+        let recorded = RecordedEvent {
+            seq: inner.next_seq,
+            event_type: event.event_type.clone(),
+            guild_id: event.guild_id,
+            target_user_ids: event.target_user_ids.clone(),
+            recorded_at: Utc::now(),
+            payload,
+        };
+        inner.next_seq += 1;
+        inner.events.push_back(recorded.clone());
+        if inner.events.len() > self.capacity {
+            inner.events.pop_front();
+        }
+
+        if let Err(err) = self.persist(&mut inner, &recorded).await {
+            tracing::warn!("Failed to write event log {:?}: {}", self.path, err);
+            inner.file = None;
+        }
+        drop(inner);
+
+        let _ = self.live.send(Arc::new(recorded));
+    }
+
+    async fn persist(
+        &self,
+        inner: &mut EventLogInner,
+        recorded: &RecordedEvent,
+    ) -> std::io::Result<()> {
+        if inner.lines_on_disk >= self.capacity * 2 {
+            return self.compact(inner).await;
+        }
+        if inner.file.is_none() {
+            inner.file = Some(open_append(&self.path).await?);
+        }
+        let mut line = serde_json::to_string(recorded).map_err(std::io::Error::other)?;
+        line.push('\n');
+        if let Some(file) = inner.file.as_mut() {
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await?;
+        }
+        inner.lines_on_disk += 1;
+        Ok(())
+    }
+
+    /// Rewrite the file with just the buffered events.
+    async fn compact(&self, inner: &mut EventLogInner) -> std::io::Result<()> {
+        inner.file = None;
+        let mut body = String::new();
+        for event in &inner.events {
+            body.push_str(&serde_json::to_string(event).map_err(std::io::Error::other)?);
+            body.push('\n');
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, body).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        inner.lines_on_disk = inner.events.len();
+        inner.file = Some(open_append(&self.path).await?);
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.contains(&key.as_str())
+        || SENSITIVE_KEY_FRAGMENTS
+            .iter()
+            .any(|fragment| key.contains(fragment))
+}
+
+/// Replace secrets and personal details in an event payload.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    if !field.is_null() {
+                        *field = Value::String(REDACTED.to_string());
+                    }
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Record everything published on the bus until shutdown.
+pub fn spawn_recorder(
+    log: Arc<EventLog>,
+    mut receiver: broadcast::Receiver<ServerEvent>,
+    shutdown: Arc<Notify>,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                received = receiver.recv() => match received {
+                    Ok(event) => log.record(&event).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event log recorder skipped {} event(s)", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_event(event_type: &str, guild_id: Option<i64>, payload: Value) -> ServerEvent {
+        ServerEvent {
+            event_type: event_type.to_string(),
+            payload: Arc::new(payload),
+            guild_id,
+            target_user_ids: None,
+            serialized_payload: None,
+        }
+    }
+
+    #[test]
+    fn redact_hides_nested_secrets() {
+        let mut payload = json!({
+            "user": { "id": "1", "email": "a@example.com", "username": "alice" },
+            "webhook": { "token": "abc", "name": "hook" },
+            "sessions": [{ "refresh_token": "xyz", "ip_address": null }],
+        });
+        redact(&mut payload);
+        assert_eq!(payload["user"]["email"], REDACTED);
+        assert_eq!(payload["user"]["username"], "alice");
+        assert_eq!(payload["webhook"]["token"], REDACTED);
+        assert_eq!(payload["webhook"]["name"], "hook");
+        assert_eq!(payload["sessions"][0]["refresh_token"], REDACTED);
+        assert!(payload["sessions"][0]["ip_address"].is_null());
+    }
+
+    #[tokio::test]
+    async fn ring_keeps_newest_events_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let log = EventLog::open(&path, 3).await.unwrap();
+        for n in 0..8 {
+            log.record(&server_event(
+                "MESSAGE_CREATE",
+                Some(n % 2),
+                json!({ "n": n }),
+            ))
+            .await;
+        }
+        let all = log.replay(&EventFilter::default(), 0, 100).await;
+        assert_eq!(
+            all.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            [6, 7, 8]
+        );
+        drop(log);
+
+        let reopened = EventLog::open(&path, 3).await.unwrap();
+        assert_eq!(reopened.latest_seq().await, 8);
+        let filter = EventFilter {
+            event_types: None,
+            guild_id: Some(1),
+        };
+        let guild_events = reopened.replay(&filter, 6, 100).await;
+        assert_eq!(
+            guild_events
+                .iter()
+                .map(|event| event.seq)
+                .collect::<Vec<_>>(),
+            [8]
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use crate::observability;
 use dashmap::DashMap;
 use std::collections::HashSet;
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{broadcast, mpsc};
 
 #[derive(Debug, Clone)]
 pub struct ServerEvent {
@@ -16,6 +16,23 @@ pub struct ServerEvent {
     pub serialized_payload: Option<Arc<String>>,
 }
 
+/// Point-in-time view of the bus for the admin observability endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct EventBusSnapshot {
+    /// Per-session queue capacity; a session that falls this far behind lags.
+    pub session_capacity: usize,
+    pub sessions: usize,
+    pub users: usize,
+    /// Events queued for the bot system listener.
+    pub system_queue_depth: usize,
+    pub system_receivers: usize,
+    /// Events queued across all sessions, and the deepest single queue.
+    pub session_queue_total: usize,
+    pub session_queue_max: usize,
+    /// Sessions subscribed to each guild, busiest first.
+    pub guild_sessions: Vec<(i64, usize)>,
+}
+
 /// Broadcast-based event bus for real-time dispatch.
 #[derive(Clone)]
 pub struct EventBus {
@@ -24,6 +41,8 @@ pub struct EventBus {
     guild_sessions: Arc<DashMap<i64, HashSet<String>>>,
     user_sessions: Arc<DashMap<i64, HashSet<String>>>,
     system_sender: broadcast::Sender<ServerEvent>,
+    /// Forwards published events to other servers (see [`crate::cluster`]).
+    relay: Arc<OnceLock<mpsc::Sender<ServerEvent>>>,
 }
 
 #[derive(Clone)]
@@ -42,9 +61,16 @@ impl EventBus {
             guild_sessions: Arc::new(DashMap::new()),
             user_sessions: Arc::new(DashMap::new()),
             system_sender,
+            relay: Arc::new(OnceLock::new()),
         }
     }
 
+    /// Forward every event published from now on to `relay`. Returns false
+    /// if a relay was already set.
+    pub(crate) fn set_relay(&self, relay: mpsc::Sender<ServerEvent>) -> bool {
+        self.relay.set(relay).is_ok()
+    }
+
     pub fn subscribe_system(&self) -> broadcast::Receiver<ServerEvent> {
         self.system_sender.subscribe()
     }
@@ -134,6 +160,28 @@ impl EventBus {
     }
 
     pub fn publish(&self, event: ServerEvent) {
+        if let Some(relay) = self.relay.get() {
+            if relay.try_send(event.clone()).is_err() {
+                tracing::debug!(
+                    event_type = %event.event_type,
+                    "Cluster relay queue full; event not forwarded"
+                );
+            }
+        }
+
+        // Send to native bot system listener
+        let _ = self.system_sender.send(event.clone());
+        self.deliver(event);
+    }
+
+    /// Deliver an event published on another server to the sessions
+    /// connected here. The system listener only sees local events, so bots
+    /// act on each event once across the cluster.
+    pub(crate) fn deliver_remote(&self, event: ServerEvent) {
+        self.deliver(event);
+    }
+
+    fn deliver(&self, event: ServerEvent) {
         // Collect matching session IDs
         let session_ids: Vec<String> = if let Some(ref targets) = event.target_user_ids {
             // User-targeted events: look up each target user's sessions
@@ -188,9 +236,6 @@ impl EventBus {
             );
         }
 
-        // Send to native bot system listener
-        let _ = self.system_sender.send(event.clone());
-
         // Send to matching sessions
         for sid in session_ids {
             if let Some(sub) = self.sessions.get(&sid) {
@@ -199,6 +244,45 @@ impl EventBus {
         }
     }
 
+    /// Users with at least one session on this server.
+    pub fn local_user_ids(&self) -> Vec<i64> {
+        self.user_sessions
+            .iter()
+            .map(|entry| *entry.key())
+            .collect()
+    }
+
+    pub fn has_local_sessions(&self, user_id: i64) -> bool {
+        self.user_sessions.contains_key(&user_id)
+    }
+
+    pub fn snapshot(&self) -> EventBusSnapshot {
+        let mut session_queue_total = 0;
+        let mut session_queue_max = 0;
+        for entry in self.sessions.iter() {
+            let depth = entry.sender.len();
+            session_queue_total += depth;
+            session_queue_max = session_queue_max.max(depth);
+        }
+        let mut guild_sessions: Vec<(i64, usize)> = self
+            .guild_sessions
+            .iter()
+            .map(|entry| (*entry.key(), entry.len()))
+            .collect();
+        guild_sessions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        EventBusSnapshot {
+            session_capacity: self.capacity.max(256),
+            sessions: self.sessions.len(),
+            users: self.user_sessions.len(),
+            system_queue_depth: self.system_sender.len(),
+            system_receivers: self.system_sender.receiver_count(),
+            session_queue_total,
+            session_queue_max,
+            guild_sessions,
+        }
+    }
+
     /// Helper: publish a typed event with guild_id
     pub fn dispatch(&self, event_type: &str, payload: serde_json::Value, guild_id: Option<i64>) {
         let payload_arc = Arc::new(payload);
@@ -236,3 +320,49 @@ impl Default for EventBus {
         Self::new(4096)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_counts_guild_sessions_and_queued_events() {
+        let bus = EventBus::new(16);
+        let _a = bus.register_session("a", 1, &[10, 20]);
+        let _b = bus.register_session("b", 2, &[10]);
+        bus.dispatch("MESSAGE_CREATE", serde_json::json!({}), Some(10));
+        bus.dispatch("MESSAGE_CREATE", serde_json::json!({}), Some(20));
+
+        let snapshot = bus.snapshot();
+        assert_eq!(snapshot.sessions, 2);
+        assert_eq!(snapshot.users, 2);
+        assert_eq!(snapshot.guild_sessions, vec![(10, 2), (20, 1)]);
+        assert_eq!(snapshot.session_queue_total, 3);
+        assert_eq!(snapshot.session_queue_max, 2);
+
+        bus.unregister_session("a");
+        assert_eq!(bus.snapshot().guild_sessions, vec![(10, 1)]);
+    }
+
+    #[test]
+    fn only_local_events_are_relayed_and_seen_by_the_system_listener() {
+        let bus = EventBus::new(16);
+        let (relay, mut relayed) = mpsc::channel(16);
+        assert!(bus.set_relay(relay));
+        let mut system = bus.subscribe_system();
+        let mut session = bus.register_session("a", 1, &[10]);
+
+        bus.dispatch("MESSAGE_CREATE", serde_json::json!({ "n": 1 }), Some(10));
+        let mut remote = relayed.try_recv().unwrap();
+        assert_eq!(remote.payload["n"], 1);
+        assert!(system.try_recv().is_ok());
+        assert!(session.try_recv().is_ok());
+
+        remote.payload = Arc::new(serde_json::json!({ "n": 2 }));
+        bus.deliver_remote(remote);
+        assert_eq!(session.try_recv().unwrap().payload["n"], 2);
+        assert!(relayed.try_recv().is_err());
+        assert!(system.try_recv().is_err());
+        assert_eq!(bus.local_user_ids(), vec![1]);
+    }
+}
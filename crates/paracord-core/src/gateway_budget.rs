@@ -0,0 +1,153 @@
+//! Size budgets for the gateway's bulk dispatches.
+//!
+//! READY and GUILD_CREATE describe whole guilds. Left alone, one guild with
+//! tens of thousands of online members turns them into multi-megabyte
+//! frames that hold up everything queued behind them on the session's
+//! writer. Member and presence lists are capped per guild, and the
+//! dispatch as a whole is kept under a byte budget by emptying the largest
+//! guilds' lists first. A cut list is flagged with `members_has_more` or
+//! `presences_has_more`; clients page the rest in over REST.
+
+use serde_json::{json, Value};
+
+/// Members or presences a guild object carries before its list is cut.
+pub const MAX_GUILD_LIST_ITEMS: usize = 1_000;
+pub const MAX_READY_BYTES: usize = 1024 * 1024;
+pub const MAX_GUILD_CREATE_BYTES: usize = 256 * 1024;
+
+/// Lists a guild object may carry, with the flag set when one is cut.
+const GUILD_LISTS: [(&str, &str); 2] = [
+    ("members", "members_has_more"),
+    ("presences", "presences_has_more"),
+];
+
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Length of `value` serialized as JSON.
+pub fn serialized_len(value: &Value) -> usize {
+    let mut counter = ByteCounter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+fn over_list_cap(guild: &Value) -> bool {
+    GUILD_LISTS.iter().any(|(list, _)| {
+        guild
+            .get(list)
+            .and_then(Value::as_array)
+            .is_some_and(|items| items.len() > MAX_GUILD_LIST_ITEMS)
+    })
+}
+
+/// Keep the first `keep` entries of each of the guild's lists. Returns
+/// whether anything was cut.
+fn cut_guild_lists(guild: &mut Value, keep: usize) -> bool {
+    let mut cut = false;
+    for (list, flag) in GUILD_LISTS {
+        let Some(items) = guild.get_mut(list).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        if items.len() > keep {
+            items.truncate(keep);
+            guild[flag] = json!(true);
+            cut = true;
+        }
+    }
+    cut
+}
+
+/// Fit guild objects into `max_bytes` of JSON: cap every member and
+/// presence list, then empty the largest guilds' lists until the total
+/// fits. Returns whether anything was cut.
+pub fn fit_guilds(guilds: &mut [Value], max_bytes: usize) -> bool {
+    let mut cut = false;
+    for guild in guilds.iter_mut() {
+        cut |= cut_guild_lists(guild, MAX_GUILD_LIST_ITEMS);
+    }
+
+    let mut sizes: Vec<(usize, usize)> = guilds
+        .iter()
+        .enumerate()
+        .map(|(index, guild)| (serialized_len(guild), index))
+        .collect();
+    let mut total: usize = sizes.iter().map(|(size, _)| size).sum();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    for (size, index) in sizes {
+        if total <= max_bytes {
+            break;
+        }
+        if cut_guild_lists(&mut guilds[index], 0) {
+            cut = true;
+            total = total - size + serialized_len(&guilds[index]);
+        }
+    }
+    cut
+}
+
+/// A GUILD_CREATE payload cut down to budget, or `None` when it already
+/// fits. `serialized` is the payload's JSON length when already known.
+pub fn budget_guild_create(payload: &Value, serialized: Option<usize>) -> Option<Value> {
+    let size = serialized.unwrap_or_else(|| serialized_len(payload));
+    if size <= MAX_GUILD_CREATE_BYTES && !over_list_cap(payload) {
+        return None;
+    }
+    let mut guild = payload.clone();
+    fit_guilds(std::slice::from_mut(&mut guild), MAX_GUILD_CREATE_BYTES).then_some(guild)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild(id: i64, presences: usize) -> Value {
+        json!({
+            "id": id.to_string(),
+            "presences": (0..presences)
+                .map(|n| json!({ "user_id": n.to_string(), "status": "online" }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    #[test]
+    fn lists_are_capped_then_largest_guilds_emptied() {
+        let mut guilds = vec![
+            guild(1, 10),
+            guild(2, MAX_GUILD_LIST_ITEMS + 5),
+            guild(3, 20),
+        ];
+        assert!(fit_guilds(&mut guilds, usize::MAX));
+        assert_eq!(
+            guilds[1]["presences"].as_array().unwrap().len(),
+            MAX_GUILD_LIST_ITEMS
+        );
+        assert_eq!(guilds[1]["presences_has_more"], true);
+        assert!(guilds[0].get("presences_has_more").is_none());
+
+        let small = serialized_len(&guilds[0]) + serialized_len(&guilds[2]);
+        assert!(fit_guilds(&mut guilds, small + 100));
+        assert!(guilds[1]["presences"].as_array().unwrap().is_empty());
+        assert_eq!(guilds[2]["presences"].as_array().unwrap().len(), 20);
+        assert!(!fit_guilds(&mut guilds, usize::MAX));
+    }
+
+    #[test]
+    fn small_guild_create_is_left_alone() {
+        let payload = guild(1, 3);
+        assert!(budget_guild_create(&payload, None).is_none());
+        let large = guild(1, MAX_GUILD_LIST_ITEMS + 1);
+        let budgeted = budget_guild_create(&large, None).unwrap();
+        assert_eq!(budgeted["presences_has_more"], true);
+        assert_eq!(serialized_len(&payload), payload.to_string().len());
+    }
+}
@@ -0,0 +1,704 @@
+//! Per-session dispatch rules shared by the realtime transports.
+//!
+//! The WebSocket gateway and the SSE transport (`/api/v2/rt`) both turn bus
+//! events into numbered dispatches for one session. Everything that decides
+//! *what* a session sees lives here: guild and channel visibility, the age
+//! gate, blocked-author marking, guild scope changes carried by events, and
+//! the replay buffer behind resume, and member list subscriptions. The
+//! transports only frame and send.
+//!
+//! Dispatches are buffered per session for [`MAX_REPLAY_EVENTS`] events or
+//! [`MAX_REPLAY_AGE`], whichever is shorter. A client resumes by presenting
+//! the last sequence it processed and can acknowledge sequences early so the
+//! buffer doesn't hold events it already has.
+//!
+//! A disconnected session is [`detach`]ed rather than dropped: it stays on
+//! the bus for the same window, so events published while the client is
+//! away are numbered and buffered too, and [`reattach`] hands it to the
+//! connection that resumes it.
+
+use dashmap::DashMap;
+use paracord_models::gateway::EVENT_GUILD_MEMBER_LIST_UPDATE;
+use paracord_models::permissions::Permissions;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::events::ServerEvent;
+use crate::presence_manager::ClientType;
+use crate::{gateway_budget, member_list, observability, AppState};
+
+pub const MAX_REPLAY_EVENTS: usize = 100;
+pub const MAX_REPLAY_AGE: Duration = Duration::from_secs(300);
+
+pub struct GatewaySession {
+    pub user_id: i64,
+    pub guild_ids: Vec<i64>,
+    pub guild_owner_ids: HashMap<i64, i64>,
+    /// Users this user has blocked; their messages are flagged on dispatch.
+    pub blocked_user_ids: HashSet<i64>,
+    /// Messages from NSFW channels are withheld until the user has
+    /// acknowledged the age gate.
+    pub age_gate_acknowledged: bool,
+    /// NSFW flag per channel, looked up lazily while the age gate is pending.
+    pub nsfw_channels: HashMap<i64, bool>,
+    pub session_id: String,
+    /// Auth session (device) the connection's access token belongs to.
+    pub auth_session_id: Option<String>,
+    /// Guilds whose member list the client has subscribed to.
+    pub member_list_guild_ids: HashSet<i64>,
+    /// Kind of client, reported in the user's `client_status`.
+    pub client_type: ClientType,
+    pub sequence: u64,
+}
+
+impl GatewaySession {
+    pub fn new(user_id: i64, guild_ids: Vec<i64>, guild_owner_ids: HashMap<i64, i64>) -> Self {
+        Self {
+            user_id,
+            guild_ids,
+            guild_owner_ids,
+            blocked_user_ids: HashSet::new(),
+            age_gate_acknowledged: false,
+            nsfw_channels: HashMap::new(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            auth_session_id: None,
+            member_list_guild_ids: HashSet::new(),
+            client_type: ClientType::default(),
+            sequence: 0,
+        }
+    }
+
+    /// A session scoped to the guilds `user_id` currently belongs to.
+    pub async fn for_user(state: &AppState, user_id: i64) -> Self {
+        let guilds = paracord_db::guilds::get_user_guilds(&state.db, user_id)
+            .await
+            .unwrap_or_default();
+        let guild_ids = guilds.iter().map(|g| g.id).collect();
+        let guild_owner_ids = guilds.iter().map(|g| (g.id, g.owner_id)).collect();
+        Self::new(user_id, guild_ids, guild_owner_ids)
+    }
+
+    /// Load the per-user filters applied on dispatch.
+    pub async fn load_filters(&mut self, state: &AppState) {
+        self.blocked_user_ids =
+            paracord_db::relationships::get_blocked_user_ids(&state.db, self.user_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+        self.age_gate_acknowledged =
+            paracord_db::users::get_age_gate_acknowledged_at(&state.db, self.user_id)
+                .await
+                .ok()
+                .flatten()
+                .is_some();
+    }
+
+    pub fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    pub fn should_receive_event(
+        &self,
+        guild_id: Option<i64>,
+        target_user_ids: Option<&[i64]>,
+    ) -> bool {
+        // If the event targets specific users, only deliver to them.
+        if let Some(targets) = target_user_ids {
+            return targets.contains(&self.user_id);
+        }
+        match guild_id {
+            None => true,
+            Some(gid) => self.guild_ids.contains(&gid),
+        }
+    }
+
+    /// Dynamically add a guild to this session (e.g. after accepting an invite).
+    pub fn add_guild(&mut self, guild_id: i64, owner_id: i64) {
+        if !self.guild_ids.contains(&guild_id) {
+            self.guild_ids.push(guild_id);
+        }
+        self.guild_owner_ids.insert(guild_id, owner_id);
+    }
+
+    /// Dynamically remove a guild from this session (e.g. kicked/banned/left).
+    pub fn remove_guild(&mut self, guild_id: i64) {
+        self.guild_ids.retain(|id| *id != guild_id);
+        self.guild_owner_ids.remove(&guild_id);
+        self.member_list_guild_ids.remove(&guild_id);
+    }
+}
+
+/// A numbered dispatch ready to be framed by a transport.
+#[derive(Clone)]
+pub struct PreparedDispatch {
+    pub event_type: String,
+    pub sequence: u64,
+    pub payload: Arc<Value>,
+    /// The bus's pre-serialized payload, when the session didn't alter it.
+    serialized: Option<Arc<String>>,
+}
+
+impl PreparedDispatch {
+    fn data(&self) -> String {
+        match &self.serialized {
+            Some(serialized) => serialized.to_string(),
+            None => self.payload.to_string(),
+        }
+    }
+
+    /// `{"op":0,"t","s","d"}` as sent over the WebSocket gateway.
+    pub fn ws_frame(&self) -> String {
+        format!(
+            r#"{{"op":0,"t":{},"s":{},"d":{}}}"#,
+            json!(self.event_type),
+            self.sequence,
+            self.data()
+        )
+    }
+
+    /// The gateway frame plus `event_id`, the SSE resume cursor.
+    pub fn sse_frame(&self) -> String {
+        format!(
+            r#"{{"event_id":{},"op":0,"t":{},"s":{},"d":{}}}"#,
+            self.sequence,
+            json!(self.event_type),
+            self.sequence,
+            self.data()
+        )
+    }
+}
+
+fn parse_id(value: Option<&Value>) -> Option<i64> {
+    value
+        .and_then(|v| v.as_str())
+        .and_then(|raw| raw.parse::<i64>().ok())
+}
+
+fn extract_channel_id_from_event(event_type: &str, payload: &Value) -> Option<i64> {
+    if let Some(channel_id) = parse_id(payload.get("channel_id")) {
+        return Some(channel_id);
+    }
+
+    if matches!(
+        event_type,
+        "CHANNEL_CREATE"
+            | "CHANNEL_UPDATE"
+            | "CHANNEL_DELETE"
+            | "THREAD_CREATE"
+            | "THREAD_UPDATE"
+            | "THREAD_DELETE"
+    ) {
+        return parse_id(payload.get("id"));
+    }
+
+    None
+}
+
+/// Flag messages from authors the session's user has blocked so clients can
+/// collapse them. Returns `None` when the payload doesn't need changing.
+fn mark_blocked_author(
+    session: &GatewaySession,
+    event_type: &str,
+    payload: &Value,
+) -> Option<Value> {
+    if session.blocked_user_ids.is_empty()
+        || !matches!(event_type, "MESSAGE_CREATE" | "MESSAGE_UPDATE")
+    {
+        return None;
+    }
+    let author_id = parse_id(payload.get("author").and_then(|author| author.get("id")))?;
+    if !session.blocked_user_ids.contains(&author_id) {
+        return None;
+    }
+    let mut marked = payload.clone();
+    marked["author_blocked"] = json!(true);
+    Some(marked)
+}
+
+/// Whether `channel_id`'s messages must be withheld from a session whose
+/// user hasn't acknowledged the age gate.
+async fn withheld_by_age_gate(
+    state: &AppState,
+    session: &mut GatewaySession,
+    event_type: &str,
+    channel_id: i64,
+) -> bool {
+    if session.age_gate_acknowledged || !matches!(event_type, "MESSAGE_CREATE" | "MESSAGE_UPDATE") {
+        return false;
+    }
+    if let Some(&nsfw) = session.nsfw_channels.get(&channel_id) {
+        return nsfw;
+    }
+    // Fail closed: an unreadable flag withholds the message rather than leak it.
+    let nsfw = paracord_db::channels::is_channel_nsfw(&state.db, channel_id)
+        .await
+        .unwrap_or(true);
+    session.nsfw_channels.insert(channel_id, nsfw);
+    nsfw
+}
+
+async fn can_receive_channel_event(
+    state: &AppState,
+    session: &GatewaySession,
+    guild_id: i64,
+    channel_id: i64,
+) -> bool {
+    let owner_id = match session.guild_owner_ids.get(&guild_id) {
+        Some(&id) => id,
+        None => return false,
+    };
+
+    let Ok(perms) = crate::permissions::compute_channel_permissions_cached(
+        &state.permission_cache,
+        &state.db,
+        guild_id,
+        channel_id,
+        owner_id,
+        session.user_id,
+    )
+    .await
+    else {
+        return false;
+    };
+
+    perms.contains(Permissions::VIEW_CHANNEL)
+}
+
+/// Apply scope and filter changes that an event carries for this session.
+async fn track_session_changes(
+    state: &AppState,
+    session: &mut GatewaySession,
+    event: &ServerEvent,
+) {
+    let payload = &event.payload;
+    let concerns_self = || parse_id(payload.get("user_id")) == Some(session.user_id);
+    match event.event_type.as_str() {
+        "GUILD_MEMBER_ADD" if concerns_self() => {
+            if let Some(gid) = parse_id(payload.get("guild_id")) {
+                if let Some(guild) = paracord_db::guilds::get_guild(&state.db, gid)
+                    .await
+                    .ok()
+                    .flatten()
+                {
+                    session.add_guild(gid, guild.owner_id);
+                    state.event_bus.add_session_guild(&session.session_id, gid);
+                }
+            }
+        }
+        "GUILD_MEMBER_REMOVE" | "GUILD_BAN_ADD" if concerns_self() => {
+            if let Some(gid) = parse_id(payload.get("guild_id")) {
+                session.remove_guild(gid);
+                state
+                    .event_bus
+                    .remove_session_guild(&session.session_id, gid);
+            }
+        }
+        "GUILD_DELETE" => {
+            if let Some(gid) = parse_id(payload.get("id").or_else(|| payload.get("guild_id"))) {
+                session.remove_guild(gid);
+                state
+                    .event_bus
+                    .remove_session_guild(&session.session_id, gid);
+            }
+        }
+        "RELATIONSHIP_ADD" => {
+            if let Some(uid) = parse_id(payload.get("user").and_then(|u| u.get("id"))) {
+                if payload.get("type").and_then(|v| v.as_i64()) == Some(2) {
+                    session.blocked_user_ids.insert(uid);
+                } else {
+                    session.blocked_user_ids.remove(&uid);
+                }
+            }
+        }
+        "RELATIONSHIP_REMOVE" => {
+            if let Some(uid) = parse_id(payload.get("user_id")) {
+                session.blocked_user_ids.remove(&uid);
+            }
+        }
+        "AGE_GATE_ACKNOWLEDGED" => {
+            session.age_gate_acknowledged = true;
+            session.nsfw_channels.clear();
+        }
+        // Threads inherit the flag, so one change can affect several entries.
+        "CHANNEL_UPDATE" | "CHANNEL_DELETE" | "THREAD_UPDATE" | "THREAD_DELETE" => {
+            session.nsfw_channels.clear();
+        }
+        "GUILD_UPDATE" => {
+            if let (Some(gid), Some(new_owner)) =
+                (event.guild_id, parse_id(payload.get("owner_id")))
+            {
+                session.guild_owner_ids.insert(gid, new_owner);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Decide whether `event` reaches this session and, if so, number it and
+/// buffer it for replay. Returns `None` for events the session must not see.
+pub async fn prepare_dispatch(
+    state: &AppState,
+    session: &mut GatewaySession,
+    event: &ServerEvent,
+) -> Option<PreparedDispatch> {
+    if !session.should_receive_event(event.guild_id, event.target_user_ids.as_deref()) {
+        return None;
+    }
+    if let Some(guild_id) = event.guild_id {
+        if !session.guild_ids.contains(&guild_id) {
+            return None;
+        }
+        if event.event_type == EVENT_GUILD_MEMBER_LIST_UPDATE
+            && !session.member_list_guild_ids.contains(&guild_id)
+        {
+            return None;
+        }
+        if let Some(channel_id) = extract_channel_id_from_event(&event.event_type, &event.payload) {
+            if !can_receive_channel_event(state, session, guild_id, channel_id).await {
+                return None;
+            }
+            if withheld_by_age_gate(state, session, &event.event_type, channel_id).await {
+                return None;
+            }
+        }
+    }
+
+    track_session_changes(state, session, event).await;
+
+    let mut altered = mark_blocked_author(session, &event.event_type, &event.payload);
+    if event.event_type == "GUILD_CREATE" {
+        let serialized_len = event.serialized_payload.as_ref().map(|s| s.len());
+        altered = gateway_budget::budget_guild_create(&event.payload, serialized_len);
+        let size = match &altered {
+            Some(budgeted) => gateway_budget::serialized_len(budgeted),
+            None => {
+                serialized_len.unwrap_or_else(|| gateway_budget::serialized_len(&event.payload))
+            }
+        };
+        observability::ws_payload_sent(&event.event_type, size, altered.is_some());
+    }
+    let dispatch = PreparedDispatch {
+        event_type: event.event_type.clone(),
+        sequence: session.next_sequence(),
+        serialized: event
+            .serialized_payload
+            .clone()
+            .filter(|_| altered.is_none()),
+        payload: altered
+            .map(Arc::new)
+            .unwrap_or_else(|| event.payload.clone()),
+    };
+    buffer_dispatch(session, &dispatch);
+    Some(dispatch)
+}
+
+/// Subscribe the session to `guild_id`'s member list while the client shows
+/// `channel_id`, answering with the requested `ranges`. Empty `ranges`
+/// unsubscribe. Returns `None` when the channel isn't one of the session's
+/// guild channels it can view.
+pub async fn subscribe_member_list(
+    state: &AppState,
+    session: &mut GatewaySession,
+    guild_id: i64,
+    channel_id: i64,
+    ranges: &[(usize, usize)],
+) -> Option<PreparedDispatch> {
+    if !session.guild_ids.contains(&guild_id) {
+        return None;
+    }
+    if ranges.is_empty() {
+        session.member_list_guild_ids.remove(&guild_id);
+        return None;
+    }
+    let channel = paracord_db::channels::get_channel(&state.db, channel_id)
+        .await
+        .ok()
+        .flatten()?;
+    if channel.guild_id() != Some(guild_id)
+        || !can_receive_channel_event(state, session, guild_id, channel_id).await
+    {
+        return None;
+    }
+    session.member_list_guild_ids.insert(guild_id);
+
+    let mut payload = member_list::sync(state, guild_id, ranges).await;
+    payload["channel_id"] = json!(channel_id.to_string());
+    let dispatch = PreparedDispatch {
+        event_type: EVENT_GUILD_MEMBER_LIST_UPDATE.to_string(),
+        sequence: session.next_sequence(),
+        serialized: None,
+        payload: Arc::new(payload),
+    };
+    buffer_dispatch(session, &dispatch);
+    Some(dispatch)
+}
+
+struct ReplayBuffer {
+    user_id: i64,
+    /// Highest sequence dispatched to the session.
+    latest_sequence: u64,
+    events: VecDeque<(Instant, PreparedDispatch)>,
+    touched: Instant,
+}
+
+static REPLAY_BUFFERS: OnceLock<DashMap<String, ReplayBuffer>> = OnceLock::new();
+
+fn replay_buffers() -> &'static DashMap<String, ReplayBuffer> {
+    REPLAY_BUFFERS.get_or_init(|| {
+        // Buffers are only created from within the runtime; drop the ones
+        // whose session went quiet.
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MAX_REPLAY_AGE);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Some(buffers) = REPLAY_BUFFERS.get() {
+                    buffers.retain(|_, buffer| buffer.touched.elapsed() <= MAX_REPLAY_AGE);
+                }
+            }
+        });
+        DashMap::new()
+    })
+}
+
+fn buffer_dispatch(session: &GatewaySession, dispatch: &PreparedDispatch) {
+    let now = Instant::now();
+    let mut buffer = replay_buffers()
+        .entry(session.session_id.clone())
+        .or_insert_with(|| ReplayBuffer {
+            user_id: session.user_id,
+            latest_sequence: 0,
+            events: VecDeque::new(),
+            touched: now,
+        });
+    if buffer.user_id != session.user_id {
+        // A session id reused by someone else never inherits its events.
+        buffer.user_id = session.user_id;
+        buffer.events.clear();
+    }
+    while buffer
+        .events
+        .front()
+        .is_some_and(|(at, _)| at.elapsed() > MAX_REPLAY_AGE)
+    {
+        buffer.events.pop_front();
+    }
+    if buffer.events.len() >= MAX_REPLAY_EVENTS {
+        buffer.events.pop_front();
+    }
+    buffer.events.push_back((now, dispatch.clone()));
+    buffer.latest_sequence = dispatch.sequence;
+    buffer.touched = now;
+}
+
+/// Highest sequence dispatched to `session_id` and everything after
+/// `after`, or `None` when the session can't be resumed from there: it
+/// isn't `user_id`'s, nothing is buffered, or events past `after` were
+/// already dropped.
+pub fn replay_since(
+    session_id: &str,
+    user_id: i64,
+    after: u64,
+) -> Option<(u64, Vec<PreparedDispatch>)> {
+    let buffer = replay_buffers().get(session_id)?;
+    if buffer.user_id != user_id {
+        return None;
+    }
+    if buffer.latest_sequence <= after {
+        return Some((buffer.latest_sequence, Vec::new()));
+    }
+    let front = buffer.events.front()?;
+    if front.1.sequence > after.saturating_add(1) {
+        tracing::info!(
+            session_id,
+            client_seq = after,
+            oldest_buffered = front.1.sequence,
+            "replay gap too large, forcing re-identify"
+        );
+        return None;
+    }
+    let events = buffer
+        .events
+        .iter()
+        .filter(|(_, dispatch)| dispatch.sequence > after)
+        .map(|(_, dispatch)| dispatch.clone())
+        .collect();
+    Some((buffer.latest_sequence, events))
+}
+
+/// The client has processed everything up to `sequence`; stop holding it.
+pub fn acknowledge(session_id: &str, user_id: i64, sequence: u64) {
+    if let Some(mut buffer) = replay_buffers().get_mut(session_id) {
+        if buffer.user_id != user_id {
+            return;
+        }
+        while buffer
+            .events
+            .front()
+            .is_some_and(|(_, dispatch)| dispatch.sequence <= sequence)
+        {
+            buffer.events.pop_front();
+        }
+        buffer.touched = Instant::now();
+    }
+}
+
+/// Forget a session's buffered events, e.g. after it fell behind the bus
+/// and lost events that were never buffered.
+pub fn invalidate_replay(session_id: &str) {
+    replay_buffers().remove(session_id);
+}
+
+/// A session and its bus subscription, handed to the resuming connection.
+pub type ResumableSession = (GatewaySession, broadcast::Receiver<ServerEvent>);
+
+struct DetachedSession {
+    user_id: i64,
+    claim: oneshot::Sender<oneshot::Sender<ResumableSession>>,
+}
+
+static DETACHED_SESSIONS: OnceLock<DashMap<String, DetachedSession>> = OnceLock::new();
+
+fn detached_sessions() -> &'static DashMap<String, DetachedSession> {
+    DETACHED_SESSIONS.get_or_init(DashMap::new)
+}
+
+/// Number of disconnected sessions currently held for resume.
+pub fn detached_count() -> usize {
+    detached_sessions().len()
+}
+
+/// Keep a disconnected session on the bus for up to [`MAX_REPLAY_AGE`],
+/// buffering what it would have been sent, until a connection claims it
+/// with [`reattach`]. It is unregistered once that window passes or when it
+/// falls behind the bus.
+pub fn detach(
+    state: &AppState,
+    mut session: GatewaySession,
+    mut events: broadcast::Receiver<ServerEvent>,
+) {
+    let (claim, mut claimed) = oneshot::channel::<oneshot::Sender<ResumableSession>>();
+    detached_sessions().insert(
+        session.session_id.clone(),
+        DetachedSession {
+            user_id: session.user_id,
+            claim,
+        },
+    );
+    let state = state.clone();
+    tokio::spawn(async move {
+        let expiry = tokio::time::sleep(MAX_REPLAY_AGE);
+        tokio::pin!(expiry);
+        loop {
+            tokio::select! {
+                handoff = &mut claimed => {
+                    if let Ok(handoff) = handoff {
+                        match handoff.send((session, events)) {
+                            Ok(()) => return,
+                            // The resuming connection went away meanwhile.
+                            Err((returned, _)) => session = returned,
+                        }
+                    }
+                    break;
+                }
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        prepare_dispatch(&state, &mut session, &event).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        invalidate_replay(&session.session_id);
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                () = &mut expiry => break,
+            }
+        }
+        detached_sessions().remove(&session.session_id);
+        state.event_bus.unregister_session(&session.session_id);
+    });
+}
+
+/// Take over `user_id`'s detached session, or `None` when it isn't held
+/// any more.
+pub async fn reattach(session_id: &str, user_id: i64) -> Option<ResumableSession> {
+    let (_, detached) =
+        detached_sessions().remove_if(session_id, |_, detached| detached.user_id == user_id)?;
+    let (handoff, resumed) = oneshot::channel();
+    detached.claim.send(handoff).ok()?;
+    resumed.await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dispatch(sequence: u64) -> PreparedDispatch {
+        PreparedDispatch {
+            event_type: "MESSAGE_CREATE".to_string(),
+            sequence,
+            payload: Arc::new(json!({ "n": sequence })),
+            serialized: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_honors_owner_gaps_and_acks() {
+        let mut session = GatewaySession::new(7, Vec::new(), HashMap::new());
+        for _ in 0..3 {
+            let sequence = session.next_sequence();
+            buffer_dispatch(&session, &dispatch(sequence));
+        }
+        let id = session.session_id.clone();
+
+        let (latest, events) = replay_since(&id, 7, 1).unwrap();
+        assert_eq!(latest, 3);
+        assert_eq!(
+            events.iter().map(|d| d.sequence).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(replay_since(&id, 8, 1).is_none());
+        assert_eq!(replay_since(&id, 7, 3).unwrap().1.len(), 0);
+
+        acknowledge(&id, 7, 2);
+        assert!(replay_since(&id, 7, 0).is_none());
+        assert_eq!(replay_since(&id, 7, 2).unwrap().1.len(), 1);
+
+        invalidate_replay(&id);
+        assert!(replay_since(&id, 7, 2).is_none());
+    }
+
+    #[test]
+    fn frames_share_the_gateway_shape() {
+        let dispatch = dispatch(5);
+        let ws: Value = serde_json::from_str(&dispatch.ws_frame()).unwrap();
+        let sse: Value = serde_json::from_str(&dispatch.sse_frame()).unwrap();
+        assert_eq!(
+            ws,
+            json!({ "op": 0, "t": "MESSAGE_CREATE", "s": 5, "d": { "n": 5 } })
+        );
+        assert_eq!(sse["event_id"], 5);
+        assert_eq!(sse["d"], ws["d"]);
+    }
+
+    #[test]
+    fn messages_from_blocked_authors_are_flagged() {
+        let mut session = GatewaySession::new(7, Vec::new(), HashMap::new());
+        let message = json!({ "id": "1", "author": { "id": "9" }, "content": "hi" });
+        assert!(mark_blocked_author(&session, "MESSAGE_CREATE", &message).is_none());
+
+        session.blocked_user_ids.insert(9);
+        let marked = mark_blocked_author(&session, "MESSAGE_CREATE", &message).unwrap();
+        assert_eq!(marked["author_blocked"], true);
+        assert_eq!(marked["content"], "hi");
+        assert!(mark_blocked_author(&session, "MESSAGE_UPDATE", &message).is_some());
+        assert!(mark_blocked_author(&session, "TYPING_START", &message).is_none());
+        let other = json!({ "id": "2", "author": { "id": "8" } });
+        assert!(mark_blocked_author(&session, "MESSAGE_CREATE", &other).is_none());
+    }
+}
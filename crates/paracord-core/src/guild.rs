@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use rand::Rng;
 
 use crate::error::CoreError;
@@ -26,34 +27,47 @@ pub async fn create_guild_full(
     owner_id: i64,
     icon_hash: Option<&str>,
 ) -> Result<paracord_db::guilds::GuildRow, CoreError> {
+    // All or nothing: a guild without its owner membership or default role
+    // can't be managed or deleted from the client.
+    let mut tx = paracord_db::begin(pool).await?;
     let guild =
-        paracord_db::guilds::create_guild(pool, guild_id, name, owner_id, icon_hash).await?;
+        paracord_db::guilds::create_guild(&mut *tx, guild_id, name, owner_id, icon_hash).await?;
 
     // Add owner as member
-    paracord_db::members::add_member(pool, owner_id, guild_id).await?;
+    paracord_db::members::add_member(&mut *tx, owner_id, guild_id).await?;
 
     // Create the default Member role (role id = guild id).
     let default_perms = Permissions::default().bits();
-    paracord_db::roles::create_role(pool, guild_id, guild_id, "Member", default_perms).await?;
+    paracord_db::roles::create_role(&mut *tx, guild_id, guild_id, "Member", default_perms).await?;
 
     // Assign Member role to owner
-    paracord_db::roles::add_member_role(pool, owner_id, guild_id, guild_id).await?;
+    paracord_db::roles::add_member_role(&mut *tx, owner_id, guild_id, guild_id).await?;
 
     // Create #general text channel
-    let general_id = paracord_util::snowflake::generate(1);
-    paracord_db::channels::create_channel(pool, general_id, guild_id, "general", 0, 0, None, None)
-        .await?;
+    let general_id = paracord_util::snowflake::try_next_id().await?;
+    paracord_db::channels::create_channel(
+        &mut *tx, general_id, guild_id, "general", 0, 0, None, None,
+    )
+    .await?;
 
     // Create General voice channel
-    let voice_id = paracord_util::snowflake::generate(1);
-    paracord_db::channels::create_channel(pool, voice_id, guild_id, "General", 2, 1, None, None)
-        .await?;
+    let voice_id = paracord_util::snowflake::try_next_id().await?;
+    paracord_db::channels::create_channel(
+        &mut *tx, voice_id, guild_id, "General", 2, 1, None, None,
+    )
+    .await?;
 
+    tx.commit().await.map_err(paracord_db::DbError::from)?;
     Ok(guild)
 }
 
-/// Delete a guild, only allowed by the owner.
-pub async fn delete_guild(pool: &DbPool, guild_id: i64, user_id: i64) -> Result<(), CoreError> {
+/// Delete a guild, only allowed by the owner. The guild is tombstoned and
+/// can be restored until the undo window runs out; returns the deletion time.
+pub async fn delete_guild(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<DateTime<Utc>, CoreError> {
     let guild = paracord_db::guilds::get_guild(pool, guild_id)
         .await?
         .ok_or(CoreError::NotFound)?;
@@ -62,8 +76,9 @@ pub async fn delete_guild(pool: &DbPool, guild_id: i64, user_id: i64) -> Result<
         return Err(CoreError::Forbidden);
     }
 
-    paracord_db::guilds::delete_guild(pool, guild_id).await?;
-    Ok(())
+    paracord_db::tombstones::soft_delete_guild(pool, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)
 }
 
 /// Update guild fields, requires MANAGE_GUILD permission.
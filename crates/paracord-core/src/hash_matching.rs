@@ -0,0 +1,144 @@
+//! Perceptual-hash matching for uploaded images.
+//!
+//! Image attachments are hashed with a 64-bit difference hash and compared
+//! against a blocklist admins maintain through `/api/v1/admin/image-hashes`.
+//! A match within `max_distance` bits blocks the upload. Instances that
+//! subscribe to an external matching service (PhotoDNA or similar) can also
+//! have every image sent there for a verdict.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::AppState;
+
+/// Default number of differing bits still counted as the same image.
+pub const DEFAULT_MAX_DISTANCE: u32 = 6;
+
+/// Server-configured matching behaviour, copied from `[hash_matching]`.
+#[derive(Debug, Clone)]
+pub struct HashMatchingSettings {
+    pub enabled: bool,
+    pub max_distance: u32,
+    /// External service that receives each image and answers with a verdict.
+    pub service_url: Option<String>,
+    /// Sent as a bearer token to the external service.
+    pub service_token: Option<String>,
+    pub service_timeout: Duration,
+    /// Reject uploads when the external service can't be reached.
+    pub fail_closed: bool,
+}
+
+impl Default for HashMatchingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_distance: DEFAULT_MAX_DISTANCE,
+            service_url: None,
+            service_token: None,
+            service_timeout: Duration::from_secs(5),
+            fail_closed: true,
+        }
+    }
+}
+
+/// A blocklisted hash an upload came close enough to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageHashMatch {
+    pub hash: u64,
+    pub distance: u32,
+}
+
+/// Snapshot of blocklisted hashes. Cheap to clone; updates swap the set.
+#[derive(Debug, Clone, Default)]
+pub struct ImageHashList {
+    hashes: Arc<Vec<u64>>,
+}
+
+impl ImageHashList {
+    pub fn new(hashes: impl IntoIterator<Item = u64>) -> Self {
+        let unique: HashSet<u64> = hashes.into_iter().collect();
+        Self {
+            hashes: Arc::new(unique.into_iter().collect()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// The nearest listed hash within `max_distance` bits of `hash`.
+    pub fn closest(&self, hash: u64, max_distance: u32) -> Option<ImageHashMatch> {
+        self.hashes
+            .iter()
+            .map(|listed| ImageHashMatch {
+                hash: *listed,
+                distance: paracord_media::images::hash_distance(hash, *listed),
+            })
+            .filter(|candidate| candidate.distance <= max_distance)
+            .min_by_key(|candidate| candidate.distance)
+    }
+}
+
+/// Parse a hash written as 16 hex digits, with or without a `0x` prefix.
+pub fn parse_hash(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let digits = raw
+        .strip_prefix("0x")
+        .or_else(|| raw.strip_prefix("0X"))
+        .unwrap_or(raw);
+    if digits.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+pub fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// Load the blocklist from the database into the runtime snapshot.
+pub async fn reload_image_hashes(state: &AppState) -> Result<usize, CoreError> {
+    let hashes = paracord_db::image_hashes::list_hashes(&state.db)
+        .await?
+        .iter()
+        .filter_map(|hash| parse_hash(hash))
+        .collect::<Vec<_>>();
+    let list = ImageHashList::new(hashes);
+    let count = list.len();
+    state.runtime.write().await.image_hash_list = list;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_round_trip_through_hex() {
+        assert_eq!(parse_hash("00ff00ff00ff00ff"), Some(0x00ff_00ff_00ff_00ff));
+        assert_eq!(parse_hash("0xFFFFFFFFFFFFFFFF"), Some(u64::MAX));
+        assert_eq!(format_hash(0xabc), "0000000000000abc");
+        assert_eq!(parse_hash("abc"), None);
+        assert_eq!(parse_hash("zzzzzzzzzzzzzzzz"), None);
+    }
+
+    #[test]
+    fn closest_listed_hash_within_distance_wins() {
+        let list = ImageHashList::new([0b1111, 0b0111, 0xffff_0000_0000_0000]);
+        assert_eq!(
+            list.closest(0b0011, 2),
+            Some(ImageHashMatch {
+                hash: 0b0111,
+                distance: 1
+            })
+        );
+        assert_eq!(list.closest(0x00ff_0000, 4), None);
+        assert!(ImageHashList::default().closest(0, 64).is_none());
+    }
+}
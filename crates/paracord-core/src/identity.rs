@@ -241,7 +241,7 @@ pub async fn import_identity(
     let mut messages_imported: u64 = 0;
     const IMPORTED_FLAG: i32 = 1 << 4; // bit 4 = imported message
     for msg in &bundle.messages {
-        let msg_id = paracord_util::snowflake::generate(0);
+        let msg_id = paracord_util::snowflake::try_next_id().await?;
         let channel_id: i64 = match msg.channel_id.parse() {
             Ok(id) => id,
             Err(_) => {
@@ -0,0 +1,933 @@
+//! Reading chat exports from other platforms for guild imports.
+//!
+//! An archive is a `.zip` file or an extracted directory holding one of:
+//!
+//! - **Discord**: DiscordChatExporter JSON exports (one file per channel,
+//!   with the `--media` folder alongside), or a Discord data package
+//!   (`messages/c<id>/channel.json` + `messages.json`). Data packages only
+//!   hold the owner's own messages and no attachment files.
+//! - **Matrix**: Element's JSON room export, one file per room.
+//!
+//! Parsing is synchronous and only builds an [`ImportPlan`]; creating the
+//! channels, users and messages from it is up to the caller.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+
+/// Longest message content kept; longer messages are truncated.
+pub const MAX_IMPORTED_CONTENT_CHARS: usize = 2000;
+/// JSON files larger than this are not parsed.
+const MAX_JSON_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Discord,
+    Matrix,
+}
+
+impl ImportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Discord => "discord",
+            Self::Matrix => "matrix",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "discord" => Some(Self::Discord),
+            "matrix" => Some(Self::Matrix),
+            _ => None,
+        }
+    }
+}
+
+enum ArchiveRoot {
+    Dir(PathBuf),
+    Zip(Mutex<zip::ZipArchive<std::fs::File>>),
+}
+
+/// Read access to an export, whether zipped or extracted.
+pub struct ImportArchive {
+    root: ArchiveRoot,
+    files: Vec<String>,
+}
+
+impl ImportArchive {
+    pub fn open(path: &Path) -> Result<Self, CoreError> {
+        if path.is_dir() {
+            let mut files = Vec::new();
+            collect_files(path, path, &mut files)
+                .map_err(|e| CoreError::BadRequest(format!("cannot read archive: {e}")))?;
+            files.sort();
+            return Ok(Self {
+                root: ArchiveRoot::Dir(path.to_path_buf()),
+                files,
+            });
+        }
+        let file = std::fs::File::open(path)
+            .map_err(|e| CoreError::BadRequest(format!("cannot open archive: {e}")))?;
+        let zip = zip::ZipArchive::new(file)
+            .map_err(|e| CoreError::BadRequest(format!("not a zip archive: {e}")))?;
+        let mut files: Vec<String> = zip
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(str::to_string)
+            .collect();
+        files.sort();
+        Ok(Self {
+            root: ArchiveRoot::Zip(Mutex::new(zip)),
+            files,
+        })
+    }
+
+    /// Paths of every file, relative to the archive root with `/` separators.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.files
+            .binary_search_by(|f| f.as_str().cmp(name))
+            .is_ok()
+    }
+
+    /// Read one file, at most `max_bytes` long.
+    pub fn read(&self, name: &str, max_bytes: u64) -> Result<Vec<u8>, CoreError> {
+        if !self.contains(name) {
+            return Err(CoreError::NotFound);
+        }
+        let too_large = || CoreError::BadRequest(format!("{name} is too large"));
+        match &self.root {
+            ArchiveRoot::Dir(root) => {
+                let path = root.join(name);
+                let len = std::fs::metadata(&path)
+                    .map_err(|e| CoreError::Internal(e.to_string()))?
+                    .len();
+                if len > max_bytes {
+                    return Err(too_large());
+                }
+                std::fs::read(path).map_err(|e| CoreError::Internal(e.to_string()))
+            }
+            ArchiveRoot::Zip(zip) => {
+                let mut zip = zip.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let entry = zip
+                    .by_name(name)
+                    .map_err(|e| CoreError::Internal(e.to_string()))?;
+                if entry.size() > max_bytes {
+                    return Err(too_large());
+                }
+                let mut data = Vec::with_capacity(entry.size() as usize);
+                entry
+                    .take(max_bytes + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|e| CoreError::Internal(e.to_string()))?;
+                if data.len() as u64 > max_bytes {
+                    return Err(too_large());
+                }
+                Ok(data)
+            }
+        }
+    }
+
+    fn read_json(&self, name: &str) -> Result<Value, CoreError> {
+        let data = self.read(name, MAX_JSON_FILE_BYTES)?;
+        serde_json::from_slice(&data)
+            .map_err(|e| CoreError::BadRequest(format!("{name} is not valid JSON: {e}")))
+    }
+
+    fn json_files(&self) -> impl Iterator<Item = &String> {
+        self.files
+            .iter()
+            .filter(|name| name.to_ascii_lowercase().ends_with(".json"))
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if file_type.is_file() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let parts: Vec<String> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                out.push(parts.join("/"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportAuthor {
+    pub source_id: String,
+    pub name: String,
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportAttachment {
+    pub filename: String,
+    /// File inside the archive, when the export included it.
+    pub path: Option<String>,
+    /// Original URL, kept as a link when the file can't be imported.
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportMessage {
+    pub source_id: String,
+    pub author_id: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub pinned: bool,
+    pub attachments: Vec<ImportAttachment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportChannel {
+    pub source_id: String,
+    pub name: String,
+    pub topic: Option<String>,
+    /// Oldest first.
+    pub messages: Vec<ImportMessage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEmoji {
+    pub source_id: String,
+    pub name: String,
+    pub path: String,
+    pub animated: bool,
+}
+
+/// Everything an archive would create in the target guild.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    pub format: ImportFormat,
+    pub channels: Vec<ImportChannel>,
+    /// By source id.
+    pub authors: BTreeMap<String, ImportAuthor>,
+    pub emojis: Vec<ImportEmoji>,
+    /// Things in the archive that were skipped while parsing.
+    pub warnings: Vec<String>,
+}
+
+impl ImportPlan {
+    pub fn message_count(&self) -> usize {
+        self.channels.iter().map(|c| c.messages.len()).sum()
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = &ImportAttachment> {
+        self.channels
+            .iter()
+            .flat_map(|c| &c.messages)
+            .flat_map(|m| &m.attachments)
+    }
+
+    /// Counts for a dry run: what would be created and which referenced
+    /// files are missing.
+    pub fn summary(&self) -> Value {
+        let attachments: Vec<&ImportAttachment> = self.attachments().collect();
+        let with_files = attachments.iter().filter(|a| a.path.is_some()).count();
+        json!({
+            "format": self.format.as_str(),
+            "channels": self.channels.iter().map(|c| json!({
+                "name": c.name,
+                "messages": c.messages.len(),
+            })).collect::<Vec<_>>(),
+            "messages": self.message_count(),
+            "authors": self.authors.len(),
+            "attachments": {
+                "total": attachments.len(),
+                "with_files": with_files,
+                "links_only": attachments.len() - with_files,
+            },
+            "emojis": self.emojis.len(),
+        })
+    }
+}
+
+/// Guess the format from the files in the archive.
+pub fn detect_format(archive: &ImportArchive) -> Option<ImportFormat> {
+    if archive
+        .files()
+        .iter()
+        .any(|name| is_package_channel_file(name))
+    {
+        return Some(ImportFormat::Discord);
+    }
+    for name in archive.json_files() {
+        let Ok(value) = archive.read_json(name) else {
+            continue;
+        };
+        if is_chat_exporter_file(&value) {
+            return Some(ImportFormat::Discord);
+        }
+        if is_element_export(&value) {
+            return Some(ImportFormat::Matrix);
+        }
+    }
+    None
+}
+
+pub fn parse(archive: &ImportArchive, format: ImportFormat) -> Result<ImportPlan, CoreError> {
+    let mut plan = ImportPlan {
+        format,
+        channels: Vec::new(),
+        authors: BTreeMap::new(),
+        emojis: Vec::new(),
+        warnings: Vec::new(),
+    };
+    match format {
+        ImportFormat::Discord => {
+            parse_chat_exporter(archive, &mut plan)?;
+            parse_data_package(archive, &mut plan)?;
+        }
+        ImportFormat::Matrix => parse_element(archive, &mut plan)?,
+    }
+    if plan.channels.is_empty() {
+        return Err(CoreError::BadRequest(format!(
+            "no {} channels found in the archive",
+            format.as_str()
+        )));
+    }
+    for channel in &mut plan.channels {
+        channel.messages.sort_by_key(|m| m.created_at);
+        for message in &mut channel.messages {
+            if message.content.chars().count() > MAX_IMPORTED_CONTENT_CHARS {
+                message.content = message
+                    .content
+                    .chars()
+                    .take(MAX_IMPORTED_CONTENT_CHARS)
+                    .collect();
+                plan.warnings.push(format!(
+                    "message {} was truncated to {MAX_IMPORTED_CONTENT_CHARS} characters",
+                    message.source_id
+                ));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+// ── Discord ──────────────────────────────────────────────────────────────────
+
+fn is_chat_exporter_file(value: &Value) -> bool {
+    value.get("channel").is_some_and(Value::is_object)
+        && value.get("messages").is_some_and(Value::is_array)
+}
+
+fn is_package_channel_file(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('/').collect();
+    parts.len() >= 3
+        && parts[parts.len() - 1] == "channel.json"
+        && parts[parts.len() - 3] == "messages"
+        && parts[parts.len() - 2].starts_with('c')
+}
+
+fn parse_chat_exporter(archive: &ImportArchive, plan: &mut ImportPlan) -> Result<(), CoreError> {
+    let mut emoji_ids = HashSet::new();
+    for name in archive.json_files() {
+        if is_package_channel_file(name) || name.ends_with("/messages.json") {
+            continue;
+        }
+        let value = archive.read_json(name)?;
+        if !is_chat_exporter_file(&value) {
+            continue;
+        }
+        let base = parent_dir(name);
+        let channel = &value["channel"];
+        let mut messages = Vec::new();
+        let mut skipped = 0;
+        for raw in value["messages"].as_array().into_iter().flatten() {
+            let kind = raw["type"].as_str().unwrap_or("Default");
+            if kind != "Default" && kind != "Reply" {
+                skipped += 1;
+                continue;
+            }
+            let (Some(source_id), Some(author_id), Some(created_at)) = (
+                json_id(&raw["id"]),
+                json_id(&raw["author"]["id"]),
+                raw["timestamp"].as_str().and_then(parse_timestamp),
+            ) else {
+                skipped += 1;
+                continue;
+            };
+            let author = &raw["author"];
+            plan.authors
+                .entry(author_id.clone())
+                .or_insert_with(|| ImportAuthor {
+                    source_id: author_id.clone(),
+                    name: author["name"].as_str().unwrap_or("unknown").to_string(),
+                    bot: author["isBot"].as_bool().unwrap_or(false),
+                });
+            let attachments = raw["attachments"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|a| {
+                    let url = a["url"].as_str().unwrap_or_default();
+                    let filename = a["fileName"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| file_name(url));
+                    archive_reference(archive, &base, url, filename)
+                })
+                .collect();
+            let emojis = raw["inlineEmojis"].as_array().into_iter().flatten().chain(
+                raw["reactions"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|r| &r["emoji"]),
+            );
+            for emoji in emojis {
+                let Some(id) = json_id(&emoji["id"]) else {
+                    continue;
+                };
+                let image = emoji["imageUrl"].as_str().unwrap_or_default();
+                let path = resolve_local(archive, &base, image);
+                if let Some(path) = path.filter(|_| emoji_ids.insert(id.clone())) {
+                    plan.emojis.push(ImportEmoji {
+                        source_id: id,
+                        name: emoji["name"].as_str().unwrap_or("emoji").to_string(),
+                        path,
+                        animated: emoji["isAnimated"].as_bool().unwrap_or(false),
+                    });
+                }
+            }
+            messages.push(ImportMessage {
+                source_id,
+                author_id,
+                content: raw["content"].as_str().unwrap_or_default().to_string(),
+                created_at,
+                edited_at: raw["timestampEdited"].as_str().and_then(parse_timestamp),
+                pinned: raw["isPinned"].as_bool().unwrap_or(false),
+                attachments,
+            });
+        }
+        let channel_name = channel["name"].as_str().unwrap_or("imported");
+        if skipped > 0 {
+            plan.warnings.push(format!(
+                "#{channel_name}: skipped {skipped} system or malformed messages"
+            ));
+        }
+        plan.channels.push(ImportChannel {
+            source_id: json_id(&channel["id"]).unwrap_or_else(|| name.clone()),
+            name: channel_name.to_string(),
+            topic: channel["topic"]
+                .as_str()
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+            messages,
+        });
+    }
+    Ok(())
+}
+
+fn parse_data_package(archive: &ImportArchive, plan: &mut ImportPlan) -> Result<(), CoreError> {
+    let channel_files: Vec<&String> = archive
+        .files()
+        .iter()
+        .filter(|name| is_package_channel_file(name))
+        .collect();
+    if channel_files.is_empty() {
+        return Ok(());
+    }
+    // Every message in a package is the owner's.
+    let owner = archive
+        .files()
+        .iter()
+        .find(|name| name.ends_with("account/user.json"))
+        .map(|name| archive.read_json(name))
+        .transpose()?
+        .unwrap_or(Value::Null);
+    let owner_id = json_id(&owner["id"]).unwrap_or_else(|| "package-owner".to_string());
+    plan.authors
+        .entry(owner_id.clone())
+        .or_insert_with(|| ImportAuthor {
+            source_id: owner_id.clone(),
+            name: owner["username"]
+                .as_str()
+                .unwrap_or("discord-user")
+                .to_string(),
+            bot: false,
+        });
+
+    let mut skipped_private = 0;
+    for name in channel_files {
+        let channel = archive.read_json(name)?;
+        if !channel["guild"].is_object() {
+            skipped_private += 1;
+            continue;
+        }
+        let dir = parent_dir(name);
+        let messages_file = format!("{dir}messages.json");
+        if !archive.contains(&messages_file) {
+            plan.warnings.push(format!(
+                "{dir}: only JSON message files are supported, skipping"
+            ));
+            continue;
+        }
+        let raw_messages = archive.read_json(&messages_file)?;
+        let messages = raw_messages
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|raw| {
+                let created_at = raw["Timestamp"].as_str().and_then(parse_timestamp)?;
+                let attachments = raw["Attachments"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|url| ImportAttachment {
+                        filename: file_name(url),
+                        path: None,
+                        url: Some(url.to_string()),
+                    })
+                    .collect();
+                Some(ImportMessage {
+                    source_id: json_id(&raw["ID"])?,
+                    author_id: owner_id.clone(),
+                    content: raw["Contents"].as_str().unwrap_or_default().to_string(),
+                    created_at,
+                    edited_at: None,
+                    pinned: false,
+                    attachments,
+                })
+            })
+            .collect();
+        plan.channels.push(ImportChannel {
+            source_id: json_id(&channel["id"]).unwrap_or_else(|| dir.clone()),
+            name: channel["name"].as_str().unwrap_or("imported").to_string(),
+            topic: None,
+            messages,
+        });
+    }
+    if skipped_private > 0 {
+        plan.warnings.push(format!(
+            "skipped {skipped_private} direct message channels from the data package"
+        ));
+    }
+    Ok(())
+}
+
+// ── Matrix ───────────────────────────────────────────────────────────────────
+
+fn is_element_export(value: &Value) -> bool {
+    value.get("room_name").is_some() && value.get("messages").is_some_and(Value::is_array)
+}
+
+fn parse_element(archive: &ImportArchive, plan: &mut ImportPlan) -> Result<(), CoreError> {
+    let mut emoji_names = HashSet::new();
+    for name in archive.json_files() {
+        let value = archive.read_json(name)?;
+        if !is_element_export(&value) {
+            continue;
+        }
+        let base = parent_dir(name);
+        let mut messages: Vec<ImportMessage> = Vec::new();
+        let mut by_event: HashMap<String, usize> = HashMap::new();
+        let mut edits = Vec::new();
+        for event in value["messages"].as_array().into_iter().flatten() {
+            let content = &event["content"];
+            if event["type"] == "im.ponies.room_emotes" {
+                for (shortcode, image) in content["images"].as_object().into_iter().flatten() {
+                    let url = image["url"].as_str().unwrap_or_default();
+                    if let Some(path) = resolve_local(archive, &base, url)
+                        .filter(|_| emoji_names.insert(shortcode.clone()))
+                    {
+                        plan.emojis.push(ImportEmoji {
+                            source_id: shortcode.clone(),
+                            name: shortcode.clone(),
+                            animated: path.to_ascii_lowercase().ends_with(".gif"),
+                            path,
+                        });
+                    }
+                }
+                continue;
+            }
+            if event["type"] != "m.room.message" {
+                continue;
+            }
+            let (Some(sender), Some(created_at)) = (
+                event["sender"].as_str(),
+                event["origin_server_ts"]
+                    .as_i64()
+                    .and_then(DateTime::from_timestamp_millis),
+            ) else {
+                continue;
+            };
+            let relation = &content["m.relates_to"];
+            if relation["rel_type"] == "m.replace" {
+                if let (Some(target), Some(body)) = (
+                    relation["event_id"].as_str(),
+                    content["m.new_content"]["body"].as_str(),
+                ) {
+                    edits.push((target.to_string(), body.to_string(), created_at));
+                }
+                continue;
+            }
+            // Redacted events keep no content.
+            let Some(body) = content["body"].as_str() else {
+                continue;
+            };
+            plan.authors
+                .entry(sender.to_string())
+                .or_insert_with(|| ImportAuthor {
+                    source_id: sender.to_string(),
+                    name: matrix_localpart(sender).to_string(),
+                    bot: false,
+                });
+            let (text, attachments) = match content["msgtype"].as_str() {
+                Some("m.image" | "m.file" | "m.audio" | "m.video") => {
+                    let filename = content["filename"].as_str().unwrap_or(body).to_string();
+                    let url = content["url"].as_str().unwrap_or_default();
+                    let mut attachment = archive_reference(archive, &base, url, filename.clone());
+                    if attachment.path.is_none() {
+                        // Element saves downloaded media under files/.
+                        attachment.path =
+                            resolve_local(archive, &base, &format!("files/{filename}"));
+                    }
+                    (String::new(), vec![attachment])
+                }
+                Some("m.emote") => (format!("_{body}_"), Vec::new()),
+                _ => (body.to_string(), Vec::new()),
+            };
+            let source_id = event["event_id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{name}#{}", messages.len()));
+            by_event.insert(source_id.clone(), messages.len());
+            messages.push(ImportMessage {
+                source_id,
+                author_id: sender.to_string(),
+                content: text,
+                created_at,
+                edited_at: None,
+                pinned: false,
+                attachments,
+            });
+        }
+        for (target, body, at) in edits {
+            if let Some(message) = by_event.get(&target).map(|&i| &mut messages[i]) {
+                if message.edited_at.is_none_or(|prev| prev < at) {
+                    message.content = body;
+                    message.edited_at = Some(at);
+                }
+            }
+        }
+        plan.channels.push(ImportChannel {
+            source_id: value["room_id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| name.clone()),
+            name: value["room_name"]
+                .as_str()
+                .unwrap_or("imported")
+                .to_string(),
+            topic: value["topic"]
+                .as_str()
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+            messages,
+        });
+    }
+    Ok(())
+}
+
+fn matrix_localpart(user_id: &str) -> &str {
+    let trimmed = user_id.trim_start_matches('@');
+    trimmed.split(':').next().unwrap_or(trimmed)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// IDs appear as strings or numbers depending on the exporter.
+fn json_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .or_else(|_| DateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn parent_dir(name: &str) -> String {
+    name.rfind('/')
+        .map(|i| name[..=i].to_string())
+        .unwrap_or_default()
+}
+
+fn file_name(url: &str) -> String {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit(['/', '\\']).next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("attachment")
+        .to_string()
+}
+
+/// An attachment pointing either into the archive or at a remote URL.
+fn archive_reference(
+    archive: &ImportArchive,
+    base: &str,
+    url: &str,
+    filename: String,
+) -> ImportAttachment {
+    let remote = url.contains("://");
+    ImportAttachment {
+        filename,
+        path: if remote {
+            None
+        } else {
+            resolve_local(archive, base, url)
+        },
+        url: remote.then(|| url.to_string()),
+    }
+}
+
+/// Resolve a path an export wrote relative to its JSON file. Exporters
+/// percent-encode these and may use Windows separators; anything leaving
+/// the archive root is ignored.
+fn resolve_local(archive: &ImportArchive, base: &str, reference: &str) -> Option<String> {
+    if reference.is_empty() || reference.contains("://") {
+        return None;
+    }
+    let decoded = percent_decode(&reference.replace('\\', "/"));
+    let mut parts: Vec<String> = Vec::new();
+    for component in Path::new(&format!("{base}{decoded}")).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    let path = parts.join("/");
+    archive.contains(&path).then_some(path)
+}
+
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &[u8]) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn parses_chat_exporter_json_with_media() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "general.json",
+            br#"{
+                "guild": {"id": "1", "name": "Old server"},
+                "channel": {"id": "100", "name": "general", "topic": "hi"},
+                "messages": [
+                    {"id": "1002", "type": "Default", "timestamp": "2023-05-01T10:00:01+00:00",
+                     "content": "second", "isPinned": true,
+                     "author": {"id": "7", "name": "alice", "isBot": false},
+                     "attachments": [{"url": "general.json_Files/cat%20pic.png", "fileName": "cat pic.png"}],
+                     "reactions": [{"emoji": {"id": "55", "name": "party", "isAnimated": false,
+                                              "imageUrl": "general.json_Files/55.png"}, "count": 1}]},
+                    {"id": "1001", "type": "Default", "timestamp": "2023-05-01T10:00:00+00:00",
+                     "content": "first", "author": {"id": "8", "name": "bot", "isBot": true},
+                     "attachments": [{"url": "https://cdn.example/file.txt", "fileName": "file.txt"}]},
+                    {"id": "1003", "type": "GuildMemberJoin", "timestamp": "2023-05-01T10:00:02+00:00",
+                     "content": "", "author": {"id": "9", "name": "carol"}}
+                ]
+            }"#,
+        );
+        write(dir.path(), "general.json_Files/cat pic.png", b"png");
+        write(dir.path(), "general.json_Files/55.png", b"png");
+
+        let archive = ImportArchive::open(dir.path()).unwrap();
+        assert_eq!(detect_format(&archive), Some(ImportFormat::Discord));
+        let plan = parse(&archive, ImportFormat::Discord).unwrap();
+
+        assert_eq!(plan.channels.len(), 1);
+        let channel = &plan.channels[0];
+        assert_eq!(channel.name, "general");
+        assert_eq!(channel.topic.as_deref(), Some("hi"));
+        let contents: Vec<&str> = channel
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["first", "second"]);
+        assert!(channel.messages[1].pinned);
+        assert_eq!(
+            channel.messages[1].attachments[0].path.as_deref(),
+            Some("general.json_Files/cat pic.png")
+        );
+        assert_eq!(
+            channel.messages[0].attachments[0].url.as_deref(),
+            Some("https://cdn.example/file.txt")
+        );
+        assert_eq!(plan.authors.len(), 2);
+        assert!(plan.authors["8"].bot);
+        assert_eq!(plan.emojis.len(), 1);
+        assert_eq!(plan.emojis[0].name, "party");
+        assert_eq!(plan.warnings.len(), 1);
+    }
+
+    #[test]
+    fn parses_data_package_guild_channels_only() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "account/user.json",
+            br#"{"id": "42", "username": "me"}"#,
+        );
+        write(
+            dir.path(),
+            "messages/c10/channel.json",
+            br#"{"id": "10", "type": 0, "name": "lounge", "guild": {"id": "1", "name": "g"}}"#,
+        );
+        write(
+            dir.path(),
+            "messages/c10/messages.json",
+            br#"[{"ID": 11, "Timestamp": "2022-01-02 03:04:05.678000+00:00", "Contents": "hello",
+                  "Attachments": "https://cdn.discordapp.com/a/b/pic.png"}]"#,
+        );
+        write(
+            dir.path(),
+            "messages/c20/channel.json",
+            br#"{"id": "20", "type": 1, "recipients": ["42", "43"]}"#,
+        );
+
+        let archive = ImportArchive::open(dir.path()).unwrap();
+        assert_eq!(detect_format(&archive), Some(ImportFormat::Discord));
+        let plan = parse(&archive, ImportFormat::Discord).unwrap();
+        assert_eq!(plan.channels.len(), 1);
+        let message = &plan.channels[0].messages[0];
+        assert_eq!(message.author_id, "42");
+        assert_eq!(message.source_id, "11");
+        assert_eq!(message.attachments[0].filename, "pic.png");
+        assert_eq!(plan.authors["42"].name, "me");
+        assert_eq!(plan.warnings.len(), 1);
+    }
+
+    #[test]
+    fn parses_element_export_with_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "room.json",
+            br#"{
+                "room_name": "Matrix room", "topic": "chat",
+                "messages": [
+                    {"type": "m.room.message", "event_id": "$a", "sender": "@bob:example.org",
+                     "origin_server_ts": 1700000000000, "content": {"msgtype": "m.text", "body": "helo"}},
+                    {"type": "m.room.message", "event_id": "$b", "sender": "@bob:example.org",
+                     "origin_server_ts": 1700000001000,
+                     "content": {"msgtype": "m.text", "body": "* hello",
+                                 "m.new_content": {"msgtype": "m.text", "body": "hello"},
+                                 "m.relates_to": {"rel_type": "m.replace", "event_id": "$a"}}},
+                    {"type": "m.room.message", "event_id": "$c", "sender": "@eve:example.org",
+                     "origin_server_ts": 1700000002000,
+                     "content": {"msgtype": "m.image", "body": "photo.jpg", "url": "mxc://example.org/x"}},
+                    {"type": "m.room.member", "sender": "@eve:example.org", "origin_server_ts": 1700000003000,
+                     "content": {"membership": "join"}}
+                ]
+            }"#,
+        );
+        write(dir.path(), "files/photo.jpg", b"jpg");
+
+        let archive = ImportArchive::open(dir.path()).unwrap();
+        assert_eq!(detect_format(&archive), Some(ImportFormat::Matrix));
+        let plan = parse(&archive, ImportFormat::Matrix).unwrap();
+        let channel = &plan.channels[0];
+        assert_eq!(channel.name, "Matrix room");
+        assert_eq!(channel.messages.len(), 2);
+        assert_eq!(channel.messages[0].content, "hello");
+        assert!(channel.messages[0].edited_at.is_some());
+        assert_eq!(
+            channel.messages[1].attachments[0].path.as_deref(),
+            Some("files/photo.jpg")
+        );
+        assert_eq!(plan.authors["@eve:example.org"].name, "eve");
+    }
+
+    #[test]
+    fn references_cannot_escape_the_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a/b.json", b"{}");
+        let archive = ImportArchive::open(dir.path()).unwrap();
+        assert_eq!(
+            resolve_local(&archive, "a/", "../a/b.json").as_deref(),
+            Some("a/b.json")
+        );
+        assert_eq!(resolve_local(&archive, "a/", "../../etc/passwd"), None);
+        assert_eq!(resolve_local(&archive, "", "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn long_messages_are_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let long = "x".repeat(MAX_IMPORTED_CONTENT_CHARS + 10);
+        write(
+            dir.path(),
+            "room.json",
+            format!(
+                r#"{{"room_name": "r", "messages": [{{"type": "m.room.message", "event_id": "$a",
+                    "sender": "@a:b", "origin_server_ts": 1, "content": {{"body": "{long}"}}}}]}}"#
+            )
+            .as_bytes(),
+        );
+        let archive = ImportArchive::open(dir.path()).unwrap();
+        let plan = parse(&archive, ImportFormat::Matrix).unwrap();
+        assert_eq!(
+            plan.channels[0].messages[0].content.chars().count(),
+            MAX_IMPORTED_CONTENT_CHARS
+        );
+        assert_eq!(plan.warnings.len(), 1);
+    }
+}
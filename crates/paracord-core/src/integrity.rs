@@ -0,0 +1,194 @@
+//! Admin-triggered repair and integrity tasks.
+//!
+//! Each task runs as a background job so operators can fix derived data
+//! without opening a database shell. Tasks report their progress on the job
+//! row as `{ "task", "stage", "done", "total" }`, and the final report adds
+//! a task-specific `result`.
+
+use paracord_db::DbPool;
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+
+/// Progress is written at most every this many items.
+const PROGRESS_BATCH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceTask {
+    /// Rebuild the message indexes that history and search read through.
+    RebuildSearchIndex,
+    /// Recompute attachment usage per guild and flag guilds over quota.
+    RecomputeStorageUsage,
+    /// Hash webhook tokens still stored in plaintext.
+    VerifyWebhookTokens,
+    /// Remove permission overwrites whose channel, role or user is gone.
+    ValidateOverwrites,
+}
+
+impl MaintenanceTask {
+    pub const ALL: [MaintenanceTask; 4] = [
+        MaintenanceTask::RebuildSearchIndex,
+        MaintenanceTask::RecomputeStorageUsage,
+        MaintenanceTask::VerifyWebhookTokens,
+        MaintenanceTask::ValidateOverwrites,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaintenanceTask::RebuildSearchIndex => "rebuild_search_index",
+            MaintenanceTask::RecomputeStorageUsage => "recompute_storage_usage",
+            MaintenanceTask::VerifyWebhookTokens => "verify_webhook_tokens",
+            MaintenanceTask::ValidateOverwrites => "validate_overwrites",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|task| task.as_str() == value)
+    }
+}
+
+struct ProgressReporter<'a> {
+    pool: &'a DbPool,
+    job_id: i64,
+    task: MaintenanceTask,
+}
+
+impl ProgressReporter<'_> {
+    async fn report(&self, stage: &str, done: usize, total: usize) {
+        let progress = json!({
+            "task": self.task.as_str(),
+            "stage": stage,
+            "done": done,
+            "total": total,
+        });
+        if let Err(err) =
+            paracord_db::jobs::set_job_progress(self.pool, self.job_id, &progress.to_string()).await
+        {
+            tracing::warn!("Failed to record progress of job {}: {}", self.job_id, err);
+        }
+    }
+}
+
+/// Run `task` on behalf of job `job_id` and return its final report.
+pub async fn run_task(
+    pool: &DbPool,
+    task: MaintenanceTask,
+    job_id: i64,
+) -> Result<Value, CoreError> {
+    let progress = ProgressReporter { pool, job_id, task };
+    let (total, result) = match task {
+        MaintenanceTask::RebuildSearchIndex => rebuild_search_index(pool, &progress).await?,
+        MaintenanceTask::RecomputeStorageUsage => recompute_storage_usage(pool, &progress).await?,
+        MaintenanceTask::VerifyWebhookTokens => verify_webhook_tokens(pool, &progress).await?,
+        MaintenanceTask::ValidateOverwrites => validate_overwrites(pool, &progress).await?,
+    };
+    Ok(json!({
+        "task": task.as_str(),
+        "stage": "complete",
+        "done": total,
+        "total": total,
+        "result": result,
+    }))
+}
+
+async fn rebuild_search_index(
+    pool: &DbPool,
+    progress: &ProgressReporter<'_>,
+) -> Result<(usize, Value), CoreError> {
+    progress.report("reindexing", 0, 1).await;
+    paracord_db::messages::reindex_messages(pool).await?;
+    paracord_db::search::rebuild_search_index(pool).await?;
+    let messages = paracord_db::messages::count_messages(pool).await?;
+    Ok((1, json!({ "messages": messages })))
+}
+
+async fn recompute_storage_usage(
+    pool: &DbPool,
+    progress: &ProgressReporter<'_>,
+) -> Result<(usize, Value), CoreError> {
+    progress.report("scanning", 0, 0).await;
+    let usage = paracord_db::guild_storage_policies::list_guild_storage_usage(pool).await?;
+    let total_bytes: i64 = usage.iter().map(|(_, bytes, _)| bytes).sum();
+    let over_quota: Vec<Value> = usage
+        .iter()
+        .filter(|(_, bytes, quota)| quota.is_some_and(|quota| quota > 0 && *bytes > quota))
+        .map(|(guild_id, bytes, quota)| {
+            json!({
+                "guild_id": guild_id.to_string(),
+                "usage_bytes": bytes,
+                "storage_quota": quota,
+            })
+        })
+        .collect();
+    Ok((
+        usage.len(),
+        json!({
+            "guilds": usage.len(),
+            "total_bytes": total_bytes,
+            "over_quota": over_quota,
+        }),
+    ))
+}
+
+async fn verify_webhook_tokens(
+    pool: &DbPool,
+    progress: &ProgressReporter<'_>,
+) -> Result<(usize, Value), CoreError> {
+    let webhooks = paracord_db::webhooks::list_webhook_tokens(pool).await?;
+    let total = webhooks.len();
+    let mut rehashed = 0;
+    for (index, (id, token)) in webhooks.iter().enumerate() {
+        if index.is_multiple_of(PROGRESS_BATCH) {
+            progress.report("verifying", index, total).await;
+        }
+        if !paracord_db::webhooks::is_token_hash(token) {
+            paracord_db::webhooks::rehash_webhook_token(pool, *id, token).await?;
+            rehashed += 1;
+        }
+    }
+    Ok((total, json!({ "webhooks": total, "rehashed": rehashed })))
+}
+
+async fn validate_overwrites(
+    pool: &DbPool,
+    progress: &ProgressReporter<'_>,
+) -> Result<(usize, Value), CoreError> {
+    progress.report("scanning", 0, 0).await;
+    let orphans = paracord_db::channel_overwrites::find_orphaned_overwrites(pool).await?;
+    let total = orphans.len();
+    for (index, orphan) in orphans.iter().enumerate() {
+        if index.is_multiple_of(PROGRESS_BATCH) {
+            progress.report("removing", index, total).await;
+        }
+        paracord_db::channel_overwrites::delete_channel_overwrite(
+            pool,
+            orphan.channel_id,
+            orphan.target_id,
+        )
+        .await?;
+    }
+    let removed: Vec<Value> = orphans
+        .iter()
+        .map(|orphan| {
+            json!({
+                "channel_id": orphan.channel_id.to_string(),
+                "target_id": orphan.target_id.to_string(),
+                "target_type": orphan.target_type,
+            })
+        })
+        .collect();
+    Ok((total, json!({ "removed": removed })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_names_round_trip() {
+        for task in MaintenanceTask::ALL {
+            assert_eq!(MaintenanceTask::parse(task.as_str()), Some(task));
+        }
+        assert_eq!(MaintenanceTask::parse("drop_everything"), None);
+    }
+}
@@ -31,10 +31,10 @@ pub async fn create_interaction(
     interaction_type: i16,
     data: Value,
 ) -> Result<(Value, String), CoreError> {
-    let interaction_id = paracord_util::snowflake::generate(1);
+    let interaction_id = paracord_util::snowflake::try_next_id().await?;
     let token = generate_interaction_token();
     let token_hash = paracord_db::bot_applications::hash_token(&token);
-    let token_row_id = paracord_util::snowflake::generate(1);
+    let token_row_id = paracord_util::snowflake::try_next_id().await?;
     let expires_at = Utc::now() + Duration::minutes(15);
 
     paracord_db::interaction_tokens::create_interaction_token(
@@ -90,11 +90,15 @@ pub async fn create_interaction(
     Ok((interaction_payload, token))
 }
 
-/// Resolve a slash command by name for a given guild.
-/// Looks up guild-scoped commands first, then global commands for the application.
+/// Resolve a slash command for a given guild, by ID or by name.
+///
+/// A guild-scoped command shadows the same application's global command of
+/// the same name. When several applications register the name, the caller
+/// has to pick one with `command_id`.
 pub async fn resolve_slash_command(
     state: &AppState,
-    command_name: &str,
+    command_name: Option<&str>,
+    command_id: Option<i64>,
     guild_id: i64,
 ) -> Result<Option<paracord_db::application_commands::ApplicationCommandRow>, CoreError> {
     let available =
@@ -102,7 +106,162 @@ pub async fn resolve_slash_command(
             .await
             .map_err(|e| CoreError::Internal(e.to_string()))?;
 
-    Ok(available.into_iter().find(|cmd| cmd.name == command_name))
+    if let Some(command_id) = command_id {
+        return Ok(available.into_iter().find(|cmd| cmd.id == command_id));
+    }
+    let Some(command_name) = command_name else {
+        return Ok(None);
+    };
+    let mut matches: Vec<_> = available
+        .into_iter()
+        .filter(|cmd| cmd.name == command_name)
+        .collect();
+    if matches
+        .iter()
+        .any(|cmd| cmd.application_id != matches[0].application_id)
+    {
+        return Err(CoreError::BadRequest(format!(
+            "several applications provide /{command_name}; pass command_id"
+        )));
+    }
+    matches.sort_by_key(|cmd| cmd.guild_id.is_none());
+    Ok(matches.into_iter().next())
+}
+
+/// Check that `user_id` may run `command` in `channel`.
+///
+/// Invoking needs VIEW_CHANNEL and SEND_MESSAGES, plus the command's
+/// `default_member_permissions` (`0` limits it to administrators). NSFW
+/// commands only run in NSFW channels.
+pub async fn authorize_command_invocation(
+    state: &AppState,
+    command: &paracord_db::application_commands::ApplicationCommandRow,
+    guild_id: i64,
+    channel: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<(), CoreError> {
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    let perms = crate::permissions::compute_channel_permissions(
+        &state.db,
+        guild_id,
+        channel.id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    if !perms.contains(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES) {
+        return Err(CoreError::MissingPermission);
+    }
+    let required = match command.default_member_permissions {
+        Some(0) => Permissions::ADMINISTRATOR,
+        Some(bits) => Permissions::from_bits_truncate(bits),
+        None => Permissions::empty(),
+    };
+    if !perms.contains(required) {
+        return Err(CoreError::MissingPermission);
+    }
+    if command.nsfw && !channel.nsfw {
+        return Err(CoreError::BadRequest(
+            "NSFW commands can only be used in NSFW channels".into(),
+        ));
+    }
+    Ok(())
+}
+
+const OPTION_SUB_COMMAND: u64 = 1;
+const OPTION_SUB_COMMAND_GROUP: u64 = 2;
+const OPTION_STRING: u64 = 3;
+const OPTION_INTEGER: u64 = 4;
+const OPTION_BOOLEAN: u64 = 5;
+const OPTION_NUMBER: u64 = 10;
+
+/// Check the options a user supplied against the ones the command declares:
+/// no unknown or repeated names, every required option present, values of
+/// the declared type and, where choices are declared, one of them.
+/// Sub-commands and groups are checked recursively.
+pub fn validate_command_options(declared: &[Value], provided: &[Value]) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for option in provided {
+        let name = option
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("every option needs a name")?;
+        if !seen.insert(name) {
+            return Err(format!("option {name} given more than once"));
+        }
+        let spec = declared
+            .iter()
+            .find(|spec| spec.get("name").and_then(Value::as_str) == Some(name))
+            .ok_or_else(|| format!("unknown option {name}"))?;
+        let option_type = spec
+            .get("type")
+            .and_then(Value::as_u64)
+            .unwrap_or(OPTION_STRING);
+        if matches!(option_type, OPTION_SUB_COMMAND | OPTION_SUB_COMMAND_GROUP) {
+            let nested_declared = spec
+                .get("options")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let nested_provided = option
+                .get("options")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            validate_command_options(nested_declared, nested_provided)?;
+            continue;
+        }
+        let value = option
+            .get("value")
+            .ok_or_else(|| format!("option {name} needs a value"))?;
+        let type_ok = match option_type {
+            OPTION_STRING => value.is_string(),
+            OPTION_INTEGER => value.is_i64(),
+            OPTION_BOOLEAN => value.is_boolean(),
+            OPTION_NUMBER => value.is_number(),
+            // Users, channels, roles, mentionables and attachments are IDs.
+            _ => {
+                value.is_i64()
+                    || value
+                        .as_str()
+                        .is_some_and(|id| id.parse::<i64>().is_ok_and(|id| id > 0))
+            }
+        };
+        if !type_ok {
+            return Err(format!("option {name} has the wrong type"));
+        }
+        if let Some(choices) = spec.get("choices").and_then(Value::as_array) {
+            if !choices
+                .iter()
+                .any(|choice| choice.get("value") == Some(value))
+            {
+                return Err(format!("option {name} must be one of its choices"));
+            }
+        }
+    }
+
+    let subcommand_declared = declared.iter().any(|spec| {
+        matches!(
+            spec.get("type").and_then(Value::as_u64),
+            Some(OPTION_SUB_COMMAND | OPTION_SUB_COMMAND_GROUP)
+        )
+    });
+    if subcommand_declared && provided.len() != 1 {
+        return Err("choose exactly one sub-command".into());
+    }
+    for spec in declared {
+        let required = spec
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let name = spec.get("name").and_then(Value::as_str).unwrap_or_default();
+        if required && !seen.contains(name) {
+            return Err(format!("missing required option {name}"));
+        }
+    }
+    Ok(())
 }
 
 /// Process a bot's interaction response (callback).
@@ -192,7 +351,7 @@ pub async fn process_interaction_response(
                 .map_err(|e| CoreError::Internal(format!("serialize embeds: {e}")))?;
             let flags = data.get("flags").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
 
-            let message_id = paracord_util::snowflake::generate(1);
+            let message_id = paracord_util::snowflake::try_next_id().await?;
             // Message type 20 = ChatInputCommand (interaction response)
             let msg = paracord_db::messages::create_message_with_meta(
                 &state.db,
@@ -245,7 +404,7 @@ pub async fn process_interaction_response(
         // DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE (5) - acknowledge, bot will edit later
         5 => {
             // Create a placeholder message (type 20) so there's something to edit later
-            let message_id = paracord_util::snowflake::generate(1);
+            let message_id = paracord_util::snowflake::try_next_id().await?;
             let msg = paracord_db::messages::create_message(
                 &state.db,
                 message_id,
@@ -412,3 +571,69 @@ pub async fn process_interaction_response(
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared() -> Vec<Value> {
+        vec![
+            json!({ "name": "text", "type": 3, "required": true }),
+            json!({ "name": "count", "type": 4 }),
+            json!({ "name": "size", "type": 3, "choices": [
+                { "name": "Small", "value": "s" },
+                { "name": "Large", "value": "l" },
+            ] }),
+            json!({ "name": "target", "type": 6 }),
+        ]
+    }
+
+    #[test]
+    fn options_must_match_the_declaration() {
+        let ok = [
+            json!({ "name": "text", "value": "hi" }),
+            json!({ "name": "count", "value": 3 }),
+            json!({ "name": "size", "value": "l" }),
+            json!({ "name": "target", "value": "42" }),
+        ];
+        assert!(validate_command_options(&declared(), &ok).is_ok());
+
+        let bad: [&[Value]; 5] = [
+            &[json!({ "name": "count", "value": 3 })],
+            &[json!({ "name": "text", "value": 1 })],
+            &[
+                json!({ "name": "text", "value": "hi" }),
+                json!({ "name": "size", "value": "m" }),
+            ],
+            &[
+                json!({ "name": "text", "value": "hi" }),
+                json!({ "name": "other", "value": 1 }),
+            ],
+            &[
+                json!({ "name": "text", "value": "a" }),
+                json!({ "name": "text", "value": "b" }),
+            ],
+        ];
+        for options in bad {
+            assert!(
+                validate_command_options(&declared(), options).is_err(),
+                "{options:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sub_commands_are_checked_recursively() {
+        let declared = vec![
+            json!({ "name": "add", "type": 1, "options": [
+                { "name": "item", "type": 3, "required": true },
+            ] }),
+            json!({ "name": "clear", "type": 1 }),
+        ];
+        let add =
+            json!({ "name": "add", "type": 1, "options": [{ "name": "item", "value": "x" }] });
+        assert!(validate_command_options(&declared, &[add]).is_ok());
+        assert!(validate_command_options(&declared, &[json!({ "name": "add" })]).is_err());
+        assert!(validate_command_options(&declared, &[]).is_err());
+    }
+}
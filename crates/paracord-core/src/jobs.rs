@@ -0,0 +1,345 @@
+//! Durable background jobs.
+//!
+//! Work that outlives the request that started it is written to the
+//! `background_jobs` table and picked up by a pool of polling workers, so it
+//! survives restarts and failed attempts are retried with exponential
+//! backoff. Higher `priority` jobs run first. A job whose worker disappears
+//! is returned to the queue once its lease expires.
+//!
+//! Handlers are registered per job kind in a [`JobRegistry`]. A handler
+//! returns `Err` to have the attempt retried; once
+//! [`JobRow::is_last_attempt`] is true the job is marked failed instead and
+//! kept for inspection through the admin API.
+//!
+//! Periodic maintenance registers as a recurring kind instead. Each has a
+//! single row that is queued again after every run, so only one worker
+//! across all nodes runs it at a time and a failed run is simply tried again
+//! on the next interval.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use paracord_db::jobs::JobRow;
+use paracord_db::DbPool;
+use serde_json::Value;
+use tokio::sync::Notify;
+
+use crate::error::CoreError;
+use crate::AppState;
+
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+/// How long a worker may hold a job before it is considered lost.
+pub const JOB_LEASE_MINUTES: i64 = 30;
+/// Completed jobs are kept this long before the reaper prunes them.
+pub const COMPLETED_JOB_RETENTION_DAYS: i64 = 7;
+const BASE_RETRY_DELAY_SECONDS: i64 = 10;
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const REAPER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type JobHandler = Arc<dyn Fn(AppState, JobRow) -> JobFuture + Send + Sync>;
+
+/// Handlers by job kind.
+#[derive(Default, Clone)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, JobHandler>,
+    /// Recurring kinds and how long to wait between their runs.
+    recurring: HashMap<&'static str, Duration>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F, Fut>(&mut self, kind: &'static str, handler: F)
+    where
+        F: Fn(AppState, JobRow) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.handlers.insert(
+            kind,
+            Arc::new(move |state, job| Box::pin(handler(state, job)) as JobFuture),
+        );
+    }
+
+    /// Register `handler` for a kind that runs every `every`, starting as
+    /// soon as [`schedule_recurring_jobs`] first sees it.
+    pub fn register_recurring<F, Fut>(&mut self, kind: &'static str, every: Duration, handler: F)
+    where
+        F: Fn(AppState, JobRow) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.register(kind, handler);
+        self.recurring.insert(kind, every);
+    }
+
+    pub fn kinds(&self) -> Vec<&'static str> {
+        let mut kinds: Vec<_> = self.handlers.keys().copied().collect();
+        kinds.sort_unstable();
+        kinds
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JobOptions {
+    pub priority: i32,
+    pub max_attempts: i32,
+    /// Wait this long before the first attempt.
+    pub delay: Duration,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            priority: 0,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            delay: Duration::zero(),
+        }
+    }
+}
+
+/// Queue a job of `kind` with a JSON payload.
+pub async fn enqueue(
+    pool: &DbPool,
+    kind: &str,
+    payload: &Value,
+    options: JobOptions,
+) -> Result<JobRow, CoreError> {
+    let job = paracord_db::jobs::enqueue_job(
+        pool,
+        paracord_util::snowflake::try_next_id().await?,
+        kind,
+        &payload.to_string(),
+        options.priority,
+        options.max_attempts.max(1),
+        Utc::now() + options.delay,
+    )
+    .await?;
+    Ok(job)
+}
+
+/// Create or update the row of every recurring kind in `registry`.
+pub async fn schedule_recurring_jobs(pool: &DbPool, registry: &JobRegistry) {
+    for (kind, every) in &registry.recurring {
+        let id = match paracord_util::snowflake::try_next_id().await {
+            Ok(id) => id,
+            Err(err) => {
+                tracing::warn!("Failed to schedule recurring job {}: {}", kind, err);
+                continue;
+            }
+        };
+        if let Err(err) = paracord_db::jobs::schedule_recurring_job(
+            pool,
+            id,
+            kind,
+            every.num_seconds().max(1),
+            Utc::now(),
+        )
+        .await
+        {
+            tracing::warn!("Failed to schedule recurring job {}: {}", kind, err);
+        }
+    }
+}
+
+/// Parse a job's payload into the handler's payload type.
+pub fn payload<T: serde::de::DeserializeOwned>(job: &JobRow) -> Result<T, String> {
+    serde_json::from_str(&job.payload).map_err(|e| format!("Invalid {} payload: {e}", job.kind))
+}
+
+/// Delay before retrying after `attempts` failed attempts.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let seconds = BASE_RETRY_DELAY_SECONDS.saturating_mul(1_i64 << exponent);
+    Duration::seconds(seconds.min(MAX_RETRY_DELAY_SECONDS))
+}
+
+/// Claim and run up to `limit` ready jobs one after another. Returns the
+/// number of jobs run.
+pub async fn run_pending_jobs_once(
+    state: &AppState,
+    registry: &JobRegistry,
+    worker_id: &str,
+    limit: usize,
+) -> usize {
+    let mut ran = 0;
+    while ran < limit {
+        let job = match paracord_db::jobs::claim_next_job(&state.db, worker_id, Utc::now()).await {
+            Ok(Some(job)) => job,
+            Ok(None) => break,
+            Err(err) => {
+                tracing::warn!("Failed to claim background job: {}", err);
+                break;
+            }
+        };
+        run_job(state, registry, job).await;
+        ran += 1;
+    }
+    ran
+}
+
+async fn run_job(state: &AppState, registry: &JobRegistry, job: JobRow) {
+    let result = match registry.handlers.get(job.kind.as_str()) {
+        // Run the handler on its own task so a panic fails the attempt
+        // instead of taking the worker down with it.
+        Some(handler) => match tokio::spawn(handler(state.clone(), job.clone())).await {
+            Ok(result) => result,
+            Err(err) => Err(format!("Job handler panicked: {err}")),
+        },
+        None => {
+            tracing::warn!("No handler for background job kind '{}'", job.kind);
+            let _ = paracord_db::jobs::fail_job(&state.db, job.id, "Unknown job kind").await;
+            return;
+        }
+    };
+
+    let finished = match (result, job.repeat_seconds) {
+        (result, Some(repeat_seconds)) => {
+            if let Err(error) = &result {
+                tracing::warn!("Recurring job {} failed: {}", job.kind, error);
+            }
+            let run_at = Utc::now() + Duration::seconds(repeat_seconds);
+            paracord_db::jobs::reschedule_recurring_job(
+                &state.db,
+                job.id,
+                result.err().as_deref(),
+                run_at,
+            )
+            .await
+        }
+        (Ok(()), None) => paracord_db::jobs::complete_job(&state.db, job.id).await,
+        (Err(error), None) if job.is_last_attempt() => {
+            tracing::warn!(
+                "Background job {} ({}) failed after {} attempts: {}",
+                job.id,
+                job.kind,
+                job.attempts,
+                error
+            );
+            paracord_db::jobs::fail_job(&state.db, job.id, &error).await
+        }
+        (Err(error), None) => {
+            tracing::debug!(
+                "Background job {} ({}) attempt {} failed: {}",
+                job.id,
+                job.kind,
+                job.attempts,
+                error
+            );
+            let run_at = Utc::now() + retry_delay(job.attempts);
+            paracord_db::jobs::retry_job_later(&state.db, job.id, &error, run_at).await
+        }
+    };
+    if let Err(err) = finished {
+        tracing::warn!(
+            "Failed to record result of background job {}: {}",
+            job.id,
+            err
+        );
+    }
+}
+
+/// Requeue jobs with expired leases and prune old completed jobs.
+pub async fn reap_jobs_once(pool: &DbPool) {
+    let now = Utc::now();
+    match paracord_db::jobs::requeue_stale_jobs(pool, now - Duration::minutes(JOB_LEASE_MINUTES))
+        .await
+    {
+        Ok(0) => {}
+        Ok(count) => tracing::warn!("Requeued {} background jobs with expired leases", count),
+        Err(err) => tracing::warn!("Failed to requeue stale background jobs: {}", err),
+    }
+    let cutoff = now - Duration::days(COMPLETED_JOB_RETENTION_DAYS);
+    if let Err(err) = paracord_db::jobs::prune_completed_jobs(pool, cutoff).await {
+        tracing::warn!("Failed to prune completed background jobs: {}", err);
+    }
+    // Outlives any retry of the fan-out job that wrote them.
+    let before = paracord_util::snowflake::min_id_at(cutoff);
+    if let Err(err) = paracord_db::read_states::prune_mention_receipts(pool, before).await {
+        tracing::warn!("Failed to prune mention receipts: {}", err);
+    }
+}
+
+/// Start `workers` polling workers plus the lease reaper, which also keeps
+/// the registry's recurring jobs scheduled.
+pub fn spawn_job_workers(
+    state: AppState,
+    registry: Arc<JobRegistry>,
+    workers: usize,
+    shutdown: Arc<Notify>,
+) {
+    spawn_shared_job_workers(vec![state], registry, workers, shutdown);
+}
+
+/// Like [`spawn_job_workers`], with one worker pool draining the queues of
+/// several instances hosted in the same process.
+pub fn spawn_shared_job_workers(
+    states: Vec<AppState>,
+    registry: Arc<JobRegistry>,
+    workers: usize,
+    shutdown: Arc<Notify>,
+) {
+    let workers = workers.max(1);
+    let states = Arc::new(states);
+    tracing::info!(
+        "Background job workers started (workers={}, instances={}, kinds={:?})",
+        workers,
+        states.len(),
+        registry.kinds()
+    );
+
+    for index in 0..workers {
+        let states = states.clone();
+        let registry = registry.clone();
+        let shutdown = shutdown.clone();
+        let worker_id = format!("{}-{}", std::process::id(), index);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = shutdown.notified() => break,
+                    _ = interval.tick() => {
+                        for state in states.iter() {
+                            run_pending_jobs_once(state, &registry, &worker_id, usize::MAX).await;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    for state in states.iter() {
+                        schedule_recurring_jobs(&state.db, &registry).await;
+                        reap_jobs_once(&state.db).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), Duration::seconds(10));
+        assert_eq!(retry_delay(2), Duration::seconds(20));
+        assert_eq!(retry_delay(4), Duration::seconds(80));
+        assert_eq!(retry_delay(20), Duration::seconds(3600));
+    }
+}
@@ -1,17 +1,42 @@
 pub mod admin;
 pub mod auth;
 pub mod backup;
+pub mod calendar;
 pub mod channel;
+pub mod channel_export;
+pub mod cluster;
+pub mod emoji;
 pub mod error;
+pub mod event_log;
 pub mod events;
+pub mod gateway_budget;
+pub mod gateway_session;
 pub mod guild;
+pub mod hash_matching;
 pub mod identity;
+pub mod import;
+pub mod integrity;
 pub mod interactions;
+pub mod jobs;
+pub mod limits;
+pub mod maintenance;
 pub mod member_index;
+pub mod member_list;
 pub mod message;
+pub mod notifications;
 pub mod observability;
 pub mod permissions;
 pub mod presence_manager;
+pub mod push;
+pub mod reaction_burst;
+pub mod read_state;
+pub mod receipts;
+pub mod search;
+pub mod supporters;
+pub mod tag;
+pub mod thread;
+pub mod tombstone;
+pub mod url_reputation;
 pub mod user;
 
 use paracord_db::DbPool;
@@ -22,6 +47,7 @@ use paracord_relay::relay::RelayForwarder;
 use paracord_relay::room::MediaRoomManager;
 use paracord_relay::speaker::SpeakerDetector;
 use paracord_transport::endpoint::MediaEndpoint;
+use paracord_transport::transfer_limits::TransferLimiter;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{Notify, RwLock};
@@ -30,8 +56,7 @@ use tokio::sync::{Notify, RwLock};
 pub const USER_FLAG_ADMIN: i32 = 1 << 0;
 /// Bit flag: user is a bot account.
 pub const USER_FLAG_BOT: i32 = 1 << 1;
-/// Bit flag: message content is DM end-to-end encrypted ciphertext.
-pub const MESSAGE_FLAG_DM_E2EE: i32 = 1 << 0;
+pub use paracord_models::message::{MESSAGE_FLAG_DM_E2EE, MESSAGE_FLAG_VOICE_MESSAGE};
 
 pub fn is_admin(flags: i32) -> bool {
     flags & USER_FLAG_ADMIN != 0
@@ -49,6 +74,47 @@ pub struct RuntimeSettings {
     pub server_description: String,
     pub max_guilds_per_user: u32,
     pub max_members_per_guild: u32,
+    /// Only allow DMs between friends instead of routing strangers to requests.
+    pub dm_require_friendship: bool,
+    /// Instance accent color (`#rrggbb`), empty for the client default.
+    pub branding_accent_color: String,
+    /// Text shown on the login and registration pages.
+    pub branding_login_text: String,
+    /// Stored logo file name (`<hash>.<ext>`), set via the logo upload endpoint.
+    pub branding_logo: Option<String>,
+    /// Feed and admin-managed domains screened in message content.
+    pub url_denylist: url_reputation::UrlDenylist,
+    /// URL filter level for guilds that haven't picked their own.
+    pub url_filter_default_level: url_reputation::UrlFilterLevel,
+    /// Perceptual-hash screening of image uploads.
+    pub image_hash_matching: hash_matching::HashMatchingSettings,
+    /// Blocklisted image hashes, loaded from the database.
+    pub image_hash_list: hash_matching::ImageHashList,
+    /// Let guild verification hooks call loopback and private network
+    /// addresses. Off by default so guild admins can't reach internal hosts.
+    pub verification_hooks_allow_private_networks: bool,
+    /// Hours a deleted guild, channel or role can still be restored.
+    pub deletion_undo_window_hours: u32,
+    /// Custom emojis per guild (0 = no limit).
+    pub max_emojis_per_guild: u32,
+    /// Webhooks per channel (0 = no limit).
+    pub max_webhooks_per_channel: u32,
+    /// Webhooks per guild (0 = no limit).
+    pub max_webhooks_per_guild: u32,
+    /// Bot applications installed per guild (0 = no limit).
+    pub max_bots_per_guild: u32,
+    /// Roles per guild, including `@everyone` (0 = no limit).
+    pub max_roles_per_guild: u32,
+    /// Channels and categories per guild (0 = no limit).
+    pub max_channels_per_guild: u32,
+    /// Highest screen share quality preset for everyone.
+    pub max_stream_quality: String,
+    /// Upload size for supporters in bytes (0 = the normal limit).
+    pub supporter_max_upload_size: u64,
+    /// Emoji slots supporters get on top of `max_emojis_per_guild`.
+    pub supporter_extra_emoji_slots: u32,
+    /// Highest screen share quality preset for supporters.
+    pub supporter_max_stream_quality: String,
 }
 
 impl Default for RuntimeSettings {
@@ -59,6 +125,26 @@ impl Default for RuntimeSettings {
             server_description: String::new(),
             max_guilds_per_user: 100,
             max_members_per_guild: 1000,
+            dm_require_friendship: false,
+            branding_accent_color: String::new(),
+            branding_login_text: String::new(),
+            branding_logo: None,
+            url_denylist: url_reputation::UrlDenylist::default(),
+            url_filter_default_level: url_reputation::UrlFilterLevel::default(),
+            image_hash_matching: hash_matching::HashMatchingSettings::default(),
+            image_hash_list: hash_matching::ImageHashList::default(),
+            verification_hooks_allow_private_networks: false,
+            deletion_undo_window_hours: tombstone::DEFAULT_UNDO_WINDOW_HOURS,
+            max_emojis_per_guild: 0,
+            max_webhooks_per_channel: limits::DEFAULT_MAX_WEBHOOKS_PER_CHANNEL,
+            max_webhooks_per_guild: limits::DEFAULT_MAX_WEBHOOKS_PER_GUILD,
+            max_bots_per_guild: limits::DEFAULT_MAX_BOTS_PER_GUILD,
+            max_roles_per_guild: limits::DEFAULT_MAX_ROLES_PER_GUILD,
+            max_channels_per_guild: limits::DEFAULT_MAX_CHANNELS_PER_GUILD,
+            max_stream_quality: "4k60".to_string(),
+            supporter_max_upload_size: 0,
+            supporter_extra_emoji_slots: 50,
+            supporter_max_stream_quality: "4k60".to_string(),
         }
     }
 }
@@ -99,6 +185,10 @@ pub struct AppState {
     pub presence_manager: Arc<presence_manager::PresenceManager>,
     /// Native QUIC media relay state (None when using LiveKit).
     pub native_media: Option<NativeMediaState>,
+    /// Recent-event ring behind the admin firehose (None when disabled).
+    pub event_log: Option<Arc<event_log::EventLog>>,
+    /// Per-user attachment transfer concurrency and bandwidth limits.
+    pub transfer_limiter: Arc<TransferLimiter>,
 }
 
 /// State for the native QUIC-based media server.
@@ -108,10 +198,11 @@ pub struct NativeMediaState {
     pub speaker_detector: Arc<SpeakerDetector>,
     pub endpoint: Arc<MediaEndpoint>,
     pub relay_forwarder: Arc<RelayForwarder>,
-    /// Base64-encoded SHA-256 hash of the server's TLS certificate DER.
+    /// Base64-encoded SHA-256 hash of the media certificate DER.
     /// Browsers need this for `serverCertificateHashes` when connecting
-    /// to self-signed certs via WebTransport.
-    pub cert_hash: String,
+    /// to self-signed certs via WebTransport. `None` when the endpoint
+    /// presents a CA-issued certificate browsers already trust.
+    pub cert_hash: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -144,6 +235,8 @@ pub struct AppConfig {
     pub federation_max_events_per_peer_per_minute: Option<u32>,
     /// Per-peer rate limit for remote user creation (per hour). None = no limit.
     pub federation_max_user_creates_per_peer_per_hour: Option<u32>,
+    /// Per-peer federation bandwidth quota (bytes in + out per rolling hour). None = no limit.
+    pub federation_max_bytes_per_peer_per_hour: Option<u64>,
     /// Whether the native QUIC media server is enabled.
     pub native_media_enabled: bool,
     /// UDP port for the unified QUIC media endpoint (raw QUIC + WebTransport).
@@ -160,4 +253,16 @@ pub struct AppConfig {
     pub federation_file_cache_max_size: u64,
     /// TTL for cached federation files in hours.
     pub federation_file_cache_ttl_hours: u64,
+    /// Whether non-web video uploads are queued for ffmpeg transcoding.
+    pub video_transcoding_enabled: bool,
+    /// JSON file listing desktop client releases, served from
+    /// `/api/v1/client/update-manifest`. None = no server-driven updates.
+    pub client_update_manifest_path: Option<String>,
+    /// Oldest desktop client version allowed to use the API. None = no gate.
+    pub min_client_version: Option<String>,
+    /// VAPID public key (base64url) clients subscribe to Web Push with.
+    /// None = Web Push is off.
+    pub web_push_public_key: Option<String>,
+    /// Whether links posted in messages get OpenGraph/oEmbed previews.
+    pub link_previews_enabled: bool,
 }
@@ -0,0 +1,88 @@
+//! Per-guild caps on webhooks, bots, roles, channels and emojis.
+//!
+//! Caps come from [`RuntimeSettings`] and `0` means no limit. Create
+//! endpoints count what the guild already has and call [`ensure_below`]
+//! before inserting.
+
+use paracord_models::error_code::ErrorCode;
+
+use crate::error::CoreError;
+use crate::RuntimeSettings;
+
+pub const DEFAULT_MAX_WEBHOOKS_PER_CHANNEL: u32 = 15;
+pub const DEFAULT_MAX_WEBHOOKS_PER_GUILD: u32 = 1000;
+pub const DEFAULT_MAX_BOTS_PER_GUILD: u32 = 50;
+pub const DEFAULT_MAX_ROLES_PER_GUILD: u32 = 250;
+pub const DEFAULT_MAX_CHANNELS_PER_GUILD: u32 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuildLimit {
+    ChannelWebhooks,
+    GuildWebhooks,
+    Bots,
+    Roles,
+    /// Channels and categories; threads don't count.
+    Channels,
+    Emojis,
+}
+
+impl GuildLimit {
+    pub fn error_code(self) -> ErrorCode {
+        match self {
+            GuildLimit::ChannelWebhooks => ErrorCode::MaxChannelWebhooks,
+            GuildLimit::GuildWebhooks => ErrorCode::MaxGuildWebhooks,
+            GuildLimit::Bots => ErrorCode::MaxGuildBots,
+            GuildLimit::Roles => ErrorCode::MaxGuildRoles,
+            GuildLimit::Channels => ErrorCode::MaxGuildChannels,
+            GuildLimit::Emojis => ErrorCode::MaxGuildEmojis,
+        }
+    }
+
+    /// The configured cap, `None` when unlimited. Supporters may get more
+    /// emoji slots than this; see [`crate::supporters::perks_for`].
+    pub fn configured(self, settings: &RuntimeSettings) -> Option<u32> {
+        let max = match self {
+            GuildLimit::ChannelWebhooks => settings.max_webhooks_per_channel,
+            GuildLimit::GuildWebhooks => settings.max_webhooks_per_guild,
+            GuildLimit::Bots => settings.max_bots_per_guild,
+            GuildLimit::Roles => settings.max_roles_per_guild,
+            GuildLimit::Channels => settings.max_channels_per_guild,
+            GuildLimit::Emojis => settings.max_emojis_per_guild,
+        };
+        (max > 0).then_some(max)
+    }
+}
+
+/// Fail with [`CoreError::LimitReached`] once `count` has reached `max`.
+pub fn ensure_below(limit: GuildLimit, max: Option<u32>, count: i64) -> Result<(), CoreError> {
+    match max {
+        Some(max) if count >= i64::from(max) => Err(CoreError::LimitReached { limit, max }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_means_unlimited() {
+        let mut settings = RuntimeSettings {
+            max_roles_per_guild: 0,
+            ..RuntimeSettings::default()
+        };
+        assert_eq!(GuildLimit::Roles.configured(&settings), None);
+        assert!(ensure_below(GuildLimit::Roles, None, i64::MAX).is_ok());
+
+        settings.max_roles_per_guild = 2;
+        let max = GuildLimit::Roles.configured(&settings);
+        assert!(ensure_below(GuildLimit::Roles, max, 1).is_ok());
+        assert!(matches!(
+            ensure_below(GuildLimit::Roles, max, 2),
+            Err(CoreError::LimitReached {
+                limit: GuildLimit::Roles,
+                max: 2
+            })
+        ));
+    }
+}
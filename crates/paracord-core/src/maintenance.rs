@@ -0,0 +1,150 @@
+//! Scheduled SQLite maintenance and on-demand compaction.
+//!
+//! Under steady write load the WAL only shrinks when a checkpoint manages
+//! to truncate it, so long-running servers can end up with a WAL many
+//! times the size of the database. The maintenance worker checkpoints with
+//! `TRUNCATE`, refreshes planner statistics and, when configured, hands
+//! free pages back to the filesystem.
+
+use chrono::{DateTime, Utc};
+use paracord_db::DbPool;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::backup::parse_sqlite_path;
+use crate::error::CoreError;
+
+static LAST_RUN: OnceLock<Mutex<Option<MaintenanceReport>>> = OnceLock::new();
+static COMPACTING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceReport {
+    pub ran_at: DateTime<Utc>,
+    /// The checkpoint couldn't finish because of active readers or writers;
+    /// the next run tries again.
+    pub checkpoint_busy: bool,
+    pub wal_bytes_before: Option<u64>,
+    pub wal_bytes_after: Option<u64>,
+    pub freelist_pages_before: i64,
+    pub freelist_pages_after: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompactedCopy {
+    pub filename: String,
+    pub size_bytes: u64,
+    pub source_size_bytes: Option<u64>,
+}
+
+fn last_run() -> &'static Mutex<Option<MaintenanceReport>> {
+    LAST_RUN.get_or_init(|| Mutex::new(None))
+}
+
+pub fn last_maintenance_run() -> Option<MaintenanceReport> {
+    match last_run().lock() {
+        Ok(guard) => guard.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+fn file_size(path: &str) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|meta| meta.len())
+}
+
+/// Size of the database file and of its WAL, when the database is a file.
+pub fn sqlite_file_sizes(db_url: &str) -> (Option<u64>, Option<u64>) {
+    match parse_sqlite_path(db_url) {
+        Ok(path) => (file_size(&path), file_size(&format!("{path}-wal"))),
+        Err(_) => (None, None),
+    }
+}
+
+/// Checkpoint and truncate the WAL, run `PRAGMA optimize`, and release up
+/// to `incremental_vacuum_pages` free pages (`None` skips that step).
+pub async fn run_sqlite_maintenance(
+    pool: &DbPool,
+    db_url: &str,
+    incremental_vacuum_pages: Option<i64>,
+) -> Result<MaintenanceReport, CoreError> {
+    let (_, wal_bytes_before) = sqlite_file_sizes(db_url);
+    let freelist_pages_before = paracord_db::maintenance::sqlite_stats(pool)
+        .await?
+        .freelist_count;
+    let checkpoint = paracord_db::maintenance::checkpoint_wal(pool).await?;
+    paracord_db::maintenance::optimize(pool).await?;
+    if let Some(pages) = incremental_vacuum_pages {
+        paracord_db::maintenance::incremental_vacuum(pool, pages).await?;
+    }
+    let freelist_pages_after = paracord_db::maintenance::sqlite_stats(pool)
+        .await?
+        .freelist_count;
+    let (_, wal_bytes_after) = sqlite_file_sizes(db_url);
+
+    let report = MaintenanceReport {
+        ran_at: Utc::now(),
+        checkpoint_busy: checkpoint.busy,
+        wal_bytes_before,
+        wal_bytes_after,
+        freelist_pages_before,
+        freelist_pages_after,
+    };
+    match last_run().lock() {
+        Ok(mut guard) => *guard = Some(report.clone()),
+        Err(poisoned) => *poisoned.into_inner() = Some(report.clone()),
+    }
+    Ok(report)
+}
+
+/// Write a compacted copy of the live database into `dest_dir` with
+/// `VACUUM INTO`. Operators can swap it in while the server is stopped.
+pub async fn compact_into(
+    pool: &DbPool,
+    db_url: &str,
+    dest_dir: &str,
+) -> Result<CompactedCopy, CoreError> {
+    if paracord_db::active_database_engine() != paracord_db::DatabaseEngine::Sqlite {
+        return Err(CoreError::BadRequest(
+            "Compaction is only available for SQLite databases".into(),
+        ));
+    }
+    if COMPACTING.swap(true, Ordering::SeqCst) {
+        return Err(CoreError::Conflict(
+            "A compaction is already running".into(),
+        ));
+    }
+    let result = compact_into_inner(pool, db_url, dest_dir).await;
+    COMPACTING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn compact_into_inner(
+    pool: &DbPool,
+    db_url: &str,
+    dest_dir: &str,
+) -> Result<CompactedCopy, CoreError> {
+    tokio::fs::create_dir_all(dest_dir)
+        .await
+        .map_err(|e| CoreError::Internal(format!("Failed to create {dest_dir}: {e}")))?;
+    let filename = format!(
+        "paracord_compacted_{}.db",
+        Utc::now().format("%Y%m%d_%H%M%S")
+    );
+    let dest_path = Path::new(dest_dir).join(&filename);
+    let dest_path_str = dest_path
+        .to_str()
+        .ok_or_else(|| CoreError::Internal("Invalid compaction path".into()))?;
+    if dest_path.exists() {
+        return Err(CoreError::Conflict(format!("{filename} already exists")));
+    }
+    paracord_db::maintenance::vacuum_into(pool, dest_path_str).await?;
+
+    let size_bytes = file_size(dest_path_str).unwrap_or(0);
+    let (source_size_bytes, _) = sqlite_file_sizes(db_url);
+    tracing::info!("Compacted database copy written to {}", dest_path.display());
+    Ok(CompactedCopy {
+        filename,
+        size_bytes,
+        source_size_bytes,
+    })
+}
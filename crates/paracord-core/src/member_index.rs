@@ -38,6 +38,14 @@ impl MemberIndex {
         recipients
     }
 
+    /// Current members of a guild, in no particular order.
+    pub fn member_ids(&self, guild_id: i64) -> Vec<i64> {
+        self.guilds
+            .get(&guild_id)
+            .map(|members| members.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
     /// Track a new member (called on GUILD_MEMBER_ADD).
     pub fn add_member(&self, guild_id: i64, user_id: i64) {
         self.guilds.entry(guild_id).or_default().insert(user_id);
@@ -0,0 +1,358 @@
+//! Lazily loaded guild member lists for the gateway.
+//!
+//! Instead of taking every member of a large guild up front, a client
+//! subscribes to the ranges of a guild's member list it is showing and
+//! receives them as `SYNC` ops in a `GUILD_MEMBER_LIST_UPDATE`. The list is
+//! built from the [`MemberIndex`](crate::member_index::MemberIndex): online
+//! members first, then offline ones, each group ordered by user id. Members
+//! who hide their presence from the guild are listed as offline.
+//!
+//! When a member comes online, goes offline, joins or leaves, one
+//! `GUILD_MEMBER_LIST_UPDATE` with `DELETE`, `INSERT` or `UPDATE` ops
+//! against list indexes goes to the guild; sessions that haven't subscribed
+//! to the guild's list drop it (see
+//! [`crate::gateway_session::subscribe_member_list`]). A guild has one
+//! list, shared by all of its channels.
+
+use paracord_models::gateway::EVENT_GUILD_MEMBER_LIST_UPDATE;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::presence_manager::PresencePrivacy;
+use crate::AppState;
+
+/// Ranges one subscription may hold.
+pub const MAX_RANGES: usize = 5;
+/// Members one range covers at most.
+pub const MAX_RANGE_LEN: usize = 100;
+
+/// Sort key of a list entry: online members come first, then by user id.
+type ListKey = (bool, i64);
+
+fn list_key(online: bool, user_id: i64) -> ListKey {
+    (!online, user_id)
+}
+
+fn is_listed_online(key: &ListKey) -> bool {
+    !key.0
+}
+
+/// Parse `[[first, last], ...]` (inclusive indexes). Malformed ranges are
+/// dropped and the rest capped at [`MAX_RANGES`] of [`MAX_RANGE_LEN`].
+pub fn parse_ranges(value: &Value) -> Vec<(usize, usize)> {
+    value
+        .as_array()
+        .map(|ranges| {
+            ranges
+                .iter()
+                .filter_map(|range| {
+                    let first = range.get(0)?.as_u64()? as usize;
+                    let last = range.get(1)?.as_u64()? as usize;
+                    (first <= last).then(|| (first, last.min(first + MAX_RANGE_LEN - 1)))
+                })
+                .take(MAX_RANGES)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn presence_online(
+    online_users: &HashSet<i64>,
+    presences: &HashMap<i64, Value>,
+    user_id: i64,
+) -> bool {
+    online_users.contains(&user_id)
+        && presences
+            .get(&user_id)
+            .and_then(|presence| presence.get("status"))
+            .and_then(Value::as_str)
+            != Some("offline")
+}
+
+/// Whether `user_id` is connected with a status other than offline or
+/// invisible, before their privacy settings are applied.
+pub async fn is_online(state: &AppState, user_id: i64) -> bool {
+    let online_users = state.online_users.read().await;
+    let presences = state.user_presences.read().await;
+    presence_online(&online_users, &presences, user_id)
+}
+
+/// Keys of `guild_id`'s members other than `except`, unsorted.
+async fn list_keys(state: &AppState, guild_id: i64, except: Option<i64>) -> Vec<ListKey> {
+    let member_ids = state.member_index.member_ids(guild_id);
+    let online_users = state.online_users.read().await;
+    let presences = state.user_presences.read().await;
+    member_ids
+        .into_iter()
+        .filter(|user_id| Some(*user_id) != except)
+        .map(|user_id| {
+            let online = presence_online(&online_users, &presences, user_id)
+                && state.presence_manager.visible_in_guild(user_id, guild_id);
+            list_key(online, user_id)
+        })
+        .collect()
+}
+
+/// Entries of a sorted list that fall in `range`.
+fn range_slice(sorted: &[ListKey], (first, last): (usize, usize)) -> &[ListKey] {
+    let start = first.min(sorted.len());
+    let end = last.saturating_add(1).min(sorted.len());
+    &sorted[start..end]
+}
+
+fn list_update(guild_id: i64, keys: &[ListKey], ops: Vec<Value>) -> Value {
+    json!({
+        "guild_id": guild_id.to_string(),
+        "member_count": keys.len(),
+        "online_count": keys.iter().filter(|key| is_listed_online(key)).count(),
+        "ops": ops,
+    })
+}
+
+/// List items for `keys`: the member, with their presence when listed
+/// online.
+async fn items(state: &AppState, guild_id: i64, keys: &[ListKey]) -> Vec<Value> {
+    let user_ids: Vec<i64> = keys.iter().map(|(_, user_id)| *user_id).collect();
+    let rows: HashMap<i64, paracord_db::members::MemberWithUserRow> =
+        paracord_db::members::get_guild_members_by_ids(&state.db, guild_id, &user_ids)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|row| (row.user_id, row))
+            .collect();
+    let presences: Vec<Value> = {
+        let presences = state.user_presences.read().await;
+        keys.iter()
+            .map(|key @ (_, user_id)| {
+                if !is_listed_online(key) {
+                    return Value::Null;
+                }
+                presences.get(user_id).cloned().unwrap_or_else(
+                    || json!({ "user_id": user_id.to_string(), "status": "online" }),
+                )
+            })
+            .collect()
+    };
+
+    let mut items = Vec::with_capacity(keys.len());
+    for ((_, user_id), presence) in keys.iter().zip(presences) {
+        let Some(member) = rows.get(user_id) else {
+            // Left between the index read and the query; keep the indexes
+            // lined up.
+            items.push(json!({ "user_id": user_id.to_string(), "presence": presence }));
+            continue;
+        };
+        let roles: Vec<String> =
+            paracord_db::roles::get_member_roles(&state.db, *user_id, guild_id)
+                .await
+                .unwrap_or_default()
+                .iter()
+                .map(|role| role.id.to_string())
+                .collect();
+        items.push(json!({
+            "user_id": user_id.to_string(),
+            "guild_id": guild_id.to_string(),
+            "nick": member.nick,
+            "joined_at": member.joined_at.to_rfc3339(),
+            "roles": roles,
+            "user": {
+                "id": user_id.to_string(),
+                "username": member.username,
+                "discriminator": member.discriminator,
+                "avatar_hash": member.user_avatar_hash,
+                "flags": member.user_flags,
+                "bot": crate::is_bot(member.user_flags),
+            },
+            "presence": presence,
+        }));
+    }
+    items
+}
+
+/// `GUILD_MEMBER_LIST_UPDATE` payload with a `SYNC` op for each of
+/// `ranges`.
+pub async fn sync(state: &AppState, guild_id: i64, ranges: &[(usize, usize)]) -> Value {
+    let mut keys = list_keys(state, guild_id, None).await;
+    keys.sort_unstable();
+    let mut ops = Vec::with_capacity(ranges.len());
+    for &range in ranges {
+        let items = items(state, guild_id, range_slice(&keys, range)).await;
+        ops.push(json!({ "op": "SYNC", "range": [range.0, range.1], "items": items }));
+    }
+    list_update(guild_id, &keys, ops)
+}
+
+/// Ops moving `user_id` from `before` to `after`, where `None` is "not
+/// listed" and `Some(online)` its group. `others` holds everyone else.
+/// When the member stays listed, the last op is the one to carry its item.
+fn move_ops(
+    others: &[ListKey],
+    user_id: i64,
+    before: Option<bool>,
+    after: Option<bool>,
+) -> Vec<Value> {
+    let index_of = |online: bool| {
+        let key = list_key(online, user_id);
+        others.iter().filter(|other| **other < key).count()
+    };
+    let old_index = before.map(index_of);
+    let new_index = after.map(index_of);
+    match (old_index, new_index) {
+        (Some(old), Some(new)) if old == new => vec![json!({ "op": "UPDATE", "index": new })],
+        _ => old_index
+            .map(|index| json!({ "op": "DELETE", "index": index }))
+            .into_iter()
+            .chain(new_index.map(|index| json!({ "op": "INSERT", "index": index })))
+            .collect(),
+    }
+}
+
+async fn publish_move(
+    state: &AppState,
+    guild_id: i64,
+    user_id: i64,
+    before: Option<bool>,
+    after: Option<bool>,
+) {
+    if before == after {
+        return;
+    }
+    let mut keys = list_keys(state, guild_id, Some(user_id)).await;
+    let mut ops = move_ops(&keys, user_id, before, after);
+    if let Some(online) = after {
+        let key = list_key(online, user_id);
+        let item = items(state, guild_id, &[key]).await.pop();
+        if let (Some(op), Some(item)) = (ops.last_mut(), item) {
+            op["item"] = item;
+        }
+        keys.push(key);
+    }
+    state.event_bus.dispatch(
+        EVENT_GUILD_MEMBER_LIST_UPDATE,
+        list_update(guild_id, &keys, ops),
+        Some(guild_id),
+    );
+}
+
+/// Add `user_id` to `guild_id` in the member index and insert them into the
+/// guild's member list.
+pub fn add_member(state: &AppState, guild_id: i64, user_id: i64) {
+    state.member_index.add_member(guild_id, user_id);
+    let state = state.clone();
+    tokio::spawn(async move {
+        let online = is_online(&state, user_id).await
+            && state.presence_manager.visible_in_guild(user_id, guild_id);
+        publish_move(&state, guild_id, user_id, None, Some(online)).await;
+    });
+}
+
+/// Remove `user_id` from `guild_id` in the member index and delete them
+/// from the guild's member list.
+pub fn remove_member(state: &AppState, guild_id: i64, user_id: i64) {
+    state.member_index.remove_member(guild_id, user_id);
+    let state = state.clone();
+    tokio::spawn(async move {
+        let online = is_online(&state, user_id).await
+            && state.presence_manager.visible_in_guild(user_id, guild_id);
+        publish_move(&state, guild_id, user_id, Some(online), None).await;
+    });
+}
+
+/// `user_id`'s presence went from `was_online` to what it is now; move
+/// them between groups in the lists of `guild_ids` that show it.
+pub async fn presence_changed(
+    state: &AppState,
+    user_id: i64,
+    guild_ids: Vec<i64>,
+    was_online: bool,
+) {
+    let online = is_online(state, user_id).await;
+    if online == was_online {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        for guild_id in guild_ids {
+            if state.presence_manager.visible_in_guild(user_id, guild_id) {
+                publish_move(&state, guild_id, user_id, Some(was_online), Some(online)).await;
+            }
+        }
+    });
+}
+
+/// An online user's privacy changed from `previous` to `privacy`; move
+/// them in the lists of guilds that stopped or started seeing them online.
+pub async fn privacy_changed(
+    state: &AppState,
+    user_id: i64,
+    guild_ids: &[i64],
+    previous: &PresencePrivacy,
+    privacy: &PresencePrivacy,
+) {
+    if !is_online(state, user_id).await {
+        return;
+    }
+    for &guild_id in guild_ids {
+        let before = previous.visible_to(false, &[guild_id]);
+        let after = privacy.visible_to(false, &[guild_id]);
+        publish_move(state, guild_id, user_id, Some(before), Some(after)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_are_capped_and_validated() {
+        let ranges = parse_ranges(&json!([[0, 99], [100, 500], [5, 1], "x", [7]]));
+        assert_eq!(ranges, vec![(0, 99), (100, 199)]);
+        let many = parse_ranges(&json!([[0, 0], [1, 1], [2, 2], [3, 3], [4, 4], [5, 5]]));
+        assert_eq!(many.len(), MAX_RANGES);
+        assert!(parse_ranges(&json!(null)).is_empty());
+    }
+
+    #[test]
+    fn online_members_sort_first() {
+        let mut keys = vec![list_key(false, 1), list_key(true, 9), list_key(true, 3)];
+        keys.sort_unstable();
+        assert_eq!(
+            keys.iter().map(|(_, id)| *id).collect::<Vec<_>>(),
+            vec![3, 9, 1]
+        );
+        assert_eq!(range_slice(&keys, (1, 10)), &keys[1..]);
+        assert!(range_slice(&keys, (5, 10)).is_empty());
+    }
+
+    #[test]
+    fn moves_delete_and_insert_at_list_indexes() {
+        // Online: 2, 8. Offline: 1, 4.
+        let others = [
+            list_key(true, 2),
+            list_key(true, 8),
+            list_key(false, 1),
+            list_key(false, 4),
+        ];
+
+        assert_eq!(
+            move_ops(&others, 5, Some(false), Some(true)),
+            vec![
+                json!({ "op": "DELETE", "index": 4 }),
+                json!({ "op": "INSERT", "index": 1 }),
+            ]
+        );
+        assert_eq!(
+            move_ops(&others, 3, None, Some(false)),
+            vec![json!({ "op": "INSERT", "index": 3 })]
+        );
+        assert_eq!(
+            move_ops(&others, 5, Some(true), None),
+            vec![json!({ "op": "DELETE", "index": 1 })]
+        );
+        // Nobody sorts between the two positions.
+        assert_eq!(
+            move_ops(&others[..1], 5, Some(true), Some(false)),
+            vec![json!({ "op": "UPDATE", "index": 1 })]
+        );
+    }
+}
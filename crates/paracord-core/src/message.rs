@@ -1,6 +1,6 @@
 use crate::error::CoreError;
 use crate::permissions;
-use crate::MESSAGE_FLAG_DM_E2EE;
+use crate::{MESSAGE_FLAG_DM_E2EE, MESSAGE_FLAG_VOICE_MESSAGE};
 use paracord_db::DbPool;
 use paracord_models::permissions::Permissions;
 
@@ -76,6 +76,7 @@ pub struct CreateMessageOptions {
     pub allow_empty_content: bool,
     pub dm_e2ee: Option<DmE2eePayload>,
     pub nonce: Option<String>,
+    pub voice_message: bool,
 }
 
 /// Create a message, requires SEND_MESSAGES and VIEW_CHANNEL.
@@ -99,6 +100,7 @@ pub async fn create_message(
             allow_empty_content: false,
             dm_e2ee: None,
             nonce: None,
+            voice_message: false,
         },
     )
     .await
@@ -126,6 +128,7 @@ pub async fn create_message_with_type(
             allow_empty_content: false,
             dm_e2ee: None,
             nonce: None,
+            voice_message: false,
         },
     )
     .await
@@ -199,11 +202,17 @@ pub async fn create_message_with_options(
         .await?;
         permissions::require_permission(perms, Permissions::VIEW_CHANNEL)?;
         permissions::require_permission(perms, Permissions::SEND_MESSAGES)?;
+        crate::channel::ensure_posting_allowed(pool, &channel, perms, author_id).await?;
     } else {
         if !paracord_db::dms::is_dm_recipient(pool, channel_id, author_id).await? {
             return Err(CoreError::Forbidden);
         }
-        let recipients = paracord_db::dms::get_dm_recipient_ids(pool, channel_id).await?;
+        // Group DM members are vetted for blocks when they are added.
+        let recipients = if channel.channel_type == 1 {
+            paracord_db::dms::get_dm_recipient_ids(pool, channel_id).await?
+        } else {
+            Vec::new()
+        };
         for recipient_id in recipients {
             if recipient_id == author_id {
                 continue;
@@ -240,6 +249,22 @@ pub async fn create_message_with_options(
         }
     }
 
+    if options.voice_message {
+        flags |= MESSAGE_FLAG_VOICE_MESSAGE;
+    }
+
+    if let Some(reference_id) = options.reference_id {
+        // Users can't reply to someone who has blocked them.
+        if let Some(referenced) = paracord_db::messages::get_message(pool, reference_id).await? {
+            if referenced.author_id != author_id
+                && paracord_db::relationships::has_blocked(pool, referenced.author_id, author_id)
+                    .await?
+            {
+                return Err(CoreError::Forbidden);
+            }
+        }
+    }
+
     let e2ee_header = options.dm_e2ee.as_ref().and_then(|p| p.header.clone());
 
     let msg = paracord_db::messages::create_message_with_meta(
@@ -289,10 +314,17 @@ pub async fn edit_message_with_options(
     if msg.channel_id != channel_id {
         return Err(CoreError::NotFound);
     }
+    if msg.flags & MESSAGE_FLAG_VOICE_MESSAGE != 0 {
+        return Err(CoreError::BadRequest(
+            "Voice messages cannot be edited".into(),
+        ));
+    }
     let channel = paracord_db::channels::get_channel(pool, channel_id)
         .await?
         .ok_or(CoreError::NotFound)?;
 
+    crate::channel::ensure_not_archived(&channel)?;
+
     if channel.guild_id().is_some() {
         if dm_e2ee.is_some() {
             return Err(CoreError::BadRequest(
@@ -0,0 +1,360 @@
+//! Per-user notification settings and mention counting.
+//!
+//! Members pick a level per guild (all messages, mentions only, nothing),
+//! can mute a guild or channel (optionally until a timestamp) and can ignore
+//! `@everyone`/`@here`. Channel overrides win over the guild setting and
+//! threads use their parent's override. When a guild message is created,
+//! [`record_mentions`] bumps `mention_count` in the read state of every
+//! mentioned member whose settings let the mention through. Role and
+//! `@everyone`/`@here` mentions can reach a whole guild, so those messages
+//! are counted by a background job rather than during the send request
+//! (see [`ParsedMentions::fans_out`]).
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use paracord_db::channels::ChannelRow;
+use paracord_db::notification_settings::{
+    ChannelNotificationOverrideRow, GuildNotificationSettingsRow, LEVEL_ALL, LEVEL_MENTIONS,
+    LEVEL_NONE,
+};
+use paracord_models::permissions::Permissions;
+
+use crate::error::CoreError;
+use crate::AppState;
+
+/// Users looked up per settings query.
+const LOOKUP_CHUNK: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    All,
+    Mentions,
+    None,
+}
+
+impl NotificationLevel {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "all" => Some(Self::All),
+            "mentions" => Some(Self::Mentions),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Mentions => "mentions",
+            Self::None => "none",
+        }
+    }
+
+    pub fn from_db(value: i16) -> Self {
+        match value {
+            LEVEL_MENTIONS => Self::Mentions,
+            LEVEL_NONE => Self::None,
+            _ => Self::All,
+        }
+    }
+
+    pub fn to_db(self) -> i16 {
+        match self {
+            Self::All => LEVEL_ALL,
+            Self::Mentions => LEVEL_MENTIONS,
+            Self::None => LEVEL_NONE,
+        }
+    }
+}
+
+/// Whether a mute set with `muted`/`muted_until` is still in effect.
+pub fn mute_active(muted: bool, muted_until: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    muted && muted_until.is_none_or(|until| until > now)
+}
+
+/// A member's settings for one channel after applying the channel override.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveSettings {
+    pub level: NotificationLevel,
+    pub muted: bool,
+    pub suppress_everyone: bool,
+}
+
+impl EffectiveSettings {
+    pub fn resolve(
+        guild: &GuildNotificationSettingsRow,
+        channel: Option<&ChannelNotificationOverrideRow>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let guild_muted = mute_active(guild.muted, guild.muted_until, now);
+        let channel_muted = channel.is_some_and(|o| mute_active(o.muted, o.muted_until, now));
+        let level = channel.and_then(|o| o.level).unwrap_or(guild.level);
+        Self {
+            level: NotificationLevel::from_db(level),
+            muted: guild_muted || channel_muted,
+            suppress_everyone: guild.suppress_everyone,
+        }
+    }
+
+    /// Whether a mention counts toward the member's badge. `broad` is an
+    /// `@everyone` or `@here` mention rather than a user or role mention.
+    pub fn counts_mention(&self, broad: bool) -> bool {
+        if self.muted || self.level == NotificationLevel::None {
+            return false;
+        }
+        !(broad && self.suppress_everyone)
+    }
+}
+
+/// Mentions written in message content.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedMentions {
+    pub users: BTreeSet<i64>,
+    pub roles: BTreeSet<i64>,
+    pub everyone: bool,
+    pub here: bool,
+}
+
+impl ParsedMentions {
+    /// Whether the mentions reach members through a role or `@everyone`/
+    /// `@here` rather than by name, so resolving them scales with the guild.
+    pub fn fans_out(&self) -> bool {
+        self.everyone || self.here || !self.roles.is_empty()
+    }
+}
+
+/// Collect `<@id>`, `<@!id>`, `<@&id>`, `@everyone` and `@here`.
+pub fn parse_mentions(content: &str) -> ParsedMentions {
+    let mut mentions = ParsedMentions {
+        everyone: content.contains("@everyone"),
+        here: content.contains("@here"),
+        ..ParsedMentions::default()
+    };
+    let mut rest = content;
+    while let Some(start) = rest.find("<@") {
+        rest = &rest[start + 2..];
+        let (is_role, body) = match rest.as_bytes().first() {
+            Some(b'&') => (true, &rest[1..]),
+            Some(b'!') => (false, &rest[1..]),
+            _ => (false, rest),
+        };
+        let Some(end) = body.find('>') else {
+            break;
+        };
+        let id = &body[..end];
+        if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(id) = id.parse::<i64>() {
+                if is_role {
+                    mentions.roles.insert(id);
+                } else {
+                    mentions.users.insert(id);
+                }
+            }
+        }
+    }
+    mentions
+}
+
+/// Count the mentions in a new guild message and return the members whose
+/// `mention_count` went up. Members already counted for `message_id` are
+/// neither counted nor returned again.
+pub async fn record_mentions(
+    state: &AppState,
+    channel: &ChannelRow,
+    message_id: i64,
+    author_id: i64,
+    content: &str,
+) -> Result<Vec<i64>, CoreError> {
+    let recipients = mention_recipients(state, channel, author_id, content).await?;
+    if recipients.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut tx = paracord_db::begin(&state.db).await?;
+    let counted = paracord_db::read_states::increment_mention_counts(
+        &mut tx,
+        channel.id,
+        message_id,
+        &recipients,
+    )
+    .await?;
+    tx.commit().await.map_err(paracord_db::DbError::from)?;
+    Ok(counted)
+}
+
+/// Members a new guild message's mentions should count for, after their
+/// notification settings. The author is never included, and members who
+/// can't see the channel are skipped.
+pub async fn mention_recipients(
+    state: &AppState,
+    channel: &ChannelRow,
+    author_id: i64,
+    content: &str,
+) -> Result<Vec<i64>, CoreError> {
+    let Some(guild_id) = channel.guild_id() else {
+        return Ok(Vec::new());
+    };
+    let mut mentions = parse_mentions(content);
+    if mentions.users.is_empty()
+        && mentions.roles.is_empty()
+        && !mentions.everyone
+        && !mentions.here
+    {
+        return Ok(Vec::new());
+    }
+    let guild = paracord_db::guilds::get_guild(&state.db, guild_id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    let author_perms = crate::permissions::compute_channel_permissions_cached(
+        &state.permission_cache,
+        &state.db,
+        guild_id,
+        channel.id,
+        guild.owner_id,
+        author_id,
+    )
+    .await?;
+    let can_mention_everyone = author_perms.contains(Permissions::MENTION_EVERYONE);
+    if mentions.roles.remove(&guild_id) {
+        mentions.everyone = true;
+    }
+
+    // user id -> whether only a broad mention reached them
+    let mut candidates: HashMap<i64, bool> = HashMap::new();
+    if can_mention_everyone && (mentions.everyone || mentions.here) {
+        let members = paracord_db::members::get_guild_member_user_ids(&state.db, guild_id).await?;
+        let online = if mentions.everyone {
+            None
+        } else {
+            Some(state.online_users.read().await.clone())
+        };
+        for user_id in members {
+            if online
+                .as_ref()
+                .is_none_or(|online| online.contains(&user_id))
+            {
+                candidates.insert(user_id, true);
+            }
+        }
+    }
+    for role_id in &mentions.roles {
+        let Some(role) = paracord_db::roles::get_role(&state.db, *role_id).await? else {
+            continue;
+        };
+        if role.space_id != guild_id || !(role.mentionable || can_mention_everyone) {
+            continue;
+        }
+        for user_id in paracord_db::roles::get_role_member_ids(&state.db, guild_id, role.id).await?
+        {
+            candidates.insert(user_id, false);
+        }
+    }
+    for user_id in &mentions.users {
+        candidates.insert(*user_id, false);
+    }
+    candidates.remove(&author_id);
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let settings_channel_id = if channel.channel_type == 6 {
+        channel.parent_id.unwrap_or(channel.id)
+    } else {
+        channel.id
+    };
+    let user_ids: Vec<i64> = candidates.keys().copied().collect();
+    let mut guild_settings = HashMap::new();
+    let mut overrides = HashMap::new();
+    for chunk in user_ids.chunks(LOOKUP_CHUNK) {
+        for row in paracord_db::notification_settings::get_guild_settings_for_users(
+            &state.db, guild_id, chunk,
+        )
+        .await?
+        {
+            guild_settings.insert(row.user_id, row);
+        }
+        for row in paracord_db::notification_settings::get_channel_overrides_for_users(
+            &state.db,
+            settings_channel_id,
+            chunk,
+        )
+        .await?
+        {
+            overrides.insert(row.user_id, row);
+        }
+    }
+
+    let now = Utc::now();
+    let mut notified = Vec::new();
+    for (user_id, broad) in candidates {
+        let guild_row = guild_settings
+            .remove(&user_id)
+            .unwrap_or_else(|| GuildNotificationSettingsRow::defaults(user_id, guild_id));
+        let effective = EffectiveSettings::resolve(&guild_row, overrides.get(&user_id), now);
+        if !effective.counts_mention(broad) {
+            continue;
+        }
+        if !crate::permissions::is_guild_member(&state.db, guild_id, user_id).await? {
+            continue;
+        }
+        let perms = crate::permissions::compute_channel_permissions_cached(
+            &state.permission_cache,
+            &state.db,
+            guild_id,
+            channel.id,
+            guild.owner_id,
+            user_id,
+        )
+        .await?;
+        if perms.contains(Permissions::VIEW_CHANNEL) {
+            notified.push(user_id);
+        }
+    }
+    notified.sort_unstable();
+    Ok(notified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn parses_user_role_and_broad_mentions() {
+        let parsed = parse_mentions("hi <@1> <@!2> <@&3> <@x> <@4 @here");
+        assert!(parsed.fans_out());
+        assert_eq!(parsed.users.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(parsed.roles.into_iter().collect::<Vec<_>>(), vec![3]);
+        assert!(parsed.here);
+        assert!(!parsed.everyone);
+        assert!(!parse_mentions("hi <@1>").fans_out());
+    }
+
+    #[test]
+    fn channel_overrides_and_expired_mutes_resolve() {
+        let now = Utc::now();
+        let mut guild = GuildNotificationSettingsRow::defaults(1, 10);
+        guild.suppress_everyone = true;
+        let effective = EffectiveSettings::resolve(&guild, None, now);
+        assert!(effective.counts_mention(false));
+        assert!(!effective.counts_mention(true));
+
+        guild.muted = true;
+        guild.muted_until = Some(now - Duration::minutes(1));
+        let channel = ChannelNotificationOverrideRow {
+            user_id: 1,
+            channel_id: 20,
+            guild_id: 10,
+            level: Some(LEVEL_NONE),
+            muted: false,
+            muted_until: None,
+        };
+        let effective = EffectiveSettings::resolve(&guild, Some(&channel), now);
+        assert!(!effective.muted);
+        assert_eq!(effective.level, NotificationLevel::None);
+        assert!(!effective.counts_mention(false));
+
+        guild.muted_until = None;
+        assert!(EffectiveSettings::resolve(&guild, None, now).muted);
+    }
+}
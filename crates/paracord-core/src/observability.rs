@@ -1,6 +1,9 @@
+use dashmap::DashMap;
+use paracord_db::DbPool;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 const EVENT_TYPE_FALLBACK: &str = "OTHER";
 const MAX_EVENT_TYPE_LEN: usize = 64;
@@ -9,10 +12,27 @@ const MAX_EVENT_TYPE_KEYS: usize = 128;
 static WS_CONNECTIONS_ACTIVE: AtomicU64 = AtomicU64::new(0);
 static WS_EVENTS_TOTAL: AtomicU64 = AtomicU64::new(0);
 static WS_EVENTS_BY_TYPE: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+static READY_PAYLOAD_SIZES: PayloadSizes = PayloadSizes::new();
+static GUILD_CREATE_PAYLOAD_SIZES: PayloadSizes = PayloadSizes::new();
 static WIRE_TRACE_ENABLED: OnceLock<bool> = OnceLock::new();
 static WIRE_TRACE_PAYLOADS_ENABLED: OnceLock<bool> = OnceLock::new();
 static WIRE_TRACE_PAYLOAD_MAX_BYTES: OnceLock<usize> = OnceLock::new();
 
+/// A pool probe waiting at least this long counts as pool exhaustion.
+const DB_POOL_EXHAUSTED_WAIT: Duration = Duration::from_millis(250);
+/// Minimum gap between two pool exhaustion warnings.
+const DB_POOL_EXHAUSTED_LOG_INTERVAL: Duration = Duration::from_secs(60);
+/// In-flight requests named in a pool exhaustion warning.
+const DB_POOL_EXHAUSTED_CONTEXT_REQUESTS: usize = 5;
+
+static DB_POOL_WAIT_LAST_US: AtomicU64 = AtomicU64::new(0);
+static DB_POOL_WAIT_SUM_US: AtomicU64 = AtomicU64::new(0);
+static DB_POOL_WAIT_COUNT: AtomicU64 = AtomicU64::new(0);
+static DB_POOL_EXHAUSTED_TOTAL: AtomicU64 = AtomicU64::new(0);
+static DB_POOL_EXHAUSTED_LOGGED_AT: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+static IN_FLIGHT_REQUESTS: OnceLock<DashMap<u64, (String, Instant)>> = OnceLock::new();
+static NEXT_IN_FLIGHT_ID: AtomicU64 = AtomicU64::new(0);
+
 fn ws_events_by_type() -> &'static Mutex<HashMap<String, u64>> {
     WS_EVENTS_BY_TYPE.get_or_init(|| Mutex::new(HashMap::new()))
 }
@@ -111,11 +131,83 @@ pub fn ws_event_dispatched(event_type: &str) {
     *entry = entry.saturating_add(1);
 }
 
+/// Upper bounds, in bytes, of the READY and GUILD_CREATE size buckets.
+pub const WS_PAYLOAD_SIZE_BUCKETS: [u64; 6] =
+    [4_096, 16_384, 65_536, 262_144, 1_048_576, 4_194_304];
+
+struct PayloadSizes {
+    buckets: [AtomicU64; WS_PAYLOAD_SIZE_BUCKETS.len()],
+    count: AtomicU64,
+    sum_bytes: AtomicU64,
+    truncated: AtomicU64,
+}
+
+impl PayloadSizes {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; WS_PAYLOAD_SIZE_BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum_bytes: AtomicU64::new(0),
+            truncated: AtomicU64::new(0),
+        }
+    }
+
+    fn snapshot(&self, event_type: &'static str) -> WsPayloadSizeSnapshot {
+        let mut cumulative = 0;
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|bucket| {
+                cumulative += bucket.load(Ordering::Relaxed);
+                cumulative
+            })
+            .collect();
+        WsPayloadSizeSnapshot {
+            event_type,
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum_bytes: self.sum_bytes.load(Ordering::Relaxed),
+            truncated: self.truncated.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Record the size of a READY or GUILD_CREATE dispatch and whether its
+/// member or presence lists were cut to fit the budget. Other event types
+/// are ignored.
+pub fn ws_payload_sent(event_type: &str, bytes: usize, truncated: bool) {
+    let sizes = match event_type {
+        "READY" => &READY_PAYLOAD_SIZES,
+        "GUILD_CREATE" => &GUILD_CREATE_PAYLOAD_SIZES,
+        _ => return,
+    };
+    let bytes = bytes as u64;
+    if let Some(index) = WS_PAYLOAD_SIZE_BUCKETS.iter().position(|le| bytes <= *le) {
+        sizes.buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+    sizes.count.fetch_add(1, Ordering::Relaxed);
+    sizes.sum_bytes.fetch_add(bytes, Ordering::Relaxed);
+    if truncated {
+        sizes.truncated.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WsPayloadSizeSnapshot {
+    pub event_type: &'static str,
+    /// Cumulative counts for each bound in [`WS_PAYLOAD_SIZE_BUCKETS`].
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum_bytes: u64,
+    pub truncated: u64,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct WsMetricsSnapshot {
     pub active_connections: u64,
     pub total_events: u64,
     pub events_by_type: Vec<(String, u64)>,
+    pub payload_sizes: Vec<WsPayloadSizeSnapshot>,
 }
 
 pub fn ws_metrics_snapshot() -> WsMetricsSnapshot {
@@ -131,6 +223,162 @@ pub fn ws_metrics_snapshot() -> WsMetricsSnapshot {
         active_connections,
         total_events,
         events_by_type,
+        payload_sizes: vec![
+            READY_PAYLOAD_SIZES.snapshot("READY"),
+            GUILD_CREATE_PAYLOAD_SIZES.snapshot("GUILD_CREATE"),
+        ],
+    }
+}
+
+/// Process memory as reported by the kernel, in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcessMemorySnapshot {
+    pub resident_bytes: u64,
+    pub peak_resident_bytes: u64,
+    pub virtual_bytes: u64,
+}
+
+/// Read the process's memory usage. Only Linux exposes it without extra
+/// dependencies; elsewhere this returns `None`.
+pub fn process_memory_snapshot() -> Option<ProcessMemorySnapshot> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_proc_status(&status)
+}
+
+fn parse_proc_status(status: &str) -> Option<ProcessMemorySnapshot> {
+    let field = |name: &str| -> Option<u64> {
+        let line = status.lines().find(|line| line.starts_with(name))?;
+        let kib = line[name.len()..]
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kib.saturating_mul(1024))
+    };
+    Some(ProcessMemorySnapshot {
+        resident_bytes: field("VmRSS:")?,
+        peak_resident_bytes: field("VmHWM:").unwrap_or_default(),
+        virtual_bytes: field("VmSize:").unwrap_or_default(),
+    })
+}
+
+fn in_flight_requests() -> &'static DashMap<u64, (String, Instant)> {
+    IN_FLIGHT_REQUESTS.get_or_init(DashMap::new)
+}
+
+/// Registered for the lifetime of an HTTP request, so pool exhaustion can
+/// be logged with the requests that were holding or waiting on connections.
+pub struct InFlightRequest {
+    id: u64,
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        in_flight_requests().remove(&self.id);
+    }
+}
+
+pub fn track_request(label: String) -> InFlightRequest {
+    let id = NEXT_IN_FLIGHT_ID.fetch_add(1, Ordering::Relaxed);
+    in_flight_requests().insert(id, (label, Instant::now()));
+    InFlightRequest { id }
+}
+
+/// The oldest in-flight requests with how long they have been running.
+pub fn longest_in_flight_requests(limit: usize) -> Vec<(String, Duration)> {
+    let mut requests: Vec<(String, Duration)> = in_flight_requests()
+        .iter()
+        .map(|entry| (entry.0.clone(), entry.1.elapsed()))
+        .collect();
+    requests.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+    requests.truncate(limit);
+    requests
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DbPoolMetricsSnapshot {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+    /// Wait of the most recent probe.
+    pub wait_last: Duration,
+    pub wait_sum: Duration,
+    pub wait_count: u64,
+    pub exhausted_total: u64,
+}
+
+pub fn db_pool_metrics_snapshot(pool: &DbPool) -> DbPoolMetricsSnapshot {
+    DbPoolMetricsSnapshot {
+        size: pool.size(),
+        idle: pool.num_idle(),
+        max_connections: pool.options().get_max_connections(),
+        wait_last: Duration::from_micros(DB_POOL_WAIT_LAST_US.load(Ordering::Relaxed)),
+        wait_sum: Duration::from_micros(DB_POOL_WAIT_SUM_US.load(Ordering::Relaxed)),
+        wait_count: DB_POOL_WAIT_COUNT.load(Ordering::Relaxed),
+        exhausted_total: DB_POOL_EXHAUSTED_TOTAL.load(Ordering::Relaxed),
+    }
+}
+
+/// Record how long a connection checkout waited. Returns whether the wait
+/// counts as pool exhaustion.
+fn record_db_pool_wait(wait: Duration) -> bool {
+    let wait_us = u64::try_from(wait.as_micros()).unwrap_or(u64::MAX);
+    DB_POOL_WAIT_LAST_US.store(wait_us, Ordering::Relaxed);
+    DB_POOL_WAIT_SUM_US.fetch_add(wait_us, Ordering::Relaxed);
+    DB_POOL_WAIT_COUNT.fetch_add(1, Ordering::Relaxed);
+    let exhausted = wait >= DB_POOL_EXHAUSTED_WAIT;
+    if exhausted {
+        DB_POOL_EXHAUSTED_TOTAL.fetch_add(1, Ordering::Relaxed);
+    }
+    exhausted
+}
+
+fn should_log_pool_exhaustion(now: Instant) -> bool {
+    let mut logged_at = match DB_POOL_EXHAUSTED_LOGGED_AT
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+    {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if logged_at.is_some_and(|at| now.duration_since(at) < DB_POOL_EXHAUSTED_LOG_INTERVAL) {
+        return false;
+    }
+    *logged_at = Some(now);
+    true
+}
+
+/// Check out and return one connection, timing the wait a request would
+/// see right now. A slow checkout is logged along with the longest-running
+/// requests, which are the likely connection holders.
+pub async fn sample_db_pool(pool: &DbPool) {
+    let started = Instant::now();
+    let acquired = pool.acquire().await.map(drop);
+    let wait = started.elapsed();
+    let exhausted = record_db_pool_wait(wait);
+    if let Err(err) = &acquired {
+        tracing::warn!(
+            "Database pool probe failed after {:?}: {} (size {}, idle {}, max {})",
+            wait,
+            err,
+            pool.size(),
+            pool.num_idle(),
+            pool.options().get_max_connections()
+        );
+    } else if exhausted && should_log_pool_exhaustion(Instant::now()) {
+        let requests = longest_in_flight_requests(DB_POOL_EXHAUSTED_CONTEXT_REQUESTS)
+            .into_iter()
+            .map(|(label, elapsed)| format!("{} ({:?})", label, elapsed))
+            .collect::<Vec<_>>()
+            .join(", ");
+        tracing::warn!(
+            "Database pool exhausted: waited {:?} for a connection (size {}, max {}); longest in-flight requests: {}",
+            wait,
+            pool.size(),
+            pool.options().get_max_connections(),
+            if requests.is_empty() { "none" } else { &requests }
+        );
     }
 }
 
@@ -145,6 +393,41 @@ fn reset_for_tests() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn proc_status_memory_fields_are_parsed() {
+        let status =
+            "Name:\tparacord\nVmHWM:\t   2048 kB\nVmRSS:\t   1024 kB\nVmSize:\t  8192 kB\n";
+        assert_eq!(
+            parse_proc_status(status),
+            Some(ProcessMemorySnapshot {
+                resident_bytes: 1024 * 1024,
+                peak_resident_bytes: 2048 * 1024,
+                virtual_bytes: 8192 * 1024,
+            })
+        );
+        assert_eq!(parse_proc_status("Name:\tparacord\n"), None);
+    }
+
+    #[test]
+    fn in_flight_requests_are_listed_oldest_first() {
+        let first = track_request("GET /api/v1/slow".to_string());
+        std::thread::sleep(Duration::from_millis(5));
+        let second = track_request("GET /api/v1/fast".to_string());
+        let longest = longest_in_flight_requests(usize::MAX);
+        let slow = longest
+            .iter()
+            .position(|(label, _)| label == "GET /api/v1/slow");
+        let fast = longest
+            .iter()
+            .position(|(label, _)| label == "GET /api/v1/fast");
+        assert!(slow.unwrap() < fast.unwrap());
+        drop(first);
+        drop(second);
+        assert!(!longest_in_flight_requests(usize::MAX)
+            .iter()
+            .any(|(label, _)| label.ends_with("/slow")));
+    }
+
     #[test]
     fn ws_connection_close_is_saturating() {
         reset_for_tests();
@@ -106,6 +106,48 @@ pub fn is_server_admin(perms: Permissions) -> bool {
     perms.contains(Permissions::ADMINISTRATOR)
 }
 
+/// Moderation and management permissions, which self-assignable roles may
+/// not grant.
+pub fn privileged_permissions() -> Permissions {
+    Permissions::ADMINISTRATOR
+        | Permissions::KICK_MEMBERS
+        | Permissions::BAN_MEMBERS
+        | Permissions::MANAGE_CHANNELS
+        | Permissions::MANAGE_GUILD
+        | Permissions::VIEW_AUDIT_LOG
+        | Permissions::MANAGE_MESSAGES
+        | Permissions::MENTION_EVERYONE
+        | Permissions::MUTE_MEMBERS
+        | Permissions::DEAFEN_MEMBERS
+        | Permissions::MOVE_MEMBERS
+        | Permissions::MANAGE_NICKNAMES
+        | Permissions::MANAGE_ROLES
+        | Permissions::MANAGE_WEBHOOKS
+        | Permissions::MANAGE_EMOJIS
+        | Permissions::RECORD_VOICE
+        | Permissions::POST_OVERRIDE
+        | Permissions::MANAGE_THREADS
+        | Permissions::MODERATE_MEMBERS
+        | Permissions::MANAGE_INVITES
+        | Permissions::VIEW_REPORTS
+}
+
+/// Who receives moderation reports for a guild: the owner plus members with
+/// `VIEW_REPORTS` or `ADMINISTRATOR`.
+pub async fn report_recipients(
+    pool: &DbPool,
+    guild_id: i64,
+    guild_owner_id: i64,
+) -> Result<Vec<i64>, CoreError> {
+    let mask = (Permissions::VIEW_REPORTS | Permissions::ADMINISTRATOR).bits();
+    let mut recipients =
+        paracord_db::roles::get_member_ids_with_permissions(pool, guild_id, mask).await?;
+    if !recipients.contains(&guild_owner_id) {
+        recipients.push(guild_owner_id);
+    }
+    Ok(recipients)
+}
+
 /// Compute permissions from a set of Role rows
 pub fn compute_permissions_from_roles(
     roles: &[paracord_db::roles::RoleRow],
@@ -340,6 +382,8 @@ mod tests {
             managed: false,
             mentionable: false,
             server_wide: false,
+            self_assignable: false,
+            self_assign_group: None,
             created_at: Utc::now(),
         }
     }
@@ -1,18 +1,214 @@
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinHandle;
 
-/// Manages deferred offline presence transitions to avoid race conditions
-/// between connection guard drops and reconnections.
+use crate::AppState;
+
+/// Who may see a user's presence and activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresenceVisibility {
+    #[default]
+    Everyone,
+    Friends,
+    Nobody,
+}
+
+impl PresenceVisibility {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "everyone" => Some(Self::Everyone),
+            "friends" => Some(Self::Friends),
+            "nobody" => Some(Self::Nobody),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Everyone => "everyone",
+            Self::Friends => "friends",
+            Self::Nobody => "nobody",
+        }
+    }
+}
+
+/// A user's presence privacy settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresencePrivacy {
+    pub visibility: PresenceVisibility,
+    /// Guilds whose members should see the user as offline.
+    pub hidden_guild_ids: HashSet<i64>,
+}
+
+impl PresencePrivacy {
+    pub fn from_settings(settings: &paracord_db::users::UserSettingsRow) -> Self {
+        Self {
+            visibility: PresenceVisibility::parse(&settings.presence_visibility)
+                .unwrap_or_default(),
+            hidden_guild_ids: settings.presence_hidden_guild_ids.iter().copied().collect(),
+        }
+    }
+
+    /// Whether another user may see this user's presence, given whether they
+    /// are friends and which guilds they share.
+    pub fn visible_to(&self, is_friend: bool, shared_guild_ids: &[i64]) -> bool {
+        match self.visibility {
+            PresenceVisibility::Nobody => false,
+            PresenceVisibility::Friends => is_friend,
+            PresenceVisibility::Everyone => {
+                is_friend
+                    || shared_guild_ids
+                        .iter()
+                        .any(|guild_id| !self.hidden_guild_ids.contains(guild_id))
+            }
+        }
+    }
+}
+
+/// The kind of client a gateway session runs in, from the `client`
+/// property sent with IDENTIFY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientType {
+    Desktop,
+    #[default]
+    Web,
+    Mobile,
+}
+
+impl ClientType {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "desktop" => Some(Self::Desktop),
+            "web" => Some(Self::Web),
+            "mobile" => Some(Self::Mobile),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Desktop => "desktop",
+            Self::Web => "web",
+            Self::Mobile => "mobile",
+        }
+    }
+}
+
+/// What one gateway session reports about its user. A user's presence is
+/// merged from all of their sessions' entries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionPresence {
+    /// `online`, `idle`, `dnd` or `offline` (invisible).
+    pub status: String,
+    pub client: ClientType,
+    pub custom_status: Option<String>,
+    pub activities: Vec<Value>,
+    /// Milliseconds since the epoch. Of two copies of an entry, the later
+    /// one wins.
+    pub updated_at: i64,
+}
+
+/// A user's session entries, keyed by gateway session id.
+pub type SessionPresences = HashMap<String, SessionPresence>;
+
+/// Priority of a status when merging sessions; the highest one shows.
+fn status_rank(status: &str) -> u8 {
+    match status {
+        "dnd" => 3,
+        "online" => 2,
+        "idle" => 1,
+        _ => 0,
+    }
+}
+
+fn higher_status<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if status_rank(b) > status_rank(a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// Fold `other` into `into`: every session from either side, and the later
+/// update where both have one. Merging is commutative and idempotent, so
+/// servers that exchange their entries agree however they are combined.
+pub fn merge_sessions(into: &mut SessionPresences, other: &SessionPresences) {
+    for (session_id, entry) in other {
+        match into.get(session_id) {
+            Some(existing) if existing.updated_at >= entry.updated_at => {}
+            _ => {
+                into.insert(session_id.clone(), entry.clone());
+            }
+        }
+    }
+}
+
+/// The presence others see for a user with `sessions`: the highest status
+/// of any session, the status per client type in `client_status`, the
+/// custom status last set, and the activities of every visible session,
+/// newest first. Sessions that are invisible count as offline. `None` when
+/// no session is left.
+pub fn merged_presence(user_id: i64, sessions: &SessionPresences) -> Option<Value> {
+    let mut entries: Vec<&SessionPresence> = sessions.values().collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.updated_at));
+
+    let mut status = "offline";
+    let mut client_status: HashMap<&str, &str> = HashMap::new();
+    let mut activities = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| status_rank(&entry.status) > 0)
+    {
+        status = higher_status(status, &entry.status);
+        let client = client_status
+            .entry(entry.client.as_str())
+            .or_insert("offline");
+        *client = higher_status(client, &entry.status);
+        activities.extend(entry.activities.iter().cloned());
+    }
+    let custom_status = if status == "offline" {
+        None
+    } else {
+        entries[0].custom_status.as_deref()
+    };
+    Some(json!({
+        "user_id": user_id.to_string(),
+        "status": status,
+        "custom_status": custom_status,
+        "activities": activities,
+        "client_status": client_status,
+    }))
+}
+
+/// Tracks each user's presence per gateway session and defers the removal
+/// of a disconnected session's entry, so a client that reconnects or
+/// resumes doesn't briefly show its user offline.
+///
+/// When a session disconnects, the handler schedules its removal through
+/// this manager. If the session resumes within the grace period, the
+/// pending removal is cancelled. The user goes offline once their last
+/// session is removed. With a cluster, entries of sessions on other
+/// servers are merged in as well.
 ///
-/// When a user disconnects, instead of immediately marking them offline,
-/// the handler schedules a delayed check via this manager. If the user
-/// reconnects within the grace period, the pending offline task is cancelled.
+/// It also caches each user's presence privacy so fan-out and READY presence
+/// lists can be filtered without a settings query per member.
 pub struct PresenceManager {
-    pending_offlines: Arc<DashMap<i64, JoinHandle<()>>>,
+    pending_offlines: Arc<DashMap<String, JoinHandle<()>>>,
     grace_period: Duration,
+    privacy: DashMap<i64, PresencePrivacy>,
+    /// Entries of sessions connected to this server.
+    sessions: DashMap<i64, SessionPresences>,
+    /// Entries of sessions on other servers, as of the last cluster sync.
+    remote_sessions: DashMap<i64, SessionPresences>,
 }
 
 impl PresenceManager {
@@ -20,35 +216,144 @@ impl PresenceManager {
         Self {
             pending_offlines: Arc::new(DashMap::new()),
             grace_period: Duration::from_millis(1500),
+            privacy: DashMap::new(),
+            sessions: DashMap::new(),
+            remote_sessions: DashMap::new(),
         }
     }
 
-    /// Schedule a deferred offline check for `user_id`.
+    /// Schedule a deferred offline check for `session_id`.
     ///
-    /// Any previously pending offline task for the same user is cancelled first.
+    /// Any previously pending task for the same session is cancelled first.
     /// After `grace_period` elapses, the provided future runs (which should
-    /// re-check connection count and mark offline only if still 0).
-    pub fn schedule_offline<F>(&self, user_id: i64, task: F)
+    /// remove the session's entry and publish what changed).
+    pub fn schedule_offline<F>(&self, session_id: &str, task: F)
     where
         F: Future<Output = ()> + Send + 'static,
     {
-        self.cancel_offline(user_id);
+        self.cancel_offline(session_id);
         let pending = self.pending_offlines.clone();
         let delay = self.grace_period;
+        let key = session_id.to_string();
         let handle = tokio::spawn(async move {
             tokio::time::sleep(delay).await;
             task.await;
-            pending.remove(&user_id);
+            pending.remove(&key);
         });
-        self.pending_offlines.insert(user_id, handle);
+        self.pending_offlines.insert(session_id.to_string(), handle);
     }
 
-    /// Cancel any pending offline task for `user_id` (e.g. on reconnect).
-    pub fn cancel_offline(&self, user_id: i64) {
-        if let Some((_, handle)) = self.pending_offlines.remove(&user_id) {
+    /// Cancel any pending offline task for `session_id` (e.g. on resume).
+    pub fn cancel_offline(&self, session_id: &str) {
+        if let Some((_, handle)) = self.pending_offlines.remove(session_id) {
             handle.abort();
         }
     }
+
+    /// What `session_id` last reported for `user_id`.
+    pub fn session_presence(&self, user_id: i64, session_id: &str) -> Option<SessionPresence> {
+        self.sessions
+            .get(&user_id)
+            .and_then(|sessions| sessions.get(session_id).cloned())
+    }
+
+    pub fn set_session_presence(&self, user_id: i64, session_id: &str, presence: SessionPresence) {
+        self.sessions
+            .entry(user_id)
+            .or_default()
+            .insert(session_id.to_string(), presence);
+    }
+
+    pub fn remove_session(&self, user_id: i64, session_id: &str) {
+        self.sessions.remove_if_mut(&user_id, |_, sessions| {
+            sessions.remove(session_id);
+            sessions.is_empty()
+        });
+    }
+
+    /// Whether `user_id` has a session on this server or, as of the last
+    /// sync, on another one.
+    pub fn has_sessions(&self, user_id: i64) -> bool {
+        self.sessions.contains_key(&user_id) || self.remote_sessions.contains_key(&user_id)
+    }
+
+    /// Entries of every user with a session on this server.
+    pub fn local_sessions(&self) -> Vec<(i64, SessionPresences)> {
+        self.sessions
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Replace the entries of sessions on other servers. Returns the users
+    /// whose remote entries changed.
+    pub fn set_remote_sessions(&self, remote: HashMap<i64, SessionPresences>) -> HashSet<i64> {
+        let mut changed: HashSet<i64> = self
+            .remote_sessions
+            .iter()
+            .filter(|entry| remote.get(entry.key()) != Some(entry.value()))
+            .map(|entry| *entry.key())
+            .collect();
+        changed.extend(
+            remote
+                .iter()
+                .filter(|(user_id, sessions)| {
+                    self.remote_sessions
+                        .get(user_id)
+                        .is_none_or(|existing| existing.value() != *sessions)
+                })
+                .map(|(user_id, _)| *user_id),
+        );
+        self.remote_sessions.clear();
+        for (user_id, sessions) in remote {
+            self.remote_sessions.insert(user_id, sessions);
+        }
+        changed
+    }
+
+    /// `user_id`'s session entries here and elsewhere.
+    fn all_sessions(&self, user_id: i64) -> SessionPresences {
+        let mut sessions = self
+            .sessions
+            .get(&user_id)
+            .map(|sessions| sessions.clone())
+            .unwrap_or_default();
+        if let Some(remote) = self.remote_sessions.get(&user_id) {
+            merge_sessions(&mut sessions, &remote);
+        }
+        sessions
+    }
+
+    /// `user_id`'s presence merged across their sessions here and elsewhere.
+    pub fn merged(&self, user_id: i64) -> Option<Value> {
+        merged_presence(user_id, &self.all_sessions(user_id))
+    }
+
+    /// The custom status `user_id` last set from any of their sessions, for
+    /// a new session to carry over.
+    pub fn custom_status(&self, user_id: i64) -> Option<String> {
+        self.all_sessions(user_id)
+            .into_values()
+            .max_by_key(|entry| entry.updated_at)
+            .and_then(|entry| entry.custom_status)
+    }
+
+    /// Cached privacy settings for `user_id`, if they have been loaded.
+    pub fn cached_privacy(&self, user_id: i64) -> Option<PresencePrivacy> {
+        self.privacy.get(&user_id).map(|entry| entry.clone())
+    }
+
+    /// Whether `user_id`'s presence is shown to `guild_id`'s members who
+    /// aren't their friends. Uncached settings count as the default.
+    pub fn visible_in_guild(&self, user_id: i64, guild_id: i64) -> bool {
+        self.privacy
+            .get(&user_id)
+            .is_none_or(|privacy| privacy.visible_to(false, &[guild_id]))
+    }
+
+    pub fn set_privacy(&self, user_id: i64, privacy: PresencePrivacy) {
+        self.privacy.insert(user_id, privacy);
+    }
 }
 
 impl Default for PresenceManager {
@@ -56,3 +361,265 @@ impl Default for PresenceManager {
         Self::new()
     }
 }
+
+/// Presence privacy for `user_id`, loading it from settings on a cache miss.
+pub async fn load_privacy(state: &AppState, user_id: i64) -> PresencePrivacy {
+    if let Some(privacy) = state.presence_manager.cached_privacy(user_id) {
+        return privacy;
+    }
+    let privacy = match paracord_db::users::get_user_settings(&state.db, user_id).await {
+        Ok(Some(settings)) => PresencePrivacy::from_settings(&settings),
+        Ok(None) => PresencePrivacy::default(),
+        // Don't cache on error so the next lookup retries.
+        Err(_) => return PresencePrivacy::default(),
+    };
+    state.presence_manager.set_privacy(user_id, privacy.clone());
+    privacy
+}
+
+fn recipients_for(
+    state: &AppState,
+    user_id: i64,
+    guild_ids: &[i64],
+    friend_ids: &[i64],
+    privacy: &PresencePrivacy,
+) -> HashSet<i64> {
+    let mut recipients = HashSet::new();
+    if privacy.visibility == PresenceVisibility::Everyone {
+        let visible_guild_ids: Vec<i64> = guild_ids
+            .iter()
+            .copied()
+            .filter(|guild_id| !privacy.hidden_guild_ids.contains(guild_id))
+            .collect();
+        recipients = state
+            .member_index
+            .get_presence_recipients(user_id, &visible_guild_ids);
+    }
+    if privacy.visibility != PresenceVisibility::Nobody {
+        recipients.extend(friend_ids.iter().copied());
+    }
+    recipients.insert(user_id);
+    recipients
+}
+
+/// Users allowed to receive `user_id`'s presence updates: guild members and
+/// friends, narrowed by the user's privacy settings. Always includes the user.
+pub async fn presence_recipients(state: &AppState, user_id: i64, guild_ids: &[i64]) -> Vec<i64> {
+    let privacy = load_privacy(state, user_id).await;
+    let friend_ids = if privacy.visibility == PresenceVisibility::Nobody {
+        Vec::new()
+    } else {
+        paracord_db::relationships::get_friend_user_ids(&state.db, user_id)
+            .await
+            .unwrap_or_default()
+    };
+    recipients_for(state, user_id, guild_ids, &friend_ids, &privacy)
+        .into_iter()
+        .collect()
+}
+
+/// Whether `viewer_id` may see `target_id`'s presence.
+pub async fn can_view_presence(state: &AppState, viewer_id: i64, target_id: i64) -> bool {
+    if viewer_id == target_id {
+        return true;
+    }
+    let privacy = load_privacy(state, target_id).await;
+    if privacy.visibility == PresenceVisibility::Nobody {
+        return false;
+    }
+    let is_friend = paracord_db::relationships::are_friends(&state.db, viewer_id, target_id)
+        .await
+        .unwrap_or(false);
+    if privacy.visibility == PresenceVisibility::Friends || is_friend {
+        return is_friend;
+    }
+    let shared_guild_ids: Vec<i64> =
+        paracord_db::users::get_mutual_guilds(&state.db, viewer_id, target_id)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|guild| guild.id)
+            .collect();
+    privacy.visible_to(false, &shared_guild_ids)
+}
+
+/// Offline placeholder sent to viewers who may not see a user's presence.
+pub fn offline_presence(user_id: i64) -> Value {
+    json!({
+        "user_id": user_id.to_string(),
+        "status": "offline",
+        "custom_status": Value::Null,
+        "activities": [],
+        "client_status": {},
+    })
+}
+
+/// Recompute `user_id`'s presence from their sessions into
+/// `user_presences`, offline once none is left. Returns the new presence
+/// when it changed.
+pub async fn refresh_presence(state: &AppState, user_id: i64) -> Option<Value> {
+    let presence = state
+        .presence_manager
+        .merged(user_id)
+        .unwrap_or_else(|| offline_presence(user_id));
+    let mut presences = state.user_presences.write().await;
+    if presences.get(&user_id) == Some(&presence) {
+        return None;
+    }
+    presences.insert(user_id, presence.clone());
+    Some(presence)
+}
+
+/// Store new privacy settings and reconcile what other users currently see:
+/// viewers who lost access get an offline update, newly allowed viewers get
+/// the live presence.
+pub async fn apply_privacy(state: &AppState, user_id: i64, privacy: PresencePrivacy) {
+    let previous = load_privacy(state, user_id).await;
+    state.presence_manager.set_privacy(user_id, privacy.clone());
+    if previous == privacy || !state.online_users.read().await.contains(&user_id) {
+        return;
+    }
+    let Some(presence) = state.user_presences.read().await.get(&user_id).cloned() else {
+        return;
+    };
+
+    let guild_ids: Vec<i64> = paracord_db::guilds::get_user_guilds(&state.db, user_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|guild| guild.id)
+        .collect();
+    crate::member_list::privacy_changed(state, user_id, &guild_ids, &previous, &privacy).await;
+    let friend_ids = paracord_db::relationships::get_friend_user_ids(&state.db, user_id)
+        .await
+        .unwrap_or_default();
+    let before = recipients_for(state, user_id, &guild_ids, &friend_ids, &previous);
+    let after = recipients_for(state, user_id, &guild_ids, &friend_ids, &privacy);
+
+    let revoked: Vec<i64> = before.difference(&after).copied().collect();
+    if !revoked.is_empty() {
+        state
+            .event_bus
+            .dispatch_to_users("PRESENCE_UPDATE", offline_presence(user_id), revoked);
+    }
+    let granted: Vec<i64> = after.difference(&before).copied().collect();
+    if !granted.is_empty() {
+        state
+            .event_bus
+            .dispatch_to_users("PRESENCE_UPDATE", presence, granted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn privacy(visibility: PresenceVisibility, hidden: &[i64]) -> PresencePrivacy {
+        PresencePrivacy {
+            visibility,
+            hidden_guild_ids: hidden.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn everyone_visibility_respects_hidden_guilds() {
+        let p = privacy(PresenceVisibility::Everyone, &[1]);
+        assert!(!p.visible_to(false, &[1]));
+        assert!(p.visible_to(false, &[1, 2]));
+        assert!(p.visible_to(true, &[1]));
+        assert!(!p.visible_to(false, &[]));
+    }
+
+    #[test]
+    fn friends_and_nobody_visibility() {
+        let friends = privacy(PresenceVisibility::Friends, &[]);
+        assert!(friends.visible_to(true, &[]));
+        assert!(!friends.visible_to(false, &[1]));
+
+        let nobody = privacy(PresenceVisibility::Nobody, &[]);
+        assert!(!nobody.visible_to(true, &[1]));
+    }
+
+    #[test]
+    fn visibility_round_trips() {
+        for v in [
+            PresenceVisibility::Everyone,
+            PresenceVisibility::Friends,
+            PresenceVisibility::Nobody,
+        ] {
+            assert_eq!(PresenceVisibility::parse(v.as_str()), Some(v));
+        }
+        assert_eq!(PresenceVisibility::parse("invisible"), None);
+    }
+
+    fn entry(status: &str, client: ClientType, updated_at: i64) -> SessionPresence {
+        SessionPresence {
+            status: status.to_string(),
+            client,
+            custom_status: None,
+            activities: Vec::new(),
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn merged_status_takes_highest_priority_per_client() {
+        let mut sessions = SessionPresences::new();
+        sessions.insert("a".into(), entry("idle", ClientType::Desktop, 1));
+        sessions.insert("b".into(), entry("online", ClientType::Mobile, 2));
+        sessions.insert("c".into(), entry("dnd", ClientType::Desktop, 3));
+        sessions.insert("d".into(), entry("offline", ClientType::Web, 4));
+
+        let presence = merged_presence(7, &sessions).unwrap();
+        assert_eq!(presence["status"], "dnd");
+        assert_eq!(
+            presence["client_status"],
+            json!({ "desktop": "dnd", "mobile": "online" })
+        );
+
+        sessions.retain(|id, _| id == "d");
+        let presence = merged_presence(7, &sessions).unwrap();
+        assert_eq!(presence["status"], "offline");
+        assert_eq!(presence["client_status"], json!({}));
+        assert!(merged_presence(7, &SessionPresences::new()).is_none());
+    }
+
+    #[test]
+    fn merge_keeps_latest_entry_in_any_order() {
+        let mut left = SessionPresences::new();
+        left.insert("a".into(), entry("online", ClientType::Web, 5));
+        left.insert("b".into(), entry("idle", ClientType::Web, 1));
+        let mut right = SessionPresences::new();
+        right.insert("a".into(), entry("dnd", ClientType::Web, 3));
+        right.insert("b".into(), entry("online", ClientType::Web, 2));
+        right.insert("c".into(), entry("idle", ClientType::Mobile, 1));
+
+        let mut forward = left.clone();
+        merge_sessions(&mut forward, &right);
+        let mut backward = right.clone();
+        merge_sessions(&mut backward, &left);
+        assert_eq!(forward, backward);
+        assert_eq!(forward["a"].status, "online");
+        assert_eq!(forward["b"].status, "online");
+        assert_eq!(forward.len(), 3);
+
+        let mut again = forward.clone();
+        merge_sessions(&mut again, &right);
+        assert_eq!(again, forward);
+    }
+
+    #[test]
+    fn user_stays_online_until_last_session_is_removed() {
+        let manager = PresenceManager::new();
+        manager.set_session_presence(7, "a", entry("online", ClientType::Desktop, 1));
+        manager.set_session_presence(7, "b", entry("idle", ClientType::Mobile, 2));
+
+        manager.remove_session(7, "a");
+        assert!(manager.has_sessions(7));
+        assert_eq!(manager.merged(7).unwrap()["status"], "idle");
+
+        manager.remove_session(7, "b");
+        assert!(!manager.has_sessions(7));
+        assert!(manager.merged(7).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+//! Web Push for users who aren't connected.
+//!
+//! Mentions that got through the member's notification settings (see
+//! [`crate::notifications::record_mentions`]) and messages in accepted DMs
+//! are queued in `push_deliveries`, once per push subscription of every
+//! recipient without a gateway connection. The server's delivery worker
+//! encrypts and sends them. Deliveries are released at most
+//! [`DELIVERIES_PER_SECOND`] at a time, so an `@everyone` in a large guild
+//! reaches the push services over several seconds instead of in one burst.
+
+use chrono::{Duration, Utc};
+use paracord_db::channels::ChannelRow;
+use paracord_db::messages::MessageRow;
+use paracord_db::DbTransaction;
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+use crate::AppState;
+
+/// Most push subscriptions one user may register.
+pub const MAX_SUBSCRIPTIONS_PER_USER: usize = 10;
+/// Longest message preview put in a notification, in characters.
+const MAX_PREVIEW_CHARS: usize = 180;
+const LOOKUP_CHUNK: usize = 500;
+/// Deliveries of one message that become due in the same second.
+pub const DELIVERIES_PER_SECOND: usize = 200;
+
+fn preview(message: &MessageRow) -> String {
+    if message.e2ee_header.is_some() {
+        return "Sent an encrypted message".to_string();
+    }
+    let content = message.content.as_deref().unwrap_or("").trim();
+    if content.is_empty() {
+        return "Sent an attachment".to_string();
+    }
+    let mut chars = content.chars();
+    let mut preview: String = chars.by_ref().take(MAX_PREVIEW_CHARS).collect();
+    if chars.next().is_some() {
+        preview.push('…');
+    }
+    preview
+}
+
+/// Notification for a new message, as the service worker receives it.
+pub fn message_payload(channel: &ChannelRow, message: &MessageRow, author_name: &str) -> Value {
+    let title = match (channel.guild_id(), channel.name.as_deref()) {
+        (Some(_), Some(name)) => format!("{author_name} in #{name}"),
+        _ => author_name.to_string(),
+    };
+    json!({
+        "type": "message",
+        "title": title,
+        "body": preview(message),
+        "tag": channel.id.to_string(),
+        "channel_id": channel.id.to_string(),
+        "guild_id": channel.guild_id().map(|id| id.to_string()),
+        "message_id": message.id.to_string(),
+    })
+}
+
+/// Queue a push of `message` to each of `recipients` that isn't online.
+/// Returns the number of deliveries queued.
+pub async fn queue_message_push(
+    state: &AppState,
+    channel: &ChannelRow,
+    message: &MessageRow,
+    recipients: &[i64],
+) -> Result<usize, CoreError> {
+    let mut tx = paracord_db::begin(&state.db).await?;
+    let queued = queue_message_push_in(&mut tx, state, channel, message, recipients).await?;
+    tx.commit().await.map_err(paracord_db::DbError::from)?;
+    Ok(queued)
+}
+
+/// [`queue_message_push`] inside the caller's transaction, so the pushes
+/// commit or roll back together with whatever decided who gets them.
+pub async fn queue_message_push_in(
+    tx: &mut DbTransaction,
+    state: &AppState,
+    channel: &ChannelRow,
+    message: &MessageRow,
+    recipients: &[i64],
+) -> Result<usize, CoreError> {
+    if state.config.web_push_public_key.is_none() || recipients.is_empty() {
+        return Ok(0);
+    }
+    let offline: Vec<i64> = {
+        let online = state.online_users.read().await;
+        recipients
+            .iter()
+            .copied()
+            .filter(|user_id| *user_id != message.author_id && !online.contains(user_id))
+            .collect()
+    };
+    if offline.is_empty() {
+        return Ok(0);
+    }
+
+    let mut subscription_ids = Vec::new();
+    for chunk in offline.chunks(LOOKUP_CHUNK) {
+        subscription_ids.extend(
+            paracord_db::push_subscriptions::get_subscription_ids_for_users(&mut **tx, chunk)
+                .await?,
+        );
+    }
+    if subscription_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let author_name = paracord_db::users::get_user_by_id(&mut **tx, message.author_id)
+        .await?
+        .map(|user| user.display_name.unwrap_or(user.username))
+        .unwrap_or_else(|| "Someone".to_string());
+    let payload = message_payload(channel, message, &author_name).to_string();
+    let mut deliveries: Vec<(i64, i64)> = Vec::with_capacity(subscription_ids.len());
+    for subscription_id in subscription_ids {
+        deliveries.push((
+            paracord_util::snowflake::try_next_id().await?,
+            subscription_id,
+        ));
+    }
+    let now = Utc::now();
+    for (second, chunk) in deliveries.chunks(DELIVERIES_PER_SECOND).enumerate() {
+        paracord_db::push_subscriptions::enqueue_deliveries(
+            tx,
+            chunk,
+            &payload,
+            now + Duration::seconds(second as i64),
+        )
+        .await?;
+    }
+    Ok(deliveries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn message(content: &str) -> MessageRow {
+        MessageRow {
+            id: 3,
+            channel_id: 2,
+            author_id: 1,
+            content: Some(content.to_string()),
+            nonce: None,
+            message_type: 0,
+            flags: 0,
+            edited_at: None,
+            pinned: false,
+            reference_id: None,
+            e2ee_header: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn previews_are_truncated_and_hide_encrypted_content() {
+        assert_eq!(preview(&message("  hello ")), "hello");
+        assert_eq!(preview(&message("")), "Sent an attachment");
+        let long = "x".repeat(MAX_PREVIEW_CHARS + 5);
+        let truncated = preview(&message(&long));
+        assert_eq!(truncated.chars().count(), MAX_PREVIEW_CHARS + 1);
+        assert!(truncated.ends_with('…'));
+        let mut encrypted = message("ciphertext");
+        encrypted.e2ee_header = Some("{}".into());
+        assert_eq!(preview(&encrypted), "Sent an encrypted message");
+    }
+}
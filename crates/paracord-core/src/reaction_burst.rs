@@ -0,0 +1,176 @@
+//! Coalescing of reaction bursts.
+//!
+//! A popular message can collect hundreds of reactions within seconds. The
+//! first few changes to a message in a short window go out as the usual
+//! `MESSAGE_REACTION_ADD`/`MESSAGE_REACTION_REMOVE` events; past that, the
+//! rest of the window is folded into a single `MESSAGE_REACTION_BULK`
+//! carrying the message's current counts.
+
+use dashmap::DashMap;
+use paracord_models::gateway::EVENT_MESSAGE_REACTION_BULK;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::AppState;
+
+const BURST_WINDOW: Duration = Duration::from_secs(1);
+/// Changes per message and window that are still dispatched one by one.
+const BURST_THRESHOLD: u32 = 10;
+/// Idle windows are pruned once this many messages are tracked.
+const MAX_TRACKED_MESSAGES: usize = 10_000;
+
+static BURSTS: OnceLock<BurstTracker> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BurstDecision {
+    /// Send the individual event.
+    Dispatch,
+    /// The message just crossed the threshold; schedule a bulk event.
+    ScheduleFlush,
+    /// A bulk event is already scheduled and will include this change.
+    Absorb,
+}
+
+struct Burst {
+    window_start: Instant,
+    changes: u32,
+    flush_pending: bool,
+}
+
+#[derive(Default)]
+struct BurstTracker {
+    bursts: DashMap<i64, Burst>,
+}
+
+impl BurstTracker {
+    fn record(&self, message_id: i64, now: Instant) -> BurstDecision {
+        if self.bursts.len() > MAX_TRACKED_MESSAGES {
+            self.bursts.retain(|_, burst| {
+                burst.flush_pending || now.duration_since(burst.window_start) < BURST_WINDOW
+            });
+        }
+        let mut burst = self.bursts.entry(message_id).or_insert(Burst {
+            window_start: now,
+            changes: 0,
+            flush_pending: false,
+        });
+        if burst.flush_pending {
+            return BurstDecision::Absorb;
+        }
+        if now.duration_since(burst.window_start) >= BURST_WINDOW {
+            burst.window_start = now;
+            burst.changes = 0;
+        }
+        burst.changes = burst.changes.saturating_add(1);
+        if burst.changes <= BURST_THRESHOLD {
+            BurstDecision::Dispatch
+        } else {
+            burst.flush_pending = true;
+            BurstDecision::ScheduleFlush
+        }
+    }
+
+    /// Start a new window once a bulk event has gone out. The window starts
+    /// full, so a burst that keeps going stays coalesced.
+    fn flushed(&self, message_id: i64, now: Instant) {
+        if let Some(mut burst) = self.bursts.get_mut(&message_id) {
+            burst.window_start = now;
+            burst.changes = BURST_THRESHOLD;
+            burst.flush_pending = false;
+        }
+    }
+}
+
+/// Dispatch a reaction change in a guild channel, folding bursts on one
+/// message into `MESSAGE_REACTION_BULK`.
+pub fn dispatch_reaction_change(
+    state: &AppState,
+    event_type: &str,
+    payload: Value,
+    guild_id: i64,
+    channel_id: i64,
+    message_id: i64,
+) {
+    let tracker = BURSTS.get_or_init(BurstTracker::default);
+    match tracker.record(message_id, Instant::now()) {
+        BurstDecision::Dispatch => state
+            .event_bus
+            .dispatch(event_type, payload, Some(guild_id)),
+        BurstDecision::Absorb => {}
+        BurstDecision::ScheduleFlush => {
+            let state = state.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(BURST_WINDOW).await;
+                // Reopen the window before reading, so changes that land
+                // after the read schedule another bulk event.
+                tracker.flushed(message_id, Instant::now());
+                let reactions = match paracord_db::reactions::get_message_reactions(
+                    &state.db, message_id,
+                )
+                .await
+                {
+                    Ok(reactions) => reactions,
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to load reactions for message {}: {}",
+                            message_id,
+                            err
+                        );
+                        return;
+                    }
+                };
+                let reactions: Vec<Value> = reactions
+                    .iter()
+                    .map(|reaction| {
+                        json!({
+                            "emoji": reaction.emoji_name,
+                            "emoji_id": reaction.emoji_id.map(|id| id.to_string()),
+                            "count": reaction.count,
+                        })
+                    })
+                    .collect();
+                state.event_bus.dispatch(
+                    EVENT_MESSAGE_REACTION_BULK,
+                    json!({
+                        "guild_id": guild_id.to_string(),
+                        "channel_id": channel_id.to_string(),
+                        "message_id": message_id.to_string(),
+                        "reactions": reactions,
+                    }),
+                    Some(guild_id),
+                );
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_past_the_threshold_are_coalesced() {
+        let tracker = BurstTracker::default();
+        let start = Instant::now();
+        for _ in 0..BURST_THRESHOLD {
+            assert_eq!(tracker.record(1, start), BurstDecision::Dispatch);
+        }
+        assert_eq!(tracker.record(1, start), BurstDecision::ScheduleFlush);
+        assert_eq!(tracker.record(1, start), BurstDecision::Absorb);
+        assert_eq!(tracker.record(2, start), BurstDecision::Dispatch);
+
+        // A burst that keeps going after the flush stays coalesced...
+        tracker.flushed(1, start + BURST_WINDOW);
+        assert_eq!(
+            tracker.record(1, start + BURST_WINDOW),
+            BurstDecision::ScheduleFlush
+        );
+        // ...while a quiet message goes back to individual events.
+        tracker.flushed(1, start + BURST_WINDOW * 2);
+        assert_eq!(
+            tracker.record(1, start + BURST_WINDOW * 4),
+            BurstDecision::Dispatch
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Batched channel acks.
+//!
+//! Instead of one `PUT /channels/{id}/read` per channel, clients can send a
+//! batch of acks over the gateway (`op 18`) or as the `read_state_ack`
+//! realtime command. Each ack is checked on its own and acks for channels
+//! the user can't read are dropped; the rest are written in one transaction
+//! and answered with a single `READ_STATE_UPDATE` to the user's sessions.
+
+use std::collections::BTreeMap;
+
+use paracord_db::read_states::ReadStateRow;
+use paracord_models::gateway::EVENT_READ_STATE_UPDATE;
+use paracord_models::permissions::Permissions;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+use crate::receipts::{self, ReceiptKind};
+use crate::AppState;
+
+/// Most acks accepted in one batch.
+pub const MAX_ACK_BATCH: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadAck {
+    pub channel_id: String,
+    pub last_message_id: String,
+}
+
+/// Payload of the gateway opcode and the realtime command.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadAckBatch {
+    pub acks: Vec<ReadAck>,
+}
+
+pub fn read_state_to_json(row: &ReadStateRow) -> Value {
+    json!({
+        "channel_id": row.channel_id.to_string(),
+        "last_message_id": row.last_message_id.to_string(),
+        "mention_count": row.mention_count,
+    })
+}
+
+/// Parse a batch into `channel_id -> last_message_id`, keeping the last ack
+/// for a channel that appears twice.
+pub fn parse_acks(acks: &[ReadAck]) -> Result<BTreeMap<i64, i64>, CoreError> {
+    if acks.len() > MAX_ACK_BATCH {
+        return Err(CoreError::BadRequest(format!(
+            "at most {MAX_ACK_BATCH} acks per batch"
+        )));
+    }
+    let mut parsed = BTreeMap::new();
+    for ack in acks {
+        let channel_id = ack
+            .channel_id
+            .parse::<i64>()
+            .map_err(|_| CoreError::BadRequest("Invalid channel_id".into()))?;
+        let last_message_id = ack
+            .last_message_id
+            .parse::<i64>()
+            .ok()
+            .filter(|id| *id >= 0)
+            .ok_or_else(|| CoreError::BadRequest("Invalid last_message_id".into()))?;
+        parsed.insert(channel_id, last_message_id);
+    }
+    Ok(parsed)
+}
+
+/// Same check as `PUT /channels/{id}/read`: `VIEW_CHANNEL` and
+/// `READ_MESSAGE_HISTORY` in guilds, membership in DMs.
+async fn can_ack(
+    state: &AppState,
+    channel: &paracord_db::channels::ChannelRow,
+    user_id: i64,
+) -> Result<bool, CoreError> {
+    let Some(guild_id) = channel.guild_id() else {
+        return Ok(paracord_db::dms::is_dm_recipient(&state.db, channel.id, user_id).await?);
+    };
+    if !crate::permissions::is_guild_member(&state.db, guild_id, user_id).await? {
+        return Ok(false);
+    }
+    let Some(guild) = paracord_db::guilds::get_guild(&state.db, guild_id).await? else {
+        return Ok(false);
+    };
+    let perms = crate::permissions::compute_channel_permissions_cached(
+        &state.permission_cache,
+        &state.db,
+        guild_id,
+        channel.id,
+        guild.owner_id,
+        user_id,
+    )
+    .await?;
+    Ok(perms.contains(Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY))
+}
+
+/// Apply a batch of acks for `user_id` and return the stored read states.
+pub async fn apply_acks(
+    state: &AppState,
+    user_id: i64,
+    acks: &[ReadAck],
+) -> Result<Vec<ReadStateRow>, CoreError> {
+    let parsed = parse_acks(acks)?;
+    let mut accepted = Vec::with_capacity(parsed.len());
+    let mut dm_reads = Vec::new();
+    for (channel_id, last_message_id) in parsed {
+        let Some(channel) = paracord_db::channels::get_channel(&state.db, channel_id).await? else {
+            continue;
+        };
+        if !can_ack(state, &channel, user_id).await? {
+            continue;
+        }
+        if channel.guild_id().is_none() && last_message_id > 0 {
+            dm_reads.push((channel_id, last_message_id));
+        }
+        accepted.push((channel_id, last_message_id));
+    }
+    if accepted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = paracord_db::read_states::update_read_states(&state.db, user_id, &accepted).await?;
+    for (channel_id, message_id) in dm_reads {
+        receipts::record_receipt(state, channel_id, user_id, message_id, ReceiptKind::Read).await?;
+    }
+    state.event_bus.dispatch_to_users(
+        EVENT_READ_STATE_UPDATE,
+        json!({ "read_states": rows.iter().map(read_state_to_json).collect::<Vec<_>>() }),
+        vec![user_id],
+    );
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(channel_id: &str, last_message_id: &str) -> ReadAck {
+        ReadAck {
+            channel_id: channel_id.to_string(),
+            last_message_id: last_message_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn later_acks_for_a_channel_win() {
+        let parsed = parse_acks(&[ack("1", "10"), ack("2", "5"), ack("1", "12")]).unwrap();
+        assert_eq!(
+            parsed.into_iter().collect::<Vec<_>>(),
+            vec![(1, 12), (2, 5)]
+        );
+    }
+
+    #[test]
+    fn malformed_or_oversized_batches_are_rejected() {
+        assert!(parse_acks(&[ack("x", "1")]).is_err());
+        assert!(parse_acks(&[ack("1", "-4")]).is_err());
+        let too_many: Vec<_> = (0..=MAX_ACK_BATCH).map(|_| ack("1", "1")).collect();
+        assert!(parse_acks(&too_many).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Delivered and read receipts for DM and group DM channels.
+//!
+//! A message counts as delivered once a gateway session of a recipient has
+//! been sent its `MESSAGE_CREATE`, and as read once the recipient acks the
+//! channel. Receipts only move forward. They are shared with the other
+//! recipients through `DM_RECEIPT_UPDATE`, unless the reader turned
+//! `dm_receipts_enabled` off; users who turn it off don't see anyone else's
+//! receipts either.
+//!
+//! When the message came from a federated peer, the receipt is also sent to
+//! that server alone as an `m.receipt` event.
+
+use chrono::Utc;
+use paracord_db::dm_receipts::DmReceiptRow;
+use paracord_models::gateway::{EVENT_DM_RECEIPT_UPDATE, EVENT_MESSAGE_CREATE};
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+use crate::AppState;
+
+/// Federation event type carrying a receipt for a peer's message.
+pub const FEDERATION_RECEIPT_EVENT: &str = "m.receipt";
+const CHANNEL_TYPE_DM: i16 = 1;
+const CHANNEL_TYPE_GROUP_DM: i16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptKind {
+    Delivered,
+    Read,
+}
+
+impl ReceiptKind {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "delivered" => Some(Self::Delivered),
+            "read" => Some(Self::Read),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Delivered => "delivered",
+            Self::Read => "read",
+        }
+    }
+}
+
+pub fn receipt_to_json(receipt: &DmReceiptRow) -> Value {
+    json!({
+        "channel_id": receipt.channel_id.to_string(),
+        "user_id": receipt.user_id.to_string(),
+        "last_delivered_message_id": receipt.last_delivered_message_id.map(|id| id.to_string()),
+        "delivered_at": receipt.delivered_at.map(|at| at.to_rfc3339()),
+        "last_read_message_id": receipt.last_read_message_id.map(|id| id.to_string()),
+        "read_at": receipt.read_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Called by the gateway after it sent an event to one of `user_id`'s
+/// sessions. Records a delivered receipt for DM messages from someone else.
+pub fn note_event_delivered(
+    state: &AppState,
+    user_id: i64,
+    event_type: &str,
+    guild_id: Option<i64>,
+    payload: &Value,
+) {
+    if event_type != EVENT_MESSAGE_CREATE || guild_id.is_some() {
+        return;
+    }
+    let id_field = |value: Option<&Value>| {
+        value
+            .and_then(Value::as_str)
+            .and_then(|raw| raw.parse::<i64>().ok())
+    };
+    let (Some(channel_id), Some(message_id), Some(author_id)) = (
+        id_field(payload.get("channel_id")),
+        id_field(payload.get("id")),
+        id_field(payload.get("author").and_then(|author| author.get("id"))),
+    ) else {
+        return;
+    };
+    if author_id == user_id {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = record_receipt(
+            &state,
+            channel_id,
+            user_id,
+            message_id,
+            ReceiptKind::Delivered,
+        )
+        .await
+        {
+            tracing::debug!(
+                "Failed to record delivery of message {} to {}: {}",
+                message_id,
+                user_id,
+                err
+            );
+        }
+    });
+}
+
+/// Record a receipt by a local user and share it. Returns false when the
+/// channel isn't a DM the user is in or the receipt didn't move forward.
+pub async fn record_receipt(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+    message_id: i64,
+    kind: ReceiptKind,
+) -> Result<bool, CoreError> {
+    store_and_publish(state, channel_id, user_id, message_id, kind, true).await
+}
+
+/// Apply an `m.receipt` from a peer. `user_id` is the local account mapped to
+/// the remote reader; the receipt is not federated again.
+pub async fn apply_federated_receipt(
+    state: &AppState,
+    user_id: i64,
+    message_id: i64,
+    kind: ReceiptKind,
+) -> Result<bool, CoreError> {
+    let Some(message) = paracord_db::messages::get_message(&state.db, message_id).await? else {
+        return Ok(false);
+    };
+    store_and_publish(state, message.channel_id, user_id, message_id, kind, false).await
+}
+
+/// Receipts of the other recipients of a DM channel that `viewer_id` may see.
+pub async fn visible_receipts(
+    state: &AppState,
+    channel_id: i64,
+    viewer_id: i64,
+) -> Result<Vec<DmReceiptRow>, CoreError> {
+    if !paracord_db::dms::is_dm_recipient(&state.db, channel_id, viewer_id).await? {
+        return Err(CoreError::NotFound);
+    }
+    let receipts = paracord_db::dm_receipts::list_channel_receipts(&state.db, channel_id).await?;
+    let user_ids: Vec<i64> = receipts.iter().map(|receipt| receipt.user_id).collect();
+    let disabled = paracord_db::users::get_dm_receipts_disabled_users(&state.db, &user_ids).await?;
+    if disabled.contains(&viewer_id) {
+        return Ok(Vec::new());
+    }
+    Ok(receipts
+        .into_iter()
+        .filter(|receipt| receipt.user_id != viewer_id && !disabled.contains(&receipt.user_id))
+        .collect())
+}
+
+async fn store_and_publish(
+    state: &AppState,
+    channel_id: i64,
+    user_id: i64,
+    message_id: i64,
+    kind: ReceiptKind,
+    federate: bool,
+) -> Result<bool, CoreError> {
+    let Some(channel) = paracord_db::channels::get_channel(&state.db, channel_id).await? else {
+        return Ok(false);
+    };
+    if !matches!(
+        channel.channel_type,
+        CHANNEL_TYPE_DM | CHANNEL_TYPE_GROUP_DM
+    ) || !paracord_db::dms::is_dm_recipient(&state.db, channel_id, user_id).await?
+    {
+        return Ok(false);
+    }
+
+    let changed = match kind {
+        ReceiptKind::Delivered => {
+            paracord_db::dm_receipts::mark_delivered(&state.db, channel_id, user_id, message_id)
+                .await?
+        }
+        ReceiptKind::Read => {
+            paracord_db::dm_receipts::mark_read(&state.db, channel_id, user_id, message_id).await?
+        }
+    };
+    if !changed {
+        return Ok(false);
+    }
+
+    let recipients = paracord_db::dms::get_dm_recipient_ids(&state.db, channel_id).await?;
+    let disabled =
+        paracord_db::users::get_dm_receipts_disabled_users(&state.db, &recipients).await?;
+    if disabled.contains(&user_id) {
+        return Ok(true);
+    }
+    let targets: Vec<i64> = recipients
+        .into_iter()
+        .filter(|id| *id != user_id && !disabled.contains(id))
+        .collect();
+    if !targets.is_empty() {
+        state.event_bus.dispatch_to_users(
+            EVENT_DM_RECEIPT_UPDATE,
+            json!({
+                "channel_id": channel_id.to_string(),
+                "user_id": user_id.to_string(),
+                "message_id": message_id.to_string(),
+                "type": kind.as_str(),
+                "at": Utc::now().to_rfc3339(),
+            }),
+            targets,
+        );
+    }
+
+    if federate {
+        federate_receipt(state, user_id, message_id, kind).await;
+    }
+    Ok(true)
+}
+
+/// Send a receipt for a message that came from a peer back to that peer.
+async fn federate_receipt(state: &AppState, user_id: i64, message_id: i64, kind: ReceiptKind) {
+    let Some(service) = state.federation_service.clone() else {
+        return;
+    };
+    if !service.is_enabled() {
+        return;
+    }
+    let Ok(Some((origin_server, remote_message_id))) =
+        paracord_db::federation::get_remote_message_by_local(&state.db, message_id).await
+    else {
+        return;
+    };
+    let Ok(Some(user)) = paracord_db::users::get_user_by_id(&state.db, user_id).await else {
+        return;
+    };
+
+    let timestamp_ms = Utc::now().timestamp_millis();
+    let envelope = match service.build_custom_envelope(
+        FEDERATION_RECEIPT_EVENT,
+        format!("!dm:{}", service.domain()),
+        &user.username,
+        &json!({
+            "message_id": remote_message_id,
+            "receipt_type": kind.as_str(),
+        }),
+        timestamp_ms,
+        None,
+        Some(&format!("{}:{}:{}", message_id, user_id, kind.as_str())),
+    ) {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            tracing::debug!("Failed to build receipt envelope: {}", err);
+            return;
+        }
+    };
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        if !service
+            .send_envelope_to_server(&db, &envelope, &origin_server)
+            .await
+        {
+            tracing::debug!(
+                "Receipt for message {} not sent: {} is not a trusted peer",
+                message_id,
+                origin_server
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_kinds_round_trip() {
+        for kind in [ReceiptKind::Delivered, ReceiptKind::Read] {
+            assert_eq!(ReceiptKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(ReceiptKind::parse("seen"), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Message search query syntax.
+//!
+//! A search box string mixes free text with operators: `from:<user_id>`,
+//! `in:<channel_id>`, `has:attachment` / `has:none`, and
+//! `before:` / `after:` taking a message ID or a `YYYY-MM-DD` date. Anything
+//! that isn't a recognised operator is left in the text, so `10:30` or
+//! `re:sync` still search as written.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub author_ids: Vec<i64>,
+    pub channel_ids: Vec<i64>,
+    pub has_attachment: Option<bool>,
+    /// Exclusive upper bound on message ID.
+    pub before: Option<i64>,
+    /// Exclusive lower bound on message ID.
+    pub after: Option<i64>,
+}
+
+impl SearchQuery {
+    /// Whether anything narrows the search besides the text.
+    pub fn has_filters(&self) -> bool {
+        !self.author_ids.is_empty()
+            || !self.channel_ids.is_empty()
+            || self.has_attachment.is_some()
+            || self.before.is_some()
+            || self.after.is_some()
+    }
+}
+
+/// Split `raw` into text and operators. Errors name the offending operator.
+pub fn parse_query(raw: &str) -> Result<SearchQuery, String> {
+    let mut query = SearchQuery::default();
+    let mut words = Vec::new();
+    for word in raw.split_whitespace() {
+        let Some((op, value)) = word.split_once(':') else {
+            words.push(word);
+            continue;
+        };
+        match op.to_ascii_lowercase().as_str() {
+            "from" => query.author_ids.push(parse_id(op, value)?),
+            "in" => query.channel_ids.push(parse_id(op, value)?),
+            "has" => query.has_attachment = Some(parse_has(value)?),
+            "before" => query.before = Some(parse_bound(op, value)?),
+            "after" => {
+                // A date means "after that day", so start from the next one.
+                query.after = Some(match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                    Ok(date) => day_start(date.succ_opt().unwrap_or(date)) - 1,
+                    Err(_) => parse_id(op, value)?,
+                });
+            }
+            _ => words.push(word),
+        }
+    }
+    query.text = words.join(" ");
+    Ok(query)
+}
+
+/// `attachment` or `none`, as accepted by `has:` and the `has` parameter.
+pub fn parse_has(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "attachment" | "file" => Ok(true),
+        "none" => Ok(false),
+        _ => Err(format!("has: expects attachment or none, not {value:?}")),
+    }
+}
+
+/// A message ID, or a `YYYY-MM-DD` date meaning the first ID of that day.
+pub fn parse_bound(op: &str, value: &str) -> Result<i64, String> {
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Ok(day_start(date)),
+        Err(_) => parse_id(op, value),
+    }
+}
+
+fn parse_id(op: &str, value: &str) -> Result<i64, String> {
+    value
+        .parse::<i64>()
+        .ok()
+        .filter(|id| *id > 0)
+        .ok_or_else(|| format!("{op}: expects an ID, not {value:?}"))
+}
+
+fn day_start(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    paracord_util::snowflake::min_id_at(Utc.from_utc_datetime(&midnight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_are_pulled_out_of_the_text() {
+        let query = parse_query("deploy from:42 in:7 has:attachment notes").unwrap();
+        assert_eq!(query.text, "deploy notes");
+        assert_eq!(query.author_ids, vec![42]);
+        assert_eq!(query.channel_ids, vec![7]);
+        assert_eq!(query.has_attachment, Some(true));
+        assert!(query.has_filters());
+
+        let plain = parse_query("meet at 10:30 re:sync").unwrap();
+        assert_eq!(plain.text, "meet at 10:30 re:sync");
+        assert!(!plain.has_filters());
+    }
+
+    #[test]
+    fn dates_bound_whole_days() {
+        let query = parse_query("before:2026-03-02 after:2026-03-01").unwrap();
+        let day = |d: u32| day_start(NaiveDate::from_ymd_opt(2026, 3, d).unwrap());
+        assert_eq!(query.before, Some(day(2)));
+        assert_eq!(query.after, Some(day(2) - 1));
+        assert!(day(1) < query.after.unwrap());
+
+        assert_eq!(parse_query("before:123").unwrap().before, Some(123));
+    }
+
+    #[test]
+    fn bad_operator_values_are_rejected() {
+        assert!(parse_query("from:someone").is_err());
+        assert!(parse_query("has:link").is_err());
+        assert!(parse_query("after:yesterday").is_err());
+    }
+}
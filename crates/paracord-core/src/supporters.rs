@@ -0,0 +1,92 @@
+//! Supporter perks.
+//!
+//! Admins mark users as supporters directly, or mark roles whose members
+//! count as supporters; donation platform webhooks grant time-limited
+//! status. Supporters get the limits configured in the `supporter_*`
+//! runtime settings instead of the instance defaults. The checks in the
+//! upload, emoji and stream routes read their limits from [`perks_for`].
+
+use chrono::Utc;
+
+use crate::AppState;
+
+/// Stream quality presets from lowest to highest.
+pub const STREAM_QUALITY_ORDER: [&str; 4] = ["720p30", "1080p60", "1440p60", "4k60"];
+
+/// Limits that apply to one user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Perks {
+    pub supporter: bool,
+    pub max_upload_size: u64,
+    /// `None` = no limit.
+    pub max_emojis_per_guild: Option<u32>,
+    pub max_stream_quality: String,
+}
+
+pub fn stream_quality_rank(preset: &str) -> Option<usize> {
+    STREAM_QUALITY_ORDER.iter().position(|name| *name == preset)
+}
+
+/// Whether `requested` is at or below the `max` preset.
+pub fn stream_quality_allowed(max: &str, requested: &str) -> bool {
+    match (stream_quality_rank(max), stream_quality_rank(requested)) {
+        (Some(max), Some(requested)) => requested <= max,
+        _ => false,
+    }
+}
+
+/// Whether the user currently has supporter status. Lookup failures count
+/// as not a supporter.
+pub async fn is_supporter(state: &AppState, user_id: i64) -> bool {
+    match paracord_db::supporters::is_supporter(&state.db, user_id, Utc::now()).await {
+        Ok(supporter) => supporter,
+        Err(err) => {
+            tracing::warn!("Failed to look up supporter status of {}: {}", user_id, err);
+            false
+        }
+    }
+}
+
+pub async fn perks_for(state: &AppState, user_id: i64) -> Perks {
+    let supporter = is_supporter(state, user_id).await;
+    let settings = state.runtime.read().await;
+    let base_emojis = settings.max_emojis_per_guild;
+    let mut perks = Perks {
+        supporter,
+        max_upload_size: state.config.max_upload_size,
+        max_emojis_per_guild: (base_emojis > 0).then_some(base_emojis),
+        max_stream_quality: settings.max_stream_quality.clone(),
+    };
+    if supporter {
+        perks.max_upload_size = perks
+            .max_upload_size
+            .max(settings.supporter_max_upload_size);
+        perks.max_emojis_per_guild = perks
+            .max_emojis_per_guild
+            .map(|limit| limit.saturating_add(settings.supporter_extra_emoji_slots));
+        if stream_quality_rank(&settings.supporter_max_stream_quality)
+            > stream_quality_rank(&perks.max_stream_quality)
+        {
+            perks.max_stream_quality = settings.supporter_max_stream_quality.clone();
+        }
+    }
+    perks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_quality_order_matches_media_presets() {
+        let presets: Vec<String> = paracord_media::streaming::quality_presets()
+            .into_iter()
+            .map(|preset| preset.name)
+            .collect();
+        assert_eq!(presets, STREAM_QUALITY_ORDER);
+        assert!(stream_quality_allowed("1080p60", "720p30"));
+        assert!(stream_quality_allowed("1080p60", "1080p60"));
+        assert!(!stream_quality_allowed("1080p60", "4k60"));
+        assert!(!stream_quality_allowed("1080p60", "8k"));
+    }
+}
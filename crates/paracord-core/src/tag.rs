@@ -0,0 +1,74 @@
+//! Guild tags: moderator-defined text snippets that members post by sending
+//! `/tag <name>`. The server swaps the command for the snippet before the
+//! message is stored, so tags work without a bot in the guild.
+
+use crate::error::CoreError;
+use paracord_db::guild_tags::GuildTagRow;
+use paracord_db::DbPool;
+
+pub const MAX_TAG_NAME_LEN: usize = 32;
+pub const MAX_TAGS_PER_GUILD: i64 = 250;
+const TAG_COMMAND: &str = "/tag";
+
+/// Lowercase and check a tag name: 1-32 of `a-z`, `0-9`, `-` and `_`.
+pub fn normalize_tag_name(name: &str) -> Result<String, CoreError> {
+    let name = name.trim().to_ascii_lowercase();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_TAG_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(CoreError::BadRequest(
+            "Tag name must be 1-32 characters of a-z, 0-9, - and _".into(),
+        ));
+    }
+    Ok(name)
+}
+
+/// The tag name if `content` is exactly `/tag <name>`.
+pub fn parse_tag_invocation(content: &str) -> Option<&str> {
+    let rest = content.trim().strip_prefix(TAG_COMMAND)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = rest.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(name)
+}
+
+/// Look up a tag for posting and count the use.
+pub async fn use_tag(pool: &DbPool, guild_id: i64, name: &str) -> Result<GuildTagRow, CoreError> {
+    let name = normalize_tag_name(name)?;
+    let tag = paracord_db::guild_tags::get_tag_by_name(pool, guild_id, &name)
+        .await?
+        .ok_or_else(|| CoreError::BadRequest(format!("Unknown tag '{name}'")))?;
+    paracord_db::guild_tags::increment_tag_uses(pool, tag.id).await?;
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_only_exact_invocations() {
+        assert_eq!(parse_tag_invocation("/tag rules"), Some("rules"));
+        assert_eq!(parse_tag_invocation("  /tag   FAQ  "), Some("FAQ"));
+        assert_eq!(parse_tag_invocation("/tag"), None);
+        assert_eq!(parse_tag_invocation("/tagrules"), None);
+        assert_eq!(parse_tag_invocation("/tag two words"), None);
+        assert_eq!(parse_tag_invocation("see /tag rules"), None);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(normalize_tag_name(" Rules ").unwrap(), "rules");
+        assert_eq!(normalize_tag_name("how-to_2").unwrap(), "how-to_2");
+        assert!(normalize_tag_name("").is_err());
+        assert!(normalize_tag_name("no spaces").is_err());
+        assert!(normalize_tag_name(&"a".repeat(33)).is_err());
+    }
+}
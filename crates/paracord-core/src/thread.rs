@@ -0,0 +1,166 @@
+//! Thread archiving policy.
+//!
+//! Threads archive themselves after `auto_archive_duration` minutes without
+//! a message or an archive state change. A guild's thread policy can cap the
+//! number of active threads, pick the default duration for new threads and
+//! stop posts from un-archiving threads.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use paracord_db::channels::ChannelRow;
+use paracord_db::DbPool;
+use paracord_models::permissions::Permissions;
+
+use crate::error::CoreError;
+use crate::permissions;
+
+/// Allowed `auto_archive_duration` values, in minutes.
+pub const AUTO_ARCHIVE_DURATIONS: [i64; 4] = [60, 1440, 4320, 10080];
+pub const DEFAULT_AUTO_ARCHIVE_DURATION: i64 = 1440;
+/// Upper bound for a guild's `max_active_threads`.
+pub const MAX_ACTIVE_THREADS_LIMIT: i32 = 1000;
+
+pub fn is_valid_auto_archive_duration(minutes: i64) -> bool {
+    AUTO_ARCHIVE_DURATIONS.contains(&minutes)
+}
+
+/// The duration for a new thread: the requested one, else the guild default.
+pub async fn resolve_auto_archive_duration(
+    pool: &DbPool,
+    guild_id: i64,
+    requested: Option<i64>,
+) -> Result<i64, CoreError> {
+    if let Some(minutes) = requested {
+        if !is_valid_auto_archive_duration(minutes) {
+            return Err(CoreError::BadRequest(
+                "auto_archive_duration must be one of 60, 1440, 4320 or 10080".into(),
+            ));
+        }
+        return Ok(minutes);
+    }
+    let policy =
+        paracord_db::guild_thread_policies::get_effective_guild_thread_policy(pool, guild_id)
+            .await?;
+    Ok(policy
+        .default_auto_archive_duration
+        .map(i64::from)
+        .unwrap_or(DEFAULT_AUTO_ARCHIVE_DURATION))
+}
+
+/// Refuse to create or un-archive a thread once the guild is at its active
+/// thread limit.
+pub async fn ensure_thread_capacity(pool: &DbPool, guild_id: i64) -> Result<(), CoreError> {
+    let policy =
+        paracord_db::guild_thread_policies::get_effective_guild_thread_policy(pool, guild_id)
+            .await?;
+    let Some(limit) = policy.max_active_threads else {
+        return Ok(());
+    };
+    let active = paracord_db::channels::count_active_guild_threads(pool, guild_id).await?;
+    if active >= i64::from(limit) {
+        return Err(CoreError::BadRequest(format!(
+            "Maximum number of active threads reached ({limit})"
+        )));
+    }
+    Ok(())
+}
+
+/// Un-archive `thread` so `user_id` can post in it. Locked threads, and
+/// threads in guilds that turned `unarchive_on_message` off, are only
+/// reopened for members with MANAGE_THREADS. Returns the updated thread, or
+/// `None` when it wasn't archived.
+pub async fn reopen_for_message(
+    pool: &DbPool,
+    thread: &ChannelRow,
+    user_id: i64,
+) -> Result<Option<ChannelRow>, CoreError> {
+    if !thread.is_archived_thread() {
+        return Ok(None);
+    }
+    let Some(guild_id) = thread.guild_id() else {
+        return Ok(None);
+    };
+    let policy =
+        paracord_db::guild_thread_policies::get_effective_guild_thread_policy(pool, guild_id)
+            .await?;
+    if thread.is_locked_thread() || !policy.unarchive_on_message {
+        let guild = paracord_db::guilds::get_guild(pool, guild_id)
+            .await?
+            .ok_or(CoreError::NotFound)?;
+        let perms = permissions::compute_channel_permissions(
+            pool,
+            guild_id,
+            thread.id,
+            guild.owner_id,
+            user_id,
+        )
+        .await?;
+        if !perms.contains(Permissions::MANAGE_THREADS) {
+            return Err(CoreError::BadRequest("Thread is archived".into()));
+        }
+    }
+    ensure_thread_capacity(pool, guild_id).await?;
+    let reopened =
+        paracord_db::channels::update_thread(pool, thread.id, None, Some(false), None, None)
+            .await?;
+    Ok(Some(reopened))
+}
+
+/// Whether `thread` has gone `auto_archive_duration` minutes without activity.
+pub fn is_inactive(thread: &ChannelRow, now: DateTime<Utc>) -> bool {
+    let minutes = thread
+        .thread_auto_archive_duration()
+        .unwrap_or(DEFAULT_AUTO_ARCHIVE_DURATION);
+    now - thread.thread_last_activity() >= Duration::minutes(minutes)
+}
+
+/// Archive inactive threads, then the least recently active threads of any
+/// guild still over its active thread limit. Returns the archived threads.
+pub async fn archive_stale_threads(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<ChannelRow>, CoreError> {
+    let mut by_guild: HashMap<i64, Vec<ChannelRow>> = HashMap::new();
+    for thread in paracord_db::channels::get_all_active_threads(pool).await? {
+        if let Some(guild_id) = thread.guild_id() {
+            by_guild.entry(guild_id).or_default().push(thread);
+        }
+    }
+
+    let mut archived = Vec::new();
+    for (guild_id, mut threads) in by_guild {
+        let policy =
+            paracord_db::guild_thread_policies::get_effective_guild_thread_policy(pool, guild_id)
+                .await?;
+        threads.sort_by_key(|thread| thread.thread_last_activity());
+        let over_limit = policy
+            .max_active_threads
+            .map(|limit| threads.len().saturating_sub(limit.max(0) as usize))
+            .unwrap_or(0);
+        for (index, thread) in threads.iter().enumerate() {
+            if index >= over_limit && !is_inactive(thread, now) {
+                continue;
+            }
+            let updated =
+                paracord_db::channels::update_thread(pool, thread.id, None, Some(true), None, None)
+                    .await?;
+            archived.push(updated);
+        }
+    }
+    Ok(archived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_standard_durations_are_valid() {
+        for minutes in AUTO_ARCHIVE_DURATIONS {
+            assert!(is_valid_auto_archive_duration(minutes));
+        }
+        assert!(!is_valid_auto_archive_duration(0));
+        assert!(!is_valid_auto_archive_duration(90));
+    }
+}
@@ -0,0 +1,140 @@
+//! Undo window for deleted guilds, channels and roles.
+//!
+//! Deletes leave a tombstone (`deleted_at`) behind. Until the window set by
+//! `deletion_undo_window_hours` runs out, a server admin can restore the
+//! row as it was; afterwards the purge job deletes it for good, together
+//! with everything that hangs off it.
+
+use chrono::{DateTime, Duration, Utc};
+use paracord_db::jobs::JobRow;
+use paracord_db::tombstones::{TombstoneKind, TombstoneRow};
+use paracord_db::DbPool;
+use serde_json::{json, Value};
+
+use crate::error::CoreError;
+use crate::AppState;
+
+pub const DEFAULT_UNDO_WINDOW_HOURS: u32 = 24;
+pub const MAX_UNDO_WINDOW_HOURS: u32 = 720;
+/// Tombstones purged per run of the purge job.
+const PURGE_BATCH_SIZE: i64 = 100;
+
+/// When a tombstone stops being restorable.
+pub fn restorable_until(deleted_at: DateTime<Utc>, window_hours: u32) -> DateTime<Utc> {
+    deleted_at + Duration::hours(i64::from(window_hours))
+}
+
+/// Add `deleted_at` and `restorable_until` to a `*_DELETE` dispatch payload.
+pub async fn with_tombstone(
+    state: &AppState,
+    mut payload: Value,
+    deleted_at: DateTime<Utc>,
+) -> Value {
+    let window_hours = state.runtime.read().await.deletion_undo_window_hours;
+    payload["deleted_at"] = json!(deleted_at.to_rfc3339());
+    payload["restorable_until"] = json!(restorable_until(deleted_at, window_hours).to_rfc3339());
+    payload
+}
+
+/// Restore a tombstoned guild, channel or role, returning it with the ids
+/// of the threads that came back with a channel. Channels and roles of a
+/// guild that is itself deleted can only come back with the guild.
+pub async fn restore(
+    pool: &DbPool,
+    kind: TombstoneKind,
+    id: i64,
+) -> Result<(TombstoneRow, Vec<i64>), CoreError> {
+    let tombstone = paracord_db::tombstones::get_tombstone(pool, kind, id)
+        .await?
+        .ok_or(CoreError::NotFound)?;
+    if kind != TombstoneKind::Guild
+        && paracord_db::guilds::get_guild(pool, tombstone.guild_id)
+            .await?
+            .is_none()
+    {
+        return Err(CoreError::BadRequest(
+            "The guild is deleted; restore the guild first".into(),
+        ));
+    }
+    if kind == TombstoneKind::Channel {
+        if let Some(parent_id) = tombstone.parent_id {
+            if paracord_db::channels::get_channel(pool, parent_id)
+                .await?
+                .is_none()
+            {
+                return Err(CoreError::BadRequest(
+                    "The parent channel is deleted; restore it first".into(),
+                ));
+            }
+        }
+    }
+    let thread_ids = if kind == TombstoneKind::Channel {
+        paracord_db::tombstones::threads_deleted_with(pool, id).await?
+    } else {
+        Vec::new()
+    };
+    if !paracord_db::tombstones::restore(pool, kind, id).await? {
+        return Err(CoreError::NotFound);
+    }
+    Ok((tombstone, thread_ids))
+}
+
+/// Permanently delete tombstones older than the undo window. Returns the
+/// purged rows.
+pub async fn purge_expired(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    window_hours: u32,
+) -> Result<Vec<TombstoneRow>, CoreError> {
+    let cutoff = now - Duration::hours(i64::from(window_hours));
+    let expired =
+        paracord_db::tombstones::list_expired_tombstones(pool, cutoff, PURGE_BATCH_SIZE).await?;
+    let mut purged = Vec::with_capacity(expired.len());
+    for tombstone in expired {
+        let result = match tombstone.kind {
+            TombstoneKind::Guild => paracord_db::guilds::delete_guild(pool, tombstone.id).await,
+            TombstoneKind::Channel => {
+                paracord_db::tombstones::purge_channel(pool, tombstone.id).await
+            }
+            TombstoneKind::Role => {
+                paracord_db::roles::delete_guild_role(pool, tombstone.guild_id, tombstone.id).await
+            }
+        };
+        match result {
+            Ok(()) => purged.push(tombstone),
+            Err(err) => tracing::warn!(
+                "Failed to purge deleted {} {}: {}",
+                tombstone.kind.as_str(),
+                tombstone.id,
+                err
+            ),
+        }
+    }
+    Ok(purged)
+}
+
+/// Recurring job: purge the tombstones whose undo window ran out.
+pub async fn run_purge_job(state: AppState, _job: JobRow) -> Result<(), String> {
+    let window_hours = state.runtime.read().await.deletion_undo_window_hours;
+    let purged = purge_expired(&state.db, Utc::now(), window_hours)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !purged.is_empty() {
+        tracing::info!("Purged {} deleted guilds, channels and roles", purged.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restorable_until_adds_the_window() {
+        let deleted_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(
+            restorable_until(deleted_at, 24),
+            deleted_at + Duration::days(1)
+        );
+    }
+}
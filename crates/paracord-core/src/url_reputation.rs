@@ -0,0 +1,328 @@
+//! URL reputation checks for plaintext message content.
+//!
+//! The denylist combines domains pulled from configured feeds with entries
+//! admins add by hand. A listed domain also covers its subdomains. Each guild
+//! picks whether matches are only flagged or rejected outright through
+//! `bot_settings.url_filter.level`, falling back to the server default.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::error::CoreError;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlFilterLevel {
+    /// Denylisted links are ignored.
+    Off,
+    /// Messages go through but are reported to moderators.
+    #[default]
+    Flag,
+    /// Messages containing denylisted links are rejected.
+    Block,
+}
+
+impl UrlFilterLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UrlFilterLevel::Off => "off",
+            UrlFilterLevel::Flag => "flag",
+            UrlFilterLevel::Block => "block",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(UrlFilterLevel::Off),
+            "flag" => Some(UrlFilterLevel::Flag),
+            "block" => Some(UrlFilterLevel::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Where a denylisted domain came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenylistSource {
+    Feed,
+    Manual,
+}
+
+impl DenylistSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DenylistSource::Feed => "feed",
+            DenylistSource::Manual => "manual",
+        }
+    }
+}
+
+/// A link in message content that hit the denylist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenylistMatch {
+    /// Host as written in the message.
+    pub host: String,
+    /// Denylist entry it matched (the host itself or a parent domain).
+    pub domain: String,
+    pub source: DenylistSource,
+}
+
+/// Snapshot of denylisted domains. Cheap to clone; updates swap whole sets.
+#[derive(Debug, Clone, Default)]
+pub struct UrlDenylist {
+    feed: Arc<HashSet<String>>,
+    manual: Arc<HashSet<String>>,
+}
+
+impl UrlDenylist {
+    pub fn set_feed_domains(&mut self, domains: HashSet<String>) {
+        self.feed = Arc::new(domains);
+    }
+
+    pub fn set_manual_domains(&mut self, domains: HashSet<String>) {
+        self.manual = Arc::new(domains);
+    }
+
+    pub fn feed_len(&self) -> usize {
+        self.feed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.feed.is_empty() && self.manual.is_empty()
+    }
+
+    /// First denylisted link in `content`, if any.
+    pub fn check(&self, content: &str) -> Option<DenylistMatch> {
+        if self.is_empty() {
+            return None;
+        }
+        extract_hosts(content)
+            .into_iter()
+            .find_map(|host| self.lookup(&host))
+    }
+
+    fn lookup(&self, host: &str) -> Option<DenylistMatch> {
+        let mut candidate = host;
+        loop {
+            let source = if self.manual.contains(candidate) {
+                Some(DenylistSource::Manual)
+            } else if self.feed.contains(candidate) {
+                Some(DenylistSource::Feed)
+            } else {
+                None
+            };
+            if let Some(source) = source {
+                return Some(DenylistMatch {
+                    host: host.to_string(),
+                    domain: candidate.to_string(),
+                    source,
+                });
+            }
+            // Stop before testing the bare TLD.
+            let (_, parent) = candidate.split_once('.')?;
+            if !parent.contains('.') {
+                return None;
+            }
+            candidate = parent;
+        }
+    }
+}
+
+/// Reload admin-managed entries from the database into the live denylist.
+pub async fn reload_manual_domains(state: &AppState) -> Result<usize, CoreError> {
+    let domains: HashSet<String> = paracord_db::url_denylist::list_entries(&state.db)
+        .await?
+        .into_iter()
+        .map(|entry| entry.domain)
+        .collect();
+    let count = domains.len();
+    state
+        .runtime
+        .write()
+        .await
+        .url_denylist
+        .set_manual_domains(domains);
+    Ok(count)
+}
+
+/// Filter level for a guild from its `bot_settings` JSON.
+pub fn guild_level(bot_settings: Option<&str>, default: UrlFilterLevel) -> UrlFilterLevel {
+    bot_settings
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+        .and_then(|settings| {
+            settings
+                .get("url_filter")
+                .and_then(|filter| filter.get("level"))
+                .and_then(|level| level.as_str())
+                .and_then(UrlFilterLevel::parse)
+        })
+        .unwrap_or(default)
+}
+
+/// Parse a feed body. Accepts one domain per line or hosts-file lines
+/// (`0.0.0.0 scam.example`), with `#` comments.
+pub fn parse_feed(body: &str) -> HashSet<String> {
+    let mut domains = HashSet::new();
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens
+            .peek()
+            .is_some_and(|first| first.parse::<IpAddr>().is_ok())
+        {
+            tokens.next();
+        }
+        for token in tokens {
+            if let Some(domain) = normalize_domain(token) {
+                domains.insert(domain);
+            }
+        }
+    }
+    domains
+}
+
+/// Canonical denylist form of a domain or URL: lowercase host without
+/// scheme, port, path, or a leading `*.`.
+pub fn normalize_domain(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let raw = raw.strip_prefix("*.").unwrap_or(raw);
+    let raw = raw.split_once("://").map_or(raw, |(_, rest)| rest);
+    parse_host(raw)
+}
+
+/// Hosts of every link-like token in `content`, with or without a scheme.
+pub fn extract_hosts(content: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for token in content.split(|ch: char| {
+        ch.is_whitespace() || matches!(ch, '<' | '>' | '(' | ')' | '[' | ']' | '"' | '\'' | '`')
+    }) {
+        let rest = match token.find("://") {
+            Some(index) => &token[index + 3..],
+            None => token,
+        };
+        if let Some(host) = parse_host(rest) {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts
+}
+
+fn parse_host(raw: &str) -> Option<String> {
+    let authority = raw.split(['/', '?', '#', '\\']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    let host = host
+        .trim_end_matches(['.', ',', ';', '!', '*', '_', '~'])
+        .trim_start_matches(['*', '_', '~'])
+        .to_ascii_lowercase();
+
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 || host.len() > 253 {
+        return None;
+    }
+    let valid_label = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+    };
+    if !labels.iter().all(valid_label) {
+        return None;
+    }
+    let tld = labels[labels.len() - 1];
+    let tld_ok = tld.starts_with("xn--")
+        || (tld.len() >= 2 && tld.chars().all(|ch| ch.is_ascii_alphabetic()));
+    tld_ok.then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn denylist(feed: &[&str], manual: &[&str]) -> UrlDenylist {
+        let mut list = UrlDenylist::default();
+        list.set_feed_domains(feed.iter().map(|d| d.to_string()).collect());
+        list.set_manual_domains(manual.iter().map(|d| d.to_string()).collect());
+        list
+    }
+
+    #[test]
+    fn extracts_hosts_with_and_without_scheme() {
+        let hosts = extract_hosts(
+            "free nitro at https://User@Discord-Gift.example:443/claim?x=1, \
+             also [here](http://steam-trade.example/login) or nitro-drop.example. e.g. this",
+        );
+        assert_eq!(
+            hosts,
+            vec![
+                "discord-gift.example",
+                "steam-trade.example",
+                "nitro-drop.example",
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_listed_domains_and_their_subdomains() {
+        let list = denylist(&["scam.example"], &["phish.example"]);
+        let hit = list
+            .check("login at https://accounts.scam.example/verify")
+            .expect("subdomain matches");
+        assert_eq!(hit.domain, "scam.example");
+        assert_eq!(hit.host, "accounts.scam.example");
+        assert_eq!(hit.source, DenylistSource::Feed);
+
+        let hit = list.check("phish.example").expect("bare domain matches");
+        assert_eq!(hit.source, DenylistSource::Manual);
+
+        assert!(list.check("https://notscam.example/").is_none());
+        assert!(list.check("nothing to see here").is_none());
+    }
+
+    #[test]
+    fn parses_plain_and_hosts_format_feeds() {
+        let domains = parse_feed(
+            "# phishing feed\n\
+             scam.example\n\
+             0.0.0.0 Gift-Claim.example # comment\n\
+             127.0.0.1 localhost\n\
+             https://wallet-verify.example/path\n\
+             not a domain\n",
+        );
+        let mut domains: Vec<_> = domains.into_iter().collect();
+        domains.sort();
+        assert_eq!(
+            domains,
+            vec![
+                "gift-claim.example",
+                "scam.example",
+                "wallet-verify.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn guild_level_falls_back_to_default() {
+        let settings = r#"{"url_filter":{"level":"block"}}"#;
+        assert_eq!(
+            guild_level(Some(settings), UrlFilterLevel::Flag),
+            UrlFilterLevel::Block
+        );
+        assert_eq!(
+            guild_level(Some(r#"{"auto_mod":{}}"#), UrlFilterLevel::Off),
+            UrlFilterLevel::Off
+        );
+        assert_eq!(
+            guild_level(None, UrlFilterLevel::Flag),
+            UrlFilterLevel::Flag
+        );
+    }
+}
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS voice_recordings (
+    id            BIGINT PRIMARY KEY,
+    guild_id      BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    channel_id    BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    egress_id     TEXT NOT NULL UNIQUE,
+    kind          TEXT NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'starting',
+    started_by    BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    attachment_id BIGINT REFERENCES attachments(id) ON DELETE SET NULL,
+    error         TEXT,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    ended_at      TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_voice_recordings_channel ON voice_recordings(channel_id, status);
+CREATE INDEX IF NOT EXISTS idx_voice_recordings_attachment ON voice_recordings(attachment_id);
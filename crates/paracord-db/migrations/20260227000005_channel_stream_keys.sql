@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS channel_stream_keys (
+    id           BIGINT PRIMARY KEY,
+    channel_id   BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    guild_id     BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    user_id      BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key_hash     TEXT NOT NULL UNIQUE,
+    name         TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    last_used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_stream_keys_channel ON channel_stream_keys(channel_id);
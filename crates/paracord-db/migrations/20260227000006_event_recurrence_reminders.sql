@@ -0,0 +1,9 @@
+ALTER TABLE scheduled_events ADD COLUMN recurrence_rule TEXT;
+ALTER TABLE scheduled_events ADD COLUMN reminder_minutes INTEGER;
+
+CREATE TABLE IF NOT EXISTS event_reminders_sent (
+    event_id         BIGINT NOT NULL REFERENCES scheduled_events(id) ON DELETE CASCADE,
+    occurrence_start TEXT NOT NULL,
+    sent_at          TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (event_id, occurrence_start)
+);
@@ -0,0 +1,3 @@
+-- 0 = accepted, 1 = pending message request, 2 = declined
+ALTER TABLE dm_recipients
+ADD COLUMN request_state SMALLINT NOT NULL DEFAULT 0;
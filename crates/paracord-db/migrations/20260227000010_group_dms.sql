@@ -0,0 +1,15 @@
+ALTER TABLE channels ADD COLUMN icon_hash TEXT;
+
+-- Sender keys for E2EE group DMs, encrypted pairwise for each recipient.
+CREATE TABLE IF NOT EXISTS group_dm_sender_keys (
+    channel_id   BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    sender_id    BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    epoch        BIGINT NOT NULL,
+    ciphertext   TEXT NOT NULL,
+    header       TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (channel_id, sender_id, recipient_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_group_dm_sender_keys_recipient ON group_dm_sender_keys(channel_id, recipient_id);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS guild_themes (
+    guild_id     BIGINT PRIMARY KEY REFERENCES spaces(id) ON DELETE CASCADE,
+    accent_color TEXT,
+    custom_css   TEXT,
+    updated_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS guild_theme_assets (
+    id           BIGINT PRIMARY KEY,
+    guild_id     BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    name         TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size         BIGINT NOT NULL,
+    uploader_id  BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (guild_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_theme_assets_guild ON guild_theme_assets(guild_id);
@@ -0,0 +1,2 @@
+-- Webhooks suspended for sustained abuse of the execute endpoint.
+ALTER TABLE webhooks ADD COLUMN suspended_at TEXT;
@@ -0,0 +1,8 @@
+ALTER TABLE user_settings
+ADD COLUMN device_approval_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE auth_sessions ADD COLUMN device_approved_at TEXT;
+ALTER TABLE auth_sessions ADD COLUMN device_approval_requested_at TEXT;
+
+-- Sessions that exist today belong to devices the user already uses.
+UPDATE auth_sessions SET device_approved_at = issued_at;
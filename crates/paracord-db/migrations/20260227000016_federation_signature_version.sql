@@ -0,0 +1,4 @@
+-- Encoding the event signatures were made over; 1 is the pre-canonical
+-- serde_json form, which every stored event so far uses.
+ALTER TABLE federation_events ADD COLUMN signature_version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE federation_outbound_queue ADD COLUMN signature_version BIGINT NOT NULL DEFAULT 1;
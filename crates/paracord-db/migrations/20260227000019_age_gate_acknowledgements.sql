@@ -0,0 +1,6 @@
+-- Users who confirmed they may view NSFW channels. Until a row exists,
+-- messages from NSFW channels are withheld over REST and the gateway.
+CREATE TABLE IF NOT EXISTS user_age_gate_acknowledgements (
+    user_id         BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    acknowledged_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
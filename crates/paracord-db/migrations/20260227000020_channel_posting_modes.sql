@@ -0,0 +1,3 @@
+-- Channel posting mode: 0 = everyone with SEND_MESSAGES, 1 = read-only
+-- (POST_OVERRIDE required), 2 = webhook and bot posts only.
+ALTER TABLE channels ADD COLUMN posting_mode SMALLINT NOT NULL DEFAULT 0;
@@ -0,0 +1,29 @@
+-- Durable background job queue. Workers claim `pending` rows whose `run_at`
+-- has passed, highest priority first. A claimed row is `running` with
+-- `locked_at` set; rows left running by a crashed worker are requeued once
+-- their lease expires. Failed attempts are retried with backoff until
+-- `max_attempts`, after which the job stays `failed` for inspection.
+-- Recurring jobs (`repeat_seconds` set) keep one row per kind that goes back
+-- to `pending` after every run instead of finishing.
+CREATE TABLE IF NOT EXISTS background_jobs (
+    id             BIGINT PRIMARY KEY,
+    kind           TEXT NOT NULL,
+    payload        TEXT NOT NULL DEFAULT '{}',
+    priority       INTEGER NOT NULL DEFAULT 0,
+    status         TEXT NOT NULL DEFAULT 'pending',
+    attempts       INTEGER NOT NULL DEFAULT 0,
+    max_attempts   INTEGER NOT NULL DEFAULT 5,
+    run_at         TEXT NOT NULL DEFAULT (datetime('now')),
+    locked_by      TEXT,
+    locked_at      TEXT,
+    last_error     TEXT,
+    created_at     TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at     TEXT NOT NULL DEFAULT (datetime('now')),
+    completed_at   TEXT,
+    repeat_seconds BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_background_jobs_ready ON background_jobs(status, priority, run_at);
+CREATE INDEX IF NOT EXISTS idx_background_jobs_kind ON background_jobs(kind, status);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_background_jobs_recurring
+    ON background_jobs(kind) WHERE repeat_seconds IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Delivered/read receipts for DM channels: the newest message each recipient
+-- has received on a client and has read.
+CREATE TABLE IF NOT EXISTS dm_receipts (
+    channel_id                BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    user_id                   BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    last_delivered_message_id BIGINT,
+    delivered_at              TEXT,
+    last_read_message_id      BIGINT,
+    read_at                   TEXT,
+    PRIMARY KEY (channel_id, user_id)
+);
+
+-- Users who turn receipts off neither share nor see them.
+ALTER TABLE user_settings
+ADD COLUMN dm_receipts_enabled BOOLEAN NOT NULL DEFAULT TRUE;
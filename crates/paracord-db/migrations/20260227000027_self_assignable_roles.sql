@@ -0,0 +1,4 @@
+-- Roles members may give themselves. Roles sharing a self_assign_group are
+-- mutually exclusive: picking one drops the others in the group.
+ALTER TABLE roles ADD COLUMN self_assignable BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE roles ADD COLUMN self_assign_group TEXT;
@@ -0,0 +1,10 @@
+-- Deleted guilds, channels and roles are kept as tombstones until the undo
+-- window runs out, so a server admin can restore them. Reads skip rows with
+-- `deleted_at` set; the purge worker removes them for good afterwards.
+ALTER TABLE spaces ADD COLUMN deleted_at TEXT;
+ALTER TABLE channels ADD COLUMN deleted_at TEXT;
+ALTER TABLE roles ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_spaces_deleted_at ON spaces(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_channels_deleted_at ON channels(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_roles_deleted_at ON roles(deleted_at);
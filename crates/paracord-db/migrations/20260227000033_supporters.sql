@@ -0,0 +1,17 @@
+-- Users granted supporter perks, by an admin or a donation platform webhook.
+CREATE TABLE IF NOT EXISTS supporters (
+    user_id    BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- 'manual', 'kofi' or 'patreon'.
+    source     TEXT NOT NULL,
+    note       TEXT,
+    -- NULL never expires.
+    expires_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Roles whose members count as supporters.
+CREATE TABLE IF NOT EXISTS supporter_roles (
+    role_id    BIGINT PRIMARY KEY REFERENCES roles(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,5 @@
+-- Member lists page by user_id within a guild. The composite index serves
+-- the cursor scan as well as plain guild lookups, so it replaces
+-- idx_members_guild.
+CREATE INDEX IF NOT EXISTS idx_members_guild_user ON members(guild_id, user_id);
+DROP INDEX IF EXISTS idx_members_guild;
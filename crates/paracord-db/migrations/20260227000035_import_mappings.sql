@@ -0,0 +1,11 @@
+-- Source-platform ids of everything a guild import created, so an
+-- interrupted import can be re-run without duplicating users, channels,
+-- emojis or messages.
+CREATE TABLE IF NOT EXISTS import_mappings (
+    guild_id   BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    kind       TEXT NOT NULL,
+    source_id  TEXT NOT NULL,
+    local_id   BIGINT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (guild_id, kind, source_id)
+);
@@ -0,0 +1,35 @@
+-- Full-text index over message content. The FTS5 table stores only the
+-- index and reads content back from `messages` (rowid = messages.id);
+-- the triggers keep it in step with every insert, edit and delete.
+-- End-to-end encrypted DMs (flags & 1) are never indexed.
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content,
+    content = 'messages',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages
+WHEN new.content IS NOT NULL AND (new.flags & 1) = 0
+BEGIN
+    INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages
+WHEN old.content IS NOT NULL AND (old.flags & 1) = 0
+BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content) VALUES ('delete', old.id, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content, flags ON messages
+BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content)
+        SELECT 'delete', old.id, old.content
+        WHERE old.content IS NOT NULL AND (old.flags & 1) = 0;
+    INSERT INTO messages_fts (rowid, content)
+        SELECT new.id, new.content
+        WHERE new.content IS NOT NULL AND (new.flags & 1) = 0;
+END;
+
+INSERT INTO messages_fts (rowid, content)
+    SELECT id, content FROM messages WHERE content IS NOT NULL AND (flags & 1) = 0;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS image_hash_blocklist (
+    hash       TEXT PRIMARY KEY,
+    reason     TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
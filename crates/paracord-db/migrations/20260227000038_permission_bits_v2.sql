@@ -0,0 +1,52 @@
+-- Permission bits v2: MANAGE_THREADS, MODERATE_MEMBERS, USE_SOUNDBOARD,
+-- MANAGE_INVITES and VIEW_REPORTS were split out of existing bits, so every
+-- stored bitfield that predates them gets the new bits its old ones implied
+-- (same mapping as `Permissions::upgrade_legacy`):
+--
+-- MANAGE_CHANNELS (1 << 4 = 16)        -> MANAGE_THREADS   (1 << 33 = 8,589,934,592)
+-- MUTE_MEMBERS    (1 << 22 = 4,194,304) -> MODERATE_MEMBERS (1 << 34 = 17,179,869,184)
+-- SPEAK           (1 << 21 = 2,097,152) -> USE_SOUNDBOARD   (1 << 35 = 34,359,738,368)
+-- MANAGE_GUILD    (1 << 5 = 32)        -> MANAGE_INVITES   (1 << 36 = 68,719,476,736)
+--                                        + VIEW_REPORTS     (1 << 37 = 137,438,953,472)
+--
+-- Only bitfields without any bit at or above 1 << 33 are touched.
+
+UPDATE roles
+SET permissions = permissions
+    | (CASE WHEN (permissions & 16) != 0 THEN 8589934592 ELSE 0 END)
+    | (CASE WHEN (permissions & 4194304) != 0 THEN 17179869184 ELSE 0 END)
+    | (CASE WHEN (permissions & 2097152) != 0 THEN 34359738368 ELSE 0 END)
+    | (CASE WHEN (permissions & 32) != 0 THEN 206158430208 ELSE 0 END)
+WHERE permissions >= 0 AND permissions < 8589934592;
+
+UPDATE channel_overwrites
+SET allow_perms = allow_perms
+    | (CASE WHEN (allow_perms & 16) != 0 THEN 8589934592 ELSE 0 END)
+    | (CASE WHEN (allow_perms & 4194304) != 0 THEN 17179869184 ELSE 0 END)
+    | (CASE WHEN (allow_perms & 2097152) != 0 THEN 34359738368 ELSE 0 END)
+    | (CASE WHEN (allow_perms & 32) != 0 THEN 206158430208 ELSE 0 END)
+WHERE allow_perms >= 0 AND allow_perms < 8589934592;
+
+UPDATE channel_overwrites
+SET deny_perms = deny_perms
+    | (CASE WHEN (deny_perms & 16) != 0 THEN 8589934592 ELSE 0 END)
+    | (CASE WHEN (deny_perms & 4194304) != 0 THEN 17179869184 ELSE 0 END)
+    | (CASE WHEN (deny_perms & 2097152) != 0 THEN 34359738368 ELSE 0 END)
+    | (CASE WHEN (deny_perms & 32) != 0 THEN 206158430208 ELSE 0 END)
+WHERE deny_perms >= 0 AND deny_perms < 8589934592;
+
+UPDATE bot_applications
+SET permissions = permissions
+    | (CASE WHEN (permissions & 16) != 0 THEN 8589934592 ELSE 0 END)
+    | (CASE WHEN (permissions & 4194304) != 0 THEN 17179869184 ELSE 0 END)
+    | (CASE WHEN (permissions & 2097152) != 0 THEN 34359738368 ELSE 0 END)
+    | (CASE WHEN (permissions & 32) != 0 THEN 206158430208 ELSE 0 END)
+WHERE permissions >= 0 AND permissions < 8589934592;
+
+UPDATE bot_guild_installs
+SET permissions = permissions
+    | (CASE WHEN (permissions & 16) != 0 THEN 8589934592 ELSE 0 END)
+    | (CASE WHEN (permissions & 4194304) != 0 THEN 17179869184 ELSE 0 END)
+    | (CASE WHEN (permissions & 2097152) != 0 THEN 34359738368 ELSE 0 END)
+    | (CASE WHEN (permissions & 32) != 0 THEN 206158430208 ELSE 0 END)
+WHERE permissions >= 0 AND permissions < 8589934592;
@@ -0,0 +1,28 @@
+-- Web Push subscriptions registered by browsers and desktop clients. An
+-- endpoint belongs to one user; re-registering it moves it to the caller.
+CREATE TABLE IF NOT EXISTS push_subscriptions (
+    id           BIGINT PRIMARY KEY,
+    user_id      BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    endpoint     TEXT NOT NULL UNIQUE,
+    p256dh       TEXT NOT NULL,
+    auth         TEXT NOT NULL,
+    user_agent   TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    last_used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_subscriptions_user ON push_subscriptions(user_id);
+
+-- Notifications waiting to be sent, one row per subscription. The delivery
+-- worker claims due rows by moving `next_attempt_at` forward, and deletes
+-- them once sent, rejected, or out of attempts.
+CREATE TABLE IF NOT EXISTS push_deliveries (
+    id              BIGINT PRIMARY KEY,
+    subscription_id BIGINT NOT NULL REFERENCES push_subscriptions(id) ON DELETE CASCADE,
+    payload         TEXT NOT NULL,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT (datetime('now')),
+    created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_push_deliveries_due ON push_deliveries(next_attempt_at);
@@ -0,0 +1,4 @@
+-- Image attachment previews. `thumbnail_format` (`jpeg` or `png`) is set
+-- once the thumbnails are in storage; NULL means there are none.
+ALTER TABLE attachments ADD COLUMN blurhash TEXT;
+ALTER TABLE attachments ADD COLUMN thumbnail_format TEXT;
@@ -0,0 +1,17 @@
+-- Guild mod log: audit log entries and automod actions mirrored into a
+-- channel. Lines wait in `mod_log_queue` until the next flush batches them
+-- into one system message per guild.
+CREATE TABLE IF NOT EXISTS guild_mod_log_settings (
+    guild_id          BIGINT PRIMARY KEY REFERENCES spaces(id) ON DELETE CASCADE,
+    channel_id        BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    include_audit_log BOOLEAN NOT NULL DEFAULT TRUE,
+    include_automod   BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at        TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS mod_log_queue (
+    id         BIGINT PRIMARY KEY,
+    guild_id   BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    content    TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
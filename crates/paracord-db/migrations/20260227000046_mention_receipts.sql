@@ -0,0 +1,7 @@
+-- Which members a message's mentions were already counted for, so a retried
+-- fan-out job doesn't count them twice. Pruned after a week.
+CREATE TABLE IF NOT EXISTS mention_receipts (
+    message_id BIGINT NOT NULL,
+    user_id    BIGINT NOT NULL,
+    PRIMARY KEY (message_id, user_id)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE attachments ADD COLUMN duration_ms INTEGER;
+ALTER TABLE attachments ADD COLUMN waveform TEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE attachments ADD COLUMN transcode_status TEXT;
+ALTER TABLE attachments ADD COLUMN transcoded_size INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_attachments_transcode_status ON attachments(transcode_status);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS voice_channel_stacks (
+    channel_id BIGINT PRIMARY KEY REFERENCES channels(id) ON DELETE CASCADE,
+    guild_id   BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    stack      TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_voice_channel_stacks_guild ON voice_channel_stacks(guild_id);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS user_notes (
+    user_id    BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_id  BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    note       TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, target_id)
+);
+
+CREATE TABLE IF NOT EXISTS member_mod_notes (
+    id         BIGINT PRIMARY KEY,
+    guild_id   BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    user_id    BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    author_id  BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    content    TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_member_mod_notes_member ON member_mod_notes(guild_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_member_mod_notes_author ON member_mod_notes(author_id);
@@ -0,0 +1,5 @@
+ALTER TABLE user_settings
+ADD COLUMN presence_visibility TEXT NOT NULL DEFAULT 'everyone';
+
+ALTER TABLE user_settings
+ADD COLUMN presence_hidden_guild_ids TEXT NOT NULL DEFAULT '[]';
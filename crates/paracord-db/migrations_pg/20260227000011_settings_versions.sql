@@ -0,0 +1,8 @@
+ALTER TABLE user_settings
+ADD COLUMN appearance_version BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE user_settings
+ADD COLUMN notifications_version BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE user_settings
+ADD COLUMN keybinds_version BIGINT NOT NULL DEFAULT 0;
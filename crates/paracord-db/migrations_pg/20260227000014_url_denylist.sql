@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS url_denylist (
+    domain     TEXT PRIMARY KEY,
+    reason     TEXT,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,13 @@
+-- Bytes exchanged with each federated peer, in one-minute buckets
+-- (`window_start` is unix time in minutes). Quotas sum the last hour.
+CREATE TABLE IF NOT EXISTS federation_peer_bandwidth (
+    server_name     VARCHAR(255) NOT NULL,
+    category        VARCHAR(32) NOT NULL,
+    window_start    BIGINT NOT NULL,
+    bytes_in        BIGINT NOT NULL DEFAULT 0,
+    bytes_out       BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (server_name, category, window_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_fed_peer_bandwidth_window
+    ON federation_peer_bandwidth(window_start);
@@ -0,0 +1,5 @@
+-- Cached federated files are stored once per content hash, so several
+-- (origin_server, origin_attachment_id) rows can share one storage_key.
+CREATE INDEX IF NOT EXISTS idx_fed_file_cache_hash ON federation_file_cache (content_hash);
+CREATE INDEX IF NOT EXISTS idx_fed_file_cache_storage_key ON federation_file_cache (storage_key);
+CREATE INDEX IF NOT EXISTS idx_fed_file_cache_accessed ON federation_file_cache (last_accessed_at);
@@ -0,0 +1,25 @@
+-- Archived channels are frozen and left out of the guild channel list, but
+-- can still be fetched and read.
+ALTER TABLE channels ADD COLUMN archived_at TEXT;
+
+-- Channel transcripts rendered in the background and kept in the storage
+-- backend until `expires_at`. Downloads require the token handed out when
+-- the export was requested; only its hash is stored.
+CREATE TABLE IF NOT EXISTS channel_exports (
+    id            BIGINT PRIMARY KEY,
+    guild_id      BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    channel_id    BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    requested_by  BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    format        TEXT NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'pending',
+    token_hash    TEXT NOT NULL,
+    storage_key   TEXT,
+    message_count BIGINT NOT NULL DEFAULT 0,
+    error         TEXT,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    completed_at  TEXT,
+    expires_at    TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_exports_channel ON channel_exports(channel_id, id);
+CREATE INDEX IF NOT EXISTS idx_channel_exports_expires ON channel_exports(expires_at);
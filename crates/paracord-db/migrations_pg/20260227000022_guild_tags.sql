@@ -0,0 +1,13 @@
+-- Text snippets that members post with `/tag <name>`. Names are stored
+-- lowercased and are unique per guild.
+CREATE TABLE IF NOT EXISTS guild_tags (
+    id         BIGINT PRIMARY KEY,
+    guild_id   BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    name       TEXT NOT NULL,
+    content    TEXT NOT NULL,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    uses       BIGINT NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (guild_id, name)
+);
@@ -0,0 +1,15 @@
+-- User reminders. Pending rows (`delivered_at IS NULL`) are picked up by the
+-- reminder worker once `due_at` passes, including after a restart.
+CREATE TABLE IF NOT EXISTS reminders (
+    id           BIGINT PRIMARY KEY,
+    user_id      BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    channel_id   BIGINT REFERENCES channels(id) ON DELETE SET NULL,
+    message_id   BIGINT,
+    content      TEXT NOT NULL,
+    due_at       TEXT NOT NULL,
+    delivered_at TEXT,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminders_user ON reminders(user_id, due_at);
+CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(delivered_at, due_at);
@@ -0,0 +1,9 @@
+-- Per-guild thread archiving policy. Guilds without a row use the defaults:
+-- no active thread limit and threads un-archive when someone posts in them.
+CREATE TABLE IF NOT EXISTS guild_thread_policies (
+    guild_id                      BIGINT PRIMARY KEY REFERENCES spaces(id) ON DELETE CASCADE,
+    max_active_threads            INTEGER,
+    default_auto_archive_duration INTEGER,
+    unarchive_on_message          BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at                    TEXT NOT NULL DEFAULT (datetime('now'))
+);
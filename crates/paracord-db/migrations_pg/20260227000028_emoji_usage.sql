@@ -0,0 +1,13 @@
+-- Daily rollups of custom emoji use in messages and reactions.
+CREATE TABLE IF NOT EXISTS emoji_usage_daily (
+    emoji_id       BIGINT NOT NULL REFERENCES emojis(id) ON DELETE CASCADE,
+    guild_id       BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    day            TEXT NOT NULL,
+    message_count  BIGINT NOT NULL DEFAULT 0,
+    reaction_count BIGINT NOT NULL DEFAULT 0,
+    last_used_at   TEXT NOT NULL,
+    PRIMARY KEY (emoji_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_emoji_usage_daily_guild_day
+    ON emoji_usage_daily (guild_id, day);
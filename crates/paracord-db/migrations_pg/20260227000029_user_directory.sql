@@ -0,0 +1,4 @@
+-- Users opt in to being listed in the user directory and in directory
+-- searches from trusted federated peers.
+ALTER TABLE user_settings
+ADD COLUMN directory_discoverable BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,26 @@
+-- External verification hook called when someone joins a guild.
+CREATE TABLE IF NOT EXISTS guild_verification_hooks (
+    guild_id   BIGINT PRIMARY KEY REFERENCES spaces(id) ON DELETE CASCADE,
+    url        TEXT NOT NULL,
+    secret     TEXT NOT NULL,
+    timeout_ms INTEGER NOT NULL DEFAULT 5000,
+    -- Admit joins when the hook can't be reached instead of holding them.
+    fail_open  BOOLEAN NOT NULL DEFAULT FALSE,
+    enabled    BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Joins the hook held ('pending') or turned away ('denied').
+CREATE TABLE IF NOT EXISTS guild_join_requests (
+    guild_id    BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    user_id     BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status      TEXT NOT NULL DEFAULT 'pending',
+    reason      TEXT,
+    invite_code TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (guild_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_join_requests_status
+    ON guild_join_requests (guild_id, status);
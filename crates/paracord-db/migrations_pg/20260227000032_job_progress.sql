@@ -0,0 +1,4 @@
+-- Handlers can report how far along a running job is. JSON document, shape
+-- defined by the job kind; kept after the job finishes so it can carry the
+-- final result.
+ALTER TABLE background_jobs ADD COLUMN progress TEXT;
@@ -0,0 +1,5 @@
+-- Full-text index over message content. Queries must use this exact
+-- expression for the planner to pick the index. The 'simple' configuration
+-- doesn't stem, which suits multilingual chat and matches the SQLite index.
+CREATE INDEX IF NOT EXISTS idx_messages_content_search
+    ON messages USING GIN (to_tsvector('simple', COALESCE(content, '')));
@@ -0,0 +1,28 @@
+-- Per-user notification settings. `level` is 0 (all messages), 1 (mentions
+-- only) or 2 (nothing). A mute applies while `muted` is set and
+-- `muted_until` is NULL or still in the future. Users without a row get
+-- level 0, unmuted, with @everyone/@here honored.
+CREATE TABLE IF NOT EXISTS guild_notification_settings (
+    user_id           BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    guild_id          BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    level             SMALLINT NOT NULL DEFAULT 0,
+    muted             BOOLEAN NOT NULL DEFAULT FALSE,
+    muted_until       TEXT,
+    suppress_everyone BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at        TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, guild_id)
+);
+
+-- Channel overrides; a NULL `level` inherits the guild setting.
+CREATE TABLE IF NOT EXISTS channel_notification_overrides (
+    user_id     BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    channel_id  BIGINT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    guild_id    BIGINT NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    level       SMALLINT,
+    muted       BOOLEAN NOT NULL DEFAULT FALSE,
+    muted_until TEXT,
+    PRIMARY KEY (user_id, channel_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_notification_overrides_guild
+    ON channel_notification_overrides (user_id, guild_id);
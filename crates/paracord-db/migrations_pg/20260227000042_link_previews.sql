@@ -0,0 +1,10 @@
+-- Link previews fetched for URLs posted in messages, shared by every
+-- message linking the same URL. `embed_data` is NULL when the page had
+-- nothing to preview, so it isn't fetched again until the row goes stale.
+CREATE TABLE IF NOT EXISTS link_previews (
+    url        TEXT PRIMARY KEY,
+    embed_data TEXT,
+    fetched_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_embeds_message_id ON message_embeds(message_id);
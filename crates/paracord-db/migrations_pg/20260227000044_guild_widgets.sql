@@ -0,0 +1,9 @@
+-- Opt-in public guild widget. `invite_code` is the invite the widget hands
+-- out, created when a channel is picked.
+CREATE TABLE IF NOT EXISTS guild_widget_settings (
+    guild_id    BIGINT PRIMARY KEY REFERENCES spaces(id) ON DELETE CASCADE,
+    enabled     BOOLEAN NOT NULL DEFAULT FALSE,
+    channel_id  BIGINT REFERENCES channels(id) ON DELETE SET NULL,
+    invite_code TEXT,
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,15 @@
+-- Devices a user has approved for gateway access, keyed by a hash of the
+-- client's device id, so signing in again from one needs no new approval.
+CREATE TABLE IF NOT EXISTS approved_devices (
+    user_id     BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    device_hash TEXT NOT NULL,
+    approved_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, device_hash)
+);
+
+-- Single-use code that approves a device when no trusted session can.
+CREATE TABLE IF NOT EXISTS device_recovery_codes (
+    user_id    BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    code_hash  TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
@@ -1,4 +1,4 @@
-use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbExecutor, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -17,6 +17,17 @@ pub struct AttachmentRow {
     pub upload_created_at: DateTime<Utc>,
     pub upload_expires_at: Option<DateTime<Utc>>,
     pub content_hash: Option<String>,
+    /// Clip length for voice-message attachments.
+    pub duration_ms: Option<i32>,
+    /// Base64-encoded amplitude summary (one byte per sample) for voice messages.
+    pub waveform: Option<String>,
+    /// Video transcoding job state (`pending`, `processing`, `done`, `failed`).
+    pub transcode_status: Option<String>,
+    /// Combined size of every transcoded rendition.
+    pub transcoded_size: Option<i32>,
+    pub blurhash: Option<String>,
+    /// Format of the generated thumbnails; `None` until they are stored.
+    pub thumbnail_format: Option<String>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for AttachmentRow {
@@ -40,6 +51,12 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for AttachmentRow {
                 .map(datetime_from_db_text)
                 .transpose()?,
             content_hash: row.try_get("content_hash")?,
+            duration_ms: row.try_get("duration_ms")?,
+            waveform: row.try_get("waveform")?,
+            transcode_status: row.try_get("transcode_status")?,
+            transcoded_size: row.try_get("transcoded_size")?,
+            blurhash: row.try_get("blurhash")?,
+            thumbnail_format: row.try_get("thumbnail_format")?,
         })
     }
 }
@@ -69,7 +86,8 @@ pub async fn create_attachment(
          RETURNING
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash",
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format",
     )
     .bind(id)
     .bind(message_id)
@@ -93,7 +111,8 @@ pub async fn get_attachment(pool: &DbPool, id: i64) -> Result<Option<AttachmentR
         "SELECT
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
          FROM attachments WHERE id = $1",
     )
     .bind(id)
@@ -102,6 +121,107 @@ pub async fn get_attachment(pool: &DbPool, id: i64) -> Result<Option<AttachmentR
     Ok(row)
 }
 
+pub async fn set_voice_metadata(
+    pool: &DbPool,
+    id: i64,
+    duration_ms: i32,
+    waveform: Option<&str>,
+) -> Result<(), DbError> {
+    sqlx::query("UPDATE attachments SET duration_ms = $2, waveform = $3 WHERE id = $1")
+        .bind(id)
+        .bind(duration_ms)
+        .bind(waveform)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Record the dimensions, BlurHash and thumbnail format of an image
+/// attachment once its thumbnails are stored.
+pub async fn set_image_metadata(
+    pool: &DbPool,
+    id: i64,
+    width: i32,
+    height: i32,
+    blurhash: Option<&str>,
+    thumbnail_format: &str,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "UPDATE attachments
+         SET width = $2, height = $3, blurhash = $4, thumbnail_format = $5
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(width)
+    .bind(height)
+    .bind(blurhash)
+    .bind(thumbnail_format)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_transcode_status(
+    pool: &DbPool,
+    id: i64,
+    status: &str,
+    transcoded_size: Option<i32>,
+) -> Result<(), DbError> {
+    sqlx::query("UPDATE attachments SET transcode_status = $2, transcoded_size = $3 WHERE id = $1")
+        .bind(id)
+        .bind(status)
+        .bind(transcoded_size)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Atomically move a `pending` transcode job to `processing`.
+///
+/// Returns false when another worker already claimed it.
+pub async fn claim_transcode_job(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE attachments SET transcode_status = 'processing'
+         WHERE id = $1 AND transcode_status = 'pending'",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_pending_transcode_jobs(
+    pool: &DbPool,
+    limit: i64,
+) -> Result<Vec<AttachmentRow>, DbError> {
+    let rows = sqlx::query_as::<_, AttachmentRow>(
+        "SELECT
+            id, message_id, filename, content_type, size, url, width, height,
+            uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
+         FROM attachments
+         WHERE transcode_status = 'pending'
+         ORDER BY upload_created_at ASC
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Requeue jobs left in `processing` by a previous run that exited mid-transcode.
+pub async fn requeue_interrupted_transcodes(pool: &DbPool) -> Result<u64, DbError> {
+    let result = sqlx::query(
+        "UPDATE attachments SET transcode_status = 'pending'
+         WHERE transcode_status = 'processing'",
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
 pub async fn delete_attachment(pool: &DbPool, id: i64) -> Result<(), DbError> {
     sqlx::query("DELETE FROM attachments WHERE id = $1")
         .bind(id)
@@ -118,7 +238,8 @@ pub async fn get_message_attachments(
         "SELECT
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
          FROM attachments WHERE message_id = $1",
     )
     .bind(message_id)
@@ -127,8 +248,8 @@ pub async fn get_message_attachments(
     Ok(rows)
 }
 
-pub async fn attach_to_message(
-    pool: &DbPool,
+pub async fn attach_to_message<'e>(
+    executor: impl DbExecutor<'e>,
     id: i64,
     message_id: i64,
     uploader_id: i64,
@@ -149,7 +270,7 @@ pub async fn attach_to_message(
     .bind(uploader_id)
     .bind(channel_id)
     .bind(datetime_to_db_text(now))
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(result.rows_affected() > 0)
 }
@@ -163,7 +284,8 @@ pub async fn get_expired_pending_attachments(
         "SELECT
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
          FROM attachments
          WHERE message_id IS NULL
            AND upload_expires_at IS NOT NULL
@@ -198,7 +320,8 @@ pub async fn get_attachments_for_message_ids(
         "SELECT
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
          FROM attachments
          WHERE message_id IN ({})
          ORDER BY upload_created_at ASC
@@ -225,10 +348,14 @@ pub async fn get_unlinked_attachments_older_than(
         "SELECT
             id, message_id, filename, content_type, size, url, width, height,
             uploader_id, upload_channel_id, upload_created_at, upload_expires_at,
-            content_hash
+            content_hash, duration_ms, waveform, transcode_status, transcoded_size,
+            blurhash, thumbnail_format
          FROM attachments
          WHERE message_id IS NULL
            AND upload_created_at <= $1
+           AND id NOT IN (
+               SELECT attachment_id FROM voice_recordings WHERE attachment_id IS NOT NULL
+           )
          ORDER BY upload_created_at ASC
          LIMIT $2",
     )
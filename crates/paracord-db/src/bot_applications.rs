@@ -270,6 +270,15 @@ pub async fn list_guild_bots(
     Ok(rows)
 }
 
+pub async fn count_guild_bots(pool: &DbPool, guild_id: i64) -> Result<i64, DbError> {
+    let count: (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM bot_guild_installs WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(count.0)
+}
+
 pub async fn is_bot_in_guild(
     pool: &DbPool,
     bot_app_id: i64,
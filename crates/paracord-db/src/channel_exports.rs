@@ -0,0 +1,288 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+
+/// Standalone HTML page.
+pub const EXPORT_FORMAT_HTML: &str = "html";
+/// JSON document with the channel and its messages.
+pub const EXPORT_FORMAT_JSON: &str = "json";
+
+pub const EXPORT_STATUS_PENDING: &str = "pending";
+pub const EXPORT_STATUS_COMPLETE: &str = "complete";
+pub const EXPORT_STATUS_FAILED: &str = "failed";
+
+#[derive(Debug, Clone)]
+pub struct ChannelExportRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub requested_by: Option<i64>,
+    pub format: String,
+    pub status: String,
+    pub storage_key: Option<String>,
+    pub message_count: i64,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ChannelExportRow {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ChannelExportRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        let completed_at_raw: Option<String> = row.try_get("completed_at")?;
+        let expires_at_raw: String = row.try_get("expires_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            channel_id: row.try_get("channel_id")?,
+            requested_by: row.try_get("requested_by")?,
+            format: row.try_get("format")?,
+            status: row.try_get("status")?,
+            storage_key: row.try_get("storage_key")?,
+            message_count: row.try_get("message_count")?,
+            error: row.try_get("error")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            completed_at: completed_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            expires_at: datetime_from_db_text(&expires_at_raw)?,
+        })
+    }
+}
+
+fn hash_download_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.trim().as_bytes());
+    let digest = hasher.finalize();
+    let mut out = String::with_capacity(digest.len() * 2);
+    for b in digest {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_export(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    requested_by: i64,
+    format: &str,
+    download_token: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<ChannelExportRow, DbError> {
+    let row = sqlx::query_as::<_, ChannelExportRow>(
+        "INSERT INTO channel_exports
+            (id, guild_id, channel_id, requested_by, format, token_hash, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING
+            id, guild_id, channel_id, requested_by, format, status, storage_key,
+            message_count, error, created_at, completed_at, expires_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(channel_id)
+    .bind(requested_by)
+    .bind(format)
+    .bind(hash_download_token(download_token))
+    .bind(datetime_to_db_text(expires_at))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_export(pool: &DbPool, id: i64) -> Result<Option<ChannelExportRow>, DbError> {
+    let row = sqlx::query_as::<_, ChannelExportRow>(
+        "SELECT
+            id, guild_id, channel_id, requested_by, format, status, storage_key,
+            message_count, error, created_at, completed_at, expires_at
+         FROM channel_exports WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Look up an export by id, only if `download_token` matches.
+pub async fn get_export_by_token(
+    pool: &DbPool,
+    id: i64,
+    download_token: &str,
+) -> Result<Option<ChannelExportRow>, DbError> {
+    let row = sqlx::query_as::<_, ChannelExportRow>(
+        "SELECT
+            id, guild_id, channel_id, requested_by, format, status, storage_key,
+            message_count, error, created_at, completed_at, expires_at
+         FROM channel_exports WHERE id = $1 AND token_hash = $2",
+    )
+    .bind(id)
+    .bind(hash_download_token(download_token))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Exports of a channel, newest first.
+pub async fn list_channel_exports(
+    pool: &DbPool,
+    channel_id: i64,
+    limit: i64,
+) -> Result<Vec<ChannelExportRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelExportRow>(
+        "SELECT
+            id, guild_id, channel_id, requested_by, format, status, storage_key,
+            message_count, error, created_at, completed_at, expires_at
+         FROM channel_exports
+         WHERE channel_id = $1
+         ORDER BY id DESC
+         LIMIT $2",
+    )
+    .bind(channel_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn complete_export(
+    pool: &DbPool,
+    id: i64,
+    storage_key: &str,
+    message_count: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE channel_exports
+         SET status = 'complete', storage_key = $2, message_count = $3,
+             completed_at = datetime('now')
+         WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(id)
+    .bind(storage_key)
+    .bind(message_count)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fail_export(pool: &DbPool, id: i64, error: &str) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE channel_exports
+         SET status = 'failed', error = $2, completed_at = datetime('now')
+         WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_expired_exports(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ChannelExportRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelExportRow>(
+        "SELECT
+            id, guild_id, channel_id, requested_by, format, status, storage_key,
+            message_count, error, created_at, completed_at, expires_at
+         FROM channel_exports
+         WHERE expires_at <= $1
+         ORDER BY expires_at ASC
+         LIMIT $2",
+    )
+    .bind(datetime_to_db_text(now))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete_export(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM channel_exports WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    async fn setup_channel(pool: &DbPool) -> (i64, i64) {
+        crate::users::create_user(pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(pool, 100, "Test Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(pool, 10, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        (100, 10)
+    }
+
+    #[tokio::test]
+    async fn test_export_lifecycle_and_token_lookup() {
+        let pool = test_pool().await;
+        let (guild_id, channel_id) = setup_channel(&pool).await;
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        let export = create_export(
+            &pool,
+            500,
+            guild_id,
+            channel_id,
+            1,
+            EXPORT_FORMAT_HTML,
+            "secret",
+            expires_at,
+        )
+        .await
+        .unwrap();
+        assert_eq!(export.status, EXPORT_STATUS_PENDING);
+
+        assert!(get_export_by_token(&pool, 500, "wrong")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(complete_export(&pool, 500, "exports/500.html", 3)
+            .await
+            .unwrap());
+        assert!(!fail_export(&pool, 500, "too late").await.unwrap());
+
+        let export = get_export_by_token(&pool, 500, "secret")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(export.status, EXPORT_STATUS_COMPLETE);
+        assert_eq!(export.message_count, 3);
+        assert_eq!(export.storage_key.as_deref(), Some("exports/500.html"));
+
+        assert!(get_expired_exports(&pool, Utc::now(), 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let expired = get_expired_exports(&pool, expires_at + chrono::Duration::seconds(1), 10)
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+    }
+}
@@ -91,3 +91,215 @@ pub async fn delete_channel_overwrite(
     .await?;
     Ok(())
 }
+
+/// Overwrites that point at a channel, role or user that no longer exists,
+/// or carry an unknown target type. Roles of a deleted guild or channel that
+/// can still be restored are not orphans.
+pub async fn find_orphaned_overwrites(pool: &DbPool) -> Result<Vec<ChannelOverwriteRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelOverwriteRow>(
+        "SELECT o.channel_id, o.target_id, o.target_type, o.allow_perms, o.deny_perms
+         FROM channel_overwrites o
+         LEFT JOIN channels c ON c.id = o.channel_id
+         WHERE c.id IS NULL
+            OR o.target_type NOT IN (0, 1)
+            OR (o.target_type = 0 AND o.target_id <> c.space_id AND NOT EXISTS (
+                    SELECT 1 FROM roles r WHERE r.id = o.target_id AND r.space_id = c.space_id))
+            OR (o.target_type = 1 AND NOT EXISTS (
+                    SELECT 1 FROM users u WHERE u.id = o.target_id))
+         ORDER BY o.channel_id, o.target_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// What [`replace_channel_overwrites`] changed.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelOverwriteDiff {
+    pub added: Vec<ChannelOverwriteRow>,
+    pub updated: Vec<ChannelOverwriteRow>,
+    pub removed: Vec<ChannelOverwriteRow>,
+}
+
+impl ChannelOverwriteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Replace the full overwrite set of a channel in one transaction. Targets
+/// missing from `overwrites` are removed; unchanged rows are left alone.
+pub async fn replace_channel_overwrites(
+    pool: &DbPool,
+    channel_id: i64,
+    overwrites: &[ChannelOverwriteRow],
+) -> Result<ChannelOverwriteDiff, DbError> {
+    let mut tx = pool.begin().await?;
+    let existing = sqlx::query_as::<_, ChannelOverwriteRow>(
+        "SELECT channel_id, target_id, target_type, allow_perms, deny_perms
+         FROM channel_overwrites
+         WHERE channel_id = $1",
+    )
+    .bind(channel_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut diff = ChannelOverwriteDiff::default();
+    for current in &existing {
+        if !overwrites.iter().any(|o| o.target_id == current.target_id) {
+            diff.removed.push(current.clone());
+        }
+    }
+    for overwrite in overwrites {
+        match existing.iter().find(|e| e.target_id == overwrite.target_id) {
+            None => diff.added.push(overwrite.clone()),
+            Some(current)
+                if current.target_type != overwrite.target_type
+                    || current.allow_perms != overwrite.allow_perms
+                    || current.deny_perms != overwrite.deny_perms =>
+            {
+                diff.updated.push(overwrite.clone())
+            }
+            Some(_) => {}
+        }
+    }
+
+    for removed in &diff.removed {
+        sqlx::query("DELETE FROM channel_overwrites WHERE channel_id = $1 AND target_id = $2")
+            .bind(channel_id)
+            .bind(removed.target_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    for overwrite in diff.added.iter().chain(diff.updated.iter()) {
+        sqlx::query(
+            "INSERT INTO channel_overwrites (channel_id, target_id, target_type, allow_perms, deny_perms)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (channel_id, target_id) DO UPDATE
+             SET target_type = EXCLUDED.target_type,
+                 allow_perms = EXCLUDED.allow_perms,
+                 deny_perms = EXCLUDED.deny_perms",
+        )
+        .bind(channel_id)
+        .bind(overwrite.target_id)
+        .bind(overwrite.target_type)
+        .bind(overwrite.allow_perms)
+        .bind(overwrite.deny_perms)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    fn overwrite(target_id: i64, allow_perms: i64, deny_perms: i64) -> ChannelOverwriteRow {
+        ChannelOverwriteRow {
+            channel_id: 200,
+            target_id,
+            target_type: 0,
+            allow_perms,
+            deny_perms,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_channel_overwrites_diffs_the_set() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 200, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 100, 0, 0, 1024)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 300, 0, 1024, 0)
+            .await
+            .unwrap();
+
+        let diff = replace_channel_overwrites(
+            &pool,
+            200,
+            &[
+                overwrite(100, 0, 1024),
+                overwrite(300, 1024, 2048),
+                overwrite(301, 8, 0),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.updated.len(), 1);
+        assert!(diff.removed.is_empty());
+
+        let diff = replace_channel_overwrites(&pool, 200, &[overwrite(301, 8, 0)])
+            .await
+            .unwrap();
+        assert!(diff.added.is_empty() && diff.updated.is_empty());
+        assert_eq!(diff.removed.len(), 2);
+        let rows = get_channel_overwrites(&pool, 200).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].target_id, 301);
+        assert!(
+            replace_channel_overwrites(&pool, 200, &[overwrite(301, 8, 0)])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_orphaned_overwrites() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 200, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 300, 100, "Mods", 0)
+            .await
+            .unwrap();
+        // @everyone, a live role and a live member are fine...
+        upsert_channel_overwrite(&pool, 200, 100, 0, 0, 1024)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 300, 0, 1024, 0)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 1, 1, 1024, 0)
+            .await
+            .unwrap();
+        // ...a missing role, a missing user and an unknown type are not.
+        upsert_channel_overwrite(&pool, 200, 301, 0, 1024, 0)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 2, 1, 1024, 0)
+            .await
+            .unwrap();
+        upsert_channel_overwrite(&pool, 200, 3, 7, 1024, 0)
+            .await
+            .unwrap();
+
+        let orphans = find_orphaned_overwrites(&pool).await.unwrap();
+        let targets: Vec<i64> = orphans.iter().map(|o| o.target_id).collect();
+        assert_eq!(targets, vec![2, 3, 301]);
+    }
+}
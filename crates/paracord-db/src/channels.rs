@@ -1,9 +1,9 @@
-use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbExecutor, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 use std::collections::BTreeSet;
 
-fn thread_is_archived(thread_metadata: Option<&str>) -> bool {
+fn thread_metadata_flag(thread_metadata: Option<&str>, key: &str) -> bool {
     let Some(raw) = thread_metadata else {
         return false;
     };
@@ -11,11 +11,15 @@ fn thread_is_archived(thread_metadata: Option<&str>) -> bool {
         return false;
     };
     value
-        .get("archived")
-        .and_then(|archived| archived.as_bool())
+        .get(key)
+        .and_then(|flag| flag.as_bool())
         .unwrap_or(false)
 }
 
+fn thread_is_archived(thread_metadata: Option<&str>) -> bool {
+    thread_metadata_flag(thread_metadata, "archived")
+}
+
 #[derive(Debug, Clone)]
 pub struct ChannelRow {
     pub id: i64,
@@ -36,6 +40,8 @@ pub struct ChannelRow {
     pub message_count: Option<i32>,
     pub applied_tags: Option<String>,
     pub default_sort_order: Option<i32>,
+    pub posting_mode: i16,
+    pub archived_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -53,6 +59,7 @@ pub struct ForumTagRow {
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ChannelRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let created_at_raw: String = row.try_get("created_at")?;
+        let archived_at_raw: Option<String> = row.try_get("archived_at")?;
         Ok(Self {
             id: row.try_get("id")?,
             space_id: row.try_get("space_id")?,
@@ -72,6 +79,11 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ChannelRow {
             message_count: row.try_get("message_count")?,
             applied_tags: row.try_get("applied_tags")?,
             default_sort_order: row.try_get("default_sort_order")?,
+            posting_mode: row.try_get("posting_mode")?,
+            archived_at: archived_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
             created_at: datetime_from_db_text(&created_at_raw)?,
         })
     }
@@ -97,11 +109,50 @@ impl ChannelRow {
     pub fn guild_id(&self) -> Option<i64> {
         self.space_id
     }
+
+    pub fn is_archived_thread(&self) -> bool {
+        self.channel_type == 6 && thread_is_archived(self.thread_metadata.as_deref())
+    }
+
+    pub fn is_locked_thread(&self) -> bool {
+        self.channel_type == 6 && thread_metadata_flag(self.thread_metadata.as_deref(), "locked")
+    }
+
+    /// Minutes without activity after which the thread is archived.
+    pub fn thread_auto_archive_duration(&self) -> Option<i64> {
+        let raw = self.thread_metadata.as_deref()?;
+        serde_json::from_str::<serde_json::Value>(raw)
+            .ok()?
+            .get("auto_archive_duration")?
+            .as_i64()
+    }
+
+    /// The thread's newest message or archive state change, whichever is
+    /// later, falling back to its creation time.
+    pub fn thread_last_activity(&self) -> DateTime<Utc> {
+        let last_message_at = self
+            .last_message_id
+            .map(paracord_util::snowflake::created_at);
+        let archive_changed_at = self
+            .thread_metadata
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            .and_then(|metadata| {
+                metadata
+                    .get("archive_timestamp")
+                    .and_then(|value| value.as_str())
+                    .and_then(|raw| datetime_from_db_text(raw).ok())
+            });
+        [last_message_at, archive_changed_at]
+            .into_iter()
+            .flatten()
+            .fold(self.created_at, DateTime::max)
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
-pub async fn create_channel(
-    pool: &DbPool,
+pub async fn create_channel<'e>(
+    executor: impl DbExecutor<'e>,
     id: i64,
     space_id: i64,
     name: &str,
@@ -113,7 +164,7 @@ pub async fn create_channel(
     let row = sqlx::query_as::<_, ChannelRow>(
         "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids)
          VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, '[]'))
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -122,15 +173,15 @@ pub async fn create_channel(
     .bind(position)
     .bind(parent_id)
     .bind(required_role_ids)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
     Ok(row)
 }
 
 pub async fn get_channel(pool: &DbPool, id: i64) -> Result<Option<ChannelRow>, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
-         FROM channels WHERE id = $1"
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
+         FROM channels WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -145,8 +196,8 @@ pub async fn get_guild_channels(pool: &DbPool, space_id: i64) -> Result<Vec<Chan
 
 pub async fn get_space_channels(pool: &DbPool, space_id: i64) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
-         FROM channels WHERE space_id = $1 ORDER BY position"
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
+         FROM channels WHERE space_id = $1 AND deleted_at IS NULL ORDER BY position"
     )
     .bind(space_id)
     .fetch_all(pool)
@@ -154,31 +205,128 @@ pub async fn get_space_channels(pool: &DbPool, space_id: i64) -> Result<Vec<Chan
     Ok(rows)
 }
 
+/// Channels and categories in a guild, not counting threads.
+pub async fn count_guild_channels(pool: &DbPool, space_id: i64) -> Result<i64, DbError> {
+    let count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM channels
+         WHERE space_id = $1 AND channel_type <> 6 AND deleted_at IS NULL",
+    )
+    .bind(space_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count.0)
+}
+
 pub async fn update_channel(
     pool: &DbPool,
     id: i64,
     name: Option<&str>,
     topic: Option<&str>,
     required_role_ids: Option<&str>,
+    nsfw: Option<bool>,
+    posting_mode: Option<i16>,
 ) -> Result<ChannelRow, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
         "UPDATE channels
          SET name = COALESCE($2, name),
              topic = COALESCE($3, topic),
              required_role_ids = COALESCE($4, required_role_ids),
+             nsfw = COALESCE($5, nsfw),
+             posting_mode = COALESCE($6, posting_mode),
              updated_at = datetime('now')
          WHERE id = $1
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
     )
     .bind(id)
     .bind(name)
     .bind(topic)
     .bind(required_role_ids)
+    .bind(nsfw)
+    .bind(posting_mode)
     .fetch_one(pool)
     .await?;
     Ok(row)
 }
 
+/// Set a channel's NSFW flag without touching anything else.
+pub async fn set_channel_nsfw(pool: &DbPool, id: i64, nsfw: bool) -> Result<(), DbError> {
+    sqlx::query("UPDATE channels SET nsfw = $2, updated_at = datetime('now') WHERE id = $1")
+        .bind(id)
+        .bind(nsfw)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Archive or unarchive a channel. Archiving an already archived channel
+/// keeps its original timestamp.
+pub async fn set_channel_archived(
+    pool: &DbPool,
+    id: i64,
+    archived: bool,
+) -> Result<Option<ChannelRow>, DbError> {
+    let sql = if archived {
+        "UPDATE channels
+         SET archived_at = COALESCE(archived_at, datetime('now')), updated_at = datetime('now')
+         WHERE id = $1
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
+    } else {
+        "UPDATE channels
+         SET archived_at = NULL, updated_at = datetime('now')
+         WHERE id = $1
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
+    };
+    let row = sqlx::query_as::<_, ChannelRow>(sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Set a channel's posting mode without touching anything else.
+pub async fn set_channel_posting_mode(
+    pool: &DbPool,
+    id: i64,
+    posting_mode: i16,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "UPDATE channels SET posting_mode = $2, updated_at = datetime('now') WHERE id = $1",
+    )
+    .bind(id)
+    .bind(posting_mode)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether a channel is NSFW. Threads inherit the flag from their parent.
+pub async fn is_channel_nsfw(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT 1
+         FROM channels c
+         LEFT JOIN channels p ON p.id = c.parent_id AND c.channel_type = 6
+         WHERE c.id = $1
+           AND (c.nsfw OR COALESCE(p.nsfw, FALSE))
+         LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+/// Spaces with at least one NSFW channel.
+pub async fn list_nsfw_space_ids(pool: &DbPool) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT DISTINCT space_id
+         FROM channels
+         WHERE nsfw AND space_id IS NOT NULL AND deleted_at IS NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(space_id,)| space_id).collect())
+}
+
 pub async fn delete_channel(pool: &DbPool, id: i64) -> Result<(), DbError> {
     sqlx::query("DELETE FROM channels WHERE id = $1")
         .bind(id)
@@ -188,7 +336,7 @@ pub async fn delete_channel(pool: &DbPool, id: i64) -> Result<(), DbError> {
 }
 
 pub async fn count_channels(pool: &DbPool) -> Result<i64, DbError> {
-    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM channels")
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM channels WHERE deleted_at IS NULL")
         .fetch_one(pool)
         .await?;
     Ok(row.0)
@@ -215,15 +363,17 @@ pub async fn update_channel_positions(
     guild_id: i64,
     positions: &[(i64, i32, Option<Option<i64>>)],
 ) -> Result<Vec<ChannelRow>, DbError> {
+    // Apply the whole reorder or none of it.
+    let mut tx = pool.begin().await?;
     let mut changed = Vec::new();
     for &(channel_id, position, ref parent_id) in positions {
         let existing = sqlx::query_as::<_, ChannelRow>(
-            "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
-             FROM channels WHERE id = $1 AND space_id = $2"
+            "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
+             FROM channels WHERE id = $1 AND space_id = $2 AND deleted_at IS NULL"
         )
         .bind(channel_id)
         .bind(guild_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
         let Some(existing) = existing else { continue };
@@ -240,15 +390,16 @@ pub async fn update_channel_positions(
         let row = sqlx::query_as::<_, ChannelRow>(
             "UPDATE channels SET position = $2, parent_id = $3, updated_at = datetime('now')
              WHERE id = $1
-             RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+             RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
         )
         .bind(channel_id)
         .bind(position)
         .bind(new_parent)
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
         changed.push(row);
     }
+    tx.commit().await?;
     Ok(changed)
 }
 
@@ -286,7 +437,7 @@ pub async fn create_thread(
     let row = sqlx::query_as::<_, ChannelRow>(
         "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count)
          VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0)
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -305,9 +456,9 @@ pub async fn get_channel_threads(
     parent_channel_id: i64,
 ) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
          FROM channels
-         WHERE parent_id = $1 AND channel_type = 6
+         WHERE parent_id = $1 AND channel_type = 6 AND deleted_at IS NULL
          ORDER BY created_at DESC"
     )
     .bind(parent_channel_id)
@@ -325,9 +476,9 @@ pub async fn get_archived_threads(
     parent_channel_id: i64,
 ) -> Result<Vec<ChannelRow>, DbError> {
     let rows = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
          FROM channels
-         WHERE parent_id = $1 AND channel_type = 6
+         WHERE parent_id = $1 AND channel_type = 6 AND deleted_at IS NULL
          ORDER BY created_at DESC"
     )
     .bind(parent_channel_id)
@@ -339,16 +490,19 @@ pub async fn get_archived_threads(
         .collect())
 }
 
-/// Update thread archived/locked state and optionally rename.
+/// Update thread archived/locked state and optionally rename. Archiving or
+/// unarchiving stamps `archive_timestamp`, which restarts the inactivity
+/// clock for auto-archiving.
 pub async fn update_thread(
     pool: &DbPool,
     thread_id: i64,
     name: Option<&str>,
     archived: Option<bool>,
     locked: Option<bool>,
+    auto_archive_duration: Option<i64>,
 ) -> Result<ChannelRow, DbError> {
     let existing = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
          FROM channels
          WHERE id = $1 AND channel_type = 6",
     )
@@ -367,15 +521,15 @@ pub async fn update_thread(
 
     if let Some(archived_val) = archived {
         metadata["archived"] = serde_json::Value::Bool(archived_val);
-        if archived_val {
-            metadata["archive_timestamp"] = serde_json::Value::String(
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            );
-        }
+        metadata["archive_timestamp"] =
+            serde_json::Value::String(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string());
     }
     if let Some(locked_val) = locked {
         metadata["locked"] = serde_json::Value::Bool(locked_val);
     }
+    if let Some(duration) = auto_archive_duration {
+        metadata["auto_archive_duration"] = serde_json::Value::from(duration);
+    }
 
     let metadata_raw = serde_json::to_string(&metadata).unwrap_or_else(|_| "{}".to_string());
 
@@ -385,7 +539,7 @@ pub async fn update_thread(
              thread_metadata = $3,
              updated_at = datetime('now')
          WHERE id = $1 AND channel_type = 6
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at",
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at",
     )
     .bind(thread_id)
     .bind(name)
@@ -395,6 +549,37 @@ pub async fn update_thread(
     Ok(row)
 }
 
+/// Non-archived threads across every guild.
+pub async fn get_all_active_threads(pool: &DbPool) -> Result<Vec<ChannelRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelRow>(
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
+         FROM channels
+         WHERE channel_type = 6 AND deleted_at IS NULL
+         ORDER BY id ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter(|row| !thread_is_archived(row.thread_metadata.as_deref()))
+        .collect())
+}
+
+/// Number of non-archived threads in a guild, forum posts included.
+pub async fn count_active_guild_threads(pool: &DbPool, guild_id: i64) -> Result<i64, DbError> {
+    let rows: Vec<(Option<String>,)> = sqlx::query_as(
+        "SELECT thread_metadata FROM channels
+         WHERE space_id = $1 AND channel_type = 6 AND deleted_at IS NULL",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .filter(|(metadata,)| !thread_is_archived(metadata.as_deref()))
+        .count() as i64)
+}
+
 /// Increment the message count for a thread channel.
 pub async fn increment_thread_message_count(pool: &DbPool, thread_id: i64) -> Result<(), DbError> {
     sqlx::query(
@@ -432,7 +617,7 @@ pub async fn create_forum_post(
     let row = sqlx::query_as::<_, ChannelRow>(
         "INSERT INTO channels (id, space_id, name, channel_type, position, parent_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags)
          VALUES ($1, $2, $3, 6, 0, $4, '[]', $5, $6, 0, $7)
-         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at"
+         RETURNING id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at"
     )
     .bind(id)
     .bind(space_id)
@@ -461,9 +646,9 @@ pub async fn get_forum_posts(
     };
 
     let sql = format!(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, created_at
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw, rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids, thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode, archived_at, created_at
          FROM channels
-         WHERE parent_id = $1 AND channel_type = 6
+         WHERE parent_id = $1 AND channel_type = 6 AND deleted_at IS NULL
          ORDER BY {}",
         order
     );
@@ -642,9 +827,17 @@ mod tests {
         create_channel(&pool, 40, guild_id, "old-name", 0, 0, None, None)
             .await
             .unwrap();
-        let updated = update_channel(&pool, 40, Some("new-name"), Some("A topic"), None)
-            .await
-            .unwrap();
+        let updated = update_channel(
+            &pool,
+            40,
+            Some("new-name"),
+            Some("A topic"),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(updated.name.as_deref(), Some("new-name"));
         assert_eq!(updated.topic.as_deref(), Some("A topic"));
     }
@@ -656,13 +849,57 @@ mod tests {
         create_channel(&pool, 41, guild_id, "keep-name", 0, 0, None, None)
             .await
             .unwrap();
-        let updated = update_channel(&pool, 41, None, Some("topic only"), None)
+        let updated = update_channel(&pool, 41, None, Some("topic only"), None, None, None)
             .await
             .unwrap();
         assert_eq!(updated.name.as_deref(), Some("keep-name"));
         assert_eq!(updated.topic.as_deref(), Some("topic only"));
     }
 
+    #[tokio::test]
+    async fn test_channel_posting_mode() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        let created = create_channel(&pool, 44, guild_id, "announcements", 0, 0, None, None)
+            .await
+            .unwrap();
+        assert_eq!(created.posting_mode, 0);
+
+        let updated = update_channel(&pool, 44, None, None, None, None, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(updated.posting_mode, 1);
+
+        set_channel_posting_mode(&pool, 44, 2).await.unwrap();
+        let channel = get_channel(&pool, 44).await.unwrap().unwrap();
+        assert_eq!(channel.posting_mode, 2);
+    }
+
+    #[tokio::test]
+    async fn test_nsfw_flag_is_inherited_by_threads() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 42, guild_id, "after-dark", 0, 0, None, None)
+            .await
+            .unwrap();
+        create_thread(&pool, 43, guild_id, 42, "thread", 1, 1440, None)
+            .await
+            .unwrap();
+        assert!(!is_channel_nsfw(&pool, 43).await.unwrap());
+        assert!(list_nsfw_space_ids(&pool).await.unwrap().is_empty());
+
+        let updated = update_channel(&pool, 42, None, None, None, Some(true), None)
+            .await
+            .unwrap();
+        assert!(updated.nsfw);
+        assert!(is_channel_nsfw(&pool, 42).await.unwrap());
+        assert!(is_channel_nsfw(&pool, 43).await.unwrap());
+        assert_eq!(list_nsfw_space_ids(&pool).await.unwrap(), vec![guild_id]);
+
+        set_channel_nsfw(&pool, 42, false).await.unwrap();
+        assert!(!is_channel_nsfw(&pool, 43).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_delete_channel() {
         let pool = test_pool().await;
@@ -766,6 +1003,46 @@ mod tests {
         assert_eq!(threads.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_archiving_threads_updates_active_counts() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        create_channel(&pool, 96, guild_id, "parent", 0, 0, None, None)
+            .await
+            .unwrap();
+        let thread = create_thread(&pool, 97, guild_id, 96, "thread", 1, 60, None)
+            .await
+            .unwrap();
+        create_thread(&pool, 98, guild_id, 96, "other", 1, 60, None)
+            .await
+            .unwrap();
+        assert_eq!(thread.thread_auto_archive_duration(), Some(60));
+        assert_eq!(thread.thread_last_activity(), thread.created_at);
+        assert_eq!(
+            count_active_guild_threads(&pool, guild_id).await.unwrap(),
+            2
+        );
+
+        let archived = update_thread(&pool, 97, None, Some(true), None, Some(1440))
+            .await
+            .unwrap();
+        assert!(archived.is_archived_thread());
+        assert!(!archived.is_locked_thread());
+        assert_eq!(archived.thread_auto_archive_duration(), Some(1440));
+        assert!(archived.thread_last_activity() >= thread.created_at);
+        assert_eq!(
+            count_active_guild_threads(&pool, guild_id).await.unwrap(),
+            1
+        );
+        let active: Vec<i64> = get_all_active_threads(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.id)
+            .collect();
+        assert_eq!(active, vec![98]);
+    }
+
     #[tokio::test]
     async fn test_guild_id_backward_compat() {
         let pool = test_pool().await;
@@ -775,4 +1052,47 @@ mod tests {
             .unwrap();
         assert_eq!(channel.guild_id(), Some(guild_id));
     }
+
+    #[tokio::test]
+    async fn test_uncommitted_transaction_rolls_back() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        {
+            let mut tx = crate::begin(&pool).await.unwrap();
+            create_channel(&mut *tx, 30, guild_id, "draft", 0, 0, None, None)
+                .await
+                .unwrap();
+        }
+        assert!(get_channel(&pool, 30).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_guild_role_clears_channel_references() {
+        let pool = test_pool().await;
+        let guild_id = setup_guild(&pool).await;
+        crate::roles::create_role(&pool, 200, guild_id, "Gate", 0)
+            .await
+            .unwrap();
+        create_channel(&pool, 40, guild_id, "gated", 0, 0, None, Some("[200,201]"))
+            .await
+            .unwrap();
+        crate::channel_overwrites::upsert_channel_overwrite(&pool, 40, 200, 0, 0, 1024)
+            .await
+            .unwrap();
+
+        crate::roles::delete_guild_role(&pool, guild_id, 200)
+            .await
+            .unwrap();
+
+        let channel = get_channel(&pool, 40).await.unwrap().unwrap();
+        assert_eq!(
+            parse_required_role_ids(&channel.required_role_ids),
+            vec![201]
+        );
+        let overwrites = crate::channel_overwrites::get_channel_overwrites(&pool, 40)
+            .await
+            .unwrap();
+        assert!(overwrites.is_empty());
+        assert!(crate::roles::get_role(&pool, 200).await.unwrap().is_none());
+    }
 }
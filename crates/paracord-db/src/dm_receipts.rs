@@ -0,0 +1,178 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct DmReceiptRow {
+    pub channel_id: i64,
+    pub user_id: i64,
+    pub last_delivered_message_id: Option<i64>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub last_read_message_id: Option<i64>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for DmReceiptRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let delivered_at_raw: Option<String> = row.try_get("delivered_at")?;
+        let read_at_raw: Option<String> = row.try_get("read_at")?;
+        Ok(Self {
+            channel_id: row.try_get("channel_id")?,
+            user_id: row.try_get("user_id")?,
+            last_delivered_message_id: row.try_get("last_delivered_message_id")?,
+            delivered_at: delivered_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            last_read_message_id: row.try_get("last_read_message_id")?,
+            read_at: read_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+        })
+    }
+}
+
+/// Record that `user_id` received `message_id`. Receipts only move forward;
+/// returns false when the user already had this or a newer message.
+pub async fn mark_delivered(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+    message_id: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO dm_receipts (channel_id, user_id, last_delivered_message_id, delivered_at)
+         VALUES ($1, $2, $3, datetime('now'))
+         ON CONFLICT (channel_id, user_id) DO UPDATE SET
+             last_delivered_message_id = EXCLUDED.last_delivered_message_id,
+             delivered_at = EXCLUDED.delivered_at
+         WHERE dm_receipts.last_delivered_message_id IS NULL
+            OR dm_receipts.last_delivered_message_id < EXCLUDED.last_delivered_message_id",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(message_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record that `user_id` read up to `message_id`, which also counts as
+/// delivered. Returns false when the read receipt didn't move forward.
+pub async fn mark_read(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+    message_id: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO dm_receipts (
+             channel_id, user_id, last_delivered_message_id, delivered_at,
+             last_read_message_id, read_at
+         ) VALUES ($1, $2, $3, datetime('now'), $3, datetime('now'))
+         ON CONFLICT (channel_id, user_id) DO UPDATE SET
+             last_read_message_id = EXCLUDED.last_read_message_id,
+             read_at = EXCLUDED.read_at,
+             delivered_at = CASE
+                 WHEN dm_receipts.last_delivered_message_id IS NULL
+                   OR dm_receipts.last_delivered_message_id < EXCLUDED.last_read_message_id
+                 THEN EXCLUDED.delivered_at ELSE dm_receipts.delivered_at END,
+             last_delivered_message_id = CASE
+                 WHEN dm_receipts.last_delivered_message_id IS NULL
+                   OR dm_receipts.last_delivered_message_id < EXCLUDED.last_read_message_id
+                 THEN EXCLUDED.last_read_message_id ELSE dm_receipts.last_delivered_message_id END
+         WHERE dm_receipts.last_read_message_id IS NULL
+            OR dm_receipts.last_read_message_id < EXCLUDED.last_read_message_id",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(message_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_receipt(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<Option<DmReceiptRow>, DbError> {
+    let row = sqlx::query_as::<_, DmReceiptRow>(
+        "SELECT channel_id, user_id, last_delivered_message_id, delivered_at, last_read_message_id, read_at
+         FROM dm_receipts WHERE channel_id = $1 AND user_id = $2",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_channel_receipts(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<DmReceiptRow>, DbError> {
+    let rows = sqlx::query_as::<_, DmReceiptRow>(
+        "SELECT channel_id, user_id, last_delivered_message_id, delivered_at, last_read_message_id, read_at
+         FROM dm_receipts WHERE channel_id = $1
+         ORDER BY user_id ASC",
+    )
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_receipts_only_move_forward() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "alice", 1, "a@example.com", "hash")
+            .await
+            .unwrap();
+        crate::users::create_user(&pool, 2, "bob", 1, "b@example.com", "hash")
+            .await
+            .unwrap();
+        crate::dms::create_dm_channel(&pool, 100, 1, 2)
+            .await
+            .unwrap();
+
+        assert!(mark_delivered(&pool, 100, 2, 10).await.unwrap());
+        assert!(!mark_delivered(&pool, 100, 2, 10).await.unwrap());
+        assert!(!mark_delivered(&pool, 100, 2, 9).await.unwrap());
+        assert!(mark_delivered(&pool, 100, 2, 12).await.unwrap());
+
+        // Reading an older message leaves the delivered receipt alone.
+        assert!(mark_read(&pool, 100, 2, 11).await.unwrap());
+        let receipt = get_receipt(&pool, 100, 2).await.unwrap().unwrap();
+        assert_eq!(receipt.last_delivered_message_id, Some(12));
+        assert_eq!(receipt.last_read_message_id, Some(11));
+        assert!(receipt.read_at.is_some());
+
+        assert!(!mark_read(&pool, 100, 2, 11).await.unwrap());
+        assert!(mark_read(&pool, 100, 2, 15).await.unwrap());
+        let receipt = get_receipt(&pool, 100, 2).await.unwrap().unwrap();
+        assert_eq!(receipt.last_delivered_message_id, Some(15));
+
+        // A read receipt also creates the row when nothing was delivered yet.
+        assert!(mark_read(&pool, 100, 1, 15).await.unwrap());
+        let receipts = list_channel_receipts(&pool, 100).await.unwrap();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].last_delivered_message_id, Some(15));
+
+        assert!(crate::users::get_dm_receipts_disabled_users(&pool, &[1, 2])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
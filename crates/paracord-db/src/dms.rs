@@ -1,5 +1,12 @@
 use crate::{channels::ChannelRow, DbError, DbPool};
 
+/// The DM is a normal conversation for this recipient.
+pub const DM_REQUEST_ACCEPTED: i16 = 0;
+/// The DM came from a non-friend and sits in the recipient's request inbox.
+pub const DM_REQUEST_PENDING: i16 = 1;
+/// The recipient declined the request; new messages are not delivered to them.
+pub const DM_REQUEST_DECLINED: i16 = 2;
+
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct DmChannelWithRecipientRow {
     pub id: i64,
@@ -10,6 +17,7 @@ pub struct DmChannelWithRecipientRow {
     pub recipient_discriminator: i16,
     pub recipient_avatar_hash: Option<String>,
     pub recipient_public_key: Option<String>,
+    pub request_state: i16,
 }
 
 pub async fn find_dm_channel_between(
@@ -19,9 +27,9 @@ pub async fn find_dm_channel_between(
 ) -> Result<Option<ChannelRow>, DbError> {
     let row = sqlx::query_as::<_, ChannelRow>(
         "SELECT c.id, c.space_id, c.name, c.topic, c.channel_type, c.position, c.parent_id,
-                c.nsfw, c.rate_limit_per_user, c.bitrate, c.user_limit, c.last_message_id,
+                CASE WHEN c.nsfw THEN 1 ELSE 0 END AS nsfw, c.rate_limit_per_user, c.bitrate, c.user_limit, c.last_message_id,
                 c.required_role_ids, c.thread_metadata, c.owner_id, c.message_count,
-                c.applied_tags, c.default_sort_order, c.created_at
+                c.applied_tags, c.default_sort_order, c.posting_mode, c.archived_at, c.created_at
          FROM channels c
          INNER JOIN dm_recipients a ON a.channel_id = c.id AND a.user_id = $1
          INNER JOIN dm_recipients b ON b.channel_id = c.id AND b.user_id = $2
@@ -64,10 +72,10 @@ pub async fn create_dm_channel(
     tx.commit().await?;
 
     let row = sqlx::query_as::<_, ChannelRow>(
-        "SELECT id, space_id, name, topic, channel_type, position, parent_id, nsfw,
+        "SELECT id, space_id, name, topic, channel_type, position, parent_id, CASE WHEN nsfw THEN 1 ELSE 0 END AS nsfw,
                 rate_limit_per_user, bitrate, user_limit, last_message_id, required_role_ids,
-                thread_metadata, owner_id, message_count, applied_tags, default_sort_order,
-                created_at
+                thread_metadata, owner_id, message_count, applied_tags, default_sort_order, posting_mode,
+                archived_at, created_at
          FROM channels
          WHERE id = $1",
     )
@@ -88,7 +96,8 @@ pub async fn list_user_dm_channels(
                 u.username AS recipient_username,
                 u.discriminator AS recipient_discriminator,
                 u.avatar_hash AS recipient_avatar_hash,
-                u.public_key AS recipient_public_key
+                u.public_key AS recipient_public_key,
+                me.request_state
          FROM channels c
          INNER JOIN dm_recipients me ON me.channel_id = c.id
          INNER JOIN dm_recipients other ON other.channel_id = c.id AND other.user_id != me.user_id
@@ -126,3 +135,286 @@ pub async fn is_dm_recipient(
     .await?;
     Ok(exists.is_some())
 }
+
+pub async fn get_request_state(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<Option<i16>, DbError> {
+    let row: Option<(i16,)> = sqlx::query_as(
+        "SELECT request_state FROM dm_recipients WHERE channel_id = $1 AND user_id = $2",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(state,)| state))
+}
+
+pub async fn set_request_state(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+    request_state: i16,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE dm_recipients SET request_state = $3
+         WHERE channel_id = $1 AND user_id = $2 AND request_state != $3",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(request_state)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Recipients of a DM channel together with their request state.
+pub async fn get_dm_recipient_request_states(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<(i64, i16)>, DbError> {
+    let rows: Vec<(i64, i16)> =
+        sqlx::query_as("SELECT user_id, request_state FROM dm_recipients WHERE channel_id = $1")
+            .bind(channel_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GroupDmRow {
+    pub id: i64,
+    pub name: Option<String>,
+    pub icon_hash: Option<String>,
+    pub owner_id: Option<i64>,
+    pub last_message_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DmRecipientRow {
+    pub user_id: i64,
+    pub username: String,
+    pub discriminator: i16,
+    pub avatar_hash: Option<String>,
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SenderKeyRow {
+    pub sender_id: i64,
+    pub recipient_id: i64,
+    pub epoch: i64,
+    pub ciphertext: String,
+    pub header: Option<String>,
+}
+
+pub async fn create_group_dm_channel(
+    pool: &DbPool,
+    channel_id: i64,
+    owner_id: i64,
+    name: Option<&str>,
+    recipient_ids: &[i64],
+) -> Result<GroupDmRow, DbError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO channels (id, space_id, name, channel_type, position, owner_id)
+         VALUES ($1, NULL, $2, 3, 0, $3)",
+    )
+    .bind(channel_id)
+    .bind(name)
+    .bind(owner_id)
+    .execute(&mut *tx)
+    .await?;
+
+    for user_id in std::iter::once(&owner_id).chain(recipient_ids.iter()) {
+        sqlx::query(
+            "INSERT INTO dm_recipients (channel_id, user_id)
+             VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(channel_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    get_group_dm(pool, channel_id)
+        .await?
+        .ok_or(DbError::NotFound)
+}
+
+pub async fn get_group_dm(pool: &DbPool, channel_id: i64) -> Result<Option<GroupDmRow>, DbError> {
+    let row = sqlx::query_as::<_, GroupDmRow>(
+        "SELECT id, name, icon_hash, owner_id, last_message_id
+         FROM channels
+         WHERE id = $1 AND channel_type = 3",
+    )
+    .bind(channel_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_user_group_dms(pool: &DbPool, user_id: i64) -> Result<Vec<GroupDmRow>, DbError> {
+    let rows = sqlx::query_as::<_, GroupDmRow>(
+        "SELECT c.id, c.name, c.icon_hash, c.owner_id, c.last_message_id
+         FROM channels c
+         INNER JOIN dm_recipients me ON me.channel_id = c.id
+         WHERE c.channel_type = 3 AND me.user_id = $1
+         ORDER BY CASE WHEN c.last_message_id IS NULL THEN 1 ELSE 0 END, c.last_message_id DESC, c.id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn list_dm_recipients(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<DmRecipientRow>, DbError> {
+    let rows = sqlx::query_as::<_, DmRecipientRow>(
+        "SELECT u.id AS user_id, u.username, u.discriminator, u.avatar_hash, u.public_key
+         FROM dm_recipients r
+         INNER JOIN users u ON u.id = r.user_id
+         WHERE r.channel_id = $1
+         ORDER BY u.id",
+    )
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn add_dm_recipient(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO dm_recipients (channel_id, user_id)
+         VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove a group DM participant along with any sender keys they sent or received.
+pub async fn remove_dm_recipient(
+    pool: &DbPool,
+    channel_id: i64,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM dm_recipients WHERE channel_id = $1 AND user_id = $2")
+        .bind(channel_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "DELETE FROM group_dm_sender_keys
+         WHERE channel_id = $1 AND (sender_id = $2 OR recipient_id = $2)",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Update a group DM's name and icon. `None` keeps the current value and an
+/// empty string clears it.
+pub async fn update_group_dm(
+    pool: &DbPool,
+    channel_id: i64,
+    name: Option<&str>,
+    icon_hash: Option<&str>,
+) -> Result<GroupDmRow, DbError> {
+    let row = sqlx::query_as::<_, GroupDmRow>(
+        "UPDATE channels
+         SET name = NULLIF(COALESCE($2, name), ''),
+             icon_hash = NULLIF(COALESCE($3, icon_hash), '')
+         WHERE id = $1 AND channel_type = 3
+         RETURNING id, name, icon_hash, owner_id, last_message_id",
+    )
+    .bind(channel_id)
+    .bind(name)
+    .bind(icon_hash)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn set_group_dm_owner(
+    pool: &DbPool,
+    channel_id: i64,
+    owner_id: i64,
+) -> Result<GroupDmRow, DbError> {
+    let row = sqlx::query_as::<_, GroupDmRow>(
+        "UPDATE channels SET owner_id = $2
+         WHERE id = $1 AND channel_type = 3
+         RETURNING id, name, icon_hash, owner_id, last_message_id",
+    )
+    .bind(channel_id)
+    .bind(owner_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn upsert_sender_key(
+    pool: &DbPool,
+    channel_id: i64,
+    sender_id: i64,
+    recipient_id: i64,
+    epoch: i64,
+    ciphertext: &str,
+    header: Option<&str>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO group_dm_sender_keys (channel_id, sender_id, recipient_id, epoch, ciphertext, header)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (channel_id, sender_id, recipient_id) DO UPDATE SET
+            epoch = $4,
+            ciphertext = $5,
+            header = $6,
+            created_at = datetime('now')",
+    )
+    .bind(channel_id)
+    .bind(sender_id)
+    .bind(recipient_id)
+    .bind(epoch)
+    .bind(ciphertext)
+    .bind(header)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Sender keys addressed to `recipient_id` in a group DM.
+pub async fn list_sender_keys_for(
+    pool: &DbPool,
+    channel_id: i64,
+    recipient_id: i64,
+) -> Result<Vec<SenderKeyRow>, DbError> {
+    let rows = sqlx::query_as::<_, SenderKeyRow>(
+        "SELECT sender_id, recipient_id, epoch, ciphertext, header
+         FROM group_dm_sender_keys
+         WHERE channel_id = $1 AND recipient_id = $2
+         ORDER BY sender_id",
+    )
+    .bind(channel_id)
+    .bind(recipient_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,159 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Row;
+
+/// Where a custom emoji was used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiUsageKind {
+    Message,
+    Reaction,
+}
+
+/// Usage of one of a guild's emojis over a range of days. Emojis without
+/// any recorded use have zero counts and no `last_used_at`.
+#[derive(Debug, Clone)]
+pub struct EmojiUsageRow {
+    pub emoji_id: i64,
+    pub message_count: i64,
+    pub reaction_count: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for EmojiUsageRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let last_used_at_raw: Option<String> = row.try_get("last_used_at")?;
+        Ok(Self {
+            emoji_id: row.try_get("emoji_id")?,
+            message_count: row.try_get("message_count")?,
+            reaction_count: row.try_get("reaction_count")?,
+            last_used_at: last_used_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+        })
+    }
+}
+
+fn day_key(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+/// Count a use of `emoji_id` on the day of `at`. Emojis that don't belong
+/// to `guild_id` are ignored; returns whether the use was counted.
+pub async fn record_emoji_usage(
+    pool: &DbPool,
+    guild_id: i64,
+    emoji_id: i64,
+    kind: EmojiUsageKind,
+    at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let (message_count, reaction_count) = match kind {
+        EmojiUsageKind::Message => (1_i64, 0_i64),
+        EmojiUsageKind::Reaction => (0, 1),
+    };
+    let result = sqlx::query(
+        "INSERT INTO emoji_usage_daily (emoji_id, guild_id, day, message_count, reaction_count, last_used_at)
+         SELECT id, space_id, $3, $4, $5, $6 FROM emojis WHERE id = $1 AND space_id = $2
+         ON CONFLICT (emoji_id, day) DO UPDATE SET
+            message_count = emoji_usage_daily.message_count + excluded.message_count,
+            reaction_count = emoji_usage_daily.reaction_count + excluded.reaction_count,
+            last_used_at = excluded.last_used_at",
+    )
+    .bind(emoji_id)
+    .bind(guild_id)
+    .bind(day_key(at.date_naive()))
+    .bind(message_count)
+    .bind(reaction_count)
+    .bind(datetime_to_db_text(at))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Usage of every emoji of the guild from `since` onwards, most used first.
+/// `last_used_at` covers all recorded history, not just the range.
+pub async fn get_guild_emoji_usage(
+    pool: &DbPool,
+    guild_id: i64,
+    since: NaiveDate,
+) -> Result<Vec<EmojiUsageRow>, DbError> {
+    let rows = sqlx::query_as::<_, EmojiUsageRow>(
+        "SELECT e.id AS emoji_id,
+                COALESCE(SUM(CASE WHEN u.day >= $2 THEN u.message_count ELSE 0 END), 0) AS message_count,
+                COALESCE(SUM(CASE WHEN u.day >= $2 THEN u.reaction_count ELSE 0 END), 0) AS reaction_count,
+                MAX(u.last_used_at) AS last_used_at
+         FROM emojis e
+         LEFT JOIN emoji_usage_daily u ON u.emoji_id = e.id
+         WHERE e.space_id = $1
+         GROUP BY e.id
+         ORDER BY message_count + reaction_count DESC, e.id ASC",
+    )
+    .bind(guild_id)
+    .bind(day_key(since))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_usage_rolls_up_per_day() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 200, "Other", 1, None)
+            .await
+            .unwrap();
+        crate::emojis::create_emoji(&pool, 10, 100, "wave", 1, false)
+            .await
+            .unwrap();
+        crate::emojis::create_emoji(&pool, 11, 100, "unused", 1, false)
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let old = now - chrono::Duration::days(40);
+        for (kind, at) in [
+            (EmojiUsageKind::Message, now),
+            (EmojiUsageKind::Message, now),
+            (EmojiUsageKind::Reaction, now),
+            (EmojiUsageKind::Reaction, old),
+        ] {
+            assert!(record_emoji_usage(&pool, 100, 10, kind, at).await.unwrap());
+        }
+        // Another guild's use of the emoji is not counted.
+        assert!(
+            !record_emoji_usage(&pool, 200, 10, EmojiUsageKind::Message, now)
+                .await
+                .unwrap()
+        );
+
+        let since = (now - chrono::Duration::days(30)).date_naive();
+        let usage = get_guild_emoji_usage(&pool, 100, since).await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].emoji_id, 10);
+        assert_eq!((usage[0].message_count, usage[0].reaction_count), (2, 1));
+        assert!(usage[0].last_used_at.is_some());
+        assert_eq!(usage[1].emoji_id, 11);
+        assert_eq!((usage[1].message_count, usage[1].reaction_count), (0, 0));
+        assert!(usage[1].last_used_at.is_none());
+
+        let all_time = get_guild_emoji_usage(&pool, 100, old.date_naive())
+            .await
+            .unwrap();
+        assert_eq!(all_time[0].reaction_count, 2);
+    }
+}
@@ -37,7 +37,8 @@ pub async fn create_emoji(
     let row = sqlx::query_as::<_, EmojiRow>(
         "INSERT INTO emojis (id, space_id, name, creator_id, animated)
          VALUES ($1, $2, $3, $4, $5)
-         RETURNING id, space_id AS guild_id, name, creator_id, animated, created_at",
+         RETURNING id, space_id AS guild_id, name, creator_id,
+                CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at",
     )
     .bind(id)
     .bind(guild_id)
@@ -51,7 +52,8 @@ pub async fn create_emoji(
 
 pub async fn get_emoji(pool: &DbPool, id: i64) -> Result<Option<EmojiRow>, DbError> {
     let row = sqlx::query_as::<_, EmojiRow>(
-        "SELECT id, space_id AS guild_id, name, creator_id, animated, created_at
+        "SELECT id, space_id AS guild_id, name, creator_id,
+                CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at
          FROM emojis WHERE id = $1",
     )
     .bind(id)
@@ -62,7 +64,8 @@ pub async fn get_emoji(pool: &DbPool, id: i64) -> Result<Option<EmojiRow>, DbErr
 
 pub async fn get_guild_emojis(pool: &DbPool, guild_id: i64) -> Result<Vec<EmojiRow>, DbError> {
     let rows = sqlx::query_as::<_, EmojiRow>(
-        "SELECT id, space_id AS guild_id, name, creator_id, animated, created_at
+        "SELECT id, space_id AS guild_id, name, creator_id,
+                CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at
          FROM emojis WHERE space_id = $1 ORDER BY name",
     )
     .bind(guild_id)
@@ -71,11 +74,20 @@ pub async fn get_guild_emojis(pool: &DbPool, guild_id: i64) -> Result<Vec<EmojiR
     Ok(rows)
 }
 
+pub async fn count_guild_emojis(pool: &DbPool, guild_id: i64) -> Result<i64, DbError> {
+    let row = sqlx::query("SELECT COUNT(*) AS count FROM emojis WHERE space_id = $1")
+        .bind(guild_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get("count")?)
+}
+
 pub async fn update_emoji(pool: &DbPool, id: i64, name: &str) -> Result<EmojiRow, DbError> {
     let row = sqlx::query_as::<_, EmojiRow>(
         "UPDATE emojis SET name = $2
          WHERE id = $1
-         RETURNING id, space_id AS guild_id, name, creator_id, animated, created_at",
+         RETURNING id, space_id AS guild_id, name, creator_id,
+                CASE WHEN animated THEN 1 ELSE 0 END AS animated, created_at",
     )
     .bind(id)
     .bind(name)
@@ -55,6 +55,7 @@ pub struct OutboundFederationEventRow {
     pub depth: i64,
     pub state_key: Option<String>,
     pub signatures: Value,
+    pub signature_version: u32,
     pub attempt_count: i64,
 }
 
@@ -76,6 +77,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for OutboundFederationEventRow {
             depth: row.try_get("depth")?,
             state_key: row.try_get("state_key")?,
             signatures: json_from_db_text(&signatures_raw)?,
+            signature_version: row.try_get::<i64, _>("signature_version")? as u32,
             attempt_count: row.try_get("attempt_count")?,
         })
     }
@@ -290,17 +292,18 @@ pub async fn enqueue_outbound_event(
     depth: i64,
     state_key: Option<&str>,
     signatures: &Value,
+    signature_version: u32,
     now_ms: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         "INSERT INTO federation_outbound_queue (
              destination_server, event_id, room_id, event_type, sender, origin_server, origin_ts,
-             content, depth, state_key, signatures, attempt_count, next_attempt_at_ms, last_error,
-             created_at_ms, updated_at_ms
+             content, depth, state_key, signatures, signature_version, attempt_count,
+             next_attempt_at_ms, last_error, created_at_ms, updated_at_ms
          ) VALUES (
              $1, $2, $3, $4, $5, $6, $7,
-             $8, $9, $10, $11, 0, $12, NULL,
-             $12, $12
+             $8, $9, $10, $11, $12, 0,
+             $13, NULL, $13, $13
          )
          ON CONFLICT (destination_server, event_id) DO UPDATE SET
              next_attempt_at_ms = CASE WHEN federation_outbound_queue.next_attempt_at_ms < EXCLUDED.next_attempt_at_ms THEN federation_outbound_queue.next_attempt_at_ms ELSE EXCLUDED.next_attempt_at_ms END,
@@ -321,6 +324,7 @@ pub async fn enqueue_outbound_event(
     .bind(serde_json::to_string(signatures).map_err(|e| {
         sqlx::Error::Protocol(format!("invalid federation signatures json: {e}"))
     })?)
+    .bind(i64::from(signature_version))
     .bind(now_ms)
     .execute(pool)
     .await?;
@@ -347,6 +351,7 @@ pub async fn fetch_due_outbound_events(
              q.depth,
              q.state_key,
              q.signatures,
+             q.signature_version,
              q.attempt_count
          FROM federation_outbound_queue q
          INNER JOIN federated_servers fs
@@ -552,6 +557,24 @@ pub async fn get_local_message_id_by_event(
     Ok(row.map(|(id,)| id))
 }
 
+/// The origin server and its message id for a local copy of a federated
+/// message, if the message came from a peer.
+pub async fn get_remote_message_by_local(
+    pool: &DbPool,
+    local_message_id: i64,
+) -> Result<Option<(String, String)>, sqlx::Error> {
+    let row: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT origin_server, remote_message_id
+         FROM federation_message_map
+         WHERE local_message_id = $1
+         LIMIT 1",
+    )
+    .bind(local_message_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(|(origin, remote_id)| remote_id.map(|remote_id| (origin, remote_id))))
+}
+
 pub async fn upsert_room_membership(
     pool: &DbPool,
     room_id: &str,
@@ -830,6 +853,90 @@ pub async fn purge_expired_outbound_events(
     Ok(rows)
 }
 
+/// Bytes exchanged with one peer in one category, summed over a window.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PeerBandwidthRow {
+    pub server_name: String,
+    pub category: String,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+}
+
+/// Add to a peer's byte counters for the one-minute bucket `window_start`.
+pub async fn add_peer_bandwidth(
+    pool: &DbPool,
+    server_name: &str,
+    category: &str,
+    window_start: i64,
+    bytes_in: i64,
+    bytes_out: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO federation_peer_bandwidth (server_name, category, window_start, bytes_in, bytes_out)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (server_name, category, window_start) DO UPDATE SET
+             bytes_in = federation_peer_bandwidth.bytes_in + EXCLUDED.bytes_in,
+             bytes_out = federation_peer_bandwidth.bytes_out + EXCLUDED.bytes_out",
+    )
+    .bind(server_name)
+    .bind(category)
+    .bind(window_start)
+    .bind(bytes_in)
+    .bind(bytes_out)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Total bytes in and out for one peer since bucket `since_window`.
+pub async fn peer_bandwidth_total_since(
+    pool: &DbPool,
+    server_name: &str,
+    since_window: i64,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(bytes_in + bytes_out), 0) AS BIGINT)
+         FROM federation_peer_bandwidth
+         WHERE server_name = $1 AND window_start >= $2",
+    )
+    .bind(server_name)
+    .bind(since_window)
+    .fetch_one(pool)
+    .await
+}
+
+/// Per-peer, per-category totals since bucket `since_window`.
+pub async fn list_peer_bandwidth_since(
+    pool: &DbPool,
+    since_window: i64,
+) -> Result<Vec<PeerBandwidthRow>, sqlx::Error> {
+    sqlx::query_as::<_, PeerBandwidthRow>(
+        "SELECT server_name,
+                category,
+                CAST(SUM(bytes_in) AS BIGINT) AS bytes_in,
+                CAST(SUM(bytes_out) AS BIGINT) AS bytes_out
+         FROM federation_peer_bandwidth
+         WHERE window_start >= $1
+         GROUP BY server_name, category
+         ORDER BY server_name, category",
+    )
+    .bind(since_window)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn purge_peer_bandwidth_before(
+    pool: &DbPool,
+    window_start: i64,
+) -> Result<u64, sqlx::Error> {
+    let rows = sqlx::query("DELETE FROM federation_peer_bandwidth WHERE window_start < $1")
+        .bind(window_start)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(rows)
+}
+
 /// Store or replace the local server's ed25519 keypair (singleton row, id=1).
 pub async fn upsert_server_keypair(
     pool: &DbPool,
@@ -52,6 +52,26 @@ pub async fn get_cached_file(
     Ok(row)
 }
 
+/// Any cache row whose content hashes to `content_hash`. Rows for the same
+/// bytes share one `storage_key`.
+pub async fn get_cached_file_by_hash(
+    pool: &DbPool,
+    content_hash: &str,
+) -> Result<Option<FedFileCacheRow>, DbError> {
+    let row = sqlx::query_as::<_, FedFileCacheRow>(
+        "SELECT id, origin_server, origin_attachment_id, content_hash, filename,
+                content_type, size, storage_key, cached_at, expires_at, last_accessed_at
+         FROM federation_file_cache
+         WHERE content_hash = $1
+         ORDER BY id ASC
+         LIMIT 1",
+    )
+    .bind(content_hash)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_cached_file(
     pool: &DbPool,
@@ -123,11 +143,29 @@ pub async fn delete_cache_entry(pool: &DbPool, id: i64) -> Result<(), DbError> {
     Ok(())
 }
 
-pub async fn get_total_cache_size(pool: &DbPool) -> Result<i64, DbError> {
-    let total: Option<i64> =
-        sqlx::query_scalar("SELECT COALESCE(SUM(size), 0) FROM federation_file_cache")
+/// How many cache rows still point at `storage_key`.
+pub async fn count_entries_for_storage_key(
+    pool: &DbPool,
+    storage_key: &str,
+) -> Result<i64, DbError> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM federation_file_cache WHERE storage_key = $1")
+            .bind(storage_key)
             .fetch_one(pool)
             .await?;
+    Ok(count)
+}
+
+/// Bytes held in storage; a blob shared by several rows counts once.
+pub async fn get_total_cache_size(pool: &DbPool) -> Result<i64, DbError> {
+    let total: Option<i64> = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(size), 0) AS BIGINT)
+         FROM (SELECT storage_key, MAX(size) AS size
+               FROM federation_file_cache
+               GROUP BY storage_key) AS blobs",
+    )
+    .fetch_one(pool)
+    .await?;
     Ok(total.unwrap_or(0))
 }
 
@@ -109,6 +109,24 @@ pub async fn get_guild_storage_usage(pool: &DbPool, guild_id: i64) -> Result<i64
     Ok(total.unwrap_or(0))
 }
 
+/// `(guild_id, usage_bytes, storage_quota)` for every guild.
+pub async fn list_guild_storage_usage(
+    pool: &DbPool,
+) -> Result<Vec<(i64, i64, Option<i64>)>, DbError> {
+    let rows: Vec<(i64, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT s.id, CAST(COALESCE(SUM(a.size), 0) AS BIGINT), p.storage_quota
+         FROM spaces s
+         LEFT JOIN channels c ON c.space_id = s.id
+         LEFT JOIN attachments a ON a.upload_channel_id = c.id
+         LEFT JOIN guild_storage_policies p ON p.guild_id = s.id
+         GROUP BY s.id, p.storage_quota
+         ORDER BY s.id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn get_guild_attachments(
     pool: &DbPool,
     guild_id: i64,
@@ -119,7 +137,9 @@ pub async fn get_guild_attachments(
         sqlx::query_as::<_, crate::attachments::AttachmentRow>(
             "SELECT a.id, a.message_id, a.filename, a.content_type, a.size, a.url,
                     a.width, a.height, a.uploader_id, a.upload_channel_id,
-                    a.upload_created_at, a.upload_expires_at, a.content_hash
+                    a.upload_created_at, a.upload_expires_at, a.content_hash,
+                    a.duration_ms, a.waveform, a.transcode_status, a.transcoded_size,
+                    a.blurhash, a.thumbnail_format
              FROM attachments a
              JOIN channels c ON a.upload_channel_id = c.id
              WHERE c.space_id = $1 AND a.id < $2
@@ -135,7 +155,9 @@ pub async fn get_guild_attachments(
         sqlx::query_as::<_, crate::attachments::AttachmentRow>(
             "SELECT a.id, a.message_id, a.filename, a.content_type, a.size, a.url,
                     a.width, a.height, a.uploader_id, a.upload_channel_id,
-                    a.upload_created_at, a.upload_expires_at, a.content_hash
+                    a.upload_created_at, a.upload_expires_at, a.content_hash,
+                    a.duration_ms, a.waveform, a.transcode_status, a.transcoded_size,
+                    a.blurhash, a.thumbnail_format
              FROM attachments a
              JOIN channels c ON a.upload_channel_id = c.id
              WHERE c.space_id = $1
@@ -159,7 +181,9 @@ pub async fn get_guild_attachments_older_than(
     let rows = sqlx::query_as::<_, crate::attachments::AttachmentRow>(
         "SELECT a.id, a.message_id, a.filename, a.content_type, a.size, a.url,
                 a.width, a.height, a.uploader_id, a.upload_channel_id,
-                a.upload_created_at, a.upload_expires_at, a.content_hash
+                a.upload_created_at, a.upload_expires_at, a.content_hash,
+                a.duration_ms, a.waveform, a.transcode_status, a.transcoded_size,
+                a.blurhash, a.thumbnail_format
          FROM attachments a
          JOIN channels c ON a.upload_channel_id = c.id
          WHERE c.space_id = $1 AND a.upload_created_at <= $2
@@ -0,0 +1,189 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A guild text snippet, posted by members with `/tag <name>`.
+#[derive(Debug, Clone)]
+pub struct GuildTagRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub name: String,
+    pub content: String,
+    /// `None` once the creator's account has been deleted.
+    pub created_by: Option<i64>,
+    pub uses: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for GuildTagRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            name: row.try_get("name")?,
+            content: row.try_get("content")?,
+            created_by: row.try_get("created_by")?,
+            uses: row.try_get("uses")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+pub async fn create_tag(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    name: &str,
+    content: &str,
+    created_by: i64,
+) -> Result<GuildTagRow, DbError> {
+    let row = sqlx::query_as::<_, GuildTagRow>(
+        "INSERT INTO guild_tags (id, guild_id, name, content, created_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, guild_id, name, content, created_by, uses, created_at, updated_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(name)
+    .bind(content)
+    .bind(created_by)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_tag_by_name(
+    pool: &DbPool,
+    guild_id: i64,
+    name: &str,
+) -> Result<Option<GuildTagRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildTagRow>(
+        "SELECT id, guild_id, name, content, created_by, uses, created_at, updated_at
+         FROM guild_tags WHERE guild_id = $1 AND name = $2",
+    )
+    .bind(guild_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Tags of a guild, alphabetical.
+pub async fn list_guild_tags(pool: &DbPool, guild_id: i64) -> Result<Vec<GuildTagRow>, DbError> {
+    let rows = sqlx::query_as::<_, GuildTagRow>(
+        "SELECT id, guild_id, name, content, created_by, uses, created_at, updated_at
+         FROM guild_tags WHERE guild_id = $1
+         ORDER BY name ASC",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn count_guild_tags(pool: &DbPool, guild_id: i64) -> Result<i64, DbError> {
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM guild_tags WHERE guild_id = $1")
+        .bind(guild_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(row.0)
+}
+
+pub async fn update_tag(
+    pool: &DbPool,
+    id: i64,
+    name: Option<&str>,
+    content: Option<&str>,
+) -> Result<Option<GuildTagRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildTagRow>(
+        "UPDATE guild_tags
+         SET name = COALESCE($2, name),
+             content = COALESCE($3, content),
+             updated_at = datetime('now')
+         WHERE id = $1
+         RETURNING id, guild_id, name, content, created_by, uses, created_at, updated_at",
+    )
+    .bind(id)
+    .bind(name)
+    .bind(content)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn increment_tag_uses(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("UPDATE guild_tags SET uses = uses + 1 WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_tag(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM guild_tags WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_tag_crud_and_uses() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Test Guild", 1, None)
+            .await
+            .unwrap();
+
+        create_tag(&pool, 10, 100, "rules", "Be nice.", 1)
+            .await
+            .unwrap();
+        create_tag(&pool, 11, 100, "faq", "Read the docs.", 1)
+            .await
+            .unwrap();
+        assert!(create_tag(&pool, 12, 100, "faq", "Duplicate", 1)
+            .await
+            .is_err());
+
+        let names: Vec<String> = list_guild_tags(&pool, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(names, vec!["faq", "rules"]);
+        assert_eq!(count_guild_tags(&pool, 100).await.unwrap(), 2);
+
+        increment_tag_uses(&pool, 10).await.unwrap();
+        increment_tag_uses(&pool, 10).await.unwrap();
+        let updated = update_tag(&pool, 10, None, Some("Be kind."))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.name, "rules");
+        assert_eq!(updated.content, "Be kind.");
+        assert_eq!(updated.uses, 2);
+
+        delete_tag(&pool, 10).await.unwrap();
+        assert!(get_tag_by_name(&pool, 100, "rules")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
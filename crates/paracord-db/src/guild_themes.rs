@@ -0,0 +1,151 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// Per-guild look: an accent color and custom CSS applied on top of each
+/// member's own theme.
+#[derive(Debug, Clone)]
+pub struct GuildThemeRow {
+    pub guild_id: i64,
+    pub accent_color: Option<String>,
+    pub custom_css: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An image uploaded for use by a guild's custom CSS.
+#[derive(Debug, Clone)]
+pub struct GuildThemeAssetRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub name: String,
+    pub content_type: String,
+    pub size: i64,
+    /// `None` once the uploader's account has been deleted.
+    pub uploader_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for GuildThemeRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            accent_color: row.try_get("accent_color")?,
+            custom_css: row.try_get("custom_css")?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for GuildThemeAssetRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            name: row.try_get("name")?,
+            content_type: row.try_get("content_type")?,
+            size: row.try_get("size")?,
+            uploader_id: row.try_get("uploader_id")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+pub async fn get_theme(pool: &DbPool, guild_id: i64) -> Result<Option<GuildThemeRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildThemeRow>(
+        "SELECT guild_id, accent_color, custom_css, updated_at
+         FROM guild_themes WHERE guild_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Replace a guild's theme. `None` clears a field.
+pub async fn upsert_theme(
+    pool: &DbPool,
+    guild_id: i64,
+    accent_color: Option<&str>,
+    custom_css: Option<&str>,
+) -> Result<GuildThemeRow, DbError> {
+    let row = sqlx::query_as::<_, GuildThemeRow>(
+        "INSERT INTO guild_themes (guild_id, accent_color, custom_css)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (guild_id) DO UPDATE SET
+            accent_color = $2,
+            custom_css = $3,
+            updated_at = datetime('now')
+         RETURNING guild_id, accent_color, custom_css, updated_at",
+    )
+    .bind(guild_id)
+    .bind(accent_color)
+    .bind(custom_css)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn create_asset(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    name: &str,
+    content_type: &str,
+    size: i64,
+    uploader_id: i64,
+) -> Result<GuildThemeAssetRow, DbError> {
+    let row = sqlx::query_as::<_, GuildThemeAssetRow>(
+        "INSERT INTO guild_theme_assets (id, guild_id, name, content_type, size, uploader_id)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, guild_id, name, content_type, size, uploader_id, created_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(name)
+    .bind(content_type)
+    .bind(size)
+    .bind(uploader_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_asset(pool: &DbPool, id: i64) -> Result<Option<GuildThemeAssetRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildThemeAssetRow>(
+        "SELECT id, guild_id, name, content_type, size, uploader_id, created_at
+         FROM guild_theme_assets WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_assets(pool: &DbPool, guild_id: i64) -> Result<Vec<GuildThemeAssetRow>, DbError> {
+    let rows = sqlx::query_as::<_, GuildThemeAssetRow>(
+        "SELECT id, guild_id, name, content_type, size, uploader_id, created_at
+         FROM guild_theme_assets WHERE guild_id = $1 ORDER BY name",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn count_assets(pool: &DbPool, guild_id: i64) -> Result<i64, DbError> {
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM guild_theme_assets WHERE guild_id = $1")
+        .bind(guild_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(row.0)
+}
+
+pub async fn delete_asset(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM guild_theme_assets WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
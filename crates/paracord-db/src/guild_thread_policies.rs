@@ -0,0 +1,96 @@
+use crate::{bool_from_any_row, DbError, DbPool};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct GuildThreadPolicyRow {
+    pub guild_id: i64,
+    /// Most non-archived threads the guild may have; `None` is unlimited.
+    pub max_active_threads: Option<i32>,
+    /// `auto_archive_duration` for new threads that don't pick one.
+    pub default_auto_archive_duration: Option<i32>,
+    /// Whether posting in an archived, unlocked thread un-archives it.
+    pub unarchive_on_message: bool,
+    pub updated_at: String,
+}
+
+impl Default for GuildThreadPolicyRow {
+    fn default() -> Self {
+        Self {
+            guild_id: 0,
+            max_active_threads: None,
+            default_auto_archive_duration: None,
+            unarchive_on_message: true,
+            updated_at: String::new(),
+        }
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for GuildThreadPolicyRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            max_active_threads: row.try_get("max_active_threads")?,
+            default_auto_archive_duration: row.try_get("default_auto_archive_duration")?,
+            unarchive_on_message: bool_from_any_row(row, "unarchive_on_message")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+pub async fn get_guild_thread_policy(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Option<GuildThreadPolicyRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildThreadPolicyRow>(
+        "SELECT guild_id, max_active_threads, default_auto_archive_duration,
+                CASE WHEN unarchive_on_message THEN 1 ELSE 0 END AS unarchive_on_message,
+                updated_at
+         FROM guild_thread_policies WHERE guild_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// The guild's policy, or the defaults when it never set one.
+pub async fn get_effective_guild_thread_policy(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<GuildThreadPolicyRow, DbError> {
+    Ok(get_guild_thread_policy(pool, guild_id)
+        .await?
+        .unwrap_or(GuildThreadPolicyRow {
+            guild_id,
+            ..GuildThreadPolicyRow::default()
+        }))
+}
+
+pub async fn upsert_guild_thread_policy(
+    pool: &DbPool,
+    guild_id: i64,
+    max_active_threads: Option<i32>,
+    default_auto_archive_duration: Option<i32>,
+    unarchive_on_message: bool,
+) -> Result<GuildThreadPolicyRow, DbError> {
+    let row = sqlx::query_as::<_, GuildThreadPolicyRow>(
+        "INSERT INTO guild_thread_policies
+            (guild_id, max_active_threads, default_auto_archive_duration, unarchive_on_message, updated_at)
+         VALUES ($1, $2, $3, $4, datetime('now'))
+         ON CONFLICT(guild_id) DO UPDATE SET
+            max_active_threads = excluded.max_active_threads,
+            default_auto_archive_duration = excluded.default_auto_archive_duration,
+            unarchive_on_message = excluded.unarchive_on_message,
+            updated_at = datetime('now')
+         RETURNING guild_id, max_active_threads, default_auto_archive_duration,
+                   CASE WHEN unarchive_on_message THEN 1 ELSE 0 END AS unarchive_on_message,
+                   updated_at",
+    )
+    .bind(guild_id)
+    .bind(max_active_threads)
+    .bind(default_auto_archive_duration)
+    .bind(unarchive_on_message)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
@@ -0,0 +1,257 @@
+use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+pub const JOIN_REQUEST_PENDING: &str = "pending";
+pub const JOIN_REQUEST_DENIED: &str = "denied";
+
+#[derive(Debug, Clone)]
+pub struct VerificationHookRow {
+    pub guild_id: i64,
+    pub url: String,
+    /// HMAC-SHA256 key for request and callback signatures.
+    pub secret: String,
+    pub timeout_ms: i32,
+    /// Admit joins when the hook can't be reached instead of holding them.
+    pub fail_open: bool,
+    pub enabled: bool,
+    pub updated_at: String,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for VerificationHookRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            url: row.try_get("url")?,
+            secret: row.try_get("secret")?,
+            timeout_ms: row.try_get("timeout_ms")?,
+            fail_open: bool_from_any_row(row, "fail_open")?,
+            enabled: bool_from_any_row(row, "enabled")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinRequestRow {
+    pub guild_id: i64,
+    pub user_id: i64,
+    /// `pending` or `denied`.
+    pub status: String,
+    pub reason: Option<String>,
+    pub invite_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for JoinRequestRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            user_id: row.try_get("user_id")?,
+            status: row.try_get("status")?,
+            reason: row.try_get("reason")?,
+            invite_code: row.try_get("invite_code")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+pub async fn get_verification_hook(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Option<VerificationHookRow>, DbError> {
+    let row = sqlx::query_as::<_, VerificationHookRow>(
+        "SELECT guild_id, url, secret, timeout_ms,
+                CASE WHEN fail_open THEN 1 ELSE 0 END AS fail_open,
+                CASE WHEN enabled THEN 1 ELSE 0 END AS enabled,
+                updated_at
+         FROM guild_verification_hooks WHERE guild_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn upsert_verification_hook(
+    pool: &DbPool,
+    guild_id: i64,
+    url: &str,
+    secret: &str,
+    timeout_ms: i32,
+    fail_open: bool,
+    enabled: bool,
+) -> Result<VerificationHookRow, DbError> {
+    let row = sqlx::query_as::<_, VerificationHookRow>(
+        "INSERT INTO guild_verification_hooks
+            (guild_id, url, secret, timeout_ms, fail_open, enabled, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, datetime('now'))
+         ON CONFLICT(guild_id) DO UPDATE SET
+            url = excluded.url,
+            secret = excluded.secret,
+            timeout_ms = excluded.timeout_ms,
+            fail_open = excluded.fail_open,
+            enabled = excluded.enabled,
+            updated_at = excluded.updated_at
+         RETURNING guild_id, url, secret, timeout_ms,
+                   CASE WHEN fail_open THEN 1 ELSE 0 END AS fail_open,
+                   CASE WHEN enabled THEN 1 ELSE 0 END AS enabled,
+                   updated_at",
+    )
+    .bind(guild_id)
+    .bind(url)
+    .bind(secret)
+    .bind(timeout_ms)
+    .bind(fail_open)
+    .bind(enabled)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn delete_verification_hook(pool: &DbPool, guild_id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM guild_verification_hooks WHERE guild_id = $1")
+        .bind(guild_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the outcome of a held or refused join, replacing any earlier one.
+pub async fn upsert_join_request(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+    status: &str,
+    reason: Option<&str>,
+    invite_code: Option<&str>,
+) -> Result<JoinRequestRow, DbError> {
+    let row = sqlx::query_as::<_, JoinRequestRow>(
+        "INSERT INTO guild_join_requests (guild_id, user_id, status, reason, invite_code)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT(guild_id, user_id) DO UPDATE SET
+            status = excluded.status,
+            reason = excluded.reason,
+            invite_code = COALESCE(excluded.invite_code, guild_join_requests.invite_code),
+            updated_at = datetime('now')
+         RETURNING guild_id, user_id, status, reason, invite_code, created_at, updated_at",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .bind(status)
+    .bind(reason)
+    .bind(invite_code)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_join_request(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<Option<JoinRequestRow>, DbError> {
+    let row = sqlx::query_as::<_, JoinRequestRow>(
+        "SELECT guild_id, user_id, status, reason, invite_code, created_at, updated_at
+         FROM guild_join_requests WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Joins waiting for a decision, oldest first.
+pub async fn list_pending_join_requests(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Vec<JoinRequestRow>, DbError> {
+    let rows = sqlx::query_as::<_, JoinRequestRow>(
+        "SELECT guild_id, user_id, status, reason, invite_code, created_at, updated_at
+         FROM guild_join_requests
+         WHERE guild_id = $1 AND status = $2
+         ORDER BY created_at ASC, user_id ASC",
+    )
+    .bind(guild_id)
+    .bind(JOIN_REQUEST_PENDING)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete_join_request(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<bool, DbError> {
+    let result =
+        sqlx::query("DELETE FROM guild_join_requests WHERE guild_id = $1 AND user_id = $2")
+            .bind(guild_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_hook_and_join_requests_round_trip() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::users::create_user(&pool, 2, "joiner", 1, "j@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+
+        let hook =
+            upsert_verification_hook(&pool, 100, "https://a.example", "s1", 5000, false, true)
+                .await
+                .unwrap();
+        assert!(hook.enabled && !hook.fail_open);
+        let hook =
+            upsert_verification_hook(&pool, 100, "https://b.example", "s2", 2000, true, false)
+                .await
+                .unwrap();
+        assert_eq!(hook.url, "https://b.example");
+        assert!(hook.fail_open && !hook.enabled);
+
+        upsert_join_request(&pool, 100, 2, JOIN_REQUEST_PENDING, None, Some("abc"))
+            .await
+            .unwrap();
+        let pending = list_pending_join_requests(&pool, 100).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].invite_code.as_deref(), Some("abc"));
+
+        let denied = upsert_join_request(&pool, 100, 2, JOIN_REQUEST_DENIED, Some("no"), None)
+            .await
+            .unwrap();
+        assert_eq!(denied.status, JOIN_REQUEST_DENIED);
+        assert_eq!(denied.invite_code.as_deref(), Some("abc"));
+        assert!(list_pending_join_requests(&pool, 100)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(delete_join_request(&pool, 100, 2).await.unwrap());
+        assert!(delete_verification_hook(&pool, 100).await.unwrap());
+        assert!(get_verification_hook(&pool, 100).await.unwrap().is_none());
+    }
+}
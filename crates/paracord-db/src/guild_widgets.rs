@@ -0,0 +1,66 @@
+use crate::{bool_from_any_row, DbError, DbPool};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct WidgetSettingsRow {
+    pub guild_id: i64,
+    pub enabled: bool,
+    pub channel_id: Option<i64>,
+    pub invite_code: Option<String>,
+    pub updated_at: String,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for WidgetSettingsRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            enabled: bool_from_any_row(row, "enabled")?,
+            channel_id: row.try_get("channel_id")?,
+            invite_code: row.try_get("invite_code")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+pub async fn get_settings(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Option<WidgetSettingsRow>, DbError> {
+    let row = sqlx::query_as::<_, WidgetSettingsRow>(
+        "SELECT guild_id, CASE WHEN enabled THEN 1 ELSE 0 END AS enabled,
+                channel_id, invite_code, updated_at
+         FROM guild_widget_settings WHERE guild_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn upsert_settings(
+    pool: &DbPool,
+    guild_id: i64,
+    enabled: bool,
+    channel_id: Option<i64>,
+    invite_code: Option<&str>,
+) -> Result<WidgetSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, WidgetSettingsRow>(
+        "INSERT INTO guild_widget_settings
+            (guild_id, enabled, channel_id, invite_code, updated_at)
+         VALUES ($1, $2, $3, $4, datetime('now'))
+         ON CONFLICT(guild_id) DO UPDATE SET
+            enabled = excluded.enabled,
+            channel_id = excluded.channel_id,
+            invite_code = excluded.invite_code,
+            updated_at = datetime('now')
+         RETURNING guild_id, CASE WHEN enabled THEN 1 ELSE 0 END AS enabled,
+                   channel_id, invite_code, updated_at",
+    )
+    .bind(guild_id)
+    .bind(enabled)
+    .bind(channel_id)
+    .bind(invite_code)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
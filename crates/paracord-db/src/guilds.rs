@@ -1,4 +1,4 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::{datetime_from_db_text, DbError, DbExecutor, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 use std::collections::HashSet;
@@ -46,8 +46,8 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for SpaceRow {
 // Backward compat alias
 pub type GuildRow = SpaceRow;
 
-pub async fn create_space(
-    pool: &DbPool,
+pub async fn create_space<'e>(
+    executor: impl DbExecutor<'e>,
     id: i64,
     name: &str,
     owner_id: i64,
@@ -62,25 +62,25 @@ pub async fn create_space(
     .bind(name)
     .bind(owner_id)
     .bind(icon_hash)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
     Ok(row)
 }
 
-pub async fn create_guild(
-    pool: &DbPool,
+pub async fn create_guild<'e>(
+    executor: impl DbExecutor<'e>,
     id: i64,
     name: &str,
     owner_id: i64,
     icon_hash: Option<&str>,
 ) -> Result<SpaceRow, DbError> {
-    create_space(pool, id, name, owner_id, icon_hash).await
+    create_space(executor, id, name, owner_id, icon_hash).await
 }
 
 pub async fn get_space(pool: &DbPool, id: i64) -> Result<Option<SpaceRow>, DbError> {
     let row = sqlx::query_as::<_, SpaceRow>(
         "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
-         FROM spaces WHERE id = $1"
+         FROM spaces WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -182,6 +182,7 @@ pub async fn list_all_spaces(pool: &DbPool) -> Result<Vec<SpaceRow>, DbError> {
     let rows = sqlx::query_as::<_, SpaceRow>(
         "SELECT id, name, description, icon_hash, banner_hash, owner_id, features, system_channel_id, vanity_url_code, visibility, allowed_roles, created_at, hub_settings, bot_settings
          FROM spaces
+         WHERE deleted_at IS NULL
          ORDER BY created_at ASC"
     )
     .fetch_all(pool)
@@ -195,7 +196,7 @@ pub async fn get_user_guilds(pool: &DbPool, user_id: i64) -> Result<Vec<SpaceRow
                 s.system_channel_id, s.vanity_url_code, s.visibility, s.allowed_roles, s.created_at, s.hub_settings, s.bot_settings
          FROM spaces s
          INNER JOIN members m ON m.guild_id = s.id
-         WHERE m.user_id = $1
+         WHERE m.user_id = $1 AND s.deleted_at IS NULL
          ORDER BY s.created_at ASC",
     )
     .bind(user_id)
@@ -220,7 +221,8 @@ pub async fn get_user_guilds(pool: &DbPool, user_id: i64) -> Result<Vec<SpaceRow
              FROM member_roles mr
              INNER JOIN roles r ON r.id = mr.role_id
              WHERE mr.user_id = $1
-               AND r.space_id = $2",
+               AND r.space_id = $2
+               AND r.deleted_at IS NULL",
         )
         .bind(user_id)
         .bind(row.id)
@@ -248,7 +250,7 @@ pub async fn list_all_guilds(pool: &DbPool) -> Result<Vec<SpaceRow>, DbError> {
 }
 
 pub async fn count_spaces(pool: &DbPool) -> Result<i64, DbError> {
-    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM spaces")
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM spaces WHERE deleted_at IS NULL")
         .fetch_one(pool)
         .await?;
     Ok(row.0)
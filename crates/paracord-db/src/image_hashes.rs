@@ -0,0 +1,79 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A perceptual image hash an admin added to the upload blocklist, stored
+/// as 16 lowercase hex digits.
+#[derive(Debug, Clone)]
+pub struct ImageHashRow {
+    pub hash: String,
+    pub reason: Option<String>,
+    /// `None` once the admin's account has been deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ImageHashRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            hash: row.try_get("hash")?,
+            reason: row.try_get("reason")?,
+            created_by: row.try_get("created_by")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+pub async fn list_entries(pool: &DbPool) -> Result<Vec<ImageHashRow>, DbError> {
+    let rows = sqlx::query_as::<_, ImageHashRow>(
+        "SELECT hash, reason, created_by, created_at
+         FROM image_hash_blocklist ORDER BY hash",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn list_hashes(pool: &DbPool) -> Result<Vec<String>, DbError> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT hash FROM image_hash_blocklist")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(hash,)| hash).collect())
+}
+
+/// Add or re-annotate a batch of hashes in one transaction. Returns how
+/// many rows were written.
+pub async fn upsert_entries(
+    pool: &DbPool,
+    hashes: &[String],
+    reason: Option<&str>,
+    created_by: i64,
+) -> Result<u64, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut written = 0;
+    for hash in hashes {
+        written += sqlx::query(
+            "INSERT INTO image_hash_blocklist (hash, reason, created_by)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (hash) DO UPDATE SET reason = $2",
+        )
+        .bind(hash)
+        .bind(reason)
+        .bind(created_by)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(written)
+}
+
+/// Returns whether an entry was removed.
+pub async fn delete_entry(pool: &DbPool, hash: &str) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM image_hash_blocklist WHERE hash = $1")
+        .bind(hash)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
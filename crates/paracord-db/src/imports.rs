@@ -0,0 +1,98 @@
+use crate::{DbError, DbExecutor, DbPool};
+
+/// Kinds of object a guild import maps from source ids to local ids.
+pub const KIND_USER: &str = "user";
+pub const KIND_CHANNEL: &str = "channel";
+pub const KIND_EMOJI: &str = "emoji";
+pub const KIND_MESSAGE: &str = "message";
+
+pub async fn get_import_mapping(
+    pool: &DbPool,
+    guild_id: i64,
+    kind: &str,
+    source_id: &str,
+) -> Result<Option<i64>, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT local_id FROM import_mappings
+         WHERE guild_id = $1 AND kind = $2 AND source_id = $3",
+    )
+    .bind(guild_id)
+    .bind(kind)
+    .bind(source_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(id,)| id))
+}
+
+/// Record that `source_id` was imported as `local_id`. The first mapping
+/// wins if two workers race.
+pub async fn set_import_mapping<'e>(
+    executor: impl DbExecutor<'e>,
+    guild_id: i64,
+    kind: &str,
+    source_id: &str,
+    local_id: i64,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO import_mappings (guild_id, kind, source_id, local_id)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (guild_id, kind, source_id) DO NOTHING",
+    )
+    .bind(guild_id)
+    .bind(kind)
+    .bind(source_id)
+    .bind(local_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_mappings_are_per_guild_and_kind() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "One", 1, None)
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 200, "Two", 1, None)
+            .await
+            .unwrap();
+
+        set_import_mapping(&pool, 100, KIND_USER, "42", 5000)
+            .await
+            .unwrap();
+        set_import_mapping(&pool, 100, KIND_USER, "42", 6000)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_import_mapping(&pool, 100, KIND_USER, "42")
+                .await
+                .unwrap(),
+            Some(5000)
+        );
+        assert_eq!(
+            get_import_mapping(&pool, 100, KIND_CHANNEL, "42")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            get_import_mapping(&pool, 200, KIND_USER, "42")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
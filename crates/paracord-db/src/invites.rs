@@ -105,6 +105,7 @@ pub async fn get_guild_invites(pool: &DbPool, guild_id: i64) -> Result<Vec<Invit
          FROM invites i
          INNER JOIN channels c ON c.id = i.channel_id
          WHERE c.space_id = $1
+           AND c.deleted_at IS NULL
            AND (i.max_age IS NULL OR i.max_age = 0 OR datetime(i.created_at, '+' || i.max_age || ' seconds') > datetime('now'))
          ORDER BY i.created_at DESC",
     )
@@ -0,0 +1,503 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+pub const JOB_STATUS_PENDING: &str = "pending";
+pub const JOB_STATUS_RUNNING: &str = "running";
+pub const JOB_STATUS_COMPLETE: &str = "complete";
+pub const JOB_STATUS_FAILED: &str = "failed";
+
+const SELECT_COLS: &str = "id, kind, payload, priority, status, attempts, max_attempts, run_at, \
+     locked_by, locked_at, last_error, progress, created_at, updated_at, completed_at, \
+     repeat_seconds";
+
+#[derive(Debug, Clone)]
+pub struct JobRow {
+    pub id: i64,
+    pub kind: String,
+    /// JSON document handed to the job's handler.
+    pub payload: String,
+    pub priority: i32,
+    pub status: String,
+    /// Attempts started so far, including the current one while running.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub locked_by: Option<String>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// JSON progress report written by the handler, if it reports any.
+    pub progress: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set for recurring jobs: seconds from the end of one run to the next.
+    pub repeat_seconds: Option<i64>,
+}
+
+impl JobRow {
+    /// Whether a failure of the running attempt is final.
+    pub fn is_last_attempt(&self) -> bool {
+        self.attempts >= self.max_attempts
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for JobRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let run_at_raw: String = row.try_get("run_at")?;
+        let locked_at_raw: Option<String> = row.try_get("locked_at")?;
+        let created_at_raw: String = row.try_get("created_at")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        let completed_at_raw: Option<String> = row.try_get("completed_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            kind: row.try_get("kind")?,
+            payload: row.try_get("payload")?,
+            priority: row.try_get("priority")?,
+            status: row.try_get("status")?,
+            attempts: row.try_get("attempts")?,
+            max_attempts: row.try_get("max_attempts")?,
+            run_at: datetime_from_db_text(&run_at_raw)?,
+            locked_by: row.try_get("locked_by")?,
+            locked_at: locked_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            last_error: row.try_get("last_error")?,
+            progress: row.try_get("progress")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+            completed_at: completed_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            repeat_seconds: row.try_get("repeat_seconds")?,
+        })
+    }
+}
+
+pub async fn enqueue_job(
+    pool: &DbPool,
+    id: i64,
+    kind: &str,
+    payload: &str,
+    priority: i32,
+    max_attempts: i32,
+    run_at: DateTime<Utc>,
+) -> Result<JobRow, DbError> {
+    let sql = format!(
+        "INSERT INTO background_jobs (id, kind, payload, priority, max_attempts, run_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {SELECT_COLS}"
+    );
+    let row = sqlx::query_as::<_, JobRow>(&sql)
+        .bind(id)
+        .bind(kind)
+        .bind(payload)
+        .bind(priority)
+        .bind(max_attempts)
+        .bind(datetime_to_db_text(run_at))
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Make sure the recurring job `kind` exists and repeats every
+/// `repeat_seconds`, first running at `run_at` if it is new. A row left
+/// `failed` (e.g. while no handler was registered) is queued again.
+pub async fn schedule_recurring_job(
+    pool: &DbPool,
+    id: i64,
+    kind: &str,
+    repeat_seconds: i64,
+    run_at: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO background_jobs (id, kind, payload, max_attempts, run_at, repeat_seconds)
+         VALUES ($1, $2, '{}', 1, $3, $4)
+         ON CONFLICT (kind) WHERE repeat_seconds IS NOT NULL DO UPDATE SET
+            repeat_seconds = excluded.repeat_seconds,
+            run_at = CASE WHEN background_jobs.status = 'failed'
+                THEN excluded.run_at ELSE background_jobs.run_at END,
+            status = CASE WHEN background_jobs.status = 'failed'
+                THEN 'pending' ELSE background_jobs.status END",
+    )
+    .bind(id)
+    .bind(kind)
+    .bind(datetime_to_db_text(run_at))
+    .bind(repeat_seconds)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_job(pool: &DbPool, id: i64) -> Result<Option<JobRow>, DbError> {
+    let sql = format!("SELECT {SELECT_COLS} FROM background_jobs WHERE id = $1");
+    let row = sqlx::query_as::<_, JobRow>(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Claim the next ready job for `worker_id`: highest priority first, then
+/// oldest `run_at`. Returns `None` when nothing is ready or another worker
+/// claimed the same row first.
+pub async fn claim_next_job(
+    pool: &DbPool,
+    worker_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<JobRow>, DbError> {
+    let sql = format!(
+        "UPDATE background_jobs
+         SET status = 'running', attempts = attempts + 1, locked_by = $2,
+             locked_at = $1, updated_at = $1
+         WHERE id = (
+             SELECT id FROM background_jobs
+             WHERE status = 'pending' AND run_at <= $1
+             ORDER BY priority DESC, run_at ASC, id ASC
+             LIMIT 1
+         ) AND status = 'pending'
+         RETURNING {SELECT_COLS}"
+    );
+    let row = sqlx::query_as::<_, JobRow>(&sql)
+        .bind(datetime_to_db_text(now))
+        .bind(worker_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn complete_job(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE background_jobs
+         SET status = 'complete', locked_by = NULL, locked_at = NULL,
+             updated_at = datetime('now'), completed_at = datetime('now')
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the progress of a running job.
+pub async fn set_job_progress(pool: &DbPool, id: i64, progress: &str) -> Result<(), DbError> {
+    sqlx::query(
+        "UPDATE background_jobs SET progress = $2, updated_at = datetime('now')
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(progress)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Put a failed attempt back in the queue to run again at `run_at`.
+pub async fn retry_job_later(
+    pool: &DbPool,
+    id: i64,
+    error: &str,
+    run_at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE background_jobs
+         SET status = 'pending', run_at = $2, last_error = $3, locked_by = NULL,
+             locked_at = NULL, updated_at = datetime('now')
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(datetime_to_db_text(run_at))
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Finish a run of a recurring job and queue the next one at `run_at`.
+/// `error` is recorded when the run failed.
+pub async fn reschedule_recurring_job(
+    pool: &DbPool,
+    id: i64,
+    error: Option<&str>,
+    run_at: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE background_jobs
+         SET status = 'pending', attempts = 0, run_at = $2, last_error = $3,
+             locked_by = NULL, locked_at = NULL, updated_at = datetime('now'),
+             completed_at = datetime('now')
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(datetime_to_db_text(run_at))
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fail_job(pool: &DbPool, id: i64, error: &str) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE background_jobs
+         SET status = 'failed', last_error = $2, locked_by = NULL, locked_at = NULL,
+             updated_at = datetime('now'), completed_at = datetime('now')
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Return jobs whose worker has held them since before `lease_cutoff` to the
+/// queue. The interrupted attempt still counts towards `max_attempts`.
+pub async fn requeue_stale_jobs(
+    pool: &DbPool,
+    lease_cutoff: DateTime<Utc>,
+) -> Result<u64, DbError> {
+    let result = sqlx::query(
+        "UPDATE background_jobs
+         SET status = 'pending', locked_by = NULL, locked_at = NULL,
+             last_error = 'worker lease expired', updated_at = datetime('now')
+         WHERE status = 'running' AND locked_at < $1",
+    )
+    .bind(datetime_to_db_text(lease_cutoff))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Queue a failed job again from scratch.
+pub async fn reset_failed_job(
+    pool: &DbPool,
+    id: i64,
+    run_at: DateTime<Utc>,
+) -> Result<Option<JobRow>, DbError> {
+    let sql = format!(
+        "UPDATE background_jobs
+         SET status = 'pending', attempts = 0, run_at = $2, completed_at = NULL,
+             progress = NULL, updated_at = datetime('now')
+         WHERE id = $1 AND status = 'failed'
+         RETURNING {SELECT_COLS}"
+    );
+    let row = sqlx::query_as::<_, JobRow>(&sql)
+        .bind(id)
+        .bind(datetime_to_db_text(run_at))
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Delete a job that is not running.
+pub async fn cancel_job(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM background_jobs WHERE id = $1 AND status <> 'running'")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Jobs newest first, optionally filtered by status and kind.
+pub async fn list_jobs(
+    pool: &DbPool,
+    status: Option<&str>,
+    kind: Option<&str>,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<JobRow>, DbError> {
+    let mut conditions = Vec::new();
+    let mut param = 0;
+    if status.is_some() {
+        param += 1;
+        conditions.push(format!("status = ${param}"));
+    }
+    if kind.is_some() {
+        param += 1;
+        conditions.push(format!("kind = ${param}"));
+    }
+    if before.is_some() {
+        param += 1;
+        conditions.push(format!("id < ${param}"));
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let sql = format!(
+        "SELECT {SELECT_COLS} FROM background_jobs {where_clause}
+         ORDER BY id DESC
+         LIMIT ${}",
+        param + 1
+    );
+
+    let mut query = sqlx::query_as::<_, JobRow>(&sql);
+    if let Some(status) = status {
+        query = query.bind(status);
+    }
+    if let Some(kind) = kind {
+        query = query.bind(kind);
+    }
+    if let Some(before) = before {
+        query = query.bind(before);
+    }
+    let rows = query.bind(limit).fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// `(status, count)` for every status with at least one job.
+pub async fn count_jobs_by_status(pool: &DbPool) -> Result<Vec<(String, i64)>, DbError> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT status, COUNT(*) FROM background_jobs GROUP BY status ORDER BY status",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Drop completed jobs finished before `cutoff`. Failed jobs are kept until
+/// an admin retries or cancels them.
+pub async fn prune_completed_jobs(pool: &DbPool, cutoff: DateTime<Utc>) -> Result<u64, DbError> {
+    let result =
+        sqlx::query("DELETE FROM background_jobs WHERE status = 'complete' AND completed_at < $1")
+            .bind(datetime_to_db_text(cutoff))
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_claim_order_and_retry_lifecycle() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        enqueue_job(&pool, 1, "low", "{}", 0, 2, now).await.unwrap();
+        enqueue_job(&pool, 2, "high", "{}", 10, 2, now)
+            .await
+            .unwrap();
+        enqueue_job(&pool, 3, "later", "{}", 100, 2, now + Duration::hours(1))
+            .await
+            .unwrap();
+
+        let job = claim_next_job(&pool, "w1", now).await.unwrap().unwrap();
+        assert_eq!(job.id, 2);
+        assert_eq!(job.status, JOB_STATUS_RUNNING);
+        assert_eq!(job.attempts, 1);
+        assert!(!job.is_last_attempt());
+        assert!(complete_job(&pool, 2).await.unwrap());
+
+        let job = claim_next_job(&pool, "w1", now).await.unwrap().unwrap();
+        assert_eq!(job.id, 1);
+        assert!(retry_job_later(&pool, 1, "boom", now).await.unwrap());
+        let job = claim_next_job(&pool, "w1", now).await.unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert!(job.is_last_attempt());
+        assert!(fail_job(&pool, 1, "boom again").await.unwrap());
+        assert!(claim_next_job(&pool, "w1", now).await.unwrap().is_none());
+
+        let counts = count_jobs_by_status(&pool).await.unwrap();
+        assert!(counts.contains(&(JOB_STATUS_FAILED.to_string(), 1)));
+        let failed = list_jobs(&pool, Some(JOB_STATUS_FAILED), None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].last_error.as_deref(), Some("boom again"));
+
+        let reset = reset_failed_job(&pool, 1, now).await.unwrap().unwrap();
+        assert_eq!(reset.status, JOB_STATUS_PENDING);
+        assert_eq!(reset.attempts, 0);
+        assert!(cancel_job(&pool, 1).await.unwrap());
+        assert!(get_job(&pool, 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recurring_jobs_keep_one_row_per_kind() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        schedule_recurring_job(&pool, 1, "sweep", 60, now)
+            .await
+            .unwrap();
+        schedule_recurring_job(&pool, 2, "sweep", 300, now)
+            .await
+            .unwrap();
+        let jobs = list_jobs(&pool, None, Some("sweep"), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].repeat_seconds, Some(300));
+
+        let job = claim_next_job(&pool, "w1", now).await.unwrap().unwrap();
+        assert_eq!(job.id, 1);
+        assert!(
+            reschedule_recurring_job(&pool, 1, Some("boom"), now + Duration::minutes(5))
+                .await
+                .unwrap()
+        );
+        let job = get_job(&pool, 1).await.unwrap().unwrap();
+        assert_eq!(job.status, JOB_STATUS_PENDING);
+        assert_eq!(job.attempts, 0);
+        assert_eq!(job.last_error.as_deref(), Some("boom"));
+        assert!(claim_next_job(&pool, "w1", now).await.unwrap().is_none());
+
+        let job = claim_next_job(&pool, "w1", now + Duration::minutes(6))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(fail_job(&pool, job.id, "Unknown job kind").await.unwrap());
+        schedule_recurring_job(&pool, 3, "sweep", 300, now)
+            .await
+            .unwrap();
+        let job = claim_next_job(&pool, "w1", now).await.unwrap().unwrap();
+        assert_eq!(job.id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_running_jobs_are_requeued() {
+        let pool = test_pool().await;
+        let now = Utc::now();
+        enqueue_job(&pool, 1, "export", "{}", 0, 3, now)
+            .await
+            .unwrap();
+        claim_next_job(&pool, "crashed", now - Duration::hours(1))
+            .await
+            .unwrap();
+        // The job was not ready an hour ago.
+        assert_eq!(
+            get_job(&pool, 1).await.unwrap().unwrap().status,
+            JOB_STATUS_PENDING
+        );
+
+        claim_next_job(&pool, "crashed", now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            requeue_stale_jobs(&pool, now - Duration::minutes(5))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            requeue_stale_jobs(&pool, now + Duration::seconds(1))
+                .await
+                .unwrap(),
+            1
+        );
+        let job = claim_next_job(&pool, "w2", now).await.unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert_eq!(job.locked_by.as_deref(), Some("w2"));
+    }
+}
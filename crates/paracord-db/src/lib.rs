@@ -3,30 +3,58 @@ pub mod attachments;
 pub mod audit_log;
 pub mod bans;
 pub mod bot_applications;
+pub mod channel_exports;
 pub mod channel_overwrites;
 pub mod channels;
+pub mod dm_receipts;
 pub mod dms;
+pub mod emoji_usage;
 pub mod emojis;
 pub mod federation;
 pub mod federation_file_cache;
 pub mod guild_storage_policies;
+pub mod guild_tags;
+pub mod guild_themes;
+pub mod guild_thread_policies;
+pub mod guild_verification;
+pub mod guild_widgets;
 pub mod guilds;
+pub mod image_hashes;
+pub mod imports;
 pub mod interaction_tokens;
 pub mod invites;
+pub mod jobs;
+pub mod link_previews;
+pub mod maintenance;
+pub mod member_notes;
 pub mod members;
+pub mod message_embeds;
 pub mod messages;
+pub mod mod_log;
+pub mod notification_settings;
 pub mod polls;
 pub mod prekeys;
+pub mod push_subscriptions;
 pub mod rate_limits;
 pub mod reactions;
 pub mod read_states;
 pub mod relationships;
+pub mod reminders;
 pub mod roles;
+
 pub mod scheduled_events;
+pub mod search;
 pub mod security_events;
 pub mod server_settings;
 pub mod sessions;
+pub mod stream_keys;
+pub mod supporters;
+pub mod tombstones;
+pub mod url_denylist;
+pub mod user_notes;
 pub mod users;
+pub mod voice_recordings;
+pub mod voice_stacks;
 pub mod voice_states;
 pub mod webhooks;
 
@@ -37,6 +65,21 @@ use thiserror::Error;
 
 pub type DbPool = sqlx::AnyPool;
 
+/// A transaction on the pool. Dropping it without `commit` rolls back every
+/// write made through it.
+pub type DbTransaction = sqlx::Transaction<'static, sqlx::Any>;
+
+/// Where a query helper can run: `&pool`, or `&mut *tx` inside a transaction.
+pub trait DbExecutor<'e>: sqlx::Executor<'e, Database = sqlx::Any> {}
+
+impl<'e, T: sqlx::Executor<'e, Database = sqlx::Any>> DbExecutor<'e> for T {}
+
+/// Start a transaction for a mutation that spans several query helpers, so a
+/// failure part way through can't leave half of it applied.
+pub async fn begin(pool: &DbPool) -> Result<DbTransaction, DbError> {
+    Ok(pool.begin().await?)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DatabaseEngine {
     Sqlite,
@@ -60,6 +103,8 @@ pub enum DbError {
     Sqlx(#[from] sqlx::Error),
     #[error("not found")]
     NotFound,
+    #[error("id generation failed: {0}")]
+    Snowflake(#[from] paracord_util::snowflake::SnowflakeError),
 }
 
 /// Optional tuning knobs applied after each PostgreSQL connection is established.
@@ -69,8 +114,16 @@ pub struct PgConnectOptions {
     pub statement_timeout_secs: u64,
     /// `idle_in_transaction_session_timeout` in seconds (0 = disabled).
     pub idle_in_transaction_timeout_secs: u64,
+    /// Ceiling the pool may grow to under load (0 = fixed at
+    /// `max_connections`). `max_connections` stay open; the extra
+    /// connections are closed after [`ADAPTIVE_POOL_IDLE_TIMEOUT`] idle.
+    pub adaptive_max_connections: u32,
 }
 
+/// How long a connection above the baseline may sit idle in an adaptive
+/// PostgreSQL pool before it is closed.
+pub const ADAPTIVE_POOL_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
 pub async fn create_pool(database_url: &str, max_connections: u32) -> Result<DbPool, sqlx::Error> {
     create_pool_full(database_url, max_connections, None, None, None).await
 }
@@ -138,8 +191,16 @@ pub async fn create_pool_full(
 
     let after_connect_key = sqlite_key_hex.clone();
     let pg_opts = pg_options.unwrap_or_default();
-    AnyPoolOptions::new()
-        .max_connections(max_connections)
+    let mut pool_options = AnyPoolOptions::new().max_connections(max_connections);
+    if matches!(engine, DatabaseEngine::Postgres)
+        && pg_opts.adaptive_max_connections > max_connections
+    {
+        pool_options = pool_options
+            .max_connections(pg_opts.adaptive_max_connections)
+            .min_connections(max_connections)
+            .idle_timeout(ADAPTIVE_POOL_IDLE_TIMEOUT);
+    }
+    pool_options
         .after_connect(move |conn, _meta| {
             let sqlite_key_hex = after_connect_key.clone();
             let sqlite_db = matches!(engine, DatabaseEngine::Sqlite);
@@ -0,0 +1,53 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct LinkPreviewRow {
+    pub url: String,
+    /// Serialized embed; `None` when the page had nothing to preview.
+    pub embed_data: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for LinkPreviewRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let fetched_at_raw: String = row.try_get("fetched_at")?;
+        Ok(Self {
+            url: row.try_get("url")?,
+            embed_data: row.try_get("embed_data")?,
+            fetched_at: datetime_from_db_text(&fetched_at_raw)?,
+        })
+    }
+}
+
+pub async fn get_preview(pool: &DbPool, url: &str) -> Result<Option<LinkPreviewRow>, DbError> {
+    let row = sqlx::query_as::<_, LinkPreviewRow>(
+        "SELECT url, embed_data, fetched_at FROM link_previews WHERE url = $1",
+    )
+    .bind(url)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn upsert_preview(
+    pool: &DbPool,
+    url: &str,
+    embed_data: Option<&str>,
+    fetched_at: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO link_previews (url, embed_data, fetched_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (url) DO UPDATE SET
+            embed_data = excluded.embed_data,
+            fetched_at = excluded.fetched_at",
+    )
+    .bind(url)
+    .bind(embed_data)
+    .bind(datetime_to_db_text(fetched_at))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! SQLite housekeeping: WAL checkpoints, `PRAGMA optimize`, incremental
+//! vacuum and compacted copies. None of this applies to PostgreSQL, which
+//! manages its own storage.
+
+use crate::{DbError, DbPool};
+use sqlx::Row;
+
+/// `auto_vacuum` value for incremental mode.
+pub const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+#[derive(Debug, Clone)]
+pub struct SqliteStats {
+    pub page_size: i64,
+    pub page_count: i64,
+    pub freelist_count: i64,
+    /// 0 = none, 1 = full, 2 = incremental.
+    pub auto_vacuum: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalCheckpoint {
+    /// Set when readers or writers kept the checkpoint from finishing.
+    pub busy: bool,
+    pub log_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+async fn pragma_i64(pool: &DbPool, pragma: &str) -> Result<i64, DbError> {
+    let row = sqlx::query(&format!("PRAGMA {pragma}"))
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get(0)?)
+}
+
+pub async fn sqlite_stats(pool: &DbPool) -> Result<SqliteStats, DbError> {
+    Ok(SqliteStats {
+        page_size: pragma_i64(pool, "page_size").await?,
+        page_count: pragma_i64(pool, "page_count").await?,
+        freelist_count: pragma_i64(pool, "freelist_count").await?,
+        auto_vacuum: pragma_i64(pool, "auto_vacuum").await?,
+    })
+}
+
+/// Copy the WAL back into the database and truncate the WAL file to zero
+/// bytes.
+pub async fn checkpoint_wal(pool: &DbPool) -> Result<WalCheckpoint, DbError> {
+    let row = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .fetch_one(pool)
+        .await?;
+    let busy: i64 = row.try_get(0)?;
+    Ok(WalCheckpoint {
+        busy: busy != 0,
+        log_frames: row.try_get(1)?,
+        checkpointed_frames: row.try_get(2)?,
+    })
+}
+
+pub async fn optimize(pool: &DbPool) -> Result<(), DbError> {
+    sqlx::query("PRAGMA optimize").execute(pool).await?;
+    Ok(())
+}
+
+/// Release up to `pages` free pages back to the filesystem (0 = all).
+/// Does nothing unless `auto_vacuum` is incremental.
+pub async fn incremental_vacuum(pool: &DbPool, pages: i64) -> Result<(), DbError> {
+    let sql = if pages > 0 {
+        format!("PRAGMA incremental_vacuum({pages})")
+    } else {
+        "PRAGMA incremental_vacuum".to_string()
+    };
+    // The pragma returns one row per freed page; they have to be stepped
+    // through for the work to happen.
+    sqlx::query(&sql).fetch_all(pool).await?;
+    Ok(())
+}
+
+/// Switch the database to incremental auto-vacuum. Changing the mode only
+/// takes effect after a full `VACUUM`, which rewrites the whole file, so
+/// this runs once and is a no-op afterwards. Returns whether it switched.
+pub async fn enable_incremental_auto_vacuum(pool: &DbPool) -> Result<bool, DbError> {
+    let mut conn = pool.acquire().await?;
+    let row = sqlx::query("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await?;
+    let current: i64 = row.try_get(0)?;
+    if current == AUTO_VACUUM_INCREMENTAL {
+        return Ok(false);
+    }
+    sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("VACUUM").execute(&mut *conn).await?;
+    Ok(true)
+}
+
+/// Write a compacted copy of the database to `dest_path`, which must not
+/// exist yet. The live database is left untouched.
+pub async fn vacuum_into(pool: &DbPool, dest_path: &str) -> Result<(), DbError> {
+    let quoted = dest_path.replace('\'', "''");
+    sqlx::query(&format!("VACUUM INTO '{quoted}'"))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_maintenance_pragmas_run_on_sqlite() {
+        let dir = std::env::temp_dir().join(format!("paracord-maintenance-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("paracord.db");
+        let pool = crate::create_pool(&format!("sqlite://{}?mode=rwc", db_path.display()), 1)
+            .await
+            .unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        let stats = sqlite_stats(&pool).await.unwrap();
+        assert!(stats.page_count > 0);
+
+        let checkpoint = checkpoint_wal(&pool).await.unwrap();
+        assert!(!checkpoint.busy);
+        optimize(&pool).await.unwrap();
+        assert!(enable_incremental_auto_vacuum(&pool).await.unwrap());
+        assert!(!enable_incremental_auto_vacuum(&pool).await.unwrap());
+        assert_eq!(
+            sqlite_stats(&pool).await.unwrap().auto_vacuum,
+            AUTO_VACUUM_INCREMENTAL
+        );
+        incremental_vacuum(&pool, 100).await.unwrap();
+
+        let dest = dir.join("compacted.db");
+        vacuum_into(&pool, dest.to_str().unwrap()).await.unwrap();
+        assert!(std::fs::metadata(&dest).unwrap().len() > 0);
+        assert!(vacuum_into(&pool, dest.to_str().unwrap()).await.is_err());
+        pool.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A staff-visible moderation note on a guild member. Notes are append-only
+/// so the list doubles as the member's moderation history.
+#[derive(Debug, Clone)]
+pub struct MemberNoteRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
+    /// `None` once the author's account has been deleted.
+    pub author_id: Option<i64>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberNoteRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            user_id: row.try_get("user_id")?,
+            author_id: row.try_get("author_id")?,
+            content: row.try_get("content")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+pub async fn create_note(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    user_id: i64,
+    author_id: i64,
+    content: &str,
+) -> Result<MemberNoteRow, DbError> {
+    let row = sqlx::query_as::<_, MemberNoteRow>(
+        "INSERT INTO member_mod_notes (id, guild_id, user_id, author_id, content)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, guild_id, user_id, author_id, content, created_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(user_id)
+    .bind(author_id)
+    .bind(content)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_note(pool: &DbPool, id: i64) -> Result<Option<MemberNoteRow>, DbError> {
+    let row = sqlx::query_as::<_, MemberNoteRow>(
+        "SELECT id, guild_id, user_id, author_id, content, created_at
+         FROM member_mod_notes WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Notes on one member, newest first.
+pub async fn list_member_notes(
+    pool: &DbPool,
+    guild_id: i64,
+    user_id: i64,
+) -> Result<Vec<MemberNoteRow>, DbError> {
+    let rows = sqlx::query_as::<_, MemberNoteRow>(
+        "SELECT id, guild_id, user_id, author_id, content, created_at
+         FROM member_mod_notes WHERE guild_id = $1 AND user_id = $2
+         ORDER BY id DESC",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Notes written by `author_id`, for data export.
+pub async fn list_notes_by_author(
+    pool: &DbPool,
+    author_id: i64,
+) -> Result<Vec<MemberNoteRow>, DbError> {
+    let rows = sqlx::query_as::<_, MemberNoteRow>(
+        "SELECT id, guild_id, user_id, author_id, content, created_at
+         FROM member_mod_notes WHERE author_id = $1
+         ORDER BY id ASC",
+    )
+    .bind(author_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete_note(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM member_mod_notes WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
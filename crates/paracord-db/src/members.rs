@@ -1,4 +1,6 @@
-use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use crate::{
+    bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbExecutor, DbPool,
+};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -71,11 +73,15 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MemberWithUserRow {
 }
 
 /// Add a user as a server-wide member. guild_id kept for API compat but ignored.
-pub async fn add_member(pool: &DbPool, user_id: i64, guild_id: i64) -> Result<(), DbError> {
+pub async fn add_member<'e>(
+    executor: impl DbExecutor<'e>,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<(), DbError> {
     sqlx::query("INSERT INTO members (user_id, guild_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
         .bind(user_id)
         .bind(guild_id)
-        .execute(pool)
+        .execute(executor)
         .await?;
     Ok(())
 }
@@ -85,6 +91,7 @@ pub async fn add_server_member(pool: &DbPool, user_id: i64) -> Result<(), DbErro
         "INSERT INTO members (user_id, guild_id)
          SELECT $1, s.id
          FROM spaces s
+         WHERE s.deleted_at IS NULL
          ON CONFLICT DO NOTHING",
     )
     .bind(user_id)
@@ -120,6 +127,8 @@ pub async fn get_server_member(pool: &DbPool, user_id: i64) -> Result<Option<Mem
     Ok(row)
 }
 
+/// A page of a guild's members ordered by user id. Pass the last `user_id`
+/// of the previous page as `after` to continue.
 pub async fn get_guild_members(
     pool: &DbPool,
     guild_id: i64,
@@ -149,7 +158,7 @@ pub async fn get_guild_members(
              FROM members m
              INNER JOIN users u ON u.id = m.user_id
              WHERE m.guild_id = $2
-             ORDER BY joined_at
+             ORDER BY m.user_id
              LIMIT $1"
         )
         .bind(limit)
@@ -276,6 +285,34 @@ pub async fn get_guild_member_user_ids(pool: &DbPool, guild_id: i64) -> Result<V
     Ok(rows.into_iter().map(|(user_id,)| user_id).collect())
 }
 
+/// The members of `guild_id` among `user_ids`, with their users.
+pub async fn get_guild_members_by_ids(
+    pool: &DbPool,
+    guild_id: i64,
+    user_ids: &[i64],
+) -> Result<Vec<MemberWithUserRow>, DbError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=user_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT m.user_id, m.nick, m.avatar_hash, m.joined_at, CASE WHEN m.deaf THEN 1 ELSE 0 END AS deaf, CASE WHEN m.mute THEN 1 ELSE 0 END AS mute, m.communication_disabled_until,
+                u.username, u.discriminator, u.avatar_hash AS user_avatar_hash, u.flags AS user_flags
+         FROM members m
+         INNER JOIN users u ON u.id = m.user_id
+         WHERE m.guild_id = $1
+           AND m.user_id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, MemberWithUserRow>(&sql).bind(guild_id);
+    for user_id in user_ids {
+        query = query.bind(*user_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
 pub async fn share_any_guild(pool: &DbPool, user_a: i64, user_b: i64) -> Result<bool, DbError> {
     let row: Option<(i64,)> = sqlx::query_as(
         "SELECT 1
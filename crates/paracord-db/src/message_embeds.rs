@@ -0,0 +1,33 @@
+use crate::{DbError, DbPool};
+
+/// Serialized embeds of a message, in the order they were attached.
+pub async fn get_message_embeds(pool: &DbPool, message_id: i64) -> Result<Vec<String>, DbError> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT embed_data FROM message_embeds WHERE message_id = $1 ORDER BY id")
+            .bind(message_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().map(|(data,)| data).collect())
+}
+
+/// Replace every embed of a message with `embeds`.
+pub async fn replace_message_embeds(
+    pool: &DbPool,
+    message_id: i64,
+    embeds: &[String],
+) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM message_embeds WHERE message_id = $1")
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+    for embed in embeds {
+        sqlx::query("INSERT INTO message_embeds (message_id, embed_data) VALUES ($1, $2)")
+            .bind(message_id)
+            .bind(embed)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
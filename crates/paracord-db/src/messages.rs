@@ -1,4 +1,4 @@
-use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool, DbTransaction};
 use chrono::{DateTime, Utc};
 use paracord_models::permissions::Permissions;
 use sqlx::Row;
@@ -121,6 +121,50 @@ pub async fn create_message_with_meta(
     Ok(row)
 }
 
+/// Insert a message carried over from another platform, keeping its
+/// original timestamps. Returns `false` when a message with `id` already
+/// exists, so re-running an import doesn't duplicate anything. Runs in the
+/// caller's transaction so the message commits together with its
+/// attachments and import mapping.
+#[allow(clippy::too_many_arguments)]
+pub async fn import_message(
+    tx: &mut DbTransaction,
+    id: i64,
+    channel_id: i64,
+    author_id: i64,
+    content: &str,
+    created_at: &str,
+    edited_at: Option<&str>,
+    pinned: bool,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO messages (id, channel_id, author_id, content, edited_at, pinned, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (id) DO NOTHING",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .bind(author_id)
+    .bind(content)
+    .bind(edited_at)
+    .bind(pinned)
+    .bind(created_at)
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query(
+        "UPDATE channels SET last_message_id = $1
+         WHERE id = $2 AND (last_message_id IS NULL OR last_message_id < $1)",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(true)
+}
+
 fn is_nonce_dedup_unique_violation(err: &sqlx::Error) -> bool {
     let sqlx::Error::Database(db_err) = err else {
         return false;
@@ -214,6 +258,33 @@ pub async fn get_channel_messages(
     Ok(rows)
 }
 
+/// Up to `limit` messages centred on `around`, newest first. The message
+/// with that id is included when it exists. Both halves are index range
+/// scans from the anchor, so jumping deep into history costs the same as
+/// reading the latest page.
+pub async fn get_messages_around(
+    pool: &DbPool,
+    channel_id: i64,
+    around: i64,
+    limit: i64,
+) -> Result<Vec<MessageRow>, DbError> {
+    let older_limit = limit / 2;
+    let mut rows = sqlx::query_as::<_, MessageRow>(
+        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
+         FROM messages WHERE channel_id = $1 AND id >= $2 ORDER BY id ASC LIMIT $3",
+    )
+    .bind(channel_id)
+    .bind(around)
+    .bind(limit - older_limit)
+    .fetch_all(pool)
+    .await?;
+    rows.reverse();
+    if older_limit > 0 {
+        rows.extend(get_channel_messages(pool, channel_id, Some(around), None, older_limit).await?);
+    }
+    Ok(rows)
+}
+
 pub async fn update_message(pool: &DbPool, id: i64, content: &str) -> Result<MessageRow, DbError> {
     let row = sqlx::query_as::<_, MessageRow>(
         "UPDATE messages SET content = $2, edited_at = datetime('now')
@@ -269,6 +340,7 @@ pub async fn update_message_authorized_with_meta(
                AND m.guild_id = ctx.guild_id
              INNER JOIN roles r
                 ON r.space_id = ctx.guild_id
+               AND r.deleted_at IS NULL
              LEFT JOIN member_roles mr
                 ON mr.role_id = r.id
                AND mr.user_id = $3
@@ -336,6 +408,7 @@ pub async fn delete_message_authorized(
                AND m.guild_id = ctx.guild_id
              INNER JOIN roles r
                 ON r.space_id = ctx.guild_id
+               AND r.deleted_at IS NULL
              LEFT JOIN member_roles mr
                 ON mr.role_id = r.id
                AND mr.user_id = $3
@@ -428,34 +501,36 @@ pub async fn count_messages(pool: &DbPool) -> Result<i64, DbError> {
     Ok(row.0)
 }
 
+/// Rebuild the indexes on `messages` that history and search read through,
+/// then refresh the planner's statistics for the table.
+pub async fn reindex_messages(pool: &DbPool) -> Result<(), DbError> {
+    let reindex = match crate::active_database_engine() {
+        crate::DatabaseEngine::Sqlite => "REINDEX messages",
+        crate::DatabaseEngine::Postgres => "REINDEX TABLE messages",
+    };
+    sqlx::query(reindex).execute(pool).await?;
+    sqlx::query("ANALYZE messages").execute(pool).await?;
+    Ok(())
+}
+
+/// Messages in one channel matching `query`, best match first. See
+/// [`crate::search`] for filters and multi-channel searches.
 pub async fn search_messages(
     pool: &DbPool,
     channel_id: i64,
     query: &str,
     limit: i64,
 ) -> Result<Vec<MessageRow>, DbError> {
-    const MESSAGE_FLAG_DM_E2EE: i32 = 1 << 0;
-    let escaped = query
-        .replace('\\', "\\\\")
-        .replace('%', "\\%")
-        .replace('_', "\\_");
-    let pattern = format!("%{}%", escaped);
-    let rows = sqlx::query_as::<_, MessageRow>(
-        "SELECT id, channel_id, author_id, content, nonce, message_type, flags, edited_at, CASE WHEN pinned THEN 1 ELSE 0 END AS pinned, reference_id, e2ee_header, created_at
-         FROM messages
-         WHERE channel_id = $1
-           AND content LIKE $2 ESCAPE '\\'
-           AND (flags & $4) = 0
-         ORDER BY id DESC
-         LIMIT $3",
+    crate::search::search_messages(
+        pool,
+        &crate::search::MessageSearch {
+            text: query.to_string(),
+            channel_ids: vec![channel_id],
+            limit,
+            ..Default::default()
+        },
     )
-    .bind(channel_id)
-    .bind(pattern)
-    .bind(limit)
-    .bind(MESSAGE_FLAG_DM_E2EE)
-    .fetch_all(pool)
-    .await?;
-    Ok(rows)
+    .await
 }
 
 pub async fn get_message_ids_older_than(
@@ -463,14 +538,18 @@ pub async fn get_message_ids_older_than(
     older_than: DateTime<Utc>,
     limit: i64,
 ) -> Result<Vec<i64>, DbError> {
+    // IDs encode their creation time, so the primary key doubles as the
+    // timestamp index.
     let rows: Vec<(i64,)> = sqlx::query_as(
         "SELECT id
          FROM messages
-         WHERE created_at <= $1
-         ORDER BY created_at ASC
+         WHERE id < $1
+         ORDER BY id ASC
          LIMIT $2",
     )
-    .bind(datetime_to_db_text(older_than))
+    .bind(paracord_util::snowflake::min_id_at(
+        older_than + chrono::Duration::milliseconds(1),
+    ))
     .bind(limit)
     .fetch_all(pool)
     .await?;
@@ -689,6 +768,82 @@ mod tests {
         assert_eq!(messages.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_get_messages_around() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        for i in 0..10 {
+            create_message(
+                &pool,
+                6500 + i,
+                channel_id,
+                user_id,
+                &format!("msg {}", i),
+                0,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let messages = get_messages_around(&pool, channel_id, 6505, 4)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![6506, 6505, 6504, 6503]);
+
+        // Near the start of the channel the page is just shorter.
+        let messages = get_messages_around(&pool, channel_id, 6500, 4)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![6501, 6500]);
+    }
+
+    #[tokio::test]
+    async fn test_import_message_keeps_timestamps_once() {
+        let pool = test_pool().await;
+        let (user_id, _, channel_id) = setup_channel(&pool).await;
+        let mut tx = crate::begin(&pool).await.unwrap();
+        let inserted = import_message(
+            &mut tx,
+            6900,
+            channel_id,
+            user_id,
+            "old",
+            "2021-03-04 05:06:07",
+            Some("2021-03-05 00:00:00"),
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(inserted);
+        let again = import_message(
+            &mut tx,
+            6900,
+            channel_id,
+            user_id,
+            "dup",
+            "2021-03-04 05:06:07",
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(!again);
+        tx.commit().await.unwrap();
+
+        let msg = get_message(&pool, 6900).await.unwrap().unwrap();
+        assert_eq!(msg.content.as_deref(), Some("old"));
+        assert_eq!(msg.created_at.to_string(), "2021-03-04 05:06:07 UTC");
+        assert!(msg.pinned);
+        assert!(msg.edited_at.is_some());
+        let channel = crate::channels::get_channel(&pool, channel_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(channel.last_message_id, Some(6900));
+    }
+
     #[tokio::test]
     async fn test_update_message() {
         let pool = test_pool().await;
@@ -0,0 +1,135 @@
+use crate::{bool_from_any_row, DbError, DbPool};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct ModLogSettingsRow {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub include_audit_log: bool,
+    pub include_automod: bool,
+    pub updated_at: String,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ModLogSettingsRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            guild_id: row.try_get("guild_id")?,
+            channel_id: row.try_get("channel_id")?,
+            include_audit_log: bool_from_any_row(row, "include_audit_log")?,
+            include_automod: bool_from_any_row(row, "include_automod")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ModLogQueueRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub content: String,
+}
+
+pub async fn get_settings(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Option<ModLogSettingsRow>, DbError> {
+    let row = sqlx::query_as::<_, ModLogSettingsRow>(
+        "SELECT guild_id, channel_id,
+                CASE WHEN include_audit_log THEN 1 ELSE 0 END AS include_audit_log,
+                CASE WHEN include_automod THEN 1 ELSE 0 END AS include_automod,
+                updated_at
+         FROM guild_mod_log_settings WHERE guild_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn upsert_settings(
+    pool: &DbPool,
+    guild_id: i64,
+    channel_id: i64,
+    include_audit_log: bool,
+    include_automod: bool,
+) -> Result<ModLogSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, ModLogSettingsRow>(
+        "INSERT INTO guild_mod_log_settings
+            (guild_id, channel_id, include_audit_log, include_automod, updated_at)
+         VALUES ($1, $2, $3, $4, datetime('now'))
+         ON CONFLICT(guild_id) DO UPDATE SET
+            channel_id = excluded.channel_id,
+            include_audit_log = excluded.include_audit_log,
+            include_automod = excluded.include_automod,
+            updated_at = datetime('now')
+         RETURNING guild_id, channel_id,
+                   CASE WHEN include_audit_log THEN 1 ELSE 0 END AS include_audit_log,
+                   CASE WHEN include_automod THEN 1 ELSE 0 END AS include_automod,
+                   updated_at",
+    )
+    .bind(guild_id)
+    .bind(channel_id)
+    .bind(include_audit_log)
+    .bind(include_automod)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Turn the mod log off. Lines still queued for the guild are dropped.
+pub async fn delete_settings(pool: &DbPool, guild_id: i64) -> Result<bool, DbError> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM guild_mod_log_settings WHERE guild_id = $1")
+        .bind(guild_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM mod_log_queue WHERE guild_id = $1")
+        .bind(guild_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn enqueue_line(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    content: &str,
+) -> Result<(), DbError> {
+    sqlx::query("INSERT INTO mod_log_queue (id, guild_id, content) VALUES ($1, $2, $3)")
+        .bind(id)
+        .bind(guild_id)
+        .bind(content)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Oldest queued lines across all guilds.
+pub async fn get_queued_lines(pool: &DbPool, limit: i64) -> Result<Vec<ModLogQueueRow>, DbError> {
+    let rows = sqlx::query_as::<_, ModLogQueueRow>(
+        "SELECT id, guild_id, content FROM mod_log_queue ORDER BY id LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete_queued_lines(pool: &DbPool, ids: &[i64]) -> Result<(), DbError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let placeholders = (1..=ids.len())
+        .map(|i| format!("${i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!("DELETE FROM mod_log_queue WHERE id IN ({placeholders})");
+    let mut query = sqlx::query(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    query.execute(pool).await?;
+    Ok(())
+}
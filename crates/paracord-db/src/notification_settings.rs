@@ -0,0 +1,230 @@
+use crate::{bool_from_any_row, datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+pub const LEVEL_ALL: i16 = 0;
+pub const LEVEL_MENTIONS: i16 = 1;
+pub const LEVEL_NONE: i16 = 2;
+
+#[derive(Debug, Clone)]
+pub struct GuildNotificationSettingsRow {
+    pub user_id: i64,
+    pub guild_id: i64,
+    pub level: i16,
+    pub muted: bool,
+    /// When the mute lifts; `None` with `muted` set means until unmuted.
+    pub muted_until: Option<DateTime<Utc>>,
+    /// Ignore `@everyone` and `@here`.
+    pub suppress_everyone: bool,
+}
+
+impl GuildNotificationSettingsRow {
+    /// Settings for a user who never changed them.
+    pub fn defaults(user_id: i64, guild_id: i64) -> Self {
+        Self {
+            user_id,
+            guild_id,
+            level: LEVEL_ALL,
+            muted: false,
+            muted_until: None,
+            suppress_everyone: false,
+        }
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for GuildNotificationSettingsRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let muted_until_raw: Option<String> = row.try_get("muted_until")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            guild_id: row.try_get("guild_id")?,
+            level: row.try_get("level")?,
+            muted: bool_from_any_row(row, "muted")?,
+            muted_until: muted_until_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            suppress_everyone: bool_from_any_row(row, "suppress_everyone")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelNotificationOverrideRow {
+    pub user_id: i64,
+    pub channel_id: i64,
+    pub guild_id: i64,
+    /// `None` inherits the guild level.
+    pub level: Option<i16>,
+    pub muted: bool,
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ChannelNotificationOverrideRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let muted_until_raw: Option<String> = row.try_get("muted_until")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            channel_id: row.try_get("channel_id")?,
+            guild_id: row.try_get("guild_id")?,
+            level: row.try_get("level")?,
+            muted: bool_from_any_row(row, "muted")?,
+            muted_until: muted_until_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+        })
+    }
+}
+
+const GUILD_COLS: &str = "user_id, guild_id, level, CASE WHEN muted THEN 1 ELSE 0 END AS muted, muted_until, CASE WHEN suppress_everyone THEN 1 ELSE 0 END AS suppress_everyone";
+const OVERRIDE_COLS: &str =
+    "user_id, channel_id, guild_id, level, CASE WHEN muted THEN 1 ELSE 0 END AS muted, muted_until";
+
+pub async fn get_guild_settings(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<Option<GuildNotificationSettingsRow>, DbError> {
+    let row = sqlx::query_as::<_, GuildNotificationSettingsRow>(&format!(
+        "SELECT {GUILD_COLS} FROM guild_notification_settings
+         WHERE user_id = $1 AND guild_id = $2"
+    ))
+    .bind(user_id)
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Stored guild settings of every user in `user_ids` who has any.
+pub async fn get_guild_settings_for_users(
+    pool: &DbPool,
+    guild_id: i64,
+    user_ids: &[i64],
+) -> Result<Vec<GuildNotificationSettingsRow>, DbError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=user_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT {GUILD_COLS} FROM guild_notification_settings
+         WHERE guild_id = $1 AND user_id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, GuildNotificationSettingsRow>(&sql).bind(guild_id);
+    for user_id in user_ids {
+        query = query.bind(*user_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
+pub async fn upsert_guild_settings(
+    pool: &DbPool,
+    settings: &GuildNotificationSettingsRow,
+) -> Result<GuildNotificationSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, GuildNotificationSettingsRow>(&format!(
+        "INSERT INTO guild_notification_settings
+            (user_id, guild_id, level, muted, muted_until, suppress_everyone, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, datetime('now'))
+         ON CONFLICT (user_id, guild_id) DO UPDATE SET
+            level = excluded.level,
+            muted = excluded.muted,
+            muted_until = excluded.muted_until,
+            suppress_everyone = excluded.suppress_everyone,
+            updated_at = datetime('now')
+         RETURNING {GUILD_COLS}"
+    ))
+    .bind(settings.user_id)
+    .bind(settings.guild_id)
+    .bind(settings.level)
+    .bind(settings.muted)
+    .bind(settings.muted_until.map(datetime_to_db_text))
+    .bind(settings.suppress_everyone)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_channel_overrides(
+    pool: &DbPool,
+    user_id: i64,
+    guild_id: i64,
+) -> Result<Vec<ChannelNotificationOverrideRow>, DbError> {
+    let rows = sqlx::query_as::<_, ChannelNotificationOverrideRow>(&format!(
+        "SELECT {OVERRIDE_COLS} FROM channel_notification_overrides
+         WHERE user_id = $1 AND guild_id = $2
+         ORDER BY channel_id"
+    ))
+    .bind(user_id)
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Overrides that users in `user_ids` set on `channel_id`.
+pub async fn get_channel_overrides_for_users(
+    pool: &DbPool,
+    channel_id: i64,
+    user_ids: &[i64],
+) -> Result<Vec<ChannelNotificationOverrideRow>, DbError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (2..=user_ids.len() + 1)
+        .map(|i| format!("${}", i))
+        .collect();
+    let sql = format!(
+        "SELECT {OVERRIDE_COLS} FROM channel_notification_overrides
+         WHERE channel_id = $1 AND user_id IN ({})",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, ChannelNotificationOverrideRow>(&sql).bind(channel_id);
+    for user_id in user_ids {
+        query = query.bind(*user_id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
+pub async fn upsert_channel_override(
+    pool: &DbPool,
+    row: &ChannelNotificationOverrideRow,
+) -> Result<ChannelNotificationOverrideRow, DbError> {
+    let row = sqlx::query_as::<_, ChannelNotificationOverrideRow>(&format!(
+        "INSERT INTO channel_notification_overrides
+            (user_id, channel_id, guild_id, level, muted, muted_until)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, channel_id) DO UPDATE SET
+            level = excluded.level,
+            muted = excluded.muted,
+            muted_until = excluded.muted_until
+         RETURNING {OVERRIDE_COLS}"
+    ))
+    .bind(row.user_id)
+    .bind(row.channel_id)
+    .bind(row.guild_id)
+    .bind(row.level)
+    .bind(row.muted)
+    .bind(row.muted_until.map(datetime_to_db_text))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn delete_channel_override(
+    pool: &DbPool,
+    user_id: i64,
+    channel_id: i64,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "DELETE FROM channel_notification_overrides WHERE user_id = $1 AND channel_id = $2",
+    )
+    .bind(user_id)
+    .bind(channel_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use crate::{
+    datetime_from_db_text, datetime_to_db_text, DbError, DbExecutor, DbPool, DbTransaction,
+};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct PushSubscriptionRow {
+    pub id: i64,
+    pub user_id: i64,
+    pub endpoint: String,
+    /// Client public key, base64url (uncompressed P-256 point).
+    pub p256dh: String,
+    /// Client auth secret, base64url.
+    pub auth: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for PushSubscriptionRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        let last_used_at_raw: Option<String> = row.try_get("last_used_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            endpoint: row.try_get("endpoint")?,
+            p256dh: row.try_get("p256dh")?,
+            auth: row.try_get("auth")?,
+            user_agent: row.try_get("user_agent")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            last_used_at: last_used_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+        })
+    }
+}
+
+/// A queued notification with the subscription it goes to.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PushDeliveryRow {
+    pub id: i64,
+    pub subscription_id: i64,
+    pub payload: String,
+    /// Attempts made so far, including the one just claimed.
+    pub attempts: i32,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+const SUBSCRIPTION_COLS: &str =
+    "id, user_id, endpoint, p256dh, auth, user_agent, created_at, last_used_at";
+
+/// Register `endpoint` for `user_id`, taking it over if another user had it.
+pub async fn upsert_subscription(
+    pool: &DbPool,
+    id: i64,
+    user_id: i64,
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+    user_agent: Option<&str>,
+) -> Result<PushSubscriptionRow, DbError> {
+    let row = sqlx::query_as::<_, PushSubscriptionRow>(&format!(
+        "INSERT INTO push_subscriptions (id, user_id, endpoint, p256dh, auth, user_agent)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (endpoint) DO UPDATE SET
+            user_id = excluded.user_id,
+            p256dh = excluded.p256dh,
+            auth = excluded.auth,
+            user_agent = excluded.user_agent
+         RETURNING {SUBSCRIPTION_COLS}"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .bind(endpoint)
+    .bind(p256dh)
+    .bind(auth)
+    .bind(user_agent)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_user_subscriptions(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<PushSubscriptionRow>, DbError> {
+    let rows = sqlx::query_as::<_, PushSubscriptionRow>(&format!(
+        "SELECT {SUBSCRIPTION_COLS} FROM push_subscriptions WHERE user_id = $1 ORDER BY id"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete_user_subscription(
+    pool: &DbPool,
+    user_id: i64,
+    endpoint: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM push_subscriptions WHERE user_id = $1 AND endpoint = $2")
+        .bind(user_id)
+        .bind(endpoint)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Drop a subscription the push service reported as gone, with its queue.
+pub async fn delete_subscription(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM push_deliveries WHERE subscription_id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM push_subscriptions WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Subscription ids of every user in `user_ids`.
+pub async fn get_subscription_ids_for_users<'e>(
+    executor: impl DbExecutor<'e>,
+    user_ids: &[i64],
+) -> Result<Vec<i64>, DbError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders: Vec<String> = (1..=user_ids.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT id FROM push_subscriptions WHERE user_id IN ({}) ORDER BY id",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_scalar::<_, i64>(&sql);
+    for user_id in user_ids {
+        query = query.bind(*user_id);
+    }
+    Ok(query.fetch_all(executor).await?)
+}
+
+/// Queue `payload` once per `(delivery_id, subscription_id)` pair, due at
+/// `not_before`, in the caller's transaction.
+pub async fn enqueue_deliveries(
+    tx: &mut DbTransaction,
+    deliveries: &[(i64, i64)],
+    payload: &str,
+    not_before: DateTime<Utc>,
+) -> Result<(), DbError> {
+    for &(id, subscription_id) in deliveries {
+        sqlx::query(
+            "INSERT INTO push_deliveries (id, subscription_id, payload, next_attempt_at)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(id)
+        .bind(subscription_id)
+        .bind(payload)
+        .bind(datetime_to_db_text(not_before))
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Claim up to `limit` due deliveries by pushing their next attempt out to
+/// `lease_until`, so other nodes skip them while this one sends.
+pub async fn claim_due_deliveries(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<PushDeliveryRow>, DbError> {
+    let now = datetime_to_db_text(now);
+    let claimed: Vec<i64> = sqlx::query_scalar(
+        "UPDATE push_deliveries
+         SET next_attempt_at = $2, attempts = attempts + 1
+         WHERE id IN (
+             SELECT id FROM push_deliveries
+             WHERE next_attempt_at <= $1
+             ORDER BY next_attempt_at, id
+             LIMIT $3
+         ) AND next_attempt_at <= $1
+         RETURNING id",
+    )
+    .bind(&now)
+    .bind(datetime_to_db_text(lease_until))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    if claimed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders: Vec<String> = (1..=claimed.len()).map(|i| format!("${}", i)).collect();
+    let sql = format!(
+        "SELECT d.id, d.subscription_id, d.payload, d.attempts, s.endpoint, s.p256dh, s.auth
+         FROM push_deliveries d
+         INNER JOIN push_subscriptions s ON s.id = d.subscription_id
+         WHERE d.id IN ({})
+         ORDER BY d.id",
+        placeholders.join(", ")
+    );
+    let mut query = sqlx::query_as::<_, PushDeliveryRow>(&sql);
+    for id in &claimed {
+        query = query.bind(*id);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
+pub async fn delete_delivery(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM push_deliveries WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn reschedule_delivery(
+    pool: &DbPool,
+    id: i64,
+    next_attempt_at: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query("UPDATE push_deliveries SET next_attempt_at = $2 WHERE id = $1")
+        .bind(id)
+        .bind(datetime_to_db_text(next_attempt_at))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_subscription_used(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("UPDATE push_subscriptions SET last_used_at = datetime('now') WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -31,14 +31,37 @@ pub struct ReactionCountRow {
     pub count: i64,
 }
 
+/// Reaction counts for one message, with whether the viewer reacted.
+#[derive(Debug, Clone)]
+pub struct MessageReactionCountRow {
+    pub message_id: i64,
+    pub emoji_name: String,
+    pub emoji_id: Option<i64>,
+    pub count: i64,
+    pub me: bool,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for MessageReactionCountRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            message_id: row.try_get("message_id")?,
+            emoji_name: row.try_get("emoji_name")?,
+            emoji_id: row.try_get("emoji_id")?,
+            count: row.try_get("count")?,
+            me: bool_from_any_row(row, "me")?,
+        })
+    }
+}
+
+/// Returns false when the user had already reacted with this emoji.
 pub async fn add_reaction(
     pool: &DbPool,
     message_id: i64,
     user_id: i64,
     emoji_name: &str,
     emoji_id: Option<i64>,
-) -> Result<(), DbError> {
-    sqlx::query(
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
         "INSERT INTO reactions (message_id, user_id, emoji_name, emoji_id)
          VALUES ($1, $2, $3, $4)
          ON CONFLICT (message_id, user_id, emoji_name) DO NOTHING",
@@ -49,7 +72,7 @@ pub async fn add_reaction(
     .bind(emoji_id)
     .execute(pool)
     .await?;
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
 pub async fn remove_reaction(
@@ -102,3 +125,90 @@ pub async fn get_reaction_users(
     .await?;
     Ok(rows.into_iter().map(|r| r.0).collect())
 }
+
+/// Aggregated reactions for a page of messages in one query, ordered by
+/// message and then by first use of each emoji.
+pub async fn get_reactions_for_messages(
+    pool: &DbPool,
+    message_ids: &[i64],
+    viewer_id: i64,
+) -> Result<Vec<MessageReactionCountRow>, DbError> {
+    if message_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Safe since values are i64, not user-supplied strings.
+    let placeholders: String = message_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let query = format!(
+        "SELECT message_id, emoji_name, emoji_id, COUNT(*) AS count,
+                MAX(CASE WHEN user_id = $1 THEN 1 ELSE 0 END) AS me
+         FROM reactions
+         WHERE message_id IN ({})
+         GROUP BY message_id, emoji_name, emoji_id
+         ORDER BY message_id, MIN(created_at)",
+        placeholders
+    );
+    let rows = sqlx::query_as::<_, MessageReactionCountRow>(&query)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_get_reactions_for_messages_aggregates_per_message() {
+        let pool = test_pool().await;
+        for (id, name) in [(1, "alice"), (2, "bob")] {
+            crate::users::create_user(&pool, id, name, 1, &format!("{name}@example.com"), "hash")
+                .await
+                .unwrap();
+        }
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 200, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        for id in [300, 301, 302] {
+            crate::messages::create_message(&pool, id, 200, 1, "hi", 0, None)
+                .await
+                .unwrap();
+        }
+        add_reaction(&pool, 300, 1, "👍", None).await.unwrap();
+        add_reaction(&pool, 300, 2, "👍", None).await.unwrap();
+        add_reaction(&pool, 300, 2, "🎉", None).await.unwrap();
+        add_reaction(&pool, 301, 2, "👍", None).await.unwrap();
+
+        let rows = get_reactions_for_messages(&pool, &[300, 301, 302], 1)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        let thumbs = rows
+            .iter()
+            .find(|r| r.message_id == 300 && r.emoji_name == "👍")
+            .unwrap();
+        assert_eq!(thumbs.count, 2);
+        assert!(thumbs.me);
+        assert!(rows
+            .iter()
+            .filter(|r| r.emoji_name != "👍" || r.message_id == 301)
+            .all(|r| !r.me));
+        assert!(get_reactions_for_messages(&pool, &[], 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
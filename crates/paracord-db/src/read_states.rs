@@ -1,4 +1,4 @@
-use crate::{DbError, DbPool};
+use crate::{DbError, DbPool, DbTransaction};
 
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ReadStateRow {
@@ -58,3 +58,78 @@ pub async fn update_read_state(
     .await?;
     Ok(row)
 }
+
+/// Write several `(channel_id, last_message_id)` acks for one user in a
+/// single transaction.
+pub async fn update_read_states(
+    pool: &DbPool,
+    user_id: i64,
+    acks: &[(i64, i64)],
+) -> Result<Vec<ReadStateRow>, DbError> {
+    let mut tx = pool.begin().await?;
+    let mut rows = Vec::with_capacity(acks.len());
+    for &(channel_id, last_message_id) in acks {
+        let row = sqlx::query_as::<_, ReadStateRow>(
+            "INSERT INTO read_states (user_id, channel_id, last_message_id, mention_count)
+             VALUES ($1, $2, $3, 0)
+             ON CONFLICT (user_id, channel_id) DO UPDATE SET last_message_id = $3, mention_count = 0
+             RETURNING user_id, channel_id, last_message_id, mention_count",
+        )
+        .bind(user_id)
+        .bind(channel_id)
+        .bind(last_message_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        rows.push(row);
+    }
+    tx.commit().await?;
+    Ok(rows)
+}
+
+/// Bump `mention_count` for each of `user_ids` in `channel_id`, creating
+/// read states for users who never acked the channel. Each user is counted
+/// once per `message_id`, so repeating a call is harmless; returns the users
+/// counted by this call. Runs in the caller's transaction so the counts can
+/// commit together with the pushes they trigger.
+pub async fn increment_mention_counts(
+    tx: &mut DbTransaction,
+    channel_id: i64,
+    message_id: i64,
+    user_ids: &[i64],
+) -> Result<Vec<i64>, DbError> {
+    let mut counted = Vec::with_capacity(user_ids.len());
+    for &user_id in user_ids {
+        let receipt = sqlx::query(
+            "INSERT INTO mention_receipts (message_id, user_id) VALUES ($1, $2)
+             ON CONFLICT (message_id, user_id) DO NOTHING",
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+        if receipt.rows_affected() == 0 {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO read_states (user_id, channel_id, last_message_id, mention_count)
+             VALUES ($1, $2, 0, 1)
+             ON CONFLICT (user_id, channel_id) DO UPDATE
+                SET mention_count = read_states.mention_count + 1",
+        )
+        .bind(user_id)
+        .bind(channel_id)
+        .execute(&mut **tx)
+        .await?;
+        counted.push(user_id);
+    }
+    Ok(counted)
+}
+
+/// Drop mention receipts for messages older than `before_message_id`.
+pub async fn prune_mention_receipts(pool: &DbPool, before_message_id: i64) -> Result<u64, DbError> {
+    let result = sqlx::query("DELETE FROM mention_receipts WHERE message_id < $1")
+        .bind(before_message_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -188,3 +188,33 @@ pub async fn is_blocked_either_direction(
     .await?;
     Ok(row.is_some())
 }
+
+/// Whether `user_id` has blocked `target_id` (one direction only).
+pub async fn has_blocked(pool: &DbPool, user_id: i64, target_id: i64) -> Result<bool, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT 1
+         FROM relationships
+         WHERE user_id = $1
+           AND target_id = $2
+           AND rel_type = 2
+         LIMIT 1",
+    )
+    .bind(user_id)
+    .bind(target_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+pub async fn get_blocked_user_ids(pool: &DbPool, user_id: i64) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT target_id
+         FROM relationships
+         WHERE user_id = $1
+           AND rel_type = 2",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
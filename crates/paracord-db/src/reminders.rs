@@ -0,0 +1,219 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone)]
+pub struct ReminderRow {
+    pub id: i64,
+    pub user_id: i64,
+    /// Channel to ping when the reminder fires; `None` delivers it to the
+    /// user only.
+    pub channel_id: Option<i64>,
+    /// Message the reminder is about, if any.
+    pub message_id: Option<i64>,
+    pub content: String,
+    pub due_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ReminderRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let due_at_raw: String = row.try_get("due_at")?;
+        let delivered_at_raw: Option<String> = row.try_get("delivered_at")?;
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            channel_id: row.try_get("channel_id")?,
+            message_id: row.try_get("message_id")?,
+            content: row.try_get("content")?,
+            due_at: datetime_from_db_text(&due_at_raw)?,
+            delivered_at: delivered_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+pub async fn create_reminder(
+    pool: &DbPool,
+    id: i64,
+    user_id: i64,
+    channel_id: Option<i64>,
+    message_id: Option<i64>,
+    content: &str,
+    due_at: DateTime<Utc>,
+) -> Result<ReminderRow, DbError> {
+    let row = sqlx::query_as::<_, ReminderRow>(
+        "INSERT INTO reminders (id, user_id, channel_id, message_id, content, due_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, user_id, channel_id, message_id, content, due_at, delivered_at, created_at",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(channel_id)
+    .bind(message_id)
+    .bind(content)
+    .bind(datetime_to_db_text(due_at))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_reminder(pool: &DbPool, id: i64) -> Result<Option<ReminderRow>, DbError> {
+    let row = sqlx::query_as::<_, ReminderRow>(
+        "SELECT id, user_id, channel_id, message_id, content, due_at, delivered_at, created_at
+         FROM reminders WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// A user's undelivered reminders, soonest first.
+pub async fn list_pending_user_reminders(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Vec<ReminderRow>, DbError> {
+    let rows = sqlx::query_as::<_, ReminderRow>(
+        "SELECT id, user_id, channel_id, message_id, content, due_at, delivered_at, created_at
+         FROM reminders
+         WHERE user_id = $1 AND delivered_at IS NULL
+         ORDER BY due_at ASC, id ASC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn count_pending_user_reminders(pool: &DbPool, user_id: i64) -> Result<i64, DbError> {
+    let row: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM reminders WHERE user_id = $1 AND delivered_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// Undelivered reminders due at or before `now`, oldest first.
+pub async fn get_due_reminders(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ReminderRow>, DbError> {
+    let rows = sqlx::query_as::<_, ReminderRow>(
+        "SELECT id, user_id, channel_id, message_id, content, due_at, delivered_at, created_at
+         FROM reminders
+         WHERE delivered_at IS NULL AND due_at <= $1
+         ORDER BY due_at ASC, id ASC
+         LIMIT $2",
+    )
+    .bind(datetime_to_db_text(now))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Claim a reminder for delivery. Returns false when it was already
+/// delivered or cancelled, so each reminder fires once.
+pub async fn mark_reminder_delivered(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE reminders SET delivered_at = datetime('now')
+         WHERE id = $1 AND delivered_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_reminder(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM reminders WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Drop delivered reminders older than `cutoff`.
+pub async fn prune_delivered_reminders(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, DbError> {
+    let result =
+        sqlx::query("DELETE FROM reminders WHERE delivered_at IS NOT NULL AND delivered_at < $1")
+            .bind(datetime_to_db_text(cutoff))
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_due_reminders_fire_once() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "alice", 1, "a@example.com", "hash")
+            .await
+            .unwrap();
+        let now = Utc::now();
+        create_reminder(
+            &pool,
+            10,
+            1,
+            None,
+            None,
+            "later",
+            now + chrono::Duration::hours(1),
+        )
+        .await
+        .unwrap();
+        create_reminder(
+            &pool,
+            11,
+            1,
+            None,
+            None,
+            "now",
+            now - chrono::Duration::minutes(1),
+        )
+        .await
+        .unwrap();
+
+        let pending = list_pending_user_reminders(&pool, 1).await.unwrap();
+        assert_eq!(
+            pending.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![11, 10]
+        );
+
+        let due = get_due_reminders(&pool, now, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].content, "now");
+
+        assert!(mark_reminder_delivered(&pool, 11).await.unwrap());
+        assert!(!mark_reminder_delivered(&pool, 11).await.unwrap());
+        assert!(get_due_reminders(&pool, now, 10).await.unwrap().is_empty());
+        assert_eq!(count_pending_user_reminders(&pool, 1).await.unwrap(), 1);
+
+        let pruned = prune_delivered_reminders(&pool, Utc::now() + chrono::Duration::minutes(1))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+        assert!(get_reminder(&pool, 11).await.unwrap().is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbPool};
+use crate::{bool_from_any_row, datetime_from_db_text, DbError, DbExecutor, DbPool};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -14,6 +14,10 @@ pub struct RoleRow {
     pub managed: bool,
     pub mentionable: bool,
     pub server_wide: bool,
+    /// Members may add and remove this role themselves.
+    pub self_assignable: bool,
+    /// Self-assignable roles in the same group are mutually exclusive.
+    pub self_assign_group: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -31,6 +35,8 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for RoleRow {
             managed: bool_from_any_row(row, "managed")?,
             mentionable: bool_from_any_row(row, "mentionable")?,
             server_wide: bool_from_any_row(row, "server_wide")?,
+            self_assignable: bool_from_any_row(row, "self_assignable")?,
+            self_assign_group: row.try_get("self_assign_group")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
         })
     }
@@ -43,8 +49,8 @@ impl RoleRow {
     }
 }
 
-pub async fn create_role(
-    pool: &DbPool,
+pub async fn create_role<'e>(
+    executor: impl DbExecutor<'e>,
     id: i64,
     space_id: i64,
     name: &str,
@@ -53,21 +59,21 @@ pub async fn create_role(
     let row = sqlx::query_as::<_, RoleRow>(
         "INSERT INTO roles (id, space_id, name, permissions)
          VALUES ($1, $2, $3, $4)
-         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at"
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN self_assignable THEN 1 ELSE 0 END AS self_assignable, self_assign_group, created_at"
     )
     .bind(id)
     .bind(space_id)
     .bind(name)
     .bind(permissions)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
     Ok(row)
 }
 
 pub async fn get_role(pool: &DbPool, id: i64) -> Result<Option<RoleRow>, DbError> {
     let row = sqlx::query_as::<_, RoleRow>(
-        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at
-         FROM roles WHERE id = $1"
+        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN self_assignable THEN 1 ELSE 0 END AS self_assignable, self_assign_group, created_at
+         FROM roles WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -92,7 +98,7 @@ pub async fn update_role(
             permissions = COALESCE($5, permissions),
             mentionable = COALESCE($6, mentionable)
          WHERE id = $1
-         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at"
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN self_assignable THEN 1 ELSE 0 END AS self_assignable, self_assign_group, created_at"
     )
     .bind(id)
     .bind(name)
@@ -105,11 +111,71 @@ pub async fn update_role(
     Ok(row)
 }
 
-pub async fn delete_role(pool: &DbPool, id: i64) -> Result<(), DbError> {
-    sqlx::query("DELETE FROM roles WHERE id = $1")
-        .bind(id)
-        .execute(pool)
+/// Mark a role as self-assignable, or not, and set its exclusive group.
+pub async fn set_role_self_assign(
+    pool: &DbPool,
+    id: i64,
+    self_assignable: bool,
+    group: Option<&str>,
+) -> Result<RoleRow, DbError> {
+    let row = sqlx::query_as::<_, RoleRow>(
+        "UPDATE roles SET self_assignable = $2, self_assign_group = $3
+         WHERE id = $1
+         RETURNING id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN self_assignable THEN 1 ELSE 0 END AS self_assignable, self_assign_group, created_at"
+    )
+    .bind(id)
+    .bind(self_assignable)
+    .bind(group)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Delete a guild role together with what references it: channel permission
+/// overwrites targeting it and its id in channels' `required_role_ids`.
+pub async fn delete_guild_role(pool: &DbPool, guild_id: i64, role_id: i64) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM channel_overwrites
+         WHERE target_id = $1
+           AND target_type = 0
+           AND channel_id IN (SELECT id FROM channels WHERE space_id = $2)",
+    )
+    .bind(role_id)
+    .bind(guild_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let gated: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, required_role_ids FROM channels
+         WHERE space_id = $1 AND required_role_ids != '[]'",
+    )
+    .bind(guild_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for (channel_id, raw) in gated {
+        let role_ids = crate::channels::parse_required_role_ids(&raw);
+        if !role_ids.contains(&role_id) {
+            continue;
+        }
+        let remaining: Vec<i64> = role_ids.into_iter().filter(|id| *id != role_id).collect();
+        sqlx::query("UPDATE channels SET required_role_ids = $2 WHERE id = $1")
+            .bind(channel_id)
+            .bind(crate::channels::serialize_required_role_ids(&remaining))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM member_roles WHERE role_id = $1")
+        .bind(role_id)
+        .execute(&mut *tx)
         .await?;
+    sqlx::query("DELETE FROM roles WHERE id = $1 AND space_id = $2")
+        .bind(role_id)
+        .bind(guild_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
     Ok(())
 }
 
@@ -119,8 +185,8 @@ pub async fn get_guild_roles(pool: &DbPool, space_id: i64) -> Result<Vec<RoleRow
 
 pub async fn get_space_roles(pool: &DbPool, space_id: i64) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
-        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, created_at
-         FROM roles WHERE space_id = $1 ORDER BY position"
+        "SELECT id, space_id, name, color, CASE WHEN hoist THEN 1 ELSE 0 END AS hoist, position, permissions, CASE WHEN managed THEN 1 ELSE 0 END AS managed, CASE WHEN mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN self_assignable THEN 1 ELSE 0 END AS self_assignable, self_assign_group, created_at
+         FROM roles WHERE space_id = $1 AND deleted_at IS NULL ORDER BY position"
     )
     .bind(space_id)
     .fetch_all(pool)
@@ -128,9 +194,74 @@ pub async fn get_space_roles(pool: &DbPool, space_id: i64) -> Result<Vec<RoleRow
     Ok(rows)
 }
 
-/// member_roles no longer has guild_id - just user_id + role_id
-pub async fn add_member_role(
+pub async fn count_guild_roles(pool: &DbPool, space_id: i64) -> Result<i64, DbError> {
+    let count: (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM roles WHERE space_id = $1 AND deleted_at IS NULL")
+            .bind(space_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(count.0)
+}
+
+/// Members assigned `role_id`; the @everyone role (`role_id == space_id`)
+/// returns every member.
+pub async fn get_role_member_ids(
+    pool: &DbPool,
+    space_id: i64,
+    role_id: i64,
+) -> Result<Vec<i64>, DbError> {
+    if role_id == space_id {
+        return crate::members::get_guild_member_user_ids(pool, space_id).await;
+    }
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT mr.user_id FROM member_roles mr
+         INNER JOIN roles r ON r.id = mr.role_id
+         WHERE mr.role_id = $1 AND r.space_id = $2 AND r.deleted_at IS NULL",
+    )
+    .bind(role_id)
+    .bind(space_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Members holding a role, or the @everyone role, that grants any bit in
+/// `mask`.
+pub async fn get_member_ids_with_permissions(
     pool: &DbPool,
+    space_id: i64,
+    mask: i64,
+) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT m.user_id FROM members m
+         WHERE m.guild_id = $1
+           AND (
+                EXISTS (
+                    SELECT 1 FROM roles r
+                    WHERE r.id = $1
+                      AND r.deleted_at IS NULL
+                      AND (r.permissions & $2) != 0
+                )
+                OR EXISTS (
+                    SELECT 1 FROM member_roles mr
+                    INNER JOIN roles r ON r.id = mr.role_id
+                    WHERE mr.user_id = m.user_id
+                      AND r.space_id = $1
+                      AND r.deleted_at IS NULL
+                      AND (r.permissions & $2) != 0
+                )
+           )",
+    )
+    .bind(space_id)
+    .bind(mask)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// member_roles no longer has guild_id - just user_id + role_id
+pub async fn add_member_role<'e>(
+    executor: impl DbExecutor<'e>,
     user_id: i64,
     guild_id: i64,
     role_id: i64,
@@ -142,6 +273,7 @@ pub async fn add_member_role(
              SELECT 1 FROM roles r
              WHERE r.id = $3
                AND r.space_id = $2
+               AND r.deleted_at IS NULL
          )
            AND EXISTS (
              SELECT 1 FROM members m
@@ -153,7 +285,7 @@ pub async fn add_member_role(
     .bind(user_id)
     .bind(guild_id)
     .bind(role_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
@@ -189,12 +321,13 @@ pub async fn get_member_roles(
 ) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
         "SELECT DISTINCT
-            r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.created_at
+            r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN r.self_assignable THEN 1 ELSE 0 END AS self_assignable, r.self_assign_group, r.created_at
          FROM roles r
          LEFT JOIN member_roles mr
             ON mr.role_id = r.id
             AND mr.user_id = $1
          WHERE r.space_id = $2
+           AND r.deleted_at IS NULL
            AND (
                 mr.user_id IS NOT NULL
                 OR (
@@ -217,10 +350,10 @@ pub async fn get_member_roles(
 
 pub async fn get_user_all_roles(pool: &DbPool, user_id: i64) -> Result<Vec<RoleRow>, DbError> {
     let rows = sqlx::query_as::<_, RoleRow>(
-        "SELECT r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, r.created_at
+        "SELECT r.id, r.space_id, r.name, r.color, CASE WHEN r.hoist THEN 1 ELSE 0 END AS hoist, r.position, r.permissions, CASE WHEN r.managed THEN 1 ELSE 0 END AS managed, CASE WHEN r.mentionable THEN 1 ELSE 0 END AS mentionable, CASE WHEN r.server_wide THEN 1 ELSE 0 END AS server_wide, CASE WHEN r.self_assignable THEN 1 ELSE 0 END AS self_assignable, r.self_assign_group, r.created_at
          FROM roles r
          INNER JOIN member_roles mr ON mr.role_id = r.id
-         WHERE mr.user_id = $1
+         WHERE mr.user_id = $1 AND r.deleted_at IS NULL
          ORDER BY r.position"
     )
     .bind(user_id)
@@ -267,6 +400,23 @@ mod tests {
         assert!(!role.mentionable);
     }
 
+    #[tokio::test]
+    async fn test_set_role_self_assign() {
+        let pool = test_pool().await;
+        let (_user_id, guild_id) = setup_guild(&pool).await;
+        create_role(&pool, 503, guild_id, "Red", 0).await.unwrap();
+        let role = set_role_self_assign(&pool, 503, true, Some("colors"))
+            .await
+            .unwrap();
+        assert!(role.self_assignable);
+        assert_eq!(role.self_assign_group.as_deref(), Some("colors"));
+        let listed = get_guild_roles(&pool, guild_id).await.unwrap();
+        assert!(listed.iter().any(|r| r.id == 503 && r.self_assignable));
+        let cleared = set_role_self_assign(&pool, 503, false, None).await.unwrap();
+        assert!(!cleared.self_assignable);
+        assert!(cleared.self_assign_group.is_none());
+    }
+
     #[tokio::test]
     async fn test_get_role() {
         let pool = test_pool().await;
@@ -324,7 +474,7 @@ mod tests {
         let pool = test_pool().await;
         let (_user_id, guild_id) = setup_guild(&pool).await;
         create_role(&pool, 504, guild_id, "Gone", 0).await.unwrap();
-        delete_role(&pool, 504).await.unwrap();
+        delete_guild_role(&pool, guild_id, 504).await.unwrap();
         let role = get_role(&pool, 504).await.unwrap();
         assert!(role.is_none());
     }
@@ -16,6 +16,10 @@ pub struct ScheduledEventRow {
     pub entity_type: i32,
     pub location: Option<String>,
     pub image_url: Option<String>,
+    /// RRULE subset (see `paracord_core::calendar`), `None` for one-off events.
+    pub recurrence_rule: Option<String>,
+    /// Minutes before each occurrence to remind RSVP'd members.
+    pub reminder_minutes: Option<i32>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -43,6 +47,8 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for ScheduledEventRow {
             entity_type: row.try_get("entity_type")?,
             location: row.try_get("location")?,
             image_url: row.try_get("image_url")?,
+            recurrence_rule: row.try_get("recurrence_rule")?,
+            reminder_minutes: row.try_get("reminder_minutes")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
         })
     }
@@ -74,11 +80,13 @@ pub async fn create_event(
     channel_id: Option<i64>,
     location: Option<&str>,
     image_url: Option<&str>,
+    recurrence_rule: Option<&str>,
+    reminder_minutes: Option<i32>,
 ) -> Result<ScheduledEventRow, DbError> {
     let row = sqlx::query_as::<_, ScheduledEventRow>(
-        "INSERT INTO scheduled_events (id, guild_id, creator_id, name, description, scheduled_start, scheduled_end, entity_type, channel_id, location, image_url)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-         RETURNING id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, created_at"
+        "INSERT INTO scheduled_events (id, guild_id, creator_id, name, description, scheduled_start, scheduled_end, entity_type, channel_id, location, image_url, recurrence_rule, reminder_minutes)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+         RETURNING id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at"
     )
     .bind(id)
     .bind(guild_id)
@@ -91,6 +99,8 @@ pub async fn create_event(
     .bind(channel_id)
     .bind(location)
     .bind(image_url)
+    .bind(recurrence_rule)
+    .bind(reminder_minutes)
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -98,7 +108,7 @@ pub async fn create_event(
 
 pub async fn get_event(pool: &DbPool, id: i64) -> Result<Option<ScheduledEventRow>, DbError> {
     let row = sqlx::query_as::<_, ScheduledEventRow>(
-        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, created_at
+        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at
          FROM scheduled_events WHERE id = $1"
     )
     .bind(id)
@@ -112,7 +122,7 @@ pub async fn get_guild_events(
     guild_id: i64,
 ) -> Result<Vec<ScheduledEventRow>, DbError> {
     let rows = sqlx::query_as::<_, ScheduledEventRow>(
-        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, created_at
+        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at
          FROM scheduled_events WHERE guild_id = $1
          ORDER BY scheduled_start ASC"
     )
@@ -122,6 +132,67 @@ pub async fn get_guild_events(
     Ok(rows)
 }
 
+/// Scheduled or active events that have a reminder configured.
+pub async fn get_events_with_reminders(pool: &DbPool) -> Result<Vec<ScheduledEventRow>, DbError> {
+    let rows = sqlx::query_as::<_, ScheduledEventRow>(
+        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at
+         FROM scheduled_events
+         WHERE reminder_minutes IS NOT NULL AND status IN (1, 2)"
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Scheduled (not yet started) events hosted in a voice channel.
+pub async fn get_scheduled_voice_events(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<ScheduledEventRow>, DbError> {
+    let rows = sqlx::query_as::<_, ScheduledEventRow>(
+        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at
+         FROM scheduled_events
+         WHERE channel_id = $1 AND entity_type = 1 AND status = 1"
+    )
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Active events hosted in a voice channel, across all guilds.
+pub async fn get_active_voice_events(pool: &DbPool) -> Result<Vec<ScheduledEventRow>, DbError> {
+    let rows = sqlx::query_as::<_, ScheduledEventRow>(
+        "SELECT id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at
+         FROM scheduled_events
+         WHERE channel_id IS NOT NULL AND entity_type = 1 AND status = 2"
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Move an event to `status` if it is still in `from_status`. Returns `None`
+/// when another writer changed the status first.
+pub async fn transition_event_status(
+    pool: &DbPool,
+    id: i64,
+    from_status: i32,
+    status: i32,
+) -> Result<Option<ScheduledEventRow>, DbError> {
+    let row = sqlx::query_as::<_, ScheduledEventRow>(
+        "UPDATE scheduled_events SET status = $3
+         WHERE id = $1 AND status = $2
+         RETURNING id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at"
+    )
+    .bind(id)
+    .bind(from_status)
+    .bind(status)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn update_event(
     pool: &DbPool,
@@ -134,7 +205,10 @@ pub async fn update_event(
     channel_id: Option<i64>,
     location: Option<&str>,
     image_url: Option<&str>,
+    recurrence_rule: Option<&str>,
+    reminder_minutes: Option<i32>,
 ) -> Result<ScheduledEventRow, DbError> {
+    // An empty rule / zero minutes clears the column; `None` leaves it as is.
     let row = sqlx::query_as::<_, ScheduledEventRow>(
         "UPDATE scheduled_events
          SET name = COALESCE($2, name),
@@ -144,9 +218,11 @@ pub async fn update_event(
              status = COALESCE($6, status),
              channel_id = COALESCE($7, channel_id),
              location = COALESCE($8, location),
-             image_url = COALESCE($9, image_url)
+             image_url = COALESCE($9, image_url),
+             recurrence_rule = NULLIF(COALESCE($10, recurrence_rule), ''),
+             reminder_minutes = NULLIF(COALESCE($11, reminder_minutes), 0)
          WHERE id = $1
-         RETURNING id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, created_at"
+         RETURNING id, guild_id, channel_id, creator_id, name, description, scheduled_start, scheduled_end, status, entity_type, location, image_url, recurrence_rule, reminder_minutes, created_at"
     )
     .bind(id)
     .bind(name)
@@ -157,6 +233,8 @@ pub async fn update_event(
     .bind(channel_id)
     .bind(location)
     .bind(image_url)
+    .bind(recurrence_rule)
+    .bind(reminder_minutes)
     .fetch_one(pool)
     .await?;
     Ok(row)
@@ -221,3 +299,30 @@ pub async fn has_rsvp(pool: &DbPool, event_id: i64, user_id: i64) -> Result<bool
             .await?;
     Ok(row.0 > 0)
 }
+
+/// Record that the reminder for one occurrence went out. Returns `false` if it
+/// had already been sent.
+pub async fn mark_reminder_sent(
+    pool: &DbPool,
+    event_id: i64,
+    occurrence_start: &str,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "INSERT INTO event_reminders_sent (event_id, occurrence_start)
+         VALUES ($1, $2)
+         ON CONFLICT (event_id, occurrence_start) DO NOTHING",
+    )
+    .bind(event_id)
+    .bind(occurrence_start)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn prune_sent_reminders(pool: &DbPool, before: &str) -> Result<u64, DbError> {
+    let result = sqlx::query("DELETE FROM event_reminders_sent WHERE occurrence_start < $1")
+        .bind(before)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
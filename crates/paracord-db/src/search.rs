@@ -0,0 +1,423 @@
+//! Full-text message search.
+//!
+//! SQLite keeps an FTS5 index in `messages_fts`, Postgres a GIN index over
+//! `to_tsvector('simple', content)`. Both are maintained by the database
+//! itself (triggers and an expression index), so every write path that
+//! touches `messages` stays searchable without extra calls. This module
+//! composes the queries: free text plus author, attachment, channel and
+//! ID-range filters, ranked by relevance or newest first.
+
+use paracord_models::message::MESSAGE_FLAG_DM_E2EE;
+
+use crate::messages::MessageRow;
+use crate::{DatabaseEngine, DbError, DbPool};
+
+/// Terms past this are ignored; long pasted queries only slow the index down.
+const MAX_QUERY_TERMS: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Best match first; newest first when there is no text to rank by.
+    #[default]
+    Relevance,
+    Newest,
+    Oldest,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearch {
+    /// Free text; every word must match, the last one as a prefix.
+    pub text: String,
+    /// Channels to search. Empty matches nothing.
+    pub channel_ids: Vec<i64>,
+    pub author_ids: Vec<i64>,
+    /// `Some(true)` for messages with attachments, `Some(false)` without.
+    pub has_attachment: Option<bool>,
+    /// Message IDs (use `snowflake::min_id_at` to bound by time).
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+    pub order: SearchOrder,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Words of `text` as the index sees them: runs of letters and digits.
+/// Everything else, including query syntax, is dropped.
+pub fn search_terms(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(MAX_QUERY_TERMS)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn fts5_query(terms: &[String]) -> String {
+    let last = terms.len().saturating_sub(1);
+    terms
+        .iter()
+        .enumerate()
+        .map(|(i, term)| {
+            if i == last {
+                format!("\"{term}\"*")
+            } else {
+                format!("\"{term}\"")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn tsquery(terms: &[String]) -> String {
+    let last = terms.len().saturating_sub(1);
+    terms
+        .iter()
+        .enumerate()
+        .map(|(i, term)| {
+            if i == last {
+                format!("{term}:*")
+            } else {
+                term.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+enum Bind {
+    Int(i64),
+    Text(String),
+}
+
+/// SQL and binds for `search`. Split out so the composed statement can be
+/// checked for both engines.
+fn build_query(engine: DatabaseEngine, search: &MessageSearch) -> (String, Vec<Bind>) {
+    let terms = search_terms(&search.text);
+    let mut binds = Vec::new();
+    let bind = |value: Bind, binds: &mut Vec<Bind>| {
+        binds.push(value);
+        format!("${}", binds.len())
+    };
+
+    let mut from = String::from("messages m");
+    let mut conditions = Vec::new();
+    let mut rank = None;
+    if !terms.is_empty() {
+        match engine {
+            DatabaseEngine::Sqlite => {
+                from.push_str(" JOIN messages_fts f ON f.rowid = m.id");
+                let p = bind(Bind::Text(fts5_query(&terms)), &mut binds);
+                conditions.push(format!("messages_fts MATCH {p}"));
+                // bm25: lower is better.
+                rank = Some("bm25(messages_fts) ASC".to_string());
+            }
+            DatabaseEngine::Postgres => {
+                let p = bind(Bind::Text(tsquery(&terms)), &mut binds);
+                let vector = "to_tsvector('simple', COALESCE(m.content, ''))";
+                conditions.push(format!("{vector} @@ to_tsquery('simple', {p})"));
+                rank = Some(format!("ts_rank({vector}, to_tsquery('simple', {p})) DESC"));
+            }
+        }
+    }
+
+    let channels: Vec<String> = search
+        .channel_ids
+        .iter()
+        .map(|id| bind(Bind::Int(*id), &mut binds))
+        .collect();
+    if channels.is_empty() {
+        conditions.push("1 = 0".to_string());
+    } else {
+        conditions.push(format!("m.channel_id IN ({})", channels.join(", ")));
+    }
+    if !search.author_ids.is_empty() {
+        let authors: Vec<String> = search
+            .author_ids
+            .iter()
+            .map(|id| bind(Bind::Int(*id), &mut binds))
+            .collect();
+        conditions.push(format!("m.author_id IN ({})", authors.join(", ")));
+    }
+    if let Some(has) = search.has_attachment {
+        let exists = "EXISTS (SELECT 1 FROM attachments a WHERE a.message_id = m.id)";
+        conditions.push(if has {
+            exists.to_string()
+        } else {
+            format!("NOT {exists}")
+        });
+    }
+    if let Some(before) = search.before {
+        let p = bind(Bind::Int(before), &mut binds);
+        conditions.push(format!("m.id < {p}"));
+    }
+    if let Some(after) = search.after {
+        let p = bind(Bind::Int(after), &mut binds);
+        conditions.push(format!("m.id > {p}"));
+    }
+    let flag = bind(Bind::Int(i64::from(MESSAGE_FLAG_DM_E2EE)), &mut binds);
+    conditions.push(format!("(m.flags & {flag}) = 0"));
+
+    let order = match (search.order, rank) {
+        (SearchOrder::Relevance, Some(rank)) => format!("{rank}, m.id DESC"),
+        (SearchOrder::Oldest, _) => "m.id ASC".to_string(),
+        _ => "m.id DESC".to_string(),
+    };
+    let limit = bind(Bind::Int(search.limit), &mut binds);
+    let offset = bind(Bind::Int(search.offset), &mut binds);
+    let sql = format!(
+        "SELECT m.id, m.channel_id, m.author_id, m.content, m.nonce, m.message_type, m.flags, m.edited_at, CASE WHEN m.pinned THEN 1 ELSE 0 END AS pinned, m.reference_id, m.e2ee_header, m.created_at
+         FROM {from}
+         WHERE {}
+         ORDER BY {order}
+         LIMIT {limit} OFFSET {offset}",
+        conditions.join(" AND ")
+    );
+    (sql, binds)
+}
+
+pub async fn search_messages(
+    pool: &DbPool,
+    search: &MessageSearch,
+) -> Result<Vec<MessageRow>, DbError> {
+    let (sql, binds) = build_query(crate::active_database_engine(), search);
+    let mut query = sqlx::query_as::<_, MessageRow>(&sql);
+    for value in binds {
+        query = match value {
+            Bind::Int(value) => query.bind(value),
+            Bind::Text(value) => query.bind(value),
+        };
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
+/// Rebuild the full-text index from `messages`. Postgres' expression index
+/// is rebuilt along with the table's other indexes by `reindex_messages`.
+pub async fn rebuild_search_index(pool: &DbPool) -> Result<(), DbError> {
+    if crate::active_database_engine() == DatabaseEngine::Sqlite {
+        let mut tx = pool.begin().await?;
+        sqlx::query("INSERT INTO messages_fts (messages_fts) VALUES ('delete-all')")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO messages_fts (rowid, content)
+             SELECT id, content FROM messages WHERE content IS NOT NULL AND (flags & $1) = 0",
+        )
+        .bind(MESSAGE_FLAG_DM_E2EE)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("INSERT INTO messages_fts (messages_fts) VALUES ('optimize')")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{
+        create_message, create_message_with_meta, delete_message, update_message,
+    };
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    async fn setup(pool: &DbPool) -> (i64, i64, i64) {
+        crate::users::create_user(pool, 1, "alice", 1, "a@example.com", "hash")
+            .await
+            .unwrap();
+        crate::users::create_user(pool, 2, "bob", 1, "b@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(pool, 200, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(pool, 201, 100, "random", 0, 1, None, None)
+            .await
+            .unwrap();
+        (1, 2, 200)
+    }
+
+    fn search(text: &str, channel_ids: &[i64]) -> MessageSearch {
+        MessageSearch {
+            text: text.to_string(),
+            channel_ids: channel_ids.to_vec(),
+            limit: 25,
+            ..Default::default()
+        }
+    }
+
+    async fn ids(pool: &DbPool, search: &MessageSearch) -> Vec<i64> {
+        search_messages(pool, search)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect()
+    }
+
+    #[test]
+    fn query_syntax_is_stripped() {
+        assert_eq!(
+            search_terms("Hello, \"wörld\" OR NEAR(x*)"),
+            ["hello", "wörld", "or", "near", "x"]
+        );
+        assert_eq!(fts5_query(&search_terms("rust lang")), "\"rust\" \"lang\"*");
+        assert_eq!(tsquery(&search_terms("rust lang")), "rust & lang:*");
+    }
+
+    #[test]
+    fn postgres_query_uses_the_indexed_expression() {
+        let (sql, binds) = build_query(
+            DatabaseEngine::Postgres,
+            &MessageSearch {
+                has_attachment: Some(true),
+                author_ids: vec![7],
+                ..search("hello", &[1, 2])
+            },
+        );
+        assert!(sql.contains(
+            "to_tsvector('simple', COALESCE(m.content, '')) @@ to_tsquery('simple', $1)"
+        ));
+        assert!(sql.contains("m.channel_id IN ($2, $3)"));
+        assert!(sql.contains("m.author_id IN ($4)"));
+        assert!(sql.contains("ts_rank("));
+        assert_eq!(binds.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn index_follows_creates_edits_and_deletes() {
+        let pool = test_pool().await;
+        let (alice, _, channel) = setup(&pool).await;
+        create_message(&pool, 1000, channel, alice, "the quick brown fox", 0, None)
+            .await
+            .unwrap();
+        create_message(&pool, 1001, channel, alice, "lazy dogs", 0, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(&pool, &search("quick", &[channel])).await, [1000]);
+        assert_eq!(ids(&pool, &search("qui", &[channel])).await, [1000]);
+
+        update_message(&pool, 1000, "a slow turtle").await.unwrap();
+        assert!(ids(&pool, &search("quick", &[channel])).await.is_empty());
+        assert_eq!(ids(&pool, &search("turtle", &[channel])).await, [1000]);
+
+        delete_message(&pool, 1001).await.unwrap();
+        assert!(ids(&pool, &search("dogs", &[channel])).await.is_empty());
+
+        // Encrypted DMs never reach the index.
+        create_message_with_meta(
+            &pool,
+            1002,
+            channel,
+            alice,
+            "secret turtle",
+            0,
+            None,
+            1,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(&pool, &search("turtle", &[channel])).await, [1000]);
+
+        rebuild_search_index(&pool).await.unwrap();
+        assert_eq!(ids(&pool, &search("turtle", &[channel])).await, [1000]);
+    }
+
+    #[tokio::test]
+    async fn filters_and_ranking() {
+        let pool = test_pool().await;
+        let (alice, bob, channel) = setup(&pool).await;
+        create_message(
+            &pool,
+            2000,
+            channel,
+            alice,
+            "release release notes",
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        create_message(
+            &pool,
+            2001,
+            channel,
+            bob,
+            "release day was long and quiet",
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        create_message(&pool, 2002, 201, bob, "release elsewhere", 0, None)
+            .await
+            .unwrap();
+        create_message(&pool, 2003, channel, bob, "see attached", 0, None)
+            .await
+            .unwrap();
+        crate::attachments::create_attachment(
+            &pool,
+            3000,
+            Some(2003),
+            "a.png",
+            None,
+            1,
+            "/a",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // The message that says "release" twice ranks first.
+        assert_eq!(
+            ids(&pool, &search("release", &[channel])).await,
+            [2000, 2001]
+        );
+        let newest = MessageSearch {
+            order: SearchOrder::Newest,
+            ..search("release", &[channel, 201])
+        };
+        assert_eq!(ids(&pool, &newest).await, [2002, 2001, 2000]);
+
+        let by_bob = MessageSearch {
+            author_ids: vec![bob],
+            ..search("", &[channel])
+        };
+        assert_eq!(ids(&pool, &by_bob).await, [2003, 2001]);
+
+        let with_files = MessageSearch {
+            has_attachment: Some(true),
+            ..search("", &[channel, 201])
+        };
+        assert_eq!(ids(&pool, &with_files).await, [2003]);
+
+        let window = MessageSearch {
+            after: Some(2000),
+            before: Some(2003),
+            ..search("", &[channel, 201])
+        };
+        assert_eq!(ids(&pool, &window).await, [2002, 2001]);
+
+        let paged = MessageSearch {
+            limit: 1,
+            offset: 1,
+            ..newest
+        };
+        assert_eq!(ids(&pool, &paged).await, [2001]);
+        assert!(ids(&pool, &search("release", &[])).await.is_empty());
+    }
+}
@@ -25,6 +25,10 @@ pub struct AuthSessionRow {
     pub expires_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub revoked_reason: Option<String>,
+    /// When the device was approved for gateway access; `None` until then.
+    pub device_approved_at: Option<DateTime<Utc>>,
+    /// When an approval prompt was last sent to the user's other sessions.
+    pub device_approval_requested_at: Option<DateTime<Utc>>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for AuthSessionRow {
@@ -33,6 +37,9 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for AuthSessionRow {
         let last_seen_at_raw: String = row.try_get("last_seen_at")?;
         let expires_at_raw: String = row.try_get("expires_at")?;
         let revoked_at_raw: Option<String> = row.try_get("revoked_at")?;
+        let device_approved_at_raw: Option<String> = row.try_get("device_approved_at")?;
+        let device_approval_requested_at_raw: Option<String> =
+            row.try_get("device_approval_requested_at")?;
         Ok(Self {
             id: row.try_get("id")?,
             user_id: row.try_get("user_id")?,
@@ -50,6 +57,14 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for AuthSessionRow {
                 .map(datetime_from_db_text)
                 .transpose()?,
             revoked_reason: row.try_get("revoked_reason")?,
+            device_approved_at: device_approved_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            device_approval_requested_at: device_approval_requested_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
         })
     }
 }
@@ -109,7 +124,8 @@ pub async fn create_session(
          )
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
          RETURNING id, user_id, refresh_token_hash, current_jti, pub_key, device_id, user_agent, ip_address,
-                   issued_at, last_seen_at, expires_at, revoked_at, revoked_reason",
+                   issued_at, last_seen_at, expires_at, revoked_at, revoked_reason,
+                device_approved_at, device_approval_requested_at",
     )
     .bind(id)
     .bind(user_id)
@@ -131,7 +147,8 @@ pub async fn get_session_by_refresh_hash(
 ) -> Result<Option<AuthSessionRow>, DbError> {
     let row = sqlx::query_as::<_, AuthSessionRow>(
         "SELECT id, user_id, refresh_token_hash, current_jti, pub_key, device_id, user_agent, ip_address,
-                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason
+                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason,
+                device_approved_at, device_approval_requested_at
          FROM auth_sessions
          WHERE refresh_token_hash = $1",
     )
@@ -147,7 +164,8 @@ pub async fn get_session_by_id(
 ) -> Result<Option<AuthSessionRow>, DbError> {
     let row = sqlx::query_as::<_, AuthSessionRow>(
         "SELECT id, user_id, refresh_token_hash, current_jti, pub_key, device_id, user_agent, ip_address,
-                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason
+                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason,
+                device_approved_at, device_approval_requested_at
          FROM auth_sessions
          WHERE id = $1",
     )
@@ -164,7 +182,8 @@ pub async fn list_user_sessions(
 ) -> Result<Vec<AuthSessionRow>, DbError> {
     let rows = sqlx::query_as::<_, AuthSessionRow>(
         "SELECT id, user_id, refresh_token_hash, current_jti, pub_key, device_id, user_agent, ip_address,
-                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason
+                issued_at, last_seen_at, expires_at, revoked_at, revoked_reason,
+                device_approved_at, device_approval_requested_at
          FROM auth_sessions
          WHERE user_id = $1
            AND revoked_at IS NULL
@@ -319,6 +338,171 @@ pub async fn is_access_token_active(
     Ok(row.is_some())
 }
 
+/// Approve the device behind a pending session. Returns `false` when the
+/// session is gone, revoked, or already approved.
+pub async fn approve_session_device(
+    pool: &DbPool,
+    session_id: &str,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE auth_sessions
+         SET device_approved_at = $3
+         WHERE id = $1
+           AND user_id = $2
+           AND revoked_at IS NULL
+           AND device_approved_at IS NULL",
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Approve every active session of a user, used when device approval is
+/// switched on so the devices already in use stay signed in.
+pub async fn approve_all_user_session_devices(
+    pool: &DbPool,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> Result<u64, DbError> {
+    let result = sqlx::query(
+        "UPDATE auth_sessions
+         SET device_approved_at = $2
+         WHERE user_id = $1
+           AND revoked_at IS NULL
+           AND expires_at > $2
+           AND device_approved_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn mark_device_approval_requested(
+    pool: &DbPool,
+    session_id: &str,
+    now: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "UPDATE auth_sessions
+         SET device_approval_requested_at = $2
+         WHERE id = $1",
+    )
+    .bind(session_id)
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Active sessions of a user with an approved device, other than
+/// `except_session_id`.
+pub async fn count_approved_device_sessions(
+    pool: &DbPool,
+    user_id: i64,
+    except_session_id: &str,
+    now: DateTime<Utc>,
+) -> Result<i64, DbError> {
+    let row: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*)
+         FROM auth_sessions
+         WHERE user_id = $1
+           AND id != $2
+           AND revoked_at IS NULL
+           AND expires_at > $3
+           AND device_approved_at IS NOT NULL",
+    )
+    .bind(user_id)
+    .bind(except_session_id)
+    .bind(datetime_to_db_text(now))
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// Short, non-reversible label for a device id, for showing a device to the
+/// user without revealing the id that approves it.
+pub fn device_fingerprint(device_id: &str) -> String {
+    crate::sha256_hex(device_id)[..12].to_string()
+}
+
+/// Remember `device_id` as approved for `user_id`, so later sessions from
+/// the same device are approved without asking.
+pub async fn remember_approved_device(
+    pool: &DbPool,
+    user_id: i64,
+    device_id: &str,
+    now: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO approved_devices (user_id, device_hash, approved_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, device_hash) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(crate::sha256_hex(device_id))
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn is_device_approved(
+    pool: &DbPool,
+    user_id: i64,
+    device_id: &str,
+) -> Result<bool, DbError> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT user_id FROM approved_devices WHERE user_id = $1 AND device_hash = $2",
+    )
+    .bind(user_id)
+    .bind(crate::sha256_hex(device_id))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+/// Replace the user's device recovery code. Only its hash is stored.
+pub async fn set_device_recovery_code(
+    pool: &DbPool,
+    user_id: i64,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO device_recovery_codes (user_id, code_hash, created_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id) DO UPDATE SET code_hash = $2, created_at = $3",
+    )
+    .bind(user_id)
+    .bind(crate::sha256_hex(code))
+    .bind(datetime_to_db_text(now))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Use up the user's device recovery code if `code` matches it.
+pub async fn consume_device_recovery_code(
+    pool: &DbPool,
+    user_id: i64,
+    code: &str,
+) -> Result<bool, DbError> {
+    let result =
+        sqlx::query("DELETE FROM device_recovery_codes WHERE user_id = $1 AND code_hash = $2")
+            .bind(user_id)
+            .bind(crate::sha256_hex(code.trim()))
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 pub async fn purge_expired_sessions(
     pool: &DbPool,
     now: DateTime<Utc>,
@@ -403,4 +587,40 @@ mod tests {
             .expect("revoked active check");
         assert!(!inactive_revoked);
     }
+
+    #[tokio::test]
+    async fn approved_devices_and_recovery_codes() {
+        let db = setup_db().await;
+        let user =
+            crate::users::create_user(&db, 7002, "devices", 1, "devices@example.com", "hash")
+                .await
+                .expect("create user");
+        let now = Utc::now();
+
+        assert!(!is_device_approved(&db, user.id, "device-1").await.unwrap());
+        remember_approved_device(&db, user.id, "device-1", now)
+            .await
+            .expect("remember device");
+        remember_approved_device(&db, user.id, "device-1", now)
+            .await
+            .expect("remembering twice");
+        assert!(is_device_approved(&db, user.id, "device-1").await.unwrap());
+        assert!(!is_device_approved(&db, user.id, "device-2").await.unwrap());
+
+        set_device_recovery_code(&db, user.id, "first", now)
+            .await
+            .expect("set code");
+        set_device_recovery_code(&db, user.id, "second", now)
+            .await
+            .expect("replace code");
+        assert!(!consume_device_recovery_code(&db, user.id, "first")
+            .await
+            .unwrap());
+        assert!(consume_device_recovery_code(&db, user.id, "second")
+            .await
+            .unwrap());
+        assert!(!consume_device_recovery_code(&db, user.id, "second")
+            .await
+            .unwrap());
+    }
 }
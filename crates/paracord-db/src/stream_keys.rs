@@ -0,0 +1,113 @@
+use crate::{DbError, DbPool};
+use sqlx::Row;
+
+/// A per-channel key that lets an external encoder (OBS) publish over WHIP.
+/// Only the SHA-256 of the key is stored.
+#[derive(Debug, Clone)]
+pub struct StreamKeyRow {
+    pub id: i64,
+    pub channel_id: i64,
+    pub guild_id: i64,
+    /// User the key publishes on behalf of.
+    pub user_id: i64,
+    pub key_hash: String,
+    pub name: Option<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for StreamKeyRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            channel_id: row.try_get("channel_id")?,
+            guild_id: row.try_get("guild_id")?,
+            user_id: row.try_get("user_id")?,
+            key_hash: row.try_get("key_hash")?,
+            name: row.try_get("name")?,
+            created_at: row.try_get("created_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+        })
+    }
+}
+
+pub async fn create_stream_key(
+    pool: &DbPool,
+    id: i64,
+    channel_id: i64,
+    guild_id: i64,
+    user_id: i64,
+    key_hash: &str,
+    name: Option<&str>,
+) -> Result<StreamKeyRow, DbError> {
+    let row = sqlx::query_as::<_, StreamKeyRow>(
+        "INSERT INTO channel_stream_keys (id, channel_id, guild_id, user_id, key_hash, name)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, channel_id, guild_id, user_id, key_hash, name, created_at, last_used_at",
+    )
+    .bind(id)
+    .bind(channel_id)
+    .bind(guild_id)
+    .bind(user_id)
+    .bind(key_hash)
+    .bind(name)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_stream_key(pool: &DbPool, id: i64) -> Result<Option<StreamKeyRow>, DbError> {
+    let row = sqlx::query_as::<_, StreamKeyRow>(
+        "SELECT id, channel_id, guild_id, user_id, key_hash, name, created_at, last_used_at
+         FROM channel_stream_keys WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_stream_key_by_hash(
+    pool: &DbPool,
+    key_hash: &str,
+) -> Result<Option<StreamKeyRow>, DbError> {
+    let row = sqlx::query_as::<_, StreamKeyRow>(
+        "SELECT id, channel_id, guild_id, user_id, key_hash, name, created_at, last_used_at
+         FROM channel_stream_keys WHERE key_hash = $1",
+    )
+    .bind(key_hash)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_channel_stream_keys(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<StreamKeyRow>, DbError> {
+    let rows = sqlx::query_as::<_, StreamKeyRow>(
+        "SELECT id, channel_id, guild_id, user_id, key_hash, name, created_at, last_used_at
+         FROM channel_stream_keys WHERE channel_id = $1
+         ORDER BY id",
+    )
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn touch_stream_key(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("UPDATE channel_stream_keys SET last_used_at = datetime('now') WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_stream_key(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM channel_stream_keys WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A user granted supporter perks directly. Role-based supporters are not
+/// listed here; see [`is_supporter`].
+#[derive(Debug, Clone)]
+pub struct SupporterRow {
+    pub user_id: i64,
+    /// `manual`, `kofi` or `patreon`.
+    pub source: String,
+    pub note: Option<String>,
+    /// `None` never expires.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for SupporterRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let expires_at_raw: Option<String> = row.try_get("expires_at")?;
+        let created_at_raw: String = row.try_get("created_at")?;
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            source: row.try_get("source")?,
+            note: row.try_get("note")?,
+            expires_at: expires_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+const SELECT_COLS: &str = "user_id, source, note, expires_at, created_at, updated_at";
+
+/// Grant or replace a user's supporter status.
+pub async fn upsert_supporter(
+    pool: &DbPool,
+    user_id: i64,
+    source: &str,
+    note: Option<&str>,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<SupporterRow, DbError> {
+    let row = sqlx::query_as::<_, SupporterRow>(&format!(
+        "INSERT INTO supporters (user_id, source, note, expires_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (user_id) DO UPDATE SET
+             source = EXCLUDED.source,
+             note = EXCLUDED.note,
+             expires_at = EXCLUDED.expires_at,
+             updated_at = datetime('now')
+         RETURNING {SELECT_COLS}"
+    ))
+    .bind(user_id)
+    .bind(source)
+    .bind(note)
+    .bind(expires_at.map(datetime_to_db_text))
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_supporter(pool: &DbPool, user_id: i64) -> Result<Option<SupporterRow>, DbError> {
+    let row = sqlx::query_as::<_, SupporterRow>(&format!(
+        "SELECT {SELECT_COLS} FROM supporters WHERE user_id = $1"
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Direct grants, newest first, including expired ones.
+pub async fn list_supporters(pool: &DbPool) -> Result<Vec<SupporterRow>, DbError> {
+    let rows = sqlx::query_as::<_, SupporterRow>(&format!(
+        "SELECT {SELECT_COLS} FROM supporters ORDER BY created_at DESC, user_id DESC"
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Returns whether a grant was removed.
+pub async fn delete_supporter(pool: &DbPool, user_id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM supporters WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn add_supporter_role(pool: &DbPool, role_id: i64) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO supporter_roles (role_id) VALUES ($1) ON CONFLICT (role_id) DO NOTHING",
+    )
+    .bind(role_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Returns whether the role was marked.
+pub async fn remove_supporter_role(pool: &DbPool, role_id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM supporter_roles WHERE role_id = $1")
+        .bind(role_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Supporter roles as `(role_id, guild_id)`.
+pub async fn list_supporter_roles(pool: &DbPool) -> Result<Vec<(i64, i64)>, DbError> {
+    let rows = sqlx::query(
+        "SELECT sr.role_id, r.space_id
+         FROM supporter_roles sr
+         JOIN roles r ON r.id = sr.role_id
+         ORDER BY sr.role_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("role_id")?, row.try_get("space_id")?)))
+        .collect()
+}
+
+/// Whether `user_id` has an unexpired grant or holds a supporter role.
+pub async fn is_supporter(
+    pool: &DbPool,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> Result<bool, DbError> {
+    let row = sqlx::query(
+        "SELECT 1 AS hit FROM supporters
+         WHERE user_id = $1 AND (expires_at IS NULL OR expires_at > $2)
+         UNION ALL
+         SELECT 1 AS hit FROM member_roles mr
+         JOIN supporter_roles sr ON sr.role_id = mr.role_id
+         JOIN roles r ON r.id = mr.role_id
+         WHERE mr.user_id = $1 AND r.deleted_at IS NULL
+         LIMIT 1",
+    )
+    .bind(user_id)
+    .bind(datetime_to_db_text(now))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_grants_expiry_and_roles() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::users::create_user(&pool, 2, "member", 1, "m@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        let now = Utc::now();
+        assert!(!is_supporter(&pool, 1, now).await.unwrap());
+
+        upsert_supporter(&pool, 1, "kofi", None, Some(now + Duration::days(30)))
+            .await
+            .unwrap();
+        assert!(is_supporter(&pool, 1, now).await.unwrap());
+        assert!(!is_supporter(&pool, 1, now + Duration::days(31))
+            .await
+            .unwrap());
+        let row = upsert_supporter(&pool, 1, "manual", Some("thanks"), None)
+            .await
+            .unwrap();
+        assert_eq!(row.source, "manual");
+        assert!(row.expires_at.is_none());
+        assert_eq!(list_supporters(&pool).await.unwrap().len(), 1);
+        assert!(delete_supporter(&pool, 1).await.unwrap());
+        assert!(!is_supporter(&pool, 1, now).await.unwrap());
+
+        crate::roles::create_role(&pool, 500, 100, "Patrons", 0)
+            .await
+            .unwrap();
+        crate::members::add_member(&pool, 2, 100).await.unwrap();
+        crate::roles::add_member_role(&pool, 2, 100, 500)
+            .await
+            .unwrap();
+        assert!(!is_supporter(&pool, 2, now).await.unwrap());
+        add_supporter_role(&pool, 500).await.unwrap();
+        add_supporter_role(&pool, 500).await.unwrap();
+        assert_eq!(list_supporter_roles(&pool).await.unwrap(), vec![(500, 100)]);
+        assert!(is_supporter(&pool, 2, now).await.unwrap());
+        assert!(remove_supporter_role(&pool, 500).await.unwrap());
+        assert!(!is_supporter(&pool, 2, now).await.unwrap());
+    }
+}
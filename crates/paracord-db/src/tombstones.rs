@@ -0,0 +1,372 @@
+//! Soft-deleted guilds, channels and roles.
+//!
+//! Deleting one of these sets `deleted_at` instead of removing the row.
+//! Regular reads skip such rows, so they look deleted to everyone until a
+//! server admin restores them or the purge job removes them for good.
+
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbExecutor, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TombstoneKind {
+    Guild,
+    Channel,
+    Role,
+}
+
+impl TombstoneKind {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "guild" => Some(Self::Guild),
+            "channel" => Some(Self::Channel),
+            "role" => Some(Self::Role),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Guild => "guild",
+            Self::Channel => "channel",
+            Self::Role => "role",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TombstoneRow {
+    pub kind: TombstoneKind,
+    pub id: i64,
+    /// Owning guild; the guild's own id for guild tombstones.
+    pub guild_id: i64,
+    pub name: Option<String>,
+    /// Set for threads, which are deleted and restored with their parent.
+    pub parent_id: Option<i64>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for TombstoneRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let kind_raw: String = row.try_get("kind")?;
+        let deleted_at_raw: String = row.try_get("deleted_at")?;
+        Ok(Self {
+            kind: TombstoneKind::parse(&kind_raw).ok_or_else(|| sqlx::Error::ColumnDecode {
+                index: "kind".into(),
+                source: format!("unknown tombstone kind '{kind_raw}'").into(),
+            })?,
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            name: row.try_get("name")?,
+            parent_id: row.try_get("parent_id")?,
+            deleted_at: datetime_from_db_text(&deleted_at_raw)?,
+        })
+    }
+}
+
+const TOMBSTONES_SQL: &str =
+    "SELECT 'guild' AS kind, id, id AS guild_id, name, NULL AS parent_id, deleted_at
+     FROM spaces WHERE deleted_at IS NOT NULL
+     UNION ALL
+     SELECT 'channel' AS kind, id, space_id AS guild_id, name,
+            CASE WHEN channel_type = 6 THEN parent_id END AS parent_id, deleted_at
+     FROM channels WHERE deleted_at IS NOT NULL AND space_id IS NOT NULL
+     UNION ALL
+     SELECT 'role' AS kind, id, space_id AS guild_id, name, NULL AS parent_id, deleted_at
+     FROM roles WHERE deleted_at IS NOT NULL";
+
+/// Tombstone a guild. Its channels, roles and members are left alone and
+/// come back with it. Returns the deletion time, or `None` if the guild
+/// doesn't exist or is already deleted.
+pub async fn soft_delete_guild(pool: &DbPool, id: i64) -> Result<Option<DateTime<Utc>>, DbError> {
+    let deleted_at = Utc::now();
+    let result =
+        sqlx::query("UPDATE spaces SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .bind(datetime_to_db_text(deleted_at))
+            .execute(pool)
+            .await?;
+    Ok((result.rows_affected() > 0).then_some(deleted_at))
+}
+
+/// Tombstone a channel along with its threads. Channels in a deleted
+/// category are left alone. Returns the deletion time and the ids of the
+/// threads deleted with the channel.
+pub async fn soft_delete_channel(
+    pool: &DbPool,
+    id: i64,
+) -> Result<Option<(DateTime<Utc>, Vec<i64>)>, DbError> {
+    let now = Utc::now();
+    let deleted_at = datetime_to_db_text(now);
+    let mut tx = pool.begin().await?;
+    let result =
+        sqlx::query("UPDATE channels SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .bind(&deleted_at)
+            .execute(&mut *tx)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    sqlx::query(
+        "UPDATE channels SET deleted_at = $2
+         WHERE parent_id = $1 AND channel_type = 6 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .bind(&deleted_at)
+    .execute(&mut *tx)
+    .await?;
+    let thread_ids = threads_deleted_with(&mut *tx, id).await?;
+    tx.commit().await?;
+    Ok(Some((now, thread_ids)))
+}
+
+/// Threads that were tombstoned together with channel `id`.
+pub async fn threads_deleted_with<'e>(
+    executor: impl DbExecutor<'e>,
+    id: i64,
+) -> Result<Vec<i64>, DbError> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT id FROM channels
+         WHERE parent_id = $1 AND channel_type = 6
+           AND deleted_at = (SELECT deleted_at FROM channels WHERE id = $1)
+         ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(executor)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Tombstone a role. Role assignments are kept so a restore gives the
+/// role back to the same members.
+pub async fn soft_delete_role(
+    pool: &DbPool,
+    guild_id: i64,
+    role_id: i64,
+) -> Result<Option<DateTime<Utc>>, DbError> {
+    let deleted_at = Utc::now();
+    let result = sqlx::query(
+        "UPDATE roles SET deleted_at = $3
+         WHERE id = $1 AND space_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(role_id)
+    .bind(guild_id)
+    .bind(datetime_to_db_text(deleted_at))
+    .execute(pool)
+    .await?;
+    Ok((result.rows_affected() > 0).then_some(deleted_at))
+}
+
+pub async fn get_tombstone(
+    pool: &DbPool,
+    kind: TombstoneKind,
+    id: i64,
+) -> Result<Option<TombstoneRow>, DbError> {
+    let row = sqlx::query_as::<_, TombstoneRow>(&format!(
+        "SELECT kind, id, guild_id, name, parent_id, deleted_at
+         FROM ({TOMBSTONES_SQL}) t
+         WHERE kind = $1 AND id = $2"
+    ))
+    .bind(kind.as_str())
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Deleted guilds, channels and roles, newest first. Threads deleted with
+/// their parent channel are left out.
+pub async fn list_tombstones(pool: &DbPool) -> Result<Vec<TombstoneRow>, DbError> {
+    let rows = sqlx::query_as::<_, TombstoneRow>(&format!(
+        "SELECT kind, id, guild_id, name, parent_id, deleted_at
+         FROM ({TOMBSTONES_SQL}) t
+         WHERE NOT EXISTS (
+             SELECT 1 FROM channels p
+             WHERE t.kind = 'channel' AND p.id = t.parent_id AND p.deleted_at = t.deleted_at
+         )
+         ORDER BY deleted_at DESC, id DESC"
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Tombstones deleted before `cutoff`, with threads ahead of channels so
+/// children are purged before their parent.
+pub async fn list_expired_tombstones(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<TombstoneRow>, DbError> {
+    let rows = sqlx::query_as::<_, TombstoneRow>(&format!(
+        "SELECT kind, id, guild_id, name, parent_id, deleted_at
+         FROM ({TOMBSTONES_SQL}) t
+         WHERE deleted_at < $1
+         ORDER BY CASE WHEN parent_id IS NULL THEN 1 ELSE 0 END, deleted_at ASC, id ASC
+         LIMIT $2"
+    ))
+    .bind(datetime_to_db_text(cutoff))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Permanently delete a tombstoned channel. Channels still filed under it
+/// (a deleted category) are moved out of it first.
+pub async fn purge_channel(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE channels SET parent_id = NULL WHERE parent_id = $1 AND channel_type <> 6")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM channels WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Clear a tombstone. Restoring a channel also restores the threads that
+/// were deleted with it.
+pub async fn restore(pool: &DbPool, kind: TombstoneKind, id: i64) -> Result<bool, DbError> {
+    let restored = match kind {
+        TombstoneKind::Guild => {
+            sqlx::query(
+                "UPDATE spaces SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            )
+            .bind(id)
+            .execute(pool)
+            .await?
+        }
+        TombstoneKind::Role => {
+            sqlx::query(
+                "UPDATE roles SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            )
+            .bind(id)
+            .execute(pool)
+            .await?
+        }
+        TombstoneKind::Channel => {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "UPDATE channels SET deleted_at = NULL
+                 WHERE parent_id = $1 AND channel_type = 6
+                   AND deleted_at = (SELECT deleted_at FROM channels WHERE id = $1)",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            let result = sqlx::query(
+                "UPDATE channels SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            result
+        }
+    };
+    Ok(restored.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> DbPool {
+        let pool = crate::create_pool("sqlite::memory:", 1).await.unwrap();
+        crate::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_hide_and_restore_rows() {
+        let pool = test_pool().await;
+        crate::users::create_user(&pool, 1, "owner", 1, "o@example.com", "hash")
+            .await
+            .unwrap();
+        crate::guilds::create_guild(&pool, 100, "Guild", 1, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 200, 100, "general", 0, 0, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 201, 100, "thread", 6, 0, Some(200), None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 210, 100, "topics", 4, 0, None, None)
+            .await
+            .unwrap();
+        crate::channels::create_channel(&pool, 211, 100, "in-category", 0, 0, Some(210), None)
+            .await
+            .unwrap();
+        crate::roles::create_role(&pool, 300, 100, "mods", 0)
+            .await
+            .unwrap();
+
+        let (_, threads) = soft_delete_channel(&pool, 200).await.unwrap().unwrap();
+        assert_eq!(threads, vec![201]);
+        assert!(soft_delete_channel(&pool, 200).await.unwrap().is_none());
+        assert!(crate::channels::get_channel(&pool, 201)
+            .await
+            .unwrap()
+            .is_none());
+        let (_, children) = soft_delete_channel(&pool, 210).await.unwrap().unwrap();
+        assert!(children.is_empty(), "only threads go with their parent");
+        assert!(crate::channels::get_channel(&pool, 211)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(soft_delete_role(&pool, 100, 300).await.unwrap().is_some());
+        assert!(crate::roles::get_role(&pool, 300).await.unwrap().is_none());
+
+        let listed = list_tombstones(&pool).await.unwrap();
+        let ids: Vec<i64> = listed.iter().map(|row| row.id).collect();
+        assert_eq!(listed.len(), 3, "threads are listed with their parent");
+        assert!(ids.contains(&200) && ids.contains(&210) && ids.contains(&300));
+        let category = get_tombstone(&pool, TombstoneKind::Channel, 210)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(category.parent_id, None);
+
+        assert_eq!(threads_deleted_with(&pool, 200).await.unwrap(), vec![201]);
+        assert!(restore(&pool, TombstoneKind::Channel, 200).await.unwrap());
+        assert!(restore(&pool, TombstoneKind::Channel, 210).await.unwrap());
+        assert!(crate::channels::get_channel(&pool, 201)
+            .await
+            .unwrap()
+            .is_some());
+
+        assert!(soft_delete_guild(&pool, 100).await.unwrap().is_some());
+        assert!(crate::guilds::get_guild(&pool, 100)
+            .await
+            .unwrap()
+            .is_none());
+        let expired = list_expired_tombstones(&pool, Utc::now() + chrono::Duration::hours(1), 10)
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 2);
+        assert!(
+            list_expired_tombstones(&pool, Utc::now() - chrono::Duration::hours(1), 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(soft_delete_channel(&pool, 210).await.unwrap().is_some());
+        purge_channel(&pool, 210).await.unwrap();
+        let moved = crate::channels::get_channel(&pool, 211)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.parent_id, None);
+        let guild = get_tombstone(&pool, TombstoneKind::Guild, 100)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(guild.name.as_deref(), Some("Guild"));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A domain an admin added to the URL denylist by hand. Feed entries are
+/// not stored here; they live in the server's on-disk feed cache.
+#[derive(Debug, Clone)]
+pub struct UrlDenylistRow {
+    pub domain: String,
+    pub reason: Option<String>,
+    /// `None` once the admin's account has been deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UrlDenylistRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let created_at_raw: String = row.try_get("created_at")?;
+        Ok(Self {
+            domain: row.try_get("domain")?,
+            reason: row.try_get("reason")?,
+            created_by: row.try_get("created_by")?,
+            created_at: datetime_from_db_text(&created_at_raw)?,
+        })
+    }
+}
+
+pub async fn list_entries(pool: &DbPool) -> Result<Vec<UrlDenylistRow>, DbError> {
+    let rows = sqlx::query_as::<_, UrlDenylistRow>(
+        "SELECT domain, reason, created_by, created_at
+         FROM url_denylist ORDER BY domain",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Add or re-annotate a domain.
+pub async fn upsert_entry(
+    pool: &DbPool,
+    domain: &str,
+    reason: Option<&str>,
+    created_by: i64,
+) -> Result<UrlDenylistRow, DbError> {
+    let row = sqlx::query_as::<_, UrlDenylistRow>(
+        "INSERT INTO url_denylist (domain, reason, created_by)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (domain) DO UPDATE SET reason = $2
+         RETURNING domain, reason, created_by, created_at",
+    )
+    .bind(domain)
+    .bind(reason)
+    .bind(created_by)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Returns whether an entry was removed.
+pub async fn delete_entry(pool: &DbPool, domain: &str) -> Result<bool, DbError> {
+    let result = sqlx::query("DELETE FROM url_denylist WHERE domain = $1")
+        .bind(domain)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
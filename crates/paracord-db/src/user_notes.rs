@@ -0,0 +1,82 @@
+use crate::{datetime_from_db_text, DbError, DbPool};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// A private note one user keeps about another. Only the author can see it.
+#[derive(Debug, Clone)]
+pub struct UserNoteRow {
+    pub user_id: i64,
+    pub target_id: i64,
+    pub note: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserNoteRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        let updated_at_raw: String = row.try_get("updated_at")?;
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            target_id: row.try_get("target_id")?,
+            note: row.try_get("note")?,
+            updated_at: datetime_from_db_text(&updated_at_raw)?,
+        })
+    }
+}
+
+pub async fn get_note(
+    pool: &DbPool,
+    user_id: i64,
+    target_id: i64,
+) -> Result<Option<UserNoteRow>, DbError> {
+    let row = sqlx::query_as::<_, UserNoteRow>(
+        "SELECT user_id, target_id, note, updated_at
+         FROM user_notes WHERE user_id = $1 AND target_id = $2",
+    )
+    .bind(user_id)
+    .bind(target_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_notes(pool: &DbPool, user_id: i64) -> Result<Vec<UserNoteRow>, DbError> {
+    let rows = sqlx::query_as::<_, UserNoteRow>(
+        "SELECT user_id, target_id, note, updated_at
+         FROM user_notes WHERE user_id = $1
+         ORDER BY updated_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn upsert_note(
+    pool: &DbPool,
+    user_id: i64,
+    target_id: i64,
+    note: &str,
+) -> Result<UserNoteRow, DbError> {
+    let row = sqlx::query_as::<_, UserNoteRow>(
+        "INSERT INTO user_notes (user_id, target_id, note)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, target_id) DO UPDATE
+         SET note = EXCLUDED.note, updated_at = datetime('now')
+         RETURNING user_id, target_id, note, updated_at",
+    )
+    .bind(user_id)
+    .bind(target_id)
+    .bind(note)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn delete_note(pool: &DbPool, user_id: i64, target_id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM user_notes WHERE user_id = $1 AND target_id = $2")
+        .bind(user_id)
+        .bind(target_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
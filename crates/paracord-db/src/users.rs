@@ -1,4 +1,6 @@
-use crate::{bool_from_any_row, datetime_from_db_text, json_from_db_text, DbError, DbPool};
+use crate::{
+    bool_from_any_row, datetime_from_db_text, json_from_db_text, DbError, DbExecutor, DbPool,
+};
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
@@ -47,11 +49,57 @@ pub struct UserSettingsRow {
     pub locale: String,
     pub message_display: String,
     pub crypto_auth_enabled: bool,
+    /// New gateway sessions need approval from an already trusted session.
+    pub device_approval_required: bool,
+    /// Share delivered/read receipts in DMs, and see other recipients' receipts.
+    pub dm_receipts_enabled: bool,
+    /// Listed in the user directory, locally and for trusted peers.
+    pub directory_discoverable: bool,
     pub notifications: serde_json::Value,
     pub keybinds: serde_json::Value,
+    pub presence_visibility: String,
+    pub presence_hidden_guild_ids: Vec<i64>,
+    pub appearance_version: i64,
+    pub notifications_version: i64,
+    pub keybinds_version: i64,
     pub updated_at: DateTime<Utc>,
 }
 
+impl UserSettingsRow {
+    pub fn version(&self, scope: SettingsScope) -> i64 {
+        match scope {
+            SettingsScope::Appearance => self.appearance_version,
+            SettingsScope::Notifications => self.notifications_version,
+            SettingsScope::Keybinds => self.keybinds_version,
+        }
+    }
+}
+
+/// Independently versioned sections of a user's settings document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsScope {
+    /// Theme, locale, message display, and custom CSS.
+    Appearance,
+    Notifications,
+    Keybinds,
+}
+
+impl SettingsScope {
+    pub const ALL: [SettingsScope; 3] = [
+        SettingsScope::Appearance,
+        SettingsScope::Notifications,
+        SettingsScope::Keybinds,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SettingsScope::Appearance => "appearance",
+            SettingsScope::Notifications => "notifications",
+            SettingsScope::Keybinds => "keybinds",
+        }
+    }
+}
+
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let created_at_raw: String = row.try_get("created_at")?;
@@ -97,6 +145,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserSettingsRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let notifications_raw: String = row.try_get("notifications")?;
         let keybinds_raw: String = row.try_get("keybinds")?;
+        let hidden_guilds_raw: String = row.try_get("presence_hidden_guild_ids")?;
         let updated_at_raw: String = row.try_get("updated_at")?;
         Ok(Self {
             user_id: row.try_get("user_id")?,
@@ -105,8 +154,18 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for UserSettingsRow {
             locale: row.try_get("locale")?,
             message_display: row.try_get("message_display")?,
             crypto_auth_enabled: bool_from_any_row(row, "crypto_auth_enabled")?,
+            device_approval_required: bool_from_any_row(row, "device_approval_required")?,
+            dm_receipts_enabled: bool_from_any_row(row, "dm_receipts_enabled")?,
+            directory_discoverable: bool_from_any_row(row, "directory_discoverable")?,
             notifications: json_from_db_text(&notifications_raw)?,
             keybinds: json_from_db_text(&keybinds_raw)?,
+            presence_visibility: row.try_get("presence_visibility")?,
+            presence_hidden_guild_ids: serde_json::from_str(&hidden_guilds_raw).map_err(|e| {
+                sqlx::Error::Protocol(format!("invalid presence_hidden_guild_ids: {e}"))
+            })?,
+            appearance_version: row.try_get("appearance_version")?,
+            notifications_version: row.try_get("notifications_version")?,
+            keybinds_version: row.try_get("keybinds_version")?,
             updated_at: datetime_from_db_text(&updated_at_raw)?,
         })
     }
@@ -172,13 +231,16 @@ pub async fn create_user_as_first_admin(
     Ok(row)
 }
 
-pub async fn get_user_by_id(pool: &DbPool, id: i64) -> Result<Option<UserRow>, DbError> {
+pub async fn get_user_by_id<'e>(
+    executor: impl DbExecutor<'e>,
+    id: i64,
+) -> Result<Option<UserRow>, DbError> {
     let row = sqlx::query_as::<_, UserRow>(
         "SELECT id, username, discriminator, email, display_name, avatar_hash, banner_hash, bio, accent_color, flags, created_at, public_key
          FROM users WHERE id = $1",
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
     Ok(row)
 }
@@ -300,7 +362,7 @@ pub async fn get_user_settings(
     user_id: i64,
 ) -> Result<Option<UserSettingsRow>, DbError> {
     let row = sqlx::query_as::<_, UserSettingsRow>(
-        "SELECT user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, notifications, keybinds, updated_at
+        "SELECT user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at
          FROM user_settings WHERE user_id = $1",
     )
     .bind(user_id)
@@ -390,8 +452,13 @@ pub async fn upsert_user_settings(
             crypto_auth_enabled = COALESCE($6, user_settings.crypto_auth_enabled),
             notifications = COALESCE($7, user_settings.notifications),
             keybinds = COALESCE($8, user_settings.keybinds),
+            appearance_version = user_settings.appearance_version + 1,
+            notifications_version = user_settings.notifications_version
+                + CASE WHEN $7 IS NULL THEN 0 ELSE 1 END,
+            keybinds_version = user_settings.keybinds_version
+                + CASE WHEN $8 IS NULL THEN 0 ELSE 1 END,
             updated_at = datetime('now')
-         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, notifications, keybinds, updated_at",
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
     )
     .bind(user_id)
     .bind(theme)
@@ -406,6 +473,277 @@ pub async fn upsert_user_settings(
     Ok(row)
 }
 
+/// Update who can see a user's presence. The settings row must already exist.
+pub async fn update_presence_privacy(
+    pool: &DbPool,
+    user_id: i64,
+    visibility: &str,
+    hidden_guild_ids: &[i64],
+) -> Result<UserSettingsRow, DbError> {
+    let hidden_guild_ids = serde_json::to_string(hidden_guild_ids).map_err(|e| {
+        DbError::Sqlx(sqlx::Error::Protocol(format!(
+            "invalid presence_hidden_guild_ids: {e}"
+        )))
+    })?;
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET presence_visibility = $2, presence_hidden_guild_ids = $3, updated_at = datetime('now')
+         WHERE user_id = $1
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(visibility)
+    .bind(hidden_guild_ids)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Create a default settings row for the user if none exists yet.
+pub async fn ensure_user_settings(pool: &DbPool, user_id: i64) -> Result<(), DbError> {
+    sqlx::query(
+        "INSERT INTO user_settings (user_id) VALUES ($1)
+         ON CONFLICT (user_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Compare-and-swap the appearance scope. Returns `None` when
+/// `expected_version` is set and no longer matches the stored version.
+pub async fn update_appearance_settings(
+    pool: &DbPool,
+    user_id: i64,
+    expected_version: Option<i64>,
+    theme: &str,
+    locale: &str,
+    message_display: &str,
+    custom_css: Option<&str>,
+) -> Result<Option<UserSettingsRow>, DbError> {
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET theme = $3, locale = $4, message_display = $5, custom_css = $6,
+             appearance_version = appearance_version + 1,
+             updated_at = datetime('now')
+         WHERE user_id = $1 AND appearance_version = COALESCE($2, appearance_version)
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(expected_version)
+    .bind(theme)
+    .bind(locale)
+    .bind(message_display)
+    .bind(custom_css)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Compare-and-swap a JSON settings scope (notifications or keybinds).
+/// Returns `None` when `expected_version` no longer matches.
+pub async fn update_json_settings(
+    pool: &DbPool,
+    user_id: i64,
+    scope: SettingsScope,
+    expected_version: Option<i64>,
+    value: &serde_json::Value,
+) -> Result<Option<UserSettingsRow>, DbError> {
+    let (column, version_column) = match scope {
+        SettingsScope::Notifications => ("notifications", "notifications_version"),
+        SettingsScope::Keybinds => ("keybinds", "keybinds_version"),
+        SettingsScope::Appearance => {
+            return Err(DbError::Sqlx(sqlx::Error::Protocol(
+                "appearance settings are not a JSON scope".into(),
+            )))
+        }
+    };
+    let value = serde_json::to_string(value)
+        .map_err(|e| DbError::Sqlx(sqlx::Error::Protocol(format!("invalid {column} json: {e}"))))?;
+    let sql = format!(
+        "UPDATE user_settings
+         SET {column} = $3, {version_column} = {version_column} + 1, updated_at = datetime('now')
+         WHERE user_id = $1 AND {version_column} = COALESCE($2, {version_column})
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at"
+    );
+    let row = sqlx::query_as::<_, UserSettingsRow>(&sql)
+        .bind(user_id)
+        .bind(expected_version)
+        .bind(value)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Toggle crypto auth. The settings row must already exist.
+pub async fn update_crypto_auth_enabled(
+    pool: &DbPool,
+    user_id: i64,
+    enabled: bool,
+) -> Result<UserSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET crypto_auth_enabled = $2, updated_at = datetime('now')
+         WHERE user_id = $1
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(enabled)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Toggle device approval for new gateway sessions. The settings row must already exist.
+pub async fn update_device_approval_required(
+    pool: &DbPool,
+    user_id: i64,
+    enabled: bool,
+) -> Result<UserSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET device_approval_required = $2, updated_at = datetime('now')
+         WHERE user_id = $1
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(enabled)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Toggle DM delivered/read receipts. The settings row must already exist.
+pub async fn update_dm_receipts_enabled(
+    pool: &DbPool,
+    user_id: i64,
+    enabled: bool,
+) -> Result<UserSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET dm_receipts_enabled = $2, updated_at = datetime('now')
+         WHERE user_id = $1
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(enabled)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Opt in to or out of the user directory. The settings row must already
+/// exist.
+pub async fn update_directory_discoverable(
+    pool: &DbPool,
+    user_id: i64,
+    discoverable: bool,
+) -> Result<UserSettingsRow, DbError> {
+    let row = sqlx::query_as::<_, UserSettingsRow>(
+        "UPDATE user_settings
+         SET directory_discoverable = $2, updated_at = datetime('now')
+         WHERE user_id = $1
+         RETURNING user_id, theme, custom_css, locale, message_display, CASE WHEN crypto_auth_enabled THEN 1 ELSE 0 END AS crypto_auth_enabled, CASE WHEN device_approval_required THEN 1 ELSE 0 END AS device_approval_required, CASE WHEN dm_receipts_enabled THEN 1 ELSE 0 END AS dm_receipts_enabled, CASE WHEN directory_discoverable THEN 1 ELSE 0 END AS directory_discoverable, notifications, keybinds, presence_visibility, presence_hidden_guild_ids, appearance_version, notifications_version, keybinds_version, updated_at",
+    )
+    .bind(user_id)
+    .bind(discoverable)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Discoverable local users whose username or display name starts with
+/// `query` (case-insensitive), by username. Bots and accounts standing in
+/// for federated users are never listed.
+pub async fn search_directory_users(
+    pool: &DbPool,
+    query: &str,
+    bot_flag: i32,
+    limit: i64,
+) -> Result<Vec<UserRow>, DbError> {
+    let escaped = query
+        .to_lowercase()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = format!("{}%", escaped);
+    let rows = sqlx::query_as::<_, UserRow>(
+        "SELECT u.id, u.username, u.discriminator, u.email, u.display_name, u.avatar_hash, u.banner_hash, u.bio, u.accent_color, u.flags, u.created_at, u.public_key
+         FROM users u
+         INNER JOIN user_settings s ON s.user_id = u.id
+         WHERE s.directory_discoverable = TRUE
+           AND (u.flags & $2) = 0
+           AND (LOWER(u.username) LIKE $1 ESCAPE '\\'
+                OR LOWER(COALESCE(u.display_name, '')) LIKE $1 ESCAPE '\\')
+           AND NOT EXISTS (
+               SELECT 1 FROM federation_remote_users r WHERE r.local_user_id = u.id
+           )
+         ORDER BY LOWER(u.username) ASC, u.id ASC
+         LIMIT $3",
+    )
+    .bind(pattern)
+    .bind(bot_flag)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// The subset of `user_ids` with DM receipts turned off. Users without a
+/// settings row use the default (on).
+pub async fn get_dm_receipts_disabled_users(
+    pool: &DbPool,
+    user_ids: &[i64],
+) -> Result<Vec<i64>, DbError> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = (1..=user_ids.len())
+        .map(|i| format!("${i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT user_id FROM user_settings
+         WHERE dm_receipts_enabled = FALSE AND user_id IN ({placeholders})"
+    );
+    let mut query = sqlx::query_as::<_, (i64,)>(&sql);
+    for user_id in user_ids {
+        query = query.bind(*user_id);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// When the user confirmed they may view NSFW channels, if they have.
+pub async fn get_age_gate_acknowledged_at(
+    pool: &DbPool,
+    user_id: i64,
+) -> Result<Option<String>, DbError> {
+    let row: Option<(String,)> = sqlx::query_as(
+        "SELECT acknowledged_at FROM user_age_gate_acknowledgements WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(at,)| at))
+}
+
+/// Record the age gate acknowledgement. Repeating it keeps the first timestamp.
+pub async fn acknowledge_age_gate(pool: &DbPool, user_id: i64) -> Result<String, DbError> {
+    let (at,): (String,) = sqlx::query_as(
+        "INSERT INTO user_age_gate_acknowledgements (user_id)
+         VALUES ($1)
+         ON CONFLICT (user_id) DO UPDATE
+            SET acknowledged_at = user_age_gate_acknowledgements.acknowledged_at
+         RETURNING acknowledged_at",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(at)
+}
+
 pub async fn update_user_public_key(
     pool: &DbPool,
     id: i64,
@@ -494,6 +832,7 @@ pub async fn get_mutual_guilds(
          FROM spaces s
          INNER JOIN members ma ON ma.guild_id = s.id AND ma.user_id = $1
          INNER JOIN members mb ON mb.guild_id = s.id AND mb.user_id = $2
+         WHERE s.deleted_at IS NULL
          ORDER BY s.name",
     )
     .bind(user_a)
@@ -891,6 +1230,38 @@ mod tests {
         assert_eq!(updated.theme, "light");
     }
 
+    #[tokio::test]
+    async fn test_settings_scopes_compare_and_swap() {
+        let pool = test_pool().await;
+        create_user(&pool, 97, "cas_u", 1, "cas@example.com", "h")
+            .await
+            .unwrap();
+        ensure_user_settings(&pool, 97).await.unwrap();
+
+        let updated =
+            update_appearance_settings(&pool, 97, Some(0), "light", "en-US", "cozy", None)
+                .await
+                .unwrap()
+                .expect("matching version applies");
+        assert_eq!(updated.appearance_version, 1);
+        assert_eq!(updated.keybinds_version, 0);
+
+        // A writer still on version 0 loses the race.
+        let stale = update_appearance_settings(&pool, 97, Some(0), "dark", "en-US", "cozy", None)
+            .await
+            .unwrap();
+        assert!(stale.is_none());
+
+        let keybinds = serde_json::json!({ "mute": "Ctrl+M" });
+        let updated = update_json_settings(&pool, 97, SettingsScope::Keybinds, None, &keybinds)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.keybinds, keybinds);
+        assert_eq!(updated.keybinds_version, 1);
+        assert_eq!(updated.theme, "light");
+    }
+
     #[tokio::test]
     async fn test_get_user_settings_none_when_not_set() {
         let pool = test_pool().await;
@@ -900,4 +1271,30 @@ mod tests {
         let settings = get_user_settings(&pool, 96).await.unwrap();
         assert!(settings.is_none());
     }
+
+    #[tokio::test]
+    async fn test_search_directory_users_only_lists_opted_in() {
+        let pool = test_pool().await;
+        for (id, name) in [(90, "alice"), (91, "alicia"), (92, "al_bot"), (93, "bob")] {
+            create_user(&pool, id, name, 1, &format!("{name}@example.com"), "h")
+                .await
+                .unwrap();
+            ensure_user_settings(&pool, id).await.unwrap();
+        }
+        for id in [90, 92, 93] {
+            let settings = update_directory_discoverable(&pool, id, true)
+                .await
+                .unwrap();
+            assert!(settings.directory_discoverable);
+        }
+        update_user_flags(&pool, 92, 1 << 1).await.unwrap();
+
+        let found = search_directory_users(&pool, "AL", 1 << 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(found.iter().map(|u| u.id).collect::<Vec<_>>(), vec![90]);
+        // `_` is matched literally.
+        let found = search_directory_users(&pool, "al_", 0, 10).await.unwrap();
+        assert_eq!(found.iter().map(|u| u.id).collect::<Vec<_>>(), vec![92]);
+    }
 }
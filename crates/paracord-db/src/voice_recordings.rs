@@ -0,0 +1,199 @@
+use crate::{DbError, DbPool};
+use sqlx::Row;
+
+/// Room composite recorded to a file and imported as a guild attachment.
+pub const RECORDING_KIND_FILE: &str = "file";
+/// Room composite pushed to one or more RTMP endpoints.
+pub const RECORDING_KIND_RTMP: &str = "rtmp";
+
+pub const RECORDING_STATUS_STARTING: &str = "starting";
+pub const RECORDING_STATUS_ACTIVE: &str = "active";
+pub const RECORDING_STATUS_COMPLETE: &str = "complete";
+pub const RECORDING_STATUS_FAILED: &str = "failed";
+
+#[derive(Debug, Clone)]
+pub struct VoiceRecordingRow {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// LiveKit egress id.
+    pub egress_id: String,
+    pub kind: String,
+    pub status: String,
+    pub started_by: Option<i64>,
+    pub attachment_id: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub ended_at: Option<String>,
+}
+
+impl VoiceRecordingRow {
+    pub fn is_finished(&self) -> bool {
+        self.status == RECORDING_STATUS_COMPLETE || self.status == RECORDING_STATUS_FAILED
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for VoiceRecordingRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            guild_id: row.try_get("guild_id")?,
+            channel_id: row.try_get("channel_id")?,
+            egress_id: row.try_get("egress_id")?,
+            kind: row.try_get("kind")?,
+            status: row.try_get("status")?,
+            started_by: row.try_get("started_by")?,
+            attachment_id: row.try_get("attachment_id")?,
+            error: row.try_get("error")?,
+            created_at: row.try_get("created_at")?,
+            ended_at: row.try_get("ended_at")?,
+        })
+    }
+}
+
+pub async fn create_recording(
+    pool: &DbPool,
+    id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    egress_id: &str,
+    kind: &str,
+    started_by: i64,
+) -> Result<VoiceRecordingRow, DbError> {
+    let row = sqlx::query_as::<_, VoiceRecordingRow>(
+        "INSERT INTO voice_recordings (id, guild_id, channel_id, egress_id, kind, started_by)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at",
+    )
+    .bind(id)
+    .bind(guild_id)
+    .bind(channel_id)
+    .bind(egress_id)
+    .bind(kind)
+    .bind(started_by)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_recording(pool: &DbPool, id: i64) -> Result<Option<VoiceRecordingRow>, DbError> {
+    let row = sqlx::query_as::<_, VoiceRecordingRow>(
+        "SELECT
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at
+         FROM voice_recordings WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_recording_by_egress(
+    pool: &DbPool,
+    egress_id: &str,
+) -> Result<Option<VoiceRecordingRow>, DbError> {
+    let row = sqlx::query_as::<_, VoiceRecordingRow>(
+        "SELECT
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at
+         FROM voice_recordings WHERE egress_id = $1",
+    )
+    .bind(egress_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_recording_by_attachment(
+    pool: &DbPool,
+    attachment_id: i64,
+) -> Result<Option<VoiceRecordingRow>, DbError> {
+    let row = sqlx::query_as::<_, VoiceRecordingRow>(
+        "SELECT
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at
+         FROM voice_recordings WHERE attachment_id = $1",
+    )
+    .bind(attachment_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Recordings for a channel, newest first.
+pub async fn list_channel_recordings(
+    pool: &DbPool,
+    channel_id: i64,
+    limit: i64,
+) -> Result<Vec<VoiceRecordingRow>, DbError> {
+    let rows = sqlx::query_as::<_, VoiceRecordingRow>(
+        "SELECT
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at
+         FROM voice_recordings
+         WHERE channel_id = $1
+         ORDER BY id DESC
+         LIMIT $2",
+    )
+    .bind(channel_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Recordings in a channel that have not ended yet.
+pub async fn get_active_channel_recordings(
+    pool: &DbPool,
+    channel_id: i64,
+) -> Result<Vec<VoiceRecordingRow>, DbError> {
+    let rows = sqlx::query_as::<_, VoiceRecordingRow>(
+        "SELECT
+            id, guild_id, channel_id, egress_id, kind, status, started_by,
+            attachment_id, error, created_at, ended_at
+         FROM voice_recordings
+         WHERE channel_id = $1 AND status IN ('starting', 'active')
+         ORDER BY id ASC",
+    )
+    .bind(channel_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn mark_recording_active(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE voice_recordings SET status = 'active'
+         WHERE id = $1 AND status = 'starting'",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Move a recording to a terminal state. Returns `false` if it had already
+/// finished, so lifecycle events are only emitted once.
+pub async fn finish_recording(
+    pool: &DbPool,
+    id: i64,
+    status: &str,
+    attachment_id: Option<i64>,
+    error: Option<&str>,
+) -> Result<bool, DbError> {
+    let result = sqlx::query(
+        "UPDATE voice_recordings
+         SET status = $2, attachment_id = $3, error = $4, ended_at = datetime('now')
+         WHERE id = $1 AND status IN ('starting', 'active')",
+    )
+    .bind(id)
+    .bind(status)
+    .bind(attachment_id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,84 @@
+use crate::{DbError, DbPool};
+use sqlx::Row;
+
+/// Channel always uses LiveKit, even when native media is enabled.
+pub const VOICE_STACK_LIVEKIT: &str = "livekit";
+/// Channel always uses the native QUIC media server.
+pub const VOICE_STACK_NATIVE: &str = "native";
+
+pub fn is_valid_voice_stack(stack: &str) -> bool {
+    matches!(stack, VOICE_STACK_LIVEKIT | VOICE_STACK_NATIVE)
+}
+
+#[derive(Debug, Clone)]
+pub struct VoiceStackRow {
+    pub channel_id: i64,
+    pub guild_id: i64,
+    pub stack: String,
+    pub updated_at: String,
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for VoiceStackRow {
+    fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            channel_id: row.try_get("channel_id")?,
+            guild_id: row.try_get("guild_id")?,
+            stack: row.try_get("stack")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+pub async fn get_channel_stack(pool: &DbPool, channel_id: i64) -> Result<Option<String>, DbError> {
+    let stack: Option<String> =
+        sqlx::query_scalar("SELECT stack FROM voice_channel_stacks WHERE channel_id = $1")
+            .bind(channel_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(stack)
+}
+
+pub async fn list_guild_stacks(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Vec<VoiceStackRow>, DbError> {
+    let rows = sqlx::query_as::<_, VoiceStackRow>(
+        "SELECT channel_id, guild_id, stack, updated_at
+         FROM voice_channel_stacks WHERE guild_id = $1
+         ORDER BY channel_id",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn set_channel_stack(
+    pool: &DbPool,
+    channel_id: i64,
+    guild_id: i64,
+    stack: &str,
+) -> Result<VoiceStackRow, DbError> {
+    let row = sqlx::query_as::<_, VoiceStackRow>(
+        "INSERT INTO voice_channel_stacks (channel_id, guild_id, stack, updated_at)
+         VALUES ($1, $2, $3, datetime('now'))
+         ON CONFLICT(channel_id) DO UPDATE SET
+            stack = excluded.stack,
+            updated_at = datetime('now')
+         RETURNING channel_id, guild_id, stack, updated_at",
+    )
+    .bind(channel_id)
+    .bind(guild_id)
+    .bind(stack)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn clear_channel_stack(pool: &DbPool, channel_id: i64) -> Result<(), DbError> {
+    sqlx::query("DELETE FROM voice_channel_stacks WHERE channel_id = $1")
+        .bind(channel_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::{datetime_from_db_text, DbError, DbPool};
+use crate::{datetime_from_db_text, datetime_to_db_text, DbError, DbPool};
 use chrono::{DateTime, Utc};
 use sha2::{Digest, Sha256};
 use sqlx::Row;
@@ -12,11 +12,13 @@ pub struct WebhookRow {
     pub name: String,
     pub token: String,
     pub created_at: DateTime<Utc>,
+    pub suspended_at: Option<DateTime<Utc>>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for WebhookRow {
     fn from_row(row: &'r sqlx::any::AnyRow) -> Result<Self, sqlx::Error> {
         let created_at_raw: String = row.try_get("created_at")?;
+        let suspended_at_raw: Option<String> = row.try_get("suspended_at")?;
         Ok(Self {
             id: row.try_get("id")?,
             space_id: row.try_get("space_id")?,
@@ -25,6 +27,10 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for WebhookRow {
             name: row.try_get("name")?,
             token: row.try_get("token")?,
             created_at: datetime_from_db_text(&created_at_raw)?,
+            suspended_at: suspended_at_raw
+                .as_deref()
+                .map(datetime_from_db_text)
+                .transpose()?,
         })
     }
 }
@@ -66,7 +72,7 @@ pub async fn create_webhook(
     let row = sqlx::query_as::<_, WebhookRow>(
         "INSERT INTO webhooks (id, space_id, channel_id, name, token, creator_id)
          VALUES ($1, $2, $3, $4, $5, $6)
-         RETURNING id, space_id, channel_id, creator_id, name, token, created_at",
+         RETURNING id, space_id, channel_id, creator_id, name, token, created_at, suspended_at",
     )
     .bind(id)
     .bind(space_id)
@@ -81,7 +87,7 @@ pub async fn create_webhook(
 
 pub async fn get_webhook(pool: &DbPool, id: i64) -> Result<Option<WebhookRow>, DbError> {
     let row = sqlx::query_as::<_, WebhookRow>(
-        "SELECT id, space_id, channel_id, creator_id, name, token, created_at
+        "SELECT id, space_id, channel_id, creator_id, name, token, created_at, suspended_at
          FROM webhooks WHERE id = $1",
     )
     .bind(id)
@@ -97,7 +103,7 @@ pub async fn get_webhook_by_id_and_token(
 ) -> Result<Option<WebhookRow>, DbError> {
     let token_hash = normalize_token_hash(token);
     let row = sqlx::query_as::<_, WebhookRow>(
-        "SELECT id, space_id, channel_id, creator_id, name, token, created_at
+        "SELECT id, space_id, channel_id, creator_id, name, token, created_at, suspended_at
          FROM webhooks WHERE id = $1 AND (token = $2 OR token = $3)",
     )
     .bind(id)
@@ -113,7 +119,7 @@ pub async fn get_channel_webhooks(
     channel_id: i64,
 ) -> Result<Vec<WebhookRow>, DbError> {
     let rows = sqlx::query_as::<_, WebhookRow>(
-        "SELECT id, space_id, channel_id, creator_id, name, token, created_at
+        "SELECT id, space_id, channel_id, creator_id, name, token, created_at, suspended_at
          FROM webhooks WHERE channel_id = $1 ORDER BY created_at",
     )
     .bind(channel_id)
@@ -124,7 +130,7 @@ pub async fn get_channel_webhooks(
 
 pub async fn get_guild_webhooks(pool: &DbPool, space_id: i64) -> Result<Vec<WebhookRow>, DbError> {
     let rows = sqlx::query_as::<_, WebhookRow>(
-        "SELECT id, space_id, channel_id, creator_id, name, token, created_at
+        "SELECT id, space_id, channel_id, creator_id, name, token, created_at, suspended_at
          FROM webhooks WHERE space_id = $1 ORDER BY created_at",
     )
     .bind(space_id)
@@ -133,6 +139,22 @@ pub async fn get_guild_webhooks(pool: &DbPool, space_id: i64) -> Result<Vec<Webh
     Ok(rows)
 }
 
+pub async fn count_channel_webhooks(pool: &DbPool, channel_id: i64) -> Result<i64, DbError> {
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM webhooks WHERE channel_id = $1")
+        .bind(channel_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count.0)
+}
+
+pub async fn count_guild_webhooks(pool: &DbPool, space_id: i64) -> Result<i64, DbError> {
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM webhooks WHERE space_id = $1")
+        .bind(space_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count.0)
+}
+
 pub async fn update_webhook(
     pool: &DbPool,
     id: i64,
@@ -141,7 +163,7 @@ pub async fn update_webhook(
     let row = sqlx::query_as::<_, WebhookRow>(
         "UPDATE webhooks SET name = COALESCE($2, name)
          WHERE id = $1
-         RETURNING id, space_id, channel_id, creator_id, name, token, created_at",
+         RETURNING id, space_id, channel_id, creator_id, name, token, created_at, suspended_at",
     )
     .bind(id)
     .bind(name)
@@ -150,6 +172,50 @@ pub async fn update_webhook(
     Ok(row)
 }
 
+/// Suspend a webhook. Returns `false` if it was already suspended.
+pub async fn suspend_webhook(pool: &DbPool, id: i64) -> Result<bool, DbError> {
+    let result =
+        sqlx::query("UPDATE webhooks SET suspended_at = $2 WHERE id = $1 AND suspended_at IS NULL")
+            .bind(id)
+            .bind(datetime_to_db_text(Utc::now()))
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn clear_webhook_suspension(pool: &DbPool, id: i64) -> Result<(), DbError> {
+    sqlx::query("UPDATE webhooks SET suspended_at = NULL WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Whether a stored webhook token is already a SHA-256 hash. Rows created
+/// before tokens were hashed hold the plaintext token.
+pub fn is_token_hash(stored: &str) -> bool {
+    is_hex_sha256(stored)
+}
+
+/// `(id, stored token)` of every webhook.
+pub async fn list_webhook_tokens(pool: &DbPool) -> Result<Vec<(i64, String)>, DbError> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, token FROM webhooks ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// Replace a plaintext stored token with its hash.
+pub async fn rehash_webhook_token(pool: &DbPool, id: i64, stored: &str) -> Result<(), DbError> {
+    sqlx::query("UPDATE webhooks SET token = $2 WHERE id = $1 AND token = $3")
+        .bind(id)
+        .bind(normalize_token_hash(stored))
+        .bind(stored)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 pub async fn delete_webhook(pool: &DbPool, id: i64) -> Result<(), DbError> {
     sqlx::query("DELETE FROM webhooks WHERE id = $1")
         .bind(id)
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use paracord_federation::{canonical_envelope_bytes, FederationEventEnvelope};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(envelope) = serde_json::from_slice::<FederationEventEnvelope>(data) else {
+        return;
+    };
+    let _ = envelope.validate();
+    let canonical = canonical_envelope_bytes(&envelope);
+    // Canonical bytes are always valid JSON.
+    assert!(serde_json::from_slice::<serde_json::Value>(&canonical).is_ok());
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use paracord_federation::{hex_decode, hex_encode};
+
+fuzz_target!(|data: &str| {
+    if let Some(bytes) = hex_decode(data) {
+        assert_eq!(hex_encode(&bytes), data.to_ascii_lowercase());
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use paracord_federation::signing;
+
+// Input: `<payload>\n<signature hex>\n<public key hex>`.
+fuzz_target!(|data: &str| {
+    let mut parts = data.splitn(3, '\n');
+    let payload = parts.next().unwrap_or_default();
+    let signature = parts.next().unwrap_or_default();
+    let public_key = parts.next().unwrap_or_default();
+    let _ = signing::verify(payload.as_bytes(), signature, public_key);
+});
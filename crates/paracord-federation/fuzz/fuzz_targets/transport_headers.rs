@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use paracord_federation::transport::{parse_transport_headers, timestamp_within_skew};
+
+// Input: origin, key id, timestamp and signature separated by newlines.
+fuzz_target!(|data: &str| {
+    let mut parts = data.splitn(4, '\n');
+    let parsed = parse_transport_headers(parts.next(), parts.next(), parts.next(), parts.next());
+    if let Ok(headers) = parsed {
+        assert!(headers.timestamp_ms >= 0);
+        assert_eq!(headers.signature_hex.len(), 128);
+        let _ = timestamp_within_skew(i64::MIN, headers.timestamp_ms, i64::MIN);
+    }
+});
@@ -0,0 +1,108 @@
+//! Per-peer bandwidth accounting.
+//!
+//! Bytes exchanged with each federated server are counted per category in
+//! one-minute buckets; quotas apply to the sum over the last [`WINDOW_MINUTES`].
+
+use paracord_db::DbPool;
+
+use crate::FederationError;
+
+/// Event pushes, pulls and catch-up pages.
+pub const CATEGORY_EVENTS: &str = "events";
+/// Media token and relay requests.
+pub const CATEGORY_MEDIA_RELAY: &str = "media_relay";
+/// Attachment downloads through the file proxy.
+pub const CATEGORY_FILE: &str = "file";
+
+/// Length of the rolling window quotas are measured over.
+pub const WINDOW_MINUTES: i64 = 60;
+
+/// Buckets older than this are purged.
+const RETAIN_MINUTES: i64 = 24 * 60;
+
+/// The one-minute bucket `now_ms` falls in.
+pub fn minute_bucket(now_ms: i64) -> i64 {
+    now_ms.div_euclid(60_000)
+}
+
+/// First bucket of the rolling window ending at `now_ms`.
+pub fn window_start(now_ms: i64) -> i64 {
+    minute_bucket(now_ms) - (WINDOW_MINUTES - 1)
+}
+
+/// Whether `used_bytes` has reached `quota_bytes`. `None` or `0` means no quota.
+pub fn quota_exceeded(used_bytes: u64, quota_bytes: Option<u64>) -> bool {
+    matches!(quota_bytes, Some(quota) if quota > 0 && used_bytes >= quota)
+}
+
+fn peer_key(server_name: &str) -> String {
+    server_name.trim().to_ascii_lowercase()
+}
+
+/// Count bytes exchanged with `server_name`. Failures are logged, not
+/// returned, so accounting never fails the request it measures.
+pub async fn record(
+    pool: &DbPool,
+    server_name: &str,
+    category: &str,
+    bytes_in: usize,
+    bytes_out: usize,
+) {
+    if bytes_in == 0 && bytes_out == 0 {
+        return;
+    }
+    let bucket = minute_bucket(chrono::Utc::now().timestamp_millis());
+    if let Err(e) = paracord_db::federation::add_peer_bandwidth(
+        pool,
+        &peer_key(server_name),
+        category,
+        bucket,
+        i64::try_from(bytes_in).unwrap_or(i64::MAX),
+        i64::try_from(bytes_out).unwrap_or(i64::MAX),
+    )
+    .await
+    {
+        tracing::warn!(
+            "federation: failed to record bandwidth for {}: {}",
+            server_name,
+            e
+        );
+    }
+}
+
+/// Bytes exchanged with `server_name` over the current window.
+pub async fn usage(pool: &DbPool, server_name: &str) -> Result<u64, FederationError> {
+    let since = window_start(chrono::Utc::now().timestamp_millis());
+    let total =
+        paracord_db::federation::peer_bandwidth_total_since(pool, &peer_key(server_name), since)
+            .await?;
+    Ok(total.max(0) as u64)
+}
+
+/// Drop buckets no dashboard or quota looks at any more.
+pub async fn purge_expired(pool: &DbPool) -> Result<u64, FederationError> {
+    let cutoff = minute_bucket(chrono::Utc::now().timestamp_millis()) - RETAIN_MINUTES;
+    Ok(paracord_db::federation::purge_peer_bandwidth_before(pool, cutoff).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_covers_the_last_hour_of_buckets() {
+        let now_ms = 1_700_000_000_000;
+        let bucket = minute_bucket(now_ms);
+        assert_eq!(bucket, 28_333_333);
+        assert_eq!(bucket - window_start(now_ms) + 1, WINDOW_MINUTES);
+        assert_eq!(minute_bucket(-1), -1);
+    }
+
+    #[test]
+    fn zero_or_missing_quota_is_unlimited() {
+        assert!(!quota_exceeded(u64::MAX, None));
+        assert!(!quota_exceeded(u64::MAX, Some(0)));
+        assert!(!quota_exceeded(999, Some(1_000)));
+        assert!(quota_exceeded(1_000, Some(1_000)));
+    }
+}
@@ -0,0 +1,177 @@
+//! Canonical JSON encoding used for everything that gets signed.
+//!
+//! The output doesn't depend on `serde_json` features, map ordering or the
+//! Rust toolchain, so another implementation can reproduce it byte for byte:
+//!
+//! - no whitespace;
+//! - object keys sorted by their UTF-8 bytes (i.e. by code point);
+//! - strings escape only `"`, `\` and control characters, using `\b`, `\f`,
+//!   `\n`, `\r`, `\t` where they exist and lowercase `\u00xx` otherwise;
+//! - integers, and floats with no fractional part below 2^53, are written as
+//!   plain decimal integers (`-0.0` becomes `0`);
+//! - other floats use the shortest digits that round-trip (as computed by
+//!   `ryu`). They are written as plain decimals when the decimal point falls
+//!   after at most 16 digits or at most 4 zeros precede the first digit, with
+//!   `.0` kept on integral values (`0.5`, `0.00001`, `9007199254740992.0`);
+//!   otherwise as `d[.ddd]e[-]x`, the exponent without `+` or leading zeros
+//!   (`1e16`, `1.5e-7`, `-2.5e300`).
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Largest integer an IEEE double represents exactly.
+const MAX_SAFE_FLOAT_INT: f64 = 9_007_199_254_740_992.0;
+
+/// Encode `value` as canonical JSON.
+pub fn to_vec(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Serialize `value` and encode the result as canonical JSON.
+pub fn to_vec_from<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    Ok(to_vec(&serde_json::to_value(value)?))
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(true) => out.extend_from_slice(b"true"),
+        Value::Bool(false) => out.extend_from_slice(b"false"),
+        Value::Number(number) => write_number(out, number),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(out, item);
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(out, key);
+                out.push(b':');
+                write_value(out, item);
+            }
+            out.push(b'}');
+        }
+    }
+}
+
+fn write_number(out: &mut Vec<u8>, number: &Number) {
+    if let Some(n) = number.as_i64() {
+        out.extend_from_slice(n.to_string().as_bytes());
+    } else if let Some(n) = number.as_u64() {
+        out.extend_from_slice(n.to_string().as_bytes());
+    } else if let Some(f) = number.as_f64() {
+        if f.fract() == 0.0 && f.abs() < MAX_SAFE_FLOAT_INT {
+            out.extend_from_slice((f as i64).to_string().as_bytes());
+        } else {
+            // Written by ryu directly rather than through `Number`'s Display,
+            // whose exponent spelling has changed between serde_json versions.
+            out.extend_from_slice(ryu::Buffer::new().format_finite(f).as_bytes());
+        }
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push(b'"');
+    let bytes = s.as_bytes();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let short: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            0x08 => b"\\b",
+            0x0c => b"\\f",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x00..=0x1f => b"",
+            _ => continue,
+        };
+        out.extend_from_slice(&bytes[start..i]);
+        if short.is_empty() {
+            out.extend_from_slice(b"\\u00");
+            out.push(HEX[(b >> 4) as usize]);
+            out.push(HEX[(b & 0xf) as usize]);
+        } else {
+            out.extend_from_slice(short);
+        }
+        start = i + 1;
+    }
+    out.extend_from_slice(&bytes[start..]);
+    out.push(b'"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn canonical(value: Value) -> String {
+        String::from_utf8(to_vec(&value)).unwrap()
+    }
+
+    #[test]
+    fn sorts_keys_at_every_level() {
+        assert_eq!(
+            canonical(json!({ "b": 1, "a": { "z": [3, { "y": 2, "x": 1 }], "é": 0, "A": null } })),
+            r#"{"a":{"A":null,"z":[3,{"x":1,"y":2}],"é":0},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn numbers_have_one_spelling() {
+        assert_eq!(canonical(json!(-42)), "-42");
+        assert_eq!(canonical(json!(u64::MAX)), "18446744073709551615");
+        assert_eq!(canonical(json!(5.0)), "5");
+        assert_eq!(canonical(json!(-0.0)), "0");
+        assert_eq!(canonical(json!(0.5)), "0.5");
+        assert_eq!(canonical(json!(1e300)), "1e300");
+    }
+
+    #[test]
+    fn floats_switch_to_exponents_at_fixed_points() {
+        assert_eq!(canonical(json!(123.456)), "123.456");
+        assert_eq!(canonical(json!(0.00001)), "0.00001");
+        assert_eq!(canonical(json!(0.000001)), "1e-6");
+        assert_eq!(canonical(json!(1.5e-7)), "1.5e-7");
+        assert_eq!(canonical(json!(-2.5e-300)), "-2.5e-300");
+        assert_eq!(
+            canonical(json!(9_007_199_254_740_992.0)),
+            "9007199254740992.0"
+        );
+        assert_eq!(canonical(json!(1e16)), "1e16");
+        assert_eq!(canonical(json!(1.2345e20)), "1.2345e20");
+        assert_eq!(canonical(json!(-2.5e300)), "-2.5e300");
+        assert_eq!(canonical(json!(f64::MAX)), "1.7976931348623157e308");
+        assert_eq!(canonical(json!(5e-324)), "5e-324");
+    }
+
+    #[test]
+    fn strings_escape_only_what_json_requires() {
+        assert_eq!(
+            canonical(json!("a\"b\\c\n\u{1}/é\u{2028}")),
+            "\"a\\\"b\\\\c\\n\\u0001/é\u{2028}\""
+        );
+    }
+
+    #[test]
+    fn output_parses_back_to_the_same_value() {
+        let value = json!({ "content": "hi\u{7f}", "n": [1, 2.25, true, null], "e": {} });
+        let parsed: Value = serde_json::from_slice(&to_vec(&value)).unwrap();
+        assert_eq!(parsed, value);
+    }
+}
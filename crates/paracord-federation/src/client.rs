@@ -1,7 +1,10 @@
+use crate::canonical;
 use crate::protocol::{FederatedEvent, ServerInfo};
 use crate::signing;
 use crate::transport;
-use crate::{FederationError, FederationEventEnvelope, FederationServerKey};
+use crate::{
+    FederationError, FederationEventEnvelope, FederationServerKey, SIGNATURE_VERSION_LEGACY,
+};
 use ed25519_dalek::SigningKey;
 use reqwest::Client;
 use std::time::Duration;
@@ -99,7 +102,7 @@ impl FederationClient {
     ) -> Result<PostEventResponse, FederationError> {
         let url = format!("{}/event", federation_endpoint.trim_end_matches('/'));
         let body_bytes =
-            serde_json::to_vec(envelope).map_err(|e| FederationError::Http(e.to_string()))?;
+            canonical::to_vec_from(envelope).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body_bytes).await?;
         let body: PostEventResponse = resp
             .json()
@@ -126,6 +129,7 @@ impl FederationClient {
             depth: 0,
             state_key: None,
             signatures: event.signatures.clone(),
+            signature_version: SIGNATURE_VERSION_LEGACY,
         };
         self.post_event(federation_endpoint, &envelope).await
     }
@@ -183,7 +187,8 @@ impl FederationClient {
         payload: &FederationInviteRequest,
     ) -> Result<FederationInviteResponse, FederationError> {
         let url = format!("{}/invite", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -196,7 +201,8 @@ impl FederationClient {
         payload: &FederationJoinRequest,
     ) -> Result<FederationJoinResponse, FederationError> {
         let url = format!("{}/join", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -209,7 +215,8 @@ impl FederationClient {
         payload: &FederationLeaveRequest,
     ) -> Result<FederationLeaveResponse, FederationError> {
         let url = format!("{}/leave", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -222,7 +229,8 @@ impl FederationClient {
         payload: &FederationMediaTokenRequest,
     ) -> Result<FederationMediaTokenResponse, FederationError> {
         let url = format!("{}/media/token", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -235,7 +243,8 @@ impl FederationClient {
         payload: &FederationMediaRelayRequest,
     ) -> Result<FederationMediaRelayResponse, FederationError> {
         let url = format!("{}/media/relay", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -248,7 +257,8 @@ impl FederationClient {
         payload: &FederationFileTokenRequest,
     ) -> Result<FederationFileTokenResponse, FederationError> {
         let url = format!("{}/file/token", federation_endpoint.trim_end_matches('/'));
-        let body = serde_json::to_vec(payload).map_err(|e| FederationError::Http(e.to_string()))?;
+        let body =
+            canonical::to_vec_from(payload).map_err(|e| FederationError::Http(e.to_string()))?;
         let resp = self.post_with_retry(&url, body).await?;
         resp.json()
             .await
@@ -282,6 +292,38 @@ impl FederationClient {
         Ok((bytes.to_vec(), content_type, filename))
     }
 
+    /// Search a peer's user directory. Searches are interactive, so a failed
+    /// request is not retried.
+    pub async fn search_users(
+        &self,
+        federation_endpoint: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<FederationUserSearchResponse, FederationError> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/users/search", federation_endpoint.trim_end_matches('/')),
+            &[("q", query.to_string()), ("limit", limit.to_string())],
+        )
+        .map_err(|e| FederationError::Http(e.to_string()))?;
+        let path = transport::request_path_from_url(url.as_str());
+        let request =
+            self.with_transport_signature_headers(self.http.get(url.clone()), "GET", &path, &[]);
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| FederationError::Http(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(FederationError::RemoteError(format!(
+                "request to {} returned {}",
+                url,
+                resp.status()
+            )));
+        }
+        resp.json()
+            .await
+            .map_err(|e| FederationError::RemoteError(format!("invalid user search response: {e}")))
+    }
+
     /// GET request with exponential backoff retry.
     async fn get_with_retry(&self, url: &str) -> Result<reqwest::Response, FederationError> {
         self.get_with_retry_with_headers(url, &[]).await
@@ -516,3 +558,16 @@ pub struct FederationFileTokenResponse {
     pub download_url: String,
     pub expires_in_seconds: i64,
 }
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FederationDirectoryUser {
+    /// Canonical `@username:server` identity.
+    pub user_id: String,
+    pub username: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FederationUserSearchResponse {
+    pub users: Vec<FederationDirectoryUser>,
+}
@@ -1,3 +1,5 @@
+pub mod bandwidth;
+pub mod canonical;
 pub mod client;
 pub mod protocol;
 pub mod signing;
@@ -26,6 +28,10 @@ pub enum FederationError {
     RemoteError(String),
     #[error("unknown server: {0}")]
     UnknownServer(String),
+    #[error("invalid federation envelope: {0}")]
+    InvalidEnvelope(&'static str),
+    #[error("invalid federation transport headers: {0}")]
+    InvalidTransportHeaders(&'static str),
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +75,29 @@ pub struct FederationEventEnvelope {
     pub depth: i64,
     pub state_key: Option<String>,
     pub signatures: Value,
+    /// Which byte encoding `signatures` were made over. Peers that predate
+    /// versioning omit the field, which reads as [`SIGNATURE_VERSION_LEGACY`].
+    #[serde(
+        default = "legacy_signature_version",
+        skip_serializing_if = "is_legacy_signature_version"
+    )]
+    pub signature_version: u32,
+}
+
+/// Signatures over `serde_json::to_vec` output, whose key order and number
+/// formatting depend on the sender's build. Verified, never produced.
+pub const SIGNATURE_VERSION_LEGACY: u32 = 1;
+/// Signatures over [`canonical`] JSON.
+pub const SIGNATURE_VERSION_CANONICAL: u32 = 2;
+
+fn legacy_signature_version() -> u32 {
+    SIGNATURE_VERSION_LEGACY
+}
+
+// Legacy envelopes are re-encoded exactly as their senders encoded them, so
+// transport body hashes still match.
+fn is_legacy_signature_version(version: &u32) -> bool {
+    *version == SIGNATURE_VERSION_LEGACY
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -158,8 +187,8 @@ impl FederationService {
             return Err(FederationError::Disabled);
         }
         let rows = sqlx::query(
-            "INSERT INTO federation_events (event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "INSERT INTO federation_events (event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures, signature_version)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
              ON CONFLICT (event_id) DO NOTHING",
         )
         .bind(&envelope.event_id)
@@ -180,6 +209,7 @@ impl FederationService {
                 "invalid federation signatures json: {e}"
             )))
         })?)
+        .bind(i64::from(envelope.signature_version))
         .execute(pool)
         .await?
         .rows_affected();
@@ -195,7 +225,7 @@ impl FederationService {
             return Err(FederationError::Disabled);
         }
         let row = sqlx::query_as::<_, FederationEventEnvelopeRow>(
-            "SELECT event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures
+            "SELECT event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures, signature_version
              FROM federation_events WHERE event_id = $1",
         )
         .bind(event_id)
@@ -260,6 +290,7 @@ impl FederationService {
         content: &Value,
         channel_name: Option<&str>,
         channel_type: Option<i16>,
+        channel_nsfw: Option<bool>,
         guild_name: Option<&str>,
         timestamp_ms: i64,
     ) -> Result<FederationEventEnvelope, FederationError> {
@@ -283,6 +314,9 @@ impl FederationService {
         if let Some(kind) = channel_type {
             message_content["channel_type"] = Value::Number(serde_json::Number::from(kind));
         }
+        if let Some(nsfw) = channel_nsfw {
+            message_content["channel_nsfw"] = Value::Bool(nsfw);
+        }
         if let Some(name) = guild_name {
             message_content["guild_name"] = Value::String(name.to_string());
         }
@@ -299,18 +333,27 @@ impl FederationService {
             depth: timestamp_ms,
             state_key: None,
             signatures: serde_json::json!({}),
+            signature_version: SIGNATURE_VERSION_CANONICAL,
         };
 
-        // Build canonical payload (excluding signatures) and sign it
-        let canonical = canonical_envelope_bytes(&envelope);
+        self.sign_envelope(&mut envelope)?;
+        Ok(envelope)
+    }
+
+    /// Replace `envelope`'s signatures with our own over its current
+    /// contents. Call again after changing a built envelope's content.
+    pub fn sign_envelope(
+        &self,
+        envelope: &mut FederationEventEnvelope,
+    ) -> Result<(), FederationError> {
+        let canonical = canonical_envelope_bytes(envelope);
         let signature_hex = self.sign_payload(&canonical)?;
         envelope.signatures = serde_json::json!({
             self.config.server_name.clone(): {
                 self.config.key_id.clone(): signature_hex,
             }
         });
-
-        Ok(envelope)
+        Ok(())
     }
 
     /// Build a signed custom federation event envelope.
@@ -330,7 +373,7 @@ impl FederationService {
         }
 
         let event_suffix = event_stable_id.map(str::to_string).unwrap_or_else(|| {
-            let content_bytes = serde_json::to_vec(content).unwrap_or_default();
+            let content_bytes = canonical::to_vec(content);
             let digest = transport::sha256_hex(&content_bytes);
             digest.chars().take(12).collect::<String>()
         });
@@ -355,16 +398,10 @@ impl FederationService {
             depth: timestamp_ms,
             state_key,
             signatures: serde_json::json!({}),
+            signature_version: SIGNATURE_VERSION_CANONICAL,
         };
 
-        let canonical = canonical_envelope_bytes(&envelope);
-        let signature_hex = self.sign_payload(&canonical)?;
-        envelope.signatures = serde_json::json!({
-            self.config.server_name.clone(): {
-                self.config.key_id.clone(): signature_hex,
-            }
-        });
-
+        self.sign_envelope(&mut envelope)?;
         Ok(envelope)
     }
 
@@ -462,6 +499,7 @@ impl FederationService {
                 return;
             }
         };
+        let envelope_len = canonical::to_vec_from(envelope).map_or(0, |body| body.len());
 
         for peer in &peers {
             // Don't forward back to ourselves
@@ -484,91 +522,155 @@ impl FederationService {
                 }
             }
 
-            if let Err(e) = paracord_db::federation::enqueue_outbound_event(
-                pool,
-                &peer.server_name,
-                &envelope.event_id,
-                &envelope.room_id,
-                &envelope.event_type,
-                &envelope.sender,
-                &envelope.origin_server,
-                envelope.origin_ts,
-                &envelope.content,
-                envelope.depth,
-                envelope.state_key.as_deref(),
-                &envelope.signatures,
-                now_ms,
-            )
-            .await
-            {
-                tracing::warn!(
-                    "federation: failed to enqueue outbound event {} for {}: {}",
+            self.deliver_to_peer(pool, &client, peer, envelope, envelope_len, now_ms)
+                .await;
+        }
+    }
+
+    /// Send an envelope to one trusted peer only, for events such as DM
+    /// receipts that concern a single remote server. Returns false when the
+    /// server isn't a trusted peer.
+    pub async fn send_envelope_to_server(
+        &self,
+        pool: &DbPool,
+        envelope: &FederationEventEnvelope,
+        server_name: &str,
+    ) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let peers = match paracord_db::federation::list_trusted_federated_servers(pool).await {
+            Ok(servers) => servers,
+            Err(e) => {
+                tracing::error!("federation: failed to list trusted peers: {e}");
+                return false;
+            }
+        };
+        let Some(peer) = peers.iter().find(|peer| {
+            peer.server_name.eq_ignore_ascii_case(server_name)
+                || peer.domain.eq_ignore_ascii_case(server_name)
+        }) else {
+            return false;
+        };
+        let client = match self.build_signed_client() {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!("federation: failed to create HTTP client: {e}");
+                return false;
+            }
+        };
+        let envelope_len = canonical::to_vec_from(envelope).map_or(0, |body| body.len());
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        self.deliver_to_peer(pool, &client, peer, envelope, envelope_len, now_ms)
+            .await;
+        true
+    }
+
+    /// Queue an envelope for `peer` and attempt delivery right away. Failed
+    /// attempts stay queued for the outbound retry worker.
+    async fn deliver_to_peer(
+        &self,
+        pool: &DbPool,
+        client: &FederationClient,
+        peer: &paracord_db::federation::FederatedServerRow,
+        envelope: &FederationEventEnvelope,
+        envelope_len: usize,
+        now_ms: i64,
+    ) {
+        if let Err(e) = paracord_db::federation::enqueue_outbound_event(
+            pool,
+            &peer.server_name,
+            &envelope.event_id,
+            &envelope.room_id,
+            &envelope.event_type,
+            &envelope.sender,
+            &envelope.origin_server,
+            envelope.origin_ts,
+            &envelope.content,
+            envelope.depth,
+            envelope.state_key.as_deref(),
+            &envelope.signatures,
+            envelope.signature_version,
+            now_ms,
+        )
+        .await
+        {
+            tracing::warn!(
+                "federation: failed to enqueue outbound event {} for {}: {}",
+                envelope.event_id,
+                peer.server_name,
+                e
+            );
+        }
+
+        let attempt_started = std::time::Instant::now();
+        let delivered = client.post_event(&peer.federation_endpoint, envelope).await;
+        bandwidth::record(
+            pool,
+            &peer.server_name,
+            bandwidth::CATEGORY_EVENTS,
+            0,
+            envelope_len,
+        )
+        .await;
+        match delivered {
+            Ok(resp) => {
+                let latency_ms = attempt_started.elapsed().as_millis() as i64;
+                let attempt_ts = chrono::Utc::now().timestamp_millis();
+                let _ = paracord_db::federation::record_delivery_attempt(
+                    pool,
+                    &peer.server_name,
+                    &envelope.event_id,
+                    true,
+                    Some(202),
+                    None,
+                    Some(latency_ms),
+                    attempt_ts,
+                )
+                .await;
+                let _ = paracord_db::federation::mark_outbound_event_delivered(
+                    pool,
+                    &peer.server_name,
+                    &envelope.event_id,
+                )
+                .await;
+                tracing::info!(
+                    "federation: forwarded event {} to {} (inserted={})",
                     envelope.event_id,
                     peer.server_name,
-                    e
+                    resp.inserted,
                 );
             }
-
-            let attempt_started = std::time::Instant::now();
-            match client.post_event(&peer.federation_endpoint, envelope).await {
-                Ok(resp) => {
-                    let latency_ms = attempt_started.elapsed().as_millis() as i64;
-                    let attempt_ts = chrono::Utc::now().timestamp_millis();
-                    let _ = paracord_db::federation::record_delivery_attempt(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        true,
-                        Some(202),
-                        None,
-                        Some(latency_ms),
-                        attempt_ts,
-                    )
-                    .await;
-                    let _ = paracord_db::federation::mark_outbound_event_delivered(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                    )
-                    .await;
-                    tracing::info!(
-                        "federation: forwarded event {} to {} (inserted={})",
-                        envelope.event_id,
-                        peer.server_name,
-                        resp.inserted,
-                    );
-                }
-                Err(e) => {
-                    let latency_ms = attempt_started.elapsed().as_millis() as i64;
-                    let attempt_ts = chrono::Utc::now().timestamp_millis();
-                    let retry_at = next_retry_ts(attempt_ts, 0);
-                    let err_msg = e.to_string();
-                    let _ = paracord_db::federation::record_delivery_attempt(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        false,
-                        None,
-                        Some(&err_msg),
-                        Some(latency_ms),
-                        attempt_ts,
-                    )
-                    .await;
-                    let _ = paracord_db::federation::mark_outbound_event_retry(
-                        pool,
-                        &peer.server_name,
-                        &envelope.event_id,
-                        retry_at,
-                        Some(&err_msg),
-                        attempt_ts,
-                    )
-                    .await;
-                    tracing::warn!(
-                        "federation: failed to forward event {} to {}: {e}",
-                        envelope.event_id,
-                        peer.server_name,
-                    );
-                }
+            Err(e) => {
+                let latency_ms = attempt_started.elapsed().as_millis() as i64;
+                let attempt_ts = chrono::Utc::now().timestamp_millis();
+                let retry_at = next_retry_ts(attempt_ts, 0);
+                let err_msg = e.to_string();
+                let _ = paracord_db::federation::record_delivery_attempt(
+                    pool,
+                    &peer.server_name,
+                    &envelope.event_id,
+                    false,
+                    None,
+                    Some(&err_msg),
+                    Some(latency_ms),
+                    attempt_ts,
+                )
+                .await;
+                let _ = paracord_db::federation::mark_outbound_event_retry(
+                    pool,
+                    &peer.server_name,
+                    &envelope.event_id,
+                    retry_at,
+                    Some(&err_msg),
+                    attempt_ts,
+                )
+                .await;
+                tracing::warn!(
+                    "federation: failed to forward event {} to {}: {e}",
+                    envelope.event_id,
+                    peer.server_name,
+                );
             }
         }
     }
@@ -633,10 +735,19 @@ impl FederationService {
                 depth: row.depth,
                 state_key: row.state_key.clone(),
                 signatures: row.signatures.clone(),
+                signature_version: row.signature_version,
             };
 
             let started = std::time::Instant::now();
             let delivered = client.post_event(&row.federation_endpoint, &envelope).await;
+            bandwidth::record(
+                pool,
+                &row.destination_server,
+                bandwidth::CATEGORY_EVENTS,
+                0,
+                canonical::to_vec_from(&envelope).map_or(0, |body| body.len()),
+            )
+            .await;
             let attempt_ts = chrono::Utc::now().timestamp_millis();
             let latency_ms = started.elapsed().as_millis() as i64;
 
@@ -712,7 +823,7 @@ impl FederationService {
             return Err(FederationError::Disabled);
         }
         let rows = sqlx::query_as::<_, FederationEventEnvelopeRow>(
-            "SELECT event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures
+            "SELECT event_id, room_id, event_type, sender, origin_server, origin_ts, content, depth, state_key, signatures, signature_version
              FROM federation_events
              WHERE room_id = $1
                AND depth > $2
@@ -734,8 +845,27 @@ fn next_retry_ts(now_ms: i64, attempt_count: i64) -> i64 {
     now_ms.saturating_add(delay_ms.min(3_600_000))
 }
 
-/// Build the canonical bytes used for signing an envelope (excludes signatures).
+/// Build the bytes an envelope's signatures cover (everything except
+/// `signatures`), in the encoding its `signature_version` names.
 pub fn canonical_envelope_bytes(envelope: &FederationEventEnvelope) -> Vec<u8> {
+    if envelope.signature_version == SIGNATURE_VERSION_LEGACY {
+        return legacy_envelope_bytes(envelope);
+    }
+    canonical::to_vec(&serde_json::json!({
+        "event_id": envelope.event_id,
+        "room_id": envelope.room_id,
+        "event_type": envelope.event_type,
+        "sender": envelope.sender,
+        "origin_server": envelope.origin_server,
+        "origin_ts": envelope.origin_ts,
+        "content": envelope.content,
+        "depth": envelope.depth,
+        "state_key": envelope.state_key,
+        "signature_version": envelope.signature_version,
+    }))
+}
+
+fn legacy_envelope_bytes(envelope: &FederationEventEnvelope) -> Vec<u8> {
     serde_json::to_vec(&serde_json::json!({
         "event_id": envelope.event_id,
         "room_id": envelope.room_id,
@@ -750,6 +880,73 @@ pub fn canonical_envelope_bytes(envelope: &FederationEventEnvelope) -> Vec<u8> {
     .unwrap_or_default()
 }
 
+/// Longest accepted envelope id, room id, sender, origin or state key.
+pub const MAX_ENVELOPE_FIELD_LEN: usize = 255;
+/// Longest accepted envelope event type.
+pub const MAX_EVENT_TYPE_LEN: usize = 128;
+
+fn has_control_chars(value: &str) -> bool {
+    value.chars().any(char::is_control)
+}
+
+fn check_field(value: &str, max_len: usize, reason: &'static str) -> Result<(), FederationError> {
+    if value.is_empty() || value.len() > max_len || has_control_chars(value) {
+        return Err(FederationError::InvalidEnvelope(reason));
+    }
+    Ok(())
+}
+
+/// NUL can't be stored in PostgreSQL text columns; reject it up front.
+fn contains_nul(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.contains('\0'),
+        Value::Array(items) => items.iter().any(contains_nul),
+        Value::Object(map) => map.iter().any(|(k, v)| k.contains('\0') || contains_nul(v)),
+        _ => false,
+    }
+}
+
+impl FederationEventEnvelope {
+    /// Check the fields a peer controls before the envelope is verified or
+    /// stored. Content size and depth limits are enforced by the ingest route.
+    pub fn validate(&self) -> Result<(), FederationError> {
+        check_field(&self.event_id, MAX_ENVELOPE_FIELD_LEN, "event_id")?;
+        check_field(&self.room_id, MAX_ENVELOPE_FIELD_LEN, "room_id")?;
+        check_field(&self.sender, MAX_ENVELOPE_FIELD_LEN, "sender")?;
+        check_field(&self.origin_server, MAX_ENVELOPE_FIELD_LEN, "origin_server")?;
+        if self.event_type.is_empty()
+            || self.event_type.len() > MAX_EVENT_TYPE_LEN
+            || !self
+                .event_type
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+        {
+            return Err(FederationError::InvalidEnvelope("event_type"));
+        }
+        if let Some(state_key) = &self.state_key {
+            if state_key.len() > MAX_ENVELOPE_FIELD_LEN || has_control_chars(state_key) {
+                return Err(FederationError::InvalidEnvelope("state_key"));
+            }
+        }
+        if self.origin_ts < 0 {
+            return Err(FederationError::InvalidEnvelope("origin_ts"));
+        }
+        if !matches!(
+            self.signature_version,
+            SIGNATURE_VERSION_LEGACY | SIGNATURE_VERSION_CANONICAL
+        ) {
+            return Err(FederationError::InvalidEnvelope("signature_version"));
+        }
+        if !self.signatures.is_object() {
+            return Err(FederationError::InvalidEnvelope("signatures"));
+        }
+        if contains_nul(&self.content) || contains_nul(&self.signatures) {
+            return Err(FederationError::InvalidEnvelope("content"));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FederationEventEnvelopeRow {
     event_id: String,
@@ -762,6 +959,7 @@ struct FederationEventEnvelopeRow {
     depth: i64,
     state_key: Option<String>,
     signatures: Value,
+    signature_version: u32,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for FederationEventEnvelopeRow {
@@ -783,6 +981,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::any::AnyRow> for FederationEventEnvelopeRow {
             depth: row.try_get("depth")?,
             state_key: row.try_get("state_key")?,
             signatures,
+            signature_version: row.try_get::<i64, _>("signature_version")? as u32,
         })
     }
 }
@@ -800,6 +999,7 @@ impl From<FederationEventEnvelopeRow> for FederationEventEnvelope {
             depth: value.depth,
             state_key: value.state_key,
             signatures: value.signatures,
+            signature_version: value.signature_version,
         }
     }
 }
@@ -819,18 +1019,26 @@ pub fn hex_encode(bytes: &[u8]) -> String {
     out
 }
 
+/// Decode a hex string. Only ASCII hex digits are accepted, so peer input
+/// with signs or multi-byte characters is rejected instead of misparsed.
 pub fn hex_decode(value: &str) -> Option<Vec<u8>> {
-    if !value.len().is_multiple_of(2) {
+    let bytes = value.as_bytes();
+    if !bytes.len().is_multiple_of(2) {
         return None;
     }
-    let mut out = Vec::with_capacity(value.len() / 2);
-    let mut i = 0;
-    while i < value.len() {
-        let byte = u8::from_str_radix(&value[i..i + 2], 16).ok()?;
-        out.push(byte);
-        i += 2;
+    bytes
+        .chunks_exact(2)
+        .map(|pair| Some((hex_digit(pair[0])? << 4) | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
     }
-    Some(out)
 }
 
 #[cfg(test)]
@@ -862,11 +1070,13 @@ mod tests {
                 &serde_json::json!("hello"),
                 Some("general"),
                 Some(0),
+                Some(true),
                 Some("Guild"),
                 ts,
             )
             .expect("message envelope should build");
         assert_eq!(env.room_id, "!300:chat.example");
+        assert_eq!(env.content["channel_nsfw"], serde_json::json!(true));
         assert_eq!(env.depth, ts);
         assert_eq!(env.event_type, "m.message");
     }
@@ -889,4 +1099,76 @@ mod tests {
         assert_eq!(env.depth, ts);
         assert_eq!(env.room_id, "!42:chat.example");
     }
+
+    #[test]
+    fn new_envelopes_sign_canonical_json() {
+        let service = test_service();
+        let env = service
+            .build_custom_envelope(
+                "m.member.join",
+                "!42:chat.example".to_string(),
+                "bob",
+                &serde_json::json!({"user_id":"123","guild_id":"42"}),
+                1_700_000_000_999,
+                None,
+                Some("42:123"),
+            )
+            .expect("custom envelope should build");
+        assert_eq!(env.signature_version, SIGNATURE_VERSION_CANONICAL);
+        let bytes = canonical_envelope_bytes(&env);
+        assert!(String::from_utf8_lossy(&bytes)
+            .starts_with(r#"{"content":{"guild_id":"42","user_id":"123"},"depth":"#));
+        let signature = env.signatures["node-a.example"]["ed25519:test"]
+            .as_str()
+            .unwrap();
+        let public_key = service.signing_public_key().unwrap();
+        assert!(service
+            .verify_payload(&bytes, signature, &public_key)
+            .is_ok());
+
+        // The version is signed, so it can't be swapped for the legacy one.
+        let mut downgraded = env.clone();
+        downgraded.signature_version = SIGNATURE_VERSION_LEGACY;
+        assert!(service
+            .verify_payload(
+                &canonical_envelope_bytes(&downgraded),
+                signature,
+                &public_key
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn envelopes_without_a_version_verify_with_legacy_bytes() {
+        let service = test_service();
+        let mut env: FederationEventEnvelope = serde_json::from_value(serde_json::json!({
+            "event_id": "$m_message:1:chat.example",
+            "room_id": "!42:chat.example",
+            "event_type": "m.message",
+            "sender": "@bob:chat.example",
+            "origin_server": "node-a.example",
+            "origin_ts": 1_700_000_000_000_i64,
+            "content": {"body": "hi"},
+            "depth": 1_700_000_000_000_i64,
+            "state_key": null,
+            "signatures": {},
+        }))
+        .unwrap();
+        assert_eq!(env.signature_version, SIGNATURE_VERSION_LEGACY);
+        let signature = service.sign_payload(&legacy_envelope_bytes(&env)).unwrap();
+        assert!(service
+            .verify_payload(
+                &canonical_envelope_bytes(&env),
+                &signature,
+                &service.signing_public_key().unwrap()
+            )
+            .is_ok());
+
+        // Re-encoding a legacy envelope must not add the field its sender never sent.
+        env.signatures = serde_json::json!({});
+        assert!(serde_json::to_value(&env)
+            .unwrap()
+            .get("signature_version")
+            .is_none());
+    }
 }
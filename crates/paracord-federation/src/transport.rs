@@ -1,7 +1,81 @@
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 
+use crate::{canonical, FederationError};
+
 pub const DEFAULT_MAX_SKEW_MS: i64 = 60_000;
 
+/// Ed25519 signatures are 64 bytes, sent as 128 hex chars.
+const SIGNATURE_HEX_LEN: usize = 128;
+const MAX_ORIGIN_LEN: usize = 255;
+const MAX_KEY_ID_LEN: usize = 128;
+/// Digits in the largest positive `i64`.
+const MAX_TIMESTAMP_DIGITS: usize = 19;
+
+/// The `X-Paracord-*` headers that authenticate a server-to-server request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportHeaders {
+    pub origin: String,
+    pub key_id: String,
+    pub timestamp_ms: i64,
+    pub signature_hex: String,
+}
+
+fn header_value<'a>(
+    raw: Option<&'a str>,
+    max_len: usize,
+    reason: &'static str,
+) -> Result<&'a str, FederationError> {
+    let value = raw.map(str::trim).unwrap_or_default();
+    if value.is_empty()
+        || value.len() > max_len
+        || !value.bytes().all(|b| b.is_ascii_graphic() || b == b' ')
+    {
+        return Err(FederationError::InvalidTransportHeaders(reason));
+    }
+    Ok(value)
+}
+
+/// Parse the raw `X-Paracord-Origin`, `-Key-Id`, `-Timestamp` and
+/// `-Signature` values. Anything that can't be a real signed request is
+/// rejected here, before keys are looked up.
+pub fn parse_transport_headers(
+    origin: Option<&str>,
+    key_id: Option<&str>,
+    timestamp: Option<&str>,
+    signature: Option<&str>,
+) -> Result<TransportHeaders, FederationError> {
+    let origin = header_value(origin, MAX_ORIGIN_LEN, "origin")?;
+    if origin.contains(['/', '@', '?', '#']) {
+        return Err(FederationError::InvalidTransportHeaders("origin"));
+    }
+    let key_id = header_value(key_id, MAX_KEY_ID_LEN, "key_id")?;
+    let timestamp = header_value(timestamp, MAX_TIMESTAMP_DIGITS, "timestamp")?;
+    if !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(FederationError::InvalidTransportHeaders("timestamp"));
+    }
+    let timestamp_ms = timestamp
+        .parse::<i64>()
+        .map_err(|_| FederationError::InvalidTransportHeaders("timestamp"))?;
+    let signature_hex = header_value(signature, SIGNATURE_HEX_LEN, "signature")?;
+    if signature_hex.len() != SIGNATURE_HEX_LEN
+        || !signature_hex.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return Err(FederationError::InvalidTransportHeaders("signature"));
+    }
+    Ok(TransportHeaders {
+        origin: origin.to_string(),
+        key_id: key_id.to_string(),
+        timestamp_ms,
+        signature_hex: signature_hex.to_string(),
+    })
+}
+
+/// Whether a request timestamp is within `max_skew_ms` of `now_ms`.
+pub fn timestamp_within_skew(now_ms: i64, timestamp_ms: i64, max_skew_ms: i64) -> bool {
+    now_ms.abs_diff(timestamp_ms) <= max_skew_ms.unsigned_abs()
+}
+
 pub fn sha256_hex(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
@@ -52,6 +126,22 @@ pub fn canonical_transport_bytes_with_body(
     canonical_transport_bytes(method, path, timestamp_ms, &sha256_hex(body))
 }
 
+/// The byte encodings a sender may have hashed for a JSON request body,
+/// canonical first. Receivers only see the parsed body, so they re-encode it;
+/// peers that predate canonical JSON hashed `serde_json::to_vec` output.
+pub fn json_body_encodings<T: Serialize + ?Sized>(body: &T) -> Vec<Vec<u8>> {
+    let mut encodings = Vec::with_capacity(2);
+    if let Ok(bytes) = canonical::to_vec_from(body) {
+        encodings.push(bytes);
+    }
+    if let Ok(bytes) = serde_json::to_vec(body) {
+        if !encodings.contains(&bytes) {
+            encodings.push(bytes);
+        }
+    }
+    encodings
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +154,12 @@ mod tests {
             "POST\n/_paracord/federation/v1/event\n123\nabc"
         );
     }
+
+    #[test]
+    fn json_body_encodings_put_canonical_first() {
+        let body = serde_json::json!({ "b": 1, "a": 2 });
+        let encodings = json_body_encodings(&body);
+        assert_eq!(encodings[0], br#"{"a":2,"b":1}"#.to_vec());
+        assert!(encodings.contains(&serde_json::to_vec(&body).unwrap()));
+    }
 }
@@ -0,0 +1,257 @@
+use ed25519_dalek::SigningKey;
+use paracord_federation::transport::{
+    canonical_transport_bytes_with_body, parse_transport_headers, timestamp_within_skew,
+};
+use paracord_federation::{
+    canonical_envelope_bytes, hex_decode, hex_encode, signing, FederationEventEnvelope,
+    SIGNATURE_VERSION_CANONICAL, SIGNATURE_VERSION_LEGACY,
+};
+use proptest::prelude::*;
+use serde_json::Value;
+
+/// JSON without floats; floats don't survive a text round trip exactly.
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        "\\PC{0,24}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(4, 48, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::btree_map("\\PC{0,12}", inner, 0..6)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+fn envelope() -> impl Strategy<Value = FederationEventEnvelope> {
+    (
+        "[a-z0-9$:.]{1,64}",
+        "![0-9]{1,19}:[a-z.]{1,32}",
+        "m\\.[a-z.]{1,32}",
+        "@[a-z0-9_]{1,24}:[a-z.]{1,32}",
+        "[a-z.]{1,32}",
+        0i64..i64::MAX,
+        json_value(),
+        any::<i64>(),
+        prop::option::of("[ -~]{0,64}"),
+        prop_oneof![
+            Just(SIGNATURE_VERSION_LEGACY),
+            Just(SIGNATURE_VERSION_CANONICAL)
+        ],
+    )
+        .prop_map(
+            |(
+                event_id,
+                room_id,
+                event_type,
+                sender,
+                origin_server,
+                origin_ts,
+                content,
+                depth,
+                state_key,
+                signature_version,
+            )| FederationEventEnvelope {
+                event_id,
+                room_id,
+                event_type,
+                sender,
+                origin_server,
+                origin_ts,
+                content,
+                depth,
+                state_key,
+                signatures: serde_json::json!({}),
+                signature_version,
+            },
+        )
+}
+
+/// Rebuild every object with its keys inserted in reverse order; only
+/// observable when `serde_json` preserves insertion order.
+fn reverse_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .rev()
+                .map(|(k, v)| (k.clone(), reverse_keys(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(reverse_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+fn test_key() -> SigningKey {
+    SigningKey::from_bytes(&[11u8; 32])
+}
+
+proptest! {
+    #[test]
+    fn hex_round_trips(bytes in prop::collection::vec(any::<u8>(), 0..96)) {
+        let encoded = hex_encode(&bytes);
+        prop_assert_eq!(hex_decode(&encoded), Some(bytes.clone()));
+        prop_assert_eq!(hex_decode(&encoded.to_ascii_uppercase()), Some(bytes));
+    }
+
+    #[test]
+    fn hex_decode_accepts_only_hex_digits(input in "\\PC{0,64}") {
+        if let Some(bytes) = hex_decode(&input) {
+            prop_assert!(input.bytes().all(|b| b.is_ascii_hexdigit()));
+            prop_assert_eq!(hex_encode(&bytes), input.to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn generated_envelopes_validate(env in envelope()) {
+        prop_assert!(env.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_never_panics_on_arbitrary_fields(
+        event_id in "\\PC{0,300}",
+        event_type in "\\PC{0,140}",
+        origin_ts in any::<i64>(),
+        content in json_value(),
+    ) {
+        let env = FederationEventEnvelope {
+            event_id,
+            room_id: "!1:remote.example".into(),
+            event_type,
+            sender: "@alice:remote.example".into(),
+            origin_server: "remote.example".into(),
+            origin_ts,
+            content,
+            depth: 0,
+            state_key: None,
+            signatures: serde_json::json!({}),
+            signature_version: SIGNATURE_VERSION_CANONICAL,
+        };
+        let _ = env.validate();
+    }
+
+    #[test]
+    fn canonical_bytes_survive_json_round_trip(env in envelope()) {
+        let wire = serde_json::to_vec(&env).unwrap();
+        let parsed: FederationEventEnvelope = serde_json::from_slice(&wire).unwrap();
+        prop_assert_eq!(canonical_envelope_bytes(&parsed), canonical_envelope_bytes(&env));
+    }
+
+    #[test]
+    fn canonical_bytes_exclude_signatures(env in envelope(), signatures in json_value()) {
+        let mut signed = env.clone();
+        signed.signatures = signatures;
+        prop_assert_eq!(canonical_envelope_bytes(&signed), canonical_envelope_bytes(&env));
+    }
+
+    #[test]
+    fn canonical_bytes_ignore_content_key_order(env in envelope()) {
+        prop_assume!(env.signature_version == SIGNATURE_VERSION_CANONICAL);
+        let reordered = reverse_keys(&env.content);
+        let mut other = env.clone();
+        other.content = reordered;
+        prop_assert_eq!(canonical_envelope_bytes(&other), canonical_envelope_bytes(&env));
+    }
+
+    #[test]
+    fn signatures_bind_every_signed_field(env in envelope(), suffix in "[a-z]{1,8}") {
+        let key = test_key();
+        let public_hex = hex_encode(&key.verifying_key().to_bytes());
+        let signature = signing::sign(&key, &canonical_envelope_bytes(&env));
+        prop_assert!(signing::verify(&canonical_envelope_bytes(&env), &signature, &public_hex).is_ok());
+
+        let mut tampered = env.clone();
+        tampered.sender.push_str(&suffix);
+        prop_assert!(signing::verify(&canonical_envelope_bytes(&tampered), &signature, &public_hex).is_err());
+
+        let mut tampered = env.clone();
+        tampered.origin_ts = tampered.origin_ts.wrapping_add(1);
+        prop_assert!(signing::verify(&canonical_envelope_bytes(&tampered), &signature, &public_hex).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_garbage_signatures(
+        payload in prop::collection::vec(any::<u8>(), 0..64),
+        signature in "\\PC{0,160}",
+        public_key in "\\PC{0,80}",
+    ) {
+        prop_assert!(signing::verify(&payload, &signature, &public_key).is_err());
+    }
+
+    #[test]
+    fn transport_headers_round_trip(
+        origin in "[a-z0-9.-]{1,64}(:[0-9]{1,5})?",
+        key_id in "ed25519:[a-z0-9]{1,16}",
+        timestamp_ms in 0i64..i64::MAX,
+        body in prop::collection::vec(any::<u8>(), 0..64),
+    ) {
+        let canonical = canonical_transport_bytes_with_body("POST", "/_paracord/federation/v1/event", timestamp_ms, &body);
+        let signature = signing::sign(&test_key(), &canonical);
+        let timestamp = timestamp_ms.to_string();
+        let parsed = parse_transport_headers(
+            Some(&origin),
+            Some(&key_id),
+            Some(&timestamp),
+            Some(&signature),
+        )
+        .unwrap();
+        prop_assert_eq!(parsed.origin, origin);
+        prop_assert_eq!(parsed.key_id, key_id);
+        prop_assert_eq!(parsed.timestamp_ms, timestamp_ms);
+        prop_assert_eq!(parsed.signature_hex, signature);
+    }
+
+    #[test]
+    fn transport_header_parser_never_panics(
+        origin in prop::option::of("\\PC{0,300}"),
+        key_id in prop::option::of("\\PC{0,200}"),
+        timestamp in prop::option::of("\\PC{0,24}"),
+        signature in prop::option::of("\\PC{0,200}"),
+    ) {
+        if let Ok(headers) = parse_transport_headers(
+            origin.as_deref(),
+            key_id.as_deref(),
+            timestamp.as_deref(),
+            signature.as_deref(),
+        ) {
+            prop_assert!(headers.timestamp_ms >= 0);
+            prop_assert_eq!(headers.signature_hex.len(), 128);
+        }
+    }
+
+    #[test]
+    fn skew_check_handles_extreme_timestamps(now in any::<i64>(), ts in any::<i64>(), skew in any::<i64>()) {
+        let within = timestamp_within_skew(now, ts, skew);
+        prop_assert_eq!(within, (now as i128 - ts as i128).abs() <= (skew as i128).abs());
+    }
+}
+
+#[test]
+fn hex_decode_rejects_signs_and_multibyte_input() {
+    assert_eq!(hex_decode("+1+1"), None);
+    assert_eq!(hex_decode("aéa"), None);
+    assert_eq!(hex_decode("éé"), None);
+}
+
+#[test]
+fn transport_headers_reject_malformed_values() {
+    let signature = "ab".repeat(64);
+    let parse = |origin: &str, timestamp: &str, signature: &str| {
+        parse_transport_headers(
+            Some(origin),
+            Some("ed25519:test"),
+            Some(timestamp),
+            Some(signature),
+        )
+    };
+    assert!(parse("remote.example", "1700000000000", &signature).is_ok());
+    assert!(parse("remote.example", "-1", &signature).is_err());
+    assert!(parse("remote.example", "+1700000000000", &signature).is_err());
+    assert!(parse("remote.example", "99999999999999999999", &signature).is_err());
+    assert!(parse("remote.example/evil", "1700000000000", &signature).is_err());
+    assert!(parse("remote.example", "1700000000000", "abcd").is_err());
+    assert!(parse("remote.example", "1700000000000", &"zz".repeat(64)).is_err());
+}
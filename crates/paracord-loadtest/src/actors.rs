@@ -0,0 +1,272 @@
+use futures_util::{SinkExt, StreamExt};
+use paracord_models::gateway::{
+    OP_DISPATCH, OP_HEARTBEAT, OP_HEARTBEAT_ACK, OP_HELLO, OP_IDENTIFY, OP_INVALID_SESSION,
+};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::api::{id_field, Api};
+use crate::report::Recorder;
+
+/// Prefix of generated message content; the rest carries the send time so
+/// gateway actors can measure delivery latency.
+const CONTENT_PREFIX: &str = "loadtest:";
+const REACTION_EMOJI: &str = "%F0%9F%91%8D";
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// Time since a generated message was sent, read from its content.
+fn fanout_latency(content: &str) -> Option<Duration> {
+    let sent: u64 = content
+        .strip_prefix(CONTENT_PREFIX)?
+        .split(':')
+        .next()?
+        .parse()
+        .ok()?;
+    Some(Duration::from_micros(unix_micros().saturating_sub(sent)))
+}
+
+/// What a REST actor does on each tick.
+#[derive(Debug, Clone, Copy)]
+pub struct RestMix {
+    pub messages_per_second: f64,
+    pub reaction_ratio: f64,
+    pub voice_ratio: f64,
+}
+
+pub struct Target {
+    pub text_channel_id: i64,
+    pub voice_channel_id: Option<i64>,
+}
+
+/// One synthetic gateway connection held open until `deadline`.
+pub async fn run_gateway(
+    gateway_url: String,
+    token: String,
+    recorder: Arc<Recorder>,
+    deadline: Instant,
+) {
+    let started = Instant::now();
+    let (socket, _) = match tokio_tungstenite::connect_async(gateway_url.as_str()).await {
+        Ok(connected) => connected,
+        Err(err) => {
+            tracing::debug!("gateway connect failed: {}", err);
+            recorder.error("gateway_ready", "connect");
+            return;
+        }
+    };
+    let (mut sink, mut stream) = socket.split();
+
+    let mut heartbeat_every = Duration::from_secs(30);
+    let mut heartbeat_sent: Option<Instant> = None;
+    let mut seq: Option<u64> = None;
+    let mut ready = false;
+    let mut next_heartbeat = tokio::time::Instant::now() + heartbeat_every;
+
+    loop {
+        let frame = tokio::select! {
+            _ = tokio::time::sleep_until(deadline.into()) => break,
+            _ = tokio::time::sleep_until(next_heartbeat) => {
+                if heartbeat_sent.is_some() {
+                    recorder.error("gateway_heartbeat", "missed_ack");
+                }
+                let beat = json!({ "op": OP_HEARTBEAT, "d": seq }).to_string();
+                if sink.send(Message::Text(beat.into())).await.is_err() {
+                    recorder.error("gateway_heartbeat", "send");
+                    return;
+                }
+                heartbeat_sent = Some(Instant::now());
+                next_heartbeat += heartbeat_every;
+                continue;
+            }
+            frame = stream.next() => frame,
+        };
+
+        let text = match frame {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(frame))) => {
+                let code = frame.map(|f| u16::from(f.code)).unwrap_or(1005);
+                let op = if ready {
+                    "gateway_session"
+                } else {
+                    "gateway_ready"
+                };
+                recorder.error(op, format!("closed_{code}"));
+                return;
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(err)) => {
+                tracing::debug!("gateway read failed: {}", err);
+                recorder.error("gateway_session", "read");
+                return;
+            }
+            None => {
+                recorder.error("gateway_session", "eof");
+                return;
+            }
+        };
+        let Ok(payload) = serde_json::from_str::<Value>(text.as_str()) else {
+            continue;
+        };
+        let op = payload.get("op").and_then(Value::as_u64).unwrap_or(255) as u8;
+        match op {
+            OP_HELLO => {
+                if let Some(ms) = payload["d"]["heartbeat_interval"].as_u64() {
+                    heartbeat_every = Duration::from_millis(ms.max(1000));
+                    next_heartbeat = tokio::time::Instant::now() + heartbeat_every;
+                }
+                let identify = json!({
+                    "op": OP_IDENTIFY,
+                    "d": {
+                        "token": token,
+                        "properties": { "os": std::env::consts::OS, "browser": "paracord-loadtest" },
+                    },
+                })
+                .to_string();
+                if sink.send(Message::Text(identify.into())).await.is_err() {
+                    recorder.error("gateway_ready", "send");
+                    return;
+                }
+            }
+            OP_HEARTBEAT_ACK => {
+                if let Some(sent) = heartbeat_sent.take() {
+                    recorder.success("gateway_heartbeat", sent.elapsed());
+                }
+            }
+            OP_INVALID_SESSION => {
+                recorder.error("gateway_ready", "invalid_session");
+                return;
+            }
+            OP_DISPATCH => {
+                if let Some(s) = payload.get("s").and_then(Value::as_u64) {
+                    seq = Some(s);
+                }
+                match payload.get("t").and_then(Value::as_str) {
+                    Some("READY") if !ready => {
+                        ready = true;
+                        recorder.success("gateway_ready", started.elapsed());
+                    }
+                    Some("MESSAGE_CREATE") => {
+                        if let Some(latency) =
+                            payload["d"]["content"].as_str().and_then(fanout_latency)
+                        {
+                            recorder.success("gateway_fanout", latency);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let _ = sink.send(Message::Close(None)).await;
+}
+
+/// One REST actor sending messages, reactions and voice joins until `deadline`.
+pub async fn run_rest(
+    api: Api,
+    target: Arc<Target>,
+    mix: RestMix,
+    recorder: Arc<Recorder>,
+    deadline: Instant,
+) {
+    let period = Duration::from_secs_f64(1.0 / mix.messages_per_second.max(0.01));
+    // Spread actors out so they don't all fire on the same tick.
+    let offset = period.mul_f64(rand::random::<f64>());
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + offset, period);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut own_messages: VecDeque<i64> = VecDeque::new();
+    let mut sent = 0u64;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline.into()) => break,
+            _ = ticker.tick() => {}
+        }
+        let roll = rand::random::<f64>();
+        if let Some(voice_channel_id) = target.voice_channel_id.filter(|_| roll < mix.voice_ratio) {
+            voice_round_trip(&api, voice_channel_id, &recorder).await;
+        } else if roll < mix.voice_ratio + mix.reaction_ratio && !own_messages.is_empty() {
+            let message_id = own_messages[rand::random::<usize>() % own_messages.len()];
+            let path = format!(
+                "/api/v1/channels/{}/messages/{}/reactions/{}/@me",
+                target.text_channel_id, message_id, REACTION_EMOJI
+            );
+            match api.call(Method::PUT, &path, None).await {
+                Ok((_, latency)) => recorder.success("reaction_add", latency),
+                Err(err) => recorder.error("reaction_add", err.kind),
+            }
+        } else {
+            sent += 1;
+            let content = format!("{CONTENT_PREFIX}{}:{sent}", unix_micros());
+            let path = format!("/api/v1/channels/{}/messages", target.text_channel_id);
+            match api
+                .call(Method::POST, &path, Some(json!({ "content": content })))
+                .await
+            {
+                Ok((body, latency)) => {
+                    recorder.success("message_send", latency);
+                    if let Ok(id) = id_field(&body, "id") {
+                        own_messages.push_back(id);
+                        if own_messages.len() > 20 {
+                            own_messages.pop_front();
+                        }
+                    }
+                }
+                Err(err) => recorder.error("message_send", err.kind),
+            }
+        }
+    }
+}
+
+/// Join a voice channel (signaling only, no media) and leave again.
+async fn voice_round_trip(api: &Api, channel_id: i64, recorder: &Recorder) {
+    match api
+        .call(
+            Method::GET,
+            &format!("/api/v1/voice/{channel_id}/join"),
+            None,
+        )
+        .await
+    {
+        Ok((_, latency)) => recorder.success("voice_join", latency),
+        Err(err) => {
+            recorder.error("voice_join", err.kind);
+            return;
+        }
+    }
+    match api
+        .call(
+            Method::POST,
+            &format!("/api/v1/voice/{channel_id}/leave"),
+            None,
+        )
+        .await
+    {
+        Ok((_, latency)) => recorder.success("voice_leave", latency),
+        Err(err) => recorder.error("voice_leave", err.kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fanout_latency_reads_send_time_from_content() {
+        let sent = unix_micros() - 1_500;
+        let latency = fanout_latency(&format!("{CONTENT_PREFIX}{sent}:7")).unwrap();
+        assert!(latency >= Duration::from_micros(1_500));
+        assert!(fanout_latency("hello").is_none());
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+/// Failure of a single REST call, keyed for the report.
+#[derive(Debug)]
+pub struct CallError {
+    pub kind: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Thin REST client bound to one account.
+#[derive(Clone)]
+pub struct Api {
+    http: reqwest::Client,
+    base: String,
+    token: Option<String>,
+}
+
+impl Api {
+    pub fn new(http: reqwest::Client, base: &str) -> Self {
+        Self {
+            http,
+            base: base.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    pub fn with_token(&self, token: String) -> Self {
+        Self {
+            http: self.http.clone(),
+            base: self.base.clone(),
+            token: Some(token),
+        }
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Send a request and return the JSON body (or `null`) with its latency.
+    pub async fn call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<(Value, Duration), CallError> {
+        let mut request = self.http.request(method, format!("{}{}", self.base, path));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let started = Instant::now();
+        let response = request.send().await.map_err(|err| CallError {
+            kind: if err.is_timeout() {
+                "timeout".into()
+            } else {
+                "connect".into()
+            },
+            message: err.to_string(),
+        })?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let elapsed = started.elapsed();
+        if !status.is_success() {
+            return Err(CallError {
+                kind: format!("http_{}", status.as_u16()),
+                message: text,
+            });
+        }
+        let value = if status == StatusCode::NO_CONTENT || text.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::Null)
+        };
+        Ok((value, elapsed))
+    }
+
+    /// Log in, registering the account first if it doesn't exist yet.
+    pub async fn sign_in(&self, username: &str, password: &str) -> Result<Api> {
+        let login = self
+            .call(
+                Method::POST,
+                "/api/v1/auth/login",
+                Some(json!({ "username": username, "password": password })),
+            )
+            .await;
+        let body = match login {
+            Ok((body, _)) => body,
+            Err(_) => {
+                self.call(
+                    Method::POST,
+                    "/api/v1/auth/register",
+                    Some(json!({
+                        "username": username,
+                        "password": password,
+                        "email": format!("{username}@loadtest.invalid"),
+                    })),
+                )
+                .await
+                .with_context(|| format!("could not log in or register {username}"))?
+                .0
+            }
+        };
+        let token = body
+            .get("token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("no token in auth response for {username}"))?;
+        Ok(self.with_token(token.to_string()))
+    }
+
+    pub async fn create_guild(&self, name: &str) -> Result<i64> {
+        let (guild, _) = self
+            .call(
+                Method::POST,
+                "/api/v1/guilds",
+                Some(json!({ "name": name })),
+            )
+            .await?;
+        id_field(&guild, "id")
+    }
+
+    /// First text and voice channel of a guild.
+    pub async fn guild_channels(&self, guild_id: i64) -> Result<(i64, Option<i64>)> {
+        let (channels, _) = self
+            .call(
+                Method::GET,
+                &format!("/api/v1/guilds/{guild_id}/channels"),
+                None,
+            )
+            .await?;
+        let channels = channels.as_array().cloned().unwrap_or_default();
+        let of_type = |kind: i64| {
+            channels
+                .iter()
+                .find(|c| c.get("type").and_then(Value::as_i64) == Some(kind))
+                .and_then(|c| id_field(c, "id").ok())
+        };
+        let Some(text) = of_type(0) else {
+            bail!("guild {guild_id} has no text channel");
+        };
+        Ok((text, of_type(2)))
+    }
+
+    pub async fn create_invite(&self, channel_id: i64) -> Result<String> {
+        let (invite, _) = self
+            .call(
+                Method::POST,
+                &format!("/api/v1/channels/{channel_id}/invites"),
+                Some(json!({ "max_uses": 0, "max_age": 0 })),
+            )
+            .await?;
+        invite
+            .get("code")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("no code in invite response"))
+    }
+
+    pub async fn accept_invite(&self, code: &str) -> Result<()> {
+        match self
+            .call(Method::POST, &format!("/api/v1/invites/{code}"), None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            // Already a member from an earlier run.
+            Err(err) if err.kind == "http_409" => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Snowflake IDs are sent as strings; accept numbers too.
+pub fn id_field(value: &Value, key: &str) -> Result<i64> {
+    let raw = value
+        .get(key)
+        .ok_or_else(|| anyhow!("missing `{key}` in response"))?;
+    raw.as_str()
+        .and_then(|s| s.parse().ok())
+        .or_else(|| raw.as_i64())
+        .ok_or_else(|| anyhow!("invalid `{key}` in response"))
+}
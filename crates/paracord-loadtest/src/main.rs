@@ -0,0 +1,211 @@
+//! Synthetic load generator for a running Paracord server.
+//!
+//! Signs in a pool of test accounts, puts them in one guild, then holds
+//! gateway connections open while REST actors send messages, add reactions
+//! and join voice. Prints per-operation latency percentiles and error counts,
+//! and exits non-zero when the error rate exceeds `--max-error-rate`.
+
+mod actors;
+mod api;
+mod report;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+use actors::{RestMix, Target};
+use api::Api;
+use report::{Recorder, Report};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "paracord-loadtest",
+    about = "Generate synthetic gateway and REST traffic against a Paracord server"
+)]
+struct Args {
+    /// Base URL of the server under test.
+    #[arg(long, default_value = "http://127.0.0.1:8080")]
+    target: String,
+
+    /// Gateway URL; derived from --target when omitted.
+    #[arg(long)]
+    gateway_url: Option<String>,
+
+    /// Test accounts to sign in (registered on first use).
+    #[arg(long, default_value = "20")]
+    users: usize,
+
+    /// Username prefix for test accounts.
+    #[arg(long, default_value = "loadtest")]
+    user_prefix: String,
+
+    #[arg(long, default_value = "loadtest-password")]
+    password: String,
+
+    /// Concurrent gateway connections, spread over the accounts.
+    #[arg(long, default_value = "50")]
+    connections: usize,
+
+    /// Concurrent REST actors, spread over the accounts.
+    #[arg(long, default_value = "10")]
+    rest_actors: usize,
+
+    /// Actions per second for each REST actor.
+    #[arg(long, default_value = "1.0")]
+    rate: f64,
+
+    /// Share of REST actions that add a reaction instead of sending a message.
+    #[arg(long, default_value = "0.3")]
+    reaction_ratio: f64,
+
+    /// Share of REST actions that join and leave the voice channel.
+    #[arg(long, default_value = "0.05")]
+    voice_ratio: f64,
+
+    /// How long to generate traffic, in seconds.
+    #[arg(long, default_value = "60")]
+    duration: u64,
+
+    /// Spread connection and actor start-up over this many seconds.
+    #[arg(long, default_value = "10")]
+    ramp_up: u64,
+
+    /// Print the report as JSON instead of a table.
+    #[arg(long)]
+    json: bool,
+
+    /// Fail (exit code 1) when the overall error rate is above this.
+    #[arg(long, default_value = "0.01")]
+    max_error_rate: f64,
+}
+
+fn gateway_url(target: &str) -> String {
+    let base = target.trim_end_matches('/');
+    let ws = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        base.to_string()
+    };
+    format!("{ws}/gateway")
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    if args.users == 0 {
+        bail!("--users must be at least 1");
+    }
+    if args.reaction_ratio + args.voice_ratio > 1.0 {
+        bail!("--reaction-ratio and --voice-ratio must add up to at most 1.0");
+    }
+
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    let anonymous = Api::new(http, &args.target);
+
+    // ── Setup ───────────────────────────────────────────────────────────
+    info!("Signing in {} test account(s)", args.users);
+    let mut accounts = Vec::with_capacity(args.users);
+    for n in 0..args.users {
+        let username = format!("{}{}", args.user_prefix, n);
+        accounts.push(anonymous.sign_in(&username, &args.password).await?);
+    }
+
+    let owner = &accounts[0];
+    let guild_id = owner
+        .create_guild(&format!("{} {}", args.user_prefix, std::process::id()))
+        .await
+        .context("failed to create the test guild")?;
+    let (text_channel_id, voice_channel_id) = owner.guild_channels(guild_id).await?;
+    let invite = owner.create_invite(text_channel_id).await?;
+    for account in &accounts[1..] {
+        account.accept_invite(&invite).await?;
+    }
+    info!(
+        "Using guild {} (text channel {}, voice channel {:?})",
+        guild_id, text_channel_id, voice_channel_id
+    );
+
+    // ── Traffic ─────────────────────────────────────────────────────────
+    let recorder = Arc::new(Recorder::default());
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(args.ramp_up + args.duration);
+    let gateway_url = args
+        .gateway_url
+        .clone()
+        .unwrap_or_else(|| gateway_url(&args.target));
+    let target = Arc::new(Target {
+        text_channel_id,
+        voice_channel_id,
+    });
+    let mix = RestMix {
+        messages_per_second: args.rate,
+        reaction_ratio: args.reaction_ratio,
+        voice_ratio: args.voice_ratio,
+    };
+    let ramp_step = |index: usize, total: usize| {
+        Duration::from_secs(args.ramp_up).mul_f64(index as f64 / total.max(1) as f64)
+    };
+
+    let mut tasks = Vec::with_capacity(args.connections + args.rest_actors);
+    for index in 0..args.connections {
+        let token = accounts[index % accounts.len()]
+            .token()
+            .unwrap_or_default()
+            .to_string();
+        let delay = ramp_step(index, args.connections);
+        let (url, recorder) = (gateway_url.clone(), recorder.clone());
+        tasks.push(tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            actors::run_gateway(url, token, recorder, deadline).await;
+        }));
+    }
+    for index in 0..args.rest_actors {
+        let api = accounts[index % accounts.len()].clone();
+        let delay = ramp_step(index, args.rest_actors);
+        let (target, recorder) = (target.clone(), recorder.clone());
+        tasks.push(tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            actors::run_rest(api, target, mix, recorder, deadline).await;
+        }));
+    }
+    info!(
+        "Running {} gateway connection(s) and {} REST actor(s) for {}s (+{}s ramp-up)",
+        args.connections, args.rest_actors, args.duration, args.ramp_up
+    );
+    for task in tasks {
+        if let Err(err) = task.await {
+            warn!("load test task failed: {}", err);
+        }
+    }
+
+    // ── Report ──────────────────────────────────────────────────────────
+    let report = Report {
+        elapsed: started.elapsed(),
+        ops: recorder.snapshot(),
+    };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+    } else {
+        report.print_table();
+    }
+    if report.error_rate() > args.max_error_rate {
+        warn!(
+            "Error rate {:.2}% is above the {:.2}% limit",
+            report.error_rate() * 100.0,
+            args.max_error_rate * 100.0
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency samples and error counts for one kind of operation.
+#[derive(Debug, Default, Clone)]
+pub struct OpStats {
+    latencies_us: Vec<u64>,
+    errors: u64,
+    error_kinds: BTreeMap<String, u64>,
+}
+
+impl OpStats {
+    pub fn count(&self) -> u64 {
+        self.latencies_us.len() as u64 + self.errors
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Latency at quantile `q` (0.0-1.0) over successful samples.
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        if self.latencies_us.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies_us.clone();
+        sorted.sort_unstable();
+        let rank = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len());
+        Some(Duration::from_micros(sorted[rank - 1]))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.latencies_us
+            .iter()
+            .max()
+            .map(|us| Duration::from_micros(*us))
+    }
+}
+
+/// Shared collector the actors report into.
+#[derive(Default)]
+pub struct Recorder {
+    ops: Mutex<BTreeMap<&'static str, OpStats>>,
+}
+
+impl Recorder {
+    pub fn success(&self, op: &'static str, latency: Duration) {
+        let mut ops = self.ops.lock().unwrap_or_else(|p| p.into_inner());
+        ops.entry(op)
+            .or_default()
+            .latencies_us
+            .push(latency.as_micros() as u64);
+    }
+
+    pub fn error(&self, op: &'static str, kind: impl Into<String>) {
+        let mut ops = self.ops.lock().unwrap_or_else(|p| p.into_inner());
+        let stats = ops.entry(op).or_default();
+        stats.errors += 1;
+        *stats.error_kinds.entry(kind.into()).or_default() += 1;
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, OpStats> {
+        self.ops.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+}
+
+pub struct Report {
+    pub elapsed: Duration,
+    pub ops: BTreeMap<&'static str, OpStats>,
+}
+
+fn ms(duration: Option<Duration>) -> Value {
+    duration
+        .map(|d| json!((d.as_secs_f64() * 1000.0 * 100.0).round() / 100.0))
+        .unwrap_or(Value::Null)
+}
+
+impl Report {
+    pub fn total_count(&self) -> u64 {
+        self.ops.values().map(OpStats::count).sum()
+    }
+
+    pub fn total_errors(&self) -> u64 {
+        self.ops.values().map(OpStats::errors).sum()
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        let total = self.total_count();
+        if total == 0 {
+            return 0.0;
+        }
+        self.total_errors() as f64 / total as f64
+    }
+
+    pub fn to_json(&self) -> Value {
+        let secs = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        let ops: serde_json::Map<String, Value> = self
+            .ops
+            .iter()
+            .map(|(name, stats)| {
+                (
+                    name.to_string(),
+                    json!({
+                        "count": stats.count(),
+                        "errors": stats.errors(),
+                        "error_kinds": stats.error_kinds,
+                        "per_second": stats.count() as f64 / secs,
+                        "p50_ms": ms(stats.percentile(0.50)),
+                        "p95_ms": ms(stats.percentile(0.95)),
+                        "p99_ms": ms(stats.percentile(0.99)),
+                        "max_ms": ms(stats.max()),
+                    }),
+                )
+            })
+            .collect();
+        json!({
+            "elapsed_seconds": self.elapsed.as_secs_f64(),
+            "total": self.total_count(),
+            "errors": self.total_errors(),
+            "error_rate": self.error_rate(),
+            "operations": ops,
+        })
+    }
+
+    pub fn print_table(&self) {
+        let secs = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        let fmt = |d: Option<Duration>| {
+            d.map(|d| format!("{:.1}", d.as_secs_f64() * 1000.0))
+                .unwrap_or_else(|| "-".into())
+        };
+        println!(
+            "{:<22} {:>9} {:>7} {:>8} {:>9} {:>9} {:>9} {:>9}",
+            "operation", "count", "errors", "per_sec", "p50_ms", "p95_ms", "p99_ms", "max_ms"
+        );
+        for (name, stats) in &self.ops {
+            println!(
+                "{:<22} {:>9} {:>7} {:>8.1} {:>9} {:>9} {:>9} {:>9}",
+                name,
+                stats.count(),
+                stats.errors(),
+                stats.count() as f64 / secs,
+                fmt(stats.percentile(0.50)),
+                fmt(stats.percentile(0.95)),
+                fmt(stats.percentile(0.99)),
+                fmt(stats.max()),
+            );
+            for (kind, count) in &stats.error_kinds {
+                println!("  {:<20} {:>9}", kind, count);
+            }
+        }
+        println!(
+            "\n{} operations in {:.1}s, error rate {:.2}%",
+            self.total_count(),
+            secs,
+            self.error_rate() * 100.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let recorder = Recorder::default();
+        for ms in 1..=100 {
+            recorder.success("message_send", Duration::from_millis(ms));
+        }
+        let ops = recorder.snapshot();
+        let stats = &ops["message_send"];
+        assert_eq!(stats.percentile(0.50), Some(Duration::from_millis(50)));
+        assert_eq!(stats.percentile(0.99), Some(Duration::from_millis(99)));
+        assert_eq!(stats.max(), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn error_rate_counts_failures_across_operations() {
+        let recorder = Recorder::default();
+        recorder.success("message_send", Duration::from_millis(5));
+        recorder.success("reaction_add", Duration::from_millis(5));
+        recorder.success("reaction_add", Duration::from_millis(5));
+        recorder.error("message_send", "http_429");
+        let report = Report {
+            elapsed: Duration::from_secs(1),
+            ops: recorder.snapshot(),
+        };
+        assert_eq!(report.total_count(), 4);
+        assert_eq!(report.error_rate(), 0.25);
+        assert_eq!(
+            report.to_json()["operations"]["message_send"]["error_kinds"]["http_429"],
+            1
+        );
+    }
+}
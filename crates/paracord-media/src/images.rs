@@ -0,0 +1,393 @@
+//! Avatar and icon resizing.
+//!
+//! Uploaded avatars are decoded once, squared off, and re-encoded at a fixed
+//! set of sizes so member lists never have to fetch the full-size original.
+//! Variants are produced as PNG and (lossless) WebP; the API picks one based
+//! on the client's `Accept` header.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader, Limits};
+use std::io::Cursor;
+use thiserror::Error;
+
+/// Sizes (in pixels, square) generated for every avatar/icon upload.
+pub const AVATAR_SIZES: [u32; 4] = [32, 64, 128, 256];
+
+/// Largest side length accepted when decoding an uploaded image.
+const MAX_SOURCE_DIMENSION: u32 = 4096;
+/// Upper bound on decoder allocations (64 MiB).
+const MAX_DECODE_ALLOC: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ImageProcessingError {
+    #[error("unsupported or corrupt image: {0}")]
+    Decode(String),
+    #[error("failed to encode image: {0}")]
+    Encode(String),
+}
+
+/// Output encoding for a resized variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariantFormat {
+    Png,
+    WebP,
+}
+
+impl VariantFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::WebP => "webp",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::WebP => "image/webp",
+        }
+    }
+
+    fn as_image_format(self) -> image::ImageFormat {
+        match self {
+            Self::Png => image::ImageFormat::Png,
+            Self::WebP => image::ImageFormat::WebP,
+        }
+    }
+
+    /// Pick WebP when the `Accept` header explicitly allows it, PNG otherwise.
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Self::Png;
+        };
+        let allows_webp = accept.split(',').any(|part| {
+            let mut pieces = part.split(';');
+            let media = pieces.next().unwrap_or("").trim();
+            if !media.eq_ignore_ascii_case("image/webp") {
+                return false;
+            }
+            // Honour an explicit `q=0` opt-out.
+            !pieces.any(|param| {
+                let param = param.trim();
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            })
+        });
+        if allows_webp {
+            Self::WebP
+        } else {
+            Self::Png
+        }
+    }
+}
+
+/// Raster formats accepted for stored-as-is image assets (guild theme
+/// assets, instance branding). SVG is deliberately absent: it can carry script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetImageType {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+}
+
+impl AssetImageType {
+    /// Identify the format from the file's magic bytes, ignoring whatever
+    /// content type the client claimed.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            Some(Self::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(Self::WebP)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "image/png" => Some(Self::Png),
+            "image/jpeg" => Some(Self::Jpeg),
+            "image/gif" => Some(Self::Gif),
+            "image/webp" => Some(Self::WebP),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::WebP => "image/webp",
+        }
+    }
+}
+
+/// A single resized rendition of an uploaded image.
+#[derive(Debug, Clone)]
+pub struct ImageVariant {
+    pub size: u32,
+    pub format: VariantFormat,
+    pub data: Vec<u8>,
+}
+
+/// Snap an arbitrary requested size to the smallest generated size that is
+/// at least as large, falling back to the largest size.
+pub fn normalize_avatar_size(requested: Option<u32>) -> u32 {
+    let largest = AVATAR_SIZES[AVATAR_SIZES.len() - 1];
+    match requested {
+        Some(size) => AVATAR_SIZES
+            .iter()
+            .copied()
+            .find(|candidate| *candidate >= size)
+            .unwrap_or(largest),
+        None => largest,
+    }
+}
+
+/// Storage key for a resized avatar variant.
+pub fn avatar_variant_key(owner_id: i64, hash: &str, size: u32, format: VariantFormat) -> String {
+    format!(
+        "avatars/{}/{}_{}.{}",
+        owner_id,
+        hash,
+        size,
+        format.extension()
+    )
+}
+
+/// Storage key for the normalized source image an avatar was generated from.
+pub fn avatar_source_key(owner_id: i64, hash: &str) -> String {
+    format!("avatars/{}/{}.png", owner_id, hash)
+}
+
+/// Storage key for a resized guild icon variant.
+pub fn icon_variant_key(guild_id: i64, hash: &str, size: u32, format: VariantFormat) -> String {
+    format!(
+        "icons/{}/{}_{}.{}",
+        guild_id,
+        hash,
+        size,
+        format.extension()
+    )
+}
+
+/// Storage key for the normalized source image a guild icon was generated from.
+pub fn icon_source_key(guild_id: i64, hash: &str) -> String {
+    format!("icons/{}/{}.png", guild_id, hash)
+}
+
+/// Decode an uploaded image with dimension and allocation limits applied.
+pub fn decode_image(data: &[u8]) -> Result<DynamicImage, ImageProcessingError> {
+    decode_image_with_limits(data, MAX_SOURCE_DIMENSION, MAX_DECODE_ALLOC)
+}
+
+pub(crate) fn decode_image_with_limits(
+    data: &[u8],
+    max_dimension: u32,
+    max_alloc: u64,
+) -> Result<DynamicImage, ImageProcessingError> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| ImageProcessingError::Decode(e.to_string()))?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension);
+    limits.max_image_height = Some(max_dimension);
+    limits.max_alloc = Some(max_alloc);
+    reader.limits(limits);
+    reader
+        .decode()
+        .map_err(|e| ImageProcessingError::Decode(e.to_string()))
+}
+
+/// Center-crop to a square and resize to `size` x `size`.
+pub fn resize_square(image: &DynamicImage, size: u32) -> DynamicImage {
+    image.resize_to_fill(size, size, FilterType::Lanczos3)
+}
+
+/// Encode an image in the requested variant format.
+pub fn encode_image(
+    image: &DynamicImage,
+    format: VariantFormat,
+) -> Result<Vec<u8>, ImageProcessingError> {
+    // The WebP encoder only accepts 8-bit RGB(A); normalise everything to RGBA8.
+    let rgba = DynamicImage::ImageRgba8(image.to_rgba8());
+    let mut out = Cursor::new(Vec::new());
+    rgba.write_to(&mut out, format.as_image_format())
+        .map_err(|e| ImageProcessingError::Encode(e.to_string()))?;
+    Ok(out.into_inner())
+}
+
+/// Produce a single resized variant directly from raw source bytes.
+pub fn render_variant(
+    source: &[u8],
+    size: u32,
+    format: VariantFormat,
+) -> Result<Vec<u8>, ImageProcessingError> {
+    let image = decode_image(source)?;
+    encode_image(&resize_square(&image, size), format)
+}
+
+/// Generate every avatar size in both PNG and WebP.
+///
+/// Also returns the normalized source (largest size, PNG) used for on-demand
+/// regeneration if a variant is later missing from storage.
+pub fn generate_avatar_variants(
+    data: &[u8],
+) -> Result<(Vec<u8>, Vec<ImageVariant>), ImageProcessingError> {
+    let image = decode_image(data)?;
+    let largest = AVATAR_SIZES[AVATAR_SIZES.len() - 1];
+    let source = encode_image(&resize_square(&image, largest), VariantFormat::Png)?;
+
+    let mut variants = Vec::with_capacity(AVATAR_SIZES.len() * 2);
+    for size in AVATAR_SIZES {
+        let resized = resize_square(&image, size);
+        for format in [VariantFormat::Png, VariantFormat::WebP] {
+            variants.push(ImageVariant {
+                size,
+                format,
+                data: encode_image(&resized, format)?,
+            });
+        }
+    }
+    Ok((source, variants))
+}
+
+/// 64-bit difference hash of an image, for matching re-encoded or resized
+/// copies against a list of known-bad images.
+///
+/// The image is reduced to a 9x8 grayscale grid and each bit records whether
+/// a cell is brighter than its right-hand neighbour, so small edits flip
+/// only a few bits. Compare hashes with [`hash_distance`].
+pub fn perceptual_hash(data: &[u8]) -> Result<u64, ImageProcessingError> {
+    let grid = decode_image(data)?
+        .resize_exact(9, 8, FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if grid.get_pixel(x, y)[0] > grid.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+/// Number of differing bits between two perceptual hashes.
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_png(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::new_rgba8(width, height);
+        encode_image(&img, VariantFormat::Png).expect("encode sample")
+    }
+
+    #[test]
+    fn normalizes_requested_sizes() {
+        assert_eq!(normalize_avatar_size(None), 256);
+        assert_eq!(normalize_avatar_size(Some(1)), 32);
+        assert_eq!(normalize_avatar_size(Some(64)), 64);
+        assert_eq!(normalize_avatar_size(Some(65)), 128);
+        assert_eq!(normalize_avatar_size(Some(4096)), 256);
+    }
+
+    #[test]
+    fn negotiates_webp_only_when_accepted() {
+        assert_eq!(VariantFormat::negotiate(None), VariantFormat::Png);
+        assert_eq!(
+            VariantFormat::negotiate(Some("image/avif,image/webp,*/*")),
+            VariantFormat::WebP
+        );
+        assert_eq!(
+            VariantFormat::negotiate(Some("image/webp;q=0, image/png")),
+            VariantFormat::Png
+        );
+        assert_eq!(
+            VariantFormat::negotiate(Some("image/png,*/*;q=0.8")),
+            VariantFormat::Png
+        );
+    }
+
+    #[test]
+    fn sniffs_asset_types_from_magic_bytes() {
+        assert_eq!(
+            AssetImageType::sniff(&sample_png(4, 4)),
+            Some(AssetImageType::Png)
+        );
+        assert_eq!(
+            AssetImageType::sniff(b"GIF89a\x01\x00"),
+            Some(AssetImageType::Gif)
+        );
+        assert_eq!(
+            AssetImageType::sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+            Some(AssetImageType::WebP)
+        );
+        assert_eq!(AssetImageType::sniff(b"<svg onload=alert(1)>"), None);
+        assert_eq!(AssetImageType::sniff(b""), None);
+    }
+
+    #[test]
+    fn generates_all_variants_as_squares() {
+        let (source, variants) = generate_avatar_variants(&sample_png(300, 200)).unwrap();
+        assert_eq!(variants.len(), AVATAR_SIZES.len() * 2);
+        let decoded_source = decode_image(&source).unwrap();
+        assert_eq!(decoded_source.width(), 256);
+        for variant in variants {
+            let decoded = decode_image(&variant.data).unwrap();
+            assert_eq!(decoded.width(), variant.size);
+            assert_eq!(decoded.height(), variant.size);
+        }
+    }
+
+    #[test]
+    fn rejects_non_image_payloads() {
+        assert!(matches!(
+            generate_avatar_variants(b"definitely not an image"),
+            Err(ImageProcessingError::Decode(_))
+        ));
+    }
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+            let v = ((x * 255 / width) ^ (y * 97 / height)) as u8;
+            image::Rgb([v, v / 2, 255 - v])
+        }))
+    }
+
+    #[test]
+    fn resized_copies_hash_close_together() {
+        let original = gradient(320, 240);
+        let hash = perceptual_hash(&encode_image(&original, VariantFormat::Png).unwrap()).unwrap();
+        let smaller = original.resize_exact(160, 120, FilterType::Lanczos3);
+        let resized =
+            perceptual_hash(&encode_image(&smaller, VariantFormat::WebP).unwrap()).unwrap();
+        assert!(hash_distance(hash, resized) <= 4);
+
+        let flipped = original.fliph();
+        let other = perceptual_hash(&encode_image(&flipped, VariantFormat::Png).unwrap()).unwrap();
+        assert!(hash_distance(hash, other) > 16);
+    }
+}
@@ -1,10 +1,16 @@
+pub mod images;
 pub mod livekit;
 pub mod s3;
 pub mod storage;
 pub mod streaming;
+pub mod thumbnails;
+pub mod transcode;
 pub mod voice;
+pub mod whip;
 
-pub use livekit::{AudioBitrate, LiveKitConfig, WebhookEvent};
+pub use livekit::{
+    AudioBitrate, EgressInfo, EgressOutput, IngressInfo, LiveKitConfig, WebhookEvent,
+};
 pub use s3::S3Config;
 pub use storage::{
     LocalStorage, P2PTransferRequest, Storage, StorageBackend, StorageConfig, StorageError,
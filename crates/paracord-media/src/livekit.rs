@@ -34,6 +34,8 @@ pub struct VideoGrant {
     pub can_publish_sources: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hidden: Option<bool>,
+    #[serde(rename = "roomRecord", skip_serializing_if = "Option::is_none")]
+    pub room_record: Option<bool>,
 }
 
 impl VideoGrant {
@@ -49,6 +51,7 @@ impl VideoGrant {
             can_publish_data: None,
             can_publish_sources: None,
             hidden: None,
+            room_record: None,
         }
     }
 }
@@ -90,6 +93,8 @@ pub struct WebhookEvent {
     pub room: Option<WebhookRoom>,
     pub participant: Option<WebhookParticipant>,
     pub track: Option<WebhookTrack>,
+    #[serde(rename = "egressInfo", alias = "egress_info")]
+    pub egress_info: Option<EgressInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +120,77 @@ pub struct WebhookTrack {
     pub muted: Option<bool>,
 }
 
+/// Destination for a room composite egress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EgressOutput {
+    /// MP4 written by the egress service to `filepath`.
+    File { filepath: String },
+    /// Push to one or more RTMP ingest URLs (stream keys included).
+    Rtmp { urls: Vec<String> },
+}
+
+/// Egress state as reported by the Egress service and `egress_*` webhooks.
+///
+/// Protobuf JSON may use either proto or camelCase field names depending on
+/// the endpoint, so both spellings are accepted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EgressInfo {
+    #[serde(alias = "egressId", default)]
+    pub egress_id: String,
+    #[serde(alias = "roomName", default)]
+    pub room_name: Option<String>,
+    /// `EGRESS_STARTING`, `EGRESS_ACTIVE`, `EGRESS_ENDING`, `EGRESS_COMPLETE`,
+    /// `EGRESS_FAILED`, `EGRESS_ABORTED` or `EGRESS_LIMIT_REACHED`.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(alias = "fileResults", default)]
+    pub file_results: Vec<EgressFileInfo>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EgressFileInfo {
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+/// Ingress created for a WHIP publisher.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngressInfo {
+    #[serde(alias = "ingressId", default)]
+    pub ingress_id: String,
+    /// WHIP base URL of the Ingress service; the publish endpoint is
+    /// `{url}/{stream_key}`.
+    #[serde(default)]
+    pub url: String,
+    #[serde(alias = "streamKey", default)]
+    pub stream_key: String,
+}
+
+impl IngressInfo {
+    pub fn whip_url(&self) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), self.stream_key)
+    }
+}
+
+impl EgressInfo {
+    /// Whether the egress finished and produced its output.
+    pub fn is_complete(&self) -> bool {
+        self.status.as_deref() == Some("EGRESS_COMPLETE")
+    }
+
+    /// Whether the egress ended without producing (all of) its output.
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self.status.as_deref(),
+            Some("EGRESS_FAILED" | "EGRESS_ABORTED" | "EGRESS_LIMIT_REACHED")
+        )
+    }
+}
+
 impl LiveKitConfig {
     /// Generate an admin token for LiveKit API calls.
     fn generate_admin_token(&self, grant: VideoGrant) -> Result<String, anyhow::Error> {
@@ -151,6 +227,7 @@ impl LiveKitConfig {
             can_publish_data: None,
             can_publish_sources: None,
             hidden: None,
+            room_record: None,
         })
     }
 
@@ -204,6 +281,7 @@ impl LiveKitConfig {
                 room_list: None,
                 room_admin: None,
                 hidden: None,
+                room_record: None,
             },
             metadata: Some(metadata.to_string()),
         };
@@ -251,6 +329,7 @@ impl LiveKitConfig {
                 room_list: None,
                 room_admin: None,
                 hidden: None,
+                room_record: None,
             },
             metadata: Some(metadata.to_string()),
         };
@@ -300,6 +379,7 @@ impl LiveKitConfig {
                 room_list: None,
                 room_admin: None,
                 hidden: None,
+                room_record: None,
             },
             metadata: Some(metadata.to_string()),
         };
@@ -312,6 +392,42 @@ impl LiveKitConfig {
         Ok(token)
     }
 
+    /// Generate a hidden, subscribe-only token for a WHEP playback session.
+    pub fn generate_whep_viewer_token(
+        &self,
+        room_name: &str,
+        identity: &str,
+    ) -> Result<String, anyhow::Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let claims = LiveKitClaims {
+            exp: now + LIVEKIT_TOKEN_TTL_SECONDS,
+            iss: self.api_key.clone(),
+            sub: identity.to_string(),
+            name: None,
+            video: VideoGrant {
+                room_join: Some(true),
+                room: Some(room_name.to_string()),
+                can_publish: Some(false),
+                can_subscribe: Some(true),
+                can_publish_data: Some(false),
+                can_publish_sources: None,
+                room_create: None,
+                room_list: None,
+                room_admin: None,
+                hidden: Some(true),
+                room_record: None,
+            },
+            metadata: None,
+        };
+
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.api_secret.as_bytes()),
+        )?;
+        Ok(token)
+    }
+
     /// Generate a view-only token for watching a stream (cannot publish video/screen).
     pub fn generate_stream_viewer_token(
         &self,
@@ -336,6 +452,7 @@ impl LiveKitConfig {
                 room_list: None,
                 room_admin: None,
                 hidden: None,
+                room_record: None,
             },
             metadata: None,
         };
@@ -597,6 +714,159 @@ impl LiveKitConfig {
         Ok(())
     }
 
+    fn generate_record_token(&self, room_name: Option<&str>) -> Result<String, anyhow::Error> {
+        self.generate_admin_token(VideoGrant {
+            room_create: None,
+            room_list: None,
+            room_admin: None,
+            room_join: None,
+            room: room_name.map(str::to_string),
+            can_publish: None,
+            can_subscribe: None,
+            can_publish_data: None,
+            can_publish_sources: None,
+            hidden: None,
+            room_record: Some(true),
+        })
+    }
+
+    /// Start a room composite egress (recording or RTMP restream) via the
+    /// LiveKit Egress service.
+    pub async fn start_room_composite_egress(
+        &self,
+        room_name: &str,
+        output: &EgressOutput,
+        audio_only: bool,
+    ) -> Result<EgressInfo, anyhow::Error> {
+        let admin_token = self.generate_record_token(Some(room_name))?;
+
+        let mut body = serde_json::json!({
+            "room_name": room_name,
+            "layout": "speaker",
+            "audio_only": audio_only,
+        });
+        match output {
+            EgressOutput::File { filepath } => {
+                body["file_outputs"] = serde_json::json!([{
+                    "file_type": if audio_only { "OGG" } else { "MP4" },
+                    "filepath": filepath,
+                }]);
+            }
+            EgressOutput::Rtmp { urls } => {
+                body["stream_outputs"] = serde_json::json!([{
+                    "protocol": "RTMP",
+                    "urls": urls,
+                }]);
+            }
+        }
+
+        let client = Self::api_client();
+        let resp = client
+            .post(format!(
+                "{}/twirp/livekit.Egress/StartRoomCompositeEgress",
+                self.http_url
+            ))
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Failed to start LiveKit egress: {}", err);
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    /// Stop a running egress. LiveKit finalizes the output and reports the
+    /// result through an `egress_ended` webhook.
+    pub async fn stop_egress(&self, egress_id: &str) -> Result<EgressInfo, anyhow::Error> {
+        let admin_token = self.generate_record_token(None)?;
+
+        let client = Self::api_client();
+        let resp = client
+            .post(format!("{}/twirp/livekit.Egress/StopEgress", self.http_url))
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "egress_id": egress_id,
+            }))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Failed to stop LiveKit egress: {}", err);
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    /// Create a WHIP ingress that publishes into `room_name` as
+    /// `participant_identity`.
+    pub async fn create_whip_ingress(
+        &self,
+        room_name: &str,
+        participant_identity: &str,
+        participant_name: &str,
+    ) -> Result<IngressInfo, anyhow::Error> {
+        let admin_token = self.generate_admin_token(VideoGrant::admin())?;
+
+        let client = Self::api_client();
+        let resp = client
+            .post(format!(
+                "{}/twirp/livekit.Ingress/CreateIngress",
+                self.http_url
+            ))
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "input_type": "WHIP_INPUT",
+                "name": participant_identity,
+                "room_name": room_name,
+                "participant_identity": participant_identity,
+                "participant_name": participant_name,
+                // OBS already encodes with WebRTC-compatible codecs.
+                "bypass_transcoding": true,
+            }))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Failed to create LiveKit ingress: {}", err);
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    pub async fn delete_ingress(&self, ingress_id: &str) -> Result<(), anyhow::Error> {
+        let admin_token = self.generate_admin_token(VideoGrant::admin())?;
+
+        let client = Self::api_client();
+        let resp = client
+            .post(format!(
+                "{}/twirp/livekit.Ingress/DeleteIngress",
+                self.http_url
+            ))
+            .header("Authorization", format!("Bearer {}", admin_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "ingress_id": ingress_id,
+            }))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Failed to delete LiveKit ingress: {}", err);
+        }
+
+        Ok(())
+    }
+
     /// Parse and validate a LiveKit webhook request body.
     /// Returns the parsed event. The caller should verify the webhook
     /// token/signature at the HTTP layer before calling this.
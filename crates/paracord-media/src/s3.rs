@@ -16,6 +16,9 @@ pub struct S3Config {
     pub access_key_id: Option<String>,
     /// Secret access key.
     pub secret_access_key: Option<String>,
+    /// File holding the secret access key; read by the server at startup.
+    #[serde(default)]
+    pub secret_access_key_file: Option<String>,
     /// Optional CDN/public base URL for serving files (e.g. `https://cdn.example.com`).
     /// When set, `get_url` returns `{cdn_url}/{prefix}{key}` instead of a presigned URL.
     pub cdn_url: Option<String>,
@@ -44,6 +47,7 @@ impl Default for S3Config {
             prefix: String::new(),
             access_key_id: None,
             secret_access_key: None,
+            secret_access_key_file: None,
             cdn_url: None,
             presign_expiry_seconds: default_presign_expiry(),
             force_path_style: false,
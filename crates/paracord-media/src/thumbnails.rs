@@ -0,0 +1,247 @@
+//! Image attachment thumbnails.
+//!
+//! Image uploads get downscaled previews at a fixed set of sizes so message
+//! lists never have to fetch the original. Each preview fits inside a
+//! `size` x `size` box with the aspect ratio kept, and is never upscaled.
+//! Opaque images are encoded as JPEG, images with transparency as PNG. A
+//! BlurHash is computed alongside for clients to paint while loading.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::io::Cursor;
+
+use crate::images::{decode_image_with_limits, AssetImageType, ImageProcessingError};
+
+/// Bounding box sizes generated for every image attachment.
+pub const THUMBNAIL_SIZES: [u32; 3] = [160, 320, 640];
+
+/// Attachments are photos more often than avatars are, so allow larger
+/// sources: 8192 x 8192 RGBA fits in the allocation limit.
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 80;
+/// BlurHash components (x, y) and the side length it's computed at.
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+const BLURHASH_SAMPLE_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailFormat {
+    Jpeg,
+    Png,
+}
+
+impl ThumbnailFormat {
+    /// Value stored in `attachments.thumbnail_format`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "jpeg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub size: u32,
+    pub data: Vec<u8>,
+}
+
+/// Everything generated for one image attachment.
+#[derive(Debug, Clone)]
+pub struct ThumbnailSet {
+    /// Dimensions of the original image.
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: Option<String>,
+    pub format: ThumbnailFormat,
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+/// Whether an upload of this content type gets thumbnails.
+pub fn is_thumbnailable(content_type: &str) -> bool {
+    AssetImageType::from_content_type(content_type.trim()).is_some()
+}
+
+/// Snap a requested size to the smallest generated size that is at least as
+/// large, falling back to the largest size.
+pub fn normalize_thumbnail_size(requested: Option<u32>) -> u32 {
+    let largest = THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1];
+    match requested {
+        Some(size) => THUMBNAIL_SIZES
+            .iter()
+            .copied()
+            .find(|candidate| *candidate >= size)
+            .unwrap_or(largest),
+        None => largest,
+    }
+}
+
+/// Storage key for one thumbnail of an attachment.
+pub fn thumbnail_storage_key(attachment_id: i64, size: u32, format: ThumbnailFormat) -> String {
+    format!(
+        "attachments/{}.thumb_{}.{}",
+        attachment_id,
+        size,
+        format.extension()
+    )
+}
+
+/// Decode `data` and produce every thumbnail size plus the image metadata.
+pub fn generate_thumbnails(data: &[u8]) -> Result<ThumbnailSet, ImageProcessingError> {
+    let image = decode_image_with_limits(data, MAX_SOURCE_DIMENSION, MAX_DECODE_ALLOC)?;
+    let format = if has_transparency(&image) {
+        ThumbnailFormat::Png
+    } else {
+        ThumbnailFormat::Jpeg
+    };
+
+    let mut thumbnails = Vec::with_capacity(THUMBNAIL_SIZES.len());
+    for size in THUMBNAIL_SIZES {
+        let resized = if image.width() > size || image.height() > size {
+            image.resize(size, size, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+        thumbnails.push(Thumbnail {
+            size,
+            data: encode_thumbnail(&resized, format)?,
+        });
+    }
+
+    Ok(ThumbnailSet {
+        width: image.width(),
+        height: image.height(),
+        blurhash: compute_blurhash(&image),
+        format,
+        thumbnails,
+    })
+}
+
+fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX)
+}
+
+fn encode_thumbnail(
+    image: &DynamicImage,
+    format: ThumbnailFormat,
+) -> Result<Vec<u8>, ImageProcessingError> {
+    let mut out = Cursor::new(Vec::new());
+    match format {
+        ThumbnailFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(encoder)
+                .map_err(|e| ImageProcessingError::Encode(e.to_string()))?;
+        }
+        ThumbnailFormat::Png => {
+            DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_to(&mut out, image::ImageFormat::Png)
+                .map_err(|e| ImageProcessingError::Encode(e.to_string()))?;
+        }
+    }
+    Ok(out.into_inner())
+}
+
+fn compute_blurhash(image: &DynamicImage) -> Option<String> {
+    let sample = image
+        .resize(
+            BLURHASH_SAMPLE_SIZE,
+            BLURHASH_SAMPLE_SIZE,
+            FilterType::Triangle,
+        )
+        .to_rgba8();
+    blurhash::encode(
+        BLURHASH_COMPONENTS.0,
+        BLURHASH_COMPONENTS.1,
+        sample.width(),
+        sample.height(),
+        sample.as_raw(),
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::{decode_image, encode_image, VariantFormat};
+
+    fn sample(width: u32, height: u32, alpha: u8) -> Vec<u8> {
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, 128, alpha])
+        }));
+        encode_image(&img, VariantFormat::Png).expect("encode sample")
+    }
+
+    #[test]
+    fn thumbnails_keep_aspect_ratio_without_upscaling() {
+        let set = generate_thumbnails(&sample(1000, 500, 255)).unwrap();
+        assert_eq!((set.width, set.height), (1000, 500));
+        assert_eq!(set.format, ThumbnailFormat::Jpeg);
+        assert!(set.blurhash.as_deref().is_some_and(|hash| hash.len() == 28));
+        let dims: Vec<(u32, u32)> = set
+            .thumbnails
+            .iter()
+            .map(|thumb| {
+                let decoded = decode_image(&thumb.data).unwrap();
+                (decoded.width(), decoded.height())
+            })
+            .collect();
+        assert_eq!(dims, vec![(160, 80), (320, 160), (640, 320)]);
+
+        let small = generate_thumbnails(&sample(200, 100, 255)).unwrap();
+        let largest = decode_image(&small.thumbnails[2].data).unwrap();
+        assert_eq!((largest.width(), largest.height()), (200, 100));
+    }
+
+    #[test]
+    fn transparent_images_stay_png() {
+        let set = generate_thumbnails(&sample(64, 64, 100)).unwrap();
+        assert_eq!(set.format, ThumbnailFormat::Png);
+        assert_eq!(
+            ThumbnailFormat::parse(set.format.as_str()),
+            Some(ThumbnailFormat::Png)
+        );
+    }
+
+    #[test]
+    fn normalizes_requested_sizes() {
+        assert_eq!(normalize_thumbnail_size(None), 640);
+        assert_eq!(normalize_thumbnail_size(Some(100)), 160);
+        assert_eq!(normalize_thumbnail_size(Some(321)), 640);
+        assert_eq!(normalize_thumbnail_size(Some(5000)), 640);
+    }
+
+    #[test]
+    fn only_raster_images_are_thumbnailed() {
+        assert!(is_thumbnailable("image/jpeg"));
+        assert!(!is_thumbnailable("image/svg+xml"));
+        assert!(!is_thumbnailable("video/mp4"));
+        assert!(matches!(
+            generate_thumbnails(b"not an image"),
+            Err(ImageProcessingError::Decode(_))
+        ));
+    }
+}
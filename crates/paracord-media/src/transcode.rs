@@ -0,0 +1,254 @@
+//! ffmpeg-backed video transcoding.
+//!
+//! Browsers only reliably play H.264/AAC in MP4 and VP8/VP9 in WebM. Uploads
+//! in other containers (MKV, MOV, AVI, ...) are queued for an H.264 MP4 and a
+//! VP9/Opus WebM rendition, served alongside the original file.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::process::Command;
+
+/// Job state stored on `attachments.transcode_status`.
+pub const TRANSCODE_PENDING: &str = "pending";
+pub const TRANSCODE_PROCESSING: &str = "processing";
+pub const TRANSCODE_DONE: &str = "done";
+pub const TRANSCODE_FAILED: &str = "failed";
+
+/// Container and codecs of a transcoded rendition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscodeFormat {
+    /// H.264 video and AAC audio.
+    Mp4,
+    /// VP9 video and Opus audio.
+    WebM,
+}
+
+impl TranscodeFormat {
+    /// Every rendition the worker produces, in order of preference.
+    pub const ALL: [Self; 2] = [Self::Mp4, Self::WebM];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "mp4" => Some(Self::Mp4),
+            "webm" => Some(Self::WebM),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::WebM => "webm",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Mp4 => "video/mp4",
+            Self::WebM => "video/webm",
+        }
+    }
+
+    /// Pick WebM only when the `Accept` header asks for it and not for MP4;
+    /// H.264 plays in more browsers, so it stays the default.
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Self::Mp4;
+        };
+        let accepts = |media: &str| {
+            accept.split(',').any(|part| {
+                part.split(';')
+                    .next()
+                    .is_some_and(|value| value.trim().eq_ignore_ascii_case(media))
+            })
+        };
+        if accepts("video/webm") && !accepts("video/mp4") {
+            Self::WebM
+        } else {
+            Self::Mp4
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TranscodeError {
+    #[error("failed to run ffmpeg: {0}")]
+    Spawn(String),
+    #[error("ffmpeg timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("ffmpeg exited with status {status}: {stderr}")]
+    Failed { status: String, stderr: String },
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Whether an uploaded file should get a web-compatible rendition.
+///
+/// MP4 and WebM are assumed playable; every other `video/*` type is queued.
+pub fn needs_transcode(content_type: &str) -> bool {
+    let content_type = content_type.trim().to_ascii_lowercase();
+    content_type.starts_with("video/")
+        && !matches!(content_type.as_str(), "video/mp4" | "video/webm")
+}
+
+/// Storage key for a transcoded rendition of an attachment.
+pub fn transcoded_storage_key(attachment_id: i64, format: TranscodeFormat) -> String {
+    format!(
+        "attachments/{}.transcoded.{}",
+        attachment_id,
+        format.extension()
+    )
+}
+
+/// Configuration for the transcoder process.
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    ffmpeg_path: PathBuf,
+    timeout: Duration,
+    work_dir: PathBuf,
+}
+
+impl Transcoder {
+    pub fn new(
+        ffmpeg_path: impl Into<PathBuf>,
+        timeout: Duration,
+        work_dir: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            ffmpeg_path: ffmpeg_path.into(),
+            timeout,
+            work_dir: work_dir.into(),
+        }
+    }
+
+    /// Check that the configured ffmpeg binary can be executed.
+    pub async fn probe(&self) -> bool {
+        Command::new(&self.ffmpeg_path)
+            .arg("-version")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    /// Transcode `input` into `format` and return the encoded bytes.
+    pub async fn transcode(
+        &self,
+        job_id: i64,
+        input: &[u8],
+        format: TranscodeFormat,
+    ) -> Result<Vec<u8>, TranscodeError> {
+        tokio::fs::create_dir_all(&self.work_dir).await?;
+        let input_path = self.work_dir.join(format!("{}.src", job_id));
+        let output_path = self
+            .work_dir
+            .join(format!("{}.{}", job_id, format.extension()));
+        tokio::fs::write(&input_path, input).await?;
+
+        let result = self.run_ffmpeg(&input_path, &output_path, format).await;
+        let output = match result {
+            Ok(()) => tokio::fs::read(&output_path)
+                .await
+                .map_err(TranscodeError::from),
+            Err(err) => Err(err),
+        };
+
+        let _ = tokio::fs::remove_file(&input_path).await;
+        let _ = tokio::fs::remove_file(&output_path).await;
+        output
+    }
+
+    async fn run_ffmpeg(
+        &self,
+        input: &Path,
+        output: &Path,
+        format: TranscodeFormat,
+    ) -> Result<(), TranscodeError> {
+        let mut command = Command::new(&self.ffmpeg_path);
+        command
+            .arg("-hide_banner")
+            .args(["-loglevel", "error", "-nostdin", "-y", "-i"])
+            .arg(input)
+            .args(["-map", "0:v:0", "-map", "0:a:0?"]);
+        match format {
+            TranscodeFormat::Mp4 => command
+                .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "23"])
+                .args(["-pix_fmt", "yuv420p", "-c:a", "aac", "-b:a", "128k"])
+                .args(["-movflags", "+faststart", "-f", "mp4"]),
+            TranscodeFormat::WebM => command
+                .args(["-c:v", "libvpx-vp9", "-crf", "33", "-b:v", "0"])
+                .args(["-deadline", "good", "-cpu-used", "4", "-row-mt", "1"])
+                .args(["-pix_fmt", "yuv420p", "-c:a", "libopus", "-b:a", "128k"])
+                .args(["-f", "webm"]),
+        };
+        command
+            .arg(output)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        let child = command
+            .spawn()
+            .map_err(|e| TranscodeError::Spawn(e.to_string()))?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| TranscodeError::Timeout(self.timeout))??;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr: String = stderr.trim().chars().take(512).collect();
+        Err(TranscodeError::Failed {
+            status: output.status.to_string(),
+            stderr,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_non_web_video_needs_transcode() {
+        assert!(needs_transcode("video/x-matroska"));
+        assert!(needs_transcode("video/quicktime"));
+        assert!(needs_transcode("Video/X-MSVideo"));
+        assert!(!needs_transcode("video/mp4"));
+        assert!(!needs_transcode("video/webm"));
+        assert!(!needs_transcode("audio/ogg"));
+        assert!(!needs_transcode("image/png"));
+    }
+
+    #[test]
+    fn transcoded_key_is_distinct_from_original() {
+        assert_eq!(
+            transcoded_storage_key(42, TranscodeFormat::Mp4),
+            "attachments/42.transcoded.mp4"
+        );
+        assert_eq!(
+            transcoded_storage_key(42, TranscodeFormat::WebM),
+            "attachments/42.transcoded.webm"
+        );
+    }
+
+    #[test]
+    fn negotiates_webm_only_when_mp4_is_not_accepted() {
+        assert_eq!(TranscodeFormat::negotiate(None), TranscodeFormat::Mp4);
+        assert_eq!(
+            TranscodeFormat::negotiate(Some("video/webm")),
+            TranscodeFormat::WebM
+        );
+        assert_eq!(
+            TranscodeFormat::negotiate(Some("video/webm;q=0.9, video/mp4")),
+            TranscodeFormat::Mp4
+        );
+        assert_eq!(
+            TranscodeFormat::negotiate(Some("*/*")),
+            TranscodeFormat::Mp4
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use super::livekit::AudioBitrate;
+use super::livekit::{AudioBitrate, EgressInfo, EgressOutput};
 
 #[derive(Debug, Clone)]
 pub struct VoiceParticipant {
@@ -35,6 +36,12 @@ pub struct VoiceManager {
     rooms: RwLock<HashMap<i64, VoiceRoom>>,
     /// Maps channel_id -> LiveKit room name
     active_livekit_rooms: Arc<RwLock<HashMap<i64, String>>>,
+    /// Directory shared with the LiveKit Egress service that file recordings
+    /// are written to.
+    recording_dir: Option<PathBuf>,
+    /// WebRTC gateway used for native-media WHIP and all WHEP sessions.
+    webrtc_gateway: Option<Arc<super::whip::WebRtcGateway>>,
+    whip_sessions: super::whip::WhipSessionRegistry,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -57,9 +64,69 @@ impl VoiceManager {
             livekit,
             rooms: RwLock::new(HashMap::new()),
             active_livekit_rooms: Arc::new(RwLock::new(HashMap::new())),
+            recording_dir: None,
+            webrtc_gateway: None,
+            whip_sessions: super::whip::WhipSessionRegistry::new(),
         }
     }
 
+    /// Enable file recordings written to `dir` by the LiveKit Egress service.
+    /// The directory must be mounted at the same path in both processes.
+    pub fn with_recording_dir(mut self, dir: Option<String>) -> Self {
+        self.recording_dir = dir.filter(|dir| !dir.trim().is_empty()).map(PathBuf::from);
+        self
+    }
+
+    pub fn recording_dir(&self) -> Option<&Path> {
+        self.recording_dir.as_deref()
+    }
+
+    /// Route native-media WHIP and all WHEP sessions through the WebRTC
+    /// gateway at `url`.
+    pub fn with_webrtc_gateway(mut self, url: Option<String>) -> Self {
+        self.webrtc_gateway = url
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| Arc::new(super::whip::WebRtcGateway::new(url)));
+        self
+    }
+
+    pub fn webrtc_gateway(&self) -> Option<&super::whip::WebRtcGateway> {
+        self.webrtc_gateway.as_deref()
+    }
+
+    pub fn whip_sessions(&self) -> &super::whip::WhipSessionRegistry {
+        &self.whip_sessions
+    }
+
+    pub fn livekit(&self) -> &super::livekit::LiveKitConfig {
+        &self.livekit
+    }
+
+    /// LiveKit room currently hosting a channel, if any.
+    pub async fn livekit_room(&self, channel_id: i64) -> Option<String> {
+        self.active_livekit_rooms
+            .read()
+            .await
+            .get(&channel_id)
+            .cloned()
+    }
+
+    /// Start a room composite egress for a LiveKit room.
+    pub async fn start_egress(
+        &self,
+        room_name: &str,
+        output: &EgressOutput,
+        audio_only: bool,
+    ) -> Result<EgressInfo, anyhow::Error> {
+        self.livekit
+            .start_room_composite_egress(room_name, output, audio_only)
+            .await
+    }
+
+    pub async fn stop_egress(&self, egress_id: &str) -> Result<EgressInfo, anyhow::Error> {
+        self.livekit.stop_egress(egress_id).await
+    }
+
     /// Join a voice channel - creates LiveKit room if needed, returns token.
     #[allow(clippy::too_many_arguments)]
     pub async fn join_channel(
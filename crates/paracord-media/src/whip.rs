@@ -0,0 +1,274 @@
+//! WHIP ingest and WHEP playback.
+//!
+//! OBS (or any WHIP client) publishes into a voice channel with a per-channel
+//! stream key, and lightweight WHEP viewers watch without a full client. The
+//! server terminates neither WebRTC session itself: publishes into LiveKit
+//! channels go to LiveKit Ingress, and everything touching native media (plus
+//! WHEP playback) is handed to an external WebRTC gateway together with
+//! server-minted credentials for the room.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::sync::RwLock;
+
+const SDP_CONTENT_TYPE: &str = "application/sdp";
+
+/// Which way media flows for a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhipDirection {
+    /// WHIP: an external encoder publishes into the channel.
+    Publish,
+    /// WHEP: an external player watches the channel.
+    Play,
+}
+
+/// Room the WebRTC gateway should attach the session to.
+#[derive(Debug, Clone)]
+pub enum GatewayTarget {
+    Native {
+        endpoint: String,
+        room: String,
+        token: String,
+    },
+    LiveKit {
+        url: String,
+        room: String,
+        token: String,
+    },
+}
+
+impl GatewayTarget {
+    fn headers(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            GatewayTarget::Native {
+                endpoint,
+                room,
+                token,
+            } => vec![
+                ("X-Paracord-Media-Endpoint", endpoint.as_str()),
+                ("X-Paracord-Media-Room", room.as_str()),
+                ("X-Paracord-Media-Token", token.as_str()),
+            ],
+            GatewayTarget::LiveKit { url, room, token } => vec![
+                ("X-Paracord-LiveKit-Url", url.as_str()),
+                ("X-Paracord-LiveKit-Room", room.as_str()),
+                ("X-Paracord-LiveKit-Token", token.as_str()),
+            ],
+        }
+    }
+}
+
+/// Result of a WHIP/WHEP offer/answer exchange with an upstream endpoint.
+#[derive(Debug, Clone)]
+pub struct SdpExchange {
+    pub answer: String,
+    /// Absolute URL of the upstream session resource (for teardown).
+    pub resource_url: String,
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+/// POST an SDP offer to a WHIP/WHEP endpoint and return its answer.
+pub async fn exchange_sdp(
+    url: &str,
+    bearer: Option<&str>,
+    headers: &[(&str, &str)],
+    offer: &str,
+) -> Result<SdpExchange, anyhow::Error> {
+    let mut request = http_client()
+        .post(url)
+        .header("Content-Type", SDP_CONTENT_TYPE)
+        .body(offer.to_string());
+    if let Some(bearer) = bearer {
+        request = request.header("Authorization", format!("Bearer {}", bearer));
+    }
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let resp = request.send().await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let err = resp.text().await.unwrap_or_default();
+        anyhow::bail!("WHIP/WHEP endpoint returned {}: {}", status, err);
+    }
+    let base = reqwest::Url::parse(url)?;
+    let resource_url = resp
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| anyhow::anyhow!("WHIP/WHEP endpoint did not return a Location"))?;
+    // Location is usually relative to the endpoint.
+    let resource_url = base.join(resource_url)?.to_string();
+    let answer = resp.text().await?;
+    Ok(SdpExchange {
+        answer,
+        resource_url,
+    })
+}
+
+/// End an upstream WHIP/WHEP session.
+pub async fn delete_resource(url: &str, bearer: Option<&str>) -> Result<(), anyhow::Error> {
+    let mut request = http_client().delete(url);
+    if let Some(bearer) = bearer {
+        request = request.header("Authorization", format!("Bearer {}", bearer));
+    }
+    let resp = request.send().await?;
+    // The session may already be gone (e.g. the encoder disconnected).
+    if !resp.status().is_success() && resp.status() != reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!("Failed to delete WHIP/WHEP session: {}", resp.status());
+    }
+    Ok(())
+}
+
+/// External WebRTC gateway that bridges WHIP/WHEP sessions onto a room.
+pub struct WebRtcGateway {
+    base_url: String,
+}
+
+impl WebRtcGateway {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn offer(
+        &self,
+        direction: WhipDirection,
+        target: &GatewayTarget,
+        offer: &str,
+    ) -> Result<SdpExchange, anyhow::Error> {
+        let path = match direction {
+            WhipDirection::Publish => "whip",
+            WhipDirection::Play => "whep",
+        };
+        exchange_sdp(
+            &format!("{}/{}", self.base_url, path),
+            None,
+            &target.headers(),
+            offer,
+        )
+        .await
+    }
+}
+
+/// A live WHIP/WHEP session proxied by the server.
+#[derive(Debug, Clone)]
+pub struct WhipSession {
+    pub id: String,
+    pub channel_id: i64,
+    pub direction: WhipDirection,
+    /// Stream key id for publishers, user id for viewers.
+    pub owner_id: i64,
+    pub resource_url: String,
+    /// LiveKit ingress backing a publish session, deleted on teardown.
+    pub ingress_id: Option<String>,
+}
+
+/// In-memory registry of proxied sessions.
+#[derive(Default)]
+pub struct WhipSessionRegistry {
+    sessions: RwLock<HashMap<String, WhipSession>>,
+}
+
+impl WhipSessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn insert(&self, session: WhipSession) {
+        self.sessions
+            .write()
+            .await
+            .insert(session.id.clone(), session);
+    }
+
+    pub async fn get(&self, id: &str) -> Option<WhipSession> {
+        self.sessions.read().await.get(id).cloned()
+    }
+
+    pub async fn remove(&self, id: &str) -> Option<WhipSession> {
+        self.sessions.write().await.remove(id)
+    }
+
+    /// Sessions owned by `owner_id` in the given direction.
+    pub async fn owned_by(&self, direction: WhipDirection, owner_id: i64) -> Vec<WhipSession> {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| session.direction == direction && session.owner_id == owner_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of sessions in a channel in the given direction.
+    pub async fn count(&self, channel_id: i64, direction: WhipDirection) -> usize {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| session.channel_id == channel_id && session.direction == direction)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, channel_id: i64, direction: WhipDirection, owner_id: i64) -> WhipSession {
+        WhipSession {
+            id: id.to_string(),
+            channel_id,
+            direction,
+            owner_id,
+            resource_url: format!("http://gateway/{}", id),
+            ingress_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn registry_tracks_sessions_by_owner_and_channel() {
+        let registry = WhipSessionRegistry::new();
+        registry
+            .insert(session("a", 1, WhipDirection::Publish, 10))
+            .await;
+        registry
+            .insert(session("b", 1, WhipDirection::Play, 20))
+            .await;
+        registry
+            .insert(session("c", 2, WhipDirection::Play, 20))
+            .await;
+
+        assert_eq!(registry.count(1, WhipDirection::Publish).await, 1);
+        assert_eq!(registry.count(1, WhipDirection::Play).await, 1);
+        assert_eq!(registry.owned_by(WhipDirection::Play, 20).await.len(), 2);
+        assert!(registry
+            .owned_by(WhipDirection::Publish, 20)
+            .await
+            .is_empty());
+
+        assert_eq!(registry.remove("a").await.unwrap().owner_id, 10);
+        assert!(registry.get("a").await.is_none());
+        assert_eq!(registry.count(1, WhipDirection::Publish).await, 0);
+    }
+
+    #[test]
+    fn gateway_headers_carry_target_credentials() {
+        let target = GatewayTarget::Native {
+            endpoint: "127.0.0.1:8443".to_string(),
+            room: "1:2".to_string(),
+            token: "tok".to_string(),
+        };
+        let headers: HashMap<_, _> = target.headers().into_iter().collect();
+        assert_eq!(headers["X-Paracord-Media-Room"], "1:2");
+        assert_eq!(headers["X-Paracord-Media-Token"], "tok");
+    }
+}
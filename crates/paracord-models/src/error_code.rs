@@ -0,0 +1,210 @@
+//! Machine-readable API error codes.
+//!
+//! Every REST error carries one of these as `error_code` (number) alongside
+//! `code` (stable name). Numbers are grouped by kind:
+//!
+//! - `0`: unexpected server error
+//! - `10xxx`: the referenced resource doesn't exist
+//! - `20xxx`: the action is throttled
+//! - `30xxx`: a per-guild limit was reached
+//! - `40xxx`: authentication, client, or state problems
+//! - `50xxx`: the request was rejected as not allowed or invalid
+//! - `130000`: the server can't handle the request right now
+//!
+//! Codes are never reused; add new ones instead of changing a meaning.
+
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum ErrorCode {
+    InternalError = 0,
+
+    NotFound = 10000,
+    UnknownChannel = 10003,
+    UnknownGuild = 10004,
+    UnknownInvite = 10006,
+    UnknownMember = 10007,
+    UnknownMessage = 10008,
+    UnknownRole = 10011,
+    UnknownUser = 10013,
+    UnknownEmoji = 10014,
+    UnknownWebhook = 10015,
+    UnknownSession = 10020,
+
+    RateLimited = 20028,
+    FederationQuotaExceeded = 20029,
+
+    MaxGuildRoles = 30005,
+    MaxChannelWebhooks = 30007,
+    MaxGuildEmojis = 30008,
+    MaxGuildChannels = 30013,
+    MaxGuildWebhooks = 30058,
+    MaxGuildBots = 30059,
+
+    Unauthorized = 40001,
+    ClientOutdated = 40010,
+    Conflict = 40090,
+
+    Forbidden = 50001,
+    WidgetDisabled = 50004,
+    MissingPermissions = 50013,
+    AgeGateRequired = 50030,
+    MemberVerificationDenied = 50031,
+    BadRequest = 50035,
+
+    ServiceUnavailable = 130000,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::InternalError,
+        ErrorCode::NotFound,
+        ErrorCode::UnknownChannel,
+        ErrorCode::UnknownGuild,
+        ErrorCode::UnknownInvite,
+        ErrorCode::UnknownMember,
+        ErrorCode::UnknownMessage,
+        ErrorCode::UnknownRole,
+        ErrorCode::UnknownUser,
+        ErrorCode::UnknownEmoji,
+        ErrorCode::UnknownWebhook,
+        ErrorCode::UnknownSession,
+        ErrorCode::RateLimited,
+        ErrorCode::FederationQuotaExceeded,
+        ErrorCode::MaxGuildRoles,
+        ErrorCode::MaxChannelWebhooks,
+        ErrorCode::MaxGuildEmojis,
+        ErrorCode::MaxGuildChannels,
+        ErrorCode::MaxGuildWebhooks,
+        ErrorCode::MaxGuildBots,
+        ErrorCode::Unauthorized,
+        ErrorCode::ClientOutdated,
+        ErrorCode::Conflict,
+        ErrorCode::Forbidden,
+        ErrorCode::WidgetDisabled,
+        ErrorCode::MissingPermissions,
+        ErrorCode::AgeGateRequired,
+        ErrorCode::MemberVerificationDenied,
+        ErrorCode::BadRequest,
+        ErrorCode::ServiceUnavailable,
+    ];
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Stable name sent as `code`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::UnknownChannel => "UNKNOWN_CHANNEL",
+            ErrorCode::UnknownGuild => "UNKNOWN_GUILD",
+            ErrorCode::UnknownInvite => "UNKNOWN_INVITE",
+            ErrorCode::UnknownMember => "UNKNOWN_MEMBER",
+            ErrorCode::UnknownMessage => "UNKNOWN_MESSAGE",
+            ErrorCode::UnknownRole => "UNKNOWN_ROLE",
+            ErrorCode::UnknownUser => "UNKNOWN_USER",
+            ErrorCode::UnknownEmoji => "UNKNOWN_EMOJI",
+            ErrorCode::UnknownWebhook => "UNKNOWN_WEBHOOK",
+            ErrorCode::UnknownSession => "UNKNOWN_SESSION",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::FederationQuotaExceeded => "FEDERATION_QUOTA_EXCEEDED",
+            ErrorCode::MaxGuildRoles => "MAX_GUILD_ROLES",
+            ErrorCode::MaxChannelWebhooks => "MAX_CHANNEL_WEBHOOKS",
+            ErrorCode::MaxGuildEmojis => "MAX_GUILD_EMOJIS",
+            ErrorCode::MaxGuildChannels => "MAX_GUILD_CHANNELS",
+            ErrorCode::MaxGuildWebhooks => "MAX_GUILD_WEBHOOKS",
+            ErrorCode::MaxGuildBots => "MAX_GUILD_BOTS",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::ClientOutdated => "CLIENT_OUTDATED",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::WidgetDisabled => "WIDGET_DISABLED",
+            ErrorCode::MissingPermissions => "MISSING_PERMISSIONS",
+            ErrorCode::AgeGateRequired => "AGE_GATE_REQUIRED",
+            ErrorCode::MemberVerificationDenied => "MEMBER_VERIFICATION_DENIED",
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+        }
+    }
+
+    /// HTTP status the code is returned with.
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorCode::InternalError => 500,
+            ErrorCode::NotFound
+            | ErrorCode::UnknownChannel
+            | ErrorCode::UnknownGuild
+            | ErrorCode::UnknownInvite
+            | ErrorCode::UnknownMember
+            | ErrorCode::UnknownMessage
+            | ErrorCode::UnknownRole
+            | ErrorCode::UnknownUser
+            | ErrorCode::UnknownEmoji
+            | ErrorCode::UnknownWebhook
+            | ErrorCode::UnknownSession => 404,
+            ErrorCode::RateLimited | ErrorCode::FederationQuotaExceeded => 429,
+            ErrorCode::MaxGuildRoles
+            | ErrorCode::MaxChannelWebhooks
+            | ErrorCode::MaxGuildEmojis
+            | ErrorCode::MaxGuildChannels
+            | ErrorCode::MaxGuildWebhooks
+            | ErrorCode::MaxGuildBots => 400,
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::ClientOutdated => 426,
+            ErrorCode::Conflict => 409,
+            ErrorCode::Forbidden
+            | ErrorCode::WidgetDisabled
+            | ErrorCode::MissingPermissions
+            | ErrorCode::AgeGateRequired
+            | ErrorCode::MemberVerificationDenied => 403,
+            ErrorCode::BadRequest => 400,
+            ErrorCode::ServiceUnavailable => 503,
+        }
+    }
+
+    /// English description, used as the message for errors without a
+    /// more specific reason.
+    pub fn message(self) -> &'static str {
+        match self {
+            ErrorCode::InternalError => "internal server error",
+            ErrorCode::NotFound => "not found",
+            ErrorCode::UnknownChannel => "unknown channel",
+            ErrorCode::UnknownGuild => "unknown guild",
+            ErrorCode::UnknownInvite => "unknown invite",
+            ErrorCode::UnknownMember => "unknown member",
+            ErrorCode::UnknownMessage => "unknown message",
+            ErrorCode::UnknownRole => "unknown role",
+            ErrorCode::UnknownUser => "unknown user",
+            ErrorCode::UnknownEmoji => "unknown emoji",
+            ErrorCode::UnknownWebhook => "unknown webhook",
+            ErrorCode::UnknownSession => "unknown session",
+            ErrorCode::RateLimited => "rate limited",
+            ErrorCode::FederationQuotaExceeded => "federation bandwidth quota exceeded",
+            ErrorCode::MaxGuildRoles => "maximum number of guild roles reached",
+            ErrorCode::MaxChannelWebhooks => "maximum number of webhooks in this channel reached",
+            ErrorCode::MaxGuildEmojis => "maximum number of emojis reached",
+            ErrorCode::MaxGuildChannels => "maximum number of guild channels reached",
+            ErrorCode::MaxGuildWebhooks => "maximum number of guild webhooks reached",
+            ErrorCode::MaxGuildBots => "maximum number of bots in this guild reached",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::ClientOutdated => "client outdated",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::WidgetDisabled => "widget disabled",
+            ErrorCode::MissingPermissions => "missing permissions",
+            ErrorCode::AgeGateRequired => "age gate acknowledgement required",
+            ErrorCode::MemberVerificationDenied => "membership verification denied",
+            ErrorCode::BadRequest => "bad request",
+            ErrorCode::ServiceUnavailable => "service unavailable",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.code())
+    }
+}
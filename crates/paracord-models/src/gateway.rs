@@ -8,6 +8,8 @@ pub const OP_VOICE_STATE_UPDATE: u8 = 4;
 pub const OP_RESUME: u8 = 6;
 pub const OP_REQUEST_GUILD_MEMBERS: u8 = 8;
 pub const OP_TYPING_START: u8 = 5;
+pub const OP_READ_STATE_ACK: u8 = 18;
+pub const OP_GUILD_SUBSCRIPTIONS: u8 = 19;
 
 // Server -> Client opcodes
 pub const OP_DISPATCH: u8 = 0;
@@ -46,13 +48,17 @@ pub const EVENT_GUILD_DELETE: &str = "GUILD_DELETE";
 pub const EVENT_GUILD_BAN_ADD: &str = "GUILD_BAN_ADD";
 pub const EVENT_GUILD_BAN_REMOVE: &str = "GUILD_BAN_REMOVE";
 pub const EVENT_GUILD_EMOJIS_UPDATE: &str = "GUILD_EMOJIS_UPDATE";
+pub const EVENT_GUILD_THEME_UPDATE: &str = "GUILD_THEME_UPDATE";
+pub const EVENT_GUILD_TAGS_UPDATE: &str = "GUILD_TAGS_UPDATE";
 pub const EVENT_GUILD_MEMBER_ADD: &str = "GUILD_MEMBER_ADD";
 pub const EVENT_GUILD_MEMBER_REMOVE: &str = "GUILD_MEMBER_REMOVE";
 pub const EVENT_GUILD_MEMBER_UPDATE: &str = "GUILD_MEMBER_UPDATE";
 pub const EVENT_GUILD_MEMBERS_CHUNK: &str = "GUILD_MEMBERS_CHUNK";
+pub const EVENT_GUILD_MEMBER_LIST_UPDATE: &str = "GUILD_MEMBER_LIST_UPDATE";
 pub const EVENT_GUILD_ROLE_CREATE: &str = "GUILD_ROLE_CREATE";
 pub const EVENT_GUILD_ROLE_UPDATE: &str = "GUILD_ROLE_UPDATE";
 pub const EVENT_GUILD_ROLE_DELETE: &str = "GUILD_ROLE_DELETE";
+pub const EVENT_GUILD_JOIN_REQUEST_UPDATE: &str = "GUILD_JOIN_REQUEST_UPDATE";
 
 // Channel events
 pub const EVENT_CHANNEL_CREATE: &str = "CHANNEL_CREATE";
@@ -68,6 +74,10 @@ pub const EVENT_MESSAGE_DELETE_BULK: &str = "MESSAGE_DELETE_BULK";
 pub const EVENT_MESSAGE_REACTION_ADD: &str = "MESSAGE_REACTION_ADD";
 pub const EVENT_MESSAGE_REACTION_REMOVE: &str = "MESSAGE_REACTION_REMOVE";
 pub const EVENT_MESSAGE_REACTION_REMOVE_ALL: &str = "MESSAGE_REACTION_REMOVE_ALL";
+pub const EVENT_MESSAGE_REACTION_BULK: &str = "MESSAGE_REACTION_BULK";
+
+// Auto-moderation events
+pub const EVENT_AUTO_MODERATION_ACTION_EXECUTION: &str = "AUTO_MODERATION_ACTION_EXECUTION";
 
 // Presence and typing
 pub const EVENT_PRESENCE_UPDATE: &str = "PRESENCE_UPDATE";
@@ -76,6 +86,12 @@ pub const EVENT_TYPING_START: &str = "TYPING_START";
 // Voice events
 pub const EVENT_VOICE_STATE_UPDATE: &str = "VOICE_STATE_UPDATE";
 pub const EVENT_VOICE_SERVER_UPDATE: &str = "VOICE_SERVER_UPDATE";
+pub const EVENT_VOICE_ROOM_SIGNAL: &str = "VOICE_ROOM_SIGNAL";
+pub const EVENT_VOICE_SPEAKING: &str = "VOICE_SPEAKING";
+pub const EVENT_VOICE_METRICS: &str = "VOICE_METRICS";
+pub const EVENT_VOICE_RECORDING_START: &str = "VOICE_RECORDING_START";
+pub const EVENT_VOICE_RECORDING_UPDATE: &str = "VOICE_RECORDING_UPDATE";
+pub const EVENT_VOICE_RECORDING_STOP: &str = "VOICE_RECORDING_STOP";
 
 // Invite events
 pub const EVENT_INVITE_CREATE: &str = "INVITE_CREATE";
@@ -85,6 +101,28 @@ pub const EVENT_INVITE_DELETE: &str = "INVITE_DELETE";
 pub const EVENT_RELATIONSHIP_ADD: &str = "RELATIONSHIP_ADD";
 pub const EVENT_RELATIONSHIP_REMOVE: &str = "RELATIONSHIP_REMOVE";
 
+// User events
+pub const EVENT_USER_SETTINGS_UPDATE: &str = "USER_SETTINGS_UPDATE";
+pub const EVENT_DEVICE_APPROVAL_REQUEST: &str = "DEVICE_APPROVAL_REQUEST";
+pub const EVENT_DEVICE_APPROVAL_PENDING: &str = "DEVICE_APPROVAL_PENDING";
+pub const EVENT_DEVICE_APPROVAL_UPDATE: &str = "DEVICE_APPROVAL_UPDATE";
+pub const EVENT_REMINDER_DUE: &str = "REMINDER_DUE";
+pub const EVENT_USER_GUILD_SETTINGS_UPDATE: &str = "USER_GUILD_SETTINGS_UPDATE";
+
+// Group DM events
+pub const EVENT_GROUP_DM_CREATE: &str = "GROUP_DM_CREATE";
+pub const EVENT_GROUP_DM_UPDATE: &str = "GROUP_DM_UPDATE";
+pub const EVENT_GROUP_DM_DELETE: &str = "GROUP_DM_DELETE";
+pub const EVENT_GROUP_DM_RECIPIENT_ADD: &str = "GROUP_DM_RECIPIENT_ADD";
+pub const EVENT_GROUP_DM_RECIPIENT_REMOVE: &str = "GROUP_DM_RECIPIENT_REMOVE";
+pub const EVENT_GROUP_DM_SENDER_KEY: &str = "GROUP_DM_SENDER_KEY";
+
+// DM events
+pub const EVENT_DM_RECEIPT_UPDATE: &str = "DM_RECEIPT_UPDATE";
+
+// Read state events
+pub const EVENT_READ_STATE_UPDATE: &str = "READ_STATE_UPDATE";
+
 // Media events
 pub const EVENT_MEDIA_SESSION_DESC: &str = "MEDIA_SESSION_DESC";
 pub const EVENT_MEDIA_KEY_DELIVER: &str = "MEDIA_KEY_DELIVER";
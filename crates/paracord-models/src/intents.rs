@@ -21,6 +21,7 @@ bitflags! {
         const DIRECT_MESSAGE_TYPING     = 1 << 14;
         const MESSAGE_CONTENT           = 1 << 15; // privileged
         const GUILD_SCHEDULED_EVENTS    = 1 << 16;
+        const AUTO_MODERATION_EXECUTION = 1 << 21;
     }
 }
 
@@ -79,6 +80,8 @@ pub fn intent_required_for_event(event_name: &str) -> Option<GatewayIntents> {
         | EVENT_CHANNEL_UPDATE
         | EVENT_CHANNEL_DELETE
         | EVENT_CHANNEL_PINS_UPDATE
+        | EVENT_GUILD_THEME_UPDATE
+        | EVENT_GUILD_TAGS_UPDATE
         | EVENT_GUILD_ROLE_CREATE
         | EVENT_GUILD_ROLE_UPDATE
         | EVENT_GUILD_ROLE_DELETE => Some(GatewayIntents::GUILDS),
@@ -87,7 +90,8 @@ pub fn intent_required_for_event(event_name: &str) -> Option<GatewayIntents> {
         EVENT_GUILD_MEMBER_ADD
         | EVENT_GUILD_MEMBER_UPDATE
         | EVENT_GUILD_MEMBER_REMOVE
-        | EVENT_GUILD_MEMBERS_CHUNK => Some(GatewayIntents::GUILD_MEMBERS),
+        | EVENT_GUILD_MEMBERS_CHUNK
+        | EVENT_GUILD_MEMBER_LIST_UPDATE => Some(GatewayIntents::GUILD_MEMBERS),
 
         // GUILD_MODERATION
         EVENT_GUILD_BAN_ADD | EVENT_GUILD_BAN_REMOVE => {
@@ -103,7 +107,12 @@ pub fn intent_required_for_event(event_name: &str) -> Option<GatewayIntents> {
         }
 
         // GUILD_VOICE_STATES
-        EVENT_VOICE_STATE_UPDATE => Some(GatewayIntents::GUILD_VOICE_STATES),
+        EVENT_VOICE_STATE_UPDATE
+        | EVENT_VOICE_ROOM_SIGNAL
+        | EVENT_VOICE_SPEAKING
+        | EVENT_VOICE_RECORDING_START
+        | EVENT_VOICE_RECORDING_UPDATE
+        | EVENT_VOICE_RECORDING_STOP => Some(GatewayIntents::GUILD_VOICE_STATES),
 
         // GUILD_PRESENCES (privileged)
         EVENT_PRESENCE_UPDATE => Some(GatewayIntents::GUILD_PRESENCES),
@@ -117,13 +126,28 @@ pub fn intent_required_for_event(event_name: &str) -> Option<GatewayIntents> {
         // GUILD_MESSAGE_REACTIONS
         EVENT_MESSAGE_REACTION_ADD
         | EVENT_MESSAGE_REACTION_REMOVE
-        | EVENT_MESSAGE_REACTION_REMOVE_ALL => {
+        | EVENT_MESSAGE_REACTION_REMOVE_ALL
+        | EVENT_MESSAGE_REACTION_BULK => {
             Some(GatewayIntents::GUILD_MESSAGE_REACTIONS)
         }
 
         // GUILD_MESSAGE_TYPING
         EVENT_TYPING_START => Some(GatewayIntents::GUILD_MESSAGE_TYPING),
 
+        // DIRECT_MESSAGES
+        EVENT_GROUP_DM_CREATE
+        | EVENT_GROUP_DM_UPDATE
+        | EVENT_GROUP_DM_DELETE
+        | EVENT_GROUP_DM_RECIPIENT_ADD
+        | EVENT_GROUP_DM_RECIPIENT_REMOVE
+        | EVENT_GROUP_DM_SENDER_KEY
+        | EVENT_DM_RECEIPT_UPDATE => Some(GatewayIntents::DIRECT_MESSAGES),
+
+        // AUTO_MODERATION_EXECUTION
+        EVENT_AUTO_MODERATION_ACTION_EXECUTION => {
+            Some(GatewayIntents::AUTO_MODERATION_EXECUTION)
+        }
+
         // Always dispatched (READY, RESUMED, interactions, media, etc.)
         _ => None,
     }
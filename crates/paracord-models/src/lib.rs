@@ -5,6 +5,7 @@ pub mod bot;
 pub mod channel;
 pub mod embed;
 pub mod emoji;
+pub mod error_code;
 pub mod gateway;
 pub mod guild;
 pub mod invite;
@@ -4,6 +4,11 @@ use serde::{Deserialize, Serialize};
 use crate::attachment::Attachment;
 use crate::embed::Embed;
 
+/// Bit flag: message content is DM end-to-end encrypted ciphertext.
+pub const MESSAGE_FLAG_DM_E2EE: i32 = 1 << 0;
+/// Bit flag: message is a voice message (single audio attachment, no text).
+pub const MESSAGE_FLAG_VOICE_MESSAGE: i32 = 1 << 1;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(i16)]
 pub enum MessageType {
@@ -34,6 +34,57 @@ bitflags! {
         const MANAGE_ROLES         = 1 << 28;
         const MANAGE_WEBHOOKS      = 1 << 29;
         const MANAGE_EMOJIS        = 1 << 30;
+        const RECORD_VOICE         = 1 << 31;
+        const POST_OVERRIDE        = 1 << 32;
+        const MANAGE_THREADS       = 1 << 33;
+        const MODERATE_MEMBERS     = 1 << 34;
+        const USE_SOUNDBOARD       = 1 << 35;
+        const MANAGE_INVITES       = 1 << 36;
+        const VIEW_REPORTS         = 1 << 37;
+    }
+}
+
+/// Clients that understand bits above `POST_OVERRIDE` send this header with
+/// [`PERMISSIONS_VERSION`]; bitfields from anyone else go through
+/// [`Permissions::upgrade_legacy`].
+pub const PERMISSIONS_VERSION_HEADER: &str = "x-paracord-permissions-version";
+pub const PERMISSIONS_VERSION: u32 = 2;
+
+impl Permissions {
+    /// Every bit that existed before `MANAGE_THREADS`.
+    pub const LEGACY: Permissions = Permissions::from_bits_truncate((1 << 33) - 1);
+
+    /// Map a bitfield from the old set onto the current one. The new bits
+    /// were split out of broader ones, so holders of the old bit keep what
+    /// they could do before:
+    ///
+    /// - `MANAGE_CHANNELS` -> `MANAGE_THREADS`
+    /// - `MUTE_MEMBERS` -> `MODERATE_MEMBERS`
+    /// - `SPEAK` -> `USE_SOUNDBOARD`
+    /// - `MANAGE_GUILD` -> `MANAGE_INVITES`, `VIEW_REPORTS`
+    ///
+    /// Bitfields that already carry any new bit are returned unchanged.
+    /// `migrations/*_permission_bits_v2.sql` applies the same mapping to
+    /// stored rows.
+    pub fn upgrade_legacy(bits: i64) -> i64 {
+        if bits & !Self::LEGACY.bits() != 0 {
+            return bits;
+        }
+        let old = Permissions::from_bits_retain(bits);
+        let mut upgraded = old;
+        if old.contains(Self::MANAGE_CHANNELS) {
+            upgraded |= Self::MANAGE_THREADS;
+        }
+        if old.contains(Self::MUTE_MEMBERS) {
+            upgraded |= Self::MODERATE_MEMBERS;
+        }
+        if old.contains(Self::SPEAK) {
+            upgraded |= Self::USE_SOUNDBOARD;
+        }
+        if old.contains(Self::MANAGE_GUILD) {
+            upgraded |= Self::MANAGE_INVITES | Self::VIEW_REPORTS;
+        }
+        upgraded.bits()
     }
 }
 
@@ -58,8 +109,32 @@ impl Default for Permissions {
             | Self::ADD_REACTIONS
             | Self::CONNECT
             | Self::SPEAK
+            | Self::USE_SOUNDBOARD
             | Self::STREAM
             | Self::USE_VAD
             | Self::CHANGE_NICKNAME
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_bits_map_forward() {
+        let old =
+            (Permissions::MANAGE_CHANNELS | Permissions::MANAGE_GUILD | Permissions::SPEAK).bits();
+        let upgraded = Permissions::from_bits_truncate(Permissions::upgrade_legacy(old));
+        assert!(upgraded.contains(
+            Permissions::MANAGE_THREADS
+                | Permissions::MANAGE_INVITES
+                | Permissions::VIEW_REPORTS
+                | Permissions::USE_SOUNDBOARD
+        ));
+        assert!(!upgraded.contains(Permissions::MODERATE_MEMBERS));
+
+        let current = (Permissions::MANAGE_GUILD | Permissions::MANAGE_THREADS).bits();
+        assert_eq!(Permissions::upgrade_legacy(current), current);
+        assert_eq!(Permissions::upgrade_legacy(0), 0);
+    }
+}
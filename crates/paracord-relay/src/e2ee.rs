@@ -196,9 +196,11 @@ mod tests {
     use super::*;
     use std::sync::Mutex;
 
+    type Delivered = Arc<Mutex<Vec<(i64, MediaKeyDeliver)>>>;
+
     /// Collects delivered keys for test assertions.
-    fn mock_delivery() -> (KeyDeliveryFn, Arc<Mutex<Vec<(i64, MediaKeyDeliver)>>>) {
-        let delivered: Arc<Mutex<Vec<(i64, MediaKeyDeliver)>>> = Arc::new(Mutex::new(Vec::new()));
+    fn mock_delivery() -> (KeyDeliveryFn, Delivered) {
+        let delivered: Delivered = Arc::new(Mutex::new(Vec::new()));
         let delivered_clone = delivered.clone();
         let f: KeyDeliveryFn = Arc::new(move |user_id, deliver| {
             delivered_clone.lock().unwrap().push((user_id, deliver));
@@ -7,3 +7,4 @@ pub mod relay;
 pub mod room;
 pub mod signaling;
 pub mod speaker;
+pub mod stats;
@@ -133,6 +133,15 @@ impl P2PCoordinator {
         }
     }
 
+    /// Forget the connection state for a pair (e.g. the room grew past two
+    /// participants). Registered addresses are kept.
+    pub fn reset_pair(&self, user_a: i64, user_b: i64) {
+        let pair = PeerPair::new(user_a, user_b);
+        if self.connections.remove(&pair).is_some() {
+            debug!(user_a, user_b, "p2p: pair reset");
+        }
+    }
+
     /// Get the P2P status between two peers.
     pub fn get_status(&self, user_a: i64, user_b: i64) -> Option<P2PStatus> {
         let pair = PeerPair::new(user_a, user_b);
@@ -240,6 +249,18 @@ mod tests {
         assert_eq!(coord.get_status(1, 2), None);
     }
 
+    #[test]
+    fn reset_pair_keeps_addresses() {
+        let coord = P2PCoordinator::new();
+        let addr: SocketAddr = "1.2.3.4:5000".parse().unwrap();
+        coord.register_address(1, addr);
+        coord.initiate_p2p(1, 2);
+
+        coord.reset_pair(2, 1);
+        assert_eq!(coord.get_status(1, 2), None);
+        assert_eq!(coord.get_address(1), Some(addr));
+    }
+
     #[test]
     fn get_room_peer_addresses() {
         let coord = P2PCoordinator::new();
@@ -1,14 +1,21 @@
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use dashmap::DashMap;
-use tokio::sync::{mpsc, Mutex, Notify};
+use tokio::sync::{broadcast, mpsc, Mutex, Notify};
 use tracing::{debug, info, warn};
 
-use paracord_transport::protocol::{MediaHeader, HEADER_SIZE};
+use paracord_transport::control::{ControlMessage, RoomSignalKind};
+use paracord_transport::protocol::{MediaHeader, TrackType, HEADER_SIZE};
 
+use crate::p2p::{P2PCoordinator, P2PStatus};
 use crate::room::MediaRoomManager;
-use crate::speaker::SpeakerDetector;
+use crate::speaker::{SpeakerDetector, SpeakerForwardingPolicy};
+use crate::stats::{ParticipantStatsSnapshot, StatsTracker};
 
 /// Transport abstraction for relay connections.
 /// Raw QUIC is used for Tauri desktop and federation; channel-bridged
@@ -46,15 +53,20 @@ pub struct ConnectionHandle {
     pub user_id: i64,
     pub room_id: String,
     transport: MediaTransport,
+    /// Public address usable for a direct peer-to-peer path. Only raw QUIC
+    /// clients can hole-punch; bridged browser sessions never have one.
+    direct_addr: Option<SocketAddr>,
 }
 
 impl ConnectionHandle {
     /// Create a handle wrapping a raw QUIC connection.
     pub fn new(user_id: i64, room_id: String, conn: quinn::Connection) -> Self {
+        let direct_addr = Some(conn.remote_address());
         Self {
             user_id,
             room_id,
             transport: MediaTransport::Quic(conn),
+            direct_addr,
         }
     }
 
@@ -72,6 +84,7 @@ impl ConnectionHandle {
                 outbound_tx,
                 inbound_rx: Arc::new(Mutex::new(inbound_rx)),
             },
+            direct_addr: None,
         }
     }
 
@@ -96,6 +109,18 @@ impl ConnectionHandle {
         }
     }
 
+    /// Whether both handles wrap the same underlying connection or session.
+    pub fn same_transport(&self, other: &ConnectionHandle) -> bool {
+        match (&self.transport, &other.transport) {
+            (MediaTransport::Quic(a), MediaTransport::Quic(b)) => a.stable_id() == b.stable_id(),
+            (
+                MediaTransport::Bridged { outbound_tx: a, .. },
+                MediaTransport::Bridged { outbound_tx: b, .. },
+            ) => a.same_channel(b),
+            _ => false,
+        }
+    }
+
     /// Check if the connection is still alive.
     pub fn is_alive(&self) -> bool {
         match &self.transport {
@@ -105,7 +130,101 @@ impl ConnectionHandle {
     }
 }
 
+/// Maximum length (in bytes) of a reaction emoji.
+const MAX_REACTION_EMOJI_LEN: usize = 32;
+/// Room signals a single participant may send per window.
+const ROOM_SIGNAL_BURST: u32 = 10;
+/// Length of the room signal rate-limit window.
+const ROOM_SIGNAL_WINDOW: Duration = Duration::from_secs(1);
+/// How long a room's speaker ranking is reused before being recomputed.
+const SPEAKER_RANK_REFRESH: Duration = Duration::from_millis(100);
+/// Capacity of the room signal event channel consumed by the gateway.
+const ROOM_SIGNAL_EVENT_CAPACITY: usize = 256;
+/// Capacity of the speaking event channel consumed by the gateway.
+const SPEAKING_EVENT_CAPACITY: usize = 1024;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RoomSignalError {
+    #[error("user {0} is not connected to the relay")]
+    NotConnected(i64),
+    #[error("invalid room signal: {0}")]
+    Invalid(&'static str),
+    #[error("room signal rate limit exceeded")]
+    RateLimited,
+}
+
+/// A room signal accepted by the relay, published for gateway fan-out to
+/// clients that are not connected to the media server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomSignalEvent {
+    pub room_id: String,
+    pub sender_user_id: i64,
+    pub signal: RoomSignalKind,
+}
+
+/// A participant started or stopped speaking, published for gateway
+/// fan-out so clients can light speaking indicators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeakingEvent {
+    pub room_id: String,
+    pub user_id: i64,
+    pub speaking: bool,
+}
+
+fn ordered_pair(a: i64, b: i64) -> (i64, i64) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn validate_room_signal(signal: &RoomSignalKind) -> Result<(), RoomSignalError> {
+    match signal {
+        RoomSignalKind::Reaction { emoji } => {
+            let emoji = emoji.trim();
+            if emoji.is_empty() {
+                return Err(RoomSignalError::Invalid("emoji must not be empty"));
+            }
+            if emoji.len() > MAX_REACTION_EMOJI_LEN {
+                return Err(RoomSignalError::Invalid("emoji is too long"));
+            }
+            if emoji.chars().any(char::is_control) {
+                return Err(RoomSignalError::Invalid(
+                    "emoji contains control characters",
+                ));
+            }
+            Ok(())
+        }
+        RoomSignalKind::RaiseHand { .. } => Ok(()),
+    }
+}
+
 /// The relay forwarder manages connections and forwards media packets between
+/// Relay-wide figures for the metrics endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelayMetrics {
+    /// Rooms with at least one connected participant.
+    pub active_rooms: usize,
+    pub participants: usize,
+    /// Packets and bytes delivered to recipients since startup.
+    pub forwarded_packets: u64,
+    pub forwarded_bytes: u64,
+    /// One entry per active room, ordered by room id.
+    pub rooms: Vec<RoomBandwidth>,
+}
+
+/// Bandwidth of one room over the last stats window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoomBandwidth {
+    pub room_id: String,
+    pub participants: usize,
+    /// Sum of the participants' upload bitrates.
+    pub inbound_kbps: u64,
+    /// Sum of the participants' download bitrates.
+    pub outbound_kbps: u64,
+}
+
 /// participants in the same room based on their subscriptions.
 ///
 /// It never inspects or decrypts the encrypted payload -- it only reads the
@@ -117,6 +236,31 @@ pub struct RelayForwarder {
     room_manager: Arc<MediaRoomManager>,
     /// Speaker detector for audio level tracking.
     speaker_detector: Arc<SpeakerDetector>,
+    /// Which speakers' audio is forwarded, by room size.
+    forwarding_policy: SpeakerForwardingPolicy,
+    /// Cached top-speaker sets per room: (computed at, forwarded user ids).
+    speaker_ranks: DashMap<String, (Instant, HashSet<i64>)>,
+    /// Per-participant packet/loss/bitrate accounting.
+    stats: StatsTracker,
+    /// Packets/bytes delivered to recipients, for `metrics`.
+    forwarded_packets: AtomicU64,
+    forwarded_bytes: AtomicU64,
+    /// Direct-path negotiation for two-party rooms.
+    p2p: Arc<P2PCoordinator>,
+    /// Users that reported they can attempt a direct path.
+    p2p_capable: DashMap<i64, ()>,
+    /// Room id -> the peer pair currently negotiating or using a direct path.
+    p2p_pairs: DashMap<String, (i64, i64)>,
+    /// Outbound control-stream queues, keyed by user_id.
+    control_channels: DashMap<i64, mpsc::UnboundedSender<ControlMessage>>,
+    /// Per-user room signal rate-limit windows (window start, count).
+    signal_windows: DashMap<i64, (Instant, u32)>,
+    /// Accepted room signals, for gateway fan-out.
+    signal_events: broadcast::Sender<RoomSignalEvent>,
+    /// Speaking start/stop transitions, for gateway fan-out.
+    speaking_events: broadcast::Sender<SpeakingEvent>,
+    /// Participants that muted themselves; their audio is dropped.
+    muted: DashMap<i64, ()>,
     /// Notify signal for shutdown.
     shutdown: Notify,
 }
@@ -130,23 +274,365 @@ impl RelayForwarder {
             connections: DashMap::new(),
             room_manager,
             speaker_detector,
+            forwarding_policy: SpeakerForwardingPolicy::forward_all(),
+            speaker_ranks: DashMap::new(),
+            stats: StatsTracker::new(),
+            forwarded_packets: AtomicU64::new(0),
+            forwarded_bytes: AtomicU64::new(0),
+            p2p: Arc::new(P2PCoordinator::new()),
+            p2p_capable: DashMap::new(),
+            p2p_pairs: DashMap::new(),
+            control_channels: DashMap::new(),
+            signal_windows: DashMap::new(),
+            signal_events: broadcast::channel(ROOM_SIGNAL_EVENT_CAPACITY).0,
+            speaking_events: broadcast::channel(SPEAKING_EVENT_CAPACITY).0,
+            muted: DashMap::new(),
             shutdown: Notify::new(),
         }
     }
 
+    /// Only forward the loudest speakers' audio in large rooms.
+    pub fn with_forwarding_policy(mut self, policy: SpeakerForwardingPolicy) -> Self {
+        self.forwarding_policy = policy;
+        self
+    }
+
     /// Register a new participant connection for relay forwarding.
     pub fn add_connection(&self, handle: ConnectionHandle) {
         let user_id = handle.user_id;
         let room_id = handle.room_id.clone();
         info!(user_id, room_id = %room_id, "relay: participant connected");
         self.connections.insert(user_id, handle);
+        // A third participant joining downgrades any direct pair to relay.
+        self.evaluate_p2p(&room_id);
+    }
+
+    /// Remove a participant's connection only if it is still `handle`, so a
+    /// session that ends after the participant reconnected leaves the new
+    /// one in place.
+    pub fn remove_connection_if_current(&self, handle: &ConnectionHandle) {
+        let current = self
+            .connections
+            .get(&handle.user_id)
+            .is_some_and(|entry| entry.value().same_transport(handle));
+        if current {
+            self.remove_connection(handle.user_id);
+        }
     }
 
     /// Remove a participant's connection.
     pub fn remove_connection(&self, user_id: i64) {
-        if self.connections.remove(&user_id).is_some() {
+        self.control_channels.remove(&user_id);
+        self.signal_windows.remove(&user_id);
+        self.p2p_capable.remove(&user_id);
+        self.p2p.remove_address(user_id);
+        self.stats.remove(user_id);
+        self.muted.remove(&user_id);
+        let was_speaking = self.speaker_detector.remove_user(user_id);
+        if let Some((_, handle)) = self.connections.remove(&user_id) {
             info!(user_id, "relay: participant disconnected");
+            if was_speaking {
+                self.publish_speaking(&handle.room_id, user_id, false);
+            }
+            // The remaining participants may now be a two-party call.
+            self.evaluate_p2p(&handle.room_id);
+            if !self
+                .connections
+                .iter()
+                .any(|entry| entry.value().room_id == handle.room_id)
+            {
+                self.speaker_ranks.remove(&handle.room_id);
+            }
+        }
+    }
+
+    /// Forwarding statistics for every participant in `room_id`.
+    pub fn room_stats(&self, room_id: &str) -> Vec<ParticipantStatsSnapshot> {
+        self.connections
+            .iter()
+            .filter(|entry| entry.value().room_id == room_id)
+            .filter_map(|entry| self.stats.snapshot(*entry.key()))
+            .collect()
+    }
+
+    /// Forwarding statistics for every connected participant, keyed by room id.
+    pub fn all_room_stats(
+        &self,
+    ) -> std::collections::HashMap<String, Vec<ParticipantStatsSnapshot>> {
+        let mut rooms = self.stats.snapshot_rooms();
+        for stats in rooms.values_mut() {
+            stats.retain(|snapshot| self.connections.contains_key(&snapshot.user_id));
+        }
+        rooms.retain(|_, stats| !stats.is_empty());
+        rooms
+    }
+
+    /// Room, participant and throughput figures across the relay.
+    pub fn metrics(&self) -> RelayMetrics {
+        let mut rooms: BTreeMap<String, RoomBandwidth> = BTreeMap::new();
+        for entry in self.connections.iter() {
+            let room_id = &entry.value().room_id;
+            rooms
+                .entry(room_id.clone())
+                .or_insert_with(|| RoomBandwidth {
+                    room_id: room_id.clone(),
+                    ..RoomBandwidth::default()
+                })
+                .participants += 1;
+        }
+        for (room_id, stats) in self.all_room_stats() {
+            if let Some(room) = rooms.get_mut(&room_id) {
+                room.inbound_kbps = stats.iter().map(|s| u64::from(s.inbound_kbps)).sum();
+                room.outbound_kbps = stats.iter().map(|s| u64::from(s.outbound_kbps)).sum();
+            }
+        }
+        RelayMetrics {
+            active_rooms: rooms.len(),
+            participants: self.connections.len(),
+            forwarded_packets: self.forwarded_packets.load(Ordering::Relaxed),
+            forwarded_bytes: self.forwarded_bytes.load(Ordering::Relaxed),
+            rooms: rooms.into_values().collect(),
+        }
+    }
+
+    /// The direct-path coordinator shared by all rooms.
+    pub fn p2p(&self) -> &Arc<P2PCoordinator> {
+        &self.p2p
+    }
+
+    /// Record whether a participant can attempt a direct peer-to-peer path
+    /// and re-evaluate their room.
+    pub fn set_p2p_capability(&self, user_id: i64, supported: bool) {
+        let Some((room_id, direct_addr)) = self
+            .connections
+            .get(&user_id)
+            .map(|handle| (handle.room_id.clone(), handle.direct_addr))
+        else {
+            return;
+        };
+        match direct_addr.filter(|_| supported) {
+            Some(addr) => {
+                self.p2p_capable.insert(user_id, ());
+                self.p2p.register_address(user_id, addr);
+            }
+            None => {
+                self.p2p_capable.remove(&user_id);
+                self.p2p.remove_address(user_id);
+            }
+        }
+        self.evaluate_p2p(&room_id);
+    }
+
+    /// Apply a participant's report on a hole-punch attempt. A failure on
+    /// either side sends both peers back to the relay.
+    pub fn handle_p2p_result(&self, user_id: i64, peer_user_id: i64, established: bool) {
+        let Some(room_id) = self
+            .connections
+            .get(&user_id)
+            .map(|handle| handle.room_id.clone())
+        else {
+            return;
+        };
+        let pair = self.p2p_pairs.get(&room_id).map(|pair| *pair);
+        if pair != Some(ordered_pair(user_id, peer_user_id)) {
+            return;
+        }
+        if established {
+            // Late success after the timeout already fell back is ignored.
+            if self.p2p.get_status(user_id, peer_user_id) == Some(P2PStatus::Attempting) {
+                self.p2p.mark_established(user_id, peer_user_id);
+            }
+        } else if self.p2p.get_status(user_id, peer_user_id) != Some(P2PStatus::FailedUsingRelay) {
+            self.p2p.mark_failed(user_id, peer_user_id);
+            self.send_control(user_id, ControlMessage::P2PFallback { peer_user_id });
+            self.send_control(
+                peer_user_id,
+                ControlMessage::P2PFallback {
+                    peer_user_id: user_id,
+                },
+            );
+        }
+    }
+
+    /// Upgrade a room to a direct path when it has exactly two P2P-capable
+    /// participants, and downgrade any existing pair otherwise.
+    fn evaluate_p2p(&self, room_id: &str) {
+        let members: Vec<i64> = self
+            .connections
+            .iter()
+            .filter(|entry| entry.value().room_id == room_id)
+            .map(|entry| *entry.key())
+            .collect();
+        let eligible = match members.as_slice() {
+            [a, b] if self.p2p_capable.contains_key(a) && self.p2p_capable.contains_key(b) => {
+                Some(ordered_pair(*a, *b))
+            }
+            _ => None,
+        };
+        let current = self.p2p_pairs.get(room_id).map(|pair| *pair);
+        if current == eligible {
+            return;
+        }
+
+        if let Some((a, b)) = current {
+            self.p2p_pairs.remove(room_id);
+            self.p2p.reset_pair(a, b);
+            self.send_control(a, ControlMessage::P2PFallback { peer_user_id: b });
+            self.send_control(b, ControlMessage::P2PFallback { peer_user_id: a });
+            info!(
+                room_id,
+                user_a = a,
+                user_b = b,
+                "relay: direct path downgraded"
+            );
+        }
+
+        let Some((a, b)) = eligible else {
+            return;
+        };
+        let (Some(addr_a), Some(addr_b)) = (self.p2p.get_address(a), self.p2p.get_address(b))
+        else {
+            return;
+        };
+        self.p2p.initiate_p2p(a, b);
+        self.p2p.spawn_timeout(a, b);
+        self.p2p_pairs.insert(room_id.to_string(), (a, b));
+        self.send_control(
+            a,
+            ControlMessage::P2PCandidate {
+                peer_user_id: b,
+                addr: addr_b.to_string(),
+            },
+        );
+        self.send_control(
+            b,
+            ControlMessage::P2PCandidate {
+                peer_user_id: a,
+                addr: addr_a.to_string(),
+            },
+        );
+        info!(
+            room_id,
+            user_a = a,
+            user_b = b,
+            "relay: direct path offered"
+        );
+    }
+
+    /// Queue a control message for a participant. Returns `false` if the
+    /// participant has no open control stream.
+    fn send_control(&self, user_id: i64, message: ControlMessage) -> bool {
+        self.control_channels
+            .get(&user_id)
+            .is_some_and(|tx| tx.send(message).is_ok())
+    }
+
+    /// Attach the outbound control-stream queue for a connected participant.
+    /// Messages pushed here are written to the participant's control stream
+    /// by the transport-specific writer task.
+    pub fn attach_control_channel(&self, user_id: i64, tx: mpsc::UnboundedSender<ControlMessage>) {
+        self.control_channels.insert(user_id, tx);
+    }
+
+    /// Subscribe to room signals accepted by the relay.
+    pub fn subscribe_room_signals(&self) -> broadcast::Receiver<RoomSignalEvent> {
+        self.signal_events.subscribe()
+    }
+
+    /// Subscribe to speaking start/stop transitions.
+    pub fn subscribe_speaking(&self) -> broadcast::Receiver<SpeakingEvent> {
+        self.speaking_events.subscribe()
+    }
+
+    fn publish_speaking(&self, room_id: &str, user_id: i64, speaking: bool) {
+        // No receivers just means nothing is listening for gateway fan-out.
+        let _ = self.speaking_events.send(SpeakingEvent {
+            room_id: room_id.to_string(),
+            user_id,
+            speaking,
+        });
+    }
+
+    /// Record a participant's self-mute. Audio from muted participants is
+    /// not forwarded, and muting ends any speaking indicator at once.
+    pub fn set_muted(&self, user_id: i64, muted: bool) {
+        if !muted {
+            self.muted.remove(&user_id);
+            return;
+        }
+        self.muted.insert(user_id, ());
+        let room_id = self
+            .connections
+            .get(&user_id)
+            .map(|handle| handle.room_id.clone());
+        if self.speaker_detector.remove_user(user_id) {
+            if let Some(room_id) = room_id {
+                self.publish_speaking(&room_id, user_id, false);
+            }
+        }
+    }
+
+    pub fn is_muted(&self, user_id: i64) -> bool {
+        self.muted.contains_key(&user_id)
+    }
+
+    /// Validate a room signal from `sender_id` and relay it to every other
+    /// participant in the sender's room over their control streams.
+    ///
+    /// Returns the number of participants the signal was delivered to.
+    pub fn broadcast_room_signal(
+        &self,
+        sender_id: i64,
+        signal: RoomSignalKind,
+    ) -> Result<usize, RoomSignalError> {
+        let room_id = self
+            .connections
+            .get(&sender_id)
+            .map(|handle| handle.room_id.clone())
+            .ok_or(RoomSignalError::NotConnected(sender_id))?;
+        validate_room_signal(&signal)?;
+        self.check_signal_rate(sender_id)?;
+
+        let message = ControlMessage::RoomSignal {
+            sender_user_id: sender_id,
+            signal: signal.clone(),
+        };
+        let mut delivered = 0usize;
+        for entry in self.connections.iter() {
+            let handle = entry.value();
+            if handle.user_id == sender_id || handle.room_id != room_id {
+                continue;
+            }
+            if self.send_control(handle.user_id, message.clone()) {
+                delivered += 1;
+            }
         }
+
+        // No receivers just means nothing is listening for gateway fan-out.
+        let _ = self.signal_events.send(RoomSignalEvent {
+            room_id,
+            sender_user_id: sender_id,
+            signal,
+        });
+        debug!(
+            sender = sender_id,
+            recipients = delivered,
+            "relay: room signal"
+        );
+        Ok(delivered)
+    }
+
+    fn check_signal_rate(&self, user_id: i64) -> Result<(), RoomSignalError> {
+        let now = Instant::now();
+        let mut window = self.signal_windows.entry(user_id).or_insert((now, 0));
+        if now.duration_since(window.0) >= ROOM_SIGNAL_WINDOW {
+            *window = (now, 0);
+        }
+        if window.1 >= ROOM_SIGNAL_BURST {
+            return Err(RoomSignalError::RateLimited);
+        }
+        window.1 += 1;
+        Ok(())
     }
 
     /// Spawn the forwarding loop for a single participant.
@@ -195,30 +681,54 @@ impl RelayForwarder {
                     }
                 };
 
-                // Feed audio level to speaker detector
-                forwarder.speaker_detector.report_audio_level(
-                    user_id,
-                    &room_id,
-                    header.audio_level,
-                );
+                forwarder
+                    .stats
+                    .record_inbound(user_id, &room_id, &header, datagram.len());
+
+                if header.track_type == TrackType::Audio {
+                    if forwarder.is_muted(user_id) {
+                        continue;
+                    }
+                    // Video packets carry no level, so only audio feeds
+                    // the speaker detector.
+                    if let Some(speaking) = forwarder.speaker_detector.report_audio_level(
+                        user_id,
+                        &room_id,
+                        header.audio_level,
+                    ) {
+                        forwarder.publish_speaking(&room_id, user_id, speaking);
+                    }
+                }
 
                 // Look up the sender's room and find subscribers
-                forwarder.forward_to_subscribers(user_id, &room_id, &datagram);
+                forwarder.forward_to_subscribers(user_id, &room_id, header.track_type, &datagram);
             }
 
             // Clean up on disconnect
-            forwarder.remove_connection(user_id);
+            forwarder.remove_connection_if_current(&handle);
             info!(user_id, room_id = %room_id, "relay: forwarding task ended");
         });
     }
 
     /// Forward a complete packet (header + encrypted payload) to all subscribers.
-    fn forward_to_subscribers(&self, sender_id: i64, room_id: &str, packet: &Bytes) {
+    fn forward_to_subscribers(
+        &self,
+        sender_id: i64,
+        room_id: &str,
+        track_type: TrackType,
+        packet: &Bytes,
+    ) {
         let room = match self.room_manager.get_room(room_id) {
             Some(r) => r,
             None => return,
         };
 
+        if track_type == TrackType::Audio
+            && !self.is_forwarded_speaker(sender_id, room_id, &room.user_ids())
+        {
+            return;
+        }
+
         // Find all participants subscribed to this sender
         let mut forward_count = 0u32;
         for participant in room.participants.values() {
@@ -231,6 +741,10 @@ impl RelayForwarder {
             if !participant.subscriptions.contains(&sender_id) {
                 continue;
             }
+            // Media already flows over the direct path.
+            if self.p2p.get_status(sender_id, participant.user_id) == Some(P2PStatus::Established) {
+                continue;
+            }
 
             // Look up the recipient's connection handle
             if let Some(recipient_conn) = self.connections.get(&participant.user_id) {
@@ -241,6 +755,12 @@ impl RelayForwarder {
                         error = %e,
                         "relay: failed to forward datagram"
                     );
+                } else {
+                    self.stats
+                        .record_outbound(participant.user_id, room_id, packet.len());
+                    self.forwarded_packets.fetch_add(1, Ordering::Relaxed);
+                    self.forwarded_bytes
+                        .fetch_add(packet.len() as u64, Ordering::Relaxed);
                 }
                 forward_count += 1;
             }
@@ -255,11 +775,43 @@ impl RelayForwarder {
         }
     }
 
+    /// Whether `sender_id`'s audio is among the top speakers forwarded in
+    /// `room_id` under the current policy. The ranking is cached briefly so
+    /// it is not recomputed for every packet.
+    fn is_forwarded_speaker(&self, sender_id: i64, room_id: &str, room_user_ids: &[i64]) -> bool {
+        let Some(limit) = self.forwarding_policy.speaker_limit(room_user_ids.len()) else {
+            return true;
+        };
+        let now = Instant::now();
+        if let Some(cached) = self.speaker_ranks.get(room_id) {
+            if now.duration_since(cached.0) < SPEAKER_RANK_REFRESH {
+                return cached.1.contains(&sender_id);
+            }
+        }
+        let top: HashSet<i64> = self
+            .speaker_detector
+            .top_speakers(room_user_ids, limit)
+            .into_iter()
+            .collect();
+        let forwarded = top.contains(&sender_id);
+        self.speaker_ranks.insert(room_id.to_string(), (now, top));
+        forwarded
+    }
+
     /// Signal shutdown to all forwarding tasks.
     pub fn shutdown(&self) {
         self.shutdown.notify_waiters();
     }
 
+    /// Users currently connected to `room_id`.
+    pub fn room_user_ids(&self, room_id: &str) -> Vec<i64> {
+        self.connections
+            .iter()
+            .filter(|entry| entry.value().room_id == room_id)
+            .map(|entry| *entry.key())
+            .collect()
+    }
+
     /// Get the number of active connections.
     pub fn connection_count(&self) -> usize {
         self.connections.len()
@@ -278,4 +830,352 @@ mod tests {
         let forwarder = RelayForwarder::new(Arc::new(mgr), Arc::new(SpeakerDetector::new()));
         assert_eq!(forwarder.connection_count(), 0);
     }
+
+    fn bridged_handle(user_id: i64, room_id: &str) -> ConnectionHandle {
+        let (outbound_tx, _outbound_rx) = mpsc::unbounded_channel();
+        let (_inbound_tx, inbound_rx) = mpsc::unbounded_channel();
+        ConnectionHandle::new_bridged(user_id, room_id.to_string(), outbound_tx, inbound_rx)
+    }
+
+    fn forwarder_with(
+        users: &[(i64, &str)],
+    ) -> (RelayForwarder, Vec<mpsc::UnboundedReceiver<ControlMessage>>) {
+        let forwarder = RelayForwarder::new(
+            Arc::new(MediaRoomManager::new()),
+            Arc::new(SpeakerDetector::new()),
+        );
+        let mut receivers = Vec::new();
+        for (user_id, room_id) in users {
+            forwarder.add_connection(bridged_handle(*user_id, room_id));
+            let (tx, rx) = mpsc::unbounded_channel();
+            forwarder.attach_control_channel(*user_id, tx);
+            receivers.push(rx);
+        }
+        (forwarder, receivers)
+    }
+
+    #[test]
+    fn metrics_group_connections_by_room() {
+        let (forwarder, _receivers) = forwarder_with(&[(1, "1:10"), (2, "1:10"), (3, "1:11")]);
+        forwarder.stats.record_outbound(2, "1:10", 1_000);
+        let metrics = forwarder.metrics();
+        assert_eq!(metrics.active_rooms, 2);
+        assert_eq!(metrics.participants, 3);
+        assert_eq!(
+            metrics
+                .rooms
+                .iter()
+                .map(|room| (room.room_id.as_str(), room.participants))
+                .collect::<Vec<_>>(),
+            vec![("1:10", 2), ("1:11", 1)]
+        );
+
+        forwarder.remove_connection(3);
+        assert_eq!(forwarder.metrics().active_rooms, 1);
+    }
+
+    #[test]
+    fn ended_session_does_not_remove_its_replacement() {
+        let forwarder = RelayForwarder::new(
+            Arc::new(MediaRoomManager::new()),
+            Arc::new(SpeakerDetector::new()),
+        );
+        let old = bridged_handle(1, "1:10");
+        let new = bridged_handle(1, "1:10");
+        forwarder.add_connection(old.clone());
+        forwarder.add_connection(new.clone());
+
+        forwarder.remove_connection_if_current(&old);
+        assert_eq!(forwarder.connection_count(), 1);
+        forwarder.remove_connection_if_current(&new);
+        assert_eq!(forwarder.connection_count(), 0);
+    }
+
+    #[test]
+    fn room_signal_reaches_only_same_room_peers() {
+        let (forwarder, mut receivers) = forwarder_with(&[(1, "1:10"), (2, "1:10"), (3, "1:11")]);
+        let mut events = forwarder.subscribe_room_signals();
+        let signal = RoomSignalKind::Reaction {
+            emoji: "\u{1f389}".to_string(),
+        };
+
+        assert_eq!(forwarder.broadcast_room_signal(1, signal.clone()), Ok(1));
+        assert!(receivers[0].try_recv().is_err());
+        assert_eq!(
+            receivers[1].try_recv().unwrap(),
+            ControlMessage::RoomSignal {
+                sender_user_id: 1,
+                signal: signal.clone(),
+            }
+        );
+        assert!(receivers[2].try_recv().is_err());
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.room_id, "1:10");
+        assert_eq!(event.sender_user_id, 1);
+        assert_eq!(event.signal, signal);
+    }
+
+    #[test]
+    fn muting_or_leaving_ends_speaking() {
+        let (forwarder, _receivers) = forwarder_with(&[(1, "1:10"), (2, "1:10")]);
+        let mut events = forwarder.subscribe_speaking();
+        for user_id in [1, 2] {
+            assert_eq!(
+                forwarder
+                    .speaker_detector
+                    .report_audio_level(user_id, "1:10", 10),
+                Some(true)
+            );
+        }
+
+        forwarder.set_muted(1, true);
+        assert!(forwarder.is_muted(1));
+        forwarder.remove_connection(2);
+        let ended: Vec<_> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert_eq!(
+            ended,
+            vec![
+                SpeakingEvent {
+                    room_id: "1:10".to_string(),
+                    user_id: 1,
+                    speaking: false,
+                },
+                SpeakingEvent {
+                    room_id: "1:10".to_string(),
+                    user_id: 2,
+                    speaking: false,
+                },
+            ]
+        );
+
+        // Muting someone who is already quiet publishes nothing.
+        forwarder.set_muted(1, false);
+        forwarder.set_muted(1, true);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn room_signal_rejects_invalid_and_unknown_senders() {
+        let (forwarder, _receivers) = forwarder_with(&[(1, "1:10")]);
+        assert_eq!(
+            forwarder.broadcast_room_signal(9, RoomSignalKind::RaiseHand { raised: true }),
+            Err(RoomSignalError::NotConnected(9))
+        );
+        assert!(matches!(
+            forwarder.broadcast_room_signal(
+                1,
+                RoomSignalKind::Reaction {
+                    emoji: "  ".to_string()
+                }
+            ),
+            Err(RoomSignalError::Invalid(_))
+        ));
+        assert!(matches!(
+            forwarder.broadcast_room_signal(
+                1,
+                RoomSignalKind::Reaction {
+                    emoji: "x".repeat(MAX_REACTION_EMOJI_LEN + 1)
+                }
+            ),
+            Err(RoomSignalError::Invalid(_))
+        ));
+    }
+
+    fn direct_handle(user_id: i64, room_id: &str, port: u16) -> ConnectionHandle {
+        let mut handle = bridged_handle(user_id, room_id);
+        handle.direct_addr = Some(SocketAddr::from(([203, 0, 113, user_id as u8], port)));
+        handle
+    }
+
+    fn p2p_forwarder() -> (
+        RelayForwarder,
+        std::collections::HashMap<i64, mpsc::UnboundedReceiver<ControlMessage>>,
+    ) {
+        let forwarder = RelayForwarder::new(
+            Arc::new(MediaRoomManager::new()),
+            Arc::new(SpeakerDetector::new()),
+        );
+        let mut receivers = std::collections::HashMap::new();
+        for user_id in 1..=3 {
+            let (tx, rx) = mpsc::unbounded_channel();
+            forwarder.attach_control_channel(user_id, tx);
+            receivers.insert(user_id, rx);
+        }
+        (forwarder, receivers)
+    }
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<ControlMessage>) -> Vec<ControlMessage> {
+        let mut out = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            out.push(msg);
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn two_capable_peers_are_offered_direct_path() {
+        let (forwarder, mut rx) = p2p_forwarder();
+        forwarder.add_connection(direct_handle(1, "1:10", 5001));
+        forwarder.add_connection(direct_handle(2, "1:10", 5002));
+        forwarder.set_p2p_capability(1, true);
+        assert!(drain(rx.get_mut(&1).unwrap()).is_empty());
+
+        forwarder.set_p2p_capability(2, true);
+        assert_eq!(
+            drain(rx.get_mut(&1).unwrap()),
+            vec![ControlMessage::P2PCandidate {
+                peer_user_id: 2,
+                addr: "203.0.113.2:5002".to_string(),
+            }]
+        );
+        assert_eq!(
+            drain(rx.get_mut(&2).unwrap()),
+            vec![ControlMessage::P2PCandidate {
+                peer_user_id: 1,
+                addr: "203.0.113.1:5001".to_string(),
+            }]
+        );
+
+        forwarder.handle_p2p_result(2, 1, true);
+        assert_eq!(
+            forwarder.p2p().get_status(1, 2),
+            Some(P2PStatus::Established)
+        );
+    }
+
+    #[tokio::test]
+    async fn bridged_peers_stay_on_relay() {
+        let (forwarder, mut rx) = p2p_forwarder();
+        forwarder.add_connection(direct_handle(1, "1:10", 5001));
+        forwarder.add_connection(bridged_handle(2, "1:10"));
+        forwarder.set_p2p_capability(1, true);
+        forwarder.set_p2p_capability(2, true);
+        assert!(drain(rx.get_mut(&1).unwrap()).is_empty());
+        assert_eq!(forwarder.p2p().get_status(1, 2), None);
+    }
+
+    #[tokio::test]
+    async fn third_participant_downgrades_and_leave_upgrades() {
+        let (forwarder, mut rx) = p2p_forwarder();
+        for user_id in 1..=2 {
+            forwarder.add_connection(direct_handle(user_id, "1:10", 5000 + user_id as u16));
+            forwarder.set_p2p_capability(user_id, true);
+        }
+        forwarder.handle_p2p_result(1, 2, true);
+        drain(rx.get_mut(&1).unwrap());
+        drain(rx.get_mut(&2).unwrap());
+
+        forwarder.add_connection(direct_handle(3, "1:10", 5003));
+        assert_eq!(
+            drain(rx.get_mut(&1).unwrap()),
+            vec![ControlMessage::P2PFallback { peer_user_id: 2 }]
+        );
+        assert_eq!(forwarder.p2p().get_status(1, 2), None);
+
+        forwarder.set_p2p_capability(3, true);
+        forwarder.remove_connection(2);
+        assert_eq!(
+            drain(rx.get_mut(&3).unwrap()),
+            vec![ControlMessage::P2PCandidate {
+                peer_user_id: 1,
+                addr: "203.0.113.1:5001".to_string(),
+            }]
+        );
+        assert_eq!(
+            forwarder.p2p().get_status(1, 3),
+            Some(P2PStatus::Attempting)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_attempt_falls_back_without_retrying() {
+        let (forwarder, mut rx) = p2p_forwarder();
+        for user_id in 1..=2 {
+            forwarder.add_connection(direct_handle(user_id, "1:10", 5000 + user_id as u16));
+            forwarder.set_p2p_capability(user_id, true);
+        }
+        drain(rx.get_mut(&2).unwrap());
+
+        forwarder.handle_p2p_result(1, 2, false);
+        assert_eq!(
+            drain(rx.get_mut(&2).unwrap()),
+            vec![ControlMessage::P2PFallback { peer_user_id: 1 }]
+        );
+        assert_eq!(
+            forwarder.p2p().get_status(1, 2),
+            Some(P2PStatus::FailedUsingRelay)
+        );
+
+        // A late success report does not resurrect the pair.
+        forwarder.handle_p2p_result(2, 1, true);
+        forwarder.set_p2p_capability(1, true);
+        assert_eq!(
+            forwarder.p2p().get_status(1, 2),
+            Some(P2PStatus::FailedUsingRelay)
+        );
+        assert!(drain(rx.get_mut(&2).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn large_rooms_forward_only_top_speakers() {
+        use crate::participant::MediaParticipant;
+        use crate::speaker::SpeakerForwardingTier;
+
+        let rooms = Arc::new(MediaRoomManager::new());
+        let speakers = Arc::new(SpeakerDetector::new());
+        let forwarder = RelayForwarder::new(Arc::clone(&rooms), Arc::clone(&speakers))
+            .with_forwarding_policy(SpeakerForwardingPolicy::new(vec![SpeakerForwardingTier {
+                min_participants: 4,
+                max_speakers: 1,
+            }]));
+        let room_id = rooms.get_or_create_room(1, 10);
+        let mut outbound = std::collections::HashMap::new();
+        for user_id in 1..=4 {
+            rooms
+                .join_room(1, 10, MediaParticipant::new(user_id, format!("s{user_id}")))
+                .unwrap();
+            let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+            let (_inbound_tx, inbound_rx) = mpsc::unbounded_channel();
+            forwarder.add_connection(ConnectionHandle::new_bridged(
+                user_id,
+                room_id.clone(),
+                outbound_tx,
+                inbound_rx,
+            ));
+            outbound.insert(user_id, outbound_rx);
+        }
+        for (user_id, level) in [(1, 20), (2, 80), (3, 127), (4, 127)] {
+            for _ in 0..5 {
+                speakers.report_audio_level(user_id, &room_id, level);
+            }
+        }
+
+        let packet = Bytes::from_static(b"audio");
+        forwarder.forward_to_subscribers(2, &room_id, TrackType::Audio, &packet);
+        assert!(outbound.get_mut(&1).unwrap().try_recv().is_err());
+
+        forwarder.forward_to_subscribers(1, &room_id, TrackType::Audio, &packet);
+        for user_id in 2..=4 {
+            assert!(outbound.get_mut(&user_id).unwrap().try_recv().is_ok());
+        }
+
+        // Video is never filtered by speaker rank.
+        forwarder.forward_to_subscribers(2, &room_id, TrackType::Video, &packet);
+        assert!(outbound.get_mut(&1).unwrap().try_recv().is_ok());
+    }
+
+    #[test]
+    fn room_signal_is_rate_limited() {
+        let (forwarder, _receivers) = forwarder_with(&[(1, "1:10"), (2, "1:10")]);
+        for _ in 0..ROOM_SIGNAL_BURST {
+            assert!(forwarder
+                .broadcast_room_signal(1, RoomSignalKind::RaiseHand { raised: true })
+                .is_ok());
+        }
+        assert_eq!(
+            forwarder.broadcast_room_signal(1, RoomSignalKind::RaiseHand { raised: false }),
+            Err(RoomSignalError::RateLimited)
+        );
+    }
 }
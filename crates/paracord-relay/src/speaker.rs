@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 
@@ -13,11 +14,58 @@ const WINDOW_SIZE: usize = 5;
 /// Values below this threshold indicate speech activity.
 const SPEAKING_THRESHOLD: u8 = 100;
 
+/// How long the window must stay quiet before a speaker is reported as
+/// stopped, so pauses between words don't toggle the indicator.
+const SPEAKING_HOLD: Duration = Duration::from_millis(400);
+
+/// Selective forwarding threshold: rooms with at least `min_participants`
+/// forward audio from at most `max_speakers` of the loudest participants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeakerForwardingTier {
+    pub min_participants: usize,
+    pub max_speakers: usize,
+}
+
+/// SFU audio policy keyed by room size. With no tiers every participant's
+/// audio is forwarded (full mesh through the relay).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpeakerForwardingPolicy {
+    tiers: Vec<SpeakerForwardingTier>,
+}
+
+impl SpeakerForwardingPolicy {
+    pub fn new(mut tiers: Vec<SpeakerForwardingTier>) -> Self {
+        tiers.retain(|tier| tier.max_speakers > 0);
+        tiers.sort_by_key(|tier| tier.min_participants);
+        Self { tiers }
+    }
+
+    /// Forward every participant's audio regardless of room size.
+    pub fn forward_all() -> Self {
+        Self::default()
+    }
+
+    /// Number of speakers to forward in a room of `room_size`, or `None` if
+    /// all audio should be forwarded.
+    pub fn speaker_limit(&self, room_size: usize) -> Option<usize> {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| room_size >= tier.min_participants)
+            .map(|tier| tier.max_speakers)
+            .filter(|&limit| limit < room_size)
+    }
+}
+
 /// Per-user audio level history for sliding window averaging.
 #[allow(dead_code)]
 struct AudioLevelHistory {
     levels: VecDeque<u8>,
     room_id: String,
+    /// Debounced speaking state last reported to listeners.
+    speaking: bool,
+    /// When the window last averaged as speech.
+    last_loud: Option<Instant>,
 }
 
 impl AudioLevelHistory {
@@ -25,9 +73,27 @@ impl AudioLevelHistory {
         Self {
             levels: VecDeque::with_capacity(WINDOW_SIZE),
             room_id,
+            speaking: false,
+            last_loud: None,
         }
     }
 
+    /// Update the debounced speaking state after a sample. Returns the new
+    /// state when it changed.
+    fn settle(&mut self, now: Instant) -> Option<bool> {
+        if self.is_speaking() {
+            self.last_loud = Some(now);
+        }
+        let speaking = self
+            .last_loud
+            .is_some_and(|at| now.duration_since(at) < SPEAKING_HOLD);
+        if speaking == self.speaking {
+            return None;
+        }
+        self.speaking = speaking;
+        Some(speaking)
+    }
+
     /// Add a new audio level sample and return the current average.
     fn push(&mut self, level: u8) -> u8 {
         if self.levels.len() >= WINDOW_SIZE {
@@ -66,18 +132,34 @@ impl SpeakerDetector {
         }
     }
 
-    /// Report an audio level for a user.
-    pub fn report_audio_level(&self, user_id: i64, room_id: &str, level: u8) {
+    /// Report an audio level for a user. Returns `Some(speaking)` when the
+    /// user starts speaking or has been quiet long enough to count as
+    /// stopped.
+    pub fn report_audio_level(&self, user_id: i64, room_id: &str, level: u8) -> Option<bool> {
+        self.report_audio_level_at(user_id, room_id, level, Instant::now())
+    }
+
+    fn report_audio_level_at(
+        &self,
+        user_id: i64,
+        room_id: &str,
+        level: u8,
+        now: Instant,
+    ) -> Option<bool> {
         let mut entry = self
             .histories
             .entry(user_id)
             .or_insert_with(|| AudioLevelHistory::new(room_id.to_string()));
         entry.push(level);
+        entry.settle(now)
     }
 
-    /// Remove tracking for a user (on disconnect).
-    pub fn remove_user(&self, user_id: i64) {
-        self.histories.remove(&user_id);
+    /// Remove tracking for a user (on disconnect). Returns whether they
+    /// were last reported as speaking.
+    pub fn remove_user(&self, user_id: i64) -> bool {
+        self.histories
+            .remove(&user_id)
+            .is_some_and(|(_, history)| history.speaking)
     }
 
     /// Get the current speaker update for a room.
@@ -96,6 +178,18 @@ impl SpeakerDetector {
         SpeakerUpdate { speakers }
     }
 
+    /// Rank room members by current audio level (loudest first) and return
+    /// the top `limit` user IDs. Users with no audio history are skipped.
+    pub fn top_speakers(&self, room_user_ids: &[i64], limit: usize) -> Vec<i64> {
+        let mut ranked: Vec<(u8, i64)> = room_user_ids
+            .iter()
+            .filter_map(|&uid| self.histories.get(&uid).map(|hist| (hist.average(), uid)))
+            .collect();
+        // dBov: lower is louder. Ties break on user id for stable selection.
+        ranked.sort_unstable();
+        ranked.into_iter().take(limit).map(|(_, uid)| uid).collect()
+    }
+
     /// Check if a specific user is currently speaking.
     pub fn is_speaking(&self, user_id: i64) -> bool {
         self.histories
@@ -192,6 +286,74 @@ mod tests {
         assert!(!detector.is_speaking(1));
     }
 
+    #[test]
+    fn speaking_transitions_are_debounced() {
+        let detector = SpeakerDetector::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        assert_eq!(detector.report_audio_level_at(1, "room1", 127, at(0)), None);
+        assert_eq!(
+            detector.report_audio_level_at(1, "room1", 10, at(20)),
+            Some(true)
+        );
+        // A short pause keeps the speaker lit.
+        for ms in [40, 60, 80, 100, 120] {
+            assert_eq!(
+                detector.report_audio_level_at(1, "room1", 127, at(ms)),
+                None
+            );
+        }
+        assert_eq!(
+            detector.report_audio_level_at(1, "room1", 127, at(500)),
+            Some(false)
+        );
+        assert_eq!(
+            detector.report_audio_level_at(1, "room1", 127, at(520)),
+            None
+        );
+
+        for ms in [600, 620, 640] {
+            detector.report_audio_level_at(1, "room1", 10, at(ms));
+        }
+        assert!(detector.remove_user(1));
+        assert!(!detector.remove_user(1));
+    }
+
+    #[test]
+    fn top_speakers_ranks_loudest_first() {
+        let detector = SpeakerDetector::new();
+        for (uid, level) in [(1, 90), (2, 20), (3, 127), (4, 50)] {
+            for _ in 0..5 {
+                detector.report_audio_level(uid, "room1", level);
+            }
+        }
+        assert_eq!(detector.top_speakers(&[1, 2, 3, 4, 5], 2), vec![2, 4]);
+        assert_eq!(detector.top_speakers(&[1, 3], 5), vec![1, 3]);
+    }
+
+    #[test]
+    fn forwarding_policy_picks_tier_by_room_size() {
+        let policy = SpeakerForwardingPolicy::new(vec![
+            SpeakerForwardingTier {
+                min_participants: 25,
+                max_speakers: 3,
+            },
+            SpeakerForwardingTier {
+                min_participants: 8,
+                max_speakers: 5,
+            },
+        ]);
+        assert_eq!(policy.speaker_limit(2), None);
+        assert_eq!(policy.speaker_limit(8), Some(5));
+        assert_eq!(policy.speaker_limit(24), Some(5));
+        assert_eq!(policy.speaker_limit(50), Some(3));
+        assert_eq!(
+            SpeakerForwardingPolicy::forward_all().speaker_limit(50),
+            None
+        );
+    }
+
     #[test]
     fn unknown_user_defaults() {
         let detector = SpeakerDetector::new();
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde_json::{json, Value};
+
+use paracord_transport::protocol::{MediaHeader, TrackType};
+
+/// RTP-style media clock rates used to interpret `MediaHeader::timestamp`.
+const AUDIO_CLOCK_RATE: f64 = 48_000.0;
+const VIDEO_CLOCK_RATE: f64 = 90_000.0;
+
+/// Window over which inbound/outbound bitrates are estimated.
+const BITRATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Sequence/jitter state for a single inbound stream (one SSRC).
+struct StreamState {
+    clock_rate: f64,
+    base_seq: u32,
+    max_seq: u16,
+    cycles: u32,
+    received: u64,
+    /// Interarrival jitter in clock units (RFC 3550 §6.4.1 estimator).
+    jitter: f64,
+    last_transit: Option<f64>,
+}
+
+impl StreamState {
+    fn new(header: &MediaHeader) -> Self {
+        let clock_rate = match header.track_type {
+            TrackType::Audio => AUDIO_CLOCK_RATE,
+            TrackType::Video => VIDEO_CLOCK_RATE,
+        };
+        Self {
+            clock_rate,
+            base_seq: header.sequence as u32,
+            max_seq: header.sequence,
+            cycles: 0,
+            received: 0,
+            jitter: 0.0,
+            last_transit: None,
+        }
+    }
+
+    fn update(&mut self, header: &MediaHeader, arrival_secs: f64) {
+        if self.received > 0 {
+            let delta = header.sequence.wrapping_sub(self.max_seq) as i16;
+            if delta > 0 {
+                if header.sequence < self.max_seq {
+                    self.cycles += 1 << 16;
+                }
+                self.max_seq = header.sequence;
+            }
+        }
+        self.received += 1;
+
+        let transit = arrival_secs * self.clock_rate - header.timestamp as f64;
+        if let Some(last) = self.last_transit {
+            let d = (transit - last).abs();
+            self.jitter += (d - self.jitter) / 16.0;
+        }
+        self.last_transit = Some(transit);
+    }
+
+    fn expected(&self) -> u64 {
+        let extended_max = self.cycles as u64 + self.max_seq as u64;
+        (extended_max + 1).saturating_sub(self.base_seq as u64)
+    }
+
+    fn lost(&self) -> u64 {
+        self.expected().saturating_sub(self.received)
+    }
+
+    fn jitter_ms(&self) -> f64 {
+        self.jitter / self.clock_rate * 1000.0
+    }
+}
+
+/// Byte counter that produces a bitrate estimate once per window.
+struct RateWindow {
+    started: Instant,
+    bytes: u64,
+    last_kbps: u32,
+}
+
+impl RateWindow {
+    fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            bytes: 0,
+            last_kbps: 0,
+        }
+    }
+
+    fn record(&mut self, bytes: usize, now: Instant) {
+        self.roll(now);
+        self.bytes += bytes as u64;
+    }
+
+    fn roll(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.started);
+        if elapsed >= BITRATE_WINDOW {
+            self.last_kbps = ((self.bytes * 8) as f64 / elapsed.as_secs_f64() / 1000.0) as u32;
+            self.started = now;
+            self.bytes = 0;
+        }
+    }
+}
+
+struct ParticipantStats {
+    room_id: String,
+    epoch: Instant,
+    packets_in: u64,
+    bytes_in: u64,
+    packets_out: u64,
+    bytes_out: u64,
+    streams: HashMap<u32, StreamState>,
+    inbound_rate: RateWindow,
+    outbound_rate: RateWindow,
+}
+
+impl ParticipantStats {
+    fn new(room_id: &str, now: Instant) -> Self {
+        Self {
+            room_id: room_id.to_string(),
+            epoch: now,
+            packets_in: 0,
+            bytes_in: 0,
+            packets_out: 0,
+            bytes_out: 0,
+            streams: HashMap::new(),
+            inbound_rate: RateWindow::new(now),
+            outbound_rate: RateWindow::new(now),
+        }
+    }
+
+    fn snapshot(&mut self, user_id: i64, now: Instant) -> ParticipantStatsSnapshot {
+        self.inbound_rate.roll(now);
+        self.outbound_rate.roll(now);
+        let expected: u64 = self.streams.values().map(StreamState::expected).sum();
+        let lost: u64 = self.streams.values().map(StreamState::lost).sum();
+        let jitter_ms = self
+            .streams
+            .values()
+            .map(StreamState::jitter_ms)
+            .fold(0.0, f64::max);
+        ParticipantStatsSnapshot {
+            user_id,
+            room_id: self.room_id.clone(),
+            packets_in: self.packets_in,
+            bytes_in: self.bytes_in,
+            packets_out: self.packets_out,
+            bytes_out: self.bytes_out,
+            packets_lost: lost,
+            loss_pct: if expected == 0 {
+                0.0
+            } else {
+                (lost as f64 / expected as f64 * 100.0) as f32
+            },
+            jitter_ms: jitter_ms as f32,
+            inbound_kbps: self.inbound_rate.last_kbps,
+            outbound_kbps: self.outbound_rate.last_kbps,
+        }
+    }
+}
+
+/// Point-in-time forwarding statistics for one participant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticipantStatsSnapshot {
+    pub user_id: i64,
+    pub room_id: String,
+    /// Packets received from the participant.
+    pub packets_in: u64,
+    pub bytes_in: u64,
+    /// Packets forwarded to the participant.
+    pub packets_out: u64,
+    pub bytes_out: u64,
+    /// Packets the participant sent that never reached the relay, estimated
+    /// from sequence number gaps.
+    pub packets_lost: u64,
+    pub loss_pct: f32,
+    /// Worst interarrival jitter across the participant's streams.
+    pub jitter_ms: f32,
+    /// Estimated upload bitrate over the last window.
+    pub inbound_kbps: u32,
+    /// Estimated download bitrate over the last window.
+    pub outbound_kbps: u32,
+}
+
+impl ParticipantStatsSnapshot {
+    /// Gateway/API representation (ids as strings, like every other payload).
+    pub fn to_json(&self) -> Value {
+        json!({
+            "user_id": self.user_id.to_string(),
+            "packets_in": self.packets_in,
+            "bytes_in": self.bytes_in,
+            "packets_out": self.packets_out,
+            "bytes_out": self.bytes_out,
+            "packets_lost": self.packets_lost,
+            "loss_pct": self.loss_pct,
+            "jitter_ms": self.jitter_ms,
+            "inbound_kbps": self.inbound_kbps,
+            "outbound_kbps": self.outbound_kbps,
+        })
+    }
+}
+
+/// Per-participant packet accounting for the relay.
+///
+/// Only the cleartext `MediaHeader` is inspected, so loss and jitter are
+/// measured on the client-to-relay leg.
+pub struct StatsTracker {
+    participants: DashMap<i64, ParticipantStats>,
+}
+
+impl StatsTracker {
+    pub fn new() -> Self {
+        Self {
+            participants: DashMap::new(),
+        }
+    }
+
+    /// Record a packet received from `user_id`.
+    pub fn record_inbound(&self, user_id: i64, room_id: &str, header: &MediaHeader, len: usize) {
+        self.record_inbound_at(user_id, room_id, header, len, Instant::now());
+    }
+
+    fn record_inbound_at(
+        &self,
+        user_id: i64,
+        room_id: &str,
+        header: &MediaHeader,
+        len: usize,
+        now: Instant,
+    ) {
+        let mut stats = self
+            .participants
+            .entry(user_id)
+            .or_insert_with(|| ParticipantStats::new(room_id, now));
+        if stats.room_id != room_id {
+            *stats = ParticipantStats::new(room_id, now);
+        }
+        stats.packets_in += 1;
+        stats.bytes_in += len as u64;
+        stats.inbound_rate.record(len, now);
+        let arrival = now.duration_since(stats.epoch).as_secs_f64();
+        stats
+            .streams
+            .entry(header.ssrc)
+            .or_insert_with(|| StreamState::new(header))
+            .update(header, arrival);
+    }
+
+    /// Record a packet forwarded to `user_id`.
+    pub fn record_outbound(&self, user_id: i64, room_id: &str, len: usize) {
+        let now = Instant::now();
+        let mut stats = self
+            .participants
+            .entry(user_id)
+            .or_insert_with(|| ParticipantStats::new(room_id, now));
+        stats.packets_out += 1;
+        stats.bytes_out += len as u64;
+        stats.outbound_rate.record(len, now);
+    }
+
+    /// Snapshot a single participant.
+    pub fn snapshot(&self, user_id: i64) -> Option<ParticipantStatsSnapshot> {
+        let now = Instant::now();
+        self.participants
+            .get_mut(&user_id)
+            .map(|mut stats| stats.snapshot(user_id, now))
+    }
+
+    /// Snapshot every tracked participant, grouped by room id.
+    pub fn snapshot_rooms(&self) -> HashMap<String, Vec<ParticipantStatsSnapshot>> {
+        let now = Instant::now();
+        let mut rooms: HashMap<String, Vec<ParticipantStatsSnapshot>> = HashMap::new();
+        for mut entry in self.participants.iter_mut() {
+            let user_id = *entry.key();
+            let snapshot = entry.value_mut().snapshot(user_id, now);
+            rooms
+                .entry(snapshot.room_id.clone())
+                .or_default()
+                .push(snapshot);
+        }
+        rooms
+    }
+
+    /// Drop stats for a disconnected participant.
+    pub fn remove(&self, user_id: i64) {
+        self.participants.remove(&user_id);
+    }
+}
+
+impl Default for StatsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio_header(sequence: u16, timestamp: u32) -> MediaHeader {
+        let mut header = MediaHeader::new(TrackType::Audio, 1234);
+        header.sequence = sequence;
+        header.timestamp = timestamp;
+        header
+    }
+
+    #[test]
+    fn counts_sequence_gaps_as_loss() {
+        let tracker = StatsTracker::new();
+        let start = Instant::now();
+        for (i, seq) in [10u16, 11, 12, 15, 16].into_iter().enumerate() {
+            let now = start + Duration::from_millis(20 * i as u64);
+            tracker.record_inbound_at(7, "1:2", &audio_header(seq, seq as u32 * 960), 100, now);
+        }
+        let snap = tracker.snapshot(7).unwrap();
+        assert_eq!(snap.packets_in, 5);
+        assert_eq!(snap.bytes_in, 500);
+        assert_eq!(snap.packets_lost, 2);
+        assert!((snap.loss_pct - 2.0 / 7.0 * 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn handles_sequence_wraparound() {
+        let tracker = StatsTracker::new();
+        let now = Instant::now();
+        for seq in [65534u16, 65535, 0, 1] {
+            tracker.record_inbound_at(7, "1:2", &audio_header(seq, 0), 10, now);
+        }
+        let snap = tracker.snapshot(7).unwrap();
+        assert_eq!(snap.packets_lost, 0);
+    }
+
+    #[test]
+    fn reordered_packets_do_not_count_as_loss() {
+        let tracker = StatsTracker::new();
+        let now = Instant::now();
+        for seq in [1u16, 3, 2, 4] {
+            tracker.record_inbound_at(7, "1:2", &audio_header(seq, 0), 10, now);
+        }
+        assert_eq!(tracker.snapshot(7).unwrap().packets_lost, 0);
+    }
+
+    #[test]
+    fn steady_stream_has_no_jitter() {
+        let tracker = StatsTracker::new();
+        let start = Instant::now();
+        for i in 0..50u16 {
+            let now = start + Duration::from_millis(20 * i as u64);
+            tracker.record_inbound_at(7, "1:2", &audio_header(i, i as u32 * 960), 10, now);
+        }
+        assert!(tracker.snapshot(7).unwrap().jitter_ms < 0.01);
+    }
+
+    #[test]
+    fn groups_snapshots_by_room() {
+        let tracker = StatsTracker::new();
+        tracker.record_inbound(1, "1:2", &audio_header(0, 0), 10);
+        tracker.record_outbound(2, "1:2", 10);
+        tracker.record_outbound(3, "1:3", 10);
+        let rooms = tracker.snapshot_rooms();
+        assert_eq!(rooms["1:2"].len(), 2);
+        assert_eq!(rooms["1:3"].len(), 1);
+
+        tracker.remove(1);
+        assert!(tracker.snapshot(1).is_none());
+    }
+}
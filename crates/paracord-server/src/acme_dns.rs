@@ -0,0 +1,202 @@
+//! DNS-01 support for the certbot-driven ACME flow.
+//!
+//! Cloudflare and RFC 2136 use the matching certbot plugins. The webhook
+//! provider runs certbot in manual mode with this binary as its auth and
+//! cleanup hooks (`paracord-server acme-dns-hook <stage>`), which post each
+//! TXT record to the configured URL for an external service to publish.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cli::AcmeDnsHookStage;
+use crate::config::{AcmeChallenge, AcmeDnsProvider, TlsAcmeConfig};
+
+const HOOK_WEBHOOK_URL_ENV: &str = "PARACORD_ACME_DNS_WEBHOOK_URL";
+const HOOK_WEBHOOK_TOKEN_ENV: &str = "PARACORD_ACME_DNS_WEBHOOK_TOKEN";
+const HOOK_PROPAGATION_ENV: &str = "PARACORD_ACME_DNS_PROPAGATION_SECONDS";
+
+pub fn validate(acme: &TlsAcmeConfig) -> Result<()> {
+    if acme.challenge == AcmeChallenge::Http01 {
+        if let Some(domain) = acme.domains.iter().find(|d| d.trim().starts_with("*.")) {
+            anyhow::bail!(
+                "wildcard domain '{}' requires tls.acme.challenge = \"dns-01\"",
+                domain.trim()
+            );
+        }
+        return Ok(());
+    }
+
+    let dns = &acme.dns;
+    match dns.provider {
+        AcmeDnsProvider::Cloudflare | AcmeDnsProvider::Rfc2136 => {
+            let credentials = configured(dns.credentials_path.as_deref()).with_context(|| {
+                format!(
+                    "tls.acme.dns.provider = \"{}\" requires tls.acme.dns.credentials_path",
+                    dns.provider.as_str()
+                )
+            })?;
+            if !Path::new(credentials).is_file() {
+                anyhow::bail!(
+                    "tls.acme.dns.credentials_path {:?} does not exist",
+                    credentials
+                );
+            }
+        }
+        AcmeDnsProvider::Webhook => {
+            let url = configured(dns.webhook_url.as_deref())
+                .context("tls.acme.dns.provider = \"webhook\" requires tls.acme.dns.webhook_url")?;
+            let parsed = reqwest::Url::parse(url)
+                .with_context(|| format!("tls.acme.dns.webhook_url '{}' is not a URL", url))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                anyhow::bail!("tls.acme.dns.webhook_url must use http or https");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the challenge-specific certbot arguments for `acme`.
+pub fn apply_certbot_args(
+    command: &mut tokio::process::Command,
+    acme: &TlsAcmeConfig,
+) -> Result<()> {
+    if acme.challenge == AcmeChallenge::Http01 {
+        command
+            .arg("--webroot")
+            .arg("-w")
+            .arg(acme.webroot_path.trim());
+        return Ok(());
+    }
+
+    let dns = &acme.dns;
+    let propagation = dns.propagation_seconds.to_string();
+    match dns.provider {
+        AcmeDnsProvider::Cloudflare | AcmeDnsProvider::Rfc2136 => {
+            let plugin = format!("dns-{}", dns.provider.as_str());
+            command
+                .arg(format!("--{plugin}"))
+                .arg(format!("--{plugin}-credentials"))
+                .arg(configured(dns.credentials_path.as_deref()).unwrap_or_default())
+                .arg(format!("--{plugin}-propagation-seconds"))
+                .arg(&propagation);
+        }
+        AcmeDnsProvider::Webhook => {
+            let exe = std::env::current_exe()
+                .context("Failed to locate the server executable for the DNS hook")?;
+            let hook = |stage: &str| format!("\"{}\" acme-dns-hook {stage}", exe.display());
+            command
+                .arg("--manual")
+                .arg("--preferred-challenges")
+                .arg("dns")
+                .arg("--manual-auth-hook")
+                .arg(hook("auth"))
+                .arg("--manual-cleanup-hook")
+                .arg(hook("cleanup"))
+                .env(
+                    HOOK_WEBHOOK_URL_ENV,
+                    configured(dns.webhook_url.as_deref()).unwrap_or_default(),
+                )
+                .env(HOOK_PROPAGATION_ENV, &propagation);
+            match configured(dns.webhook_token.as_deref()) {
+                Some(token) => command.env(HOOK_WEBHOOK_TOKEN_ENV, token),
+                None => command.env_remove(HOOK_WEBHOOK_TOKEN_ENV),
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Entry point for certbot's manual hooks. certbot passes the domain and
+/// validation string through `CERTBOT_DOMAIN` and `CERTBOT_VALIDATION`.
+pub async fn run_hook(stage: AcmeDnsHookStage) -> Result<()> {
+    let domain = std::env::var("CERTBOT_DOMAIN").context("CERTBOT_DOMAIN is not set")?;
+    let validation =
+        std::env::var("CERTBOT_VALIDATION").context("CERTBOT_VALIDATION is not set")?;
+    let url = std::env::var(HOOK_WEBHOOK_URL_ENV)
+        .with_context(|| format!("{HOOK_WEBHOOK_URL_ENV} is not set"))?;
+    let action = match stage {
+        AcmeDnsHookStage::Auth => "present",
+        AcmeDnsHookStage::Cleanup => "cleanup",
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    let mut request = client.post(&url).json(&json!({
+        "action": action,
+        "domain": domain,
+        "record_name": challenge_record_name(&domain),
+        "value": validation,
+    }));
+    if let Some(token) = std::env::var(HOOK_WEBHOOK_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+    {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("DNS webhook request to {url} failed"))?;
+    if !response.status().is_success() {
+        anyhow::bail!("DNS webhook returned {} for {action}", response.status());
+    }
+
+    if stage == AcmeDnsHookStage::Auth {
+        let propagation = std::env::var(HOOK_PROPAGATION_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60);
+        tokio::time::sleep(Duration::from_secs(propagation)).await;
+    }
+    Ok(())
+}
+
+fn challenge_record_name(domain: &str) -> String {
+    format!("_acme-challenge.{}", domain.trim_start_matches("*."))
+}
+
+fn configured(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acme(domains: &[&str], challenge: AcmeChallenge) -> TlsAcmeConfig {
+        let mut acme = TlsAcmeConfig {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            challenge,
+            ..TlsAcmeConfig::default()
+        };
+        acme.dns.provider = AcmeDnsProvider::Webhook;
+        acme
+    }
+
+    #[test]
+    fn wildcard_domains_require_dns_challenge() {
+        let err = validate(&acme(&["*.chat.example.com"], AcmeChallenge::Http01))
+            .expect_err("http-01 cannot issue wildcards");
+        assert!(err.to_string().contains("dns-01"));
+
+        let mut dns = acme(&["*.chat.example.com"], AcmeChallenge::Dns01);
+        assert!(validate(&dns).is_err(), "webhook provider needs a URL");
+        dns.dns.webhook_url = Some("https://dns-hooks.example.com/acme".into());
+        assert!(validate(&dns).is_ok());
+    }
+
+    #[test]
+    fn challenge_record_strips_wildcard_label() {
+        assert_eq!(
+            challenge_record_name("*.chat.example.com"),
+            "_acme-challenge.chat.example.com"
+        );
+        assert_eq!(
+            challenge_record_name("chat.example.com"),
+            "_acme-challenge.chat.example.com"
+        );
+    }
+}
@@ -120,7 +120,9 @@ async fn handle_welcome_bot(
         .unwrap_or("User");
     let content = template.replace("{user}", username);
 
-    let msg_id = paracord_util::snowflake::generate(1);
+    let Ok(msg_id) = paracord_util::snowflake::try_next_id().await else {
+        return;
+    };
 
     if let Ok(msg) = paracord_db::messages::create_message(
         &state.db,
@@ -223,8 +225,21 @@ async fn handle_auto_mod(
                     }),
                     Some(guild_id),
                 );
+                if let Some(author_id) = author_id_str.and_then(|id| id.parse().ok()) {
+                    paracord_api::routes::mod_log::record_automod_action(
+                        state,
+                        guild_id,
+                        channel_id,
+                        author_id,
+                        "removed a message",
+                        Some("restricted words"),
+                    )
+                    .await;
+                }
 
-                let warning_id = paracord_util::snowflake::generate(1);
+                let Ok(warning_id) = paracord_util::snowflake::try_next_id().await else {
+                    return;
+                };
                 let warning_content =
                     "A message was removed for containing restricted words.".to_string();
                 if let Ok(warning_msg) = paracord_db::messages::create_message(
@@ -1,13 +1,66 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(name = "paracord-server", about = "Paracord chat server")]
 pub struct Args {
     /// Path to configuration file
-    #[arg(short, long, default_value = "config/paracord.toml")]
+    #[arg(short, long, global = true, default_value = "config/paracord.toml")]
     pub config: String,
 
     /// Path to directory containing built web UI files (overrides config)
     #[arg(long)]
     pub web_dir: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Import a Discord or Matrix export into an existing guild. The import
+    /// runs as a background job on the server; this waits for it unless
+    /// --detach is given.
+    Import(ImportArgs),
+    /// Validate the --config file without starting the server: report
+    /// unknown keys and conflicting settings, then print the effective
+    /// configuration with secrets redacted. Exits non-zero on errors.
+    Check,
+    /// certbot manual hook used by the DNS-01 webhook provider
+    #[command(hide = true)]
+    AcmeDnsHook {
+        #[arg(value_enum)]
+        stage: AcmeDnsHookStage,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ImportArgs {
+    /// Export archive (.zip) or extracted export directory
+    pub path: PathBuf,
+    /// Guild to import into
+    #[arg(long)]
+    pub guild_id: i64,
+    /// Export format; detected from the archive when omitted
+    #[arg(long, value_enum)]
+    pub format: Option<ImportFormatArg>,
+    /// Only report what would be imported
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Queue the job and exit without waiting for it
+    #[arg(long)]
+    pub detach: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormatArg {
+    Discord,
+    Matrix,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AcmeDnsHookStage {
+    Auth,
+    Cleanup,
 }
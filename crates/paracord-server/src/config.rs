@@ -1,7 +1,12 @@
 use anyhow::Result;
+use paracord_api::rate_limit::{RateLimits, RouteClass, RouteLimit};
+use paracord_core::hash_matching::HashMatchingSettings;
+use paracord_core::url_reputation::UrlFilterLevel;
 use paracord_media::S3Config;
+use paracord_util::snowflake::SnowflakeFormat;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 
 fn harden_secret_file_permissions(path: &str) -> Result<()> {
@@ -60,6 +65,32 @@ pub struct Config {
     pub at_rest: AtRestConfig,
     #[serde(default)]
     pub backup: BackupConfig,
+    #[serde(default)]
+    pub transcoding: TranscodingConfig,
+    #[serde(default)]
+    pub client_updates: ClientUpdatesConfig,
+    #[serde(default)]
+    pub url_reputation: UrlReputationConfig,
+    #[serde(default)]
+    pub hash_matching: HashMatchingConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub sqlite_maintenance: SqliteMaintenanceConfig,
+    #[serde(default)]
+    pub supporters: SupportersConfig,
+    #[serde(default)]
+    pub web_push: WebPushConfig,
+    #[serde(default)]
+    pub link_previews: LinkPreviewsConfig,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    #[serde(default)]
+    pub tenants: TenantsConfig,
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    #[serde(default)]
+    pub developer: DeveloperConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -72,6 +103,29 @@ pub struct ServerConfig {
     /// Public URL of this server (e.g., https://chat.example.com).
     /// Used for CORS auto-configuration and invite links.
     pub public_url: Option<String>,
+    /// Optional second listener (e.g. 127.0.0.1:9090) for /health, /metrics
+    /// and /api/v1/admin/*. When set, the public listener stops serving
+    /// metrics and admin endpoints.
+    pub internal_bind: Option<String>,
+    /// Snowflake worker id (0-1023). Nodes sharing a database need distinct
+    /// ids or they can generate colliding IDs.
+    #[serde(default = "default_worker_id")]
+    pub worker_id: u64,
+    /// Epoch generated IDs count from. `discord` makes them Discord
+    /// snowflakes, so imported Discord IDs keep their order and timestamps.
+    #[serde(default)]
+    pub snowflake_format: SnowflakeFormat,
+    /// Custom epoch in Unix milliseconds, overriding `snowflake_format`.
+    #[serde(default)]
+    pub snowflake_epoch: Option<u64>,
+}
+
+impl ServerConfig {
+    /// Epoch for generated IDs in Unix milliseconds.
+    pub fn snowflake_epoch_millis(&self) -> u64 {
+        self.snowflake_epoch
+            .unwrap_or_else(|| self.snowflake_format.epoch_millis())
+    }
 }
 
 impl Default for ServerConfig {
@@ -81,11 +135,15 @@ impl Default for ServerConfig {
             server_name: default_server_name(),
             web_dir: None,
             public_url: None,
+            internal_bind: None,
+            worker_id: default_worker_id(),
+            snowflake_format: SnowflakeFormat::default(),
+            snowflake_epoch: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DatabaseConfig {
     #[serde(default = "default_database_engine")]
     pub engine: DatabaseEngine,
@@ -98,6 +156,10 @@ pub struct DatabaseConfig {
     /// Idle-in-transaction timeout in seconds for PostgreSQL (0 = disabled).
     #[serde(default)]
     pub idle_in_transaction_timeout_secs: u64,
+    /// PostgreSQL only: let the pool grow past `max_connections` up to this
+    /// many connections under load (0 = disabled).
+    #[serde(default)]
+    pub adaptive_max_connections: u32,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
@@ -117,13 +179,20 @@ impl Default for DatabaseConfig {
             max_connections: default_max_connections(),
             statement_timeout_secs: 0,
             idle_in_transaction_timeout_secs: 0,
+            adaptive_max_connections: 0,
         }
     }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AuthConfig {
+    /// May be left empty when `jwt_secret_file` or the secrets command
+    /// supplies it.
+    #[serde(default)]
     pub jwt_secret: String,
+    /// File holding `jwt_secret`, e.g. a Docker or Kubernetes secret.
+    #[serde(default)]
+    pub jwt_secret_file: Option<String>,
     #[serde(default = "default_jwt_expiry")]
     pub jwt_expiry_seconds: u64,
     #[serde(default = "default_true")]
@@ -138,6 +207,7 @@ impl Default for AuthConfig {
     fn default() -> Self {
         Self {
             jwt_secret: generate_random_hex(64),
+            jwt_secret_file: None,
             jwt_expiry_seconds: default_jwt_expiry(),
             registration_enabled: true,
             allow_username_login: true,
@@ -156,6 +226,8 @@ pub struct StorageConfig {
     pub max_upload_size: u64,
     #[serde(default = "default_max_guild_storage_quota")]
     pub max_guild_storage_quota: u64,
+    #[serde(default)]
+    pub limits: StorageLimitsConfig,
 }
 
 impl Default for StorageConfig {
@@ -165,6 +237,34 @@ impl Default for StorageConfig {
             path: default_storage_path(),
             max_upload_size: default_max_upload_size(),
             max_guild_storage_quota: default_max_guild_storage_quota(),
+            limits: StorageLimitsConfig::default(),
+        }
+    }
+}
+
+/// Per-user attachment transfer limits, applied to HTTP and QUIC transfers
+/// alike. Zero disables a limit.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StorageLimitsConfig {
+    #[serde(default = "default_max_concurrent_uploads")]
+    pub max_concurrent_uploads: u32,
+    #[serde(default = "default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: u32,
+    /// Shared by all of a user's uploads.
+    #[serde(default)]
+    pub upload_bytes_per_second: u64,
+    /// Shared by all of a user's downloads.
+    #[serde(default)]
+    pub download_bytes_per_second: u64,
+}
+
+impl Default for StorageLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_uploads: default_max_concurrent_uploads(),
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            upload_bytes_per_second: 0,
+            download_bytes_per_second: 0,
         }
     }
 }
@@ -200,6 +300,49 @@ pub struct VoiceConfig {
     /// Require E2EE sender key exchange for all media sessions.
     #[serde(default = "default_true")]
     pub e2ee_required: bool,
+    /// Selective audio forwarding tiers. Rooms with at least
+    /// `min_participants` only receive the `max_speakers` loudest speakers.
+    /// An empty list forwards every participant's audio.
+    #[serde(default = "default_voice_speaker_forwarding")]
+    pub speaker_forwarding: Vec<SpeakerForwardingTierConfig>,
+    /// Base URL of the WebRTC gateway that serves WHIP publishes into native
+    /// media channels and WHEP playback. Unset limits WHIP to LiveKit Ingress.
+    #[serde(default)]
+    pub webrtc_gateway_url: Option<String>,
+    /// Certificate presented by the media endpoint.
+    #[serde(default)]
+    pub cert: MediaCertSource,
+    /// PEM certificate chain for `cert = "files"`.
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// PEM private key for `cert = "files"`.
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// Extra subject alternative names for the self-signed certificate, on
+    /// top of localhost, the public URL's host and the detected IPs.
+    #[serde(default)]
+    pub cert_sans: Vec<String>,
+}
+
+/// Where the media endpoint's certificate comes from.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum MediaCertSource {
+    /// Generated at startup; browsers pin it via `serverCertificateHashes`.
+    #[default]
+    #[serde(rename = "self-signed")]
+    SelfSigned,
+    /// The `[tls]` certificate, reloaded when ACME renews it.
+    #[serde(rename = "server")]
+    Server,
+    /// `[voice] cert_path` / `key_path`.
+    #[serde(rename = "files")]
+    Files,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpeakerForwardingTierConfig {
+    pub min_participants: u32,
+    pub max_speakers: u32,
 }
 
 impl Default for VoiceConfig {
@@ -210,6 +353,12 @@ impl Default for VoiceConfig {
             max_participants_per_room: default_voice_max_participants(),
             audio_bitrate: default_voice_audio_bitrate(),
             e2ee_required: true,
+            speaker_forwarding: default_voice_speaker_forwarding(),
+            webrtc_gateway_url: None,
+            cert: MediaCertSource::default(),
+            cert_path: None,
+            key_path: None,
+            cert_sans: Vec::new(),
         }
     }
 }
@@ -230,6 +379,9 @@ pub struct LiveKitConfig {
     pub api_key: String,
     #[serde(default = "default_livekit_secret")]
     pub api_secret: String,
+    /// File holding `api_secret`.
+    #[serde(default)]
+    pub api_secret_file: Option<String>,
     #[serde(default = "default_livekit_url")]
     pub url: String,
     #[serde(default = "default_livekit_http_url")]
@@ -237,6 +389,11 @@ pub struct LiveKitConfig {
     /// Public LiveKit URL sent to clients (e.g., wss://chat.example.com/livekit).
     /// Falls back to `url` if not set.
     pub public_url: Option<String>,
+    /// Directory the LiveKit Egress service writes recordings to. Must be
+    /// mounted at the same path for both Paracord and Egress. Unset disables
+    /// file recording (RTMP restreaming still works).
+    #[serde(default)]
+    pub egress_output_dir: Option<String>,
 }
 
 impl Default for LiveKitConfig {
@@ -244,9 +401,11 @@ impl Default for LiveKitConfig {
         Self {
             api_key: format!("paracord_{}", generate_random_hex(8)),
             api_secret: generate_random_hex(32),
+            api_secret_file: None,
             url: default_livekit_url(),
             http_url: default_livekit_http_url(),
             public_url: None,
+            egress_output_dir: None,
         }
     }
 }
@@ -270,6 +429,13 @@ pub struct TlsConfig {
     pub key_path: String,
     #[serde(default = "default_true")]
     pub auto_generate: bool,
+    /// Serve the API over HTTP/3 as well, advertised through `Alt-Svc`.
+    #[serde(default = "default_false")]
+    pub http3: bool,
+    /// UDP port for HTTP/3. Defaults to `port`; must differ from the native
+    /// media port when both are enabled.
+    #[serde(default)]
+    pub http3_port: Option<u16>,
     #[serde(default)]
     pub acme: TlsAcmeConfig,
 }
@@ -282,6 +448,8 @@ impl Default for TlsConfig {
             cert_path: default_cert_path(),
             key_path: default_key_path(),
             auto_generate: true,
+            http3: false,
+            http3_port: None,
             acme: TlsAcmeConfig::default(),
         }
     }
@@ -312,6 +480,82 @@ pub struct TlsAcmeConfig {
     pub auto_renew: bool,
     #[serde(default = "default_acme_renew_interval_seconds")]
     pub renew_interval_seconds: u64,
+    /// Challenge type; wildcard domains and servers without port 80 need `dns-01`.
+    #[serde(default)]
+    pub challenge: AcmeChallenge,
+    #[serde(default)]
+    pub dns: TlsAcmeDnsConfig,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum AcmeChallenge {
+    #[default]
+    #[serde(rename = "http-01")]
+    Http01,
+    #[serde(rename = "dns-01")]
+    Dns01,
+}
+
+impl AcmeChallenge {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AcmeChallenge::Http01 => "http-01",
+            AcmeChallenge::Dns01 => "dns-01",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AcmeDnsProvider {
+    /// certbot-dns-cloudflare plugin; `credentials_path` holds the API token.
+    #[default]
+    Cloudflare,
+    /// certbot-dns-rfc2136 plugin; `credentials_path` holds the TSIG key.
+    Rfc2136,
+    /// Paracord posts each TXT record to `webhook_url` for an external
+    /// service to publish.
+    Webhook,
+}
+
+impl AcmeDnsProvider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AcmeDnsProvider::Cloudflare => "cloudflare",
+            AcmeDnsProvider::Rfc2136 => "rfc2136",
+            AcmeDnsProvider::Webhook => "webhook",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsAcmeDnsConfig {
+    #[serde(default)]
+    pub provider: AcmeDnsProvider,
+    /// certbot plugin credentials file (Cloudflare and RFC 2136).
+    pub credentials_path: Option<String>,
+    /// How long to wait for TXT records to propagate before validation.
+    #[serde(default = "default_acme_dns_propagation_seconds")]
+    pub propagation_seconds: u64,
+    pub webhook_url: Option<String>,
+    /// Sent as a bearer token to `webhook_url`.
+    pub webhook_token: Option<String>,
+    /// File holding `webhook_token`.
+    #[serde(default)]
+    pub webhook_token_file: Option<String>,
+}
+
+impl Default for TlsAcmeDnsConfig {
+    fn default() -> Self {
+        Self {
+            provider: AcmeDnsProvider::default(),
+            credentials_path: None,
+            propagation_seconds: default_acme_dns_propagation_seconds(),
+            webhook_url: None,
+            webhook_token: None,
+            webhook_token_file: None,
+        }
+    }
 }
 
 impl Default for TlsAcmeConfig {
@@ -330,6 +574,8 @@ impl Default for TlsAcmeConfig {
             serve_http_challenge: true,
             auto_renew: true,
             renew_interval_seconds: default_acme_renew_interval_seconds(),
+            challenge: AcmeChallenge::default(),
+            dns: TlsAcmeDnsConfig::default(),
         }
     }
 }
@@ -370,6 +616,12 @@ pub struct AtRestConfig {
     pub enabled: bool,
     #[serde(default = "default_at_rest_key_env")]
     pub key_env: String,
+    /// File holding the master key; takes precedence over `key_env`.
+    #[serde(default)]
+    pub key_file: Option<String>,
+    /// Master key from `key_file` or the secrets command.
+    #[serde(skip)]
+    pub key: Option<String>,
     #[serde(default = "default_false")]
     pub encrypt_sqlite: bool,
     #[serde(default = "default_false")]
@@ -383,6 +635,8 @@ impl Default for AtRestConfig {
         Self {
             enabled: false,
             key_env: default_at_rest_key_env(),
+            key_file: None,
+            key: None,
             encrypt_sqlite: false,
             encrypt_files: false,
             allow_plaintext_file_reads: false,
@@ -403,6 +657,8 @@ pub struct FederationConfig {
     pub max_events_per_peer_per_minute: Option<u32>,
     #[serde(default = "default_max_user_creates_per_peer_per_hour")]
     pub max_user_creates_per_peer_per_hour: Option<u32>,
+    #[serde(default)]
+    pub max_bytes_per_peer_per_hour: Option<u64>,
     #[serde(default = "default_false")]
     pub file_cache_enabled: bool,
     #[serde(default = "default_federation_file_cache_max_size")]
@@ -420,6 +676,7 @@ impl Default for FederationConfig {
             allow_discovery: false,
             max_events_per_peer_per_minute: default_max_events_per_peer_per_minute(),
             max_user_creates_per_peer_per_hour: default_max_user_creates_per_peer_per_hour(),
+            max_bytes_per_peer_per_hour: None,
             file_cache_enabled: false,
             file_cache_max_size: default_federation_file_cache_max_size(),
             file_cache_ttl_hours: default_federation_file_cache_ttl_hours(),
@@ -453,6 +710,422 @@ impl Default for BackupConfig {
     }
 }
 
+/// Optional ffmpeg-backed video transcoding worker.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TranscodingConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Path or command name of the ffmpeg binary.
+    #[serde(default = "default_ffmpeg_path")]
+    pub ffmpeg_path: String,
+    /// How often the worker polls for queued jobs.
+    #[serde(default = "default_transcoding_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Per-job ffmpeg timeout; jobs exceeding it are marked failed.
+    #[serde(default = "default_transcoding_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for TranscodingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ffmpeg_path: default_ffmpeg_path(),
+            poll_interval_seconds: default_transcoding_poll_interval_seconds(),
+            timeout_seconds: default_transcoding_timeout_seconds(),
+        }
+    }
+}
+
+/// Server-driven desktop client updates and version gating.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ClientUpdatesConfig {
+    /// JSON file listing published desktop builds per channel and platform.
+    #[serde(default)]
+    pub manifest_path: Option<String>,
+    /// Desktop clients older than this are rejected with `CLIENT_OUTDATED`.
+    #[serde(default)]
+    pub minimum_version: Option<String>,
+}
+
+/// Phishing and invite-scam link screening for guild messages.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UrlReputationConfig {
+    /// Level for guilds that haven't picked one in their bot settings.
+    #[serde(default)]
+    pub default_level: UrlFilterLevel,
+    /// Plain domain lists or hosts files to download into the denylist.
+    #[serde(default)]
+    pub feeds: Vec<String>,
+    #[serde(default = "default_url_feed_refresh_interval_seconds")]
+    pub refresh_interval_seconds: u64,
+    /// Last successful feed snapshot, loaded at startup before the first
+    /// refresh completes.
+    #[serde(default = "default_url_feed_cache_path")]
+    pub cache_path: String,
+}
+
+impl Default for UrlReputationConfig {
+    fn default() -> Self {
+        Self {
+            default_level: UrlFilterLevel::default(),
+            feeds: Vec::new(),
+            refresh_interval_seconds: default_url_feed_refresh_interval_seconds(),
+            cache_path: default_url_feed_cache_path(),
+        }
+    }
+}
+
+/// Perceptual-hash screening of image uploads against a blocklist and,
+/// optionally, an external matching service.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HashMatchingConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Differing bits (out of 64) still treated as the same image.
+    #[serde(default = "default_hash_match_max_distance")]
+    pub max_distance: u32,
+    /// Endpoint each image upload is POSTed to for a verdict.
+    #[serde(default)]
+    pub service_url: Option<String>,
+    /// Bearer token sent to `service_url`.
+    #[serde(default)]
+    pub service_token: Option<String>,
+    /// File holding `service_token`.
+    #[serde(default)]
+    pub service_token_file: Option<String>,
+    #[serde(default = "default_hash_match_service_timeout_ms")]
+    pub service_timeout_ms: u64,
+    /// Reject uploads when the service is unreachable or errors.
+    #[serde(default = "default_true")]
+    pub fail_closed: bool,
+}
+
+impl Default for HashMatchingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_distance: default_hash_match_max_distance(),
+            service_url: None,
+            service_token: None,
+            service_token_file: None,
+            service_timeout_ms: default_hash_match_service_timeout_ms(),
+            fail_closed: true,
+        }
+    }
+}
+
+impl HashMatchingConfig {
+    pub fn to_settings(&self) -> HashMatchingSettings {
+        HashMatchingSettings {
+            enabled: self.enabled,
+            max_distance: self.max_distance,
+            service_url: self
+                .service_url
+                .clone()
+                .filter(|url| !url.trim().is_empty()),
+            service_token: self
+                .service_token
+                .clone()
+                .filter(|token| !token.trim().is_empty()),
+            service_timeout: std::time::Duration::from_millis(self.service_timeout_ms),
+            fail_closed: self.fail_closed,
+        }
+    }
+}
+
+/// HTTP rate limits and the requests that skip them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// Client addresses or CIDR ranges, e.g. monitoring hosts.
+    #[serde(default)]
+    pub allowlist_ips: Vec<String>,
+    /// Tokens accepted in the `X-Paracord-RateLimit-Bypass` header.
+    #[serde(default)]
+    pub allowlist_tokens: Vec<String>,
+    /// File with more bypass tokens, one per line.
+    #[serde(default)]
+    pub allowlist_tokens_file: Option<String>,
+    /// Exact paths, or prefixes ending in `*`.
+    #[serde(default = "default_rate_limit_exempt_paths")]
+    pub exempt_paths: Vec<String>,
+    /// Token bucket per route class (`global`, `auth`, `messages`,
+    /// `uploads`, `api`); classes left out keep their defaults.
+    #[serde(default)]
+    pub limits: BTreeMap<String, RateLimitRule>,
+}
+
+/// Up to `burst` requests at once, refilled at `per_minute`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct RateLimitRule {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RateLimitConfig {
+    /// The configured limits over the defaults.
+    pub fn route_limits(&self) -> Result<RateLimits, String> {
+        let mut limits = RateLimits::default();
+        for (name, rule) in &self.limits {
+            let class = RouteClass::from_name(name)
+                .ok_or_else(|| format!("rate_limit.limits: unknown route class '{name}'"))?;
+            let limit = RouteLimit {
+                burst: rule.burst,
+                per_minute: rule.per_minute,
+            };
+            if !limit.is_valid() {
+                return Err(format!(
+                    "rate_limit.limits.{name}: burst and per_minute must be positive"
+                ));
+            }
+            limits.set(class, limit);
+        }
+        Ok(limits)
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            allowlist_ips: Vec::new(),
+            allowlist_tokens: Vec::new(),
+            allowlist_tokens_file: None,
+            exempt_paths: default_rate_limit_exempt_paths(),
+            limits: BTreeMap::new(),
+        }
+    }
+}
+
+/// Periodic housekeeping for SQLite databases; ignored on PostgreSQL.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SqliteMaintenanceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// How often to checkpoint and truncate the WAL and run `PRAGMA optimize`.
+    #[serde(default = "default_sqlite_maintenance_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Switch the database to `auto_vacuum = INCREMENTAL` at startup (a
+    /// one-time full `VACUUM`) and release free pages on every run.
+    #[serde(default = "default_false")]
+    pub incremental_vacuum: bool,
+    /// Free pages released per run (0 = all).
+    #[serde(default = "default_incremental_vacuum_pages")]
+    pub incremental_vacuum_pages: i64,
+}
+
+impl Default for SqliteMaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: default_sqlite_maintenance_interval_seconds(),
+            incremental_vacuum: false,
+            incremental_vacuum_pages: default_incremental_vacuum_pages(),
+        }
+    }
+}
+
+/// Donation platform webhooks that grant supporter perks.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SupportersConfig {
+    /// Ko-fi's verification token; enables `/api/v1/supporters/webhooks/kofi`.
+    #[serde(default)]
+    pub kofi_verification_token: Option<String>,
+    /// Patreon's webhook secret; enables `/api/v1/supporters/webhooks/patreon`.
+    #[serde(default)]
+    pub patreon_webhook_secret: Option<String>,
+    #[serde(default)]
+    pub kofi_verification_token_file: Option<String>,
+    #[serde(default)]
+    pub patreon_webhook_secret_file: Option<String>,
+    /// Days of supporter status each payment adds.
+    #[serde(default = "default_supporter_grant_days")]
+    pub grant_days: u32,
+}
+
+impl Default for SupportersConfig {
+    fn default() -> Self {
+        Self {
+            kofi_verification_token: None,
+            patreon_webhook_secret: None,
+            kofi_verification_token_file: None,
+            patreon_webhook_secret_file: None,
+            grant_days: default_supporter_grant_days(),
+        }
+    }
+}
+
+/// Web Push notifications for mentions and DMs to users with no gateway
+/// connection.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebPushConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Contact push services can reach the operator at, as a `mailto:` or
+    /// `https:` URL (the VAPID `sub` claim).
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// VAPID private key: a base64url P-256 scalar. Read from `key_path`
+    /// (and generated there on first start) when unset.
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default = "default_vapid_key_path")]
+    pub key_path: String,
+}
+
+impl Default for WebPushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            subject: None,
+            private_key: None,
+            key_path: default_vapid_key_path(),
+        }
+    }
+}
+
+/// Previews (embeds) for links posted in messages.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LinkPreviewsConfig {
+    /// Fetch OpenGraph/oEmbed metadata for posted links. The server makes
+    /// the requests, so linked sites see its address rather than users'.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for LinkPreviewsConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// External secret store hook, run once at startup.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecretsConfig {
+    /// Program and arguments printing a JSON object of secrets, keyed by
+    /// their config path (e.g. `{"auth.jwt_secret": "..."}`). Empty
+    /// disables the hook.
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// Kill the command and fail startup after this long.
+    #[serde(default = "default_secrets_command_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            command: Vec::new(),
+            timeout_seconds: default_secrets_command_timeout_seconds(),
+        }
+    }
+}
+
+/// Experimental: several isolated instances served by one process.
+///
+/// Each instance has its own database and storage prefix and is picked by
+/// the request's Host header. Requests for any other host go to the main
+/// instance configured by `[database]` and `[storage]`. The process, the
+/// native media endpoint and the background job workers are shared.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TenantsConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub instances: Vec<TenantConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TenantConfig {
+    /// Host name (without port) this instance answers for.
+    pub host: String,
+    /// Database of this instance. Uses the `[database]` engine and pool
+    /// settings.
+    pub database_url: String,
+    /// Subdirectory of the storage and media paths (and S3 key prefix)
+    /// holding this instance's files. Defaults to the host.
+    #[serde(default)]
+    pub storage_prefix: Option<String>,
+    #[serde(default)]
+    pub public_url: Option<String>,
+    /// Signs this instance's tokens. Defaults to a secret derived from
+    /// `auth.jwt_secret` and the host, so tokens never carry across
+    /// instances.
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+}
+
+impl TenantConfig {
+    pub fn storage_prefix(&self) -> &str {
+        self.storage_prefix.as_deref().unwrap_or(&self.host)
+    }
+}
+
+/// Several servers sharing one database behind a load balancer.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClusterConfig {
+    #[serde(default)]
+    pub backend: ClusterBackend,
+    /// Redis server for the `redis` backend, e.g. `redis://10.0.0.5:6379/0`.
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    #[serde(default)]
+    pub redis_url_file: Option<String>,
+    /// Servers with the same prefix form one cluster.
+    #[serde(default = "default_cluster_key_prefix")]
+    pub key_prefix: String,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            backend: ClusterBackend::default(),
+            redis_url: None,
+            redis_url_file: None,
+            key_prefix: default_cluster_key_prefix(),
+        }
+    }
+}
+
+/// Where the event bus, presence and rate limits live.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum ClusterBackend {
+    /// In this process; the server runs alone.
+    #[default]
+    #[serde(rename = "memory")]
+    Memory,
+    /// Shared with the other servers through Redis.
+    #[serde(rename = "redis")]
+    Redis,
+}
+
+/// Debugging aids for integration developers.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeveloperConfig {
+    /// Record gateway events and expose the admin event firehose.
+    #[serde(default = "default_false")]
+    pub event_firehose: bool,
+    /// Number of recent events kept for replay.
+    #[serde(default = "default_event_log_capacity")]
+    pub event_log_capacity: usize,
+    #[serde(default = "default_event_log_path")]
+    pub event_log_path: String,
+    /// Serve Swagger UI at /api/v1/docs. The OpenAPI document itself is
+    /// always available at /api/v1/openapi.json.
+    #[serde(default = "default_false")]
+    pub swagger_ui: bool,
+}
+
+impl Default for DeveloperConfig {
+    fn default() -> Self {
+        Self {
+            event_firehose: false,
+            event_log_capacity: default_event_log_capacity(),
+            event_log_path: default_event_log_path(),
+            swagger_ui: false,
+        }
+    }
+}
+
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
 /// Generate a cryptographically random hex string of the given length.
@@ -473,6 +1146,9 @@ fn generate_random_hex(len: usize) -> String {
 fn default_server_name() -> String {
     "localhost".into()
 }
+fn default_worker_id() -> u64 {
+    1
+}
 fn default_database_engine() -> DatabaseEngine {
     DatabaseEngine::Sqlite
 }
@@ -527,6 +1203,18 @@ fn default_voice_max_participants() -> u32 {
 fn default_voice_audio_bitrate() -> u32 {
     96_000
 }
+fn default_voice_speaker_forwarding() -> Vec<SpeakerForwardingTierConfig> {
+    vec![
+        SpeakerForwardingTierConfig {
+            min_participants: 10,
+            max_speakers: 6,
+        },
+        SpeakerForwardingTierConfig {
+            min_participants: 25,
+            max_speakers: 4,
+        },
+    ]
+}
 fn default_tls_port() -> u16 {
     8443
 }
@@ -551,6 +1239,9 @@ fn default_acme_cert_name() -> String {
 fn default_acme_renew_interval_seconds() -> u64 {
     43_200
 }
+fn default_acme_dns_propagation_seconds() -> u64 {
+    60
+}
 fn default_retention_interval_seconds() -> u64 {
     3600
 }
@@ -587,6 +1278,76 @@ fn default_auto_backup_interval() -> u64 {
 fn default_max_backups() -> u32 {
     10
 }
+/// Comma separated environment list, blanks dropped.
+fn split_env_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn default_rate_limit_exempt_paths() -> Vec<String> {
+    vec!["/health".to_string()]
+}
+fn default_sqlite_maintenance_interval_seconds() -> u64 {
+    3600
+}
+fn default_incremental_vacuum_pages() -> i64 {
+    2000
+}
+fn default_max_concurrent_uploads() -> u32 {
+    4
+}
+fn default_max_concurrent_downloads() -> u32 {
+    8
+}
+fn default_supporter_grant_days() -> u32 {
+    31
+}
+fn default_ffmpeg_path() -> String {
+    "ffmpeg".into()
+}
+fn default_transcoding_poll_interval_seconds() -> u64 {
+    15
+}
+fn default_transcoding_timeout_seconds() -> u64 {
+    1800 // 30 minutes
+}
+
+fn default_url_feed_refresh_interval_seconds() -> u64 {
+    21600 // 6 hours
+}
+
+fn default_url_feed_cache_path() -> String {
+    "./data/url-denylist-cache.txt".into()
+}
+
+fn default_hash_match_max_distance() -> u32 {
+    paracord_core::hash_matching::DEFAULT_MAX_DISTANCE
+}
+
+fn default_hash_match_service_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_event_log_capacity() -> usize {
+    5000
+}
+
+fn default_event_log_path() -> String {
+    "./data/event-log.jsonl".into()
+}
+fn default_vapid_key_path() -> String {
+    "./data/vapid_private_key.txt".into()
+}
+fn default_secrets_command_timeout_seconds() -> u64 {
+    10
+}
+fn default_cluster_key_prefix() -> String {
+    "paracord".into()
+}
 
 fn looks_like_placeholder_secret(raw: &str) -> bool {
     let normalized = raw.trim().to_ascii_lowercase();
@@ -602,7 +1363,7 @@ fn looks_like_placeholder_secret(raw: &str) -> bool {
         || normalized == "secret"
 }
 
-fn validate_secret_configuration(config: &Config) -> Result<()> {
+pub fn validate_secret_configuration(config: &Config) -> Result<()> {
     let jwt_secret = config.auth.jwt_secret.trim();
     if jwt_secret.len() < 32 || looks_like_placeholder_secret(jwt_secret) {
         anyhow::bail!(
@@ -622,7 +1383,7 @@ fn validate_secret_configuration(config: &Config) -> Result<()> {
 }
 
 /// Generate a commented config file template with the given values filled in.
-fn generate_config_template(config: &Config) -> String {
+pub fn generate_config_template(config: &Config) -> String {
     format!(
         r#"# Paracord Server Configuration
 # Generated automatically on first run. Edit as needed.
@@ -632,14 +1393,30 @@ bind_address = "{bind_address}"
 server_name = "{server_name}"
 # Set explicitly for internet-facing deployments:
 # public_url = "https://your-domain-or-ip:8443"
+# Serve /metrics and /api/v1/admin/* only on this address so they can be
+# firewalled away from the public listener:
+# internal_bind = "127.0.0.1:9090"
+# Unique per node when several servers share one database (0-1023):
+# worker_id = 1
+# ID epoch: "paracord" (2024-01-01) or "discord" (2015-01-01, Discord-compatible
+# IDs). Pick before creating data; it can only move earlier afterwards.
+# snowflake_format = "paracord"
+# snowflake_epoch = 1704067200000
 
 [database]
 engine = "{db_engine}"
 url = "{db_url}"
 max_connections = {max_connections}
+# PostgreSQL only: grow the pool up to this many connections under load and
+# close the extra ones again after 5 minutes idle:
+# adaptive_max_connections = 50
 
 [auth]
 jwt_secret = "{jwt_secret}"
+# Every secret can instead be read from a file, e.g. a Docker or Kubernetes
+# secret: jwt_secret_file, livekit.api_secret_file, s3.secret_access_key_file,
+# at_rest.key_file and so on (or PARACORD_JWT_SECRET_FILE, ...).
+# jwt_secret_file = "/run/secrets/paracord_jwt_secret"
 jwt_expiry_seconds = {jwt_expiry}
 registration_enabled = {registration_enabled}
 # Allow username logins for password auth (in addition to email).
@@ -653,6 +1430,15 @@ require_email = {require_email}
 storage_type = "{storage_type}"
 path = "{storage_path}"
 
+[storage.limits]
+# Per-user attachment transfers, over HTTP and QUIC. 0 = unlimited.
+max_concurrent_uploads = {max_concurrent_uploads}
+max_concurrent_downloads = {max_concurrent_downloads}
+# Bandwidth shared by all of a user's transfers in each direction, in bytes
+# per second. Useful on home connections where voice shares the uplink.
+upload_bytes_per_second = {upload_bytes_per_second}
+download_bytes_per_second = {download_bytes_per_second}
+
 # [s3]
 # # S3-compatible object storage (MinIO, AWS S3, R2, DigitalOcean Spaces, etc.).
 # # Only used when storage.storage_type = "s3".
@@ -682,6 +1468,8 @@ url = "{lk_url}"
 http_url = "{lk_http_url}"
 # Optional public URL sent to clients:
 # public_url = "wss://your-domain-or-ip:8443/livekit"
+# Directory shared with the LiveKit Egress service for voice recordings:
+# egress_output_dir = "./data/recordings"
 
 [federation]
 enabled = {federation_enabled}
@@ -693,6 +1481,9 @@ allow_discovery = {federation_allow_discovery}
 # max_events_per_peer_per_minute = 120
 # Per-peer rate limit for remote user creation (per hour). Set to 0 to disable.
 # max_user_creates_per_peer_per_hour = 100
+# Per-peer bandwidth quota: bytes exchanged (events, media relay, file proxy)
+# per rolling hour. Unset or 0 = no limit.
+# max_bytes_per_peer_per_hour = 2147483648
 
 [network]
 # On Windows, optionally auto-create local firewall allow rules.
@@ -706,10 +1497,17 @@ port = {tls_port}
 cert_path = "{tls_cert}"
 key_path = "{tls_key}"
 auto_generate = {tls_auto}
+# Also serve the API over HTTP/3 (UDP, defaults to the TLS port). Native
+# media listens on UDP too, so set http3_port if [voice] port matches.
+http3 = {tls_http3}
+# http3_port = 8444
 
 [tls.acme]
-# Optional ACME automation (certbot HTTP-01 webroot flow).
+# Optional ACME automation through certbot. "http-01" uses the webroot flow;
+# "dns-01" publishes TXT records instead, for wildcard domains or servers
+# that can't expose port 80.
 enabled = {acme_enabled}
+challenge = "{acme_challenge}"
 client_path = "{acme_client_path}"
 directory_url = "{acme_directory_url}"
 # email = "ops@example.com"
@@ -724,6 +1522,16 @@ auto_renew = {acme_auto_renew}
 renew_interval_seconds = {acme_renew_interval_seconds}
 # additional_args = ["--preferred-challenges", "http"]
 
+[tls.acme.dns]
+# Used when challenge = "dns-01". cloudflare and rfc2136 need the matching
+# certbot plugin and its credentials file; webhook POSTs each TXT record to
+# webhook_url as {{"action", "domain", "record_name", "value"}}.
+provider = "{acme_dns_provider}"
+# credentials_path = "/etc/paracord/cloudflare.ini"
+propagation_seconds = {acme_dns_propagation_seconds}
+# webhook_url = "https://dns-hooks.example.com/acme"
+# webhook_token = "change-me"
+
 [retention]
 # Data retention purge worker. Disabled by default.
 enabled = {retention_enabled}
@@ -744,6 +1552,8 @@ enabled = {at_rest_enabled}
 # Name of environment variable that contains the 32-byte master key
 # (hex or base64 encoded).
 key_env = "{at_rest_key_env}"
+# Or read the master key from a file:
+# key_file = "/run/secrets/paracord_at_rest_key"
 # SQLCipher mode for SQLite (requires SQLCipher-enabled SQLite build).
 encrypt_sqlite = {at_rest_encrypt_sqlite}
 # AES-256-GCM encryption for attachment payload bytes on disk.
@@ -762,6 +1572,134 @@ auto_backup_interval_seconds = {backup_interval}
 include_media = {backup_include_media}
 # Maximum number of backups to keep (oldest are pruned).
 max_backups = {backup_max_backups}
+
+[transcoding]
+# Convert uploaded videos browsers can't play (MKV, MOV, AVI, ...) into
+# H.264 MP4 renditions. Requires ffmpeg. Disabled by default.
+enabled = {transcoding_enabled}
+ffmpeg_path = "{transcoding_ffmpeg_path}"
+poll_interval_seconds = {transcoding_poll_interval}
+# Per-job limit; longer transcodes are marked failed.
+timeout_seconds = {transcoding_timeout}
+
+[client_updates]
+# JSON file listing desktop client releases, served at
+# /api/v1/client/update-manifest. Format:
+# {{ "releases": [{{ "channel": "stable", "platform": "windows-x86_64",
+#   "version": "1.4.0", "url": "...", "signature": "...", "notes": "..." }}] }}
+# manifest_path = "./data/client-updates.json"
+# Reject desktop clients older than this version.
+# minimum_version = "1.0.0"
+
+[url_reputation]
+# Screen links in guild messages against a phishing/invite-scam denylist.
+# "off", "flag" (report to moderators), or "block" (reject the message).
+# Guilds can override this in their bot settings.
+default_level = "{url_default_level}"
+# Plain domain lists or hosts files, merged with domains added through
+# /api/v1/admin/url-denylist.
+# feeds = ["https://example.com/phishing-domains.txt"]
+refresh_interval_seconds = {url_feed_refresh_interval}
+cache_path = "{url_feed_cache_path}"
+
+[hash_matching]
+# Compare image uploads against perceptual hashes added through
+# /api/v1/admin/image-hashes and block close matches.
+enabled = {hash_matching_enabled}
+# Differing bits (out of 64) still treated as the same image.
+max_distance = {hash_matching_max_distance}
+# External matching service; each image is POSTed there and must answer
+# {{ "match": true|false, "reason": "..." }}.
+# service_url = "https://matcher.example.com/v1/match"
+# service_token = ""
+service_timeout_ms = {hash_matching_service_timeout_ms}
+# Reject uploads while the service is unreachable.
+fail_closed = {hash_matching_fail_closed}
+
+[rate_limit]
+# Requests that skip the HTTP rate limiter: client addresses or CIDR
+# ranges, tokens sent in the X-Paracord-RateLimit-Bypass header, and exact
+# paths or prefixes ending in "*".
+# allowlist_ips = ["10.0.0.0/8"]
+# allowlist_tokens = ["long-random-token-for-internal-services"]
+exempt_paths = ["/health"]
+# Token buckets per route class: up to burst requests at once, refilled at
+# per_minute. global and auth are per client address; messages, uploads and
+# api are per user once a request authenticates. Admins can override these
+# at runtime with the rate_limits server setting.
+# [rate_limit.limits]
+# global = {{ burst = 120, per_minute = 7200 }}
+# auth = {{ burst = 60, per_minute = 60 }}
+# messages = {{ burst = 30, per_minute = 120 }}
+# uploads = {{ burst = 10, per_minute = 60 }}
+# api = {{ burst = 200, per_minute = 3000 }}
+
+[sqlite_maintenance]
+# SQLite only: checkpoint and truncate the WAL and run PRAGMA optimize.
+enabled = {sqlite_maintenance_enabled}
+interval_seconds = {sqlite_maintenance_interval}
+# Switch to incremental auto-vacuum (one full VACUUM at the next startup)
+# and release up to incremental_vacuum_pages free pages per run (0 = all).
+incremental_vacuum = {sqlite_incremental_vacuum}
+incremental_vacuum_pages = {sqlite_incremental_vacuum_pages}
+
+[supporters]
+# Grant supporter perks from donation platforms. Payers are matched to
+# accounts by email; each payment adds grant_days of supporter status.
+# kofi_verification_token = ""
+# patreon_webhook_secret = ""
+grant_days = {supporter_grant_days}
+
+[web_push]
+# Notify users without an open client of mentions and DMs through their
+# browser's push service. Needs a subject push services can contact.
+enabled = {web_push_enabled}
+# subject = "mailto:admin@example.com"
+# VAPID key pair; the private key is generated here on first start.
+key_path = "{web_push_key_path}"
+
+[link_previews]
+# Fetch titles, descriptions and images for links posted in messages.
+# Requests to private and loopback addresses are always refused.
+enabled = {link_previews_enabled}
+
+[secrets]
+# Fetch secrets from an external store (Vault, a cloud KMS, ...) at startup.
+# The command prints a JSON object keyed by config path and overrides both
+# inline values and *_file secrets, e.g.
+#   {{"auth.jwt_secret": "...", "livekit.api_secret": "...", "at_rest.key": "..."}}
+# command = ["/usr/local/bin/paracord-secrets", "--env", "production"]
+# timeout_seconds = 10
+
+[tenants]
+# Experimental: host several isolated instances in this process, picked by
+# the request's Host header. Other hosts are served by the main instance.
+enabled = {tenants_enabled}
+# [[tenants.instances]]
+# host = "alpha.example.com"
+# database_url = "sqlite://./data/tenants/alpha.db?mode=rwc"
+# # Files go under <storage.path>/<storage_prefix> (default: the host).
+# storage_prefix = "alpha"
+# public_url = "https://alpha.example.com"
+
+[cluster]
+# Run several servers on one database behind a load balancer. "memory"
+# keeps the event bus, presence and rate limits in this process; "redis"
+# shares them with every server using the same redis_url and key_prefix.
+backend = "{cluster_backend}"
+# redis_url = "redis://127.0.0.1:6379/0"
+key_prefix = "{cluster_key_prefix}"
+
+[developer]
+# Record gateway events (secrets redacted) and expose them to admins at
+# /api/v1/admin/events and /api/v1/admin/events/stream. Disabled by default.
+event_firehose = {event_firehose}
+# Recent events kept on disk for replay.
+event_log_capacity = {event_log_capacity}
+event_log_path = "{event_log_path}"
+# Serve Swagger UI at /api/v1/docs. The OpenAPI document is always served
+# at /api/v1/openapi.json.
+swagger_ui = {swagger_ui}
 "#,
         bind_address = config.server.bind_address,
         server_name = config.server.server_name,
@@ -778,6 +1716,10 @@ max_backups = {backup_max_backups}
         require_email = config.auth.require_email,
         storage_type = config.storage.storage_type,
         storage_path = config.storage.path,
+        max_concurrent_uploads = config.storage.limits.max_concurrent_uploads,
+        max_concurrent_downloads = config.storage.limits.max_concurrent_downloads,
+        upload_bytes_per_second = config.storage.limits.upload_bytes_per_second,
+        download_bytes_per_second = config.storage.limits.download_bytes_per_second,
         media_path = config.media.storage_path,
         max_file_size = config.media.max_file_size,
         p2p_threshold = config.media.p2p_threshold,
@@ -798,6 +1740,7 @@ max_backups = {backup_max_backups}
         tls_cert = config.tls.cert_path,
         tls_key = config.tls.key_path,
         tls_auto = config.tls.auto_generate,
+        tls_http3 = config.tls.http3,
         acme_enabled = config.tls.acme.enabled,
         acme_client_path = config.tls.acme.client_path,
         acme_directory_url = config.tls.acme.directory_url,
@@ -806,6 +1749,9 @@ max_backups = {backup_max_backups}
         acme_serve_http_challenge = config.tls.acme.serve_http_challenge,
         acme_auto_renew = config.tls.acme.auto_renew,
         acme_renew_interval_seconds = config.tls.acme.renew_interval_seconds,
+        acme_challenge = config.tls.acme.challenge.as_str(),
+        acme_dns_provider = config.tls.acme.dns.provider.as_str(),
+        acme_dns_propagation_seconds = config.tls.acme.dns.propagation_seconds,
         retention_enabled = config.retention.enabled,
         retention_interval = config.retention.interval_seconds,
         retention_batch = config.retention.batch_size,
@@ -814,11 +1760,40 @@ max_backups = {backup_max_backups}
         at_rest_encrypt_sqlite = config.at_rest.encrypt_sqlite,
         at_rest_encrypt_files = config.at_rest.encrypt_files,
         at_rest_allow_plaintext = config.at_rest.allow_plaintext_file_reads,
+        web_push_enabled = config.web_push.enabled,
+        link_previews_enabled = config.link_previews.enabled,
+        web_push_key_path = config.web_push.key_path,
         backup_dir = config.backup.backup_dir,
         backup_auto_enabled = config.backup.auto_backup_enabled,
         backup_interval = config.backup.auto_backup_interval_seconds,
         backup_include_media = config.backup.include_media,
         backup_max_backups = config.backup.max_backups,
+        transcoding_enabled = config.transcoding.enabled,
+        transcoding_ffmpeg_path = config.transcoding.ffmpeg_path,
+        transcoding_poll_interval = config.transcoding.poll_interval_seconds,
+        transcoding_timeout = config.transcoding.timeout_seconds,
+        url_default_level = config.url_reputation.default_level.as_str(),
+        url_feed_refresh_interval = config.url_reputation.refresh_interval_seconds,
+        url_feed_cache_path = config.url_reputation.cache_path,
+        hash_matching_enabled = config.hash_matching.enabled,
+        hash_matching_max_distance = config.hash_matching.max_distance,
+        hash_matching_service_timeout_ms = config.hash_matching.service_timeout_ms,
+        hash_matching_fail_closed = config.hash_matching.fail_closed,
+        sqlite_maintenance_enabled = config.sqlite_maintenance.enabled,
+        sqlite_maintenance_interval = config.sqlite_maintenance.interval_seconds,
+        sqlite_incremental_vacuum = config.sqlite_maintenance.incremental_vacuum,
+        sqlite_incremental_vacuum_pages = config.sqlite_maintenance.incremental_vacuum_pages,
+        supporter_grant_days = config.supporters.grant_days,
+        tenants_enabled = config.tenants.enabled,
+        cluster_backend = match config.cluster.backend {
+            ClusterBackend::Memory => "memory",
+            ClusterBackend::Redis => "redis",
+        },
+        cluster_key_prefix = config.cluster.key_prefix,
+        event_firehose = config.developer.event_firehose,
+        event_log_capacity = config.developer.event_log_capacity,
+        event_log_path = config.developer.event_log_path,
+        swagger_ui = config.developer.swagger_ui,
     )
 }
 
@@ -848,346 +1823,651 @@ impl Config {
             config
         };
         let _ = harden_secret_file_permissions(path);
+        apply_env_overrides(&mut config);
+        crate::secrets::resolve(&mut config)?;
+        validate_secret_configuration(&config)?;
+        Ok(config)
+    }
+}
 
-        // Environment variable overrides
-        if let Ok(value) = std::env::var("PARACORD_BIND_ADDRESS") {
-            config.server.bind_address = value;
-        }
-        if let Ok(value) = std::env::var("PARACORD_SERVER_NAME") {
-            config.server.server_name = value;
-        }
-        if let Ok(value) = std::env::var("PARACORD_WEB_DIR") {
-            config.server.web_dir = Some(value);
-        }
-        if let Ok(value) = std::env::var("PARACORD_PUBLIC_URL") {
-            config.server.public_url = Some(value);
+/// Apply the `PARACORD_*` environment overrides on top of the file values.
+pub fn apply_env_overrides(config: &mut Config) {
+    if let Ok(value) = std::env::var("PARACORD_BIND_ADDRESS") {
+        config.server.bind_address = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_SERVER_NAME") {
+        config.server.server_name = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_WEB_DIR") {
+        config.server.web_dir = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_PUBLIC_URL") {
+        config.server.public_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_WORKER_ID") {
+        if let Ok(parsed) = value.trim().parse::<u64>() {
+            config.server.worker_id = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_DATABASE_URL") {
-            config.database.url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_SNOWFLAKE_FORMAT") {
+        if let Some(format) = SnowflakeFormat::parse(&value) {
+            config.server.snowflake_format = format;
         }
-        if let Ok(value) = std::env::var("PARACORD_DATABASE_ENGINE") {
-            let normalized = value.trim().to_ascii_lowercase();
-            match normalized.as_str() {
-                "sqlite" => config.database.engine = DatabaseEngine::Sqlite,
-                "postgres" | "postgresql" => config.database.engine = DatabaseEngine::Postgres,
-                _ => {
-                    tracing::warn!(
-                        "Ignoring invalid PARACORD_DATABASE_ENGINE value '{}'; expected sqlite or postgres",
-                        value
-                    );
-                }
+    }
+    if let Ok(value) = std::env::var("PARACORD_SNOWFLAKE_EPOCH") {
+        let value = value.trim();
+        config.server.snowflake_epoch = if value.is_empty() {
+            None
+        } else {
+            value.parse::<u64>().ok()
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_INTERNAL_BIND") {
+        config.server.internal_bind = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_URL") {
+        config.database.url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_ENGINE") {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "sqlite" => config.database.engine = DatabaseEngine::Sqlite,
+            "postgres" | "postgresql" => config.database.engine = DatabaseEngine::Postgres,
+            _ => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_DATABASE_ENGINE value '{}'; expected sqlite or postgres",
+                    value
+                );
             }
         }
-        if let Ok(value) = std::env::var("PARACORD_DATABASE_MAX_CONNECTIONS") {
-            if let Ok(parsed) = value.parse::<u32>() {
-                config.database.max_connections = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_MAX_CONNECTIONS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.database.max_connections = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_DATABASE_STATEMENT_TIMEOUT_SECS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.database.statement_timeout_secs = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_ADAPTIVE_MAX_CONNECTIONS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.database.adaptive_max_connections = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_DATABASE_IDLE_IN_TRANSACTION_TIMEOUT_SECS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.database.idle_in_transaction_timeout_secs = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_STATEMENT_TIMEOUT_SECS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.database.statement_timeout_secs = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_JWT_SECRET") {
-            config.auth.jwt_secret = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_DATABASE_IDLE_IN_TRANSACTION_TIMEOUT_SECS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.database.idle_in_transaction_timeout_secs = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_JWT_EXPIRY_SECONDS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.auth.jwt_expiry_seconds = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_JWT_SECRET") {
+        config.auth.jwt_secret = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_JWT_EXPIRY_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.auth.jwt_expiry_seconds = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_REGISTRATION_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.auth.registration_enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_REGISTRATION_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.auth.registration_enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_AUTH_ALLOW_USERNAME_LOGIN") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.auth.allow_username_login = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AUTH_ALLOW_USERNAME_LOGIN") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.auth.allow_username_login = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_AUTH_REQUIRE_EMAIL") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.auth.require_email = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AUTH_REQUIRE_EMAIL") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.auth.require_email = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_STORAGE_TYPE") {
-            config.storage.storage_type = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_STORAGE_TYPE") {
+        config.storage.storage_type = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_STORAGE_PATH") {
+        config.storage.path = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_MAX_CONCURRENT_UPLOADS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.storage.limits.max_concurrent_uploads = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_STORAGE_PATH") {
-            config.storage.path = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_MAX_CONCURRENT_DOWNLOADS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.storage.limits.max_concurrent_downloads = parsed;
         }
-        // S3 environment overrides
-        if let Ok(value) = std::env::var("PARACORD_S3_BUCKET") {
-            config.s3.bucket = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_UPLOAD_BYTES_PER_SECOND") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.storage.limits.upload_bytes_per_second = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_REGION") {
-            config.s3.region = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_DOWNLOAD_BYTES_PER_SECOND") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.storage.limits.download_bytes_per_second = parsed;
+        }
+    }
+    // S3 environment overrides
+    if let Ok(value) = std::env::var("PARACORD_S3_BUCKET") {
+        config.s3.bucket = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_REGION") {
+        config.s3.region = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_ENDPOINT_URL") {
+        config.s3.endpoint_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_ACCESS_KEY_ID") {
+        config.s3.access_key_id = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_SECRET_ACCESS_KEY") {
+        config.s3.secret_access_key = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_PREFIX") {
+        config.s3.prefix = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_CDN_URL") {
+        config.s3.cdn_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_S3_FORCE_PATH_STYLE") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.s3.force_path_style = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_ENDPOINT_URL") {
-            config.s3.endpoint_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_MEDIA_STORAGE_PATH") {
+        config.media.storage_path = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_URL") {
+        config.livekit.url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_HTTP_URL") {
+        config.livekit.http_url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_API_KEY") {
+        config.livekit.api_key = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_API_SECRET") {
+        config.livekit.api_secret = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_PUBLIC_URL") {
+        config.livekit.public_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_LIVEKIT_EGRESS_OUTPUT_DIR") {
+        let value = value.trim();
+        config.livekit.egress_output_dir = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_WINDOWS_FIREWALL_AUTO_ALLOW") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.network.windows_firewall_auto_allow = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_ACCESS_KEY_ID") {
-            config.s3.access_key_id = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.tls.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_SECRET_ACCESS_KEY") {
-            config.s3.secret_access_key = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_HTTP3") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.tls.http3 = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_PREFIX") {
-            config.s3.prefix = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_HTTP3_PORT") {
+        if let Ok(parsed) = value.parse::<u16>() {
+            config.tls.http3_port = Some(parsed);
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_CDN_URL") {
-            config.s3.cdn_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.tls.acme.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_S3_FORCE_PATH_STYLE") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.s3.force_path_style = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CLIENT_PATH") {
+        if !value.trim().is_empty() {
+            config.tls.acme.client_path = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_MEDIA_STORAGE_PATH") {
-            config.media.storage_path = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DIRECTORY_URL") {
+        if !value.trim().is_empty() {
+            config.tls.acme.directory_url = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_URL") {
-            config.livekit.url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_EMAIL") {
+        config.tls.acme.email = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DOMAINS") {
+        config.tls.acme.domains = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_WEBROOT_PATH") {
+        if !value.trim().is_empty() {
+            config.tls.acme.webroot_path = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_HTTP_URL") {
-            config.livekit.http_url = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CERT_NAME") {
+        if !value.trim().is_empty() {
+            config.tls.acme.cert_name = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_API_KEY") {
-            config.livekit.api_key = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CERT_SOURCE_PATH") {
+        config.tls.acme.cert_source_path = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_KEY_SOURCE_PATH") {
+        config.tls.acme.key_source_path = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_SERVE_HTTP_CHALLENGE") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.tls.acme.serve_http_challenge = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_API_SECRET") {
-            config.livekit.api_secret = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_AUTO_RENEW") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.tls.acme.auto_renew = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_LIVEKIT_PUBLIC_URL") {
-            config.livekit.public_url = Some(value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_RENEW_INTERVAL_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.tls.acme.renew_interval_seconds = parsed.max(300);
         }
-        if let Ok(value) = std::env::var("PARACORD_WINDOWS_FIREWALL_AUTO_ALLOW") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.network.windows_firewall_auto_allow = parsed;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_ADDITIONAL_ARGS") {
+        config.tls.acme.additional_args = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CHALLENGE") {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "http-01" | "http" => config.tls.acme.challenge = AcmeChallenge::Http01,
+            "dns-01" | "dns" => config.tls.acme.challenge = AcmeChallenge::Dns01,
+            _ => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_TLS_ACME_CHALLENGE value '{}'; expected http-01 or dns-01",
+                    value
+                );
             }
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.tls.enabled = parsed;
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DNS_PROVIDER") {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cloudflare" => config.tls.acme.dns.provider = AcmeDnsProvider::Cloudflare,
+            "rfc2136" => config.tls.acme.dns.provider = AcmeDnsProvider::Rfc2136,
+            "webhook" => config.tls.acme.dns.provider = AcmeDnsProvider::Webhook,
+            _ => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_TLS_ACME_DNS_PROVIDER value '{}'; expected cloudflare, rfc2136, or webhook",
+                    value
+                );
             }
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.tls.acme.enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DNS_CREDENTIALS_PATH") {
+        config.tls.acme.dns.credentials_path = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DNS_PROPAGATION_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.tls.acme.dns.propagation_seconds = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CLIENT_PATH") {
-            if !value.trim().is_empty() {
-                config.tls.acme.client_path = value;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DNS_WEBHOOK_URL") {
+        config.tls.acme.dns.webhook_url = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DNS_WEBHOOK_TOKEN") {
+        config.tls.acme.dns.webhook_token = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value)
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.federation.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DIRECTORY_URL") {
-            if !value.trim().is_empty() {
-                config.tls.acme.directory_url = value;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_DOMAIN") {
+        if !value.trim().is_empty() {
+            config.federation.domain = Some(value);
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_EMAIL") {
-            config.tls.acme.email = if value.trim().is_empty() {
-                None
-            } else {
-                Some(value)
-            };
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_SIGNING_KEY_PATH") {
+        let trimmed = value.trim();
+        config.federation.signing_key_path = if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        };
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_ALLOW_DISCOVERY") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.federation.allow_discovery = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_DOMAINS") {
-            config.tls.acme.domains = value
-                .split(',')
-                .map(str::trim)
-                .filter(|entry| !entry.is_empty())
-                .map(str::to_string)
-                .collect();
-        }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_WEBROOT_PATH") {
-            if !value.trim().is_empty() {
-                config.tls.acme.webroot_path = value;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_MAX_EVENTS_PER_PEER_PER_MINUTE") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.federation.max_events_per_peer_per_minute = Some(parsed);
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CERT_NAME") {
-            if !value.trim().is_empty() {
-                config.tls.acme.cert_name = value;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_MAX_USER_CREATES_PER_PEER_PER_HOUR") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.federation.max_user_creates_per_peer_per_hour = Some(parsed);
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_CERT_SOURCE_PATH") {
-            config.tls.acme.cert_source_path = if value.trim().is_empty() {
-                None
-            } else {
-                Some(value)
-            };
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_MAX_BYTES_PER_PEER_PER_HOUR") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.federation.max_bytes_per_peer_per_hour = Some(parsed);
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_KEY_SOURCE_PATH") {
-            config.tls.acme.key_source_path = if value.trim().is_empty() {
-                None
-            } else {
-                Some(value)
-            };
+    }
+    if let Ok(value) = std::env::var("PARACORD_MAX_GUILD_STORAGE_QUOTA") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.storage.max_guild_storage_quota = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_SERVE_HTTP_CHALLENGE") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.tls.acme.serve_http_challenge = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.federation.file_cache_enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_AUTO_RENEW") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.tls.acme.auto_renew = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_MAX_SIZE") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.federation.file_cache_max_size = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_RENEW_INTERVAL_SECONDS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.tls.acme.renew_interval_seconds = parsed.max(300);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_TTL_HOURS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.federation.file_cache_ttl_hours = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_TLS_ACME_ADDITIONAL_ARGS") {
-            config.tls.acme.additional_args = value
-                .split(',')
-                .map(str::trim)
-                .filter(|entry| !entry.is_empty())
-                .map(str::to_string)
-                .collect();
-        }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.federation.enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.retention.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_DOMAIN") {
-            if !value.trim().is_empty() {
-                config.federation.domain = Some(value);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_INTERVAL_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.retention.interval_seconds = parsed.max(60);
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_SIGNING_KEY_PATH") {
-            let trimmed = value.trim();
-            config.federation.signing_key_path = if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed.to_string())
-            };
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_BATCH_SIZE") {
+        if let Ok(parsed) = value.parse::<i64>() {
+            config.retention.batch_size = parsed.clamp(1, 10_000);
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_ALLOW_DISCOVERY") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.federation.allow_discovery = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_MESSAGE_DAYS") {
+        config.retention.message_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_ATTACHMENT_DAYS") {
+        config.retention.attachment_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_AUDIT_LOG_DAYS") {
+        config.retention.audit_log_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_SECURITY_EVENT_DAYS") {
+        config.retention.security_event_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RETENTION_SESSION_DAYS") {
+        config.retention.session_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_AT_REST_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.at_rest.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_MAX_EVENTS_PER_PEER_PER_MINUTE") {
-            if let Ok(parsed) = value.parse::<u32>() {
-                config.federation.max_events_per_peer_per_minute = Some(parsed);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AT_REST_KEY_ENV") {
+        if !value.trim().is_empty() {
+            config.at_rest.key_env = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_MAX_USER_CREATES_PER_PEER_PER_HOUR") {
-            if let Ok(parsed) = value.parse::<u32>() {
-                config.federation.max_user_creates_per_peer_per_hour = Some(parsed);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AT_REST_ENCRYPT_SQLITE") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.at_rest.encrypt_sqlite = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_MAX_GUILD_STORAGE_QUOTA") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.storage.max_guild_storage_quota = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AT_REST_ENCRYPT_FILES") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.at_rest.encrypt_files = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.federation.file_cache_enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_AT_REST_ALLOW_PLAINTEXT_FILE_READS") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.at_rest.allow_plaintext_file_reads = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_MAX_SIZE") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.federation.file_cache_max_size = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_BACKUP_DIR") {
+        config.backup.backup_dir = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_BACKUP_AUTO_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.backup.auto_backup_enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_FEDERATION_FILE_CACHE_TTL_HOURS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.federation.file_cache_ttl_hours = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_BACKUP_INTERVAL_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.backup.auto_backup_interval_seconds = parsed.max(3600);
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.retention.enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_BACKUP_INCLUDE_MEDIA") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.backup.include_media = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_INTERVAL_SECONDS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.retention.interval_seconds = parsed.max(60);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_BACKUP_MAX_BACKUPS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.backup.max_backups = parsed.clamp(1, 100);
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_BATCH_SIZE") {
-            if let Ok(parsed) = value.parse::<i64>() {
-                config.retention.batch_size = parsed.clamp(1, 10_000);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_TRANSCODING_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.transcoding.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_MESSAGE_DAYS") {
-            config.retention.message_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_FFMPEG_PATH") {
+        if !value.trim().is_empty() {
+            config.transcoding.ffmpeg_path = value;
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_ATTACHMENT_DAYS") {
-            config.retention.attachment_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_TRANSCODING_TIMEOUT_SECONDS") {
+        if let Ok(parsed) = value.parse::<u64>() {
+            config.transcoding.timeout_seconds = parsed.max(30);
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_AUDIT_LOG_DAYS") {
-            config.retention.audit_log_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_CLIENT_UPDATE_MANIFEST") {
+        let value = value.trim();
+        config.client_updates.manifest_path = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_MIN_CLIENT_VERSION") {
+        let value = value.trim();
+        config.client_updates.minimum_version = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_URL_FILTER_DEFAULT_LEVEL") {
+        if let Some(level) = UrlFilterLevel::parse(&value) {
+            config.url_reputation.default_level = level;
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_SECURITY_EVENT_DAYS") {
-            config.retention.security_event_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_URL_DENYLIST_FEEDS") {
+        config.url_reputation.feeds = split_env_list(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_HASH_MATCHING_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.hash_matching.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_RETENTION_SESSION_DAYS") {
-            config.retention.session_days = parse_optional_days(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_HASH_MATCHING_MAX_DISTANCE") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.hash_matching.max_distance = parsed.min(32);
         }
-        if let Ok(value) = std::env::var("PARACORD_AT_REST_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.at_rest.enabled = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_HASH_MATCHING_SERVICE_URL") {
+        let value = value.trim();
+        config.hash_matching.service_url = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_HASH_MATCHING_SERVICE_TOKEN") {
+        let value = value.trim();
+        config.hash_matching.service_token = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_HASH_MATCHING_FAIL_CLOSED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.hash_matching.fail_closed = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_AT_REST_KEY_ENV") {
-            if !value.trim().is_empty() {
-                config.at_rest.key_env = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_ALLOWLIST_IPS") {
+        config.rate_limit.allowlist_ips = split_env_list(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_ALLOWLIST_TOKENS") {
+        config.rate_limit.allowlist_tokens = split_env_list(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RATE_LIMIT_EXEMPT_PATHS") {
+        config.rate_limit.exempt_paths = split_env_list(&value);
+    }
+    if let Ok(value) = std::env::var("PARACORD_RATE_LIMITS") {
+        match paracord_api::rate_limit::parse_limits(&value) {
+            Ok(limits) => {
+                for (class, limit) in limits {
+                    config.rate_limit.limits.insert(
+                        class.name().to_string(),
+                        RateLimitRule {
+                            burst: limit.burst,
+                            per_minute: limit.per_minute,
+                        },
+                    );
+                }
             }
-        }
-        if let Ok(value) = std::env::var("PARACORD_AT_REST_ENCRYPT_SQLITE") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.at_rest.encrypt_sqlite = parsed;
+            Err(err) => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_RATE_LIMITS value '{}': {}",
+                    value,
+                    err
+                );
             }
         }
-        if let Ok(value) = std::env::var("PARACORD_AT_REST_ENCRYPT_FILES") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.at_rest.encrypt_files = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_KOFI_VERIFICATION_TOKEN") {
+        let value = value.trim();
+        config.supporters.kofi_verification_token = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_PATREON_WEBHOOK_SECRET") {
+        let value = value.trim();
+        config.supporters.patreon_webhook_secret = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_SUPPORTER_GRANT_DAYS") {
+        if let Ok(parsed) = value.parse::<u32>() {
+            config.supporters.grant_days = parsed.max(1);
         }
-        if let Ok(value) = std::env::var("PARACORD_AT_REST_ALLOW_PLAINTEXT_FILE_READS") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.at_rest.allow_plaintext_file_reads = parsed;
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_WEB_PUSH_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.web_push.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_BACKUP_DIR") {
-            config.backup.backup_dir = value;
+    }
+    if let Ok(value) = std::env::var("PARACORD_WEB_PUSH_SUBJECT") {
+        let value = value.trim();
+        config.web_push.subject = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_VAPID_PRIVATE_KEY") {
+        let value = value.trim();
+        config.web_push.private_key = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_LINK_PREVIEWS_ENABLED") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.link_previews.enabled = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_BACKUP_AUTO_ENABLED") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.backup.auto_backup_enabled = parsed;
+    }
+    if let Ok(value) = std::env::var("PARACORD_CLUSTER_BACKEND") {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "memory" => config.cluster.backend = ClusterBackend::Memory,
+            "redis" => config.cluster.backend = ClusterBackend::Redis,
+            _ => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_CLUSTER_BACKEND value '{}'; expected memory or redis",
+                    value
+                );
             }
         }
-        if let Ok(value) = std::env::var("PARACORD_BACKUP_INTERVAL_SECONDS") {
-            if let Ok(parsed) = value.parse::<u64>() {
-                config.backup.auto_backup_interval_seconds = parsed.max(3600);
-            }
+    }
+    if let Ok(value) = std::env::var("PARACORD_REDIS_URL") {
+        let value = value.trim();
+        config.cluster.redis_url = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_CLUSTER_KEY_PREFIX") {
+        let value = value.trim();
+        if !value.is_empty() {
+            config.cluster.key_prefix = value.to_string();
         }
-        if let Ok(value) = std::env::var("PARACORD_BACKUP_INCLUDE_MEDIA") {
-            if let Ok(parsed) = value.parse::<bool>() {
-                config.backup.include_media = parsed;
-            }
+    }
+    crate::secrets::apply_env_overrides(config);
+    if let Ok(value) = std::env::var("PARACORD_EVENT_FIREHOSE") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.developer.event_firehose = parsed;
+        }
+    }
+    if let Ok(value) = std::env::var("PARACORD_EVENT_LOG_CAPACITY") {
+        if let Ok(parsed) = value.parse::<usize>() {
+            config.developer.event_log_capacity = parsed.max(1);
+        }
+    }
+    if let Ok(value) = std::env::var("PARACORD_SWAGGER_UI") {
+        if let Ok(parsed) = value.parse::<bool>() {
+            config.developer.swagger_ui = parsed;
         }
-        if let Ok(value) = std::env::var("PARACORD_BACKUP_MAX_BACKUPS") {
-            if let Ok(parsed) = value.parse::<u32>() {
-                config.backup.max_backups = parsed.clamp(1, 100);
+    }
+    if let Ok(value) = std::env::var("PARACORD_VOICE_WEBRTC_GATEWAY_URL") {
+        let value = value.trim();
+        config.voice.webrtc_gateway_url = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_VOICE_CERT") {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "self-signed" => config.voice.cert = MediaCertSource::SelfSigned,
+            "server" => config.voice.cert = MediaCertSource::Server,
+            "files" => config.voice.cert = MediaCertSource::Files,
+            _ => {
+                tracing::warn!(
+                    "Ignoring invalid PARACORD_VOICE_CERT value '{}'; expected self-signed, server, or files",
+                    value
+                );
             }
         }
-
-        validate_secret_configuration(&config)?;
-        Ok(config)
+    }
+    if let Ok(value) = std::env::var("PARACORD_VOICE_CERT_PATH") {
+        let value = value.trim();
+        config.voice.cert_path = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_VOICE_KEY_PATH") {
+        let value = value.trim();
+        config.voice.key_path = (!value.is_empty()).then(|| value.to_string());
+    }
+    if let Ok(value) = std::env::var("PARACORD_VOICE_CERT_SANS") {
+        config.voice.cert_sans = split_env_list(&value);
     }
 }
 
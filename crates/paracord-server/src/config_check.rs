@@ -0,0 +1,558 @@
+//! `paracord-server check`: validate a config file without starting the server.
+//!
+//! The file is parsed against the typed [`Config`] schema, keys the schema
+//! doesn't know are reported as warnings (serde ignores them silently at
+//! startup), and settings that only make sense together are checked against
+//! each other. The effective configuration, after `PARACORD_*` environment
+//! overrides, is printed with secrets redacted. Any error makes the command
+//! exit non-zero.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::config::{self, AcmeChallenge, ClusterBackend, Config, DatabaseEngine, MediaCertSource};
+
+const REDACTED: &str = "<redacted>";
+
+/// Dotted paths of values never printed.
+const SECRET_KEYS: &[&str] = &[
+    "auth.jwt_secret",
+    "livekit.api_secret",
+    "s3.secret_access_key",
+    "tls.acme.dns.webhook_token",
+    "hash_matching.service_token",
+    "rate_limit.allowlist_tokens",
+    "supporters.kofi_verification_token",
+    "supporters.patreon_webhook_secret",
+    "web_push.private_key",
+    "cluster.redis_url",
+];
+
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    /// Effective configuration with secrets redacted; `None` when the file
+    /// could not be parsed.
+    pub effective: Option<String>,
+}
+
+impl CheckReport {
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(message.into());
+    }
+}
+
+/// Check the config file at `path` and print the report.
+pub fn run(path: &str) -> Result<()> {
+    let report = check_file(path);
+    if let Some(effective) = &report.effective {
+        println!("{effective}");
+    }
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for error in &report.errors {
+        eprintln!("error: {error}");
+    }
+    if !report.errors.is_empty() {
+        anyhow::bail!(
+            "{path}: {} error(s), {} warning(s)",
+            report.errors.len(),
+            report.warnings.len()
+        );
+    }
+    eprintln!("{path}: OK ({} warning(s))", report.warnings.len());
+    Ok(())
+}
+
+/// Unlike [`Config::load`], a missing file is an error rather than a
+/// generated template.
+pub fn check_file(path: &str) -> CheckReport {
+    match std::fs::read_to_string(path) {
+        Ok(content) => check_str(&content, true),
+        Err(err) => CheckReport {
+            errors: vec![format!("cannot read {path}: {err}")],
+            ..CheckReport::default()
+        },
+    }
+}
+
+/// `apply_env` layers the `PARACORD_*` overrides on before the cross-field
+/// checks, as startup does.
+pub fn check_str(content: &str, apply_env: bool) -> CheckReport {
+    let mut report = CheckReport::default();
+    let raw: toml::Value = match toml::from_str(content) {
+        Ok(raw) => raw,
+        Err(err) => {
+            report.error(format!("invalid TOML: {err}"));
+            return report;
+        }
+    };
+    let mut config: Config = match toml::from_str(content) {
+        Ok(config) => config,
+        Err(err) => {
+            report.error(format!("schema: {err}"));
+            return report;
+        }
+    };
+
+    // Every key the schema knows is present after a round trip, except
+    // unset optional ones, and those can't be in `raw` either.
+    match toml::Value::try_from(&config) {
+        Ok(known) => {
+            let mut unknown = Vec::new();
+            collect_unknown_keys(&raw, &known, "", &mut unknown);
+            for key in unknown {
+                report.warn(format!("unknown key `{key}` is ignored"));
+            }
+        }
+        Err(err) => report.error(format!("cannot serialize configuration: {err}")),
+    }
+
+    if apply_env {
+        config::apply_env_overrides(&mut config);
+    }
+    if let Err(err) = crate::secrets::load_secret_files(&mut config) {
+        report.error(format!("{err:#}"));
+    }
+    // The secrets command may reach external services; only run it for a
+    // real check.
+    if apply_env {
+        if let Err(err) = crate::secrets::run_secrets_command(&mut config) {
+            report.error(format!("{err:#}"));
+        }
+    }
+    check_cross_field(&config, &mut report);
+
+    match toml::Value::try_from(&config) {
+        Ok(mut effective) => {
+            redact_secrets(&mut effective);
+            report.effective = toml::to_string_pretty(&effective).ok();
+        }
+        Err(err) => report.error(format!("cannot serialize configuration: {err}")),
+    }
+    report
+}
+
+fn collect_unknown_keys(
+    raw: &toml::Value,
+    known: &toml::Value,
+    prefix: &str,
+    out: &mut Vec<String>,
+) {
+    match (raw, known) {
+        (toml::Value::Table(raw), toml::Value::Table(known)) => {
+            for (key, value) in raw {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match known.get(key) {
+                    Some(known_value) => collect_unknown_keys(value, known_value, &path, out),
+                    None => out.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(raw), toml::Value::Array(known)) => {
+            for (index, (value, known_value)) in raw.iter().zip(known).enumerate() {
+                collect_unknown_keys(value, known_value, &format!("{prefix}[{index}]"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_secrets(effective: &mut toml::Value) {
+    for key in SECRET_KEYS {
+        let mut segments = key.split('.').peekable();
+        let mut table = effective.as_table_mut();
+        while let (Some(segment), Some(current)) = (segments.next(), table) {
+            if segments.peek().is_none() {
+                if let Some(value) = current.get_mut(segment) {
+                    redact_value(value);
+                }
+                break;
+            }
+            table = current.get_mut(segment).and_then(toml::Value::as_table_mut);
+        }
+    }
+    if let Some(instances) = effective
+        .get_mut("tenants")
+        .and_then(|tenants| tenants.get_mut("instances"))
+        .and_then(toml::Value::as_array_mut)
+    {
+        for instance in instances {
+            if let Some(secret) = instance.get_mut("jwt_secret") {
+                redact_value(secret);
+            }
+        }
+    }
+}
+
+fn redact_value(value: &mut toml::Value) {
+    match value {
+        toml::Value::String(secret) if !secret.is_empty() => *secret = REDACTED.to_string(),
+        toml::Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn is_set(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.trim().is_empty())
+}
+
+fn check_cross_field(config: &Config, report: &mut CheckReport) {
+    if let Err(err) = config::validate_secret_configuration(config) {
+        report.error(err.to_string());
+    }
+    if config.server.worker_id > paracord_util::snowflake::MAX_WORKER_ID as u64 {
+        report.error(format!(
+            "server.worker_id must be between 0 and {}",
+            paracord_util::snowflake::MAX_WORKER_ID
+        ));
+    }
+
+    let url = config.database.url.trim_start();
+    let postgres_url = url.starts_with("postgres://") || url.starts_with("postgresql://");
+    match config.database.engine {
+        DatabaseEngine::Sqlite if !url.starts_with("sqlite:") => {
+            report.error("database.engine = \"sqlite\" requires a sqlite: database.url")
+        }
+        DatabaseEngine::Postgres if !postgres_url => report.error(
+            "database.engine = \"postgres\" requires a postgres:// or postgresql:// database.url",
+        ),
+        _ => {}
+    }
+
+    match config.storage.storage_type.as_str() {
+        "local" => {}
+        "s3" => {
+            if config.s3.bucket.trim().is_empty() {
+                report.error("storage.storage_type = \"s3\" requires s3.bucket");
+            }
+        }
+        other => report.error(format!(
+            "storage.storage_type must be \"local\" or \"s3\", got \"{other}\""
+        )),
+    }
+
+    if config.voice.cert == MediaCertSource::Files
+        && !(is_set(config.voice.cert_path.as_deref()) && is_set(config.voice.key_path.as_deref()))
+    {
+        report.error("voice.cert = \"files\" requires voice.cert_path and voice.key_path");
+    }
+
+    if let Err(err) = config.rate_limit.route_limits() {
+        report.error(err);
+    }
+
+    check_tls(config, report);
+    check_federation(config, report);
+    check_web_push(config, report);
+    check_tenants(config, report);
+    check_cluster(config, report);
+}
+
+fn check_tls(config: &Config, report: &mut CheckReport) {
+    let tls = &config.tls;
+    if tls.acme.enabled && !tls.enabled {
+        report.error("tls.acme.enabled requires tls.enabled; ACME would never run");
+    }
+    if tls.http3 && !tls.enabled {
+        report.error("tls.http3 requires tls.enabled");
+    }
+    if tls.enabled && tls.http3 && config.voice.native_media {
+        let http3_port = tls.http3_port.unwrap_or(tls.port);
+        if http3_port == config.voice.port {
+            report.warn(format!(
+                "HTTP/3 stays off: UDP port {http3_port} is also voice.port; set tls.http3_port"
+            ));
+        }
+    }
+    if !tls.enabled {
+        return;
+    }
+    if tls.acme.enabled {
+        if let Err(err) = crate::tls::validate_acme_config(tls) {
+            report.error(format!("{err:#}"));
+        }
+        if tls.acme.challenge == AcmeChallenge::Http01 {
+            if let Some(domain) = tls.acme.domains.iter().find(|d| d.trim().starts_with("*.")) {
+                report.error(format!(
+                    "tls.acme.domains contains wildcard {domain}; wildcards need tls.acme.challenge = \"dns-01\""
+                ));
+            }
+        }
+        if !is_set(tls.acme.email.as_deref()) {
+            report.warn("tls.acme.email is unset; expiry notices can't be delivered");
+        }
+    } else if !tls.auto_generate {
+        for (key, path) in [
+            ("tls.cert_path", &tls.cert_path),
+            ("tls.key_path", &tls.key_path),
+        ] {
+            if !Path::new(path).is_file() {
+                report.error(format!(
+                    "{key} {path:?} does not exist and tls.auto_generate and tls.acme are off"
+                ));
+            }
+        }
+    }
+}
+
+fn check_federation(config: &Config, report: &mut CheckReport) {
+    let federation = &config.federation;
+    if !federation.enabled {
+        if federation.allow_discovery {
+            report.warn("federation.allow_discovery has no effect while federation is disabled");
+        }
+        return;
+    }
+
+    let domain = federation
+        .domain
+        .as_deref()
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+        .unwrap_or(config.server.server_name.trim());
+    if domain == "localhost" || !domain.contains('.') {
+        report.warn(format!(
+            "federation domain {domain:?} isn't reachable by peers; set federation.domain"
+        ));
+    }
+    match config.server.public_url.as_deref().map(str::trim) {
+        Some(url) if url.starts_with("https://") => {}
+        Some(url) if !url.is_empty() => report.error(format!(
+            "federation requires an https server.public_url, got {url:?}"
+        )),
+        _ => report.warn("federation is enabled without server.public_url"),
+    }
+
+    if let Some(key_path) = federation
+        .signing_key_path
+        .as_deref()
+        .filter(|path| !path.trim().is_empty())
+    {
+        // A missing key is generated at startup; an unreadable one is fatal.
+        if Path::new(key_path).exists() {
+            let valid = std::fs::read_to_string(key_path).is_ok_and(|raw| {
+                paracord_federation::signing::signing_key_from_hex(raw.trim()).is_ok()
+            });
+            if !valid {
+                report.error(format!(
+                    "federation.signing_key_path {key_path:?} is not a hex ed25519 private key"
+                ));
+            }
+        }
+    }
+}
+
+fn check_web_push(config: &Config, report: &mut CheckReport) {
+    let web_push = &config.web_push;
+    if !web_push.enabled {
+        return;
+    }
+    if let Err(err) = crate::web_push::validate_subject(web_push.subject.as_deref()) {
+        report.error(format!("{err:#}"));
+    }
+    let (source, raw) = match web_push.private_key.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => ("web_push.private_key", raw.to_string()),
+        // A missing key file is generated at startup.
+        _ => match std::fs::read_to_string(&web_push.key_path) {
+            Ok(raw) => ("web_push.key_path", raw),
+            Err(_) => return,
+        },
+    };
+    if crate::web_push::VapidKeys::from_private_key(&raw).is_err() {
+        report.error(format!("{source} is not a base64url P-256 private key"));
+    }
+}
+
+fn check_tenants(config: &Config, report: &mut CheckReport) {
+    if !config.tenants.enabled {
+        return;
+    }
+    if config.tenants.instances.is_empty() {
+        report.warn("tenants.enabled is set but no [[tenants.instances]] are configured");
+    }
+    for problem in crate::tenants::problems(config) {
+        report.error(problem);
+    }
+}
+
+fn check_cluster(config: &Config, report: &mut CheckReport) {
+    let cluster = &config.cluster;
+    if cluster.backend != ClusterBackend::Redis {
+        return;
+    }
+    match cluster.redis_url.as_deref().map(str::trim) {
+        None | Some("") => report.error("cluster.backend = \"redis\" requires cluster.redis_url"),
+        Some(url) if !(url.starts_with("redis://") || url.starts_with("rediss://")) => {
+            report.error("cluster.redis_url must start with redis:// or rediss://")
+        }
+        _ => {}
+    }
+    if cluster.key_prefix.trim().is_empty() {
+        report.error("cluster.key_prefix must not be empty");
+    }
+    if config.database.engine == DatabaseEngine::Sqlite {
+        report.warn("cluster.backend = \"redis\" with SQLite: every server needs the same database, so use PostgreSQL");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+[server]
+bind_address = "0.0.0.0:8080"
+
+[database]
+url = "sqlite://./data/paracord.db?mode=rwc"
+
+[auth]
+jwt_secret = "0123456789abcdef0123456789abcdef"
+
+[storage]
+
+[livekit]
+api_key = "paracord_0123abcd"
+api_secret = "f00dfeedf00dfeedf00dfeedf00dfeed"
+
+[federation]
+enabled = false
+"#;
+
+    #[test]
+    fn generated_template_passes_without_warnings() {
+        let template = config::generate_config_template(&Config::default());
+        let report = check_str(&template, false);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(
+            report.warnings.iter().all(|w| !w.contains("unknown key")),
+            "{:?}",
+            report.warnings
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings_with_their_path() {
+        let report = check_str(&format!(
+            "{BASE}\n[tls]\nenabled = false\ncert_pth = \"x\"\n\n[voice]\n[[voice.speaker_forwarding]]\nmin_participants = 0\nmax_speakers = 4\nmax_speekers = 2\n\n[typo]\nx = 1\n"
+        ), false);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.warnings.iter().any(|w| w.contains("`tls.cert_pth`")));
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.contains("`voice.speaker_forwarding[0].max_speekers`")));
+        assert!(report.warnings.iter().any(|w| w.contains("`typo`")));
+    }
+
+    #[test]
+    fn cross_field_errors_fail_the_check() {
+        let report = check_str(
+            &format!("{BASE}\n[tls]\nenabled = false\n[tls.acme]\nenabled = true\n"),
+            false,
+        );
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.contains("tls.acme.enabled requires tls.enabled")));
+
+        let report = check_str(
+            &format!("{BASE}\n[tls.acme]\nenabled = true\ndomains = [\"*.example.com\"]\n"),
+            false,
+        );
+        assert!(report.errors.iter().any(|e| e.contains("wildcard")));
+
+        let report = check_str(&BASE.replace("enabled = false", "enabled = true"), false);
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.contains("federation domain")));
+
+        let report = check_str(
+            &format!("{BASE}\n[web_push]\nenabled = true\nsubject = \"ops@example.com\"\nprivate_key = \"nope\"\n"),
+            false,
+        );
+        assert!(report.errors.iter().any(|e| e.contains("web_push.subject")));
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.contains("web_push.private_key is not")));
+
+        let report = check_str(
+            &format!(
+                "{BASE}\n[supporters]\nkofi_verification_token_file = \"/nonexistent/kofi\"\n"
+            ),
+            false,
+        );
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.contains("supporters.kofi_verification_token")));
+
+        let report = check_str(
+            &format!(
+                "{BASE}\n[tenants]\nenabled = true\n[[tenants.instances]]\nhost = \"alpha.example.com:8080\"\ndatabase_url = \"sqlite://./data/paracord.db?mode=rwc\"\nstorage_prefix = \"alpha\"\njwt_secret = \"short\"\n"
+            ),
+            false,
+        );
+        for expected in [
+            "instances[0].host",
+            "instances[0].database_url",
+            "instances[0].jwt_secret",
+        ] {
+            assert!(
+                report.errors.iter().any(|e| e.contains(expected)),
+                "{expected}: {:?}",
+                report.errors
+            );
+        }
+        assert!(!report.effective.unwrap().contains("\"short\""));
+
+        let report = check_str(&format!("{BASE}\n[cluster]\nbackend = \"redis\"\n"), false);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.contains("requires cluster.redis_url")));
+        let report = check_str(
+            &format!("{BASE}\n[cluster]\nbackend = \"redis\"\nredis_url = \"redis://:hunter2@cache:6379/0\"\n"),
+            false,
+        );
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.warnings.iter().any(|w| w.contains("PostgreSQL")));
+        assert!(!report.effective.unwrap().contains("hunter2"));
+    }
+
+    #[test]
+    fn schema_errors_are_reported_without_output() {
+        let report = check_str(&format!("{BASE}\n[tls]\nport = \"eighty\"\n"), false);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("port"), "{}", report.errors[0]);
+        assert!(report.effective.is_none());
+    }
+
+    #[test]
+    fn effective_configuration_redacts_secrets() {
+        let report = check_str(
+            &format!("{BASE}\n[rate_limit]\nallowlist_tokens = [\"bypass-token\"]\n"),
+            false,
+        );
+        let effective = report.effective.expect("effective config");
+        assert!(!effective.contains("0123456789abcdef0123456789abcdef"));
+        assert!(!effective.contains("f00dfeed"));
+        assert!(!effective.contains("bypass-token"));
+        assert!(effective.contains(REDACTED));
+        assert!(effective.contains("paracord_0123abcd"));
+    }
+}
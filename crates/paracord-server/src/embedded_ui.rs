@@ -1,47 +1,74 @@
-use axum::http::{header, StatusCode, Uri};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
+use rust_embed::EmbeddedFile;
 
 #[derive(rust_embed::Embed)]
 #[folder = "../../client/dist/"]
 struct WebAssets;
 
+/// Pre-compressed siblings written by `npm run build:server`
+/// (`client/scripts/precompress.mjs`), in order of preference.
+const PRECOMPRESSED: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
 pub fn router() -> axum::Router {
     axum::Router::new().fallback(serve_embedded)
 }
 
-async fn serve_embedded(uri: Uri) -> Response {
+async fn serve_embedded(uri: Uri, headers: HeaderMap) -> Response {
     let path = uri.path().trim_start_matches('/');
 
     // Try the exact path first
     if let Some(content) = WebAssets::get(path) {
-        let mime = mime_guess::from_path(path).first_or_octet_stream();
-        return (
-            StatusCode::OK,
-            [
-                (header::CONTENT_TYPE, mime.as_ref().to_string()),
-                (header::CACHE_CONTROL, cache_control(path).to_string()),
-            ],
-            content.data.into_owned(),
-        )
-            .into_response();
+        return serve_file(path, content, &headers);
     }
 
     // SPA fallback: serve index.html for all non-file routes
     match WebAssets::get("index.html") {
-        Some(content) => (
-            StatusCode::OK,
-            [
-                (header::CONTENT_TYPE, "text/html".to_string()),
-                (header::CACHE_CONTROL, "no-cache".to_string()),
-            ],
-            content.data.into_owned(),
-        )
-            .into_response(),
+        Some(content) => serve_file("index.html", content, &headers),
         None => (StatusCode::NOT_FOUND, "Web UI not found").into_response(),
     }
 }
 
+fn serve_file(path: &str, content: EmbeddedFile, headers: &HeaderMap) -> Response {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let (encoding, content) = PRECOMPRESSED
+        .iter()
+        .filter(|(encoding, _)| accepts_encoding(headers, encoding))
+        .find_map(|(encoding, ext)| {
+            WebAssets::get(&format!("{path}.{ext}")).map(|file| (Some(*encoding), file))
+        })
+        .unwrap_or((None, content));
+    let etag = entity_tag(&content, encoding);
+
+    let mut response = if if_none_match(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let mut response = (StatusCode::OK, content.data.into_owned()).into_response();
+        if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+        if let Some(encoding) = encoding {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+        response
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control(path)),
+    );
+    response_headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    response
+}
+
 /// Assets with hashes in their filename can be cached aggressively.
+/// Everything else (notably index.html) is revalidated against its ETag.
 fn cache_control(path: &str) -> &'static str {
     if path.starts_with("assets/") {
         "public, max-age=31536000, immutable"
@@ -49,3 +76,94 @@ fn cache_control(path: &str) -> &'static str {
         "no-cache"
     }
 }
+
+/// Strong ETag from the build-time content hash, distinct per encoding.
+fn entity_tag(content: &EmbeddedFile, encoding: Option<&str>) -> String {
+    let hash: String = content.metadata.sha256_hash()[..12]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    match encoding {
+        Some(encoding) => format!("\"{hash}-{encoding}\""),
+        None => format!("\"{hash}\""),
+    }
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// Whether `Accept-Encoding` lists `encoding` without `q=0`.
+fn accepts_encoding(headers: &HeaderMap, encoding: &str) -> bool {
+    for value in headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+    {
+        for item in value.split(',') {
+            let mut params = item.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            if !name.eq_ignore_ascii_case(encoding) {
+                continue;
+            }
+            let refused = params.any(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+            return !refused;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn accept_encoding_honors_zero_quality() {
+        let accept = headers(header::ACCEPT_ENCODING, "gzip, deflate, br;q=0");
+        assert!(accepts_encoding(&accept, "gzip"));
+        assert!(!accepts_encoding(&accept, "br"));
+        assert!(!accepts_encoding(&HeaderMap::new(), "gzip"));
+    }
+
+    #[test]
+    fn if_none_match_accepts_lists_and_weak_tags() {
+        let etag = "\"abc123-br\"";
+        assert!(if_none_match(
+            &headers(header::IF_NONE_MATCH, "\"old\", W/\"abc123-br\""),
+            etag
+        ));
+        assert!(if_none_match(&headers(header::IF_NONE_MATCH, "*"), etag));
+        assert!(!if_none_match(
+            &headers(header::IF_NONE_MATCH, "\"abc123\""),
+            etag
+        ));
+    }
+
+    #[test]
+    fn only_hashed_assets_are_immutable() {
+        assert_eq!(
+            cache_control("assets/index-BxK3a9_f.js"),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(cache_control("index.html"), "no-cache");
+        assert_eq!(cache_control("sw.js"), "no-cache");
+    }
+}
@@ -0,0 +1,159 @@
+//! Optional HTTP/3 listener serving the same router as the HTTPS listener.
+//!
+//! Browsers discover it through the `Alt-Svc` header on HTTPS responses and
+//! then multiplex API calls and SSE streams over a single QUIC connection.
+//! WebSocket upgrades never reach it: browsers keep using HTTP/1.1 for those.
+
+use anyhow::{Context, Result};
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderValue, Request, Response, StatusCode};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_util::StreamExt;
+use quinn::crypto::rustls::QuicServerConfig;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tower::ServiceExt;
+
+use crate::config::TlsConfig;
+
+/// Matches the largest body the API accepts (attachment uploads).
+const MAX_REQUEST_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+type H3Stream = h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+
+/// `Alt-Svc` value advertising HTTP/3 on `port`.
+pub fn alt_svc_value(port: u16) -> HeaderValue {
+    HeaderValue::from_str(&format!("h3=\":{port}\"; ma=86400"))
+        .unwrap_or_else(|_| HeaderValue::from_static("clear"))
+}
+
+/// Bind the HTTP/3 endpoint on `addr` with the HTTPS certificate and serve
+/// `app` until `shutdown` fires.
+pub fn spawn(
+    app: axum::Router,
+    tls_config: &TlsConfig,
+    addr: SocketAddr,
+    shutdown: Arc<Notify>,
+) -> Result<()> {
+    let server_crypto = crate::tls::build_quic_server_config(tls_config)?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+        QuicServerConfig::try_from(server_crypto).context("TLS config unusable for QUIC")?,
+    ));
+    let endpoint = quinn::Endpoint::server(server_config, addr)
+        .with_context(|| format!("failed to bind HTTP/3 listener on UDP {addr}"))?;
+
+    tokio::spawn(async move {
+        loop {
+            let incoming = tokio::select! {
+                _ = shutdown.notified() => break,
+                incoming = endpoint.accept() => match incoming {
+                    Some(incoming) => incoming,
+                    None => break,
+                },
+            };
+            let app = app.clone();
+            tokio::spawn(async move {
+                let conn = match incoming.await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::debug!("HTTP/3 handshake failed: {}", e);
+                        return;
+                    }
+                };
+                serve_connection(conn, app).await;
+            });
+        }
+        endpoint.close(quinn::VarInt::from_u32(0), b"shutdown");
+    });
+    Ok(())
+}
+
+async fn serve_connection(conn: quinn::Connection, app: axum::Router) {
+    let remote_addr = conn.remote_address();
+    let mut h3_conn: h3::server::Connection<h3_quinn::Connection, Bytes> =
+        match h3::server::Connection::new(h3_quinn::Connection::new(conn)).await {
+            Ok(h3_conn) => h3_conn,
+            Err(e) => {
+                tracing::debug!(addr = %remote_addr, "HTTP/3 setup failed: {}", e);
+                return;
+            }
+        };
+
+    loop {
+        let resolver = match h3_conn.accept().await {
+            Ok(Some(resolver)) => resolver,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::debug!(addr = %remote_addr, "HTTP/3 connection closed: {}", e);
+                break;
+            }
+        };
+        let app = app.clone();
+        tokio::spawn(async move {
+            let (request, stream) = match resolver.resolve_request().await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    tracing::debug!(addr = %remote_addr, "HTTP/3 request failed: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = serve_request(request, stream, app, remote_addr).await {
+                tracing::debug!(addr = %remote_addr, "HTTP/3 response failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn serve_request(
+    request: Request<()>,
+    mut stream: H3Stream,
+    app: axum::Router,
+    remote_addr: SocketAddr,
+) -> Result<()> {
+    let mut body = BytesMut::new();
+    while let Some(chunk) = stream.recv_data().await? {
+        if body.len() + chunk.remaining() > MAX_REQUEST_BODY_BYTES {
+            let response = Response::builder()
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .body(())?;
+            stream.send_response(response).await?;
+            stream.finish().await?;
+            return Ok(());
+        }
+        body.put(chunk);
+    }
+
+    let (parts, ()) = request.into_parts();
+    let mut request = Request::from_parts(parts, Body::from(body.freeze()));
+    request.extensions_mut().insert(ConnectInfo(remote_addr));
+
+    let response = match app.oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let (mut parts, body) = response.into_parts();
+    // Connection-specific headers are malformed in HTTP/3.
+    for name in [
+        header::CONNECTION,
+        header::TRANSFER_ENCODING,
+        header::UPGRADE,
+        header::HeaderName::from_static("keep-alive"),
+    ] {
+        parts.headers.remove(name);
+    }
+    stream
+        .send_response(Response::from_parts(parts, ()))
+        .await?;
+
+    let mut data = body.into_data_stream();
+    while let Some(chunk) = data.next().await {
+        let chunk = chunk?;
+        if !chunk.is_empty() {
+            stream.send_data(chunk).await?;
+        }
+    }
+    stream.finish().await?;
+    Ok(())
+}
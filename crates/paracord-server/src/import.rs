@@ -0,0 +1,103 @@
+//! `paracord-server import`: queue a guild import from the command line.
+//!
+//! The import itself runs on the server's job workers, the same as one
+//! queued through `POST /api/v1/admin/imports`, so the server has to be
+//! running (or started later) for it to make progress. Unlike the admin
+//! endpoint, any path readable by the server can be imported.
+
+use anyhow::{Context, Result};
+use paracord_api::routes::imports::{enqueue_import, ImportJobPayload};
+use paracord_core::import::ImportFormat;
+use std::time::Duration;
+
+use crate::cli::{ImportArgs, ImportFormatArg};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a job may sit unclaimed before hinting that no server is running.
+const UNCLAIMED_HINT_AFTER: Duration = Duration::from_secs(10);
+
+pub async fn run(db: &paracord_db::DbPool, args: ImportArgs) -> Result<()> {
+    let path = args
+        .path
+        .canonicalize()
+        .with_context(|| format!("cannot read {}", args.path.display()))?;
+    let payload = ImportJobPayload {
+        guild_id: args.guild_id.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        format: args.format.map(|format| match format {
+            ImportFormatArg::Discord => ImportFormat::Discord,
+            ImportFormatArg::Matrix => ImportFormat::Matrix,
+        }),
+        dry_run: args.dry_run,
+        requested_by: None,
+    };
+    let job = enqueue_import(db, &payload).await.map_err(|e| match e {
+        paracord_core::error::CoreError::NotFound => {
+            anyhow::anyhow!("guild {} does not exist", args.guild_id)
+        }
+        other => anyhow::anyhow!(other.to_string()),
+    })?;
+    println!(
+        "Queued {}import of {} as job {}",
+        if args.dry_run { "dry-run " } else { "" },
+        path.display(),
+        job.id
+    );
+    if args.detach {
+        println!("Follow it with GET /api/v1/admin/jobs/{}", job.id);
+        return Ok(());
+    }
+
+    let started = tokio::time::Instant::now();
+    let mut hinted = false;
+    let mut last_progress = None;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let job = paracord_db::jobs::get_job(db, job.id)
+            .await?
+            .context("the import job was cancelled")?;
+        if job.progress != last_progress {
+            if let Some(progress) = job
+                .progress
+                .as_deref()
+                .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            {
+                println!(
+                    "[{}] created {} users, {} channels, {} emojis, {} messages, {} attachments",
+                    progress["stage"].as_str().unwrap_or("?"),
+                    progress["created"]["users"],
+                    progress["created"]["channels"],
+                    progress["created"]["emojis"],
+                    progress["created"]["messages"],
+                    progress["created"]["attachments"],
+                );
+            }
+            last_progress = job.progress.clone();
+        }
+        match job.status.as_str() {
+            paracord_db::jobs::JOB_STATUS_COMPLETE => {
+                if let Some(progress) = job.progress.as_deref() {
+                    let report: serde_json::Value = serde_json::from_str(progress)?;
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                return Ok(());
+            }
+            paracord_db::jobs::JOB_STATUS_FAILED => {
+                anyhow::bail!(
+                    "import failed: {}",
+                    job.last_error.as_deref().unwrap_or("unknown error")
+                );
+            }
+            paracord_db::jobs::JOB_STATUS_PENDING
+                if job.attempts == 0 && !hinted && started.elapsed() >= UNCLAIMED_HINT_AFTER =>
+            {
+                println!(
+                    "Waiting for a running server to pick up job {}; press Ctrl-C to stop watching (the job stays queued)",
+                    job.id
+                );
+                hinted = true;
+            }
+            _ => {}
+        }
+    }
+}
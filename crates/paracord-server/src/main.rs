@@ -7,13 +7,21 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing_subscriber::EnvFilter;
 
+mod acme_dns;
 mod bots;
 mod cli;
 mod config;
+mod config_check;
 #[cfg(feature = "embed-ui")]
 mod embedded_ui;
+mod http3;
+mod import;
 mod livekit_proc;
+mod secrets;
+mod tenants;
 mod tls;
+mod url_feeds;
+mod web_push;
 
 #[derive(Clone, Default)]
 struct AtRestRuntimeProfile {
@@ -66,13 +74,27 @@ async fn main() -> Result<()> {
         .init();
 
     let args = cli::Args::parse();
+    if let Some(cli::Command::AcmeDnsHook { stage }) = args.command {
+        return acme_dns::run_hook(stage).await;
+    }
+    if let Some(cli::Command::Check) = args.command {
+        return config_check::run(&args.config);
+    }
     let config = config::Config::load(&args.config)?;
+    paracord_util::snowflake::set_worker_id(config.server.worker_id)
+        .context("invalid server.worker_id")?;
+    paracord_util::snowflake::set_epoch(config.server.snowflake_epoch_millis())
+        .context("invalid server.snowflake_epoch")?;
     if config.tls.acme.enabled && !config.tls.enabled {
         tracing::warn!(
             "tls.acme.enabled is true while tls.enabled is false; ACME automation will be inactive"
         );
     }
     let at_rest_profile = build_at_rest_profile(&config)?;
+    if let Some(cli::Command::Import(import_args)) = args.command {
+        let db = open_database(&config.database, at_rest_profile.sqlite_key_hex.clone()).await?;
+        return import::run(&db, import_args).await;
+    }
     if livekit_credentials_look_insecure(&config.livekit.api_key, &config.livekit.api_secret) {
         if config.server.public_url.is_some() {
             anyhow::bail!(
@@ -254,50 +276,35 @@ async fn main() -> Result<()> {
         livekit_reachable = true;
     }
 
-    let db_engine = map_db_engine(config.database.engine);
-    let pg_options = paracord_db::PgConnectOptions {
-        statement_timeout_secs: config.database.statement_timeout_secs,
-        idle_in_transaction_timeout_secs: config.database.idle_in_transaction_timeout_secs,
-    };
-    let db = paracord_db::create_pool_full(
-        &config.database.url,
-        config.database.max_connections,
-        Some(db_engine),
-        at_rest_profile.sqlite_key_hex.clone(),
-        Some(pg_options),
-    )
-    .await
-    .map_err(|e| {
-        if matches!(db_engine, paracord_db::DatabaseEngine::Postgres) {
-            anyhow::anyhow!(
-                "Failed to connect to PostgreSQL at '{}': {}. \
-                 Check that the server is running, credentials are correct, \
-                 and the database exists. For SSL connections, append ?sslmode=require to the URL.",
-                config.database.url,
-                e
-            )
-        } else {
-            anyhow::anyhow!("{}", e)
-        }
-    })?;
-    paracord_db::run_migrations_for_engine(&db, db_engine)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to run {} migrations: {}", db_engine.as_str(), e))?;
-
-    // Clear stale voice states from the database. After a server restart no
-    // client is actually connected to a LiveKit room, so any leftover rows
-    // are ghosts from a previous process.
-    match paracord_db::voice_states::clear_all_voice_states(&db).await {
-        Ok(n) if n > 0 => {
-            tracing::info!("Cleared {} stale voice state(s) from previous session", n)
-        }
-        Ok(_) => {}
-        Err(e) => tracing::warn!("Failed to clear stale voice states: {}", e),
-    }
+    let db = open_database(&config.database, at_rest_profile.sqlite_key_hex.clone()).await?;
+    clear_stale_voice_states(&db).await;
 
     // ── Load runtime settings from database ─────────────────────────────────
-    let runtime = load_runtime_settings(&db).await;
-    let runtime = Arc::new(RwLock::new(runtime));
+    let runtime = load_instance_runtime(&db, &config).await;
+
+    let event_log = if config.developer.event_firehose {
+        match paracord_core::event_log::EventLog::open(
+            &config.developer.event_log_path,
+            config.developer.event_log_capacity,
+        )
+        .await
+        {
+            Ok(log) => {
+                tracing::warn!(
+                    "Developer event firehose enabled (keeping {} events in {})",
+                    log.capacity(),
+                    config.developer.event_log_path
+                );
+                Some(Arc::new(log))
+            }
+            Err(e) => {
+                tracing::warn!("Event firehose disabled: failed to open event log: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     // Create LiveKit config for the media layer
     // On Windows, "localhost" can resolve to IPv6 [::1] which may hang if
@@ -329,7 +336,11 @@ async fn main() -> Result<()> {
         }
     }
 
-    let voice = Arc::new(paracord_media::VoiceManager::new(livekit_config));
+    let voice = Arc::new(
+        paracord_media::VoiceManager::new(livekit_config)
+            .with_recording_dir(config.livekit.egress_output_dir.clone())
+            .with_webrtc_gateway(config.voice.webrtc_gateway_url.clone()),
+    );
     let storage = Arc::new(paracord_media::StorageManager::new(
         paracord_media::StorageConfig {
             base_path: config.media.storage_path.clone().into(),
@@ -423,6 +434,27 @@ async fn main() -> Result<()> {
         None
     };
 
+    let transcoder = if config.transcoding.enabled {
+        let transcoder = paracord_media::transcode::Transcoder::new(
+            &config.transcoding.ffmpeg_path,
+            std::time::Duration::from_secs(config.transcoding.timeout_seconds.max(30)),
+            Path::new(&config.storage.path).join("transcode"),
+        );
+        if transcoder.probe().await {
+            Some(transcoder)
+        } else {
+            tracing::warn!(
+                "Video transcoding enabled but ffmpeg ('{}') could not be executed; transcoding disabled",
+                config.transcoding.ffmpeg_path
+            );
+            None
+        }
+    } else {
+        None
+    };
+
+    let vapid_keys = web_push::load_keys(&config.web_push).context("failed to load VAPID key")?;
+
     let memberships = paracord_db::members::get_all_memberships(&db)
         .await
         .context("failed to load memberships for member index")?;
@@ -463,6 +495,7 @@ async fn main() -> Result<()> {
             federation_max_user_creates_per_peer_per_hour: config
                 .federation
                 .max_user_creates_per_peer_per_hour,
+            federation_max_bytes_per_peer_per_hour: config.federation.max_bytes_per_peer_per_hour,
             native_media_enabled: config.voice.native_media,
             native_media_port: config.voice.port,
             native_media_max_participants: config.voice.max_participants_per_room,
@@ -471,6 +504,11 @@ async fn main() -> Result<()> {
             federation_file_cache_enabled: config.federation.file_cache_enabled,
             federation_file_cache_max_size: config.federation.file_cache_max_size,
             federation_file_cache_ttl_hours: config.federation.file_cache_ttl_hours,
+            video_transcoding_enabled: transcoder.is_some(),
+            client_update_manifest_path: config.client_updates.manifest_path.clone(),
+            min_client_version: config.client_updates.minimum_version.clone(),
+            web_push_public_key: vapid_keys.as_ref().map(|keys| keys.public_key.clone()),
+            link_previews_enabled: config.link_previews.enabled,
         },
         voice,
         storage,
@@ -482,31 +520,58 @@ async fn main() -> Result<()> {
         member_index: Arc::new(member_index),
         presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
         native_media: None,
+        event_log,
+        transfer_limiter: Arc::new(paracord_transport::transfer_limits::TransferLimiter::new(
+            paracord_transport::transfer_limits::TransferLimits {
+                max_concurrent_uploads: config.storage.limits.max_concurrent_uploads,
+                max_concurrent_downloads: config.storage.limits.max_concurrent_downloads,
+                upload_bytes_per_second: config.storage.limits.upload_bytes_per_second,
+                download_bytes_per_second: config.storage.limits.download_bytes_per_second,
+            },
+        )),
+    };
+
+    // ── Tenants (experimental) ──────────────────────────────────────────────
+    let mut tenants =
+        tenants::open(&config, &state, at_rest_profile.sqlite_key_hex.clone()).await?;
+
+    // ── TLS / HTTPS setup ───────────────────────────────────────────────────
+    // Runs before the media server, which may present the same certificate.
+    let tls_enabled = config.tls.enabled;
+    let tls_rustls_config = if tls_enabled {
+        match tls::ensure_certs(
+            &config.tls,
+            detected_external_ip.as_deref(),
+            detected_local_ip.as_deref(),
+        )
+        .await
+        {
+            Ok(cfg) => Some(cfg),
+            Err(e) => {
+                tracing::warn!("TLS setup failed, HTTPS disabled: {}", e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
     // ── Native QUIC media server ─────────────────────────────────────────────
     // Uses a single UDP port (defaults to 8443, same as TLS) with ALPN-based
     // routing: `h3` → WebTransport (browsers), anything else → raw QUIC
     // (desktop/federation). Admins only need to forward one port (TCP + UDP).
+    let mut media_endpoint_for_renewal = None;
     if config.voice.native_media {
-        use paracord_transport::endpoint::{generate_self_signed_cert, MediaEndpoint};
+        use paracord_transport::endpoint::MediaEndpoint;
 
         let media_port = config.voice.port;
         let media_addr: std::net::SocketAddr = format!("0.0.0.0:{}", media_port).parse()?;
-        match generate_self_signed_cert() {
-            Ok(tls) => {
-                // Compute SHA-256 hash of the DER certificate for WebTransport
-                // `serverCertificateHashes`. Browsers need this to trust
-                // self-signed certs.
-                let cert_hash = {
-                    use sha2::{Digest, Sha256};
-                    let mut hasher = Sha256::new();
-                    hasher.update(tls.cert_chain[0].as_ref());
-                    let digest = hasher.finalize();
-                    use base64::Engine;
-                    base64::engine::general_purpose::STANDARD.encode(digest)
-                };
-
+        match tls::load_media_cert(
+            &config,
+            detected_external_ip.as_deref(),
+            detected_local_ip.as_deref(),
+        ) {
+            Ok(tls::MediaCert { tls, cert_hash }) => {
                 // Single unified endpoint: ALPN `h3` for WebTransport browsers,
                 // `paracord-media` for raw QUIC desktop/federation clients.
                 // Clients MUST send a matching ALPN (rustls requires it).
@@ -518,11 +583,29 @@ async fn main() -> Result<()> {
                     Ok(endpoint) => {
                         let rooms = Arc::new(paracord_relay::room::MediaRoomManager::new());
                         let speaker = Arc::new(paracord_relay::speaker::SpeakerDetector::new());
-                        let relay_forwarder = Arc::new(paracord_relay::relay::RelayForwarder::new(
-                            Arc::clone(&rooms),
-                            Arc::clone(&speaker),
-                        ));
+                        let forwarding_policy =
+                            paracord_relay::speaker::SpeakerForwardingPolicy::new(
+                                config
+                                    .voice
+                                    .speaker_forwarding
+                                    .iter()
+                                    .map(|tier| paracord_relay::speaker::SpeakerForwardingTier {
+                                        min_participants: tier.min_participants as usize,
+                                        max_speakers: tier.max_speakers as usize,
+                                    })
+                                    .collect(),
+                            );
+                        let relay_forwarder = Arc::new(
+                            paracord_relay::relay::RelayForwarder::new(
+                                Arc::clone(&rooms),
+                                Arc::clone(&speaker),
+                            )
+                            .with_forwarding_policy(forwarding_policy),
+                        );
                         let endpoint = Arc::new(endpoint);
+                        if config.voice.cert == config::MediaCertSource::Server {
+                            media_endpoint_for_renewal = Some(Arc::clone(&endpoint));
+                        }
                         let native_state = paracord_core::NativeMediaState {
                             rooms: Arc::clone(&rooms),
                             speaker_detector: Arc::clone(&speaker),
@@ -540,12 +623,35 @@ async fn main() -> Result<()> {
                         // each connection to the appropriate handler.
                         {
                             let relay = Arc::clone(&relay_forwarder);
-                            let jwt_secret = config.auth.jwt_secret.clone();
-                            let db = state.db.clone();
+                            let auth: Arc<[tenants::MediaAuth]> = std::iter::once(&state)
+                                .chain(tenants.iter().map(|tenant| &tenant.state))
+                                .map(tenants::MediaAuth::for_state)
+                                .collect();
                             tokio::spawn(async move {
-                                unified_media_accept_loop(endpoint, relay, jwt_secret, db).await;
+                                unified_media_accept_loop(endpoint, relay, auth).await;
                             });
                         }
+                        // Events are addressed by guild and user ids, which
+                        // don't collide across instances, so every bus gets
+                        // every room's events.
+                        for event_bus in std::iter::once(&state)
+                            .chain(tenants.iter().map(|tenant| &tenant.state))
+                            .map(|state| state.event_bus.clone())
+                        {
+                            spawn_room_signal_dispatch(
+                                Arc::clone(&relay_forwarder),
+                                event_bus.clone(),
+                            );
+                            spawn_speaking_dispatch(
+                                Arc::clone(&relay_forwarder),
+                                event_bus.clone(),
+                            );
+                            spawn_voice_metrics_dispatch(
+                                Arc::clone(&relay_forwarder),
+                                event_bus,
+                                shutdown_notify.clone(),
+                            );
+                        }
                     }
                     Err(e) => {
                         tracing::error!("Failed to start native QUIC media server: {}", e);
@@ -553,11 +659,15 @@ async fn main() -> Result<()> {
                 }
             }
             Err(e) => {
-                tracing::error!("Failed to generate self-signed cert for media: {}", e);
+                tracing::error!("Failed to load media endpoint certificate: {:#}", e);
             }
         }
     }
 
+    for tenant in &mut tenants {
+        tenant.state.native_media = state.native_media.clone();
+    }
+
     // ── QUIC file transfer partial upload cleanup ─────────────────────────────
     if config.voice.native_media {
         let partial_dir = std::path::Path::new(&config.storage.path).join("partial");
@@ -568,20 +678,77 @@ async fn main() -> Result<()> {
         tracing::info!("QUIC file transfer enabled (sharing native media QUIC endpoint)");
     }
 
-    paracord_api::install_http_rate_limiter();
-    paracord_api::spawn_http_rate_limiter_cleanup(shutdown_notify.clone());
+    paracord_api::rate_limit::install(
+        config
+            .rate_limit
+            .route_limits()
+            .map_err(|err| anyhow::anyhow!(err))?,
+    );
+    // Admin overrides from the `rate_limits` server setting.
+    if let Ok(Some(spec)) =
+        paracord_db::server_settings::get_setting(&state.db, "rate_limits").await
+    {
+        if let Err(err) = paracord_api::rate_limit::apply_setting(&spec) {
+            tracing::warn!("Ignoring invalid rate_limits setting: {}", err);
+        }
+    }
 
-    spawn_pending_attachment_cleanup(
-        state.db.clone(),
-        state.storage_backend.clone(),
-        shutdown_notify.clone(),
+    // ── Cluster ──────────────────────────────────────────────────────────────
+    // With Redis, events, presence and rate limits are shared with the other
+    // servers; tenants get their own key prefix.
+    if config.cluster.backend == config::ClusterBackend::Redis {
+        let url = config
+            .cluster
+            .redis_url
+            .as_deref()
+            .context("cluster.backend = \"redis\" requires cluster.redis_url")?;
+        let prefix = config.cluster.key_prefix.trim_end_matches(':');
+        let cluster =
+            paracord_core::cluster::Cluster::start(&state, url, prefix, shutdown_notify.clone())
+                .await
+                .context("failed to connect to the cluster's Redis server")?;
+        paracord_api::rate_limit::share_buckets(cluster);
+        for tenant in &tenants {
+            paracord_core::cluster::Cluster::start(
+                &tenant.state,
+                url,
+                &format!("{prefix}:{}", tenant.host),
+                shutdown_notify.clone(),
+            )
+            .await
+            .with_context(|| format!("failed to join tenant {} to the cluster", tenant.host))?;
+        }
+    }
+    let rate_limit_bypass = paracord_api::rate_limit_bypass::RateLimitBypass::new(
+        &config.rate_limit.allowlist_ips,
+        &config.rate_limit.allowlist_tokens,
+        &config.rate_limit.exempt_paths,
     );
-    spawn_retention_jobs(
-        state.db.clone(),
-        state.storage_backend.clone(),
-        config.retention.clone(),
-        shutdown_notify.clone(),
+    if !rate_limit_bypass.is_empty() {
+        tracing::info!(
+            "HTTP rate limiter exemptions: {} addresses, {} tokens, paths {:?}",
+            config.rate_limit.allowlist_ips.len(),
+            config.rate_limit.allowlist_tokens.len(),
+            config.rate_limit.exempt_paths
+        );
+    }
+    paracord_api::rate_limit_bypass::install(rate_limit_bypass);
+    let supporter_webhooks = paracord_api::supporter_webhooks::SupporterWebhooks::new(
+        config.supporters.kofi_verification_token.as_deref(),
+        config.supporters.patreon_webhook_secret.as_deref(),
+        config.supporters.grant_days,
     );
+    if supporter_webhooks.kofi_enabled() || supporter_webhooks.patreon_enabled() {
+        tracing::info!(
+            "Supporter webhooks enabled: Ko-fi {}, Patreon {}",
+            supporter_webhooks.kofi_enabled(),
+            supporter_webhooks.patreon_enabled()
+        );
+    }
+    paracord_api::supporter_webhooks::install(supporter_webhooks);
+    paracord_api::spawn_http_rate_limiter_cleanup(shutdown_notify.clone());
+
+    // Process-wide workers, run for the main instance only.
     spawn_auto_backup(
         config.backup.clone(),
         config.database.url.clone(),
@@ -590,54 +757,153 @@ async fn main() -> Result<()> {
         shutdown_notify.clone(),
     );
     spawn_federation_delivery_worker(state.clone(), shutdown_notify.clone());
-    bots::spawn_bot_manager(state.clone(), shutdown_notify.clone());
+    spawn_federation_file_cache_sweeper(state.clone(), shutdown_notify.clone());
+    spawn_db_pool_monitor_worker(state.clone(), shutdown_notify.clone());
+    if let Some(log) = state.event_log.clone() {
+        paracord_core::event_log::spawn_recorder(
+            log,
+            state.event_bus.subscribe_system(),
+            shutdown_notify.clone(),
+        );
+    }
+
+    let instances: Vec<paracord_core::AppState> = std::iter::once(state.clone())
+        .chain(tenants.iter().map(|tenant| tenant.state.clone()))
+        .collect();
+    for instance in &instances {
+        spawn_instance_workers(
+            instance,
+            &config,
+            vapid_keys.clone(),
+            shutdown_notify.clone(),
+        );
+        if transcoder.is_some() {
+            requeue_interrupted_transcodes(instance).await;
+        }
+        if let Err(e) = paracord_core::url_reputation::reload_manual_domains(instance).await {
+            tracing::warn!("Failed to load URL denylist entries: {}", e);
+        }
+        if let Err(e) = paracord_core::hash_matching::reload_image_hashes(instance).await {
+            tracing::warn!("Failed to load image hash blocklist: {}", e);
+        }
+    }
+    let mut job_registry = paracord_api::jobs::job_registry();
+    register_maintenance_jobs(&mut job_registry, &config, transcoder);
+    paracord_core::jobs::spawn_shared_job_workers(
+        instances.clone(),
+        Arc::new(job_registry),
+        BACKGROUND_JOB_WORKERS,
+        shutdown_notify.clone(),
+    );
+    url_feeds::spawn(
+        instances,
+        config.url_reputation.clone(),
+        shutdown_notify.clone(),
+    );
 
-    let router = paracord_api::build_router()
-        .merge(paracord_ws::gateway_router())
-        .with_state(state);
+    // Management endpoints move to their own listener when configured.
+    let internal_app = config.server.internal_bind.as_ref().map(|_| {
+        let internal_router = paracord_api::build_internal_router();
+        tenants::host_router(
+            internal_router.clone().with_state(state.clone()),
+            tenants
+                .iter()
+                .map(|tenant| {
+                    let router = internal_router.clone().with_state(tenant.state.clone());
+                    (tenant.host.clone(), router)
+                })
+                .collect(),
+        )
+    });
+    let api_router = if internal_app.is_some() {
+        paracord_api::build_public_router()
+    } else {
+        paracord_api::build_router()
+    };
+    let api_router = if config.developer.swagger_ui {
+        api_router.merge(paracord_api::build_swagger_ui_router())
+    } else {
+        api_router
+    };
+    let instance_router = api_router.merge(paracord_ws::gateway_router());
 
     // ── Web UI serving ───────────────────────────────────────────────────────
-    let web_ui_status;
-    let app = if let Some(ref dir) = web_dir {
-        let index_path = dir.join("index.html");
-        let spa_fallback = tower_http::services::ServeFile::new(&index_path);
-        let serve_dir = tower_http::services::ServeDir::new(dir).not_found_service(spa_fallback);
-        web_ui_status = format!("Serving from {:?}", dir);
-        router.fallback_service(serve_dir)
+    let web_ui_status = if let Some(ref dir) = web_dir {
+        format!("Serving from {:?}", dir)
+    } else if cfg!(feature = "embed-ui") {
+        "Embedded".to_string()
     } else {
-        #[cfg(feature = "embed-ui")]
-        {
-            web_ui_status = "Embedded".to_string();
-            router.merge(embedded_ui::router())
-        }
-        #[cfg(not(feature = "embed-ui"))]
-        {
-            web_ui_status = "None (API-only mode)".to_string();
+        "None (API-only mode)".to_string()
+    };
+    // Invite pages also get OpenGraph tags so other chat apps can unfurl
+    // shared invites.
+    let with_web_ui = |router: axum::Router<paracord_core::AppState>,
+                       state: paracord_core::AppState|
+     -> axum::Router {
+        let invite_previews = axum::middleware::from_fn_with_state(
+            state.clone(),
+            paracord_api::invite_previews::middleware,
+        );
+        if let Some(ref dir) = web_dir {
+            let index_path = dir.join("index.html");
+            let spa_fallback = tower_http::services::ServeFile::new(&index_path);
+            let serve_dir =
+                tower_http::services::ServeDir::new(dir).not_found_service(spa_fallback);
             router
+                .with_state(state)
+                .fallback_service(serve_dir)
+                .layer(invite_previews)
+        } else {
+            #[cfg(feature = "embed-ui")]
+            {
+                router
+                    .with_state(state)
+                    .merge(embedded_ui::router())
+                    .layer(invite_previews)
+            }
+            #[cfg(not(feature = "embed-ui"))]
+            {
+                // No web UI, so no invite pages to annotate.
+                drop(invite_previews);
+                router.with_state(state)
+            }
         }
     };
+    let app = tenants::host_router(
+        with_web_ui(instance_router.clone(), state),
+        tenants
+            .iter()
+            .map(|tenant| {
+                let router = with_web_ui(instance_router.clone(), tenant.state.clone());
+                (tenant.host.clone(), router)
+            })
+            .collect(),
+    );
 
     let listener = tokio::net::TcpListener::bind(&config.server.bind_address).await?;
 
-    // ── TLS / HTTPS setup ───────────────────────────────────────────────────
-    let tls_enabled = config.tls.enabled;
-    let tls_rustls_config = if tls_enabled {
-        match tls::ensure_certs(
-            &config.tls,
-            detected_external_ip.as_deref(),
-            detected_local_ip.as_deref(),
-        )
-        .await
-        {
-            Ok(cfg) => Some(cfg),
-            Err(e) => {
-                tracing::warn!("TLS setup failed, HTTPS disabled: {}", e);
-                None
+    if let (Some(internal_bind), Some(internal_app)) = (&config.server.internal_bind, internal_app)
+    {
+        let internal_listener = tokio::net::TcpListener::bind(internal_bind)
+            .await
+            .with_context(|| format!("failed to bind internal listener on {internal_bind}"))?;
+        let shutdown = shutdown_notify.clone();
+        tokio::spawn(async move {
+            let result = axum::serve(
+                internal_listener,
+                internal_app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move { shutdown.notified().await })
+            .await;
+            if let Err(e) = result {
+                tracing::error!("Internal listener failed: {}", e);
             }
-        }
-    } else {
-        None
-    };
+        });
+        tracing::info!(
+            "Metrics and admin API listening on {} (internal only)",
+            internal_bind
+        );
+    }
 
     let tls_status = if let Some(ref _cfg) = tls_rustls_config {
         format!("Enabled (port {})", tls_port)
@@ -659,6 +925,7 @@ async fn main() -> Result<()> {
         tls::spawn_acme_renewal_task(
             config.tls.clone(),
             rustls_config.clone(),
+            media_endpoint_for_renewal,
             shutdown_notify.clone(),
         );
     }
@@ -720,6 +987,53 @@ async fn main() -> Result<()> {
         let app_https = app
             .clone()
             .layer(axum::middleware::from_fn(inject_https_proto));
+
+        // Optional HTTP/3 listener; HTTPS responses advertise it via Alt-Svc.
+        let http3_port = config.tls.http3_port.unwrap_or(tls_port);
+        let http3_started = if !config.tls.http3 {
+            false
+        } else if config.voice.native_media && http3_port == config.voice.port {
+            tracing::warn!(
+                "HTTP/3 disabled: UDP port {} is used by native media; set [tls] http3_port",
+                http3_port
+            );
+            false
+        } else {
+            let http3_addr: std::net::SocketAddr =
+                format!("{}:{}", bind_host, http3_port).parse()?;
+            match http3::spawn(
+                app_https.clone(),
+                &config.tls,
+                http3_addr,
+                shutdown_notify.clone(),
+            ) {
+                Ok(()) => {
+                    tracing::info!("HTTP/3 listening on UDP port {}", http3_port);
+                    true
+                }
+                Err(e) => {
+                    tracing::warn!("HTTP/3 listener failed to start: {:#}", e);
+                    false
+                }
+            }
+        };
+        let app_https = if http3_started {
+            let alt_svc = http3::alt_svc_value(http3_port);
+            app_https.layer(axum::middleware::from_fn(
+                move |req: axum::extract::Request, next: axum::middleware::Next| {
+                    let alt_svc = alt_svc.clone();
+                    async move {
+                        let mut response = next.run(req).await;
+                        response
+                            .headers_mut()
+                            .insert(axum::http::header::ALT_SVC, alt_svc);
+                        response
+                    }
+                },
+            ))
+        } else {
+            app_https
+        };
         let redirect_port = tls_port;
         let tls_redirect_config = config.tls.clone();
         let http_redirect_app = axum::Router::new().fallback(move |req: axum::extract::Request| {
@@ -775,10 +1089,13 @@ fn ensure_data_dirs(config: &config::Config) {
         }
     }
 
-    // Database parent directory
-    if matches!(config.database.engine, config::DatabaseEngine::Sqlite) {
-        if let Some(db_path) = config
-            .database
+    ensure_database_dir(&config.database);
+}
+
+/// Create the parent directory of a SQLite database file.
+fn ensure_database_dir(database: &config::DatabaseConfig) {
+    if matches!(database.engine, config::DatabaseEngine::Sqlite) {
+        if let Some(db_path) = database
             .url
             .strip_prefix("sqlite://")
             .and_then(|s| s.split('?').next())
@@ -879,6 +1196,121 @@ fn harden_secret_file_permissions(path: &Path) {
     }
 }
 
+const SNOWFLAKE_EPOCH_SETTING: &str = "snowflake_epoch";
+
+/// Connect to the configured database and bring its schema up to date.
+async fn open_database(
+    database: &config::DatabaseConfig,
+    sqlite_key_hex: Option<String>,
+) -> Result<paracord_db::DbPool> {
+    let db_engine = map_db_engine(database.engine);
+    let pg_options = paracord_db::PgConnectOptions {
+        statement_timeout_secs: database.statement_timeout_secs,
+        idle_in_transaction_timeout_secs: database.idle_in_transaction_timeout_secs,
+        adaptive_max_connections: database.adaptive_max_connections,
+    };
+    if database.adaptive_max_connections > 0 {
+        if !matches!(db_engine, paracord_db::DatabaseEngine::Postgres) {
+            tracing::warn!(
+                "database.adaptive_max_connections only applies to PostgreSQL; ignoring"
+            );
+        } else if database.adaptive_max_connections <= database.max_connections {
+            tracing::warn!(
+                "database.adaptive_max_connections ({}) is not above max_connections ({}); ignoring",
+                database.adaptive_max_connections,
+                database.max_connections
+            );
+        }
+    }
+    let db = paracord_db::create_pool_full(
+        &database.url,
+        database.max_connections,
+        Some(db_engine),
+        sqlite_key_hex,
+        Some(pg_options),
+    )
+    .await
+    .map_err(|e| {
+        if matches!(db_engine, paracord_db::DatabaseEngine::Postgres) {
+            anyhow::anyhow!(
+                "Failed to connect to PostgreSQL at '{}': {}. \
+                 Check that the server is running, credentials are correct, \
+                 and the database exists. For SSL connections, append ?sslmode=require to the URL.",
+                database.url,
+                e
+            )
+        } else {
+            anyhow::anyhow!("{}", e)
+        }
+    })?;
+    paracord_db::run_migrations_for_engine(&db, db_engine)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to run {} migrations: {}", db_engine.as_str(), e))?;
+    check_snowflake_epoch(&db).await?;
+    Ok(db)
+}
+
+/// Clear stale voice states from the database. After a server restart no
+/// client is actually connected to a LiveKit room, so any leftover rows are
+/// ghosts from a previous process.
+async fn clear_stale_voice_states(db: &paracord_db::DbPool) {
+    match paracord_db::voice_states::clear_all_voice_states(db).await {
+        Ok(n) if n > 0 => {
+            tracing::info!("Cleared {} stale voice state(s) from previous session", n)
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to clear stale voice states: {}", e),
+    }
+}
+
+/// Runtime settings stored in `db`, plus the config-driven defaults.
+async fn load_instance_runtime(
+    db: &paracord_db::DbPool,
+    config: &config::Config,
+) -> Arc<RwLock<paracord_core::RuntimeSettings>> {
+    let mut runtime = load_runtime_settings(db).await;
+    runtime.url_filter_default_level = config.url_reputation.default_level;
+    runtime.image_hash_matching = config.hash_matching.to_settings();
+    if let Some(domains) = url_feeds::load_cache(&config.url_reputation.cache_path) {
+        runtime.url_denylist.set_feed_domains(domains);
+    }
+    Arc::new(RwLock::new(runtime))
+}
+
+/// Refuse to start when the configured ID epoch is later than the one the
+/// database was created with: new IDs would sort before existing ones. An
+/// earlier epoch keeps IDs increasing and is recorded, with a warning since
+/// older IDs then decode to earlier timestamps.
+async fn check_snowflake_epoch(db: &paracord_db::DbPool) -> Result<()> {
+    let epoch = paracord_util::snowflake::epoch();
+    let stored = paracord_db::server_settings::get_setting(db, SNOWFLAKE_EPOCH_SETTING)
+        .await
+        .context("failed to read the stored snowflake epoch")?
+        .and_then(|raw| raw.parse::<u64>().ok());
+    let previous = match stored {
+        Some(previous) => Some(previous),
+        // Databases created before the epoch was configurable used the default.
+        None if paracord_db::users::count_users(db).await.unwrap_or(0) > 0 => {
+            Some(paracord_util::snowflake::PARACORD_EPOCH)
+        }
+        None => None,
+    };
+    // A later epoch makes new IDs sort before existing ones; an earlier one
+    // dates every stored ID too early, so ID-bounded queries such as message
+    // retention would treat the whole history as expired.
+    if let Some(previous) = previous.filter(|previous| *previous != epoch) {
+        anyhow::bail!(
+            "server.snowflake_epoch ({epoch}) differs from this database's epoch ({previous}); existing IDs would be misdated"
+        );
+    }
+    if stored != Some(epoch) {
+        paracord_db::server_settings::set_setting(db, SNOWFLAKE_EPOCH_SETTING, &epoch.to_string())
+            .await
+            .context("failed to record the snowflake epoch")?;
+    }
+    Ok(())
+}
+
 async fn load_runtime_settings(db: &paracord_db::DbPool) -> paracord_core::RuntimeSettings {
     let mut settings = paracord_core::RuntimeSettings::default();
 
@@ -898,26 +1330,80 @@ async fn load_runtime_settings(db: &paracord_db::DbPool) -> paracord_core::Runti
                         settings.max_members_per_guild = v;
                     }
                 }
-                _ => {}
-            }
-        }
-    }
-
-    settings
-}
-
-/// On Windows, ensure firewall rules exist so inbound connections are not blocked.
-/// Uses `netsh advfirewall` to add allow-rules for the server and LiveKit binaries.
-/// Silently ignored if the rules already exist or if the user lacks admin rights.
-#[cfg(target_os = "windows")]
-fn ensure_firewall_rule() {
-    let exe = match std::env::current_exe() {
-        Ok(p) => p,
-        Err(_) => return,
-    };
-    let exe_str = exe.display().to_string();
-
-    // Rule for the main Paracord server (TCP)
+                "dm_require_friendship" => settings.dm_require_friendship = value == "true",
+                "verification_hooks_allow_private_networks" => {
+                    settings.verification_hooks_allow_private_networks = value == "true"
+                }
+                "deletion_undo_window_hours" => {
+                    if let Ok(v) = value.parse() {
+                        settings.deletion_undo_window_hours = v;
+                    }
+                }
+                "max_emojis_per_guild" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_emojis_per_guild = v;
+                    }
+                }
+                "max_webhooks_per_channel" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_webhooks_per_channel = v;
+                    }
+                }
+                "max_webhooks_per_guild" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_webhooks_per_guild = v;
+                    }
+                }
+                "max_bots_per_guild" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_bots_per_guild = v;
+                    }
+                }
+                "max_roles_per_guild" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_roles_per_guild = v;
+                    }
+                }
+                "max_channels_per_guild" => {
+                    if let Ok(v) = value.parse() {
+                        settings.max_channels_per_guild = v;
+                    }
+                }
+                "supporter_max_upload_size" => {
+                    if let Ok(v) = value.parse() {
+                        settings.supporter_max_upload_size = v;
+                    }
+                }
+                "supporter_extra_emoji_slots" => {
+                    if let Ok(v) = value.parse() {
+                        settings.supporter_extra_emoji_slots = v;
+                    }
+                }
+                "max_stream_quality" => settings.max_stream_quality = value,
+                "supporter_max_stream_quality" => settings.supporter_max_stream_quality = value,
+                "branding_accent_color" => settings.branding_accent_color = value,
+                "branding_login_text" => settings.branding_login_text = value,
+                "branding_logo" => settings.branding_logo = Some(value).filter(|v| !v.is_empty()),
+                _ => {}
+            }
+        }
+    }
+
+    settings
+}
+
+/// On Windows, ensure firewall rules exist so inbound connections are not blocked.
+/// Uses `netsh advfirewall` to add allow-rules for the server and LiveKit binaries.
+/// Silently ignored if the rules already exist or if the user lacks admin rights.
+#[cfg(target_os = "windows")]
+fn ensure_firewall_rule() {
+    let exe = match std::env::current_exe() {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    let exe_str = exe.display().to_string();
+
+    // Rule for the main Paracord server (TCP)
     add_windows_firewall_rule("Paracord Server", &exe_str, "TCP");
 
     // Rules for LiveKit (TCP + UDP for WebRTC media)
@@ -985,7 +1471,8 @@ fn add_windows_firewall_rule(rule_name: &str, program: &str, protocol: &str) {
 
 /// Middleware that injects `X-Forwarded-Proto: https` on requests arriving
 /// via the HTTPS listener, so downstream handlers (e.g. voice join) can
-/// return `wss://` URLs instead of `ws://`.
+/// return `wss://` URLs instead of `ws://`. HTTP/2 and HTTP/3 requests carry
+/// the host in `:authority`; copy it into `Host` for handlers that read it.
 async fn inject_https_proto(
     mut req: axum::extract::Request,
     next: axum::middleware::Next,
@@ -994,6 +1481,15 @@ async fn inject_https_proto(
         "x-forwarded-proto",
         axum::http::HeaderValue::from_static("https"),
     );
+    if !req.headers().contains_key(axum::http::header::HOST) {
+        if let Some(host) = req
+            .uri()
+            .authority()
+            .and_then(|authority| axum::http::HeaderValue::from_str(authority.as_str()).ok())
+        {
+            req.headers_mut().insert(axum::http::header::HOST, host);
+        }
+    }
     next.run(req).await
 }
 
@@ -1084,19 +1580,27 @@ fn build_at_rest_profile(config: &config::Config) -> Result<AtRestRuntimeProfile
         return Ok(AtRestRuntimeProfile::default());
     }
 
-    let key_env_name = config.at_rest.key_env.trim();
-    if key_env_name.is_empty() {
-        anyhow::bail!("at_rest.key_env must not be empty when at-rest encryption is enabled");
-    }
-    let raw_master_key = std::env::var(key_env_name).with_context(|| {
-        format!(
-            "at-rest encryption is enabled but env var '{}' is not set",
-            key_env_name
-        )
-    })?;
+    let (raw_master_key, key_source) = match config.at_rest.key.clone() {
+        Some(key) => (key, "at_rest.key"),
+        None => {
+            let key_env_name = config.at_rest.key_env.trim();
+            if key_env_name.is_empty() {
+                anyhow::bail!(
+                    "at_rest.key_env must not be empty when at-rest encryption is enabled"
+                );
+            }
+            let raw_master_key = std::env::var(key_env_name).with_context(|| {
+                format!(
+                    "at-rest encryption is enabled but env var '{}' is not set",
+                    key_env_name
+                )
+            })?;
+            (raw_master_key, key_env_name)
+        }
+    };
 
     let master_key = paracord_util::at_rest::parse_master_key(&raw_master_key)
-        .map_err(|err| anyhow::anyhow!("invalid at-rest key in {}: {}", key_env_name, err))?;
+        .map_err(|err| anyhow::anyhow!("invalid at-rest key in {}: {}", key_source, err))?;
 
     let sqlite_key_hex = if encrypt_sqlite {
         Some(paracord_util::at_rest::derive_sqlite_key_hex(&master_key))
@@ -1125,27 +1629,33 @@ fn build_at_rest_profile(config: &config::Config) -> Result<AtRestRuntimeProfile
     })
 }
 
-fn spawn_pending_attachment_cleanup(
-    db: paracord_db::DbPool,
-    backend: Arc<paracord_media::Storage>,
+/// Background work every instance (main or tenant) runs on its own data.
+fn spawn_instance_workers(
+    state: &paracord_core::AppState,
+    config: &config::Config,
+    vapid_keys: Option<web_push::VapidKeys>,
     shutdown: Arc<tokio::sync::Notify>,
 ) {
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-        loop {
-            tokio::select! {
-                _ = shutdown.notified() => {
-                    break;
-                }
-                _ = interval.tick() => {
-                    if let Err(err) = cleanup_pending_attachments_once(&db, &backend).await {
-                        tracing::warn!("Pending attachment cleanup failed: {}", err);
-                    }
-                }
-            }
-        }
-    });
+    spawn_scheduled_event_jobs(state.clone(), shutdown.clone());
+    spawn_reminder_worker(state.clone(), shutdown.clone());
+    spawn_mod_log_worker(state.clone(), shutdown.clone());
+    spawn_thread_archive_worker(state.clone(), shutdown.clone());
+    if matches!(config.database.engine, config::DatabaseEngine::Sqlite) {
+        spawn_sqlite_maintenance_worker(
+            state.clone(),
+            config.sqlite_maintenance.clone(),
+            shutdown.clone(),
+        );
+    }
+    bots::spawn_bot_manager(state.clone(), shutdown.clone());
+    if let Some(keys) = vapid_keys {
+        web_push::spawn(
+            state.clone(),
+            keys,
+            config.web_push.subject.clone().unwrap_or_default(),
+            shutdown,
+        );
+    }
 }
 
 async fn cleanup_pending_attachments_once(
@@ -1171,6 +1681,21 @@ async fn cleanup_pending_attachments_once(
     Ok(())
 }
 
+async fn cleanup_expired_channel_exports_once(
+    db: &paracord_db::DbPool,
+    backend: &paracord_media::Storage,
+) -> Result<()> {
+    let expired =
+        paracord_db::channel_exports::get_expired_exports(db, chrono::Utc::now(), 256).await?;
+    for export in expired {
+        if let Some(storage_key) = export.storage_key.as_deref() {
+            let _ = backend.delete(storage_key).await;
+        }
+        let _ = paracord_db::channel_exports::delete_export(db, export.id).await;
+    }
+    Ok(())
+}
+
 fn spawn_federation_delivery_worker(
     state: paracord_core::AppState,
     shutdown: Arc<tokio::sync::Notify>,
@@ -1195,41 +1720,162 @@ fn spawn_federation_delivery_worker(
                         .await;
                     let cutoff = chrono::Utc::now().timestamp_millis() - 86_400_000;
                     let _ = paracord_db::federation::prune_transport_replay_cache(&state.db, cutoff).await;
+                    let _ = paracord_federation::bandwidth::purge_expired(&state.db).await;
                 }
             }
         }
     });
 }
 
-fn spawn_retention_jobs(
-    db: paracord_db::DbPool,
-    backend: Arc<paracord_media::Storage>,
-    retention: config::RetentionConfig,
+fn spawn_federation_file_cache_sweeper(
+    state: paracord_core::AppState,
     shutdown: Arc<tokio::sync::Notify>,
 ) {
-    if !retention.enabled {
-        tracing::info!("Retention worker disabled");
+    if !state.config.federation_file_cache_enabled {
         return;
     }
 
-    let interval_seconds = retention.interval_seconds.max(60);
-    tracing::info!(
-        "Retention worker enabled (interval={}s, batch_size={})",
-        interval_seconds,
-        retention.batch_size
-    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_api::routes::files::sweep_federated_file_cache_once(&state).await;
+                }
+            }
+        }
+    });
+}
 
+fn spawn_scheduled_event_jobs(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_seconds));
+        // Voice events whose channel is empty, and since when.
+        let mut empty_since = std::collections::HashMap::new();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         loop {
             tokio::select! {
-                _ = shutdown.notified() => {
-                    break;
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_api::routes::events::run_event_reminders_once(&state).await;
+                    paracord_api::routes::events::end_idle_voice_events_once(&state, &mut empty_since)
+                        .await;
+                }
+            }
+        }
+    });
+}
+
+fn spawn_reminder_worker(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_api::routes::reminders::run_due_reminders_once(&state).await;
+                }
+            }
+        }
+    });
+}
+
+/// Post queued mod log lines; the interval is how long lines batch up.
+fn spawn_mod_log_worker(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_api::routes::mod_log::run_mod_log_flush_once(&state).await;
+                }
+            }
+        }
+    });
+}
+
+fn spawn_thread_archive_worker(state: paracord_core::AppState, shutdown: Arc<tokio::sync::Notify>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_api::routes::channels::run_thread_auto_archive_once(&state).await;
+                }
+            }
+        }
+    });
+}
+
+/// Probe the database pool so checkout waits show up in metrics and
+/// exhaustion is logged before requests start timing out.
+fn spawn_db_pool_monitor_worker(
+    state: paracord_core::AppState,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    paracord_core::observability::sample_db_pool(&state.db).await;
                 }
+            }
+        }
+    });
+}
+
+/// Keep the SQLite WAL from growing without bound and refresh planner
+/// statistics.
+fn spawn_sqlite_maintenance_worker(
+    state: paracord_core::AppState,
+    maintenance: config::SqliteMaintenanceConfig,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    if !maintenance.enabled {
+        tracing::info!("SQLite maintenance worker disabled");
+        return;
+    }
+    tokio::spawn(async move {
+        if maintenance.incremental_vacuum {
+            match paracord_db::maintenance::enable_incremental_auto_vacuum(&state.db).await {
+                Ok(true) => tracing::info!("Switched SQLite database to incremental auto-vacuum"),
+                Ok(false) => {}
+                Err(err) => tracing::warn!("Failed to enable incremental auto-vacuum: {}", err),
+            }
+        }
+        let vacuum_pages = maintenance
+            .incremental_vacuum
+            .then_some(maintenance.incremental_vacuum_pages.max(0));
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            maintenance.interval_seconds.max(60),
+        ));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
                 _ = interval.tick() => {
-                    if let Err(err) = run_retention_once(&db, &backend, &retention).await {
-                        tracing::warn!("Retention cleanup failed: {}", err);
+                    match paracord_core::maintenance::run_sqlite_maintenance(
+                        &state.db,
+                        &state.config.database_url,
+                        vacuum_pages,
+                    )
+                    .await
+                    {
+                        Ok(report) if report.checkpoint_busy => {
+                            tracing::warn!("SQLite WAL checkpoint was blocked by active connections; retrying next run");
+                        }
+                        Ok(_) => {}
+                        Err(err) => tracing::warn!("SQLite maintenance failed: {}", err),
                     }
                 }
             }
@@ -1237,6 +1883,81 @@ fn spawn_retention_jobs(
     });
 }
 
+/// Concurrent workers draining the background job queue.
+const BACKGROUND_JOB_WORKERS: usize = 4;
+/// Recurring job: drop expired pending uploads and channel exports.
+const JOB_PENDING_ATTACHMENT_CLEANUP: &str = "pending_attachment_cleanup";
+/// Recurring job: apply `[retention]` limits.
+const JOB_RETENTION: &str = "retention";
+/// Recurring job: transcode a batch of queued video attachments.
+const JOB_VIDEO_TRANSCODE: &str = "video_transcode";
+
+/// Periodic cleanup and video transcoding passes, run on every instance as
+/// recurring background jobs.
+fn register_maintenance_jobs(
+    registry: &mut paracord_core::jobs::JobRegistry,
+    config: &config::Config,
+    transcoder: Option<paracord_media::transcode::Transcoder>,
+) {
+    registry.register_recurring(
+        JOB_PENDING_ATTACHMENT_CLEANUP,
+        chrono::Duration::minutes(5),
+        |state: paracord_core::AppState, _job| async move {
+            let attachments =
+                cleanup_pending_attachments_once(&state.db, &state.storage_backend).await;
+            let exports =
+                cleanup_expired_channel_exports_once(&state.db, &state.storage_backend).await;
+            attachments.and(exports).map_err(|e| e.to_string())
+        },
+    );
+
+    let retention = config.retention.clone();
+    if retention.enabled {
+        let interval_seconds = retention.interval_seconds.max(60);
+        tracing::info!(
+            "Retention job enabled (interval={}s, batch_size={})",
+            interval_seconds,
+            retention.batch_size
+        );
+        registry.register_recurring(
+            JOB_RETENTION,
+            chrono::Duration::seconds(interval_seconds as i64),
+            move |state: paracord_core::AppState, _job| {
+                let retention = retention.clone();
+                async move {
+                    run_retention_once(&state.db, &state.storage_backend, &retention)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            },
+        );
+    } else {
+        tracing::info!("Retention job disabled");
+    }
+
+    if let Some(transcoder) = transcoder {
+        let interval_seconds = config.transcoding.poll_interval_seconds.max(5);
+        tracing::info!(
+            "Video transcoding job enabled (interval={}s)",
+            interval_seconds
+        );
+        registry.register_recurring(
+            JOB_VIDEO_TRANSCODE,
+            chrono::Duration::seconds(interval_seconds as i64),
+            move |state: paracord_core::AppState, _job| {
+                let transcoder = transcoder.clone();
+                async move {
+                    run_transcoding_once(&state, &transcoder)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            },
+        );
+    } else {
+        tracing::info!("Video transcoding job disabled");
+    }
+}
+
 async fn run_retention_once(
     db: &paracord_db::DbPool,
     backend: &paracord_media::Storage,
@@ -1518,6 +2239,103 @@ async fn purge_security_events_older_than(
     Ok(total_deleted)
 }
 
+/// Put transcodes that were running when the server stopped back in line.
+async fn requeue_interrupted_transcodes(state: &paracord_core::AppState) {
+    match paracord_db::attachments::requeue_interrupted_transcodes(&state.db).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Requeued {} interrupted transcode job(s)", count),
+        Err(err) => tracing::warn!("Failed requeueing interrupted transcodes: {}", err),
+    }
+}
+
+async fn run_transcoding_once(
+    state: &paracord_core::AppState,
+    transcoder: &paracord_media::transcode::Transcoder,
+) -> Result<()> {
+    const TRANSCODE_BATCH: i64 = 4;
+    let jobs =
+        paracord_db::attachments::get_pending_transcode_jobs(&state.db, TRANSCODE_BATCH).await?;
+    for job in jobs {
+        if !paracord_db::attachments::claim_transcode_job(&state.db, job.id).await? {
+            continue;
+        }
+        match transcode_attachment(state, transcoder, &job).await {
+            Ok(size) => {
+                paracord_db::attachments::set_transcode_status(
+                    &state.db,
+                    job.id,
+                    paracord_media::transcode::TRANSCODE_DONE,
+                    Some(size),
+                )
+                .await?;
+                tracing::info!("Transcoded attachment {} ({} bytes)", job.id, size);
+            }
+            Err(err) => {
+                tracing::warn!("Transcoding attachment {} failed: {}", job.id, err);
+                paracord_db::attachments::set_transcode_status(
+                    &state.db,
+                    job.id,
+                    paracord_media::transcode::TRANSCODE_FAILED,
+                    None,
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn transcode_attachment(
+    state: &paracord_core::AppState,
+    transcoder: &paracord_media::transcode::Transcoder,
+    attachment: &paracord_db::attachments::AttachmentRow,
+) -> Result<i32> {
+    let stored = state
+        .storage_backend
+        .retrieve(&attachment_storage_key(attachment))
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let cryptor = state.config.file_cryptor.as_ref();
+    let source = match cryptor {
+        Some(cryptor) if paracord_util::at_rest::FileCryptor::payload_is_encrypted(&stored) => {
+            let aad = format!("attachment:{}", attachment.id);
+            cryptor
+                .decrypt_with_aad(&stored, aad.as_bytes())
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?
+        }
+        _ => stored,
+    };
+
+    // Every rendition counts towards the attachment's stored size.
+    let mut total_size: i32 = 0;
+    for format in paracord_media::transcode::TranscodeFormat::ALL {
+        let output = transcoder.transcode(attachment.id, &source, format).await?;
+        let size = i32::try_from(output.len()).context("transcoded output too large")?;
+        total_size = total_size
+            .checked_add(size)
+            .context("transcoded output too large")?;
+        let payload = match cryptor {
+            Some(cryptor) => {
+                let aad =
+                    paracord_api::routes::files::transcoded_attachment_aad(attachment.id, format);
+                cryptor
+                    .encrypt_with_aad(&output, aad.as_bytes())
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+            }
+            None => output,
+        };
+        state
+            .storage_backend
+            .store(
+                &paracord_media::transcode::transcoded_storage_key(attachment.id, format),
+                &payload,
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+    Ok(total_size)
+}
+
 fn attachment_storage_key(attachment: &paracord_db::attachments::AttachmentRow) -> String {
     let ext = std::path::Path::new(&attachment.filename)
         .extension()
@@ -1534,6 +2352,23 @@ async fn remove_attachment_file(
     if let Err(err) = backend.delete(&key).await {
         tracing::warn!("Failed deleting attachment file {}: {}", attachment.id, err);
     }
+    if attachment.transcode_status.is_some() {
+        for format in paracord_media::transcode::TranscodeFormat::ALL {
+            let key = paracord_media::transcode::transcoded_storage_key(attachment.id, format);
+            let _ = backend.delete(&key).await;
+        }
+    }
+    if let Some(format) = attachment
+        .thumbnail_format
+        .as_deref()
+        .and_then(paracord_media::thumbnails::ThumbnailFormat::parse)
+    {
+        for size in paracord_media::thumbnails::THUMBNAIL_SIZES {
+            let key =
+                paracord_media::thumbnails::thumbnail_storage_key(attachment.id, size, format);
+            let _ = backend.delete(&key).await;
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1692,8 +2527,7 @@ fn spawn_auto_backup(
 async fn unified_media_accept_loop(
     endpoint: Arc<paracord_transport::endpoint::MediaEndpoint>,
     relay: Arc<paracord_relay::relay::RelayForwarder>,
-    jwt_secret: String,
-    db: paracord_db::DbPool,
+    auth: Arc<[tenants::MediaAuth]>,
 ) {
     tracing::info!(
         "Unified media accept loop started (ALPN routing: h3 → WebTransport, other → raw QUIC)"
@@ -1708,22 +2542,25 @@ async fn unified_media_accept_loop(
         };
 
         let relay = Arc::clone(&relay);
-        let jwt_secret = jwt_secret.clone();
-        let db = db.clone();
+        let auth = Arc::clone(&auth);
         tokio::spawn(async move {
+            use paracord_transport::metrics::{self as transport_metrics, HandshakeStage};
             let conn = match incoming.accept() {
                 Ok(connecting) => match connecting.await {
                     Ok(conn) => conn,
                     Err(e) => {
+                        transport_metrics::record_handshake_failure(HandshakeStage::Quic);
                         tracing::debug!("Media connection failed: {}", e);
                         return;
                     }
                 },
                 Err(e) => {
+                    transport_metrics::record_handshake_failure(HandshakeStage::Accept);
                     tracing::debug!("Media incoming accept failed: {}", e);
                     return;
                 }
             };
+            transport_metrics::record_quic_connection();
 
             // Inspect the negotiated ALPN to determine connection type.
             let alpn = conn
@@ -1734,9 +2571,9 @@ async fn unified_media_accept_loop(
             let is_h3 = alpn.as_deref() == Some(b"h3");
 
             if is_h3 {
-                handle_webtransport_connection(conn, relay, jwt_secret, db).await;
+                handle_webtransport_connection(conn, relay, auth).await;
             } else {
-                handle_raw_quic_connection(conn, relay, jwt_secret, db).await;
+                handle_raw_quic_connection(conn, relay, auth).await;
             }
         });
     }
@@ -1746,39 +2583,52 @@ async fn unified_media_accept_loop(
 async fn handle_raw_quic_connection(
     conn: quinn::Connection,
     relay: Arc<paracord_relay::relay::RelayForwarder>,
-    jwt_secret: String,
-    db: paracord_db::DbPool,
+    auth: Arc<[tenants::MediaAuth]>,
 ) {
     let remote_addr = conn.remote_address();
     tracing::info!(addr = %remote_addr, "QUIC: new raw media connection");
 
-    let media_conn = match paracord_transport::connection::MediaConnection::accept_and_auth(
-        conn.clone(),
-        &jwt_secret,
-        paracord_transport::connection::ConnectionMode::Relay,
-    )
-    .await
-    {
-        Ok(mc) => mc,
-        Err(e) => {
-            tracing::warn!(addr = %remote_addr, "QUIC: auth failed: {}", e);
-            return;
-        }
-    };
+    let secrets: Vec<&str> = auth.iter().map(|a| a.jwt_secret.as_str()).collect();
+    let (mut media_conn, instance) =
+        match paracord_transport::connection::MediaConnection::accept_and_auth_any(
+            conn.clone(),
+            &secrets,
+            paracord_transport::connection::ConnectionMode::Relay,
+        )
+        .await
+        {
+            Ok(authed) => authed,
+            Err(e) => {
+                paracord_transport::metrics::record_handshake_failure(
+                    paracord_transport::metrics::HandshakeStage::Auth,
+                );
+                tracing::warn!(addr = %remote_addr, "QUIC: auth failed: {}", e);
+                return;
+            }
+        };
 
     let user_id = media_conn.meta().user_id;
     tracing::info!(user_id, addr = %remote_addr, "QUIC: authenticated");
 
-    // Look up user's voice state to determine room
-    let room_id = match paracord_db::voice_states::get_all_user_voice_states(&db, user_id).await {
-        Ok(states) if !states.is_empty() => {
-            let vs = &states[0];
-            let guild_id = vs.guild_id().unwrap_or(0);
-            format!("{}:{}", guild_id, vs.channel_id)
-        }
-        _ => {
-            tracing::warn!(user_id, "QUIC: user not in any voice channel");
-            return;
+    // Prefer a room scoped into the token (WHIP publishers), otherwise look
+    // up the user's voice state to determine room.
+    let token_room = media_conn.meta().room.clone();
+    let room_id = match token_room {
+        Some(room) => room,
+        None => {
+            match paracord_db::voice_states::get_all_user_voice_states(&auth[instance].db, user_id)
+                .await
+            {
+                Ok(states) if !states.is_empty() => {
+                    let vs = &states[0];
+                    let guild_id = vs.guild_id().unwrap_or(0);
+                    format!("{}:{}", guild_id, vs.channel_id)
+                }
+                _ => {
+                    tracing::warn!(user_id, "QUIC: user not in any voice channel");
+                    return;
+                }
+            }
         }
     };
 
@@ -1786,19 +2636,25 @@ async fn handle_raw_quic_connection(
     relay.add_connection(handle.clone());
     relay.spawn_forwarding_task(handle);
     tracing::info!(user_id, room_id = %room_id, "QUIC: relay forwarding started");
+
+    if let Some((send, recv)) = media_conn.take_control_stream() {
+        tokio::spawn(run_quic_control_stream(user_id, relay, send, recv));
+    }
 }
 
 /// Handle an HTTP/3 WebTransport connection from a browser client.
+///
+/// Browsers may pool several sessions onto one connection or open a new
+/// session after an earlier one closed, so sessions are accepted until the
+/// connection goes away and each is authenticated on its own.
 async fn handle_webtransport_connection(
     conn: quinn::Connection,
     relay: Arc<paracord_relay::relay::RelayForwarder>,
-    jwt_secret: String,
-    db: paracord_db::DbPool,
+    auth: Arc<[tenants::MediaAuth]>,
 ) {
     let remote_addr = conn.remote_address();
     tracing::info!(addr = %remote_addr, "WebTransport: new HTTP/3 connection");
 
-    // Handle as HTTP/3 and accept WebTransport session
     let mut h3_session =
         match paracord_transport::webtransport::WebTransportServer::handle_connection(conn.clone())
             .await
@@ -1806,34 +2662,59 @@ async fn handle_webtransport_connection(
             Ok(s) => s,
             Err(e) => {
                 tracing::debug!(addr = %remote_addr, "WebTransport: HTTP/3 setup failed: {}", e);
+                paracord_transport::metrics::record_handshake_failure(
+                    paracord_transport::metrics::HandshakeStage::WebTransport,
+                );
                 return;
             }
         };
 
-    let wt_session = match h3_session.accept_session().await {
-        Ok(Some(s)) => s,
-        Ok(None) => {
-            tracing::debug!(addr = %remote_addr, "WebTransport: no session received");
-            return;
-        }
-        Err(e) => {
-            tracing::debug!(addr = %remote_addr, "WebTransport: session accept failed: {}", e);
-            return;
+    loop {
+        match h3_session.accept_session().await {
+            Ok(Some(session)) => {
+                tokio::spawn(handle_webtransport_session(
+                    session,
+                    relay.clone(),
+                    auth.clone(),
+                ));
+            }
+            Ok(None) => {
+                tracing::debug!(addr = %remote_addr, "WebTransport: connection closed");
+                return;
+            }
+            Err(e) => {
+                tracing::debug!(addr = %remote_addr, "WebTransport: session accept failed: {}", e);
+                return;
+            }
         }
-    };
+    }
+}
 
+/// Authenticate one WebTransport session and attach it to the relay.
+async fn handle_webtransport_session(
+    mut wt_session: paracord_transport::webtransport::WebTransportSession,
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    auth: Arc<[tenants::MediaAuth]>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let remote_addr = wt_session.remote_address();
     tracing::info!(
         addr = %remote_addr,
         path = %wt_session.path(),
+        session_id = wt_session.session_id(),
         "WebTransport: session established"
     );
+    let attempt = paracord_transport::metrics::HandshakeAttempt::new(
+        paracord_transport::metrics::HandshakeStage::Auth,
+    );
 
     // Authenticate: read first bidi stream message (newline-delimited
     // JSON `{ "type": "auth", "token": "..." }`).
     let (mut send, mut recv) = match wt_session.accept_bi().await {
-        Ok(pair) => pair,
-        Err(e) => {
-            tracing::warn!(addr = %remote_addr, "WebTransport: no bidi stream for auth: {}", e);
+        Some(pair) => pair,
+        None => {
+            tracing::warn!(addr = %remote_addr, "WebTransport: session closed before auth stream");
             return;
         }
     };
@@ -1843,10 +2724,15 @@ async fn handle_webtransport_connection(
     let mut total = 0usize;
     let user_id: i64;
     let room_id: String;
+    let leftover: Vec<u8>;
 
     loop {
         match recv.read(&mut buf[total..]).await {
-            Ok(Some(n)) => {
+            Ok(0) => {
+                tracing::warn!(addr = %remote_addr, "WebTransport: stream closed before auth");
+                return;
+            }
+            Ok(n) => {
                 total += n;
                 // Look for newline delimiter
                 if let Some(nl_pos) = buf[..total].iter().position(|&b| b == b'\n') {
@@ -1872,18 +2758,30 @@ async fn handle_webtransport_connection(
                         }
                     };
 
-                    // Validate JWT
+                    // Validate JWT against every instance sharing the endpoint.
                     let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
-                    let token_data = match jsonwebtoken::decode::<serde_json::Value>(
-                        token,
-                        &jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes()),
-                        &validation,
-                    ) {
-                        Ok(td) => td,
-                        Err(e) => {
-                            tracing::warn!(addr = %remote_addr, "WebTransport: JWT validation failed: {}", e);
-                            return;
+                    let mut decoded = None;
+                    let mut last_error = None;
+                    for instance in auth.iter() {
+                        match jsonwebtoken::decode::<serde_json::Value>(
+                            token,
+                            &jsonwebtoken::DecodingKey::from_secret(instance.jwt_secret.as_bytes()),
+                            &validation,
+                        ) {
+                            Ok(td) => {
+                                decoded = Some((td, instance.db.clone()));
+                                break;
+                            }
+                            Err(e) => last_error = Some(e),
                         }
+                    }
+                    let Some((token_data, db)) = decoded else {
+                        tracing::warn!(
+                            addr = %remote_addr,
+                            "WebTransport: JWT validation failed: {:?}",
+                            last_error
+                        );
+                        return;
                     };
 
                     let claims = token_data.claims;
@@ -1925,6 +2823,7 @@ async fn handle_webtransport_connection(
                     let ack = b"{\"type\":\"auth_ok\"}\n";
                     let _ = send.write_all(ack).await;
 
+                    leftover = buf[nl_pos + 1..total].to_vec();
                     break;
                 }
 
@@ -1933,10 +2832,6 @@ async fn handle_webtransport_connection(
                     return;
                 }
             }
-            Ok(None) => {
-                tracing::warn!(addr = %remote_addr, "WebTransport: stream closed before auth");
-                return;
-            }
             Err(e) => {
                 tracing::warn!(addr = %remote_addr, "WebTransport: read error during auth: {}", e);
                 return;
@@ -1950,13 +2845,11 @@ async fn handle_webtransport_connection(
         addr = %remote_addr,
         "WebTransport: authenticated"
     );
+    attempt.complete();
 
-    // Spawn datagram bridge (handles QSID framing).
-    // The first WebTransport session on a fresh connection has QSID = 0.
-    let (outbound_tx, inbound_rx) = paracord_transport::webtransport::spawn_webtransport_bridge(
-        wt_session.quinn_conn().clone(),
-        0,
-    );
+    // Spawn datagram bridge (handles QSID framing for this session).
+    let (outbound_tx, inbound_rx) =
+        paracord_transport::webtransport::spawn_webtransport_bridge(&mut wt_session);
 
     // Create bridged connection handle and start forwarding
     let handle = paracord_relay::relay::ConnectionHandle::new_bridged(
@@ -1972,6 +2865,284 @@ async fn handle_webtransport_connection(
         room_id = %room_id,
         "WebTransport: relay forwarding started"
     );
+
+    tokio::spawn(run_webtransport_control_stream(
+        user_id, relay, send, recv, leftover,
+    ));
+}
+
+// ── In-call control stream ───────────────────────────────────────────────
+
+/// Maximum size of a newline-delimited control message from a browser.
+const WEBTRANSPORT_CONTROL_LINE_MAX: usize = 8192;
+
+/// Apply a control message received on a participant's control stream after
+/// authentication.
+fn handle_media_control_message(
+    relay: &paracord_relay::relay::RelayForwarder,
+    user_id: i64,
+    msg: paracord_transport::control::ControlMessage,
+    reply: &tokio::sync::mpsc::UnboundedSender<paracord_transport::control::ControlMessage>,
+) {
+    use paracord_transport::control::ControlMessage;
+    match msg {
+        ControlMessage::RoomSignal { signal, .. } => {
+            if let Err(e) = relay.broadcast_room_signal(user_id, signal) {
+                tracing::debug!(user_id, "Media control: room signal dropped: {}", e);
+            }
+        }
+        ControlMessage::P2PCapability { supported } => {
+            relay.set_p2p_capability(user_id, supported);
+        }
+        ControlMessage::P2PResult {
+            peer_user_id,
+            established,
+        } => {
+            relay.handle_p2p_result(user_id, peer_user_id, established);
+        }
+        ControlMessage::Ping => {
+            let _ = reply.send(ControlMessage::Pong);
+        }
+        other => {
+            tracing::debug!(user_id, message = ?other, "Media control: ignoring message");
+        }
+    }
+}
+
+/// Serve the length-prefixed control stream of a raw QUIC participant.
+async fn run_quic_control_stream(
+    user_id: i64,
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    mut send: quinn::SendStream,
+    mut recv: quinn::RecvStream,
+) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    relay.attach_control_channel(user_id, tx.clone());
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let frame = match msg.encode() {
+                Ok(frame) => frame,
+                Err(e) => {
+                    tracing::warn!(user_id, "Media control: encode failed: {}", e);
+                    continue;
+                }
+            };
+            if send.write_all(&frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut codec = paracord_transport::control::ControlCodec::new();
+    let mut buf = vec![0u8; 4096];
+    'read: while let Ok(Some(n)) = recv.read(&mut buf).await {
+        codec.feed(&buf[..n]);
+        loop {
+            match codec.decode_next() {
+                Ok(Some(msg)) => handle_media_control_message(&relay, user_id, msg, &tx),
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!(user_id, "QUIC: invalid control message: {}", e);
+                    break 'read;
+                }
+            }
+        }
+    }
+    writer.abort();
+}
+
+/// Serve the newline-delimited JSON control stream of a WebTransport
+/// participant. `pending` holds any bytes read past the auth message.
+async fn run_webtransport_control_stream(
+    user_id: i64,
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    mut send: paracord_transport::webtransport::WebTransportSendStream,
+    mut recv: paracord_transport::webtransport::WebTransportRecvStream,
+    mut pending: Vec<u8>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (tx, mut rx) =
+        tokio::sync::mpsc::unbounded_channel::<paracord_transport::control::ControlMessage>();
+    relay.attach_control_channel(user_id, tx.clone());
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let mut line = match serde_json::to_vec(&msg) {
+                Ok(line) => line,
+                Err(e) => {
+                    tracing::warn!(user_id, "Media control: encode failed: {}", e);
+                    continue;
+                }
+            };
+            line.push(b'\n');
+            if send.write_all(&line).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut buf = vec![0u8; 4096];
+    'read: loop {
+        while let Some(nl_pos) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=nl_pos).collect();
+            let line = &line[..line.len() - 1];
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice(line) {
+                Ok(msg) => handle_media_control_message(&relay, user_id, msg, &tx),
+                Err(e) => {
+                    tracing::debug!(user_id, "WebTransport: invalid control message: {}", e);
+                }
+            }
+        }
+        if pending.len() > WEBTRANSPORT_CONTROL_LINE_MAX {
+            tracing::warn!(user_id, "WebTransport: control message too large");
+            break 'read;
+        }
+        match recv.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => pending.extend_from_slice(&buf[..n]),
+        }
+    }
+    writer.abort();
+}
+
+/// How often connected voice participants receive `VOICE_METRICS`.
+const VOICE_METRICS_INTERVAL_SECONDS: u64 = 5;
+
+/// Periodically push relay forwarding stats to everyone connected to each
+/// room, driving the client's connection quality indicator.
+fn spawn_voice_metrics_dispatch(
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    event_bus: paracord_core::events::EventBus,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            VOICE_METRICS_INTERVAL_SECONDS,
+        ));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    break;
+                }
+                _ = interval.tick() => {
+                    for (room_id, stats) in relay.all_room_stats() {
+                        let Some((guild_id, channel_id)) = room_id.split_once(':') else {
+                            continue;
+                        };
+                        let user_ids: Vec<i64> = stats.iter().map(|s| s.user_id).collect();
+                        let participants: Vec<serde_json::Value> =
+                            stats.iter().map(|s| s.to_json()).collect();
+                        event_bus.dispatch_to_users(
+                            paracord_models::gateway::EVENT_VOICE_METRICS,
+                            serde_json::json!({
+                                "guild_id": guild_id,
+                                "channel_id": channel_id,
+                                "participants": participants,
+                            }),
+                            user_ids,
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Surface accepted room signals (reactions, raised hands) as gateway events
+/// so guild members who are not connected to the media server see them too.
+fn spawn_room_signal_dispatch(
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    event_bus: paracord_core::events::EventBus,
+) {
+    let mut signals = relay.subscribe_room_signals();
+    tokio::spawn(async move {
+        loop {
+            let event = match signals.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!(skipped, "Room signal dispatch lagged");
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            // Relay room ids are `{guild_id}:{channel_id}`.
+            // DM calls (guild 0) have no audience beyond the connected peers.
+            let Some((guild_id, channel_id)) = event
+                .room_id
+                .split_once(':')
+                .and_then(|(g, c)| Some((g.parse::<i64>().ok()?, c.parse::<i64>().ok()?)))
+                .filter(|(guild_id, _)| *guild_id != 0)
+            else {
+                continue;
+            };
+            let mut payload = serde_json::json!({
+                "guild_id": guild_id.to_string(),
+                "channel_id": channel_id.to_string(),
+                "user_id": event.sender_user_id.to_string(),
+            });
+            if let (Some(obj), Ok(serde_json::Value::Object(signal))) =
+                (payload.as_object_mut(), serde_json::to_value(&event.signal))
+            {
+                obj.extend(signal);
+            }
+            event_bus.dispatch(
+                paracord_models::gateway::EVENT_VOICE_ROOM_SIGNAL,
+                payload,
+                Some(guild_id),
+            );
+        }
+    });
+}
+
+fn spawn_speaking_dispatch(
+    relay: Arc<paracord_relay::relay::RelayForwarder>,
+    event_bus: paracord_core::events::EventBus,
+) {
+    let mut speaking = relay.subscribe_speaking();
+    tokio::spawn(async move {
+        loop {
+            let event = match speaking.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!(skipped, "Speaking dispatch lagged");
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            // Relay room ids are `{guild_id}:{channel_id}`.
+            let Some((guild_id, channel_id)) = event
+                .room_id
+                .split_once(':')
+                .and_then(|(g, c)| Some((g.parse::<i64>().ok()?, c.parse::<i64>().ok()?)))
+            else {
+                continue;
+            };
+            let payload = serde_json::json!({
+                "guild_id": (guild_id != 0).then(|| guild_id.to_string()),
+                "channel_id": channel_id.to_string(),
+                "user_id": event.user_id.to_string(),
+                "speaking": event.speaking,
+            });
+            if guild_id != 0 {
+                event_bus.dispatch(
+                    paracord_models::gateway::EVENT_VOICE_SPEAKING,
+                    payload,
+                    Some(guild_id),
+                );
+            } else {
+                // DM calls have no guild audience; tell the other callers.
+                event_bus.dispatch_to_users(
+                    paracord_models::gateway::EVENT_VOICE_SPEAKING,
+                    payload,
+                    relay.room_user_ids(&event.room_id),
+                );
+            }
+        }
+    });
 }
 
 #[cfg(test)]
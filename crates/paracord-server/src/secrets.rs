@@ -0,0 +1,281 @@
+//! Secrets kept out of the config file and environment.
+//!
+//! Every secret can be read from a file named by its `*_file` key or a
+//! `PARACORD_*_FILE` variable (Docker and Kubernetes mount secrets as
+//! files), and `[secrets] command` can fetch them from an external store
+//! such as Vault or a cloud KMS. Later sources win: inline value, then
+//! `PARACORD_*`, then the file, then the command.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+
+struct SecretSource {
+    /// Config path of the value, as the secrets command names it.
+    key: &'static str,
+    /// Environment variable that sets the file path.
+    file_env: Option<&'static str>,
+    file: fn(&mut Config) -> Option<&mut Option<String>>,
+    apply: fn(&mut Config, String),
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+const SOURCES: &[SecretSource] = &[
+    SecretSource {
+        key: "auth.jwt_secret",
+        file_env: Some("PARACORD_JWT_SECRET_FILE"),
+        file: |c| Some(&mut c.auth.jwt_secret_file),
+        apply: |c, v| c.auth.jwt_secret = v,
+    },
+    SecretSource {
+        key: "livekit.api_secret",
+        file_env: Some("PARACORD_LIVEKIT_API_SECRET_FILE"),
+        file: |c| Some(&mut c.livekit.api_secret_file),
+        apply: |c, v| c.livekit.api_secret = v,
+    },
+    SecretSource {
+        key: "s3.secret_access_key",
+        file_env: Some("PARACORD_S3_SECRET_ACCESS_KEY_FILE"),
+        file: |c| Some(&mut c.s3.secret_access_key_file),
+        apply: |c, v| c.s3.secret_access_key = non_empty(v),
+    },
+    SecretSource {
+        key: "at_rest.key",
+        file_env: Some("PARACORD_AT_REST_KEY_FILE"),
+        file: |c| Some(&mut c.at_rest.key_file),
+        apply: |c, v| c.at_rest.key = non_empty(v),
+    },
+    SecretSource {
+        key: "tls.acme.dns.webhook_token",
+        file_env: Some("PARACORD_TLS_ACME_DNS_WEBHOOK_TOKEN_FILE"),
+        file: |c| Some(&mut c.tls.acme.dns.webhook_token_file),
+        apply: |c, v| c.tls.acme.dns.webhook_token = non_empty(v),
+    },
+    SecretSource {
+        key: "hash_matching.service_token",
+        file_env: Some("PARACORD_HASH_MATCHING_SERVICE_TOKEN_FILE"),
+        file: |c| Some(&mut c.hash_matching.service_token_file),
+        apply: |c, v| c.hash_matching.service_token = non_empty(v),
+    },
+    SecretSource {
+        key: "rate_limit.allowlist_tokens",
+        file_env: Some("PARACORD_RATE_LIMIT_ALLOWLIST_TOKENS_FILE"),
+        file: |c| Some(&mut c.rate_limit.allowlist_tokens_file),
+        apply: |c, v| {
+            c.rate_limit.allowlist_tokens = v
+                .lines()
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(str::to_string)
+                .collect()
+        },
+    },
+    SecretSource {
+        key: "supporters.kofi_verification_token",
+        file_env: Some("PARACORD_KOFI_VERIFICATION_TOKEN_FILE"),
+        file: |c| Some(&mut c.supporters.kofi_verification_token_file),
+        apply: |c, v| c.supporters.kofi_verification_token = non_empty(v),
+    },
+    SecretSource {
+        key: "supporters.patreon_webhook_secret",
+        file_env: Some("PARACORD_PATREON_WEBHOOK_SECRET_FILE"),
+        file: |c| Some(&mut c.supporters.patreon_webhook_secret_file),
+        apply: |c, v| c.supporters.patreon_webhook_secret = non_empty(v),
+    },
+    SecretSource {
+        key: "cluster.redis_url",
+        file_env: Some("PARACORD_REDIS_URL_FILE"),
+        file: |c| Some(&mut c.cluster.redis_url_file),
+        apply: |c, v| c.cluster.redis_url = non_empty(v),
+    },
+    // Already file-backed through `web_push.key_path`.
+    SecretSource {
+        key: "web_push.private_key",
+        file_env: None,
+        file: |_| None,
+        apply: |c, v| c.web_push.private_key = non_empty(v),
+    },
+];
+
+/// Apply the `PARACORD_*_FILE` variables.
+pub fn apply_env_overrides(config: &mut Config) {
+    for source in SOURCES {
+        let (Some(env), Some(file)) = (source.file_env, (source.file)(config)) else {
+            continue;
+        };
+        if let Ok(value) = std::env::var(env) {
+            let value = value.trim();
+            *file = (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    if let Ok(value) = std::env::var("PARACORD_SECRETS_COMMAND") {
+        config.secrets.command = value.split_whitespace().map(str::to_string).collect();
+    }
+}
+
+/// Read every `*_file` secret, then run the secrets command.
+pub fn resolve(config: &mut Config) -> Result<()> {
+    load_secret_files(config)?;
+    run_secrets_command(config)
+}
+
+pub fn load_secret_files(config: &mut Config) -> Result<()> {
+    for source in SOURCES {
+        let Some(path) = (source.file)(config)
+            .and_then(|file| file.clone())
+            .filter(|path| !path.trim().is_empty())
+        else {
+            continue;
+        };
+        let value = std::fs::read_to_string(path.trim())
+            .with_context(|| format!("cannot read {} from '{}'", source.key, path.trim()))?;
+        let value = value.trim();
+        if value.is_empty() {
+            anyhow::bail!("{} file '{}' is empty", source.key, path.trim());
+        }
+        (source.apply)(config, value.to_string());
+    }
+    Ok(())
+}
+
+pub fn run_secrets_command(config: &mut Config) -> Result<()> {
+    let Some((program, args)) = config.secrets.command.split_first() else {
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(config.secrets.timeout_seconds.max(1));
+    let output = run_with_timeout(program, args, timeout)
+        .with_context(|| format!("secrets command '{program}' failed"))?;
+    let values: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&output)
+        .with_context(|| format!("secrets command '{program}' did not print a JSON object"))?;
+    apply_fetched(config, values)?;
+    Ok(())
+}
+
+fn apply_fetched(
+    config: &mut Config,
+    values: serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    for (key, value) in values {
+        let source = SOURCES
+            .iter()
+            .find(|source| source.key == key)
+            .with_context(|| format!("secrets command returned unknown key '{key}'"))?;
+        let value = match value {
+            serde_json::Value::String(value) => value.trim().to_string(),
+            // Lists, for the allowlist tokens.
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(str::trim))
+                .collect::<Option<Vec<_>>>()
+                .with_context(|| format!("secrets command value for '{key}' is not a string"))?
+                .join("\n"),
+            _ => anyhow::bail!("secrets command value for '{key}' is not a string"),
+        };
+        (source.apply)(config, value);
+    }
+    Ok(())
+}
+
+fn run_with_timeout(program: &str, args: &[String], timeout: Duration) -> Result<Vec<u8>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    // Drain stdout on another thread so a large output can't block the child.
+    let mut stdout = child.stdout.take().context("no stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        stdout.read_to_end(&mut out).map(|_| out)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let output = reader
+        .join()
+        .map_err(|_| anyhow::anyhow!("stdout reader panicked"))??;
+    if !status.success() {
+        anyhow::bail!("exited with {status}");
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_files_replace_inline_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let jwt = dir.path().join("jwt");
+        std::fs::write(&jwt, "file-secret-0123456789abcdef0123456789\n").unwrap();
+        let tokens = dir.path().join("tokens");
+        std::fs::write(&tokens, "one\n\n two \n").unwrap();
+
+        let mut config = Config::default();
+        config.auth.jwt_secret_file = Some(jwt.to_string_lossy().into_owned());
+        config.rate_limit.allowlist_tokens_file = Some(tokens.to_string_lossy().into_owned());
+        load_secret_files(&mut config).unwrap();
+        assert_eq!(
+            config.auth.jwt_secret,
+            "file-secret-0123456789abcdef0123456789"
+        );
+        assert_eq!(config.rate_limit.allowlist_tokens, vec!["one", "two"]);
+
+        config.livekit.api_secret_file = Some(dir.path().join("missing").to_string_lossy().into());
+        let err = load_secret_files(&mut config).unwrap_err();
+        assert!(format!("{err:#}").contains("livekit.api_secret"));
+    }
+
+    #[test]
+    fn fetched_secrets_are_applied_by_config_path() {
+        let mut config = Config::default();
+        let values = serde_json::json!({
+            "at_rest.key": "00112233",
+            "rate_limit.allowlist_tokens": ["a", "b"],
+        });
+        apply_fetched(&mut config, values.as_object().unwrap().clone()).unwrap();
+        assert_eq!(config.at_rest.key.as_deref(), Some("00112233"));
+        assert_eq!(config.rate_limit.allowlist_tokens, vec!["a", "b"]);
+
+        let unknown = serde_json::json!({ "server.bind_address": "x" });
+        assert!(apply_fetched(&mut config, unknown.as_object().unwrap().clone()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secrets_command_output_is_parsed() {
+        let mut config = Config::default();
+        config.secrets.command = vec![
+            "sh".into(),
+            "-c".into(),
+            r#"printf '{"supporters.kofi_verification_token": "kofi"}'"#.into(),
+        ];
+        run_secrets_command(&mut config).unwrap();
+        assert_eq!(
+            config.supporters.kofi_verification_token.as_deref(),
+            Some("kofi")
+        );
+
+        config.secrets.command = vec!["sh".into(), "-c".into(), "exit 3".into()];
+        assert!(run_secrets_command(&mut config).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Experimental multi-tenant mode.
+//!
+//! `[tenants]` lists extra instances hosted next to the main one. Each gets
+//! its own database, storage prefix, runtime settings, event bus and
+//! in-memory caches, and is picked by the request's Host header; unknown
+//! hosts are served by the main instance. Voice, the native media endpoint,
+//! the transfer limiter and the background job workers are shared.
+//! Federation stays with the main instance.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::Request;
+use axum::Router;
+use hkdf::Hkdf;
+use paracord_core::AppState;
+use sha2::Sha256;
+use tower::ServiceExt;
+
+use crate::config::{Config, DatabaseEngine, TenantConfig};
+
+pub struct Tenant {
+    pub host: String,
+    pub state: AppState,
+}
+
+/// Token secret and database of one instance, for authenticating
+/// connections on the shared media endpoint.
+#[derive(Clone)]
+pub struct MediaAuth {
+    pub jwt_secret: String,
+    pub db: paracord_db::DbPool,
+}
+
+impl MediaAuth {
+    pub fn for_state(state: &AppState) -> Self {
+        Self {
+            jwt_secret: state.config.jwt_secret.clone(),
+            db: state.db.clone(),
+        }
+    }
+}
+
+/// Everything wrong with `[tenants]`, one message per problem.
+pub fn problems(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !config.tenants.enabled {
+        return problems;
+    }
+    let mut hosts = HashSet::new();
+    let mut databases = HashSet::from([config.database.url.trim().to_string()]);
+    let mut prefixes = HashSet::new();
+    for (index, tenant) in config.tenants.instances.iter().enumerate() {
+        let name = format!("tenants.instances[{index}]");
+        match normalize_host(&tenant.host) {
+            Some(host) if host == tenant.host.trim() && !host.contains([':', '/']) => {
+                if !hosts.insert(host) {
+                    problems.push(format!("{name}.host '{}' is listed twice", tenant.host));
+                }
+            }
+            _ => problems.push(format!(
+                "{name}.host '{}' must be a lowercase host name without scheme or port",
+                tenant.host
+            )),
+        }
+
+        let database_url = tenant.database_url.trim();
+        if database_url.is_empty() {
+            problems.push(format!("{name}.database_url is empty"));
+        } else if !databases.insert(database_url.to_string()) {
+            problems.push(format!(
+                "{name}.database_url is already used by another instance"
+            ));
+        } else if !database_url_matches_engine(database_url, config.database.engine) {
+            problems.push(format!(
+                "{name}.database_url does not match database.engine '{}'",
+                engine_name(config.database.engine)
+            ));
+        }
+
+        let prefix = tenant.storage_prefix();
+        if !is_valid_storage_prefix(prefix) {
+            problems.push(format!(
+                "{name}.storage_prefix '{prefix}' must be a single path segment of letters, digits, '.', '-' or '_'"
+            ));
+        } else if !prefixes.insert(prefix.to_string()) {
+            problems.push(format!(
+                "{name}.storage_prefix '{prefix}' is already used by another instance"
+            ));
+        }
+
+        if tenant
+            .jwt_secret
+            .as_deref()
+            .is_some_and(|secret| secret.trim().len() < 32)
+        {
+            problems.push(format!("{name}.jwt_secret must be at least 32 characters"));
+        }
+    }
+    problems
+}
+
+/// Open every configured tenant. `main` supplies the shared parts.
+pub async fn open(
+    config: &Config,
+    main: &AppState,
+    sqlite_key_hex: Option<String>,
+) -> Result<Vec<Tenant>> {
+    if !config.tenants.enabled {
+        return Ok(Vec::new());
+    }
+    if let Some(problem) = problems(config).into_iter().next() {
+        anyhow::bail!("invalid [tenants] configuration: {problem}");
+    }
+    let mut tenants = Vec::with_capacity(config.tenants.instances.len());
+    for tenant in &config.tenants.instances {
+        let opened = open_tenant(config, main, tenant, sqlite_key_hex.clone())
+            .await
+            .with_context(|| format!("failed to open tenant '{}'", tenant.host))?;
+        tracing::info!(
+            "Tenant '{}' ready (storage prefix '{}')",
+            tenant.host,
+            tenant.storage_prefix()
+        );
+        tenants.push(opened);
+    }
+    Ok(tenants)
+}
+
+async fn open_tenant(
+    config: &Config,
+    main: &AppState,
+    tenant: &TenantConfig,
+    sqlite_key_hex: Option<String>,
+) -> Result<Tenant> {
+    let mut database = config.database.clone();
+    database.url = tenant.database_url.trim().to_string();
+    crate::ensure_database_dir(&database);
+    let db = crate::open_database(&database, sqlite_key_hex).await?;
+    crate::clear_stale_voice_states(&db).await;
+    let runtime = crate::load_instance_runtime(&db, config).await;
+
+    let prefix = tenant.storage_prefix();
+    let storage_path = join_path(&config.storage.path, prefix);
+    let media_storage_path = join_path(&config.media.storage_path, prefix);
+    let backup_dir = join_path(&config.backup.backup_dir, prefix);
+    for dir in [&storage_path, &media_storage_path, &backup_dir] {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create directory '{dir}'"))?;
+    }
+
+    let storage = Arc::new(paracord_media::StorageManager::new(
+        paracord_media::StorageConfig {
+            base_path: media_storage_path.clone().into(),
+            max_file_size: config.media.max_file_size,
+            p2p_threshold: config.media.p2p_threshold,
+            allowed_extensions: None,
+        },
+    ));
+    let s3_cfg = (config.storage.storage_type == "s3").then(|| {
+        let mut s3 = config.s3.clone();
+        s3.prefix = format!("{}{}/", s3.prefix, prefix);
+        s3
+    });
+    let storage_backend = Arc::new(
+        paracord_media::create_storage_backend(
+            &config.storage.storage_type,
+            &storage_path,
+            s3_cfg.as_ref(),
+        )
+        .await
+        .context("failed to initialize storage backend")?,
+    );
+
+    let memberships = paracord_db::members::get_all_memberships(&db)
+        .await
+        .context("failed to load memberships for member index")?;
+    let member_index = paracord_core::member_index::MemberIndex::from_memberships(memberships);
+
+    let mut app_config = main.config.clone();
+    app_config.jwt_secret = match tenant.jwt_secret.as_deref() {
+        Some(secret) => secret.trim().to_string(),
+        None => derive_jwt_secret(&config.auth.jwt_secret, &tenant.host),
+    };
+    app_config.storage_path = storage_path;
+    app_config.media_storage_path = media_storage_path;
+    app_config.backup_dir = backup_dir;
+    app_config.database_url = database.url;
+    app_config.public_url = tenant.public_url.clone();
+
+    Ok(Tenant {
+        host: tenant.host.trim().to_string(),
+        state: AppState {
+            db,
+            event_bus: paracord_core::events::EventBus::default(),
+            config: app_config,
+            runtime,
+            storage,
+            storage_backend,
+            online_users: Default::default(),
+            user_presences: Default::default(),
+            permission_cache: paracord_core::build_permission_cache(),
+            federation_service: None,
+            member_index: Arc::new(member_index),
+            presence_manager: Arc::new(paracord_core::presence_manager::PresenceManager::new()),
+            event_log: None,
+            ..main.clone()
+        },
+    })
+}
+
+/// Route each request to the tenant named by its Host header, falling back
+/// to `default`.
+pub fn host_router(default: Router, tenants: Vec<(String, Router)>) -> Router {
+    if tenants.is_empty() {
+        return default;
+    }
+    let tenants: Arc<HashMap<String, Router>> = Arc::new(tenants.into_iter().collect());
+    Router::new().fallback_service(tower::service_fn(move |req: Request| {
+        let router = request_host(&req)
+            .and_then(|host| tenants.get(&host).cloned())
+            .unwrap_or_else(|| default.clone());
+        router.oneshot(req)
+    }))
+}
+
+fn request_host(req: &Request) -> Option<String> {
+    // HTTP/2 and HTTP/3 carry the authority in the URI instead of a Host header.
+    req.uri()
+        .host()
+        .or_else(|| {
+            req.headers()
+                .get(axum::http::header::HOST)
+                .and_then(|value| value.to_str().ok())
+        })
+        .and_then(normalize_host)
+}
+
+/// Lowercase `host` and strip any port and trailing dot.
+pub fn normalize_host(host: &str) -> Option<String> {
+    let host = host.trim();
+    let host = if let Some(rest) = host.strip_prefix('[') {
+        // Bracketed IPv6 literal, optionally followed by a port.
+        &host[..rest.find(']')? + 2]
+    } else {
+        host.rsplit_once(':')
+            .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+            .map_or(host, |(name, _)| name)
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Per-tenant token secret, so a token issued by one instance never
+/// validates on another.
+fn derive_jwt_secret(main_secret: &str, host: &str) -> String {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"paracord-tenant-jwt"), main_secret.as_bytes());
+    let mut secret = [0u8; 32];
+    hkdf.expand(host.trim().as_bytes(), &mut secret)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    secret.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn is_valid_storage_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix != "."
+        && prefix != ".."
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+fn database_url_matches_engine(url: &str, engine: DatabaseEngine) -> bool {
+    match engine {
+        DatabaseEngine::Sqlite => url.starts_with("sqlite:"),
+        DatabaseEngine::Postgres => {
+            url.starts_with("postgres://") || url.starts_with("postgresql://")
+        }
+    }
+}
+
+fn engine_name(engine: DatabaseEngine) -> &'static str {
+    match engine {
+        DatabaseEngine::Sqlite => "sqlite",
+        DatabaseEngine::Postgres => "postgres",
+    }
+}
+
+fn join_path(base: &str, prefix: &str) -> String {
+    Path::new(base).join(prefix).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+
+    fn tenant(host: &str, database_url: &str) -> TenantConfig {
+        TenantConfig {
+            host: host.into(),
+            database_url: database_url.into(),
+            storage_prefix: None,
+            public_url: None,
+            jwt_secret: None,
+        }
+    }
+
+    #[test]
+    fn hosts_are_normalized() {
+        assert_eq!(
+            normalize_host("Chat.Example.COM").as_deref(),
+            Some("chat.example.com")
+        );
+        assert_eq!(
+            normalize_host("chat.example.com:8443").as_deref(),
+            Some("chat.example.com")
+        );
+        assert_eq!(
+            normalize_host("chat.example.com.").as_deref(),
+            Some("chat.example.com")
+        );
+        assert_eq!(normalize_host("[::1]:8080").as_deref(), Some("[::1]"));
+        assert_eq!(normalize_host(""), None);
+    }
+
+    #[test]
+    fn derived_secrets_differ_per_host() {
+        let main = "0123456789abcdef0123456789abcdef";
+        let alpha = derive_jwt_secret(main, "alpha.example.com");
+        assert_eq!(alpha.len(), 64);
+        assert_eq!(alpha, derive_jwt_secret(main, "alpha.example.com"));
+        assert_ne!(alpha, derive_jwt_secret(main, "beta.example.com"));
+        assert_ne!(
+            alpha,
+            derive_jwt_secret("another-main-secret-0123456789abc", "alpha.example.com")
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_tenants() {
+        let mut config = Config::default();
+        config.tenants.enabled = true;
+        config.tenants.instances = vec![
+            tenant("alpha.example.com", "sqlite://./data/alpha.db?mode=rwc"),
+            tenant("beta.example.com", "sqlite://./data/beta.db?mode=rwc"),
+        ];
+        assert!(problems(&config).is_empty());
+
+        config.tenants.instances.push(tenant(
+            "Alpha.example.com:443",
+            "sqlite://./data/alpha.db?mode=rwc",
+        ));
+        config
+            .tenants
+            .instances
+            .push(tenant("gamma.example.com", "postgres://db/gamma"));
+        let mut dotdot = tenant("delta.example.com", "sqlite://./data/delta.db");
+        dotdot.storage_prefix = Some("..".into());
+        config.tenants.instances.push(dotdot);
+        let problems = problems(&config);
+        assert_eq!(problems.len(), 5, "{problems:?}");
+        assert!(problems[0].contains("instances[2].host"));
+        assert!(problems[1].contains("instances[2].database_url is already used"));
+        // The prefix defaults to the host, which isn't a valid segment here.
+        assert!(problems[2].contains("instances[2].storage_prefix"));
+        assert!(problems[3].contains("instances[3].database_url does not match"));
+        assert!(problems[4].contains("instances[4].storage_prefix '..'"));
+    }
+
+    #[tokio::test]
+    async fn requests_are_routed_by_host() {
+        let default = Router::new().route("/", get(|| async { "main" }));
+        let alpha = Router::new().route("/", get(|| async { "alpha" }));
+        let app = host_router(default, vec![("alpha.example.com".into(), alpha)]);
+
+        for (host, expected) in [
+            ("alpha.example.com", "alpha"),
+            ("ALPHA.example.com:8443", "alpha"),
+            ("other.example.com", "main"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/")
+                        .header("host", host)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), 1024)
+                .await
+                .unwrap();
+            assert_eq!(&body[..], expected.as_bytes(), "host {host}");
+        }
+    }
+}
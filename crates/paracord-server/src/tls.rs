@@ -5,7 +5,8 @@ use axum_server::tls_rustls::RustlsConfig;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::config::TlsConfig;
+use crate::config::{AcmeChallenge, Config, MediaCertSource, TlsConfig};
+use paracord_transport::endpoint::MediaEndpoint;
 
 fn harden_private_key_permissions(_path: &Path) -> Result<()> {
     #[cfg(unix)]
@@ -17,10 +18,13 @@ fn harden_private_key_permissions(_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn build_server_config_from_files(
+fn read_cert_and_key(
     cert_path: &Path,
     key_path: &Path,
-) -> Result<rustls::ServerConfig> {
+) -> Result<(
+    Vec<rustls::pki_types::CertificateDer<'static>>,
+    rustls::pki_types::PrivateKeyDer<'static>,
+)> {
     let cert_pem = std::fs::read(cert_path)
         .with_context(|| format!("Failed to read cert from {:?}", cert_path))?;
     let certs = {
@@ -38,14 +42,34 @@ fn build_server_config_from_files(
             .with_context(|| format!("Failed to read key from {:?}", key_path))?;
         PrivateKeyDer::from_pem_slice(&key_pem).context("Failed to parse PEM private key")?
     };
+    Ok((certs, key))
+}
 
+fn build_server_config_from_files(
+    cert_path: &Path,
+    key_path: &Path,
+) -> Result<rustls::ServerConfig> {
+    let (certs, key) = read_cert_and_key(cert_path, key_path)?;
     let mut server_config = rustls::ServerConfig::builder()
         .with_no_client_auth()
         .with_single_cert(certs, key)
         .context("Failed to build rustls ServerConfig")?;
 
-    // Force HTTP/1.1 for WebSocket compatibility with current gateway stack.
-    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    // HTTP/2 multiplexes API calls and SSE streams. hyper doesn't advertise
+    // extended CONNECT, so browsers open a separate HTTP/1.1 connection for
+    // the gateway and LiveKit WebSockets.
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+/// rustls config for the HTTP/3 listener, using the same certificate as
+/// the HTTPS listener.
+pub fn build_quic_server_config(tls_config: &TlsConfig) -> Result<rustls::ServerConfig> {
+    let mut server_config = build_server_config_from_files(
+        Path::new(&tls_config.cert_path),
+        Path::new(&tls_config.key_path),
+    )?;
+    server_config.alpn_protocols = vec![b"h3".to_vec()];
     Ok(server_config)
 }
 
@@ -57,6 +81,106 @@ pub fn reload_rustls_from_disk(rustls_config: &RustlsConfig, tls_config: &TlsCon
     Ok(())
 }
 
+/// Certificate for the native media endpoint.
+pub struct MediaCert {
+    pub tls: paracord_transport::endpoint::TlsConfig,
+    /// Base64 SHA-256 of the leaf certificate for `serverCertificateHashes`.
+    /// Only set for self-signed certificates: browsers skip normal chain
+    /// validation when given hashes, so CA-issued ones must not be pinned.
+    pub cert_hash: Option<String>,
+}
+
+/// Load or generate the media endpoint certificate per `[voice] cert`.
+pub fn load_media_cert(
+    config: &Config,
+    external_ip: Option<&str>,
+    local_ip: Option<&str>,
+) -> Result<MediaCert> {
+    let (cert_path, key_path) = match config.voice.cert {
+        MediaCertSource::SelfSigned => {
+            let sans = media_cert_sans(config, external_ip, local_ip);
+            let tls = paracord_transport::endpoint::generate_self_signed_cert_for(sans)
+                .context("Failed to generate self-signed media certificate")?;
+            let cert_hash = {
+                use base64::Engine;
+                use sha2::{Digest, Sha256};
+                let digest = Sha256::digest(tls.cert_chain[0].as_ref());
+                base64::engine::general_purpose::STANDARD.encode(digest)
+            };
+            return Ok(MediaCert {
+                tls,
+                cert_hash: Some(cert_hash),
+            });
+        }
+        MediaCertSource::Server => {
+            if !config.tls.enabled {
+                anyhow::bail!("voice.cert = \"server\" requires tls.enabled");
+            }
+            (config.tls.cert_path.as_str(), config.tls.key_path.as_str())
+        }
+        MediaCertSource::Files => match (&config.voice.cert_path, &config.voice.key_path) {
+            (Some(cert), Some(key)) => (cert.as_str(), key.as_str()),
+            _ => {
+                anyhow::bail!("voice.cert = \"files\" requires voice.cert_path and voice.key_path")
+            }
+        },
+    };
+    let (cert_chain, private_key) = read_cert_and_key(Path::new(cert_path), Path::new(key_path))?;
+    Ok(MediaCert {
+        tls: paracord_transport::endpoint::TlsConfig {
+            cert_chain,
+            private_key,
+        },
+        cert_hash: None,
+    })
+}
+
+/// Names the self-signed media certificate is valid for.
+fn media_cert_sans(
+    config: &Config,
+    external_ip: Option<&str>,
+    local_ip: Option<&str>,
+) -> Vec<String> {
+    let public_host = config.server.public_url.as_deref().and_then(url_host);
+    let mut sans: Vec<String> = Vec::new();
+    for name in ["localhost", "127.0.0.1", "::1"]
+        .into_iter()
+        .chain(public_host)
+        .chain(local_ip)
+        .chain(external_ip)
+        .chain(config.voice.cert_sans.iter().map(String::as_str))
+    {
+        let name = name.trim();
+        if !name.is_empty() && !sans.iter().any(|existing| existing == name) {
+            sans.push(name.to_string());
+        }
+    }
+    sans
+}
+
+/// Host of `url` without scheme, port, path or IPv6 brackets.
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = match authority.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+/// Re-read the `[tls]` certificate into the media endpoint.
+fn reload_media_from_disk(endpoint: &MediaEndpoint, tls_config: &TlsConfig) -> Result<()> {
+    let (cert_chain, private_key) = read_cert_and_key(
+        Path::new(&tls_config.cert_path),
+        Path::new(&tls_config.key_path),
+    )?;
+    endpoint.reload_tls(paracord_transport::endpoint::TlsConfig {
+        cert_chain,
+        private_key,
+    })
+}
+
 /// Ensure TLS certificate and key files exist, generating them if needed.
 /// Returns a `RustlsConfig` ready for use with `axum-server`.
 pub async fn ensure_certs(
@@ -113,7 +237,10 @@ pub async fn maybe_serve_acme_http_challenge(
     tls_config: &TlsConfig,
     request_path: &str,
 ) -> Option<axum::response::Response> {
-    if !tls_config.acme.enabled || !tls_config.acme.serve_http_challenge {
+    if !tls_config.acme.enabled
+        || !tls_config.acme.serve_http_challenge
+        || tls_config.acme.challenge != AcmeChallenge::Http01
+    {
         return None;
     }
 
@@ -157,9 +284,12 @@ pub async fn maybe_serve_acme_http_challenge(
     }
 }
 
+/// Periodically renew through ACME and reload the HTTPS listener, plus the
+/// media endpoint when it shares the `[tls]` certificate.
 pub fn spawn_acme_renewal_task(
     tls_config: TlsConfig,
     rustls_config: RustlsConfig,
+    media_endpoint: Option<Arc<MediaEndpoint>>,
     shutdown: Arc<tokio::sync::Notify>,
 ) {
     if !tls_config.acme.enabled || !tls_config.acme.auto_renew {
@@ -192,6 +322,17 @@ pub fn spawn_acme_renewal_task(
                                 } else {
                                     tracing::info!("TLS certificate reloaded after ACME cycle");
                                 }
+                                if let Some(endpoint) = &media_endpoint {
+                                    match reload_media_from_disk(endpoint, &tls_config) {
+                                        Ok(()) => tracing::info!(
+                                            "Media endpoint certificate reloaded after ACME cycle"
+                                        ),
+                                        Err(err) => tracing::warn!(
+                                            "Media endpoint cert reload failed: {}",
+                                            err
+                                        ),
+                                    }
+                                }
                             }
                         }
                         Err(err) => tracing::warn!("ACME automation cycle failed: {}", err),
@@ -205,17 +346,19 @@ pub fn spawn_acme_renewal_task(
 async fn run_acme_automation_cycle(tls_config: &TlsConfig) -> Result<bool> {
     validate_acme_config(tls_config)?;
 
-    let challenge_dir = Path::new(&tls_config.acme.webroot_path)
-        .join(".well-known")
-        .join("acme-challenge");
-    std::fs::create_dir_all(&challenge_dir)
-        .with_context(|| format!("Failed creating ACME challenge path: {:?}", challenge_dir))?;
+    if tls_config.acme.challenge == AcmeChallenge::Http01 {
+        let challenge_dir = Path::new(&tls_config.acme.webroot_path)
+            .join(".well-known")
+            .join("acme-challenge");
+        std::fs::create_dir_all(&challenge_dir)
+            .with_context(|| format!("Failed creating ACME challenge path: {:?}", challenge_dir))?;
+    }
 
     run_certbot_certonly(tls_config).await?;
     sync_cert_from_acme_source(tls_config)
 }
 
-fn validate_acme_config(tls_config: &TlsConfig) -> Result<()> {
+pub fn validate_acme_config(tls_config: &TlsConfig) -> Result<()> {
     if tls_config.acme.domains.is_empty() {
         anyhow::bail!("tls.acme.enabled=true requires at least one entry in tls.acme.domains");
     }
@@ -228,7 +371,7 @@ fn validate_acme_config(tls_config: &TlsConfig) -> Result<()> {
     if tls_config.acme.cert_name.trim().is_empty() {
         anyhow::bail!("tls.acme.cert_name must not be empty");
     }
-    Ok(())
+    crate::acme_dns::validate(&tls_config.acme)
 }
 
 async fn run_certbot_certonly(tls_config: &TlsConfig) -> Result<()> {
@@ -242,10 +385,8 @@ async fn run_certbot_certonly(tls_config: &TlsConfig) -> Result<()> {
         .arg("--server")
         .arg(acme.directory_url.trim())
         .arg("--cert-name")
-        .arg(acme.cert_name.trim())
-        .arg("--webroot")
-        .arg("-w")
-        .arg(acme.webroot_path.trim());
+        .arg(acme.cert_name.trim());
+    crate::acme_dns::apply_certbot_args(&mut command, acme)?;
 
     if let Some(email) = acme
         .email
@@ -440,3 +581,37 @@ fn generate_self_signed(
     tracing::info!("TLS private key written to {:?}", key_path);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_signed_media_cert_covers_public_host_and_configured_names() {
+        let mut config = Config::default();
+        config.server.public_url = Some("https://chat.example.com:8443/app".into());
+        config.voice.cert_sans = vec!["media.example.com".into(), "localhost".into()];
+
+        let sans = media_cert_sans(&config, Some("203.0.113.7"), None);
+        assert_eq!(
+            sans,
+            vec![
+                "localhost",
+                "127.0.0.1",
+                "::1",
+                "chat.example.com",
+                "203.0.113.7",
+                "media.example.com",
+            ]
+        );
+        assert_eq!(url_host("http://[2001:db8::1]:8080"), Some("2001:db8::1"));
+    }
+
+    #[test]
+    fn files_source_requires_both_paths() {
+        let mut config = Config::default();
+        config.voice.cert = MediaCertSource::Files;
+        config.voice.cert_path = Some("/nonexistent/cert.pem".into());
+        assert!(load_media_cert(&config, None, None).is_err());
+    }
+}
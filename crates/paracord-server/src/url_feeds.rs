@@ -0,0 +1,127 @@
+//! Downloads the configured phishing/invite-scam domain feeds into the URL
+//! denylist and keeps an on-disk snapshot so a restart doesn't start with an
+//! empty list while feeds are unreachable.
+
+use anyhow::{Context, Result};
+use paracord_core::url_reputation;
+use paracord_core::AppState;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::config::UrlReputationConfig;
+
+const MAX_FEED_BYTES: usize = 32 * 1024 * 1024;
+
+/// Domains from the last saved feed snapshot.
+pub fn load_cache(path: &str) -> Option<HashSet<String>> {
+    let body = std::fs::read_to_string(path).ok()?;
+    Some(url_reputation::parse_feed(&body))
+}
+
+/// Refresh the feeds on an interval and hand the domains to every instance.
+pub fn spawn(states: Vec<AppState>, config: UrlReputationConfig, shutdown: Arc<Notify>) {
+    if config.feeds.is_empty() {
+        return;
+    }
+    let interval_seconds = config.refresh_interval_seconds.max(300);
+    tracing::info!(
+        "URL denylist feeds enabled ({} feed(s), interval={}s)",
+        config.feeds.len(),
+        interval_seconds
+    );
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!("URL denylist feeds disabled: {}", err);
+                return;
+            }
+        };
+        // Last good download per feed, so one failing feed doesn't drop the
+        // domains it contributed.
+        let mut per_feed: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => {
+                    break;
+                }
+                _ = interval.tick() => {
+                    refresh_once(&states, &client, &config, &mut per_feed).await;
+                }
+            }
+        }
+    });
+}
+
+async fn refresh_once(
+    states: &[AppState],
+    client: &reqwest::Client,
+    config: &UrlReputationConfig,
+    per_feed: &mut HashMap<String, HashSet<String>>,
+) {
+    for feed in &config.feeds {
+        match fetch_feed(client, feed).await {
+            Ok(domains) => {
+                per_feed.insert(feed.clone(), domains);
+            }
+            Err(err) => tracing::warn!("URL denylist feed {} failed: {:#}", feed, err),
+        }
+    }
+    if per_feed.is_empty() {
+        return;
+    }
+
+    let domains: HashSet<String> = per_feed.values().flatten().cloned().collect();
+    if let Err(err) = write_cache(&config.cache_path, &domains) {
+        tracing::warn!("Failed to save URL denylist cache: {:#}", err);
+    }
+    tracing::info!("URL denylist feeds refreshed ({} domains)", domains.len());
+    for state in states {
+        state
+            .runtime
+            .write()
+            .await
+            .url_denylist
+            .set_feed_domains(domains.clone());
+    }
+}
+
+async fn fetch_feed(client: &reqwest::Client, url: &str) -> Result<HashSet<String>> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_FEED_BYTES as u64)
+    {
+        anyhow::bail!("feed is larger than {} bytes", MAX_FEED_BYTES);
+    }
+    let body = response.bytes().await?;
+    if body.len() > MAX_FEED_BYTES {
+        anyhow::bail!("feed is larger than {} bytes", MAX_FEED_BYTES);
+    }
+    Ok(url_reputation::parse_feed(&String::from_utf8_lossy(&body)))
+}
+
+fn write_cache(path: &str, domains: &HashSet<String>) -> Result<()> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut sorted: Vec<&str> = domains.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    let mut body = sorted.join("\n");
+    body.push('\n');
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, body).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
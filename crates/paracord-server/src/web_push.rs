@@ -0,0 +1,469 @@
+//! Web Push delivery: VAPID (RFC 8292) signed requests carrying payloads
+//! encrypted for the subscriber with `aes128gcm` (RFC 8291).
+//!
+//! `paracord_core::push` queues notifications in `push_deliveries`; the
+//! worker here claims due rows and sends them. A push service answering 404
+//! or 410 has dropped the subscription, so it is deleted with its queue.
+//! Other failures are retried with backoff until `MAX_ATTEMPTS`.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use paracord_core::AppState;
+use paracord_db::push_subscriptions::PushDeliveryRow;
+use rand::RngCore;
+use sha2::Sha256;
+use tokio::sync::Notify;
+
+use crate::config::WebPushConfig;
+
+const MAX_ATTEMPTS: i32 = 5;
+const BATCH_SIZE: i64 = 64;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a claimed delivery is hidden from other workers.
+const CLAIM_LEASE_SECONDS: i64 = 120;
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+/// How long push services keep an undelivered message.
+const PUSH_TTL_SECONDS: u32 = 86_400;
+const VAPID_TOKEN_LIFETIME_SECONDS: i64 = 12 * 3600;
+/// Record size advertised in the `aes128gcm` header. Payloads are sent as a
+/// single record, so anything up to this size fits.
+const RECORD_SIZE: u32 = 4096;
+
+#[derive(Clone)]
+pub struct VapidKeys {
+    signing_key: SigningKey,
+    /// Uncompressed public key, base64url; the `applicationServerKey`.
+    pub public_key: String,
+}
+
+impl VapidKeys {
+    pub fn from_private_key(raw: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw.trim().trim_end_matches('='))
+            .context("VAPID private key is not base64url")?;
+        let signing_key = SigningKey::from_slice(&bytes)
+            .map_err(|_| anyhow::anyhow!("VAPID private key is not a P-256 scalar"))?;
+        let public_key = PublicKey::from(signing_key.verifying_key());
+        Ok(Self {
+            public_key: URL_SAFE_NO_PAD.encode(public_key.to_encoded_point(false).as_bytes()),
+            signing_key,
+        })
+    }
+
+    fn private_key_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.signing_key.to_bytes())
+    }
+
+    /// `Authorization` header for a push to `endpoint`.
+    fn authorization(&self, endpoint: &url::Url, subject: &str, now: i64) -> Result<String> {
+        let audience = endpoint.origin().ascii_serialization();
+        let header = URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": now + VAPID_TOKEN_LIFETIME_SECONDS,
+                "sub": subject,
+            })
+            .to_string(),
+        );
+        let signing_input = format!("{header}.{claims}");
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+        Ok(format!(
+            "vapid t={signing_input}.{}, k={}",
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key
+        ))
+    }
+}
+
+/// The VAPID keys when Web Push is enabled. The private key comes from
+/// `web_push.private_key`, else from `key_path`, which is created on first
+/// start.
+pub fn load_keys(config: &WebPushConfig) -> Result<Option<VapidKeys>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    validate_subject(config.subject.as_deref())?;
+    if let Some(raw) = config
+        .private_key
+        .as_deref()
+        .filter(|raw| !raw.trim().is_empty())
+    {
+        return VapidKeys::from_private_key(raw)
+            .context("invalid web_push.private_key")
+            .map(Some);
+    }
+
+    let path = Path::new(&config.key_path);
+    if path.exists() {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read VAPID key from '{}'", path.display()))?;
+        return VapidKeys::from_private_key(&raw)
+            .with_context(|| format!("invalid VAPID key at '{}'", path.display()))
+            .map(Some);
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "failed to create VAPID key directory '{}'",
+                parent.display()
+            )
+        })?;
+    }
+    let keys = VapidKeys {
+        signing_key: SigningKey::random(&mut rand::rngs::OsRng),
+        public_key: String::new(),
+    };
+    let keys = VapidKeys::from_private_key(&keys.private_key_base64())?;
+    std::fs::write(path, format!("{}\n", keys.private_key_base64()))
+        .with_context(|| format!("failed to write VAPID key file '{}'", path.display()))?;
+    crate::harden_secret_file_permissions(path);
+    tracing::info!("Generated VAPID key at '{}'", path.display());
+    Ok(Some(keys))
+}
+
+/// Push services need a way to contact the operator.
+pub fn validate_subject(subject: Option<&str>) -> Result<()> {
+    match subject.map(str::trim) {
+        Some(subject) if subject.starts_with("mailto:") || subject.starts_with("https://") => {
+            Ok(())
+        }
+        _ => {
+            anyhow::bail!("web_push.enabled requires web_push.subject as a mailto: or https:// URL")
+        }
+    }
+}
+
+/// Encrypt `plaintext` for a subscriber's `p256dh` key and `auth` secret
+/// as a single `aes128gcm` record.
+fn encrypt_payload(ua_public: &[u8], auth_secret: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0_u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let as_secret = SecretKey::random(&mut rand::rngs::OsRng);
+    encrypt_payload_with(&as_secret, salt, ua_public, auth_secret, plaintext)
+}
+
+fn encrypt_payload_with(
+    as_secret: &SecretKey,
+    salt: [u8; 16],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    let ua_key = PublicKey::from_sec1_bytes(ua_public)
+        .map_err(|_| anyhow::anyhow!("invalid subscription p256dh key"))?;
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(as_public.as_bytes());
+    let mut ikm = [0_u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| anyhow::anyhow!("HKDF expand failed"))?;
+
+    let prk = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek = [0_u8; 16];
+    let mut nonce = [0_u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .and_then(|_| prk.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|_| anyhow::anyhow!("HKDF expand failed"))?;
+
+    // Padding delimiter of the last (only) record.
+    let mut record = plaintext.to_vec();
+    record.push(0x02);
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)
+        .map_err(|_| anyhow::anyhow!("invalid content encryption key"))?
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .map_err(|_| anyhow::anyhow!("payload encryption failed"))?;
+    if ciphertext.len() + 86 > RECORD_SIZE as usize {
+        anyhow::bail!("push payload too large");
+    }
+
+    let mut body = Vec::with_capacity(86 + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.as_bytes().len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// Resolver that refuses hosts with a non-public address, so subscription
+/// endpoints can't be used to reach the server's own network.
+struct PublicOnlyResolver;
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} did not resolve").into());
+            }
+            if addrs
+                .iter()
+                .any(|addr| !paracord_util::net::is_public_ip(addr.ip()))
+            {
+                return Err(format!("{host} resolves to a private address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+pub fn spawn(state: AppState, keys: VapidKeys, subject: String, shutdown: Arc<Notify>) {
+    tracing::info!("Web Push enabled (subject={})", subject);
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!("Web Push disabled: {}", err);
+                return;
+            }
+        };
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = shutdown.notified() => break,
+                _ = interval.tick() => {
+                    // Keep going while full batches come back.
+                    while deliver_once(&state, &client, &keys, &subject).await == BATCH_SIZE as usize {}
+                }
+            }
+        }
+    });
+}
+
+enum Outcome {
+    Sent,
+    /// The subscription no longer exists at the push service.
+    Gone,
+    /// Won't succeed on retry.
+    Rejected(String),
+    Retry(String),
+}
+
+async fn deliver_once(
+    state: &AppState,
+    client: &reqwest::Client,
+    keys: &VapidKeys,
+    subject: &str,
+) -> usize {
+    let now = chrono::Utc::now();
+    let deliveries = match paracord_db::push_subscriptions::claim_due_deliveries(
+        &state.db,
+        now,
+        now + chrono::Duration::seconds(CLAIM_LEASE_SECONDS),
+        BATCH_SIZE,
+    )
+    .await
+    {
+        Ok(deliveries) => deliveries,
+        Err(err) => {
+            tracing::warn!("Failed to claim push deliveries: {}", err);
+            return 0;
+        }
+    };
+
+    for delivery in &deliveries {
+        let outcome = send(client, keys, subject, delivery).await;
+        let result = match outcome {
+            Outcome::Sent => {
+                let _ = paracord_db::push_subscriptions::mark_subscription_used(
+                    &state.db,
+                    delivery.subscription_id,
+                )
+                .await;
+                paracord_db::push_subscriptions::delete_delivery(&state.db, delivery.id).await
+            }
+            Outcome::Gone => {
+                paracord_db::push_subscriptions::delete_subscription(
+                    &state.db,
+                    delivery.subscription_id,
+                )
+                .await
+            }
+            Outcome::Rejected(reason) => {
+                tracing::debug!("Push delivery {} rejected: {}", delivery.id, reason);
+                paracord_db::push_subscriptions::delete_delivery(&state.db, delivery.id).await
+            }
+            Outcome::Retry(reason) if delivery.attempts >= MAX_ATTEMPTS => {
+                tracing::debug!(
+                    "Push delivery {} dropped after {} attempts: {}",
+                    delivery.id,
+                    delivery.attempts,
+                    reason
+                );
+                paracord_db::push_subscriptions::delete_delivery(&state.db, delivery.id).await
+            }
+            Outcome::Retry(reason) => {
+                tracing::debug!("Push delivery {} will be retried: {}", delivery.id, reason);
+                paracord_db::push_subscriptions::reschedule_delivery(
+                    &state.db,
+                    delivery.id,
+                    chrono::Utc::now() + retry_delay(delivery.attempts),
+                )
+                .await
+            }
+        };
+        if let Err(err) = result {
+            tracing::warn!(
+                "Failed to record push delivery {} result: {}",
+                delivery.id,
+                err
+            );
+        }
+    }
+    deliveries.len()
+}
+
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let seconds = BASE_RETRY_DELAY_SECONDS.saturating_mul(1_i64 << exponent);
+    chrono::Duration::seconds(seconds.min(MAX_RETRY_DELAY_SECONDS))
+}
+
+async fn send(
+    client: &reqwest::Client,
+    keys: &VapidKeys,
+    subject: &str,
+    delivery: &PushDeliveryRow,
+) -> Outcome {
+    let endpoint = match url::Url::parse(&delivery.endpoint) {
+        Ok(url) if url.scheme() == "https" && matches!(url.host(), Some(url::Host::Domain(_))) => {
+            url
+        }
+        _ => return Outcome::Gone,
+    };
+    let (Ok(ua_public), Ok(auth_secret)) = (
+        URL_SAFE_NO_PAD.decode(&delivery.p256dh),
+        URL_SAFE_NO_PAD.decode(&delivery.auth),
+    ) else {
+        return Outcome::Gone;
+    };
+    let body = match encrypt_payload(&ua_public, &auth_secret, delivery.payload.as_bytes()) {
+        Ok(body) => body,
+        Err(err) => return Outcome::Rejected(err.to_string()),
+    };
+    let authorization = match keys.authorization(&endpoint, subject, chrono::Utc::now().timestamp())
+    {
+        Ok(header) => header,
+        Err(err) => return Outcome::Rejected(err.to_string()),
+    };
+
+    let response = client
+        .post(endpoint)
+        .header("authorization", authorization)
+        .header("content-encoding", "aes128gcm")
+        .header("content-type", "application/octet-stream")
+        .header("ttl", PUSH_TTL_SECONDS.to_string())
+        .header("urgency", "high")
+        .body(body)
+        .send()
+        .await;
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            if status.is_success() {
+                Outcome::Sent
+            } else if status.as_u16() == 404 || status.as_u16() == 410 {
+                Outcome::Gone
+            } else if status.as_u16() == 429 || status.is_server_error() {
+                Outcome::Retry(format!("push service returned {status}"))
+            } else {
+                Outcome::Rejected(format!("push service returned {status}"))
+            }
+        }
+        Err(err) => Outcome::Retry(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn b64(raw: &str) -> Vec<u8> {
+        URL_SAFE_NO_PAD.decode(raw).unwrap()
+    }
+
+    /// RFC 8291 appendix A.
+    #[test]
+    fn encryption_matches_rfc_8291_example() {
+        let as_secret =
+            SecretKey::from_slice(&b64("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+        let salt: [u8; 16] = b64("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+        let body = encrypt_payload_with(
+            &as_secret,
+            salt,
+            &b64("BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4"),
+            &b64("BTBZMqHH6r4Tts7J_aSIgg"),
+            b"When I grow up, I want to be a watermelon",
+        )
+        .unwrap();
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn vapid_token_is_signed_for_the_endpoint_origin() {
+        use p256::ecdsa::signature::Verifier;
+
+        let generated = SigningKey::random(&mut rand::rngs::OsRng);
+        let keys =
+            VapidKeys::from_private_key(&URL_SAFE_NO_PAD.encode(generated.to_bytes())).unwrap();
+        let endpoint = url::Url::parse("https://push.example.com/send/abc").unwrap();
+        let header = keys
+            .authorization(&endpoint, "mailto:ops@example.com", 1_700_000_000)
+            .unwrap();
+
+        let (token, public_key) = header
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split_once(", k="))
+            .unwrap();
+        assert_eq!(public_key, keys.public_key);
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let claims: serde_json::Value =
+            serde_json::from_slice(&b64(signing_input.split('.').nth(1).unwrap())).unwrap();
+        assert_eq!(claims["aud"], "https://push.example.com");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+        let signature = Signature::from_slice(&b64(signature)).unwrap();
+        assert!(generated
+            .verifying_key()
+            .verify(signing_input.as_bytes(), &signature)
+            .is_ok());
+    }
+
+    #[test]
+    fn subject_must_be_a_contact_url() {
+        assert!(validate_subject(Some("mailto:ops@example.com")).is_ok());
+        assert!(validate_subject(Some("https://example.com/contact")).is_ok());
+        assert!(validate_subject(Some("ops@example.com")).is_err());
+        assert!(validate_subject(None).is_err());
+    }
+}
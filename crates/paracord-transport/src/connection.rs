@@ -28,6 +28,11 @@ pub struct MediaClaims {
     pub iat: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sid: Option<String>,
+    /// Relay room the token is scoped to (`{guild_id}:{channel_id}`), for
+    /// participants that have no voice state of their own (e.g. WHIP
+    /// publishers).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
 }
 
 /// Metadata tracked for each authenticated connection.
@@ -35,6 +40,8 @@ pub struct MediaClaims {
 pub struct ConnectionMeta {
     pub user_id: i64,
     pub session_id: Option<String>,
+    /// Room claim from the auth token, if any.
+    pub room: Option<String>,
     pub remote_addr: SocketAddr,
     pub mode: ConnectionMode,
 }
@@ -43,6 +50,9 @@ pub struct ConnectionMeta {
 pub struct MediaConnection {
     conn: Connection,
     meta: ConnectionMeta,
+    /// The control stream used for authentication, kept open for in-call
+    /// control messages until taken by the caller.
+    control: Option<(quinn::SendStream, quinn::RecvStream)>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -68,7 +78,11 @@ pub enum ConnectionError {
 impl MediaConnection {
     /// Wrap an already-authenticated connection.
     pub fn new(conn: Connection, meta: ConnectionMeta) -> Self {
-        Self { conn, meta }
+        Self {
+            conn,
+            meta,
+            control: None,
+        }
     }
 
     /// Accept an incoming connection and authenticate via control stream.
@@ -80,6 +94,19 @@ impl MediaConnection {
         jwt_secret: &str,
         mode: ConnectionMode,
     ) -> Result<Self, ConnectionError> {
+        Self::accept_and_auth_any(conn, &[jwt_secret], mode)
+            .await
+            .map(|(conn, _)| conn)
+    }
+
+    /// Like [`Self::accept_and_auth`], but accepts a JWT signed with any of
+    /// `jwt_secrets` (several instances sharing one endpoint). Also returns
+    /// the index of the secret that validated the token.
+    pub async fn accept_and_auth_any(
+        conn: Connection,
+        jwt_secrets: &[&str],
+        mode: ConnectionMode,
+    ) -> Result<(Self, usize), ConnectionError> {
         let remote_addr = conn.remote_address();
 
         // Accept the first bidirectional stream (control stream)
@@ -109,17 +136,27 @@ impl MediaConnection {
 
         // Validate JWT
         let validation = Validation::new(Algorithm::HS256);
-        let token_data = decode::<MediaClaims>(
-            &token,
-            &DecodingKey::from_secret(jwt_secret.as_bytes()),
-            &validation,
-        )
-        .map_err(|e| ConnectionError::AuthFailed(e.to_string()))?;
+        let mut result = Err(ConnectionError::AuthFailed("no secret configured".into()));
+        for (index, jwt_secret) in jwt_secrets.iter().enumerate() {
+            match decode::<MediaClaims>(
+                &token,
+                &DecodingKey::from_secret(jwt_secret.as_bytes()),
+                &validation,
+            ) {
+                Ok(token_data) => {
+                    result = Ok((index, token_data));
+                    break;
+                }
+                Err(e) => result = Err(ConnectionError::AuthFailed(e.to_string())),
+            }
+        }
+        let (secret_index, token_data) = result?;
 
         let claims = token_data.claims;
         let meta = ConnectionMeta {
             user_id: claims.sub,
             session_id: claims.sid,
+            room: claims.room,
             remote_addr,
             mode,
         };
@@ -128,7 +165,14 @@ impl MediaConnection {
         let ack = ControlMessage::Pong.encode()?;
         send.write_all(&ack).await?;
 
-        Ok(Self { conn, meta })
+        Ok((
+            Self {
+                conn,
+                meta,
+                control: Some((send, recv)),
+            },
+            secret_index,
+        ))
     }
 
     /// Connect to a remote endpoint and authenticate.
@@ -169,11 +213,16 @@ impl MediaConnection {
         let meta = ConnectionMeta {
             user_id: 0,
             session_id: None,
+            room: None,
             remote_addr,
             mode,
         };
 
-        Ok(Self { conn, meta })
+        Ok(Self {
+            conn,
+            meta,
+            control: Some((send, recv)),
+        })
     }
 
     /// Send an unreliable datagram (for media packets).
@@ -199,6 +248,14 @@ impl MediaConnection {
         Ok(self.conn.accept_bi().await?)
     }
 
+    /// Take ownership of the control stream opened during authentication.
+    ///
+    /// Returns `None` if the connection was wrapped via [`MediaConnection::new`]
+    /// or the stream has already been taken.
+    pub fn take_control_stream(&mut self) -> Option<(quinn::SendStream, quinn::RecvStream)> {
+        self.control.take()
+    }
+
     /// Connection metadata (user, session, address, mode).
     pub fn meta(&self) -> &ConnectionMeta {
         &self.meta
@@ -248,10 +305,12 @@ mod tests {
             exp: 9999999999,
             iat: 1000000000,
             sid: Some("session-1".to_string()),
+            room: None,
         };
         let json = serde_json::to_string(&claims).unwrap();
         let parsed: MediaClaims = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.sub, 42);
         assert_eq!(parsed.sid.as_deref(), Some("session-1"));
+        assert!(!json.contains("room"));
     }
 }
@@ -69,6 +69,36 @@ pub enum ControlMessage {
     /// Keepalive pong.
     Pong,
 
+    /// Client reports whether it can attempt direct peer-to-peer media
+    /// (raw QUIC transport, NAT allows hole punching).
+    #[serde(rename = "p2p_capability")]
+    P2PCapability { supported: bool },
+
+    /// Server asks the client to hole-punch a direct path to a peer.
+    #[serde(rename = "p2p_candidate")]
+    P2PCandidate { peer_user_id: i64, addr: String },
+
+    /// Client reports the outcome of a hole-punch attempt.
+    #[serde(rename = "p2p_result")]
+    P2PResult {
+        peer_user_id: i64,
+        established: bool,
+    },
+
+    /// Server tells the client to route media for a peer through the relay
+    /// again (attempt failed or the room is no longer two-party).
+    #[serde(rename = "p2p_fallback")]
+    P2PFallback { peer_user_id: i64 },
+
+    /// Lightweight in-call signal (emoji reaction, raised hand) broadcast to
+    /// everyone else in the room. Clients leave `sender_user_id` as 0; the
+    /// relay fills it in from the authenticated connection.
+    RoomSignal {
+        #[serde(default)]
+        sender_user_id: i64,
+        signal: RoomSignalKind,
+    },
+
     // ── File transfer messages ───────────────────────────────────────────
     /// Client initiates a file upload on a dedicated bidi stream.
     FileTransferInit {
@@ -134,6 +164,16 @@ pub enum ControlMessage {
     FileTransferCancel { transfer_id: String },
 }
 
+/// Payload of a [`ControlMessage::RoomSignal`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RoomSignalKind {
+    /// A transient emoji reaction.
+    Reaction { emoji: String },
+    /// Raise or lower the sender's hand.
+    RaiseHand { raised: bool },
+}
+
 /// Maximum control message size (256 KiB).
 const MAX_MESSAGE_SIZE: u32 = 256 * 1024;
 
@@ -457,6 +497,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn room_signal_round_trip() {
+        for signal in [
+            RoomSignalKind::Reaction {
+                emoji: "\u{1f44d}".to_string(),
+            },
+            RoomSignalKind::RaiseHand { raised: true },
+        ] {
+            let msg = ControlMessage::RoomSignal {
+                sender_user_id: 42,
+                signal,
+            };
+            let encoded = msg.encode().unwrap();
+            let (decoded, _) = ControlMessage::decode(&encoded).unwrap().unwrap();
+            assert_eq!(msg, decoded);
+        }
+    }
+
+    #[test]
+    fn room_signal_sender_defaults_to_zero() {
+        let json = r#"{"type":"room_signal","signal":{"kind":"raise_hand","raised":false}}"#;
+        let msg: ControlMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            msg,
+            ControlMessage::RoomSignal {
+                sender_user_id: 0,
+                signal: RoomSignalKind::RaiseHand { raised: false },
+            }
+        );
+    }
+
+    #[test]
+    fn p2p_messages_round_trip() {
+        for msg in [
+            ControlMessage::P2PCapability { supported: true },
+            ControlMessage::P2PCandidate {
+                peer_user_id: 7,
+                addr: "203.0.113.5:4433".to_string(),
+            },
+            ControlMessage::P2PResult {
+                peer_user_id: 7,
+                established: false,
+            },
+            ControlMessage::P2PFallback { peer_user_id: 7 },
+        ] {
+            let encoded = msg.encode().unwrap();
+            let (decoded, _) = ControlMessage::decode(&encoded).unwrap().unwrap();
+            assert_eq!(msg, decoded);
+        }
+        let json = serde_json::to_string(&ControlMessage::P2PFallback { peer_user_id: 1 }).unwrap();
+        assert!(json.contains("\"type\":\"p2p_fallback\""));
+    }
+
     #[test]
     fn incomplete_frame_returns_none() {
         let msg = ControlMessage::Ping;
@@ -18,6 +18,8 @@ pub struct TlsConfig {
 /// A QUIC endpoint that can act as both server and client.
 pub struct MediaEndpoint {
     endpoint: quinn::Endpoint,
+    /// ALPN protocols advertised by the server side, kept for TLS reloads.
+    alpn_protocols: Vec<Vec<u8>>,
 }
 
 impl MediaEndpoint {
@@ -45,7 +47,10 @@ impl MediaEndpoint {
         let mut endpoint = quinn::Endpoint::server(server_config, addr)?;
         endpoint.set_default_client_config(client_config);
 
-        Ok(Self { endpoint })
+        Ok(Self {
+            endpoint,
+            alpn_protocols: Vec::new(),
+        })
     }
 
     /// Bind a unified QUIC endpoint that advertises multiple ALPN protocols.
@@ -59,14 +64,7 @@ impl MediaEndpoint {
         tls: TlsConfig,
         alpn_protocols: Vec<Vec<u8>>,
     ) -> anyhow::Result<Self> {
-        let mut server_crypto = rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(tls.cert_chain.clone(), tls.private_key.clone_key())?;
-
-        server_crypto.alpn_protocols = alpn_protocols;
-
-        let server_config =
-            quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(server_crypto)?));
+        let server_config = unified_server_config(&tls, alpn_protocols.clone())?;
 
         let client_crypto = rustls::ClientConfig::builder()
             .dangerous()
@@ -80,7 +78,18 @@ impl MediaEndpoint {
         let mut endpoint = quinn::Endpoint::server(server_config, addr)?;
         endpoint.set_default_client_config(client_config);
 
-        Ok(Self { endpoint })
+        Ok(Self {
+            endpoint,
+            alpn_protocols,
+        })
+    }
+
+    /// Swap in a new certificate (e.g. after an ACME renewal). Existing
+    /// connections keep the one they were established with.
+    pub fn reload_tls(&self, tls: TlsConfig) -> anyhow::Result<()> {
+        let server_config = unified_server_config(&tls, self.alpn_protocols.clone())?;
+        self.endpoint.set_server_config(Some(server_config));
+        Ok(())
     }
 
     /// Create a client-only endpoint (no server config, for P2P initiators).
@@ -101,7 +110,10 @@ impl MediaEndpoint {
         let mut endpoint = quinn::Endpoint::client(addr)?;
         endpoint.set_default_client_config(client_config);
 
-        Ok(Self { endpoint })
+        Ok(Self {
+            endpoint,
+            alpn_protocols: Vec::new(),
+        })
     }
 
     /// Accept the next incoming QUIC connection.
@@ -139,10 +151,31 @@ impl MediaEndpoint {
     }
 }
 
+fn unified_server_config(
+    tls: &TlsConfig,
+    alpn_protocols: Vec<Vec<u8>>,
+) -> anyhow::Result<quinn::ServerConfig> {
+    let mut server_crypto = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(tls.cert_chain.clone(), tls.private_key.clone_key())?;
+
+    server_crypto.alpn_protocols = alpn_protocols;
+
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(
+        QuicServerConfig::try_from(server_crypto)?,
+    )))
+}
+
 /// Generate a self-signed TLS certificate for development use.
 pub fn generate_self_signed_cert() -> anyhow::Result<TlsConfig> {
+    generate_self_signed_cert_for(vec!["localhost".to_string()])
+}
+
+/// Generate a self-signed TLS certificate valid for `subject_alt_names`
+/// (DNS names or IP addresses).
+pub fn generate_self_signed_cert_for(subject_alt_names: Vec<String>) -> anyhow::Result<TlsConfig> {
     let rcgen::CertifiedKey { cert, key_pair } =
-        rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        rcgen::generate_simple_self_signed(subject_alt_names)?;
 
     let cert_der = CertificateDer::from(cert.der().to_vec());
     let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
@@ -269,4 +302,35 @@ mod tests {
         server.close();
         client.close();
     }
+
+    #[tokio::test]
+    async fn reloaded_cert_is_presented_to_new_connections() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let server = MediaEndpoint::bind_unified(
+            "127.0.0.1:0".parse().unwrap(),
+            generate_self_signed_cert().unwrap(),
+            vec![b"paracord-media".to_vec()],
+        )
+        .unwrap();
+        let renewed = generate_self_signed_cert_for(vec!["media.example.com".into()]).unwrap();
+        let renewed_der = renewed.cert_chain[0].clone();
+        server.reload_tls(renewed).unwrap();
+
+        let client = MediaEndpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        let connecting = client
+            .connect(server.local_addr().unwrap(), "media.example.com")
+            .unwrap();
+        let incoming = server.accept().await.expect("server should accept");
+        let _server_conn = incoming.accept().unwrap().await.unwrap();
+        let client_conn = connecting.await.unwrap();
+
+        let presented = client_conn
+            .peer_identity()
+            .and_then(|identity| identity.downcast::<Vec<CertificateDer<'static>>>().ok())
+            .expect("server presents a certificate chain");
+        assert_eq!(presented[0], renewed_der);
+
+        server.close();
+        client.close();
+    }
 }
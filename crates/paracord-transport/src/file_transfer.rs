@@ -15,6 +15,7 @@ use tokio::io::AsyncWriteExt;
 use tracing;
 
 use crate::control::{ControlMessage, StreamFrame, StreamFrameCodec, StreamFrameError};
+use crate::transfer_limits::{TransferDirection, TransferLimiter};
 
 /// Default chunk size for file transfer data frames (256 KiB).
 pub const DEFAULT_CHUNK_SIZE: u32 = 256 * 1024;
@@ -264,12 +265,15 @@ pub enum FileTransferError {
 ///
 /// Reads FileTransferInit, validates the token, streams data chunks to a temp
 /// file, sends periodic progress ACKs, and returns the completed file data.
+/// Uploads count against the user's transfer limits and are read no faster
+/// than their upload rate.
 pub async fn handle_upload_stream(
     send: &mut quinn::SendStream,
     recv: &mut quinn::RecvStream,
     jwt_secret: &str,
     tracker: &TransferTracker,
     partial_mgr: &PartialUploadManager,
+    limiter: &TransferLimiter,
 ) -> Result<UploadResult, FileTransferError> {
     let mut codec = StreamFrameCodec::new();
     let mut buf = vec![0u8; 32 * 1024];
@@ -302,6 +306,16 @@ pub async fn handle_upload_stream(
         let _ = send.write_all(&reject.encode()?).await;
         return Err(FileTransferError::FileTooLarge { size: claims.fsize });
     }
+    let Some(permit) = limiter.try_begin(claims.sub, TransferDirection::Upload) else {
+        let reject = StreamFrame::Control(ControlMessage::FileTransferReject {
+            transfer_id: transfer_id.clone(),
+            reason: "too many concurrent uploads".into(),
+        });
+        let _ = send.write_all(&reject.encode()?).await;
+        return Err(FileTransferError::Rejected(
+            "too many concurrent uploads".into(),
+        ));
+    };
 
     // 3. Handle resume
     partial_mgr.ensure_dir().await?;
@@ -389,6 +403,7 @@ pub async fn handle_upload_stream(
                         ));
                     }
 
+                    permit.throttle(data.len()).await;
                     file.write_all(&data)
                         .await
                         .map_err(|e| FileTransferError::Io(e.to_string()))?;
@@ -452,6 +467,8 @@ pub struct UploadResult {
 /// Handle an incoming download stream.
 ///
 /// Reads FileDownloadRequest, validates auth, streams the file data back.
+/// `user_id` is the authenticated downloader, whose transfer limits apply.
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_download_stream(
     send: &mut quinn::SendStream,
     recv: &mut quinn::RecvStream,
@@ -460,6 +477,8 @@ pub async fn handle_download_stream(
     filename: &str,
     content_type: &str,
     attachment_id: &str,
+    user_id: i64,
+    limiter: &TransferLimiter,
 ) -> Result<(), FileTransferError> {
     let mut codec = StreamFrameCodec::new();
     let mut buf = vec![0u8; 32 * 1024];
@@ -480,6 +499,17 @@ pub async fn handle_download_stream(
         }
     };
 
+    let Some(permit) = limiter.try_begin(user_id, TransferDirection::Download) else {
+        let reject = StreamFrame::Control(ControlMessage::FileTransferReject {
+            transfer_id: req_attachment_id.clone(),
+            reason: "too many concurrent downloads".into(),
+        });
+        let _ = send.write_all(&reject.encode()?).await;
+        return Err(FileTransferError::Rejected(
+            "too many concurrent downloads".into(),
+        ));
+    };
+
     let offset = range_start.unwrap_or(0) as usize;
     let data_to_send = if offset < file_data.len() {
         &file_data[offset..]
@@ -502,6 +532,7 @@ pub async fn handle_download_stream(
     // 3. Send data in chunks
     let chunk_size = DEFAULT_CHUNK_SIZE as usize;
     for chunk in data_to_send.chunks(chunk_size) {
+        permit.throttle(chunk.len()).await;
         let frame = StreamFrame::Data(Bytes::copy_from_slice(chunk));
         send.write_all(&frame.encode()?)
             .await
@@ -3,5 +3,7 @@ pub mod control;
 pub mod endpoint;
 pub mod federation;
 pub mod file_transfer;
+pub mod metrics;
 pub mod protocol;
+pub mod transfer_limits;
 pub mod webtransport;
@@ -0,0 +1,164 @@
+//! Process-wide counters for the media endpoint, scraped by `/metrics`.
+//!
+//! Connections are counted once their QUIC handshake completes. A
+//! [`HandshakeAttempt`] follows a connection through WebTransport setup and
+//! authentication and records the stage it failed at if it is dropped
+//! before [`HandshakeAttempt::complete`].
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static QUIC_CONNECTIONS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static HANDSHAKE_FAILURES: [AtomicU64; HandshakeStage::ALL.len()] =
+    [const { AtomicU64::new(0) }; HandshakeStage::ALL.len()];
+static WEBTRANSPORT_SESSIONS_ACTIVE: AtomicU64 = AtomicU64::new(0);
+static WEBTRANSPORT_SESSIONS_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Where a media connection attempt gave up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeStage {
+    /// The endpoint refused the incoming connection.
+    Accept,
+    /// The QUIC/TLS handshake failed or timed out.
+    Quic,
+    /// HTTP/3 setup or the WebTransport CONNECT failed.
+    WebTransport,
+    /// The client never presented a valid token.
+    Auth,
+}
+
+impl HandshakeStage {
+    pub const ALL: [HandshakeStage; 4] = [
+        HandshakeStage::Accept,
+        HandshakeStage::Quic,
+        HandshakeStage::WebTransport,
+        HandshakeStage::Auth,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HandshakeStage::Accept => "accept",
+            HandshakeStage::Quic => "quic",
+            HandshakeStage::WebTransport => "webtransport",
+            HandshakeStage::Auth => "auth",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Count a connection that made it through the QUIC handshake.
+pub fn record_quic_connection() {
+    QUIC_CONNECTIONS_TOTAL.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_handshake_failure(stage: HandshakeStage) {
+    HANDSHAKE_FAILURES[stage.index()].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a failure at the current stage when dropped without
+/// [`complete`](Self::complete), so early returns need no bookkeeping.
+pub struct HandshakeAttempt {
+    stage: Option<HandshakeStage>,
+}
+
+impl HandshakeAttempt {
+    pub fn new(stage: HandshakeStage) -> Self {
+        Self { stage: Some(stage) }
+    }
+
+    pub fn advance(&mut self, stage: HandshakeStage) {
+        self.stage = Some(stage);
+    }
+
+    pub fn complete(mut self) {
+        self.stage = None;
+    }
+}
+
+impl Drop for HandshakeAttempt {
+    fn drop(&mut self) {
+        if let Some(stage) = self.stage {
+            record_handshake_failure(stage);
+        }
+    }
+}
+
+/// Keeps a WebTransport session counted as active until dropped.
+pub struct WebTransportSessionGuard(());
+
+impl WebTransportSessionGuard {
+    pub fn open() -> Self {
+        WEBTRANSPORT_SESSIONS_TOTAL.fetch_add(1, Ordering::Relaxed);
+        WEBTRANSPORT_SESSIONS_ACTIVE.fetch_add(1, Ordering::Relaxed);
+        Self(())
+    }
+}
+
+impl Drop for WebTransportSessionGuard {
+    fn drop(&mut self) {
+        let _ = WEBTRANSPORT_SESSIONS_ACTIVE.fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |current| Some(current.saturating_sub(1)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransportMetricsSnapshot {
+    pub quic_connections_total: u64,
+    pub handshake_failures: Vec<(HandshakeStage, u64)>,
+    pub webtransport_sessions_active: u64,
+    pub webtransport_sessions_total: u64,
+}
+
+pub fn snapshot() -> TransportMetricsSnapshot {
+    TransportMetricsSnapshot {
+        quic_connections_total: QUIC_CONNECTIONS_TOTAL.load(Ordering::Relaxed),
+        handshake_failures: HandshakeStage::ALL
+            .iter()
+            .map(|stage| {
+                (
+                    *stage,
+                    HANDSHAKE_FAILURES[stage.index()].load(Ordering::Relaxed),
+                )
+            })
+            .collect(),
+        webtransport_sessions_active: WEBTRANSPORT_SESSIONS_ACTIVE.load(Ordering::Relaxed),
+        webtransport_sessions_total: WEBTRANSPORT_SESSIONS_TOTAL.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failures(stage: HandshakeStage) -> u64 {
+        HANDSHAKE_FAILURES[stage.index()].load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn dropped_attempts_count_at_their_last_stage() {
+        let before_auth = failures(HandshakeStage::Auth);
+        let before_wt = failures(HandshakeStage::WebTransport);
+
+        let mut attempt = HandshakeAttempt::new(HandshakeStage::WebTransport);
+        attempt.advance(HandshakeStage::Auth);
+        drop(attempt);
+        HandshakeAttempt::new(HandshakeStage::WebTransport).complete();
+
+        assert_eq!(failures(HandshakeStage::Auth), before_auth + 1);
+        assert_eq!(failures(HandshakeStage::WebTransport), before_wt);
+    }
+
+    #[test]
+    fn session_guard_tracks_active_sessions() {
+        let before = snapshot().webtransport_sessions_total;
+        let guard = WebTransportSessionGuard::open();
+        assert!(snapshot().webtransport_sessions_active >= 1);
+        drop(guard);
+        assert!(snapshot().webtransport_sessions_total > before);
+    }
+}
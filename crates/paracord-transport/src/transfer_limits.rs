@@ -0,0 +1,225 @@
+//! Per-user file transfer limits.
+//!
+//! Caps how many uploads and downloads one user can run at once and,
+//! optionally, how fast they go. The bandwidth budget is shared by all of a
+//! user's transfers in the same direction, whether they arrive over HTTP or
+//! the QUIC file transfer streams, so a single account can't saturate the
+//! uplink of a small server and starve voice traffic.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::time::Instant;
+
+/// Limits for one user. Zero disables a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferLimits {
+    pub max_concurrent_uploads: u32,
+    pub max_concurrent_downloads: u32,
+    pub upload_bytes_per_second: u64,
+    pub download_bytes_per_second: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// One direction of a user's transfers: the running count and when the
+/// shared bandwidth budget is next free.
+#[derive(Debug, Default)]
+struct Lane {
+    active: AtomicU32,
+    next_free: Mutex<Option<Instant>>,
+}
+
+#[derive(Debug, Default)]
+struct UserTransfers {
+    uploads: Lane,
+    downloads: Lane,
+}
+
+impl UserTransfers {
+    fn lane(&self, direction: TransferDirection) -> &Lane {
+        match direction {
+            TransferDirection::Upload => &self.uploads,
+            TransferDirection::Download => &self.downloads,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.uploads.active.load(Ordering::Acquire) == 0
+            && self.downloads.active.load(Ordering::Acquire) == 0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TransferLimiter {
+    limits: TransferLimits,
+    users: Arc<DashMap<i64, Arc<UserTransfers>>>,
+}
+
+impl TransferLimiter {
+    pub fn new(limits: TransferLimits) -> Self {
+        Self {
+            limits,
+            users: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Start a transfer for `user_id`. `None` when the user already has the
+    /// maximum number of transfers running in that direction. The slot is
+    /// released when the permit is dropped.
+    pub fn try_begin(&self, user_id: i64, direction: TransferDirection) -> Option<TransferPermit> {
+        let (max_concurrent, bytes_per_second) = match direction {
+            TransferDirection::Upload => (
+                self.limits.max_concurrent_uploads,
+                self.limits.upload_bytes_per_second,
+            ),
+            TransferDirection::Download => (
+                self.limits.max_concurrent_downloads,
+                self.limits.download_bytes_per_second,
+            ),
+        };
+        // Count the transfer while holding the map entry so a permit dropped
+        // concurrently can't remove the user in between.
+        let entry = self.users.entry(user_id).or_default();
+        let admitted = entry
+            .lane(direction)
+            .active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (max_concurrent == 0 || count < max_concurrent).then_some(count + 1)
+            })
+            .is_ok();
+        let user = entry.clone();
+        drop(entry);
+        if !admitted {
+            return None;
+        }
+        Some(TransferPermit {
+            user_id,
+            direction,
+            bytes_per_second,
+            user,
+            users: self.users.clone(),
+        })
+    }
+
+    /// Transfers currently running for `user_id` as `(uploads, downloads)`.
+    pub fn active(&self, user_id: i64) -> (u32, u32) {
+        self.users
+            .get(&user_id)
+            .map(|user| {
+                (
+                    user.uploads.active.load(Ordering::Acquire),
+                    user.downloads.active.load(Ordering::Acquire),
+                )
+            })
+            .unwrap_or((0, 0))
+    }
+}
+
+/// A running transfer. Holds one of the user's concurrency slots until
+/// dropped.
+#[derive(Debug)]
+pub struct TransferPermit {
+    user_id: i64,
+    direction: TransferDirection,
+    bytes_per_second: u64,
+    user: Arc<UserTransfers>,
+    users: Arc<DashMap<i64, Arc<UserTransfers>>>,
+}
+
+impl TransferPermit {
+    /// Wait until `bytes` more fit in the user's bandwidth budget. Returns
+    /// immediately when no rate is configured.
+    pub async fn throttle(&self, bytes: usize) {
+        if self.bytes_per_second == 0 || bytes == 0 {
+            return;
+        }
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64);
+        let start = {
+            let mut next_free = self
+                .user
+                .lane(self.direction)
+                .next_free
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = Instant::now();
+            let start = next_free.map_or(now, |at| at.max(now));
+            *next_free = Some(start + cost);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+impl Drop for TransferPermit {
+    fn drop(&mut self) {
+        self.user
+            .lane(self.direction)
+            .active
+            .fetch_sub(1, Ordering::AcqRel);
+        self.users
+            .remove_if(&self.user_id, |_, user| user.is_idle());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrency_is_limited_per_user_and_direction() {
+        let limiter = TransferLimiter::new(TransferLimits {
+            max_concurrent_uploads: 1,
+            max_concurrent_downloads: 2,
+            ..TransferLimits::default()
+        });
+        let upload = limiter.try_begin(1, TransferDirection::Upload).unwrap();
+        assert!(limiter.try_begin(1, TransferDirection::Upload).is_none());
+        assert!(limiter.try_begin(2, TransferDirection::Upload).is_some());
+
+        let first = limiter.try_begin(1, TransferDirection::Download).unwrap();
+        let _second = limiter.try_begin(1, TransferDirection::Download).unwrap();
+        assert!(limiter.try_begin(1, TransferDirection::Download).is_none());
+        assert_eq!(limiter.active(1), (1, 2));
+
+        drop(first);
+        drop(upload);
+        assert!(limiter.try_begin(1, TransferDirection::Upload).is_some());
+        assert!(limiter.try_begin(1, TransferDirection::Download).is_some());
+    }
+
+    #[test]
+    fn idle_users_are_forgotten() {
+        let limiter = TransferLimiter::new(TransferLimits::default());
+        let permit = limiter.try_begin(7, TransferDirection::Download).unwrap();
+        assert_eq!(limiter.users.len(), 1);
+        drop(permit);
+        assert!(limiter.users.is_empty());
+    }
+
+    #[tokio::test]
+    async fn bandwidth_is_shared_between_transfers() {
+        let limiter = TransferLimiter::new(TransferLimits {
+            download_bytes_per_second: 100_000,
+            ..TransferLimits::default()
+        });
+        let a = limiter.try_begin(1, TransferDirection::Download).unwrap();
+        let b = limiter.try_begin(1, TransferDirection::Download).unwrap();
+        let started = Instant::now();
+        a.throttle(10_000).await;
+        b.throttle(10_000).await;
+        a.throttle(5_000).await;
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        let unlimited = limiter.try_begin(1, TransferDirection::Upload).unwrap();
+        let started = Instant::now();
+        unlimited.throttle(1_000_000).await;
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+}
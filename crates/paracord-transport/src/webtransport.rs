@@ -2,14 +2,23 @@
 //!
 //! Wraps h3 + h3-quinn to accept WebTransport sessions over HTTP/3,
 //! providing the same datagram/stream interface as native QUIC connections.
+//! Each connection may carry several sessions; datagrams are routed by
+//! their quarter stream ID prefix and streams by their session header.
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use bytes::Bytes;
 use h3::ext::Protocol;
+use h3::frame::FrameStream;
+use h3::proto::frame::Frame;
+use h3::quic::BidiStream as _;
 use h3::server::Connection as H3Connection;
+use h3::stream::BufRecvStream;
+use h3::webtransport::SessionId;
 use quinn::crypto::rustls::QuicServerConfig;
+use tokio::sync::mpsc;
 
 use crate::endpoint::TlsConfig;
 
@@ -86,9 +95,12 @@ impl WebTransportServer {
             .build(h3_quinn::Connection::new(conn.clone()))
             .await?;
 
+        let routes = SharedRoutes::default();
+        tokio::spawn(route_datagrams(conn.clone(), Arc::clone(&routes)));
         Ok(H3Session {
             h3_conn,
             quinn_conn: conn,
+            routes,
         })
     }
 
@@ -103,10 +115,41 @@ impl WebTransportServer {
     }
 }
 
-/// An active HTTP/3 session that can accept WebTransport upgrades.
+/// Send half of a WebTransport bidirectional stream.
+pub type WebTransportSendStream = BufRecvStream<h3_quinn::SendStream<Bytes>, Bytes>;
+/// Receive half of a WebTransport bidirectional stream.
+pub type WebTransportRecvStream = BufRecvStream<h3_quinn::RecvStream, Bytes>;
+type StreamPair = (WebTransportSendStream, WebTransportRecvStream);
+
+/// Where datagrams and streams of each open session on a connection go.
+#[derive(Default)]
+struct SessionRoutes {
+    /// Quarter stream ID (the datagram prefix) -> inbound datagram queue.
+    datagrams: HashMap<u64, mpsc::UnboundedSender<Bytes>>,
+    /// CONNECT stream ID (the stream header) -> incoming bidi stream queue.
+    streams: HashMap<u64, mpsc::UnboundedSender<StreamPair>>,
+}
+
+type SharedRoutes = Arc<Mutex<SessionRoutes>>;
+
+fn lock_routes(routes: &SharedRoutes) -> std::sync::MutexGuard<'_, SessionRoutes> {
+    match routes.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// An active HTTP/3 connection that can accept WebTransport upgrades.
+///
+/// A browser may open several sessions on one connection, or a new one
+/// after an earlier one closed, so keep calling
+/// [`accept_session`](Self::accept_session) for as long as the connection
+/// lives: besides yielding new sessions, it hands each incoming
+/// WebTransport stream to the session it belongs to.
 pub struct H3Session {
     h3_conn: H3Connection<h3_quinn::Connection, Bytes>,
     quinn_conn: quinn::Connection,
+    routes: SharedRoutes,
 }
 
 impl H3Session {
@@ -118,41 +161,148 @@ impl H3Session {
         &mut self,
     ) -> Result<Option<WebTransportSession>, WebTransportError> {
         loop {
-            let resolver = match self.h3_conn.accept().await? {
-                Some(resolver) => resolver,
-                None => return Ok(None),
-            };
+            let stream =
+                match std::future::poll_fn(|cx| self.h3_conn.poll_accept_request_stream(cx)).await?
+                {
+                    Some(stream) => stream,
+                    None => return Ok(None),
+                };
+
+            // The first frame tells a session's stream from a new request.
+            let mut frames = FrameStream::new(BufRecvStream::new(stream));
+            let frame = std::future::poll_fn(|cx| frames.poll_next(cx)).await;
+            if let Ok(Some(Frame::WebTransportStream(session_id))) = frame {
+                self.route_stream(session_id, frames.into_inner());
+                continue;
+            }
 
-            let (request, _stream) = resolver.resolve_request().await?;
+            let resolved = self
+                .h3_conn
+                .create_resolver(frames)
+                .accept_with_frame(frame);
+            let (request, mut stream) = match resolved {
+                Ok(resolved) => match resolved.resolve().await {
+                    Ok(request) => request,
+                    Err(h3::error::StreamError::ConnectionError(e)) => return Err(e.into()),
+                    Err(e) => {
+                        tracing::debug!(error = %e, "dropping malformed HTTP/3 request");
+                        continue;
+                    }
+                },
+                Err(h3::error::StreamError::ConnectionError(e)) => return Err(e.into()),
+                Err(e) => {
+                    tracing::debug!(error = %e, "dropping malformed HTTP/3 request");
+                    continue;
+                }
+            };
             let (parts, _body) = request.into_parts();
 
             // Check if this is a WebTransport CONNECT request
             let is_webtransport =
                 parts.extensions.get::<Protocol>() == Some(&Protocol::WEB_TRANSPORT);
+            if !is_webtransport {
+                tracing::debug!(
+                    method = %parts.method,
+                    uri = %parts.uri,
+                    "ignoring non-WebTransport HTTP/3 request"
+                );
+                continue;
+            }
 
-            if is_webtransport {
-                return Ok(Some(WebTransportSession {
-                    quinn_conn: self.quinn_conn.clone(),
-                    path: parts.uri.path().to_string(),
-                }));
+            let response = http::Response::builder()
+                .status(http::StatusCode::OK)
+                .body(())
+                .expect("static response");
+            if let Err(e) = stream.send_response(response).await {
+                tracing::debug!(error = %e, "WebTransport CONNECT response failed");
+                continue;
             }
 
-            tracing::debug!(
-                method = %parts.method,
-                uri = %parts.uri,
-                "ignoring non-WebTransport HTTP/3 request"
-            );
+            let session_id = stream.id().into_inner();
+            let (datagram_tx, datagram_rx) = mpsc::unbounded_channel();
+            let (stream_tx, stream_rx) = mpsc::unbounded_channel();
+            {
+                let mut routes = lock_routes(&self.routes);
+                routes.datagrams.insert(session_id / 4, datagram_tx);
+                routes.streams.insert(session_id, stream_tx);
+            }
+            tokio::spawn(watch_session(stream, session_id, Arc::clone(&self.routes)));
+
+            return Ok(Some(WebTransportSession {
+                quinn_conn: self.quinn_conn.clone(),
+                path: parts.uri.path().to_string(),
+                session_id,
+                datagrams: Some(datagram_rx),
+                streams: stream_rx,
+            }));
         }
     }
+
+    fn route_stream(
+        &self,
+        session_id: SessionId,
+        stream: BufRecvStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    ) {
+        let tx = {
+            let routes = lock_routes(&self.routes);
+            routes
+                .streams
+                .iter()
+                .find(|(id, _)| SessionId::try_from(**id).ok() == Some(session_id))
+                .map(|(_, tx)| tx.clone())
+        };
+        match tx {
+            Some(tx) => {
+                let _ = tx.send(stream.split());
+            }
+            // Dropping the stream resets it.
+            None => tracing::debug!(?session_id, "WebTransport stream for unknown session"),
+        }
+    }
+}
+
+/// A session lasts as long as its CONNECT stream. Once the browser closes
+/// it (or the connection goes away) its routes are dropped, which ends the
+/// session's datagram and stream queues.
+async fn watch_session(
+    mut stream: h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    session_id: u64,
+    routes: SharedRoutes,
+) {
+    let _open = crate::metrics::WebTransportSessionGuard::open();
+    // Capsules (e.g. CLOSE_WEBTRANSPORT_SESSION) are followed by FIN.
+    while let Ok(Some(_)) = stream.recv_data().await {}
+    let mut routes = lock_routes(&routes);
+    routes.datagrams.remove(&(session_id / 4));
+    routes.streams.remove(&session_id);
+    tracing::debug!(session_id, "WebTransport session closed");
 }
 
-/// A WebTransport session wrapping the underlying QUIC connection.
+/// Route every datagram on the connection to its session by QSID prefix.
+async fn route_datagrams(quinn_conn: quinn::Connection, routes: SharedRoutes) {
+    while let Ok(datagram) = quinn_conn.read_datagram().await {
+        let Some((qsid, prefix_len)) = decode_quic_varint(&datagram) else {
+            continue;
+        };
+        let tx = lock_routes(&routes).datagrams.get(&qsid).cloned();
+        if let Some(tx) = tx {
+            let _ = tx.send(datagram.slice(prefix_len..));
+        }
+    }
+}
+
+/// One WebTransport session on a (possibly shared) HTTP/3 connection.
 ///
 /// Provides the same datagram/stream interface as `MediaConnection`
 /// so browser clients can interop with native QUIC clients.
 pub struct WebTransportSession {
     quinn_conn: quinn::Connection,
     path: String,
+    /// Stream ID of the CONNECT request.
+    session_id: u64,
+    /// Taken by [`spawn_webtransport_bridge`].
+    datagrams: Option<mpsc::UnboundedReceiver<Bytes>>,
+    streams: mpsc::UnboundedReceiver<StreamPair>,
 }
 
 impl WebTransportSession {
@@ -161,28 +311,20 @@ impl WebTransportSession {
         &self.path
     }
 
-    /// Send an unreliable datagram (for media packets).
-    pub fn send_datagram(&self, data: Bytes) -> Result<(), quinn::SendDatagramError> {
-        self.quinn_conn.send_datagram(data)
+    /// Stream ID of the CONNECT request that opened the session.
+    pub fn session_id(&self) -> u64 {
+        self.session_id
     }
 
-    /// Receive an unreliable datagram.
-    pub async fn read_datagram(&self) -> Result<Bytes, quinn::ConnectionError> {
-        self.quinn_conn.read_datagram().await
+    /// Prefix of this session's datagrams (RFC 9297 quarter stream ID).
+    pub fn quarter_stream_id(&self) -> u64 {
+        self.session_id / 4
     }
 
-    /// Open a bidirectional stream.
-    pub async fn open_bi(
-        &self,
-    ) -> Result<(quinn::SendStream, quinn::RecvStream), quinn::ConnectionError> {
-        self.quinn_conn.open_bi().await
-    }
-
-    /// Accept a bidirectional stream from the browser.
-    pub async fn accept_bi(
-        &self,
-    ) -> Result<(quinn::SendStream, quinn::RecvStream), quinn::ConnectionError> {
-        self.quinn_conn.accept_bi().await
+    /// Accept the next bidirectional stream the browser opened on this
+    /// session. `None` once the session is closed.
+    pub async fn accept_bi(&mut self) -> Option<StreamPair> {
+        self.streams.recv().await
     }
 
     /// Remote address of the browser client.
@@ -195,7 +337,7 @@ impl WebTransportSession {
         &self.quinn_conn
     }
 
-    /// Close the session.
+    /// Close the whole connection, including any other sessions on it.
     pub fn close(&self, reason: &str) {
         self.quinn_conn
             .close(quinn::VarInt::from_u32(1), reason.as_bytes());
@@ -257,33 +399,35 @@ fn encode_quic_varint(val: u64) -> Vec<u8> {
 }
 
 /// Spawn a datagram bridge that translates between HTTP/3 datagrams
-/// (with QSID varint prefix) and raw media packets.
+/// (with QSID varint prefix) and raw media packets for one session.
 ///
 /// Returns `(outbound_tx, inbound_rx)` channels:
-/// - Write raw media packets to `outbound_tx` → bridge prepends QSID and
-///   sends via the QUIC connection.
-/// - Read raw media packets from `inbound_rx` ← bridge strips QSID from
-///   incoming QUIC datagrams.
+/// - Write raw media packets to `outbound_tx` → bridge prepends the
+///   session's QSID and sends via the QUIC connection.
+/// - Read raw media packets from `inbound_rx` ← datagrams carrying the
+///   session's QSID, prefix stripped. It closes when the session does.
+///
+/// Call at most once per session; later calls get a closed `inbound_rx`.
 pub fn spawn_webtransport_bridge(
-    quinn_conn: quinn::Connection,
-    qsid: u64,
+    session: &mut WebTransportSession,
 ) -> (
     tokio::sync::mpsc::UnboundedSender<Bytes>,
     tokio::sync::mpsc::UnboundedReceiver<Bytes>,
 ) {
     let (outbound_tx, mut outbound_rx) = tokio::sync::mpsc::unbounded_channel::<Bytes>();
-    let (inbound_tx, inbound_rx) = tokio::sync::mpsc::unbounded_channel::<Bytes>();
+    let inbound_rx = session
+        .datagrams
+        .take()
+        .unwrap_or_else(|| mpsc::unbounded_channel().1);
 
-    let qsid_prefix = Bytes::from(encode_quic_varint(qsid));
-    let conn_out = quinn_conn.clone();
-    let prefix_clone = qsid_prefix.clone();
+    let qsid_prefix = Bytes::from(encode_quic_varint(session.quarter_stream_id()));
+    let conn_out = session.quinn_conn.clone();
 
     // Outbound: relay → browser
     tokio::spawn(async move {
         while let Some(raw_packet) = outbound_rx.recv().await {
-            let mut datagram =
-                bytes::BytesMut::with_capacity(prefix_clone.len() + raw_packet.len());
-            datagram.extend_from_slice(&prefix_clone);
+            let mut datagram = bytes::BytesMut::with_capacity(qsid_prefix.len() + raw_packet.len());
+            datagram.extend_from_slice(&qsid_prefix);
             datagram.extend_from_slice(&raw_packet);
             if conn_out.send_datagram(datagram.freeze()).is_err() {
                 break;
@@ -291,20 +435,23 @@ pub fn spawn_webtransport_bridge(
         }
     });
 
-    // Inbound: browser → relay
-    tokio::spawn(async move {
-        while let Ok(datagram) = quinn_conn.read_datagram().await {
-            // Strip the QSID varint prefix
-            if let Some((_qsid_val, prefix_len)) = decode_quic_varint(&datagram) {
-                if prefix_len <= datagram.len() {
-                    let raw = datagram.slice(prefix_len..);
-                    if inbound_tx.send(raw).is_err() {
-                        break;
-                    }
-                }
-            }
-        }
-    });
-
     (outbound_tx, inbound_rx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qsid_prefix_round_trips_for_later_sessions() {
+        // Session CONNECT streams are client bidi streams: 0, 4, 8, ...
+        for session_id in [0u64, 4, 256, 65_536, 1 << 32] {
+            let qsid = session_id / 4;
+            let mut datagram = encode_quic_varint(qsid);
+            let prefix_len = datagram.len();
+            datagram.extend_from_slice(b"media");
+            assert_eq!(decode_quic_varint(&datagram), Some((qsid, prefix_len)));
+        }
+        assert_eq!(decode_quic_varint(&[0x40]), None);
+    }
+}
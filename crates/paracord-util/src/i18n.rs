@@ -0,0 +1,300 @@
+//! Localization of server-generated, user-facing strings.
+//!
+//! English is the source language: messages are written in English at the
+//! call site and the English text doubles as the catalog key. Translation
+//! bundles live in `locales/<tag>.json` and are embedded in the binary. A key
+//! may contain `{name}` placeholders, which lets messages built with
+//! `format!` be translated after the fact by matching them against the key.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+/// Locale used when nothing better can be negotiated.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Every locale the server can answer in, default first.
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "de", "es", "fr"];
+
+const BUNDLES: &[(&str, &str)] = &[
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+#[derive(Debug)]
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn parse_template(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|idx| open + idx) else {
+            break;
+        };
+        if open > 0 {
+            segments.push(Segment::Literal(&rest[..open]));
+        }
+        segments.push(Segment::Placeholder(&rest[open + 1..close]));
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    segments
+}
+
+/// Match `message` against a `{placeholder}` template, returning the
+/// captured values in order.
+fn match_template<'t, 'm>(template: &'t str, message: &'m str) -> Option<Vec<(&'t str, &'m str)>> {
+    let segments = parse_template(template);
+    let mut captures = Vec::new();
+    let mut pos = 0;
+    for (idx, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(literal) => {
+                if !message[pos..].starts_with(literal) {
+                    return None;
+                }
+                pos += literal.len();
+            }
+            Segment::Placeholder(name) => {
+                let end = match segments.get(idx + 1) {
+                    Some(Segment::Literal(next)) => pos + message[pos..].find(next)?,
+                    // Adjacent placeholders are ambiguous; don't try.
+                    Some(Segment::Placeholder(_)) => return None,
+                    None => message.len(),
+                };
+                if end == pos {
+                    return None;
+                }
+                captures.push((*name, &message[pos..end]));
+                pos = end;
+            }
+        }
+    }
+    (pos == message.len()).then_some(captures)
+}
+
+fn fill_template(template: &str, args: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    for segment in parse_template(template) {
+        match segment {
+            Segment::Literal(literal) => out.push_str(literal),
+            Segment::Placeholder(name) => match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => out.push_str(value),
+                None => {
+                    out.push('{');
+                    out.push_str(name);
+                    out.push('}');
+                }
+            },
+        }
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+struct Catalog {
+    exact: HashMap<String, String>,
+    /// Keys with placeholders, tried in order when there is no exact match.
+    templates: Vec<(String, String)>,
+}
+
+impl Catalog {
+    fn parse(source: &str) -> Result<Self, serde_json::Error> {
+        let entries: BTreeMap<String, String> = serde_json::from_str(source)?;
+        let mut catalog = Catalog::default();
+        for (key, value) in entries {
+            if key.contains('{') {
+                catalog.templates.push((key, value));
+            } else {
+                catalog.exact.insert(key, value);
+            }
+        }
+        Ok(catalog)
+    }
+}
+
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    static CATALOGS: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        BUNDLES
+            .iter()
+            .map(|(locale, source)| (*locale, Catalog::parse(source).unwrap_or_default()))
+            .collect()
+    })
+}
+
+/// Map a language tag onto a supported locale: an exact match first, then
+/// its primary language (`de-AT` -> `de`).
+pub fn supported_locale(tag: &str) -> Option<&'static str> {
+    let tag = tag.trim().replace('_', "-").to_ascii_lowercase();
+    if tag.is_empty() {
+        return None;
+    }
+    let primary = tag.split('-').next().unwrap_or_default();
+    SUPPORTED_LOCALES
+        .iter()
+        .find(|locale| **locale == tag)
+        .or_else(|| SUPPORTED_LOCALES.iter().find(|locale| **locale == primary))
+        .copied()
+}
+
+/// Language ranges from an `Accept-Language` header, highest weight first.
+/// Ranges with `q=0` and the `*` wildcard are dropped.
+pub fn parse_accept_language(header: &str) -> Vec<&str> {
+    let mut ranges: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let tag = params.next()?.trim();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable sort keeps header order among equal weights.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Pick the response locale. An explicit preference (the user's saved
+/// locale) wins over the request's `Accept-Language` header.
+pub fn negotiate(preferred: Option<&str>, accept_language: Option<&str>) -> &'static str {
+    preferred
+        .and_then(supported_locale)
+        .or_else(|| {
+            accept_language.and_then(|header| {
+                parse_accept_language(header)
+                    .into_iter()
+                    .find_map(supported_locale)
+            })
+        })
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Translate an English message. Messages without a translation are
+/// returned unchanged.
+pub fn translate<'a>(locale: &str, message: &'a str) -> Cow<'a, str> {
+    let Some(catalog) = catalogs().get(locale) else {
+        return Cow::Borrowed(message);
+    };
+    if let Some(translated) = catalog.exact.get(message) {
+        return Cow::Owned(translated.clone());
+    }
+    for (template, translated) in &catalog.templates {
+        if let Some(args) = match_template(template, message) {
+            return Cow::Owned(fill_template(translated, &args));
+        }
+    }
+    Cow::Borrowed(message)
+}
+
+/// Translate a `{placeholder}` template and fill in `args`.
+pub fn translate_args(locale: &str, template: &str, args: &[(&str, &str)]) -> String {
+    let translated = catalogs()
+        .get(locale)
+        .and_then(|catalog| {
+            catalog
+                .exact
+                .get(template)
+                .or_else(|| {
+                    catalog
+                        .templates
+                        .iter()
+                        .find(|(key, _)| key == template)
+                        .map(|(_, value)| value)
+                })
+                .map(String::as_str)
+        })
+        .unwrap_or(template);
+    fill_template(translated, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = parse_template(template)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Placeholder(name) => Some(name),
+                Segment::Literal(_) => None,
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn bundles_parse_and_keep_placeholders() {
+        for (locale, source) in BUNDLES {
+            let entries: BTreeMap<String, String> = serde_json::from_str(source)
+                .unwrap_or_else(|err| panic!("{locale} bundle is invalid: {err}"));
+            assert!(SUPPORTED_LOCALES.contains(locale));
+            for (key, value) in &entries {
+                assert_eq!(
+                    placeholders(key),
+                    placeholders(value),
+                    "{locale}: placeholders differ for {key:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn negotiates_from_preference_then_accept_language() {
+        assert_eq!(negotiate(Some("fr-CA"), Some("de")), "fr");
+        assert_eq!(
+            negotiate(Some("xx"), Some("da, de-AT;q=0.8, en;q=0.5")),
+            "de"
+        );
+        assert_eq!(negotiate(None, Some("es;q=0.2, fr;q=0.9")), "fr");
+        assert_eq!(negotiate(None, Some("de;q=0, *")), DEFAULT_LOCALE);
+        assert_eq!(negotiate(Some("en_US"), None), "en");
+        assert_eq!(negotiate(None, None), DEFAULT_LOCALE);
+    }
+
+    #[test]
+    fn translates_exact_and_formatted_messages() {
+        assert_eq!(translate("de", "not found"), "nicht gefunden");
+        assert_eq!(
+            translate("de", "Image must be under 1024 KB"),
+            "Das Bild muss kleiner als 1024 KB sein"
+        );
+        assert_eq!(translate("de", "no such message"), "no such message");
+        assert_eq!(translate("en", "not found"), "not found");
+        assert_eq!(
+            translate_args("fr", "bad request: {reason}", &[("reason", "x")]),
+            "requête invalide : x"
+        );
+        assert_eq!(
+            translate_args("en", "bad request: {reason}", &[("reason", "x")]),
+            "bad request: x"
+        );
+    }
+
+    #[test]
+    fn template_matching_requires_a_full_match() {
+        let template = "reminder_minutes must be {min}-{max}";
+        assert_eq!(
+            match_template(template, "reminder_minutes must be 1-10080"),
+            Some(vec![("min", "1"), ("max", "10080")])
+        );
+        assert_eq!(
+            match_template(template, "reminder_minutes must be -5"),
+            None
+        );
+        assert_eq!(match_template("{a}{b}", "xy"), None);
+    }
+}
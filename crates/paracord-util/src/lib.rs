@@ -1,4 +1,6 @@
 pub mod at_rest;
+pub mod i18n;
+pub mod net;
 pub mod pagination;
 pub mod snowflake;
 pub mod validation;
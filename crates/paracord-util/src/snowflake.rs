@@ -1,8 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
 use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
 
 /// Custom epoch: 2024-01-01T00:00:00Z
-const PARACORD_EPOCH: u64 = 1_704_067_200_000;
+pub const PARACORD_EPOCH: u64 = 1_704_067_200_000;
+
+/// Discord's epoch: 2015-01-01T00:00:00Z
+pub const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+/// Largest worker id that fits the 10 worker bits.
+pub const MAX_WORKER_ID: u16 = 0x3FF;
+
+/// [`try_next_id`] waits out backwards clock jumps up to this long; longer
+/// ones are refused so IDs issued before the jump can't be handed out again.
+const MAX_BACKWARDS_WAIT_MS: u64 = 2_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SnowflakeError {
+    #[error("worker id {0} is out of range (0-1023)")]
+    InvalidWorkerId(u64),
+    #[error("system clock moved backwards by {0} ms")]
+    ClockMovedBackwards(u64),
+    #[error("epoch {0} is in the future")]
+    InvalidEpoch(u64),
+}
+
+/// Which epoch IDs count from. Both use the same bit layout; Discord splits
+/// the 10 worker bits into a 5-bit worker and 5-bit process id, so IDs
+/// generated in Discord mode are valid Discord snowflakes and imported
+/// Discord IDs sort and date correctly next to native ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnowflakeFormat {
+    #[default]
+    Paracord,
+    Discord,
+}
+
+impl SnowflakeFormat {
+    pub fn epoch_millis(self) -> u64 {
+        match self {
+            SnowflakeFormat::Paracord => PARACORD_EPOCH,
+            SnowflakeFormat::Discord => DISCORD_EPOCH,
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "paracord" => Some(SnowflakeFormat::Paracord),
+            "discord" => Some(SnowflakeFormat::Discord),
+            _ => None,
+        }
+    }
+}
+
+/// The parts packed into a snowflake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnowflakeParts {
+    /// Unix timestamp in milliseconds.
+    pub timestamp_millis: u64,
+    pub worker_id: u16,
+    pub sequence: u16,
+}
 
 struct SnowflakeState {
     last_timestamp: u64,
@@ -14,29 +76,114 @@ static STATE: Mutex<SnowflakeState> = Mutex::new(SnowflakeState {
     sequence: 0,
 });
 
-fn current_timestamp() -> u64 {
+static WORKER_ID: AtomicU16 = AtomicU16::new(1);
+
+static EPOCH: AtomicU64 = AtomicU64::new(PARACORD_EPOCH);
+
+fn unix_millis() -> Result<u64, SnowflakeError> {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .expect("time went backwards")
-        .as_millis() as u64
-        - PARACORD_EPOCH
+        .map(|since| since.as_millis() as u64)
+        .map_err(|err| SnowflakeError::ClockMovedBackwards(err.duration().as_millis() as u64))
+}
+
+fn current_timestamp() -> Result<u64, SnowflakeError> {
+    Ok(unix_millis()?.saturating_sub(epoch()))
+}
+
+/// Set the epoch (Unix ms) used to generate and decode IDs. Call once at
+/// startup, before any IDs are issued: moving it later on an existing
+/// database makes new IDs sort before old ones.
+pub fn set_epoch(epoch_millis: u64) -> Result<(), SnowflakeError> {
+    if epoch_millis > unix_millis()? {
+        return Err(SnowflakeError::InvalidEpoch(epoch_millis));
+    }
+    EPOCH.store(epoch_millis, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Epoch of this node's IDs in Unix ms ([`PARACORD_EPOCH`] unless configured).
+pub fn epoch() -> u64 {
+    EPOCH.load(Ordering::Relaxed)
+}
+
+/// Set the worker id used by [`next_id`]. Every node sharing a database needs
+/// its own id.
+pub fn set_worker_id(worker_id: u64) -> Result<(), SnowflakeError> {
+    if worker_id > MAX_WORKER_ID as u64 {
+        return Err(SnowflakeError::InvalidWorkerId(worker_id));
+    }
+    WORKER_ID.store(worker_id as u16, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Worker id of this node (1 unless configured).
+pub fn worker_id() -> u16 {
+    WORKER_ID.load(Ordering::Relaxed)
+}
+
+/// Generate a Snowflake ID for this node's configured worker id. A short
+/// backwards clock jump is waited out on the async runtime, outside the
+/// generator lock; a longer one fails, and callers should surface that as
+/// a temporary outage.
+pub async fn try_next_id() -> Result<i64, SnowflakeError> {
+    let mut waited = 0;
+    loop {
+        match try_generate(worker_id()) {
+            Err(SnowflakeError::ClockMovedBackwards(behind))
+                if waited + behind <= MAX_BACKWARDS_WAIT_MS =>
+            {
+                let pause = behind.clamp(1, 10);
+                tokio::time::sleep(Duration::from_millis(pause)).await;
+                waited += pause;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Test-only shorthand for [`try_generate`] with this node's worker id that
+/// panics on failure.
+#[cfg(any(test, feature = "test-util"))]
+pub fn next_id() -> i64 {
+    generate(worker_id())
+}
+
+/// Test-only shorthand for [`try_generate`] that panics on failure.
+#[cfg(any(test, feature = "test-util"))]
+pub fn generate(worker_id: u16) -> i64 {
+    try_generate(worker_id).unwrap_or_else(|err| panic!("snowflake generation failed: {err}"))
 }
 
 /// Generate a Snowflake ID.
 /// Format: 42 bits timestamp | 10 bits worker | 12 bits sequence
-pub fn generate(worker_id: u16) -> i64 {
-    let mut state = STATE.lock().unwrap();
-    let mut timestamp = current_timestamp();
+///
+/// Never waits for the clock: while it is behind the last issued ID this
+/// fails with [`SnowflakeError::ClockMovedBackwards`], so the lock is never
+/// held across a sleep.
+pub fn try_generate(worker_id: u16) -> Result<i64, SnowflakeError> {
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    issue(&mut state, worker_id)
+}
+
+fn issue(state: &mut SnowflakeState, worker_id: u16) -> Result<i64, SnowflakeError> {
+    let mut timestamp = current_timestamp()?;
+
+    if timestamp < state.last_timestamp {
+        return Err(SnowflakeError::ClockMovedBackwards(
+            state.last_timestamp - timestamp,
+        ));
+    }
 
     if timestamp == state.last_timestamp {
         state.sequence = (state.sequence + 1) & 0xFFF;
         if state.sequence == 0 {
             // Sequence overflow: wait until next millisecond to avoid collisions.
             while timestamp <= state.last_timestamp {
-                drop(state);
                 std::hint::spin_loop();
-                state = STATE.lock().unwrap();
-                timestamp = current_timestamp();
+                timestamp = current_timestamp()?;
             }
         }
     } else {
@@ -45,11 +192,136 @@ pub fn generate(worker_id: u16) -> i64 {
 
     state.last_timestamp = timestamp;
     let seq = state.sequence;
-    let id = (timestamp << 22) | ((worker_id as u64 & 0x3FF) << 12) | seq;
-    id as i64
+    let id = (timestamp << 22) | ((worker_id as u64 & MAX_WORKER_ID as u64) << 12) | seq;
+    Ok(id as i64)
 }
 
 /// Extract the Unix timestamp (ms) from a snowflake.
 pub fn timestamp_millis(id: i64) -> u64 {
-    ((id as u64) >> 22) + PARACORD_EPOCH
+    ((id as u64) >> 22) + epoch()
+}
+
+/// When the ID was issued. Prefer this over a separate `created_at` column
+/// for ordering and range queries: IDs are indexed and monotonic.
+pub fn created_at(id: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(timestamp_millis(id) as i64).unwrap_or_default()
+}
+
+/// The smallest ID that could be issued at `at`. Every ID created at or after
+/// `at` is `>=` this, every earlier one is `<`, so it works as a `before` or
+/// `after` bound for time-based pagination.
+pub fn min_id_at(at: DateTime<Utc>) -> i64 {
+    let millis = u64::try_from(at.timestamp_millis()).unwrap_or(0);
+    (millis.saturating_sub(epoch()) << 22) as i64
+}
+
+/// Rebase an ID generated against `source_epoch` (e.g. [`DISCORD_EPOCH`]
+/// for Discord exports) onto this node's epoch, keeping its timestamp,
+/// worker and sequence bits so imported data stays in chronological order.
+/// `None` if the ID predates this node's epoch.
+pub fn rebase(id: i64, source_epoch: u64) -> Option<i64> {
+    let raw = id as u64;
+    let millis = (raw >> 22) + source_epoch;
+    let timestamp = millis.checked_sub(epoch())?;
+    if timestamp >> 41 != 0 {
+        return None;
+    }
+    Some(((timestamp << 22) | (raw & 0x3F_FFFF)) as i64)
+}
+
+/// Split a snowflake into its timestamp, worker id, and sequence.
+pub fn decompose(id: i64) -> SnowflakeParts {
+    let raw = id as u64;
+    SnowflakeParts {
+        timestamp_millis: timestamp_millis(id),
+        worker_id: ((raw >> 12) & MAX_WORKER_ID as u64) as u16,
+        sequence: (raw & 0xFFF) as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompose_round_trips_generated_ids() {
+        let before = current_timestamp().unwrap() + PARACORD_EPOCH;
+        let id = generate(513);
+        let parts = decompose(id);
+        assert_eq!(parts.worker_id, 513);
+        assert!(parts.timestamp_millis >= before);
+        assert!(parts.sequence <= 0xFFF);
+    }
+
+    #[test]
+    fn ids_are_unique_and_increasing() {
+        let ids: Vec<i64> = (0..10_000).map(|_| generate(7)).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn worker_id_must_fit_ten_bits() {
+        assert_eq!(
+            set_worker_id(1024),
+            Err(SnowflakeError::InvalidWorkerId(1024))
+        );
+        assert!(set_worker_id(MAX_WORKER_ID as u64).is_ok());
+        assert_eq!(worker_id(), MAX_WORKER_ID);
+        set_worker_id(1).unwrap();
+    }
+
+    #[test]
+    fn created_at_and_min_id_at_agree() {
+        let id = generate(2);
+        let at = created_at(id);
+        assert!(min_id_at(at) <= id);
+        assert!(min_id_at(at + chrono::Duration::milliseconds(1)) > id);
+        assert_eq!(
+            SnowflakeFormat::parse("Discord"),
+            Some(SnowflakeFormat::Discord)
+        );
+        assert_eq!(SnowflakeFormat::parse("twitter"), None);
+    }
+
+    #[test]
+    fn rebase_keeps_discord_timestamps() {
+        // Discord's documented example: 2016-04-30 11:18:25.796 UTC.
+        let discord_id: i64 = 175_928_847_299_117_063;
+        let rebased = rebase(discord_id, DISCORD_EPOCH);
+        // Older than the Paracord epoch, so it can't be represented.
+        assert_eq!(rebased, None);
+
+        let recent = ((1_735_689_600_000 - DISCORD_EPOCH) << 22 | 0x1234) as i64;
+        let rebased = rebase(recent, DISCORD_EPOCH).unwrap();
+        assert_eq!(timestamp_millis(rebased), 1_735_689_600_000);
+        assert_eq!(rebased & 0x3F_FFFF, 0x1234);
+        assert!(set_epoch(unix_millis().unwrap() + 60_000).is_err());
+    }
+
+    #[test]
+    fn backwards_jumps_fail_without_waiting() {
+        let mut state = SnowflakeState {
+            last_timestamp: current_timestamp().unwrap() + 60_000,
+            sequence: 0,
+        };
+        assert!(matches!(
+            issue(&mut state, 3),
+            Err(SnowflakeError::ClockMovedBackwards(behind)) if behind >= 59_000
+        ));
+
+        let started = std::time::Instant::now();
+        state.last_timestamp = current_timestamp().unwrap() + 500;
+        assert!(matches!(
+            issue(&mut state, 3),
+            Err(SnowflakeError::ClockMovedBackwards(behind)) if behind <= 500
+        ));
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn try_next_id_issues_ids() {
+        let first = try_next_id().await.unwrap();
+        let second = try_next_id().await.unwrap();
+        assert!(first < second);
+    }
 }
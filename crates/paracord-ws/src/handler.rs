@@ -6,19 +6,21 @@ use paracord_core::{observability, AppState};
 use paracord_models::gateway::*;
 use paracord_models::permissions::Permissions;
 use serde_json::{json, Value};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashSet;
 use std::num::NonZeroU32;
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::{Arc, OnceLock};
-use tokio::sync::Semaphore;
+use tokio::sync::{broadcast, Semaphore};
 use tokio::time::{Duration, Instant};
 
 use crate::compression::WsCompressor;
-use crate::session::Session;
+use paracord_core::events::ServerEvent;
+use paracord_core::gateway_budget;
+use paracord_core::gateway_session::{self, GatewaySession as Session};
+use paracord_core::presence_manager::{ClientType, SessionPresence};
 
 const HEARTBEAT_INTERVAL_MS: u64 = 41250;
 const HEARTBEAT_TIMEOUT_MS: u64 = 90000;
-const SESSION_TTL_SECONDS: i64 = 3600;
 const HEARTBEAT_ACK_MSG: &str = r#"{"op":11}"#;
 const HELLO_MSG_PREFIX: &str = r#"{"op":10,"d":{"heartbeat_interval":"#;
 const HELLO_MSG_SUFFIX: &str = r#"}}"#;
@@ -29,65 +31,14 @@ const WS_MAX_MESSAGES_PER_MINUTE_DEFAULT: u32 = 240;
 const WS_MAX_PRESENCE_UPDATES_PER_MINUTE_DEFAULT: u32 = 60;
 const WS_MAX_TYPING_EVENTS_PER_MINUTE_DEFAULT: u32 = 120;
 const WS_MAX_VOICE_UPDATES_PER_MINUTE_DEFAULT: u32 = 60;
+const DEVICE_APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+const DEVICE_APPROVAL_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CLOSE_DEVICE_APPROVAL_DENIED: u16 = 4010;
+const CLOSE_DEVICE_APPROVAL_TIMEOUT: u16 = 4011;
 
-#[derive(Clone)]
-#[allow(dead_code)]
-struct CachedSession {
-    user_id: i64,
-    guild_ids: Vec<i64>,
-    guild_owner_ids: HashMap<i64, i64>,
-    sequence: u64,
-    updated_at: i64,
-}
-
-static SESSION_CACHE: OnceLock<moka::future::Cache<String, CachedSession>> = OnceLock::new();
 static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
 static USER_CONNECTIONS: OnceLock<dashmap::DashMap<i64, usize>> = OnceLock::new();
 
-struct BufferedEvent {
-    sequence: u64,
-    event_type: String,
-    payload: Arc<Value>,
-    timestamp: Instant,
-}
-
-static EVENT_BUFFERS: OnceLock<dashmap::DashMap<String, VecDeque<BufferedEvent>>> = OnceLock::new();
-
-fn event_buffers() -> &'static dashmap::DashMap<String, VecDeque<BufferedEvent>> {
-    EVENT_BUFFERS.get_or_init(|| {
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(300));
-            interval.tick().await; // skip immediate first tick
-            loop {
-                interval.tick().await;
-                if let Some(buffers) = EVENT_BUFFERS.get() {
-                    let keys: Vec<String> = buffers.iter().map(|r| r.key().clone()).collect();
-                    for key in keys {
-                        buffers.remove_if(&key, |_, buffer| {
-                            buffer
-                                .back()
-                                .is_none_or(|e| e.timestamp.elapsed() > MAX_REPLAY_AGE)
-                        });
-                    }
-                }
-            }
-        });
-        dashmap::DashMap::new()
-    })
-}
-
-const MAX_REPLAY_EVENTS: usize = 100;
-const MAX_REPLAY_AGE: Duration = Duration::from_secs(300); // 5 minutes
-
-fn session_cache() -> &'static moka::future::Cache<String, CachedSession> {
-    SESSION_CACHE.get_or_init(|| {
-        moka::future::Cache::builder()
-            .max_capacity(ws_limits().session_cache_max_entries as u64)
-            .time_to_live(std::time::Duration::from_secs(SESSION_TTL_SECONDS as u64))
-            .build()
-    })
-}
-
 fn user_connections() -> &'static dashmap::DashMap<i64, usize> {
     USER_CONNECTIONS.get_or_init(dashmap::DashMap::new)
 }
@@ -507,104 +458,29 @@ fn extract_activities(raw: Option<&Value>) -> Vec<Value> {
     activities
 }
 
-fn build_presence_payload(
-    user_id: i64,
-    status: Option<&str>,
-    activities: Option<&Value>,
-    custom_status: Option<&str>,
-) -> Value {
-    json!({
-        "user_id": user_id.to_string(),
-        "status": normalize_status(status),
-        "custom_status": custom_status.map(|v| truncate_for_presence(v, MAX_ACTIVITY_TEXT_LEN)),
-        "activities": extract_activities(activities),
-    })
-}
-
-fn default_presence_payload(user_id: i64, status: &str) -> Value {
-    json!({
-        "user_id": user_id.to_string(),
-        "status": normalize_status(Some(status)),
-        "custom_status": Value::Null,
-        "activities": [],
-    })
-}
-
 async fn collect_presence_recipient_ids(
     state: &AppState,
     user_id: i64,
     guild_ids: &[i64],
 ) -> Vec<i64> {
-    // In-memory lookup: zero DB queries for guild members
-    let mut recipients = state
-        .member_index
-        .get_presence_recipients(user_id, guild_ids);
-    recipients.insert(user_id);
-
-    // Friends still need a DB query (not tracked in the member index)
-    if let Ok(friend_ids) =
-        paracord_db::relationships::get_friend_user_ids(&state.db, user_id).await
-    {
-        recipients.extend(friend_ids);
-    }
-
-    recipients.into_iter().collect()
+    // Guild members come from the in-memory index; privacy settings narrow
+    // the audience to friends, visible guilds, or nobody.
+    paracord_core::presence_manager::presence_recipients(state, user_id, guild_ids).await
 }
 
-fn extract_channel_id_from_event(event_type: &str, payload: &Value) -> Option<i64> {
-    if let Some(raw) = payload.get("channel_id").and_then(|v| v.as_str()) {
-        if let Ok(channel_id) = raw.parse::<i64>() {
-            return Some(channel_id);
-        }
-    }
-
-    if matches!(
-        event_type,
-        "CHANNEL_CREATE"
-            | "CHANNEL_UPDATE"
-            | "CHANNEL_DELETE"
-            | "THREAD_CREATE"
-            | "THREAD_UPDATE"
-            | "THREAD_DELETE"
-    ) {
-        return payload
-            .get("id")
-            .and_then(|v| v.as_str())
-            .and_then(|raw| raw.parse::<i64>().ok());
-    }
-
-    None
-}
-
-async fn can_receive_guild_event(_state: &AppState, session: &mut Session, guild_id: i64) -> bool {
-    session.guild_ids.contains(&guild_id)
-}
-
-async fn can_receive_channel_event(
-    state: &AppState,
-    session: &Session,
-    guild_id: i64,
-    channel_id: i64,
-) -> bool {
-    let owner_id = match session.guild_owner_ids.get(&guild_id) {
-        Some(&id) => id,
-        None => return false,
-    };
-
-    let Ok(perms) = paracord_core::permissions::compute_channel_permissions_cached(
-        &state.permission_cache,
-        &state.db,
-        guild_id,
-        channel_id,
-        owner_id,
-        session.user_id,
-    )
-    .await
+/// Recompute `user_id`'s presence from their sessions and, when it changed,
+/// publish it to the users allowed to see it and to member lists.
+async fn publish_presence(state: &AppState, user_id: i64, guild_ids: &[i64], was_online: bool) {
+    let Some(presence) = paracord_core::presence_manager::refresh_presence(state, user_id).await
     else {
-        return false;
+        return;
     };
-
-    perms.contains(Permissions::VIEW_CHANNEL)
+    paracord_core::member_list::presence_changed(state, user_id, guild_ids.to_vec(), was_online)
+        .await;
+    let presence_recipient_ids = collect_presence_recipient_ids(state, user_id, guild_ids).await;
+    state
+        .event_bus
+        .dispatch_to_users(EVENT_PRESENCE_UPDATE, presence, presence_recipient_ids);
 }
 
 pub async fn handle_connection(socket: WebSocket, state: AppState, compress: bool) {
@@ -655,30 +531,39 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
     }
 
     // Wait for IDENTIFY (timeout 30s)
-    let identify_timeout = Duration::from_secs(30);
-    let (session, resumed, requested_seq) = match tokio::time::timeout(
-        identify_timeout,
-        wait_for_identify_or_resume(&mut receiver, &state),
-    )
-    .await
-    {
-        Ok(Some(result)) => result,
-        _ => {
-            let _ = send_ws_text_logged(
-                &mut sender,
-                json!({"op": OP_INVALID_SESSION, "d": false}).to_string(),
-                &compressor,
-                None,
-                None,
-                "invalid_session",
-                Some(OP_INVALID_SESSION),
-                None,
-                None,
-            )
-            .await;
+    let identify_deadline = Instant::now() + Duration::from_secs(30);
+    let (session, mut resumed_events, requested_seq) = loop {
+        let handshake = tokio::time::timeout_at(
+            identify_deadline,
+            wait_for_identify_or_resume(&mut receiver, &state),
+        )
+        .await;
+        let rejected_resume = match handshake {
+            Ok(Some(Handshake::Identify(session))) => break (session, None, 0),
+            Ok(Some(Handshake::Resume(session, events, seq))) => {
+                break (session, Some(events), seq)
+            }
+            Ok(Some(Handshake::ResumeRejected)) => true,
+            _ => false,
+        };
+        // A rejected RESUME leaves the connection open for a fresh IDENTIFY.
+        let sent = send_ws_text_logged(
+            &mut sender,
+            json!({"op": OP_INVALID_SESSION, "d": false}).to_string(),
+            &compressor,
+            None,
+            None,
+            "invalid_session",
+            Some(OP_INVALID_SESSION),
+            None,
+            None,
+        )
+        .await;
+        if !rejected_resume || sent.is_err() {
             return;
         }
     };
+    let resumed = resumed_events.is_some();
 
     if !try_acquire_user_connection_slot(session.user_id) {
         let _ = send_ws_close_logged(
@@ -690,11 +575,20 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
             "user_capacity_close",
         )
         .await;
+        if let Some(events) = resumed_events {
+            gateway_session::detach(&state, session, events);
+        }
         return;
     }
     connection_guard.user_id = Some(session.user_id);
 
-    if resumed {
+    if !resumed
+        && !await_device_approval(&mut sender, &mut receiver, &session, &state, &compressor).await
+    {
+        return;
+    }
+
+    if let Some(events) = resumed_events {
         // Send RESUMED first so the client knows the session was accepted
         let resumed_payload = json!({
             "op": OP_DISPATCH,
@@ -716,45 +610,34 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
         .await
         .is_err()
         {
+            gateway_session::detach(&state, session, events);
             return;
         }
 
-        // Replay missed events (collect into Vec first to avoid holding DashMap lock across .await)
-        let events_to_replay: Vec<(u64, String, Arc<Value>)> = event_buffers()
-            .get(&session.session_id)
-            .map(|buffer| {
-                buffer
-                    .iter()
-                    .filter(|e| e.sequence > requested_seq)
-                    .map(|e| (e.sequence, e.event_type.clone(), e.payload.clone()))
-                    .collect()
-            })
-            .unwrap_or_default();
+        let events_to_replay =
+            gateway_session::replay_since(&session.session_id, session.user_id, requested_seq)
+                .map(|(_, events)| events)
+                .unwrap_or_default();
 
         let mut replay_count: u64 = 0;
-        for (seq, event_type, payload) in &events_to_replay {
-            let gateway_msg = json!({
-                "op": OP_DISPATCH,
-                "t": event_type,
-                "s": seq,
-                "d": **payload
-            });
+        for dispatch in &events_to_replay {
             if send_ws_text_logged(
                 &mut sender,
-                gateway_msg.to_string(),
+                dispatch.ws_frame(),
                 &compressor,
                 Some(session.user_id),
                 Some(session.session_id.as_str()),
                 "replay",
                 Some(OP_DISPATCH),
-                Some(event_type.as_str()),
-                Some(*seq),
+                Some(dispatch.event_type.as_str()),
+                Some(dispatch.sequence),
             )
             .await
             .is_ok()
             {
                 replay_count += 1;
             } else {
+                gateway_session::detach(&state, session, events);
                 return;
             }
         }
@@ -763,6 +646,7 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
             replayed_events = replay_count,
             "session resumed with event replay"
         );
+        resumed_events = Some(events);
     } else {
         // Fresh IDENTIFY (not a resume) — the client just loaded, so any
         // voice state in the DB from a prior session is stale.  Clean it
@@ -811,6 +695,14 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
         // Snapshot of currently online users for building presence lists
         let online_snapshot = state.online_users.read().await.clone();
         let presence_snapshot = state.user_presences.read().await.clone();
+        let viewer_id = session.user_id;
+        let viewer_friend_ids: Arc<HashSet<i64>> = Arc::new(
+            paracord_db::relationships::get_friend_user_ids(&state.db, viewer_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        );
 
         // Fetch guild data for READY with bounded concurrency.
         let sem = Arc::new(Semaphore::new(10));
@@ -822,6 +714,7 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
                 let sem = sem.clone();
                 let online_snapshot = online_snapshot.clone();
                 let presence_snapshot = presence_snapshot.clone();
+                let viewer_friend_ids = viewer_friend_ids.clone();
                 async move {
                     let _permit = sem.acquire_owned().await.ok()?;
                     let guild = paracord_db::guilds::get_guild(&state.db, gid)
@@ -865,6 +758,14 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
                     let presences_json: Vec<Value> = member_ids
                         .iter()
                         .filter(|uid| online_snapshot.contains(uid))
+                        .filter(|uid| {
+                            **uid == viewer_id
+                                || state
+                                    .presence_manager
+                                    .cached_privacy(**uid)
+                                    .unwrap_or_default()
+                                    .visible_to(viewer_friend_ids.contains(uid), &[gid])
+                        })
                         .map(|uid| {
                             presence_snapshot.get(uid).cloned().unwrap_or_else(|| {
                                 json!({
@@ -893,7 +794,9 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
             .collect();
 
         let guild_results = futures_util::future::join_all(guild_futures).await;
-        let guilds_json: Vec<Value> = guild_results.into_iter().flatten().collect();
+        let mut guilds_json: Vec<Value> = guild_results.into_iter().flatten().collect();
+        let truncated =
+            gateway_budget::fit_guilds(&mut guilds_json, gateway_budget::MAX_READY_BYTES);
 
         let ready = json!({
             "op": OP_DISPATCH,
@@ -904,10 +807,12 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
                 "guilds": guilds_json,
                 "session_id": &session.session_id,
             }
-        });
+        })
+        .to_string();
+        observability::ws_payload_sent(EVENT_READY, ready.len(), truncated);
         if send_ws_text_logged(
             &mut sender,
-            ready.to_string(),
+            ready,
             &compressor,
             Some(session.user_id),
             Some(session.session_id.as_str()),
@@ -926,45 +831,36 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
     // Save user_id before session is moved into run_session
     let session_user_id = session.user_id;
 
-    // Track this user as online
-    state.presence_manager.cancel_offline(session_user_id);
+    // Track this session's presence. A resumed session keeps what it last
+    // reported; a new one starts online with the user's custom status.
+    let session_id = session.session_id.clone();
+    state.presence_manager.cancel_offline(&session_id);
+    let was_online = paracord_core::member_list::is_online(&state, session_user_id).await;
     state.online_users.write().await.insert(session_user_id);
-    let online_presence = {
-        let existing = state
-            .user_presences
-            .read()
-            .await
-            .get(&session_user_id)
-            .cloned();
-        if let Some(mut value) = existing {
-            if let Some(obj) = value.as_object_mut() {
-                obj.insert("user_id".to_string(), json!(session_user_id.to_string()));
-                obj.insert("status".to_string(), json!("online"));
-                if !obj.contains_key("activities") {
-                    obj.insert("activities".to_string(), json!([]));
-                }
-            }
-            value
-        } else {
-            default_presence_payload(session_user_id, "online")
-        }
-    };
+    let session_presence = state
+        .presence_manager
+        .session_presence(session_user_id, &session_id)
+        .unwrap_or_else(|| SessionPresence {
+            status: "online".to_string(),
+            client: session.client_type,
+            custom_status: state.presence_manager.custom_status(session_user_id),
+            activities: Vec::new(),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        });
     state
-        .user_presences
-        .write()
-        .await
-        .insert(session_user_id, online_presence.clone());
-
-    // Publish presence only to users who share a guild or friendship edge.
-    let presence_recipient_ids =
-        collect_presence_recipient_ids(&state, session_user_id, &session.guild_ids).await;
-    state.event_bus.dispatch_to_users(
-        EVENT_PRESENCE_UPDATE,
-        online_presence,
-        presence_recipient_ids,
-    );
-
-    let session = run_session(sender, receiver, session, state.clone(), &compressor).await;
+        .presence_manager
+        .set_session_presence(session_user_id, &session_id, session_presence);
+    publish_presence(&state, session_user_id, &session.guild_ids, was_online).await;
+
+    let guild_ids = run_session(
+        sender,
+        receiver,
+        session,
+        resumed_events,
+        state.clone(),
+        &compressor,
+    )
+    .await;
 
     // Voice cleanup: when the gateway WebSocket drops, don't remove voice
     // state immediately — the user may still be connected to LiveKit (their
@@ -1054,63 +950,44 @@ pub async fn handle_connection(socket: WebSocket, state: AppState, compress: boo
         }
     }
 
-    // Only mark offline when this was the user's last active gateway connection.
-    // `USER_CONNECTIONS` still includes this connection until `connection_guard` drops,
-    // so `<= 1` means no other live session remains.
-    let should_mark_offline = {
-        user_connections()
-            .get(&session_user_id)
-            .map(|c| *c)
-            .unwrap_or(0)
-            <= 1
-    };
-
-    if should_mark_offline {
-        // Defer the offline transition through PresenceManager to avoid race
-        // conditions where a reconnecting client briefly appears offline.
-        let state_clone = state.clone();
-        let guild_ids = session.guild_ids.clone();
-        state
-            .presence_manager
-            .schedule_offline(session_user_id, async move {
-                // Re-check connection count after the grace period — the user may
-                // have reconnected during the delay.
-                let still_offline = user_connections()
-                    .get(&session_user_id)
-                    .map(|c| *c)
-                    .unwrap_or(0)
-                    == 0;
-                if !still_offline {
-                    return;
-                }
-
+    // Drop this session's presence after a grace period through
+    // PresenceManager, so a client that reconnects or resumes doesn't
+    // briefly appear offline. The user goes offline with their last session.
+    let state_clone = state.clone();
+    let removed_session_id = session_id.clone();
+    state
+        .presence_manager
+        .schedule_offline(&session_id, async move {
+            let was_online =
+                paracord_core::member_list::is_online(&state_clone, session_user_id).await;
+            state_clone
+                .presence_manager
+                .remove_session(session_user_id, &removed_session_id);
+            if !state_clone.presence_manager.has_sessions(session_user_id) {
                 state_clone
                     .online_users
                     .write()
                     .await
                     .remove(&session_user_id);
-                let offline_presence = default_presence_payload(session_user_id, "offline");
-                state_clone
-                    .user_presences
-                    .write()
-                    .await
-                    .insert(session_user_id, offline_presence.clone());
-
-                let offline_presence_recipient_ids =
-                    collect_presence_recipient_ids(&state_clone, session_user_id, &guild_ids).await;
-                state_clone.event_bus.dispatch_to_users(
-                    EVENT_PRESENCE_UPDATE,
-                    offline_presence,
-                    offline_presence_recipient_ids,
-                );
-            });
-    }
+            }
+            publish_presence(&state_clone, session_user_id, &guild_ids, was_online).await;
+        });
+}
+
+enum Handshake {
+    Identify(Session),
+    /// A detached session taken over by RESUME, its bus subscription and
+    /// the last sequence the client processed.
+    Resume(Session, broadcast::Receiver<ServerEvent>, u64),
+    /// The session is gone or its buffer no longer reaches back to the
+    /// client's sequence; the client has to IDENTIFY again.
+    ResumeRejected,
 }
 
 async fn wait_for_identify_or_resume(
     receiver: &mut (impl StreamExt<Item = Result<Message, axum::Error>> + Unpin),
     state: &AppState,
-) -> Option<(Session, bool, u64)> {
+) -> Option<Handshake> {
     while let Some(Ok(msg)) = receiver.next().await {
         if let Message::Text(text) = msg {
             if let Ok(payload) = serde_json::from_str::<Value>(&text) {
@@ -1144,80 +1021,39 @@ async fn wait_for_identify_or_resume(
                         }
                         let op = payload.get("op").and_then(|v| v.as_u64())?;
                         if op == OP_IDENTIFY as u64 {
-                            let guilds =
-                                paracord_db::guilds::get_user_guilds(&state.db, claims.sub)
-                                    .await
-                                    .unwrap_or_default();
-                            let guild_ids = guilds.iter().map(|g| g.id).collect();
-                            let guild_owner_ids =
-                                guilds.iter().map(|g| (g.id, g.owner_id)).collect();
-                            return Some((
-                                Session::new(claims.sub, guild_ids, guild_owner_ids),
-                                false,
-                                0,
-                            ));
+                            let mut session = Session::for_user(state, claims.sub).await;
+                            session.auth_session_id = Some(session_id.to_string());
+                            session.client_type = d
+                                .get("properties")
+                                .and_then(|v| v.get("client"))
+                                .and_then(|v| v.as_str())
+                                .and_then(ClientType::parse)
+                                .unwrap_or_default();
+                            return Some(Handshake::Identify(session));
                         }
                         if op == OP_RESUME as u64 {
                             let requested_session_id =
-                                d.get("session_id").and_then(|v| v.as_str())?.to_string();
+                                d.get("session_id").and_then(|v| v.as_str())?;
                             let requested_seq = d.get("seq").and_then(|v| v.as_u64()).unwrap_or(0);
-                            if let Some(cached) = session_cache().get(&requested_session_id).await {
-                                if cached.user_id == claims.sub {
-                                    let mut can_replay = true;
-                                    if cached.sequence > requested_seq {
-                                        if let Some(buffer) =
-                                            event_buffers().get(&requested_session_id)
-                                        {
-                                            if let Some(front) = buffer.front() {
-                                                if front.sequence > requested_seq.saturating_add(1)
-                                                {
-                                                    can_replay = false;
-                                                }
-                                            } else {
-                                                can_replay = false;
-                                            }
-                                        } else {
-                                            can_replay = false;
-                                        }
-                                    }
-
-                                    if can_replay {
-                                        let mut resumed = Session::new(
-                                            cached.user_id,
-                                            cached.guild_ids.clone(),
-                                            cached.guild_owner_ids.clone(),
-                                        );
-                                        resumed.session_id = requested_session_id;
-                                        resumed.sequence = cached.sequence.max(requested_seq);
-                                        return Some((resumed, true, requested_seq));
-                                    } else {
-                                        let oldest_buffered = event_buffers()
-                                            .get(&requested_session_id)
-                                            .and_then(|b| b.front().map(|e| e.sequence));
-                                        tracing::info!(
-                                            session_id = %requested_session_id,
-                                            client_seq = requested_seq,
-                                            oldest_buffered = oldest_buffered,
-                                            "replay gap too large, forcing re-identify"
-                                        );
-                                    }
-                                }
+                            let Some((mut resumed, events)) =
+                                gateway_session::reattach(requested_session_id, claims.sub).await
+                            else {
+                                return Some(Handshake::ResumeRejected);
+                            };
+                            let rolled_over = resumed.sequence > requested_seq
+                                && gateway_session::replay_since(
+                                    &resumed.session_id,
+                                    resumed.user_id,
+                                    requested_seq,
+                                )
+                                .is_none();
+                            if rolled_over {
+                                state.event_bus.unregister_session(&resumed.session_id);
+                                gateway_session::invalidate_replay(&resumed.session_id);
+                                return Some(Handshake::ResumeRejected);
                             }
-                            // If resume can't be honored (cache miss/mismatch), fall back to a
-                            // fresh session immediately so clients recover without an extra
-                            // invalid-session reconnect cycle.
-                            let guilds =
-                                paracord_db::guilds::get_user_guilds(&state.db, claims.sub)
-                                    .await
-                                    .unwrap_or_default();
-                            let guild_ids = guilds.iter().map(|g| g.id).collect();
-                            let guild_owner_ids =
-                                guilds.iter().map(|g| (g.id, g.owner_id)).collect();
-                            return Some((
-                                Session::new(claims.sub, guild_ids, guild_owner_ids),
-                                false,
-                                0,
-                            ));
+                            resumed.auth_session_id = Some(session_id.to_string());
+                            return Some(Handshake::Resume(resumed, events, requested_seq));
                         }
                     }
                 }
@@ -1227,18 +1063,238 @@ async fn wait_for_identify_or_resume(
     None
 }
 
+/// Hold a fresh IDENTIFY from an unrecognized device until one of the user's
+/// trusted sessions approves it, when the account requires device approval.
+/// Returns `false` when the connection should be dropped.
+async fn await_device_approval(
+    sender: &mut (impl SinkExt<Message> + Unpin),
+    receiver: &mut (impl StreamExt<Item = Result<Message, axum::Error>> + Unpin),
+    session: &Session,
+    state: &AppState,
+    compressor: &WsCompressor,
+) -> bool {
+    let Some(auth_session_id) = session.auth_session_id.as_deref() else {
+        return true;
+    };
+    let required = paracord_db::users::get_user_settings(&state.db, session.user_id)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|settings| settings.device_approval_required);
+    if !required {
+        return true;
+    }
+    let is_bot = paracord_db::users::get_user_by_id(&state.db, session.user_id)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|user| paracord_core::is_bot(user.flags));
+    if is_bot {
+        return true;
+    }
+    let Ok(Some(auth_session)) =
+        paracord_db::sessions::get_session_by_id(&state.db, auth_session_id).await
+    else {
+        return false;
+    };
+    let now = chrono::Utc::now();
+    if auth_session.device_approved_at.is_some() {
+        if let Some(device_id) = auth_session.device_id.as_deref() {
+            let _ = paracord_db::sessions::remember_approved_device(
+                &state.db,
+                session.user_id,
+                device_id,
+                now,
+            )
+            .await;
+        }
+        return true;
+    }
+    // A device approved once is recognized on later logins.
+    if let Some(device_id) = auth_session.device_id.as_deref() {
+        let known =
+            paracord_db::sessions::is_device_approved(&state.db, session.user_id, device_id)
+                .await
+                .unwrap_or(false);
+        if known {
+            return paracord_db::sessions::approve_session_device(
+                &state.db,
+                auth_session_id,
+                session.user_id,
+                now,
+            )
+            .await
+            .is_ok();
+        }
+    }
+
+    // With no trusted session online the user recovers with their recovery
+    // code over REST instead; nothing here approves the device implicitly.
+    let trusted_sessions = paracord_db::sessions::count_approved_device_sessions(
+        &state.db,
+        session.user_id,
+        auth_session_id,
+        now,
+    )
+    .await
+    .unwrap_or(0);
+
+    // Subscribe before prompting so a quick approval isn't missed.
+    let mut system_rx = state.event_bus.subscribe_system();
+    let _ = paracord_db::sessions::mark_device_approval_requested(&state.db, auth_session_id, now)
+        .await;
+    state.event_bus.dispatch_to_users(
+        EVENT_DEVICE_APPROVAL_REQUEST,
+        json!({
+            "session_id": auth_session_id,
+            "device_id": auth_session
+                .device_id
+                .as_deref()
+                .map(paracord_db::sessions::device_fingerprint),
+            "user_agent": auth_session.user_agent,
+            "ip_address": auth_session.ip_address,
+            "requested_at": now.to_rfc3339(),
+        }),
+        vec![session.user_id],
+    );
+    let pending = json!({
+        "op": OP_DISPATCH,
+        "t": EVENT_DEVICE_APPROVAL_PENDING,
+        "s": null,
+        "d": {
+            "session_id": auth_session_id,
+            "expires_in_seconds": DEVICE_APPROVAL_TIMEOUT.as_secs(),
+            "trusted_sessions": trusted_sessions,
+        }
+    });
+    if send_ws_text_logged(
+        sender,
+        pending.to_string(),
+        compressor,
+        Some(session.user_id),
+        Some(session.session_id.as_str()),
+        "device_approval_pending",
+        Some(OP_DISPATCH),
+        Some(EVENT_DEVICE_APPROVAL_PENDING),
+        None,
+    )
+    .await
+    .is_err()
+    {
+        return false;
+    }
+
+    let deadline = tokio::time::sleep(DEVICE_APPROVAL_TIMEOUT);
+    tokio::pin!(deadline);
+    let mut recheck = tokio::time::interval(DEVICE_APPROVAL_RECHECK_INTERVAL);
+    recheck.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let approved = loop {
+        tokio::select! {
+            _ = &mut deadline => {
+                let _ = send_ws_close_logged(
+                    sender,
+                    CLOSE_DEVICE_APPROVAL_TIMEOUT,
+                    "Device approval timed out",
+                    Some(session.user_id),
+                    Some(session.session_id.as_str()),
+                    "device_approval_timeout",
+                )
+                .await;
+                return false;
+            }
+            event = system_rx.recv() => {
+                match event {
+                    Ok(event) if event.event_type == EVENT_DEVICE_APPROVAL_UPDATE => {
+                        let matches = event
+                            .payload
+                            .get("session_id")
+                            .and_then(|v| v.as_str())
+                            == Some(auth_session_id);
+                        if matches {
+                            break event
+                                .payload
+                                .get("approved")
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false);
+                        }
+                    }
+                    Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return false,
+                }
+            }
+            // Approvals made on another node only show up in the database.
+            _ = recheck.tick() => {
+                match paracord_db::sessions::get_session_by_id(&state.db, auth_session_id).await {
+                    Ok(Some(row)) if row.revoked_at.is_some() => break false,
+                    Ok(Some(row)) if row.device_approved_at.is_some() => break true,
+                    Ok(Some(_)) => {}
+                    Ok(None) => break false,
+                    Err(_) => {}
+                }
+            }
+            msg = receiver.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        let op = serde_json::from_str::<Value>(&text)
+                            .ok()
+                            .and_then(|payload| payload.get("op").and_then(|v| v.as_u64()));
+                        if op == Some(OP_HEARTBEAT as u64)
+                            && send_ws_text_logged(
+                                sender,
+                                HEARTBEAT_ACK_MSG.to_string(),
+                                compressor,
+                                Some(session.user_id),
+                                Some(session.session_id.as_str()),
+                                "heartbeat_ack",
+                                Some(OP_HEARTBEAT_ACK),
+                                None,
+                                None,
+                            )
+                            .await
+                            .is_err()
+                        {
+                            return false;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return false,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    };
+
+    if !approved {
+        let _ = send_ws_close_logged(
+            sender,
+            CLOSE_DEVICE_APPROVAL_DENIED,
+            "Device approval denied",
+            Some(session.user_id),
+            Some(session.session_id.as_str()),
+            "device_approval_denied",
+        )
+        .await;
+    }
+    approved
+}
+
+/// Serve the session until the connection ends, then detach it for resume.
+/// Returns the guilds the session ended up in.
 async fn run_session(
     mut sender: impl SinkExt<Message> + Unpin,
     mut receiver: impl StreamExt<Item = Result<Message, axum::Error>> + Unpin,
     mut session: Session,
+    resumed_events: Option<broadcast::Receiver<ServerEvent>>,
     state: AppState,
     compressor: &WsCompressor,
-) -> Session {
-    let mut event_rx = state.event_bus.register_session(
-        session.session_id.clone(),
-        session.user_id,
-        &session.guild_ids,
-    );
+) -> Vec<i64> {
+    let mut event_rx = resumed_events.unwrap_or_else(|| {
+        state.event_bus.register_session(
+            session.session_id.clone(),
+            session.user_id,
+            &session.guild_ids,
+        )
+    });
+    session.load_filters(&state).await;
     let heartbeat_timeout = Duration::from_millis(HEARTBEAT_TIMEOUT_MS);
     let rate_limits = user_rate_limits();
     let mut ws_ping_interval = tokio::time::interval(Duration::from_secs(20));
@@ -1246,6 +1302,7 @@ async fn run_session(
     let heartbeat_sleep = tokio::time::sleep(heartbeat_timeout);
     tokio::pin!(heartbeat_sleep);
 
+    let mut resumable = true;
     let (disconnect_reason, heartbeat_timed_out) = loop {
         tokio::select! {
             msg = receiver.next() => {
@@ -1336,117 +1393,21 @@ async fn run_session(
             event = event_rx.recv() => {
                 match event {
                     Ok(event) => {
-                        if !session.should_receive_event(event.guild_id, event.target_user_ids.as_deref()) {
+                        let Some(dispatch) =
+                            gateway_session::prepare_dispatch(&state, &mut session, &event).await
+                        else {
                             continue;
-                        }
-
-                        if let Some(guild_id) = event.guild_id {
-                            if !can_receive_guild_event(&state, &mut session, guild_id).await {
-                                continue;
-                            }
-                            if let Some(channel_id) =
-                                extract_channel_id_from_event(&event.event_type, &event.payload)
-                            {
-                                if !can_receive_channel_event(&state, &session, guild_id, channel_id).await {
-                                    continue;
-                                }
-                            }
-                        }
-
-                        // Dynamically update guild scope for this active session.
-                        if event.event_type == "GUILD_MEMBER_ADD" {
-                            if let Some(uid) = event.payload.get("user_id").and_then(|v| v.as_str()) {
-                                if uid == session.user_id.to_string() {
-                                    if let Some(gid) = event.payload.get("guild_id")
-                                        .and_then(|v| v.as_str())
-                                        .and_then(|s| s.parse::<i64>().ok())
-                                    {
-                                        if let Some(guild) = paracord_db::guilds::get_guild(&state.db, gid)
-                                            .await
-                                            .ok()
-                                            .flatten()
-                                        {
-                                            session.add_guild(gid, guild.owner_id);
-                                            state.event_bus.add_session_guild(&session.session_id, gid);
-                                        }
-                                    }
-                                }
-                            }
-                        } else if event.event_type == "GUILD_MEMBER_REMOVE" || event.event_type == "GUILD_BAN_ADD" {
-                            if let Some(uid) = event.payload.get("user_id").and_then(|v| v.as_str()) {
-                                if uid == session.user_id.to_string() {
-                                    if let Some(gid) = event.payload.get("guild_id")
-                                        .and_then(|v| v.as_str())
-                                        .and_then(|s| s.parse::<i64>().ok())
-                                    {
-                                        session.remove_guild(gid);
-                                        state
-                                            .event_bus
-                                            .remove_session_guild(&session.session_id, gid);
-                                    }
-                                }
-                            }
-                        } else if event.event_type == "GUILD_DELETE" {
-                            if let Some(gid) = event.payload.get("id")
-                                .or_else(|| event.payload.get("guild_id"))
-                                .and_then(|v| v.as_str())
-                                .and_then(|s| s.parse::<i64>().ok())
-                            {
-                                session.remove_guild(gid);
-                                state
-                                    .event_bus
-                                    .remove_session_guild(&session.session_id, gid);
-                            }
-                        } else if event.event_type == "GUILD_UPDATE" {
-                            if let Some(gid) = event.guild_id {
-                                if let Some(new_owner) = event.payload.get("owner_id")
-                                    .and_then(|v| v.as_str())
-                                    .and_then(|s| s.parse::<i64>().ok())
-                                {
-                                    session.guild_owner_ids.insert(gid, new_owner);
-                                }
-                            }
-                        }
-
-                        let seq = session.next_sequence();
-
-                        // Buffer the event for potential replay
-                        let mut buffer_entry = event_buffers().entry(session.session_id.clone()).or_default();
-                        while buffer_entry.front().map(|e| e.timestamp.elapsed() > MAX_REPLAY_AGE).unwrap_or(false) {
-                            buffer_entry.pop_front();
-                        }
-                        if buffer_entry.len() >= MAX_REPLAY_EVENTS {
-                            buffer_entry.pop_front();
-                        }
-                        buffer_entry.push_back(BufferedEvent {
-                            sequence: seq,
-                            event_type: event.event_type.clone(),
-                            payload: event.payload.clone(),
-                            timestamp: Instant::now(),
-                        });
-                        drop(buffer_entry);
-
-                        let dispatch_str = if let Some(ref pre) = event.serialized_payload {
-                            format!(r#"{{"op":0,"t":"{}","s":{},"d":{}}}"#, event.event_type, seq, pre)
-                        } else {
-                            let dispatch = json!({
-                                "op": OP_DISPATCH,
-                                "t": event.event_type,
-                                "s": seq,
-                                "d": *event.payload,
-                            });
-                            dispatch.to_string()
                         };
                         if send_ws_text_logged(
                             &mut sender,
-                            dispatch_str,
+                            dispatch.ws_frame(),
                             compressor,
                             Some(session.user_id),
                             Some(session.session_id.as_str()),
                             "dispatch",
                             Some(OP_DISPATCH),
                             Some(event.event_type.as_str()),
-                            Some(seq),
+                            Some(dispatch.sequence),
                         )
                         .await
                         .is_err()
@@ -1454,6 +1415,13 @@ async fn run_session(
                             break ("websocket send error".to_string(), false);
                         }
                         observability::ws_event_dispatched(&event.event_type);
+                        paracord_core::receipts::note_event_delivered(
+                            &state,
+                            session.user_id,
+                            &event.event_type,
+                            event.guild_id,
+                            &event.payload,
+                        );
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
                         tracing::warn!(
@@ -1461,6 +1429,10 @@ async fn run_session(
                             session.user_id,
                             skipped
                         );
+                        // The skipped events were never buffered, so a resume
+                        // would silently miss them.
+                        gateway_session::invalidate_replay(&session.session_id);
+                        resumable = false;
                         let _ = send_ws_close_logged(
                             &mut sender,
                             1013,
@@ -1503,20 +1475,13 @@ async fn run_session(
             disconnect_reason
         );
     }
-    state.event_bus.unregister_session(&session.session_id);
-    session_cache()
-        .insert(
-            session.session_id.clone(),
-            CachedSession {
-                user_id: session.user_id,
-                guild_ids: session.guild_ids.clone(),
-                guild_owner_ids: session.guild_owner_ids.clone(),
-                sequence: session.sequence,
-                updated_at: chrono::Utc::now().timestamp(),
-            },
-        )
-        .await;
-    session
+    let guild_ids = session.guild_ids.clone();
+    if resumable && gateway_session::detached_count() < ws_limits().session_cache_max_entries {
+        gateway_session::detach(&state, session, event_rx);
+    } else {
+        state.event_bus.unregister_session(&session.session_id);
+    }
+    guild_ids
 }
 
 async fn handle_client_message(
@@ -1530,6 +1495,10 @@ async fn handle_client_message(
 
     match op {
         OP_HEARTBEAT => {
+            // The heartbeat carries the last sequence the client processed.
+            if let Some(sequence) = payload.get("d").and_then(|v| v.as_u64()) {
+                gateway_session::acknowledge(&session.session_id, session.user_id, sequence);
+            }
             let _ = send_ws_text_logged(
                 sender,
                 HEARTBEAT_ACK_MSG.to_string(),
@@ -1545,48 +1514,42 @@ async fn handle_client_message(
         }
         OP_PRESENCE_UPDATE => {
             if let Some(d) = payload.get("d") {
-                let existing_presence = state
-                    .user_presences
-                    .read()
-                    .await
-                    .get(&session.user_id)
-                    .cloned();
-                let status = d.get("status").and_then(|v| v.as_str());
-                let custom_status = d.get("custom_status").and_then(|v| v.as_str()).or_else(|| {
-                    existing_presence
-                        .as_ref()
-                        .and_then(|v| v.get("custom_status"))
-                        .and_then(|v| v.as_str())
-                });
-                let activities = d
-                    .get("activities")
-                    .or_else(|| existing_presence.as_ref().and_then(|v| v.get("activities")));
-                let effective_status = status.or_else(|| {
-                    existing_presence
+                // Fields left out keep this session's previous values; the
+                // custom status is shared by all of the user's sessions.
+                let existing = state
+                    .presence_manager
+                    .session_presence(session.user_id, &session.session_id);
+                let status = d
+                    .get("status")
+                    .and_then(|v| v.as_str())
+                    .or_else(|| existing.as_ref().map(|p| p.status.as_str()));
+                let custom_status = d
+                    .get("custom_status")
+                    .and_then(|v| v.as_str())
+                    .map(|v| truncate_for_presence(v, MAX_ACTIVITY_TEXT_LEN))
+                    .or_else(|| state.presence_manager.custom_status(session.user_id));
+                let activities = match d.get("activities") {
+                    Some(activities) => extract_activities(Some(activities)),
+                    None => existing
                         .as_ref()
-                        .and_then(|v| v.get("status"))
-                        .and_then(|v| v.as_str())
-                });
-                let presence_payload = build_presence_payload(
-                    session.user_id,
-                    effective_status,
-                    activities,
+                        .map(|p| p.activities.clone())
+                        .unwrap_or_default(),
+                };
+                let session_presence = SessionPresence {
+                    status: normalize_status(status).to_string(),
+                    client: session.client_type,
                     custom_status,
+                    activities,
+                    updated_at: chrono::Utc::now().timestamp_millis(),
+                };
+                let was_online =
+                    paracord_core::member_list::is_online(state, session.user_id).await;
+                state.presence_manager.set_session_presence(
+                    session.user_id,
+                    &session.session_id,
+                    session_presence,
                 );
-                state
-                    .user_presences
-                    .write()
-                    .await
-                    .insert(session.user_id, presence_payload.clone());
-
-                let presence_recipient_ids =
-                    collect_presence_recipient_ids(state, session.user_id, &session.guild_ids)
-                        .await;
-                state.event_bus.dispatch_to_users(
-                    EVENT_PRESENCE_UPDATE,
-                    presence_payload,
-                    presence_recipient_ids,
-                );
+                publish_presence(state, session.user_id, &session.guild_ids, was_online).await;
             }
         }
         OP_TYPING_START => {
@@ -1665,6 +1628,54 @@ async fn handle_client_message(
                 }
             }
         }
+        OP_READ_STATE_ACK => {
+            let batch = payload.get("d").cloned().and_then(|d| {
+                serde_json::from_value::<paracord_core::read_state::ReadAckBatch>(d).ok()
+            });
+            let Some(batch) = batch else {
+                return;
+            };
+            if let Err(err) =
+                paracord_core::read_state::apply_acks(state, session.user_id, &batch.acks).await
+            {
+                tracing::debug!(user_id = session.user_id, error = %err, "read state ack rejected");
+            }
+        }
+        OP_GUILD_SUBSCRIPTIONS => {
+            let Some(d) = payload.get("d") else {
+                return;
+            };
+            let parse_id = |key: &str| {
+                d.get(key)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| v.parse::<i64>().ok())
+            };
+            let (Some(guild_id), Some(channel_id)) = (parse_id("guild_id"), parse_id("channel_id"))
+            else {
+                return;
+            };
+            let ranges =
+                paracord_core::member_list::parse_ranges(d.get("ranges").unwrap_or(&Value::Null));
+            let Some(dispatch) = gateway_session::subscribe_member_list(
+                state, session, guild_id, channel_id, &ranges,
+            )
+            .await
+            else {
+                return;
+            };
+            let _ = send_ws_text_logged(
+                sender,
+                dispatch.ws_frame(),
+                compressor,
+                Some(session.user_id),
+                Some(session.session_id.as_str()),
+                "dispatch",
+                Some(OP_DISPATCH),
+                Some(EVENT_GUILD_MEMBER_LIST_UPDATE),
+                Some(dispatch.sequence),
+            )
+            .await;
+        }
         OP_VOICE_STATE_UPDATE => {
             if let Some(d) = payload.get("d") {
                 let self_mute = d
@@ -1797,6 +1808,11 @@ async fn handle_client_message(
                             .voice
                             .update_self_deaf(channel_id, session.user_id, self_deaf)
                             .await;
+                        if let Some(ref native) = state.native_media {
+                            native
+                                .relay_forwarder
+                                .set_muted(session.user_id, self_mute || self_deaf);
+                        }
 
                         // Read actual self_stream from VoiceManager instead of hardcoding false
                         let current_self_stream = state
@@ -1,6 +1,5 @@
 mod compression;
 mod handler;
-mod session;
 
 use axum::{
     extract::{ws::WebSocketUpgrade, Query, State},